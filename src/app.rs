@@ -1,23 +1,337 @@
+use crate::assets;
 use crate::audio::mixer::AudioMixer;
+use crate::audio::music::STINGER_DUCK;
+use crate::audio::MusicManager;
+use crate::data::crash;
 use crate::data::save::SaveManager;
-use crate::render::camera::GameCamera;
+use crate::ecs::SystemProfile;
 use crate::render::graphics_enhancement::{GraphicsEnhancement, QualityPreset};
 use crate::states::{StateManager, StateType};
+use crate::ui::console::{self, CommandRegistry, DevConsole};
+use macroquad::audio::Sound;
 use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
-pub const VIRTUAL_WIDTH: f32 = 1920.0;
-pub const VIRTUAL_HEIGHT: f32 = 1080.0;
-pub const FIXED_TIMESTEP: f64 = 1.0 / 120.0;
+/// Seconds a frame can wait on the window before the player counts as away
+const AWAY_STALL: f64 = 1.0;
+/// Seconds the music takes to dip under a stinger; it comes back slower
+const STINGER_FADE: f32 = 0.15;
+
+/// Fixed-timestep bookkeeping for the game loop: frame time goes in, whole
+/// simulation ticks come out, and the remainder carries over
+#[derive(Debug, Default)]
+pub struct FrameClock {
+    accumulator: f64,
+}
+
+impl FrameClock {
+    /// Add a frame's time and return how many ticks of `timestep` are due
+    pub fn advance(&mut self, frame_time: f64, timestep: f64) -> u32 {
+        self.accumulator += frame_time;
+        let mut ticks = 0;
+        while self.accumulator >= timestep {
+            self.accumulator -= timestep;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// How far into the next tick the leftover time is, for render blending
+    pub fn interpolation(&self, timestep: f64) -> f64 {
+        self.accumulator / timestep
+    }
+}
+
+/// Hitstop or slow motion a state asks the simulation to run at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeScaleRequest {
+    /// Freeze the simulation for this many seconds
+    Hitstop(f32),
+    /// Run it at `scale` for `duration` seconds, easing back at the end
+    SlowMotion { scale: f32, duration: f32 },
+}
+
+/// Slow motion eases back to full speed over its last this many seconds
+const SLOW_MOTION_EASE: f32 = 0.25;
+
+/// Game-feel time scale. Hitstop and slow motion stretch only the
+/// simulation's ticks, so the UI and pausing carry on in real time, and
+/// their timers count down in real time so a hitstop lasts as long as asked.
+#[derive(Debug, Default)]
+pub struct TimeScale {
+    hitstop: f32,
+    /// Slowest scale asked for and the seconds it has left
+    slow_motion: Option<(f32, f32)>,
+}
+
+impl TimeScale {
+    /// Take on `request`, weakened by the intensity setting; 0 leaves it out
+    pub fn request(&mut self, request: TimeScaleRequest, intensity: f32) {
+        let intensity = intensity.clamp(0.0, 1.0);
+        match request {
+            TimeScaleRequest::Hitstop(seconds) => {
+                self.hitstop = self.hitstop.max(seconds * intensity);
+            }
+            TimeScaleRequest::SlowMotion { scale, duration } => {
+                let scale = 1.0 - (1.0 - scale.clamp(0.0, 1.0)) * intensity;
+                let duration = duration * intensity;
+                self.slow_motion = Some(match self.slow_motion {
+                    Some((current, left)) => (current.min(scale), left.max(duration)),
+                    None => (scale, duration),
+                });
+            }
+        }
+    }
+
+    /// Count `real_dt` off and return the scale the simulation runs at for it
+    pub fn update(&mut self, real_dt: f32) -> f32 {
+        if self.hitstop > 0.0 {
+            self.hitstop = (self.hitstop - real_dt).max(0.0);
+            return 0.0;
+        }
+        let Some((scale, left)) = self.slow_motion else {
+            return 1.0;
+        };
+        let left = left - real_dt;
+        if left <= 0.0 {
+            self.slow_motion = None;
+            return 1.0;
+        }
+        self.slow_motion = Some((scale, left));
+        let ease = (left / SLOW_MOTION_EASE).min(1.0);
+        1.0 - (1.0 - scale) * ease
+    }
+}
+
+/// Time scales F7/F8 step through; `NORMAL_SPEED` is the starting one
+const DEBUG_TIME_SCALES: [f64; 6] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0];
+const NORMAL_SPEED: usize = 3;
+
+/// Debug pause and frame step: the world freezes while rendering carries
+/// on, and each step runs exactly one fixed tick
+#[derive(Debug)]
+pub struct DebugClock {
+    paused: bool,
+    step_requested: bool,
+    time_scale_index: usize,
+    /// Ticks simulated since startup, to line timings up against
+    frame: u64,
+}
+
+impl Default for DebugClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            step_requested: false,
+            time_scale_index: NORMAL_SPEED,
+            frame: 0,
+        }
+    }
+}
+
+impl DebugClock {
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Run one tick on the next frame; only while paused
+    pub fn request_step(&mut self) {
+        self.step_requested = self.paused;
+    }
+
+    pub fn slower(&mut self) {
+        self.time_scale_index = self.time_scale_index.saturating_sub(1);
+    }
+
+    pub fn faster(&mut self) {
+        self.time_scale_index = (self.time_scale_index + 1).min(DEBUG_TIME_SCALES.len() - 1);
+    }
+
+    /// Back to running at normal speed
+    pub fn reset(&mut self) {
+        self.paused = false;
+        self.step_requested = false;
+        self.time_scale_index = NORMAL_SPEED;
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        DEBUG_TIME_SCALES[self.time_scale_index]
+    }
+
+    /// Ticks due this frame. Paused, frame time is dropped rather than
+    /// banked, so resuming doesn't fast-forward.
+    pub fn ticks(&mut self, clock: &mut FrameClock, frame_time: f64, timestep: f64) -> u32 {
+        let ticks = if self.paused {
+            std::mem::take(&mut self.step_requested) as u32
+        } else {
+            clock.advance(frame_time * self.time_scale(), timestep)
+        };
+        self.frame += ticks as u64;
+        ticks
+    }
+
+    /// Whether there is anything to show on screen
+    fn is_active(&self) -> bool {
+        self.paused || self.time_scale_index != NORMAL_SPEED
+    }
+}
+
+/// Frames the F3 frame time graph keeps
+const FRAME_HISTORY: usize = 120;
+/// Frame time the graph marks as the budget, in seconds
+const FRAME_BUDGET: f32 = 1.0 / 60.0;
+
+/// Textured draws since the last frame. macroquad batches everything on one
+/// texture into a draw call, so these plus the shape batch are a fair
+/// estimate of what the frame costs the GPU.
+static DRAW_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Renderers call this once per textured draw or atlas batch
+pub fn count_draw_call() {
+    DRAW_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The F3 overlay: frame rate, a frame time graph, entity and particle
+/// counts, and what each ECS system and the renderer cost
+#[derive(Debug, Default)]
+pub struct PerfOverlay {
+    visible: bool,
+    frame_times: VecDeque<f32>,
+    /// Seconds the states spent updating and rendering last frame
+    update_time: f64,
+    render_time: f64,
+    profile: SystemProfile,
+    draw_calls: usize,
+}
+
+impl PerfOverlay {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        crate::ecs::set_profiling(self.visible);
+    }
+
+    pub fn record_frame(&mut self, frame_time: f32) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Mean frame time over the graph's history
+    pub fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    /// Take the frame's system timings and draw count
+    fn collect(&mut self, update_time: f64, render_time: f64) {
+        self.update_time = update_time;
+        self.render_time = render_time;
+        self.profile = crate::ecs::take_system_profile();
+        // The shape and text batch is always there
+        self.draw_calls = DRAW_CALLS.swap(0, Ordering::Relaxed) + 1;
+    }
+
+    fn render(&self) {
+        let frame_time = self.average_frame_time();
+        let fps = if frame_time > 0.0 { 1.0 / frame_time } else { 0.0 };
+        let mut lines = vec![
+            format!("FPS {:.0} ({:.2} ms)", fps, frame_time * 1000.0),
+            format!(
+                "Update {:.2} ms  Render {:.2} ms",
+                self.update_time * 1000.0,
+                self.render_time * 1000.0
+            ),
+            format!(
+                "Entities {}  Particles {}  Draw calls ~{}",
+                self.profile.entities, self.profile.particles, self.draw_calls
+            ),
+        ];
+        if self.profile.timings.is_empty() {
+            lines.push("No systems ran".to_string());
+        }
+        for (name, seconds) in &self.profile.timings {
+            lines.push(format!("{:<22}{:>7.3} ms", name, seconds * 1000.0));
+        }
+
+        let graph_height = 50.0;
+        let width = 340.0;
+        let height = 40.0 + graph_height + lines.len() as f32 * 18.0;
+        draw_rectangle(10.0, 10.0, width, height, Color::new(0.0, 0.0, 0.0, 0.75));
+        self.render_graph(20.0, 20.0, width - 20.0, graph_height);
+
+        for (i, line) in lines.iter().enumerate() {
+            let y = 20.0 + graph_height + 24.0 + i as f32 * 18.0;
+            draw_text(line, 20.0, y, 18.0, if i == 0 { YELLOW } else { WHITE });
+        }
+    }
+
+    /// A bar per frame scaled so twice the budget fills the height, with the
+    /// budget marked; frames over it show red
+    fn render_graph(&self, x: f32, y: f32, width: f32, height: f32) {
+        draw_rectangle(x, y, width, height, Color::new(1.0, 1.0, 1.0, 0.08));
+        let bar_width = width / FRAME_HISTORY as f32;
+        for (i, &time) in self.frame_times.iter().enumerate() {
+            let bar = (time / (FRAME_BUDGET * 2.0)).min(1.0) * height;
+            let color = if time > FRAME_BUDGET { RED } else { GREEN };
+            draw_rectangle(x + i as f32 * bar_width, y + height - bar, bar_width, bar, color);
+        }
+        let budget_y = y + height * 0.5;
+        draw_line(x, budget_y, x + width, budget_y, 1.0, Color::new(1.0, 1.0, 0.0, 0.6));
+    }
+}
+
+/// Console commands that work whatever state is up; the state's own are
+/// tried after these
+fn console_commands() -> CommandRegistry<Application> {
+    let mut commands = CommandRegistry::<Application>::new();
+    commands
+        .add("help", "", |app, _| {
+            let mut usages = console_commands().usages();
+            usages.extend(app.state_manager.command_usages());
+            for usage in usages {
+                app.console.print(format!("  {}", usage), LIGHTGRAY);
+            }
+            Ok(String::new())
+        })
+        .add("clear", "", |app, _| {
+            app.console.clear();
+            Ok(String::new())
+        })
+        .add("hitboxes", "[on|off]", |_, args| {
+            let enabled = console::switch(args, crate::data::get_debug_hitboxes())?;
+            crate::data::set_debug_hitboxes(enabled);
+            Ok(format!("Hitboxes {}", if enabled { "on" } else { "off" }))
+        })
+        .add("perf", "", |app, _| {
+            app.perf_overlay.toggle();
+            Ok(String::new())
+        })
+        .add("verify_replay", "[path]", |_, args| {
+            crate::states::versus::verify_replay(args.first().map(PathBuf::from))
+        });
+    commands
+}
 
 pub struct Application {
     state_manager: StateManager,
-    camera: GameCamera,
     audio_mixer: AudioMixer,
+    /// Whether the mixer has been handed the sounds the boot screen loaded
+    sounds_bound: bool,
+    music: MusicManager,
     graphics_enhancement: GraphicsEnhancement,
-    #[allow(dead_code)] // Future use: save/load system
+    /// Counts playtime for the active profile
     save_manager: SaveManager,
-    accumulator: f64,
-    fullscreen: bool,
+    clock: FrameClock,
+    debug_clock: DebugClock,
+    time_scale: TimeScale,
+    perf_overlay: PerfOverlay,
+    console: DevConsole,
     #[allow(dead_code)] // Future use: borderless fullscreen mode
     borderless: bool,
 }
@@ -32,14 +346,32 @@ impl Application {
         graphics.vignette_strength = 0.15;
         graphics.ambient_light = Color::new(0.8, 0.8, 0.85, 1.0); // Much brighter ambient
 
+        // Keep the default hook's stderr report, then dump the crash to disk
+        // for the next launch to offer up
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+            match crash::CrashDump::capture(info).write() {
+                Ok(path) => eprintln!("Crash report written to {}", path.display()),
+                Err(e) => eprintln!("{}", e),
+            }
+        }));
+
+        crate::data::GameConfig::load().apply_globals();
+        crate::plugins::install(&crate::plugins::default_plugins());
+
         Self {
             state_manager: StateManager::new(),
-            camera: GameCamera::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT),
             audio_mixer: AudioMixer::new(),
+            sounds_bound: false,
+            music: MusicManager::new(),
             graphics_enhancement: graphics,
             save_manager: SaveManager::new(),
-            accumulator: 0.0,
-            fullscreen: false,
+            clock: FrameClock::default(),
+            debug_clock: DebugClock::default(),
+            time_scale: TimeScale::default(),
+            perf_overlay: PerfOverlay::default(),
+            console: DevConsole::default(),
             borderless: false,
         }
     }
@@ -47,6 +379,10 @@ impl Application {
     pub async fn run(&mut self) {
         self.state_manager.push_state(StateType::Boot);
 
+        // Closing the window goes through the loop's exit, so a staged update
+        // still gets installed
+        prevent_quit();
+
         let mut last_time = get_time();
 
         loop {
@@ -54,68 +390,317 @@ impl Application {
             let frame_time = (current_time - last_time).min(0.25);
             last_time = current_time;
 
-            self.accumulator += frame_time;
+            self.step(frame_time);
 
-            self.handle_global_input();
+            if self.state_manager.should_quit() || is_quit_requested() {
+                break;
+            }
 
-            while self.accumulator >= FIXED_TIMESTEP {
-                self.state_manager.fixed_update(FIXED_TIMESTEP);
-                self.accumulator -= FIXED_TIMESTEP;
+            let waiting_since = get_time();
+            next_frame().await;
+            // macroquad doesn't report focus changes, but frames stall while
+            // the window is minimized or hidden, so a long wait means the
+            // player was away
+            if get_time() - waiting_since > AWAY_STALL {
+                crate::data::set_window_away();
             }
+        }
+
+        self.save_manager.record_playtime();
+        crate::data::cloud::upload_on_exit();
+        crate::updater::install_staged_update();
+    }
+
+    /// Run one frame of `frame_time` seconds: input, the simulation ticks it
+    /// covers, the per-frame update and the render
+    pub fn step(&mut self, frame_time: f64) {
+        self.handle_global_input();
+        self.perf_overlay.record_frame(frame_time as f32);
+        self.console.update(frame_time as f32);
+        if self.console.is_open() {
+            if let Some(line) = self.console.handle_input() {
+                self.run_console_line(&line);
+            }
+        }
+
+        // Simulation runs at a fixed tick rate; render blends between the
+        // last two ticks using the leftover time. Slow motion runs fewer
+        // ticks rather than shorter ones.
+        let intensity = crate::data::get_game_feel();
+        for request in crate::data::take_time_scale_requests() {
+            self.time_scale.request(request, intensity);
+        }
+        let game_feel = self.time_scale.update(frame_time as f32);
+        let time_scale =
+            self.graphics_enhancement.get_time_scale() * self.state_manager.game_speed();
+        let timestep = 1.0 / crate::data::get_tick_rate() as f64;
+        let sim_time = frame_time * (time_scale * game_feel) as f64;
+        // The world holds still while the console is down, and keys go to it
+        let ticks = if self.console.is_open() {
+            0
+        } else {
+            self.debug_clock.ticks(&mut self.clock, sim_time, timestep)
+        };
 
-            let interpolation = self.accumulator / FIXED_TIMESTEP;
+        assets::update(frame_time as f32);
+        self.bind_loaded_sounds();
+        self.audio_mixer.set_muted(crate::data::get_away_muted());
+        self.audio_mixer.set_volumes(crate::data::get_volumes());
+        self.audio_mixer.set_sidechain(crate::data::take_music_duck());
+        self.update_music(frame_time as f32);
+        self.audio_mixer.update(frame_time as f32);
+        self.save_manager.update(frame_time as f32);
 
-            self.camera.update();
-            self.audio_mixer.update(frame_time as f32);
+        // Apply time scale for slow-motion effects. While the debug pause
+        // holds the world, the per-frame update only moves on with a step.
+        // Hitstop and game-feel slow motion leave it alone, so the UI and
+        // pause menu keep real time.
+        let adjusted_frame_time = if self.debug_clock.paused {
+            ticks as f32 * timestep as f32
+        } else {
+            frame_time as f32 * time_scale * self.debug_clock.time_scale() as f32
+        };
+        let update_start = Instant::now();
+        if !self.console.is_open() {
+            self.state_manager.step(ticks, timestep, adjusted_frame_time);
+        }
+        let update_time = update_start.elapsed().as_secs_f64();
 
-            // Apply time scale for slow-motion effects
-            let adjusted_frame_time = frame_time as f32 * self.graphics_enhancement.get_time_scale();
-            self.state_manager.update(adjusted_frame_time);
+        // Enable graphics enhancement (fixed to not use render targets)
+        self.graphics_enhancement.begin_frame(frame_time as f32);
 
-            // Enable graphics enhancement (fixed to not use render targets)
-            self.graphics_enhancement.begin_frame(frame_time as f32);
+        clear_background(BLACK);
 
-            clear_background(BLACK);
+        // Paused, show the last tick exactly rather than a blend
+        let interpolation = if self.debug_clock.paused {
+            1.0
+        } else {
+            self.clock.interpolation(timestep) as f32
+        };
+        let render_start = Instant::now();
+        self.state_manager.render(interpolation);
 
-            self.camera.apply_transform();
-            self.state_manager.render(interpolation as f32);
-            self.camera.reset_transform();
+        // Render post-processing effects (vignette, lights, flash)
+        self.graphics_enhancement.end_frame();
+        let render_time = render_start.elapsed().as_secs_f64();
 
-            // Render post-processing effects (vignette, lights, flash)
-            self.graphics_enhancement.end_frame();
+        if self.debug_clock.is_active() {
+            self.render_debug_clock();
+        }
+        if self.perf_overlay.visible {
+            self.perf_overlay.collect(update_time, render_time);
+            self.perf_overlay.render();
+        } else {
+            DRAW_CALLS.store(0, Ordering::Relaxed);
+        }
+        self.console.render();
+    }
 
-            self.render_letterbox();
+    /// Run a line typed into the console: the app's commands first, then the
+    /// state's
+    fn run_console_line(&mut self, line: &str) {
+        self.console.echo(line);
+        let result = match console_commands().run(self, line) {
+            Some(result) => result,
+            None => self.state_manager.run_command(line).unwrap_or_else(|| {
+                let name = line.split_whitespace().next().unwrap_or(line);
+                Err(format!("Unknown command '{}', try help", name))
+            }),
+        };
+        self.console.report(result);
+    }
 
-            if self.state_manager.should_quit() {
-                break;
+    /// Once the boot screen has loaded the assets, give the mixer the files
+    /// under `sounds` and `music`, by their names there
+    fn bind_loaded_sounds(&mut self) {
+        if self.sounds_bound || !assets::is_loaded() {
+            return;
+        }
+        self.sounds_bound = true;
+        for (id, sound) in assets::all::<Sound>("sounds/") {
+            self.audio_mixer.load_sound(id, sound);
+        }
+        for (id, track) in assets::all::<Sound>("music/") {
+            self.audio_mixer.load_music(id, track);
+        }
+    }
+
+    /// Play the cues and sounds states queued, then keep the mixer's music channel and
+    /// ducking in line with the track and any stinger over it
+    fn update_music(&mut self, dt: f32) {
+        for cue in crate::data::take_music_cues() {
+            if let Some(stinger) = self.music.play_cue(cue, rand::gen_range(0, 2)) {
+                self.audio_mixer.play_sound(stinger.id, 1.0, 0.0);
+                self.audio_mixer.duck(STINGER_DUCK, STINGER_FADE);
             }
+        }
+        for sound in crate::data::take_sounds() {
+            self.audio_mixer.play(&sound);
+        }
 
-            next_frame().await;
+        let stinger_was_active = self.music.stinger_active();
+        self.music.update(dt);
+        if let Some(track) = self.music.current_track() {
+            self.audio_mixer.play_music(track, true);
         }
+        if stinger_was_active && !self.music.stinger_active() {
+            self.audio_mixer.duck(1.0, STINGER_FADE * 4.0);
+        }
+    }
+
+    fn render_debug_clock(&self) {
+        let clock = &self.debug_clock;
+        let status = if clock.paused { "PAUSED" } else { "RUNNING" };
+        let text = format!(
+            "{} x{} | tick {} | F5 pause  F6 step  F7/F8 speed",
+            status,
+            clock.time_scale(),
+            clock.frame
+        );
+        let dims = measure_text(&text, None, 20, 1.0);
+        let x = screen_width() - dims.width - 20.0;
+        draw_rectangle(
+            x - 10.0,
+            10.0,
+            dims.width + 20.0,
+            32.0,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_text(&text, x, 32.0, 20.0, if clock.paused { YELLOW } else { WHITE });
     }
 
     fn handle_global_input(&mut self) {
         if is_key_pressed(KeyCode::F11) {
             self.toggle_fullscreen();
         }
+        if is_key_pressed(KeyCode::F3) {
+            self.perf_overlay.toggle();
+        }
+
+        if crate::data::get_dev_console() && is_key_pressed(KeyCode::GraveAccent) {
+            self.console.toggle();
+        }
 
         if is_key_pressed(KeyCode::Escape) {
-            self.state_manager.handle_escape();
+            if self.console.is_open() {
+                self.console.toggle();
+            } else {
+                self.state_manager.handle_escape();
+            }
+        }
+
+        if crate::data::get_debug_controls() {
+            if is_key_pressed(KeyCode::F5) {
+                self.debug_clock.toggle_pause();
+            }
+            if is_key_pressed(KeyCode::F6) {
+                self.debug_clock.request_step();
+            }
+            if is_key_pressed(KeyCode::F7) {
+                self.debug_clock.slower();
+            }
+            if is_key_pressed(KeyCode::F8) {
+                self.debug_clock.faster();
+            }
+        } else if self.debug_clock.is_active() {
+            // Switched off in settings while paused or slowed
+            self.debug_clock.reset();
         }
     }
 
+    /// F11 flips the fullscreen setting, and it's kept for the next launch
     fn toggle_fullscreen(&mut self) {
-        self.fullscreen = !self.fullscreen;
-        if self.fullscreen {
-            request_new_screen_size(screen_width(), screen_height());
-        } else {
-            request_new_screen_size(VIRTUAL_WIDTH, VIRTUAL_HEIGHT);
+        let mut config = crate::data::GameConfig::load();
+        config.fullscreen = !config.fullscreen;
+        config.apply_window();
+        if let Err(e) = config.save() {
+            crash::log(e.to_string());
         }
     }
 
-    fn render_letterbox(&self) {}
-
     pub fn get_graphics_enhancement_mut(&mut self) -> &mut GraphicsEnhancement {
         &mut self.graphics_enhancement
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DebugClock, FrameClock, PerfOverlay, TimeScale, TimeScaleRequest, FRAME_HISTORY};
+
+    const TIMESTEP: f64 = 1.0 / 120.0;
+
+    #[test]
+    fn frame_covers_whole_ticks() {
+        let mut clock = FrameClock::default();
+        assert_eq!(clock.advance(1.0 / 60.0, TIMESTEP), 2);
+        assert!(clock.interpolation(TIMESTEP) < 1e-6);
+    }
+
+    #[test]
+    fn leftover_time_carries_into_the_next_frame() {
+        let mut clock = FrameClock::default();
+        let frame = TIMESTEP * 0.75;
+
+        assert_eq!(clock.advance(frame, TIMESTEP), 0);
+        assert!((clock.interpolation(TIMESTEP) - 0.75).abs() < 1e-6);
+        assert_eq!(clock.advance(frame, TIMESTEP), 1);
+        assert!((clock.interpolation(TIMESTEP) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn paused_clock_only_runs_requested_steps() {
+        let mut clock = FrameClock::default();
+        let mut debug = DebugClock::default();
+        debug.toggle_pause();
+
+        assert_eq!(debug.ticks(&mut clock, 1.0, TIMESTEP), 0);
+        debug.request_step();
+        assert_eq!(debug.ticks(&mut clock, 1.0, TIMESTEP), 1);
+        assert_eq!(debug.ticks(&mut clock, 1.0, TIMESTEP), 0);
+
+        // Time spent paused isn't made up on resume
+        debug.toggle_pause();
+        assert_eq!(debug.ticks(&mut clock, 1.0 / 60.0, TIMESTEP), 2);
+    }
+
+    #[test]
+    fn hitstop_freezes_then_slow_motion_eases_back() {
+        let mut feel = TimeScale::default();
+        feel.request(TimeScaleRequest::Hitstop(0.05), 1.0);
+        feel.request(TimeScaleRequest::SlowMotion { scale: 0.2, duration: 1.0 }, 0.5);
+        assert_eq!(feel.update(0.05), 0.0);
+
+        // Half intensity only slows to 0.6
+        assert!((feel.update(0.1) - 0.6).abs() < 1e-6);
+        let easing = feel.update(0.3);
+        assert!(easing > 0.6 && easing < 1.0);
+        assert_eq!(feel.update(0.2), 1.0);
+
+        feel.request(TimeScaleRequest::Hitstop(0.05), 0.0);
+        assert_eq!(feel.update(0.01), 1.0);
+    }
+
+    #[test]
+    fn frame_graph_keeps_only_its_history() {
+        let mut overlay = PerfOverlay::default();
+        assert_eq!(overlay.average_frame_time(), 0.0);
+        for _ in 0..FRAME_HISTORY {
+            overlay.record_frame(0.1);
+        }
+        overlay.record_frame(0.1 + FRAME_HISTORY as f32 * 0.01);
+        assert_eq!(overlay.frame_times.len(), FRAME_HISTORY);
+        assert!((overlay.average_frame_time() - 0.11).abs() < 1e-4);
+    }
+
+    #[test]
+    fn time_scale_changes_ticks_per_frame() {
+        let mut clock = FrameClock::default();
+        let mut debug = DebugClock::default();
+
+        debug.slower();
+        assert_eq!(debug.ticks(&mut clock, 1.0 / 60.0, TIMESTEP), 1);
+        debug.faster();
+        debug.faster();
+        assert_eq!(debug.ticks(&mut clock, 1.0 / 60.0, TIMESTEP), 4);
+    }
+}
@@ -0,0 +1,287 @@
+use super::{Assets, Loaded, Source};
+use macroquad::audio::{load_sound_from_bytes, Sound};
+use macroquad::experimental::coroutines::{start_coroutine, Coroutine};
+use macroquad::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time a frame spends decoding before it lets the boot screen draw
+const FRAME_BUDGET: Duration = Duration::from_millis(8);
+
+/// What a file is, by its extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Texture,
+    Font,
+    Sound,
+    Data,
+}
+
+impl Kind {
+    fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Kind::Texture),
+            "ttf" | "otf" => Some(Kind::Font),
+            "wav" | "ogg" => Some(Kind::Sound),
+            "json" | "ron" | "txt" | "csv" => Some(Kind::Data),
+            _ => None,
+        }
+    }
+}
+
+/// A file found under the assets folder, waiting to load
+#[derive(Debug, PartialEq)]
+struct Entry {
+    name: String,
+    path: PathBuf,
+    kind: Kind,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Every asset file under `root`, sorted by name. Names use `/` whatever the
+/// platform and leave the extension off.
+fn scan(root: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut folders = vec![root.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        let Ok(listing) = fs::read_dir(&folder) else {
+            continue;
+        };
+        for path in listing.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.is_dir() {
+                folders.push(path);
+                continue;
+            }
+            let Some(kind) = Kind::of(&path) else {
+                continue;
+            };
+            let Ok(relative) = path.with_extension("").strip_prefix(root).map(Path::to_path_buf)
+            else {
+                continue;
+            };
+            let name: Vec<String> = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            let meta = fs::metadata(&path).ok();
+            entries.push(Entry {
+                name: name.join("/"),
+                size: meta.as_ref().map_or(0, |meta| meta.len()),
+                modified: meta.and_then(|meta| meta.modified().ok()),
+                path,
+                kind,
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+/// A file on its way from bytes to an asset. Sounds decode in a macroquad
+/// coroutine; everything else is done at once.
+pub enum Decoding {
+    Done(Option<Result<Loaded, String>>),
+    Sound(Coroutine<Result<Sound, String>>),
+}
+
+impl Decoding {
+    /// The decoded asset, handed over once; `None` while a sound still is
+    pub fn poll(&mut self) -> Option<Result<Loaded, String>> {
+        match self {
+            Decoding::Done(done) => done.take(),
+            Decoding::Sound(coroutine) => match coroutine.retrieve() {
+                Some(sound) => Some(sound.map(Loaded::Sound)),
+                None if coroutine.is_done() => Some(Err("sound decoding stopped".to_string())),
+                None => None,
+            },
+        }
+    }
+}
+
+pub fn decode(kind: Kind, bytes: Vec<u8>) -> Decoding {
+    Decoding::Done(Some(match kind {
+        Kind::Texture => Image::from_file_with_format(&bytes, None)
+            .map(|image| Loaded::Texture(Texture2D::from_image(&image)))
+            .map_err(|e| e.to_string()),
+        Kind::Font => load_ttf_font_from_bytes(&bytes)
+            .map(Loaded::Font)
+            .map_err(|e| e.to_string()),
+        Kind::Sound => {
+            return Decoding::Sound(start_coroutine(async move {
+                load_sound_from_bytes(&bytes).await.map_err(|e| e.to_string())
+            }))
+        }
+        Kind::Data => String::from_utf8(bytes)
+            .map(Loaded::Data)
+            .map_err(|_| "not UTF-8 text".to_string()),
+    }))
+}
+
+/// Loads every asset file while the boot screen shows how far along it is.
+/// A worker thread reads the files; decoding happens here on the main thread,
+/// a frame's budget at a time, since textures go to the GPU.
+pub struct AssetLoader {
+    entries: Vec<Entry>,
+    files: Receiver<(usize, Result<Vec<u8>, String>)>,
+    decoding: Option<(usize, Decoding)>,
+    assets: Assets,
+    total_bytes: u64,
+    loaded_bytes: u64,
+    finished: usize,
+    failed: Vec<String>,
+}
+
+impl AssetLoader {
+    pub fn start() -> Self {
+        Self::from_directory(&super::assets_directory())
+    }
+
+    fn from_directory(root: &Path) -> Self {
+        let entries = scan(root);
+        let paths: Vec<PathBuf> = entries.iter().map(|entry| entry.path.clone()).collect();
+        let (sender, files) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (index, path) in paths.into_iter().enumerate() {
+                let read = fs::read(&path).map_err(|e| e.to_string());
+                if sender.send((index, read)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            total_bytes: entries.iter().map(|entry| entry.size).sum(),
+            entries,
+            files,
+            decoding: None,
+            assets: Assets::default(),
+            loaded_bytes: 0,
+            finished: 0,
+            failed: Vec::new(),
+        }
+    }
+
+    /// Decode what's been read until this frame's budget runs out
+    pub fn poll(&mut self) {
+        let started = Instant::now();
+        while !self.is_done() && started.elapsed() < FRAME_BUDGET {
+            if let Some((index, mut decoding)) = self.decoding.take() {
+                match decoding.poll() {
+                    Some(result) => self.finish_entry(index, result),
+                    None => {
+                        // A sound's coroutine runs between frames
+                        self.decoding = Some((index, decoding));
+                        return;
+                    }
+                }
+                continue;
+            }
+
+            match self.files.try_recv() {
+                Ok((index, Ok(bytes))) => {
+                    let decoding = decode(self.entries[index].kind, bytes);
+                    self.decoding = Some((index, decoding));
+                }
+                Ok((index, Err(e))) => self.finish_entry(index, Err(e)),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    // The reader stopped early; whatever's left can't arrive
+                    for index in self.finished..self.entries.len() {
+                        self.finish_entry(index, Err("never read".to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish_entry(&mut self, index: usize, result: Result<Loaded, String>) {
+        let entry = &self.entries[index];
+        match result {
+            Ok(asset) => {
+                let source = Source {
+                    path: entry.path.clone(),
+                    kind: entry.kind,
+                    modified: entry.modified,
+                };
+                self.assets.insert(entry.name.clone(), source, asset);
+            }
+            Err(e) => self.failed.push(format!("{}: {}", entry.name, e)),
+        }
+        self.loaded_bytes += entry.size;
+        self.finished += 1;
+    }
+
+    /// Share of the bytes loaded, 0 to 1; files are read in order, so bytes
+    /// measure what's left better than file counts
+    pub fn progress(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return if self.is_done() { 1.0 } else { 0.0 };
+        }
+        self.loaded_bytes as f32 / self.total_bytes as f32
+    }
+
+    /// The asset being loaded now, for the loading screen
+    pub fn current(&self) -> Option<&str> {
+        self.entries.get(self.finished).map(|entry| entry.name.as_str())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished == self.entries.len()
+    }
+
+    /// The loaded assets, and a line for each file that couldn't be
+    pub fn finish(self) -> (Assets, Vec<String>) {
+        (self.assets, self.failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Handle;
+
+    #[test]
+    fn data_files_load_under_their_names_and_report_progress() {
+        let dir = std::env::temp_dir().join("bas-veeg-arc-test-assets");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("data/maps")).unwrap();
+        fs::write(dir.join("data/maps/school.json"), "{\"spawns\": 4}").unwrap();
+        fs::write(dir.join("data/credits.txt"), "Bas").unwrap();
+        fs::write(dir.join("data/bad.csv"), [0xff, 0xfe]).unwrap();
+        fs::write(dir.join("readme.md"), "not an asset").unwrap();
+
+        let names: Vec<(String, Kind)> =
+            scan(&dir).into_iter().map(|entry| (entry.name, entry.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("data/bad".to_string(), Kind::Data),
+                ("data/credits".to_string(), Kind::Data),
+                ("data/maps/school".to_string(), Kind::Data),
+            ]
+        );
+
+        let mut loader = AssetLoader::from_directory(&dir);
+        assert_eq!(loader.progress(), 0.0);
+        let started = Instant::now();
+        while !loader.is_done() && started.elapsed() < Duration::from_secs(5) {
+            loader.poll();
+        }
+        assert_eq!(loader.progress(), 1.0);
+        assert_eq!(loader.current(), None);
+
+        let (assets, failed) = loader.finish();
+        assert_eq!(failed, vec!["data/bad: not UTF-8 text".to_string()]);
+        let school: Handle<String> = assets.handle("data/maps/school").unwrap();
+        assert_eq!(assets.get(school).as_deref(), Some("{\"spawns\": 4}"));
+        assert_eq!(assets.handle::<Texture2D>("data/credits"), None);
+        let everything = assets.all::<String>("data/");
+        let found: Vec<&str> = everything.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(found, vec!["credits", "maps/school"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
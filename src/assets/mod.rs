@@ -0,0 +1,318 @@
+use crate::data::crash;
+use macroquad::audio::Sound;
+use macroquad::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+mod loader;
+
+pub use loader::AssetLoader;
+use loader::Kind;
+
+/// Seconds between checks for changed files in debug builds
+#[cfg(debug_assertions)]
+const HOT_RELOAD_INTERVAL: f32 = 1.0;
+
+/// Where asset files live: `assets` next to the game, or under the working
+/// folder when running from a checkout
+pub fn assets_directory() -> PathBuf {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("assets")));
+    match beside_exe {
+        Some(dir) if dir.is_dir() => dir,
+        _ => PathBuf::from("assets"),
+    }
+}
+
+/// A loaded asset of type `T`. Handles stay valid across hot reloads, which
+/// swap what's behind them.
+pub struct Handle<T> {
+    index: usize,
+    _asset: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: usize) -> Self {
+        Self {
+            index,
+            _asset: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+/// Where an asset sits in the store, by kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Texture(usize),
+    Font(usize),
+    Sound(usize),
+    Data(usize),
+}
+
+/// Something the store holds: textures, fonts, sounds, and data files as text
+pub trait Asset: Clone + 'static {
+    fn index(slot: Slot) -> Option<usize>;
+    fn fetch(assets: &Assets, index: usize) -> Option<Self>;
+}
+
+impl Asset for Texture2D {
+    fn index(slot: Slot) -> Option<usize> {
+        match slot {
+            Slot::Texture(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    fn fetch(assets: &Assets, index: usize) -> Option<Self> {
+        assets.textures.get(index).cloned()
+    }
+}
+
+impl Asset for Font {
+    fn index(slot: Slot) -> Option<usize> {
+        match slot {
+            Slot::Font(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    fn fetch(assets: &Assets, index: usize) -> Option<Self> {
+        assets.fonts.get(index).cloned()
+    }
+}
+
+impl Asset for Sound {
+    fn index(slot: Slot) -> Option<usize> {
+        match slot {
+            Slot::Sound(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    fn fetch(assets: &Assets, index: usize) -> Option<Self> {
+        assets.sounds.get(index).cloned()
+    }
+}
+
+impl Asset for String {
+    fn index(slot: Slot) -> Option<usize> {
+        match slot {
+            Slot::Data(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    fn fetch(assets: &Assets, index: usize) -> Option<Self> {
+        assets.data.get(index).cloned()
+    }
+}
+
+/// A decoded file, ready to go into its slot
+pub enum Loaded {
+    Texture(Texture2D),
+    Font(Font),
+    Sound(Sound),
+    Data(String),
+}
+
+/// The file behind an asset and when it last changed, for hot reload
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+struct Source {
+    path: PathBuf,
+    kind: Kind,
+    modified: Option<SystemTime>,
+}
+
+/// Every loaded asset, looked up by name: its path under the assets folder
+/// without the extension, like `textures/explosion` or `sounds/hit_light`
+#[derive(Default)]
+pub struct Assets {
+    textures: Vec<Texture2D>,
+    fonts: Vec<Font>,
+    sounds: Vec<Sound>,
+    data: Vec<String>,
+    names: HashMap<String, Slot>,
+    sources: Vec<(Source, Slot)>,
+    /// Sounds being decoded again after their file changed
+    #[cfg(debug_assertions)]
+    reloading: Vec<(usize, loader::Decoding)>,
+    #[cfg(debug_assertions)]
+    since_check: f32,
+}
+
+impl Assets {
+    fn insert(&mut self, name: String, source: Source, asset: Loaded) {
+        let slot = match asset {
+            Loaded::Texture(texture) => {
+                self.textures.push(texture);
+                Slot::Texture(self.textures.len() - 1)
+            }
+            Loaded::Font(font) => {
+                self.fonts.push(font);
+                Slot::Font(self.fonts.len() - 1)
+            }
+            Loaded::Sound(sound) => {
+                self.sounds.push(sound);
+                Slot::Sound(self.sounds.len() - 1)
+            }
+            Loaded::Data(text) => {
+                self.data.push(text);
+                Slot::Data(self.data.len() - 1)
+            }
+        };
+        self.names.insert(name, slot);
+        self.sources.push((source, slot));
+    }
+
+    #[allow(dead_code)] // Future use: looking up a single asset by name
+    pub fn handle<T: Asset>(&self, name: &str) -> Option<Handle<T>> {
+        self.names.get(name).and_then(|&slot| T::index(slot)).map(Handle::new)
+    }
+
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> Option<T> {
+        T::fetch(self, handle.index)
+    }
+
+    /// Every asset of type `T` whose name starts with `prefix`, with the
+    /// prefix taken off, sorted by name
+    pub fn all<T: Asset>(&self, prefix: &str) -> Vec<(String, Handle<T>)> {
+        let mut found: Vec<(String, Handle<T>)> = self
+            .names
+            .iter()
+            .filter_map(|(name, &slot)| {
+                let rest = name.strip_prefix(prefix)?;
+                Some((rest.to_string(), Handle::new(T::index(slot)?)))
+            })
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        found
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Put a freshly decoded file into its slot. A texture the same size as
+    /// the old one is updated in place, so copies taken from it change too.
+    #[cfg(debug_assertions)]
+    fn replace(&mut self, slot: Slot, asset: Loaded) {
+        match (slot, asset) {
+            (Slot::Texture(index), Loaded::Texture(texture)) => {
+                let old = &self.textures[index];
+                if old.size() == texture.size() {
+                    old.update(&texture.get_texture_data());
+                } else {
+                    self.textures[index] = texture;
+                }
+            }
+            (Slot::Font(index), Loaded::Font(font)) => self.fonts[index] = font,
+            (Slot::Sound(index), Loaded::Sound(sound)) => self.sounds[index] = sound,
+            (Slot::Data(index), Loaded::Data(text)) => self.data[index] = text,
+            _ => {}
+        }
+    }
+
+    /// Decode any file that changed on disk since it was loaded again
+    #[cfg(debug_assertions)]
+    fn hot_reload(&mut self, dt: f32) {
+        self.since_check += dt;
+        if self.since_check >= HOT_RELOAD_INTERVAL {
+            self.since_check = 0.0;
+            for (index, (source, _)) in self.sources.iter_mut().enumerate() {
+                let modified =
+                    std::fs::metadata(&source.path).and_then(|meta| meta.modified()).ok();
+                if modified.is_none() || modified == source.modified {
+                    continue;
+                }
+                source.modified = modified;
+                match std::fs::read(&source.path) {
+                    Ok(bytes) => self.reloading.push((index, loader::decode(source.kind, bytes))),
+                    Err(e) => {
+                        crash::log(format!("Failed to reload {}: {}", source.path.display(), e));
+                    }
+                }
+            }
+        }
+
+        let mut still_decoding = Vec::new();
+        for (index, mut decoding) in std::mem::take(&mut self.reloading) {
+            let (source, slot) = &self.sources[index];
+            let path = source.path.display().to_string();
+            match decoding.poll() {
+                Some(Ok(asset)) => {
+                    self.replace(*slot, asset);
+                    crash::log(format!("Reloaded {}", path));
+                }
+                Some(Err(e)) => crash::log(format!("Failed to reload {}: {}", path, e)),
+                None => still_decoding.push((index, decoding)),
+            }
+        }
+        self.reloading = still_decoding;
+    }
+}
+
+thread_local! {
+    /// The loaded assets. GPU and audio resources belong to the main thread,
+    /// where the game runs, so they're kept there.
+    static ASSETS: RefCell<Option<Assets>> = const { RefCell::new(None) };
+}
+
+/// Make `assets` the ones the game draws and plays from
+pub fn install(assets: Assets) {
+    crash::log(format!("Loaded {} assets", assets.len()));
+    ASSETS.with(|current| *current.borrow_mut() = Some(assets));
+}
+
+/// Whether the boot screen has finished loading everything
+pub fn is_loaded() -> bool {
+    ASSETS.with(|assets| assets.borrow().is_some())
+}
+
+#[allow(dead_code)] // Future use: looking up a single asset by name
+pub fn find<T: Asset>(name: &str) -> Option<Handle<T>> {
+    ASSETS.with(|assets| assets.borrow().as_ref()?.handle(name))
+}
+
+pub fn get<T: Asset>(handle: Handle<T>) -> Option<T> {
+    ASSETS.with(|assets| assets.borrow().as_ref()?.get(handle))
+}
+
+pub fn all<T: Asset>(prefix: &str) -> Vec<(String, Handle<T>)> {
+    ASSETS.with(|assets| {
+        assets.borrow().as_ref().map(|assets| assets.all(prefix)).unwrap_or_default()
+    })
+}
+
+/// Pick up edited asset files; only debug builds watch for them
+pub fn update(_dt: f32) {
+    #[cfg(debug_assertions)]
+    ASSETS.with(|assets| {
+        if let Some(assets) = assets.borrow_mut().as_mut() {
+            assets.hot_reload(_dt);
+        }
+    });
+}
@@ -0,0 +1,123 @@
+use crate::audio::mixer::{Bus, SoundRequest};
+use crate::combat::combo_system::StyleRank;
+use crate::data::characters::{Character, CharacterId};
+use std::collections::VecDeque;
+
+/// Seconds between callouts, so one finishes before the next starts
+const CALLOUT_GAP: f32 = 1.1;
+/// Callouts waiting behind the one being said; the oldest is dropped
+const MAX_QUEUED: usize = 3;
+/// Waves past this are called as "next wave" rather than by number
+const NUMBERED_WAVES: usize = 10;
+
+/// Something the announcer calls out over the fight
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Callout {
+    WaveStart(usize),
+    /// Someone hit back out of a combo being landed on them
+    ComboBreaker,
+    RankUp(StyleRank),
+    KnockOut,
+    /// A versus round taken by this character
+    RoundWin(CharacterId),
+}
+
+impl Callout {
+    pub fn sound_id(&self) -> String {
+        match self {
+            Callout::WaveStart(wave) if *wave <= NUMBERED_WAVES => {
+                format!("announcer_wave_{}", wave)
+            }
+            Callout::WaveStart(_) => "announcer_next_wave".to_string(),
+            Callout::ComboBreaker => "announcer_combo_breaker".to_string(),
+            Callout::RankUp(rank) => format!("announcer_rank_{}", rank.to_string().to_lowercase()),
+            Callout::KnockOut => "announcer_ko".to_string(),
+            Callout::RoundWin(character) => {
+                let name = Character::get_by_id(*character).name.to_lowercase();
+                format!("announcer_{}_wins", name.replace(' ', "_"))
+            }
+        }
+    }
+}
+
+/// Says callouts one at a time, on the announcer bus. Rank-ups that pile up
+/// behind each other are only called at the highest one, and nothing is
+/// queued while the announcer is switched off.
+pub struct Announcer {
+    queue: VecDeque<Callout>,
+    /// Seconds until the next callout can start
+    cooldown: f32,
+}
+
+impl Announcer {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            cooldown: 0.0,
+        }
+    }
+
+    pub fn announce(&mut self, callout: Callout) {
+        if crate::data::get_volumes().announcer <= 0.0 {
+            return;
+        }
+        if matches!(callout, Callout::RankUp(_)) {
+            self.queue.retain(|queued| !matches!(queued, Callout::RankUp(_)));
+        }
+        if self.queue.len() == MAX_QUEUED {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(callout);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+        if self.cooldown > 0.0 {
+            return;
+        }
+        if let Some(callout) = self.queue.pop_front() {
+            let id = callout.sound_id();
+            crate::data::queue_sound(SoundRequest::new(Bus::Announcer, &id));
+            self.cooldown = CALLOUT_GAP;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.cooldown = 0.0;
+    }
+}
+
+impl Default for Announcer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacked_rank_ups_are_called_at_the_highest() {
+        let mut announcer = Announcer::new();
+        announcer.queue.push_back(Callout::WaveStart(2));
+        announcer.announce(Callout::RankUp(StyleRank::B));
+        announcer.announce(Callout::RankUp(StyleRank::A));
+        assert_eq!(
+            announcer.queue,
+            [Callout::WaveStart(2), Callout::RankUp(StyleRank::A)]
+        );
+    }
+
+    #[test]
+    fn callouts_name_what_they_call() {
+        assert_eq!(Callout::WaveStart(3).sound_id(), "announcer_wave_3");
+        assert_eq!(Callout::WaveStart(NUMBERED_WAVES + 1).sound_id(), "announcer_next_wave");
+        assert_eq!(Callout::RankUp(StyleRank::SSS).sound_id(), "announcer_rank_sss");
+        assert_eq!(
+            Callout::RoundWin(CharacterId::PalaBaba).sound_id(),
+            "announcer_yigit_baba_wins"
+        );
+    }
+}
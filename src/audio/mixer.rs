@@ -1,19 +1,102 @@
+use crate::assets::{self, Handle};
+use crate::data::GameConfig;
 use ahash::AHashMap;
-use macroquad::audio::Sound;
+use macroquad::audio::{self as sound, PlaySoundParams, Sound};
 use macroquad::prelude::*;
 
+/// Music volume while the sidechain is keyed by dialogue or a super
+pub const SIDECHAIN_DEPTH: f32 = 0.4;
+/// Seconds the music takes to dip under a line, and to come back after
+const SIDECHAIN_ATTACK: f32 = 0.15;
+const SIDECHAIN_RELEASE: f32 = 0.6;
+/// The channel the music track plays on
+const MUSIC_CHANNEL: usize = 15;
+
+/// Which group of sounds a channel is mixed into; each has its own volume
+/// under the master
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bus {
+    Music,
+    Sfx,
+    Voice,
+    Announcer,
+}
+
+/// Volume of the master and each bus, 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BusVolumes {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub voice: f32,
+    /// Zero while the announcer is switched off
+    pub announcer: f32,
+}
+
+impl BusVolumes {
+    pub const DEFAULT: BusVolumes = BusVolumes {
+        master: 1.0,
+        music: 0.8,
+        sfx: 1.0,
+        voice: 1.0,
+        announcer: 1.0,
+    };
+
+    pub fn from_config(config: &GameConfig) -> Self {
+        Self {
+            master: config.master_volume.clamp(0.0, 1.0),
+            music: config.music_volume.clamp(0.0, 1.0),
+            sfx: config.sfx_volume.clamp(0.0, 1.0),
+            voice: config.voice_volume.clamp(0.0, 1.0),
+            announcer: if config.announcer_enabled {
+                config.announcer_volume.clamp(0.0, 1.0)
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// A sound to play: where it sits in the stereo field (-1 left to 1 right)
+/// and how far its pitch is shifted (1 as recorded)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundRequest {
+    pub bus: Bus,
+    pub id: String,
+    pub volume: f32,
+    pub pan: f32,
+    pub pitch: f32,
+}
+
+impl SoundRequest {
+    /// `id` on `bus`, centred, at full volume and its own pitch
+    pub fn new(bus: Bus, id: &str) -> Self {
+        Self {
+            bus,
+            id: id.to_string(),
+            volume: 1.0,
+            pan: 0.0,
+            pitch: 1.0,
+        }
+    }
+}
+
 pub struct AudioMixer {
     channels: Vec<AudioChannel>,
-    #[allow(dead_code)] // Future use: preloaded sound effects
-    sound_library: AHashMap<String, Sound>,
-    #[allow(dead_code)] // Future use: preloaded music tracks
-    music_tracks: AHashMap<String, Sound>,
-    master_volume: f32,
-    sfx_volume: f32,
-    music_volume: f32,
+    /// Sound effects and music tracks from the assets folder, by id
+    sound_library: AHashMap<String, Handle<Sound>>,
+    music_tracks: AHashMap<String, Handle<Sound>>,
+    volumes: BusVolumes,
     #[allow(dead_code)] // Used in play_music method
     current_music: Option<String>,
+    /// The track playing on the music channel, when there's a file for it
+    music_sound: Option<Sound>,
     ducking: DuckingState,
+    /// Dips the music while something keys it, on top of stinger ducking
+    sidechain_keyed: bool,
+    sidechain_level: f32,
+    /// Everything silent, without losing the volumes
+    muted: bool,
 }
 
 #[derive(Clone)]
@@ -23,6 +106,9 @@ pub struct AudioChannel {
     pub volume: f32,
     #[allow(dead_code)] // Future use: stereo panning
     pub pan: f32,
+    #[allow(dead_code)] // Future use: pitch shifting
+    pub pitch: f32,
+    pub bus: Bus,
     pub playing: Option<PlayingSound>,
 }
 
@@ -51,6 +137,8 @@ impl AudioMixer {
                 name: format!("channel_{}", i),
                 volume: 1.0,
                 pan: 0.0,
+                pitch: 1.0,
+                bus: if i == MUSIC_CHANNEL { Bus::Music } else { Bus::Sfx },
                 playing: None,
             });
         }
@@ -59,10 +147,9 @@ impl AudioMixer {
             channels,
             sound_library: AHashMap::new(),
             music_tracks: AHashMap::new(),
-            master_volume: 1.0,
-            sfx_volume: 1.0,
-            music_volume: 0.8,
+            volumes: BusVolumes::DEFAULT,
             current_music: None,
+            music_sound: None,
             ducking: DuckingState {
                 active: false,
                 target_volume: 1.0,
@@ -70,27 +157,53 @@ impl AudioMixer {
                 duration: 0.0,
                 timer: 0.0,
             },
+            sidechain_keyed: false,
+            sidechain_level: 1.0,
+            muted: false,
         }
     }
 
-    pub fn load_sound(&mut self, _id: String, _path: &str) {}
+    pub fn load_sound(&mut self, id: String, sound: Handle<Sound>) {
+        self.sound_library.insert(id, sound);
+    }
 
-    pub fn load_music(&mut self, _id: String, _path: &str) {}
+    pub fn load_music(&mut self, id: String, track: Handle<Sound>) {
+        self.music_tracks.insert(id, track);
+    }
 
     pub fn play_sound(&mut self, sound_id: &str, volume: f32, pan: f32) -> Option<usize> {
-        for (i, channel) in self.channels.iter_mut().enumerate() {
-            if channel.playing.is_none() {
-                channel.playing = Some(PlayingSound {
-                    sound_id: sound_id.to_string(),
-                    start_time: get_time(),
-                    looping: false,
-                });
-                channel.volume = volume;
-                channel.pan = pan;
-                return Some(i);
-            }
+        self.play(&SoundRequest {
+            volume,
+            pan,
+            ..SoundRequest::new(Bus::Sfx, sound_id)
+        })
+    }
+
+    /// Play a sound on a free channel mixed into its bus
+    pub fn play(&mut self, request: &SoundRequest) -> Option<usize> {
+        let i = (0..self.channels.len())
+            .find(|&i| i != MUSIC_CHANNEL && self.channels[i].playing.is_none())?;
+        let channel = &mut self.channels[i];
+        channel.playing = Some(PlayingSound {
+            sound_id: request.id.clone(),
+            start_time: get_time(),
+            looping: false,
+        });
+        channel.volume = request.volume;
+        channel.pan = request.pan.clamp(-1.0, 1.0);
+        channel.pitch = request.pitch;
+        channel.bus = request.bus;
+
+        // Ids without a file still take their channel, so timing stays the same
+        let loaded = self.sound_library.get(&request.id).and_then(|sound| assets::get(*sound));
+        if let Some(loaded) = loaded {
+            let params = PlaySoundParams {
+                looped: false,
+                volume: self.get_final_volume(i),
+            };
+            sound::play_sound(&loaded, params);
         }
-        None
+        Some(i)
     }
 
     pub fn play_music(&mut self, music_id: &str, looping: bool) {
@@ -101,19 +214,32 @@ impl AudioMixer {
         self.stop_music();
         self.current_music = Some(music_id.to_string());
 
-        if self.channels.len() > 15 {
-            self.channels[15].playing = Some(PlayingSound {
+        if let Some(channel) = self.channels.get_mut(MUSIC_CHANNEL) {
+            channel.playing = Some(PlayingSound {
                 sound_id: music_id.to_string(),
                 start_time: get_time(),
                 looping,
             });
-            self.channels[15].volume = self.music_volume;
+            channel.volume = 1.0;
+        }
+
+        let track = self.music_tracks.get(music_id).and_then(|track| assets::get(*track));
+        if let Some(track) = track {
+            let params = PlaySoundParams {
+                looped: looping,
+                volume: self.get_final_volume(MUSIC_CHANNEL),
+            };
+            sound::play_sound(&track, params);
+            self.music_sound = Some(track);
         }
     }
 
     pub fn stop_music(&mut self) {
-        if self.channels.len() > 15 {
-            self.channels[15].playing = None;
+        if let Some(channel) = self.channels.get_mut(MUSIC_CHANNEL) {
+            channel.playing = None;
+        }
+        if let Some(track) = self.music_sound.take() {
+            sound::stop_sound(&track);
         }
         self.current_music = None;
     }
@@ -148,6 +274,18 @@ impl AudioMixer {
             }
         }
 
+        let (target, time) = if self.sidechain_keyed {
+            (SIDECHAIN_DEPTH, SIDECHAIN_ATTACK)
+        } else {
+            (1.0, SIDECHAIN_RELEASE)
+        };
+        let step = (1.0 - SIDECHAIN_DEPTH) * dt / time;
+        self.sidechain_level = if self.sidechain_level > target {
+            (self.sidechain_level - step).max(target)
+        } else {
+            (self.sidechain_level + step).min(target)
+        };
+
         for channel in &mut self.channels {
             if let Some(playing) = &channel.playing {
                 if !playing.looping && get_time() - playing.start_time > 5.0 {
@@ -155,34 +293,85 @@ impl AudioMixer {
                 }
             }
         }
+
+        // Ducking, the sidechain and volume changes reach the playing track
+        if let Some(track) = &self.music_sound {
+            sound::set_sound_volume(track, self.get_final_volume(MUSIC_CHANNEL));
+        }
     }
 
-    pub fn set_master_volume(&mut self, volume: f32) {
-        self.master_volume = volume.clamp(0.0, 1.0);
+    pub fn set_volumes(&mut self, volumes: BusVolumes) {
+        self.volumes = volumes;
     }
 
-    pub fn set_sfx_volume(&mut self, volume: f32) {
-        self.sfx_volume = volume.clamp(0.0, 1.0);
+    /// Hold the music under whatever's keying it (a line being spoken, a
+    /// super's cinematic) for as long as this is set
+    pub fn set_sidechain(&mut self, keyed: bool) {
+        self.sidechain_keyed = keyed;
     }
 
-    pub fn set_music_volume(&mut self, volume: f32) {
-        self.music_volume = volume.clamp(0.0, 1.0);
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
     }
 
     pub fn get_final_volume(&self, channel_id: usize) -> f32 {
-        if channel_id >= self.channels.len() {
+        if self.muted || channel_id >= self.channels.len() {
             return 0.0;
         }
 
         let channel = &self.channels[channel_id];
-        let is_music = channel_id == 15;
 
-        let type_volume = if is_music {
-            self.music_volume
+        // Ducking dips the music under stingers and lines, not the sounds themselves
+        let ducking = if channel.bus == Bus::Music {
+            self.ducking.current_volume * self.sidechain_level
         } else {
-            self.sfx_volume
+            1.0
+        };
+
+        let bus = match channel.bus {
+            Bus::Music => self.volumes.music,
+            Bus::Sfx => self.volumes.sfx,
+            Bus::Voice => self.volumes.voice,
+            Bus::Announcer => self.volumes.announcer,
         };
 
-        channel.volume * type_volume * self.master_volume * self.ducking.current_volume
+        channel.volume * bus * self.volumes.master * ducking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_bus_has_its_own_volume_under_the_master() {
+        let mut mixer = AudioMixer::new();
+        mixer.set_volumes(BusVolumes {
+            master: 0.5,
+            music: 0.8,
+            sfx: 1.0,
+            voice: 0.25,
+            announcer: 1.0,
+        });
+        mixer.channels[0].bus = Bus::Voice;
+        assert_eq!(mixer.get_final_volume(0), 0.125);
+        assert_eq!(mixer.get_final_volume(1), 0.5);
+        assert_eq!(mixer.get_final_volume(MUSIC_CHANNEL), 0.4);
+    }
+
+    #[test]
+    fn the_sidechain_dips_only_the_music_and_lets_it_back_up() {
+        let mut mixer = AudioMixer::new();
+        mixer.set_sidechain(true);
+        mixer.update(SIDECHAIN_ATTACK);
+        let music = BusVolumes::DEFAULT.music;
+        assert!((mixer.get_final_volume(MUSIC_CHANNEL) - music * SIDECHAIN_DEPTH).abs() < 1e-5);
+        assert_eq!(mixer.get_final_volume(0), 1.0);
+
+        mixer.set_sidechain(false);
+        mixer.update(SIDECHAIN_RELEASE * 0.5);
+        assert!(mixer.get_final_volume(MUSIC_CHANNEL) < music);
+        mixer.update(SIDECHAIN_RELEASE);
+        assert_eq!(mixer.get_final_volume(MUSIC_CHANNEL), music);
     }
 }
@@ -1,6 +1,10 @@
+pub mod announcer;
 pub mod mixer;
 pub mod music;
 pub mod sfx;
+pub mod stingers;
+pub mod voice_bank;
 
 pub use mixer::AudioMixer;
-// MusicManager, MusicStem, ImpactType, SFXManager not yet used
+pub use music::{MusicCue, MusicManager};
+// MusicStem not yet used
@@ -1,9 +1,45 @@
+use crate::audio::stingers::{victory_stinger, Stinger};
+use crate::data::characters::CharacterId;
+use serde::{Deserialize, Serialize};
+
+/// The track every results screen shares, under each character's stinger
+pub const RESULTS_TRACK: &str = "results";
+/// The track under each wave's fighting
+pub const BATTLE_TRACK: &str = "battle";
+/// The shopkeeper's tune while they're set up between waves
+pub const SHOP_TRACK: &str = "shop";
+/// Track volume while a stinger is playing over it
+pub const STINGER_DUCK: f32 = 0.35;
+
+/// Something a state wants the music to do; the app plays them each frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MusicCue {
+    /// A round won mid-match: the stinger over whatever is playing
+    RoundWin {
+        character: CharacterId,
+        mastery_level: u32,
+    },
+    /// A match or run won: the stinger over the results track
+    Victory {
+        character: CharacterId,
+        mastery_level: u32,
+    },
+    /// The results track on its own
+    Results,
+    /// A wave starting
+    Battle,
+    /// The shopkeeper setting up between waves
+    Shop,
+}
+
 pub struct MusicManager {
     current_track: Option<String>,
     queued_track: Option<String>,
     crossfade_duration: f32,
     crossfade_timer: f32,
     stem_volumes: StemVolumes,
+    /// Stinger layered over the track and how long it has left
+    stinger: Option<(&'static Stinger, f32)>,
 }
 
 #[derive(Clone, Debug)]
@@ -27,10 +63,15 @@ impl MusicManager {
                 melody: 1.0,
                 harmony: 1.0,
             },
+            stinger: None,
         }
     }
 
     pub fn play_track(&mut self, track_name: String) {
+        let playing = self.queued_track.as_ref().or(self.current_track.as_ref());
+        if playing == Some(&track_name) {
+            return;
+        }
         if self.current_track.is_some() {
             self.queued_track = Some(track_name);
             self.crossfade_timer = 0.0;
@@ -39,7 +80,59 @@ impl MusicManager {
         }
     }
 
+    /// Layer a stinger over the track, cutting off any still playing
+    pub fn play_stinger(&mut self, stinger: &'static Stinger) {
+        self.stinger = Some((stinger, stinger.duration));
+    }
+
+    /// Play a cue, returning the stinger it started
+    pub fn play_cue(&mut self, cue: MusicCue, roll: usize) -> Option<&'static Stinger> {
+        let (character, mastery_level) = match cue {
+            MusicCue::RoundWin {
+                character,
+                mastery_level,
+            } => (character, mastery_level),
+            MusicCue::Victory {
+                character,
+                mastery_level,
+            } => {
+                self.play_track(RESULTS_TRACK.to_string());
+                (character, mastery_level)
+            }
+            MusicCue::Results => {
+                self.play_track(RESULTS_TRACK.to_string());
+                return None;
+            }
+            MusicCue::Battle => {
+                self.play_track(BATTLE_TRACK.to_string());
+                return None;
+            }
+            MusicCue::Shop => {
+                self.play_track(SHOP_TRACK.to_string());
+                return None;
+            }
+        };
+        let stinger = victory_stinger(character, mastery_level, roll);
+        self.play_stinger(stinger);
+        Some(stinger)
+    }
+
+    pub fn current_track(&self) -> Option<&str> {
+        self.current_track.as_deref()
+    }
+
+    pub fn stinger_active(&self) -> bool {
+        self.stinger.is_some()
+    }
+
     pub fn update(&mut self, dt: f32) {
+        if let Some((_, remaining)) = &mut self.stinger {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                self.stinger = None;
+            }
+        }
+
         if self.queued_track.is_some() {
             self.crossfade_timer += dt;
 
@@ -76,3 +169,26 @@ pub enum MusicStem {
     Melody,
     Harmony,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_victory_layers_its_stinger_over_the_results_track() {
+        let mut music = MusicManager::new();
+        let cue = MusicCue::Victory {
+            character: CharacterId::Luca,
+            mastery_level: 1,
+        };
+        let stinger = music.play_cue(cue, 0).expect("a victory plays a stinger");
+        assert_eq!(music.current_track(), Some(RESULTS_TRACK));
+        assert!(music.stinger_active());
+
+        // Entering the results screen keeps the track going rather than restarting it
+        assert!(music.play_cue(MusicCue::Results, 0).is_none());
+        music.update(stinger.duration);
+        assert_eq!(music.current_track(), Some(RESULTS_TRACK));
+        assert!(!music.stinger_active());
+    }
+}
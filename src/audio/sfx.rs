@@ -1,9 +1,18 @@
+use crate::audio::mixer::{Bus, SoundRequest};
+use crate::render::map_system::MapType;
 use macroquad::prelude::*;
 
+/// How far either way a positional sound's pitch is nudged, so repeats don't
+/// sound like one sample on a loop
+const PITCH_VARIATION: f32 = 0.08;
+
 pub struct SFXManager {
     impact_sounds: Vec<String>,
     voice_lines: Vec<VoiceLine>,
     environment_sounds: Vec<String>,
+    /// Typewriter blip per speaker, and the one anyone else gets
+    blip_sounds: Vec<(String, String)>,
+    default_blip: String,
 }
 
 #[derive(Clone, Debug)]
@@ -22,6 +31,7 @@ impl SFXManager {
                 "hit_heavy".to_string(),
                 "block".to_string(),
                 "parry".to_string(),
+                "hit_special".to_string(),
             ],
             voice_lines: Self::create_voice_lines(),
             environment_sounds: vec![
@@ -29,7 +39,23 @@ impl SFXManager {
                 "locker_slam".to_string(),
                 "paint_splash".to_string(),
                 "alarm_bell".to_string(),
+                "bomb_whistle".to_string(),
+                "explosion".to_string(),
             ],
+            blip_sounds: [
+                ("Bas", "blip_bas"),
+                ("Wolters", "blip_wolters"),
+                ("Berkay", "blip_berkay"),
+                ("Luca", "blip_luca"),
+                ("Nitin", "blip_nitin"),
+                ("Hadi", "blip_hadi"),
+                ("Bastiaan", "blip_bastiaan"),
+                ("Jad", "blip_jad"),
+            ]
+            .iter()
+            .map(|(speaker, id)| (speaker.to_string(), id.to_string()))
+            .collect(),
+            default_blip: "blip_default".to_string(),
         }
     }
 
@@ -92,9 +118,52 @@ impl SFXManager {
             ImpactType::Heavy => &self.impact_sounds[1],
             ImpactType::Block => &self.impact_sounds[2],
             ImpactType::Parry => &self.impact_sounds[3],
+            ImpactType::Special => &self.impact_sounds[4],
+        }
+    }
+
+    pub fn get_bomb_whistle(&self) -> &str {
+        &self.environment_sounds[4]
+    }
+
+    pub fn get_explosion_sound(&self) -> &str {
+        &self.environment_sounds[5]
+    }
+
+    /// Footsteps for what the map's floor is made of
+    pub fn get_footstep_sound(map: MapType) -> &'static str {
+        match map {
+            MapType::Classroom | MapType::Hallway | MapType::Kitchen => "footstep_tile",
+            MapType::Cafeteria => "footstep_linoleum",
+            MapType::Gym => "footstep_court",
+            MapType::Library | MapType::PrincipalOffice => "footstep_carpet",
+            MapType::Rooftop => "footstep_gravel",
+            MapType::JanitorCloset => "footstep_concrete",
+        }
+    }
+
+    /// `id` panned to where `screen_x` is across a screen `screen_width`
+    /// wide, at a slightly random pitch
+    pub fn positional(id: &str, screen_x: f32, screen_width: f32) -> SoundRequest {
+        let pan = if screen_width > 0.0 {
+            (screen_x / screen_width * 2.0 - 1.0).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        SoundRequest {
+            pan,
+            pitch: rand::gen_range(1.0 - PITCH_VARIATION, 1.0 + PITCH_VARIATION),
+            ..SoundRequest::new(Bus::Sfx, id)
         }
     }
 
+    pub fn get_blip_sound(&self, speaker: &str) -> &str {
+        self.blip_sounds
+            .iter()
+            .find(|(name, _)| name == speaker)
+            .map_or(&self.default_blip, |(_, id)| id)
+    }
+
     pub fn get_voice_line(&self, character: &str) -> Option<&VoiceLine> {
         self.voice_lines.iter().find(|v| v.character == character)
     }
@@ -121,4 +190,22 @@ pub enum ImpactType {
     Heavy,
     Block,
     Parry,
+    Special,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sounds_pan_across_the_screen_with_a_little_pitch_spread() {
+        let left = SFXManager::positional("hit_light", 0.0, 1920.0);
+        let middle = SFXManager::positional("hit_light", 960.0, 1920.0);
+        let offscreen = SFXManager::positional("hit_light", 4000.0, 1920.0);
+        assert_eq!(left.pan, -1.0);
+        assert_eq!(middle.pan, 0.0);
+        assert_eq!(offscreen.pan, 1.0);
+        assert!((left.pitch - 1.0).abs() <= PITCH_VARIATION);
+        assert_eq!(left.bus, Bus::Sfx);
+    }
 }
@@ -0,0 +1,128 @@
+use crate::data::characters::CharacterId;
+
+/// Mastery level that adds a character's alternate victory stinger to the rotation
+pub const ALT_STINGER_LEVEL: u32 = 15;
+
+/// A short musical hit played over the track when a character wins
+pub struct Stinger {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Seconds the track stays ducked under it
+    pub duration: f32,
+}
+
+/// Default and alternate (mastery-unlocked) stingers for one character
+pub struct VictoryTheme {
+    pub stingers: [Stinger; 2],
+}
+
+const fn stinger(id: &'static str, name: &'static str, duration: f32) -> Stinger {
+    Stinger { id, name, duration }
+}
+
+const fn theme(default: Stinger, alternate: Stinger) -> VictoryTheme {
+    VictoryTheme {
+        stingers: [default, alternate],
+    }
+}
+
+const BAS: VictoryTheme = theme(
+    stinger("stinger_bas_winter_arc", "Winter Arc", 3.0),
+    stinger("stinger_bas_clean_sweep", "Clean Sweep", 3.5),
+);
+const BERKAY: VictoryTheme = theme(
+    stinger("stinger_berkay_too_easy", "Too Easy", 2.5),
+    stinger("stinger_berkay_hair_flip", "Hair Flip", 3.0),
+);
+const GEFFERINHO: VictoryTheme = theme(
+    stinger("stinger_gefferinho_golazo", "Golazo", 3.0),
+    stinger("stinger_gefferinho_samba", "Samba", 3.5),
+);
+const HADI: VictoryTheme = theme(
+    stinger("stinger_hadi_aina", "Aina", 2.5),
+    stinger("stinger_hadi_pure_gold", "Pure Gold", 3.0),
+);
+const NITIN: VictoryTheme = theme(
+    stinger("stinger_nitin_burned_out", "Burned Out", 2.5),
+    stinger("stinger_nitin_spicy", "Spicy", 3.0),
+);
+const LUCA: VictoryTheme = theme(
+    stinger("stinger_luca_plan_complete", "Plan Complete", 3.0),
+    stinger("stinger_luca_on_schedule", "On Schedule", 2.5),
+);
+const PALABABA: VictoryTheme = theme(
+    stinger("stinger_palababa_babas_way", "Baba's Way", 3.5),
+    stinger("stinger_palababa_homework", "Homework", 3.0),
+);
+const FUFINHO: VictoryTheme = theme(
+    stinger("stinger_fufinho_eaten_up", "Eaten Up", 2.5),
+    stinger("stinger_fufinho_seconds", "Seconds", 3.0),
+);
+const EFEABI: VictoryTheme = theme(
+    stinger("stinger_efeabi_paid_in_full", "Paid In Full", 3.0),
+    stinger("stinger_efeabi_lemon_squeeze", "Lemon Squeeze", 2.5),
+);
+const JAD: VictoryTheme = theme(
+    stinger("stinger_jad_empty_bucket", "Empty Bucket", 2.5),
+    stinger("stinger_jad_extra_crispy", "Extra Crispy", 3.0),
+);
+const UMUT: VictoryTheme = theme(
+    stinger("stinger_umut_loot", "Loot", 3.0),
+    stinger("stinger_umut_achievement", "Achievement Unlocked", 3.5),
+);
+const KEIZER_BOM_TAHA: VictoryTheme = theme(
+    stinger("stinger_keizer_mission_complete", "Mission Complete", 4.0),
+    stinger("stinger_keizer_back_to_base", "Back To Base", 3.5),
+);
+const GOON_LORD_TOBESE: VictoryTheme = theme(
+    stinger("stinger_tobese_empty_glass", "Empty Glass", 3.0),
+    stinger("stinger_tobese_another_litre", "Another Litre", 3.5),
+);
+
+pub fn victory_theme(id: CharacterId) -> &'static VictoryTheme {
+    match id {
+        CharacterId::Bas => &BAS,
+        CharacterId::Berkay => &BERKAY,
+        CharacterId::Gefferinho => &GEFFERINHO,
+        CharacterId::Hadi => &HADI,
+        CharacterId::Nitin => &NITIN,
+        CharacterId::Luca => &LUCA,
+        CharacterId::PalaBaba => &PALABABA,
+        CharacterId::Fufinho => &FUFINHO,
+        CharacterId::EfeAbi => &EFEABI,
+        CharacterId::Jad => &JAD,
+        CharacterId::Umut => &UMUT,
+        CharacterId::KeizerBomTaha => &KEIZER_BOM_TAHA,
+        CharacterId::GoonLordTobese => &GOON_LORD_TOBESE,
+    }
+}
+
+/// The stinger to play for a win at `mastery_level`. Once the alternate is
+/// unlocked `roll` picks between the two; before that it's always the default.
+pub fn victory_stinger(
+    character: CharacterId,
+    mastery_level: u32,
+    roll: usize,
+) -> &'static Stinger {
+    let stingers = &victory_theme(character).stingers;
+    if mastery_level >= ALT_STINGER_LEVEL {
+        &stingers[roll % stingers.len()]
+    } else {
+        &stingers[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_alternate_stinger_waits_for_mastery() {
+        let default = victory_stinger(CharacterId::Bas, 1, 1);
+        assert_eq!(default.id, "stinger_bas_winter_arc");
+
+        let alternate = victory_stinger(CharacterId::Bas, ALT_STINGER_LEVEL, 1);
+        assert_eq!(alternate.id, "stinger_bas_clean_sweep");
+        assert_eq!(victory_stinger(CharacterId::Bas, ALT_STINGER_LEVEL, 2).id, default.id);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::audio::mixer::{Bus, SoundRequest};
+use crate::data::characters::CharacterId;
+use macroquad::prelude::*;
+
+/// Least time between one fighter's hit or hurt grunts, so a flurry doesn't
+/// turn into a wall of shouting
+const GRUNT_COOLDOWN: f32 = 0.9;
+/// Chance a landed hit gets a shout at all, once the cooldown allows one
+const HIT_CHANCE: f32 = 0.5;
+
+/// When a character speaks up in a fight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoiceCue {
+    Ability,
+    Hit,
+    Hurt,
+}
+
+impl VoiceCue {
+    fn tag(self) -> &'static str {
+        match self {
+            VoiceCue::Ability => "ability",
+            VoiceCue::Hit => "hit",
+            VoiceCue::Hurt => "hurt",
+        }
+    }
+}
+
+/// How many recorded takes a character has for each cue. Takes are named
+/// `voice_<prefix>_<cue>_<n>`, counting from 1.
+pub struct VoiceBank {
+    pub prefix: &'static str,
+    pub ability: usize,
+    pub hit: usize,
+    pub hurt: usize,
+}
+
+const fn bank(prefix: &'static str, ability: usize, hit: usize, hurt: usize) -> VoiceBank {
+    VoiceBank {
+        prefix,
+        ability,
+        hit,
+        hurt,
+    }
+}
+
+const BAS: VoiceBank = bank("bas", 3, 4, 3);
+const BERKAY: VoiceBank = bank("berkay", 3, 4, 3);
+const LUCA: VoiceBank = bank("luca", 3, 3, 3);
+const GEFFERINHO: VoiceBank = bank("gefferinho", 3, 4, 3);
+const HADI: VoiceBank = bank("hadi", 2, 3, 3);
+const NITIN: VoiceBank = bank("nitin", 3, 4, 3);
+const PALABABA: VoiceBank = bank("palababa", 2, 3, 3);
+const FUFINHO: VoiceBank = bank("fufinho", 2, 3, 2);
+const EFEABI: VoiceBank = bank("efeabi", 2, 3, 3);
+const JAD: VoiceBank = bank("jad", 3, 3, 3);
+const UMUT: VoiceBank = bank("umut", 2, 3, 2);
+const KEIZER_BOM_TAHA: VoiceBank = bank("keizer", 3, 3, 3);
+const GOON_LORD_TOBESE: VoiceBank = bank("tobese", 2, 3, 3);
+
+pub fn voice_bank(id: CharacterId) -> &'static VoiceBank {
+    match id {
+        CharacterId::Bas => &BAS,
+        CharacterId::Berkay => &BERKAY,
+        CharacterId::Luca => &LUCA,
+        CharacterId::Gefferinho => &GEFFERINHO,
+        CharacterId::Hadi => &HADI,
+        CharacterId::Nitin => &NITIN,
+        CharacterId::PalaBaba => &PALABABA,
+        CharacterId::Fufinho => &FUFINHO,
+        CharacterId::EfeAbi => &EFEABI,
+        CharacterId::Jad => &JAD,
+        CharacterId::Umut => &UMUT,
+        CharacterId::KeizerBomTaha => &KEIZER_BOM_TAHA,
+        CharacterId::GoonLordTobese => &GOON_LORD_TOBESE,
+    }
+}
+
+impl VoiceBank {
+    pub fn takes(&self, cue: VoiceCue) -> usize {
+        match cue {
+            VoiceCue::Ability => self.ability,
+            VoiceCue::Hit => self.hit,
+            VoiceCue::Hurt => self.hurt,
+        }
+    }
+
+    pub fn take_id(&self, cue: VoiceCue, take: usize) -> String {
+        format!("voice_{}_{}_{}", self.prefix, cue.tag(), take)
+    }
+}
+
+/// One fighter's voice: picks a take for each cue without repeating the last
+/// one, and keeps hit and hurt grunts spaced out. Abilities always speak.
+pub struct FighterVoice {
+    last_take: Option<(VoiceCue, usize)>,
+    grunt_cooldown: f32,
+}
+
+impl FighterVoice {
+    pub fn new() -> Self {
+        Self {
+            last_take: None,
+            grunt_cooldown: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.grunt_cooldown = (self.grunt_cooldown - dt).max(0.0);
+    }
+
+    pub fn speak(&mut self, character: CharacterId, cue: VoiceCue) {
+        if cue != VoiceCue::Ability {
+            if self.grunt_cooldown > 0.0 {
+                return;
+            }
+            if cue == VoiceCue::Hit && rand::gen_range(0.0, 1.0) > HIT_CHANCE {
+                return;
+            }
+            self.grunt_cooldown = GRUNT_COOLDOWN;
+        }
+
+        let bank = voice_bank(character);
+        let Some(take) = self.pick_take(cue, bank.takes(cue), rand::rand() as usize) else {
+            return;
+        };
+        self.last_take = Some((cue, take));
+        crate::data::queue_sound(SoundRequest::new(Bus::Voice, &bank.take_id(cue, take)));
+    }
+
+    /// A take from 1 to `takes` picked by `roll`, stepping past the one just
+    /// said when there's another to say
+    fn pick_take(&self, cue: VoiceCue, takes: usize, roll: usize) -> Option<usize> {
+        if takes == 0 {
+            return None;
+        }
+        let take = roll % takes + 1;
+        match self.last_take {
+            Some((last_cue, last)) if last_cue == cue && last == take && takes > 1 => {
+                Some(take % takes + 1)
+            }
+            _ => Some(take),
+        }
+    }
+}
+
+impl Default for FighterVoice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_take_is_not_said_twice_running() {
+        let mut voice = FighterVoice::new();
+        voice.last_take = Some((VoiceCue::Hit, 2));
+        assert_eq!(voice.pick_take(VoiceCue::Hit, 3, 1), Some(3));
+        assert_eq!(voice.pick_take(VoiceCue::Hurt, 3, 1), Some(2));
+        assert_eq!(voice.pick_take(VoiceCue::Hit, 1, 0), Some(1));
+        assert_eq!(voice.pick_take(VoiceCue::Hit, 0, 0), None);
+        let take = voice_bank(CharacterId::Bas).take_id(VoiceCue::Ability, 1);
+        assert_eq!(take, "voice_bas_ability_1");
+    }
+}
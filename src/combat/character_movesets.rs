@@ -1,6 +1,11 @@
 use macroquad::prelude::*;
 use crate::ecs::CharacterType;
 use crate::combat::plane_system::{PlaneSystem, BombPattern};
+use crate::combat::hitbox::{AttackHeight, HitType, Hitbox};
+use serde::{Deserialize, Serialize};
+
+/// Movesets are authored against 1000 HP; fighters on the stage have 100
+pub const STAGE_SCALE: f32 = 0.1;
 
 /// Complete character stats including all combat parameters
 #[derive(Clone, Debug)]
@@ -9,6 +14,7 @@ pub struct CharacterStats {
     pub max_health: f32,
     pub base_damage: f32,
     pub base_speed: f32,
+    pub attack_speed: f32,     // Divides light and heavy attack timings
     pub jump_force: f32,
     pub weight: f32,           // Affects knockback and fall speed
     pub air_mobility: f32,     // Control while airborne
@@ -59,7 +65,49 @@ pub struct MoveData {
     pub properties: Vec<MoveProperty>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+impl MoveData {
+    /// Seconds from the first startup frame to the last recovery frame
+    pub fn duration(&self) -> f32 {
+        (self.startup_frames + self.active_frames + self.recovery_frames) as f32 / 60.0
+    }
+
+    /// The move as a stage hitbox, its damage scaled by `damage_scale`.
+    /// Like the shared specials, only the ones that aren't projectiles break armor.
+    pub fn hitbox(&self, damage_scale: f32) -> Hitbox {
+        let hit_type = if self.properties.contains(&MoveProperty::Projectile) {
+            HitType::Projectile
+        } else {
+            HitType::Special
+        };
+        let can_juggle = self.properties.contains(&MoveProperty::Juggle);
+        Hitbox {
+            offset: self.hitbox_offset,
+            size: self.hitbox_size,
+            damage: self.damage * damage_scale,
+            hitstun: self.hitstun_frames as f32 / 60.0,
+            blockstun: self.blockstun_frames as f32 / 60.0,
+            pushback: self.knockback * 4.0,
+            launch_power: if can_juggle { Vec2::new(0.0, -300.0) } else { Vec2::ZERO },
+            armor_break: hit_type == HitType::Special,
+            hit_type,
+            can_juggle,
+            height: self.attack_height(),
+        }
+    }
+
+    /// Block height implied by the move's Overhead/Low properties
+    pub fn attack_height(&self) -> AttackHeight {
+        if self.properties.contains(&MoveProperty::Overhead) {
+            AttackHeight::Overhead
+        } else if self.properties.contains(&MoveProperty::Low) {
+            AttackHeight::Low
+        } else {
+            AttackHeight::Mid
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum MoveId {
     // Universal moves
     LightPunch,
@@ -101,6 +149,15 @@ pub enum MoveId {
     FoodFight,          // AoE debuff
     NutritionalValue,   // Self-heal
     ServingTime,        // Projectile barrage
+
+    // Roster signature specials
+    ArcRush,
+    HairFlip,
+    MasterPlan,
+    GoldenUppercut,
+    SpiceBurst,
+    Golazo,
+    BabaSlam,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -126,6 +183,8 @@ pub struct CharacterMoveset {
     pub character_type: CharacterType,
     pub stats: CharacterStats,
     pub moves: Vec<MoveData>,
+    /// What the Special button does; None keeps the shared paintbrush swing
+    pub special_move: Option<MoveId>,
     pub combo_routes: Vec<ComboRoute>,
     pub special_mechanics: CharacterMechanics,
 }
@@ -242,6 +301,7 @@ impl CharacterMoveset {
             max_health: 950.0,
             base_damage: 1.2,
             base_speed: 250.0,
+            attack_speed: 1.0,
             jump_force: 420.0,
             weight: 1.3,
             air_mobility: 0.8,
@@ -307,7 +367,7 @@ impl CharacterMoveset {
                 can_cancel: vec![MoveId::AerialAssault],
                 meter_gain: 10.0,
                 meter_cost: 0.0,
-                properties: vec![MoveProperty::Juggle],
+                properties: vec![MoveProperty::Juggle, MoveProperty::Overhead],
             },
             MoveData {
                 move_id: MoveId::OrderBarrage,
@@ -451,6 +511,7 @@ impl CharacterMoveset {
             character_type: CharacterType::KeizerBomTaha,
             stats,
             moves,
+            special_move: Some(MoveId::OrderBarrage),
             combo_routes,
             special_mechanics: CharacterMechanics::PlaneSystem {
                 plane_system: PlaneSystem::new(),
@@ -468,37 +529,235 @@ impl CharacterMoveset {
         }
     }
 
-    /// Default moveset for characters without custom movesets
+    /// Default moveset for characters without custom movesets: their own
+    /// stats and a signature special, and nothing else authored yet
     fn default_moveset(character_type: CharacterType) -> Self {
-        let stats = CharacterStats {
+        let special = Self::signature_special(character_type);
+        Self {
             character_type,
-            max_health: 1000.0,
-            base_damage: 1.0,
-            base_speed: 250.0,
+            stats: Self::roster_stats(character_type),
+            special_move: special.as_ref().map(|special| special.move_id),
+            moves: special.into_iter().collect(),
+            combo_routes: Vec::new(),
+            special_mechanics: CharacterMechanics::Standard,
+        }
+    }
+
+    /// Stock stats with a character's health, damage and speeds swapped in
+    fn standard_stats(
+        character_type: CharacterType,
+        max_health: f32,
+        base_damage: f32,
+        base_speed: f32,
+        attack_speed: f32,
+    ) -> CharacterStats {
+        CharacterStats {
+            character_type,
+            max_health,
+            base_damage,
+            base_speed,
+            attack_speed,
             jump_force: 400.0,
-            weight: 1.0,
+            weight: character_type.weight(),
             air_mobility: 1.0,
             dash_distance: 150.0,
             dash_cooldown: 1.0,
             block_strength: 0.75,
             parry_window: 0.13,
             special_traits: vec![],
-        };
+        }
+    }
 
-        Self {
-            character_type,
-            stats,
-            moves: Vec::new(),
-            combo_routes: Vec::new(),
-            special_mechanics: CharacterMechanics::Standard,
+    fn roster_stats(character_type: CharacterType) -> CharacterStats {
+        match character_type {
+            // The all-rounder everyone else is measured against
+            CharacterType::Bas => CharacterStats {
+                special_traits: vec![SpecialTrait::ComboExtender],
+                ..Self::standard_stats(character_type, 1000.0, 1.0, 260.0, 1.0)
+            },
+            // Quick hands and feet, folds under pressure
+            CharacterType::Berkay => CharacterStats {
+                air_mobility: 1.2,
+                dash_distance: 180.0,
+                block_strength: 0.65,
+                parry_window: 0.16,
+                special_traits: vec![SpecialTrait::LightWeight, SpecialTrait::AirDash],
+                ..Self::standard_stats(character_type, 850.0, 0.9, 300.0, 1.2)
+            },
+            // Patient, with the best guard on the roster
+            CharacterType::Luca => CharacterStats {
+                block_strength: 0.85,
+                parry_window: 0.18,
+                special_traits: vec![SpecialTrait::CounterAttacks, SpecialTrait::MeterBuilder],
+                ..Self::standard_stats(character_type, 950.0, 1.0, 270.0, 1.1)
+            },
+            // Slower swings that hit harder and launch
+            CharacterType::Hadi => CharacterStats {
+                special_traits: vec![SpecialTrait::ComboExtender],
+                ..Self::standard_stats(character_type, 1050.0, 1.1, 245.0, 0.95)
+            },
+            // All offence
+            CharacterType::Nitin => CharacterStats {
+                block_strength: 0.7,
+                ..Self::standard_stats(character_type, 950.0, 1.15, 255.0, 1.0)
+            },
+            // Fast and works from range
+            CharacterType::Gefferinho => CharacterStats {
+                dash_distance: 170.0,
+                special_traits: vec![SpecialTrait::AirDash, SpecialTrait::RangedSpecialist],
+                ..Self::standard_stats(character_type, 900.0, 1.0, 285.0, 1.1)
+            },
+            // Slow, heavy and hard to put down
+            CharacterType::YigitBaba => CharacterStats {
+                jump_force: 360.0,
+                air_mobility: 0.8,
+                dash_distance: 120.0,
+                block_strength: 0.9,
+                parry_window: 0.1,
+                special_traits: vec![SpecialTrait::HeavyArmor, SpecialTrait::SuperArmor],
+                ..Self::standard_stats(character_type, 1200.0, 1.25, 220.0, 0.85)
+            },
+            _ => Self::standard_stats(character_type, 1000.0, 1.0, 250.0, 1.0),
         }
     }
 
+    /// The move each playable character's Special button throws
+    fn signature_special(character_type: CharacterType) -> Option<MoveData> {
+        let special = match character_type {
+            CharacterType::Bas => MoveData {
+                move_id: MoveId::ArcRush,
+                name: "Arc Rush",
+                startup_frames: 8,
+                active_frames: 6,
+                recovery_frames: 16,
+                damage: 150.0,
+                hitstun_frames: 36,
+                blockstun_frames: 20,
+                knockback: Vec2::new(40.0, 0.0),
+                hitbox_offset: Vec2::new(70.0, 0.0),
+                hitbox_size: Vec2::new(130.0, 50.0),
+                can_cancel: vec![],
+                meter_gain: 12.0,
+                meter_cost: 0.0,
+                properties: vec![],
+            },
+            CharacterType::Berkay => MoveData {
+                move_id: MoveId::HairFlip,
+                name: "Hair Flip Flurry",
+                startup_frames: 4,
+                active_frames: 10,
+                recovery_frames: 10,
+                damage: 110.0,
+                hitstun_frames: 24,
+                blockstun_frames: 14,
+                knockback: Vec2::new(25.0, 0.0),
+                hitbox_offset: Vec2::new(45.0, 0.0),
+                hitbox_size: Vec2::new(90.0, 60.0),
+                can_cancel: vec![],
+                meter_gain: 10.0,
+                meter_cost: 0.0,
+                properties: vec![MoveProperty::MultiHit(3)],
+            },
+            CharacterType::Luca => MoveData {
+                move_id: MoveId::MasterPlan,
+                name: "Master Plan",
+                startup_frames: 10,
+                active_frames: 5,
+                recovery_frames: 14,
+                damage: 135.0,
+                hitstun_frames: 40,
+                blockstun_frames: 16,
+                knockback: Vec2::new(30.0, 0.0),
+                hitbox_offset: Vec2::new(95.0, 20.0),
+                hitbox_size: Vec2::new(140.0, 30.0),
+                can_cancel: vec![],
+                meter_gain: 14.0,
+                meter_cost: 0.0,
+                properties: vec![MoveProperty::Low],
+            },
+            CharacterType::Hadi => MoveData {
+                move_id: MoveId::GoldenUppercut,
+                name: "Golden Uppercut",
+                startup_frames: 9,
+                active_frames: 5,
+                recovery_frames: 20,
+                damage: 160.0,
+                hitstun_frames: 45,
+                blockstun_frames: 18,
+                knockback: Vec2::new(10.0, -35.0),
+                hitbox_offset: Vec2::new(40.0, -30.0),
+                hitbox_size: Vec2::new(70.0, 100.0),
+                can_cancel: vec![],
+                meter_gain: 12.0,
+                meter_cost: 0.0,
+                properties: vec![MoveProperty::Juggle],
+            },
+            CharacterType::Nitin => MoveData {
+                move_id: MoveId::SpiceBurst,
+                name: "Spice Burst",
+                startup_frames: 12,
+                active_frames: 10,
+                recovery_frames: 16,
+                damage: 140.0,
+                hitstun_frames: 30,
+                blockstun_frames: 18,
+                knockback: Vec2::new(35.0, -10.0),
+                hitbox_offset: Vec2::ZERO,
+                hitbox_size: Vec2::new(200.0, 160.0),
+                can_cancel: vec![],
+                meter_gain: 12.0,
+                meter_cost: 0.0,
+                properties: vec![],
+            },
+            CharacterType::Gefferinho => MoveData {
+                move_id: MoveId::Golazo,
+                name: "Golazo",
+                startup_frames: 14,
+                active_frames: 8,
+                recovery_frames: 12,
+                damage: 120.0,
+                hitstun_frames: 30,
+                blockstun_frames: 12,
+                knockback: Vec2::new(50.0, -10.0),
+                hitbox_offset: Vec2::new(150.0, 0.0),
+                hitbox_size: Vec2::new(70.0, 40.0),
+                can_cancel: vec![],
+                meter_gain: 10.0,
+                meter_cost: 0.0,
+                properties: vec![MoveProperty::Projectile],
+            },
+            CharacterType::YigitBaba => MoveData {
+                move_id: MoveId::BabaSlam,
+                name: "Baba Slam",
+                startup_frames: 18,
+                active_frames: 8,
+                recovery_frames: 22,
+                damage: 220.0,
+                hitstun_frames: 50,
+                blockstun_frames: 26,
+                knockback: Vec2::new(45.0, -20.0),
+                hitbox_offset: Vec2::new(30.0, 20.0),
+                hitbox_size: Vec2::new(180.0, 90.0),
+                can_cancel: vec![],
+                meter_gain: 16.0,
+                meter_cost: 0.0,
+                properties: vec![MoveProperty::Low, MoveProperty::Juggle],
+            },
+            _ => return None,
+        };
+        Some(special)
+    }
+
     /// Find move data by ID
     pub fn get_move(&self, move_id: MoveId) -> Option<&MoveData> {
         self.moves.iter().find(|m| m.move_id == move_id)
     }
 
+    /// The move behind the Special button, if this character has its own
+    pub fn special(&self) -> Option<&MoveData> {
+        self.get_move(self.special_move?)
+    }
+
     /// Check if a move can be performed
     pub fn can_perform_move(&self, move_id: MoveId, current_meter: f32) -> bool {
         if let Some(move_data) = self.get_move(move_id) {
@@ -571,3 +830,36 @@ impl FrameDataCalculator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_playable_character_plays_differently() {
+        let roster = [
+            CharacterType::Bas,
+            CharacterType::Berkay,
+            CharacterType::Luca,
+            CharacterType::Hadi,
+            CharacterType::Nitin,
+            CharacterType::Gefferinho,
+            CharacterType::YigitBaba,
+        ];
+        let movesets: Vec<_> = roster.iter().map(|&c| CharacterMoveset::for_character(c)).collect();
+
+        for (i, moveset) in movesets.iter().enumerate() {
+            let special = moveset.special().expect("each character has a signature special");
+            assert!(special.duration() > 0.0);
+
+            for other in &movesets[i + 1..] {
+                let (a, b) = (&moveset.stats, &other.stats);
+                let same = a.max_health == b.max_health
+                    && a.base_speed == b.base_speed
+                    && a.attack_speed == b.attack_speed;
+                assert!(!same, "{:?} and {:?} share stats", a.character_type, b.character_type);
+                assert_ne!(moveset.special_move, other.special_move);
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::ecs::FighterState;
+
+/// Attacks the assist button walks through, one per swing
+pub const ASSIST_ROUTE: [FighterState; 3] = [
+    FighterState::LightAttack,
+    FighterState::LightAttack,
+    FighterState::HeavyAttack,
+];
+/// Seconds without an assisted swing before the route starts over
+const ROUTE_RESET: f32 = 0.8;
+
+/// One-button combos for players who can't press several buttons in time.
+/// Holding the assist key swings through `ASSIST_ROUTE` like holding an
+/// attack key repeats it.
+pub struct ComboAssist {
+    step: usize,
+    idle: f32,
+}
+
+impl ComboAssist {
+    pub fn new() -> Self {
+        Self { step: 0, idle: 0.0 }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.idle += dt;
+        if self.idle > ROUTE_RESET {
+            self.step = 0;
+        }
+    }
+
+    /// The swing the assist throws next
+    pub fn next_attack(&self) -> FighterState {
+        ASSIST_ROUTE[self.step]
+    }
+
+    /// The swing went out; line up the one after it
+    pub fn advance(&mut self) {
+        self.step = (self.step + 1) % ASSIST_ROUTE.len();
+        self.idle = 0.0;
+    }
+}
+
+impl Default for ComboAssist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_route_loops_and_starts_over_after_a_pause() {
+        let mut assist = ComboAssist::new();
+        let swings: Vec<FighterState> = (0..4)
+            .map(|_| {
+                let swing = assist.next_attack();
+                assist.advance();
+                assist.update(0.3);
+                swing
+            })
+            .collect();
+        assert_eq!(swings[2], FighterState::HeavyAttack);
+        assert_eq!(swings[3], FighterState::LightAttack);
+
+        assert_eq!(assist.step, 1);
+        assist.update(ROUTE_RESET);
+        assert_eq!(assist.step, 0);
+    }
+}
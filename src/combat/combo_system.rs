@@ -1,4 +1,5 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 /// Advanced combo system with style rankings and juggling
@@ -17,7 +18,7 @@ pub struct ComboSystem {
 }
 
 /// Style rank for combo performance
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum StyleRank {
     D,
     C,
@@ -433,6 +434,19 @@ impl StyleRank {
         }
     }
 
+    /// Share a shopkeeper knocks off for a fighter with this much style
+    pub fn haggle_discount(&self) -> f32 {
+        match self {
+            StyleRank::D => 0.0,
+            StyleRank::C => 0.05,
+            StyleRank::B => 0.1,
+            StyleRank::A => 0.15,
+            StyleRank::S => 0.2,
+            StyleRank::SS => 0.25,
+            StyleRank::SSS => 0.3,
+        }
+    }
+
     pub fn get_title(&self) -> &str {
         match self {
             StyleRank::D => "Dull",
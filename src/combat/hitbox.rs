@@ -12,6 +12,7 @@ pub struct Hitbox {
     pub hit_type: HitType,
     pub can_juggle: bool,
     pub armor_break: bool,
+    pub height: AttackHeight,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -27,7 +28,67 @@ pub enum HitType {
     Projectile,
 }
 
+/// Which block stance an attack must be guarded with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackHeight {
+    Mid,      // Blocked standing or crouching
+    Overhead, // Must be blocked standing
+    Low,      // Must be blocked crouching
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockStance {
+    Standing,
+    Crouching,
+}
+
+impl AttackHeight {
+    /// Overheads beat crouch block, lows beat stand block
+    pub fn is_blocked_by(self, stance: BlockStance) -> bool {
+        match self {
+            AttackHeight::Mid => true,
+            AttackHeight::Overhead => stance == BlockStance::Standing,
+            AttackHeight::Low => stance == BlockStance::Crouching,
+        }
+    }
+
+    /// The stance that guards this attack
+    pub fn required_stance(self) -> Option<BlockStance> {
+        match self {
+            AttackHeight::Mid => None,
+            AttackHeight::Overhead => Some(BlockStance::Standing),
+            AttackHeight::Low => Some(BlockStance::Crouching),
+        }
+    }
+
+    /// The attack height that opens up a defender holding `stance`
+    pub fn beating(stance: BlockStance) -> Self {
+        match stance {
+            BlockStance::Standing => AttackHeight::Low,
+            BlockStance::Crouching => AttackHeight::Overhead,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AttackHeight::Mid => "MID",
+            AttackHeight::Overhead => "OVERHEAD",
+            AttackHeight::Low => "LOW",
+        }
+    }
+}
+
 impl Hitbox {
+    pub fn with_height(mut self, height: AttackHeight) -> Self {
+        self.height = height;
+        if height == AttackHeight::Overhead {
+            self.hit_type = HitType::Overhead;
+        } else if height == AttackHeight::Low {
+            self.hit_type = HitType::Sweep;
+        }
+        self
+    }
+
     pub fn new_light() -> Self {
         Self {
             offset: Vec2::new(40.0, 0.0),
@@ -40,6 +101,7 @@ impl Hitbox {
             hit_type: HitType::Light,
             can_juggle: false,
             armor_break: false,
+            height: AttackHeight::Mid,
         }
     }
 
@@ -55,6 +117,7 @@ impl Hitbox {
             hit_type: HitType::Heavy,
             can_juggle: false,
             armor_break: true,
+            height: AttackHeight::Mid,
         }
     }
 
@@ -70,6 +133,7 @@ impl Hitbox {
             hit_type: HitType::Launcher,
             can_juggle: true,
             armor_break: false,
+            height: AttackHeight::Mid,
         }
     }
 
@@ -86,6 +150,7 @@ impl Hitbox {
                 hit_type: HitType::Special,
                 can_juggle: false,
                 armor_break: true,
+                height: AttackHeight::Mid,
             },
             SpecialType::EraserBomb => Self {
                 offset: Vec2::ZERO,
@@ -98,6 +163,7 @@ impl Hitbox {
                 hit_type: HitType::Special,
                 can_juggle: true,
                 armor_break: true,
+                height: AttackHeight::Mid,
             },
             SpecialType::MarkerBlast => Self {
                 offset: Vec2::new(80.0, 0.0),
@@ -110,6 +176,7 @@ impl Hitbox {
                 hit_type: HitType::Projectile,
                 can_juggle: false,
                 armor_break: false,
+                height: AttackHeight::Mid,
             },
         }
     }
@@ -126,6 +193,7 @@ impl Hitbox {
             hit_type: HitType::Super,
             can_juggle: true,
             armor_break: true,
+            height: AttackHeight::Mid,
         }
     }
 }
@@ -0,0 +1,239 @@
+use crate::combat::character_movesets::{CharacterMoveset, FrameDataCalculator, MoveData, MoveId};
+use crate::data::CHARACTERS;
+use macroquad::prelude::*;
+use serde::Serialize;
+use std::path::Path;
+
+/// The body box training mode draws, centred on the fighter
+const BODY_SIZE: Vec2 = Vec2::new(60.0, 160.0);
+const CELL_WIDTH: f32 = 480.0;
+const CELL_HEIGHT: f32 = 360.0;
+const COLUMNS: usize = 3;
+const TITLE_HEIGHT: f32 = 80.0;
+/// Where the fighter stands inside a cell
+const FIGHTER_AT: Vec2 = Vec2::new(150.0, 170.0);
+
+const STARTUP_COLOR: Color = Color::new(0.3, 0.8, 0.4, 1.0);
+const ACTIVE_COLOR: Color = Color::new(0.9, 0.25, 0.25, 1.0);
+const RECOVERY_COLOR: Color = Color::new(0.3, 0.5, 0.9, 1.0);
+
+/// One character's moves as written to `<character>.json`
+#[derive(Serialize, Debug)]
+pub struct CharacterFrameData {
+    pub character: String,
+    pub max_health: f32,
+    pub base_damage: f32,
+    pub base_speed: f32,
+    pub weight: f32,
+    pub block_strength: f32,
+    pub parry_window: f32,
+    /// Width and height of the body the hitboxes are drawn against
+    pub body: [f32; 2],
+    pub moves: Vec<MoveFrameData>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MoveFrameData {
+    pub id: MoveId,
+    pub name: &'static str,
+    pub startup: u32,
+    pub active: u32,
+    pub recovery: u32,
+    pub total: u32,
+    pub damage: f32,
+    pub hitstun: u32,
+    pub blockstun: u32,
+    pub on_hit: i32,
+    pub on_block: i32,
+    pub safe_on_block: bool,
+    pub height: String,
+    /// Centre of the hitbox from the fighter's, facing right
+    pub hitbox_offset: [f32; 2],
+    pub hitbox_size: [f32; 2],
+    pub knockback: [f32; 2],
+    pub meter_gain: f32,
+    pub meter_cost: f32,
+    pub cancels_into: Vec<MoveId>,
+    pub properties: Vec<String>,
+}
+
+impl MoveFrameData {
+    pub fn from_move(data: &MoveData) -> Self {
+        Self {
+            id: data.move_id,
+            name: data.name,
+            startup: data.startup_frames,
+            active: data.active_frames,
+            recovery: data.recovery_frames,
+            total: data.startup_frames + data.active_frames + data.recovery_frames,
+            damage: data.damage,
+            hitstun: data.hitstun_frames,
+            blockstun: data.blockstun_frames,
+            on_hit: FrameDataCalculator::frame_advantage_on_hit(data),
+            on_block: FrameDataCalculator::frame_advantage_on_block(data),
+            safe_on_block: FrameDataCalculator::is_safe_on_block(data),
+            height: format!("{:?}", data.attack_height()),
+            hitbox_offset: data.hitbox_offset.to_array(),
+            hitbox_size: data.hitbox_size.to_array(),
+            knockback: data.knockback.to_array(),
+            meter_gain: data.meter_gain,
+            meter_cost: data.meter_cost,
+            cancels_into: data.can_cancel.clone(),
+            properties: data.properties.iter().map(|p| format!("{:?}", p)).collect(),
+        }
+    }
+}
+
+impl CharacterFrameData {
+    pub fn from_moveset(character: &str, moveset: &CharacterMoveset) -> Self {
+        let stats = &moveset.stats;
+        Self {
+            character: character.to_string(),
+            max_health: stats.max_health,
+            base_damage: stats.base_damage,
+            base_speed: stats.base_speed,
+            weight: stats.weight,
+            block_strength: stats.block_strength,
+            parry_window: stats.parry_window,
+            body: BODY_SIZE.to_array(),
+            moves: moveset.moves.iter().map(MoveFrameData::from_move).collect(),
+        }
+    }
+}
+
+/// Write every roster character's frame data to `directory`: a JSON file
+/// each, and a PNG sheet of hitboxes and frame bars for those with moves.
+/// Needs the window for drawing, so runs in place of the game.
+pub async fn export(directory: &Path) -> Result<usize, String> {
+    std::fs::create_dir_all(directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+
+    let mut written = 0;
+    for character in CHARACTERS.iter() {
+        let moveset = CharacterMoveset::for_character(character.id.to_character_type());
+        let data = CharacterFrameData::from_moveset(character.name, &moveset);
+        let stem = format!("{:?}", character.id).to_lowercase();
+
+        let json = serde_json::to_string_pretty(&data)
+            .map_err(|e| format!("Failed to serialize {} frame data: {}", character.name, e))?;
+        let json_path = directory.join(format!("{}.json", stem));
+        std::fs::write(&json_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", json_path.display(), e))?;
+        written += 1;
+
+        if data.moves.is_empty() {
+            continue;
+        }
+        let image = render_sheet(&data, &moveset).await;
+        let png_path = directory.join(format!("{}.png", stem));
+        image.export_png(&png_path.to_string_lossy());
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Draw the character's sheet off screen and read it back
+async fn render_sheet(data: &CharacterFrameData, moveset: &CharacterMoveset) -> Image {
+    let rows = data.moves.len().div_ceil(COLUMNS);
+    let width = CELL_WIDTH * COLUMNS as f32;
+    let height = TITLE_HEIGHT + CELL_HEIGHT * rows as f32;
+
+    let target = render_target(width as u32, height as u32);
+    set_camera(&Camera2D {
+        render_target: Some(target.clone()),
+        ..Camera2D::from_display_rect(Rect::new(0.0, 0.0, width, height))
+    });
+    clear_background(Color::new(0.1, 0.1, 0.13, 1.0));
+
+    let title = format!("{} - FRAME DATA", data.character.to_uppercase());
+    draw_text(&title, 24.0, 52.0, 40.0, WHITE);
+    let legend = [
+        ("Startup", STARTUP_COLOR),
+        ("Active", ACTIVE_COLOR),
+        ("Recovery", RECOVERY_COLOR),
+    ];
+    let mut legend_x = width - 300.0;
+    for (label, color) in legend {
+        draw_text(label, legend_x, 52.0, 22.0, color);
+        legend_x += measure_text(label, None, 22, 1.0).width + 20.0;
+    }
+
+    for (index, (frames, move_data)) in data.moves.iter().zip(&moveset.moves).enumerate() {
+        let x = (index % COLUMNS) as f32 * CELL_WIDTH;
+        let y = TITLE_HEIGHT + (index / COLUMNS) as f32 * CELL_HEIGHT;
+        render_cell(frames, move_data, vec2(x, y));
+    }
+
+    set_default_camera();
+    // The draws only reach the target once the frame is flushed
+    next_frame().await;
+    target.texture.get_texture_data()
+}
+
+fn render_cell(frames: &MoveFrameData, data: &MoveData, origin: Vec2) {
+    draw_rectangle_lines(origin.x, origin.y, CELL_WIDTH, CELL_HEIGHT, 1.0, DARKGRAY);
+    draw_text(frames.name, origin.x + 16.0, origin.y + 30.0, 26.0, WHITE);
+    let tag = format!("{:?} | {}", frames.id, frames.height);
+    draw_text(&tag, origin.x + 16.0, origin.y + 50.0, 16.0, GRAY);
+
+    // Body and hitbox, facing right
+    let fighter = origin + FIGHTER_AT;
+    let body = fighter - BODY_SIZE * 0.5;
+    draw_rectangle(body.x, body.y, BODY_SIZE.x, BODY_SIZE.y, Color::new(0.3, 0.5, 0.9, 0.5));
+    let hitbox = fighter + data.hitbox_offset - data.hitbox_size * 0.5;
+    let size = data.hitbox_size;
+    draw_rectangle(hitbox.x, hitbox.y, size.x, size.y, Color::new(0.9, 0.2, 0.2, 0.45));
+    draw_rectangle_lines(hitbox.x, hitbox.y, size.x, size.y, 2.0, ACTIVE_COLOR);
+
+    // One block per frame: startup, then active, then recovery
+    let bar_y = origin.y + 270.0;
+    let bar_width = CELL_WIDTH - 32.0;
+    let frame_width = bar_width / frames.total.max(1) as f32;
+    let phases = [
+        (frames.startup, STARTUP_COLOR),
+        (frames.active, ACTIVE_COLOR),
+        (frames.recovery, RECOVERY_COLOR),
+    ];
+    let mut bar_x = origin.x + 16.0;
+    for (count, color) in phases {
+        for _ in 0..count {
+            draw_rectangle(bar_x, bar_y, (frame_width - 1.0).max(1.0), 18.0, color);
+            bar_x += frame_width;
+        }
+    }
+
+    let timing = format!(
+        "Startup {}f  Active {}f  Recovery {}f  Total {}f",
+        frames.startup, frames.active, frames.recovery, frames.total
+    );
+    draw_text(&timing, origin.x + 16.0, bar_y + 40.0, 18.0, LIGHTGRAY);
+    let outcome = format!(
+        "Damage {}  On hit {:+}  On block {:+}{}",
+        frames.damage,
+        frames.on_hit,
+        frames.on_block,
+        if frames.safe_on_block { "" } else { " (unsafe)" }
+    );
+    draw_text(&outcome, origin.x + 16.0, bar_y + 62.0, 18.0, LIGHTGRAY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::CharacterType;
+
+    #[test]
+    fn frame_data_matches_the_moveset() {
+        let moveset = CharacterMoveset::for_character(CharacterType::KeizerBomTaha);
+        let data = CharacterFrameData::from_moveset("Keizer Bom Taha", &moveset);
+        assert_eq!(data.moves.len(), moveset.moves.len());
+
+        for (frames, move_data) in data.moves.iter().zip(&moveset.moves) {
+            assert_eq!(frames.total, moveset.get_total_frames(move_data.move_id));
+            assert_eq!(
+                frames.on_block,
+                move_data.blockstun_frames as i32 - move_data.recovery_frames as i32
+            );
+        }
+    }
+}
@@ -1,6 +1,12 @@
+use crate::coop::input_handler::KeyboardBindings;
+use crate::ui::input_display::{self, InputHistory, HISTORY_LENGTH};
+use crate::util::host::Host;
 use macroquad::prelude::*;
 use std::collections::VecDeque;
 
+/// Labels for the buttons `record_frame` packs, in bit order
+pub const BUTTON_LABELS: [&str; 6] = ["LP", "HP", "SP", "SU", "PA", "DO"];
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum InputAction {
     Left,
@@ -28,6 +34,8 @@ pub struct InputManager {
     buffer: VecDeque<InputEvent>,
     buffer_window: f64,
     current_state: InputState,
+    /// What was held each recorded tick, for the input display and replays
+    history: InputHistory,
 }
 
 #[derive(Clone, Debug)]
@@ -61,67 +69,69 @@ impl InputManager {
                 parry: false,
                 dodge: false,
             },
+            history: InputHistory::new(HISTORY_LENGTH),
         }
     }
 
-    pub fn update(&mut self) {
-        let current_time = get_time();
+    /// Read this frame's presses off P1's `keys`
+    pub fn update(&mut self, host: &dyn Host, keys: &KeyboardBindings) {
+        let current_time = host.time();
 
-        self.current_state.left = is_key_down(KeyCode::A);
-        self.current_state.right = is_key_down(KeyCode::D);
-        self.current_state.up = is_key_down(KeyCode::W);
-        self.current_state.down = is_key_down(KeyCode::S);
+        self.current_state.left = host.key_down(keys.move_left);
+        self.current_state.right = host.key_down(keys.move_right);
+        self.current_state.up = host.key_down(keys.move_up);
+        self.current_state.down = host.key_down(keys.move_down);
 
-        if is_key_pressed(KeyCode::A) {
+        if host.key_pressed(keys.move_left) {
             self.add_input(InputAction::Left, current_time, true);
         }
-        if is_key_released(KeyCode::A) {
+        if host.key_released(keys.move_left) {
             self.add_input(InputAction::Left, current_time, false);
         }
 
-        if is_key_pressed(KeyCode::D) {
+        if host.key_pressed(keys.move_right) {
             self.add_input(InputAction::Right, current_time, true);
         }
-        if is_key_released(KeyCode::D) {
+        if host.key_released(keys.move_right) {
             self.add_input(InputAction::Right, current_time, false);
         }
 
-        if is_key_pressed(KeyCode::W) {
+        if host.key_pressed(keys.move_up) {
             self.add_input(InputAction::Up, current_time, true);
             self.add_input(InputAction::Jump, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::S) {
+        if host.key_pressed(keys.move_down) {
             self.add_input(InputAction::Down, current_time, true);
             self.add_input(InputAction::Crouch, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::J) {
+        if host.key_pressed(keys.light_attack) {
             self.current_state.light_attack = true;
             self.add_input(InputAction::LightAttack, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::K) {
+        if host.key_pressed(keys.heavy_attack) {
             self.current_state.heavy_attack = true;
             self.add_input(InputAction::HeavyAttack, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::L) {
+        if host.key_pressed(keys.special_attack) {
             self.current_state.special = true;
             self.add_input(InputAction::Special, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::U) {
+        if host.key_pressed(KeyCode::U) {
             self.current_state.super_move = true;
             self.add_input(InputAction::Super, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::I) {
+        if host.key_pressed(KeyCode::I) {
             self.current_state.parry = true;
             self.add_input(InputAction::Parry, current_time, true);
         }
 
-        if is_key_pressed(KeyCode::O) {
+        if host.key_pressed(keys.dodge) {
             self.current_state.dodge = true;
             self.add_input(InputAction::Dodge, current_time, true);
         }
@@ -129,6 +139,34 @@ impl InputManager {
         self.clean_buffer(current_time);
     }
 
+    /// Sample the held direction and buttons into the history. Call once per
+    /// simulation tick so the frame counts line up with the game's frames.
+    pub fn record_frame(&mut self, keys: &KeyboardBindings) {
+        let axis = |negative: KeyCode, positive: KeyCode| {
+            is_key_down(positive) as i32 as f32 - is_key_down(negative) as i32 as f32
+        };
+        let x = axis(keys.move_left, keys.move_right);
+        let y = axis(keys.move_up, keys.move_down);
+        let direction = input_display::numpad_direction(x, y);
+        let buttons = input_display::pack_buttons(&[
+            is_key_down(keys.light_attack),
+            is_key_down(keys.heavy_attack),
+            is_key_down(keys.special_attack),
+            is_key_down(KeyCode::U),
+            is_key_down(KeyCode::I),
+            is_key_down(keys.dodge),
+        ]);
+        self.history.push(direction, buttons);
+    }
+
+    pub fn history(&self) -> &InputHistory {
+        &self.history
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
     fn add_input(&mut self, action: InputAction, timestamp: f64, pressed: bool) {
         self.buffer.push_back(InputEvent {
             action,
@@ -6,7 +6,8 @@ use crate::combat::{
     plane_system::PlaneSystem,
     boss_system::BossManager,
 };
-use crate::render::{EnhancedVFXSystem, ImpactType, AuraType};
+use crate::data::move_vfx::{MoveVfxTable, VfxMoment};
+use crate::render::{EnhancedVFXSystem, ImpactType, VFXManager};
 use crate::progression::{
     skill_tree::SkillTree,
     character_mastery::CharacterMastery,
@@ -20,6 +21,9 @@ pub struct CombatIntegrationManager {
     pub character_movesets: HashMap<CharacterType, CharacterMoveset>,
     pub character_states: HashMap<u32, CharacterCombatState>,  // entity_id -> state
     pub enhanced_vfx: EnhancedVFXSystem,
+    /// Per-move trails, sparks and cracks, picked from `move_vfx`
+    pub vfx: VFXManager,
+    pub move_vfx: MoveVfxTable,
     pub combo_tracker: ComboTracker,
     pub damage_calculator: DamageCalculator,
     pub hit_detector: HitDetector,
@@ -357,14 +361,21 @@ impl FrameDataDisplay {
         if self.show_hitboxes {
             if let Some(move_id) = state.current_move {
                 if let Some(move_data) = moveset.get_move(move_id) {
-                    let hitbox_pos = state.position + move_data.hitbox_offset;
+                    let active = state.move_frame > move_data.startup_frames
+                        && state.move_frame <= move_data.startup_frames + move_data.active_frames;
+                    let mut offset = move_data.hitbox_offset;
+                    if !state.facing_right {
+                        offset.x = -offset.x;
+                    }
+                    let hitbox_pos = state.position + offset;
+                    let color = if active { RED } else { Color::new(1.0, 0.0, 0.0, 0.3) };
                     draw_rectangle_lines(
                         hitbox_pos.x - move_data.hitbox_size.x / 2.0,
                         hitbox_pos.y - move_data.hitbox_size.y / 2.0,
                         move_data.hitbox_size.x,
                         move_data.hitbox_size.y,
                         2.0,
-                        RED,
+                        color,
                     );
                 }
             }
@@ -385,7 +396,11 @@ impl FrameDataDisplay {
         // Display frame data
         if self.show_frame_advantage {
             if let Some(move_id) = state.current_move {
-                let text = format!("Frame: {}", state.move_frame);
+                let height = moveset
+                    .get_move(move_id)
+                    .map(|m| m.attack_height().label())
+                    .unwrap_or("MID");
+                let text = format!("Frame: {} [{}]", state.move_frame, height);
                 draw_text(
                     &text,
                     state.position.x - 30.0,
@@ -851,6 +866,8 @@ impl CombatIntegrationManager {
             character_movesets,
             character_states: HashMap::new(),
             enhanced_vfx: EnhancedVFXSystem::new(),
+            vfx: VFXManager::new(),
+            move_vfx: MoveVfxTable::load(),
             combo_tracker: ComboTracker::new(),
             damage_calculator: DamageCalculator::new(),
             hit_detector: HitDetector::new(),
@@ -899,6 +916,7 @@ impl CombatIntegrationManager {
     pub fn update(&mut self, dt: f32) {
         // Update VFX
         self.enhanced_vfx.update(dt);
+        self.vfx.update(dt);
 
         // Update character states
         for state in self.character_states.values_mut() {
@@ -951,6 +969,31 @@ impl CombatIntegrationManager {
         }
     }
 
+    /// Play a move's swing effects where its hitbox comes out
+    pub fn spawn_swing_vfx(&mut self, entity_id: u32, move_id: MoveId) {
+        let Some(state) = self.character_states.get(&entity_id) else {
+            return;
+        };
+        let Some(move_data) = self
+            .character_movesets
+            .get(&state.character_type)
+            .and_then(|moveset| moveset.get_move(move_id))
+        else {
+            return;
+        };
+
+        let (direction, rotation) = if state.facing_right {
+            (1.0, 0.0)
+        } else {
+            (-1.0, std::f32::consts::PI)
+        };
+        let offset = move_data.hitbox_offset;
+        let position = state.position + Vec2::new(offset.x * direction, offset.y);
+        for effect in self.move_vfx.effects(move_data, VfxMoment::Swing) {
+            self.vfx.spawn_effect_rotated(effect, position, rotation);
+        }
+    }
+
     pub fn process_hit(
         &mut self,
         attacker_id: u32,
@@ -1006,6 +1049,10 @@ impl CombatIntegrationManager {
                         damage_result.is_critical,
                     );
 
+                    for effect in self.move_vfx.effects(move_data, VfxMoment::Hit) {
+                        self.vfx.spawn_effect(effect, hit_position);
+                    }
+
                     // Update defender state
                     self.character_states.insert(defender_id, defender);
                 }
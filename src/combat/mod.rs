@@ -1,15 +1,20 @@
 pub mod ai;
 pub mod boss_system;
 pub mod character_movesets;
+pub mod combo_assist;
 pub mod combos;
 pub mod combo_system;
 pub mod hitbox;
+pub mod hitbox_export;
 pub mod hurtbox;
 pub mod inputs;
 pub mod integration;
 pub mod meter;
 pub mod plane_system;
+pub mod plugin;
 pub mod supers;
+pub mod tag_team;
+pub mod versus_ai;
 pub mod weapons;
 
 // Only export what's actively used
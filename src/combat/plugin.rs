@@ -0,0 +1,10 @@
+use crate::ecs::{CombatSystem, Stage};
+use crate::plugins::{Plugin, PluginRegistry};
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_system(Stage::Combat, || Box::new(CombatSystem::new()));
+    }
+}
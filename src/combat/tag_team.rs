@@ -0,0 +1,153 @@
+use crate::combat::character_movesets::CharacterMoveset;
+use crate::data::{AbilityState, CharacterId};
+use crate::progression::CharacterMastery;
+use std::mem;
+
+/// Seconds between assist calls
+pub const ASSIST_COOLDOWN: f32 = 8.0;
+/// Seconds before the team can tag again
+pub const TAG_COOLDOWN: f32 = 2.5;
+/// Extra damage per assist or tag that lands inside one combo
+pub const TAG_COMBO_BONUS: f32 = 0.15;
+/// Most assists and tags one combo can stack
+pub const MAX_TAG_CHAIN: u32 = 3;
+
+/// The character waiting on the sidelines of a single-player run. They can
+/// be called in for one attack, or tagged in to take over; the two share
+/// one health bar, so tagging buys time but never heals.
+pub struct TagTeam {
+    pub partner: CharacterId,
+    pub partner_moveset: CharacterMoveset,
+    /// The bench keeps its ability ticking, so a tag doesn't reset it
+    pub partner_ability: AbilityState,
+    pub partner_mastery: CharacterMastery,
+    assist_cooldown: f32,
+    tag_cooldown: f32,
+    /// Assists and tags landed in the current combo
+    chain: u32,
+}
+
+impl TagTeam {
+    pub fn new(partner: CharacterId, mastery: CharacterMastery) -> Self {
+        Self {
+            partner,
+            partner_moveset: CharacterMoveset::for_character(partner.to_character_type()),
+            partner_ability: AbilityState::new(partner),
+            partner_mastery: mastery,
+            assist_cooldown: 0.0,
+            tag_cooldown: 0.0,
+            chain: 0,
+        }
+    }
+
+    /// `combo_live` is whether the point character's combo is still going;
+    /// the chain ends with it
+    pub fn update(&mut self, dt: f32, combo_live: bool) {
+        self.assist_cooldown = (self.assist_cooldown - dt).max(0.0);
+        self.tag_cooldown = (self.tag_cooldown - dt).max(0.0);
+        self.partner_ability.update(dt);
+        if !combo_live {
+            self.chain = 0;
+        }
+    }
+
+    /// How far the assist has recharged, 0 to 1
+    pub fn assist_charge(&self) -> f32 {
+        1.0 - self.assist_cooldown / ASSIST_COOLDOWN
+    }
+
+    /// How far the tag has recharged, 0 to 1
+    pub fn tag_charge(&self) -> f32 {
+        1.0 - self.tag_cooldown / TAG_COOLDOWN
+    }
+
+    /// Starts the assist cooldown if it's ready
+    pub fn call_assist(&mut self, combo_live: bool) -> bool {
+        if self.assist_cooldown > 0.0 {
+            return false;
+        }
+        self.assist_cooldown = ASSIST_COOLDOWN;
+        self.extend_chain(combo_live);
+        true
+    }
+
+    /// Swaps the point character's id, ability and mastery with the
+    /// partner's. The outgoing character can't be called straight back in.
+    pub fn tag(
+        &mut self,
+        point: &mut CharacterId,
+        ability: &mut AbilityState,
+        mastery: &mut CharacterMastery,
+        combo_live: bool,
+    ) -> bool {
+        if self.tag_cooldown > 0.0 {
+            return false;
+        }
+        mem::swap(point, &mut self.partner);
+        mem::swap(ability, &mut self.partner_ability);
+        mem::swap(mastery, &mut self.partner_mastery);
+        self.partner_moveset = CharacterMoveset::for_character(self.partner.to_character_type());
+        self.tag_cooldown = TAG_COOLDOWN;
+        self.assist_cooldown = self.assist_cooldown.max(TAG_COOLDOWN);
+        self.extend_chain(combo_live);
+        true
+    }
+
+    /// Assists and tags landed in the current combo
+    pub fn chain(&self) -> u32 {
+        self.chain
+    }
+
+    /// Damage multiplier the current tag combo is worth
+    pub fn damage_bonus(&self) -> f32 {
+        1.0 + self.chain as f32 * TAG_COMBO_BONUS
+    }
+
+    fn extend_chain(&mut self, combo_live: bool) {
+        if combo_live {
+            self.chain = (self.chain + 1).min(MAX_TAG_CHAIN);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagging_swaps_the_point_character_and_waits_out_the_cooldown() {
+        let mut team = TagTeam::new(CharacterId::Hadi, CharacterMastery::new(CharacterId::Hadi));
+        let mut point = CharacterId::Bas;
+        let mut ability = AbilityState::new(point);
+        let mut mastery = CharacterMastery::new(point);
+
+        assert!(team.tag(&mut point, &mut ability, &mut mastery, false));
+        assert_eq!(point, CharacterId::Hadi);
+        assert_eq!(ability.character_id, CharacterId::Hadi);
+        assert_eq!(mastery.character, CharacterId::Hadi);
+        assert_eq!(team.partner, CharacterId::Bas);
+        assert_eq!(team.partner_mastery.character, CharacterId::Bas);
+
+        assert!(!team.tag(&mut point, &mut ability, &mut mastery, false));
+        team.update(TAG_COOLDOWN, false);
+        assert!(team.tag(&mut point, &mut ability, &mut mastery, false));
+        assert_eq!(point, CharacterId::Bas);
+    }
+
+    #[test]
+    fn the_tag_combo_only_builds_inside_a_live_combo() {
+        let mut team = TagTeam::new(CharacterId::Luca, CharacterMastery::new(CharacterId::Luca));
+        assert!(team.call_assist(false));
+        assert_eq!(team.chain(), 0);
+        assert!(!team.call_assist(true));
+
+        team.update(ASSIST_COOLDOWN, true);
+        assert!(team.call_assist(true));
+        assert_eq!(team.chain(), 1);
+        assert!((team.damage_bonus() - (1.0 + TAG_COMBO_BONUS)).abs() < 1e-6);
+
+        team.update(0.1, false);
+        assert_eq!(team.chain(), 0);
+        assert_eq!(team.damage_bonus(), 1.0);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::combat::ai::AIAction;
+use crate::combat::character_movesets::CharacterMoveset;
+use crate::coop::input_handler::PlayerInput;
+use crate::ecs::CharacterType;
+use crate::util::rng::SeededRng;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How sharp the CPU plays
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CpuDifficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl CpuDifficulty {
+    pub const ALL: [CpuDifficulty; 3] =
+        [CpuDifficulty::Easy, CpuDifficulty::Normal, CpuDifficulty::Hard];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CpuDifficulty::Easy => "EASY",
+            CpuDifficulty::Normal => "NORMAL",
+            CpuDifficulty::Hard => "HARD",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let current = Self::ALL.iter().position(|&level| level == self).unwrap_or(0);
+        Self::ALL[(current + 1) % Self::ALL.len()]
+    }
+
+    /// Seconds between decisions
+    fn reaction_time(self) -> f32 {
+        match self {
+            CpuDifficulty::Easy => 0.45,
+            CpuDifficulty::Normal => 0.25,
+            CpuDifficulty::Hard => 0.12,
+        }
+    }
+
+    /// Chance of blocking an attack it sees coming, before the character's
+    /// own block strength is figured in
+    fn block_rate(self) -> f32 {
+        match self {
+            CpuDifficulty::Easy => 0.25,
+            CpuDifficulty::Normal => 0.55,
+            CpuDifficulty::Hard => 0.85,
+        }
+    }
+
+    /// Chance a decision is thrown away and the CPU just stands there
+    fn mistake_rate(self) -> f32 {
+        match self {
+            CpuDifficulty::Easy => 0.35,
+            CpuDifficulty::Normal => 0.15,
+            CpuDifficulty::Hard => 0.03,
+        }
+    }
+}
+
+/// What the CPU wants out of the fight
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CpuStyle {
+    /// Walks in and keeps swinging
+    Rushdown,
+    /// Holds the edge of its heavy's range and punishes whoever walks in
+    Zoner,
+    /// Blocks its way in to point blank, then goes for heavies
+    Grappler,
+}
+
+impl CpuStyle {
+    pub const ALL: [CpuStyle; 3] = [CpuStyle::Rushdown, CpuStyle::Zoner, CpuStyle::Grappler];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CpuStyle::Rushdown => "RUSHDOWN",
+            CpuStyle::Zoner => "ZONER",
+            CpuStyle::Grappler => "GRAPPLER",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let current = Self::ALL.iter().position(|&style| style == self).unwrap_or(0);
+        Self::ALL[(current + 1) % Self::ALL.len()]
+    }
+
+    /// The style's behaviour tree: a selector over sequences. The first
+    /// branch whose checks all pass picks the action.
+    fn tree(self) -> &'static [Branch] {
+        use AIAction::*;
+        use Check::*;
+        match self {
+            CpuStyle::Rushdown => &[
+                Branch(&[Incoming, Blocks], Block),
+                Branch(&[InLightRange, Roll(0.7)], LightAttack),
+                Branch(&[InHeavyRange], HeavyAttack),
+                Branch(&[], MoveToward),
+            ],
+            CpuStyle::Zoner => &[
+                Branch(&[Incoming, Blocks], Block),
+                Branch(&[Closer(0.6), Roll(0.6)], MoveAway),
+                Branch(&[InHeavyRange], HeavyAttack),
+                Branch(&[Farther(1.4)], MoveToward),
+                Branch(&[], Wait),
+            ],
+            CpuStyle::Grappler => &[
+                Branch(&[Incoming, Blocks], Block),
+                Branch(&[Closer(0.5)], HeavyAttack),
+                Branch(&[InLightRange, Roll(0.3)], LightAttack),
+                Branch(&[Roll(0.5)], Block),
+                Branch(&[], MoveToward),
+            ],
+        }
+    }
+}
+
+/// Picked on versus select when P2 is left to the CPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuSettings {
+    pub difficulty: CpuDifficulty,
+    pub style: CpuStyle,
+}
+
+impl Default for CpuSettings {
+    fn default() -> Self {
+        Self {
+            difficulty: CpuDifficulty::Normal,
+            style: CpuStyle::Rushdown,
+        }
+    }
+}
+
+/// What the CPU can see of the fight each frame
+#[derive(Clone, Copy, Debug)]
+pub struct CpuView {
+    /// Gap between the fighters
+    pub distance: f32,
+    /// 1.0 if the opponent is to the right, -1.0 if left
+    pub toward: f32,
+    /// The opponent's swing will land if nothing is done about it
+    pub incoming: bool,
+    pub can_act: bool,
+    pub light_range: f32,
+    pub heavy_range: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Check {
+    Incoming,
+    /// Passes as often as the CPU manages to block
+    Blocks,
+    InLightRange,
+    InHeavyRange,
+    /// Nearer than this share of the heavy's range
+    Closer(f32),
+    /// Further than this share of the heavy's range
+    Farther(f32),
+    Roll(f32),
+}
+
+struct Branch(&'static [Check], AIAction);
+
+/// A versus opponent. Reads the fight through `CpuView` and drives P2 with
+/// the same `PlayerInput` a human would.
+pub struct CpuOpponent {
+    settings: CpuSettings,
+    /// From the character's moveset; better blockers block more often
+    block_rate: f32,
+    decision_timer: f32,
+    action: AIAction,
+}
+
+impl CpuOpponent {
+    pub fn new(settings: CpuSettings, character: CharacterType) -> Self {
+        let stats = CharacterMoveset::for_character(character).stats;
+        // 0.75 is the stock block strength
+        let block_rate = settings.difficulty.block_rate() * stats.block_strength / 0.75;
+        Self {
+            settings,
+            block_rate: block_rate.clamp(0.0, 1.0),
+            decision_timer: 0.0,
+            action: AIAction::Wait,
+        }
+    }
+
+    /// This frame's input. A new action is only picked once the reaction
+    /// time is up; attacks are pressed on the frame they're picked, while
+    /// movement and blocking are held until the next decision. Every roll
+    /// comes from `rng`, so a replay with the match's seed picks the same.
+    pub fn think(&mut self, dt: f32, view: &CpuView, rng: &mut SeededRng) -> PlayerInput {
+        self.decision_timer -= dt;
+        let decided = self.decision_timer <= 0.0;
+        if decided {
+            self.decision_timer = self.settings.difficulty.reaction_time();
+            self.action = if rng.chance(self.settings.difficulty.mistake_rate()) {
+                AIAction::Wait
+            } else {
+                self.decide(view, || rng.next_f32())
+            };
+        }
+
+        let mut input = PlayerInput::default();
+        match self.action {
+            AIAction::MoveToward => input.movement.x = view.toward,
+            AIAction::MoveAway => input.movement.x = -view.toward,
+            AIAction::Block => input.block = true,
+            AIAction::LightAttack => {
+                input.light_attack = true;
+                input.light_attack_pressed = decided;
+            }
+            AIAction::HeavyAttack => {
+                input.heavy_attack = true;
+                input.heavy_attack_pressed = decided;
+            }
+            _ => {}
+        }
+        input
+    }
+
+    /// Walk the style's tree. `roll` gives a fresh number in `0..1` for each
+    /// chance check.
+    fn decide(&self, view: &CpuView, mut roll: impl FnMut() -> f32) -> AIAction {
+        let mut passes = |check: &Check| match *check {
+            Check::Incoming => view.incoming,
+            Check::Blocks => roll() < self.block_rate,
+            Check::InLightRange => view.distance <= view.light_range,
+            Check::InHeavyRange => view.distance <= view.heavy_range,
+            Check::Closer(share) => view.distance < view.heavy_range * share,
+            Check::Farther(share) => view.distance > view.heavy_range * share,
+            Check::Roll(chance) => roll() < chance,
+        };
+
+        for Branch(checks, action) in self.settings.style.tree() {
+            // Only block can interrupt a swing or hitstun
+            if !view.can_act && *action != AIAction::Block {
+                continue;
+            }
+            if checks.iter().all(&mut passes) {
+                return action.clone();
+            }
+        }
+        AIAction::Wait
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(distance: f32, incoming: bool) -> CpuView {
+        CpuView {
+            distance,
+            toward: 1.0,
+            incoming,
+            can_act: true,
+            light_range: 110.0,
+            heavy_range: 135.0,
+        }
+    }
+
+    fn cpu(difficulty: CpuDifficulty, style: CpuStyle) -> CpuOpponent {
+        CpuOpponent::new(CpuSettings { difficulty, style }, CharacterType::Bas)
+    }
+
+    #[test]
+    fn styles_want_different_distances() {
+        let far = view(400.0, false);
+        let close = view(60.0, false);
+        let always = || 0.0;
+
+        let rushdown = cpu(CpuDifficulty::Normal, CpuStyle::Rushdown);
+        assert_eq!(rushdown.decide(&far, always), AIAction::MoveToward);
+        assert_eq!(rushdown.decide(&close, always), AIAction::LightAttack);
+
+        let zoner = cpu(CpuDifficulty::Normal, CpuStyle::Zoner);
+        assert_eq!(zoner.decide(&close, always), AIAction::MoveAway);
+        assert_eq!(zoner.decide(&view(130.0, false), always), AIAction::HeavyAttack);
+
+        let grappler = cpu(CpuDifficulty::Normal, CpuStyle::Grappler);
+        assert_eq!(grappler.decide(&close, always), AIAction::HeavyAttack);
+    }
+
+    #[test]
+    fn harder_cpus_block_more() {
+        let incoming = view(100.0, true);
+        // A roll between the two block rates
+        let roll = || 0.7;
+        let easy = cpu(CpuDifficulty::Easy, CpuStyle::Rushdown);
+        let hard = cpu(CpuDifficulty::Hard, CpuStyle::Rushdown);
+        assert_ne!(easy.decide(&incoming, roll), AIAction::Block);
+        assert_eq!(hard.decide(&incoming, roll), AIAction::Block);
+
+        // Stuck in a swing, blocking is the only way out
+        let stuck = CpuView {
+            can_act: false,
+            ..view(60.0, false)
+        };
+        assert_eq!(hard.decide(&stuck, || 0.0), AIAction::Wait);
+    }
+}
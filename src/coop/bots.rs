@@ -0,0 +1,166 @@
+use crate::combat::hitbox::Hitbox;
+use crate::combat::versus_ai::{CpuDifficulty, CpuOpponent, CpuSettings, CpuStyle, CpuView};
+use crate::coop::input_handler::PlayerInput;
+use crate::data::characters::{AbilityEffect, Character, CharacterId};
+use crate::util::rng::SeededRng;
+use macroquad::prelude::*;
+
+/// Gap a bot keeps from P1 when there's nothing left to fight
+const FOLLOW_DISTANCE: f32 = 120.0;
+/// Enemies in reach before a bot spends stamina on its special
+const SPECIAL_CROWD: usize = 2;
+const SPECIAL_COOLDOWN: f32 = 4.0;
+/// Depth gap a swing still connects across
+const DEPTH_TOLERANCE: f32 = 20.0;
+
+/// What a bot can see of the stage each frame
+#[derive(Clone, Copy, Debug)]
+pub struct BotView<'a> {
+    pub position: Vec2,
+    /// Every enemy still standing
+    pub enemies: &'a [Vec2],
+    /// Out of hitstun and not mid-swing
+    pub can_act: bool,
+    /// P1's position, to tag along with between waves
+    pub leader: Vec2,
+}
+
+/// Plays an empty co-op seat as that seat's character. Fights through the
+/// versus CPU, lining up in depth first since swings only land on the same
+/// row, and leans on its special when it's crowded.
+pub struct CoopBot {
+    cpu: CpuOpponent,
+    light_range: f32,
+    heavy_range: f32,
+    special_timer: f32,
+}
+
+impl CoopBot {
+    pub fn new(character: CharacterId, difficulty: CpuDifficulty) -> Self {
+        let settings = CpuSettings {
+            difficulty,
+            style: Self::style_for(character),
+        };
+        let reach = |hitbox: Hitbox| hitbox.offset.x + hitbox.size.x * 0.5;
+        Self {
+            cpu: CpuOpponent::new(settings, character.to_character_type()),
+            light_range: reach(Hitbox::new_light()),
+            heavy_range: reach(Hitbox::new_heavy()),
+            special_timer: SPECIAL_COOLDOWN,
+        }
+    }
+
+    /// Bruisers with area or staying-power abilities wade in, ranged and
+    /// control abilities keep their distance, the rest rush down
+    fn style_for(character: CharacterId) -> CpuStyle {
+        match Character::get_by_id(character).effects.first() {
+            Some(
+                AbilityEffect::SplashDamage(..)
+                | AbilityEffect::HealthBoost(_)
+                | AbilityEffect::ArmorBoost(_),
+            ) => CpuStyle::Grappler,
+            Some(
+                AbilityEffect::ProjectileDamage(_)
+                | AbilityEffect::Slow(..)
+                | AbilityEffect::Stun(_),
+            ) => CpuStyle::Zoner,
+            _ => CpuStyle::Rushdown,
+        }
+    }
+
+    /// This frame's input for the seat, rolling off the simulation's `rng`
+    pub fn think(&mut self, dt: f32, view: &BotView, rng: &mut SeededRng) -> PlayerInput {
+        self.special_timer = (self.special_timer - dt).max(0.0);
+
+        let nearest = view.enemies.iter().copied().min_by(|a, b| {
+            let (a, b) = (a.distance(view.position), b.distance(view.position));
+            a.total_cmp(&b)
+        });
+        let Some(target) = nearest else {
+            return Self::follow(view);
+        };
+
+        let crowd = view
+            .enemies
+            .iter()
+            .filter(|enemy| enemy.distance(view.position) <= self.heavy_range)
+            .count();
+        if view.can_act && crowd >= SPECIAL_CROWD && self.special_timer <= 0.0 {
+            self.special_timer = SPECIAL_COOLDOWN;
+            return PlayerInput {
+                special_attack: true,
+                special_attack_pressed: true,
+                ..PlayerInput::default()
+            };
+        }
+
+        let gap = target - view.position;
+        let cpu_view = CpuView {
+            distance: gap.x.abs(),
+            toward: if gap.x >= 0.0 { 1.0 } else { -1.0 },
+            // Guests can't guard, so there's nothing to react to
+            incoming: false,
+            can_act: view.can_act,
+            light_range: self.light_range,
+            heavy_range: self.heavy_range,
+        };
+        let mut input = self.cpu.think(dt, &cpu_view, rng);
+        if gap.y.abs() > DEPTH_TOLERANCE {
+            input.movement.y = gap.y.signum();
+        }
+        input.movement = input.movement.normalize_or_zero();
+        input
+    }
+
+    fn follow(view: &BotView) -> PlayerInput {
+        let gap = view.leader - view.position;
+        let movement = if gap.length() > FOLLOW_DISTANCE {
+            gap.normalize()
+        } else {
+            Vec2::ZERO
+        };
+        PlayerInput {
+            movement,
+            ..PlayerInput::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(enemies: &[Vec2]) -> BotView<'_> {
+        BotView {
+            position: Vec2::ZERO,
+            enemies,
+            can_act: true,
+            leader: Vec2::new(400.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn with_the_wave_clear_a_bot_walks_back_to_p1() {
+        let mut bot = CoopBot::new(CharacterId::Bas, CpuDifficulty::Normal);
+        let rng = &mut SeededRng::new(1);
+        assert_eq!(bot.think(0.1, &view(&[]), rng).movement, Vec2::X);
+
+        let close = BotView {
+            position: Vec2::new(350.0, 0.0),
+            ..view(&[])
+        };
+        assert_eq!(bot.think(0.1, &close, rng).movement, Vec2::ZERO);
+    }
+
+    #[test]
+    fn a_crowded_bot_uses_its_special_then_waits_for_it() {
+        let mut bot = CoopBot::new(CharacterId::Bas, CpuDifficulty::Hard);
+        let crowd = [Vec2::new(40.0, 0.0), Vec2::new(-40.0, 0.0)];
+        let rng = &mut SeededRng::new(1);
+        assert!(!bot.think(0.1, &view(&crowd), rng).special_attack_pressed, "starts on cooldown");
+
+        let input = bot.think(SPECIAL_COOLDOWN, &view(&crowd), rng);
+        assert!(input.special_attack_pressed);
+        assert!(!bot.think(0.1, &view(&crowd), rng).special_attack_pressed);
+    }
+}
@@ -1,22 +1,36 @@
-use super::player_manager::PlayerSlot;
+use super::player_manager::{PlayerSlot, MAX_PLAYERS};
+use crate::util::host::Host;
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// Input device types
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum InputDevice {
     Keyboard,
+    /// Arrow-key side of the keyboard, for a second player sharing it
+    KeyboardArrows,
+    /// Numpad, for a third player on a full-size keyboard
+    KeyboardNumpad,
     Gamepad(u8),
+    /// A seat in an online game, driven by the inputs both sides exchanged
+    Network(u8),
+    /// A seat left to a bot, driven by what it picked this frame
+    Bot(u8),
 }
 
 /// Handles input for multiple players with different input devices
 pub struct CoopInputHandler {
-    keyboard_bindings: KeyboardBindings,
+    keyboard_bindings: ReboundKeys,
+    arrows_bindings: ReboundKeys,
+    numpad_bindings: KeyboardBindings,
     gamepad_bindings: GamepadBindings,
     last_gamepad_count: u8,
+    network_inputs: [PlayerInput; 2],
+    bot_inputs: [PlayerInput; MAX_PLAYERS],
 }
 
 /// Keyboard bindings for player 1
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 pub struct KeyboardBindings {
     pub move_up: KeyCode,
     pub move_down: KeyCode,
@@ -29,7 +43,12 @@ pub struct KeyboardBindings {
     pub dodge: KeyCode,
     pub block: KeyCode,
     pub interact: KeyCode, // For reviving
+    /// Held to open the ping wheel
+    pub ping: KeyCode,
     pub pause: KeyCode,
+    /// Menu confirm and back, e.g. on select screens
+    pub confirm: KeyCode,
+    pub back: KeyCode,
 }
 
 /// Gamepad bindings (standard layout)
@@ -42,6 +61,7 @@ pub struct GamepadBindings {
     pub dodge: GamepadButton,
     pub block: GamepadButton,
     pub interact: GamepadButton,
+    pub ping: GamepadButton,
     pub pause: GamepadButton,
 }
 
@@ -71,6 +91,8 @@ pub struct PlayerInput {
     pub dodge: bool,
     pub block: bool,
     pub interact: bool,
+    /// Ping wheel open
+    pub ping: bool,
     pub pause: bool,
 
     // Pressed this frame
@@ -82,40 +104,91 @@ pub struct PlayerInput {
     pub block_pressed: bool,
     pub interact_pressed: bool,
     pub pause_pressed: bool,
+
+    // Menu navigation, pressed this frame
+    pub left_pressed: bool,
+    pub right_pressed: bool,
+    pub up_pressed: bool,
+    pub down_pressed: bool,
+    pub confirm_pressed: bool,
+    pub back_pressed: bool,
 }
 
 impl CoopInputHandler {
     pub fn new() -> Self {
         Self {
-            keyboard_bindings: KeyboardBindings::default(),
+            keyboard_bindings: ReboundKeys::new(KeyboardLayout::Wasd),
+            arrows_bindings: ReboundKeys::new(KeyboardLayout::Arrows),
+            numpad_bindings: KeyboardBindings::numpad_layout(),
             gamepad_bindings: GamepadBindings::default(),
             last_gamepad_count: 0,
+            network_inputs: Default::default(),
+            bot_inputs: Default::default(),
         }
     }
 
     /// Get input for a specific player
-    pub fn get_player_input(&mut self, slot: PlayerSlot, device: InputDevice) -> PlayerInput {
+    pub fn get_player_input(
+        &mut self,
+        host: &dyn Host,
+        slot: PlayerSlot,
+        device: InputDevice,
+    ) -> PlayerInput {
         match device {
-            InputDevice::Keyboard => self.get_keyboard_input(),
+            InputDevice::Keyboard => {
+                Self::get_keyboard_input(host, self.keyboard_bindings.current())
+            }
+            InputDevice::KeyboardArrows => {
+                Self::get_keyboard_input(host, self.arrows_bindings.current())
+            }
+            InputDevice::KeyboardNumpad => Self::get_keyboard_input(host, &self.numpad_bindings),
             InputDevice::Gamepad(id) => self.get_gamepad_input(id),
+            InputDevice::Network(seat) => self
+                .network_inputs
+                .get(seat as usize)
+                .cloned()
+                .unwrap_or_default(),
+            InputDevice::Bot(seat) => self
+                .bot_inputs
+                .get(seat as usize)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The keys P1 plays with on the WASD side, rebinds and all
+    pub fn player_one_keys(&mut self) -> KeyboardBindings {
+        *self.keyboard_bindings.current()
+    }
+
+    /// Input an online seat plays with until the next tick's arrives
+    pub fn set_network_input(&mut self, seat: u8, input: PlayerInput) {
+        if let Some(current) = self.network_inputs.get_mut(seat as usize) {
+            *current = input;
+        }
+    }
+
+    /// Input a bot's seat plays with this frame
+    pub fn set_bot_input(&mut self, seat: u8, input: PlayerInput) {
+        if let Some(current) = self.bot_inputs.get_mut(seat as usize) {
+            *current = input;
         }
     }
 
-    /// Get keyboard input (Player 1 only)
-    fn get_keyboard_input(&self) -> PlayerInput {
-        let bindings = &self.keyboard_bindings;
+    /// Get keyboard input for one side of the keyboard
+    fn get_keyboard_input(host: &dyn Host, bindings: &KeyboardBindings) -> PlayerInput {
 
         let mut movement = Vec2::ZERO;
-        if is_key_down(bindings.move_up) {
+        if host.key_down(bindings.move_up) {
             movement.y -= 1.0;
         }
-        if is_key_down(bindings.move_down) {
+        if host.key_down(bindings.move_down) {
             movement.y += 1.0;
         }
-        if is_key_down(bindings.move_left) {
+        if host.key_down(bindings.move_left) {
             movement.x -= 1.0;
         }
-        if is_key_down(bindings.move_right) {
+        if host.key_down(bindings.move_right) {
             movement.x += 1.0;
         }
 
@@ -126,23 +199,31 @@ impl CoopInputHandler {
 
         PlayerInput {
             movement,
-            light_attack: is_key_down(bindings.light_attack),
-            heavy_attack: is_key_down(bindings.heavy_attack),
-            special_attack: is_key_down(bindings.special_attack),
-            ability: is_key_down(bindings.ability),
-            dodge: is_key_down(bindings.dodge),
-            block: is_key_down(bindings.block),
-            interact: is_key_down(bindings.interact),
-            pause: is_key_down(bindings.pause),
-
-            light_attack_pressed: is_key_pressed(bindings.light_attack),
-            heavy_attack_pressed: is_key_pressed(bindings.heavy_attack),
-            special_attack_pressed: is_key_pressed(bindings.special_attack),
-            ability_pressed: is_key_pressed(bindings.ability),
-            dodge_pressed: is_key_pressed(bindings.dodge),
-            block_pressed: is_key_pressed(bindings.block),
-            interact_pressed: is_key_pressed(bindings.interact),
-            pause_pressed: is_key_pressed(bindings.pause),
+            light_attack: host.key_down(bindings.light_attack),
+            heavy_attack: host.key_down(bindings.heavy_attack),
+            special_attack: host.key_down(bindings.special_attack),
+            ability: host.key_down(bindings.ability),
+            dodge: host.key_down(bindings.dodge),
+            block: host.key_down(bindings.block),
+            interact: host.key_down(bindings.interact),
+            ping: host.key_down(bindings.ping),
+            pause: host.key_down(bindings.pause),
+
+            light_attack_pressed: host.key_pressed(bindings.light_attack),
+            heavy_attack_pressed: host.key_pressed(bindings.heavy_attack),
+            special_attack_pressed: host.key_pressed(bindings.special_attack),
+            ability_pressed: host.key_pressed(bindings.ability),
+            dodge_pressed: host.key_pressed(bindings.dodge),
+            block_pressed: host.key_pressed(bindings.block),
+            interact_pressed: host.key_pressed(bindings.interact),
+            pause_pressed: host.key_pressed(bindings.pause),
+
+            left_pressed: host.key_pressed(bindings.move_left),
+            right_pressed: host.key_pressed(bindings.move_right),
+            up_pressed: host.key_pressed(bindings.move_up),
+            down_pressed: host.key_pressed(bindings.move_down),
+            confirm_pressed: host.key_pressed(bindings.confirm),
+            back_pressed: host.key_pressed(bindings.back),
         }
     }
 
@@ -167,6 +248,7 @@ impl CoopInputHandler {
             dodge: false,
             block: false,
             interact: false,
+            ping: false,
             pause: false,
 
             light_attack_pressed: false,
@@ -177,6 +259,13 @@ impl CoopInputHandler {
             block_pressed: false,
             interact_pressed: false,
             pause_pressed: false,
+
+            left_pressed: false,
+            right_pressed: false,
+            up_pressed: false,
+            down_pressed: false,
+            confirm_pressed: false,
+            back_pressed: false,
         }
     }
 
@@ -210,11 +299,6 @@ impl CoopInputHandler {
         false
     }
 
-    /// Remap keyboard bindings
-    pub fn remap_keyboard(&mut self, bindings: KeyboardBindings) {
-        self.keyboard_bindings = bindings;
-    }
-
     /// Remap gamepad bindings
     pub fn remap_gamepad(&mut self, bindings: GamepadBindings) {
         self.gamepad_bindings = bindings;
@@ -242,7 +326,10 @@ impl Default for KeyboardBindings {
             dodge: KeyCode::Space,
             block: KeyCode::LeftShift,
             interact: KeyCode::F,
+            ping: KeyCode::Q,
             pause: KeyCode::Escape,
+            confirm: KeyCode::J,
+            back: KeyCode::K,
         }
     }
 }
@@ -257,27 +344,318 @@ impl Default for GamepadBindings {
             dodge: GamepadButton::RB,           // Right Bumper
             block: GamepadButton::LB,           // Left Bumper
             interact: GamepadButton::A,         // Xbox A / PS Cross
+            ping: GamepadButton::Select,        // Select / Share
             pause: GamepadButton::Start,        // Start button
         }
     }
 }
 
 impl KeyboardBindings {
-    /// Alternative WASD + Arrow keys layout for 2-player keyboard
+    /// Alternative WASD + Arrow keys layout for 2-player keyboard. Everything
+    /// sits around the arrows so it works on keyboards without a numpad.
     pub fn arrows_layout() -> Self {
         Self {
             move_up: KeyCode::Up,
             move_down: KeyCode::Down,
             move_left: KeyCode::Left,
             move_right: KeyCode::Right,
-            light_attack: KeyCode::KpMultiply,  // Numpad *
-            heavy_attack: KeyCode::Minus,       // Regular minus key
-            special_attack: KeyCode::Equal,     // Regular plus/equal key
-            ability: KeyCode::RightShift,
-            dodge: KeyCode::KpDecimal,          // Numpad .
-            block: KeyCode::KpEnter,
-            interact: KeyCode::Kp0,
+            light_attack: KeyCode::Enter,
+            heavy_attack: KeyCode::Backspace,
+            special_attack: KeyCode::RightShift,
+            ability: KeyCode::RightControl,
+            dodge: KeyCode::Slash,
+            block: KeyCode::Period,
+            interact: KeyCode::Apostrophe,
+            ping: KeyCode::Semicolon,
+            pause: KeyCode::Escape,
+            confirm: KeyCode::Enter,
+            back: KeyCode::Backspace,
+        }
+    }
+
+    pub fn key(&self, action: BindAction) -> KeyCode {
+        match action {
+            BindAction::MoveUp => self.move_up,
+            BindAction::MoveDown => self.move_down,
+            BindAction::MoveLeft => self.move_left,
+            BindAction::MoveRight => self.move_right,
+            BindAction::LightAttack => self.light_attack,
+            BindAction::HeavyAttack => self.heavy_attack,
+            BindAction::SpecialAttack => self.special_attack,
+            BindAction::Ability => self.ability,
+            BindAction::Dodge => self.dodge,
+            BindAction::Block => self.block,
+            BindAction::Interact => self.interact,
+            BindAction::Ping => self.ping,
+            BindAction::Confirm => self.confirm,
+            BindAction::Back => self.back,
+        }
+    }
+
+    fn key_mut(&mut self, action: BindAction) -> &mut KeyCode {
+        match action {
+            BindAction::MoveUp => &mut self.move_up,
+            BindAction::MoveDown => &mut self.move_down,
+            BindAction::MoveLeft => &mut self.move_left,
+            BindAction::MoveRight => &mut self.move_right,
+            BindAction::LightAttack => &mut self.light_attack,
+            BindAction::HeavyAttack => &mut self.heavy_attack,
+            BindAction::SpecialAttack => &mut self.special_attack,
+            BindAction::Ability => &mut self.ability,
+            BindAction::Dodge => &mut self.dodge,
+            BindAction::Block => &mut self.block,
+            BindAction::Interact => &mut self.interact,
+            BindAction::Ping => &mut self.ping,
+            BindAction::Confirm => &mut self.confirm,
+            BindAction::Back => &mut self.back,
+        }
+    }
+
+    /// Put `action` on `key`. Whatever else in its group was on `key` takes
+    /// the key `action` had, so nothing ends up unbound. Menu keys are their
+    /// own group, since they're meant to share keys with attacks.
+    pub fn rebind(&mut self, action: BindAction, key: KeyCode) {
+        let old = self.key(action);
+        let clash = BindAction::ALL.into_iter().find(|&other| {
+            other != action && other.is_menu() == action.is_menu() && self.key(other) == key
+        });
+        if let Some(other) = clash {
+            *self.key_mut(other) = old;
+        }
+        *self.key_mut(action) = key;
+    }
+
+    /// Numpad layout for a third player sharing the keyboard
+    pub fn numpad_layout() -> Self {
+        Self {
+            move_up: KeyCode::Kp8,
+            move_down: KeyCode::Kp5,
+            move_left: KeyCode::Kp4,
+            move_right: KeyCode::Kp6,
+            light_attack: KeyCode::Kp1,
+            heavy_attack: KeyCode::Kp2,
+            special_attack: KeyCode::Kp3,
+            ability: KeyCode::KpEnter,
+            dodge: KeyCode::Kp0,
+            block: KeyCode::KpDecimal,
+            interact: KeyCode::KpAdd,
+            ping: KeyCode::Kp7,
             pause: KeyCode::Escape,
+            confirm: KeyCode::Kp1,
+            back: KeyCode::Kp2,
+        }
+    }
+}
+
+/// A side of the keyboard players can rebind from settings
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    /// Player 1, and the menus
+    Wasd,
+    /// Player 2 sharing the keyboard
+    Arrows,
+}
+
+impl KeyboardLayout {
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyboardLayout::Wasd => "PLAYER 1 (WASD)",
+            KeyboardLayout::Arrows => "PLAYER 2 (ARROWS)",
         }
     }
+
+    pub fn next(self) -> Self {
+        match self {
+            KeyboardLayout::Wasd => KeyboardLayout::Arrows,
+            KeyboardLayout::Arrows => KeyboardLayout::Wasd,
+        }
+    }
+
+    pub fn defaults(self) -> KeyboardBindings {
+        match self {
+            KeyboardLayout::Wasd => KeyboardBindings::default(),
+            KeyboardLayout::Arrows => KeyboardBindings::arrows_layout(),
+        }
+    }
+
+    /// The defaults with the player's rebinds for this side laid over them
+    pub fn bindings(self, rebinds: &[KeyRebind]) -> KeyboardBindings {
+        let mut bindings = self.defaults();
+        for rebind in rebinds.iter().filter(|rebind| rebind.layout == self) {
+            if let Some(key) = key_from_name(&rebind.key) {
+                bindings.rebind(rebind.action, key);
+            }
+        }
+        bindings
+    }
+}
+
+/// One side of the keyboard with the player's rebinds laid over it, rebuilt
+/// whenever the Controls page changes them
+#[derive(Clone)]
+pub struct ReboundKeys {
+    layout: KeyboardLayout,
+    bindings: KeyboardBindings,
+    /// Of the rebinds `bindings` was built from
+    revision: u32,
+}
+
+impl ReboundKeys {
+    pub fn new(layout: KeyboardLayout) -> Self {
+        Self {
+            layout,
+            revision: crate::data::get_key_rebinds_revision(),
+            bindings: layout.bindings(&crate::data::get_key_rebinds()),
+        }
+    }
+
+    /// The bindings as they stand, with any rebinds since the last look
+    pub fn current(&mut self) -> &KeyboardBindings {
+        let revision = crate::data::get_key_rebinds_revision();
+        if revision != self.revision {
+            self.bindings = self.layout.bindings(&crate::data::get_key_rebinds());
+            self.revision = revision;
+        }
+        &self.bindings
+    }
+}
+
+/// What a key can be bound to. Pause stays on Escape, which the game
+/// handles before any state sees it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    LightAttack,
+    HeavyAttack,
+    SpecialAttack,
+    Ability,
+    Dodge,
+    Block,
+    Interact,
+    Ping,
+    Confirm,
+    Back,
+}
+
+impl BindAction {
+    pub const ALL: [BindAction; 14] = [
+        BindAction::MoveUp,
+        BindAction::MoveDown,
+        BindAction::MoveLeft,
+        BindAction::MoveRight,
+        BindAction::LightAttack,
+        BindAction::HeavyAttack,
+        BindAction::SpecialAttack,
+        BindAction::Ability,
+        BindAction::Dodge,
+        BindAction::Block,
+        BindAction::Interact,
+        BindAction::Ping,
+        BindAction::Confirm,
+        BindAction::Back,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BindAction::MoveUp => "Move Up",
+            BindAction::MoveDown => "Move Down",
+            BindAction::MoveLeft => "Move Left",
+            BindAction::MoveRight => "Move Right",
+            BindAction::LightAttack => "Light Attack",
+            BindAction::HeavyAttack => "Heavy Attack",
+            BindAction::SpecialAttack => "Special Attack",
+            BindAction::Ability => "Ability",
+            BindAction::Dodge => "Dodge",
+            BindAction::Block => "Block",
+            BindAction::Interact => "Revive / Interact",
+            BindAction::Ping => "Ping Wheel",
+            BindAction::Confirm => "Menu Confirm",
+            BindAction::Back => "Menu Back",
+        }
+    }
+
+    fn is_menu(self) -> bool {
+        matches!(self, BindAction::Confirm | BindAction::Back)
+    }
+}
+
+/// A key moved off its default in settings, kept by name in the config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyRebind {
+    pub layout: KeyboardLayout,
+    pub action: BindAction,
+    pub key: String,
+}
+
+impl KeyRebind {
+    /// What `bindings` changed from the defaults of `layout`
+    pub fn differences(layout: KeyboardLayout, bindings: &KeyboardBindings) -> Vec<KeyRebind> {
+        let defaults = layout.defaults();
+        BindAction::ALL
+            .into_iter()
+            .filter(|&action| bindings.key(action) != defaults.key(action))
+            .map(|action| KeyRebind {
+                layout,
+                action,
+                key: key_name(bindings.key(action)),
+            })
+            .collect()
+    }
+}
+
+/// Keys that can be bound; the rest are the game's own or too odd to offer
+#[rustfmt::skip]
+pub const BINDABLE_KEYS: [KeyCode; 77] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Space, KeyCode::Apostrophe, KeyCode::Comma, KeyCode::Minus, KeyCode::Period,
+    KeyCode::Slash, KeyCode::Semicolon, KeyCode::Equal, KeyCode::LeftBracket,
+    KeyCode::Backslash, KeyCode::RightBracket, KeyCode::GraveAccent,
+    KeyCode::Enter, KeyCode::Tab, KeyCode::Backspace, KeyCode::Insert, KeyCode::Delete,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home, KeyCode::End,
+    KeyCode::LeftShift, KeyCode::LeftControl, KeyCode::LeftAlt,
+    KeyCode::RightShift, KeyCode::RightControl, KeyCode::RightAlt,
+    KeyCode::Kp0, KeyCode::Kp1, KeyCode::Kp2, KeyCode::Kp3, KeyCode::Kp4,
+    KeyCode::Kp5, KeyCode::Kp6, KeyCode::Kp7, KeyCode::Kp8, KeyCode::Kp9,
+];
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|&key| key_name(key) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_swaps_within_a_group_and_round_trips() {
+        let mut bindings = KeyboardBindings::default();
+        bindings.rebind(BindAction::Dodge, KeyCode::Q);
+        assert_eq!(bindings.dodge, KeyCode::Q);
+        assert_eq!(bindings.ping, KeyCode::Space);
+        // Confirm shares J with light attack on purpose
+        bindings.rebind(BindAction::Confirm, KeyCode::U);
+        assert_eq!(bindings.light_attack, KeyCode::J);
+
+        let rebinds = KeyRebind::differences(KeyboardLayout::Wasd, &bindings);
+        assert_eq!(rebinds.len(), 3);
+        let loaded = KeyboardLayout::Wasd.bindings(&rebinds);
+        assert_eq!(
+            BindAction::ALL.map(|action| loaded.key(action)),
+            BindAction::ALL.map(|action| bindings.key(action))
+        );
+        assert_eq!(KeyboardLayout::Arrows.bindings(&rebinds).dodge, KeyCode::Slash);
+    }
 }
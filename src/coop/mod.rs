@@ -1,9 +1,14 @@
+pub mod bots;
 pub mod player_manager;
+pub mod plugin;
 pub mod shared_systems;
 pub mod input_handler;
+pub mod ping;
 pub mod ui_coop;
 
 pub use player_manager::{CoopPlayerManager, CoopPlayer, PlayerSlot};
-pub use shared_systems::{SharedComboSystem, ReviveSystem};
-pub use input_handler::{CoopInputHandler, InputDevice};
+pub use shared_systems::{SharedComboSystem, ReviveSystem, SharedLivesPool, Teammate};
+pub use bots::{BotView, CoopBot};
+pub use input_handler::{CoopInputHandler, InputDevice, PlayerInput};
+pub use ping::{PingBoard, PingKind, PingWheel};
 pub use ui_coop::CoopUI;
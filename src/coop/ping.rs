@@ -0,0 +1,244 @@
+use crate::data::palette;
+use macroquad::prelude::*;
+
+/// Seconds a marker stays up, fading out over the last of them
+pub const PING_LIFETIME: f32 = 4.0;
+const FADE_TIME: f32 = 0.6;
+/// How far ahead of the player "danger" and "go here" land
+const PING_REACH: f32 = 180.0;
+/// Stick or key push needed to pick a wedge
+const WHEEL_DEADZONE: f32 = 0.5;
+const WHEEL_RADIUS: f32 = 70.0;
+
+/// What a teammate is told, one per wedge of the wheel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PingKind {
+    Danger,
+    GoHere,
+    Help,
+    Thanks,
+}
+
+impl PingKind {
+    /// Clockwise from the top, matching the wheel
+    pub const ALL: [PingKind; 4] = [
+        PingKind::Danger,
+        PingKind::GoHere,
+        PingKind::Help,
+        PingKind::Thanks,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PingKind::Danger => "DANGER",
+            PingKind::GoHere => "GO HERE",
+            PingKind::Help => "HELP",
+            PingKind::Thanks => "THANKS",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PingKind::Danger => Color::new(1.0, 0.25, 0.2, 1.0),
+            PingKind::GoHere => Color::new(0.3, 0.85, 1.0, 1.0),
+            PingKind::Help => Color::new(1.0, 0.75, 0.1, 1.0),
+            PingKind::Thanks => Color::new(0.4, 1.0, 0.45, 1.0),
+        }
+    }
+
+    /// The wedge `direction` points into: up, right, down or left
+    pub fn from_direction(direction: Vec2) -> Self {
+        if direction.y.abs() >= direction.x.abs() {
+            if direction.y < 0.0 {
+                PingKind::Danger
+            } else {
+                PingKind::Help
+            }
+        } else if direction.x > 0.0 {
+            PingKind::GoHere
+        } else {
+            PingKind::Thanks
+        }
+    }
+
+    /// Where the marker goes for a player at `position` aiming along `aim`.
+    /// Help and thanks are about the player, so they stay on them.
+    pub fn placement(self, position: Vec2, aim: Vec2) -> Vec2 {
+        match self {
+            PingKind::Danger | PingKind::GoHere => position + aim.normalize_or_zero() * PING_REACH,
+            PingKind::Help | PingKind::Thanks => position,
+        }
+    }
+
+    /// Unit offset of the wedge from the wheel's centre
+    fn wedge(self) -> Vec2 {
+        match self {
+            PingKind::Danger => Vec2::new(0.0, -1.0),
+            PingKind::GoHere => Vec2::new(1.0, 0.0),
+            PingKind::Help => Vec2::new(0.0, 1.0),
+            PingKind::Thanks => Vec2::new(-1.0, 0.0),
+        }
+    }
+}
+
+/// One player's wheel: open while the ping button is held, steered with
+/// movement, and sent when the button is let go. A quick tap says "go here".
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PingWheel {
+    open: bool,
+    choice: Option<PingKind>,
+    /// Last direction pushed while open, which aims the ping
+    aim: Vec2,
+}
+
+impl PingWheel {
+    /// Feed one frame or tick of input. Returns the ping and its aim on the
+    /// frame the button is released.
+    pub fn update(&mut self, held: bool, direction: Vec2) -> Option<(PingKind, Vec2)> {
+        if held {
+            self.open = true;
+            if direction.length() >= WHEEL_DEADZONE {
+                self.choice = Some(PingKind::from_direction(direction));
+                self.aim = direction;
+            }
+            return None;
+        }
+        if !std::mem::take(&mut self.open) {
+            return None;
+        }
+
+        let kind = self.choice.take().unwrap_or(PingKind::GoHere);
+        Some((kind, std::mem::take(&mut self.aim)))
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Draw the wheel around `center` in world space
+    pub fn render(&self, center: Vec2) {
+        if !self.open {
+            return;
+        }
+
+        draw_circle(center.x, center.y, WHEEL_RADIUS + 30.0, Color::new(0.0, 0.0, 0.0, 0.45));
+        for kind in PingKind::ALL {
+            let selected = self.choice == Some(kind);
+            let color = kind.color();
+            let position = center + kind.wedge() * WHEEL_RADIUS;
+            let radius = if selected { 26.0 } else { 20.0 };
+            let fill = Color::new(color.r, color.g, color.b, if selected { 0.9 } else { 0.35 });
+            draw_circle(position.x, position.y, radius, fill);
+            draw_circle_lines(position.x, position.y, radius, 2.0, color);
+
+            let size = if selected { 18.0 } else { 14.0 };
+            let dims = measure_text(kind.label(), None, size as u16, 1.0);
+            let text_color = if selected { WHITE } else { LIGHTGRAY };
+            let x = position.x - dims.width * 0.5;
+            draw_text(kind.label(), x, position.y + 5.0, size, text_color);
+        }
+    }
+}
+
+/// A marker a teammate placed
+#[derive(Clone, Copy, Debug)]
+pub struct PingMarker {
+    pub kind: PingKind,
+    pub position: Vec2,
+    /// Seat of the player who placed it, for their colour
+    pub seat: usize,
+    age: f32,
+}
+
+/// Every live marker. Each player has one at a time so spamming the wheel
+/// doesn't litter the stage.
+#[derive(Default)]
+pub struct PingBoard {
+    markers: Vec<PingMarker>,
+}
+
+impl PingBoard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn place(&mut self, seat: usize, kind: PingKind, position: Vec2) {
+        self.markers.retain(|marker| marker.seat != seat);
+        self.markers.push(PingMarker {
+            kind,
+            position,
+            seat,
+            age: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for marker in &mut self.markers {
+            marker.age += dt;
+        }
+        self.markers.retain(|marker| marker.age < PING_LIFETIME);
+    }
+
+    pub fn markers(&self) -> &[PingMarker] {
+        &self.markers
+    }
+
+    /// Draw the markers in world space: a pulsing ring on the ground, a post
+    /// and the label in the placer's colour
+    pub fn render(&self) {
+        for marker in &self.markers {
+            let fade = ((PING_LIFETIME - marker.age) / FADE_TIME).clamp(0.0, 1.0);
+            let color = marker.kind.color();
+            let faded = |alpha: f32| Color::new(color.r, color.g, color.b, alpha * fade);
+            let Vec2 { x, y } = marker.position;
+
+            let pulse = (marker.age * 3.0).fract();
+            draw_circle_lines(x, y, 18.0 + pulse * 30.0, 3.0, faded(1.0 - pulse));
+            draw_ellipse(x, y, 22.0, 9.0, 0.0, faded(0.35));
+            draw_line(x, y, x, y - 70.0, 3.0, faded(0.9));
+            draw_circle(x, y - 78.0, 10.0, faded(1.0));
+
+            let owner = palette::active().player_color(marker.seat);
+            let label = marker.kind.label();
+            let dims = measure_text(label, None, 20, 1.0);
+            let owner = Color::new(owner.r, owner.g, owner.b, fade);
+            draw_text(label, x - dims.width * 0.5, y - 96.0, 20.0, owner);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_wheel_sends_on_release_and_a_tap_says_go_here() {
+        let mut wheel = PingWheel::default();
+        assert_eq!(wheel.update(true, Vec2::new(0.0, -1.0)), None);
+        assert!(wheel.is_open());
+        // Letting the stick go back to centre keeps the wedge
+        assert_eq!(wheel.update(true, Vec2::ZERO), None);
+        let (kind, aim) = wheel.update(false, Vec2::ZERO).unwrap();
+        assert_eq!(kind, PingKind::Danger);
+        assert_eq!(aim, Vec2::new(0.0, -1.0));
+        assert_eq!(wheel.update(false, Vec2::ZERO), None);
+
+        wheel.update(true, Vec2::ZERO);
+        assert_eq!(wheel.update(false, Vec2::ZERO), Some((PingKind::GoHere, Vec2::ZERO)));
+    }
+
+    #[test]
+    fn each_player_keeps_one_marker_until_it_expires() {
+        let mut board = PingBoard::new();
+        board.place(0, PingKind::Help, Vec2::ZERO);
+        board.place(0, PingKind::Thanks, Vec2::ZERO);
+        board.place(1, PingKind::Danger, Vec2::ZERO);
+        assert_eq!(board.markers().len(), 2);
+
+        board.update(PING_LIFETIME * 0.5);
+        board.place(1, PingKind::GoHere, Vec2::ZERO);
+        board.update(PING_LIFETIME * 0.6);
+        assert_eq!(board.markers().len(), 1);
+        assert_eq!(board.markers()[0].kind, PingKind::GoHere);
+    }
+}
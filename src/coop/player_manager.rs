@@ -1,3 +1,4 @@
+use super::input_handler::InputDevice;
 use crate::data::characters::CharacterId;
 use crate::ecs::entity::EntityId;
 use macroquad::prelude::*;
@@ -5,13 +6,8 @@ use macroquad::prelude::*;
 /// Maximum number of players in co-op
 pub const MAX_PLAYERS: usize = 4;
 
-/// Player colors for visual distinction
-pub const PLAYER_COLORS: [Color; 4] = [
-    Color::new(0.2, 0.5, 1.0, 1.0), // Blue (P1)
-    Color::new(1.0, 0.2, 0.2, 1.0), // Red (P2)
-    Color::new(0.2, 1.0, 0.2, 1.0), // Green (P3)
-    Color::new(1.0, 1.0, 0.2, 1.0), // Yellow (P4)
-];
+/// Seconds a downed player can wait for a revive before fully dying
+pub const BLEED_OUT_TIME: f32 = 30.0;
 
 /// Manages all players in a co-op session
 pub struct CoopPlayerManager {
@@ -30,7 +26,7 @@ pub struct CoopPlayer {
     pub entity_id: Option<EntityId>,
     pub character_type: CharacterId,
     pub color: Color,
-    pub input_device: InputDeviceType,
+    pub input_device: InputDevice,
     pub is_active: bool,
     pub is_downed: bool,
     pub revive_timer: f32,
@@ -52,13 +48,6 @@ pub enum PlayerSlot {
     Player4,
 }
 
-/// Input device type for each player
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum InputDeviceType {
-    KeyboardMouse,
-    Gamepad(u8), // Gamepad ID
-}
-
 impl CoopPlayerManager {
     pub fn new() -> Self {
         Self {
@@ -76,13 +65,13 @@ impl CoopPlayerManager {
         self.players[0] = Some(CoopPlayer::new(
             PlayerSlot::Player1,
             character,
-            InputDeviceType::KeyboardMouse,
+            InputDevice::Keyboard,
         ));
         self.active_player_count = 1;
     }
 
     /// Initialize for co-op mode with multiple players
-    pub fn init_coop(&mut self, player_configs: Vec<(CharacterId, InputDeviceType)>) {
+    pub fn init_coop(&mut self, player_configs: Vec<(CharacterId, InputDevice)>) {
         self.players = [None, None, None, None];
         self.active_player_count = player_configs.len().min(MAX_PLAYERS);
 
@@ -106,7 +95,7 @@ impl CoopPlayerManager {
     pub fn add_player(
         &mut self,
         character: CharacterId,
-        input_device: InputDeviceType,
+        input_device: InputDevice,
     ) -> Option<PlayerSlot> {
         if self.active_player_count >= MAX_PLAYERS {
             return None;
@@ -154,6 +143,15 @@ impl CoopPlayerManager {
         self.players[slot.to_index()].as_mut()
     }
 
+    /// The slot a device is playing in, if it has joined
+    pub fn slot_for_device(&self, device: InputDevice) -> Option<PlayerSlot> {
+        self.players
+            .iter()
+            .filter_map(|p| p.as_ref())
+            .find(|p| p.input_device == device)
+            .map(|p| p.slot)
+    }
+
     /// Get all active players
     pub fn get_active_players(&self) -> Vec<&CoopPlayer> {
         self.players
@@ -172,18 +170,21 @@ impl CoopPlayerManager {
             .collect()
     }
 
-    /// Update all players
-    pub fn update(&mut self, dt: f32) {
+    /// Update all players. Returns the players who bled out this frame.
+    pub fn update(&mut self, dt: f32) -> Vec<PlayerSlot> {
+        let mut bled_out = Vec::new();
+
         // Update downed players' revive timers
         for player in self.players.iter_mut().filter_map(|p| p.as_mut()) {
             if player.is_downed {
                 player.revive_timer += dt;
 
-                // Auto-respawn after 30 seconds if not revived
-                if player.revive_timer > 30.0 {
+                // Auto-respawn if not revived in time; this counts as a death
+                if player.revive_timer >= BLEED_OUT_TIME {
                     player.is_downed = false;
                     player.revive_timer = 0.0;
                     player.deaths += 1;
+                    bled_out.push(player.slot);
                 }
             }
         }
@@ -193,6 +194,8 @@ impl CoopPlayerManager {
             self.shared_combo_meter -= dt * 0.5;
             self.shared_combo_meter = self.shared_combo_meter.max(0.0);
         }
+
+        bled_out
     }
 
     /// Add to shared currency
@@ -258,6 +261,24 @@ impl CoopPlayerManager {
         }
     }
 
+    /// Skip the rest of a downed player's bleed-out timer
+    pub fn bleed_out(&mut self, slot: PlayerSlot) {
+        if let Some(player) = self.get_player_mut(slot) {
+            if player.is_downed {
+                player.revive_timer = BLEED_OUT_TIME;
+            }
+        }
+    }
+
+    /// Take a player out of the run for good (no lives left to respawn them)
+    pub fn eliminate(&mut self, slot: PlayerSlot) {
+        if let Some(player) = self.get_player_mut(slot) {
+            player.is_active = false;
+            player.is_downed = false;
+            player.revive_progress = 0.0;
+        }
+    }
+
     /// Update revive progress
     pub fn update_revive_progress(&mut self, slot: PlayerSlot, progress: f32) {
         if let Some(player) = self.get_player_mut(slot) {
@@ -334,12 +355,13 @@ impl CoopPlayerManager {
 }
 
 impl CoopPlayer {
-    pub fn new(slot: PlayerSlot, character: CharacterId, input_device: InputDeviceType) -> Self {
+    pub fn new(slot: PlayerSlot, character: CharacterId, input_device: InputDevice) -> Self {
         Self {
             slot,
             entity_id: None,
             character_type: character,
-            color: PLAYER_COLORS[slot.to_index()],
+            // Per-player colours come from the palette
+            color: crate::data::palette::active().player_color(slot.to_index()),
             input_device,
             is_active: true,
             is_downed: false,
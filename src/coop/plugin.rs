@@ -0,0 +1,25 @@
+use crate::plugins::{Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+
+/// Shared lives the settings page cycles through
+const LIVES_OPTIONS: [u32; 5] = [1, 3, 5, 7, 9];
+
+pub struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Co-op",
+            entries: vec![SettingsEntry {
+                label: |config| format!("Default Shared Lives: {}", config.coop_lives),
+                activate: |config| {
+                    let current = LIVES_OPTIONS
+                        .iter()
+                        .position(|&lives| lives == config.coop_lives)
+                        .unwrap_or(0);
+                    config.coop_lives = LIVES_OPTIONS[(current + 1) % LIVES_OPTIONS.len()];
+                    crate::data::set_coop_lives(config.coop_lives);
+                },
+            }],
+        });
+    }
+}
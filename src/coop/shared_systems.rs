@@ -1,7 +1,13 @@
-use super::player_manager::{CoopPlayerManager, PlayerSlot};
+use super::player_manager::{CoopPlayerManager, PlayerSlot, BLEED_OUT_TIME};
 use crate::ecs::entity::EntityId;
 use macroquad::prelude::*;
 
+/// Seconds after one player hits a target in which a partner's hit on the
+/// same target counts as a team attack
+pub const TEAM_ATTACK_WINDOW: f32 = 0.75;
+/// Seconds two players have between them to both call for the team super
+pub const TEAM_SUPER_WINDOW: f32 = 0.5;
+
 /// Shared combo system for cooperative gameplay
 pub struct SharedComboSystem {
     combo_count: u32,
@@ -9,7 +15,20 @@ pub struct SharedComboSystem {
     combo_decay_time: f32,
     last_attacker: Option<PlayerSlot>,
     combo_multiplier: f32,
+    /// Multiplier earned by tagging in a partner mid-combo, on top of the rank's
+    partner_bonus: f32,
     combo_rank: ComboRank,
+    /// Hits younger than `TEAM_ATTACK_WINDOW`, for spotting team attacks
+    recent_hits: Vec<RecentHit>,
+    /// Players who asked for the team super, with how long ago
+    super_requests: Vec<(PlayerSlot, f32)>,
+}
+
+#[derive(Clone, Copy)]
+struct RecentHit {
+    attacker: PlayerSlot,
+    target: EntityId,
+    age: f32,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -31,12 +50,25 @@ impl SharedComboSystem {
             combo_decay_time: 2.0,
             last_attacker: None,
             combo_multiplier: 1.0,
+            partner_bonus: 0.0,
             combo_rank: ComboRank::D,
+            recent_hits: Vec::new(),
+            super_requests: Vec::new(),
         }
     }
 
     /// Update combo system
     pub fn update(&mut self, dt: f32) {
+        for hit in &mut self.recent_hits {
+            hit.age += dt;
+        }
+        self.recent_hits.retain(|hit| hit.age < TEAM_ATTACK_WINDOW);
+
+        for (_, age) in &mut self.super_requests {
+            *age += dt;
+        }
+        self.super_requests.retain(|&(_, age)| age < TEAM_SUPER_WINDOW);
+
         if self.combo_count > 0 {
             self.combo_timer += dt;
 
@@ -47,19 +79,54 @@ impl SharedComboSystem {
         }
     }
 
-    /// Register a hit in the combo
-    pub fn register_hit(&mut self, attacker: PlayerSlot, is_different_from_last: bool) {
+    /// Register a hit in the combo. Any player's hit keeps the combo going.
+    /// Returns true for a team attack: a partner hit the same target within
+    /// `TEAM_ATTACK_WINDOW`.
+    pub fn register_hit(&mut self, attacker: PlayerSlot, target: EntityId) -> bool {
+        let is_different_from_last = self.last_attacker.is_some_and(|last| last != attacker);
+        let team_attack = self
+            .recent_hits
+            .iter()
+            .any(|hit| hit.target == target && hit.attacker != attacker);
+
+        self.recent_hits
+            .retain(|hit| !(hit.target == target && hit.attacker == attacker));
+        self.recent_hits.push(RecentHit {
+            attacker,
+            target,
+            age: 0.0,
+        });
+
         self.combo_count += 1;
         self.combo_timer = 0.0;
         self.last_attacker = Some(attacker);
 
         // Bonus for switching between players
         if is_different_from_last {
-            self.combo_multiplier += 0.1;
+            self.partner_bonus += 0.1;
         }
 
         // Update combo rank
         self.update_combo_rank();
+        team_attack
+    }
+
+    /// Record `player` calling for the team super. Returns the partner it pairs
+    /// up with if another player called for it within `TEAM_SUPER_WINDOW`.
+    pub fn request_team_super(&mut self, player: PlayerSlot) -> Option<PlayerSlot> {
+        let partner = self
+            .super_requests
+            .iter()
+            .map(|&(slot, _)| slot)
+            .find(|&slot| slot != player);
+
+        if partner.is_some() {
+            self.super_requests.clear();
+        } else {
+            self.super_requests.retain(|&(slot, _)| slot != player);
+            self.super_requests.push((player, 0.0));
+        }
+        partner
     }
 
     /// Get current combo count
@@ -82,6 +149,7 @@ impl SharedComboSystem {
         self.combo_count = 0;
         self.combo_timer = 0.0;
         self.combo_multiplier = 1.0;
+        self.partner_bonus = 0.0;
         self.combo_rank = ComboRank::D;
         self.last_attacker = None;
     }
@@ -107,7 +175,7 @@ impl SharedComboSystem {
             ComboRank::S => 2.5,
             ComboRank::SS => 3.0,
             ComboRank::SSS => 4.0,
-        };
+        } + self.partner_bonus;
     }
 
     /// Check if combo is active
@@ -147,16 +215,41 @@ impl ComboRank {
     }
 }
 
-/// Revive system for downed players
+/// Someone on the player's side who can be downed and revived
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Teammate {
+    Player(PlayerSlot),
+    /// AI ally; with no player slot, their bleed-out is tracked here
+    Ally(EntityId),
+}
+
+/// What happened to downed teammates during one `ReviveSystem::update`
+#[derive(Default)]
+pub struct ReviveOutcome {
+    /// Picked up before bleeding out
+    pub revived: Vec<Teammate>,
+    /// Downed allies whose bleed-out timer ran out
+    pub bled_out: Vec<EntityId>,
+    /// Finished off by an enemy standing over them
+    pub executed: Vec<Teammate>,
+}
+
+/// Revive system for downed players and allies
 pub struct ReviveSystem {
     active_revives: Vec<ActiveRevive>,
     revive_time: f32, // Time needed to complete revive
     revive_range: f32, // Distance within which players can revive
+    execute_time: f32, // Time an enemy needs over a downed teammate to finish them
+    execute_range: f32,
+    /// Downed allies and how long they've been down
+    downed_allies: Vec<(EntityId, f32)>,
+    /// Downed teammates an enemy is standing over, and how far along it is
+    executions: Vec<(Teammate, f32)>,
 }
 
 #[derive(Clone)]
 struct ActiveRevive {
-    downed_player: PlayerSlot,
+    downed: Teammate,
     reviver: PlayerSlot,
     progress: f32,
     position: Vec2,
@@ -168,80 +261,145 @@ impl ReviveSystem {
             active_revives: Vec::new(),
             revive_time: 3.0, // 3 seconds to revive
             revive_range: 100.0,
+            execute_time: 2.5,
+            execute_range: 90.0,
+            downed_allies: Vec::new(),
+            executions: Vec::new(),
         }
     }
 
+    /// Put an ally on the floor with a fresh bleed-out timer
+    pub fn down_ally(&mut self, ally: EntityId) {
+        if !self.is_ally_downed(ally) {
+            self.downed_allies.push((ally, 0.0));
+        }
+    }
+
+    pub fn is_ally_downed(&self, ally: EntityId) -> bool {
+        self.downed_allies.iter().any(|&(entity, _)| entity == ally)
+    }
+
+    /// Seconds a downed ally has been waiting for a revive
+    pub fn ally_down_time(&self, ally: EntityId) -> Option<f32> {
+        self.downed_allies
+            .iter()
+            .find(|&&(entity, _)| entity == ally)
+            .map(|&(_, elapsed)| elapsed)
+    }
+
     /// Start a revive action
-    pub fn start_revive(
-        &mut self,
-        downed_player: PlayerSlot,
-        reviver: PlayerSlot,
-        position: Vec2,
-    ) {
+    pub fn start_revive(&mut self, downed: Teammate, reviver: PlayerSlot, position: Vec2) {
         // Check if this revive is already in progress
-        if self.active_revives.iter().any(|r| r.downed_player == downed_player) {
+        if self.active_revives.iter().any(|r| r.downed == downed) {
             return;
         }
 
         self.active_revives.push(ActiveRevive {
-            downed_player,
+            downed,
             reviver,
             progress: 0.0,
             position,
         });
     }
 
-    /// Update revive progress
+    /// Advance revives, executions and allies' bleed-out. Revivers have to
+    /// stay in range and keep holding the button; `threatened` are the downed
+    /// teammates with an enemy within `get_execute_range`.
     pub fn update(
         &mut self,
         dt: f32,
         player_manager: &mut CoopPlayerManager,
-        player_positions: &[(PlayerSlot, Vec2)],
-    ) {
-        let mut completed_revives = Vec::new();
-        let mut cancelled_revives = Vec::new();
-
-        for (i, revive) in self.active_revives.iter_mut().enumerate() {
-            // Check if reviver is still in range
-            let reviver_pos = player_positions
+        positions: &[(Teammate, Vec2)],
+        holding: &[PlayerSlot],
+        threatened: &[Teammate],
+    ) -> ReviveOutcome {
+        let mut outcome = ReviveOutcome::default();
+        let position_of = |teammate: Teammate| {
+            positions
                 .iter()
-                .find(|(slot, _)| *slot == revive.reviver)
-                .map(|(_, pos)| *pos);
+                .find(|(other, _)| *other == teammate)
+                .map(|(_, pos)| *pos)
+        };
 
-            let downed_pos = player_positions
-                .iter()
-                .find(|(slot, _)| *slot == revive.downed_player)
-                .map(|(_, pos)| *pos);
-
-            if let (Some(rpos), Some(dpos)) = (reviver_pos, downed_pos) {
-                let distance = rpos.distance(dpos);
-
-                if distance <= self.revive_range {
-                    // In range, continue revive
-                    revive.progress += dt / self.revive_time;
-
-                    // Update player manager with progress
-                    player_manager.update_revive_progress(revive.downed_player, revive.progress);
-
-                    if revive.progress >= 1.0 {
-                        // Revive complete
-                        completed_revives.push(i);
-                        player_manager.revive_player(revive.downed_player);
-                    }
-                } else {
-                    // Out of range, cancel revive
-                    cancelled_revives.push(i);
+        let revive_range = self.revive_range;
+        let revive_time = self.revive_time;
+        self.active_revives.retain_mut(|revive| {
+            let reviver_pos = position_of(Teammate::Player(revive.reviver));
+            let downed_pos = position_of(revive.downed);
+
+            // Out of range, let go of the button, or players not found: cancel
+            let (Some(rpos), Some(dpos)) = (reviver_pos, downed_pos) else {
+                return false;
+            };
+            if rpos.distance(dpos) > revive_range || !holding.contains(&revive.reviver) {
+                if let Teammate::Player(slot) = revive.downed {
+                    player_manager.update_revive_progress(slot, 0.0);
                 }
-            } else {
-                // Players not found, cancel revive
-                cancelled_revives.push(i);
+                return false;
+            }
+
+            revive.progress += dt / revive_time;
+            if let Teammate::Player(slot) = revive.downed {
+                // Revives the player itself once progress reaches 100%
+                player_manager.update_revive_progress(slot, revive.progress);
+            }
+            if revive.progress < 1.0 {
+                return true;
+            }
+
+            outcome.revived.push(revive.downed);
+            false
+        });
+        for teammate in &outcome.revived {
+            if let Teammate::Ally(ally) = *teammate {
+                self.downed_allies.retain(|&(entity, _)| entity != ally);
             }
         }
 
-        // Remove completed and cancelled revives
-        for i in completed_revives.iter().chain(&cancelled_revives).rev() {
-            self.active_revives.remove(*i);
+        // Stepping away from the body resets an execution
+        self.executions.retain(|(teammate, _)| threatened.contains(teammate));
+        for &teammate in threatened {
+            if outcome.revived.contains(&teammate) {
+                continue;
+            }
+            if !self.executions.iter().any(|(other, _)| *other == teammate) {
+                self.executions.push((teammate, 0.0));
+            }
+        }
+        let execute_time = self.execute_time;
+        self.executions.retain_mut(|(teammate, progress)| {
+            *progress += dt / execute_time;
+            if *progress < 1.0 {
+                return true;
+            }
+            outcome.executed.push(*teammate);
+            false
+        });
+        for &teammate in &outcome.executed {
+            match teammate {
+                // The player manager's bleed-out handles lives and elimination
+                Teammate::Player(slot) => player_manager.bleed_out(slot),
+                Teammate::Ally(ally) => self.downed_allies.retain(|&(entity, _)| entity != ally),
+            }
         }
+
+        for (ally, elapsed) in &mut self.downed_allies {
+            *elapsed += dt;
+            if *elapsed >= BLEED_OUT_TIME {
+                outcome.bled_out.push(*ally);
+            }
+        }
+        self.downed_allies.retain(|(_, elapsed)| *elapsed < BLEED_OUT_TIME);
+
+        let finished: Vec<Teammate> = outcome
+            .executed
+            .iter()
+            .copied()
+            .chain(outcome.bled_out.iter().map(|&ally| Teammate::Ally(ally)))
+            .collect();
+        self.active_revives.retain(|r| !finished.contains(&r.downed));
+
+        outcome
     }
 
     /// Cancel a revive
@@ -249,23 +407,70 @@ impl ReviveSystem {
         self.active_revives.retain(|r| r.reviver != reviver);
     }
 
-    /// Get active revive for a player
-    pub fn get_active_revive(&self, downed_player: PlayerSlot) -> Option<(PlayerSlot, f32)> {
+    /// Get active revive for a downed teammate
+    pub fn get_active_revive(&self, downed: Teammate) -> Option<(PlayerSlot, f32)> {
         self.active_revives
             .iter()
-            .find(|r| r.downed_player == downed_player)
+            .find(|r| r.downed == downed)
             .map(|r| (r.reviver, r.progress))
     }
 
-    /// Check if player is being revived
-    pub fn is_being_revived(&self, player: PlayerSlot) -> bool {
-        self.active_revives.iter().any(|r| r.downed_player == player)
+    /// How close an enemy is to finishing off a downed teammate, 0 to 1
+    pub fn get_execution_progress(&self, downed: Teammate) -> f32 {
+        self.executions
+            .iter()
+            .find(|(other, _)| *other == downed)
+            .map_or(0.0, |&(_, progress)| progress)
+    }
+
+    /// Check if a teammate is being revived
+    pub fn is_being_revived(&self, downed: Teammate) -> bool {
+        self.active_revives.iter().any(|r| r.downed == downed)
     }
 
     /// Get revive range
     pub fn get_revive_range(&self) -> f32 {
         self.revive_range
     }
+
+    pub fn get_execute_range(&self) -> f32 {
+        self.execute_range
+    }
+}
+
+/// Continues shared by the whole co-op team. Getting downed and revived is
+/// free; only bleeding out completely spends a life.
+pub struct SharedLivesPool {
+    lives: u32,
+    max_lives: u32,
+}
+
+impl SharedLivesPool {
+    pub fn new(lives: u32) -> Self {
+        Self {
+            lives,
+            max_lives: lives,
+        }
+    }
+
+    /// Spend a life on a full death. Returns false if the pool is already empty.
+    pub fn consume(&mut self) -> bool {
+        if self.lives == 0 {
+            return false;
+        }
+        self.lives -= 1;
+        true
+    }
+
+    /// Get remaining lives
+    pub fn remaining(&self) -> u32 {
+        self.lives
+    }
+
+    /// Get lives the run started with
+    pub fn max_lives(&self) -> u32 {
+        self.max_lives
+    }
 }
 
 /// Team super move system
@@ -1,5 +1,6 @@
-use super::player_manager::{CoopPlayerManager, PlayerSlot, PLAYER_COLORS};
-use super::shared_systems::{SharedComboSystem, ReviveSystem};
+use super::player_manager::{CoopPlayerManager, PlayerSlot, BLEED_OUT_TIME};
+use super::shared_systems::{SharedComboSystem, ReviveSystem, SharedLivesPool, Teammate};
+use crate::ui::UiLayout;
 use macroquad::prelude::*;
 
 /// Co-op specific UI elements
@@ -20,21 +21,22 @@ impl CoopUI {
 
     /// Render player HUD elements for all active players
     pub fn render_player_huds(&self, player_manager: &CoopPlayerManager) {
+        let canvas = UiLayout::current();
         let active_players = player_manager.get_active_players();
         let player_count = active_players.len();
 
         for (i, player) in active_players.iter().enumerate() {
             // Calculate position for this player's HUD
             let x_offset = match player_count {
-                1 => screen_width() * 0.5,
-                2 => screen_width() * 0.25 + (i as f32 * screen_width() * 0.5),
+                1 => canvas.width() * 0.5,
+                2 => canvas.width() * 0.25 + (i as f32 * canvas.width() * 0.5),
                 3 | 4 => {
                     let cols = 2;
                     let row = i / cols;
                     let col = i % cols;
-                    screen_width() * 0.25 + (col as f32 * screen_width() * 0.5)
+                    canvas.width() * 0.25 + (col as f32 * canvas.width() * 0.5)
                 }
-                _ => screen_width() * 0.5,
+                _ => canvas.width() * 0.5,
             };
 
             let y_offset = match player_count {
@@ -106,6 +108,7 @@ impl CoopUI {
 
     /// Render shared combo counter
     pub fn render_combo_counter(&self, combo_system: &SharedComboSystem) {
+        let canvas = UiLayout::current();
         if !combo_system.is_combo_active() {
             return;
         }
@@ -115,7 +118,7 @@ impl CoopUI {
         let combo_color = combo_rank.to_color();
 
         // Center of screen, near top
-        let x = screen_width() * 0.5;
+        let x = canvas.width() * 0.5;
         let y = 100.0;
 
         // Combo count
@@ -143,11 +146,12 @@ impl CoopUI {
 
     /// Render shared currency
     pub fn render_shared_currency(&self, player_manager: &CoopPlayerManager) {
+        let canvas = UiLayout::current();
         let currency = player_manager.get_currency();
         let text = format!("${:.0}", currency);
 
         // Top right corner
-        let x = screen_width() - 150.0;
+        let x = canvas.width() - 150.0;
         let y = 30.0;
 
         draw_text(&text, x, y, self.font_size * 1.2, YELLOW);
@@ -211,6 +215,7 @@ impl CoopUI {
 
     /// Render player stats overlay (for pause/end screen)
     pub fn render_stats_overlay(&self, player_manager: &CoopPlayerManager) {
+        let canvas = UiLayout::current();
         if !self.show_stats {
             return;
         }
@@ -218,8 +223,8 @@ impl CoopUI {
         let bg_color = Color::new(0.0, 0.0, 0.0, 0.8);
         let panel_width = 600.0;
         let panel_height = 400.0;
-        let panel_x = (screen_width() - panel_width) * 0.5;
-        let panel_y = (screen_height() - panel_height) * 0.5;
+        let panel_x = (canvas.width() - panel_width) * 0.5;
+        let panel_y = (canvas.height() - panel_height) * 0.5;
 
         // Background panel
         draw_rectangle(panel_x, panel_y, panel_width, panel_height, bg_color);
@@ -267,12 +272,112 @@ impl CoopUI {
         }
     }
 
+    /// Render the shared lives pool as a row of hearts, top center
+    pub fn render_lives(&self, lives: &SharedLivesPool) {
+        let canvas = UiLayout::current();
+        let spacing = 26.0;
+        let total_width = spacing * lives.max_lives() as f32;
+        let x = canvas.width() * 0.5 - total_width * 0.5;
+        let y = 30.0;
+
+        let label = "TEAM LIVES";
+        let label_dims = measure_text(label, None, (self.font_size * 0.7) as u16, 1.0);
+        draw_text(
+            label,
+            canvas.width() * 0.5 - label_dims.width * 0.5,
+            y - 8.0,
+            self.font_size * 0.7,
+            WHITE,
+        );
+
+        for i in 0..lives.max_lives() {
+            let cx = x + spacing * (i as f32 + 0.5);
+            if i < lives.remaining() {
+                draw_circle(cx, y + 10.0, 9.0, Color::new(1.0, 0.25, 0.35, 1.0));
+            } else {
+                draw_circle_lines(cx, y + 10.0, 9.0, 2.0, Color::new(0.4, 0.4, 0.4, 1.0));
+            }
+        }
+    }
+
+    /// Render the marker over a downed teammate: time left before they bleed
+    /// out, revive progress drawn over it, and a warning while an enemy is
+    /// finishing them off
+    pub fn render_downed_marker(
+        &self,
+        position: Vec2,
+        label: &str,
+        color: Color,
+        bleed_out_remaining: f32,
+        revive_progress: f32,
+        execution_progress: f32,
+    ) {
+        let text = format!("{} DOWN {:.0}s", label, bleed_out_remaining.max(0.0));
+        let dims = measure_text(&text, None, 18, 1.0);
+        draw_text(&text, position.x - dims.width * 0.5, position.y - 90.0, 18.0, color);
+
+        let bar_width = 80.0;
+        let bar_x = position.x - bar_width * 0.5;
+        let bar_y = position.y - 84.0;
+        draw_rectangle(bar_x, bar_y, bar_width, 6.0, Color::new(0.2, 0.2, 0.2, 0.8));
+        draw_rectangle(
+            bar_x,
+            bar_y,
+            bar_width * (bleed_out_remaining / BLEED_OUT_TIME).clamp(0.0, 1.0),
+            6.0,
+            Color::new(0.9, 0.2, 0.2, 1.0),
+        );
+        if revive_progress > 0.0 {
+            draw_rectangle(bar_x, bar_y, bar_width * revive_progress.min(1.0), 6.0, GREEN);
+        }
+
+        if execution_progress > 0.0 {
+            // Flashes faster the closer the enemy is to finishing
+            let flash = (get_time() as f32 * (6.0 + execution_progress * 12.0)).sin() > 0.0;
+            let warning_color = if flash { RED } else { WHITE };
+            let warning = "EXECUTING!";
+            let warning_dims = measure_text(warning, None, 16, 1.0);
+            draw_text(
+                warning,
+                position.x - warning_dims.width * 0.5,
+                position.y - 110.0,
+                16.0,
+                warning_color,
+            );
+            draw_rectangle(
+                bar_x,
+                bar_y + 8.0,
+                bar_width * execution_progress.min(1.0),
+                3.0,
+                warning_color,
+            );
+        }
+    }
+
+    /// Render the "hold to revive" hint under a downed teammate in reach
+    pub fn render_revive_hint(&self, position: Vec2, key: &str, color: Color) {
+        let text = format!("Hold {} to revive", key);
+        let dims = measure_text(&text, None, 16, 1.0);
+        let x = position.x - dims.width * 0.5;
+        let y = position.y + 100.0;
+
+        draw_rectangle(
+            x - 6.0,
+            y - 16.0,
+            dims.width + 12.0,
+            22.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        draw_text(&text, x, y, 16.0, color);
+    }
+
     /// Render revive prompt
-    pub fn render_revive_prompt(&self, revive_system: &ReviveSystem, player_slot: PlayerSlot) {
-        if let Some((reviver, progress)) = revive_system.get_active_revive(player_slot) {
+    pub fn render_revive_prompt(&self, revive_system: &ReviveSystem, downed: Teammate) {
+        let canvas = UiLayout::current();
+        if let Some((reviver, progress)) = revive_system.get_active_revive(downed) {
             let text = format!("P{} reviving... {:.0}%", reviver.to_index() + 1, progress * 100.0);
-            let x = screen_width() * 0.5 - 100.0;
-            let y = screen_height() * 0.7;
+            let x = canvas.width() * 0.5 - 100.0;
+            let y = canvas.height() * 0.7;
 
             // Background
             draw_rectangle(x - 10.0, y - 30.0, 220.0, 40.0, Color::new(0.0, 0.0, 0.0, 0.7));
@@ -287,10 +392,11 @@ impl CoopUI {
         combo_meter: f32,
         super_cost: f32,
     ) {
+        let canvas = UiLayout::current();
         let bar_width = 200.0;
         let bar_height = 20.0;
-        let x = (screen_width() - bar_width) * 0.5;
-        let y = screen_height() - 60.0;
+        let x = (canvas.width() - bar_width) * 0.5;
+        let y = canvas.height() - 60.0;
 
         // Title
         draw_text("TEAM SUPER", x + 40.0, y - 10.0, self.font_size * 0.8, GOLD);
@@ -319,12 +425,13 @@ impl CoopUI {
 
     /// Render drop-in prompt
     pub fn render_drop_in_prompt(&self, available_slot: PlayerSlot) {
+        let canvas = UiLayout::current();
         let text = format!(
             "Press START on controller {} to join!",
             available_slot.to_index() + 1
         );
-        let x = screen_width() * 0.5 - 200.0;
-        let y = screen_height() - 100.0;
+        let x = canvas.width() * 0.5 - 200.0;
+        let y = canvas.height() - 100.0;
 
         draw_rectangle(x - 10.0, y - 30.0, 420.0, 40.0, Color::new(0.0, 0.0, 0.0, 0.7));
         draw_text(&text, x, y, self.font_size * 0.8, YELLOW);
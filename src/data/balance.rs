@@ -0,0 +1,270 @@
+use crate::data::coop_scaling::PlayerCountScaling;
+use crate::data::{storage, CharacterId, SaveBundle, UpgradeId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How hard a run's enemies were made. There's no setting for it, so real
+/// runs are always Normal; the balance simulation plays the others too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// The enemies at this difficulty, as co-op scales them for a team
+    pub fn scaling(self) -> PlayerCountScaling {
+        let (enemy_health, enemy_damage, spawn_count) = match self {
+            Difficulty::Easy => (0.75, 0.75, 1.0),
+            Difficulty::Normal => (1.0, 1.0, 1.0),
+            Difficulty::Hard => (1.5, 1.3, 1.25),
+        };
+        PlayerCountScaling {
+            enemy_health,
+            enemy_damage,
+            spawn_count,
+            ..PlayerCountScaling::SOLO
+        }
+    }
+}
+
+/// One finished run, appended to the run log for balance reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Unix seconds
+    pub timestamp: u64,
+    pub character: CharacterId,
+    /// Logs from before difficulties were recorded are all Normal
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Map the run ended on, for how far into the school it got
+    pub stage: String,
+    /// Beat the final boss rather than going down
+    pub cleared: bool,
+    /// Waves started across every map of the run
+    pub waves: u32,
+    /// Damage the player took, by what dealt it
+    pub damage_taken: BTreeMap<String, f32>,
+    /// Damage the player dealt, by attack
+    pub damage_dealt: BTreeMap<String, f32>,
+    pub upgrades: Vec<UpgradeId>,
+}
+
+/// Running totals for the run in progress
+#[derive(Default)]
+pub struct RunTally {
+    pub waves: u32,
+    damage_taken: BTreeMap<String, f32>,
+    damage_dealt: BTreeMap<String, f32>,
+}
+
+impl RunTally {
+    pub fn add_taken(&mut self, source: &str, amount: f32) {
+        *self.damage_taken.entry(source.to_string()).or_default() += amount;
+    }
+
+    pub fn add_dealt(&mut self, source: &str, amount: f32) {
+        *self.damage_dealt.entry(source.to_string()).or_default() += amount;
+    }
+
+    pub fn finish(
+        &self,
+        character: CharacterId,
+        difficulty: Difficulty,
+        stage: &str,
+        cleared: bool,
+        upgrades: &[UpgradeId],
+    ) -> RunRecord {
+        RunRecord {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            character,
+            difficulty,
+            stage: stage.to_string(),
+            cleared,
+            waves: self.waves,
+            damage_taken: self.damage_taken.clone(),
+            damage_dealt: self.damage_dealt.clone(),
+            upgrades: upgrades.to_vec(),
+        }
+    }
+}
+
+/// Outcomes for one character on one stage at one difficulty
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceRow {
+    pub character: CharacterId,
+    pub difficulty: Difficulty,
+    pub stage: String,
+    pub runs: usize,
+    pub clear_rate: f32,
+    pub average_waves: f32,
+    /// Average per run
+    pub damage_taken: BTreeMap<String, f32>,
+    /// Average per run
+    pub damage_dealt: BTreeMap<String, f32>,
+    /// Average waves with the upgrade minus without it; only upgrades that
+    /// some runs had and some didn't
+    pub upgrade_wave_delta: BTreeMap<String, f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceReport {
+    pub total_runs: usize,
+    pub rows: Vec<BalanceRow>,
+}
+
+impl BalanceReport {
+    pub fn from_runs(runs: &[RunRecord]) -> Self {
+        let mut groups: BTreeMap<(String, Difficulty, String), Vec<&RunRecord>> =
+            BTreeMap::new();
+        for run in runs {
+            let key = (format!("{:?}", run.character), run.difficulty, run.stage.clone());
+            groups.entry(key).or_default().push(run);
+        }
+
+        let rows = groups
+            .into_values()
+            .map(|runs| {
+                let count = runs.len() as f32;
+                let average_waves = |runs: &[&RunRecord]| {
+                    runs.iter().map(|run| run.waves as f32).sum::<f32>() / runs.len() as f32
+                };
+                let average_damage = |damage: fn(&RunRecord) -> &BTreeMap<String, f32>| {
+                    let mut totals: BTreeMap<String, f32> = BTreeMap::new();
+                    for run in &runs {
+                        for (source, amount) in damage(run) {
+                            *totals.entry(source.clone()).or_default() += amount / count;
+                        }
+                    }
+                    totals
+                };
+
+                let mut upgrade_wave_delta = BTreeMap::new();
+                let mut seen: Vec<UpgradeId> = Vec::new();
+                for upgrade in runs.iter().flat_map(|run| &run.upgrades) {
+                    if !seen.contains(upgrade) {
+                        seen.push(*upgrade);
+                    }
+                }
+                for upgrade in seen {
+                    let (with, without): (Vec<&RunRecord>, Vec<&RunRecord>) =
+                        runs.iter().partition(|run| run.upgrades.contains(&upgrade));
+                    if !with.is_empty() && !without.is_empty() {
+                        let delta = average_waves(&with) - average_waves(&without);
+                        upgrade_wave_delta.insert(format!("{:?}", upgrade), delta);
+                    }
+                }
+
+                BalanceRow {
+                    character: runs[0].character,
+                    difficulty: runs[0].difficulty,
+                    stage: runs[0].stage.clone(),
+                    runs: runs.len(),
+                    clear_rate: runs.iter().filter(|run| run.cleared).count() as f32 / count,
+                    average_waves: average_waves(&runs),
+                    damage_taken: average_damage(|run| &run.damage_taken),
+                    damage_dealt: average_damage(|run| &run.damage_dealt),
+                    upgrade_wave_delta,
+                }
+            })
+            .collect();
+
+        Self {
+            total_runs: runs.len(),
+            rows,
+        }
+    }
+
+    /// Long format, one `character,difficulty,stage,metric,value` line per
+    /// number, so the variable damage sources and upgrades don't need their
+    /// own columns
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("character,difficulty,stage,metric,value\n");
+        for row in &self.rows {
+            let mut line = |metric: &str, value: f32| {
+                csv.push_str(&format!(
+                    "{:?},{:?},{},{},{:.3}\n",
+                    row.character, row.difficulty, row.stage, metric, value
+                ));
+            };
+            line("runs", row.runs as f32);
+            line("clear_rate", row.clear_rate);
+            line("average_waves", row.average_waves);
+            for (source, amount) in &row.damage_taken {
+                line(&format!("damage_taken:{}", source), *amount);
+            }
+            for (source, amount) in &row.damage_dealt {
+                line(&format!("damage_dealt:{}", source), *amount);
+            }
+            for (upgrade, delta) in &row.upgrade_wave_delta {
+                line(&format!("upgrade_wave_delta:{}", upgrade), *delta);
+            }
+        }
+        csv
+    }
+
+    /// Write `balance_report.json` and `balance_report.csv` next to the run log
+    pub fn save(&self) -> Result<(), String> {
+        self.save_as(&balance_directory(), "balance_report")
+    }
+
+    /// Write `<name>.json` and `<name>.csv` into `dir`
+    pub fn save_as(&self, dir: &Path, name: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize balance report: {}", e))?;
+
+        storage::write_atomic(&dir.join(format!("{}.json", name)), &json)?;
+        storage::write_atomic(&dir.join(format!("{}.csv", name)), &self.to_csv())
+    }
+}
+
+/// Append a finished run to the log and refresh the reports from it
+pub fn record_run(record: &RunRecord) -> Result<(), String> {
+    let path = run_log_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create balance directory: {}", e))?;
+    }
+
+    let line = serde_json::to_string(record)
+        .map_err(|e| format!("Failed to serialize run: {}", e))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| format!("Failed to write run log: {}", e))?;
+
+    BalanceReport::from_runs(&load_runs()).save()
+}
+
+/// Every run in the log; lines that don't parse (e.g. from a crash
+/// mid-write) are skipped
+pub fn load_runs() -> Vec<RunRecord> {
+    fs::read_to_string(run_log_path())
+        .map(|log| {
+            log.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn run_log_path() -> PathBuf {
+    balance_directory().join("runs.jsonl")
+}
+
+/// Where run logs, balance reports and tuning tables live
+pub fn balance_directory() -> PathBuf {
+    SaveBundle::data_directory().join("balance")
+}
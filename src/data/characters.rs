@@ -38,6 +38,10 @@ pub struct Character {
     pub name: &'static str,
     pub ability_name: &'static str,
     pub voice_line: &'static str,
+    /// Extra lines unlocked by mastery, at Silver and Gold rank
+    pub mastery_lines: [&'static str; 2],
+    /// Name of the move unlocked at Master rank
+    pub signature_move: &'static str,
     pub effects: &'static [AbilityEffect],
     pub duration: f32,
     pub cooldown: f32,
@@ -106,6 +110,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Berkay",
         ability_name: "Special Kebab",
         voice_line: "ik kan niet stoppen!",
+        mastery_lines: ["de kebab geeft mij kracht!", "niemand houdt mij tegen!"],
+        signature_move: "Kebab Overload",
         effects: BERKAY_EFFECTS,
         duration: 6.0, // Increased from 5.0
         cooldown: 12.0, // Increased from 10.0
@@ -115,6 +121,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Luca",
         ability_name: "Winter Arc",
         voice_line: "nee nu ben ik klaar ik ga in mijn winter arc",
+        mastery_lines: ["de winter arc stopt nooit", "koud? ik voel niks"],
+        signature_move: "Eternal Winter",
         effects: LUCA_EFFECTS,
         duration: 5.0,
         cooldown: 12.0, // Increased from 10.0 due to buff
@@ -124,6 +132,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Gefferinho",
         ability_name: "Maar Mevrouw Rage",
         voice_line: "maar mevouw wat doe je",
+        mastery_lines: ["mevrouw, dit is oneerlijk!", "ik ga naar de directeur!"],
+        signature_move: "Mevrouw Meltdown",
         effects: GEFFERINHO_EFFECTS,
         duration: 6.0, // Increased from 5.0
         cooldown: 13.0, // Increased from 10.0
@@ -133,6 +143,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Bas",
         ability_name: "Bas Veeg",
         voice_line: "BAS VEEG!",
+        mastery_lines: ["veeg veeg veeg!", "niemand ontsnapt aan de veeg"],
+        signature_move: "Grote Veeg",
         effects: BAS_EFFECTS,
         duration: 0.1, // Instant AOE burst
         cooldown: 11.0, // Increased from 10.0
@@ -142,6 +154,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Hadi",
         ability_name: "Dubai Emirates",
         voice_line: "Dubai Emirates!",
+        mastery_lines: ["first class naar Dubai!", "te snel voor jou"],
+        signature_move: "Emirates Express",
         effects: HADI_EFFECTS,
         duration: 6.0, // Increased from 5.0
         cooldown: 12.0, // Increased from 10.0
@@ -151,6 +165,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Nitin",
         ability_name: "Barra in je Kont",
         voice_line: "Barra in je kont!",
+        mastery_lines: ["het brandt, het brandt!", "barra voor iedereen!"],
+        signature_move: "Barra Inferno",
         effects: NITIN_EFFECTS,
         duration: 1.0, // Apply DOT effect
         cooldown: 11.0, // Increased from 10.0
@@ -160,6 +176,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Yigit Baba",
         ability_name: "Sivas Rage",
         voice_line: "TURKIYEEEE",
+        mastery_lines: ["SIVAS GELIYOR", "baba is hier!"],
+        signature_move: "Sivas Earthquake",
         effects: PALA_BABA_EFFECTS,
         duration: 10.0,
         cooldown: 30.0,
@@ -169,6 +187,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Fufinho",
         ability_name: "Fufu Throw",
         voice_line: "ik eet fufu!",
+        mastery_lines: ["fufu voor iedereen!", "proef mijn fufu!"],
+        signature_move: "Fufu Meteor",
         effects: FUFINHO_EFFECTS,
         duration: 0.1, // Instant projectile
         cooldown: 8.0,
@@ -178,6 +198,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Efe abi",
         ability_name: "Lahmacun Power",
         voice_line: "Ik eet lahmacun!",
+        mastery_lines: ["extra lahmacun, abi!", "met citroen graag"],
+        signature_move: "Lahmacun Tornado",
         effects: EFE_ABI_EFFECTS,
         duration: 5.0,
         cooldown: 15.0,
@@ -187,6 +209,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Jad",
         ability_name: "KFC Rage",
         voice_line: "ik eet", // Will trigger special dialogue sequence
+        mastery_lines: ["waar is mijn bucket?", "ik wil extra saus"],
+        signature_move: "Zinger Fury",
         effects: JAD_EFFECTS,
         duration: 6.0,
         cooldown: 20.0,
@@ -196,6 +220,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Umut",
         ability_name: "Terraria Arc",
         voice_line: "Ik ga nu in mijn terraria arc",
+        mastery_lines: ["ik bouw een fort!", "tijd voor de boss fight"],
+        signature_move: "Terraria Endgame",
         effects: UMUT_EFFECTS,
         duration: 5.0,
         cooldown: 13.0,
@@ -205,6 +231,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Keizer Bom Taha",
         ability_name: "Lucht Aanval",
         voice_line: "ik gooi bommen!",
+        mastery_lines: ["luchtsteun onderweg!", "alle eenheden, aanval!"],
+        signature_move: "Tapijtbombardement",
         effects: KEIZER_BOM_TAHA_EFFECTS,
         duration: 15.0, // Max plane duration
         cooldown: 25.0,  // Long cooldown for powerful ability
@@ -214,6 +242,8 @@ pub const CHARACTERS: [Character; 13] = [
         name: "Goon Lord Tobese",
         ability_name: "Speciale Melk",
         voice_line: "Ik hou van padme!",
+        mastery_lines: ["melk maakt sterk", "de goon lord spreekt"],
+        signature_move: "Melkweg",
         effects: GOON_LORD_TOBESE_EFFECTS,
         duration: 7.0,
         cooldown: 14.0,
@@ -248,6 +278,9 @@ impl CharacterId {
     }
 }
 
+/// Most of an ability's cooldown that upgrades can take off
+pub const MAX_COOLDOWN_REDUCTION: f32 = 0.5;
+
 pub struct AbilityState {
     pub character_id: CharacterId,
     pub active: bool,
@@ -255,6 +288,10 @@ pub struct AbilityState {
     pub cooldown_time: f32,
     pub base_damage: f32,
     pub base_speed: f32,
+    /// Share of the cooldown upgrades take off
+    pub cooldown_reduction: f32,
+    /// Seconds upgrades add to each activation
+    pub duration_bonus: f32,
 }
 
 impl AbilityState {
@@ -266,9 +303,27 @@ impl AbilityState {
             cooldown_time: 0.0,
             base_damage: 1.0,
             base_speed: 1.0,
+            cooldown_reduction: 0.0,
+            duration_bonus: 0.0,
         }
     }
 
+    /// Cooldown reductions add together up to the cap; duration bonuses just add
+    pub fn set_upgrades(&mut self, cooldown_reduction: f32, duration_bonus: f32) {
+        self.cooldown_reduction = cooldown_reduction.clamp(0.0, MAX_COOLDOWN_REDUCTION);
+        self.duration_bonus = duration_bonus.max(0.0);
+    }
+
+    /// How long an activation lasts, upgrades included
+    pub fn duration(&self) -> f32 {
+        Character::get_by_id(self.character_id).duration + self.duration_bonus
+    }
+
+    /// The wait between activations, upgrades included
+    pub fn cooldown(&self) -> f32 {
+        Character::get_by_id(self.character_id).cooldown * (1.0 - self.cooldown_reduction)
+    }
+
     pub fn can_activate(&self) -> bool {
         !self.active && self.cooldown_time <= 0.0
     }
@@ -277,8 +332,8 @@ impl AbilityState {
         if self.can_activate() {
             let character = Character::get_by_id(self.character_id);
             self.active = true;
-            self.active_time = character.duration;
-            self.cooldown_time = character.cooldown;
+            self.active_time = self.duration();
+            self.cooldown_time = self.cooldown();
             return character.voice_line;
         }
         ""
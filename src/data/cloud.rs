@@ -0,0 +1,316 @@
+use crate::data::{crash, storage, GameConfig, SaveBundle};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+/// Seconds a request may take before the sync gives up on the cloud
+const TIMEOUT: u64 = 10;
+
+/// What this machine and the cloud last agreed on, kept next to the data
+/// folders but never bundled itself
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SyncState {
+    /// `fingerprint` of the local files at the last sync
+    pub synced_fingerprint: u64,
+    /// `exported_at` of the cloud's bundle at the last sync; 0 for never
+    pub remote_exported_at: u64,
+    /// When local changes first failed to go up, if they still haven't
+    pub queued_at: Option<u64>,
+}
+
+impl SyncState {
+    pub fn load() -> Self {
+        storage::read_json(&Self::file_path()).unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize cloud sync state: {}", e))?;
+        storage::write_atomic(&Self::file_path(), &json)
+    }
+
+    fn file_path() -> PathBuf {
+        SaveBundle::data_directory().join("cloud").join("sync.json")
+    }
+}
+
+/// What a sync does, from what changed on each side since the last one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Plan {
+    UpToDate,
+    Upload,
+    Download,
+    /// Both sides changed; the newest wins unless the player is asked
+    Conflict,
+    /// Both sides have data and have never synced, so neither being newer
+    /// means anything. Always asks.
+    FirstSync,
+}
+
+/// Decide a sync. `remote` is the cloud bundle's `exported_at`, if there is
+/// one.
+pub fn plan(state: &SyncState, fingerprint: u64, has_files: bool, remote: Option<u64>) -> Plan {
+    let Some(remote) = remote else {
+        return Plan::Upload;
+    };
+    if state.remote_exported_at == 0 {
+        return if has_files { Plan::FirstSync } else { Plan::Download };
+    }
+
+    let local_changed = fingerprint != state.synced_fingerprint;
+    let remote_changed = remote != state.remote_exported_at;
+    match (local_changed, remote_changed) {
+        (false, false) => Plan::UpToDate,
+        (true, false) => Plan::Upload,
+        (false, true) => Plan::Download,
+        (true, true) => Plan::Conflict,
+    }
+}
+
+/// FNV-1a over the bundle's files, so a sync can tell whether anything
+/// changed without keeping a copy
+pub fn fingerprint(bundle: &SaveBundle) -> u64 {
+    let json = serde_json::to_string(&bundle.files).unwrap_or_default();
+    json.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub enum SyncOutcome {
+    UpToDate,
+    Uploaded,
+    /// Newer data from the cloud, put in place with `apply_download` on the
+    /// main thread
+    Downloaded(SaveBundle),
+    /// Both sides changed and the player picks which to keep
+    Conflict {
+        local_modified_at: u64,
+        remote: SaveBundle,
+    },
+    /// The cloud couldn't be reached; the changes go up on the next sync
+    Queued(String),
+}
+
+impl SyncOutcome {
+    pub fn describe(&self) -> String {
+        match self {
+            SyncOutcome::UpToDate => "Cloud save is up to date".to_string(),
+            SyncOutcome::Uploaded => "Uploaded save to the cloud".to_string(),
+            SyncOutcome::Downloaded(_) => "Downloaded save from the cloud".to_string(),
+            SyncOutcome::Conflict { .. } => "Cloud save conflict".to_string(),
+            SyncOutcome::Queued(e) => format!("{}; will upload on the next sync", e),
+        }
+    }
+}
+
+/// A sync running in the background
+pub struct CloudSync {
+    result: Receiver<Result<SyncOutcome, String>>,
+}
+
+impl CloudSync {
+    /// Start a full sync, or nothing if no cloud is set up
+    pub fn start() -> Option<Self> {
+        Self::spawn(sync)
+    }
+
+    /// Put this machine's data over the cloud's, settling a conflict
+    pub fn keep_local() -> Option<Self> {
+        Self::spawn(|config| {
+            let local = SaveBundle::collect()?;
+            upload(config, &mut SyncState::load(), &local)
+        })
+    }
+
+    fn spawn(job: fn(&GameConfig) -> Result<SyncOutcome, String>) -> Option<Self> {
+        let config = GameConfig::load();
+        if config.cloud_url.is_empty() {
+            return None;
+        }
+
+        let (sender, result) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(job(&config));
+        });
+        Some(Self { result })
+    }
+
+    /// The outcome, once the sync is done
+    pub fn poll(&self) -> Option<Result<SyncOutcome, String>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("Cloud sync stopped".to_string())),
+        }
+    }
+}
+
+/// Put a downloaded bundle in place and remember it as synced, returning
+/// how many files were written
+pub fn apply_download(remote: &SaveBundle) -> Result<usize, String> {
+    let count = remote.apply()?;
+    GameConfig::load().apply_globals();
+
+    let state = SyncState {
+        synced_fingerprint: fingerprint(&SaveBundle::collect()?),
+        remote_exported_at: remote.exported_at,
+        queued_at: None,
+    };
+    state.save()?;
+    Ok(count)
+}
+
+/// Send this session's changes up as the game closes. Anything that needs
+/// the cloud's data or a choice is left for the next boot.
+pub fn upload_on_exit() {
+    let config = GameConfig::load();
+    if config.cloud_url.is_empty() {
+        return;
+    }
+
+    let result = SaveBundle::collect().and_then(|local| {
+        let mut state = SyncState::load();
+        let remote = match fetch(&config) {
+            Ok(remote) => remote,
+            Err(e) => return queue(&mut state, &local, e),
+        };
+        let exported_at = remote.as_ref().map(|bundle| bundle.exported_at);
+        match plan(&state, fingerprint(&local), !local.files.is_empty(), exported_at) {
+            Plan::Upload => upload(&config, &mut state, &local),
+            _ => Ok(SyncOutcome::UpToDate),
+        }
+    });
+    match result {
+        Ok(SyncOutcome::Queued(e)) | Err(e) => crash::log(format!("Cloud sync: {}", e)),
+        Ok(_) => {}
+    }
+}
+
+fn sync(config: &GameConfig) -> Result<SyncOutcome, String> {
+    let mut state = SyncState::load();
+    let local = SaveBundle::collect()?;
+    let remote = match fetch(config) {
+        Ok(remote) => remote,
+        Err(e) => return queue(&mut state, &local, e),
+    };
+
+    let exported_at = remote.as_ref().map(|bundle| bundle.exported_at);
+    let plan = plan(&state, fingerprint(&local), !local.files.is_empty(), exported_at);
+    let Some(remote) = remote else {
+        return upload(config, &mut state, &local);
+    };
+    let local_modified_at = SaveBundle::modified_at();
+    match plan {
+        Plan::UpToDate => Ok(SyncOutcome::UpToDate),
+        Plan::Upload => upload(config, &mut state, &local),
+        Plan::Download => Ok(SyncOutcome::Downloaded(remote)),
+        Plan::Conflict if !config.cloud_ask_on_conflict => {
+            if local_modified_at >= remote.exported_at {
+                upload(config, &mut state, &local)
+            } else {
+                Ok(SyncOutcome::Downloaded(remote))
+            }
+        }
+        Plan::Conflict | Plan::FirstSync => Ok(SyncOutcome::Conflict {
+            local_modified_at,
+            remote,
+        }),
+    }
+}
+
+/// The cloud's bundle, or `None` if nothing has been uploaded yet
+fn fetch(config: &GameConfig) -> Result<Option<SaveBundle>, String> {
+    let response = authorized(minreq::get(&config.cloud_url), config)
+        .send()
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    match response.status_code {
+        404 => Ok(None),
+        200 => SaveBundle::from_json(response.as_str().map_err(|e| e.to_string())?).map(Some),
+        status => Err(format!("Cloud returned status {}", status)),
+    }
+}
+
+fn upload(
+    config: &GameConfig,
+    state: &mut SyncState,
+    local: &SaveBundle,
+) -> Result<SyncOutcome, String> {
+    let json = serde_json::to_string(local)
+        .map_err(|e| format!("Failed to serialize save bundle: {}", e))?;
+    let sent = authorized(minreq::put(&config.cloud_url), config)
+        .with_header("Content-Type", "application/json")
+        .with_body(json)
+        .send();
+
+    match sent {
+        Ok(response) if (200..300).contains(&response.status_code) => {}
+        Ok(response) => {
+            let error = format!("Cloud refused the upload (status {})", response.status_code);
+            return queue(state, local, error);
+        }
+        Err(e) => return queue(state, local, format!("Network error: {}", e)),
+    }
+
+    state.synced_fingerprint = fingerprint(local);
+    state.remote_exported_at = local.exported_at;
+    state.queued_at = None;
+    state.save()?;
+    Ok(SyncOutcome::Uploaded)
+}
+
+/// Note that local changes are waiting to go up. With nothing changed
+/// there's nothing to queue and the failure is just an error.
+fn queue(state: &mut SyncState, local: &SaveBundle, error: String) -> Result<SyncOutcome, String> {
+    if fingerprint(local) == state.synced_fingerprint {
+        return Err(error);
+    }
+    if state.queued_at.is_none() {
+        state.queued_at = Some(local.exported_at);
+        state.save()?;
+    }
+    Ok(SyncOutcome::Queued(error))
+}
+
+fn authorized(request: minreq::Request, config: &GameConfig) -> minreq::Request {
+    let request = request
+        .with_header("User-Agent", "bas-veeg-arc-cloud")
+        .with_timeout(TIMEOUT);
+    if config.cloud_auth.is_empty() {
+        request
+    } else {
+        request.with_header("Authorization", &config.cloud_auth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synced() -> SyncState {
+        SyncState {
+            synced_fingerprint: 7,
+            remote_exported_at: 100,
+            queued_at: None,
+        }
+    }
+
+    #[test]
+    fn plans_follow_what_changed() {
+        let state = synced();
+        assert_eq!(plan(&state, 7, true, Some(100)), Plan::UpToDate);
+        assert_eq!(plan(&state, 8, true, Some(100)), Plan::Upload);
+        assert_eq!(plan(&state, 7, true, Some(200)), Plan::Download);
+        assert_eq!(plan(&state, 8, true, Some(200)), Plan::Conflict);
+        // An empty cloud always takes this machine's data
+        assert_eq!(plan(&state, 7, true, None), Plan::Upload);
+    }
+
+    #[test]
+    fn first_sync_never_overwrites_either_side() {
+        let fresh = SyncState::default();
+        assert_eq!(plan(&fresh, 7, true, Some(100)), Plan::FirstSync);
+        assert_eq!(plan(&fresh, 7, false, Some(100)), Plan::Download);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::audio::mixer::BusVolumes;
+use crate::coop::input_handler::KeyRebind;
+use crate::data::save::ColorblindMode;
+use crate::data::{storage, SaveBundle};
+use crate::ui::broadcast::AccentColor;
+use crate::ui::subtitles::{SubtitleSize, SubtitleStyle};
+use crate::ui::UiScaleMode;
+use crate::updater::UpdateChannel;
+use macroquad::miniquad::conf::{Conf, Platform};
+use macroquad::prelude::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Simulation rates offered in settings, in fixed ticks per second
+pub const TICK_RATES: [u32; 4] = [60, 120, 144, 240];
+pub const DEFAULT_TICK_RATE: u32 = 120;
+/// Slowest single-player runs can be set to go
+pub const MIN_GAME_SPEED: f32 = 0.8;
+/// Window sizes offered in settings, used when not fullscreen
+pub const RESOLUTIONS: [[u32; 2]; 5] =
+    [[1280, 720], [1600, 900], [1920, 1080], [2560, 1440], [3840, 2160]];
+
+/// Options from the settings screen, kept outside the save slots so they
+/// apply to every profile
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameConfig {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    /// Spoken lines and dialogue blips
+    pub voice_volume: f32,
+    /// Wave starts, rank-ups, KOs and round wins called out over the fight
+    pub announcer_volume: f32,
+    pub announcer_enabled: bool,
+    pub fx_intensity: f32,
+    /// How hard hitstop and slow motion hit, 0 (off) to 1
+    pub game_feel_intensity: f32,
+    /// Only takes hold when the window opens, at the next launch
+    pub vsync_enabled: bool,
+    pub fullscreen: bool,
+    /// Window size in pixels when not fullscreen
+    pub resolution: [u32; 2],
+    pub post_processing: bool,
+    pub tick_rate: u32,
+    pub ui_scale_mode: UiScaleMode,
+    /// Version the player last launched, for the "What's New" popup
+    pub last_seen_version: Option<String>,
+    pub update_channel: UpdateChannel,
+    /// Release the player chose to skip; newer ones are still offered
+    pub skipped_version: Option<String>,
+    /// Unix time the launch check stays quiet about updates until
+    pub remind_updates_after: u64,
+    /// Put Arc Tokens back to their last audited balance when the shop file
+    /// changed outside the game
+    pub rollback_unaudited_tokens: bool,
+    /// Hardcore: dashing, heavy attacks and guarding run on the stamina bar,
+    /// and enemies press in on anyone who runs it dry
+    pub hardcore_stamina: bool,
+    /// Debug: log every seeded RNG draw while a replay records, saved next to
+    /// the replay so a re-run can be checked against it
+    pub rng_audit: bool,
+    /// Debug: mark where hits land and fighters die during runs
+    pub debug_hit_markers: bool,
+    /// Debug: F5 pauses the simulation, F6 steps one tick, F7/F8 slow it
+    /// down or speed it up
+    pub debug_controls: bool,
+    /// Debug: the developer console on `~`, in release builds too
+    pub dev_console: bool,
+    /// Shared lives co-op starts with; the co-op select screen can still
+    /// change it per run
+    pub coop_lives: u32,
+    /// Relay for online co-op as `host:port`; start one with
+    /// `bas-veeg-arc --relay`
+    pub relay_address: String,
+    /// Where the bonus event schedule is fetched from at boot; empty to only
+    /// use the cached one
+    pub events_url: String,
+    /// Scoreboard and input overlay for streaming versus matches; also
+    /// switched on for one launch with `bas-veeg-arc --broadcast`
+    pub broadcast_overlay: bool,
+    /// Shown on the overlay's name plates; empty for "PLAYER 1"/"PLAYER 2"
+    pub broadcast_names: [String; 2],
+    pub broadcast_accents: [AccentColor; 2],
+    /// Save slot picked on the profiles screen; its progress is the one
+    /// played, shown and saved
+    pub active_profile: usize,
+    /// Where the save bundle is kept in the cloud: a WebDAV file or an
+    /// S3-compatible object URL. Empty to keep saves on this machine only.
+    pub cloud_url: String,
+    /// Sent as the `Authorization` header, like `Basic <base64>` for WebDAV
+    /// or `Bearer <token>`; empty for presigned or open URLs
+    pub cloud_auth: String,
+    /// When this machine and the cloud both changed since the last sync, ask
+    /// which to keep instead of keeping the newest
+    pub cloud_ask_on_conflict: bool,
+    /// Where crash reports the player agrees to send are POSTed as text.
+    /// Empty to open them as a GitHub issue in the browser instead.
+    pub crash_report_url: String,
+    /// Seconds without input before a solo run pauses itself; 0 never does
+    pub idle_pause_seconds: u32,
+    /// Pause a solo run when the window is minimized or left
+    pub pause_on_focus_loss: bool,
+    /// Silence the game while a run is paused for being away
+    pub mute_when_away: bool,
+    /// Seconds counted down before an auto-paused run picks up again
+    pub resume_countdown_seconds: u32,
+    /// Which set of team and health bar colours to draw with
+    pub colorblind_mode: ColorblindMode,
+    /// Theme from the palette's themes folder laid over it; empty for none
+    pub palette_theme: String,
+    /// Folders in the mods directory the player switched off
+    pub disabled_mods: Vec<String>,
+    /// Locale code the menus are in
+    pub language: String,
+    /// What the cast speaks in dialogue, whatever the menus are in
+    pub voice_language: String,
+    /// Shown under each line when it's not the voice language; empty for off
+    pub subtitle_language: String,
+    pub subtitle_size: SubtitleSize,
+    /// Opacity of the backing behind subtitles, 0 to 1
+    pub subtitle_background: f32,
+    /// Name each speaker in their own colour
+    pub subtitle_speaker_colors: bool,
+    /// How hard the screen shakes on hits and explosions, 0 (still) to 1
+    pub screen_shake: f32,
+    /// How bright full-screen flashes from bombs and big hits get, 0 to 1
+    pub screen_flash: f32,
+    /// Holding `combo_assist_key` swings through a combo on its own
+    pub combo_assist: bool,
+    /// Key name, as the controls page saves them
+    pub combo_assist_key: String,
+    /// Speed single-player runs go at, `MIN_GAME_SPEED` to 1
+    pub game_speed: f32,
+    /// Keys moved off their defaults on the controls page
+    pub key_rebinds: Vec<KeyRebind>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 0.8,
+            voice_volume: 1.0,
+            announcer_volume: 1.0,
+            announcer_enabled: true,
+            fx_intensity: 1.0,
+            game_feel_intensity: 1.0,
+            vsync_enabled: true,
+            fullscreen: false,
+            resolution: RESOLUTIONS[0],
+            post_processing: true,
+            tick_rate: DEFAULT_TICK_RATE,
+            ui_scale_mode: UiScaleMode::Fit,
+            last_seen_version: None,
+            update_channel: UpdateChannel::Stable,
+            skipped_version: None,
+            remind_updates_after: 0,
+            rollback_unaudited_tokens: false,
+            hardcore_stamina: false,
+            rng_audit: false,
+            debug_hit_markers: false,
+            debug_controls: false,
+            dev_console: false,
+            coop_lives: 3,
+            relay_address: format!("127.0.0.1:{}", crate::net::DEFAULT_RELAY_PORT),
+            events_url:
+                "https://raw.githubusercontent.com/compiledkernel-idk/bas-veeg-arc/main/events.json"
+                    .to_string(),
+            broadcast_overlay: false,
+            broadcast_names: Default::default(),
+            broadcast_accents: [AccentColor::Red, AccentColor::Blue],
+            active_profile: 0,
+            cloud_url: String::new(),
+            cloud_auth: String::new(),
+            cloud_ask_on_conflict: false,
+            crash_report_url: String::new(),
+            idle_pause_seconds: 60,
+            pause_on_focus_loss: true,
+            mute_when_away: true,
+            resume_countdown_seconds: 3,
+            colorblind_mode: ColorblindMode::None,
+            palette_theme: String::new(),
+            disabled_mods: Vec::new(),
+            language: "en".to_string(),
+            voice_language: "nl".to_string(),
+            subtitle_language: "en".to_string(),
+            subtitle_size: SubtitleSize::Normal,
+            subtitle_background: 0.8,
+            subtitle_speaker_colors: true,
+            screen_shake: 1.0,
+            screen_flash: 1.0,
+            combo_assist: false,
+            combo_assist_key: "U".to_string(),
+            game_speed: 1.0,
+            key_rebinds: Vec::new(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Load the saved config, or the defaults if there is none
+    pub fn load() -> Self {
+        storage::read_json(&Self::config_file_path()).unwrap_or_default()
+    }
+
+    /// Copy the options the game reads as it runs into the shared state
+    pub fn apply_globals(&self) {
+        crate::data::set_post_processing(self.post_processing);
+        crate::data::set_tick_rate(self.tick_rate);
+        crate::data::set_volumes(BusVolumes::from_config(self));
+        crate::data::set_game_feel(self.game_feel_intensity);
+        crate::data::set_screen_shake(self.screen_shake);
+        crate::data::set_screen_flash(self.screen_flash);
+        crate::data::set_combo_assist_key(self.combo_assist_key());
+        crate::data::set_game_speed(self.game_speed.clamp(MIN_GAME_SPEED, 1.0));
+        crate::data::set_key_rebinds(self.key_rebinds.clone());
+        crate::data::set_ui_scale_mode(self.ui_scale_mode);
+        crate::data::set_coop_lives(self.coop_lives);
+        crate::data::set_debug_controls(self.debug_controls);
+        crate::data::set_dev_console(self.dev_console);
+        crate::data::set_hardcore_stamina(self.hardcore_stamina);
+        crate::data::set_broadcast_overlay(self.broadcast_overlay);
+        crate::data::set_active_profile(self.active_profile);
+        crate::data::palette::reload(self);
+        crate::data::localization::reload(self);
+        crate::data::set_subtitle_style(SubtitleStyle::from_config(self));
+    }
+
+    /// The assist key, while the assist is on
+    pub fn combo_assist_key(&self) -> Option<KeyCode> {
+        let key = crate::coop::input_handler::key_from_name(&self.combo_assist_key);
+        key.filter(|_| self.combo_assist)
+    }
+
+    /// The window to open at boot, sized and synced the way settings say
+    pub fn window_conf(&self) -> Conf {
+        let [width, height] = self.resolution;
+        Conf {
+            window_title: "Bas Veeg Arc".to_string(),
+            window_width: width as i32,
+            window_height: height as i32,
+            fullscreen: self.fullscreen,
+            platform: Platform {
+                swap_interval: Some(self.vsync_enabled as i32),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Switch the open window to the fullscreen and resolution settings
+    pub fn apply_window(&self) {
+        macroquad::window::set_fullscreen(self.fullscreen);
+        if !self.fullscreen {
+            let [width, height] = self.resolution;
+            macroquad::window::request_new_screen_size(width as f32, height as f32);
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+        storage::write_atomic(&Self::config_file_path(), &json)
+            .map_err(|e| format!("Failed to write config: {}", e))
+    }
+
+    fn config_file_path() -> PathBuf {
+        SaveBundle::data_directory().join("config").join("settings.json")
+    }
+}
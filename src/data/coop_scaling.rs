@@ -0,0 +1,127 @@
+use crate::data::{balance, crash, mods, storage};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// How a co-op run gets harder, and pays more, with each extra player. Lives
+/// in `coop_scaling.json` next to the balance reports so it can be tuned
+/// without a rebuild.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CoopScaling {
+    pub tiers: Vec<PlayerCountScaling>,
+}
+
+/// Modifiers for one team size; every value multiplies the solo number
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PlayerCountScaling {
+    pub players: u32,
+    pub enemy_health: f32,
+    pub enemy_damage: f32,
+    /// Enemies per wave
+    pub spawn_count: f32,
+    /// Arc Tokens for clearing a wave
+    pub token_reward: f32,
+}
+
+impl PlayerCountScaling {
+    pub const SOLO: Self = Self {
+        players: 1,
+        enemy_health: 1.0,
+        enemy_damage: 1.0,
+        spawn_count: 1.0,
+        token_reward: 1.0,
+    };
+
+    /// One-line preview for the lobby
+    pub fn summary(&self) -> String {
+        format!(
+            "Enemy HP x{:.2}  |  Enemy damage x{:.2}  |  Enemies x{:.2}  |  Tokens x{:.2}",
+            self.enemy_health, self.enemy_damage, self.spawn_count, self.token_reward
+        )
+    }
+}
+
+impl Default for CoopScaling {
+    fn default() -> Self {
+        let tier = |players, enemy_health, enemy_damage, spawn_count, token_reward| {
+            PlayerCountScaling {
+                players,
+                enemy_health,
+                enemy_damage,
+                spawn_count,
+                token_reward,
+            }
+        };
+        Self {
+            tiers: vec![
+                tier(2, 1.25, 1.0, 1.15, 1.1),
+                tier(3, 1.5, 1.1, 1.3, 1.2),
+                tier(4, 1.75, 1.2, 1.45, 1.3),
+            ],
+        }
+    }
+}
+
+impl CoopScaling {
+    /// The built-in table with any tuning from `coop_scaling.json` laid over it
+    pub fn load() -> Self {
+        let path = mods::resolve(Self::file_path());
+        storage::read_overrides(&path, Self::default()).unwrap_or_else(|e| {
+            crash::log(format!("Failed to read co-op scaling, using defaults: {}", e));
+            Self::default()
+        })
+    }
+
+    /// Modifiers for a team of `players`. Sizes the table doesn't list use
+    /// the largest tier below them, and solo play is never scaled.
+    pub fn for_players(&self, players: usize) -> PlayerCountScaling {
+        if players <= 1 {
+            return PlayerCountScaling::SOLO;
+        }
+
+        self.tiers
+            .iter()
+            .filter(|tier| tier.players as usize <= players)
+            .max_by_key(|tier| tier.players)
+            .copied()
+            .unwrap_or(PlayerCountScaling::SOLO)
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("coop_scaling.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solo_is_never_scaled() {
+        let scaling = CoopScaling::default();
+        assert_eq!(scaling.for_players(0), PlayerCountScaling::SOLO);
+        assert_eq!(scaling.for_players(1), PlayerCountScaling::SOLO);
+    }
+
+    #[test]
+    fn unlisted_team_sizes_use_the_tier_below() {
+        let scaling = CoopScaling {
+            tiers: vec![
+                PlayerCountScaling {
+                    players: 2,
+                    enemy_health: 2.0,
+                    ..PlayerCountScaling::SOLO
+                },
+                PlayerCountScaling {
+                    players: 4,
+                    enemy_health: 4.0,
+                    ..PlayerCountScaling::SOLO
+                },
+            ],
+        };
+        assert_eq!(scaling.for_players(2).enemy_health, 2.0);
+        assert_eq!(scaling.for_players(3).enemy_health, 2.0);
+        assert_eq!(scaling.for_players(4).enemy_health, 4.0);
+        assert_eq!(scaling.for_players(6).enemy_health, 4.0);
+    }
+}
@@ -0,0 +1,411 @@
+use crate::data::characters::CharacterId;
+use crate::data::crash;
+use crate::data::poses::{PoseMotion, PoseVariant};
+use crate::data::save::{SaveManager, DEFAULT_PROFILE};
+use crate::data::ShopManager;
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+/// Where a cosmetic goes on a character's loadout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CosmeticSlot {
+    Palette,
+    Outfit,
+    HitSpark,
+    VictoryPose,
+}
+
+impl CosmeticSlot {
+    pub const ALL: [CosmeticSlot; 4] = [
+        CosmeticSlot::Palette,
+        CosmeticSlot::Outfit,
+        CosmeticSlot::HitSpark,
+        CosmeticSlot::VictoryPose,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CosmeticSlot::Palette => "PALETTE",
+            CosmeticSlot::Outfit => "OUTFIT",
+            CosmeticSlot::HitSpark => "SPARKS",
+            CosmeticSlot::VictoryPose => "VICTORY",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&slot| slot == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Accessory drawn over the sprite
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outfit {
+    Beanie,
+    Shades,
+    Scarf,
+    Crown,
+}
+
+/// What a cosmetic changes
+pub enum CosmeticLook {
+    /// The sprite is drawn multiplied by this
+    Palette([f32; 3]),
+    Outfit(Outfit, [f32; 3]),
+    /// Colour of the sparks off P1's hits
+    HitSpark([f32; 3]),
+    /// Played instead of the picked victory pose
+    VictoryPose(PoseVariant),
+}
+
+/// How a cosmetic is earned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unlock {
+    /// Bought once with Arc Tokens, for everyone it fits
+    Tokens(u32),
+    /// Free on a character once their mastery reaches the level
+    Mastery(u32),
+}
+
+pub struct Cosmetic {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Only this character can wear it; `None` fits everyone
+    pub character: Option<CharacterId>,
+    pub unlock: Unlock,
+    pub look: CosmeticLook,
+}
+
+impl Cosmetic {
+    pub fn slot(&self) -> CosmeticSlot {
+        match self.look {
+            CosmeticLook::Palette(_) => CosmeticSlot::Palette,
+            CosmeticLook::Outfit(..) => CosmeticSlot::Outfit,
+            CosmeticLook::HitSpark(_) => CosmeticSlot::HitSpark,
+            CosmeticLook::VictoryPose(_) => CosmeticSlot::VictoryPose,
+        }
+    }
+
+    pub fn fits(&self, character: CharacterId) -> bool {
+        self.character.is_none_or(|only| only == character)
+    }
+}
+
+const fn cosmetic(
+    id: &'static str,
+    name: &'static str,
+    unlock: Unlock,
+    look: CosmeticLook,
+) -> Cosmetic {
+    Cosmetic {
+        id,
+        name,
+        character: None,
+        unlock,
+        look,
+    }
+}
+
+const fn palette(id: &'static str, name: &'static str, unlock: Unlock, tint: [f32; 3]) -> Cosmetic {
+    cosmetic(id, name, unlock, CosmeticLook::Palette(tint))
+}
+
+/// A palette only `character` wears, free at mastery 12
+const fn signature(
+    id: &'static str,
+    name: &'static str,
+    character: CharacterId,
+    tint: [f32; 3],
+) -> Cosmetic {
+    Cosmetic {
+        character: Some(character),
+        ..palette(id, name, Unlock::Mastery(12), tint)
+    }
+}
+
+const fn outfit(
+    id: &'static str,
+    name: &'static str,
+    unlock: Unlock,
+    outfit: Outfit,
+    color: [f32; 3],
+) -> Cosmetic {
+    cosmetic(id, name, unlock, CosmeticLook::Outfit(outfit, color))
+}
+
+const fn spark(id: &'static str, name: &'static str, unlock: Unlock, color: [f32; 3]) -> Cosmetic {
+    cosmetic(id, name, unlock, CosmeticLook::HitSpark(color))
+}
+
+const fn victory(id: &'static str, unlock: Unlock, pose: PoseVariant) -> Cosmetic {
+    cosmetic(id, pose.name, unlock, CosmeticLook::VictoryPose(pose))
+}
+
+const fn pose(
+    name: &'static str,
+    motion: PoseMotion,
+    dutch: &'static str,
+    english: &'static str,
+) -> PoseVariant {
+    PoseVariant {
+        name,
+        motion,
+        dutch,
+        english,
+    }
+}
+
+pub const CATALOG: &[Cosmetic] = &[
+    // Palettes anyone can wear
+    palette("palette_chalk", "Chalk Dust", Unlock::Tokens(150), [0.9, 0.95, 1.0]),
+    palette("palette_detention", "Detention Red", Unlock::Tokens(200), [1.0, 0.6, 0.6]),
+    palette("palette_gym", "Gym Class", Unlock::Tokens(200), [0.65, 1.0, 0.7]),
+    palette("palette_midnight", "Midnight Oil", Unlock::Mastery(20), [0.55, 0.55, 0.9]),
+    // Each character's signature palette
+    signature("palette_bas", "Broom Closet", CharacterId::Bas, [0.75, 1.0, 0.8]),
+    signature("palette_berkay", "Kebab Glow", CharacterId::Berkay, [1.0, 0.8, 0.55]),
+    signature("palette_gefferinho", "Home Kit", CharacterId::Gefferinho, [1.0, 0.9, 0.5]),
+    signature("palette_hadi", "Desert Gold", CharacterId::Hadi, [1.0, 0.9, 0.6]),
+    signature("palette_nitin", "Ghost Pepper", CharacterId::Nitin, [1.0, 0.55, 0.45]),
+    signature("palette_luca", "Frostbite", CharacterId::Luca, [0.7, 0.85, 1.0]),
+    signature("palette_palababa", "Crescent", CharacterId::PalaBaba, [1.0, 0.5, 0.55]),
+    signature("palette_fufinho", "Plantain", CharacterId::Fufinho, [0.95, 1.0, 0.55]),
+    signature("palette_efeabi", "Sumac", CharacterId::EfeAbi, [0.9, 0.6, 0.5]),
+    signature("palette_jad", "Original Recipe", CharacterId::Jad, [1.0, 0.75, 0.5]),
+    signature("palette_umut", "Hallowed Bar", CharacterId::Umut, [0.95, 0.85, 1.0]),
+    signature("palette_keizer", "Desert Camo", CharacterId::KeizerBomTaha, [0.9, 0.85, 0.6]),
+    signature("palette_tobese", "Chocomel", CharacterId::GoonLordTobese, [0.8, 0.6, 0.45]),
+    // Outfits
+    outfit("outfit_beanie", "Winter Beanie", Unlock::Tokens(250), Outfit::Beanie, [0.2, 0.4, 0.9]),
+    outfit("outfit_shades", "Cool Shades", Unlock::Tokens(300), Outfit::Shades, [0.05, 0.05, 0.1]),
+    outfit("outfit_scarf", "Cup Final Scarf", Unlock::Tokens(250), Outfit::Scarf, [0.9, 0.2, 0.2]),
+    outfit("outfit_crown", "Class Crown", Unlock::Mastery(30), Outfit::Crown, [1.0, 0.8, 0.1]),
+    // Hit sparks
+    spark("spark_gold", "Golden Sparks", Unlock::Tokens(150), [1.0, 0.85, 0.2]),
+    spark("spark_ice", "Ice Sparks", Unlock::Tokens(150), [0.55, 0.9, 1.0]),
+    spark("spark_toxic", "Toxic Sparks", Unlock::Tokens(200), [0.5, 1.0, 0.2]),
+    spark("spark_void", "Void Sparks", Unlock::Mastery(40), [0.7, 0.2, 1.0]),
+    // Victory poses
+    victory(
+        "pose_broom_spin",
+        Unlock::Tokens(300),
+        pose(
+            "Broom Spin",
+            PoseMotion::Strike,
+            "Bezem? Dit is een wapen.",
+            "Broom? This is a weapon.",
+        ),
+    ),
+    victory(
+        "pose_mic_drop",
+        Unlock::Tokens(400),
+        pose("Mic Drop", PoseMotion::Taunt, "Klaar. Microfoon neer.", "Done. Mic drop."),
+    ),
+    victory(
+        "pose_curtain_call",
+        Unlock::Mastery(25),
+        pose("Curtain Call", PoseMotion::Guard, "Dank je, dank je.", "Thank you, thank you."),
+    ),
+];
+
+pub fn cosmetic_by_id(id: &str) -> Option<&'static Cosmetic> {
+    CATALOG.iter().find(|cosmetic| cosmetic.id == id)
+}
+
+fn rgb([r, g, b]: [f32; 3]) -> Color {
+    Color::new(r, g, b, 1.0)
+}
+
+/// What one character has on, by cosmetic id
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Loadout {
+    pub character: Option<CharacterId>,
+    pub palette: Option<String>,
+    pub outfit: Option<String>,
+    pub hit_spark: Option<String>,
+    pub victory_pose: Option<String>,
+}
+
+impl Loadout {
+    fn slot_mut(&mut self, slot: CosmeticSlot) -> &mut Option<String> {
+        match slot {
+            CosmeticSlot::Palette => &mut self.palette,
+            CosmeticSlot::Outfit => &mut self.outfit,
+            CosmeticSlot::HitSpark => &mut self.hit_spark,
+            CosmeticSlot::VictoryPose => &mut self.victory_pose,
+        }
+    }
+
+    pub fn slot(&self, slot: CosmeticSlot) -> Option<&'static Cosmetic> {
+        let id = match slot {
+            CosmeticSlot::Palette => &self.palette,
+            CosmeticSlot::Outfit => &self.outfit,
+            CosmeticSlot::HitSpark => &self.hit_spark,
+            CosmeticSlot::VictoryPose => &self.victory_pose,
+        };
+        cosmetic_by_id(id.as_deref()?)
+    }
+}
+
+/// A loadout worked out into what gets drawn
+#[derive(Clone, Copy, Default)]
+pub struct EquippedCosmetics {
+    pub tint: Option<Color>,
+    pub outfit: Option<(Outfit, Color)>,
+    pub hit_spark: Option<Color>,
+    pub victory_pose: Option<&'static PoseVariant>,
+}
+
+/// Cosmetics bought with Arc Tokens and what each character has on, in the
+/// profile save
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct CosmeticsProgress {
+    pub owned: Vec<String>,
+    pub loadouts: Vec<Loadout>,
+}
+
+impl CosmeticsProgress {
+    /// Progress from the profile save; a missing profile owns nothing
+    pub fn load() -> Self {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        match saves.load_or_create(profile, DEFAULT_PROFILE) {
+            Ok(save) => save.cosmetics.clone(),
+            Err(e) => {
+                crash::log(format!("Failed to load cosmetics: {}", e));
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the progress back into the profile, leaving the rest of it be
+    pub fn save(&self) -> Result<(), String> {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        saves.load_or_create(profile, DEFAULT_PROFILE)?.cosmetics = self.clone();
+        saves.save_to_slot(profile)
+    }
+
+    /// Whether `character` at `mastery_level` can put it on
+    pub fn available(
+        &self,
+        cosmetic: &Cosmetic,
+        character: CharacterId,
+        mastery_level: u32,
+    ) -> bool {
+        cosmetic.fits(character)
+            && match cosmetic.unlock {
+                Unlock::Tokens(_) => self.owned.iter().any(|id| id == cosmetic.id),
+                Unlock::Mastery(level) => mastery_level >= level,
+            }
+    }
+
+    /// Pay for a token cosmetic out of the shop's purse
+    pub fn buy(&mut self, cosmetic: &Cosmetic, shop: &mut ShopManager) -> Result<(), String> {
+        let Unlock::Tokens(cost) = cosmetic.unlock else {
+            return Err(format!("{} is unlocked through mastery", cosmetic.name));
+        };
+        if self.owned.iter().any(|id| id == cosmetic.id) {
+            return Err(format!("{} is already owned", cosmetic.name));
+        }
+        shop.spend_currency(cost, cosmetic.name)?;
+        self.owned.push(cosmetic.id.to_string());
+        Ok(())
+    }
+
+    pub fn loadout(&self, character: CharacterId) -> Loadout {
+        self.loadouts
+            .iter()
+            .find(|loadout| loadout.character == Some(character))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Put `cosmetic` on `character`'s `slot`; `None` takes it off
+    pub fn equip(
+        &mut self,
+        character: CharacterId,
+        slot: CosmeticSlot,
+        cosmetic: Option<&Cosmetic>,
+    ) {
+        let index = match self
+            .loadouts
+            .iter()
+            .position(|loadout| loadout.character == Some(character))
+        {
+            Some(index) => index,
+            None => {
+                self.loadouts.push(Loadout {
+                    character: Some(character),
+                    ..Loadout::default()
+                });
+                self.loadouts.len() - 1
+            }
+        };
+        *self.loadouts[index].slot_mut(slot) = cosmetic.map(|cosmetic| cosmetic.id.to_string());
+    }
+
+    /// The loadout as drawn. Anything no longer available, like a cosmetic
+    /// from a mastery since prestiged away, is left off.
+    pub fn equipped(&self, character: CharacterId, mastery_level: u32) -> EquippedCosmetics {
+        let loadout = self.loadout(character);
+        let mut equipped = EquippedCosmetics::default();
+        for slot in CosmeticSlot::ALL {
+            let Some(cosmetic) = loadout
+                .slot(slot)
+                .filter(|cosmetic| self.available(cosmetic, character, mastery_level))
+            else {
+                continue;
+            };
+            match &cosmetic.look {
+                CosmeticLook::Palette(tint) => equipped.tint = Some(rgb(*tint)),
+                CosmeticLook::Outfit(outfit, color) => {
+                    equipped.outfit = Some((*outfit, rgb(*color)))
+                }
+                CosmeticLook::HitSpark(color) => equipped.hit_spark = Some(rgb(*color)),
+                CosmeticLook::VictoryPose(pose) => equipped.victory_pose = Some(pose),
+            }
+        }
+        equipped
+    }
+}
+
+/// What fits `character` in `slot`, in catalog order
+pub fn slot_cosmetics(
+    character: CharacterId,
+    slot: CosmeticSlot,
+) -> impl Iterator<Item = &'static Cosmetic> {
+    CATALOG
+        .iter()
+        .filter(move |cosmetic| cosmetic.slot() == slot && cosmetic.fits(character))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_what_is_owned_or_mastered_is_drawn() {
+        let mut progress = CosmeticsProgress::default();
+        let beanie = cosmetic_by_id("outfit_beanie").unwrap();
+        let crown = cosmetic_by_id("outfit_crown").unwrap();
+        let signature = cosmetic_by_id("palette_luca").unwrap();
+
+        progress.equip(CharacterId::Luca, CosmeticSlot::Outfit, Some(beanie));
+        progress.equip(CharacterId::Luca, CosmeticSlot::Palette, Some(signature));
+        let equipped = progress.equipped(CharacterId::Luca, 1);
+        assert!(equipped.outfit.is_none());
+        assert!(equipped.tint.is_none());
+
+        progress.owned.push(beanie.id.to_string());
+        let equipped = progress.equipped(CharacterId::Luca, 12);
+        assert_eq!(equipped.outfit.map(|(outfit, _)| outfit), Some(Outfit::Beanie));
+        assert!(equipped.tint.is_some());
+
+        assert!(!progress.available(signature, CharacterId::Bas, 100));
+        assert!(!progress.available(crown, CharacterId::Bas, 29));
+        assert!(progress.available(crown, CharacterId::Bas, 30));
+    }
+}
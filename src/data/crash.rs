@@ -0,0 +1,331 @@
+use crate::data::{events, GameConfig, SaveBundle};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+/// Log lines a crash dump carries, newest last
+const LOG_LINES: usize = 200;
+/// Dumps kept after the player has been asked about them
+const KEPT_DUMPS: usize = 10;
+/// Length of a new-issue link, once encoded; GitHub refuses much longer URLs
+const ISSUE_URL_LIMIT: usize = 8000;
+const ISSUE_URL: &str = "https://github.com/compiledkernel-idk/bas-veeg-arc/issues/new";
+/// Seconds an endpoint upload may take
+const TIMEOUT: u64 = 10;
+
+static LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+/// Which states are up and who's playing, kept current by the state manager
+static SUMMARY: Mutex<String> = Mutex::new(String::new());
+
+/// Print `line` to stderr and keep it for the crash dump
+pub fn log(line: impl Into<String>) {
+    let line = line.into();
+    eprintln!("{}", line);
+    if let Ok(mut log) = LOG.lock() {
+        if log.len() == LOG_LINES {
+            log.pop_front();
+        }
+        log.push_back(line);
+    }
+}
+
+/// What the game is doing, for a dump if it crashes before this changes
+pub fn set_summary(summary: String) {
+    if let Ok(mut current) = SUMMARY.lock() {
+        *current = summary;
+    }
+}
+
+/// What a crash dump records about a panic
+#[derive(Debug)]
+pub struct CrashDump {
+    pub message: String,
+    pub location: String,
+    pub thread: String,
+    pub backtrace: String,
+    pub summary: String,
+    pub log: Vec<String>,
+    pub time: u64,
+}
+
+impl CrashDump {
+    /// Everything a dump holds, gathered inside the panic hook. The locks are
+    /// only tried, so a panic while one is held still gets its dump.
+    pub fn capture(info: &PanicHookInfo) -> Self {
+        Self {
+            message: panic_message(info.payload()),
+            location: info
+                .location()
+                .map_or_else(|| "unknown".to_string(), |location| location.to_string()),
+            thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+            backtrace: Backtrace::force_capture().to_string(),
+            summary: SUMMARY.try_lock().map(|summary| summary.clone()).unwrap_or_default(),
+            log: LOG.try_lock().map(|log| log.iter().cloned().collect()).unwrap_or_default(),
+            time: events::now(),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let summary = if self.summary.is_empty() { "unknown" } else { &self.summary };
+        format!(
+            "Bas Veeg Arc crash report\n\
+             Version: {}\n\
+             OS: {} {}\n\
+             Time: {}\n\n\
+             Panic: {}\n\
+             Location: {}\n\
+             Thread: {}\n\n\
+             Game state:\n{}\n\n\
+             Backtrace:\n{}\n\n\
+             Last {} log lines:\n{}\n",
+            crate::updater::CURRENT_VERSION,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            self.time,
+            self.message,
+            self.location,
+            self.thread,
+            summary,
+            self.backtrace.trim_end(),
+            self.log.len(),
+            self.log.join("\n"),
+        )
+    }
+
+    /// Write the dump where the next launch looks for one
+    pub fn write(&self) -> Result<PathBuf, String> {
+        let dir = new_dumps_directory();
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("crash-{}.txt", self.time));
+        fs::write(&path, self.render()).map_err(|e| format!("Failed to write crash dump: {}", e))?;
+        Ok(path)
+    }
+}
+
+/// The text a panic was raised with; anything but a string is unknown
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn dumps_directory() -> PathBuf {
+    SaveBundle::data_directory().join("crashes")
+}
+
+/// Dumps the player hasn't been asked about yet
+fn new_dumps_directory() -> PathBuf {
+    dumps_directory().join("new")
+}
+
+/// A crash dump from an earlier run, waiting for the player to send it or
+/// keep it to themselves
+pub struct PendingReport {
+    path: PathBuf,
+    contents: String,
+}
+
+/// The newest dump nobody has been asked about. Older ones from the same
+/// streak of crashes are put away unasked, so the player is asked once.
+pub fn pending_report() -> Option<PendingReport> {
+    let mut dumps: Vec<PathBuf> = fs::read_dir(new_dumps_directory())
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    dumps.sort();
+    let path = dumps.pop()?;
+    for older in dumps {
+        archive(&older);
+    }
+    let contents = fs::read_to_string(&path).ok()?;
+    Some(PendingReport { path, contents })
+}
+
+impl PendingReport {
+    /// What crashed, from the dump's panic line
+    pub fn panic(&self) -> &str {
+        self.contents
+            .lines()
+            .find_map(|line| line.strip_prefix("Panic: "))
+            .unwrap_or("unknown panic")
+    }
+
+    /// Send the dump: to the crash report endpoint in the config when there
+    /// is one, in the background, or else as a prefilled GitHub issue in the
+    /// browser for the player to look over and submit
+    pub fn send(self) -> Result<String, String> {
+        let endpoint = GameConfig::load().crash_report_url;
+        archive(&self.path);
+        if endpoint.is_empty() {
+            open_in_browser(&issue_url(&self.contents))?;
+            return Ok("Opened a crash report on GitHub".to_string());
+        }
+
+        std::thread::spawn(move || {
+            let sent = minreq::post(&endpoint)
+                .with_header("User-Agent", "bas-veeg-arc-crash-reporter")
+                .with_header("Content-Type", "text/plain; charset=utf-8")
+                .with_timeout(TIMEOUT)
+                .with_body(self.contents)
+                .send();
+            match sent {
+                Ok(response) if (200..300).contains(&response.status_code) => {
+                    log("Crash report sent");
+                }
+                Ok(response) => {
+                    log(format!("Crash report refused (status {})", response.status_code));
+                }
+                Err(e) => log(format!("Failed to send crash report: {}", e)),
+            }
+        });
+        Ok("Sending crash report...".to_string())
+    }
+
+    /// Don't send it; it stays on disk with the other dumps
+    pub fn keep_private(self) {
+        archive(&self.path);
+    }
+}
+
+/// Move a dump out of the new ones, dropping the oldest past `KEPT_DUMPS`
+fn archive(path: &Path) {
+    let dir = dumps_directory();
+    if let Some(name) = path.file_name() {
+        let _ = fs::rename(path, dir.join(name));
+    }
+
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+    let mut kept: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    kept.sort();
+    let excess = kept.len().saturating_sub(KEPT_DUMPS);
+    for old in &kept[..excess] {
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// A new-issue link with the dump as its body, cut short so the encoded link
+/// stays under `ISSUE_URL_LIMIT`
+fn issue_url(dump: &str) -> String {
+    let title = dump
+        .lines()
+        .find_map(|line| line.strip_prefix("Panic: "))
+        .map_or_else(|| "Crash report".to_string(), |panic| format!("Crash: {}", panic));
+    let title: String = title.chars().take(100).collect();
+    let url = format!("{}?labels=crash&title={}&body=", ISSUE_URL, encode_query(&title));
+
+    let (open, close) = ("```\n", "\n```");
+    let cut = "\n... (cut short; the full dump is in the crashes folder)";
+    let wrapping = encoded_len(open) + encoded_len(close);
+    let body = if url.len() + wrapping + encoded_len(dump) <= ISSUE_URL_LIMIT {
+        dump.to_string()
+    } else {
+        let mut budget = ISSUE_URL_LIMIT.saturating_sub(url.len() + wrapping + encoded_len(cut));
+        let mut body = String::new();
+        for c in dump.chars() {
+            let len = encoded_len(c.encode_utf8(&mut [0; 4]));
+            if len > budget {
+                break;
+            }
+            budget -= len;
+            body.push(c);
+        }
+        body.push_str(cut);
+        body
+    };
+    format!("{}{}", url, encode_query(&format!("{}{}{}", open, body, close)))
+}
+
+fn unreserved(byte: u8) -> bool {
+    matches!(byte, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~')
+}
+
+/// Length of `text` once percent-encoded
+fn encoded_len(text: &str) -> usize {
+    text.bytes().map(|byte| if unreserved(byte) { 1 } else { 3 }).sum()
+}
+
+/// Percent-encode `text` for a URL query value
+fn encode_query(text: &str) -> String {
+    text.bytes()
+        .map(|byte| {
+            if unreserved(byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
+
+fn open_in_browser(url: &str) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Couldn't open the browser: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_carry_the_panic_and_issue_links_stay_short() {
+        let payload: Box<dyn Any + Send> = Box::new(format!("index {} out of range", 7));
+        let dump = CrashDump {
+            message: panic_message(payload.as_ref()),
+            location: "src/states/gameplay.rs:10:5".to_string(),
+            thread: "main".to_string(),
+            backtrace: "0: bas_veeg_arc::main\n".to_string(),
+            summary: "States: Boot > Menu".to_string(),
+            log: vec!["Entered Menu".to_string(); 3],
+            time: 1_700_000_000,
+        };
+        let text = dump.render();
+        assert!(text.contains("Panic: index 7 out of range\nLocation: src/states/gameplay.rs:10"));
+        assert!(text.contains("Game state:\nStates: Boot > Menu\n"));
+        assert!(text.ends_with("Last 3 log lines:\nEntered Menu\nEntered Menu\nEntered Menu\n"));
+        assert_eq!(panic_message(&5u32), "unknown panic");
+
+        let report = PendingReport {
+            path: PathBuf::new(),
+            contents: text.clone(),
+        };
+        assert_eq!(report.panic(), "index 7 out of range");
+
+        assert_eq!(encode_query("a b&c=d/é"), "a%20b%26c%3Dd%2F%C3%A9");
+        let url = issue_url(&text.repeat(100));
+        assert!(url.starts_with(ISSUE_URL));
+        assert!(url.contains("&title=Crash%3A%20index%207%20out%20of%20range&"));
+        assert!(url.len() <= ISSUE_URL_LIMIT, "{} characters", url.len());
+        assert!(url.contains("cut%20short"));
+        let short = issue_url(&text);
+        assert!(short.len() <= ISSUE_URL_LIMIT && !short.contains("cut%20short"));
+        // Characters that grow sixfold when encoded are cut to fit too
+        assert!(issue_url(&"é".repeat(5000)).len() <= ISSUE_URL_LIMIT);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::audio::MusicCue;
+use crate::data::encounters::EncounterLine;
+use crate::data::poses::PoseMotion;
+use crate::data::{balance, mods, storage};
+use crate::render::map_system::MapType;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Actor sheet that stands in for whoever the player picked
+pub const PLAYER_SHEET: &str = "player";
+
+/// Scenes the story plays, each of which needs a script
+pub const SCRIPTED_SCENES: [CutsceneId; 5] = [
+    CutsceneId::Opening,
+    CutsceneId::RooftopIntro,
+    CutsceneId::EndingParty,
+    CutsceneId::EndingRepair,
+    CutsceneId::EndingHome,
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CutsceneId {
+    /// Meneer Wolters and the broom
+    Opening,
+    /// Before the Bastiaan fight
+    RooftopIntro,
+    /// One for each story ending
+    EndingParty,
+    EndingRepair,
+    EndingHome,
+}
+
+/// Someone on stage, in canvas units
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CutsceneActor {
+    /// What steps call them by
+    pub name: String,
+    /// Sprite sheet; `PLAYER_SHEET` for the player's character
+    pub sheet: String,
+    pub position: (f32, f32),
+    /// 1 faces right, -1 left
+    pub facing: f32,
+}
+
+/// Steps play one after another; moves, lines and waits hold the scene for
+/// their duration, poses and music cues pass straight on
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CutsceneStep {
+    /// Pan and zoom to frame `focus`; a zero duration cuts
+    Camera {
+        focus: (f32, f32),
+        zoom: f32,
+        duration: f32,
+    },
+    /// Held until the actor is posed again
+    Pose { actor: String, motion: PoseMotion },
+    /// Shown for its duration, or until skipped
+    Line(EncounterLine),
+    Music(MusicCue),
+    Wait(f32),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cutscene {
+    pub id: CutsceneId,
+    /// Map the scene is staged on
+    pub backdrop: MapType,
+    pub actors: Vec<CutsceneActor>,
+    pub steps: Vec<CutsceneStep>,
+}
+
+/// Every scene the story plays. Lives in `cutscenes.json` next to the
+/// encounters so scenes can be rewritten without a rebuild.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CutsceneScript {
+    pub scenes: Vec<Cutscene>,
+}
+
+impl Default for CutsceneScript {
+    fn default() -> Self {
+        let actor = |name: &str, sheet: &str, x, y, facing| CutsceneActor {
+            name: name.to_string(),
+            sheet: sheet.to_string(),
+            position: (x, y),
+            facing,
+        };
+        let line = |speaker: &str, dutch: &str, english: &str, duration| {
+            CutsceneStep::Line(EncounterLine {
+                speaker: speaker.to_string(),
+                dutch: dutch.to_string(),
+                english: english.to_string(),
+                duration,
+            })
+        };
+        let camera = |x, y, zoom, duration| CutsceneStep::Camera {
+            focus: (x, y),
+            zoom,
+            duration,
+        };
+        let pose = |actor: &str, motion| CutsceneStep::Pose {
+            actor: actor.to_string(),
+            motion,
+        };
+        let you = || actor("you", PLAYER_SHEET, 620.0, 700.0, 1.0);
+
+        let opening = Cutscene {
+            id: CutsceneId::Opening,
+            backdrop: MapType::Classroom,
+            actors: vec![you(), actor("wolters", "wolters", 1300.0, 700.0, -1.0)],
+            steps: vec![
+                camera(960.0, 620.0, 1.0, 0.0),
+                pose("you", PoseMotion::Taunt),
+                line(
+                    "Bas",
+                    "Kom dan! Ik veeg niks, bro!",
+                    "Come on then! I'm not sweeping anything, bro!",
+                    2.4,
+                ),
+                camera(1200.0, 620.0, 1.3, 0.8),
+                line("Meneer Wolters", "Bas, vegen!", "Bas, sweep!", 2.0),
+                pose("you", PoseMotion::Guard),
+                line("Bas", "Nee!", "No!", 1.5),
+                pose("wolters", PoseMotion::Strike),
+                line("Meneer Wolters", "Bas! Vegen, nu meteen!", "Bas! Sweep right now!", 2.4),
+            ],
+        };
+
+        let rooftop_intro = Cutscene {
+            id: CutsceneId::RooftopIntro,
+            backdrop: MapType::Rooftop,
+            actors: vec![
+                you(),
+                actor("bastiaan", "bastiaan", 1380.0, 690.0, -1.0),
+                actor("keizer", "keizer_boss", 1180.0, 520.0, -1.0),
+            ],
+            steps: vec![
+                camera(960.0, 600.0, 1.0, 0.0),
+                pose("you", PoseMotion::Guard),
+                line("You", "Het is voorbij, Bastiaan.", "It's over, Bastiaan.", 2.5),
+                camera(1380.0, 620.0, 1.4, 1.2),
+                pose("bastiaan", PoseMotion::Taunt),
+                line(
+                    "Bastiaan",
+                    "Voorbij? Mijn meesterwerk begint pas!",
+                    "Over? My masterpiece is only just beginning!",
+                    3.0,
+                ),
+                camera(1180.0, 480.0, 1.6, 0.8),
+                pose("keizer", PoseMotion::Strike),
+                line("Keizer Bom Taha", "Luchtsteun staat klaar!", "Air support is ready!", 2.5),
+                camera(960.0, 600.0, 1.0, 1.0),
+                pose("you", PoseMotion::Taunt),
+                line("You", "Kom dan!", "Come on then!", 2.0),
+                CutsceneStep::Music(MusicCue::Battle),
+            ],
+        };
+
+        let ending_party = Cutscene {
+            id: CutsceneId::EndingParty,
+            backdrop: MapType::Cafeteria,
+            actors: vec![
+                you(),
+                actor("berkay", "berkay", 900.0, 720.0, -1.0),
+                actor("hadi", "hadi", 1200.0, 700.0, -1.0),
+            ],
+            steps: vec![
+                CutsceneStep::Music(MusicCue::Results),
+                camera(900.0, 620.0, 1.0, 0.0),
+                pose("you", PoseMotion::Cheer),
+                pose("berkay", PoseMotion::Cheer),
+                line("Berkay", "Feest in de kantine, bro!", "Party in the cafeteria, bro!", 2.5),
+                camera(1200.0, 620.0, 1.3, 0.8),
+                pose("hadi", PoseMotion::Cheer),
+                line(
+                    "Hadi",
+                    "Aina broeg, dit hebben we verdiend.",
+                    "Always, bro, we earned this.",
+                    2.8,
+                ),
+                camera(900.0, 620.0, 1.0, 1.0),
+                line("You", "En niemand veegt vanavond.", "And nobody's sweeping tonight.", 2.8),
+                CutsceneStep::Wait(1.0),
+            ],
+        };
+
+        let ending_repair = Cutscene {
+            id: CutsceneId::EndingRepair,
+            backdrop: MapType::Hallway,
+            actors: vec![
+                you(),
+                actor("luca", "luca", 900.0, 700.0, -1.0),
+                actor("wolters", "wolters", 1450.0, 700.0, -1.0),
+            ],
+            steps: vec![
+                CutsceneStep::Music(MusicCue::Results),
+                camera(760.0, 620.0, 1.2, 0.0),
+                line(
+                    "Luca",
+                    "Ik heb een plan om alles te maken.",
+                    "I've got a plan to fix everything.",
+                    2.8,
+                ),
+                pose("you", PoseMotion::Strike),
+                line("You", "Geef die bezem maar.", "Hand me that broom.", 2.2),
+                camera(1450.0, 620.0, 1.4, 1.0),
+                pose("wolters", PoseMotion::Cheer),
+                line("Meneer Wolters", "Bas... je veegt?!", "Bas... you're sweeping?!", 2.6),
+                camera(960.0, 620.0, 1.0, 1.0),
+                CutsceneStep::Wait(1.0),
+            ],
+        };
+
+        let ending_home = Cutscene {
+            id: CutsceneId::EndingHome,
+            backdrop: MapType::Rooftop,
+            actors: vec![you()],
+            steps: vec![
+                CutsceneStep::Music(MusicCue::Results),
+                camera(620.0, 600.0, 1.5, 0.0),
+                pose("you", PoseMotion::Guard),
+                line("You", "Genoeg arc voor vandaag.", "Enough arc for one day.", 2.5),
+                camera(960.0, 540.0, 0.9, 2.0),
+                line("You", "Morgen weer school...", "School again tomorrow...", 2.5),
+                CutsceneStep::Wait(1.0),
+            ],
+        };
+
+        Self {
+            scenes: vec![opening, rooftop_intro, ending_party, ending_repair, ending_home],
+        }
+    }
+}
+
+impl CutsceneScript {
+    /// Load the built-in scenes with `cutscenes.json` laid over them,
+    /// swapping in the defaults if that has problems; those come back
+    /// alongside it
+    pub fn load() -> (Self, Vec<String>) {
+        let path = mods::resolve(Self::file_path());
+        let script = match storage::read_overrides(&path, Self::default()) {
+            Ok(script) => script,
+            Err(e) => return (Self::default(), vec![e]),
+        };
+        let problems = script.validate();
+        if problems.is_empty() {
+            (script, problems)
+        } else {
+            (Self::default(), problems)
+        }
+    }
+
+    /// Everything that would stop a scene from playing, one line each
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for id in SCRIPTED_SCENES {
+            match self.scenes.iter().filter(|scene| scene.id == id).count() {
+                0 => problems.push(format!("{:?}: not scripted", id)),
+                1 => {}
+                _ => problems.push(format!("{:?}: scripted more than once", id)),
+            }
+        }
+
+        for scene in &self.scenes {
+            for (index, step) in scene.steps.iter().enumerate() {
+                let at = format!("{:?} step {}", scene.id, index + 1);
+                match step {
+                    CutsceneStep::Camera { zoom, duration, .. } => {
+                        if *zoom <= 0.0 {
+                            problems.push(format!("{}: zoom must be above zero", at));
+                        }
+                        if *duration < 0.0 {
+                            problems.push(format!("{}: duration can't be negative", at));
+                        }
+                    }
+                    CutsceneStep::Pose { actor, .. } => {
+                        if !scene.actors.iter().any(|a| &a.name == actor) {
+                            problems.push(format!("{}: no actor called {}", at, actor));
+                        }
+                    }
+                    CutsceneStep::Line(line) => {
+                        if line.speaker.trim().is_empty() {
+                            problems.push(format!("{}: line without a speaker", at));
+                        }
+                        if line.duration <= 0.0 {
+                            problems.push(format!("{}: line needs a duration", at));
+                        }
+                    }
+                    CutsceneStep::Wait(seconds) if *seconds < 0.0 => {
+                        problems.push(format!("{}: wait can't be negative", at));
+                    }
+                    CutsceneStep::Music(_) | CutsceneStep::Wait(_) => {}
+                }
+            }
+        }
+        problems
+    }
+
+    pub fn scene(&self, id: CutsceneId) -> Option<&Cutscene> {
+        self.scenes.iter().find(|scene| scene.id == id)
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("cutscenes.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_script_every_scene() {
+        let script = CutsceneScript::default();
+        assert!(script.validate().is_empty());
+        for id in SCRIPTED_SCENES {
+            assert!(script.scene(id).is_some_and(|scene| !scene.steps.is_empty()));
+        }
+    }
+
+    #[test]
+    fn broken_scenes_are_reported() {
+        let mut script = CutsceneScript::default();
+        script.scenes.retain(|scene| scene.id != CutsceneId::EndingHome);
+        script.scenes[0].steps = vec![
+            CutsceneStep::Pose {
+                actor: "nobody".to_string(),
+                motion: PoseMotion::Cheer,
+            },
+            CutsceneStep::Camera {
+                focus: (0.0, 0.0),
+                zoom: 0.0,
+                duration: 1.0,
+            },
+        ];
+
+        assert_eq!(
+            script.validate(),
+            vec![
+                "EndingHome: not scripted".to_string(),
+                "Opening step 1: no actor called nobody".to_string(),
+                "Opening step 2: zoom must be above zero".to_string(),
+            ]
+        );
+    }
+}
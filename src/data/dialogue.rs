@@ -0,0 +1,525 @@
+use crate::data::cutscenes::CutsceneId;
+use crate::data::save::{SaveManager, DEFAULT_PROFILE};
+use crate::data::{balance, crash, mods, storage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Node the conversation after the boss battle opens on
+pub const AFTERMATH: &str = "aftermath";
+
+/// How a cleared run ends; each has its own cutscene and results screen
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoryEnding {
+    Party,
+    Repair,
+    Home,
+}
+
+impl StoryEnding {
+    pub const ALL: [StoryEnding; 3] = [StoryEnding::Party, StoryEnding::Repair, StoryEnding::Home];
+
+    pub fn cutscene(self) -> CutsceneId {
+        match self {
+            StoryEnding::Party => CutsceneId::EndingParty,
+            StoryEnding::Repair => CutsceneId::EndingRepair,
+            StoryEnding::Home => CutsceneId::EndingHome,
+        }
+    }
+
+    /// Headline of its results screen
+    pub fn title(self) -> &'static str {
+        match self {
+            StoryEnding::Party => "THE AFTERPARTY",
+            StoryEnding::Repair => "BACK TO SCHOOL",
+            StoryEnding::Home => "HOME AT LAST",
+        }
+    }
+
+    /// What the bonus row on its results screen is called
+    pub fn bonus_label(self) -> &'static str {
+        match self {
+            StoryEnding::Party => "Party Bonus:",
+            StoryEnding::Repair => "Repair Bonus:",
+            StoryEnding::Home => "Rest Bonus:",
+        }
+    }
+
+    /// How it played out, adjusted for the flags the save has raised
+    pub fn epilogue(self, memory: &DialogueMemory) -> Vec<&'static str> {
+        let mut lines = Vec::new();
+        match self {
+            StoryEnding::Party => {
+                lines.push("The music ran until the caretaker pulled the plug.");
+                if memory.has_flag("made_peace") {
+                    lines.push("Bastiaan showed up, and even danced a little.");
+                }
+                if memory.has_flag("school_party") {
+                    lines.push("Half the school still talks about it.");
+                }
+            }
+            StoryEnding::Repair => {
+                lines.push("By Monday the classrooms were back in one piece.");
+                if memory.has_flag("spotless") {
+                    lines.push("Nobody ever found out what happened on the roof.");
+                }
+            }
+            StoryEnding::Home => {
+                lines.push("Everyone slept straight through the weekend.");
+                if memory.has_seen(StoryEnding::Party) || memory.has_seen(StoryEnding::Repair) {
+                    lines.push("Some days that's the best way to celebrate.");
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// What the run did, for conditions to be checked against
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunFacts {
+    pub kills: u32,
+    pub waves: u32,
+    /// Took no damage the whole run
+    pub flawless: bool,
+    pub went_down: bool,
+}
+
+/// Has to hold for a choice to be offered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// `choice` was picked at `node`, in this run or an earlier one
+    Chose { node: String, choice: String },
+    /// `choice` has never been picked at `node`
+    NotChose { node: String, choice: String },
+    Flag(String),
+    SeenEnding(StoryEnding),
+    MinKills(u32),
+    MinWaves(u32),
+    Flawless,
+    /// Nobody went down during the run
+    NeverDown,
+}
+
+/// What picking a choice does, beyond being remembered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Consequence {
+    /// Carry on at another node
+    Goto(String),
+    /// Raise a flag the save keeps
+    SetFlag(String),
+    /// End the conversation on this ending
+    Ending(StoryEnding),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogueChoice {
+    pub id: String,
+    pub text: String,
+    /// All of them have to hold for the choice to be offered
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub consequences: Vec<Consequence>,
+}
+
+impl DialogueChoice {
+    /// Where the choice leads; validation makes sure there's exactly one
+    fn outcome(&self) -> Option<Outcome> {
+        self.consequences.iter().find_map(|consequence| match consequence {
+            Consequence::Goto(node) => Some(Outcome::Next(node.clone())),
+            Consequence::Ending(ending) => Some(Outcome::Ending(*ending)),
+            Consequence::SetFlag(_) => None,
+        })
+    }
+}
+
+/// One question and the answers to it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogueNode {
+    pub id: String,
+    pub speaker: String,
+    pub dutch: String,
+    pub english: String,
+    pub choices: Vec<DialogueChoice>,
+}
+
+/// Where a picked choice leads
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Next(String),
+    Ending(StoryEnding),
+}
+
+/// Choices picked, flags raised and endings seen, kept in the profile save
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DialogueMemory {
+    /// Every choice ever picked at each node
+    pub choices: BTreeMap<String, Vec<String>>,
+    pub flags: Vec<String>,
+    pub endings: Vec<StoryEnding>,
+}
+
+impl DialogueMemory {
+    /// Memory from the profile save; a missing profile remembers nothing
+    pub fn load() -> Self {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        match saves.load_or_create(profile, DEFAULT_PROFILE) {
+            Ok(save) => save.dialogue.clone(),
+            Err(e) => {
+                crash::log(format!("Failed to load dialogue choices: {}", e));
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the memory back into the profile, leaving the rest of it be
+    pub fn save(&self) -> Result<(), String> {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        saves.load_or_create(profile, DEFAULT_PROFILE)?.dialogue = self.clone();
+        saves.save_to_slot(profile)
+    }
+
+    pub fn chose(&self, node: &str, choice: &str) -> bool {
+        self.choices
+            .get(node)
+            .is_some_and(|picked| picked.iter().any(|id| id == choice))
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|raised| raised == flag)
+    }
+
+    pub fn has_seen(&self, ending: StoryEnding) -> bool {
+        self.endings.contains(&ending)
+    }
+
+    pub fn check(&self, condition: &Condition, facts: &RunFacts) -> bool {
+        match condition {
+            Condition::Chose { node, choice } => self.chose(node, choice),
+            Condition::NotChose { node, choice } => !self.chose(node, choice),
+            Condition::Flag(flag) => self.has_flag(flag),
+            Condition::SeenEnding(ending) => self.has_seen(*ending),
+            Condition::MinKills(kills) => facts.kills >= *kills,
+            Condition::MinWaves(waves) => facts.waves >= *waves,
+            Condition::Flawless => facts.flawless,
+            Condition::NeverDown => !facts.went_down,
+        }
+    }
+
+    /// Remember `choice` was picked at `node` and carry out its
+    /// consequences, returning where it leads
+    pub fn apply(&mut self, node: &str, choice: &DialogueChoice) -> Option<Outcome> {
+        let picked = self.choices.entry(node.to_string()).or_default();
+        if !picked.contains(&choice.id) {
+            picked.push(choice.id.clone());
+        }
+        for consequence in &choice.consequences {
+            match consequence {
+                Consequence::SetFlag(flag) if !self.has_flag(flag) => self.flags.push(flag.clone()),
+                Consequence::Ending(ending) if !self.has_seen(*ending) => {
+                    self.endings.push(*ending)
+                }
+                _ => {}
+            }
+        }
+        choice.outcome()
+    }
+}
+
+/// Every conversation node, from `dialogue.json`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DialogueScript {
+    pub nodes: Vec<DialogueNode>,
+}
+
+impl Default for DialogueScript {
+    fn default() -> Self {
+        let choice = |id: &str, text: &str, conditions, consequences| DialogueChoice {
+            id: id.to_string(),
+            text: text.to_string(),
+            conditions,
+            consequences,
+        };
+        let node = |id: &str, speaker: &str, dutch: &str, english: &str, choices| DialogueNode {
+            id: id.to_string(),
+            speaker: speaker.to_string(),
+            dutch: dutch.to_string(),
+            english: english.to_string(),
+            choices,
+        };
+        let goto = |node: &str| Consequence::Goto(node.to_string());
+        let flag = |flag: &str| Consequence::SetFlag(flag.to_string());
+
+        Self {
+            nodes: vec![
+                node(
+                    AFTERMATH,
+                    "Luca",
+                    "De bazen zijn verslagen! Wat doen we nu?",
+                    "The bosses are beaten! What should we do now?",
+                    vec![
+                        choice(
+                            "party",
+                            "Build a party to celebrate!",
+                            vec![],
+                            vec![goto("guests")],
+                        ),
+                        choice(
+                            "repair",
+                            "Repair the school together.",
+                            vec![],
+                            vec![goto("repairs")],
+                        ),
+                        choice(
+                            "home",
+                            "Go home and rest.",
+                            vec![],
+                            vec![Consequence::Ending(StoryEnding::Home)],
+                        ),
+                    ],
+                ),
+                node(
+                    "guests",
+                    "Berkay",
+                    "Feest! Wie nodigen we uit?",
+                    "A party! Who are we inviting?",
+                    vec![
+                        choice(
+                            "crew",
+                            "Just the crew.",
+                            vec![],
+                            vec![Consequence::Ending(StoryEnding::Party)],
+                        ),
+                        choice(
+                            "everyone",
+                            "The whole school. They saw what we did.",
+                            vec![Condition::MinKills(40)],
+                            vec![flag("school_party"), Consequence::Ending(StoryEnding::Party)],
+                        ),
+                        choice(
+                            "bastiaan",
+                            "Bastiaan too. He helped fix the damage last time.",
+                            vec![Condition::Chose {
+                                node: AFTERMATH.to_string(),
+                                choice: "repair".to_string(),
+                            }],
+                            vec![flag("made_peace"), Consequence::Ending(StoryEnding::Party)],
+                        ),
+                    ],
+                ),
+                node(
+                    "repairs",
+                    "Luca",
+                    "Goed plan. Waar beginnen we?",
+                    "Good plan. Where do we start?",
+                    vec![
+                        choice(
+                            "gym",
+                            "The gym took the worst of it.",
+                            vec![],
+                            vec![Consequence::Ending(StoryEnding::Repair)],
+                        ),
+                        choice(
+                            "rooftop",
+                            "The rooftop, before anyone sees it.",
+                            vec![Condition::NeverDown],
+                            vec![flag("spotless"), Consequence::Ending(StoryEnding::Repair)],
+                        ),
+                        choice(
+                            "give_up",
+                            "Actually... let's just go home.",
+                            vec![],
+                            vec![Consequence::Ending(StoryEnding::Home)],
+                        ),
+                    ],
+                ),
+            ],
+        }
+    }
+}
+
+impl DialogueScript {
+    /// Load the built-in conversations with `dialogue.json` laid over them,
+    /// swapping in the defaults if that has problems; those come back
+    /// alongside it
+    pub fn load() -> (Self, Vec<String>) {
+        let path = mods::resolve(Self::file_path());
+        let script = match storage::read_overrides(&path, Self::default()) {
+            Ok(script) => script,
+            Err(e) => return (Self::default(), vec![e]),
+        };
+        let problems = script.validate();
+        if problems.is_empty() {
+            (script, problems)
+        } else {
+            (Self::default(), problems)
+        }
+    }
+
+    /// Everything that would leave a conversation stuck, one line each
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.node(AFTERMATH).is_none() {
+            problems.push(format!("{}: not scripted", AFTERMATH));
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            if self.nodes[..index].iter().any(|other| other.id == node.id) {
+                problems.push(format!("{}: scripted more than once", node.id));
+            }
+            if node.choices.iter().all(|choice| !choice.conditions.is_empty()) {
+                problems.push(format!("{}: needs a choice that's always offered", node.id));
+            }
+            for choice in &node.choices {
+                let at = format!("{} choice {}", node.id, choice.id);
+                let leads = choice
+                    .consequences
+                    .iter()
+                    .filter(|c| !matches!(c, Consequence::SetFlag(_)))
+                    .count();
+                if leads != 1 {
+                    problems.push(format!("{}: needs exactly one Goto or Ending", at));
+                }
+                for consequence in &choice.consequences {
+                    if let Consequence::Goto(target) = consequence {
+                        if self.node(target).is_none() {
+                            problems.push(format!("{}: no node called {}", at, target));
+                        }
+                    }
+                }
+                for condition in &choice.conditions {
+                    if let Condition::Chose { node: target, choice: picked }
+                    | Condition::NotChose { node: target, choice: picked } = condition
+                    {
+                        let known = self
+                            .node(target)
+                            .is_some_and(|node| node.choices.iter().any(|c| &c.id == picked));
+                        if !known {
+                            problems.push(format!("{}: no choice {} at {}", at, picked, target));
+                        }
+                    }
+                }
+            }
+        }
+
+        for ending in StoryEnding::ALL {
+            let reached = self.nodes.iter().flat_map(|node| &node.choices).any(|choice| {
+                choice.consequences.contains(&Consequence::Ending(ending))
+            });
+            if !reached {
+                problems.push(format!("{:?}: no choice ends there", ending));
+            }
+        }
+        problems
+    }
+
+    pub fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("dialogue.json")
+    }
+}
+
+/// The post-boss conversation as it's being played through
+pub struct Conversation {
+    script: DialogueScript,
+    node: String,
+    memory: DialogueMemory,
+    facts: RunFacts,
+}
+
+impl Conversation {
+    pub fn new(script: DialogueScript, memory: DialogueMemory, facts: RunFacts) -> Self {
+        Self {
+            script,
+            node: AFTERMATH.to_string(),
+            memory,
+            facts,
+        }
+    }
+
+    pub fn node(&self) -> Option<&DialogueNode> {
+        self.script.node(&self.node)
+    }
+
+    /// The current node's choices whose conditions hold
+    pub fn choices(&self) -> Vec<&DialogueChoice> {
+        self.node().map_or_else(Vec::new, |node| {
+            node.choices
+                .iter()
+                .filter(|choice| {
+                    choice
+                        .conditions
+                        .iter()
+                        .all(|condition| self.memory.check(condition, &self.facts))
+                })
+                .collect()
+        })
+    }
+
+    /// Pick the `index`th offered choice. Returns the ending once the
+    /// conversation reaches one; until then it moves on to the next node.
+    pub fn choose(&mut self, index: usize) -> Option<StoryEnding> {
+        let choice = self.choices().get(index).map(|&choice| choice.clone())?;
+        match self.memory.apply(&self.node, &choice)? {
+            Outcome::Next(node) => {
+                self.node = node;
+                None
+            }
+            Outcome::Ending(ending) => Some(ending),
+        }
+    }
+
+    pub fn memory(&self) -> &DialogueMemory {
+        &self.memory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_reach_every_ending() {
+        assert!(DialogueScript::default().validate().is_empty());
+        assert_eq!(StoryEnding::Repair.cutscene(), CutsceneId::EndingRepair);
+    }
+
+    #[test]
+    fn broken_nodes_are_reported() {
+        let mut script = DialogueScript::default();
+        script.nodes.retain(|node| node.id != "repairs");
+        script.nodes[0].choices[0].consequences.push(Consequence::Ending(StoryEnding::Home));
+        let problems = script.validate();
+        assert!(problems.iter().any(|p| p.contains("no node called repairs")));
+        assert!(problems.iter().any(|p| p.contains("exactly one Goto or Ending")));
+        assert!(problems.iter().any(|p| p.starts_with("Repair")));
+    }
+
+    #[test]
+    fn earlier_choices_unlock_answers_in_later_runs() {
+        // Going down takes the rooftop repair off the table
+        let facts = RunFacts {
+            went_down: true,
+            ..RunFacts::default()
+        };
+        let memory = DialogueMemory::default();
+        let mut first = Conversation::new(DialogueScript::default(), memory, facts);
+        assert_eq!(first.choose(1), None);
+        assert_eq!(first.choices().len(), 2);
+        assert_eq!(first.choose(0), Some(StoryEnding::Repair));
+        assert!(first.memory().chose(AFTERMATH, "repair"));
+        assert!(first.memory().has_seen(StoryEnding::Repair));
+
+        let memory = first.memory().clone();
+        let mut second = Conversation::new(DialogueScript::default(), memory, facts);
+        assert_eq!(second.choose(0), None);
+        let offered: Vec<_> = second.choices().iter().map(|choice| choice.id.clone()).collect();
+        assert_eq!(offered, ["crew", "bastiaan"]);
+        assert_eq!(second.choose(1), Some(StoryEnding::Party));
+        assert!(second.memory().has_flag("made_peace"));
+    }
+}
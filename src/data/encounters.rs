@@ -0,0 +1,290 @@
+use crate::data::{balance, mods, storage};
+use crate::ecs::CharacterType;
+use crate::render::map_system::MapType;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Maps a run can reach, each of which needs a script
+pub const SCRIPTED_MAPS: [MapType; 6] = [
+    MapType::Classroom,
+    MapType::Hallway,
+    MapType::Cafeteria,
+    MapType::Gym,
+    MapType::Library,
+    MapType::Rooftop,
+];
+
+/// Scripted boss fights a wave can open with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncounterBoss {
+    /// Giant Bastiaan, with Keizer Bom Taha flying cover
+    Bastiaan,
+    /// Throws pita sirrachas
+    Mees,
+}
+
+/// One kind of enemy in a wave and its odds against the others
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct EnemyWeight {
+    pub character: CharacterType,
+    pub weight: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EncounterLine {
+    pub speaker: String,
+    pub dutch: String,
+    pub english: String,
+    pub duration: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WaveScript {
+    /// Before route, co-op and curse scaling
+    pub enemies: u32,
+    pub mix: Vec<EnemyWeight>,
+    /// Seconds before the first enemy comes in
+    pub delay: f32,
+    /// Seconds between enemies after that
+    pub interval: f32,
+    /// Indices into the map's spawn points, used in turn; empty for all of them
+    pub spawn_points: Vec<usize>,
+    pub boss: Option<EncounterBoss>,
+    /// Queued as the wave starts
+    pub intro: Vec<EncounterLine>,
+    /// Queued once it's cleared
+    pub outro: Vec<EncounterLine>,
+}
+
+impl Default for WaveScript {
+    fn default() -> Self {
+        Self {
+            enemies: 0,
+            mix: Vec::new(),
+            delay: 0.5,
+            interval: 1.0,
+            spawn_points: Vec::new(),
+            boss: None,
+            intro: Vec::new(),
+            outro: Vec::new(),
+        }
+    }
+}
+
+impl WaveScript {
+    /// A weighted pick from the mix, given a roll in `0..total_weight()`
+    pub fn pick(&self, roll: u32) -> Option<CharacterType> {
+        let mut roll = roll;
+        for entry in &self.mix {
+            if roll < entry.weight {
+                return Some(entry.character);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+
+    pub fn total_weight(&self) -> u32 {
+        self.mix.iter().map(|entry| entry.weight).sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapEncounter {
+    pub map: MapType,
+    /// In order; the map is done once the last is cleared
+    pub waves: Vec<WaveScript>,
+}
+
+/// How every map's waves play out. Lives in `encounters.json` next to the
+/// balance reports so encounters can be designed without a rebuild.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EncounterScript {
+    pub maps: Vec<MapEncounter>,
+}
+
+impl Default for EncounterScript {
+    fn default() -> Self {
+        let mix = |characters: &[CharacterType]| -> Vec<EnemyWeight> {
+            characters
+                .iter()
+                .map(|&character| EnemyWeight {
+                    character,
+                    weight: 1,
+                })
+                .collect()
+        };
+        // The count grows by one each wave
+        let waves = |first: u32, characters: &[CharacterType]| -> Vec<WaveScript> {
+            (0..3)
+                .map(|wave| WaveScript {
+                    enemies: first + wave,
+                    mix: mix(characters),
+                    ..WaveScript::default()
+                })
+                .collect()
+        };
+        let line = |speaker: &str, dutch: &str, english: &str, duration| EncounterLine {
+            speaker: speaker.to_string(),
+            dutch: dutch.to_string(),
+            english: english.to_string(),
+            duration,
+        };
+        let prefects = [CharacterType::PrefectA, CharacterType::PrefectB];
+
+        let mut cafeteria = waves(7, &prefects);
+        cafeteria[0] = WaveScript {
+            boss: Some(EncounterBoss::Mees),
+            intro: vec![
+                line("Mees", "Berkay, mag ik tikkie?", "Berkay, can I have tikkie?", 3.0),
+                line("Berkay", "Nee", "No", 2.0),
+                line(
+                    "Mees",
+                    "Geen tikkie! Pita sirracha komt dan eraan!",
+                    "No tikkie! Pita sirracha incoming then!",
+                    3.5,
+                ),
+            ],
+            ..WaveScript::default()
+        };
+
+        // The boss fight is the whole map; the rest is the dialogue choice
+        let mut rooftop = vec![WaveScript::default(); 3];
+        rooftop[0].boss = Some(EncounterBoss::Bastiaan);
+
+        let map = |map, waves| MapEncounter { map, waves };
+        Self {
+            maps: vec![
+                map(MapType::Classroom, waves(4, &[CharacterType::Wolters])),
+                map(MapType::Hallway, waves(6, &prefects)),
+                map(MapType::Cafeteria, cafeteria),
+                map(MapType::Gym, waves(9, &[CharacterType::Coach])),
+                map(MapType::Library, waves(8, &[CharacterType::Librarian])),
+                map(MapType::Rooftop, rooftop),
+            ],
+        }
+    }
+}
+
+impl EncounterScript {
+    /// Load the built-in script with `encounters.json` laid over it, and
+    /// check it against the maps' spawn points (`spawn_points` gives how many
+    /// a map has). Returns what's wrong with it alongside; a script with
+    /// problems is swapped for the defaults.
+    pub fn load(spawn_points: impl Fn(MapType) -> usize) -> (Self, Vec<String>) {
+        let path = mods::resolve(Self::file_path());
+        let script = match storage::read_overrides(&path, Self::default()) {
+            Ok(script) => script,
+            Err(e) => return (Self::default(), vec![e]),
+        };
+        let problems = script.validate(spawn_points);
+        if problems.is_empty() {
+            (script, problems)
+        } else {
+            (Self::default(), problems)
+        }
+    }
+
+    /// Everything that would stop the script from playing, one line each
+    pub fn validate(&self, spawn_points: impl Fn(MapType) -> usize) -> Vec<String> {
+        let mut problems = Vec::new();
+        for map in SCRIPTED_MAPS {
+            match self.maps.iter().filter(|encounter| encounter.map == map).count() {
+                0 => problems.push(format!("{}: no waves scripted", map.display_name())),
+                1 => {}
+                _ => problems.push(format!("{}: scripted more than once", map.display_name())),
+            }
+        }
+
+        for encounter in &self.maps {
+            let name = encounter.map.display_name();
+            if encounter.waves.is_empty() {
+                problems.push(format!("{}: no waves scripted", name));
+            }
+            let points = spawn_points(encounter.map);
+            for (index, wave) in encounter.waves.iter().enumerate() {
+                let at = format!("{} wave {}", name, index + 1);
+                if wave.enemies > 0 && wave.total_weight() == 0 {
+                    problems.push(format!("{}: enemies but no enemy mix", at));
+                }
+                if wave.delay < 0.0 {
+                    problems.push(format!("{}: delay can't be negative", at));
+                }
+                if wave.interval <= 0.0 {
+                    problems.push(format!("{}: interval must be above zero", at));
+                }
+                for &point in wave.spawn_points.iter().filter(|&&point| point >= points) {
+                    problems.push(format!(
+                        "{}: spawn point {} doesn't exist (the map has {})",
+                        at, point, points
+                    ));
+                }
+                let lines = wave.intro.iter().chain(&wave.outro);
+                if lines.clone().any(|line| line.speaker.trim().is_empty()) {
+                    problems.push(format!("{}: dialogue line without a speaker", at));
+                }
+                if lines.clone().any(|line| line.duration <= 0.0) {
+                    problems.push(format!("{}: dialogue line needs a duration", at));
+                }
+            }
+        }
+        problems
+    }
+
+    pub fn waves(&self, map: MapType) -> &[WaveScript] {
+        self.maps
+            .iter()
+            .find(|encounter| encounter.map == map)
+            .map_or(&[], |encounter| &encounter.waves)
+    }
+
+    /// The script for `wave` (counted from 1) on `map`
+    pub fn wave(&self, map: MapType, wave: usize) -> Option<&WaveScript> {
+        self.waves(map).get(wave.checked_sub(1)?)
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("encounters.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_cover_every_map() {
+        let script = EncounterScript::default();
+        assert!(script.validate(|_| 3).is_empty());
+        assert_eq!(script.waves(MapType::Gym).len(), 3);
+        assert_eq!(
+            script.wave(MapType::Cafeteria, 1).and_then(|wave| wave.boss),
+            Some(EncounterBoss::Mees)
+        );
+    }
+
+    #[test]
+    fn broken_waves_are_reported() {
+        let mut script = EncounterScript::default();
+        script.maps.retain(|encounter| encounter.map != MapType::Library);
+        script.maps[0].waves[1] = WaveScript {
+            enemies: 3,
+            spawn_points: vec![0, 5],
+            interval: 0.0,
+            ..WaveScript::default()
+        };
+
+        let problems = script.validate(|_| 3);
+        assert_eq!(
+            problems,
+            vec![
+                "Library: no waves scripted".to_string(),
+                "Classroom wave 2: enemies but no enemy mix".to_string(),
+                "Classroom wave 2: interval must be above zero".to_string(),
+                "Classroom wave 2: spawn point 5 doesn't exist (the map has 3)".to_string(),
+            ]
+        );
+    }
+}
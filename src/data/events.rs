@@ -0,0 +1,326 @@
+use crate::data::{crash, storage, GameConfig, SaveBundle};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Caps on what an events file can hand out, so a bad or hostile one can't
+/// flood the economy (or trip the token ledger's per-change limit)
+const MAX_MULTIPLIER: f32 = 3.0;
+const MAX_BOUNTY: u32 = 500;
+
+/// Arc Token payouts an event can boost
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewardKind {
+    Wave,
+    Route,
+    Boss,
+    /// Tokens dropped by smashed furniture or defeated enemies
+    Pickup,
+    /// Paid out for unlocking an achievement
+    Achievement,
+    /// Paid out for reaching a mastery milestone
+    Mastery,
+    /// Paid out for daily and weekly challenges
+    Challenge,
+    /// Paid out for reaching an account level
+    AccountLevel,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EventBonus {
+    /// Multiplies every Arc Token reward
+    Tokens { multiplier: f32 },
+    /// Extra Arc Tokens for each boss beaten
+    BossBounty { tokens: u32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EventWindow {
+    /// Unix seconds; the end is exclusive
+    Between { starts_at: u64, ends_at: u64 },
+    /// Every Saturday and Sunday, UTC
+    Weekends,
+}
+
+impl EventWindow {
+    /// The current or next occurrence as `(start, end)`
+    fn span(self, now: u64) -> (u64, u64) {
+        match self {
+            EventWindow::Between { starts_at, ends_at } => (starts_at, ends_at),
+            EventWindow::Weekends => {
+                // The epoch was a Thursday; 0 here is Sunday
+                let day = now / DAY;
+                let saturday = match (day + 4) % 7 {
+                    0 => day - 1,
+                    weekday => day + 6 - weekday,
+                };
+                (saturday * DAY, (saturday + 2) * DAY)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BonusEvent {
+    pub title: String,
+    pub bonus: EventBonus,
+    pub window: EventWindow,
+}
+
+/// Where an event stands right now, for the menu's countdowns
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventStatus {
+    /// Running; seconds until it ends
+    Active(u64),
+    /// Seconds until it starts
+    Upcoming(u64),
+    Over,
+}
+
+impl BonusEvent {
+    pub fn status(&self, now: u64) -> EventStatus {
+        let (start, end) = self.window.span(now);
+        if now >= end {
+            EventStatus::Over
+        } else if now >= start {
+            EventStatus::Active(end - now)
+        } else {
+            EventStatus::Upcoming(start - now)
+        }
+    }
+
+    pub fn is_active(&self, now: u64) -> bool {
+        matches!(self.status(now), EventStatus::Active(_))
+    }
+
+    /// One-line summary of the bonus
+    pub fn describe(&self) -> String {
+        match self.bonus {
+            EventBonus::Tokens { multiplier } => {
+                format!("x{:.1} Arc Tokens", multiplier.clamp(1.0, MAX_MULTIPLIER))
+            }
+            EventBonus::BossBounty { tokens } => {
+                format!("+{} Arc Tokens per boss", tokens.min(MAX_BOUNTY))
+            }
+        }
+    }
+}
+
+/// Limited-time bonus events. Fetched from `GameConfig::events_url` at boot
+/// and cached on disk; offline, the cache from the last fetch is used.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EventSchedule {
+    pub events: Vec<BonusEvent>,
+}
+
+impl Default for EventSchedule {
+    /// Until a schedule has been fetched: double tokens on weekends
+    fn default() -> Self {
+        Self {
+            events: vec![BonusEvent {
+                title: "Weekend Double Tokens".to_string(),
+                bonus: EventBonus::Tokens { multiplier: 2.0 },
+                window: EventWindow::Weekends,
+            }],
+        }
+    }
+}
+
+impl EventSchedule {
+    /// Boost a reward by every event running at `now`. Returns the new amount
+    /// and the titles of the events that changed it.
+    pub fn apply(&self, amount: u32, kind: RewardKind, now: u64) -> (u32, Vec<String>) {
+        if amount == 0 {
+            return (0, Vec::new());
+        }
+
+        let mut bounty = 0;
+        let mut multiplier = 1.0;
+        let mut applied = Vec::new();
+        for event in self.events.iter().filter(|event| event.is_active(now)) {
+            match event.bonus {
+                EventBonus::Tokens { multiplier: m } if m > 1.0 => multiplier *= m,
+                EventBonus::BossBounty { tokens } if kind == RewardKind::Boss && tokens > 0 => {
+                    bounty += tokens;
+                }
+                _ => continue,
+            }
+            applied.push(event.title.clone());
+        }
+
+        let boosted = (amount + bounty.min(MAX_BOUNTY)) as f32 * multiplier.min(MAX_MULTIPLIER);
+        (boosted.round() as u32, applied)
+    }
+
+    /// Events running now or starting within `horizon` seconds, soonest
+    /// ending first
+    pub fn upcoming(&self, now: u64, horizon: u64) -> Vec<(&BonusEvent, EventStatus)> {
+        let mut events: Vec<_> = self
+            .events
+            .iter()
+            .map(|event| (event, event.status(now)))
+            .filter(|(_, status)| match status {
+                EventStatus::Active(_) => true,
+                EventStatus::Upcoming(starts_in) => *starts_in <= horizon,
+                EventStatus::Over => false,
+            })
+            .collect();
+        events.sort_by_key(|(_, status)| match status {
+            EventStatus::Active(left) => (0, *left),
+            EventStatus::Upcoming(starts_in) => (1, *starts_in),
+            EventStatus::Over => (2, 0),
+        });
+        events
+    }
+
+    fn load_cached() -> Option<Self> {
+        let path = cache_file_path();
+        if !path.exists() {
+            return None;
+        }
+        storage::read_json(&path)
+            .map_err(|e| crash::log(format!("Ignoring cached events: {}", e)))
+            .ok()
+    }
+
+    fn save_cache(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize events: {}", e))?;
+        storage::write_atomic(&cache_file_path(), &json)
+    }
+}
+
+// Filled from the cache on first use, then replaced once the fetch lands
+static SCHEDULE: Mutex<Option<EventSchedule>> = Mutex::new(None);
+
+/// The bonus events in effect
+pub fn schedule() -> EventSchedule {
+    let Ok(mut schedule) = SCHEDULE.lock() else {
+        return EventSchedule::default();
+    };
+    schedule
+        .get_or_insert_with(|| EventSchedule::load_cached().unwrap_or_default())
+        .clone()
+}
+
+/// Boost a reward by the events running right now
+pub fn apply_bonuses(amount: u32, kind: RewardKind) -> (u32, Vec<String>) {
+    schedule().apply(amount, kind, now())
+}
+
+/// Fetch the latest schedule without holding up the boot screen. A failed
+/// fetch leaves the cached one in place.
+pub fn refresh_in_background() {
+    let url = GameConfig::load().events_url;
+    if url.is_empty() {
+        return;
+    }
+
+    std::thread::spawn(move || match fetch(&url) {
+        Ok(fetched) => {
+            if let Err(e) = fetched.save_cache() {
+                crash::log(format!("Failed to cache events: {}", e));
+            }
+            if let Ok(mut schedule) = SCHEDULE.lock() {
+                *schedule = Some(fetched);
+            }
+        }
+        Err(e) => crash::log(format!("Failed to fetch events, using the cached ones: {}", e)),
+    });
+}
+
+fn fetch(url: &str) -> Result<EventSchedule, String> {
+    let response = minreq::get(url)
+        .with_header("User-Agent", "bas-veeg-arc-events")
+        .with_timeout(10)
+        .send()
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if response.status_code != 200 {
+        return Err(format!("Events server returned status {}", response.status_code));
+    }
+
+    serde_json::from_str(response.as_str().map_err(|e| e.to_string())?)
+        .map_err(|e| format!("Failed to parse events: {}", e))
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Short countdown like `2d 04h` or `13m`
+pub fn format_countdown(seconds: u64) -> String {
+    let days = seconds / DAY;
+    let hours = seconds % DAY / 3600;
+    let minutes = seconds % 3600 / 60;
+    if days > 0 {
+        format!("{}d {:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m", minutes.max(1))
+    }
+}
+
+fn cache_file_path() -> PathBuf {
+    SaveBundle::data_directory().join("events").join("events.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-17, a Saturday, at noon UTC
+    const SATURDAY_NOON: u64 = 1_792_238_400;
+
+    #[test]
+    fn weekends_run_saturday_through_sunday() {
+        let weekend = EventWindow::Weekends;
+        let saturday = SATURDAY_NOON - 12 * 3600;
+        assert_eq!(weekend.span(SATURDAY_NOON), (saturday, saturday + 2 * DAY));
+        assert_eq!(weekend.span(SATURDAY_NOON + DAY), (saturday, saturday + 2 * DAY));
+
+        // Monday looks ahead to the next weekend
+        let monday = SATURDAY_NOON + 2 * DAY;
+        assert_eq!(weekend.span(monday).0, saturday + 7 * DAY);
+    }
+
+    #[test]
+    fn bounties_only_pay_for_bosses_and_bonuses_are_capped() {
+        let schedule = EventSchedule {
+            events: vec![
+                BonusEvent {
+                    title: "Bounty".to_string(),
+                    bonus: EventBonus::BossBounty { tokens: 100 },
+                    window: EventWindow::Weekends,
+                },
+                BonusEvent {
+                    title: "Greedy".to_string(),
+                    bonus: EventBonus::Tokens { multiplier: 50.0 },
+                    window: EventWindow::Between {
+                        starts_at: 0,
+                        ends_at: SATURDAY_NOON + 1,
+                    },
+                },
+            ],
+        };
+
+        let (wave, applied) = schedule.apply(40, RewardKind::Wave, SATURDAY_NOON);
+        assert_eq!(wave, 120);
+        assert_eq!(applied, vec!["Greedy".to_string()]);
+
+        let (boss, applied) = schedule.apply(100, RewardKind::Boss, SATURDAY_NOON);
+        assert_eq!(boss, 600);
+        assert_eq!(applied.len(), 2);
+
+        // Both over by Monday
+        let monday = SATURDAY_NOON + 2 * DAY;
+        assert_eq!(schedule.apply(40, RewardKind::Boss, monday).0, 40);
+    }
+}
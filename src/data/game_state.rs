@@ -1,9 +1,141 @@
-use crate::data::CharacterId;
+use crate::audio::mixer::{BusVolumes, SoundRequest};
+use crate::app::TimeScaleRequest;
+use crate::audio::MusicCue;
+use crate::combat::combo_system::StyleRank;
+use crate::combat::versus_ai::{CpuDifficulty, CpuSettings};
+use crate::coop::input_handler::KeyRebind;
+use crate::data::config::{DEFAULT_TICK_RATE, TICK_RATES};
+use crate::data::coop_scaling::PlayerCountScaling;
+use crate::data::cutscenes::CutsceneId;
+use crate::data::dialogue::StoryEnding;
+use crate::data::poses::PoseChoice;
+use crate::data::replay::Replay;
+use crate::data::save::PROFILE_SLOTS;
+use crate::data::{CharacterId, CHARACTERS};
+use crate::ecs::AllyPreset;
+use crate::progression::practice::PracticeStart;
+use crate::progression::roster::TrialRun;
+use crate::states::StateType;
+use crate::render::map_system::MapType;
+use crate::ui::subtitles::SubtitleStyle;
+use crate::ui::UiScaleMode;
+use macroquad::prelude::KeyCode;
 use std::sync::Mutex;
 
+/// Setup of the running versus session; rematches and re-selects reuse it
+#[derive(Clone, Copy, Debug)]
+pub struct MatchSettings {
+    pub characters: [CharacterId; 2],
+    pub stage: MapType,
+    /// Set when P2 is played by the CPU
+    pub cpu: Option<CpuSettings>,
+}
+
+impl MatchSettings {
+    /// Until there's a second pick, P2 takes the next character in the roster
+    pub fn new(player1: CharacterId) -> Self {
+        let player2 = CHARACTERS[(player1 as usize + 1) % CHARACTERS.len()].id;
+        Self {
+            characters: [player1, player2],
+            stage: MapType::Classroom,
+            cpu: None,
+        }
+    }
+}
+
+/// Co-op seats the lobby left to bots, after the humans'
+#[derive(Clone, Debug)]
+pub struct CoopBots {
+    pub characters: Vec<CharacterId>,
+    pub difficulty: CpuDifficulty,
+}
+
+/// How the last versus match ended, handed to the results screen
+#[derive(Clone)]
+pub struct MatchResult {
+    /// None when the match was drawn
+    pub winner: Option<String>,
+    /// Rounds each side took
+    pub rounds: [u32; 2],
+    pub duration: f32,
+    pub replay: Option<Replay>,
+}
+
+/// How a cleared story run ended, handed to the results screen
+#[derive(Clone)]
+pub struct StoryResult {
+    pub ending: StoryEnding,
+    pub epilogue: Vec<&'static str>,
+    pub waves_completed: usize,
+    pub enemies_defeated: u32,
+    pub time_survived: u32,
+}
+
+/// How a gameplay run went, for the results screen after it
+#[derive(Clone, Debug)]
+pub struct RunReport {
+    pub cleared: bool,
+    pub waves_cleared: u32,
+    pub max_combo: u32,
+    pub best_rank: StyleRank,
+    /// No damage taken all run
+    pub flawless: bool,
+    pub tokens_earned: u32,
+    pub mastery_xp: f32,
+    pub account_xp: f32,
+    /// Achievements, titles and banners picked up along the way
+    pub unlocks: Vec<String>,
+    /// Where "retry" starts the same kind of run over; `None` for runs that
+    /// can't just be started again, like co-op or practice
+    pub retry: Option<StateType>,
+    /// Set when the run was a trial of a character the profile hasn't unlocked
+    pub trial: Option<CharacterId>,
+}
+
 // Global game state to pass data between states
 static SELECTED_CHARACTER: Mutex<Option<CharacterId>> = Mutex::new(None);
+static TAG_PARTNER: Mutex<Option<CharacterId>> = Mutex::new(None);
 static COOP_PLAYERS: Mutex<Option<Vec<CharacterId>>> = Mutex::new(None);
+static ALLY_PRESET: Mutex<AllyPreset> = Mutex::new(AllyPreset::Balanced);
+static COOP_LIVES: Mutex<u32> = Mutex::new(3);
+static COOP_SCALING: Mutex<Option<PlayerCountScaling>> = Mutex::new(None);
+static COOP_BOTS: Mutex<Option<CoopBots>> = Mutex::new(None);
+static POSE_CHOICE: Mutex<PoseChoice> = Mutex::new(PoseChoice {
+    intro: 0,
+    victory: 0,
+});
+static MATCH_SETTINGS: Mutex<Option<MatchSettings>> = Mutex::new(None);
+static MATCH_RESULT: Mutex<Option<MatchResult>> = Mutex::new(None);
+static STORY_RESULT: Mutex<Option<StoryResult>> = Mutex::new(None);
+static RUN_REPORT: Mutex<Option<RunReport>> = Mutex::new(None);
+static PRACTICE_START: Mutex<Option<PracticeStart>> = Mutex::new(None);
+static TRIAL_RUN: Mutex<Option<TrialRun>> = Mutex::new(None);
+static CUTSCENE: Mutex<Option<(CutsceneId, Option<StateType>)>> = Mutex::new(None);
+static POST_PROCESSING: Mutex<bool> = Mutex::new(true);
+static TICK_RATE: Mutex<u32> = Mutex::new(DEFAULT_TICK_RATE);
+static UI_SCALE_MODE: Mutex<UiScaleMode> = Mutex::new(UiScaleMode::Fit);
+static DEBUG_CONTROLS: Mutex<bool> = Mutex::new(false);
+static DEV_CONSOLE: Mutex<bool> = Mutex::new(false);
+static DEBUG_HITBOXES: Mutex<bool> = Mutex::new(false);
+static HARDCORE_STAMINA: Mutex<bool> = Mutex::new(false);
+static BROADCAST_OVERLAY: Mutex<bool> = Mutex::new(false);
+static SUBTITLE_STYLE: Mutex<SubtitleStyle> = Mutex::new(SubtitleStyle::DEFAULT);
+static ACTIVE_PROFILE: Mutex<usize> = Mutex::new(0);
+static WINDOW_AWAY: Mutex<bool> = Mutex::new(false);
+static AWAY_MUTED: Mutex<bool> = Mutex::new(false);
+static OVER_RUN: Mutex<bool> = Mutex::new(false);
+static MUSIC_CUES: Mutex<Vec<MusicCue>> = Mutex::new(Vec::new());
+static SOUNDS: Mutex<Vec<SoundRequest>> = Mutex::new(Vec::new());
+static MUSIC_DUCK: Mutex<bool> = Mutex::new(false);
+static VOLUMES: Mutex<BusVolumes> = Mutex::new(BusVolumes::DEFAULT);
+static TIME_SCALE_REQUESTS: Mutex<Vec<TimeScaleRequest>> = Mutex::new(Vec::new());
+static GAME_FEEL: Mutex<f32> = Mutex::new(1.0);
+static SCREEN_SHAKE: Mutex<f32> = Mutex::new(1.0);
+static SCREEN_FLASH: Mutex<f32> = Mutex::new(1.0);
+static COMBO_ASSIST_KEY: Mutex<Option<KeyCode>> = Mutex::new(None);
+static GAME_SPEED: Mutex<f32> = Mutex::new(1.0);
+static KEY_REBINDS: Mutex<Vec<KeyRebind>> = Mutex::new(Vec::new());
+static KEY_REBINDS_REVISION: Mutex<u32> = Mutex::new(0);
 
 pub fn set_selected_character(character: CharacterId) {
     if let Ok(mut selected) = SELECTED_CHARACTER.lock() {
@@ -19,6 +151,17 @@ pub fn get_selected_character() -> CharacterId {
     }
 }
 
+/// Second character taken into a single-player run for assists and tags
+pub fn set_tag_partner(partner: Option<CharacterId>) {
+    if let Ok(mut current) = TAG_PARTNER.lock() {
+        *current = partner;
+    }
+}
+
+pub fn get_tag_partner() -> Option<CharacterId> {
+    TAG_PARTNER.lock().ok().and_then(|partner| *partner)
+}
+
 pub fn set_coop_players(players: Vec<CharacterId>) {
     if let Ok(mut coop) = COOP_PLAYERS.lock() {
         *coop = Some(players);
@@ -38,3 +181,550 @@ pub fn clear_coop_players() {
         *coop = None;
     }
 }
+
+pub fn set_ally_preset(preset: AllyPreset) {
+    if let Ok(mut current) = ALLY_PRESET.lock() {
+        *current = preset;
+    }
+}
+
+pub fn get_ally_preset() -> AllyPreset {
+    if let Ok(current) = ALLY_PRESET.lock() {
+        *current
+    } else {
+        AllyPreset::Balanced
+    }
+}
+
+pub fn set_coop_lives(lives: u32) {
+    if let Ok(mut current) = COOP_LIVES.lock() {
+        *current = lives;
+    }
+}
+
+pub fn get_coop_lives() -> u32 {
+    if let Ok(current) = COOP_LIVES.lock() {
+        *current
+    } else {
+        3
+    }
+}
+
+/// Difficulty modifiers the lobby previewed for the run it starts
+pub fn set_coop_scaling(scaling: PlayerCountScaling) {
+    if let Ok(mut current) = COOP_SCALING.lock() {
+        *current = Some(scaling);
+    }
+}
+
+pub fn get_coop_scaling() -> Option<PlayerCountScaling> {
+    if let Ok(current) = COOP_SCALING.lock() {
+        *current
+    } else {
+        None
+    }
+}
+
+pub fn set_coop_bots(bots: CoopBots) {
+    if let Ok(mut current) = COOP_BOTS.lock() {
+        *current = Some(bots);
+    }
+}
+
+/// The bots for the run being started, once
+pub fn take_coop_bots() -> Option<CoopBots> {
+    if let Ok(mut current) = COOP_BOTS.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+pub fn set_pose_choice(choice: PoseChoice) {
+    if let Ok(mut current) = POSE_CHOICE.lock() {
+        *current = choice;
+    }
+}
+
+pub fn get_pose_choice() -> PoseChoice {
+    if let Ok(current) = POSE_CHOICE.lock() {
+        *current
+    } else {
+        PoseChoice::default()
+    }
+}
+
+pub fn set_match_settings(settings: MatchSettings) {
+    if let Ok(mut current) = MATCH_SETTINGS.lock() {
+        *current = Some(settings);
+    }
+}
+
+/// Settings of the running versus session, if there is one
+pub fn get_match_settings() -> Option<MatchSettings> {
+    if let Ok(current) = MATCH_SETTINGS.lock() {
+        *current
+    } else {
+        None
+    }
+}
+
+pub fn clear_match_settings() {
+    if let Ok(mut current) = MATCH_SETTINGS.lock() {
+        *current = None;
+    }
+}
+
+pub fn set_match_result(result: MatchResult) {
+    if let Ok(mut current) = MATCH_RESULT.lock() {
+        *current = Some(result);
+    }
+}
+
+pub fn take_match_result() -> Option<MatchResult> {
+    if let Ok(mut current) = MATCH_RESULT.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+pub fn set_story_result(result: StoryResult) {
+    if let Ok(mut current) = STORY_RESULT.lock() {
+        *current = Some(result);
+    }
+}
+
+pub fn take_story_result() -> Option<StoryResult> {
+    if let Ok(mut current) = STORY_RESULT.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+pub fn set_run_report(report: RunReport) {
+    if let Ok(mut current) = RUN_REPORT.lock() {
+        *current = Some(report);
+    }
+}
+
+pub fn take_run_report() -> Option<RunReport> {
+    if let Ok(mut current) = RUN_REPORT.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+/// Makes the next run a practice run
+pub fn set_practice_start(start: PracticeStart) {
+    if let Ok(mut current) = PRACTICE_START.lock() {
+        *current = Some(start);
+    }
+}
+
+pub fn take_practice_start() -> Option<PracticeStart> {
+    if let Ok(mut current) = PRACTICE_START.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+/// Makes the next run a trial of a locked character; `None` for a normal run
+pub fn set_trial_run(trial: Option<TrialRun>) {
+    if let Ok(mut current) = TRIAL_RUN.lock() {
+        *current = trial;
+    }
+}
+
+pub fn take_trial_run() -> Option<TrialRun> {
+    if let Ok(mut current) = TRIAL_RUN.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+/// Scene for the cutscene state to play, and where it goes afterwards;
+/// `None` goes back to the state underneath
+pub fn set_cutscene(scene: CutsceneId, then: Option<StateType>) {
+    if let Ok(mut current) = CUTSCENE.lock() {
+        *current = Some((scene, then));
+    }
+}
+
+pub fn take_cutscene() -> Option<(CutsceneId, Option<StateType>)> {
+    if let Ok(mut current) = CUTSCENE.lock() {
+        current.take()
+    } else {
+        None
+    }
+}
+
+/// Off on low-end machines: skips the render-target post-processing pass
+pub fn set_post_processing(enabled: bool) {
+    if let Ok(mut current) = POST_PROCESSING.lock() {
+        *current = enabled;
+    }
+}
+
+pub fn get_post_processing() -> bool {
+    if let Ok(current) = POST_PROCESSING.lock() {
+        *current
+    } else {
+        true
+    }
+}
+
+/// Fixed simulation ticks per second; snapped to one of `TICK_RATES`
+pub fn set_tick_rate(rate: u32) {
+    let rate = if TICK_RATES.contains(&rate) { rate } else { DEFAULT_TICK_RATE };
+    if let Ok(mut current) = TICK_RATE.lock() {
+        *current = rate;
+    }
+}
+
+pub fn get_tick_rate() -> u32 {
+    if let Ok(current) = TICK_RATE.lock() {
+        *current
+    } else {
+        DEFAULT_TICK_RATE
+    }
+}
+
+pub fn set_ui_scale_mode(mode: UiScaleMode) {
+    if let Ok(mut current) = UI_SCALE_MODE.lock() {
+        *current = mode;
+    }
+}
+
+pub fn get_ui_scale_mode() -> UiScaleMode {
+    if let Ok(current) = UI_SCALE_MODE.lock() {
+        *current
+    } else {
+        UiScaleMode::Fit
+    }
+}
+
+/// Pause, frame-step and time-scale keys (F5-F8)
+pub fn set_debug_controls(enabled: bool) {
+    if let Ok(mut current) = DEBUG_CONTROLS.lock() {
+        *current = enabled;
+    }
+}
+
+pub fn get_debug_controls() -> bool {
+    if let Ok(current) = DEBUG_CONTROLS.lock() {
+        *current
+    } else {
+        false
+    }
+}
+
+/// The developer console on `~` in release builds; debug builds always have it
+pub fn set_dev_console(enabled: bool) {
+    if let Ok(mut current) = DEV_CONSOLE.lock() {
+        *current = enabled;
+    }
+}
+
+pub fn get_dev_console() -> bool {
+    cfg!(debug_assertions) || DEV_CONSOLE.lock().is_ok_and(|current| *current)
+}
+
+/// Hitbox and hurtbox outlines over the fight, from the console
+pub fn set_debug_hitboxes(enabled: bool) {
+    if let Ok(mut current) = DEBUG_HITBOXES.lock() {
+        *current = enabled;
+    }
+}
+
+pub fn get_debug_hitboxes() -> bool {
+    DEBUG_HITBOXES.lock().is_ok_and(|current| *current)
+}
+
+/// Hardcore stamina rules for runs
+pub fn set_hardcore_stamina(enabled: bool) {
+    if let Ok(mut current) = HARDCORE_STAMINA.lock() {
+        *current = enabled;
+    }
+}
+
+pub fn get_hardcore_stamina() -> bool {
+    if let Ok(current) = HARDCORE_STAMINA.lock() {
+        *current
+    } else {
+        false
+    }
+}
+
+/// Streaming overlay over versus matches
+pub fn set_broadcast_overlay(enabled: bool) {
+    if let Ok(mut current) = BROADCAST_OVERLAY.lock() {
+        *current = enabled;
+    }
+}
+
+pub fn get_broadcast_overlay() -> bool {
+    if let Ok(current) = BROADCAST_OVERLAY.lock() {
+        *current
+    } else {
+        false
+    }
+}
+
+/// Size, backing and colours spoken lines are drawn with
+pub fn set_subtitle_style(style: SubtitleStyle) {
+    if let Ok(mut current) = SUBTITLE_STYLE.lock() {
+        *current = style;
+    }
+}
+
+pub fn get_subtitle_style() -> SubtitleStyle {
+    if let Ok(current) = SUBTITLE_STYLE.lock() {
+        *current
+    } else {
+        SubtitleStyle::DEFAULT
+    }
+}
+
+/// Save slot whose progress, shop, mastery and audit log are in play; one
+/// past the last slot falls back to the first
+pub fn set_active_profile(slot: usize) {
+    let slot = if slot < PROFILE_SLOTS { slot } else { 0 };
+    if let Ok(mut current) = ACTIVE_PROFILE.lock() {
+        *current = slot;
+    }
+}
+
+pub fn get_active_profile() -> usize {
+    if let Ok(current) = ACTIVE_PROFILE.lock() {
+        *current
+    } else {
+        0
+    }
+}
+
+/// Frames stalled long enough that the window was probably minimized or
+/// covered; taken by gameplay to pause itself
+pub fn set_window_away() {
+    if let Ok(mut current) = WINDOW_AWAY.lock() {
+        *current = true;
+    }
+}
+
+pub fn take_window_away() -> bool {
+    if let Ok(mut current) = WINDOW_AWAY.lock() {
+        std::mem::take(&mut *current)
+    } else {
+        false
+    }
+}
+
+/// The next settings or controls screen opens from the pause menu, over a
+/// run, and pops back to it rather than going to the menu
+pub fn set_over_run() {
+    if let Ok(mut current) = OVER_RUN.lock() {
+        *current = true;
+    }
+}
+
+pub fn take_over_run() -> bool {
+    if let Ok(mut current) = OVER_RUN.lock() {
+        std::mem::take(&mut *current)
+    } else {
+        false
+    }
+}
+
+/// Audio muted while gameplay is auto-paused
+pub fn set_away_muted(muted: bool) {
+    if let Ok(mut current) = AWAY_MUTED.lock() {
+        *current = muted;
+    }
+}
+
+pub fn get_away_muted() -> bool {
+    if let Ok(current) = AWAY_MUTED.lock() {
+        *current
+    } else {
+        false
+    }
+}
+
+/// Ask the app to play a music cue on its next frame
+pub fn queue_music_cue(cue: MusicCue) {
+    if let Ok(mut cues) = MUSIC_CUES.lock() {
+        cues.push(cue);
+    }
+}
+
+pub fn take_music_cues() -> Vec<MusicCue> {
+    if let Ok(mut cues) = MUSIC_CUES.lock() {
+        std::mem::take(&mut *cues)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Ask the app to play a sound on its next frame
+pub fn queue_sound(request: SoundRequest) {
+    if let Ok(mut sounds) = SOUNDS.lock() {
+        sounds.push(request);
+    }
+}
+
+pub fn take_sounds() -> Vec<SoundRequest> {
+    if let Ok(mut sounds) = SOUNDS.lock() {
+        std::mem::take(&mut *sounds)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Keep the music dipped this frame; states call it every frame a line is
+/// up or a super is playing out
+pub fn duck_music() {
+    if let Ok(mut duck) = MUSIC_DUCK.lock() {
+        *duck = true;
+    }
+}
+
+pub fn take_music_duck() -> bool {
+    if let Ok(mut duck) = MUSIC_DUCK.lock() {
+        std::mem::take(&mut *duck)
+    } else {
+        false
+    }
+}
+
+/// Master and bus volumes from the settings
+pub fn set_volumes(volumes: BusVolumes) {
+    if let Ok(mut current) = VOLUMES.lock() {
+        *current = volumes;
+    }
+}
+
+pub fn get_volumes() -> BusVolumes {
+    if let Ok(current) = VOLUMES.lock() {
+        *current
+    } else {
+        BusVolumes::DEFAULT
+    }
+}
+
+/// Hitstop or slow motion for the app to run the simulation at
+pub fn queue_time_scale(request: TimeScaleRequest) {
+    if let Ok(mut requests) = TIME_SCALE_REQUESTS.lock() {
+        requests.push(request);
+    }
+}
+
+pub fn take_time_scale_requests() -> Vec<TimeScaleRequest> {
+    if let Ok(mut requests) = TIME_SCALE_REQUESTS.lock() {
+        std::mem::take(&mut *requests)
+    } else {
+        Vec::new()
+    }
+}
+
+/// How strong hitstop and slow motion are, 0 (off) to 1
+pub fn set_game_feel(intensity: f32) {
+    if let Ok(mut current) = GAME_FEEL.lock() {
+        *current = intensity;
+    }
+}
+
+pub fn get_game_feel() -> f32 {
+    if let Ok(current) = GAME_FEEL.lock() {
+        *current
+    } else {
+        1.0
+    }
+}
+
+/// How hard the screen shakes, 0 (still) to 1
+pub fn set_screen_shake(intensity: f32) {
+    if let Ok(mut current) = SCREEN_SHAKE.lock() {
+        *current = intensity;
+    }
+}
+
+pub fn get_screen_shake() -> f32 {
+    if let Ok(current) = SCREEN_SHAKE.lock() {
+        *current
+    } else {
+        1.0
+    }
+}
+
+/// How bright full-screen flashes get, 0 (none) to 1
+pub fn set_screen_flash(strength: f32) {
+    if let Ok(mut current) = SCREEN_FLASH.lock() {
+        *current = strength;
+    }
+}
+
+pub fn get_screen_flash() -> f32 {
+    if let Ok(current) = SCREEN_FLASH.lock() {
+        *current
+    } else {
+        1.0
+    }
+}
+
+/// Key that swings through the one-button combo, or `None` with it off
+pub fn set_combo_assist_key(key: Option<KeyCode>) {
+    if let Ok(mut current) = COMBO_ASSIST_KEY.lock() {
+        *current = key;
+    }
+}
+
+pub fn get_combo_assist_key() -> Option<KeyCode> {
+    if let Ok(current) = COMBO_ASSIST_KEY.lock() {
+        *current
+    } else {
+        None
+    }
+}
+
+/// How fast single-player runs go, 0.8 to 1
+pub fn set_game_speed(speed: f32) {
+    if let Ok(mut current) = GAME_SPEED.lock() {
+        *current = speed;
+    }
+}
+
+pub fn get_game_speed() -> f32 {
+    if let Ok(current) = GAME_SPEED.lock() {
+        *current
+    } else {
+        1.0
+    }
+}
+
+/// Keys moved off their defaults; bindings already in use pick them up
+/// through the revision
+pub fn set_key_rebinds(rebinds: Vec<KeyRebind>) {
+    if let Ok(mut current) = KEY_REBINDS.lock() {
+        *current = rebinds;
+    }
+    if let Ok(mut revision) = KEY_REBINDS_REVISION.lock() {
+        *revision += 1;
+    }
+}
+
+/// Goes up each time the rebinds are set
+pub fn get_key_rebinds_revision() -> u32 {
+    KEY_REBINDS_REVISION.lock().map_or(0, |revision| *revision)
+}
+
+pub fn get_key_rebinds() -> Vec<KeyRebind> {
+    if let Ok(current) = KEY_REBINDS.lock() {
+        current.clone()
+    } else {
+        Vec::new()
+    }
+}
@@ -0,0 +1,251 @@
+use crate::data::crash;
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{CharacterId, SaveBundle};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Oldest entries are dropped once the log grows past this
+const MAX_ENTRIES: usize = 1000;
+
+/// Source of tokens handed out from the developer console rather than earned
+pub const CONSOLE_GRANT: &str = "Console grant";
+
+/// A balance whose changes go through the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Account {
+    Tokens,
+    MasteryXp(CharacterId),
+}
+
+impl Account {
+    /// Largest single change that can be legitimate. Rewards, upgrades and
+    /// kill XP are all in the hundreds; far past that is a bug.
+    fn max_change(self) -> f64 {
+        match self {
+            Account::Tokens => 5_000.0,
+            Account::MasteryXp(_) => 10_000.0,
+        }
+    }
+
+    fn max_balance(self) -> f64 {
+        match self {
+            Account::Tokens => 1_000_000.0,
+            Account::MasteryXp(_) => 1_000_000.0,
+        }
+    }
+}
+
+/// One attempted change to a balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Unix seconds
+    pub timestamp: u64,
+    pub account: Account,
+    /// What made the change, e.g. "Wave 3 cleared" or "AttackBoost upgrade"
+    pub source: String,
+    pub amount: f64,
+    /// Balance afterwards; unchanged if the change was rejected
+    pub balance: f64,
+    /// Why the change was refused, if it was
+    #[serde(default)]
+    pub rejected: Option<String>,
+}
+
+/// The log as it's written to disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LedgerFile {
+    entries: Vec<LedgerEntry>,
+}
+
+impl Versioned for LedgerFile {
+    const MIGRATIONS: &'static [Migration] = &[wrap_entries];
+}
+
+/// Version 1 keeps the entries under a field; the log started as a bare list
+fn wrap_entries(ledger: &mut Value) -> Result<(), String> {
+    if ledger.is_array() {
+        let entries = ledger.take();
+        *ledger = serde_json::json!({ "entries": entries });
+    }
+    Ok(())
+}
+
+/// The log for one profile's file
+struct LoadedLedger {
+    path: PathBuf,
+    file: LedgerFile,
+    /// Why the file didn't load, if it didn't; it's kept as it is
+    load_error: Option<String>,
+}
+
+// Loaded on first use, written back by `save`
+static LEDGER: Mutex<Option<LoadedLedger>> = Mutex::new(None);
+
+/// Check a change of `amount` to `balance` against the account's limits and
+/// log it either way. Returns the new balance, or why the change was refused.
+pub fn audit(account: Account, source: &str, amount: f64, balance: f64) -> Result<f64, String> {
+    let new_balance = balance + amount;
+    let rejected = if !amount.is_finite() {
+        Some(format!("{} is not a valid amount", amount))
+    } else if amount.abs() > account.max_change() {
+        Some(format!("{} is more than the {} allowed at once", amount, account.max_change()))
+    } else if new_balance < 0.0 {
+        Some(format!("balance would drop to {}", new_balance))
+    } else if new_balance > account.max_balance() {
+        Some(format!("balance would pass the {} cap", account.max_balance()))
+    } else {
+        None
+    };
+
+    if let Some(reason) = &rejected {
+        crash::log(format!("Rejected {:?} change from {}: {}", account, source, reason));
+    }
+    record(LedgerEntry {
+        timestamp: now(),
+        account,
+        source: source.to_string(),
+        amount,
+        balance: if rejected.is_some() { balance } else { new_balance },
+        rejected: rejected.clone(),
+    });
+
+    match rejected {
+        Some(reason) => Err(reason),
+        None => Ok(new_balance),
+    }
+}
+
+/// Log a jump from `from` to `to` that happened outside `audit`: the first
+/// time an existing save is seen, a hand-edited file, or a rollback
+pub fn reconcile(account: Account, source: &str, from: f64, to: f64) {
+    record(LedgerEntry {
+        timestamp: now(),
+        account,
+        source: source.to_string(),
+        amount: to - from,
+        balance: to,
+        rejected: None,
+    });
+}
+
+/// Balance after the last change that went through, if there's been one
+pub fn audited_balance(account: Account) -> Option<f64> {
+    with_ledger(|entries| {
+        entries
+            .iter()
+            .rev()
+            .find(|entry| entry.account == account && entry.rejected.is_none())
+            .map(|entry| entry.balance)
+    })
+    .flatten()
+}
+
+/// Write the log to disk. A log that didn't load is never written over, so
+/// its history can still be recovered.
+pub fn save() -> Result<(), String> {
+    let mut ledger = LEDGER.lock().map_err(|_| "Audit log is poisoned".to_string())?;
+    let loaded = load(&mut ledger);
+    migrate::check_writable(loaded.load_error.as_deref())?;
+    migrate::write_versioned(&loaded.path, &loaded.file)
+        .map_err(|e| format!("Failed to write audit log: {}", e))
+}
+
+/// Forget the in-memory log so the next use reads it from disk again, e.g.
+/// after an imported bundle replaced it or another profile was picked
+pub fn reload() {
+    if let Ok(mut ledger) = LEDGER.lock() {
+        *ledger = None;
+    }
+}
+
+fn record(entry: LedgerEntry) {
+    with_ledger(|entries| {
+        entries.push(entry);
+        let excess = entries.len().saturating_sub(MAX_ENTRIES);
+        entries.drain(..excess);
+    });
+}
+
+fn with_ledger<R>(f: impl FnOnce(&mut Vec<LedgerEntry>) -> R) -> Option<R> {
+    let mut ledger = LEDGER.lock().ok()?;
+    Some(f(&mut load(&mut ledger).file.entries))
+}
+
+/// The active profile's log, read from disk unless it already was
+fn load(ledger: &mut Option<LoadedLedger>) -> &mut LoadedLedger {
+    let path = ledger_file_path(crate::data::get_active_profile());
+    // A thread playing on its own data root has its own log
+    if ledger.as_ref().is_some_and(|loaded| loaded.path == path) {
+        return ledger.as_mut().unwrap();
+    }
+    let (file, load_error) = match migrate::read_if_exists::<LedgerFile>(&path) {
+        Ok(file) => (file.unwrap_or_default(), None),
+        Err(e) => {
+            crash::log(e.clone());
+            (LedgerFile::default(), Some(e))
+        }
+    };
+    ledger.insert(LoadedLedger { path, file, load_error })
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Each profile audits its own balances
+pub fn ledger_file_path(profile: usize) -> PathBuf {
+    SaveBundle::data_directory().join("audit").join(profile_file_name("ledger", profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Give this test its own data root with `contents` as the active log
+    fn scratch_ledger(name: &str, contents: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("bas-veeg-arc-test-ledger-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        SaveBundle::set_data_root(Some(root));
+        let path = ledger_file_path(crate::data::get_active_profile());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn a_log_from_before_versions_is_read_and_kept_going() {
+        let entry = r#"{"timestamp": 1, "account": "Tokens", "source": "Wave 1 cleared",
+            "amount": 40.0, "balance": 40.0}"#;
+        let path = scratch_ledger("bare-list", &format!("[{}]", entry));
+
+        assert_eq!(audited_balance(Account::Tokens), Some(40.0));
+        assert_eq!(audit(Account::Tokens, "Wave 2 cleared", 50.0, 40.0), Ok(90.0));
+        assert!(save().is_ok());
+        let file: LedgerFile = migrate::read_versioned(&path).unwrap();
+        SaveBundle::set_data_root(None);
+
+        assert_eq!(file.entries.len(), 2);
+        assert_eq!(file.entries[1].balance, 90.0);
+    }
+
+    #[test]
+    fn a_log_that_does_not_load_is_never_saved_over() {
+        let newer = format!(r#"{{"{}": 99, "entries": []}}"#, migrate::VERSION_KEY);
+        for (name, contents) in [("corrupt", "[{ not json"), ("newer", newer.as_str())] {
+            let path = scratch_ledger(name, contents);
+
+            assert_eq!(audited_balance(Account::Tokens), None);
+            let _ = audit(Account::Tokens, "Wave 1 cleared", 40.0, 0.0);
+            assert!(save().is_err(), "{} log was saved over", name);
+            assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        }
+        SaveBundle::set_data_root(None);
+    }
+}
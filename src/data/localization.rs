@@ -0,0 +1,562 @@
+use crate::data::{balance, crash, mods, storage, GameConfig};
+use crate::plugins::{Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Looked in when a language doesn't have a string
+pub const FALLBACK_LANGUAGE: &str = "en";
+/// Languages the game ships strings for; others can be dropped in the
+/// locales folder as `<code>.json`
+pub const BUILT_IN_LANGUAGES: [&str; 2] = ["en", "nl"];
+
+/// Every built-in string: its key, then the English and the Dutch
+const STRINGS: &[(&str, &str, &str)] = &[
+    // The run's opening
+    ("dialogue.intro.wolters_1", "Bas, sweep!", "Bas, vegen!"),
+    ("dialogue.intro.wolters_2", "Bas! Sweep right now!", "Bas! Vegen, nu meteen!"),
+    ("dialogue.intro.berkay", "No bro, they'll see.", "Nee bro, ze gaan zien."),
+    (
+        "dialogue.intro.luca",
+        "Just wait... I've got a winter arc plan.",
+        "Wacht maar… ik heb een winter arc plan.",
+    ),
+    (
+        "dialogue.intro.nitin",
+        "I'm putting my barras in them.",
+        "Ik ga m’n barras in hun stoppen.",
+    ),
+    ("dialogue.intro.hadi", "Always, bro... they'll see.", "Aina broeg… ze gaan zien."),
+    (
+        "dialogue.intro.bas",
+        "Come on then! I'm not sweeping anything, bro!",
+        "Kom dan! Ik veeg niks, bro!",
+    ),
+    (
+        "dialogue.intro.bastiaan",
+        "You ruined everything, Bas! My artwork was perfect!",
+        "Je hebt alles verpest, Bas! Mijn kunst was perfect!",
+    ),
+    // Bosses
+    ("dialogue.boss.bastiaan", "I AM THE FINAL BOSS!", "IK BEN DE EINDBAAS!"),
+    ("dialogue.boss.keizer", "I throw bombs!", "Ik gooi bommen!"),
+    (
+        "dialogue.boss.mees",
+        "No tikkie for you, so you get my pita sirracha!",
+        "Je krijgt geen tikkie, dus je krijgt mijn pita sirracha!",
+    ),
+    // Moving between maps
+    ("dialogue.route.hallway", "To the hallway!", "Naar de gang!"),
+    ("dialogue.route.cafeteria", "Let's go to the cafeteria!", "Laten we naar de kantine gaan!"),
+    ("dialogue.route.gym", "The gym is next!", "De gymzaal is volgende!"),
+    ("dialogue.route.library", "To the library!", "Naar de bibliotheek!"),
+    (
+        "dialogue.route.rooftop",
+        "To the roof for the final battle!",
+        "Naar het dak voor de laatste strijd!",
+    ),
+    (
+        "dialogue.route.door_breach",
+        "Door's gone! Through to the hallway!",
+        "Deur kapot! Door naar de gang!",
+    ),
+    // Jad's KFC Rage
+    ("dialogue.jad.rage", "KFC RAGE!", "KFC RAGE!"),
+    ("dialogue.jad.angry", "now I'm angry", "nu ben ik boos"),
+    ("dialogue.jad.umut", "typical", "typisch"),
+    ("dialogue.jad.eat", "I eat", "ik eet"),
+    // Boss barks
+    ("bark.bastiaan.hurt", "You think you can stop me?", "Denk je dat je mij kan stoppen?"),
+    ("bark.bastiaan.defeated", "This isn't over...", "Dit is nog niet voorbij..."),
+    ("bark.keizer.hurt", "The emperor does not kneel!", "De keizer knielt niet!"),
+    ("bark.keizer.defeated", "My empire...", "Mijn rijk..."),
+    ("bark.mees.hurt", "Have some more sriracha!", "Nog wat sriracha erbij!"),
+    ("bark.mees.defeated", "My pitas...", "Mijn pita's..."),
+    // The dialogue box
+    (
+        "dialogue.hint",
+        "ENTER: Next  CTRL: Fast  TAB: Auto  L: Log  BACKSPACE: Skip",
+        "ENTER: Verder  CTRL: Snel  TAB: Auto  L: Log  BACKSPACE: Overslaan",
+    ),
+    ("dialogue.auto", "AUTO", "AUTO"),
+    ("dialogue.backlog", "DIALOGUE LOG", "GESPREKSLOG"),
+    (
+        "dialogue.backlog_hint",
+        "W/S: Scroll  |  L: Close",
+        "W/S: Scrollen  |  L: Sluiten",
+    ),
+    // Main menu
+    ("menu.story", "START STORY", "VERHAAL STARTEN"),
+    ("menu.endless", "ENDLESS MODE", "EINDELOZE MODUS"),
+    ("menu.winter_arc", "WINTER ARC", "WINTER ARC"),
+    ("menu.daily", "DAILY CHALLENGE", "DAGELIJKSE UITDAGING"),
+    ("menu.weekly", "WEEKLY CHALLENGE", "WEKELIJKSE UITDAGING"),
+    ("menu.coop", "CO-OP MODE (2 PLAYERS LOCAL)", "CO-OP (2 SPELERS LOKAAL)"),
+    ("menu.versus", "VERSUS", "VERSUS"),
+    ("menu.training", "TRAINING", "TRAINING"),
+    ("menu.practice", "PRACTICE", "OEFENEN"),
+    ("menu.skill_tree", "SKILL TREE", "VAARDIGHEDEN"),
+    ("menu.achievements", "ACHIEVEMENTS", "PRESTATIES"),
+    ("menu.account", "ACCOUNT", "ACCOUNT"),
+    ("menu.profiles", "PROFILES", "PROFIELEN"),
+    ("menu.mods", "MODS", "MODS"),
+    ("menu.settings", "SETTINGS", "INSTELLINGEN"),
+    ("menu.controls", "CONTROLS", "BESTURING"),
+    ("menu.exit", "EXIT", "AFSLUITEN"),
+    ("menu.boss_rush", "BOSS RUSH", "BAZENRACE"),
+    ("menu.check_updates", "CHECK FOR UPDATES", "ZOEK NAAR UPDATES"),
+    ("menu.coming_soon", "coming soon", "binnenkort"),
+    ("menu.news", "NEWS", "NIEUWS"),
+    (
+        "menu.no_news",
+        "No news until the update check has run",
+        "Nog geen nieuws zonder updatecheck",
+    ),
+    (
+        "menu.hint",
+        "W/S: category   A/D: mode   J/ENTER: select",
+        "W/S: categorie   A/D: modus   J/ENTER: kiezen",
+    ),
+    ("menu.hub.story", "STORY", "VERHAAL"),
+    ("menu.hub.versus", "VERSUS", "VERSUS"),
+    ("menu.hub.training", "TRAINING", "TRAINING"),
+    ("menu.hub.survival", "SURVIVAL", "OVERLEVEN"),
+    ("menu.hub.boss_rush", "BOSS RUSH", "BAZENRACE"),
+    ("menu.hub.challenges", "CHALLENGES", "UITDAGINGEN"),
+    ("menu.hub.shop", "SHOP & PROGRESS", "WINKEL & VOORTGANG"),
+    ("menu.hub.settings", "SETTINGS", "INSTELLINGEN"),
+    ("menu.hub.updater", "UPDATES", "UPDATES"),
+    // Settings
+    ("settings.master_volume", "Master Volume", "Hoofdvolume"),
+    ("settings.sfx_volume", "SFX Volume", "Geluidseffecten"),
+    ("settings.music_volume", "Music Volume", "Muziekvolume"),
+    ("settings.voice_volume", "Voice Volume", "Stemvolume"),
+    ("settings.announcer_volume", "Announcer Volume", "Omroepervolume"),
+    ("settings.announcer", "Announcer", "Omroeper"),
+    ("settings.fx_intensity", "FX Intensity", "Effectsterkte"),
+    ("settings.game_feel", "Hitstop & Slow-Mo", "Hitstop & slowmotion"),
+    ("settings.vsync", "VSync", "VSync"),
+    ("settings.post_processing", "Post-Processing", "Nabewerking"),
+    ("settings.low_end", "OFF (LOW-END)", "UIT (ZWAKKE PC)"),
+    ("settings.tick_rate", "Simulation Rate", "Simulatiesnelheid"),
+    ("settings.ui_scaling", "UI Scaling", "UI-schaal"),
+    ("settings.hud_layout", "HUD Layout", "HUD-indeling"),
+    ("settings.export", "Export Save Data", "Opslag exporteren"),
+    ("settings.import", "Import Save Data", "Opslag importeren"),
+    (
+        "settings.cloud_off",
+        "Cloud Sync: OFF (set cloud_url in settings.json)",
+        "Cloudsync: UIT (zet cloud_url in settings.json)",
+    ),
+    ("settings.cloud_sync", "Cloud Sync: Sync Now", "Cloudsync: Nu synchroniseren"),
+    ("settings.back", "Back", "Terug"),
+    ("settings.title", "SETTINGS", "INSTELLINGEN"),
+    ("settings.video", "VIDEO", "BEELD"),
+    ("settings.audio", "AUDIO", "GELUID"),
+    ("settings.controls", "CONTROLS", "BESTURING"),
+    ("settings.gameplay", "GAMEPLAY", "GAMEPLAY"),
+    ("settings.accessibility", "ACCESSIBILITY", "TOEGANKELIJKHEID"),
+    ("settings.save_data", "SAVE DATA", "OPSLAG"),
+    ("settings.fullscreen", "Fullscreen", "Volledig scherm"),
+    ("settings.resolution", "Resolution", "Resolutie"),
+    (
+        "settings.next_launch",
+        "Takes effect the next time the game starts",
+        "Werkt vanaf de volgende keer opstarten",
+    ),
+    ("settings.keyboard", "Keyboard", "Toetsenbord"),
+    ("settings.press_key", "press a key (ESC cancels)", "druk op een toets (ESC annuleert)"),
+    ("settings.reset_keys", "Reset These Keys", "Deze toetsen herstellen"),
+    ("settings.controls_reference", "All Controls", "Alle besturing"),
+    (
+        "settings.keys_hint",
+        "Used in co-op, versus and the menus; solo runs keep the default keys",
+        "Voor co-op, versus en menu's; solo houdt de standaardtoetsen",
+    ),
+    ("settings.hardcore_stamina", "Hardcore Stamina", "Hardcore uithoudingsvermogen"),
+    ("settings.update_channel", "Update Channel", "Updatekanaal"),
+    ("settings.screen_shake", "Screen Shake", "Schermschudden"),
+    ("settings.screen_flash", "Screen Flashes", "Schermflitsen"),
+    ("settings.colorblind", "Colorblind Team Colours", "Kleurenblinde teamkleuren"),
+    ("settings.game_speed", "Game Speed (solo)", "Spelsnelheid (solo)"),
+    ("settings.combo_assist", "One-Button Combos", "Combo's met een knop"),
+    ("settings.combo_assist_key", "Combo Button", "Comboknop"),
+    ("settings.on", "ON", "AAN"),
+    ("settings.off", "OFF", "UIT"),
+    ("settings.language", "Language", "Taal"),
+    ("settings.voices", "Voices", "Stemmen"),
+    ("settings.subtitles", "Subtitles", "Ondertitels"),
+    ("settings.subtitle_size", "Subtitle Size", "Ondertitelgrootte"),
+    ("settings.size_small", "SMALL", "KLEIN"),
+    ("settings.size_normal", "NORMAL", "NORMAAL"),
+    ("settings.size_large", "LARGE", "GROOT"),
+    ("settings.size_huge", "HUGE", "ENORM"),
+    ("settings.subtitle_background", "Subtitle Background", "Ondertitelachtergrond"),
+    ("settings.speaker_colors", "Speaker Colours", "Sprekerkleuren"),
+    // During a run
+    ("gameplay.bosses_defeated", "BOSSES DEFEATED!", "BAZEN VERSLAGEN!"),
+    (
+        "gameplay.choice_hint",
+        "Use W/S or UP/DOWN to select, ENTER to confirm",
+        "Kies met W/S of OMHOOG/OMLAAG, bevestig met ENTER",
+    ),
+    ("gameplay.choose_route", "CHOOSE YOUR ROUTE", "KIES JE ROUTE"),
+    // Results
+    ("results.wins", "{} WINS!", "{} WINT!"),
+    ("results.draw", "DRAW!", "GELIJKSPEL!"),
+    ("results.base_score", "Base Score:", "Basisscore:"),
+    ("results.time_bonus", "Time Bonus:", "Tijdbonus:"),
+    ("results.combo_bonus", "Combo Bonus:", "Combobonus:"),
+    ("results.total", "TOTAL:", "TOTAAL:"),
+    ("results.waves", "Waves Completed", "Golven voltooid"),
+    ("results.enemies", "Enemies Defeated", "Vijanden verslagen"),
+    ("results.continue", "Press SPACE to continue", "Druk op SPATIE om door te gaan"),
+    ("results.run_over", "RUN OVER", "RUN VOORBIJ"),
+    ("results.run_cleared", "RUN CLEARED", "RUN VOLTOOID"),
+    ("results.grade", "GRADE", "CIJFER"),
+    ("results.max_combo", "Max Combo", "Hoogste combo"),
+    ("results.best_style", "Best Style", "Beste stijl"),
+    ("results.flawless", "Flawless - no damage taken", "Foutloos - geen schade opgelopen"),
+    ("results.rewards", "REWARDS", "BELONINGEN"),
+    ("results.tokens", "Arc Tokens", "Arc Tokens"),
+    ("results.mastery_xp", "Mastery XP", "Meesterschap-XP"),
+    ("results.account_xp", "Account XP", "Account-XP"),
+    ("results.unlocks", "UNLOCKED", "ONTGRENDELD"),
+    ("results.no_unlocks", "Nothing new this time", "Deze keer niets nieuws"),
+    ("results.more_unlocks", "...and {} more", "...en nog {}"),
+    ("results.continue_action", "CONTINUE", "DOORGAAN"),
+    ("results.retry", "RETRY", "OPNIEUW"),
+    ("results.run_hint", "A/D: Select | ENTER: Confirm", "A/D: Kiezen | ENTER: Bevestigen"),
+    (
+        "results.trial",
+        "That was a trial of {} - unlock them for {} Arc Tokens with U on character select",
+        "Dat was een proefrun met {} - ontgrendel voor {} Arc Tokens met U bij karakterkeuze",
+    ),
+    // Cutscenes
+    ("cutscene.hint", "ENTER: Next  |  ESC: Skip", "ENTER: Verder  |  ESC: Overslaan"),
+];
+
+/// One language's strings, from `locales/<code>.json`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Locale {
+    /// Shown in settings, in the language itself
+    pub name: String,
+    pub strings: BTreeMap<String, String>,
+}
+
+impl Locale {
+    /// The strings the game ships for `code`; none for a language it doesn't
+    pub fn built_in(code: &str) -> Self {
+        let (name, column) = match code {
+            "en" => ("English", 0),
+            "nl" => ("Nederlands", 1),
+            _ => {
+                return Self {
+                    name: code.to_uppercase(),
+                    strings: BTreeMap::new(),
+                }
+            }
+        };
+        let strings = STRINGS
+            .iter()
+            .map(|&(key, english, dutch)| (key.to_string(), [english, dutch][column].to_string()))
+            .collect();
+        Self {
+            name: name.to_string(),
+            strings,
+        }
+    }
+
+    /// `code`'s built-in strings with its locale file laid over them, so a
+    /// file from an older version still has the newer strings. A missing
+    /// file for a built-in language is written out to be edited.
+    pub fn load(code: &str) -> Self {
+        let mut locale = Self::built_in(code);
+        let path = Self::file_path(code);
+        if !path.exists() && BUILT_IN_LANGUAGES.contains(&code) {
+            if let Err(e) = locale.save(code) {
+                crash::log(format!("Failed to write default {} locale: {}", code, e));
+            }
+        }
+
+        match storage::read_json::<Locale>(&mods::resolve(path)) {
+            Ok(file) => {
+                if !file.name.is_empty() {
+                    locale.name = file.name;
+                }
+                locale.strings.extend(file.strings);
+            }
+            Err(e) if !BUILT_IN_LANGUAGES.contains(&code) => {
+                crash::log(format!("Failed to read {} locale: {}", code, e))
+            }
+            Err(_) => {}
+        }
+        locale
+    }
+
+    pub fn save(&self, code: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize locale: {}", e))?;
+        storage::write_atomic(&Self::file_path(code), &json)
+    }
+
+    fn file_path(code: &str) -> PathBuf {
+        Self::directory().join(format!("{}.json", code))
+    }
+
+    fn directory() -> PathBuf {
+        balance::balance_directory().join("locales")
+    }
+}
+
+/// Text of a line someone says
+#[derive(Clone, Debug, PartialEq)]
+pub enum LineText {
+    /// Looked up in the locale files
+    Key(String),
+    /// Written out in a data file in the two languages the cast speaks; any
+    /// other language looks the English up as a key
+    Written { dutch: String, english: String },
+}
+
+/// A line as it's shown: the words spoken, and the subtitle under them
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpokenLine {
+    pub voice: String,
+    /// None with subtitles off, or when they'd only repeat the voice
+    pub subtitle: Option<String>,
+}
+
+/// Every language's strings, and which ones the menus, voices and
+/// subtitles are in
+pub struct Localization {
+    language: String,
+    voice: String,
+    /// Empty with subtitles off
+    subtitles: String,
+    locales: BTreeMap<String, Locale>,
+}
+
+impl Localization {
+    pub fn load(config: &GameConfig) -> Self {
+        let locales = Self::languages()
+            .into_iter()
+            .map(|code| {
+                let locale = Locale::load(&code);
+                (code, locale)
+            })
+            .collect();
+        Self::with_locales(config, locales)
+    }
+
+    fn with_locales(config: &GameConfig, locales: BTreeMap<String, Locale>) -> Self {
+        Self {
+            language: config.language.clone(),
+            voice: config.voice_language.clone(),
+            subtitles: config.subtitle_language.clone(),
+            locales,
+        }
+    }
+
+    /// Codes of the built-in languages and any in the locales folder, sorted
+    pub fn languages() -> Vec<String> {
+        let mut codes: Vec<String> =
+            BUILT_IN_LANGUAGES.iter().map(|code| code.to_string()).collect();
+        if let Ok(entries) = std::fs::read_dir(Locale::directory()) {
+            codes.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned())),
+            );
+        }
+        codes.sort();
+        codes.dedup();
+        codes
+    }
+
+    /// What `code` calls itself
+    pub fn name(&self, code: &str) -> String {
+        self.locales
+            .get(code)
+            .map_or_else(|| code.to_uppercase(), |locale| locale.name.clone())
+    }
+
+    /// `key` in the menu language, then in English, then the key itself
+    pub fn text(&self, key: &str) -> String {
+        self.text_in(&self.language, key)
+    }
+
+    pub fn spoken(&self, line: &LineText) -> SpokenLine {
+        let voice = self.say(&self.voice, line);
+        let subtitle = if self.subtitles.is_empty() || self.subtitles == self.voice {
+            None
+        } else {
+            Some(self.say(&self.subtitles, line)).filter(|subtitle| *subtitle != voice)
+        };
+        SpokenLine { voice, subtitle }
+    }
+
+    fn lookup(&self, code: &str, key: &str) -> Option<&str> {
+        self.locales.get(code)?.strings.get(key).map(String::as_str)
+    }
+
+    fn text_in(&self, code: &str, key: &str) -> String {
+        self.lookup(code, key)
+            .or_else(|| self.lookup(FALLBACK_LANGUAGE, key))
+            .unwrap_or(key)
+            .to_string()
+    }
+
+    fn say(&self, code: &str, line: &LineText) -> String {
+        match line {
+            LineText::Key(key) => self.text_in(code, key),
+            LineText::Written { dutch, .. } if code == "nl" => dutch.clone(),
+            LineText::Written { english, .. } => {
+                self.lookup(code, english).unwrap_or(english).to_string()
+            }
+        }
+    }
+}
+
+// Loaded for the configured languages on first use
+static ACTIVE: Mutex<Option<Localization>> = Mutex::new(None);
+
+fn with_active<T>(f: impl FnOnce(&Localization) -> T) -> T {
+    let mut active = ACTIVE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(active.get_or_insert_with(|| Localization::load(&GameConfig::load())))
+}
+
+/// `key` in the menu language
+pub fn tr(key: &str) -> String {
+    with_active(|localization| localization.text(key))
+}
+
+/// `line` in the voice and subtitle languages
+pub fn spoken(line: &LineText) -> SpokenLine {
+    with_active(|localization| localization.spoken(line))
+}
+
+/// Pick the languages up again from `config`, after it or the files changed
+pub fn reload(config: &GameConfig) {
+    let localization = Localization::load(config);
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = Some(localization);
+    }
+}
+
+/// The language after `current`; with `off` the cycle passes through no
+/// language at all, as the empty code
+fn next_language(current: &str, off: bool) -> String {
+    let mut codes = Localization::languages();
+    if off {
+        codes.push(String::new());
+    }
+    let index = codes.iter().position(|code| code == current);
+    let next = index.map_or(0, |index| (index + 1) % codes.len());
+    codes[next].clone()
+}
+
+fn language_label(key: &str, code: &str) -> String {
+    with_active(|localization| {
+        let name = if code.is_empty() {
+            localization.text("settings.off")
+        } else {
+            localization.name(code).to_uppercase()
+        };
+        format!("{}: {}", localization.text(key), name)
+    })
+}
+
+/// The "Language" settings page: menus, voices and subtitles each in their
+/// own language, switched on the spot
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Language",
+            entries: vec![
+                SettingsEntry {
+                    label: |config| language_label("settings.language", &config.language),
+                    activate: |config| {
+                        config.language = next_language(&config.language, false);
+                        reload(config);
+                    },
+                },
+                SettingsEntry {
+                    label: |config| language_label("settings.voices", &config.voice_language),
+                    activate: |config| {
+                        config.voice_language = next_language(&config.voice_language, false);
+                        reload(config);
+                    },
+                },
+                SettingsEntry {
+                    label: |config| language_label("settings.subtitles", &config.subtitle_language),
+                    activate: |config| {
+                        config.subtitle_language = next_language(&config.subtitle_language, true);
+                        reload(config);
+                    },
+                },
+            ],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn localization(language: &str, voice: &str, subtitles: &str) -> Localization {
+        let config = GameConfig {
+            language: language.to_string(),
+            voice_language: voice.to_string(),
+            subtitle_language: subtitles.to_string(),
+            ..GameConfig::default()
+        };
+        let mut locales: BTreeMap<String, Locale> = BUILT_IN_LANGUAGES
+            .iter()
+            .map(|&code| (code.to_string(), Locale::built_in(code)))
+            .collect();
+        let mut german = Locale::built_in("de");
+        german.strings.insert("menu.exit".to_string(), "BEENDEN".to_string());
+        german.strings.insert("To the hallway!".to_string(), "Zum Flur!".to_string());
+        locales.insert("de".to_string(), german);
+        Localization::with_locales(&config, locales)
+    }
+
+    #[test]
+    fn missing_strings_fall_back_to_english_then_the_key() {
+        let german = localization("de", "nl", "en");
+        assert_eq!(german.text("menu.exit"), "BEENDEN");
+        assert_eq!(german.text("menu.story"), "START STORY");
+        assert_eq!(german.text("no.such.key"), "no.such.key");
+        assert_eq!(localization("nl", "nl", "en").text("menu.story"), "VERHAAL STARTEN");
+    }
+
+    #[test]
+    fn subtitles_follow_their_own_language() {
+        let line = LineText::Key("dialogue.route.hallway".to_string());
+        let dutch_voices = localization("en", "nl", "en").spoken(&line);
+        assert_eq!(dutch_voices.voice, "Naar de gang!");
+        assert_eq!(dutch_voices.subtitle.as_deref(), Some("To the hallway!"));
+
+        assert_eq!(localization("en", "nl", "nl").spoken(&line).subtitle, None);
+        assert_eq!(localization("en", "nl", "").spoken(&line).subtitle, None);
+
+        let written = LineText::Written {
+            dutch: "Naar de gang!".to_string(),
+            english: "To the hallway!".to_string(),
+        };
+        let german = localization("en", "nl", "de").spoken(&written);
+        assert_eq!(german.subtitle.as_deref(), Some("Zum Flur!"));
+    }
+
+    #[test]
+    fn built_in_keys_are_unique() {
+        assert_eq!(Locale::built_in("en").strings.len(), STRINGS.len());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::data::{balance, crash, mods, storage};
+use crate::ecs::{LootDrop, PowerUpKind, Rarity};
+use crate::util::rng::SeededRng;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LootEntry {
+    pub drop: LootDrop,
+    pub rarity: Rarity,
+}
+
+/// Relative odds of rolling a rarity; only matter against each other
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RarityWeight {
+    pub rarity: Rarity,
+    pub weight: u32,
+}
+
+/// What defeated enemies leave behind. A roll picks a rarity by weight, then
+/// one of that rarity's entries evenly. Lives in `loot.json` next to the
+/// balance reports so drops can be tuned without a rebuild.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DropTable {
+    /// Chance a regular enemy drops anything
+    pub drop_chance: f32,
+    /// Drops a boss always leaves
+    pub boss_drops: u32,
+    pub rarities: Vec<RarityWeight>,
+    pub entries: Vec<LootEntry>,
+}
+
+impl Default for DropTable {
+    fn default() -> Self {
+        let weight = |rarity, weight| RarityWeight { rarity, weight };
+        let entry = |rarity, drop| LootEntry { drop, rarity };
+        let power_up = |kind, duration| LootDrop::PowerUp { kind, duration };
+        Self {
+            drop_chance: 0.35,
+            boss_drops: 3,
+            rarities: vec![
+                weight(Rarity::Common, 60),
+                weight(Rarity::Uncommon, 28),
+                weight(Rarity::Rare, 10),
+                weight(Rarity::Epic, 2),
+            ],
+            entries: vec![
+                entry(Rarity::Common, LootDrop::Tokens(5)),
+                entry(Rarity::Common, LootDrop::HealthPack(15.0)),
+                entry(Rarity::Uncommon, LootDrop::Tokens(15)),
+                entry(Rarity::Uncommon, LootDrop::HealthPack(35.0)),
+                entry(Rarity::Uncommon, LootDrop::MeterBattery(25.0)),
+                entry(Rarity::Rare, LootDrop::MeterBattery(100.0)),
+                entry(Rarity::Rare, power_up(PowerUpKind::Strength, 10.0)),
+                entry(Rarity::Rare, power_up(PowerUpKind::Haste, 10.0)),
+                entry(Rarity::Epic, LootDrop::Tokens(60)),
+                entry(Rarity::Epic, power_up(PowerUpKind::Regen, 12.0)),
+            ],
+        }
+    }
+}
+
+impl DropTable {
+    /// The built-in table with any tuning from `loot.json` laid over it
+    pub fn load() -> Self {
+        let path = mods::resolve(Self::file_path());
+        storage::read_overrides(&path, Self::default()).unwrap_or_else(|e| {
+            crash::log(format!("Failed to read loot table, using defaults: {}", e));
+            Self::default()
+        })
+    }
+
+    /// What one defeated enemy drops. Always draws from `rng` the same number
+    /// of times for the same table, so online runs stay in step.
+    pub fn roll_enemy(&self, rng: &mut SeededRng, boss: bool) -> Vec<LootEntry> {
+        if boss {
+            return (0..self.boss_drops).filter_map(|_| self.roll(rng)).collect();
+        }
+        if !rng.chance(self.drop_chance) {
+            return Vec::new();
+        }
+        self.roll(rng).into_iter().collect()
+    }
+
+    /// One weighted pick. Rarities without entries can't come up.
+    pub fn roll(&self, rng: &mut SeededRng) -> Option<LootEntry> {
+        let stocked: Vec<_> = self
+            .rarities
+            .iter()
+            .filter(|r| r.weight > 0 && self.entries.iter().any(|e| e.rarity == r.rarity))
+            .collect();
+        let total: u32 = stocked.iter().map(|r| r.weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = rng.next_u32() % total;
+        let rarity = stocked
+            .iter()
+            .find(|r| {
+                if pick < r.weight {
+                    return true;
+                }
+                pick -= r.weight;
+                false
+            })?
+            .rarity;
+
+        let pool: Vec<_> = self.entries.iter().filter(|e| e.rarity == rarity).collect();
+        let index = rng.next_u32() as usize % pool.len();
+        Some(*pool[index])
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("loot.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_repeat_for_the_same_seed() {
+        let table = DropTable::default();
+        let rolls = |seed| {
+            let mut rng = SeededRng::new(seed);
+            (0..50).map(|_| table.roll_enemy(&mut rng, false)).collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert!(rolls(7).iter().any(|drops| !drops.is_empty()));
+    }
+
+    #[test]
+    fn empty_rarities_are_never_rolled() {
+        let table = DropTable {
+            rarities: vec![
+                RarityWeight {
+                    rarity: Rarity::Common,
+                    weight: 1,
+                },
+                RarityWeight {
+                    rarity: Rarity::Epic,
+                    weight: 1000,
+                },
+            ],
+            entries: vec![LootEntry {
+                drop: LootDrop::Tokens(1),
+                rarity: Rarity::Common,
+            }],
+            ..DropTable::default()
+        };
+
+        let mut rng = SeededRng::new(1);
+        for _ in 0..20 {
+            assert_eq!(table.roll(&mut rng).map(|entry| entry.rarity), Some(Rarity::Common));
+        }
+        assert_eq!(table.roll_enemy(&mut rng, true).len(), 3);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::data::crash;
+use crate::data::storage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Field every versioned file carries its schema version in. Files from
+/// before there were versions don't have it and count as version 0.
+pub const VERSION_KEY: &str = "schema_version";
+
+/// One step from a schema version to the next, made on the raw JSON
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Player data that outlives a game update: saves, the shop and mastery.
+/// Adding or changing a field means adding a step to `MIGRATIONS`, which
+/// bumps the version.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Steps in order: the first brings an unversioned file to version 1,
+    /// the next version 1 to 2, and so on
+    const MIGRATIONS: &'static [Migration];
+
+    fn version() -> u32 {
+        Self::MIGRATIONS.len() as u32
+    }
+}
+
+/// First step for every file. Files from before versioning already read as
+/// version 1, so there's nothing to change beyond stamping the version.
+pub fn from_unversioned(_: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+/// `<file>.v<version>`, the file as it was before being migrated from
+/// `version`
+pub fn pre_migration_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".v{}", version));
+    PathBuf::from(name)
+}
+
+/// `<file>.unreadable`, a copy of the last version that failed to load, so
+/// starting over can't lose it
+pub fn unreadable_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".unreadable");
+    PathBuf::from(name)
+}
+
+enum Failure {
+    /// Written by a newer game; the backup would be older still
+    Newer(String),
+    Unreadable(String),
+}
+
+/// Read a versioned file, migrating it to the current version first if it's
+/// older. A migrated file keeps a copy from before the migration and is
+/// written back straight away. If the file won't load it's copied aside and
+/// the backup is tried, except when it's from a newer game, which is left
+/// alone for that game to read.
+pub fn read_versioned<T: Versioned>(path: &Path) -> Result<T, String> {
+    let error = match load(path, true) {
+        Ok(data) => return Ok(data),
+        Err(Failure::Newer(error)) => {
+            keep_unreadable(path);
+            return Err(error);
+        }
+        Err(Failure::Unreadable(error)) => error,
+    };
+    keep_unreadable(path);
+
+    let backup = storage::backup_path(path);
+    if !backup.exists() {
+        return Err(error);
+    }
+    let restored = load(&backup, false).map_err(|failure| match failure {
+        Failure::Newer(e) | Failure::Unreadable(e) => e,
+    })?;
+    crash::log(format!("{}; restored from {}", error, backup.display()));
+    Ok(restored)
+}
+
+/// `read_versioned` for a file the game may not have written yet: `None`
+/// when neither it nor its backup exists, so the caller starts fresh. Any
+/// other failure is an error, and the caller mustn't save defaults over a
+/// file that may be a newer game's or worth recovering.
+pub fn read_if_exists<T: Versioned>(path: &Path) -> Result<Option<T>, String> {
+    if !path.exists() && !storage::backup_path(path).exists() {
+        return Ok(None);
+    }
+    read_versioned(path).map(Some)
+}
+
+/// An error while `load_error` says why data's file couldn't be read, for
+/// `save` to return instead of writing over it
+pub fn check_writable(load_error: Option<&str>) -> Result<(), String> {
+    match load_error {
+        Some(error) => Err(format!("Not saving over data that didn't load: {}", error)),
+        None => Ok(()),
+    }
+}
+
+/// `write_atomic` with the current schema version stamped in
+pub fn write_versioned<T: Versioned>(path: &Path, data: &T) -> Result<(), String> {
+    let mut value = serde_json::to_value(data)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    let Some(fields) = value.as_object_mut() else {
+        return Err(format!("{} isn't a JSON object", path.display()));
+    };
+    fields.insert(VERSION_KEY.to_string(), Value::from(T::version()));
+
+    let json = serde_json::to_string_pretty(&value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    storage::write_atomic(path, &json)
+}
+
+/// Parse and migrate `path`, writing the migrated version back over it if
+/// `write_back` is set
+fn load<T: Versioned>(path: &Path, write_back: bool) -> Result<T, Failure> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| Failure::Unreadable(format!("Failed to read {}: {}", path.display(), e)))?;
+    let mut value: Value = serde_json::from_str(&json)
+        .map_err(|e| Failure::Unreadable(format!("Failed to parse {}: {}", path.display(), e)))?;
+
+    let version = value.get(VERSION_KEY).and_then(Value::as_u64).unwrap_or(0) as u32;
+    let current = T::version();
+    if version > current {
+        return Err(Failure::Newer(format!(
+            "{} is from a newer version of the game (schema {}, this one reads {})",
+            path.display(),
+            version,
+            current
+        )));
+    }
+
+    let migrating = version < current;
+    if migrating {
+        let before = pre_migration_path(path, version);
+        if !before.exists() {
+            fs::copy(path, &before).map_err(|e| {
+                Failure::Unreadable(format!("Failed to back up {}: {}", path.display(), e))
+            })?;
+        }
+        for (from, migrate) in T::MIGRATIONS.iter().enumerate().skip(version as usize) {
+            migrate(&mut value).map_err(|e| {
+                let at = path.display();
+                Failure::Unreadable(format!("Failed to migrate {} from schema {}: {}", at, from, e))
+            })?;
+        }
+    }
+
+    let data: T = serde_json::from_value(value)
+        .map_err(|e| Failure::Unreadable(format!("Failed to parse {}: {}", path.display(), e)))?;
+
+    if migrating && write_back {
+        match write_versioned(path, &data) {
+            Ok(()) => crash::log(format!(
+                "Migrated {} from schema {} to {}",
+                path.display(),
+                version,
+                current
+            )),
+            Err(e) => crash::log(format!("Failed to write migrated {}: {}", path.display(), e)),
+        }
+    }
+    Ok(data)
+}
+
+/// Copy a file that failed to load out of the way of the next save, unless
+/// the same copy is already there
+fn keep_unreadable(path: &Path) {
+    let Ok(contents) = fs::read(path) else {
+        return;
+    };
+    let kept = unreadable_path(path);
+    if fs::read(&kept).is_ok_and(|existing| existing == contents) {
+        return;
+    }
+    match fs::write(&kept, contents) {
+        Ok(()) => crash::log(format!("Kept a copy of {} at {}", path.display(), kept.display())),
+        Err(e) => crash::log(format!("Failed to keep a copy of {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// Version 1 called the balance `coins`; version 2 renamed it
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Wallet {
+        tokens: u32,
+    }
+
+    fn rename_coins(value: &mut Value) -> Result<(), String> {
+        let fields = value.as_object_mut().ok_or("not an object")?;
+        let coins = fields.remove("coins").ok_or("no coins")?;
+        fields.insert("tokens".to_string(), coins);
+        Ok(())
+    }
+
+    impl Versioned for Wallet {
+        const MIGRATIONS: &'static [Migration] = &[from_unversioned, rename_coins];
+    }
+
+    fn scratch_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("bas-veeg-arc-migrate-{}.json", name));
+        for stale in [
+            storage::backup_path(&path),
+            pre_migration_path(&path, 0),
+            unreadable_path(&path),
+        ] {
+            let _ = fs::remove_file(stale);
+        }
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn old_files_are_migrated_and_backed_up() {
+        let path = scratch_file("old", r#"{"coins": 40}"#);
+        let wallet: Wallet = read_versioned(&path).unwrap();
+        assert_eq!(wallet, Wallet { tokens: 40 });
+
+        // The original is kept and the file now carries the current version
+        let before = fs::read_to_string(pre_migration_path(&path, 0)).unwrap();
+        assert_eq!(before, r#"{"coins": 40}"#);
+        let written: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written[VERSION_KEY], 2);
+        assert_eq!(read_versioned::<Wallet>(&path).unwrap(), wallet);
+    }
+
+    #[test]
+    fn unreadable_and_newer_files_are_kept() {
+        let path = scratch_file("newer", r#"{"schema_version": 9, "tokens": 5}"#);
+        assert!(read_versioned::<Wallet>(&path).is_err());
+        assert!(path.exists());
+        assert!(unreadable_path(&path).exists());
+
+        // A broken file falls back to the backup, with the broken one set aside
+        let path = scratch_file("broken", "{ not json");
+        fs::write(storage::backup_path(&path), r#"{"schema_version": 2, "tokens": 7}"#).unwrap();
+        assert_eq!(read_versioned::<Wallet>(&path).unwrap(), Wallet { tokens: 7 });
+        assert_eq!(fs::read_to_string(unreadable_path(&path)).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn only_missing_files_read_as_fresh_starts() {
+        let missing = std::env::temp_dir().join("bas-veeg-arc-migrate-missing.json");
+        let _ = fs::remove_file(&missing);
+        let _ = fs::remove_file(storage::backup_path(&missing));
+        assert_eq!(read_if_exists::<Wallet>(&missing), Ok(None));
+
+        let path = scratch_file("newer-kept", r#"{"schema_version": 9, "tokens": 5}"#);
+        let error = read_if_exists::<Wallet>(&path).unwrap_err();
+        assert!(check_writable(Some(&error)).is_err());
+        assert_eq!(check_writable(None), Ok(()));
+    }
+}
@@ -1,11 +1,34 @@
+pub mod balance;
 pub mod characters;
+pub mod cloud;
+pub mod config;
+pub mod coop_scaling;
+pub mod cosmetics;
+pub mod crash;
+pub mod cutscenes;
+pub mod dialogue;
+pub mod encounters;
+pub mod events;
 pub mod game_state;
+pub mod ledger;
+pub mod localization;
+pub mod loot;
+pub mod migrate;
+pub mod mods;
+pub mod move_vfx;
+pub mod palette;
+pub mod portable;
+pub mod poses;
 pub mod replay;
 pub mod save;
 pub mod shop;
+pub mod storage;
 
 pub use characters::{AbilityState, Character, CharacterId, CHARACTERS};
-pub use game_state::{get_selected_character, set_selected_character, set_coop_players, get_coop_players, clear_coop_players};
+pub use config::GameConfig;
+pub use coop_scaling::{CoopScaling, PlayerCountScaling};
+pub use game_state::{get_selected_character, set_selected_character, get_tag_partner, set_tag_partner, set_coop_players, get_coop_players, clear_coop_players, get_ally_preset, set_ally_preset, get_coop_lives, set_coop_lives, get_pose_choice, set_pose_choice, MatchSettings, MatchResult, set_match_settings, get_match_settings, clear_match_settings, set_match_result, take_match_result, set_practice_start, take_practice_start, set_trial_run, take_trial_run, set_cutscene, take_cutscene, StoryResult, set_story_result, take_story_result, RunReport, set_run_report, take_run_report, get_post_processing, set_post_processing, get_tick_rate, set_tick_rate, get_ui_scale_mode, set_ui_scale_mode, get_debug_controls, set_debug_controls, get_dev_console, set_dev_console, get_debug_hitboxes, set_debug_hitboxes, get_hardcore_stamina, set_hardcore_stamina, get_broadcast_overlay, set_broadcast_overlay, get_subtitle_style, set_subtitle_style, get_active_profile, set_active_profile, set_window_away, take_window_away, set_over_run, take_over_run, set_away_muted, get_away_muted, queue_music_cue, take_music_cues, queue_sound, take_sounds, duck_music, take_music_duck, set_volumes, get_volumes, queue_time_scale, take_time_scale_requests, set_game_feel, get_game_feel, set_screen_shake, get_screen_shake, set_screen_flash, get_screen_flash, set_combo_assist_key, get_combo_assist_key, set_game_speed, get_game_speed, set_key_rebinds, get_key_rebinds, get_key_rebinds_revision, get_coop_scaling, set_coop_scaling, CoopBots, set_coop_bots, take_coop_bots};
+pub use portable::SaveBundle;
 pub use save::SaveManager;
-pub use shop::{ShopManager, UpgradeId};
+pub use shop::{Consumable, ShopManager, UpgradeId};
 // Replay system not yet fully implemented
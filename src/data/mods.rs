@@ -0,0 +1,292 @@
+use crate::data::{balance, crash, storage, GameConfig};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Version of what mods can rely on: which data files they may replace and
+/// how those files are read. Bump the minor version for additions old mods
+/// keep working with, the major version for anything that breaks them.
+pub const MOD_API_VERSION: ApiVersion = ApiVersion {
+    major: 1,
+    minor: 0,
+};
+
+/// The data files a mod may replace, each with the version of its layout.
+/// Bump a file's version whenever its fields change meaning or go away.
+pub const DATA_SCHEMAS: [(&str, u32); 5] = [
+    ("coop_scaling.json", 1),
+    ("encounters.json", 1),
+    ("loot.json", 1),
+    ("move_vfx.json", 1),
+    ("palette.json", 1),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl ApiVersion {
+    /// `"1.2"` or `"1.2.3"`; the patch number doesn't matter to mods
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().trim_start_matches('v').split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+        Some(Self { major, minor })
+    }
+}
+
+impl std::fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// `mod.json` in a mod's folder
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ModManifest {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    /// `MOD_API_VERSION` the mod was made for
+    pub api_version: String,
+    /// Schema version of each data file the mod replaces
+    pub schemas: BTreeMap<String, u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ModStatus {
+    Compatible,
+    /// Loads, but something may not work as the mod meant
+    Warning,
+    /// Made for a version this game can't read; never loaded
+    Refused,
+}
+
+impl ModStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            ModStatus::Compatible => "OK",
+            ModStatus::Warning => "WARNING",
+            ModStatus::Refused => "REFUSED",
+        }
+    }
+}
+
+/// What the compatibility check made of one mod
+#[derive(Clone, Debug)]
+pub struct ModReport {
+    /// Folder name, which is what enabling and disabling go by
+    pub folder: String,
+    pub manifest: ModManifest,
+    pub status: ModStatus,
+    /// Why it's a warning or refused, one line each
+    pub notes: Vec<String>,
+    /// Data files it replaces, if loaded
+    pub files: Vec<String>,
+    pub enabled: bool,
+}
+
+impl ModReport {
+    /// Whether the game reads its files
+    pub fn active(&self) -> bool {
+        self.enabled && self.status != ModStatus::Refused
+    }
+}
+
+/// Check `manifest` against this game. `files` are the data files in the
+/// mod's folder.
+pub fn check(manifest: &ModManifest, files: &[String]) -> (ModStatus, Vec<String>) {
+    let mut refused = Vec::new();
+    let mut warnings = Vec::new();
+
+    match ApiVersion::parse(&manifest.api_version) {
+        None => refused.push(format!("api_version {:?} isn't a version", manifest.api_version)),
+        Some(api) if api.major != MOD_API_VERSION.major => refused.push(format!(
+            "Made for mod API {}, this game has {}",
+            api, MOD_API_VERSION
+        )),
+        Some(api) if api > MOD_API_VERSION => refused.push(format!(
+            "Made for mod API {}, which needs a newer game than {}",
+            api, MOD_API_VERSION
+        )),
+        Some(_) => {}
+    }
+
+    for file in files {
+        let Some(&(_, current)) = DATA_SCHEMAS.iter().find(|(name, _)| name == file) else {
+            warnings.push(format!("{} isn't a file mods can replace; ignored", file));
+            continue;
+        };
+        match manifest.schemas.get(file) {
+            None => warnings.push(format!("{} has no schema version; read as {}", file, current)),
+            Some(&schema) if schema > current => refused.push(format!(
+                "{} is schema {}, newer than this game's {}",
+                file, schema, current
+            )),
+            Some(&schema) if schema < current => warnings.push(format!(
+                "{} is schema {}, older than this game's {}; parts may not load",
+                file, schema, current
+            )),
+            Some(_) => {}
+        }
+    }
+
+    if !refused.is_empty() {
+        refused.extend(warnings);
+        (ModStatus::Refused, refused)
+    } else if !warnings.is_empty() {
+        (ModStatus::Warning, warnings)
+    } else {
+        (ModStatus::Compatible, warnings)
+    }
+}
+
+pub fn mods_directory() -> PathBuf {
+    balance::balance_directory().join("mods")
+}
+
+/// Check every mod folder, sorted by name. A folder without a readable
+/// `mod.json` is refused rather than skipped so it shows up in the report.
+pub fn scan() -> Vec<ModReport> {
+    let Ok(entries) = fs::read_dir(mods_directory()) else {
+        return Vec::new();
+    };
+    let disabled = GameConfig::load().disabled_mods;
+
+    let mut reports: Vec<ModReport> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| {
+            let folder = entry.file_name().to_string_lossy().into_owned();
+            let enabled = !disabled.contains(&folder);
+            report(&entry.path(), folder, enabled)
+        })
+        .collect();
+    reports.sort_by(|a, b| a.folder.cmp(&b.folder));
+    reports
+}
+
+fn report(dir: &Path, folder: String, enabled: bool) -> ModReport {
+    let files = data_files(dir);
+    let (manifest, status, notes) = match storage::read_json::<ModManifest>(&dir.join("mod.json")) {
+        Ok(manifest) => {
+            let (status, notes) = check(&manifest, &files);
+            (manifest, status, notes)
+        }
+        Err(e) => (ModManifest::default(), ModStatus::Refused, vec![e]),
+    };
+    let manifest = ModManifest {
+        name: if manifest.name.is_empty() { folder.clone() } else { manifest.name },
+        ..manifest
+    };
+    ModReport {
+        folder,
+        manifest,
+        status,
+        notes,
+        files,
+        enabled,
+    }
+}
+
+/// JSON files in a mod's folder other than its manifest
+fn data_files(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".json") && name != "mod.json")
+        .collect();
+    files.sort();
+    files
+}
+
+// Scanned on first use and again when the mod manager changes something
+static REPORTS: Mutex<Option<Vec<ModReport>>> = Mutex::new(None);
+
+/// The last scan, scanning first if there hasn't been one. Problems are
+/// logged the first time.
+pub fn reports() -> Vec<ModReport> {
+    let Ok(mut reports) = REPORTS.lock() else {
+        return Vec::new();
+    };
+    reports
+        .get_or_insert_with(|| {
+            let scanned = scan();
+            for report in scanned.iter().filter(|report| report.status != ModStatus::Compatible) {
+                for note in &report.notes {
+                    let status = report.status.label();
+                    crash::log(format!("Mod {} {}: {}", report.folder, status, note));
+                }
+            }
+            scanned
+        })
+        .clone()
+}
+
+/// Scan the mods folder again
+pub fn rescan() {
+    if let Ok(mut reports) = REPORTS.lock() {
+        *reports = Some(scan());
+    }
+}
+
+/// Where to read the data file at `path` from: the first enabled,
+/// compatible mod that replaces it, or `path` itself
+pub fn resolve(path: PathBuf) -> PathBuf {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+        return path;
+    };
+    if !DATA_SCHEMAS.iter().any(|(file, _)| *file == name) {
+        return path;
+    }
+
+    reports()
+        .iter()
+        .find(|report| report.active() && report.files.contains(&name))
+        .map_or(path, |report| mods_directory().join(&report.folder).join(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(api_version: &str, schemas: &[(&str, u32)]) -> ModManifest {
+        ModManifest {
+            name: "Test".to_string(),
+            api_version: api_version.to_string(),
+            schemas: schemas.iter().map(|&(file, v)| (file.to_string(), v)).collect(),
+            ..ModManifest::default()
+        }
+    }
+
+    #[test]
+    fn api_versions_follow_semver() {
+        let files = vec!["loot.json".to_string()];
+        let schemas = [("loot.json", 1)];
+        assert_eq!(check(&manifest("1.0.3", &schemas), &files).0, ModStatus::Compatible);
+        assert_eq!(check(&manifest("2.0", &schemas), &files).0, ModStatus::Refused);
+        assert_eq!(check(&manifest("1.9", &schemas), &files).0, ModStatus::Refused);
+        assert_eq!(check(&manifest("soon", &schemas), &files).0, ModStatus::Refused);
+    }
+
+    #[test]
+    fn schemas_warn_when_old_and_refuse_when_new() {
+        let files = vec!["loot.json".to_string(), "readme.json".to_string()];
+        let (status, notes) = check(&manifest("1.0", &[("loot.json", 1)]), &files);
+        assert_eq!(status, ModStatus::Warning, "unknown files are ignored with a warning");
+        assert_eq!(notes.len(), 1);
+
+        let files = vec!["loot.json".to_string()];
+        assert_eq!(check(&manifest("1.0", &[("loot.json", 2)]), &files).0, ModStatus::Refused);
+        assert_eq!(check(&manifest("1.0", &[("loot.json", 0)]), &files).0, ModStatus::Warning);
+        assert_eq!(check(&manifest("1.0", &[]), &files).0, ModStatus::Warning);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::combat::character_movesets::{MoveData, MoveId, MoveProperty};
+use crate::data::{balance, crash, mods, storage};
+use crate::render::vfx::VFXType;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// When during a move an effect plays
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VfxMoment {
+    /// As the move starts
+    Swing,
+    /// When it connects
+    Hit,
+}
+
+/// A `MoveProperty` without its numbers, so the table can key on it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropertyKind {
+    Overhead,
+    Low,
+    Unblockable,
+    Invincible,
+    SuperArmor,
+    Juggle,
+    GroundBounce,
+    WallBounce,
+    Projectile,
+    MultiHit,
+    ChargeMeter,
+    Slow,
+    Stun,
+    DamageOverTime,
+}
+
+impl PropertyKind {
+    pub fn of(property: &MoveProperty) -> Self {
+        match property {
+            MoveProperty::Overhead => PropertyKind::Overhead,
+            MoveProperty::Low => PropertyKind::Low,
+            MoveProperty::Unblockable => PropertyKind::Unblockable,
+            MoveProperty::Invincible => PropertyKind::Invincible,
+            MoveProperty::SuperArmor => PropertyKind::SuperArmor,
+            MoveProperty::Juggle => PropertyKind::Juggle,
+            MoveProperty::GroundBounce => PropertyKind::GroundBounce,
+            MoveProperty::WallBounce => PropertyKind::WallBounce,
+            MoveProperty::Projectile => PropertyKind::Projectile,
+            MoveProperty::MultiHit(_) => PropertyKind::MultiHit,
+            MoveProperty::ChargeMeter => PropertyKind::ChargeMeter,
+            MoveProperty::Slow(_) => PropertyKind::Slow,
+            MoveProperty::Stun(_) => PropertyKind::Stun,
+            MoveProperty::DOT(_, _) => PropertyKind::DamageOverTime,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct VfxBinding {
+    pub moment: VfxMoment,
+    pub effect: VFXType,
+}
+
+/// Effects for every move with this property
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PropertyVfx {
+    pub property: PropertyKind,
+    pub effects: Vec<VfxBinding>,
+}
+
+/// Effects for one move, in place of whatever its properties would give it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MoveVfx {
+    pub move_id: MoveId,
+    pub effects: Vec<VfxBinding>,
+}
+
+/// Which effects each move plays. Moves pick theirs up from their properties,
+/// so a new move looks right without touching this; `moves` overrides single
+/// moves. Lives in `move_vfx.json` next to the balance reports so effects can
+/// be rethemed without a rebuild.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MoveVfxTable {
+    /// For a moment no property or override covers
+    pub fallback: Vec<VfxBinding>,
+    pub properties: Vec<PropertyVfx>,
+    pub moves: Vec<MoveVfx>,
+}
+
+impl Default for MoveVfxTable {
+    fn default() -> Self {
+        let swing = |effect| VfxBinding { moment: VfxMoment::Swing, effect };
+        let hit = |effect| VfxBinding { moment: VfxMoment::Hit, effect };
+        let property = |property, effects| PropertyVfx { property, effects };
+        Self {
+            fallback: vec![swing(VFXType::DashTrail), hit(VFXType::HitSpark)],
+            properties: vec![
+                property(PropertyKind::Overhead, vec![hit(VFXType::GroundCrack)]),
+                property(PropertyKind::Low, vec![swing(VFXType::DustCloud)]),
+                property(
+                    PropertyKind::Unblockable,
+                    vec![swing(VFXType::EnergyBurst), hit(VFXType::CriticalHit)],
+                ),
+                property(PropertyKind::SuperArmor, vec![swing(VFXType::SuperFlash)]),
+                property(
+                    PropertyKind::Juggle,
+                    vec![swing(VFXType::DashTrail), hit(VFXType::ShockWave)],
+                ),
+                property(
+                    PropertyKind::GroundBounce,
+                    vec![hit(VFXType::GroundCrack), hit(VFXType::DustCloud)],
+                ),
+                property(PropertyKind::WallBounce, vec![hit(VFXType::ShockWave)]),
+                property(PropertyKind::Projectile, vec![swing(VFXType::EnergyBurst)]),
+                property(PropertyKind::MultiHit, vec![hit(VFXType::ComboFlash)]),
+                property(PropertyKind::Stun, vec![hit(VFXType::LightningStrike)]),
+                property(PropertyKind::DamageOverTime, vec![hit(VFXType::PoisonCloud)]),
+            ],
+            moves: vec![
+                MoveVfx {
+                    move_id: MoveId::BombDrop,
+                    effects: vec![hit(VFXType::Explosion)],
+                },
+                MoveVfx {
+                    move_id: MoveId::SoupSplash,
+                    effects: vec![swing(VFXType::PaintSplash), hit(VFXType::HitSpark)],
+                },
+                MoveVfx {
+                    move_id: MoveId::SteamBlast,
+                    effects: vec![swing(VFXType::DustCloud), hit(VFXType::FireBurst)],
+                },
+            ],
+        }
+    }
+}
+
+impl MoveVfxTable {
+    /// The built-in bindings with any from `move_vfx.json` laid over them
+    pub fn load() -> Self {
+        let path = mods::resolve(Self::file_path());
+        storage::read_overrides(&path, Self::default()).unwrap_or_else(|e| {
+            crash::log(format!("Failed to read move VFX, using defaults: {}", e));
+            Self::default()
+        })
+    }
+
+    /// Effects `data` plays at `moment`: its override if it has one, else
+    /// every effect its properties bind, else the fallback
+    pub fn effects(&self, data: &MoveData, moment: VfxMoment) -> Vec<VFXType> {
+        let at_moment = |bindings: &[VfxBinding]| -> Vec<VFXType> {
+            bindings
+                .iter()
+                .filter(|binding| binding.moment == moment)
+                .map(|binding| binding.effect)
+                .collect()
+        };
+
+        if let Some(binding) = self.moves.iter().find(|binding| binding.move_id == data.move_id) {
+            return at_moment(&binding.effects);
+        }
+
+        let mut effects = Vec::new();
+        for property in &data.properties {
+            let kind = PropertyKind::of(property);
+            for binding in self.properties.iter().filter(|binding| binding.property == kind) {
+                for effect in at_moment(&binding.effects) {
+                    if !effects.contains(&effect) {
+                        effects.push(effect);
+                    }
+                }
+            }
+        }
+
+        if effects.is_empty() {
+            at_moment(&self.fallback)
+        } else {
+            effects
+        }
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("move_vfx.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::character_movesets::CharacterMoveset;
+    use crate::ecs::CharacterType;
+
+    fn taha_move(move_id: MoveId) -> MoveData {
+        CharacterMoveset::for_character(CharacterType::KeizerBomTaha)
+            .get_move(move_id)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn moves_take_effects_from_their_properties() {
+        let table = MoveVfxTable::default();
+
+        // Juggle + Overhead
+        let kick = taha_move(MoveId::CommanderKick);
+        assert_eq!(
+            table.effects(&kick, VfxMoment::Hit),
+            vec![VFXType::ShockWave, VFXType::GroundCrack]
+        );
+
+        // No properties, so the fallback
+        let strike = taha_move(MoveId::MilitaryStrike);
+        assert_eq!(table.effects(&strike, VfxMoment::Swing), vec![VFXType::DashTrail]);
+        assert_eq!(table.effects(&strike, VfxMoment::Hit), vec![VFXType::HitSpark]);
+    }
+
+    #[test]
+    fn overrides_replace_property_effects() {
+        let table = MoveVfxTable::default();
+        let bomb = taha_move(MoveId::BombDrop);
+        assert_eq!(table.effects(&bomb, VfxMoment::Hit), vec![VFXType::Explosion]);
+        assert!(table.effects(&bomb, VfxMoment::Swing).is_empty());
+    }
+}
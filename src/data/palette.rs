@@ -0,0 +1,311 @@
+use crate::data::save::ColorblindMode;
+use crate::data::{balance, crash, mods, storage, GameConfig};
+use crate::plugins::{Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A colour as palette files write it: `[r, g, b, a]`, each 0 to 1
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct Rgba(pub [f32; 4]);
+
+impl Rgba {
+    pub fn color(self) -> Color {
+        let [r, g, b, a] = self.0;
+        Color::new(r, g, b, a)
+    }
+}
+
+/// How one side of the fight is marked out
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TeamColors {
+    /// Outline glow under the fighter
+    pub glow: Rgba,
+    pub nametag: Rgba,
+}
+
+/// Health bars over the fighters
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct HealthColors {
+    /// Above 60%
+    pub high: Rgba,
+    /// Above 30%
+    pub mid: Rgba,
+    pub low: Rgba,
+    pub background: Rgba,
+    pub border: Rgba,
+}
+
+impl HealthColors {
+    /// Fill for a bar at `fraction` of full health
+    pub fn fill(&self, fraction: f32) -> Color {
+        if fraction > 0.6 {
+            self.high.color()
+        } else if fraction > 0.3 {
+            self.mid.color()
+        } else {
+            self.low.color()
+        }
+    }
+}
+
+/// Every colour that tells fighters apart, for one way of seeing them
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PaletteSet {
+    pub player: TeamColors,
+    pub ally: TeamColors,
+    pub enemy: TeamColors,
+    /// Outline flash while spawn invulnerability lasts
+    pub invulnerable: Rgba,
+    /// P1 to P4 in co-op and versus
+    pub players: [Rgba; 4],
+    pub health: HealthColors,
+}
+
+impl PaletteSet {
+    /// P1 is 0
+    pub fn player_color(&self, index: usize) -> Color {
+        self.players[index % self.players.len()].color()
+    }
+}
+
+/// Team, name tag and health bar colours, with an alternate set for each
+/// colourblind mode. Lives in `palette.json` next to the balance reports;
+/// a theme in `themes/<name>.json` there overrides whichever fields it sets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Palette {
+    pub standard: PaletteSet,
+    pub protanopia: PaletteSet,
+    pub deuteranopia: PaletteSet,
+    pub tritanopia: PaletteSet,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            standard: standard(),
+            protanopia: red_green(),
+            deuteranopia: red_green(),
+            tritanopia: blue_yellow(),
+        }
+    }
+}
+
+fn team(glow: [f32; 4], nametag: [f32; 4]) -> TeamColors {
+    TeamColors {
+        glow: Rgba(glow),
+        nametag: Rgba(nametag),
+    }
+}
+
+fn standard() -> PaletteSet {
+    PaletteSet {
+        player: team([1.0, 0.9, 0.0, 0.5], [0.99, 0.98, 0.0, 1.0]),
+        ally: team([0.3, 1.0, 0.5, 0.4], [0.5, 1.0, 0.6, 1.0]),
+        enemy: team([1.0, 0.3, 0.3, 0.4], [1.0, 0.5, 0.5, 1.0]),
+        invulnerable: Rgba([1.0, 1.0, 1.0, 0.9]),
+        players: [
+            Rgba([0.2, 0.5, 1.0, 1.0]),
+            Rgba([1.0, 0.2, 0.2, 1.0]),
+            Rgba([0.2, 1.0, 0.2, 1.0]),
+            Rgba([1.0, 1.0, 0.2, 1.0]),
+        ],
+        health: HealthColors {
+            high: Rgba([0.5, 1.0, 0.3, 1.0]),
+            mid: Rgba([1.0, 0.8, 0.2, 1.0]),
+            low: Rgba([1.0, 0.3, 0.2, 1.0]),
+            background: Rgba([0.15, 0.15, 0.15, 0.9]),
+            border: Rgba([0.8, 0.8, 0.8, 0.9]),
+        },
+    }
+}
+
+/// Blue against orange, for protanopia and deuteranopia
+fn red_green() -> PaletteSet {
+    PaletteSet {
+        ally: team([0.2, 0.6, 1.0, 0.45], [0.5, 0.75, 1.0, 1.0]),
+        enemy: team([1.0, 0.5, 0.0, 0.45], [1.0, 0.65, 0.3, 1.0]),
+        players: [
+            Rgba([0.2, 0.5, 1.0, 1.0]),
+            Rgba([1.0, 0.55, 0.0, 1.0]),
+            Rgba([0.9, 0.9, 0.9, 1.0]),
+            Rgba([0.85, 0.35, 0.9, 1.0]),
+        ],
+        health: HealthColors {
+            high: Rgba([0.25, 0.6, 1.0, 1.0]),
+            mid: Rgba([1.0, 0.85, 0.3, 1.0]),
+            low: Rgba([1.0, 0.45, 0.0, 1.0]),
+            ..standard().health
+        },
+        ..standard()
+    }
+}
+
+/// Teal against red and pink, for tritanopia
+fn blue_yellow() -> PaletteSet {
+    PaletteSet {
+        player: team([1.0, 0.4, 0.7, 0.5], [1.0, 0.55, 0.8, 1.0]),
+        ally: team([0.0, 0.8, 0.8, 0.45], [0.4, 0.9, 0.9, 1.0]),
+        enemy: team([1.0, 0.25, 0.25, 0.45], [1.0, 0.5, 0.5, 1.0]),
+        players: [
+            Rgba([0.0, 0.75, 0.75, 1.0]),
+            Rgba([1.0, 0.25, 0.25, 1.0]),
+            Rgba([0.95, 0.95, 0.95, 1.0]),
+            Rgba([1.0, 0.45, 0.75, 1.0]),
+        ],
+        health: HealthColors {
+            high: Rgba([0.0, 0.8, 0.7, 1.0]),
+            mid: Rgba([1.0, 0.55, 0.75, 1.0]),
+            low: Rgba([1.0, 0.2, 0.2, 1.0]),
+            ..standard().health
+        },
+        ..standard()
+    }
+}
+
+impl Palette {
+    /// The built-in palette with `palette.json`, then `theme`, laid over it
+    pub fn load(theme: &str) -> Self {
+        let path = mods::resolve(Self::file_path());
+        let palette = storage::read_overrides(&path, Self::default()).unwrap_or_else(|e| {
+            crash::log(format!("Failed to read palette, using defaults: {}", e));
+            Self::default()
+        });
+        if theme.is_empty() {
+            return palette;
+        }
+
+        let path = Self::theme_path(theme);
+        if !path.exists() {
+            crash::log(format!("No theme called {}", theme));
+            return palette;
+        }
+        storage::read_overrides(&path, palette.clone()).unwrap_or_else(|e| {
+            crash::log(format!("Failed to read theme {}: {}", theme, e));
+            palette
+        })
+    }
+
+    pub fn set(&self, mode: ColorblindMode) -> PaletteSet {
+        match mode {
+            ColorblindMode::None => self.standard,
+            ColorblindMode::Protanopia => self.protanopia,
+            ColorblindMode::Deuteranopia => self.deuteranopia,
+            ColorblindMode::Tritanopia => self.tritanopia,
+        }
+    }
+
+    /// Names of the themes in the themes folder, sorted
+    pub fn themes() -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(Self::themes_directory()) else {
+            return Vec::new();
+        };
+        let mut themes: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .collect();
+        themes.sort();
+        themes
+    }
+
+    fn file_path() -> PathBuf {
+        balance::balance_directory().join("palette.json")
+    }
+
+    fn themes_directory() -> PathBuf {
+        balance::balance_directory().join("themes")
+    }
+
+    fn theme_path(theme: &str) -> PathBuf {
+        Self::themes_directory().join(format!("{}.json", theme))
+    }
+}
+
+// Loaded for the configured mode and theme on first use
+static ACTIVE: Mutex<Option<PaletteSet>> = Mutex::new(None);
+
+/// The colours in effect
+pub fn active() -> PaletteSet {
+    let Ok(mut active) = ACTIVE.lock() else {
+        return standard();
+    };
+    *active.get_or_insert_with(|| {
+        let config = GameConfig::load();
+        Palette::load(&config.palette_theme).set(config.colorblind_mode)
+    })
+}
+
+/// Pick the palette up again from `config`, after it or the files changed
+pub fn reload(config: &GameConfig) {
+    let palette = Palette::load(&config.palette_theme).set(config.colorblind_mode);
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = Some(palette);
+    }
+}
+
+/// The "Palette" settings page: colourblind mode and theme
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Palette",
+            entries: vec![
+                SettingsEntry {
+                    label: |config| {
+                        let theme = match config.palette_theme.as_str() {
+                            "" => "DEFAULT",
+                            theme => theme,
+                        };
+                        format!("Theme: {}", theme)
+                    },
+                    activate: |config| {
+                        // Default, then each theme in the folder
+                        let themes = Palette::themes();
+                        let current = themes.iter().position(|name| *name == config.palette_theme);
+                        config.palette_theme = match current {
+                            None => themes.first().cloned().unwrap_or_default(),
+                            Some(index) => themes.get(index + 1).cloned().unwrap_or_default(),
+                        };
+                        reload(config);
+                    },
+                },
+            ],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_override_only_what_they_set() {
+        let mut value = serde_json::to_value(Palette::default()).unwrap();
+        let theme = serde_json::json!({
+            "standard": { "enemy": { "glow": [0.0, 0.0, 1.0, 1.0] } }
+        });
+        storage::overlay(&mut value, theme);
+
+        let themed: Palette = serde_json::from_value(value).unwrap();
+        assert_eq!(themed.standard.enemy.glow, Rgba([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(themed.standard.enemy.nametag, standard().enemy.nametag);
+        assert_eq!(themed.tritanopia, blue_yellow());
+    }
+
+    #[test]
+    fn colorblind_sets_keep_teams_apart() {
+        let palette = Palette::default();
+        for mode in ColorblindMode::ALL {
+            let set = palette.set(mode);
+            assert_ne!(set.ally.glow, set.enemy.glow);
+            assert_ne!(set.health.high, set.health.low);
+        }
+        assert_ne!(palette.set(ColorblindMode::Deuteranopia), palette.standard);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::data::{ledger, storage};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Bumped whenever the bundle layout changes; newer bundles are refused
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Extension of exported bundles; the contents are plain JSON
+pub const BUNDLE_EXTENSION: &str = "bvsave";
+
+/// Data folders that travel in a bundle: save slots, per-character mastery,
+/// the shop, settings, control profiles, achievements, skill trees,
+/// challenges and the account level. Replays stay behind.
+const BUNDLED_DIRECTORIES: [&str; 10] = [
+    "saves",
+    "mastery",
+    "shop",
+    "config",
+    "controls",
+    "audit",
+    "achievements",
+    "skill_tree",
+    "challenges",
+    "account",
+];
+
+thread_local! {
+    /// A folder standing in for the data directory on this thread, so a
+    /// simulated run or a test plays on a fresh profile next to the real one
+    static DATA_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Everything needed to carry progression to another machine, as one file
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveBundle {
+    pub bundle_version: u32,
+    pub game_version: String,
+    pub exported_at: u64,
+    /// Data-folder-relative path (always `/`-separated) to file contents
+    pub files: BTreeMap<String, serde_json::Value>,
+}
+
+impl SaveBundle {
+    /// Gather every bundled data file into a bundle
+    pub fn collect() -> Result<Self, String> {
+        let base = Self::data_directory();
+        let mut files = BTreeMap::new();
+
+        for dir in BUNDLED_DIRECTORIES {
+            let Ok(entries) = fs::read_dir(base.join(dir)) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+
+                let json = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let value = serde_json::from_str(&json)
+                    .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                files.insert(format!("{}/{}", dir, name), value);
+            }
+        }
+
+        Ok(Self {
+            bundle_version: BUNDLE_VERSION,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            files,
+        })
+    }
+
+    /// Write a bundle of the current data to the exports folder, returning its path
+    pub fn export() -> Result<PathBuf, String> {
+        let bundle = Self::collect()?;
+        let dir = Self::export_directory();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+        let path = dir.join(format!("bas-veeg-arc_{}.{}", bundle.exported_at, BUNDLE_EXTENSION));
+        let json = serde_json::to_string_pretty(&bundle)
+            .map_err(|e| format!("Failed to serialize save bundle: {}", e))?;
+
+        fs::write(&path, json).map_err(|e| format!("Failed to write save bundle: {}", e))?;
+        Ok(path)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read save bundle: {}", e))?;
+        Self::from_json(&json)
+    }
+
+    /// Parse a bundle, refusing ones from a newer game
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let bundle: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse save bundle: {}", e))?;
+
+        if bundle.bundle_version > BUNDLE_VERSION {
+            return Err(format!(
+                "Save bundle is from a newer version of the game ({})",
+                bundle.game_version
+            ));
+        }
+
+        Ok(bundle)
+    }
+
+    /// When a bundled file last changed, in seconds since the epoch; 0 if there
+    /// are none
+    pub fn modified_at() -> u64 {
+        let base = Self::data_directory();
+        BUNDLED_DIRECTORIES
+            .iter()
+            .filter_map(|dir| fs::read_dir(base.join(dir)).ok())
+            .flat_map(|entries| entries.flatten())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+            .filter_map(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since| since.as_secs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Most recently modified bundle in the exports folder, which is where
+    /// bundles copied over from another machine should be dropped
+    pub fn latest_export() -> Option<PathBuf> {
+        fs::read_dir(Self::export_directory())
+            .ok()?
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == BUNDLE_EXTENSION)
+            })
+            .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+            .map(|entry| entry.path())
+    }
+
+    /// Overwrite local data with the bundle's files, returning how many were
+    /// written. Every path is validated before anything is touched.
+    pub fn apply(&self) -> Result<usize, String> {
+        let base = Self::data_directory();
+        let mut targets = Vec::with_capacity(self.files.len());
+
+        for (name, value) in &self.files {
+            let relative = Self::checked_path(name)
+                .ok_or_else(|| format!("Save bundle contains an invalid path: {}", name))?;
+            targets.push((base.join(relative), value));
+        }
+
+        for (path, value) in &targets {
+            let json = serde_json::to_string_pretty(value)
+                .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+            storage::write_atomic(path, &json)?;
+        }
+        // The bundle's balances come with their own audit log
+        ledger::reload();
+
+        Ok(targets.len())
+    }
+
+    /// `<bundled dir>/<file>.json` with no way out of the data folder
+    fn checked_path(name: &str) -> Option<PathBuf> {
+        let (dir, file) = name.split_once('/')?;
+        let file_path = Path::new(file);
+        let mut components = file_path.components();
+        let plain_file = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+
+        (BUNDLED_DIRECTORIES.contains(&dir)
+            && plain_file
+            && file_path.extension().is_some_and(|ext| ext == "json"))
+        .then(|| Path::new(dir).join(file_path))
+    }
+
+    fn export_directory() -> PathBuf {
+        Self::data_directory().join("exports")
+    }
+
+    /// Keep this thread's saves, shop, ledger and tuning tables under `root`,
+    /// or back in the data directory with `None`
+    pub fn set_data_root(root: Option<PathBuf>) {
+        DATA_ROOT.with(|current| *current.borrow_mut() = root);
+    }
+
+    pub fn data_root() -> Option<PathBuf> {
+        DATA_ROOT.with(|current| current.borrow().clone())
+    }
+
+    pub fn data_directory() -> PathBuf {
+        if let Some(root) = Self::data_root() {
+            root
+        } else if cfg!(target_os = "windows") {
+            PathBuf::from(std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string()))
+                .join("BasVeegArc")
+        } else if cfg!(target_os = "macos") {
+            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+                .join("Library")
+                .join("Application Support")
+                .join("BasVeegArc")
+        } else {
+            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+                .join(".local")
+                .join("share")
+                .join("bas-veeg-arc")
+        }
+    }
+}
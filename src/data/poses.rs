@@ -0,0 +1,306 @@
+use crate::data::characters::CharacterId;
+use serde::{Deserialize, Serialize};
+
+/// Mastery levels that unlock each character's alternate intro and victory pose
+pub const ALT_INTRO_LEVEL: u32 = 5;
+pub const ALT_VICTORY_LEVEL: u32 = 10;
+
+/// How long a pose is held before play continues
+pub const POSE_DURATION: f32 = 2.5;
+
+/// Body animation a pose plays on the character's sprite sheet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoseMotion {
+    Taunt,
+    Cheer,
+    Guard,
+    Strike,
+}
+
+pub struct PoseVariant {
+    pub name: &'static str,
+    pub motion: PoseMotion,
+    pub dutch: &'static str,
+    pub english: &'static str,
+}
+
+/// Default and alternate (mastery-unlocked) poses for one character
+pub struct CharacterPoses {
+    pub intros: [PoseVariant; 2],
+    pub victories: [PoseVariant; 2],
+}
+
+const fn pose(
+    name: &'static str,
+    motion: PoseMotion,
+    dutch: &'static str,
+    english: &'static str,
+) -> PoseVariant {
+    PoseVariant {
+        name,
+        motion,
+        dutch,
+        english,
+    }
+}
+
+const BAS: CharacterPoses = CharacterPoses {
+    intros: [
+        pose(
+            "Come At Me",
+            PoseMotion::Taunt,
+            "Kom dan! Ik veeg niks!",
+            "Come on! I'm not sweeping anything!",
+        ),
+        pose(
+            "No Broom",
+            PoseMotion::Guard,
+            "Bezem? Nooit van gehoord.",
+            "Broom? Never heard of it.",
+        ),
+    ],
+    victories: [
+        pose("Winter Arc", PoseMotion::Cheer, "Winter arc, bro!", "Winter arc, bro!"),
+        pose(
+            "Clean Sweep",
+            PoseMotion::Strike,
+            "Veeg dat maar zelf op.",
+            "Sweep that up yourself.",
+        ),
+    ],
+};
+
+const BERKAY: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("They'll See", PoseMotion::Taunt, "Jullie gaan zien.", "You'll see."),
+        pose("Hair Check", PoseMotion::Guard, "Eerst m'n haar goed.", "Hair first, then fight."),
+    ],
+    victories: [
+        pose("Too Easy", PoseMotion::Cheer, "Te makkelijk, bro.", "Too easy, bro."),
+        pose(
+            "Tell Your Mum",
+            PoseMotion::Strike,
+            "Zeg het maar tegen je moeder.",
+            "Go tell your mum.",
+        ),
+    ],
+};
+
+const GEFFERINHO: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Kick-off", PoseMotion::Taunt, "Gefferinho is hier!", "Gefferinho is here!"),
+        pose("Samba", PoseMotion::Strike, "Samba op je gezicht.", "Samba on your face."),
+    ],
+    victories: [
+        pose("Golazo", PoseMotion::Cheer, "Golazo!", "Golazo!"),
+        pose(
+            "Like On The Pitch",
+            PoseMotion::Taunt,
+            "Net als op het veld.",
+            "Just like on the pitch.",
+        ),
+    ],
+};
+
+const HADI: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Aina", PoseMotion::Guard, "Aina broeg...", "Always, bro..."),
+        pose(
+            "Already Here",
+            PoseMotion::Taunt,
+            "Rustig, ik ben er al.",
+            "Relax, I'm already here.",
+        ),
+    ],
+    victories: [
+        pose("They Saw", PoseMotion::Cheer, "Ze hebben het gezien.", "They've seen it."),
+        pose("Pure Gold", PoseMotion::Strike, "Goud, zoals altijd.", "Gold, as always."),
+    ],
+};
+
+const NITIN: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Barras Ready", PoseMotion::Taunt, "Barras klaar.", "Barras ready."),
+        pose("Heating Up", PoseMotion::Strike, "Het wordt heet hier.", "It's getting hot in here."),
+    ],
+    victories: [
+        pose("Burned Out", PoseMotion::Cheer, "Uitgebrand!", "Burned out!"),
+        pose("Spicy", PoseMotion::Taunt, "Pittig, hè?", "Spicy, huh?"),
+    ],
+};
+
+const LUCA: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("The Plan", PoseMotion::Guard, "Ik heb een plan.", "I've got a plan."),
+        pose("Step One", PoseMotion::Taunt, "Stap één: winnen.", "Step one: win."),
+    ],
+    victories: [
+        pose("Plan Complete", PoseMotion::Cheer, "Plan geslaagd.", "Plan complete."),
+        pose("On Schedule", PoseMotion::Guard, "Precies op schema.", "Right on schedule."),
+    ],
+};
+
+const PALABABA: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Baba Arrives", PoseMotion::Guard, "Baba is gekomen.", "Baba has arrived."),
+        pose("Respect", PoseMotion::Taunt, "Respecteer je ouderen.", "Respect your elders."),
+    ],
+    victories: [
+        pose("Baba's Way", PoseMotion::Cheer, "Zo doet Baba dat.", "That's how Baba does it."),
+        pose("Homework", PoseMotion::Strike, "Ga je huiswerk maken.", "Go do your homework."),
+    ],
+};
+
+const FUFINHO: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Hot Fufu", PoseMotion::Taunt, "De fufu is warm!", "The fufu is hot!"),
+        pose("Who's Hungry", PoseMotion::Guard, "Wie wil er een bord?", "Who wants a plate?"),
+    ],
+    victories: [
+        pose("Eaten Up", PoseMotion::Cheer, "Opgegeten!", "Eaten up!"),
+        pose("Seconds", PoseMotion::Taunt, "Nog een portie?", "Another serving?"),
+    ],
+};
+
+const EFEABI: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Lahmacun Time", PoseMotion::Taunt, "Lahmacun tijd.", "Lahmacun time."),
+        pose("Extra Onions", PoseMotion::Guard, "Met extra uien.", "With extra onions."),
+    ],
+    victories: [
+        pose("Paid In Full", PoseMotion::Cheer, "Afgerekend, abi.", "Paid in full, abi."),
+        pose("Lemon Squeeze", PoseMotion::Strike, "Citroen erop!", "Squeeze of lemon!"),
+    ],
+};
+
+const JAD: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Where's KFC", PoseMotion::Taunt, "Waar is de KFC?", "Where's the KFC?"),
+        pose(
+            "Hungry",
+            PoseMotion::Strike,
+            "Ik heb honger. Slecht nieuws voor jou.",
+            "I'm hungry. Bad news for you.",
+        ),
+    ],
+    victories: [
+        pose("Empty Bucket", PoseMotion::Cheer, "Bucket leeg!", "Bucket empty!"),
+        pose("Extra Crispy", PoseMotion::Taunt, "Extra krokant.", "Extra crispy."),
+    ],
+};
+
+const UMUT: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Boss Fight", PoseMotion::Guard, "Boss fight gestart.", "Boss fight started."),
+        pose("Better Gear", PoseMotion::Taunt, "Ik heb betere gear.", "I've got better gear."),
+    ],
+    victories: [
+        pose("Loot", PoseMotion::Cheer, "De loot is van mij.", "The loot is mine."),
+        pose("Achievement", PoseMotion::Strike, "Achievement unlocked.", "Achievement unlocked."),
+    ],
+};
+
+const KEIZER_BOM_TAHA: CharacterPoses = CharacterPoses {
+    intros: [
+        pose(
+            "Take The Field",
+            PoseMotion::Guard,
+            "De Keizer op het slagveld!",
+            "The Keizer takes the field!",
+        ),
+        pose("Air Support", PoseMotion::Taunt, "Luchtsteun onderweg.", "Air support inbound."),
+    ],
+    victories: [
+        pose("Mission Complete", PoseMotion::Cheer, "Missie voltooid.", "Mission complete."),
+        pose("Back To Base", PoseMotion::Guard, "Terug naar de basis.", "Back to base."),
+    ],
+};
+
+const GOON_LORD_TOBESE: CharacterPoses = CharacterPoses {
+    intros: [
+        pose("Strong Milk", PoseMotion::Taunt, "Melk maakt sterk.", "Milk makes you strong."),
+        pose("Awakening", PoseMotion::Guard, "De Goon Lord ontwaakt.", "The Goon Lord awakens."),
+    ],
+    victories: [
+        pose(
+            "Empty Glass",
+            PoseMotion::Cheer,
+            "Glas leeg, jij ook.",
+            "Glass empty, and so are you.",
+        ),
+        pose("Another Litre", PoseMotion::Strike, "Nog een liter.", "Another litre."),
+    ],
+};
+
+pub fn character_poses(id: CharacterId) -> &'static CharacterPoses {
+    match id {
+        CharacterId::Bas => &BAS,
+        CharacterId::Berkay => &BERKAY,
+        CharacterId::Gefferinho => &GEFFERINHO,
+        CharacterId::Hadi => &HADI,
+        CharacterId::Nitin => &NITIN,
+        CharacterId::Luca => &LUCA,
+        CharacterId::PalaBaba => &PALABABA,
+        CharacterId::Fufinho => &FUFINHO,
+        CharacterId::EfeAbi => &EFEABI,
+        CharacterId::Jad => &JAD,
+        CharacterId::Umut => &UMUT,
+        CharacterId::KeizerBomTaha => &KEIZER_BOM_TAHA,
+        CharacterId::GoonLordTobese => &GOON_LORD_TOBESE,
+    }
+}
+
+/// Which intro and victory variant the player picked on character select
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoseChoice {
+    pub intro: usize,
+    pub victory: usize,
+}
+
+impl PoseChoice {
+    /// The intro to play at `mastery_level`; locked alternates fall back to the default
+    pub fn intro(self, character: CharacterId, mastery_level: u32) -> &'static PoseVariant {
+        let slot = if mastery_level >= ALT_INTRO_LEVEL {
+            self.intro.min(1)
+        } else {
+            0
+        };
+        &character_poses(character).intros[slot]
+    }
+
+    pub fn victory(self, character: CharacterId, mastery_level: u32) -> &'static PoseVariant {
+        let slot = if mastery_level >= ALT_VICTORY_LEVEL {
+            self.victory.min(1)
+        } else {
+            0
+        };
+        &character_poses(character).victories[slot]
+    }
+}
+
+/// A pose being held on screen
+pub struct PosePlayback {
+    pub variant: &'static PoseVariant,
+    pub elapsed: f32,
+}
+
+impl PosePlayback {
+    pub fn new(variant: &'static PoseVariant) -> Self {
+        Self {
+            variant,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / POSE_DURATION).clamp(0.0, 1.0)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= POSE_DURATION
+    }
+}
@@ -1,11 +1,91 @@
+use crate::combat::versus_ai::CpuSettings;
+use crate::data::{CharacterId, GameConfig, SaveBundle};
+use crate::util::rng::{self, RngAudit};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Replay {
     pub metadata: ReplayMetadata,
     pub frames: Vec<ReplayFrame>,
     pub checksum: u32,
+    /// RNG draws made while recording, when the RNG audit is on
+    #[serde(skip)]
+    pub rng_audit: Option<RngAudit>,
+}
+
+impl Replay {
+    /// Write the replay to the replays folder, returning where it ended up
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let dir = Self::replay_directory();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create replay directory: {}", e))?;
+
+        let path = dir.join(format!("replay_{}.json", self.metadata.timestamp));
+        let json = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize replay: {}", e))?;
+
+        fs::write(&path, json).map_err(|e| format!("Failed to write replay: {}", e))?;
+        if let Some(audit) = &self.rng_audit {
+            audit.save(&Self::rng_log_path(&path))?;
+        }
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read replay {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse replay: {}", e))
+    }
+
+    /// The last replay saved, if there is one
+    pub fn latest() -> Option<PathBuf> {
+        fs::read_dir(Self::replay_directory())
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .max_by_key(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+    }
+
+    /// Re-run a saved replay through `simulate`, which checks every frame
+    /// against what was recorded and returns how many it got through. When
+    /// the replay has an RNG log beside it, the draws are diffed against that
+    /// too, to point at the roll that went first.
+    pub fn verify(
+        path: &Path,
+        simulate: impl FnOnce(&Replay) -> Result<usize, String>,
+    ) -> Result<String, String> {
+        let replay = Self::load(path)?;
+        let log = Self::rng_log_path(path);
+        let expected = if log.exists() { Some(RngAudit::load(&log)?) } else { None };
+
+        rng::start_audit();
+        let frames = simulate(&replay);
+        let actual = rng::finish_audit().unwrap_or_default();
+
+        if let Some(divergence) = expected.as_ref().and_then(|e| e.first_divergence(&actual)) {
+            return Err(format!("Replay is not deterministic: {}", divergence));
+        }
+        let frames = frames?;
+        Ok(match expected {
+            Some(expected) => {
+                format!("{} frames and {} RNG draws match", frames, expected.calls.len())
+            }
+            None => format!("{} frames match", frames),
+        })
+    }
+
+    /// `replay_<timestamp>.rng.log` beside `replay_<timestamp>.json`
+    fn rng_log_path(replay_path: &Path) -> PathBuf {
+        replay_path.with_extension("rng.log")
+    }
+
+    fn replay_directory() -> PathBuf {
+        SaveBundle::data_directory().join("replays")
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -16,6 +96,21 @@ pub struct ReplayMetadata {
     pub stage: String,
     pub characters: Vec<String>,
     pub winner: String,
+    /// Missing from replays recorded before they could be played again
+    #[serde(default)]
+    pub setup: Option<ReplaySetup>,
+}
+
+/// What it takes to play a versus replay again from its inputs
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReplaySetup {
+    pub characters: [CharacterId; 2],
+    /// Who was driving P2, when it wasn't a second player
+    pub cpu: Option<CpuSettings>,
+    /// The CPU's dice
+    pub seed: u64,
+    /// Fighters are kept inside this, the screen's width when recording
+    pub arena_width: f32,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -65,11 +160,14 @@ impl ReplayManager {
         }
     }
 
-    pub fn start_recording(&mut self, stage: String, characters: Vec<String>) {
+    pub fn start_recording(&mut self, stage: String, characters: Vec<String>, setup: ReplaySetup) {
         self.recording = true;
         self.playing = false;
         self.recording_frame = 0;
         self.frame_buffer.clear();
+        if GameConfig::load().rng_audit {
+            rng::start_audit();
+        }
 
         let metadata = ReplayMetadata {
             version: "1.0.0".to_string(),
@@ -81,12 +179,14 @@ impl ReplayManager {
             stage,
             characters,
             winner: String::new(),
+            setup: Some(setup),
         };
 
         self.current_replay = Some(Replay {
             metadata,
             frames: Vec::new(),
             checksum: 0,
+            rng_audit: None,
         });
     }
 
@@ -103,6 +203,7 @@ impl ReplayManager {
             replay.frames = self.frame_buffer.drain(..).collect();
             let checksum = Self::calculate_checksum(&replay.frames);
             replay.checksum = checksum;
+            replay.rng_audit = rng::finish_audit();
             Some(replay.clone())
         } else {
             None
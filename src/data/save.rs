@@ -1,7 +1,91 @@
+use crate::data::cosmetics::CosmeticsProgress;
+use crate::data::dialogue::DialogueMemory;
+use crate::data::ledger;
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::{
+    crash, storage, CharacterId, GameConfig, SaveBundle, ShopManager, UpgradeId, CHARACTERS,
+};
+use crate::progression::character_mastery::CharacterMastery;
+use crate::progression::practice::PracticeProgress;
+use crate::progression::roster::{self, DailyCounters};
+use crate::progression::{AchievementManager, ChallengeManager, PlayerAccount, SkillTreeManager};
+use crate::progression::winter_arc::{MetaUnlock, WinterArcProgress};
+use crate::ui::hud_layout::HudLayout;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Profiles there's room for, each in its own save slot
+pub const PROFILE_SLOTS: usize = 3;
+pub const DEFAULT_PROFILE: &str = "Player";
+/// Longest name the profiles screen takes
+pub const MAX_PROFILE_NAME: usize = 16;
+
+/// File name for `stem` in `profile`. The first profile keeps the names from
+/// before there were profiles, so existing progress stays with it.
+pub fn profile_file_name(stem: &str, profile: usize) -> String {
+    match profile {
+        0 => format!("{}.json", stem),
+        _ => format!("{}_{}.json", stem, profile),
+    }
+}
+
+/// Files besides the save slot that belong to `profile`
+fn profile_files(profile: usize) -> Vec<PathBuf> {
+    let mut files = vec![
+        ShopManager::shop_file_path(profile),
+        ledger::ledger_file_path(profile),
+        AchievementManager::file_path(profile),
+        SkillTreeManager::file_path(profile),
+        ChallengeManager::file_path(profile),
+        PlayerAccount::file_path(profile),
+    ];
+    let mastery = CHARACTERS.iter().map(|c| CharacterMastery::mastery_file_path(c.id, profile));
+    files.extend(mastery);
+    files
+}
+
+/// Make `slot` the active profile, picking up its last character. The
+/// profile being left has its summary brought up to date first, and the
+/// audit log is written out and dropped, since each profile keeps its own.
+pub fn switch_profile(slot: usize) -> Result<(), String> {
+    SaveManager::new().record_playtime();
+    ledger::save()?;
+    crate::data::set_active_profile(slot);
+    ledger::reload();
+
+    let mut saves = SaveManager::new();
+    if let Some(character) = saves.load_or_create(slot, DEFAULT_PROFILE)?.selected_character {
+        crate::data::set_selected_character(character);
+    }
+
+    let mut config = GameConfig::load();
+    config.active_profile = slot;
+    config.save()
+}
+
+/// Share of the active profile's progression done, from 0 to 1: shop
+/// upgrade ranks bought, Winter Arc unlocks and mastery levels, weighted evenly
+pub fn completion(winter_arc: &WinterArcProgress) -> f32 {
+    let shop = ShopManager::load();
+    let owned: u32 = UpgradeId::SOLD.iter().map(|&upgrade| shop.rank(upgrade)).sum();
+    let ranks: u32 = UpgradeId::SOLD.iter().map(|upgrade| upgrade.max_rank()).sum();
+    let upgrades = owned as f32 / ranks as f32;
+
+    let unlocked = MetaUnlock::ALL.iter().filter(|&&unlock| winter_arc.has(unlock)).count();
+    let unlocks = unlocked as f32 / MetaUnlock::ALL.len() as f32;
+
+    // Everyone starts at level 1 and tops out at 100
+    let levels: u32 = CHARACTERS
+        .iter()
+        .map(|character| CharacterMastery::load(character.id).level.saturating_sub(1))
+        .sum();
+    let mastery = levels as f32 / (99 * CHARACTERS.len()) as f32;
+
+    ((upgrades + unlocks + mastery) / 3.0).clamp(0.0, 1.0)
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SaveData {
     pub profile_name: String,
@@ -10,6 +94,71 @@ pub struct SaveData {
     pub statistics: GameStatistics,
     pub settings: GameSettings,
     pub timestamp: u64,
+    #[serde(default)]
+    pub winter_arc: WinterArcProgress,
+    #[serde(default)]
+    pub hud_layout: HudLayout,
+    /// Last worked out when playtime was recorded, from 0 to 1
+    #[serde(default)]
+    pub completion: f32,
+    /// Picked again when the profile is
+    #[serde(default)]
+    pub selected_character: Option<CharacterId>,
+    #[serde(default)]
+    pub practice: PracticeProgress,
+    #[serde(default)]
+    pub cosmetics: CosmeticsProgress,
+    #[serde(default)]
+    pub dialogue: DialogueMemory,
+    #[serde(default)]
+    pub daily: DailyCounters,
+}
+
+impl Versioned for SaveData {
+    const MIGRATIONS: &'static [Migration] =
+        &[migrate::from_unversioned, add_practice, add_cosmetics, add_dialogue, add_roster];
+}
+
+/// Version 2 tracks how far runs have got, for practice
+fn add_practice(save: &mut Value) -> Result<(), String> {
+    let fields = save.as_object_mut().ok_or("save isn't a JSON object")?;
+    fields
+        .entry("practice")
+        .or_insert_with(|| serde_json::json!({ "reached": [] }));
+    Ok(())
+}
+
+/// Version 3 keeps bought cosmetics and each character's loadout
+fn add_cosmetics(save: &mut Value) -> Result<(), String> {
+    let fields = save.as_object_mut().ok_or("save isn't a JSON object")?;
+    fields
+        .entry("cosmetics")
+        .or_insert_with(|| serde_json::json!({ "owned": [], "loadouts": [] }));
+    Ok(())
+}
+
+/// Version 4 remembers story choices, flags and endings seen
+fn add_dialogue(save: &mut Value) -> Result<(), String> {
+    let fields = save.as_object_mut().ok_or("save isn't a JSON object")?;
+    fields
+        .entry("dialogue")
+        .or_insert_with(|| serde_json::json!({ "choices": {}, "flags": [], "endings": [] }));
+    Ok(())
+}
+
+/// Version 5 locks characters past the starters. Profiles from before could
+/// already pick anyone, so they keep the whole roster.
+fn add_roster(save: &mut Value) -> Result<(), String> {
+    let fields = save.as_object_mut().ok_or("save isn't a JSON object")?;
+    let unlocks = fields
+        .get_mut("unlocks")
+        .and_then(Value::as_object_mut)
+        .ok_or("save has no unlocks")?;
+    unlocks.insert("characters".to_string(), serde_json::json!(roster::everyone()));
+    fields
+        .entry("daily")
+        .or_insert_with(|| serde_json::json!({ "day": 0, "trial_runs": 0 }));
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -57,19 +206,47 @@ pub enum Difficulty {
     Extreme,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum ColorblindMode {
+    #[default]
     None,
     Protanopia,
     Deuteranopia,
     Tritanopia,
 }
 
+impl ColorblindMode {
+    pub const ALL: [ColorblindMode; 4] = [
+        ColorblindMode::None,
+        ColorblindMode::Protanopia,
+        ColorblindMode::Deuteranopia,
+        ColorblindMode::Tritanopia,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorblindMode::None => "OFF",
+            ColorblindMode::Protanopia => "PROTANOPIA",
+            ColorblindMode::Deuteranopia => "DEUTERANOPIA",
+            ColorblindMode::Tritanopia => "TRITANOPIA",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let current = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(current + 1) % Self::ALL.len()]
+    }
+}
+
 pub struct SaveManager {
     save_directory: PathBuf,
     current_save: Option<SaveData>,
     autosave_timer: f32,
     autosave_interval: f32,
+    /// Profile the unrecorded playtime belongs to
+    profile: usize,
+    /// Seconds played since playtime was last recorded
+    pending_playtime: f64,
 }
 
 impl SaveManager {
@@ -82,27 +259,13 @@ impl SaveManager {
             current_save: None,
             autosave_timer: 0.0,
             autosave_interval: 60.0,
+            profile: crate::data::get_active_profile(),
+            pending_playtime: 0.0,
         }
     }
 
     fn get_save_directory() -> PathBuf {
-        if cfg!(target_os = "windows") {
-            PathBuf::from(std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string()))
-                .join("BasVeegArc")
-                .join("saves")
-        } else if cfg!(target_os = "macos") {
-            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-                .join("Library")
-                .join("Application Support")
-                .join("BasVeegArc")
-                .join("saves")
-        } else {
-            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-                .join(".local")
-                .join("share")
-                .join("bas-veeg-arc")
-                .join("saves")
-        }
+        SaveBundle::data_directory().join("saves")
     }
 
     pub fn create_new_save(&mut self, profile_name: String) -> SaveData {
@@ -115,7 +278,7 @@ impl SaveManager {
                 difficulty: Difficulty::Normal,
             },
             unlocks: Unlocks {
-                characters: vec!["Bas".to_string()],
+                characters: roster::starter_names(),
                 stages: vec!["Art Room".to_string()],
                 music_tracks: vec!["Main Theme".to_string()],
                 gallery_items: Vec::new(),
@@ -137,10 +300,15 @@ impl SaveManager {
                 colorblind_mode: ColorblindMode::None,
                 input_buffer_window: 0.2,
             },
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            timestamp: now(),
+            winter_arc: WinterArcProgress::default(),
+            hud_layout: HudLayout::default(),
+            completion: 0.0,
+            selected_character: None,
+            practice: PracticeProgress::default(),
+            cosmetics: CosmeticsProgress::default(),
+            dialogue: DialogueMemory::default(),
+            daily: DailyCounters::default(),
         };
 
         self.current_save = Some(save.clone());
@@ -152,13 +320,8 @@ impl SaveManager {
             let filename = format!("save_{}.json", slot);
             let path = self.save_directory.join(filename);
 
-            match serde_json::to_string_pretty(save) {
-                Ok(json) => match fs::write(path, json) {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(format!("Failed to write save file: {}", e)),
-                },
-                Err(e) => Err(format!("Failed to serialize save data: {}", e)),
-            }
+            migrate::write_versioned(&path, save)
+                .map_err(|e| format!("Failed to write save file: {}", e))
         } else {
             Err("No save data to write".to_string())
         }
@@ -168,64 +331,126 @@ impl SaveManager {
         let filename = format!("save_{}.json", slot);
         let path = self.save_directory.join(filename);
 
-        match fs::read_to_string(path) {
-            Ok(json) => match serde_json::from_str::<SaveData>(&json) {
-                Ok(save) => {
-                    self.current_save = Some(save.clone());
-                    Ok(save)
-                }
-                Err(e) => Err(format!("Failed to deserialize save data: {}", e)),
-            },
-            Err(e) => Err(format!("Failed to read save file: {}", e)),
+        match migrate::read_versioned::<SaveData>(&path) {
+            Ok(save) => {
+                self.current_save = Some(save.clone());
+                Ok(save)
+            }
+            Err(e) => Err(format!("Failed to load save file: {}", e)),
         }
     }
 
+    /// Load the profile in `slot`, or start a new one there if the slot has
+    /// never been written. A slot that exists but won't load is an error, so
+    /// saving over it can't wipe the profile.
+    pub fn load_or_create(
+        &mut self,
+        slot: usize,
+        profile_name: &str,
+    ) -> Result<&mut SaveData, String> {
+        let path = self.save_directory.join(format!("save_{}.json", slot));
+        if path.exists() || storage::backup_path(&path).exists() {
+            self.load_from_slot(slot)?;
+        } else {
+            self.create_new_save(profile_name.to_string());
+        }
+        self.current_save.as_mut().ok_or_else(|| "No save data loaded".to_string())
+    }
+
     pub fn delete_slot(&self, slot: usize) -> Result<(), String> {
         let filename = format!("save_{}.json", slot);
         let path = self.save_directory.join(filename);
 
+        // The backup goes too, or loading the slot would bring it back
+        let _ = fs::remove_file(storage::backup_path(&path));
+        let _ = fs::remove_file(storage::temp_path(&path));
         match fs::remove_file(path) {
             Ok(_) => Ok(()),
             Err(e) => Err(format!("Failed to delete save file: {}", e)),
         }
     }
 
+    /// Wipe a profile: its save and every other file kept for it, so a new
+    /// profile in the slot starts from nothing. The active profile can't be
+    /// deleted out from under the game.
+    pub fn delete_profile(&self, slot: usize) -> Result<(), String> {
+        if slot == crate::data::get_active_profile() {
+            return Err("Switch to another profile before deleting this one".to_string());
+        }
+
+        for path in profile_files(slot) {
+            let _ = fs::remove_file(storage::backup_path(&path));
+            let _ = fs::remove_file(storage::temp_path(&path));
+            let _ = fs::remove_file(path);
+        }
+        self.delete_slot(slot)
+    }
+
     pub fn get_save_slots(&self) -> Vec<Option<SaveInfo>> {
-        let mut slots = vec![None; 3];
+        let mut slots = vec![None; PROFILE_SLOTS];
 
-        for i in 0..3 {
+        for (i, slot) in slots.iter_mut().enumerate() {
             let filename = format!("save_{}.json", i);
             let path = self.save_directory.join(&filename);
 
-            if path.exists() {
-                if let Ok(json) = fs::read_to_string(&path) {
-                    if let Ok(save) = serde_json::from_str::<SaveData>(&json) {
-                        slots[i] = Some(SaveInfo {
-                            slot: i,
-                            profile_name: save.profile_name,
-                            chapter: save.story_progress.current_chapter,
-                            playtime: save.statistics.total_playtime,
-                            timestamp: save.timestamp,
-                        });
-                    }
-                }
+            if let Ok(save) = migrate::read_versioned::<SaveData>(&path) {
+                *slot = Some(SaveInfo {
+                    slot: i,
+                    profile_name: save.profile_name,
+                    chapter: save.story_progress.current_chapter,
+                    playtime: save.statistics.total_playtime,
+                    timestamp: save.timestamp,
+                    completion: save.completion,
+                    selected_character: save.selected_character,
+                });
             }
         }
 
         slots
     }
 
+    /// Count playtime, recording it every autosave interval and as soon as
+    /// another profile is picked
     pub fn update(&mut self, dt: f32) {
         self.autosave_timer += dt;
+        self.pending_playtime += dt as f64;
 
-        if self.autosave_timer >= self.autosave_interval {
+        let switched = self.profile != crate::data::get_active_profile();
+        if switched || self.autosave_timer >= self.autosave_interval {
             self.autosave_timer = 0.0;
-            self.autosave();
+            self.record_playtime();
+        }
+    }
+
+    /// Add the unrecorded playtime to the profile it was played on and stamp
+    /// it as last played. While that profile is still the active one, its
+    /// completion and character are brought up to date too.
+    pub fn record_playtime(&mut self) {
+        let slot = self.profile;
+        let active = crate::data::get_active_profile();
+        let playtime = std::mem::take(&mut self.pending_playtime);
+        self.profile = active;
+
+        let save = match self.load_or_create(slot, DEFAULT_PROFILE) {
+            Ok(save) => save,
+            Err(e) => {
+                crash::log(format!("Failed to record playtime: {}", e));
+                return;
+            }
+        };
+        save.statistics.total_playtime += playtime;
+        save.timestamp = now();
+        if slot == active {
+            save.completion = completion(&save.winter_arc);
+            save.selected_character = Some(crate::data::get_selected_character());
+        }
+        if let Err(e) = self.save_to_slot(slot) {
+            crash::log(format!("Failed to record playtime: {}", e));
         }
     }
 
     pub fn autosave(&self) {
-        self.save_to_slot(0).ok();
+        self.save_to_slot(crate::data::get_active_profile()).ok();
     }
 
     pub fn get_current_save(&self) -> Option<&SaveData> {
@@ -244,4 +469,81 @@ pub struct SaveInfo {
     pub chapter: u32,
     pub playtime: f64,
     pub timestamp: u64,
+    pub completion: f32,
+    pub selected_character: Option<CharacterId>,
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every file under `dir`, however deep
+    fn files_under(dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .flat_map(|entry| match entry.path() {
+                path if path.is_dir() => files_under(&path),
+                path => vec![path],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn deleting_a_profile_takes_every_file_it_kept_with_it() {
+        let root = std::env::temp_dir().join("bas-veeg-arc-test-delete-profile");
+        let _ = fs::remove_dir_all(&root);
+        SaveBundle::set_data_root(Some(root.clone()));
+        let kept = crate::data::get_active_profile();
+        let slot = (kept + 1) % PROFILE_SLOTS;
+
+        let saves = SaveManager::new();
+        let stores = |profile: usize| {
+            let mut files = vec![
+                saves.save_directory.join(format!("save_{}.json", profile)),
+                ShopManager::shop_file_path(profile),
+                ledger::ledger_file_path(profile),
+                AchievementManager::file_path(profile),
+                SkillTreeManager::file_path(profile),
+                ChallengeManager::file_path(profile),
+                PlayerAccount::file_path(profile),
+            ];
+            files.extend(
+                CHARACTERS.iter().map(|c| CharacterMastery::mastery_file_path(c.id, profile)),
+            );
+            files
+        };
+        for profile in [kept, slot] {
+            for path in stores(profile) {
+                for file in [storage::backup_path(&path), storage::temp_path(&path), path] {
+                    fs::create_dir_all(file.parent().unwrap()).unwrap();
+                    fs::write(file, "{}").unwrap();
+                }
+            }
+        }
+        let before = files_under(&root).len();
+
+        saves.delete_profile(slot).unwrap();
+        let mut left = files_under(&root);
+        let mut expected: Vec<PathBuf> = stores(kept)
+            .into_iter()
+            .flat_map(|path| [storage::backup_path(&path), storage::temp_path(&path), path])
+            .collect();
+        SaveBundle::set_data_root(None);
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(left.len(), before / 2);
+        left.sort();
+        expected.sort();
+        assert_eq!(left, expected);
+    }
 }
@@ -1,8 +1,37 @@
+use crate::data::crash;
+use crate::data::ledger::{self, Account};
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{GameConfig, SaveBundle};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// Each rank of an upgrade costs this much more than the one before
+pub const RANK_COST_GROWTH: f32 = 1.6;
+/// Each rank is worth this share of what the one before it gave
+pub const RANK_FALLOFF: f32 = 0.6;
+/// Share of a rank's price that selling it gives back
+pub const REFUND_SHARE: f32 = 0.5;
+
+/// Price of the rank after `rank` for an upgrade whose first rank costs `base`
+pub fn rank_cost(base: u32, rank: u32) -> u32 {
+    (base as f32 * RANK_COST_GROWTH.powi(rank as i32)).round() as u32
+}
+
+/// `cost` after a haggled `discount` share comes off
+pub fn haggled(cost: u32, discount: f32) -> u32 {
+    (cost as f32 * (1.0 - discount.clamp(0.0, 1.0))).round() as u32
+}
+
+/// What `ranks` ranks add up to when the first is worth `first`
+pub fn stacked_value(first: f32, ranks: u32) -> f32 {
+    (0..ranks).map(|rank| first * RANK_FALLOFF.powi(rank as i32)).sum()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum UpgradeId {
     AttackBoost,
     HealthBoost,
@@ -12,107 +41,400 @@ pub enum UpgradeId {
     LifeSteal,
     DamageReduction,
     CriticalChance,
+    // Winter Arc run upgrades; only ever drawn during a run, never sold
+    WarmBlanket,
+    ColdBlood,
+    FrostGuard,
+}
+
+impl UpgradeId {
+    /// Upgrades the shop sells between waves
+    pub const SOLD: [UpgradeId; 8] = [
+        UpgradeId::AttackBoost,
+        UpgradeId::HealthBoost,
+        UpgradeId::SpeedBoost,
+        UpgradeId::CooldownReduction,
+        UpgradeId::AbilityDuration,
+        UpgradeId::LifeSteal,
+        UpgradeId::DamageReduction,
+        UpgradeId::CriticalChance,
+    ];
+
+    /// Ranks the shop sells; run upgrades only come in one
+    pub fn max_rank(self) -> u32 {
+        if Self::SOLD.contains(&self) {
+            3
+        } else {
+            1
+        }
+    }
+
+    /// Short name for lists, like the practice loadout
+    pub fn label(self) -> &'static str {
+        match self {
+            UpgradeId::AttackBoost => "Attack Boost",
+            UpgradeId::HealthBoost => "Health Boost",
+            UpgradeId::SpeedBoost => "Speed Boost",
+            UpgradeId::CooldownReduction => "Cooldown Reduction",
+            UpgradeId::AbilityDuration => "Ability Duration",
+            UpgradeId::LifeSteal => "Life Steal",
+            UpgradeId::DamageReduction => "Damage Reduction",
+            UpgradeId::CriticalChance => "Critical Chance",
+            UpgradeId::WarmBlanket => "Warm Blanket",
+            UpgradeId::ColdBlood => "Cold Blood",
+            UpgradeId::FrostGuard => "Frost Guard",
+        }
+    }
+}
+
+/// Single-run items: bought between waves, gone when the run ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Consumable {
+    /// Stands P1 back up instead of ending the run
+    ReviveToken,
+    /// Stuns the enemies around P1
+    SmokeBomb,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Consumable {
+    pub const ALL: [Consumable; 2] = [Consumable::ReviveToken, Consumable::SmokeBomb];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Consumable::ReviveToken => "Revive Token",
+            Consumable::SmokeBomb => "Smoke Bomb",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Consumable::ReviveToken => "Get back up once when you go down this run.",
+            Consumable::SmokeBomb => "Press G to stun everyone around you.",
+        }
+    }
+
+    pub fn cost(self) -> u32 {
+        match self {
+            Consumable::ReviveToken => 250,
+            Consumable::SmokeBomb => 60,
+        }
+    }
+
+    /// Most a run can carry at once
+    pub fn max_carried(self) -> u32 {
+        match self {
+            Consumable::ReviveToken => 1,
+            Consumable::SmokeBomb => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ShopData {
     #[serde(default)]
     pub currency: u32,
+    /// Ranks bought of each upgrade; anything missing isn't owned
     #[serde(default)]
-    pub owned_upgrades: Vec<UpgradeId>,
+    pub ranks: BTreeMap<UpgradeId, u32>,
 }
 
-impl Default for ShopData {
-    fn default() -> Self {
-        Self {
-            currency: 0,
-            owned_upgrades: Vec::new(),
-        }
-    }
+impl Versioned for ShopData {
+    const MIGRATIONS: &'static [Migration] = &[migrate::from_unversioned, rank_owned_upgrades];
+}
+
+/// Version 2 buys upgrades in ranks; each one owned before is rank 1
+fn rank_owned_upgrades(shop: &mut Value) -> Result<(), String> {
+    let fields = shop.as_object_mut().ok_or("shop data isn't a JSON object")?;
+    let owned = fields.remove("owned_upgrades").unwrap_or_else(|| Value::Array(Vec::new()));
+    let owned = owned.as_array().ok_or("owned_upgrades isn't a list")?;
+    let ranks = owned
+        .iter()
+        .map(|upgrade| {
+            let name = upgrade.as_str().ok_or("owned upgrade isn't a name")?;
+            Ok((name.to_string(), Value::from(1)))
+        })
+        .collect::<Result<serde_json::Map<_, _>, String>>()?;
+    fields.insert("ranks".to_string(), Value::Object(ranks));
+    Ok(())
 }
 
 pub struct ShopManager {
     data: ShopData,
     path: PathBuf,
+    /// Consumables bought for the run in progress; never saved
+    carried: HashMap<Consumable, u32>,
+    /// Why the shop file didn't load, if it didn't; it's kept as it is
+    load_error: Option<String>,
 }
 
 impl ShopManager {
     pub fn load() -> Self {
-        let path = Self::shop_file_path();
+        let path = Self::shop_file_path(crate::data::get_active_profile());
 
         if let Some(dir) = path.parent() {
             let _ = fs::create_dir_all(dir);
         }
 
-        let data = if path.exists() {
-            fs::read_to_string(&path)
-                .ok()
-                .and_then(|json| serde_json::from_str(&json).ok())
-                .unwrap_or_default()
-        } else {
-            ShopData::default()
+        let (mut data, load_error) = match migrate::read_if_exists::<ShopData>(&path) {
+            Ok(data) => (data.unwrap_or_default(), None),
+            Err(e) => {
+                crash::log(e.clone());
+                (ShopData::default(), Some(e))
+            }
         };
+        if load_error.is_none() {
+            Self::check_unaudited_tokens(&mut data);
+        }
 
-        Self { data, path }
+        Self {
+            data,
+            path,
+            carried: HashMap::new(),
+            load_error,
+        }
     }
 
-    fn shop_file_path() -> PathBuf {
-        let base = if cfg!(target_os = "windows") {
-            PathBuf::from(std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string()))
-                .join("BasVeegArc")
-        } else if cfg!(target_os = "macos") {
-            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-                .join("Library")
-                .join("Application Support")
-                .join("BasVeegArc")
-        } else {
-            PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
-                .join(".local")
-                .join("share")
-                .join("bas-veeg-arc")
+    /// Flag a saved balance that doesn't match the audit log, and optionally
+    /// roll it back. A save from before the log existed becomes its opening
+    /// balance.
+    fn check_unaudited_tokens(data: &mut ShopData) {
+        let saved = data.currency as f64;
+        let Some(audited) = ledger::audited_balance(Account::Tokens) else {
+            ledger::reconcile(Account::Tokens, "Opening balance", 0.0, saved);
+            return;
         };
+        if audited == saved {
+            return;
+        }
 
-        base.join("shop").join("shop_data.json")
+        crash::log(format!(
+            "Arc Tokens changed outside the game: {} saved, {} audited",
+            saved, audited
+        ));
+        ledger::reconcile(Account::Tokens, "Unaudited change", audited, saved);
+        if GameConfig::load().rollback_unaudited_tokens {
+            data.currency = audited as u32;
+            ledger::reconcile(Account::Tokens, "Rolled back unaudited change", saved, audited);
+        }
+    }
+
+    pub fn shop_file_path(profile: usize) -> PathBuf {
+        SaveBundle::data_directory().join("shop").join(profile_file_name("shop_data", profile))
     }
 
     pub fn currency(&self) -> u32 {
         self.data.currency
     }
 
-    pub fn add_currency(&mut self, amount: u32) {
-        self.data.currency = self.data.currency.saturating_add(amount);
+    /// Grant tokens, refusing amounts the audit log considers impossible
+    pub fn add_currency(&mut self, amount: u32, source: &str) -> Result<(), String> {
+        let balance = ledger::audit(
+            Account::Tokens,
+            source,
+            amount as f64,
+            self.data.currency as f64,
+        )?;
+        self.data.currency = balance as u32;
+        Ok(())
     }
 
-    pub fn try_purchase(&mut self, upgrade: UpgradeId, cost: u32) -> bool {
-        if self.has_upgrade(upgrade) {
+    /// Take `cost` tokens for something other than an upgrade
+    pub fn spend_currency(&mut self, cost: u32, source: &str) -> Result<(), String> {
+        if self.data.currency < cost {
+            return Err(format!("Needs {} Arc Tokens, you have {}", cost, self.data.currency));
+        }
+        ledger::audit(Account::Tokens, source, -(cost as f64), self.data.currency as f64)?;
+        self.data.currency -= cost;
+        Ok(())
+    }
+
+    /// Buy the next rank of `upgrade`, whose first rank costs `base_cost`
+    pub fn try_purchase(&mut self, upgrade: UpgradeId, base_cost: u32) -> bool {
+        let rank = self.rank(upgrade);
+        if rank >= upgrade.max_rank() {
             return false;
         }
 
+        let cost = rank_cost(base_cost, rank);
         if self.data.currency < cost {
             return false;
         }
 
+        let source = format!("{:?} upgrade", upgrade);
+        let spent = -(cost as f64);
+        if ledger::audit(Account::Tokens, &source, spent, self.data.currency as f64).is_err() {
+            return false;
+        }
+
         self.data.currency -= cost;
-        self.data.owned_upgrades.push(upgrade);
+        self.data.ranks.insert(upgrade, rank + 1);
         true
     }
 
+    /// Sell back the top rank of `upgrade` for part of what it cost.
+    /// Returns the refund.
+    pub fn sell(&mut self, upgrade: UpgradeId, base_cost: u32) -> Result<u32, String> {
+        let rank = self.rank(upgrade);
+        if rank == 0 {
+            return Err(format!("{} isn't owned", upgrade.label()));
+        }
+
+        let refund = (rank_cost(base_cost, rank - 1) as f32 * REFUND_SHARE).round() as u32;
+        self.add_currency(refund, &format!("{:?} refund", upgrade))?;
+        if rank == 1 {
+            self.data.ranks.remove(&upgrade);
+        } else {
+            self.data.ranks.insert(upgrade, rank - 1);
+        }
+        Ok(refund)
+    }
+
+    pub fn rank(&self, upgrade: UpgradeId) -> u32 {
+        self.data.ranks.get(&upgrade).copied().unwrap_or(0)
+    }
+
     pub fn has_upgrade(&self, upgrade: UpgradeId) -> bool {
-        self.data
-            .owned_upgrades
-            .iter()
-            .copied()
-            .any(|u| u == upgrade)
+        self.rank(upgrade) > 0
+    }
+
+    /// Every upgrade with at least one rank
+    pub fn owned_upgrades(&self) -> Vec<UpgradeId> {
+        self.data.ranks.keys().copied().collect()
+    }
+
+    /// Buy one of `item` for this run
+    pub fn buy_consumable(&mut self, item: Consumable, discount: f32) -> Result<(), String> {
+        let carried = self.carried(item);
+        if carried >= item.max_carried() {
+            return Err(format!("Can't carry more than {} {}", item.max_carried(), item.label()));
+        }
+        self.spend_currency(haggled(item.cost(), discount), item.label())?;
+        self.carried.insert(item, carried + 1);
+        Ok(())
+    }
+
+    /// Use up one of `item`, if there's one left
+    pub fn use_consumable(&mut self, item: Consumable) -> bool {
+        match self.carried.get_mut(&item) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
     }
 
-    pub fn owned_upgrades(&self) -> &[UpgradeId] {
-        &self.data.owned_upgrades
+    pub fn carried(&self, item: Consumable) -> u32 {
+        self.carried.get(&item).copied().unwrap_or(0)
     }
 
     pub fn save(&self) -> Result<(), String> {
-        let json = serde_json::to_string_pretty(&self.data)
-            .map_err(|e| format!("Failed to serialize shop data: {}", e))?;
+        migrate::check_writable(self.load_error.as_deref())?;
+        migrate::write_versioned(&self.path, &self.data)
+            .map_err(|e| format!("Failed to write shop data: {}", e))?;
+        ledger::save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A shop that never touches the real save
+    fn shop_with(currency: u32) -> ShopManager {
+        ShopManager {
+            data: ShopData {
+                currency,
+                ranks: BTreeMap::new(),
+            },
+            path: std::env::temp_dir().join("bas-veeg-arc-test-shop.json"),
+            carried: HashMap::new(),
+            load_error: None,
+        }
+    }
+
+    #[test]
+    fn purchase_spends_tokens_and_grants_the_upgrade() {
+        let mut shop = shop_with(300);
+        assert!(shop.try_purchase(UpgradeId::AttackBoost, 120));
+        assert_eq!(shop.currency(), 180);
+        assert!(shop.has_upgrade(UpgradeId::AttackBoost));
+    }
+
+    #[test]
+    fn ranks_cost_more_each_time_until_the_upgrade_is_maxed() {
+        let mut shop = shop_with(1000);
+        assert!(shop.try_purchase(UpgradeId::HealthBoost, 100));
+        assert!(shop.try_purchase(UpgradeId::HealthBoost, 100));
+        assert!(shop.try_purchase(UpgradeId::HealthBoost, 100));
+        assert!(!shop.try_purchase(UpgradeId::HealthBoost, 100), "maxed at rank 3");
+        assert_eq!(shop.currency(), 1000 - 100 - 160 - 256);
+        assert_eq!(shop.rank(UpgradeId::HealthBoost), 3);
+        assert_eq!(shop.owned_upgrades(), [UpgradeId::HealthBoost]);
+    }
+
+    #[test]
+    fn each_rank_is_worth_less_than_the_last() {
+        assert_eq!(stacked_value(10.0, 0), 0.0);
+        assert_eq!(stacked_value(10.0, 1), 10.0);
+        assert!((stacked_value(10.0, 3) - 19.6).abs() < 1e-4);
+    }
+
+    #[test]
+    fn selling_refunds_half_the_top_rank() {
+        let mut shop = shop_with(300);
+        assert!(shop.try_purchase(UpgradeId::AttackBoost, 100));
+        assert!(shop.try_purchase(UpgradeId::AttackBoost, 100));
+        assert_eq!(shop.sell(UpgradeId::AttackBoost, 100), Ok(80));
+        assert_eq!(shop.rank(UpgradeId::AttackBoost), 1);
+        assert_eq!(shop.sell(UpgradeId::AttackBoost, 100), Ok(50));
+        assert!(!shop.has_upgrade(UpgradeId::AttackBoost));
+        assert!(shop.sell(UpgradeId::AttackBoost, 100).is_err());
+        assert_eq!(shop.currency(), 300 - 260 + 130);
+    }
+
+    #[test]
+    fn consumables_are_capped_and_used_up() {
+        let mut shop = shop_with(1000);
+        assert!(shop.buy_consumable(Consumable::ReviveToken, 0.0).is_ok());
+        assert!(shop.buy_consumable(Consumable::ReviveToken, 0.0).is_err());
+        assert!(shop.use_consumable(Consumable::ReviveToken));
+        assert!(!shop.use_consumable(Consumable::ReviveToken));
+    }
+
+    #[test]
+    fn haggling_takes_a_share_off() {
+        assert_eq!(haggled(200, 0.25), 150);
+        assert_eq!(haggled(60, 0.0), 60);
+
+        let mut shop = shop_with(50);
+        assert!(shop.buy_consumable(Consumable::SmokeBomb, 0.0).is_err());
+        assert!(shop.buy_consumable(Consumable::SmokeBomb, 0.2).is_ok());
+        assert_eq!(shop.currency(), 2);
+    }
+
+    #[test]
+    fn owned_upgrades_from_before_ranks_become_rank_one() {
+        let mut old = serde_json::json!({ "currency": 5, "owned_upgrades": ["LifeSteal"] });
+        rank_owned_upgrades(&mut old).unwrap();
+        let data: ShopData = serde_json::from_value(old).unwrap();
+        assert_eq!(data.ranks.get(&UpgradeId::LifeSteal), Some(&1));
+        assert_eq!(data.currency, 5);
+    }
+
+    #[test]
+    fn purchase_without_enough_tokens_changes_nothing() {
+        let mut shop = shop_with(50);
+        assert!(!shop.try_purchase(UpgradeId::SpeedBoost, 80));
+        assert_eq!(shop.currency(), 50);
+        assert!(shop.owned_upgrades().is_empty());
+    }
 
-        fs::write(&self.path, json).map_err(|e| format!("Failed to write shop data: {}", e))
+    #[test]
+    fn impossible_grant_is_refused() {
+        let mut shop = shop_with(0);
+        assert!(shop.add_currency(80, "Wave cleared").is_ok());
+        assert!(shop.add_currency(1_000_000, "Wave cleared").is_err());
+        assert_eq!(shop.currency(), 80);
     }
 }
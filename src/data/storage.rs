@@ -0,0 +1,192 @@
+use crate::data::crash;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `<file>.tmp`, where a new version is written before it replaces `<file>`
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+/// `<file>.bak`, the previous good version of `<file>`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Replace `path` with `contents` so that a crash at any point leaves either
+/// the old or the new file in place, never a half-written one. The version
+/// being replaced is kept as a single rotating `.bak`.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let temp = temp_path(path);
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        // On disk before the rename, or a power cut could leave an empty file
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", temp.display(), e));
+    }
+
+    // Copy rather than move, so `path` itself is never missing
+    if path.exists() {
+        fs::copy(path, backup_path(path))
+            .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    }
+
+    fs::rename(&temp, path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to replace {}: {}", path.display(), e)
+    })?;
+
+    // Make the rename itself durable; not possible (or needed) on Windows
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Read JSON written by `write_atomic`, falling back to the backup if the
+/// main file is missing or unreadable
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let parse = |path: &Path| -> Result<T, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    };
+
+    parse(path).or_else(|error| {
+        let backup = backup_path(path);
+        if !backup.exists() {
+            return Err(error);
+        }
+
+        let restored = parse(&backup)?;
+        crash::log(format!("{}; restored from {}", error, backup.display()));
+        Ok(restored)
+    })
+}
+
+/// Fields a list item can be matched on when a file overrides one item
+const ITEM_KEYS: &[&str] = &["id", "map", "move_id", "property", "players", "rarity"];
+
+/// The built-in `defaults` with the tuning file at `path` laid over them, or
+/// just the defaults when there's no file. A file only holds what it changes,
+/// so defaults it doesn't mention follow the game as it's updated.
+pub fn read_overrides<T>(path: &Path, defaults: T) -> Result<T, String>
+where
+    T: Serialize + DeserializeOwned,
+{
+    if !path.exists() {
+        return Ok(defaults);
+    }
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let patch: Value = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+    let mut merged = serde_json::to_value(&defaults)
+        .map_err(|e| format!("Failed to serialize the defaults for {}: {}", path.display(), e))?;
+    overlay(&mut merged, patch);
+    serde_json::from_value(merged).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Lay `patch` over `base`. Objects merge field by field. Lists of objects
+/// merge item by item when their items name themselves by one of
+/// `ITEM_KEYS`, unique in the base list: a matching item overrides the
+/// built-in one and any other is added. Anything else is replaced outright.
+pub fn overlay(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (field, value) in patch {
+                match base.get_mut(&field) {
+                    Some(existing) => overlay(existing, value),
+                    None => {
+                        base.insert(field, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(patch)) => match item_key(base, &patch) {
+            Some(key) => {
+                for item in patch {
+                    match base.iter_mut().find(|existing| existing.get(key) == item.get(key)) {
+                        Some(existing) => overlay(existing, item),
+                        None => base.push(item),
+                    }
+                }
+            }
+            None => *base = patch,
+        },
+        (base, patch) => *base = patch,
+    }
+}
+
+/// The field items of both lists are matched on, if there is one
+fn item_key(base: &[Value], patch: &[Value]) -> Option<&'static str> {
+    ITEM_KEYS.iter().copied().find(|&key| {
+        let named = |item: &Value| item.get(key).is_some_and(|name| !name.is_object());
+        let unique = base
+            .iter()
+            .enumerate()
+            .all(|(i, item)| !base[..i].iter().any(|other| other.get(key) == item.get(key)));
+        !base.is_empty() && base.iter().all(named) && patch.iter().all(named) && unique
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overrides_change_only_what_they_mention() {
+        let mut table = json!({
+            "drop_chance": 0.35,
+            "tiers": [{"players": 2, "health": 1.25}, {"players": 3, "health": 1.5}],
+            "entries": [{"rarity": "Common", "drop": 5}, {"rarity": "Common", "drop": 15}],
+        });
+        overlay(
+            &mut table,
+            json!({
+                "tiers": [{"players": 3, "health": 2.0}, {"players": 4, "health": 3.0}],
+                "entries": [{"rarity": "Rare", "drop": 60}],
+            }),
+        );
+        assert_eq!(
+            table,
+            json!({
+                "drop_chance": 0.35,
+                "tiers": [
+                    {"players": 2, "health": 1.25},
+                    {"players": 3, "health": 2.0},
+                    {"players": 4, "health": 3.0},
+                ],
+                // Two items share a rarity, so it can't name one: the list is replaced
+                "entries": [{"rarity": "Rare", "drop": 60}],
+            })
+        );
+
+        let missing = std::env::temp_dir().join("bas-veeg-arc-test-no-overrides.json");
+        let _ = fs::remove_file(&missing);
+        assert_eq!(read_overrides(&missing, vec![1, 2]), Ok(vec![1, 2]));
+        assert!(!missing.exists());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::ecs::entity::EntityId;
+use crate::ecs::world::{Component, World};
+
+type Insert = Box<dyn FnOnce(&mut World, EntityId)>;
+
+enum Command {
+    Spawn(Vec<Insert>),
+    Destroy(EntityId),
+    Insert(EntityId, Insert),
+}
+
+/// Structural changes recorded while the world is borrowed (e.g. in the middle of a
+/// query) and applied in recording order at the next `World::flush`.
+#[derive(Default)]
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    /// Queue a new entity; attach its components with `with`
+    pub fn spawn(&mut self) -> EntityCommands<'_> {
+        self.queue.push(Command::Spawn(Vec::new()));
+        match self.queue.last_mut() {
+            Some(Command::Spawn(inserts)) => EntityCommands { inserts },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn destroy(&mut self, entity: EntityId) {
+        self.queue.push(Command::Destroy(entity));
+    }
+
+    #[allow(dead_code)] // Future use: attaching components to existing entities mid-query
+    pub fn add_component<T: Component>(&mut self, entity: EntityId, component: T) {
+        self.queue.push(Command::Insert(entity, insert(component)));
+    }
+
+    pub(super) fn apply(self, world: &mut World) {
+        for command in self.queue {
+            match command {
+                Command::Spawn(inserts) => {
+                    let entity = world.create_entity();
+                    for insert in inserts {
+                        insert(world, entity);
+                    }
+                }
+                Command::Destroy(entity) => world.destroy_entity(entity),
+                // Skip entities destroyed earlier in the same flush
+                Command::Insert(entity, insert) => {
+                    if world.is_alive(entity) {
+                        insert(world, entity);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Components for an entity queued with `Commands::spawn`
+pub struct EntityCommands<'a> {
+    inserts: &'a mut Vec<Insert>,
+}
+
+impl EntityCommands<'_> {
+    pub fn with<T: Component>(self, component: T) -> Self {
+        self.inserts.push(insert(component));
+        self
+    }
+}
+
+fn insert<T: Component>(component: T) -> Insert {
+    Box::new(move |world, entity| world.add_component(entity, component))
+}
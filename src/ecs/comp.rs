@@ -1,8 +1,12 @@
-use crate::combat::hitbox::Hitbox;
+use crate::combat::hitbox::{AttackHeight, BlockStance, Hitbox, SpecialType};
 use crate::combat::hurtbox::Hurtbox;
+use crate::data::CharacterId;
 use crate::ecs::{entity::EntityId, world::Component};
+use crate::render::map_system::SpawnPointKind;
+use crate::util::rng::{self, SeededRng};
 use bitflags::bitflags;
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug)]
 pub struct Transform {
@@ -64,6 +68,8 @@ pub struct Fighter {
     pub attack_recovery: f32,       // Recovery frames remaining
     pub is_blocking: bool,          // Is currently holding block
     pub parry_window: f32,          // Time left for perfect parry
+    pub block_stance: BlockStance,  // High (standing) or low (crouching) guard
+    pub next_attack_height: AttackHeight, // Height applied to the next attack's hitbox
 }
 
 impl Component for Fighter {}
@@ -80,7 +86,27 @@ pub struct Stamina {
 
 impl Component for Stamina {}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+impl Stamina {
+    /// Take `amount` off even when there isn't that much left, bottoming out
+    /// into exhaustion
+    pub fn drain(&mut self, amount: f32) {
+        self.current = (self.current - amount).max(0.0);
+        self.regen_delay_timer = self.regen_delay;
+        self.exhausted |= self.current <= 0.0;
+    }
+}
+
+/// A short burst of movement: the fighter slides along `direction` until the
+/// timer runs out
+#[derive(Clone, Debug)]
+pub struct Dash {
+    pub direction: Vec2,
+    pub timer: f32,
+}
+
+impl Component for Dash {}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum CharacterType {
     // Playable characters
     Bas,
@@ -104,6 +130,28 @@ pub enum CharacterType {
     Mees, // Boss that throws pita sirrachas
 }
 
+impl CharacterType {
+    /// Knockback resistance; impulses are divided by this
+    pub fn weight(self) -> f32 {
+        match self {
+            CharacterType::Bastiaan => 2.2,
+            CharacterType::KeizerBomTahaBoss => 1.9,
+            CharacterType::Mees => 1.6,
+            CharacterType::KeizerBomTaha => 1.3,
+            CharacterType::Coach => 1.3,
+            CharacterType::Chef => 1.2,
+            CharacterType::YigitBaba => 1.15,
+            CharacterType::Wolters => 1.0,
+            CharacterType::PrefectA | CharacterType::PrefectB => 0.9,
+            CharacterType::Librarian => 0.8,
+            CharacterType::Hadi => 1.05,
+            CharacterType::Gefferinho | CharacterType::Luca => 0.95,
+            CharacterType::Berkay => 0.9,
+            CharacterType::Bas | CharacterType::Nitin => 1.0,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FighterState {
     Idle,
@@ -124,6 +172,20 @@ pub enum FighterState {
     KnockedDown,
 }
 
+impl FighterState {
+    /// How long an attack swings for and what it hits with, before the
+    /// fighter's moveset changes either; `None` for states that aren't attacks
+    pub fn attack(self) -> Option<(f32, Hitbox)> {
+        match self {
+            FighterState::LightAttack => Some((0.25, Hitbox::new_light())),
+            FighterState::HeavyAttack => Some((0.4, Hitbox::new_heavy())),
+            FighterState::Special => Some((0.55, Hitbox::new_special(SpecialType::Paintbrush))),
+            FighterState::Super => Some((0.8, Hitbox::new_super())),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Team {
     Player,
@@ -144,17 +206,6 @@ impl Team {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct AnimationController {
-    pub current_animation: String,
-    pub frame: usize,
-    pub timer: f32,
-    pub frame_duration: f32,
-    pub looping: bool,
-}
-
-impl Component for AnimationController {}
-
 #[derive(Clone, Debug)]
 pub struct CollisionBox {
     pub offset: Vec2,
@@ -188,10 +239,73 @@ pub struct AIController {
     pub state_timer: f32,
     pub reaction_delay: f32,
     pub difficulty: f32,
+    pub reaction_scale: f32, // Multiplier on the delay between decisions
+    pub ability_rate: f32,   // Multiplier on how often specials are picked
 }
 
 impl Component for AIController {}
 
+/// How competent allies are for a run, picked on character select
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllyPreset {
+    Passive,
+    Balanced,
+    Aggressive,
+    Expert,
+}
+
+impl AllyPreset {
+    pub const ALL: [AllyPreset; 4] = [
+        AllyPreset::Passive,
+        AllyPreset::Balanced,
+        AllyPreset::Aggressive,
+        AllyPreset::Expert,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AllyPreset::Passive => "Passive",
+            AllyPreset::Balanced => "Balanced",
+            AllyPreset::Aggressive => "Aggressive",
+            AllyPreset::Expert => "Expert",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            AllyPreset::Passive => "Allies hang back and rarely use specials - you carry the fight",
+            AllyPreset::Balanced => "Allies support you without stealing the show",
+            AllyPreset::Aggressive => "Allies dive in and throw specials often",
+            AllyPreset::Expert => "Allies react fast, mix up guards and use specials well",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&p| p == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// AI settings for an ally spawned with this preset
+    pub fn controller(self, rng: &mut SeededRng) -> AIController {
+        let (behavior, difficulty, reaction_delay, reaction_scale, ability_rate) = match self {
+            AllyPreset::Passive => (AIBehavior::Support, 0.2, 0.9, 1.5, 0.3),
+            AllyPreset::Balanced => (AIBehavior::Support, 0.35, 0.6, 1.0, 1.0),
+            AllyPreset::Aggressive => (AIBehavior::Aggressive, 0.5, 0.4, 0.8, 1.4),
+            AllyPreset::Expert => (AIBehavior::Balanced, 0.85, 0.3, 0.6, 1.6),
+        };
+
+        AIController {
+            behavior,
+            target_entity: None,
+            state_timer: rng.range_f32(0.0, 0.5), // Stagger allies so they don't act in sync
+            reaction_delay,
+            difficulty,
+            reaction_scale,
+            ability_rate,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AIBehavior {
     Aggressive,
@@ -202,13 +316,170 @@ pub enum AIBehavior {
     Boss(BossPhase),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BossPhase {
     Phase1,
     Phase2,
     Phase3,
 }
 
+impl BossPhase {
+    /// The phase a boss fights in with `health` of its health left: one up
+    /// for each third it has lost
+    pub fn for_health(health: f32) -> Self {
+        if health > 2.0 / 3.0 {
+            BossPhase::Phase1
+        } else if health > 1.0 / 3.0 {
+            BossPhase::Phase2
+        } else {
+            BossPhase::Phase3
+        }
+    }
+}
+
+/// Shortest wind-up any boss attack gets, however fast it's meant to be
+const MIN_WINDUP: f32 = 0.25;
+/// Speed the player is expected to get out of a danger zone at
+const ESCAPE_SPEED: f32 = 600.0;
+/// Bosses in their last phase wind up this much faster, down to what's
+/// still readable
+const DESPERATE_WINDUP_SCALE: f32 = 0.75;
+
+/// How a boss attack warns the player before it lands: what the boss does
+/// while it winds up, the sound it makes and the ground it's about to hit.
+/// The AI holds the attack back for the wind-up and the state draws it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Telegraph {
+    pub windup_vfx: WindupVfx,
+    pub audio_cue: &'static str,
+    pub danger_zone: DangerZone,
+    /// Wind-up the attack is written with, before it's made readable
+    pub windup: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindupVfx {
+    /// Glint on the weapon
+    Glint,
+    /// Ring closing in on the boss
+    ChargeRing,
+    /// Flashing line along the throw
+    AimLine,
+    /// Pulsing glow around the boss
+    Aura,
+}
+
+/// Area that will be hit, relative to the boss and mirrored when it faces left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DangerZone {
+    Rect { offset: Vec2, size: Vec2 },
+    /// Everything around the boss, for attacks centred on it
+    Circle { radius: f32 },
+}
+
+impl Telegraph {
+    /// The warning `character` gives before throwing `attack`, over the zone
+    /// its hitbox will cover. Bosses with a signature move warn their own
+    /// way; everyone else gets the attack's usual one.
+    pub fn for_attack(character: CharacterType, attack: FighterState) -> Option<Self> {
+        let (_, hitbox) = attack.attack()?;
+        let danger_zone = if hitbox.offset == Vec2::ZERO {
+            DangerZone::Circle {
+                radius: hitbox.size.max_element() / 2.0,
+            }
+        } else {
+            DangerZone::Rect {
+                offset: hitbox.offset,
+                size: hitbox.size,
+            }
+        };
+
+        let (windup_vfx, audio_cue, windup) = match (character, attack) {
+            (CharacterType::Bastiaan, FighterState::HeavyAttack) => {
+                (WindupVfx::Glint, "bastiaan_windup_slam", 0.6)
+            }
+            (CharacterType::Bastiaan, FighterState::Super) => {
+                (WindupVfx::ChargeRing, "bastiaan_windup_stomp", 1.0)
+            }
+            // The pita sirracha gets a run-up before it's thrown
+            (CharacterType::Mees, FighterState::Special | FighterState::HeavyAttack) => {
+                (WindupVfx::AimLine, "mees_windup_pita", 0.5)
+            }
+            (CharacterType::Mees, FighterState::Super) => {
+                (WindupVfx::Aura, "mees_windup_fire", 0.9)
+            }
+            (_, FighterState::LightAttack) => (WindupVfx::Glint, "boss_windup_melee", 0.2),
+            (_, FighterState::HeavyAttack) => (WindupVfx::Glint, "boss_windup_melee", 0.45),
+            (_, FighterState::Special) => (WindupVfx::AimLine, "boss_windup_special", 0.5),
+            _ => (WindupVfx::ChargeRing, "boss_windup_super", 0.8),
+        };
+
+        Some(Self {
+            windup_vfx,
+            audio_cue,
+            danger_zone,
+            windup,
+        })
+    }
+
+    /// Shortest wind-up that still gives the player time to see the zone and
+    /// get out of it
+    pub fn readable_windup(&self) -> f32 {
+        let escape_distance = match self.danger_zone {
+            DangerZone::Rect { size, .. } => size.x.min(size.y) / 2.0,
+            DangerZone::Circle { radius } => radius,
+        };
+        MIN_WINDUP.max(escape_distance / ESCAPE_SPEED)
+    }
+
+    /// How long a boss in `phase` winds this up for
+    pub fn windup_in(&self, phase: BossPhase) -> f32 {
+        let windup = if phase == BossPhase::Phase3 {
+            self.windup * DESPERATE_WINDUP_SCALE
+        } else {
+            self.windup
+        };
+        windup.max(self.readable_windup())
+    }
+
+    /// The zone in world space for a boss at `position` facing `facing`
+    pub fn danger_rect(&self, position: Vec2, facing: f32) -> Rect {
+        let (center, size) = match self.danger_zone {
+            DangerZone::Rect { offset, size } => {
+                (position + Vec2::new(offset.x * facing.signum(), offset.y), size)
+            }
+            DangerZone::Circle { radius } => (position, Vec2::splat(radius * 2.0)),
+        };
+        Rect::new(center.x - size.x / 2.0, center.y - size.y / 2.0, size.x, size.y)
+    }
+}
+
+/// A boss attack being telegraphed. The AI throws it once `elapsed` reaches
+/// `windup`, and the warning stays up until the swing is over.
+#[derive(Clone, Debug)]
+pub struct BossWindup {
+    pub attack: FighterState,
+    pub height: AttackHeight,
+    pub telegraph: Telegraph,
+    pub windup: f32,
+    pub elapsed: f32,
+    /// Thrown, and landing now
+    pub landing: bool,
+}
+
+impl Component for BossWindup {}
+
+impl BossWindup {
+    /// How far through the wind-up it is, 1 once it lands
+    pub fn progress(&self) -> f32 {
+        if self.landing {
+            1.0
+        } else {
+            (self.elapsed / self.windup.max(0.001)).min(1.0)
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Weapon {
     pub weapon_type: WeaponType,
@@ -229,20 +500,264 @@ pub enum WeaponType {
     Mop,
 }
 
+/// A live particle. These aren't entities: `ParticleSystem` recycles them
+/// through an object pool and `Emitter`s are the ECS-facing side.
 #[derive(Clone, Debug)]
 pub struct Particle {
     pub particle_type: ParticleType,
+    pub position: Vec2,
     pub lifetime: f32,
     pub max_lifetime: f32,
     pub velocity: Vec2,
     pub acceleration: Vec2,
-    pub color_start: Color,
-    pub color_end: Color,
+    /// Speed gained per second along the direction it was emitted (negative slows it)
+    pub radial_acceleration: f32,
+    pub direction: Vec2,
+    pub colors: ColorRamp,
     pub size_start: f32,
     pub size_end: f32,
 }
 
-impl Component for Particle {}
+impl Particle {
+    pub fn progress(&self) -> f32 {
+        (self.lifetime / self.max_lifetime.max(0.001)).min(1.0)
+    }
+
+    pub fn color(&self) -> Color {
+        self.colors.sample(self.progress())
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size_start + (self.size_end - self.size_start) * self.progress()
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.lifetime >= self.max_lifetime
+    }
+}
+
+impl Default for Particle {
+    fn default() -> Self {
+        Self {
+            particle_type: ParticleType::Spark,
+            position: Vec2::ZERO,
+            lifetime: 0.0,
+            max_lifetime: 1.0,
+            velocity: Vec2::ZERO,
+            acceleration: Vec2::ZERO,
+            radial_acceleration: 0.0,
+            direction: Vec2::ZERO,
+            colors: ColorRamp::fade(WHITE),
+            size_start: 1.0,
+            size_end: 1.0,
+        }
+    }
+}
+
+/// Three-stop color gradient over a particle's life
+#[derive(Clone, Copy, Debug)]
+pub struct ColorRamp {
+    pub start: Color,
+    pub mid: Color,
+    pub end: Color,
+}
+
+impl ColorRamp {
+    pub fn new(start: Color, end: Color) -> Self {
+        Self {
+            start,
+            mid: lerp_color(start, end, 0.5),
+            end,
+        }
+    }
+
+    /// Same color, alpha going to zero
+    pub fn fade(color: Color) -> Self {
+        Self::new(color, Color::new(color.r, color.g, color.b, 0.0))
+    }
+
+    pub fn with_mid(mut self, mid: Color) -> Self {
+        self.mid = mid;
+        self
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        if t < 0.5 {
+            lerp_color(self.start, self.mid, t * 2.0)
+        } else {
+            lerp_color(self.mid, self.end, (t - 0.5) * 2.0)
+        }
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    Color::new(
+        a.r + (b.r - a.r) * t,
+        a.g + (b.g - a.g) * t,
+        a.b + (b.b - a.b) * t,
+        a.a + (b.a - a.a) * t,
+    )
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum EmitterMode {
+    /// Everything at once on the first update, then the emitter is finished
+    Burst(usize),
+    /// Particles per second, for `duration` seconds or forever if `None`
+    Continuous { rate: f32, duration: Option<f32> },
+}
+
+/// Where particles start relative to the emitter; they always leave moving outward
+#[derive(Clone, Copy, Debug)]
+pub enum EmitterShape {
+    Point,
+    /// Anywhere inside a disc of this radius
+    Circle(f32),
+    /// On the rim of a circle of this radius
+    Ring(f32),
+}
+
+/// Spawns particles at its entity's `Transform`. Spawn a bare Transform + Emitter
+/// entity for one-off effects, or attach one to a fighter so it follows them.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    pub mode: EmitterMode,
+    pub shape: EmitterShape,
+    pub particle_type: ParticleType,
+    pub colors: ColorRamp,
+    pub speed: (f32, f32),
+    pub lifetime: (f32, f32),
+    pub size: (f32, f32),
+    pub acceleration: Vec2,
+    pub radial_acceleration: f32,
+    /// Destroy the whole entity once finished instead of just removing the emitter
+    pub despawn_when_done: bool,
+    pub elapsed: f32,
+    pub accumulator: f32,
+}
+
+impl Component for Emitter {}
+
+impl Emitter {
+    pub fn burst(count: usize, particle_type: ParticleType, colors: ColorRamp) -> Self {
+        Self::new(EmitterMode::Burst(count), particle_type, colors)
+    }
+
+    pub fn continuous(rate: f32, particle_type: ParticleType, colors: ColorRamp) -> Self {
+        Self::new(
+            EmitterMode::Continuous {
+                rate,
+                duration: None,
+            },
+            particle_type,
+            colors,
+        )
+    }
+
+    fn new(mode: EmitterMode, particle_type: ParticleType, colors: ColorRamp) -> Self {
+        Self {
+            mode,
+            shape: EmitterShape::Point,
+            particle_type,
+            colors,
+            speed: (50.0, 150.0),
+            lifetime: (0.5, 1.0),
+            size: (8.0, 2.0),
+            acceleration: Vec2::ZERO,
+            radial_acceleration: 0.0,
+            despawn_when_done: true,
+            elapsed: 0.0,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn shape(mut self, shape: EmitterShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min, max);
+        self
+    }
+
+    pub fn lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min, max);
+        self
+    }
+
+    pub fn size(mut self, start: f32, end: f32) -> Self {
+        self.size = (start, end);
+        self
+    }
+
+    pub fn acceleration(mut self, acceleration: Vec2) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    pub fn radial_acceleration(mut self, radial_acceleration: f32) -> Self {
+        self.radial_acceleration = radial_acceleration;
+        self
+    }
+
+    /// Keep the host entity alive when this emitter finishes (for emitters on fighters)
+    pub fn attached(mut self) -> Self {
+        self.despawn_when_done = false;
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self.mode {
+            EmitterMode::Burst(_) => self.elapsed > 0.0,
+            EmitterMode::Continuous { duration, .. } => {
+                duration.is_some_and(|duration| self.elapsed >= duration)
+            }
+        }
+    }
+
+    /// How many particles to release this frame
+    pub fn advance(&mut self, dt: f32) -> usize {
+        let count = match self.mode {
+            EmitterMode::Burst(count) if self.elapsed == 0.0 => count,
+            EmitterMode::Burst(_) => 0,
+            EmitterMode::Continuous { rate, .. } => {
+                self.accumulator += rate * dt;
+                let whole = self.accumulator.floor();
+                self.accumulator -= whole;
+                whole as usize
+            }
+        };
+        // Bursts need a non-zero elapsed to count as finished, even on a zero dt frame
+        self.elapsed += dt.max(f32::EPSILON);
+        count
+    }
+
+    /// Build one particle at `origin` according to the emitter's shape and ranges.
+    /// `scale` is the host's Transform scale, so emitters on big fighters spread wider.
+    pub fn emit(&self, origin: Vec2, scale: f32, particle: &mut Particle) {
+        let angle = rng::cosmetic(0.0, std::f32::consts::TAU);
+        let direction = Vec2::new(angle.cos(), angle.sin());
+        let offset = match self.shape {
+            EmitterShape::Point => 0.0,
+            EmitterShape::Circle(radius) => rng::cosmetic(0.0, radius),
+            EmitterShape::Ring(radius) => radius,
+        };
+
+        particle.particle_type = self.particle_type.clone();
+        particle.position = origin + direction * offset * scale;
+        particle.lifetime = 0.0;
+        particle.max_lifetime = rng::cosmetic(self.lifetime.0, self.lifetime.1);
+        particle.velocity = direction * rng::cosmetic(self.speed.0, self.speed.1);
+        particle.acceleration = self.acceleration;
+        particle.radial_acceleration = self.radial_acceleration;
+        particle.direction = direction;
+        particle.colors = self.colors;
+        particle.size_start = self.size.0;
+        particle.size_end = self.size.1;
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ParticleType {
@@ -279,6 +794,101 @@ pub struct PhysicsBody {
 
 impl Component for PhysicsBody {}
 
+impl PhysicsBody {
+    /// Body for a fighter: no world gravity (y is depth), mass scales knockback
+    pub fn fighter(team: Team, mass: f32) -> Self {
+        let layer = match team {
+            Team::Enemy => CollisionLayer::ENEMY,
+            Team::Player | Team::Ally => CollisionLayer::PLAYER,
+        };
+
+        Self {
+            layer,
+            mask: CollisionLayer::WALL,
+            gravity_scale: 0.0,
+            friction: 6.0,
+            restitution: 0.15,
+            mass: mass.max(0.1),
+        }
+    }
+}
+
+/// Knockback momentum, integrated by `PhysicsSystem` separately from locomotion velocity
+/// so AI steering and player input can't cancel it out.
+#[derive(Clone, Debug, Default)]
+pub struct Knockback {
+    pub velocity: Vec2,         // Ground-plane momentum (x, depth)
+    pub height: f32,            // Height above the ground plane while launched
+    pub vertical_velocity: f32, // Upward speed of the launch
+    pub wall_splat_timer: f32,  // Time left stuck to the wall after a splat
+}
+
+impl Component for Knockback {}
+
+impl Knockback {
+    pub fn is_airborne(&self) -> bool {
+        self.height > 0.0
+    }
+}
+
+/// Scripted entrance for a freshly spawned enemy. The fighter is moved along the
+/// entrance path and stays invulnerable until `grace` runs out after landing.
+#[derive(Clone, Debug)]
+pub struct SpawnEntrance {
+    pub kind: SpawnPointKind,
+    pub from: Vec2,
+    pub to: Vec2,
+    pub elapsed: f32,
+    pub duration: f32,
+    pub grace: f32, // Invulnerability kept after landing
+}
+
+impl Component for SpawnEntrance {}
+
+impl SpawnEntrance {
+    pub fn new(kind: SpawnPointKind, from: Vec2, to: Vec2) -> Self {
+        Self {
+            kind,
+            from,
+            to,
+            elapsed: 0.0,
+            duration: kind.entrance_duration(),
+            grace: 0.6,
+        }
+    }
+
+    /// Still walking/vaulting/climbing in; AI and positional clamps leave it alone
+    pub fn is_entering(&self) -> bool {
+        self.elapsed < self.duration
+    }
+
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+
+    /// Position along the entrance path at the current progress
+    pub fn position(&self) -> Vec2 {
+        let t = self.progress();
+        match self.kind {
+            // Kick the door for the first third, then stride through it
+            SpawnPointKind::Door => {
+                let walk = ((t - 0.35) / 0.65).clamp(0.0, 1.0);
+                self.from.lerp(self.to, walk)
+            }
+            // Hop over the sill in an arc
+            SpawnPointKind::Window => {
+                let hop = (t * std::f32::consts::PI).sin() * 70.0;
+                self.from.lerp(self.to, t) - Vec2::new(0.0, hop)
+            }
+            // Climb at a steady pace, easing out at the top step
+            SpawnPointKind::Stairwell => {
+                let eased = 1.0 - (1.0 - t) * (1.0 - t);
+                self.from.lerp(self.to, eased)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Interactable {
     pub interaction_type: InteractionType,
@@ -315,3 +925,283 @@ pub struct Bomb {
 }
 
 impl Component for Bomb {}
+
+const BOOKSHELF_STANDING: f32 = 6.0;
+const BOOKSHELF_WOBBLE: f32 = 1.2;
+const BOOKSHELF_DOWN: f32 = 2.5;
+const WIND_PERIOD: f32 = 7.0;
+const WIND_GUST: f32 = 2.5;
+
+/// An interactive piece of the map, driven by `StagePropSystem`
+#[derive(Clone, Debug)]
+pub struct StageProp {
+    pub kind: PropKind,
+    /// Seconds into the prop's cycle, or its hit cooldown for basketballs
+    pub timer: f32,
+}
+
+impl Component for StageProp {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PropKind {
+    /// Sent flying by an attack; hurts the first foe of the thrower it hits
+    Chair { thrown_by: Option<(EntityId, Team)> },
+    /// Fighters walking through it keep sliding
+    SoupSpill { radius: f32 },
+    /// Bounces around the arena and off anyone it runs into
+    Basketball,
+    /// Wobbles for a moment, then topples onto whoever stands in front of it
+    Bookshelf,
+    /// Blows fighters sideways every few seconds, switching sides each gust
+    WindGust { direction: f32 },
+}
+
+impl PropKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PropKind::Chair { .. } => "Chair",
+            PropKind::SoupSpill { .. } => "Soup",
+            PropKind::Basketball => "Basketball",
+            PropKind::Bookshelf => "Bookshelf",
+            PropKind::WindGust { .. } => "Wind",
+        }
+    }
+}
+
+impl StageProp {
+    fn bookshelf_phase(&self) -> f32 {
+        self.timer % (BOOKSHELF_STANDING + BOOKSHELF_WOBBLE + BOOKSHELF_DOWN)
+    }
+
+    /// Telegraph before a bookshelf falls
+    pub fn wobbling(&self) -> bool {
+        let phase = self.bookshelf_phase();
+        self.kind == PropKind::Bookshelf
+            && (BOOKSHELF_STANDING..BOOKSHELF_STANDING + BOOKSHELF_WOBBLE).contains(&phase)
+    }
+
+    pub fn toppled(&self) -> bool {
+        self.kind == PropKind::Bookshelf
+            && self.bookshelf_phase() >= BOOKSHELF_STANDING + BOOKSHELF_WOBBLE
+    }
+
+    /// Direction the wind is blowing in right now, if a gust is on
+    pub fn gust(&self) -> Option<f32> {
+        let PropKind::WindGust { direction } = self.kind else {
+            return None;
+        };
+        if self.timer % WIND_PERIOD < WIND_PERIOD - WIND_GUST {
+            return None;
+        }
+        let flipped = (self.timer / WIND_PERIOD) as u32 % 2 == 1;
+        Some(if flipped { -direction } else { direction })
+    }
+}
+
+/// What a `Destructible` throws out when hit, and more of when it breaks
+#[derive(Clone, Copy, Debug)]
+pub struct DebrisSpec {
+    pub colors: ColorRamp,
+    /// Particles per hit; breaking throws three times as many
+    pub count: usize,
+    pub speed: f32,
+    pub size: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DestructibleKind {
+    Desk,
+    Locker,
+    Table,
+}
+
+/// Furniture that attacks can smash. Broken pieces stay on the floor as
+/// wreckage until the next wave puts out fresh ones.
+#[derive(Clone, Debug)]
+pub struct Destructible {
+    pub kind: DestructibleKind,
+    pub health: f32,
+    pub max_health: f32,
+    pub size: Vec2,
+    pub debris: DebrisSpec,
+    /// Chance of dropping Arc Tokens when it breaks
+    pub loot_chance: f32,
+}
+
+impl Component for Destructible {}
+
+impl Destructible {
+    pub fn new(kind: DestructibleKind) -> Self {
+        let wood = ColorRamp::new(Color::new(0.6, 0.4, 0.2, 1.0), Color::new(0.35, 0.2, 0.1, 0.0));
+        // Dented metal flashes a spark on the way out
+        let metal = ColorRamp::new(Color::new(0.6, 0.65, 0.7, 1.0), Color::new(0.3, 0.3, 0.35, 0.0))
+            .with_mid(Color::new(1.0, 0.9, 0.6, 0.8));
+        let (health, size, debris, loot_chance) = match kind {
+            DestructibleKind::Desk => (15.0, Vec2::new(90.0, 50.0), wood, 0.35),
+            DestructibleKind::Locker => (35.0, Vec2::new(60.0, 120.0), metal, 0.6),
+            DestructibleKind::Table => (25.0, Vec2::new(140.0, 60.0), wood, 0.45),
+        };
+
+        Self {
+            kind,
+            health,
+            max_health: health,
+            size,
+            debris: DebrisSpec {
+                colors: debris,
+                count: 8,
+                speed: 180.0,
+                size: 6.0,
+            },
+            loot_chance,
+        }
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.health <= 0.0
+    }
+}
+
+/// Temporary boosts the team shares while they last
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Harder hits
+    Strength,
+    /// Faster movement
+    Haste,
+    /// Health trickles back
+    Regen,
+}
+
+impl PowerUpKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUpKind::Strength => "Strength",
+            PowerUpKind::Haste => "Haste",
+            PowerUpKind::Regen => "Regen",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::Strength => Color::new(1.0, 0.35, 0.2, 1.0),
+            PowerUpKind::Haste => Color::new(0.3, 0.9, 1.0, 1.0),
+            PowerUpKind::Regen => Color::new(0.4, 1.0, 0.4, 1.0),
+        }
+    }
+}
+
+/// What a pickup hands over
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LootDrop {
+    /// Arc Tokens for the team's purse
+    Tokens(u32),
+    /// Health back for whoever grabs it
+    HealthPack(f32),
+    /// Super meter for whoever grabs it
+    MeterBattery(f32),
+    PowerUp { kind: PowerUpKind, duration: f32 },
+}
+
+/// How rare a drop is; rarer drops glow brighter
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    pub fn color(self) -> Color {
+        match self {
+            Rarity::Common => Color::new(0.9, 0.9, 0.9, 1.0),
+            Rarity::Uncommon => Color::new(0.3, 1.0, 0.4, 1.0),
+            Rarity::Rare => Color::new(0.3, 0.6, 1.0, 1.0),
+            Rarity::Epic => Color::new(0.8, 0.4, 1.0, 1.0),
+        }
+    }
+}
+
+/// Loot on the floor. Drifts toward players who come near and is collected
+/// by walking over it.
+#[derive(Clone, Debug)]
+pub struct Pickup {
+    pub drop: LootDrop,
+    pub rarity: Rarity,
+    /// Seconds before it vanishes
+    pub lifetime: f32,
+}
+
+impl Component for Pickup {}
+
+impl Pickup {
+    /// Seconds it stays on the floor
+    pub const LIFETIME: f32 = 12.0;
+    /// Seconds of blinking before it vanishes
+    pub const WARNING: f32 = 3.0;
+
+    pub fn new(drop: LootDrop, rarity: Rarity) -> Self {
+        Self {
+            drop,
+            rarity,
+            lifetime: Self::LIFETIME,
+        }
+    }
+
+    pub fn expiring(&self) -> bool {
+        self.lifetime < Self::WARNING
+    }
+}
+
+/// Trader who sets up between waves. Walk up to them to shop; once they
+/// pack up the next wave starts.
+#[derive(Clone, Debug)]
+pub struct Shopkeeper {
+    /// Seconds before they leave
+    pub lifetime: f32,
+    /// Share knocked off every price, once they've been haggled with. They
+    /// only hear one pitch a visit.
+    pub discount: Option<f32>,
+}
+
+impl Component for Shopkeeper {}
+
+impl Shopkeeper {
+    /// Seconds they stay between waves
+    pub const LIFETIME: f32 = 25.0;
+    /// Seconds of warning before they leave
+    pub const WARNING: f32 = 5.0;
+    /// How close P1 has to be to open the shop
+    pub const REACH: f32 = 140.0;
+
+    pub fn arriving() -> Self {
+        Self {
+            lifetime: Self::LIFETIME,
+            discount: None,
+        }
+    }
+
+    pub fn leaving(&self) -> bool {
+        self.lifetime < Self::WARNING
+    }
+}
+
+/// A tag partner called in for one attack. They can't be hit and leave
+/// once the move is done.
+#[derive(Clone, Debug)]
+pub struct Assist {
+    pub character: CharacterId,
+    /// Seconds before they jump back out
+    pub lifetime: f32,
+}
+
+impl Component for Assist {}
+
+impl Assist {
+    /// Seconds they stay after their attack ends
+    pub const LINGER: f32 = 0.3;
+    /// How far behind the point character they land
+    pub const OFFSET: f32 = 60.0;
+}
+
@@ -0,0 +1,85 @@
+use crate::coop::PingKind;
+use crate::ecs::comp::{LootDrop, Team};
+use crate::ecs::entity::EntityId;
+use crate::ecs::world::World;
+use macroquad::prelude::*;
+
+/// Cross-system gameplay events. Positions are captured when the event is sent
+/// since the entity may be gone by the time it's handled.
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    HitLanded {
+        attacker: EntityId,
+        defender: EntityId,
+        damage: f32,
+        position: Vec2,
+        /// Landed as a critical hit
+        critical: bool,
+    },
+    EntityDied {
+        entity: EntityId,
+        team: Team,
+        position: Vec2,
+    },
+    WaveCompleted {
+        wave: usize,
+    },
+    /// Sent after the boss's `EntityDied`
+    BossDefeated {
+        position: Vec2,
+    },
+    /// A boss started winding up an attack, with the cue it warns with
+    BossWindup {
+        cue: &'static str,
+        position: Vec2,
+    },
+    /// A downed player or ally got back up before bleeding out
+    TeammateRevived {
+        position: Vec2,
+    },
+    BombExploded {
+        position: Vec2,
+        radius: f32,
+    },
+    /// A player walked over a `Pickup`
+    PickupCollected {
+        collector: EntityId,
+        drop: LootDrop,
+        position: Vec2,
+    },
+    /// A co-op player let go of their ping wheel. Online it's sent from the
+    /// shared tick on both machines, so each shows the partner's marker.
+    Ping {
+        kind: PingKind,
+        position: Vec2,
+        seat: usize,
+    },
+}
+
+/// Events sent this frame, in order. Handlers read them once the producing
+/// systems have run, then the owner clears the queue.
+#[derive(Default)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn send(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Take everything queued so far, leaving the queue empty
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Reacts to gameplay events outside the state that owns the world. Plugins
+/// register these; they see every event after the state's own handlers.
+pub trait EventHandler {
+    fn handle(&mut self, world: &World, event: &GameEvent);
+
+    /// Draw in world space after the scene, for handlers that show what
+    /// they've seen
+    fn render(&self, _world: &World) {}
+}
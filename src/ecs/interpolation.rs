@@ -0,0 +1,40 @@
+use crate::ecs::comp::Transform;
+use crate::ecs::entity::EntityId;
+use crate::ecs::world::World;
+use ahash::AHashMap;
+use macroquad::prelude::*;
+
+/// Transform positions from before the latest fixed tick. Rendering blends from
+/// these to the current positions, so motion stays smooth whatever the refresh
+/// rate is relative to the tick rate.
+pub struct TransformSnapshot {
+    previous: AHashMap<EntityId, Vec2>,
+}
+
+impl TransformSnapshot {
+    pub fn new() -> Self {
+        Self {
+            previous: AHashMap::new(),
+        }
+    }
+
+    /// Call at the start of every fixed tick, including ticks that don't
+    /// simulate, so paused entities aren't drawn between two stale states
+    pub fn capture(&mut self, world: &World) {
+        self.previous.clear();
+        self.previous.extend(
+            world
+                .query::<Transform>()
+                .map(|(entity, transform)| (entity, transform.position)),
+        );
+    }
+
+    /// Where to draw `entity`, `alpha` (0..1) of the way through the next tick.
+    /// Entities spawned since the last capture are drawn where they are.
+    pub fn position(&self, entity: EntityId, current: Vec2, alpha: f32) -> Vec2 {
+        match self.previous.get(&entity) {
+            Some(previous) => previous.lerp(current, alpha.clamp(0.0, 1.0)),
+            None => current,
+        }
+    }
+}
@@ -1,9 +1,17 @@
+pub mod commands;
 pub mod comp;
 pub mod entity;
+pub mod events;
+pub mod interpolation;
+pub mod plugin;
+pub mod schedule;
 pub mod sys;
 pub mod world;
 
 pub use comp::*;
 pub use entity::EntityId;
+pub use events::{EventHandler, GameEvent};
+pub use interpolation::TransformSnapshot;
+pub use schedule::{Schedule, Stage};
 pub use sys::*;
 pub use world::{Component, World};
@@ -0,0 +1,25 @@
+use crate::ecs::schedule::Stage;
+use crate::ecs::sys::{
+    AISystem, AnimationSystem, DestructibleSystem, MovementSystem, ParticleSystem, PhysicsSystem,
+    PickupSystem, SpawnEntranceSystem, StagePropSystem,
+};
+use crate::plugins::{Plugin, PluginRegistry};
+
+/// Movement, physics, animation, stage props, furniture and loot, particles and AI:
+/// everything a fight needs besides the combat itself
+pub struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry
+            .add_system(Stage::Motion, || Box::new(SpawnEntranceSystem))
+            .add_system(Stage::Motion, || Box::new(MovementSystem))
+            .add_system(Stage::Motion, || Box::new(PhysicsSystem::new()))
+            .add_system(Stage::Motion, || Box::new(AnimationSystem::new()))
+            .add_system(Stage::Motion, || Box::new(StagePropSystem::new()))
+            .add_system(Stage::Motion, || Box::new(DestructibleSystem))
+            .add_system(Stage::Motion, || Box::new(PickupSystem))
+            .add_system(Stage::Late, || Box::new(ParticleSystem::new()))
+            .add_system(Stage::Late, || Box::new(AISystem::new()));
+    }
+}
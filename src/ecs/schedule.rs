@@ -0,0 +1,50 @@
+use crate::ecs::sys::{self, System};
+use crate::ecs::world::World;
+use std::any::Any;
+
+/// Points in the frame where registered systems run. The gameplay state runs
+/// each stage in turn and handles readbacks (wall splats, guard sparks)
+/// between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Spawn entrances, movement, knockback and animation
+    Motion,
+    /// Attacks and hit resolution
+    Combat,
+    /// Particles and AI, once the frame's hits are known
+    Late,
+}
+
+/// The ECS systems a state runs, in registration order within each stage
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(Stage, Box<dyn System>)>,
+}
+
+impl Schedule {
+    pub fn add(&mut self, stage: Stage, system: Box<dyn System>) {
+        self.systems.push((stage, system));
+    }
+
+    pub fn run(&mut self, stage: Stage, world: &mut World, dt: f32) {
+        for (system_stage, system) in &mut self.systems {
+            if *system_stage == stage {
+                sys::run_system(system.as_mut(), world, dt);
+            }
+        }
+    }
+
+    /// The registered system of type `T`, for states that read results back
+    /// or tune it between frames
+    pub fn get<T: System>(&self) -> Option<&T> {
+        self.systems
+            .iter()
+            .find_map(|(_, system)| (system.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: System>(&mut self) -> Option<&mut T> {
+        self.systems
+            .iter_mut()
+            .find_map(|(_, system)| (system.as_mut() as &mut dyn Any).downcast_mut::<T>())
+    }
+}
@@ -1,25 +1,103 @@
+use crate::combat::hitbox::{AttackHeight, BlockStance};
 use crate::ecs::comp::*;
 use crate::ecs::entity::EntityId;
+use crate::ecs::events::GameEvent;
 use crate::ecs::world::World;
+use crate::render::animation::{AnimationEvent, AnimationPlayer};
+use crate::util::pools::ObjectPool;
+use crate::util::rng::SeededRng;
+use crate::util::spatial::SpatialGrid;
 use macroquad::prelude::*;
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
 
-pub trait System {
+/// Cell size for broad-phase grids; roughly one fighter hurtbox wide
+const SPATIAL_CELL_SIZE: f32 = 128.0;
+
+/// `Any` lets a `Schedule` hand back the concrete system
+pub trait System: Any {
     fn update(&mut self, world: &mut World, dt: f32);
+
+    /// What the performance overlay calls it: the type name without its path
+    fn name(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        path.rsplit("::").next().unwrap_or(path)
+    }
+}
+
+/// What the ECS cost since the last `take_system_profile`, for the F3 overlay
+#[derive(Clone, Debug, Default)]
+pub struct SystemProfile {
+    /// Seconds each system spent updating, summed over the ticks, in the
+    /// order they first ran
+    pub timings: Vec<(&'static str, f64)>,
+    /// Live entities and particles after the last system ran
+    pub entities: usize,
+    pub particles: usize,
+}
+
+impl SystemProfile {
+    fn record(&mut self, name: &'static str, seconds: f64) {
+        match self.timings.iter_mut().find(|(system, _)| *system == name) {
+            Some((_, total)) => *total += seconds,
+            None => self.timings.push((name, seconds)),
+        }
+    }
+}
+
+/// Off unless the overlay is open, so timing costs nothing otherwise
+static PROFILING: AtomicBool = AtomicBool::new(false);
+static PROFILE: Mutex<SystemProfile> = Mutex::new(SystemProfile {
+    timings: Vec::new(),
+    entities: 0,
+    particles: 0,
+});
+
+pub fn set_profiling(enabled: bool) {
+    PROFILING.store(enabled, Ordering::Relaxed);
+}
+
+/// Everything recorded since the last call, starting the next frame empty
+pub fn take_system_profile() -> SystemProfile {
+    PROFILE.lock().map(|mut profile| std::mem::take(&mut *profile)).unwrap_or_default()
+}
+
+/// Update `system` for a tick, timed while profiling is on. Schedules run
+/// every system through here; states holding systems directly should too.
+pub fn run_system(system: &mut dyn System, world: &mut World, dt: f32) {
+    if !PROFILING.load(Ordering::Relaxed) {
+        system.update(world, dt);
+        return;
+    }
+
+    let start = Instant::now();
+    system.update(world, dt);
+    let seconds = start.elapsed().as_secs_f64();
+
+    if let Ok(mut profile) = PROFILE.lock() {
+        profile.record(system.name(), seconds);
+        profile.entities = world.entity_count();
+        if let Some(particles) = (&*system as &dyn Any).downcast_ref::<ParticleSystem>() {
+            profile.particles = particles.particles().len();
+        }
+    }
 }
 
 pub struct MovementSystem;
 
 impl System for MovementSystem {
     fn update(&mut self, world: &mut World, dt: f32) {
-        let entities: Vec<_> = world.query::<Transform>().map(|(e, _)| e).collect();
+        let moving: Vec<_> = world
+            .query::<(Transform, Velocity)>()
+            .map(|(e, (_, vel))| (e, vel.linear, vel.angular))
+            .collect();
 
-        for entity in entities {
-            if let Some(velocity) = world.get_component::<Velocity>(entity) {
-                let vel = velocity.clone();
-                if let Some(transform) = world.get_component_mut::<Transform>(entity) {
-                    transform.position += vel.linear * dt;
-                    transform.rotation += vel.angular * dt;
-                }
+        for (entity, linear, angular) in moving {
+            if let Some(transform) = world.get_component_mut::<Transform>(entity) {
+                transform.position += linear * dt;
+                transform.rotation += angular * dt;
             }
         }
     }
@@ -27,25 +105,163 @@ impl System for MovementSystem {
 
 pub struct PhysicsSystem {
     gravity: Vec2,
+    launch_gravity: f32,
+    arena: Rect,
+    wall_splats: Vec<WallSplat>,
+}
+
+/// A fighter slammed into the arena edge hard enough to stick
+#[derive(Clone, Copy, Debug)]
+pub struct WallSplat {
+    pub entity: EntityId,
+    pub position: Vec2,
+    pub speed: f32,
 }
 
+const LANDING_FRICTION: f32 = 0.45; // Fraction of ground momentum kept on landing
+const WALL_SPLAT_SPEED: f32 = 260.0;
+const WALL_SPLAT_STUN: f32 = 0.6;
+const KNOCKBACK_REST_SPEED: f32 = 4.0;
+
 impl PhysicsSystem {
     pub fn new() -> Self {
         Self {
             gravity: Vec2::new(0.0, 980.0),
+            launch_gravity: 1400.0,
+            arena: Rect::new(60.0, 340.0, 1800.0, 320.0),
+            wall_splats: Vec::new(),
+        }
+    }
+
+    /// Walkable area; its left/right edges act as walls for knockback
+    pub fn set_arena(&mut self, arena: Rect) {
+        self.arena = arena;
+    }
+
+    /// Wall splats from the last update
+    pub fn wall_splats(&self) -> &[WallSplat] {
+        &self.wall_splats
+    }
+
+    fn integrate_knockback(&mut self, world: &mut World, dt: f32) {
+        let entities: Vec<_> = world.query::<Knockback>().map(|(e, _)| e).collect();
+
+        for entity in entities {
+            let friction = world
+                .get_component::<PhysicsBody>(entity)
+                .map(|b| b.friction)
+                .unwrap_or(6.0);
+            let Some(position) = world.get_component::<Transform>(entity).map(|t| t.position)
+            else {
+                continue;
+            };
+            let Some(kb) = world.get_component_mut::<Knockback>(entity) else {
+                continue;
+            };
+
+            kb.wall_splat_timer = (kb.wall_splat_timer - dt).max(0.0);
+
+            if kb.is_airborne() || kb.vertical_velocity > 0.0 {
+                kb.vertical_velocity -= self.launch_gravity * dt;
+                kb.height += kb.vertical_velocity * dt;
+
+                if kb.height <= 0.0 {
+                    kb.height = 0.0;
+                    kb.vertical_velocity = 0.0;
+                    kb.velocity *= LANDING_FRICTION;
+                }
+            } else {
+                kb.velocity *= (-friction * dt).exp();
+            }
+
+            if kb.velocity.length_squared() < KNOCKBACK_REST_SPEED * KNOCKBACK_REST_SPEED {
+                kb.velocity = Vec2::ZERO;
+            }
+
+            let mut new_position = position + kb.velocity * dt;
+            let mut splat_speed = None;
+
+            let (left, right) = (self.arena.x, self.arena.x + self.arena.w);
+            if new_position.x < left || new_position.x > right {
+                new_position.x = new_position.x.clamp(left, right);
+                let speed = kb.velocity.x.abs();
+
+                if speed >= WALL_SPLAT_SPEED && kb.wall_splat_timer <= 0.0 {
+                    splat_speed = Some(speed);
+                    kb.wall_splat_timer = WALL_SPLAT_STUN;
+                    kb.velocity.x = -kb.velocity.x * 0.15;
+                    kb.velocity.y *= 0.3;
+                } else {
+                    kb.velocity.x = 0.0;
+                }
+            }
+
+            let (top, bottom) = (self.arena.y, self.arena.y + self.arena.h);
+            if new_position.y < top || new_position.y > bottom {
+                new_position.y = new_position.y.clamp(top, bottom);
+                kb.velocity.y = 0.0;
+            }
+
+            if let Some(transform) = world.get_component_mut::<Transform>(entity) {
+                transform.position = new_position;
+            }
+
+            if let Some(speed) = splat_speed {
+                if let Some(fighter) = world.get_component_mut::<Fighter>(entity) {
+                    fighter.state = FighterState::KnockedDown;
+                    fighter.hitstun = fighter.hitstun.max(WALL_SPLAT_STUN);
+                }
+                self.wall_splats.push(WallSplat {
+                    entity,
+                    position: new_position,
+                    speed,
+                });
+            }
+        }
+    }
+}
+
+/// Push an entity with an impulse (ground plane) and an upward launch, both scaled by mass
+pub fn apply_knockback(world: &mut World, entity: EntityId, impulse: Vec2, launch: f32) {
+    let mass = world
+        .get_component::<PhysicsBody>(entity)
+        .map(|b| b.mass)
+        .or_else(|| {
+            world
+                .get_component::<Fighter>(entity)
+                .map(|f| f.character_type.weight())
+        })
+        .unwrap_or(1.0)
+        .max(0.1);
+
+    if world.get_component::<Knockback>(entity).is_none() {
+        world.add_component(entity, Knockback::default());
+    }
+
+    if let Some(kb) = world.get_component_mut::<Knockback>(entity) {
+        // Momentum adds up across a combo instead of resetting each hit
+        kb.velocity += impulse / mass;
+        if launch > 0.0 {
+            kb.vertical_velocity = kb.vertical_velocity.max(launch / mass);
         }
     }
 }
 
 impl System for PhysicsSystem {
     fn update(&mut self, world: &mut World, dt: f32) {
+        self.wall_splats.clear();
+
         let entities: Vec<_> = world.query::<PhysicsBody>().map(|(e, _)| e).collect();
 
         for entity in entities {
+            // Knockback bodies are integrated separately below
+            if world.get_component::<Knockback>(entity).is_some() {
+                continue;
+            }
+
             if let Some(body) = world.get_component::<PhysicsBody>(entity) {
                 let gravity_force = self.gravity * body.gravity_scale;
                 let friction = body.friction;
-                drop(body);
 
                 if let Some(velocity) = world.get_component_mut::<Velocity>(entity) {
                     velocity.linear += gravity_force * dt;
@@ -53,38 +269,160 @@ impl System for PhysicsSystem {
                 }
             }
         }
+
+        self.integrate_knockback(world, dt);
     }
 }
 
-pub struct AnimationSystem;
+/// Drives scripted spawn entrances and the invulnerability window that follows them
+pub struct SpawnEntranceSystem;
+
+impl System for SpawnEntranceSystem {
+    fn update(&mut self, world: &mut World, dt: f32) {
+        let entities: Vec<_> = world.query::<SpawnEntrance>().map(|(e, _)| e).collect();
+
+        for entity in entities {
+            let Some(entrance) = world.get_component_mut::<SpawnEntrance>(entity) else {
+                continue;
+            };
+
+            let was_entering = entrance.is_entering();
+            entrance.elapsed += dt;
+            let entering = entrance.is_entering();
+            let finished = entrance.elapsed >= entrance.duration + entrance.grace;
+            let position = entrance.position();
+
+            // Once landed the fighter moves on its own; only snap it onto the last step
+            if was_entering {
+                if let Some(transform) = world.get_component_mut::<Transform>(entity) {
+                    transform.position = position;
+                }
+            }
+
+            if entering {
+                if let Some(velocity) = world.get_component_mut::<Velocity>(entity) {
+                    velocity.linear = Vec2::ZERO;
+                }
+            }
+
+            if let Some(fighter) = world.get_component_mut::<Fighter>(entity) {
+                fighter.invulnerable = !finished;
+            }
+
+            if finished {
+                world.remove_component::<SpawnEntrance>(entity);
+            }
+        }
+    }
+}
+
+/// Keeps each fighter's `AnimationPlayer` on the clip for its state and turns
+/// the clip's hitbox events into the attack's active frames
+pub struct AnimationSystem {
+    fired: Vec<AnimationEvent>,
+}
+
+impl AnimationSystem {
+    pub fn new() -> Self {
+        Self { fired: Vec::new() }
+    }
+}
 
 impl System for AnimationSystem {
     fn update(&mut self, world: &mut World, dt: f32) {
-        let entities: Vec<_> = world
-            .query_mut::<AnimationController>()
-            .map(|(e, _)| e)
+        let fighters: Vec<_> = world
+            .query::<(Fighter, AnimationPlayer)>()
+            .map(|(entity, (fighter, _))| (entity, fighter.state))
             .collect();
 
-        for entity in entities {
-            if let Some(anim) = world.get_component_mut::<AnimationController>(entity) {
-                anim.timer += dt;
+        for (entity, state) in fighters {
+            self.fired.clear();
+
+            let Some(player) = world.get_component_mut::<AnimationPlayer>(entity) else {
+                continue;
+            };
+            let switched = player.play_state(state);
+            player.advance(dt, &mut self.fired);
 
-                if anim.timer >= anim.frame_duration {
-                    anim.timer = 0.0;
-                    anim.frame += 1;
+            let Some(hitbox) = world.get_component_mut::<HitboxComponent>(entity) else {
+                continue;
+            };
+            // A new clip starts with its hitbox closed, even if the last one was cut short
+            if switched {
+                hitbox.active = false;
+            }
 
-                    if anim.looping {
-                        anim.frame = anim.frame % 8;
+            for event in &self.fired {
+                match event {
+                    AnimationEvent::HitboxOn => {
+                        hitbox.active = true;
+                        hitbox.hits_registered.clear();
                     }
+                    AnimationEvent::HitboxOff => hitbox.active = false,
                 }
             }
         }
     }
 }
 
+/// How far the player's hit bonuses can stack, shop and skill tree together
+pub const MAX_CRIT_CHANCE: f32 = 0.6;
+pub const MAX_LIFESTEAL: f32 = 0.4;
+pub const MAX_DAMAGE_REDUCTION: f32 = 0.6;
+
+/// The player's crits, lifesteal and damage reduction, as fractions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HitModifiers {
+    pub crit_chance: f32,
+    /// Damage scale of a critical hit
+    pub crit_multiplier: f32,
+    /// Share of damage dealt that comes back as health
+    pub lifesteal: f32,
+    /// Share of incoming damage taken off, chip damage included
+    pub damage_reduction: f32,
+}
+
+impl Default for HitModifiers {
+    fn default() -> Self {
+        Self {
+            crit_chance: 0.0,
+            crit_multiplier: 2.0,
+            lifesteal: 0.0,
+            damage_reduction: 0.0,
+        }
+    }
+}
+
+impl HitModifiers {
+    /// Stacked bonuses held to their caps
+    pub fn capped(self) -> Self {
+        Self {
+            crit_chance: self.crit_chance.clamp(0.0, MAX_CRIT_CHANCE),
+            crit_multiplier: self.crit_multiplier.max(1.0),
+            lifesteal: self.lifesteal.clamp(0.0, MAX_LIFESTEAL),
+            damage_reduction: self.damage_reduction.clamp(0.0, MAX_DAMAGE_REDUCTION),
+        }
+    }
+}
+
 pub struct CombatSystem {
     hit_registry: Vec<(u32, u32)>,
     player_attack_multiplier: f32,
+    enemy_attack_multiplier: f32,
+    player_modifiers: HitModifiers,
+    guard_events: Vec<GuardEvent>,
+    hurtbox_grid: SpatialGrid<EntityId>,
+    candidates: Vec<EntityId>,
+}
+
+/// Outcome of an attack that connected with a blocking defender
+#[derive(Clone, Copy, Debug)]
+pub struct GuardEvent {
+    pub attacker: EntityId,
+    pub defender: EntityId,
+    pub height: AttackHeight,
+    pub stance: BlockStance,
+    pub blocked: bool,
 }
 
 impl CombatSystem {
@@ -92,17 +430,37 @@ impl CombatSystem {
         Self {
             hit_registry: Vec::new(),
             player_attack_multiplier: 1.0,
+            enemy_attack_multiplier: 1.0,
+            player_modifiers: HitModifiers::default(),
+            guard_events: Vec::new(),
+            hurtbox_grid: SpatialGrid::new(SPATIAL_CELL_SIZE),
+            candidates: Vec::new(),
         }
     }
 
     pub fn set_player_attack_multiplier(&mut self, multiplier: f32) {
         self.player_attack_multiplier = multiplier.max(0.1);
     }
+
+    /// Scales every enemy hit, bosses included; co-op raises it per player
+    pub fn set_enemy_attack_multiplier(&mut self, multiplier: f32) {
+        self.enemy_attack_multiplier = multiplier.max(0.1);
+    }
+
+    pub fn set_player_modifiers(&mut self, modifiers: HitModifiers) {
+        self.player_modifiers = modifiers.capped();
+    }
+
+    /// Block/mixup results from the last update
+    pub fn guard_events(&self) -> &[GuardEvent] {
+        &self.guard_events
+    }
 }
 
 impl System for CombatSystem {
     fn update(&mut self, world: &mut World, dt: f32) {
         self.hit_registry.clear();
+        self.guard_events.clear();
 
         let attackers: Vec<_> = world
             .query::<HitboxComponent>()
@@ -110,11 +468,17 @@ impl System for CombatSystem {
             .map(|(e, _)| e)
             .collect();
 
-        let defenders: Vec<_> = world
-            .query::<HurtboxComponent>()
-            .filter(|(_, h)| h.active)
-            .map(|(e, _)| e)
-            .collect();
+        self.hurtbox_grid.clear();
+        for (entity, (hurtbox_comp, transform)) in world.query::<(HurtboxComponent, Transform)>() {
+            if hurtbox_comp.active {
+                self.hurtbox_grid.insert_aabb(
+                    entity,
+                    transform.position + hurtbox_comp.hurtbox.offset,
+                    hurtbox_comp.hurtbox.size,
+                );
+            }
+        }
+        let mut defenders = std::mem::take(&mut self.candidates);
 
         for attacker in attackers {
             // Extract only the data we need to avoid double cloning
@@ -145,11 +509,22 @@ impl System for CombatSystem {
                 }
             };
 
+            // Only test hurtboxes sharing a grid cell with the hitbox
+            self.hurtbox_grid
+                .query_aabb(attacker_pos + hitbox_offset, hitbox_size, &mut defenders);
+
             for defender in &defenders {
                 if attacker.as_u32() == defender.as_u32() {
                     continue;
                 }
 
+                if world
+                    .get_component::<Fighter>(*defender)
+                    .is_some_and(|f| f.invulnerable)
+                {
+                    continue;
+                }
+
                 if let (Some(att_team), Some(def_team)) = (
                     attacker_team,
                     world.get_component::<Fighter>(*defender).map(|f| f.team),
@@ -189,9 +564,13 @@ impl System for CombatSystem {
             }
         }
 
-        for (attacker_id, defender_id) in &self.hit_registry {
+        self.candidates = defenders;
+
+        let hits = std::mem::take(&mut self.hit_registry);
+        for (attacker_id, defender_id) in &hits {
             self.apply_damage(world, *attacker_id, *defender_id);
         }
+        self.hit_registry = hits;
 
         // Decay hit counter for all fighters after 2 seconds without being hit
         let fighters: Vec<_> = world.query::<Fighter>().map(|(e, _)| e).collect();
@@ -229,7 +608,25 @@ impl CombatSystem {
         left1 < right2 && right1 > left2 && top1 < bottom2 && bottom1 > top2
     }
 
-    fn apply_damage(&self, world: &mut World, attacker_id: u32, defender_id: u32) {
+    /// Returns the defender's stance if they are guarding against the attacker
+    fn guard_stance(&self, world: &World, attacker: EntityId, defender: EntityId) -> Option<BlockStance> {
+        let fighter = world.get_component::<Fighter>(defender)?;
+        if !fighter.is_blocking || fighter.hitstun > 0.0 {
+            return None;
+        }
+
+        let attacker_x = world.get_component::<Transform>(attacker)?.position.x;
+        let defender_x = world.get_component::<Transform>(defender)?.position.x;
+
+        // Attacks from behind go through the guard
+        if (attacker_x - defender_x) * fighter.facing < 0.0 {
+            return None;
+        }
+
+        Some(fighter.block_stance)
+    }
+
+    fn apply_damage(&mut self, world: &mut World, attacker_id: u32, defender_id: u32) {
         let mut damage = 10.0;
 
         let attacker_entity = EntityId(attacker_id);
@@ -251,6 +648,10 @@ impl CombatSystem {
                 // Ally attacks do moderate damage (reduced from 12.0)
                 damage = 8.0;
             }
+
+            if fighter.team == Team::Enemy {
+                damage *= self.enemy_attack_multiplier;
+            }
         }
 
         if let (Some(attacker_fighter), Some(defender_fighter)) = (
@@ -262,8 +663,74 @@ impl CombatSystem {
             }
         }
 
-        if let Some(health) = world.get_component_mut::<Health>(defender_entity) {
+        let is_player = |entity| {
+            world
+                .get_component::<Fighter>(entity)
+                .is_some_and(|f| f.character_type == CharacterType::Bas)
+        };
+        let player_attacking = is_player(attacker_entity);
+        let player_defending = is_player(defender_entity);
+        let modifiers = self.player_modifiers;
+        let critical = player_attacking
+            && modifiers.crit_chance > 0.0
+            && world.rng().chance(modifiers.crit_chance);
+        if critical {
+            damage *= modifiers.crit_multiplier;
+        }
+        if player_defending {
+            damage *= 1.0 - modifiers.damage_reduction;
+        }
+
+        let (height, blockstun, pushback, launch) = world
+            .get_component::<HitboxComponent>(attacker_entity)
+            .map(|h| {
+                (
+                    h.hitbox.height,
+                    h.hitbox.blockstun,
+                    h.hitbox.pushback.x,
+                    -h.hitbox.launch_power.y,
+                )
+            })
+            .unwrap_or((AttackHeight::Mid, 0.1, 50.0, 0.0));
+
+        if let Some(stance) = self.guard_stance(world, attacker_entity, defender_entity) {
+            let blocked = height.is_blocked_by(stance);
+            self.guard_events.push(GuardEvent {
+                attacker: attacker_entity,
+                defender: defender_entity,
+                height,
+                stance,
+                blocked,
+            });
+
+            if blocked {
+                self.apply_block(world, attacker_entity, defender_entity, damage, blockstun);
+                return;
+            }
+        }
+
+        let dealt = world.get_component_mut::<Health>(defender_entity).map_or(0.0, |health| {
+            let before = health.current;
             health.current = (health.current - damage).max(0.0);
+            before - health.current
+        });
+
+        // Lifesteal heals off what the hit actually took, not overkill
+        if player_attacking && modifiers.lifesteal > 0.0 {
+            if let Some(health) = world.get_component_mut::<Health>(attacker_entity) {
+                health.current = (health.current + dealt * modifiers.lifesteal).min(health.maximum);
+            }
+        }
+
+        if let Some(position) = world.get_component::<Transform>(defender_entity).map(|t| t.position)
+        {
+            world.events().send(GameEvent::HitLanded {
+                attacker: attacker_entity,
+                defender: defender_entity,
+                damage,
+                position,
+                critical,
+            });
         }
 
         // 3-hit stun mechanic: Only apply hitstun if defender is player and has been hit 3 times
@@ -303,80 +770,681 @@ impl CombatSystem {
             1.0
         };
 
-        if let Some(defender_velocity) = world.get_component_mut::<Velocity>(EntityId(defender_id))
-        {
-            defender_velocity.linear.x += push_dir * 220.0;
-            defender_velocity.linear.y -= 40.0;
+        apply_knockback(
+            world,
+            defender_entity,
+            Vec2::new(push_dir * (120.0 + pushback * 1.5), 0.0),
+            launch * 0.8,
+        );
+    }
+}
+
+impl CombatSystem {
+    fn apply_block(
+        &self,
+        world: &mut World,
+        attacker: EntityId,
+        defender: EntityId,
+        damage: f32,
+        blockstun: f32,
+    ) {
+        // Chip damage only
+        if let Some(health) = world.get_component_mut::<Health>(defender) {
+            health.current = (health.current - damage * 0.15).max(0.0);
+        }
+
+        if let Some(fighter) = world.get_component_mut::<Fighter>(defender) {
+            fighter.blockstun = blockstun.max(0.1);
+            fighter.state = FighterState::Blockstun;
         }
+
+        let push_dir = match (
+            world.get_component::<Transform>(attacker),
+            world.get_component::<Transform>(defender),
+        ) {
+            (Some(a), Some(d)) if (d.position.x - a.position.x).abs() >= 0.01 => {
+                (d.position.x - a.position.x).signum()
+            }
+            _ => 1.0,
+        };
+
+        apply_knockback(world, defender, Vec2::new(push_dir * 90.0, 0.0), 0.0);
     }
 }
 
-pub struct ParticleSystem;
+const CHAIR_SIZE: Vec2 = Vec2::new(50.0, 50.0);
+const CHAIR_THROW_SPEED: f32 = 650.0;
+const CHAIR_LAND_SPEED: f32 = 120.0;
+const CHAIR_DAMAGE: f32 = 14.0;
+const SOUP_SLIP: f32 = 3.0;
+const BALL_RADIUS: f32 = 45.0;
+const BALL_DAMAGE: f32 = 3.0;
+const BALL_COOLDOWN: f32 = 0.5;
+const BOOKSHELF_WIDTH: f32 = 140.0;
+const BOOKSHELF_REACH: f32 = 150.0;
+const BOOKSHELF_DAMAGE: f32 = 20.0;
+const WIND_FORCE: f32 = 900.0;
+
+/// Runs the map's `StageProp`s: chairs, soup spills, basketballs, bookshelves
+/// and wind. Props hurt fighters directly and report it as a `HitLanded` from
+/// the thrower, or from the prop itself when nobody threw it.
+pub struct StagePropSystem {
+    arena: Rect,
+}
 
-impl System for ParticleSystem {
+impl StagePropSystem {
+    pub fn new() -> Self {
+        Self {
+            arena: Rect::new(60.0, 340.0, 1800.0, 320.0),
+        }
+    }
+
+    /// Walkable area balls bounce around in and chairs stop at
+    pub fn set_arena(&mut self, arena: Rect) {
+        self.arena = arena;
+    }
+
+    fn hit_fighter(
+        world: &mut World,
+        source: EntityId,
+        target: EntityId,
+        damage: f32,
+        impulse: Vec2,
+        stun: f32,
+    ) {
+        if let Some(health) = world.get_component_mut::<Health>(target) {
+            health.current = (health.current - damage).max(0.0);
+        }
+        if let Some(fighter) = world.get_component_mut::<Fighter>(target) {
+            fighter.hitstun = fighter.hitstun.max(stun);
+            fighter.state = FighterState::Hitstun;
+        }
+        apply_knockback(world, target, impulse, 0.0);
+
+        if let Some(position) = world.get_component::<Transform>(target).map(|t| t.position) {
+            world.events().send(GameEvent::HitLanded {
+                attacker: source,
+                defender: target,
+                damage,
+                position,
+                critical: false,
+            });
+        }
+    }
+
+    /// A resting chair caught by an active hitbox flies off the way the
+    /// attacker is facing
+    fn launch_chairs(&self, world: &mut World, chairs: &[(EntityId, Vec2)]) {
+        let swings: Vec<_> = world
+            .query::<(HitboxComponent, Fighter)>()
+            .filter(|(_, (hitbox, _))| hitbox.active)
+            .map(|(e, (hitbox, fighter))| {
+                let mut offset = hitbox.hitbox.offset;
+                if fighter.facing < 0.0 {
+                    offset.x = -offset.x;
+                }
+                (e, offset, hitbox.hitbox.size, fighter.facing, fighter.team)
+            })
+            .collect();
+
+        for (attacker, offset, size, facing, team) in swings {
+            let Some(transform) = world.get_component::<Transform>(attacker) else {
+                continue;
+            };
+            let center = transform.position + offset;
+
+            for &(chair, position) in chairs {
+                let overlap = (center - position).abs();
+                if overlap.x >= (size.x + CHAIR_SIZE.x) * 0.5
+                    || overlap.y >= (size.y + CHAIR_SIZE.y) * 0.5
+                {
+                    continue;
+                }
+                if let Some(prop) = world.get_component_mut::<StageProp>(chair) {
+                    prop.kind = PropKind::Chair {
+                        thrown_by: Some((attacker, team)),
+                    };
+                }
+                if let Some(velocity) = world.get_component_mut::<Velocity>(chair) {
+                    velocity.linear = Vec2::new(facing.signum() * CHAIR_THROW_SPEED, 0.0);
+                    velocity.angular = facing.signum() * 12.0;
+                }
+            }
+        }
+    }
+
+    fn fly_chair(
+        &self,
+        world: &mut World,
+        chair: EntityId,
+        thrower: (EntityId, Team),
+        fighters: &[(EntityId, Vec2, Team)],
+        dt: f32,
+    ) {
+        let (Some(position), Some(velocity)) = (
+            world.get_component::<Transform>(chair).map(|t| t.position),
+            world.get_component::<Velocity>(chair).map(|v| v.linear),
+        ) else {
+            return;
+        };
+
+        let (thrown_by, team) = thrower;
+        let target = fighters.iter().find(|(fighter, fighter_pos, fighter_team)| {
+            *fighter != thrown_by
+                && !team.is_allied(*fighter_team)
+                && (*fighter_pos - position).abs().cmple(CHAIR_SIZE).all()
+        });
+        if let Some(&(target, _, _)) = target {
+            // Chairs break on whoever they hit
+            let impulse = Vec2::new(velocity.x.signum() * 260.0, 0.0);
+            Self::hit_fighter(world, thrown_by, target, CHAIR_DAMAGE, impulse, 0.5);
+            world.commands().destroy(chair);
+            return;
+        }
+
+        let slowed = velocity * (-1.5 * dt).exp();
+        let (left, right) = (self.arena.x, self.arena.x + self.arena.w);
+        let landed = slowed.length() < CHAIR_LAND_SPEED || position.x < left || position.x > right;
+        if landed {
+            if let Some(transform) = world.get_component_mut::<Transform>(chair) {
+                transform.position.x = transform.position.x.clamp(left, right);
+                transform.rotation = 0.0;
+            }
+            if let Some(prop) = world.get_component_mut::<StageProp>(chair) {
+                prop.kind = PropKind::Chair { thrown_by: None };
+            }
+        }
+        if let Some(velocity) = world.get_component_mut::<Velocity>(chair) {
+            velocity.linear = if landed { Vec2::ZERO } else { slowed };
+            if landed {
+                velocity.angular = 0.0;
+            }
+        }
+    }
+
+    fn bounce_ball(
+        &self,
+        world: &mut World,
+        ball: EntityId,
+        fighters: &[(EntityId, Vec2, Team)],
+        dt: f32,
+    ) {
+        let (Some(position), Some(mut velocity)) = (
+            world.get_component::<Transform>(ball).map(|t| t.position),
+            world.get_component::<Velocity>(ball).map(|v| v.linear),
+        ) else {
+            return;
+        };
+
+        let (left, right) = (self.arena.x, self.arena.x + self.arena.w);
+        let (top, bottom) = (self.arena.y, self.arena.y + self.arena.h);
+        if (position.x < left && velocity.x < 0.0) || (position.x > right && velocity.x > 0.0) {
+            velocity.x = -velocity.x;
+        }
+        if (position.y < top && velocity.y < 0.0) || (position.y > bottom && velocity.y > 0.0) {
+            velocity.y = -velocity.y;
+        }
+
+        let cooldown = world
+            .get_component_mut::<StageProp>(ball)
+            .map(|prop| {
+                prop.timer = (prop.timer - dt).max(0.0);
+                prop.timer
+            })
+            .unwrap_or(0.0);
+        let bumped = fighters
+            .iter()
+            .find(|(_, fighter_pos, _)| fighter_pos.distance(position) < BALL_RADIUS)
+            .filter(|_| cooldown <= 0.0);
+        if let Some(&(fighter, fighter_pos, _)) = bumped {
+            let away = (fighter_pos - position).normalize_or(Vec2::X);
+            Self::hit_fighter(world, ball, fighter, BALL_DAMAGE, away * 160.0, 0.2);
+            velocity = -away * velocity.length();
+            if let Some(prop) = world.get_component_mut::<StageProp>(ball) {
+                prop.timer = BALL_COOLDOWN;
+            }
+        }
+
+        if let Some(ball_velocity) = world.get_component_mut::<Velocity>(ball) {
+            ball_velocity.linear = velocity;
+        }
+    }
+
+    /// A falling bookshelf flattens everyone in the strip in front of it
+    fn topple_bookshelf(world: &mut World, shelf: EntityId, fighters: &[(EntityId, Vec2, Team)]) {
+        let Some(position) = world.get_component::<Transform>(shelf).map(|t| t.position) else {
+            return;
+        };
+
+        for &(fighter, fighter_pos, _) in fighters {
+            let offset = fighter_pos - position;
+            if offset.x.abs() < BOOKSHELF_WIDTH * 0.5 && (0.0..BOOKSHELF_REACH).contains(&offset.y)
+            {
+                let impulse = Vec2::new(0.0, 220.0);
+                Self::hit_fighter(world, shelf, fighter, BOOKSHELF_DAMAGE, impulse, 0.8);
+                if let Some(fighter) = world.get_component_mut::<Fighter>(fighter) {
+                    fighter.state = FighterState::KnockedDown;
+                }
+            }
+        }
+    }
+}
+
+impl System for StagePropSystem {
     fn update(&mut self, world: &mut World, dt: f32) {
-        let entities: Vec<_> = world.query_mut::<Particle>().map(|(e, _)| e).collect();
+        let props: Vec<_> = world
+            .query::<(StageProp, Transform)>()
+            .map(|(e, (prop, transform))| (e, prop.kind, transform.position))
+            .collect();
+        if props.is_empty() {
+            return;
+        }
 
-        let mut to_destroy = Vec::new();
+        // Fighters still walking in and invulnerable ones are left alone
+        let fighters: Vec<_> = world
+            .query::<(Fighter, Transform)>()
+            .filter(|(e, (fighter, _))| {
+                !fighter.invulnerable
+                    && !world
+                        .get_component::<SpawnEntrance>(*e)
+                        .is_some_and(|s| s.is_entering())
+            })
+            .map(|(e, (fighter, transform))| (e, transform.position, fighter.team))
+            .collect();
 
-        for entity in entities {
-            let mut should_destroy = false;
-            let mut velocity_update = Vec2::ZERO;
+        let resting_chairs: Vec<_> = props
+            .iter()
+            .filter(|(_, kind, _)| *kind == PropKind::Chair { thrown_by: None })
+            .map(|&(e, _, position)| (e, position))
+            .collect();
+        self.launch_chairs(world, &resting_chairs);
+
+        for &(prop, kind, position) in &props {
+            match kind {
+                PropKind::Chair {
+                    thrown_by: Some(thrower),
+                } => self.fly_chair(world, prop, thrower, &fighters, dt),
+                PropKind::Chair { thrown_by: None } => {}
+                PropKind::SoupSpill { radius } => {
+                    for &(fighter, fighter_pos, _) in &fighters {
+                        // The puddle is an ellipse, squashed by the floor's perspective
+                        let offset = (fighter_pos - position) / Vec2::new(radius, radius * 0.5);
+                        let airborne = world
+                            .get_component::<Knockback>(fighter)
+                            .is_some_and(|kb| kb.is_airborne());
+                        if offset.length_squared() > 1.0 || airborne {
+                            continue;
+                        }
+                        let moving = world.get_component::<Velocity>(fighter).map(|v| v.linear);
+                        if let Some(moving) = moving {
+                            apply_knockback(world, fighter, moving * SOUP_SLIP * dt, 0.0);
+                        }
+                    }
+                }
+                PropKind::Basketball => self.bounce_ball(world, prop, &fighters, dt),
+                PropKind::Bookshelf => {
+                    let Some(shelf) = world.get_component_mut::<StageProp>(prop) else {
+                        continue;
+                    };
+                    let was_standing = !shelf.toppled();
+                    shelf.timer += dt;
+                    if was_standing && shelf.toppled() {
+                        Self::topple_bookshelf(world, prop, &fighters);
+                    }
+                }
+                PropKind::WindGust { .. } => {
+                    let Some(wind) = world.get_component_mut::<StageProp>(prop) else {
+                        continue;
+                    };
+                    wind.timer += dt;
+                    let Some(direction) = wind.gust() else {
+                        continue;
+                    };
+                    let push = Vec2::new(direction * WIND_FORCE * dt, 0.0);
+                    for &(fighter, _, _) in &fighters {
+                        apply_knockback(world, fighter, push, 0.0);
+                    }
+                }
+            }
+        }
+    }
+}
 
-            if let Some(particle) = world.get_component_mut::<Particle>(entity) {
-                particle.lifetime += dt;
-                particle.velocity += particle.acceleration * dt;
-                velocity_update = particle.velocity * dt;
+/// Smashes `Destructible`s caught by active hitboxes, throwing debris and
+/// sometimes Arc Tokens
+pub struct DestructibleSystem;
 
-                if particle.lifetime >= particle.max_lifetime {
-                    should_destroy = true;
+impl DestructibleSystem {
+    fn throw_debris(world: &mut World, position: Vec2, debris: DebrisSpec, count: usize) {
+        world
+            .commands()
+            .spawn()
+            .with(Transform {
+                position,
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            })
+            .with(
+                Emitter::burst(count, ParticleType::Dust, debris.colors)
+                    .shape(EmitterShape::Circle(20.0))
+                    .speed(debris.speed * 0.5, debris.speed)
+                    .lifetime(0.4, 0.9)
+                    .size(debris.size, debris.size * 0.5)
+                    .acceleration(Vec2::new(0.0, 600.0)),
+            );
+    }
+
+    fn smash(&self, world: &mut World) {
+        let swings: Vec<_> = world
+            .query::<(HitboxComponent, Fighter)>()
+            .filter(|(_, (hitbox, _))| hitbox.active)
+            .map(|(e, (hitbox, fighter))| {
+                let mut offset = hitbox.hitbox.offset;
+                if fighter.facing < 0.0 {
+                    offset.x = -offset.x;
                 }
+                (e, offset, hitbox.hitbox.size, hitbox.hitbox.damage)
+            })
+            .collect();
+        if swings.is_empty() {
+            return;
+        }
 
-                let progress = particle.lifetime / particle.max_lifetime;
-                let scale =
-                    particle.size_start + (particle.size_end - particle.size_start) * progress;
+        let props: Vec<_> = world
+            .query::<(Destructible, Transform)>()
+            .filter(|(_, (prop, _))| !prop.is_broken())
+            .map(|(e, (prop, transform))| (e, transform.position, prop.size))
+            .collect();
 
-                if let Some(transform) = world.get_component_mut::<Transform>(entity) {
-                    transform.position += velocity_update;
-                    transform.scale = Vec2::splat(scale);
+        for (attacker, offset, size, damage) in swings {
+            let Some(transform) = world.get_component::<Transform>(attacker) else {
+                continue;
+            };
+            let center = transform.position + offset;
+
+            for &(prop, position, prop_size) in &props {
+                let overlap = (center - position).abs();
+                if overlap.x >= (size.x + prop_size.x) * 0.5
+                    || overlap.y >= (size.y + prop_size.y) * 0.5
+                {
+                    continue;
+                }
+
+                // One hit per swing, the same way fighters are hit
+                let Some(hitbox) = world.get_component_mut::<HitboxComponent>(attacker) else {
+                    continue;
+                };
+                if hitbox.hits_registered.contains(&prop.as_u32()) {
+                    continue;
+                }
+                hitbox.hits_registered.push(prop.as_u32());
+
+                let Some(target) = world.get_component_mut::<Destructible>(prop) else {
+                    continue;
+                };
+                if target.is_broken() {
+                    continue;
+                }
+                target.health = (target.health - damage).max(0.0);
+                let (debris, broken, loot_chance) =
+                    (target.debris, target.is_broken(), target.loot_chance);
+
+                let count = if broken { debris.count * 3 } else { debris.count };
+                Self::throw_debris(world, position, debris, count);
+
+                if broken && world.rng().chance(loot_chance) {
+                    let tokens = world.rng().range(5, 16) as u32;
+                    world
+                        .commands()
+                        .spawn()
+                        .with(Transform {
+                            position,
+                            rotation: 0.0,
+                            scale: Vec2::ONE,
+                        })
+                        .with(Pickup::new(
+                            LootDrop::Tokens(tokens),
+                            Rarity::Common,
+                        ));
+                }
+            }
+        }
+    }
+}
+
+impl System for DestructibleSystem {
+    fn update(&mut self, world: &mut World, _dt: f32) {
+        self.smash(world);
+    }
+}
+
+const PICKUP_RADIUS: f32 = 45.0;
+/// Pickups closer than this to a player start drifting toward them
+const MAGNET_RADIUS: f32 = 170.0;
+const MAGNET_SPEED: f32 = 380.0;
+
+/// Pulls `Pickup`s toward nearby players, hands them to whoever walks over
+/// them and clears away the ones nobody wanted
+pub struct PickupSystem;
+
+impl System for PickupSystem {
+    fn update(&mut self, world: &mut World, dt: f32) {
+        let pickups: Vec<_> = world
+            .query::<(Pickup, Transform)>()
+            .map(|(e, (pickup, transform))| (e, pickup.drop, transform.position))
+            .collect();
+        if pickups.is_empty() {
+            return;
+        }
+
+        let players: Vec<_> = world
+            .query::<(Fighter, Transform)>()
+            .filter(|(_, (fighter, _))| fighter.team == Team::Player)
+            .map(|(e, (_, transform))| (e, transform.position))
+            .collect();
+
+        for (pickup, drop, position) in pickups {
+            let nearest = players.iter().min_by(|(_, a), (_, b)| {
+                a.distance(position).total_cmp(&b.distance(position))
+            });
+
+            if let Some(&(collector, player_pos)) = nearest {
+                let distance = player_pos.distance(position);
+                if distance < PICKUP_RADIUS {
+                    world.events().send(GameEvent::PickupCollected {
+                        collector,
+                        drop,
+                        position,
+                    });
+                    world.commands().destroy(pickup);
+                    continue;
+                }
+
+                // Faster the closer it gets, so it snaps in at the end
+                if distance < MAGNET_RADIUS {
+                    let pull = 1.5 - distance / MAGNET_RADIUS;
+                    let step = (MAGNET_SPEED * pull * dt).min(distance);
+                    if let Some(transform) = world.get_component_mut::<Transform>(pickup) {
+                        transform.position += (player_pos - position).normalize_or_zero() * step;
+                    }
+                }
+            }
+
+            let Some(item) = world.get_component_mut::<Pickup>(pickup) else {
+                continue;
+            };
+            item.lifetime -= dt;
+            if item.lifetime <= 0.0 {
+                world.commands().destroy(pickup);
+            }
+        }
+    }
+}
+
+/// Hard cap on live particles; emitters past it are silently dropped
+const MAX_PARTICLES: usize = 2048;
+
+/// Runs every `Emitter` and simulates the particles they release. Particles
+/// live in a plain Vec and are recycled through an object pool, so heavy
+/// effects don't churn entities or allocations.
+pub struct ParticleSystem {
+    pool: ObjectPool<Particle>,
+    live: Vec<Particle>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self {
+            pool: ObjectPool::new(256, Particle::default, |_| {}),
+            live: Vec::with_capacity(256),
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.live
+    }
+
+    fn emit(&mut self, world: &mut World, dt: f32) {
+        let emitters: Vec<_> = world
+            .query::<(Emitter, Transform)>()
+            .map(|(e, (_, transform))| (e, transform.position, transform.scale.x))
+            .collect();
+
+        for (entity, origin, scale) in emitters {
+            let Some(emitter) = world.get_component_mut::<Emitter>(entity) else {
+                continue;
+            };
+
+            let count = emitter.advance(dt);
+            for _ in 0..count {
+                if self.live.len() >= MAX_PARTICLES {
+                    break;
                 }
+                let mut particle = self.pool.acquire();
+                emitter.emit(origin, scale, &mut particle);
+                self.live.push(particle);
             }
 
-            if should_destroy {
-                to_destroy.push(entity);
+            if emitter.is_finished() {
+                if emitter.despawn_when_done {
+                    world.commands().destroy(entity);
+                } else {
+                    world.remove_component::<Emitter>(entity);
+                }
             }
         }
+    }
+}
 
-        for entity in to_destroy {
-            world.destroy_entity(entity);
+impl System for ParticleSystem {
+    fn update(&mut self, world: &mut World, dt: f32) {
+        self.emit(world, dt);
+
+        let mut i = 0;
+        while i < self.live.len() {
+            let particle = &mut self.live[i];
+            particle.lifetime += dt;
+            particle.velocity +=
+                (particle.acceleration + particle.direction * particle.radial_acceleration) * dt;
+            particle.position += particle.velocity * dt;
+
+            if particle.is_dead() {
+                let dead = self.live.swap_remove(i);
+                self.pool.release(dead);
+            } else {
+                i += 1;
+            }
         }
     }
 }
 
-pub struct AISystem;
+/// Extra decision speed against an exhausted target, as a share of dt
+const EXHAUSTED_PRESSURE: f32 = 1.0;
+/// How close an AI has to be to punish an exhausted target with a heavy
+const EXHAUSTED_PUNISH_RANGE: f32 = 110.0;
+
+pub struct AISystem {
+    fighter_grid: SpatialGrid<EntityId>,
+}
+
+/// What an AI fighter does once it's ready to act
+enum AIChoice {
+    Block(BlockStance),
+    Attack(FighterState, AttackHeight),
+    Wait,
+}
+
+impl AISystem {
+    pub fn new() -> Self {
+        Self {
+            fighter_grid: SpatialGrid::new(SPATIAL_CELL_SIZE),
+        }
+    }
+}
 
 impl System for AISystem {
     fn update(&mut self, world: &mut World, dt: f32) {
         let entities: Vec<_> = world.query::<AIController>().map(|(e, _)| e).collect();
+        let view_width = world.view_width();
+
+        self.fighter_grid.clear();
+        for (entity, (_, transform)) in world.query::<(Fighter, Transform)>() {
+            self.fighter_grid.insert(entity, transform.position);
+        }
 
         for entity in entities {
-            let (behavior, mut target, difficulty, ready_to_act) =
+            if world
+                .get_component::<SpawnEntrance>(entity)
+                .is_some_and(|s| s.is_entering())
+            {
+                continue;
+            }
+
+            // Bosses move up a phase as they're worn down, and never back
+            let health = world
+                .get_component::<Health>(entity)
+                .map(|health| health.current / health.maximum);
+            if let (Some(health), Some(ai)) =
+                (health, world.get_component_mut::<AIController>(entity))
+            {
+                if let AIBehavior::Boss(phase) = &mut ai.behavior {
+                    *phase = (*phase).max(BossPhase::for_health(health));
+                }
+            }
+            if self.step_windup(world, entity, dt) {
+                continue;
+            }
+
+            let (behavior, mut target, difficulty, ability_rate, ready_to_act) =
                 match world.get_component_mut::<AIController>(entity) {
                     Some(ai) => {
                         ai.state_timer += dt;
                         let ready = ai.state_timer >= ai.reaction_delay;
                         if ready {
                             ai.state_timer = 0.0;
-                            // Increased cooldown: enemies attack less frequently
-                            ai.reaction_delay = (0.8_f32 - ai.difficulty * 0.3_f32).max(0.5_f32)
-                                + rand::gen_range(0.0, 0.3);
                         }
 
-                        (ai.behavior.clone(), ai.target_entity, ai.difficulty, ready)
+                        (
+                            ai.behavior.clone(),
+                            ai.target_entity,
+                            ai.difficulty,
+                            ai.ability_rate,
+                            ready,
+                        )
                     }
                     None => continue,
                 };
 
+            if ready_to_act {
+                let jitter = world.rng().range_f32(0.0, 0.3);
+                if let Some(ai) = world.get_component_mut::<AIController>(entity) {
+                    // Increased cooldown: enemies attack less frequently
+                    ai.reaction_delay = ((0.8_f32 - ai.difficulty * 0.3_f32).max(0.5_f32)
+                        + jitter)
+                        * ai.reaction_scale;
+                }
+            }
+
             let team = world
                 .get_component::<Fighter>(entity)
                 .map(|f| f.team)
@@ -420,6 +1488,20 @@ impl System for AISystem {
                 None => continue,
             };
 
+            let target_guard = world
+                .get_component::<Fighter>(target)
+                .map(|f| (f.is_blocking, f.block_stance));
+
+            // Hardcore: an exhausted target can't guard or dash away, so stop
+            // backing off, think faster and swing heavy while it lasts
+            let target_exhausted = crate::data::get_hardcore_stamina()
+                && world.get_component::<Stamina>(target).is_some_and(|s| s.exhausted);
+            if target_exhausted {
+                if let Some(ai) = world.get_component_mut::<AIController>(entity) {
+                    ai.state_timer += dt * EXHAUSTED_PRESSURE;
+                }
+            }
+
             let separation = target_pos - ai_pos;
             let distance = separation.length();
 
@@ -434,7 +1516,7 @@ impl System for AISystem {
             if distance > approach_distance {
                 movement_dir = separation.x.signum();
                 movement_speed = speed;
-            } else if distance < retreat_distance {
+            } else if distance < retreat_distance && !target_exhausted {
                 movement_dir = -separation.x.signum();
                 movement_speed = retreat_speed;
             }
@@ -442,8 +1524,15 @@ impl System for AISystem {
             let is_moving = movement_dir.abs() > 0.1;
             let depth_difference = target_pos.y - ai_pos.y;
 
+            // Knockback carries stunned fighters; don't steer against it
+            let stunned = world
+                .get_component::<Fighter>(entity)
+                .is_some_and(|f| f.hitstun > 0.0 || f.state == FighterState::KnockedDown);
+
             if let Some(velocity) = world.get_component_mut::<Velocity>(entity) {
-                if is_moving {
+                if stunned {
+                    velocity.linear = Vec2::ZERO;
+                } else if is_moving {
                     velocity.linear.x = movement_dir.signum() * movement_speed;
                 } else {
                     velocity.linear.x = 0.0;
@@ -457,10 +1546,38 @@ impl System for AISystem {
             }
 
             if let Some(transform) = world.get_component_mut::<Transform>(entity) {
-                transform.position.x = transform.position.x.clamp(50.0, screen_width() * 1.2);
+                transform.position.x = transform.position.x.clamp(50.0, view_width * 1.2);
                 transform.position.y = transform.position.y.clamp(340.0, 660.0);
             }
 
+            // Rolled before the fighter is borrowed, since the dice are the world's
+            let choice = ready_to_act.then(|| {
+                let rng = world.rng();
+                let action = if target_exhausted && distance < EXHAUSTED_PUNISH_RANGE {
+                    Some(FighterState::HeavyAttack)
+                } else {
+                    self.choose_action(rng, &behavior, distance)
+                };
+                match Self::scale_ability_use(rng, action, ability_rate) {
+                    Some(FighterState::Blocking) => {
+                        AIChoice::Block(self.choose_block_stance(rng))
+                    }
+                    Some(state) => {
+                        let height = self.choose_attack_height(rng, difficulty, target_guard);
+                        AIChoice::Attack(state, height)
+                    }
+                    None => AIChoice::Wait,
+                }
+            });
+            // Bosses telegraph their attacks instead of throwing them straight away
+            let choice = match (choice, &behavior) {
+                (Some(AIChoice::Attack(attack, height)), AIBehavior::Boss(phase)) => {
+                    self.start_windup(world, entity, attack, height, *phase);
+                    Some(AIChoice::Wait)
+                }
+                (choice, _) => choice,
+            };
+
             if let Some(fighter) = world.get_component_mut::<Fighter>(entity) {
                 if fighter.hitstun <= 0.0 && fighter.blockstun <= 0.0 {
                     if is_moving
@@ -475,10 +1592,24 @@ impl System for AISystem {
                         fighter.facing = separation.x.signum();
                     }
 
-                    if ready_to_act {
-                        if let Some(new_state) = self.choose_action(&behavior, distance) {
+                    match choice {
+                        Some(AIChoice::Block(stance)) => {
+                            fighter.state = FighterState::Blocking;
+                            fighter.is_blocking = true;
+                            fighter.block_stance = stance;
+                        }
+                        Some(AIChoice::Attack(new_state, height)) => {
                             fighter.state = new_state;
+                            fighter.is_blocking = false;
+                            fighter.next_attack_height = height;
                         }
+                        Some(AIChoice::Wait) => {
+                            if fighter.state == FighterState::Blocking {
+                                fighter.state = FighterState::Idle;
+                            }
+                            fighter.is_blocking = false;
+                        }
+                        None => {}
                     }
                 }
             }
@@ -487,6 +1618,82 @@ impl System for AISystem {
 }
 
 impl AISystem {
+    /// Start winding up `attack`, telling the state which cue to play
+    fn start_windup(
+        &self,
+        world: &mut World,
+        entity: EntityId,
+        attack: FighterState,
+        height: AttackHeight,
+        phase: BossPhase,
+    ) {
+        let Some(character) = world.get_component::<Fighter>(entity).map(|f| f.character_type)
+        else {
+            return;
+        };
+        let Some(telegraph) = Telegraph::for_attack(character, attack) else {
+            return;
+        };
+        let position = world
+            .get_component::<Transform>(entity)
+            .map_or(Vec2::ZERO, |transform| transform.position);
+        world.add_component(
+            entity,
+            BossWindup {
+                attack,
+                height,
+                telegraph,
+                windup: telegraph.windup_in(phase),
+                elapsed: 0.0,
+                landing: false,
+            },
+        );
+        world.events().send(GameEvent::BossWindup {
+            cue: telegraph.audio_cue,
+            position,
+        });
+    }
+
+    /// Run a boss's telegraphed attack: it stands still through the wind-up,
+    /// then throws the attack and holds the warning up while it lands. Hits
+    /// hold the wind-up where it is until the boss recovers. True while the
+    /// boss is busy with one.
+    fn step_windup(&self, world: &mut World, entity: EntityId, dt: f32) -> bool {
+        let stunned = world.get_component::<Fighter>(entity).map(|fighter| {
+            fighter.hitstun > 0.0
+                || matches!(fighter.state, FighterState::Hitstun | FighterState::KnockedDown)
+        });
+        let Some(windup) = world.get_component_mut::<BossWindup>(entity) else {
+            return false;
+        };
+        if stunned == Some(false) {
+            windup.elapsed += dt;
+        }
+        let (attack, height, landing) = (windup.attack, windup.height, windup.landing);
+        let due = windup.elapsed >= windup.windup;
+
+        let Some(fighter) = world.get_component_mut::<Fighter>(entity) else {
+            world.remove_component::<BossWindup>(entity);
+            return false;
+        };
+        if landing && fighter.state != attack {
+            world.remove_component::<BossWindup>(entity);
+            return false;
+        }
+        if due && !landing {
+            fighter.state = attack;
+            fighter.is_blocking = false;
+            fighter.next_attack_height = height;
+            if let Some(windup) = world.get_component_mut::<BossWindup>(entity) {
+                windup.landing = true;
+            }
+        }
+        if let Some(velocity) = world.get_component_mut::<Velocity>(entity) {
+            velocity.linear = Vec2::ZERO;
+        }
+        true
+    }
+
     fn find_player(&self, world: &World) -> Option<crate::ecs::entity::EntityId> {
         world
             .query::<Fighter>()
@@ -504,24 +1711,14 @@ impl AISystem {
             .get_component::<Transform>(origin)
             .map(|t| t.position)?;
 
-        let mut closest = None;
-        let mut min_dist_sq = f32::MAX;
-
-        for (entity, fighter) in world.query::<Fighter>() {
-            if entity == origin || fighter.team != team {
-                continue;
-            }
-
-            if let Some(transform) = world.get_component::<Transform>(entity) {
-                let dist_sq = (transform.position - origin_pos).length_squared();
-                if dist_sq < min_dist_sq {
-                    min_dist_sq = dist_sq;
-                    closest = Some(entity);
-                }
-            }
-        }
-
-        closest
+        self.fighter_grid
+            .nearest(origin_pos, |entity| {
+                entity != origin
+                    && world
+                        .get_component::<Fighter>(entity)
+                        .is_some_and(|f| f.team == team)
+            })
+            .map(|(entity, _)| entity)
     }
 
     fn find_nearest_non_ally(
@@ -534,29 +1731,51 @@ impl AISystem {
             .get_component::<Transform>(origin)
             .map(|t| t.position)?;
 
-        let mut closest = None;
-        let mut min_dist_sq = f32::MAX;
+        // Target anyone not on our team (enemies target player and allies)
+        self.fighter_grid
+            .nearest(origin_pos, |entity| {
+                entity != origin
+                    && world
+                        .get_component::<Fighter>(entity)
+                        .is_some_and(|f| f.team != my_team)
+            })
+            .map(|(entity, _)| entity)
+    }
 
-        for (entity, fighter) in world.query::<Fighter>() {
-            if entity == origin {
-                continue;
-            }
+    /// Mixups only kick in for stronger AI; weaker AI always attacks mid
+    fn choose_attack_height(
+        &self,
+        rng: &mut SeededRng,
+        difficulty: f32,
+        target_guard: Option<(bool, BlockStance)>,
+    ) -> AttackHeight {
+        if difficulty < 0.6 {
+            return AttackHeight::Mid;
+        }
 
-            // Target anyone not on our team (enemies target player and allies)
-            if fighter.team == my_team {
-                continue;
+        let roll = rng.next_f32();
+        match target_guard {
+            // Read the guard and go for the height that opens it up
+            Some((true, stance)) if roll < (difficulty - 0.3).min(0.9) => {
+                AttackHeight::beating(stance)
             }
-
-            if let Some(transform) = world.get_component::<Transform>(entity) {
-                let dist_sq = (transform.position - origin_pos).length_squared();
-                if dist_sq < min_dist_sq {
-                    min_dist_sq = dist_sq;
-                    closest = Some(entity);
+            _ if roll < (difficulty - 0.5).min(0.5) => {
+                if rng.chance(0.5) {
+                    AttackHeight::Overhead
+                } else {
+                    AttackHeight::Low
                 }
             }
+            _ => AttackHeight::Mid,
         }
+    }
 
-        closest
+    fn choose_block_stance(&self, rng: &mut SeededRng) -> BlockStance {
+        if rng.chance(0.5) {
+            BlockStance::Standing
+        } else {
+            BlockStance::Crouching
+        }
     }
 
     fn behavior_distances(&self, behavior: &AIBehavior) -> (f32, f32) {
@@ -570,8 +1789,38 @@ impl AISystem {
         }
     }
 
-    fn choose_action(&self, behavior: &AIBehavior, distance: f32) -> Option<FighterState> {
-        let roll = rand::gen_range(0.0, 1.0);
+    /// Below 1.0 some specials become light attacks; above 1.0 some heavies become specials
+    fn scale_ability_use(
+        rng: &mut SeededRng,
+        action: Option<FighterState>,
+        ability_rate: f32,
+    ) -> Option<FighterState> {
+        match action {
+            Some(FighterState::Special) if ability_rate < 1.0 => {
+                if rng.chance(ability_rate) {
+                    action
+                } else {
+                    Some(FighterState::LightAttack)
+                }
+            }
+            Some(FighterState::HeavyAttack) if ability_rate > 1.0 => {
+                if rng.chance(ability_rate - 1.0) {
+                    Some(FighterState::Special)
+                } else {
+                    action
+                }
+            }
+            _ => action,
+        }
+    }
+
+    fn choose_action(
+        &self,
+        rng: &mut SeededRng,
+        behavior: &AIBehavior,
+        distance: f32,
+    ) -> Option<FighterState> {
+        let roll = rng.next_f32();
 
         match behavior {
             AIBehavior::Aggressive => {
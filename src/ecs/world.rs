@@ -1,107 +1,225 @@
+use crate::ecs::commands::Commands;
 use crate::ecs::entity::EntityId;
+use crate::ecs::events::EventQueue;
+use crate::util::rng::SeededRng;
 use ahash::AHashMap;
 use std::any::{Any, TypeId};
+use std::cell::{RefCell, RefMut};
 
 pub struct World {
-    entities: Vec<EntityId>,
+    alive: Vec<bool>,
     components: AHashMap<TypeId, Box<dyn ComponentStorage>>,
     next_entity_id: u32,
     free_list: Vec<EntityId>,
+    commands: RefCell<Commands>,
+    events: EventQueue,
+    rng: SeededRng,
+    view_width: f32,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
-            entities: Vec::new(),
+            alive: Vec::new(),
             components: AHashMap::new(),
             next_entity_id: 0,
             free_list: Vec::new(),
+            commands: RefCell::new(Commands::default()),
+            events: EventQueue::default(),
+            rng: SeededRng::new(0),
+            view_width: 1280.0,
         }
     }
 
+    /// Gameplay event queue shared by systems and states
+    pub fn events(&mut self) -> &mut EventQueue {
+        &mut self.events
+    }
+
+    /// The simulation's random numbers: crits, AI rolls and drops. Only ever
+    /// drawn from inside a tick, so the same seed and inputs replay the same
+    /// run. Camera shake and other cosmetics use macroquad's `rand` instead,
+    /// which can be drawn any number of times a rendered frame.
+    pub fn rng(&mut self) -> &mut SeededRng {
+        &mut self.rng
+    }
+
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = SeededRng::new(seed);
+    }
+
+    /// Width of the screen the fight is shown on, which the AI keeps to;
+    /// set by the state from its window so systems never read one
+    pub fn view_width(&self) -> f32 {
+        self.view_width
+    }
+
+    pub fn set_view_width(&mut self, width: f32) {
+        self.view_width = width;
+    }
+
+    /// Deferred spawn/destroy/add_component, usable while iterating a query.
+    /// Nothing happens until `flush`; don't hold two of these at once.
+    pub fn commands(&self) -> RefMut<'_, Commands> {
+        self.commands.borrow_mut()
+    }
+
+    /// Sync point: apply everything queued through `commands`
+    pub fn flush(&mut self) {
+        let commands = std::mem::take(self.commands.get_mut());
+        commands.apply(self);
+    }
+
     pub fn create_entity(&mut self) -> EntityId {
         let id = if let Some(recycled) = self.free_list.pop() {
             recycled
         } else {
             let new_id = EntityId(self.next_entity_id);
             self.next_entity_id += 1;
+            self.alive.push(false);
             new_id
         };
 
-        self.entities.push(id);
+        self.alive[id.0 as usize] = true;
         id
     }
 
     pub fn destroy_entity(&mut self, entity: EntityId) {
-        if let Some(pos) = self.entities.iter().position(|&e| e == entity) {
-            self.entities.swap_remove(pos);
-            self.free_list.push(entity);
+        if !self.is_alive(entity) {
+            return;
+        }
 
-            for storage in self.components.values_mut() {
-                storage.remove(entity);
-            }
+        self.alive[entity.0 as usize] = false;
+        self.free_list.push(entity);
+
+        for storage in self.components.values_mut() {
+            storage.remove(entity);
         }
     }
 
-    pub fn add_component<T: Component>(&mut self, entity: EntityId, component: T) {
-        let type_id = TypeId::of::<T>();
+    pub fn entity_count(&self) -> usize {
+        self.alive.iter().filter(|&&alive| alive).count()
+    }
 
-        if !self.components.contains_key(&type_id) {
-            self.components
-                .insert(type_id, Box::new(ComponentVec::<T>::new()));
-        }
+    pub fn is_alive(&self, entity: EntityId) -> bool {
+        self.alive.get(entity.0 as usize).copied().unwrap_or(false)
+    }
 
-        if let Some(storage) = self.components.get_mut(&type_id) {
-            if let Some(typed_storage) = storage.as_any_mut().downcast_mut::<ComponentVec<T>>() {
-                typed_storage.insert(entity, component);
-            }
-        }
+    pub fn add_component<T: Component>(&mut self, entity: EntityId, component: T) {
+        self.components
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+            .expect("component storage registered under the wrong type")
+            .insert(entity, component);
+    }
+
+    pub fn remove_component<T: Component>(&mut self, entity: EntityId) -> Option<T> {
+        self.storage_mut::<T>()?.take(entity)
     }
 
     pub fn get_component<T: Component>(&self, entity: EntityId) -> Option<&T> {
-        let type_id = TypeId::of::<T>();
+        self.storage::<T>()?.get(entity)
+    }
+
+    pub fn get_component_mut<T: Component>(&mut self, entity: EntityId) -> Option<&mut T> {
+        self.storage_mut::<T>()?.get_mut(entity)
+    }
 
+    /// Iterate entities matching a single component type or a tuple of them,
+    /// e.g. `query::<Fighter>()` or `query::<(Transform, Velocity, Fighter)>()`.
+    /// Tuple queries walk the smallest storage and probe the others.
+    pub fn query<Q: Query>(&self) -> impl Iterator<Item = (EntityId, Q::Item<'_>)> {
+        let fetch = Q::fetch(self);
+        let driver: &[EntityId] = fetch.as_ref().map(Q::driver).unwrap_or(&[]);
+
+        driver.iter().filter_map(move |&entity| {
+            let fetch = fetch.as_ref()?;
+            Q::get(fetch, entity).map(|item| (entity, item))
+        })
+    }
+
+    fn storage<T: Component>(&self) -> Option<&SparseSet<T>> {
         self.components
-            .get(&type_id)?
+            .get(&TypeId::of::<T>())?
             .as_any()
-            .downcast_ref::<ComponentVec<T>>()?
-            .get(entity)
+            .downcast_ref::<SparseSet<T>>()
     }
 
-    pub fn get_component_mut<T: Component>(&mut self, entity: EntityId) -> Option<&mut T> {
-        let type_id = TypeId::of::<T>();
-
+    fn storage_mut<T: Component>(&mut self) -> Option<&mut SparseSet<T>> {
         self.components
-            .get_mut(&type_id)?
+            .get_mut(&TypeId::of::<T>())?
             .as_any_mut()
-            .downcast_mut::<ComponentVec<T>>()?
-            .get_mut(entity)
+            .downcast_mut::<SparseSet<T>>()
     }
+}
 
-    pub fn query<T: Component>(&self) -> impl Iterator<Item = (EntityId, &T)> {
-        let type_id = TypeId::of::<T>();
+pub trait Component: 'static + Send + Sync {}
 
-        self.components
-            .get(&type_id)
-            .and_then(|storage| storage.as_any().downcast_ref::<ComponentVec<T>>())
-            .map(|typed_storage| typed_storage.iter())
-            .into_iter()
-            .flatten()
+/// Something `World::query` can iterate: a component or a tuple of components
+pub trait Query {
+    type Fetch<'w>;
+    type Item<'w>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>>;
+    fn driver<'w>(fetch: &Self::Fetch<'w>) -> &'w [EntityId];
+    fn get<'w>(fetch: &Self::Fetch<'w>, entity: EntityId) -> Option<Self::Item<'w>>;
+}
+
+impl<T: Component> Query for T {
+    type Fetch<'w> = &'w SparseSet<T>;
+    type Item<'w> = &'w T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
     }
 
-    pub fn query_mut<T: Component>(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
-        let type_id = TypeId::of::<T>();
+    fn driver<'w>(fetch: &Self::Fetch<'w>) -> &'w [EntityId] {
+        &fetch.entities
+    }
 
-        self.components
-            .get_mut(&type_id)
-            .and_then(|storage| storage.as_any_mut().downcast_mut::<ComponentVec<T>>())
-            .map(|typed_storage| typed_storage.iter_mut())
-            .into_iter()
-            .flatten()
+    fn get<'w>(fetch: &Self::Fetch<'w>, entity: EntityId) -> Option<Self::Item<'w>> {
+        fetch.get(entity)
     }
 }
 
-pub trait Component: 'static + Send + Sync {}
+macro_rules! impl_tuple_query {
+    ($($name:ident),+) => {
+        impl<$($name: Component),+> Query for ($($name,)+) {
+            type Fetch<'w> = ($(&'w SparseSet<$name>,)+);
+            type Item<'w> = ($(&'w $name,)+);
+
+            fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+                Some(($(world.storage::<$name>()?,)+))
+            }
+
+            #[allow(non_snake_case)]
+            fn driver<'w>(fetch: &Self::Fetch<'w>) -> &'w [EntityId] {
+                let ($($name,)+) = *fetch;
+                let mut smallest: &'w [EntityId] = &[];
+                let mut smallest_len = usize::MAX;
+                $(
+                    if $name.entities.len() < smallest_len {
+                        smallest_len = $name.entities.len();
+                        smallest = &$name.entities;
+                    }
+                )+
+                smallest
+            }
+
+            #[allow(non_snake_case)]
+            fn get<'w>(fetch: &Self::Fetch<'w>, entity: EntityId) -> Option<Self::Item<'w>> {
+                let ($($name,)+) = *fetch;
+                Some(($($name.get(entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple_query!(A, B);
+impl_tuple_query!(A, B, C);
+impl_tuple_query!(A, B, C, D);
 
 trait ComponentStorage: Any + Send + Sync {
     fn remove(&mut self, entity: EntityId);
@@ -109,41 +227,75 @@ trait ComponentStorage: Any + Send + Sync {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-struct ComponentVec<T: Component> {
-    data: AHashMap<EntityId, T>,
+const EMPTY: u32 = u32::MAX;
+
+/// Sparse set: `sparse` maps entity ids to slots in the packed `entities`/`data` arrays,
+/// so lookups are O(1) and iteration walks contiguous memory.
+pub struct SparseSet<T: Component> {
+    sparse: Vec<u32>,
+    entities: Vec<EntityId>,
+    data: Vec<T>,
 }
 
-impl<T: Component> ComponentVec<T> {
+impl<T: Component> SparseSet<T> {
     fn new() -> Self {
         Self {
-            data: AHashMap::new(),
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            data: Vec::new(),
         }
     }
 
-    fn insert(&mut self, entity: EntityId, component: T) {
-        self.data.insert(entity, component);
+    fn slot(&self, entity: EntityId) -> Option<usize> {
+        match self.sparse.get(entity.0 as usize) {
+            Some(&index) if index != EMPTY => Some(index as usize),
+            _ => None,
+        }
     }
 
-    fn get(&self, entity: EntityId) -> Option<&T> {
-        self.data.get(&entity)
+    fn insert(&mut self, entity: EntityId, component: T) {
+        if let Some(index) = self.slot(entity) {
+            self.data[index] = component;
+            return;
+        }
+
+        let id = entity.0 as usize;
+        if id >= self.sparse.len() {
+            self.sparse.resize(id + 1, EMPTY);
+        }
+
+        self.sparse[id] = self.data.len() as u32;
+        self.entities.push(entity);
+        self.data.push(component);
     }
 
-    fn get_mut(&mut self, entity: EntityId) -> Option<&mut T> {
-        self.data.get_mut(&entity)
+    fn take(&mut self, entity: EntityId) -> Option<T> {
+        let index = self.slot(entity)?;
+        self.sparse[entity.0 as usize] = EMPTY;
+
+        self.entities.swap_remove(index);
+        let component = self.data.swap_remove(index);
+
+        // Re-point whichever entity was moved into the vacated slot
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[moved.0 as usize] = index as u32;
+        }
+
+        Some(component)
     }
 
-    fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
-        self.data.iter().map(|(&id, comp)| (id, comp))
+    fn get(&self, entity: EntityId) -> Option<&T> {
+        self.slot(entity).map(|index| &self.data[index])
     }
 
-    fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
-        self.data.iter_mut().map(|(id, comp)| (*id, comp))
+    fn get_mut(&mut self, entity: EntityId) -> Option<&mut T> {
+        self.slot(entity).map(move |index| &mut self.data[index])
     }
 }
 
-impl<T: Component> ComponentStorage for ComponentVec<T> {
+impl<T: Component> ComponentStorage for SparseSet<T> {
     fn remove(&mut self, entity: EntityId) {
-        self.data.remove(&entity);
+        self.take(entity);
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -1,9 +1,12 @@
 mod app;
+mod assets;
 mod audio;
 mod combat;
 mod coop;
 mod data;
 mod ecs;
+mod net;
+mod plugins;
 mod progression;
 mod render;
 mod states;
@@ -11,8 +14,75 @@ mod ui;
 mod updater;
 mod util;
 
-#[macroquad::main("Bas Veeg Arc")]
-async fn main() {
-    let mut application = app::Application::new();
-    application.run().await;
+fn main() {
+    // `--relay [address]` runs the online co-op relay instead of the game
+    let mut args = std::env::args().skip(1);
+    let first = args.next();
+    if first.as_deref() == Some("--relay") {
+        let address = args
+            .next()
+            .unwrap_or_else(|| format!("0.0.0.0:{}", net::DEFAULT_RELAY_PORT));
+        if let Err(e) = net::relay::run(&address) {
+            eprintln!("Relay stopped: {}", e);
+        }
+        return;
+    }
+
+    // `--export-hitboxes [directory]` writes every move's frame data and
+    // hitboxes out for documentation, then quits
+    if first.as_deref() == Some("--export-hitboxes") {
+        let directory = std::path::PathBuf::from(args.next().unwrap_or_else(|| "hitboxes".into()));
+        macroquad::Window::new("Bas Veeg Arc - Hitbox Export", async move {
+            match combat::hitbox_export::export(&directory).await {
+                Ok(count) => println!("Wrote {} files to {}", count, directory.display()),
+                Err(e) => eprintln!("Hitbox export failed: {}", e),
+            }
+        });
+        return;
+    }
+
+    // `--verify-replay [path]` plays a saved versus replay back, the last one
+    // if no path is given, and reports where it diverges from the recording
+    if first.as_deref() == Some("--verify-replay") {
+        match states::versus::verify_replay(args.next().map(std::path::PathBuf::from)) {
+            Ok(report) => println!("{}", report),
+            Err(e) => eprintln!("Replay verification failed: {}", e),
+        }
+        return;
+    }
+
+    // `--simulate-balance [runs]` bot-plays that many headless runs per
+    // character and difficulty, 5 if not given, and writes a balance report
+    if first.as_deref() == Some("--simulate-balance") {
+        let runs = args.next().and_then(|runs| runs.parse().ok()).unwrap_or(5);
+        match states::gameplay::simulate_balance(runs) {
+            Ok(summary) => println!("{}", summary),
+            Err(e) => eprintln!("Balance simulation failed: {}", e),
+        }
+        return;
+    }
+
+    // The old build, started to watch a freshly installed one launch and
+    // roll it back if it fails
+    if first.as_deref() == Some(updater::WATCH_ARG) {
+        updater::watch_update_launch();
+        return;
+    }
+
+    // An update that keeps failing before the menu gives way to the old build
+    if updater::recover_failed_update() {
+        return;
+    }
+
+    // `--broadcast` shows the streaming overlay whatever the settings say
+    let broadcast =
+        first.as_deref() == Some("--broadcast") || args.any(|arg| arg == "--broadcast");
+
+    macroquad::Window::from_config(data::GameConfig::load().window_conf(), async move {
+        let mut application = app::Application::new();
+        if broadcast {
+            data::set_broadcast_overlay(true);
+        }
+        application.run().await;
+    });
 }
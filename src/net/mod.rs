@@ -0,0 +1,144 @@
+use crate::coop::input_handler::PlayerInput;
+use crate::data::{CharacterId, PlayerCountScaling};
+use macroquad::prelude::Vec2;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub mod relay;
+mod session;
+
+pub use session::{NetRole, NetSession, HASH_INTERVAL};
+
+/// Port the relay listens on when none is given
+pub const DEFAULT_RELAY_PORT: u16 = 7777;
+
+/// One line of the protocol. Everything is newline-delimited JSON over a
+/// single TCP connection to the relay, which pairs a host with a client and
+/// then forwards their lines to each other untouched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Message {
+    /// To the relay: open a room
+    Host,
+    /// To the relay: join the room with this code
+    Join { code: String },
+    /// From the relay: the room is open under this code
+    Room { code: String },
+    /// From the relay: both players are in the room
+    Joined,
+    /// From the relay: the request couldn't be served
+    Error(String),
+    /// Between players, once paired
+    Hello { version: String, character: CharacterId },
+    /// Host to client: start the run with the host's seed and settings. The
+    /// host's difficulty scaling is sent along so a tuned table on either
+    /// side can't desync the run.
+    Start {
+        seed: u64,
+        tick_rate: u32,
+        lives: u32,
+        scaling: PlayerCountScaling,
+    },
+    /// A player's input for one simulation tick
+    Input { frame: u32, input: NetInput },
+    /// Hash of the game state after a tick, to catch desyncs
+    StateHash { frame: u32, hash: u64 },
+}
+
+const LIGHT_ATTACK: u8 = 1 << 0;
+const HEAVY_ATTACK: u8 = 1 << 1;
+const SPECIAL_ATTACK: u8 = 1 << 2;
+const ABILITY: u8 = 1 << 3;
+const DODGE: u8 = 1 << 4;
+const BLOCK: u8 = 1 << 5;
+const INTERACT: u8 = 1 << 6;
+const PING: u8 = 1 << 7;
+
+/// The parts of a `PlayerInput` that drive a fighter, packed for the wire
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetInput {
+    pub movement: (f32, f32),
+    /// Buttons down this tick
+    pub held: u8,
+    /// Buttons pressed since the last tick
+    pub pressed: u8,
+}
+
+impl NetInput {
+    pub fn from_player_input(input: &PlayerInput) -> Self {
+        let pack = |flags: &[(bool, u8)]| {
+            flags
+                .iter()
+                .filter(|(down, _)| *down)
+                .fold(0, |bits, (_, bit)| bits | bit)
+        };
+
+        Self {
+            movement: (input.movement.x, input.movement.y),
+            held: pack(&[
+                (input.light_attack, LIGHT_ATTACK),
+                (input.heavy_attack, HEAVY_ATTACK),
+                (input.special_attack, SPECIAL_ATTACK),
+                (input.ability, ABILITY),
+                (input.dodge, DODGE),
+                (input.block, BLOCK),
+                (input.interact, INTERACT),
+                (input.ping, PING),
+            ]),
+            pressed: pack(&[
+                (input.light_attack_pressed, LIGHT_ATTACK),
+                (input.heavy_attack_pressed, HEAVY_ATTACK),
+                (input.special_attack_pressed, SPECIAL_ATTACK),
+                (input.ability_pressed, ABILITY),
+                (input.dodge_pressed, DODGE),
+                (input.block_pressed, BLOCK),
+                (input.interact_pressed, INTERACT),
+            ]),
+        }
+    }
+
+    pub fn to_player_input(self) -> PlayerInput {
+        let held = |bit: u8| self.held & bit != 0;
+        let pressed = |bit: u8| self.pressed & bit != 0;
+
+        PlayerInput {
+            movement: Vec2::new(self.movement.0, self.movement.1),
+            light_attack: held(LIGHT_ATTACK),
+            heavy_attack: held(HEAVY_ATTACK),
+            special_attack: held(SPECIAL_ATTACK),
+            ability: held(ABILITY),
+            dodge: held(DODGE),
+            block: held(BLOCK),
+            interact: held(INTERACT),
+            ping: held(PING),
+            light_attack_pressed: pressed(LIGHT_ATTACK),
+            heavy_attack_pressed: pressed(HEAVY_ATTACK),
+            special_attack_pressed: pressed(SPECIAL_ATTACK),
+            ability_pressed: pressed(ABILITY),
+            dodge_pressed: pressed(DODGE),
+            block_pressed: pressed(BLOCK),
+            interact_pressed: pressed(INTERACT),
+            ..PlayerInput::default()
+        }
+    }
+
+    /// Fold in a later frame's sample: held buttons and movement are replaced,
+    /// presses add up so one between two ticks isn't lost
+    pub fn merge(&mut self, later: NetInput) {
+        self.movement = later.movement;
+        self.held = later.held;
+        self.pressed |= later.pressed;
+    }
+}
+
+// Handed from the lobby to the gameplay state it starts
+static SESSION: Mutex<Option<NetSession>> = Mutex::new(None);
+
+pub fn set_session(session: NetSession) {
+    if let Ok(mut current) = SESSION.lock() {
+        *current = Some(session);
+    }
+}
+
+pub fn take_session() -> Option<NetSession> {
+    SESSION.lock().ok()?.take()
+}
@@ -0,0 +1,125 @@
+use crate::data::crash;
+use super::Message;
+use crate::util::rng::SeededRng;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// Letters room codes are made of; no I/O/0/1 lookalikes
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 5;
+
+/// Hosts waiting for a partner, by room code
+type Rooms = Arc<Mutex<HashMap<String, BufReader<TcpStream>>>>;
+
+/// Run the relay on `address` until it fails. Each connection opens or joins
+/// a room with its first line; once a room has both players, their lines are
+/// forwarded to each other untouched until either one leaves.
+pub fn run(address: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(address)
+        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+    println!("Relay listening on {}", address);
+
+    let rooms: Rooms = Arc::new(Mutex::new(HashMap::new()));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let rooms = Arc::clone(&rooms);
+        std::thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &rooms) {
+                crash::log(format!("Relay connection dropped: {}", e));
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, rooms: &Rooms) -> Result<(), String> {
+    let _ = stream.set_nodelay(true);
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+
+    match serde_json::from_str(&line) {
+        Ok(Message::Host) => {
+            let code = {
+                let mut rooms = rooms.lock().map_err(|_| "Room list poisoned".to_string())?;
+                let code = new_code(&rooms);
+                send(reader.get_mut(), &Message::Room { code: code.clone() })?;
+                rooms.insert(code.clone(), reader);
+                code
+            };
+            println!("Opened room {}", code);
+            Ok(())
+        }
+        Ok(Message::Join { code }) => {
+            let host = rooms
+                .lock()
+                .map_err(|_| "Room list poisoned".to_string())?
+                .remove(&code);
+            let Some(host) = host else {
+                return send(reader.get_mut(), &Message::Error(format!("No room {}", code)));
+            };
+            println!("Player joined room {}", code);
+            pair(host, reader)
+        }
+        _ => send(reader.get_mut(), &Message::Error("Expected Host or Join".to_string())),
+    }
+}
+
+/// Tell both players they're in, then pipe each one's lines to the other
+fn pair(mut host: BufReader<TcpStream>, mut client: BufReader<TcpStream>) -> Result<(), String> {
+    if let Err(e) = send(host.get_mut(), &Message::Joined) {
+        let _ = send(client.get_mut(), &Message::Error("Host has left".to_string()));
+        return Err(e);
+    }
+    send(client.get_mut(), &Message::Joined)?;
+
+    let clone = |stream: &TcpStream| {
+        stream
+            .try_clone()
+            .map_err(|e| format!("Failed to clone connection: {}", e))
+    };
+    let host_writer = clone(host.get_ref())?;
+    let client_writer = clone(client.get_ref())?;
+
+    let upstream = std::thread::spawn(move || forward(host, client_writer));
+    forward(client, host_writer);
+    let _ = upstream.join();
+    Ok(())
+}
+
+/// Copy everything from one player to the other; when either end closes,
+/// close both so the other player hears about it
+fn forward(mut from: BufReader<TcpStream>, mut to: TcpStream) {
+    let _ = std::io::copy(&mut from, &mut to);
+    let _ = from.get_ref().shutdown(Shutdown::Both);
+    let _ = to.shutdown(Shutdown::Both);
+}
+
+fn send(stream: &mut TcpStream, message: &Message) -> Result<(), String> {
+    let line = serde_json::to_string(message)
+        .map_err(|e| format!("Failed to serialize message: {}", e))?;
+    writeln!(stream, "{}", line).map_err(|e| format!("Failed to send message: {}", e))
+}
+
+fn new_code(rooms: &HashMap<String, BufReader<TcpStream>>) -> String {
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut rng = SeededRng::new(seed);
+
+    loop {
+        let code: String = (0..CODE_LENGTH)
+            .map(|_| CODE_ALPHABET[rng.range(0, CODE_ALPHABET.len() as i32) as usize] as char)
+            .collect();
+        if !rooms.contains_key(&code) {
+            return code;
+        }
+    }
+}
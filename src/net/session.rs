@@ -0,0 +1,270 @@
+use crate::data::crash;
+use super::{Message, NetInput};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+/// Ticks between a local input and the tick it's applied on. Gives it time
+/// to reach the partner, so neither side has to wait on a normal connection.
+const INPUT_DELAY: u32 = 6;
+
+/// Ticks between state hash exchanges
+pub const HASH_INTERVAL: u32 = 60;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetRole {
+    /// Opened the room; plays P1
+    Host,
+    /// Joined with a code; plays P2
+    Client,
+}
+
+/// A connection to the partner through the relay, and the lockstep state of
+/// the run: both sides simulate every tick from the same pair of inputs
+pub struct NetSession {
+    role: NetRole,
+    stream: TcpStream,
+    /// Lines from the reader thread; `None` once the connection closed
+    incoming: Receiver<Option<Message>>,
+    room_code: Option<String>,
+    closed: Option<String>,
+    seed: u64,
+    /// Next tick to simulate
+    frame: u32,
+    /// Next tick a local input gets sent for
+    next_local_frame: u32,
+    /// Inputs not yet simulated, by seat (host first)
+    inputs: [BTreeMap<u32, NetInput>; 2],
+    local_hashes: BTreeMap<u32, u64>,
+    remote_hashes: BTreeMap<u32, u64>,
+    /// First tick the two sides disagreed on
+    desync: Option<u32>,
+    desync_reported: bool,
+}
+
+impl NetSession {
+    /// Open a room on the relay; the code arrives a moment later
+    pub fn host(relay: &str) -> Result<Self, String> {
+        Self::connect(relay, NetRole::Host, Message::Host)
+    }
+
+    pub fn join(relay: &str, code: &str) -> Result<Self, String> {
+        let code = code.trim().to_uppercase();
+        Self::connect(relay, NetRole::Client, Message::Join { code })
+    }
+
+    fn connect(relay: &str, role: NetRole, first: Message) -> Result<Self, String> {
+        let address = relay
+            .to_socket_addrs()
+            .map_err(|e| format!("Bad relay address {}: {}", relay, e))?
+            .next()
+            .ok_or_else(|| format!("Relay address {} didn't resolve", relay))?;
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Couldn't reach the relay at {}: {}", relay, e))?;
+        stream
+            .set_nodelay(true)
+            .map_err(|e| format!("Failed to configure connection: {}", e))?;
+        let reader = stream
+            .try_clone()
+            .map_err(|e| format!("Failed to configure connection: {}", e))?;
+
+        let (sender, incoming) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str(&line) {
+                    Ok(message) => {
+                        if sender.send(Some(message)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => crash::log(format!("Ignoring bad line from partner: {}", e)),
+                }
+            }
+            let _ = sender.send(None);
+        });
+
+        let mut session = Self {
+            role,
+            stream,
+            incoming,
+            room_code: None,
+            closed: None,
+            seed: 0,
+            frame: 0,
+            next_local_frame: 0,
+            inputs: [BTreeMap::new(), BTreeMap::new()],
+            local_hashes: BTreeMap::new(),
+            remote_hashes: BTreeMap::new(),
+            desync: None,
+            desync_reported: false,
+        };
+        session.send(&first);
+        if let Some(reason) = session.closed.take() {
+            return Err(reason);
+        }
+        Ok(session)
+    }
+
+    pub fn role(&self) -> NetRole {
+        self.role
+    }
+
+    /// Seat this machine plays: 0 for the host, 1 for the client
+    pub fn local_seat(&self) -> usize {
+        match self.role {
+            NetRole::Host => 0,
+            NetRole::Client => 1,
+        }
+    }
+
+    pub fn room_code(&self) -> Option<&str> {
+        self.room_code.as_deref()
+    }
+
+    /// Why the connection ended, once it has
+    pub fn closed(&self) -> Option<&str> {
+        self.closed.as_deref()
+    }
+
+    /// Seed both sides started the run with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Ticks simulated so far
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn send(&mut self, message: &Message) {
+        if self.closed.is_some() {
+            return;
+        }
+        let line = match serde_json::to_string(message) {
+            Ok(line) => line,
+            Err(e) => {
+                crash::log(format!("Failed to serialize message: {}", e));
+                return;
+            }
+        };
+        if let Err(e) = writeln!(self.stream, "{}", line) {
+            self.closed = Some(format!("Connection lost: {}", e));
+        }
+    }
+
+    /// Take in everything that arrived. Inputs and hashes are kept here;
+    /// other messages are handed back for the lobby.
+    pub fn poll(&mut self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        loop {
+            match self.incoming.try_recv() {
+                Ok(Some(Message::Room { code })) => self.room_code = Some(code),
+                Ok(Some(Message::Error(reason))) => self.closed = Some(reason),
+                Ok(Some(Message::Input { frame, input })) => {
+                    self.inputs[1 - self.local_seat()].insert(frame, input);
+                }
+                Ok(Some(Message::StateHash { frame, hash })) => {
+                    self.remote_hashes.insert(frame, hash);
+                    self.compare_hashes();
+                }
+                Ok(Some(message)) => messages.push(message),
+                Ok(None) | Err(TryRecvError::Disconnected) => {
+                    if self.closed.is_none() {
+                        self.closed = Some("Partner disconnected".to_string());
+                    }
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        messages
+    }
+
+    /// Start the lockstep for the run. The first `INPUT_DELAY` ticks have no
+    /// inputs from anyone yet, so they start out empty on both sides. The
+    /// partner may already be sending later ticks; those are kept.
+    pub fn begin(&mut self, seed: u64) {
+        self.seed = seed;
+        self.frame = 0;
+        self.next_local_frame = INPUT_DELAY;
+        for seat in &mut self.inputs {
+            for frame in 0..INPUT_DELAY {
+                seat.insert(frame, NetInput::default());
+            }
+        }
+    }
+
+    /// Send this tick's local input for `INPUT_DELAY` ticks from now. Ignored
+    /// while stalled on the partner, so the buffer doesn't run ahead.
+    pub fn submit_local(&mut self, input: NetInput) {
+        if self.next_local_frame > self.frame + INPUT_DELAY {
+            return;
+        }
+        let frame = self.next_local_frame;
+        self.inputs[self.local_seat()].insert(frame, input);
+        self.send(&Message::Input { frame, input });
+        self.next_local_frame += 1;
+    }
+
+    /// Both seats' inputs for the next tick, if the partner's has arrived.
+    /// `None` means the tick has to wait.
+    pub fn next_inputs(&mut self) -> Option<[NetInput; 2]> {
+        let frame = self.frame;
+        if !self.inputs.iter().all(|seat| seat.contains_key(&frame)) {
+            return None;
+        }
+
+        let [host, client] = &mut self.inputs;
+        let inputs = [host.remove(&frame)?, client.remove(&frame)?];
+        self.frame += 1;
+        Some(inputs)
+    }
+
+    /// Share the hash of the state after `frame` and check it against the
+    /// partner's
+    pub fn report_hash(&mut self, frame: u32, hash: u64) {
+        self.local_hashes.insert(frame, hash);
+        self.send(&Message::StateHash { frame, hash });
+        self.compare_hashes();
+    }
+
+    /// First tick the two sides disagreed on, reported once
+    pub fn take_desync(&mut self) -> Option<u32> {
+        if self.desync_reported {
+            return None;
+        }
+        self.desync_reported = self.desync.is_some();
+        self.desync
+    }
+
+    fn compare_hashes(&mut self) {
+        let both: Vec<u32> = self
+            .local_hashes
+            .keys()
+            .filter(|frame| self.remote_hashes.contains_key(frame))
+            .copied()
+            .collect();
+
+        for frame in both {
+            let local = self.local_hashes.remove(&frame);
+            let remote = self.remote_hashes.remove(&frame);
+            if local != remote && self.desync.is_none() {
+                self.desync = Some(frame);
+            }
+        }
+    }
+}
+
+impl Drop for NetSession {
+    /// The relay closes the partner's side too, which is how they hear we left
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::data::GameConfig;
+use crate::ecs::{EventHandler, Schedule, Stage, System};
+use std::sync::Mutex;
+
+/// A feature that hooks itself into the game at startup: the ECS systems it
+/// runs, the event handlers that react to gameplay and the settings pages it
+/// adds. States build their systems from what's registered instead of
+/// knowing every feature up front.
+pub trait Plugin {
+    fn build(&self, registry: &mut PluginRegistry);
+}
+
+/// Settings screen page a plugin contributes
+#[derive(Clone)]
+pub struct SettingsPage {
+    pub title: &'static str,
+    pub entries: Vec<SettingsEntry>,
+}
+
+/// One line on a settings page; selecting it calls `activate`, which changes
+/// the config (and anything live that mirrors it)
+#[derive(Clone, Copy)]
+pub struct SettingsEntry {
+    pub label: fn(&GameConfig) -> String,
+    pub activate: fn(&mut GameConfig),
+}
+
+type SystemFactory = fn() -> Box<dyn System>;
+type HandlerFactory = fn() -> Box<dyn EventHandler>;
+
+/// What the installed plugins registered. Systems and handlers are kept as
+/// constructors, since every run starts with fresh ones.
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    systems: Vec<(Stage, SystemFactory)>,
+    event_handlers: Vec<HandlerFactory>,
+    settings_pages: Vec<SettingsPage>,
+}
+
+impl PluginRegistry {
+    pub fn add_system(&mut self, stage: Stage, system: SystemFactory) -> &mut Self {
+        self.systems.push((stage, system));
+        self
+    }
+
+    pub fn add_event_handler(&mut self, handler: HandlerFactory) -> &mut Self {
+        self.event_handlers.push(handler);
+        self
+    }
+
+    pub fn add_settings_page(&mut self, page: SettingsPage) -> &mut Self {
+        self.settings_pages.push(page);
+        self
+    }
+
+    /// A new copy of every registered system
+    pub fn schedule(&self) -> Schedule {
+        let mut schedule = Schedule::default();
+        for (stage, system) in &self.systems {
+            schedule.add(*stage, system());
+        }
+        schedule
+    }
+
+    pub fn event_handlers(&self) -> Vec<Box<dyn EventHandler>> {
+        self.event_handlers
+            .iter()
+            .map(|handler| handler())
+            .collect()
+    }
+
+    pub fn settings_pages(&self) -> &[SettingsPage] {
+        &self.settings_pages
+    }
+}
+
+/// Settings label text for a switch
+pub fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+/// Everything the game ships with, in the order their systems run
+pub fn default_plugins() -> Vec<Box<dyn Plugin>> {
+    vec![
+        Box::new(crate::ecs::plugin::CorePlugin),
+        Box::new(crate::combat::plugin::CombatPlugin),
+        Box::new(crate::coop::plugin::CoopPlugin),
+        Box::new(crate::progression::plugin::ProgressionPlugin),
+        Box::new(crate::render::debug::RenderDebugPlugin),
+        Box::new(crate::ui::broadcast::BroadcastPlugin),
+        Box::new(crate::states::auto_pause::AutoPausePlugin),
+        Box::new(crate::data::palette::PalettePlugin),
+        Box::new(crate::data::localization::LocalizationPlugin),
+        Box::new(crate::ui::subtitles::SubtitlesPlugin),
+    ]
+}
+
+static REGISTRY: Mutex<Option<PluginRegistry>> = Mutex::new(None);
+
+/// Build the registry from `plugins`; called once at startup
+pub fn install(plugins: &[Box<dyn Plugin>]) {
+    let mut registry = PluginRegistry::default();
+    for plugin in plugins {
+        plugin.build(&mut registry);
+    }
+    if let Ok(mut current) = REGISTRY.lock() {
+        *current = Some(registry);
+    }
+}
+
+/// The installed registry; the default plugins if nothing was installed
+/// (e.g. in tests)
+pub fn registry() -> PluginRegistry {
+    if let Some(registry) = REGISTRY.lock().ok().and_then(|current| current.clone()) {
+        return registry;
+    }
+
+    let mut registry = PluginRegistry::default();
+    for plugin in default_plugins() {
+        plugin.build(&mut registry);
+    }
+    registry
+}
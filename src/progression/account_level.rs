@@ -1,7 +1,153 @@
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{crash, SaveBundle};
+use macroquad::prelude::Color;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Share of the XP a character earns that also goes to the account
+pub const ACCOUNT_XP_SHARE: f32 = 0.5;
+
+/// Titles the account earns on the way up, the first one from the start
+pub const LEVEL_TITLES: [(u32, &str); 7] = [
+    (1, "Newcomer"),
+    (5, "Hall Monitor"),
+    (15, "Class Clown"),
+    (30, "Detention Regular"),
+    (50, "Teacher's Nightmare"),
+    (75, "Principal's Problem"),
+    (100, "Legend of the Hallways"),
+];
+
+/// Banners for the results screen, by the level that unlocks them
+pub const LEVEL_BANNERS: [(u32, &str, Color); 7] = [
+    (1, "Default", Color::new(0.25, 0.25, 0.3, 1.0)),
+    (10, "Crimson", Color::new(0.6, 0.08, 0.12, 1.0)),
+    (20, "Royal Blue", Color::new(0.12, 0.22, 0.65, 1.0)),
+    (40, "Emerald", Color::new(0.05, 0.5, 0.3, 1.0)),
+    (60, "Violet", Color::new(0.42, 0.15, 0.6, 1.0)),
+    (80, "Gold", Color::new(0.75, 0.58, 0.1, 1.0)),
+    (100, "Master", Color::new(0.08, 0.08, 0.08, 1.0)),
+];
+
+/// The account's level and prestige together, saved per profile
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerAccount {
+    pub progression: AccountProgression,
+    pub prestige: PrestigeSystem,
+    /// Why the account file didn't load, if it didn't; it's kept as it is
+    #[serde(skip)]
+    load_error: Option<String>,
+}
+
+impl Versioned for PlayerAccount {
+    const MIGRATIONS: &'static [Migration] = &[migrate::from_unversioned];
+}
+
+impl PlayerAccount {
+    pub fn load() -> Self {
+        let profile = crate::data::get_active_profile();
+        match migrate::read_if_exists::<Self>(&Self::file_path(profile)) {
+            Ok(account) => account.unwrap_or_default(),
+            Err(e) => {
+                crash::log(e.clone());
+                Self {
+                    load_error: Some(e),
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        migrate::check_writable(self.load_error.as_deref())?;
+        let profile = crate::data::get_active_profile();
+        migrate::write_versioned(&Self::file_path(profile), self)
+            .map_err(|e| format!("Failed to write account data: {}", e))
+    }
+
+    pub fn file_path(profile: usize) -> PathBuf {
+        SaveBundle::data_directory()
+            .join("account")
+            .join(profile_file_name("account", profile))
+    }
+
+    /// Add XP boosted by prestige, returning the levels gained
+    pub fn add_xp(&mut self, xp: f32) -> Vec<u32> {
+        let boosted = xp * self.prestige.permanent_bonuses.xp_multiplier;
+        self.progression.add_xp(boosted)
+    }
+
+    /// Go back to level 1 for the next prestige. Titles and banners stay
+    /// unlocked; the levels' currency and skill points can be earned again.
+    pub fn prestige(&mut self) -> PrestigeResult {
+        self.prestige.prestige(&mut self.progression)
+    }
+
+    pub fn badge(&self) -> Option<PrestigeBadge> {
+        PrestigeBadge::for_prestige(self.prestige.prestige_level)
+    }
+}
+
+/// Worn next to the account level once it has prestiged, climbing a tier
+/// as the prestiges add up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrestigeBadge {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+    Diamond,
+}
+
+/// Prestige count each badge tier starts at
+const BADGE_TIERS: [(u32, PrestigeBadge); 5] = [
+    (1, PrestigeBadge::Bronze),
+    (3, PrestigeBadge::Silver),
+    (5, PrestigeBadge::Gold),
+    (10, PrestigeBadge::Platinum),
+    (25, PrestigeBadge::Diamond),
+];
+
+impl PrestigeBadge {
+    pub fn for_prestige(prestige_level: u32) -> Option<Self> {
+        BADGE_TIERS
+            .iter()
+            .rev()
+            .find(|&&(from, _)| prestige_level >= from)
+            .map(|&(_, badge)| badge)
+    }
+
+    /// The next tier up and the prestige count it takes
+    pub fn next_tier(prestige_level: u32) -> Option<(u32, Self)> {
+        BADGE_TIERS.iter().copied().find(|&(from, _)| from > prestige_level)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PrestigeBadge::Bronze => "Bronze",
+            PrestigeBadge::Silver => "Silver",
+            PrestigeBadge::Gold => "Gold",
+            PrestigeBadge::Platinum => "Platinum",
+            PrestigeBadge::Diamond => "Diamond",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PrestigeBadge::Bronze => Color::new(0.8, 0.5, 0.25, 1.0),
+            PrestigeBadge::Silver => Color::new(0.8, 0.82, 0.88, 1.0),
+            PrestigeBadge::Gold => Color::new(1.0, 0.8, 0.2, 1.0),
+            PrestigeBadge::Platinum => Color::new(0.6, 0.95, 0.95, 1.0),
+            PrestigeBadge::Diamond => Color::new(0.65, 0.75, 1.0, 1.0),
+        }
+    }
+}
 
 /// Account-wide progression system
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AccountProgression {
     pub level: u32,
     pub xp: f32,
@@ -17,10 +163,20 @@ pub struct AccountProgression {
     pub profile_banner: String,
     pub profile_icon: String,
     pub level_rewards_claimed: Vec<u32>,
+    /// Titles and banners earned so far, kept through prestige
+    pub unlocked_titles: Vec<String>,
+    pub unlocked_banners: Vec<String>,
+}
+
+impl Default for AccountProgression {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Prestige system for account resets with permanent bonuses
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PrestigeSystem {
     pub prestige_level: u32,
     pub total_prestiges: u32,
@@ -80,6 +236,8 @@ impl AccountProgression {
             profile_banner: "Default".to_string(),
             profile_icon: "Default".to_string(),
             level_rewards_claimed: vec![],
+            unlocked_titles: vec![LEVEL_TITLES[0].1.to_string()],
+            unlocked_banners: vec![LEVEL_BANNERS[0].1.to_string()],
         }
     }
 
@@ -152,57 +310,76 @@ impl AccountProgression {
         (self.xp / self.xp_for_next_level()) * 100.0
     }
 
-    /// Claim level reward
+    /// Claim level reward. A new title or banner is unlocked and worn
+    /// straight away.
     pub fn claim_level_reward(&mut self, level: u32) -> Option<LevelReward> {
         if self.level_rewards_claimed.contains(&level) || level > self.level {
             return None;
         }
 
         self.level_rewards_claimed.push(level);
-        Some(Self::get_level_reward(level))
+        let reward = Self::get_level_reward(level);
+        if let Some(title) = reward.title {
+            if !self.unlocked_titles.iter().any(|t| t == title) {
+                self.unlocked_titles.push(title.to_string());
+            }
+            self.profile_title = title.to_string();
+        }
+        if let Some(banner) = reward.banner {
+            if !self.unlocked_banners.iter().any(|b| b == banner) {
+                self.unlocked_banners.push(banner.to_string());
+            }
+            self.profile_banner = banner.to_string();
+        }
+        Some(reward)
     }
 
     /// Get reward for a specific level
     fn get_level_reward(level: u32) -> LevelReward {
-        match level {
+        let (currency, skill_points) = match level {
             // Every 5 levels
-            5 | 10 | 15 | 20 | 25 | 30 | 35 | 40 | 45 | 50 => LevelReward {
-                level,
-                currency: 100.0 * (level as f32 / 5.0),
-                skill_points: 1,
-                cosmetic: None,
-            },
+            5 | 10 | 15 | 20 | 25 | 30 | 35 | 40 | 45 | 50 => (100.0 * (level as f32 / 5.0), 1),
             // Every 10 levels - extra rewards
-            60 | 70 | 80 | 90 => LevelReward {
-                level,
-                currency: 500.0,
-                skill_points: 2,
-                cosmetic: Some(format!("Banner Lvl {}", level)),
-            },
+            60 | 70 | 80 | 90 => (500.0, 2),
             // Milestone level 100
-            100 => LevelReward {
-                level,
-                currency: 2000.0,
-                skill_points: 5,
-                cosmetic: Some("Master Banner".to_string()),
-            },
+            100 => (2000.0, 5),
             // Regular milestones every 25 after 100
-            _ if level >= 100 && level % 25 == 0 => LevelReward {
-                level,
-                currency: 1000.0,
-                skill_points: 3,
-                cosmetic: Some(format!("Legendary Banner {}", level)),
-            },
+            _ if level >= 100 && level.is_multiple_of(25) => (1000.0, 3),
             // Default
-            _ => LevelReward {
-                level,
-                currency: 50.0,
-                skill_points: 0,
-                cosmetic: None,
-            },
+            _ => (50.0, 0),
+        };
+        LevelReward {
+            level,
+            currency,
+            skill_points,
+            title: LEVEL_TITLES
+                .iter()
+                .find(|(unlock, _)| *unlock == level && level > 1)
+                .map(|&(_, title)| title),
+            banner: LEVEL_BANNERS
+                .iter()
+                .find(|(unlock, ..)| *unlock == level && level > 1)
+                .map(|&(_, banner, _)| banner),
         }
     }
 
+    /// Wear the next unlocked title
+    pub fn cycle_title(&mut self) {
+        self.profile_title = next_after(&self.unlocked_titles, &self.profile_title);
+    }
+
+    /// Show the next unlocked banner on the results screen
+    pub fn cycle_banner(&mut self) {
+        self.profile_banner = next_after(&self.unlocked_banners, &self.profile_banner);
+    }
+
+    pub fn banner_color(&self) -> Color {
+        LEVEL_BANNERS
+            .iter()
+            .find(|(_, banner, _)| *banner == self.profile_banner)
+            .map_or(LEVEL_BANNERS[0].2, |&(.., color)| color)
+    }
+
     /// Get all pending rewards
     pub fn get_pending_rewards(&self) -> Vec<LevelReward> {
         (1..=self.level)
@@ -217,7 +394,22 @@ pub struct LevelReward {
     pub level: u32,
     pub currency: f32,
     pub skill_points: u32,
-    pub cosmetic: Option<String>,
+    pub title: Option<&'static str>,
+    pub banner: Option<&'static str>,
+}
+
+/// The entry after `current` in `unlocked`, wrapping round
+fn next_after(unlocked: &[String], current: &str) -> String {
+    let Some(index) = unlocked.iter().position(|entry| entry == current) else {
+        return unlocked.first().cloned().unwrap_or_else(|| current.to_string());
+    };
+    unlocked[(index + 1) % unlocked.len()].clone()
+}
+
+impl Default for PrestigeSystem {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PrestigeSystem {
@@ -442,3 +634,44 @@ impl PrestigeUpgrade {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestone_levels_unlock_and_wear_titles_and_banners() {
+        let mut account = AccountProgression::new();
+        account.level = 10;
+        let reward = account.claim_level_reward(5).unwrap();
+        assert_eq!(reward.title, Some("Hall Monitor"));
+        assert_eq!(account.profile_title, "Hall Monitor");
+        assert!(account.claim_level_reward(5).is_none(), "claimed once");
+
+        account.claim_level_reward(10);
+        assert_eq!(account.profile_banner, "Crimson");
+        account.cycle_banner();
+        assert_eq!(account.profile_banner, "Default");
+        assert_eq!(account.banner_color(), LEVEL_BANNERS[0].2);
+    }
+
+    #[test]
+    fn prestiging_resets_the_level_but_keeps_cosmetics_and_climbs_badges() {
+        let mut account = PlayerAccount::default();
+        assert_eq!(account.badge(), None);
+        account.progression.level = 100;
+        account.progression.claim_level_reward(100);
+        assert!(matches!(account.prestige(), PrestigeResult::Success { .. }));
+
+        assert_eq!(account.progression.level, 1);
+        assert!(account.progression.level_rewards_claimed.is_empty());
+        let titles = &account.progression.unlocked_titles;
+        assert!(titles.iter().any(|t| t == "Legend of the Hallways"));
+        assert_eq!(account.badge(), Some(PrestigeBadge::Bronze));
+        assert!(matches!(account.prestige(), PrestigeResult::NotEligible));
+
+        assert_eq!(PrestigeBadge::for_prestige(4), Some(PrestigeBadge::Silver));
+        assert_eq!(PrestigeBadge::next_tier(4), Some((5, PrestigeBadge::Gold)));
+        assert_eq!(PrestigeBadge::for_prestige(30), Some(PrestigeBadge::Diamond));
+    }
+}
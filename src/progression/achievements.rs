@@ -1,11 +1,165 @@
+use crate::combat::combo_system::StyleRank;
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{crash, SaveBundle};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 /// Manages all achievements in the game
 pub struct AchievementManager {
     achievements: HashMap<String, Achievement>,
     unlocked_count: u32,
     total_count: u32,
+    stats: AchievementStats,
+    /// Why the achievements file didn't load, if it didn't; it's kept as it is
+    load_error: Option<String>,
+}
+
+/// Something that happened in play that achievements count towards. The
+/// gameplay states turn their bus events into these.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AchievementEvent {
+    EnemyDefeated,
+    BossDefeated,
+    DamageDealt(f32),
+    ComboReached(u32),
+    StyleRankReached(StyleRank),
+    WaveCleared { wave: u32, flawless: bool },
+    EndlessWaveReached(u32),
+    TeammateRevived,
+    CurrencyEarned(u32),
+    MasteryLevel { character: String, level: u32 },
+    RunFinished(RunSummary),
+}
+
+/// How a run ended, for the achievements that look at whole runs
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunSummary {
+    pub cleared: bool,
+    pub coop: bool,
+    /// No allies and no other players
+    pub solo: bool,
+    /// Any player went down, or the run was lost
+    pub died: bool,
+    pub took_damage: bool,
+}
+
+/// Lifetime totals the requirements are measured against
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AchievementStats {
+    pub enemies_defeated: u32,
+    pub bosses_defeated: u32,
+    pub damage_dealt: f32,
+    pub best_combo: u32,
+    pub best_style_rank: Option<StyleRank>,
+    pub flawless_waves: u32,
+    pub best_wave: u32,
+    pub best_endless_wave: u32,
+    pub revives: u32,
+    pub currency_earned: f32,
+    pub games_played: u32,
+    pub coop_games: u32,
+    pub coop_wins: u32,
+    pub solo_clears: u32,
+    pub deathless_clears: u32,
+    pub no_hit_clears: u32,
+    /// Highest mastery level seen, by character name
+    pub mastery_levels: BTreeMap<String, u32>,
+}
+
+impl AchievementStats {
+    fn record(&mut self, event: &AchievementEvent) {
+        match event {
+            AchievementEvent::EnemyDefeated => self.enemies_defeated += 1,
+            AchievementEvent::BossDefeated => self.bosses_defeated += 1,
+            AchievementEvent::DamageDealt(damage) => self.damage_dealt += damage,
+            AchievementEvent::ComboReached(combo) => self.best_combo = self.best_combo.max(*combo),
+            AchievementEvent::StyleRankReached(rank) => {
+                self.best_style_rank = self.best_style_rank.max(Some(*rank))
+            }
+            AchievementEvent::WaveCleared { wave, flawless } => {
+                self.best_wave = self.best_wave.max(*wave);
+                if *flawless {
+                    self.flawless_waves += 1;
+                }
+            }
+            AchievementEvent::EndlessWaveReached(wave) => {
+                self.best_endless_wave = self.best_endless_wave.max(*wave)
+            }
+            AchievementEvent::TeammateRevived => self.revives += 1,
+            AchievementEvent::CurrencyEarned(amount) => self.currency_earned += *amount as f32,
+            AchievementEvent::MasteryLevel { character, level } => {
+                let best = self.mastery_levels.entry(character.clone()).or_default();
+                *best = (*best).max(*level);
+            }
+            AchievementEvent::RunFinished(run) => {
+                self.games_played += 1;
+                if run.coop {
+                    self.coop_games += 1;
+                }
+                if !run.cleared {
+                    return;
+                }
+                if run.coop {
+                    self.coop_wins += 1;
+                }
+                if run.solo {
+                    self.solo_clears += 1;
+                }
+                if !run.died {
+                    self.deathless_clears += 1;
+                }
+                if !run.took_damage {
+                    self.no_hit_clears += 1;
+                }
+            }
+        }
+    }
+
+    /// Where these totals stand against `requirement`, in the same units as
+    /// its `max_progress`. `None` for requirements nothing measures yet.
+    fn measure(&self, requirement: &AchievementRequirement) -> Option<f32> {
+        let value = match requirement {
+            AchievementRequirement::DealDamage(_) => self.damage_dealt,
+            AchievementRequirement::DefeatEnemies(_) => self.enemies_defeated as f32,
+            AchievementRequirement::DefeatBosses(_) => self.bosses_defeated as f32,
+            AchievementRequirement::ReachCombo(_) => self.best_combo as f32,
+            AchievementRequirement::ReachStyleRank(rank) => {
+                (self.best_style_rank >= Some(*rank)) as u32 as f32
+            }
+            AchievementRequirement::FlawlessWaves(_) => self.flawless_waves as f32,
+            AchievementRequirement::WinWithoutDying => self.deathless_clears as f32,
+            AchievementRequirement::CompleteWave(_) => self.best_wave as f32,
+            AchievementRequirement::MasteryLevel(character, _) => {
+                self.mastery_levels.get(character).copied().unwrap_or(0) as f32
+            }
+            AchievementRequirement::CompleteNoHit => self.no_hit_clears as f32,
+            AchievementRequirement::CompleteSoloRun => self.solo_clears as f32,
+            AchievementRequirement::CompleteEndlessWave(_) => self.best_endless_wave as f32,
+            AchievementRequirement::CollectCurrency(_) => self.currency_earned,
+            AchievementRequirement::PlayCoopGames(_) => self.coop_games as f32,
+            AchievementRequirement::RevivePlayers(_) => self.revives as f32,
+            AchievementRequirement::WinCoopGame => self.coop_wins as f32,
+            AchievementRequirement::PlayGames(_) => self.games_played as f32,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// What's kept of a profile's achievements: the totals and when each was
+/// unlocked. The list itself comes from the code.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct AchievementSave {
+    stats: AchievementStats,
+    unlocked: BTreeMap<String, u64>,
+}
+
+impl Versioned for AchievementSave {
+    const MIGRATIONS: &'static [Migration] = &[migrate::from_unversioned];
 }
 
 /// Individual achievement
@@ -53,6 +207,9 @@ pub enum AchievementRequirement {
     DefeatEnemies(u32),
     DefeatBosses(u32),
     ReachCombo(u32),
+    ReachStyleRank(StyleRank),
+    /// Waves cleared without any player taking damage
+    FlawlessWaves(u32),
     WinWithoutDying,
     CompleteWave(u32),
 
@@ -104,6 +261,8 @@ impl AchievementManager {
             achievements: HashMap::new(),
             unlocked_count: 0,
             total_count: 0,
+            stats: AchievementStats::default(),
+            load_error: None,
         };
 
         manager.init_all_achievements();
@@ -269,6 +428,62 @@ impl AchievementManager {
                 unlock_timestamp: None,
                 hidden: false,
             },
+            Achievement {
+                id: "stylish".to_string(),
+                name: "Stylish".to_string(),
+                description: "Reach S style rank".to_string(),
+                category: AchievementCategory::Combat,
+                difficulty: AchievementDifficulty::Medium,
+                requirement: AchievementRequirement::ReachStyleRank(StyleRank::S),
+                reward: AchievementReward::Currency(100.0),
+                progress: 0.0,
+                max_progress: 1.0,
+                unlocked: false,
+                unlock_timestamp: None,
+                hidden: false,
+            },
+            Achievement {
+                id: "smokin_sexy_style".to_string(),
+                name: "Smokin' Sexy Style".to_string(),
+                description: "Reach SSS style rank".to_string(),
+                category: AchievementCategory::Combat,
+                difficulty: AchievementDifficulty::Expert,
+                requirement: AchievementRequirement::ReachStyleRank(StyleRank::SSS),
+                reward: AchievementReward::Title("Stylish".to_string()),
+                progress: 0.0,
+                max_progress: 1.0,
+                unlocked: false,
+                unlock_timestamp: None,
+                hidden: false,
+            },
+            Achievement {
+                id: "flawless_wave".to_string(),
+                name: "Flawless".to_string(),
+                description: "Clear a wave without taking damage".to_string(),
+                category: AchievementCategory::Challenge,
+                difficulty: AchievementDifficulty::Easy,
+                requirement: AchievementRequirement::FlawlessWaves(1),
+                reward: AchievementReward::Currency(75.0),
+                progress: 0.0,
+                max_progress: 1.0,
+                unlocked: false,
+                unlock_timestamp: None,
+                hidden: false,
+            },
+            Achievement {
+                id: "flawless_streak".to_string(),
+                name: "Not A Scratch".to_string(),
+                description: "Clear 25 waves without taking damage".to_string(),
+                category: AchievementCategory::Challenge,
+                difficulty: AchievementDifficulty::Hard,
+                requirement: AchievementRequirement::FlawlessWaves(25),
+                reward: AchievementReward::SkillPoints(2),
+                progress: 0.0,
+                max_progress: 25.0,
+                unlocked: false,
+                unlock_timestamp: None,
+                hidden: false,
+            },
 
             // === PROGRESSION ACHIEVEMENTS ===
             Achievement {
@@ -640,6 +855,79 @@ impl AchievementManager {
         }
     }
 
+    /// The active profile's achievements, with progress from its totals
+    pub fn load() -> Self {
+        let mut manager = Self::new();
+        let profile = crate::data::get_active_profile();
+        let save: AchievementSave = match migrate::read_if_exists(&Self::file_path(profile)) {
+            Ok(Some(save)) => save,
+            Ok(None) => return manager,
+            Err(e) => {
+                crash::log(e.clone());
+                manager.load_error = Some(e);
+                return manager;
+            }
+        };
+
+        for (id, timestamp) in &save.unlocked {
+            if let Some(achievement) = manager.achievements.get_mut(id) {
+                achievement.unlocked = true;
+                achievement.unlock_timestamp = Some(*timestamp);
+                manager.unlocked_count += 1;
+            }
+        }
+        // Anything newly measurable unlocks on the next event, reward and all
+        for achievement in manager.achievements.values_mut() {
+            if let Some(value) = save.stats.measure(&achievement.requirement) {
+                achievement.progress = value.min(achievement.max_progress);
+            }
+        }
+        manager.stats = save.stats;
+        manager
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        migrate::check_writable(self.load_error.as_deref())?;
+        let save = AchievementSave {
+            stats: self.stats.clone(),
+            unlocked: self
+                .achievements
+                .values()
+                .filter_map(|a| Some((a.id.clone(), a.unlock_timestamp?)))
+                .collect(),
+        };
+        let profile = crate::data::get_active_profile();
+        migrate::write_versioned(&Self::file_path(profile), &save)
+            .map_err(|e| format!("Failed to write achievements: {}", e))
+    }
+
+    pub fn file_path(profile: usize) -> PathBuf {
+        SaveBundle::data_directory()
+            .join("achievements")
+            .join(profile_file_name("achievements", profile))
+    }
+
+    /// Count `event` and return whatever it unlocked, by name
+    pub fn record(&mut self, event: AchievementEvent) -> Vec<Achievement> {
+        self.stats.record(&event);
+
+        let measured: Vec<(String, f32)> = self
+            .achievements
+            .values()
+            .filter(|a| !a.unlocked)
+            .filter_map(|a| Some((a.id.clone(), self.stats.measure(&a.requirement)?)))
+            .collect();
+        let mut unlocked: Vec<Achievement> = measured
+            .into_iter()
+            .filter_map(|(id, value)| {
+                self.update_progress(&id, value)?;
+                self.achievements.get(&id).cloned()
+            })
+            .collect();
+        unlocked.sort_by(|a, b| a.name.cmp(&b.name));
+        unlocked
+    }
+
     /// Update achievement progress
     pub fn update_progress(&mut self, id: &str, progress: f32) -> Option<AchievementReward> {
         if let Some(achievement) = self.achievements.get_mut(id) {
@@ -726,3 +1014,25 @@ impl AchievementDifficulty {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_unlock_what_they_measure_once() {
+        let mut manager = AchievementManager::new();
+        let unlocked = manager.record(AchievementEvent::EnemyDefeated);
+        assert_eq!(unlocked.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["first_blood"]);
+        assert!(manager.record(AchievementEvent::EnemyDefeated).is_empty());
+        assert_eq!(manager.get("killing_spree").unwrap().progress, 2.0);
+
+        let flawless = manager.record(AchievementEvent::WaveCleared { wave: 1, flawless: true });
+        let ids: Vec<&str> = flawless.iter().map(|a| a.id.as_str()).collect();
+        assert!(ids.contains(&"flawless_wave") && ids.contains(&"getting_started"));
+
+        assert!(manager.record(AchievementEvent::StyleRankReached(StyleRank::A)).is_empty());
+        let stylish = manager.record(AchievementEvent::StyleRankReached(StyleRank::S));
+        assert_eq!(stylish[0].id, "stylish");
+    }
+}
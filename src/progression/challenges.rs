@@ -1,8 +1,20 @@
 use crate::data::characters::CharacterId;
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{crash, SaveBundle};
+use crate::util::rng::SeededRng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-/// Daily and weekly challenge system
+const DAY: u64 = 24 * 60 * 60;
+const WEEK: u64 = 7 * DAY;
+/// 1 January 1970 was a Thursday; weeks run Monday to Sunday
+const WEEK_START_OFFSET: u64 = 3;
+
+/// Daily and weekly challenge system. Everything on the board comes from the
+/// day and week numbers, so every player gets the same challenges, modifiers
+/// and seeded run on the same day.
 pub struct ChallengeManager {
     pub daily_challenges: Vec<Challenge>,
     pub weekly_challenges: Vec<Challenge>,
@@ -12,6 +24,169 @@ pub struct ChallengeManager {
     pub last_weekly_reset: u64,
     pub streak_days: u32,
     pub total_challenges_completed: u32,
+    /// Days and weeks since the epoch the board was rolled for
+    day: u64,
+    week: u64,
+    pub daily_modifiers: Vec<ChallengeModifier>,
+    pub weekly_modifiers: Vec<ChallengeModifier>,
+    pub daily_run_completed: bool,
+    pub weekly_run_completed: bool,
+    /// Why the challenges file didn't load, if it didn't; it's kept as it is
+    load_error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChallengeKind {
+    Daily,
+    Weekly,
+}
+
+impl ChallengeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChallengeKind::Daily => "Daily",
+            ChallengeKind::Weekly => "Weekly",
+        }
+    }
+
+    /// Seed for the day or week `period`, different for each kind
+    fn seed(self, period: u64) -> u64 {
+        let salt = match self {
+            ChallengeKind::Daily => 0xDA11,
+            ChallengeKind::Weekly => 0x3EE7,
+        };
+        period.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ salt
+    }
+
+    fn modifier_count(self) -> usize {
+        match self {
+            ChallengeKind::Daily => 2,
+            ChallengeKind::Weekly => 3,
+        }
+    }
+
+    /// Waves the seeded run has to clear
+    pub fn run_waves(self) -> usize {
+        match self {
+            ChallengeKind::Daily => 5,
+            ChallengeKind::Weekly => 10,
+        }
+    }
+
+    /// Arc Tokens for clearing the seeded run, before the streak bonus
+    pub fn run_reward(self) -> u32 {
+        match self {
+            ChallengeKind::Daily => 300,
+            ChallengeKind::Weekly => 1000,
+        }
+    }
+}
+
+/// A twist a seeded challenge run is played under
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengeModifier {
+    ExplodingEnemies,
+    NoShop,
+    GlassCannon,
+    Swarm,
+    Juggernauts,
+}
+
+impl ChallengeModifier {
+    pub const ALL: [ChallengeModifier; 5] = [
+        ChallengeModifier::ExplodingEnemies,
+        ChallengeModifier::NoShop,
+        ChallengeModifier::GlassCannon,
+        ChallengeModifier::Swarm,
+        ChallengeModifier::Juggernauts,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChallengeModifier::ExplodingEnemies => "Volatile",
+            ChallengeModifier::NoShop => "Closed Shop",
+            ChallengeModifier::GlassCannon => "Glass Cannon",
+            ChallengeModifier::Swarm => "Swarm",
+            ChallengeModifier::Juggernauts => "Juggernauts",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ChallengeModifier::ExplodingEnemies => "Enemies explode on death",
+            ChallengeModifier::NoShop => "No shop",
+            ChallengeModifier::GlassCannon => "Deal and take double damage",
+            ChallengeModifier::Swarm => "+50% enemies per wave",
+            ChallengeModifier::Juggernauts => "Enemies have 50% more health",
+        }
+    }
+
+    pub fn damage_scale(self) -> f32 {
+        match self {
+            ChallengeModifier::GlassCannon => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn enemy_damage_scale(self) -> f32 {
+        match self {
+            ChallengeModifier::GlassCannon => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn enemy_count_scale(self) -> f32 {
+        match self {
+            ChallengeModifier::Swarm => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    pub fn enemy_health_scale(self) -> f32 {
+        match self {
+            ChallengeModifier::Juggernauts => 1.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// The seeded run on today's or this week's board
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChallengeRun {
+    pub kind: ChallengeKind,
+    pub seed: u64,
+    pub modifiers: Vec<ChallengeModifier>,
+}
+
+impl ChallengeRun {
+    pub fn has(&self, modifier: ChallengeModifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    /// Product of `scale` over every modifier
+    pub fn scale(&self, scale: fn(ChallengeModifier) -> f32) -> f32 {
+        self.modifiers.iter().map(|modifier| scale(*modifier)).product()
+    }
+}
+
+/// What's kept of a profile's board: progress and completions for the day
+/// and week it was last played. The challenges themselves come from the seed.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ChallengeSave {
+    day: u64,
+    week: u64,
+    progress: HashMap<String, f32>,
+    completed_daily: Vec<String>,
+    completed_weekly: Vec<String>,
+    daily_run_completed: bool,
+    weekly_run_completed: bool,
+    streak_days: u32,
+    total_challenges_completed: u32,
+}
+
+impl Versioned for ChallengeSave {
+    const MIGRATIONS: &'static [Migration] = &[migrate::from_unversioned];
 }
 
 /// Individual challenge
@@ -113,15 +288,28 @@ pub enum ChallengeReward {
 
 impl ChallengeManager {
     pub fn new() -> Self {
+        let now = Self::get_current_timestamp();
+        Self::for_periods(day_index(now), week_index(now))
+    }
+
+    /// The board for day `day` and week `week`, with nothing done yet
+    fn for_periods(day: u64, week: u64) -> Self {
         let mut manager = Self {
             daily_challenges: Vec::new(),
             weekly_challenges: Vec::new(),
             completed_daily: Vec::new(),
             completed_weekly: Vec::new(),
-            last_daily_reset: Self::get_current_timestamp(),
-            last_weekly_reset: Self::get_current_timestamp(),
+            last_daily_reset: day * DAY,
+            last_weekly_reset: week_start(week),
             streak_days: 0,
             total_challenges_completed: 0,
+            day,
+            week,
+            daily_modifiers: Vec::new(),
+            weekly_modifiers: Vec::new(),
+            daily_run_completed: false,
+            weekly_run_completed: false,
+            load_error: None,
         };
 
         manager.refresh_daily_challenges();
@@ -130,72 +318,176 @@ impl ChallengeManager {
         manager
     }
 
+    /// The active profile's board, rolled over to today
+    pub fn load() -> Self {
+        let profile = crate::data::get_active_profile();
+        let save: ChallengeSave = match migrate::read_if_exists(&Self::file_path(profile)) {
+            Ok(Some(save)) => save,
+            Ok(None) => return Self::new(),
+            Err(e) => {
+                crash::log(e.clone());
+                return Self {
+                    load_error: Some(e),
+                    ..Self::new()
+                };
+            }
+        };
+
+        let mut manager = Self::for_periods(save.day, save.week);
+        for challenge in manager.daily_challenges.iter_mut().chain(&mut manager.weekly_challenges) {
+            if let Some(&progress) = save.progress.get(&challenge.id) {
+                challenge.progress = progress.min(challenge.max_progress);
+            }
+        }
+        manager.completed_daily = save.completed_daily;
+        manager.completed_weekly = save.completed_weekly;
+        manager.daily_run_completed = save.daily_run_completed;
+        manager.weekly_run_completed = save.weekly_run_completed;
+        manager.streak_days = save.streak_days;
+        manager.total_challenges_completed = save.total_challenges_completed;
+        manager.update();
+        manager
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        migrate::check_writable(self.load_error.as_deref())?;
+        let save = ChallengeSave {
+            day: self.day,
+            week: self.week,
+            progress: self
+                .daily_challenges
+                .iter()
+                .chain(&self.weekly_challenges)
+                .filter(|c| c.progress > 0.0)
+                .map(|c| (c.id.clone(), c.progress))
+                .collect(),
+            completed_daily: self.completed_daily.clone(),
+            completed_weekly: self.completed_weekly.clone(),
+            daily_run_completed: self.daily_run_completed,
+            weekly_run_completed: self.weekly_run_completed,
+            streak_days: self.streak_days,
+            total_challenges_completed: self.total_challenges_completed,
+        };
+        let profile = crate::data::get_active_profile();
+        migrate::write_versioned(&Self::file_path(profile), &save)
+            .map_err(|e| format!("Failed to write challenges: {}", e))
+    }
+
+    pub fn file_path(profile: usize) -> PathBuf {
+        SaveBundle::data_directory()
+            .join("challenges")
+            .join(profile_file_name("challenges", profile))
+    }
+
     /// Update challenges and check for resets
     pub fn update(&mut self) {
-        let current_time = Self::get_current_timestamp();
+        self.roll_over(Self::get_current_timestamp());
+    }
 
-        // Check for daily reset (24 hours)
-        if current_time >= self.last_daily_reset + 86400 {
-            self.reset_daily_challenges();
+    /// Start a fresh board for whichever of the day and week has changed
+    /// by `now`
+    fn roll_over(&mut self, now: u64) {
+        let day = day_index(now);
+        if day != self.day {
+            self.reset_daily_challenges(day);
         }
 
-        // Check for weekly reset (7 days)
-        if current_time >= self.last_weekly_reset + 604800 {
-            self.reset_weekly_challenges();
+        let week = week_index(now);
+        if week != self.week {
+            self.reset_weekly_challenges(week);
         }
     }
 
     /// Reset daily challenges
-    fn reset_daily_challenges(&mut self) {
-        // Check if any daily challenges were completed
+    fn reset_daily_challenges(&mut self, day: u64) {
+        // The streak carries on into the next day if anything was completed
         let completed_any = !self.completed_daily.is_empty();
 
-        if completed_any {
+        if completed_any && day == self.day + 1 {
             self.streak_days += 1;
         } else {
             self.streak_days = 0;
         }
 
+        self.day = day;
         self.completed_daily.clear();
+        self.daily_run_completed = false;
         self.refresh_daily_challenges();
-        self.last_daily_reset = Self::get_current_timestamp();
+        self.last_daily_reset = day * DAY;
     }
 
     /// Reset weekly challenges
-    fn reset_weekly_challenges(&mut self) {
+    fn reset_weekly_challenges(&mut self, week: u64) {
+        self.week = week;
         self.completed_weekly.clear();
+        self.weekly_run_completed = false;
         self.refresh_weekly_challenges();
-        self.last_weekly_reset = Self::get_current_timestamp();
+        self.last_weekly_reset = week_start(week);
     }
 
     /// Generate new daily challenges
     fn refresh_daily_challenges(&mut self) {
-        self.daily_challenges.clear();
-
-        let templates = Self::get_daily_challenge_templates();
-        let selected = Self::select_random_challenges(&templates, 3);
-
-        for template in selected {
-            self.daily_challenges.push(template.clone());
-        }
+        let mut rng = SeededRng::new(ChallengeKind::Daily.seed(self.day));
+        let templates = Self::get_daily_challenge_templates(self.last_daily_reset + DAY);
+        self.daily_challenges = Self::select_random_challenges(&mut rng, &templates, 3);
+        self.daily_modifiers = Self::select_modifiers(&mut rng, ChallengeKind::Daily);
     }
 
     /// Generate new weekly challenges
     fn refresh_weekly_challenges(&mut self) {
-        self.weekly_challenges.clear();
+        let mut rng = SeededRng::new(ChallengeKind::Weekly.seed(self.week));
+        let templates = Self::get_weekly_challenge_templates(self.last_weekly_reset + WEEK);
+        self.weekly_challenges = Self::select_random_challenges(&mut rng, &templates, 5);
+        self.weekly_modifiers = Self::select_modifiers(&mut rng, ChallengeKind::Weekly);
+    }
 
-        let templates = Self::get_weekly_challenge_templates();
-        let selected = Self::select_random_challenges(&templates, 5);
+    fn select_modifiers(rng: &mut SeededRng, kind: ChallengeKind) -> Vec<ChallengeModifier> {
+        let mut modifiers = ChallengeModifier::ALL.to_vec();
+        rng.shuffle(&mut modifiers);
+        modifiers.truncate(kind.modifier_count());
+        modifiers
+    }
 
-        for template in selected {
-            self.weekly_challenges.push(template.clone());
+    /// The seeded run on the board: the same seed and modifiers for everyone
+    pub fn run(&self, kind: ChallengeKind) -> ChallengeRun {
+        let (period, modifiers) = match kind {
+            ChallengeKind::Daily => (self.day, &self.daily_modifiers),
+            ChallengeKind::Weekly => (self.week, &self.weekly_modifiers),
+        };
+        ChallengeRun {
+            kind,
+            seed: kind.seed(period),
+            modifiers: modifiers.clone(),
         }
     }
 
+    pub fn run_completed(&self, kind: ChallengeKind) -> bool {
+        match kind {
+            ChallengeKind::Daily => self.daily_run_completed,
+            ChallengeKind::Weekly => self.weekly_run_completed,
+        }
+    }
+
+    /// Mark the seeded run cleared and return its bonus Arc Tokens, streak
+    /// included; nothing if it was already cleared this period
+    pub fn complete_run(&mut self, kind: ChallengeKind) -> Option<u32> {
+        if self.run_completed(kind) {
+            return None;
+        }
+        match kind {
+            ChallengeKind::Daily => {
+                self.daily_run_completed = true;
+                // Counts towards the streak like any other daily
+                self.completed_daily.push("daily_run".to_string());
+            }
+            ChallengeKind::Weekly => self.weekly_run_completed = true,
+        }
+        self.total_challenges_completed += 1;
+        Some((kind.run_reward() as f32 * self.get_streak_bonus()).round() as u32)
+    }
+
     /// Get daily challenge templates
-    fn get_daily_challenge_templates() -> Vec<Challenge> {
-        let current_time = Self::get_current_timestamp();
-        let expires_at = current_time + 86400; // 24 hours
+    fn get_daily_challenge_templates(expires_at: u64) -> Vec<Challenge> {
 
         vec![
             Challenge {
@@ -298,10 +590,7 @@ impl ChallengeManager {
     }
 
     /// Get weekly challenge templates
-    fn get_weekly_challenge_templates() -> Vec<Challenge> {
-        let current_time = Self::get_current_timestamp();
-        let expires_at = current_time + 604800; // 7 days
-
+    fn get_weekly_challenge_templates(expires_at: u64) -> Vec<Challenge> {
         vec![
             Challenge {
                 id: "weekly_defeat_500".to_string(),
@@ -440,37 +729,40 @@ impl ChallengeManager {
     }
 
     /// Select random challenges from templates
-    fn select_random_challenges(templates: &[Challenge], count: usize) -> Vec<Challenge> {
-        let mut selected = Vec::new();
+    fn select_random_challenges(
+        rng: &mut SeededRng,
+        templates: &[Challenge],
+        count: usize,
+    ) -> Vec<Challenge> {
         let mut indices: Vec<usize> = (0..templates.len()).collect();
+        rng.shuffle(&mut indices);
 
-        // Simple shuffle
-        for i in 0..indices.len() {
-            let j = (macroquad::rand::gen_range(0.0, 1.0) * (indices.len() - i) as f32) as usize + i;
-            indices.swap(i, j);
-        }
-
-        for i in 0..count.min(templates.len()) {
-            selected.push(templates[indices[i]].clone());
-        }
-
-        selected
+        indices
+            .into_iter()
+            .take(count)
+            .map(|i| templates[i].clone())
+            .collect()
     }
 
-    /// Update challenge progress
-    pub fn update_progress(&mut self, challenge_type: &ChallengeType, progress: f32) -> Vec<ChallengeReward> {
-        let mut rewards = Vec::new();
+    /// Update challenge progress, returning the challenges it completed.
+    /// Combos and waves count the best reached; everything else adds up.
+    pub fn update_progress(
+        &mut self,
+        challenge_type: &ChallengeType,
+        progress: f32,
+    ) -> Vec<Challenge> {
+        let mut completed = Vec::new();
 
         // Update daily challenges
         for challenge in &mut self.daily_challenges {
             if Self::challenge_types_match(&challenge.challenge_type, challenge_type) {
                 if !self.completed_daily.contains(&challenge.id) {
-                    challenge.progress = (challenge.progress + progress).min(challenge.max_progress);
+                    Self::advance(challenge, progress);
 
                     if challenge.progress >= challenge.max_progress {
                         self.completed_daily.push(challenge.id.clone());
                         self.total_challenges_completed += 1;
-                        rewards.push(challenge.reward.clone());
+                        completed.push(challenge.clone());
                     }
                 }
             }
@@ -480,18 +772,28 @@ impl ChallengeManager {
         for challenge in &mut self.weekly_challenges {
             if Self::challenge_types_match(&challenge.challenge_type, challenge_type) {
                 if !self.completed_weekly.contains(&challenge.id) {
-                    challenge.progress = (challenge.progress + progress).min(challenge.max_progress);
+                    Self::advance(challenge, progress);
 
                     if challenge.progress >= challenge.max_progress {
                         self.completed_weekly.push(challenge.id.clone());
                         self.total_challenges_completed += 1;
-                        rewards.push(challenge.reward.clone());
+                        completed.push(challenge.clone());
                     }
                 }
             }
         }
 
-        rewards
+        completed
+    }
+
+    fn advance(challenge: &mut Challenge, progress: f32) {
+        let reached = match challenge.challenge_type {
+            ChallengeType::ReachCombo { .. } | ChallengeType::CompleteWave { .. } => {
+                challenge.progress.max(progress)
+            }
+            _ => challenge.progress + progress,
+        };
+        challenge.progress = reached.min(challenge.max_progress);
     }
 
     /// Check if challenge types match
@@ -507,6 +809,7 @@ impl ChallengeManager {
             (ChallengeType::WinCoopGame, ChallengeType::WinCoopGame) => true,
             (ChallengeType::RevivePlayers { .. }, ChallengeType::RevivePlayers { .. }) => true,
             (ChallengeType::FlawlessVictory, ChallengeType::FlawlessVictory) => true,
+            (ChallengeType::CompleteWave { .. }, ChallengeType::CompleteWave { .. }) => true,
             _ => false,
         }
     }
@@ -558,18 +861,31 @@ impl ChallengeManager {
     /// Get time until daily reset
     pub fn time_until_daily_reset(&self) -> u64 {
         let current = Self::get_current_timestamp();
-        let next_reset = self.last_daily_reset + 86400;
+        let next_reset = self.last_daily_reset + DAY;
         next_reset.saturating_sub(current)
     }
 
     /// Get time until weekly reset
     pub fn time_until_weekly_reset(&self) -> u64 {
         let current = Self::get_current_timestamp();
-        let next_reset = self.last_weekly_reset + 604800;
+        let next_reset = self.last_weekly_reset + WEEK;
         next_reset.saturating_sub(current)
     }
 }
 
+fn day_index(timestamp: u64) -> u64 {
+    timestamp / DAY
+}
+
+fn week_index(timestamp: u64) -> u64 {
+    (day_index(timestamp) + WEEK_START_OFFSET) / 7
+}
+
+/// Midnight on the Monday week `week` starts
+fn week_start(week: u64) -> u64 {
+    (week * 7).saturating_sub(WEEK_START_OFFSET) * DAY
+}
+
 impl ChallengeDifficulty {
     pub fn to_string(&self) -> &str {
         match self {
@@ -590,3 +906,50 @@ impl ChallengeDifficulty {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(challenges: &[Challenge]) -> Vec<String> {
+        challenges.iter().map(|c| c.id.clone()).collect()
+    }
+
+    #[test]
+    fn the_same_day_always_rolls_the_same_board() {
+        let a = ChallengeManager::for_periods(20_000, 2_857);
+        let b = ChallengeManager::for_periods(20_000, 2_857);
+        assert_eq!(ids(&a.daily_challenges), ids(&b.daily_challenges));
+        assert_eq!(ids(&a.weekly_challenges), ids(&b.weekly_challenges));
+        assert_eq!(a.run(ChallengeKind::Daily), b.run(ChallengeKind::Daily));
+        assert_eq!(a.daily_modifiers.len(), 2);
+        assert_eq!(a.weekly_modifiers.len(), 3);
+        assert_ne!(a.run(ChallengeKind::Daily).seed, a.run(ChallengeKind::Weekly).seed);
+    }
+
+    #[test]
+    fn weeks_turn_over_on_monday() {
+        // 2024-01-07 was a Sunday, 2024-01-08 a Monday
+        let sunday = 19_729 * DAY;
+        assert_eq!(week_index(sunday) + 1, week_index(sunday + DAY));
+        assert_eq!(week_start(week_index(sunday + DAY)), sunday + DAY);
+    }
+
+    #[test]
+    fn a_new_day_clears_the_dailies_and_keeps_the_streak_going() {
+        let day = 20_000;
+        let mut manager = ChallengeManager::for_periods(day, week_index(day * DAY));
+        assert_eq!(manager.complete_run(ChallengeKind::Daily), Some(300));
+        assert_eq!(manager.complete_run(ChallengeKind::Daily), None);
+
+        manager.roll_over((day + 1) * DAY);
+        assert_eq!(manager.streak_days, 1);
+        assert!(!manager.run_completed(ChallengeKind::Daily));
+        assert!(manager.completed_daily.is_empty());
+
+        // Missing a day ends it
+        manager.complete_run(ChallengeKind::Daily);
+        manager.roll_over((day + 3) * DAY);
+        assert_eq!(manager.streak_days, 0);
+    }
+}
@@ -1,6 +1,23 @@
-use crate::data::characters::CharacterId;
+use crate::data::characters::{Character, CharacterId};
+use crate::data::ledger::{self, Account};
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{crash, SaveBundle, CHARACTERS};
+use macroquad::prelude::Color;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Rank skins are palette swaps: the sprite is drawn multiplied by the tint.
+/// Skins not listed here, like achievement ones, draw untinted.
+pub const PALETTE_SWAPS: [(&str, [f32; 3]); 6] = [
+    ("Bronze Variant", [1.0, 0.78, 0.55]),
+    ("Silver Variant", [0.82, 0.86, 0.95]),
+    ("Gold Variant", [1.0, 0.88, 0.4]),
+    ("Platinum Variant", [0.85, 0.95, 1.0]),
+    ("Diamond Variant", [0.55, 0.88, 1.0]),
+    ("Master Skin", [1.0, 0.55, 1.0]),
+];
 
 /// Manages character mastery levels for all characters
 pub struct MasteryManager {
@@ -23,6 +40,19 @@ pub struct CharacterMastery {
     pub unlocked_skins: Vec<String>,
     pub unlocked_titles: Vec<String>,
     pub milestones: Vec<MasteryMilestone>,
+    /// Skin drawn in game; empty for the default look
+    #[serde(default)]
+    pub equipped_skin: String,
+    /// Lines the ability can call out besides the character's own
+    #[serde(default)]
+    pub unlocked_voice_lines: Vec<String>,
+    /// Why the mastery file didn't load, if it didn't; it's kept as it is
+    #[serde(skip)]
+    load_error: Option<String>,
+}
+
+impl Versioned for CharacterMastery {
+    const MIGRATIONS: &'static [Migration] = &[migrate::from_unversioned];
 }
 
 /// Mastery rank tiers
@@ -54,30 +84,28 @@ pub enum MasteryReward {
     SkillPoints(u32),
     Currency(f32),
     Cosmetic(String),
+    VoiceLine(String),
+    /// Named for the character's signature move
+    SignatureMove(String),
 }
 
 impl MasteryManager {
     pub fn new() -> Self {
-        let mut manager = Self {
-            masteries: HashMap::new(),
-        };
-
-        // Initialize mastery for all characters
-        manager.init_all_masteries();
-
-        manager
+        Self {
+            masteries: CHARACTERS
+                .iter()
+                .map(|c| (c.id, CharacterMastery::new(c.id)))
+                .collect(),
+        }
     }
 
-    fn init_all_masteries(&mut self) {
-        let characters = vec![
-            CharacterId::Bas,
-            CharacterId::Berkay,
-            CharacterId::Luca,
-            // Add more characters
-        ];
-
-        for character in characters {
-            self.masteries.insert(character, CharacterMastery::new(character));
+    /// Every character's saved mastery for the active profile
+    pub fn load() -> Self {
+        Self {
+            masteries: CHARACTERS
+                .iter()
+                .map(|c| (c.id, CharacterMastery::load(c.id)))
+                .collect(),
         }
     }
 
@@ -92,11 +120,11 @@ impl MasteryManager {
     }
 
     /// Add XP to a character
-    pub fn add_xp(&mut self, character: CharacterId, xp: f32) -> Vec<MasteryReward> {
+    pub fn add_xp(&mut self, character: CharacterId, xp: f32, source: &str) -> Vec<MasteryReward> {
         let mut rewards = Vec::new();
 
         if let Some(mastery) = self.masteries.get_mut(&character) {
-            rewards = mastery.add_xp(xp);
+            rewards = mastery.add_xp(xp, source);
         }
 
         rewards
@@ -138,12 +166,72 @@ impl CharacterMastery {
             highest_combo: 0,
             unlocked_skins: vec!["Default".to_string()],
             unlocked_titles: vec![],
-            milestones: Self::create_milestones(),
+            milestones: Self::create_milestones(character),
+            equipped_skin: String::new(),
+            unlocked_voice_lines: vec![],
+            load_error: None,
+        }
+    }
+
+    /// Load a character's saved mastery, or start fresh if there is none
+    pub fn load(character: CharacterId) -> Self {
+        let profile = crate::data::get_active_profile();
+        match migrate::read_if_exists::<Self>(&Self::mastery_file_path(character, profile)) {
+            Ok(Some(mut mastery)) => {
+                mastery.sync_milestones();
+                mastery
+            }
+            Ok(None) => Self::new(character),
+            Err(e) => {
+                crash::log(e.clone());
+                Self {
+                    load_error: Some(e),
+                    ..Self::new(character)
+                }
+            }
+        }
+    }
+
+    /// Add milestones a save from before they existed is missing, unlocking
+    /// the ones its level has already passed
+    fn sync_milestones(&mut self) {
+        let mut passed = Vec::new();
+        for milestone in Self::create_milestones(self.character) {
+            let known = self
+                .milestones
+                .iter()
+                .any(|m| m.level == milestone.level && m.name == milestone.name);
+            if known {
+                continue;
+            }
+            let unlocked = self.level >= milestone.level;
+            if unlocked {
+                passed.push(milestone.reward.clone());
+            }
+            self.milestones.push(MasteryMilestone { unlocked, ..milestone });
+        }
+        self.milestones.sort_by_key(|m| m.level);
+        for reward in &passed {
+            self.unlock(reward);
         }
     }
 
+    pub fn save(&self) -> Result<(), String> {
+        migrate::check_writable(self.load_error.as_deref())?;
+        let profile = crate::data::get_active_profile();
+        migrate::write_versioned(&Self::mastery_file_path(self.character, profile), self)
+            .map_err(|e| format!("Failed to write mastery data: {}", e))?;
+        ledger::save()
+    }
+
+    pub fn mastery_file_path(character: CharacterId, profile: usize) -> PathBuf {
+        let stem = format!("{:?}", character).to_lowercase();
+        SaveBundle::data_directory().join("mastery").join(profile_file_name(&stem, profile))
+    }
+
     /// Create mastery milestones
-    fn create_milestones() -> Vec<MasteryMilestone> {
+    fn create_milestones(character: CharacterId) -> Vec<MasteryMilestone> {
+        let data = Character::get_by_id(character);
         vec![
             MasteryMilestone {
                 level: 5,
@@ -173,6 +261,13 @@ impl CharacterMastery {
                 reward: MasteryReward::Skin("Silver Variant".to_string()),
                 unlocked: false,
             },
+            MasteryMilestone {
+                level: 25,
+                name: "Silver Voice".to_string(),
+                description: "Unlock a new voice line".to_string(),
+                reward: MasteryReward::VoiceLine(data.mastery_lines[0].to_string()),
+                unlocked: false,
+            },
             MasteryMilestone {
                 level: 30,
                 name: "Journeyman".to_string(),
@@ -194,6 +289,13 @@ impl CharacterMastery {
                 reward: MasteryReward::Skin("Gold Variant".to_string()),
                 unlocked: false,
             },
+            MasteryMilestone {
+                level: 50,
+                name: "Gold Voice".to_string(),
+                description: "Unlock a new voice line".to_string(),
+                reward: MasteryReward::VoiceLine(data.mastery_lines[1].to_string()),
+                unlocked: false,
+            },
             MasteryMilestone {
                 level: 60,
                 name: "Expert".to_string(),
@@ -229,11 +331,22 @@ impl CharacterMastery {
                 reward: MasteryReward::Skin("Master Skin".to_string()),
                 unlocked: false,
             },
+            MasteryMilestone {
+                level: 100,
+                name: "Signature".to_string(),
+                description: "Unlock the signature move".to_string(),
+                reward: MasteryReward::SignatureMove(data.signature_move.to_string()),
+                unlocked: false,
+            },
         ]
     }
 
-    /// Add XP and handle level ups
-    pub fn add_xp(&mut self, xp: f32) -> Vec<MasteryReward> {
+    /// Add XP and handle level ups. XP the audit log refuses is dropped.
+    pub fn add_xp(&mut self, xp: f32, source: &str) -> Vec<MasteryReward> {
+        let account = Account::MasteryXp(self.character);
+        if ledger::audit(account, source, xp as f64, self.xp as f64).is_err() {
+            return Vec::new();
+        }
         self.xp += xp;
 
         let mut rewards = Vec::new();
@@ -251,24 +364,61 @@ impl CharacterMastery {
                 if milestone.level == self.level && !milestone.unlocked {
                     milestone.unlocked = true;
                     rewards.push(milestone.reward.clone());
-
-                    // Apply rewards
-                    match &milestone.reward {
-                        MasteryReward::Skin(skin) => {
-                            self.unlocked_skins.push(skin.clone());
-                        }
-                        MasteryReward::Title(title) => {
-                            self.unlocked_titles.push(title.clone());
-                        }
-                        _ => {}
-                    }
                 }
             }
         }
 
+        for reward in &rewards {
+            self.unlock(reward);
+        }
         rewards
     }
 
+    /// Keep what a milestone gives; rewards paid out elsewhere are left to
+    /// whoever gets them back from `add_xp`
+    fn unlock(&mut self, reward: &MasteryReward) {
+        let (unlocked, unlock) = match reward {
+            MasteryReward::Skin(skin) => (&mut self.unlocked_skins, skin),
+            MasteryReward::Title(title) => (&mut self.unlocked_titles, title),
+            MasteryReward::VoiceLine(line) => (&mut self.unlocked_voice_lines, line),
+            _ => return,
+        };
+        if !unlocked.contains(unlock) {
+            unlocked.push(unlock.clone());
+        }
+    }
+
+    /// The next milestone still to reach
+    pub fn next_milestone(&self) -> Option<&MasteryMilestone> {
+        self.milestones.iter().find(|m| !m.unlocked)
+    }
+
+    pub fn has_signature_move(&self) -> bool {
+        self.rank == MasteryRank::Master
+    }
+
+    /// Sprite tint for the equipped skin
+    pub fn skin_tint(&self) -> Color {
+        PALETTE_SWAPS
+            .iter()
+            .find(|(skin, _)| *skin == self.equipped_skin)
+            .map_or(Color::new(1.0, 1.0, 1.0, 1.0), |(_, [r, g, b])| Color::new(*r, *g, *b, 1.0))
+    }
+
+    /// Equip the next unlocked skin, wrapping back to the default look
+    pub fn cycle_skin(&mut self) {
+        let equipped = if self.equipped_skin.is_empty() { "Default" } else { &self.equipped_skin };
+        let next = self
+            .unlocked_skins
+            .iter()
+            .position(|skin| skin == equipped)
+            .map_or(0, |i| i + 1);
+        self.equipped_skin = match self.unlocked_skins.get(next) {
+            Some(skin) if skin != "Default" => skin.clone(),
+            _ => String::new(),
+        };
+    }
+
     /// Calculate XP needed for next level
     pub fn xp_for_next_level(&self) -> f32 {
         // Exponential scaling: base * (level^1.5)
@@ -332,6 +482,23 @@ impl CharacterMastery {
     }
 }
 
+impl MasteryReward {
+    /// What kind of reward it is and what it gives, for unlock popups
+    pub fn describe(&self) -> (&'static str, String) {
+        match self {
+            MasteryReward::Skin(skin) => ("Palette Swap", skin.clone()),
+            MasteryReward::Title(title) => ("Title", title.clone()),
+            MasteryReward::SkillPoints(points) => ("Skill Points", format!("+{}", points)),
+            MasteryReward::Currency(amount) => ("Arc Tokens", format!("+{:.0}", amount)),
+            MasteryReward::Cosmetic(cosmetic) => ("Cosmetic", cosmetic.clone()),
+            MasteryReward::VoiceLine(line) => ("Voice Line", format!("\"{}\"", line)),
+            MasteryReward::SignatureMove(name) => {
+                ("Signature Move", format!("{} (with the ability)", name))
+            }
+        }
+    }
+}
+
 impl MasteryRank {
     pub fn to_string(&self) -> &str {
         match self {
@@ -364,3 +531,46 @@ pub struct GameStats {
     pub deaths: u32,
     pub highest_combo: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn master_rank_unlocks_voice_lines_skins_and_the_signature_move() {
+        let mut mastery = CharacterMastery::new(CharacterId::Bas);
+        let mut rewards = Vec::new();
+        while mastery.level < 100 {
+            rewards.extend(mastery.add_xp(10_000.0, "Test"));
+        }
+
+        assert!(mastery.has_signature_move());
+        assert!(rewards.iter().any(|r| matches!(r, MasteryReward::SignatureMove(_))));
+        assert_eq!(mastery.unlocked_voice_lines.len(), 2);
+        assert!(mastery.next_milestone().is_none());
+
+        // Cycling goes through every rank skin and back to the default look
+        let skins = mastery.unlocked_skins.len();
+        for _ in 1..skins {
+            mastery.cycle_skin();
+            assert_ne!(mastery.skin_tint(), Color::new(1.0, 1.0, 1.0, 1.0));
+        }
+        mastery.cycle_skin();
+        assert!(mastery.equipped_skin.is_empty());
+    }
+
+    #[test]
+    fn older_saves_pick_up_milestones_they_already_passed() {
+        let mut mastery = CharacterMastery::new(CharacterId::Luca);
+        mastery.level = 30;
+        mastery
+            .milestones
+            .retain(|m| !matches!(m.reward, MasteryReward::VoiceLine(_)));
+
+        mastery.sync_milestones();
+        let lines = Character::get_by_id(CharacterId::Luca).mastery_lines;
+        assert_eq!(mastery.unlocked_voice_lines, [lines[0]]);
+        let gold_voice = mastery.milestones.iter().find(|m| m.name == "Gold Voice");
+        assert!(gold_voice.is_some_and(|m| !m.unlocked));
+    }
+}
@@ -3,9 +3,13 @@ pub mod character_mastery;
 pub mod achievements;
 pub mod account_level;
 pub mod challenges;
+pub mod plugin;
+pub mod practice;
+pub mod roster;
+pub mod winter_arc;
 
 pub use skill_tree::{SkillTree, SkillNode, SkillTreeManager};
 pub use character_mastery::{CharacterMastery, MasteryRank, MasteryManager};
 pub use achievements::{Achievement, AchievementManager, AchievementCategory};
-pub use account_level::{AccountProgression, PrestigeSystem};
-pub use challenges::{ChallengeManager, Challenge, ChallengeType};
+pub use account_level::{PlayerAccount, PrestigeBadge};
+pub use challenges::{ChallengeKind, ChallengeManager, Challenge, ChallengeType};
@@ -0,0 +1,24 @@
+use crate::plugins::{on_off, Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+
+pub struct ProgressionPlugin;
+
+impl Plugin for ProgressionPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Progression",
+            entries: vec![
+                SettingsEntry {
+                    label: |config| {
+                        format!(
+                            "Roll Back Edited Arc Tokens: {}",
+                            on_off(config.rollback_unaudited_tokens)
+                        )
+                    },
+                    activate: |config| {
+                        config.rollback_unaudited_tokens = !config.rollback_unaudited_tokens
+                    },
+                },
+            ],
+        });
+    }
+}
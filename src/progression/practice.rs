@@ -0,0 +1,103 @@
+use crate::data::crash;
+use crate::data::save::{SaveManager, DEFAULT_PROFILE};
+use crate::data::UpgradeId;
+use crate::render::map_system::MapType;
+use serde::{Deserialize, Serialize};
+
+/// Furthest wave a run has started on a map
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReachedWave {
+    pub map: MapType,
+    pub wave: u32,
+}
+
+/// How far regular runs have got on each map, in the profile save. Practice
+/// can only start somewhere a run has already been.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PracticeProgress {
+    pub reached: Vec<ReachedWave>,
+}
+
+impl PracticeProgress {
+    /// Progress from the profile save; a missing profile has reached nothing
+    pub fn load() -> Self {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        match saves.load_or_create(profile, DEFAULT_PROFILE) {
+            Ok(save) => save.practice.clone(),
+            Err(e) => {
+                crash::log(format!("Failed to load practice progress: {}", e));
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the progress back into the profile, leaving the rest of it be
+    pub fn save(&self) -> Result<(), String> {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        saves.load_or_create(profile, DEFAULT_PROFILE)?.practice = self.clone();
+        saves.save_to_slot(profile)
+    }
+
+    /// Note that `wave` was started on `map`. True if no run had got that
+    /// far there before.
+    pub fn record(&mut self, map: MapType, wave: u32) -> bool {
+        match self.reached.iter_mut().find(|reached| reached.map == map) {
+            Some(reached) if reached.wave >= wave => false,
+            Some(reached) => {
+                reached.wave = wave;
+                true
+            }
+            None => {
+                self.reached.push(ReachedWave { map, wave });
+                true
+            }
+        }
+    }
+
+    /// Furthest wave reached on `map`, 0 if it's never been played
+    pub fn reached(&self, map: MapType) -> u32 {
+        self.reached
+            .iter()
+            .find(|reached| reached.map == map)
+            .map_or(0, |reached| reached.wave)
+    }
+
+    /// Whether practice may start at `wave` on `map`. With the debug controls
+    /// on, everything is open.
+    pub fn unlocked(&self, map: MapType, wave: u32) -> bool {
+        crate::data::get_debug_controls() || wave <= self.reached(map)
+    }
+}
+
+/// Where a practice run starts and what it starts with, set on the practice
+/// screen for the next run
+#[derive(Clone, Debug, PartialEq)]
+pub struct PracticeStart {
+    pub map: MapType,
+    /// Counted from 1
+    pub wave: u32,
+    /// Shop upgrades to start with, in place of everything owned
+    pub upgrades: Vec<UpgradeId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_reached_waves_unlock() {
+        let mut progress = PracticeProgress::default();
+        assert!(progress.record(MapType::Gym, 3));
+        assert!(!progress.record(MapType::Gym, 2));
+        assert!(progress.record(MapType::Gym, 4));
+
+        assert_eq!(progress.reached(MapType::Gym), 4);
+        assert_eq!(progress.reached(MapType::Library), 0);
+        assert!(progress.unlocked(MapType::Gym, 4));
+        assert!(!progress.unlocked(MapType::Gym, 5));
+        assert!(!progress.unlocked(MapType::Library, 1));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::data::characters::{Character, CharacterId, CHARACTERS};
+use crate::data::crash;
+use crate::data::save::{SaveManager, DEFAULT_PROFILE};
+use crate::data::ShopManager;
+use serde::{Deserialize, Serialize};
+
+/// Who a new profile can play from the start
+pub const STARTERS: [CharacterId; 4] =
+    [CharacterId::Berkay, CharacterId::Luca, CharacterId::Gefferinho, CharacterId::Bas];
+/// Arc Tokens it takes to unlock anyone else
+pub const UNLOCK_COST: u32 = 750;
+/// Runs a day the profile can take a locked character on for free
+pub const DAILY_TRIAL_RUNS: u32 = 1;
+/// Where a locked character can't be picked: trial runs are solo only
+pub const LOCKED_HERE: &str = "LOCKED - UNLOCK ON CHARACTER SELECT";
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Counts that start over each day, kept in the profile
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DailyCounters {
+    /// Days since the epoch the counts are for
+    pub day: u64,
+    pub trial_runs: u32,
+}
+
+impl DailyCounters {
+    /// The counts as they stand on `day`, from zero if they're from before it
+    fn on(&self, day: u64) -> Self {
+        if self.day == day {
+            self.clone()
+        } else {
+            Self { day, ..Self::default() }
+        }
+    }
+}
+
+/// Whether a character can be taken on a run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Unlocked,
+    /// Locked, with a free trial run left today
+    Trial,
+    Locked,
+}
+
+/// A run on a character the profile hasn't unlocked, and who was picked
+/// before it, to go back to once it's over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrialRun {
+    pub character: CharacterId,
+    pub previous: CharacterId,
+}
+
+/// The characters the active profile has unlocked, and its trial runs today
+#[derive(Clone, Debug)]
+pub struct Roster {
+    /// By name, as the save's unlocks keep them
+    unlocked: Vec<String>,
+    daily: DailyCounters,
+    today: u64,
+}
+
+impl Roster {
+    pub fn load() -> Self {
+        let today = now() / DAY;
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        match saves.load_or_create(profile, DEFAULT_PROFILE) {
+            Ok(save) => Self {
+                unlocked: save.unlocks.characters.clone(),
+                daily: save.daily.clone(),
+                today,
+            },
+            Err(e) => {
+                crash::log(format!("Failed to load the roster: {}", e));
+                Self {
+                    unlocked: starter_names(),
+                    daily: DailyCounters::default(),
+                    today,
+                }
+            }
+        }
+    }
+
+    /// Write the unlocks and today's counts back into the profile
+    pub fn save(&self) -> Result<(), String> {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        let save = saves.load_or_create(profile, DEFAULT_PROFILE)?;
+        save.unlocks.characters = self.unlocked.clone();
+        save.daily = self.daily.clone();
+        saves.save_to_slot(profile)
+    }
+
+    pub fn is_unlocked(&self, id: CharacterId) -> bool {
+        let name = Character::get_by_id(id).name;
+        self.unlocked.iter().any(|unlocked| unlocked == name)
+    }
+
+    pub fn trial_runs_left(&self) -> u32 {
+        DAILY_TRIAL_RUNS.saturating_sub(self.daily.on(self.today).trial_runs)
+    }
+
+    pub fn access(&self, id: CharacterId) -> Access {
+        if self.is_unlocked(id) {
+            Access::Unlocked
+        } else if self.trial_runs_left() > 0 {
+            Access::Trial
+        } else {
+            Access::Locked
+        }
+    }
+
+    /// Use up today's trial run on locked `id`
+    pub fn start_trial(&mut self, id: CharacterId) -> Result<(), String> {
+        let name = Character::get_by_id(id).name;
+        match self.access(id) {
+            Access::Unlocked => Err(format!("{} is already unlocked", name)),
+            Access::Locked => Err(format!(
+                "No trial runs left today - unlock {} for {} Arc Tokens",
+                name, UNLOCK_COST
+            )),
+            Access::Trial => {
+                self.daily = self.daily.on(self.today);
+                self.daily.trial_runs += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Buy `id` with Arc Tokens and save both sides. The unlock is written
+    /// before the tokens, and a failed write of either puts the other back,
+    /// so the tokens never go without the character.
+    pub fn unlock(&mut self, id: CharacterId, shop: &mut ShopManager) -> Result<(), String> {
+        let name = Character::get_by_id(id).name;
+        if self.is_unlocked(id) {
+            return Err(format!("{} is already unlocked", name));
+        }
+        shop.spend_currency(UNLOCK_COST, name)?;
+        self.unlocked.push(name.to_string());
+
+        let saved = self.save().and_then(|_| shop.save());
+        if let Err(e) = saved {
+            self.unlocked.retain(|unlocked| unlocked != name);
+            let refund = shop.add_currency(UNLOCK_COST, &format!("{} unlock refund", name));
+            if let Err(e) = self.save().and(refund) {
+                crash::log(format!("Failed to roll back the {} unlock: {}", name, e));
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// What a new profile's unlocks start with
+pub fn starter_names() -> Vec<String> {
+    STARTERS.iter().map(|&id| Character::get_by_id(id).name.to_string()).collect()
+}
+
+/// Everyone, for profiles from before the roster was locked
+pub fn everyone() -> Vec<String> {
+    CHARACTERS.iter().map(|character| character.name.to_string()).collect()
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_trial_run_is_spent_once_a_day_and_comes_back_the_next() {
+        let mut roster = Roster {
+            unlocked: starter_names(),
+            daily: DailyCounters { day: 99, trial_runs: 1 },
+            today: 100,
+        };
+        assert_eq!(roster.access(CharacterId::Bas), Access::Unlocked);
+        assert_eq!(roster.access(CharacterId::Jad), Access::Trial);
+
+        assert!(roster.start_trial(CharacterId::Jad).is_ok());
+        assert_eq!(roster.daily, DailyCounters { day: 100, trial_runs: 1 });
+        assert_eq!(roster.access(CharacterId::Jad), Access::Locked);
+        assert!(roster.start_trial(CharacterId::Umut).is_err());
+        assert!(roster.start_trial(CharacterId::Bas).is_err());
+
+        roster.today = 101;
+        assert_eq!(roster.trial_runs_left(), DAILY_TRIAL_RUNS);
+    }
+
+    #[test]
+    fn an_unlock_that_cannot_be_saved_keeps_the_tokens() {
+        let root = std::env::temp_dir().join("bas-veeg-arc-test-roster");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        // Saves can't go anywhere with a file where their folder should be
+        std::fs::write(root.join("saves"), "").unwrap();
+        crate::data::SaveBundle::set_data_root(Some(root.clone()));
+
+        let mut shop = ShopManager::load();
+        shop.add_currency(UNLOCK_COST, "Wave cleared").unwrap();
+        let mut roster = Roster {
+            unlocked: starter_names(),
+            daily: DailyCounters::default(),
+            today: 100,
+        };
+        let unlocked = roster.unlock(CharacterId::Jad, &mut shop);
+        let kept = ShopManager::load().currency();
+        crate::data::SaveBundle::set_data_root(None);
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(unlocked.is_err());
+        assert!(!roster.is_unlocked(CharacterId::Jad));
+        assert_eq!(shop.currency(), UNLOCK_COST);
+        assert_eq!(kept, 0);
+    }
+}
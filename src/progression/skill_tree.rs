@@ -1,11 +1,20 @@
 use crate::data::characters::CharacterId;
+use crate::data::migrate::{self, Migration, Versioned};
+use crate::data::save::profile_file_name;
+use crate::data::{crash, SaveBundle, ShopManager};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Arc Tokens that stand in for one skill point
+pub const TOKENS_PER_POINT: u32 = 200;
 
 /// Manages skill trees for all characters
 pub struct SkillTreeManager {
     trees: HashMap<CharacterId, SkillTree>,
     skill_points: HashMap<CharacterId, u32>,
+    /// Why the skill tree file didn't load, if it didn't; it's kept as it is
+    load_error: Option<String>,
 }
 
 /// Skill tree for a character with 3 specialization branches
@@ -69,17 +78,51 @@ pub enum SkillType {
     Multicast(f32),               // % chance to cast ability twice
     LuckBonus(f32),               // % better loot/rewards
 
+    StartingMeter(f32),           // % of the super meter filled at the start of a run
+    CancelRoute(CancelRoute),     // New follow-up that skips the attack delay
+
     // Special/Unique
     UnlockAbility(String),        // Unlock new ability
     PassiveBuff(String, f32),     // Passive buff with value
     SynergyBoost(String, f32),    // Boost to specific mechanics
 }
 
+/// An attack that can follow another straight away, without waiting out
+/// the auto-attack delay
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum CancelRoute {
+    LightIntoHeavy,
+    HeavyIntoSpecial,
+}
+
+impl CancelRoute {
+    pub fn label(self) -> &'static str {
+        match self {
+            CancelRoute::LightIntoHeavy => "Light > Heavy",
+            CancelRoute::HeavyIntoSpecial => "Heavy > Special",
+        }
+    }
+}
+
+/// What's kept of a profile's skill trees: unspent points per character and
+/// the level of every node bought. The trees themselves come from the code.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SkillTreeSave {
+    points: HashMap<CharacterId, u32>,
+    levels: HashMap<String, u32>,
+}
+
+impl Versioned for SkillTreeSave {
+    const MIGRATIONS: &'static [Migration] = &[migrate::from_unversioned];
+}
+
 impl SkillTreeManager {
     pub fn new() -> Self {
         let mut manager = Self {
             trees: HashMap::new(),
             skill_points: HashMap::new(),
+            load_error: None,
         };
 
         // Initialize skill trees for all characters
@@ -88,6 +131,56 @@ impl SkillTreeManager {
         manager
     }
 
+    /// The active profile's trees and points
+    pub fn load() -> Self {
+        let mut manager = Self::new();
+        let profile = crate::data::get_active_profile();
+        let save: SkillTreeSave = match migrate::read_if_exists(&Self::file_path(profile)) {
+            Ok(Some(save)) => save,
+            Ok(None) => return manager,
+            Err(e) => {
+                crash::log(e.clone());
+                manager.load_error = Some(e);
+                return manager;
+            }
+        };
+
+        manager.skill_points.extend(save.points);
+        for tree in manager.trees.values_mut() {
+            for node in tree.nodes_mut() {
+                if let Some(&level) = save.levels.get(&node.id) {
+                    node.current_level = level.min(node.max_level);
+                }
+            }
+            tree.refresh_unlocks();
+        }
+        manager
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        migrate::check_writable(self.load_error.as_deref())?;
+        let levels = self
+            .trees
+            .values()
+            .flat_map(|tree| tree.nodes())
+            .filter(|node| node.current_level > 0)
+            .map(|node| (node.id.clone(), node.current_level))
+            .collect();
+        let save = SkillTreeSave {
+            points: self.skill_points.clone(),
+            levels,
+        };
+        let profile = crate::data::get_active_profile();
+        migrate::write_versioned(&Self::file_path(profile), &save)
+            .map_err(|e| format!("Failed to write skill trees: {}", e))
+    }
+
+    pub fn file_path(profile: usize) -> PathBuf {
+        SaveBundle::data_directory()
+            .join("skill_tree")
+            .join(profile_file_name("skill_tree", profile))
+    }
+
     /// Initialize skill trees for all characters
     fn init_all_character_trees(&mut self) {
         // Create tree for all characters
@@ -208,6 +301,31 @@ impl SkillTreeManager {
                         skill_type: SkillType::UnlockAbility("VeegVortex".to_string()),
                         unlocked: false,
                     },
+                    // Cancel routes
+                    SkillNode {
+                        id: "bas_off_8".to_string(),
+                        name: "Follow-Through".to_string(),
+                        description: "Heavy attacks can follow a light straight away".to_string(),
+                        tier: 2,
+                        max_level: 1,
+                        current_level: 0,
+                        skill_point_cost: 2,
+                        prerequisites: vec!["bas_off_1".to_string()],
+                        skill_type: SkillType::CancelRoute(CancelRoute::LightIntoHeavy),
+                        unlocked: false,
+                    },
+                    SkillNode {
+                        id: "bas_off_9".to_string(),
+                        name: "Big Finish".to_string(),
+                        description: "Specials can follow a heavy attack straight away".to_string(),
+                        tier: 3,
+                        max_level: 1,
+                        current_level: 0,
+                        skill_point_cost: 3,
+                        prerequisites: vec!["bas_off_8".to_string()],
+                        skill_type: SkillType::CancelRoute(CancelRoute::HeavyIntoSpecial),
+                        unlocked: false,
+                    },
                 ],
             },
             branch_defense: SkillBranch {
@@ -398,6 +516,18 @@ impl SkillTreeManager {
                         skill_type: SkillType::Multicast(10.0),
                         unlocked: false,
                     },
+                    SkillNode {
+                        id: "bas_util_8".to_string(),
+                        name: "Early Shift".to_string(),
+                        description: "Start each run with 10% super meter per level".to_string(),
+                        tier: 2,
+                        max_level: 3,
+                        current_level: 0,
+                        skill_point_cost: 2,
+                        prerequisites: vec!["bas_util_2".to_string()],
+                        skill_type: SkillType::StartingMeter(10.0),
+                        unlocked: false,
+                    },
                 ],
             },
         }
@@ -647,6 +777,18 @@ impl SkillTreeManager {
                         skill_type: SkillType::CriticalDamage(25.0),
                         unlocked: false,
                     },
+                    SkillNode {
+                        id: format!("{}_off_5", char_id),
+                        name: "Follow-Through".to_string(),
+                        description: "Heavy attacks can follow a light straight away".to_string(),
+                        tier: 3,
+                        max_level: 1,
+                        current_level: 0,
+                        skill_point_cost: 3,
+                        prerequisites: vec![format!("{}_off_3", char_id)],
+                        skill_type: SkillType::CancelRoute(CancelRoute::LightIntoHeavy),
+                        unlocked: false,
+                    },
                 ],
             },
             branch_defense: SkillBranch {
@@ -755,6 +897,18 @@ impl SkillTreeManager {
                         skill_type: SkillType::ResourceGain(15.0),
                         unlocked: false,
                     },
+                    SkillNode {
+                        id: format!("{}_util_5", char_id),
+                        name: "Warmed Up".to_string(),
+                        description: "Start each run with 10% super meter per level".to_string(),
+                        tier: 2,
+                        max_level: 3,
+                        current_level: 0,
+                        skill_point_cost: 2,
+                        prerequisites: vec![format!("{}_util_1", char_id)],
+                        skill_type: SkillType::StartingMeter(10.0),
+                        unlocked: false,
+                    },
                 ],
             },
         }
@@ -765,9 +919,46 @@ impl SkillTreeManager {
         self.trees.get(&character)
     }
 
-    /// Get mutable skill tree for character
-    pub fn get_tree_mut(&mut self, character: CharacterId) -> Option<&mut SkillTree> {
-        self.trees.get_mut(&character)
+    /// Skill points the next level of `skill_id` costs, if it can be bought
+    /// at all: not maxed, with every prerequisite learned
+    pub fn next_level_cost(&self, character: CharacterId, skill_id: &str) -> Result<u32, String> {
+        let tree = self
+            .trees
+            .get(&character)
+            .ok_or_else(|| format!("No skill tree found for character {:?}", character))?;
+        let skill = tree
+            .find_skill(skill_id)
+            .ok_or_else(|| format!("Skill {} not found", skill_id))?;
+        if skill.current_level >= skill.max_level {
+            return Err("Skill already at max level".to_string());
+        }
+        if let Some(missing) = skill
+            .prerequisites
+            .iter()
+            .find(|id| tree.find_skill(id).is_some_and(|prereq| prereq.current_level == 0))
+        {
+            return Err(format!("Prerequisite {} not unlocked", missing));
+        }
+        Ok(skill.skill_point_cost)
+    }
+
+    /// Buy the next level of `skill_id` with Arc Tokens instead of points,
+    /// `TOKENS_PER_POINT` for each point it costs
+    pub fn unlock_skill_for_tokens(
+        &mut self,
+        character: CharacterId,
+        skill_id: &str,
+        shop: &mut ShopManager,
+    ) -> Result<u32, String> {
+        let tokens = self.next_level_cost(character, skill_id)? * TOKENS_PER_POINT;
+        shop.spend_currency(tokens, &format!("Skill {}", skill_id))?;
+        if let Some(tree) = self.trees.get_mut(&character) {
+            if let Some(skill) = tree.find_skill_mut(skill_id) {
+                skill.current_level += 1;
+            }
+            tree.refresh_unlocks();
+        }
+        Ok(tokens)
     }
 
     /// Unlock a skill node
@@ -813,6 +1004,7 @@ impl SkillTreeManager {
             // Unlock the skill
             skill.current_level += 1;
             skill.unlocked = true;
+            tree.refresh_unlocks();
 
             // Deduct skill points
             if let Some(points) = self.skill_points.get_mut(&character) {
@@ -840,11 +1032,6 @@ impl SkillTreeManager {
         self.get_skill_points(character)
     }
 
-    /// Upgrade a skill (alias for unlock_skill that returns bool)
-    pub fn upgrade_skill(&mut self, character: CharacterId, skill_id: &str) -> bool {
-        self.unlock_skill(character, skill_id).is_ok()
-    }
-
     /// Reset skill tree for a character (costs currency)
     pub fn reset_tree(&mut self, character: CharacterId) -> u32 {
         let mut refunded_points = 0;
@@ -873,6 +1060,32 @@ impl SkillTreeManager {
 }
 
 impl SkillTree {
+    pub fn branches(&self) -> [&SkillBranch; 3] {
+        [&self.branch_offense, &self.branch_defense, &self.branch_utility]
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = &SkillNode> {
+        self.branches().into_iter().flat_map(|branch| branch.nodes.iter())
+    }
+
+    fn nodes_mut(&mut self) -> impl Iterator<Item = &mut SkillNode> {
+        [&mut self.branch_offense, &mut self.branch_defense, &mut self.branch_utility]
+            .into_iter()
+            .flat_map(|branch| branch.nodes.iter_mut())
+    }
+
+    /// A node opens up once every prerequisite has a level in it
+    fn refresh_unlocks(&mut self) {
+        let learned: Vec<String> = self
+            .nodes()
+            .filter(|node| node.current_level > 0)
+            .map(|node| node.id.clone())
+            .collect();
+        for node in self.nodes_mut() {
+            node.unlocked = node.prerequisites.iter().all(|id| learned.contains(id));
+        }
+    }
+
     /// Find a skill by ID across all branches
     fn find_skill(&self, skill_id: &str) -> Option<&SkillNode> {
         self.branch_offense
@@ -905,11 +1118,9 @@ impl SkillTree {
             for node in &mut branch.nodes {
                 refunded_points += node.skill_point_cost * node.current_level;
                 node.current_level = 0;
-                if node.tier > 1 {
-                    node.unlocked = false;
-                }
             }
         }
+        self.refresh_unlocks();
 
         refunded_points
     }
@@ -956,6 +1167,9 @@ pub struct SkillBonuses {
     pub lifesteal: f32,
     pub multicast_chance: f32,
     pub luck_bonus: f32,
+    /// Share of the super meter a run starts with
+    pub starting_meter: f32,
+    pub cancel_routes: Vec<CancelRoute>,
 
     pub unlocked_abilities: Vec<String>,
 }
@@ -988,6 +1202,10 @@ impl SkillBonuses {
             SkillType::Lifesteal(v) => self.lifesteal += v * level_f,
             SkillType::Multicast(v) => self.multicast_chance += v * level_f,
             SkillType::LuckBonus(v) => self.luck_bonus += v * level_f / 100.0,
+            SkillType::StartingMeter(v) => self.starting_meter += v * level_f / 100.0,
+            SkillType::CancelRoute(route) if !self.cancel_routes.contains(route) => {
+                self.cancel_routes.push(*route)
+            }
 
             SkillType::UnlockAbility(ability) => {
                 if !self.unlocked_abilities.contains(ability) {
@@ -998,3 +1216,37 @@ impl SkillBonuses {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learning_a_node_opens_up_what_it_leads_to() {
+        let mut manager = SkillTreeManager::new();
+        manager.add_skill_points(CharacterId::Bas, 10);
+        let locked = |manager: &SkillTreeManager| {
+            let tree = manager.get_tree(CharacterId::Bas).unwrap();
+            !tree.find_skill("bas_off_8").unwrap().unlocked
+        };
+        assert!(locked(&manager));
+        assert!(manager.next_level_cost(CharacterId::Bas, "bas_off_8").is_err());
+
+        manager.unlock_skill(CharacterId::Bas, "bas_off_1").unwrap();
+        assert!(!locked(&manager));
+        assert_eq!(manager.next_level_cost(CharacterId::Bas, "bas_off_8"), Ok(2));
+    }
+
+    #[test]
+    fn meter_and_cancel_nodes_reach_the_bonuses() {
+        let mut manager = SkillTreeManager::new();
+        manager.add_skill_points(CharacterId::Bas, 20);
+        for id in ["bas_off_1", "bas_off_8", "bas_util_2", "bas_util_8", "bas_util_8"] {
+            manager.unlock_skill(CharacterId::Bas, id).unwrap();
+        }
+
+        let bonuses = manager.calculate_bonuses(CharacterId::Bas);
+        assert!((bonuses.starting_meter - 0.2).abs() < 1e-6);
+        assert_eq!(bonuses.cancel_routes, vec![CancelRoute::LightIntoHeavy]);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::data::crash;
+use crate::data::save::{SaveManager, DEFAULT_PROFILE};
+use crate::data::UpgradeId;
+use crate::util::rng::SeededRng;
+use serde::{Deserialize, Serialize};
+
+/// Upgrade cards offered after each wave, before the fourth-choice unlock
+pub const UPGRADE_CHOICES: usize = 3;
+/// Chance each card comes cursed, while there are curses left to hand out
+const CURSE_CHANCE: f32 = 0.3;
+
+const SHARDS_PER_WAVE: u32 = 5;
+/// Bonus for beating the rooftop bosses
+const CLEAR_BONUS: u32 = 50;
+/// Every curse held adds this much to the run's shard payout
+const CURSE_SHARD_BONUS: f32 = 0.25;
+
+/// An upgrade a run can draw, with what one stack of it does
+#[derive(Clone, Copy, Debug)]
+pub struct RunUpgrade {
+    pub id: UpgradeId,
+    pub title: &'static str,
+    pub description: &'static str,
+}
+
+/// Pool the upgrade cards are drawn from. Picks stack; these are run-only
+/// and none of them carry over to the next run.
+pub const RUN_UPGRADES: [RunUpgrade; 6] = [
+    RunUpgrade {
+        id: UpgradeId::AttackBoost,
+        title: "Painted Fury",
+        description: "+15% damage",
+    },
+    RunUpgrade {
+        id: UpgradeId::HealthBoost,
+        title: "Protective Apron",
+        description: "+20 max health",
+    },
+    RunUpgrade {
+        id: UpgradeId::SpeedBoost,
+        title: "Turbo Mop Shoes",
+        description: "+8% move speed",
+    },
+    RunUpgrade {
+        id: UpgradeId::WarmBlanket,
+        title: "Warm Blanket",
+        description: "Heal 15% of max health after each wave",
+    },
+    RunUpgrade {
+        id: UpgradeId::ColdBlood,
+        title: "Cold Blood",
+        description: "+30% damage, -10 max health",
+    },
+    RunUpgrade {
+        id: UpgradeId::FrostGuard,
+        title: "Frost Guard",
+        description: "Enemies deal 10% less damage",
+    },
+];
+
+impl RunUpgrade {
+    pub fn get(id: UpgradeId) -> Option<&'static RunUpgrade> {
+        RUN_UPGRADES.iter().find(|upgrade| upgrade.id == id)
+    }
+}
+
+/// Makes the rest of the run harder in exchange for more Frost Shards
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curse {
+    Blizzard,
+    BlackIce,
+    Frostbite,
+    Hailstorm,
+}
+
+impl Curse {
+    pub const ALL: [Curse; 4] = [
+        Curse::Blizzard,
+        Curse::BlackIce,
+        Curse::Frostbite,
+        Curse::Hailstorm,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Curse::Blizzard => "Blizzard",
+            Curse::BlackIce => "Black Ice",
+            Curse::Frostbite => "Frostbite",
+            Curse::Hailstorm => "Hailstorm",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Curse::Blizzard => "+30% enemies per wave",
+            Curse::BlackIce => "Enemies deal 25% more damage",
+            Curse::Frostbite => "-20% max health",
+            Curse::Hailstorm => "Enemies have 30% more health",
+        }
+    }
+}
+
+/// One card of an upgrade choice. A cursed card gives two stacks of its
+/// upgrade, and the curse for the rest of the run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UpgradeOffer {
+    pub upgrade: UpgradeId,
+    pub curse: Option<Curse>,
+}
+
+impl UpgradeOffer {
+    pub fn stacks(&self) -> usize {
+        if self.curse.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// Permanent perks, unlocked by the Frost Shards earned over every run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetaUnlock {
+    WarmStart,
+    HeadStart,
+    FourthChoice,
+}
+
+impl MetaUnlock {
+    pub const ALL: [MetaUnlock; 3] = [
+        MetaUnlock::WarmStart,
+        MetaUnlock::HeadStart,
+        MetaUnlock::FourthChoice,
+    ];
+
+    /// Lifetime Frost Shards needed
+    pub fn threshold(self) -> u32 {
+        match self {
+            MetaUnlock::WarmStart => 40,
+            MetaUnlock::HeadStart => 120,
+            MetaUnlock::FourthChoice => 250,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MetaUnlock::WarmStart => "Warm Start",
+            MetaUnlock::HeadStart => "Head Start",
+            MetaUnlock::FourthChoice => "Fourth Choice",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            MetaUnlock::WarmStart => "Start every run with +20 max health",
+            MetaUnlock::HeadStart => "Start every run with a random upgrade",
+            MetaUnlock::FourthChoice => "Four upgrade cards after each wave",
+        }
+    }
+}
+
+/// What's kept between runs, in the profile save
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct WinterArcProgress {
+    /// Frost Shards earned over every run
+    pub frost_shards: u32,
+    pub runs: u32,
+    pub clears: u32,
+    pub best_waves: u32,
+    pub unlocks: Vec<MetaUnlock>,
+}
+
+/// Payout of a finished run, for the end screen
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    pub seed: u64,
+    pub cleared: bool,
+    pub waves: u32,
+    pub shards: u32,
+    pub total_shards: u32,
+    pub unlocked: Vec<MetaUnlock>,
+}
+
+impl WinterArcProgress {
+    /// Progress from the profile save; a missing profile starts from nothing
+    pub fn load() -> Self {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        match saves.load_or_create(profile, DEFAULT_PROFILE) {
+            Ok(save) => save.winter_arc.clone(),
+            Err(e) => {
+                crash::log(format!("Failed to load Winter Arc progress: {}", e));
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the progress back into the profile, leaving the rest of it be
+    pub fn save(&self) -> Result<(), String> {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        saves.load_or_create(profile, DEFAULT_PROFILE)?.winter_arc = self.clone();
+        saves.save_to_slot(profile)
+    }
+
+    pub fn has(&self, unlock: MetaUnlock) -> bool {
+        self.unlocks.contains(&unlock)
+    }
+
+    /// Add a finished run's shards and unlock whatever they reach
+    pub fn bank(&mut self, run: &WinterArcRun, cleared: bool) -> RunResult {
+        let shards = run.shards(cleared);
+        self.frost_shards = self.frost_shards.saturating_add(shards);
+        self.runs += 1;
+        if cleared {
+            self.clears += 1;
+        }
+        self.best_waves = self.best_waves.max(run.waves_cleared());
+
+        let mut unlocked = Vec::new();
+        for unlock in MetaUnlock::ALL {
+            if !self.has(unlock) && self.frost_shards >= unlock.threshold() {
+                self.unlocks.push(unlock);
+                unlocked.push(unlock);
+            }
+        }
+
+        RunResult {
+            seed: run.seed,
+            cleared,
+            waves: run.waves_cleared(),
+            shards,
+            total_shards: self.frost_shards,
+            unlocked,
+        }
+    }
+}
+
+/// One Winter Arc run. Every draw comes from the run's seed, so a seed
+/// replays the same room order, cards and curses.
+pub struct WinterArcRun {
+    rng: SeededRng,
+    seed: u64,
+    choices: usize,
+    starting_health_bonus: f32,
+    upgrades: Vec<UpgradeId>,
+    curses: Vec<Curse>,
+    offers: Vec<UpgradeOffer>,
+    waves_cleared: u32,
+}
+
+impl WinterArcRun {
+    pub fn new(seed: u64, progress: &WinterArcProgress) -> Self {
+        let mut run = Self {
+            rng: SeededRng::new(seed),
+            seed,
+            choices: if progress.has(MetaUnlock::FourthChoice) {
+                UPGRADE_CHOICES + 1
+            } else {
+                UPGRADE_CHOICES
+            },
+            starting_health_bonus: if progress.has(MetaUnlock::WarmStart) {
+                20.0
+            } else {
+                0.0
+            },
+            upgrades: Vec::new(),
+            curses: Vec::new(),
+            offers: Vec::new(),
+            waves_cleared: 0,
+        };
+        if progress.has(MetaUnlock::HeadStart) {
+            let index = run.rng.range(0, RUN_UPGRADES.len() as i32) as usize;
+            run.upgrades.push(RUN_UPGRADES[index].id);
+        }
+        run
+    }
+
+    /// Extra max health the run starts with, from Warm Start
+    pub fn starting_health_bonus(&self) -> f32 {
+        self.starting_health_bonus
+    }
+
+    /// Put the run's rooms in their order
+    pub fn shuffle<T>(&mut self, rooms: &mut [T]) {
+        self.rng.shuffle(rooms);
+    }
+
+    /// Upgrades taken so far, one entry per stack
+    pub fn upgrades(&self) -> &[UpgradeId] {
+        &self.upgrades
+    }
+
+    pub fn stacks(&self, upgrade: UpgradeId) -> usize {
+        self.upgrades.iter().filter(|&&id| id == upgrade).count()
+    }
+
+    pub fn curses(&self) -> &[Curse] {
+        &self.curses
+    }
+
+    pub fn has_curse(&self, curse: Curse) -> bool {
+        self.curses.contains(&curse)
+    }
+
+    pub fn waves_cleared(&self) -> u32 {
+        self.waves_cleared
+    }
+
+    pub fn wave_cleared(&mut self) {
+        self.waves_cleared += 1;
+    }
+
+    /// Cards waiting to be picked; empty when there's no choice up
+    pub fn offers(&self) -> &[UpgradeOffer] {
+        &self.offers
+    }
+
+    /// Deal a new set of cards: different upgrades, and on some of them a
+    /// curse the run doesn't have yet
+    pub fn roll_offers(&mut self) {
+        let mut pool: Vec<UpgradeId> = RUN_UPGRADES.iter().map(|upgrade| upgrade.id).collect();
+        self.rng.shuffle(&mut pool);
+
+        self.offers.clear();
+        for upgrade in pool.into_iter().take(self.choices) {
+            let free: Vec<Curse> = Curse::ALL
+                .iter()
+                .copied()
+                .filter(|curse| !self.has_curse(*curse))
+                .filter(|curse| !self.offers.iter().any(|offer| offer.curse == Some(*curse)))
+                .collect();
+            let curse = if !free.is_empty() && self.rng.chance(CURSE_CHANCE) {
+                Some(free[self.rng.range(0, free.len() as i32) as usize])
+            } else {
+                None
+            };
+            self.offers.push(UpgradeOffer { upgrade, curse });
+        }
+    }
+
+    /// Pick a card, closing the choice
+    pub fn take(&mut self, index: usize) -> Option<UpgradeOffer> {
+        let offer = *self.offers.get(index)?;
+        self.offers.clear();
+        for _ in 0..offer.stacks() {
+            self.upgrades.push(offer.upgrade);
+        }
+        if let Some(curse) = offer.curse {
+            self.curses.push(curse);
+        }
+        Some(offer)
+    }
+
+    /// Frost Shards the run pays out if it ended now
+    pub fn shards(&self, cleared: bool) -> u32 {
+        let base = self.waves_cleared * SHARDS_PER_WAVE + if cleared { CLEAR_BONUS } else { 0 };
+        let bonus = 1.0 + self.curses.len() as f32 * CURSE_SHARD_BONUS;
+        (base as f32 * bonus).round() as u32
+    }
+
+    pub fn enemy_count_scale(&self) -> f32 {
+        if self.has_curse(Curse::Blizzard) {
+            1.3
+        } else {
+            1.0
+        }
+    }
+
+    pub fn enemy_health_scale(&self) -> f32 {
+        if self.has_curse(Curse::Hailstorm) {
+            1.3
+        } else {
+            1.0
+        }
+    }
+
+    /// Black Ice raises it, each Frost Guard stack takes 10% off
+    pub fn enemy_damage_scale(&self) -> f32 {
+        let curse = if self.has_curse(Curse::BlackIce) { 1.25 } else { 1.0 };
+        curse * 0.9f32.powi(self.stacks(UpgradeId::FrostGuard) as i32)
+    }
+
+    pub fn max_health_scale(&self) -> f32 {
+        if self.has_curse(Curse::Frostbite) {
+            0.8
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_seed_deals_the_same_cards() {
+        let progress = WinterArcProgress::default();
+        let mut first = WinterArcRun::new(42, &progress);
+        let mut second = WinterArcRun::new(42, &progress);
+        for _ in 0..5 {
+            first.roll_offers();
+            second.roll_offers();
+            assert_eq!(first.offers(), second.offers());
+            assert_eq!(first.offers().len(), UPGRADE_CHOICES);
+            first.take(0);
+            second.take(0);
+        }
+    }
+
+    #[test]
+    fn cursed_cards_stack_twice_and_pay_more() {
+        let mut run = WinterArcRun::new(7, &WinterArcProgress::default());
+        run.wave_cleared();
+        run.wave_cleared();
+        let plain = run.shards(false);
+
+        run.offers = vec![UpgradeOffer {
+            upgrade: UpgradeId::FrostGuard,
+            curse: Some(Curse::BlackIce),
+        }];
+        run.take(0);
+
+        assert_eq!(run.stacks(UpgradeId::FrostGuard), 2);
+        assert!(run.has_curse(Curse::BlackIce));
+        assert!(run.shards(false) > plain);
+        assert!((run.enemy_damage_scale() - 1.25 * 0.81).abs() < 1e-4);
+    }
+}
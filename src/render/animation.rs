@@ -0,0 +1,309 @@
+use crate::ecs::{Component, FighterState};
+use crate::render::character_sheets::SpriteAnimation;
+use macroquad::prelude::*;
+
+/// Something a clip triggers at an exact point on its timeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationEvent {
+    HitboxOn,
+    HitboxOff,
+}
+
+/// Body transform at one point in a clip, for a fighter facing right
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    /// Frame within the clip's sprite animation, held until the next key
+    pub frame: usize,
+    pub offset: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+const fn key(
+    time: f32,
+    frame: usize,
+    offset: (f32, f32),
+    rotation: f32,
+    scale: (f32, f32),
+) -> Keyframe {
+    Keyframe {
+        time,
+        frame,
+        offset: Vec2::new(offset.0, offset.1),
+        rotation,
+        scale: Vec2::new(scale.0, scale.1),
+    }
+}
+
+/// Keyframed fighter animation. Attack clips last exactly as long as the attack
+/// and mark their active frames with `HitboxOn`/`HitboxOff` events.
+pub struct AnimationClip {
+    pub sprite: SpriteAnimation,
+    pub duration: f32,
+    pub looping: bool,
+    pub keyframes: &'static [Keyframe],
+    pub events: &'static [(f32, AnimationEvent)],
+}
+
+const IDLE: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Idle,
+    duration: 1.6,
+    looping: true,
+    keyframes: &[
+        key(0.0, 0, (0.0, 0.0), 0.0, (1.0, 1.0)),
+        key(0.8, 1, (0.0, 1.5), 0.0, (1.02, 0.98)),
+        key(1.6, 0, (0.0, 0.0), 0.0, (1.0, 1.0)),
+    ],
+    events: &[],
+};
+
+const WALK: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Walk,
+    duration: 0.5,
+    looping: true,
+    keyframes: &[
+        key(0.0, 0, (0.0, -2.0), 0.02, (1.0, 1.0)),
+        key(0.125, 1, (0.0, 1.0), 0.0, (1.02, 0.98)),
+        key(0.25, 2, (0.0, -2.0), 0.02, (1.0, 1.0)),
+        key(0.375, 3, (0.0, 1.0), 0.0, (1.02, 0.98)),
+        key(0.5, 0, (0.0, -2.0), 0.02, (1.0, 1.0)),
+    ],
+    events: &[],
+};
+
+const DODGE: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Walk,
+    duration: 0.3,
+    looping: true,
+    keyframes: &[
+        key(0.0, 0, (3.0, 0.0), 0.1, (1.05, 0.95)),
+        key(0.15, 2, (-3.0, 2.0), 0.14, (1.08, 0.92)),
+        key(0.3, 0, (3.0, 0.0), 0.1, (1.05, 0.95)),
+    ],
+    events: &[],
+};
+
+const LIGHT: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Attack,
+    duration: 0.25,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (-2.0, 0.0), -0.05, (1.0, 1.0)),
+        key(0.06, 1, (8.0, 0.0), 0.08, (1.05, 0.97)),
+        key(0.16, 2, (6.0, 0.0), 0.03, (1.0, 1.0)),
+        key(0.25, 2, (0.0, 0.0), 0.0, (1.0, 1.0)),
+    ],
+    events: &[(0.06, AnimationEvent::HitboxOn), (0.16, AnimationEvent::HitboxOff)],
+};
+
+const HEAVY: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Attack,
+    duration: 0.4,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (-4.0, 0.0), -0.1, (1.0, 1.0)),
+        key(0.14, 0, (-6.0, 1.0), -0.14, (0.97, 1.03)),
+        key(0.16, 1, (14.0, 0.0), 0.12, (1.08, 0.95)),
+        key(0.28, 2, (10.0, 0.0), 0.05, (1.0, 1.0)),
+        key(0.4, 2, (0.0, 0.0), 0.0, (1.0, 1.0)),
+    ],
+    events: &[(0.16, AnimationEvent::HitboxOn), (0.28, AnimationEvent::HitboxOff)],
+};
+
+/// Same swing as the heavy, but launchers don't arm an attack hitbox
+const LAUNCHER: AnimationClip = AnimationClip {
+    events: &[],
+    ..HEAVY
+};
+
+const SPECIAL: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Attack,
+    duration: 0.55,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (0.0, 0.0), 0.0, (1.0, 1.0)),
+        key(0.18, 0, (-6.0, -4.0), -0.12, (0.95, 1.05)),
+        key(0.2, 1, (12.0, 0.0), 0.1, (1.1, 0.94)),
+        key(0.42, 1, (14.0, 0.0), 0.08, (1.04, 0.98)),
+        key(0.55, 2, (0.0, 0.0), 0.0, (1.0, 1.0)),
+    ],
+    events: &[(0.2, AnimationEvent::HitboxOn), (0.42, AnimationEvent::HitboxOff)],
+};
+
+const SUPER: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Attack,
+    duration: 0.8,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (0.0, 0.0), 0.0, (1.0, 1.0)),
+        key(0.25, 0, (-4.0, -10.0), -0.15, (0.92, 1.08)),
+        key(0.27, 1, (18.0, 0.0), 0.15, (1.12, 0.92)),
+        key(0.65, 1, (16.0, 0.0), 0.1, (1.05, 0.97)),
+        key(0.8, 2, (0.0, 0.0), 0.0, (1.0, 1.0)),
+    ],
+    events: &[(0.27, AnimationEvent::HitboxOn), (0.65, AnimationEvent::HitboxOff)],
+};
+
+const BLOCK: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Block,
+    duration: 0.2,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (-3.0, 0.0), -0.04, (1.0, 1.0)),
+        key(0.2, 0, (-2.0, 0.0), -0.02, (1.0, 1.0)),
+    ],
+    events: &[],
+};
+
+const HITSTUN: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Hurt,
+    duration: 0.3,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (-8.0, 0.0), -0.15, (0.94, 1.04)),
+        key(0.1, 0, (-5.0, 1.0), -0.1, (1.0, 1.0)),
+        key(0.3, 0, (-4.0, 0.0), -0.06, (1.0, 1.0)),
+    ],
+    events: &[],
+};
+
+const KO: AnimationClip = AnimationClip {
+    sprite: SpriteAnimation::Down,
+    duration: 0.3,
+    looping: false,
+    keyframes: &[
+        key(0.0, 0, (-10.0, -6.0), -0.2, (1.0, 1.0)),
+        key(0.15, 0, (-14.0, 4.0), 0.0, (1.06, 0.92)),
+        key(0.3, 0, (-14.0, 0.0), 0.0, (1.0, 1.0)),
+    ],
+    events: &[],
+};
+
+impl AnimationClip {
+    pub fn for_state(state: FighterState) -> &'static AnimationClip {
+        match state {
+            FighterState::Idle
+            | FighterState::Jumping
+            | FighterState::Falling
+            | FighterState::Crouching => &IDLE,
+            FighterState::Walking => &WALK,
+            FighterState::Dodging => &DODGE,
+            FighterState::LightAttack => &LIGHT,
+            FighterState::HeavyAttack => &HEAVY,
+            FighterState::Launcher => &LAUNCHER,
+            FighterState::Special => &SPECIAL,
+            FighterState::Super => &SUPER,
+            FighterState::Blocking | FighterState::Blockstun | FighterState::Parrying => &BLOCK,
+            FighterState::Hitstun => &HITSTUN,
+            FighterState::KnockedDown => &KO,
+        }
+    }
+
+    /// Interpolated transform at `time`; the sprite frame steps with the keys
+    pub fn sample(&self, time: f32) -> AnimationSample {
+        let keys = self.keyframes;
+        let next = keys
+            .iter()
+            .position(|k| k.time > time)
+            .unwrap_or(keys.len());
+        let from = &keys[next.saturating_sub(1)];
+        let to = keys.get(next).unwrap_or(from);
+
+        let span = to.time - from.time;
+        let t = if span > 0.0 {
+            ((time - from.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        AnimationSample {
+            sprite: self.sprite,
+            frame: from.frame,
+            offset: from.offset.lerp(to.offset, t),
+            rotation: from.rotation + (to.rotation - from.rotation) * t,
+            scale: from.scale.lerp(to.scale, t),
+        }
+    }
+}
+
+/// Where a fighter's sprite is drawn this frame, relative to its position
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationSample {
+    pub sprite: SpriteAnimation,
+    pub frame: usize,
+    pub offset: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+/// Plays the clip matching a fighter's current state
+pub struct AnimationPlayer {
+    clip: &'static AnimationClip,
+    time: f32,
+    /// Playback speed, so an attack's clip can fit the attack's own timing
+    rate: f32,
+}
+
+impl Component for AnimationPlayer {}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        Self {
+            clip: &IDLE,
+            time: 0.0,
+            rate: 1.0,
+        }
+    }
+
+    /// Restart on the state's clip if it isn't the one playing; true if it switched
+    pub fn play_state(&mut self, state: FighterState) -> bool {
+        let clip = AnimationClip::for_state(state);
+        if std::ptr::eq(clip, self.clip) {
+            return false;
+        }
+
+        self.clip = clip;
+        self.time = 0.0;
+        self.rate = 1.0;
+        true
+    }
+
+    /// Start the state's clip over, sped up or slowed to last `duration` seconds
+    pub fn play_timed(&mut self, state: FighterState, duration: f32) {
+        self.clip = AnimationClip::for_state(state);
+        self.time = 0.0;
+        self.rate = if duration > 0.0 { self.clip.duration / duration } else { 1.0 };
+    }
+
+    /// Step the timeline, appending every event it crosses to `fired`
+    pub fn advance(&mut self, dt: f32, fired: &mut Vec<AnimationEvent>) {
+        let duration = self.clip.duration;
+        let start = self.time;
+        let end = start + dt * self.rate;
+
+        if !self.clip.looping && start >= duration {
+            return;
+        }
+
+        fired.extend(
+            self.clip
+                .events
+                .iter()
+                .filter(|(time, _)| *time >= start && *time < end)
+                .map(|(_, event)| *event),
+        );
+
+        self.time = if !self.clip.looping {
+            end.min(duration)
+        } else if duration > 0.0 {
+            end % duration
+        } else {
+            0.0
+        };
+    }
+
+    pub fn sample(&self) -> AnimationSample {
+        self.clip.sample(self.time)
+    }
+}
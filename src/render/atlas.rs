@@ -40,6 +40,7 @@ impl TextureAtlas {
 
     pub fn draw_region(&self, name: &str, x: f32, y: f32, color: Color) {
         if let Some(region) = self.get_region(name) {
+            crate::app::count_draw_call();
             draw_texture_ex(
                 &self.texture,
                 x,
@@ -53,3 +54,76 @@ impl TextureAtlas {
         }
     }
 }
+
+/// Sprites queued against a single atlas texture and drawn back to back, so
+/// macroquad keeps them in one draw call instead of breaking its batch every
+/// time the texture changes between characters.
+#[derive(Default)]
+pub struct SpriteBatch {
+    sprites: Vec<BatchedSprite>,
+}
+
+struct BatchedSprite {
+    source: Rect,
+    dest: Rect,
+    flip_x: bool,
+    color: Color,
+    rotation: f32,
+    pivot: Option<Vec2>,
+}
+
+impl SpriteBatch {
+    pub fn push(&mut self, source: Rect, dest: Rect, flip_x: bool, color: Color) {
+        self.sprites.push(BatchedSprite {
+            source,
+            dest,
+            flip_x,
+            color,
+            rotation: 0.0,
+            pivot: None,
+        });
+    }
+
+    /// Like `push`, rotated by `rotation` radians around `pivot` in screen space
+    pub fn push_rotated(
+        &mut self,
+        source: Rect,
+        dest: Rect,
+        flip_x: bool,
+        color: Color,
+        rotation: f32,
+        pivot: Vec2,
+    ) {
+        self.sprites.push(BatchedSprite {
+            source,
+            dest,
+            flip_x,
+            color,
+            rotation,
+            pivot: Some(pivot),
+        });
+    }
+
+    /// Draw everything queued so far from `texture` and empty the batch
+    pub fn flush(&mut self, texture: &Texture2D) {
+        if !self.sprites.is_empty() {
+            crate::app::count_draw_call();
+        }
+        for sprite in self.sprites.drain(..) {
+            draw_texture_ex(
+                texture,
+                sprite.dest.x,
+                sprite.dest.y,
+                sprite.color,
+                DrawTextureParams {
+                    dest_size: Some(sprite.dest.size()),
+                    source: Some(sprite.source),
+                    flip_x: sprite.flip_x,
+                    rotation: sprite.rotation,
+                    pivot: sprite.pivot,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
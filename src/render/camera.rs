@@ -1,16 +1,61 @@
+use crate::util::rng;
 use macroquad::prelude::*;
 
+/// Tightest the camera frames a fight; 1.0 shows the whole arena
+const MAX_ZOOM: f32 = 1.35;
+/// World-space margin kept around framed targets
+const FRAMING_PADDING: Vec2 = Vec2::new(260.0, 200.0);
+/// Trauma lost per second; shake strength is trauma squared
+const TRAUMA_DECAY: f32 = 1.5;
+const MAX_SHAKE_OFFSET: f32 = 16.0;
+/// Degrees, as macroquad's Camera2D takes them
+const MAX_SHAKE_ANGLE: f32 = 2.5;
+/// How fast a hit-stop zoom punch springs back once the freeze ends
+const PUNCH_RECOVERY: f32 = 1.2;
+/// Height of each cutscene bar as a fraction of the screen
+const LETTERBOX_HEIGHT: f32 = 0.11;
+const LETTERBOX_SPEED: f32 = 3.0;
+
+/// Who a focus request is for. When several are up the highest wins; the
+/// player's own moments come first, explosions last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FocusPriority {
+    Explosion,
+    SuperUser,
+    BossIntro,
+    /// The arena changing mid-wave
+    StageChange,
+    Player,
+}
+
+/// A request to hold the camera on one spot, overriding the fight framing
+#[derive(Clone, Copy, Debug)]
+pub struct FocusRequest {
+    pub priority: FocusPriority,
+    pub target: Vec2,
+    pub zoom: f32,
+    /// Seconds left; `None` holds until popped
+    pub remaining: Option<f32>,
+}
+
 pub struct GameCamera {
     pub position: Vec2,
     pub target: Vec2,
     pub zoom: f32,
-    pub rotation: f32,
-    pub virtual_width: f32,
-    pub virtual_height: f32,
-    pub shake_intensity: f32,
-    pub shake_duration: f32,
+    pub target_zoom: f32,
+    /// Catch-up rate towards the target, per second
     pub follow_speed: f32,
     pub bounds: Option<CameraBounds>,
+    /// Size of the screen the view is drawn to
+    viewport: Vec2,
+    trauma: f32,
+    shake_offset: Vec2,
+    shake_angle: f32,
+    hit_stop: f32,
+    punch_zoom: f32,
+    letterbox: f32,
+    letterbox_target: f32,
+    focus: Vec<FocusRequest>,
 }
 
 #[derive(Clone, Debug)]
@@ -20,86 +65,208 @@ pub struct CameraBounds {
 }
 
 impl GameCamera {
-    pub fn new(virtual_width: f32, virtual_height: f32) -> Self {
+    pub fn new(viewport: Vec2) -> Self {
         Self {
-            position: Vec2::ZERO,
-            target: Vec2::ZERO,
+            position: viewport * 0.5,
+            target: viewport * 0.5,
             zoom: 1.0,
-            rotation: 0.0,
-            virtual_width,
-            virtual_height,
-            shake_intensity: 0.0,
-            shake_duration: 0.0,
-            follow_speed: 0.1,
+            target_zoom: 1.0,
+            follow_speed: 5.0,
             bounds: None,
+            viewport,
+            trauma: 0.0,
+            shake_offset: Vec2::ZERO,
+            shake_angle: 0.0,
+            hit_stop: 0.0,
+            punch_zoom: 0.0,
+            letterbox: 0.0,
+            letterbox_target: 0.0,
+            focus: Vec::new(),
         }
     }
 
-    pub fn update(&mut self) {
-        let dt = get_frame_time();
+    /// Advances in real time, so it keeps running through its own hit-stop
+    pub fn update(&mut self, dt: f32) {
+        for request in &mut self.focus {
+            if let Some(remaining) = &mut request.remaining {
+                *remaining -= dt;
+            }
+        }
+        self.focus.retain(|request| request.remaining.is_none_or(|left| left > 0.0));
+        if let Some(focus) = self.focus().copied() {
+            self.target = focus.target;
+            self.target_zoom = focus.zoom;
+        }
 
-        self.position = self.position.lerp(self.target, self.follow_speed);
+        let blend = 1.0 - (-self.follow_speed * dt).exp();
+        self.position = self.position.lerp(self.target, blend);
+        self.zoom += (self.target_zoom - self.zoom) * blend;
 
-        if self.shake_duration > 0.0 {
-            self.shake_duration -= dt;
-            let shake_offset = Vec2::new(
-                rand::gen_range(-1.0, 1.0) * self.shake_intensity,
-                rand::gen_range(-1.0, 1.0) * self.shake_intensity,
-            );
-            self.position += shake_offset;
+        if self.hit_stop > 0.0 {
+            self.hit_stop = (self.hit_stop - dt).max(0.0);
+        } else {
+            self.punch_zoom = (self.punch_zoom - PUNCH_RECOVERY * dt).max(0.0);
         }
 
-        if let Some(bounds) = &self.bounds {
-            self.position.x = self.position.x.clamp(bounds.min.x, bounds.max.x);
-            self.position.y = self.position.y.clamp(bounds.min.y, bounds.max.y);
-        }
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        // Toned down, or off, from the accessibility settings
+        let shake = self.trauma * self.trauma * crate::data::get_screen_shake();
+        self.shake_offset = Vec2::new(rng::cosmetic(-1.0, 1.0), rng::cosmetic(-1.0, 1.0))
+            * MAX_SHAKE_OFFSET
+            * shake;
+        self.shake_angle = rng::cosmetic(-1.0, 1.0) * MAX_SHAKE_ANGLE * shake;
+
+        let step = LETTERBOX_SPEED * dt;
+        self.letterbox += (self.letterbox_target - self.letterbox).clamp(-step, step);
+
+        self.position = self.clamp_to_bounds(self.position);
     }
 
     pub fn follow(&mut self, target: Vec2) {
         self.target = target;
     }
 
-    pub fn shake(&mut self, intensity: f32, duration: f32) {
-        self.shake_intensity = intensity;
-        self.shake_duration = duration;
+    /// Follow the screen being resized
+    pub fn set_viewport(&mut self, viewport: Vec2) {
+        self.viewport = viewport;
+    }
+
+    /// Widest spread of targets `frame_targets` can still fit on screen
+    pub fn framing_span(&self) -> Vec2 {
+        self.viewport - FRAMING_PADDING * 2.0
+    }
+
+    /// Aim at the middle of `points`, zooming in as far as keeps them all on
+    /// screen. A focus request overrides this until it ends.
+    pub fn frame_targets(&mut self, points: &[Vec2]) {
+        let Some(&first) = points.first() else {
+            return;
+        };
+
+        let (min, max) = points
+            .iter()
+            .fold((first, first), |(min, max), &p| (min.min(p), max.max(p)));
+        let span = max - min + FRAMING_PADDING * 2.0;
+        let zoom = (self.viewport.x / span.x).min(self.viewport.y / span.y);
+
+        self.target_zoom = zoom.clamp(1.0, MAX_ZOOM);
+        self.target = (min + max) * 0.5;
+    }
+
+    /// Hold the camera on `target` until popped, or for `duration` seconds.
+    /// Replaces any earlier request of the same priority.
+    pub fn push_focus(
+        &mut self,
+        priority: FocusPriority,
+        target: Vec2,
+        zoom: f32,
+        duration: Option<f32>,
+    ) {
+        self.pop_focus(priority);
+        self.focus.push(FocusRequest {
+            priority,
+            target,
+            zoom: zoom.clamp(1.0, MAX_ZOOM),
+            remaining: duration,
+        });
+    }
+
+    pub fn pop_focus(&mut self, priority: FocusPriority) {
+        self.focus.retain(|request| request.priority != priority);
+    }
+
+    /// The request in charge of the camera, if any
+    pub fn focus(&self) -> Option<&FocusRequest> {
+        self.focus.iter().max_by_key(|request| request.priority)
+    }
+
+    /// Screen shake; trauma stacks up to 1.0 and bleeds off over time
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    /// Freeze gameplay for `duration` seconds while punching the zoom in by `zoom`
+    pub fn hit_stop(&mut self, duration: f32, zoom: f32) {
+        self.hit_stop = self.hit_stop.max(duration);
+        self.punch_zoom = self.punch_zoom.max(zoom);
+    }
+
+    pub fn is_hit_stopped(&self) -> bool {
+        self.hit_stop > 0.0
+    }
+
+    pub fn set_letterbox(&mut self, enabled: bool) {
+        self.letterbox_target = if enabled { 1.0 } else { 0.0 };
     }
 
     pub fn set_bounds(&mut self, min: Vec2, max: Vec2) {
         self.bounds = Some(CameraBounds { min, max });
     }
 
-    pub fn apply_transform(&self) {
-        set_default_camera();
+    fn effective_zoom(&self) -> f32 {
+        self.zoom * (1.0 + self.punch_zoom)
+    }
+
+    fn view_size(&self) -> Vec2 {
+        self.viewport / self.effective_zoom()
+    }
+
+    /// Keep the view inside the bounds, centring on any axis the view outgrows
+    fn clamp_to_bounds(&self, position: Vec2) -> Vec2 {
+        let Some(bounds) = &self.bounds else {
+            return position;
+        };
+
+        let half = self.view_size() * 0.5;
+        let clamp_axis = |value: f32, min: f32, max: f32, half: f32| {
+            if max - min <= half * 2.0 {
+                (min + max) * 0.5
+            } else {
+                value.clamp(min + half, max - half)
+            }
+        };
+
+        Vec2::new(
+            clamp_axis(position.x, bounds.min.x, bounds.max.x, half.x),
+            clamp_axis(position.y, bounds.min.y, bounds.max.y, half.y),
+        )
+    }
+
+    /// Draw the world through the camera until `reset_transform`, into
+    /// `render_target` if there is one (e.g. for post-processing)
+    pub fn apply_transform(&self, render_target: Option<RenderTarget>) {
+        let view = self.view_size();
+        set_camera(&Camera2D {
+            target: self.position + self.shake_offset,
+            zoom: Vec2::new(2.0 / view.x, 2.0 / view.y),
+            rotation: self.shake_angle,
+            render_target,
+            ..Default::default()
+        });
     }
 
     pub fn reset_transform(&self) {
         set_default_camera();
     }
 
-    pub fn world_to_screen(&self, world_pos: Vec2) -> Vec2 {
-        let screen_width = screen_width();
-        let screen_height = screen_height();
-        let scale_x = screen_width / self.virtual_width;
-        let scale_y = screen_height / self.virtual_height;
-        let scale = scale_x.min(scale_y);
+    /// Cutscene bars, drawn in screen space after `reset_transform`
+    pub fn render_letterbox(&self) {
+        if self.letterbox <= 0.0 {
+            return;
+        }
 
-        let relative_pos = world_pos - self.position;
-        let screen_x = relative_pos.x * scale + screen_width * 0.5;
-        let screen_y = relative_pos.y * scale + screen_height * 0.5;
+        let height = screen_height() * LETTERBOX_HEIGHT * self.letterbox;
+        draw_rectangle(0.0, 0.0, screen_width(), height, BLACK);
+        draw_rectangle(0.0, screen_height() - height, screen_width(), height, BLACK);
+    }
 
-        Vec2::new(screen_x, screen_y)
+    pub fn world_to_screen(&self, world_pos: Vec2) -> Vec2 {
+        let relative = world_pos - self.position;
+        relative * self.effective_zoom() + self.viewport * 0.5
     }
 
     pub fn screen_to_world(&self, screen_pos: Vec2) -> Vec2 {
-        let screen_width = screen_width();
-        let screen_height = screen_height();
-        let scale_x = screen_width / self.virtual_width;
-        let scale_y = screen_height / self.virtual_height;
-        let scale = scale_x.min(scale_y);
-
-        let world_x = (screen_pos.x - screen_width * 0.5) / scale + self.position.x;
-        let world_y = (screen_pos.y - screen_height * 0.5) / scale + self.position.y;
-
-        Vec2::new(world_x, world_y)
+        let relative = screen_pos - self.viewport * 0.5;
+        relative / self.effective_zoom() + self.position
     }
 }
@@ -473,6 +473,7 @@ impl CharacterRenderer {
 
     fn render_low_quality(&mut self, model: &CharacterModel, position: Vec2) {
         // Simple sprite rendering
+        crate::app::count_draw_call();
         draw_texture(
             &model.base_sprite.texture,
             position.x,
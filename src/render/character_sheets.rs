@@ -0,0 +1,764 @@
+use crate::data::cosmetics::Outfit;
+use crate::data::poses::PoseMotion;
+use crate::data::CharacterId;
+use crate::render::animation::AnimationSample;
+use crate::render::atlas::{SpriteBatch, TextureAtlas};
+use ahash::AHashMap;
+use macroquad::prelude::*;
+
+/// One baked frame in atlas pixels; frames are drawn at `SPRITE_SCALE`x on screen
+const FRAME_WIDTH: u32 = 64;
+const FRAME_HEIGHT: u32 = 96;
+const SPRITE_SCALE: f32 = 2.0;
+/// Where the fighter's transform position sits inside a frame, in world units
+const PIVOT: Vec2 = Vec2::new(64.0, 104.0);
+/// Centre of a standing head from the pivot, facing right
+const HEAD: Vec2 = Vec2::new(2.0, -46.0);
+const FRAMES_PER_SHEET: usize = 16;
+
+const OUTLINE: Color = Color::new(0.08, 0.06, 0.08, 1.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpriteAnimation {
+    Idle,
+    Walk,
+    Attack,
+    Hurt,
+    Block,
+    Down,
+    Cheer,
+    Taunt,
+}
+
+impl SpriteAnimation {
+    /// First frame, frame count and playback rate in frames per second.
+    /// Attacks are driven by the caller's attack progress instead of the clock.
+    fn clip(self) -> (usize, usize, f32) {
+        match self {
+            SpriteAnimation::Idle => (0, 2, 2.0),
+            SpriteAnimation::Walk => (2, 4, 10.0),
+            SpriteAnimation::Attack => (6, 3, 0.0),
+            SpriteAnimation::Hurt => (9, 1, 0.0),
+            SpriteAnimation::Block => (10, 1, 0.0),
+            SpriteAnimation::Down => (11, 1, 0.0),
+            SpriteAnimation::Cheer => (12, 2, 4.0),
+            SpriteAnimation::Taunt => (14, 2, 3.0),
+        }
+    }
+
+    /// Clip an intro or victory pose plays; `Strike` runs the attack frames off
+    /// the pose's progress
+    pub fn for_pose(motion: PoseMotion) -> Self {
+        match motion {
+            PoseMotion::Taunt => SpriteAnimation::Taunt,
+            PoseMotion::Cheer => SpriteAnimation::Cheer,
+            PoseMotion::Guard => SpriteAnimation::Block,
+            PoseMotion::Strike => SpriteAnimation::Attack,
+        }
+    }
+
+    fn frame(self, time: f32, progress: f32) -> usize {
+        let (first, count, fps) = self.clip();
+        let offset = if fps > 0.0 {
+            (time * fps) as usize % count
+        } else {
+            ((progress.clamp(0.0, 1.0) * count as f32) as usize).min(count - 1)
+        };
+        first + offset
+    }
+
+    /// Frame `index` of the clip, as stepped by a keyframed `AnimationClip`
+    fn keyed_frame(self, index: usize) -> usize {
+        let (first, count, _) = self.clip();
+        first + index.min(count - 1)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum HairStyle {
+    Short,
+    Long,
+    Bald,
+    Cap,
+    Helmet,
+    Toque,
+}
+
+/// Palette and build a sheet is painted from
+#[derive(Clone, Copy)]
+struct Look {
+    skin: Color,
+    hair: Color,
+    shirt: Color,
+    accent: Color,
+    pants: Color,
+    shoes: Color,
+    hair_style: HairStyle,
+    build: f32,
+}
+
+/// Body offsets for one frame, in world units for a fighter facing right
+#[derive(Clone, Copy)]
+struct Pose {
+    bob: f32,
+    stride: f32,
+    reach: f32,
+    recoil: f32,
+    guard: bool,
+    arms_up: bool,
+    lying: bool,
+}
+
+const STAND: Pose = Pose {
+    bob: 0.0,
+    stride: 0.0,
+    reach: 0.0,
+    recoil: 0.0,
+    guard: false,
+    arms_up: false,
+    lying: false,
+};
+
+const POSES: [Pose; FRAMES_PER_SHEET] = [
+    // Idle
+    STAND,
+    Pose { bob: 2.0, ..STAND },
+    // Walk
+    Pose { bob: -2.0, stride: 10.0, ..STAND },
+    Pose { bob: 1.0, ..STAND },
+    Pose { bob: -2.0, stride: -10.0, ..STAND },
+    Pose { bob: 1.0, ..STAND },
+    // Attack: wind-up, contact, recovery
+    Pose { recoil: 4.0, stride: -4.0, ..STAND },
+    Pose { reach: 1.0, recoil: -6.0, stride: 8.0, ..STAND },
+    Pose { reach: 0.5, recoil: -2.0, stride: 4.0, ..STAND },
+    // Hurt
+    Pose { bob: 3.0, recoil: 8.0, ..STAND },
+    // Block
+    Pose { bob: 3.0, guard: true, ..STAND },
+    // Down
+    Pose { lying: true, ..STAND },
+    // Cheer: both fists up, hopping
+    Pose { arms_up: true, ..STAND },
+    Pose { bob: -6.0, arms_up: true, ..STAND },
+    // Taunt: leaning back, beckoning with the front hand
+    Pose { reach: 0.3, recoil: 3.0, ..STAND },
+    Pose { bob: 1.0, reach: 0.55, recoil: 4.0, ..STAND },
+];
+
+const SKIN_LIGHT: Color = Color::new(1.0, 0.85, 0.7, 1.0);
+const SKIN_BROWN: Color = Color::new(0.65, 0.45, 0.3, 1.0);
+const HAIR_BLACK: Color = Color::new(0.1, 0.1, 0.1, 1.0);
+const HAIR_BROWN: Color = Color::new(0.3, 0.2, 0.1, 1.0);
+const WHITE_CLOTH: Color = Color::new(0.95, 0.95, 0.95, 1.0);
+const JEANS: Color = Color::new(0.35, 0.35, 0.45, 1.0);
+const SNEAKERS: Color = Color::new(0.25, 0.25, 0.25, 1.0);
+
+const STUDENT: Look = Look {
+    skin: SKIN_LIGHT,
+    hair: HAIR_BLACK,
+    shirt: WHITE_CLOTH,
+    accent: WHITE_CLOTH,
+    pants: JEANS,
+    shoes: SNEAKERS,
+    hair_style: HairStyle::Short,
+    build: 1.0,
+};
+
+const PREFECT: Look = Look {
+    skin: Color::new(0.9, 0.8, 0.7, 1.0),
+    hair: Color::new(0.15, 0.15, 0.4, 1.0),
+    shirt: Color::new(0.2, 0.2, 0.5, 1.0),
+    accent: Color::new(1.0, 0.85, 0.1, 1.0),
+    pants: Color::new(0.15, 0.15, 0.3, 1.0),
+    shoes: Color::new(0.1, 0.1, 0.1, 1.0),
+    hair_style: HairStyle::Cap,
+    build: 1.0,
+};
+
+const KEIZER: Look = Look {
+    skin: Color::new(0.85, 0.75, 0.65, 1.0),
+    hair: Color::new(0.25, 0.35, 0.2, 1.0),
+    shirt: Color::new(0.2, 0.4, 0.2, 1.0),
+    accent: Color::new(0.9, 0.75, 0.2, 1.0),
+    pants: Color::new(0.25, 0.3, 0.2, 1.0),
+    shoes: Color::new(0.1, 0.1, 0.1, 1.0),
+    hair_style: HairStyle::Helmet,
+    build: 1.2,
+};
+
+/// Every sheet baked into the atlas, keyed by the name gameplay asks for
+const SHEETS: &[(&str, Look)] = &[
+    (
+        "bas",
+        Look {
+            skin: Color::new(0.85, 0.65, 0.45, 1.0),
+            hair: HAIR_BROWN,
+            shirt: Color::new(0.2, 0.35, 0.85, 1.0),
+            build: 1.15,
+            ..STUDENT
+        },
+    ),
+    (
+        "berkay",
+        Look {
+            shirt: Color::new(0.85, 0.4, 0.0, 1.0),
+            accent: Color::new(1.0, 0.8, 0.3, 1.0),
+            ..STUDENT
+        },
+    ),
+    (
+        "gefferinho",
+        Look {
+            skin: SKIN_BROWN,
+            shirt: Color::new(0.8, 0.2, 0.2, 1.0),
+            ..STUDENT
+        },
+    ),
+    (
+        "hadi",
+        Look {
+            skin: SKIN_BROWN,
+            shirt: Color::new(0.9, 0.7, 0.0, 1.0),
+            accent: Color::new(0.6, 0.4, 0.0, 1.0),
+            ..STUDENT
+        },
+    ),
+    (
+        "nitin",
+        Look {
+            skin: SKIN_BROWN,
+            shirt: Color::new(0.9, 0.3, 0.0, 1.0),
+            accent: Color::new(1.0, 0.8, 0.0, 1.0),
+            hair_style: HairStyle::Long,
+            ..STUDENT
+        },
+    ),
+    (
+        "luca",
+        Look {
+            hair: HAIR_BROWN,
+            shirt: Color::new(0.3, 0.6, 1.0, 1.0),
+            ..STUDENT
+        },
+    ),
+    (
+        "palababa",
+        Look {
+            skin: Color::new(1.0, 0.9, 0.8, 1.0),
+            hair: Color::new(0.75, 0.75, 0.75, 1.0),
+            shirt: Color::new(0.7, 0.0, 0.15, 1.0),
+            accent: Color::new(0.9, 0.75, 0.2, 1.0),
+            hair_style: HairStyle::Bald,
+            build: 1.05,
+            ..STUDENT
+        },
+    ),
+    (
+        "fufinho",
+        Look {
+            skin: Color::new(0.45, 0.3, 0.2, 1.0),
+            shirt: Color::new(0.6, 0.0, 0.6, 1.0),
+            accent: Color::new(0.9, 0.0, 0.9, 1.0),
+            build: 1.05,
+            ..STUDENT
+        },
+    ),
+    (
+        "efeabi",
+        Look {
+            skin: Color::new(0.9, 0.75, 0.6, 1.0),
+            shirt: Color::new(0.7, 0.3, 0.1, 1.0),
+            accent: Color::new(1.0, 0.9, 0.6, 1.0),
+            build: 1.1,
+            ..STUDENT
+        },
+    ),
+    (
+        "jad",
+        Look {
+            hair: Color::new(0.8, 0.0, 0.0, 1.0),
+            shirt: Color::new(0.8, 0.0, 0.0, 1.0),
+            hair_style: HairStyle::Cap,
+            build: 1.2,
+            ..STUDENT
+        },
+    ),
+    (
+        "umut",
+        Look {
+            skin: Color::new(0.95, 0.8, 0.65, 1.0),
+            hair: HAIR_BROWN,
+            shirt: Color::new(0.4, 0.2, 0.6, 1.0),
+            accent: Color::new(0.6, 0.9, 0.3, 1.0),
+            build: 1.1,
+            ..STUDENT
+        },
+    ),
+    ("keizer", KEIZER),
+    (
+        "tobese",
+        Look {
+            skin: Color::new(1.0, 0.95, 0.9, 1.0),
+            hair: Color::new(0.9, 0.85, 0.6, 1.0),
+            accent: Color::new(0.6, 0.8, 1.0, 1.0),
+            ..STUDENT
+        },
+    ),
+    (
+        "wolters",
+        Look {
+            skin: Color::new(0.9, 0.7, 0.6, 1.0),
+            hair: Color::new(0.55, 0.55, 0.55, 1.0),
+            shirt: Color::new(0.4, 0.4, 0.4, 1.0),
+            accent: Color::new(0.8, 0.1, 0.1, 1.0),
+            pants: Color::new(0.2, 0.2, 0.3, 1.0),
+            shoes: Color::new(0.1, 0.1, 0.1, 1.0),
+            ..STUDENT
+        },
+    ),
+    ("prefect_a", PREFECT),
+    (
+        "prefect_b",
+        Look {
+            skin: Color::new(0.75, 0.55, 0.4, 1.0),
+            hair: HAIR_BLACK,
+            hair_style: HairStyle::Short,
+            ..PREFECT
+        },
+    ),
+    (
+        "chef",
+        Look {
+            skin: Color::new(0.95, 0.8, 0.7, 1.0),
+            hair: Color::new(0.97, 0.97, 0.97, 1.0),
+            accent: Color::new(0.8, 0.15, 0.1, 1.0),
+            pants: Color::new(0.3, 0.3, 0.3, 1.0),
+            shoes: Color::new(0.1, 0.1, 0.1, 1.0),
+            hair_style: HairStyle::Toque,
+            build: 1.2,
+            ..STUDENT
+        },
+    ),
+    (
+        "librarian",
+        Look {
+            skin: Color::new(0.95, 0.85, 0.75, 1.0),
+            hair: Color::new(0.7, 0.7, 0.72, 1.0),
+            shirt: Color::new(0.45, 0.3, 0.45, 1.0),
+            accent: Color::new(0.9, 0.9, 0.85, 1.0),
+            pants: Color::new(0.3, 0.25, 0.3, 1.0),
+            hair_style: HairStyle::Long,
+            build: 0.95,
+            ..STUDENT
+        },
+    ),
+    (
+        "coach",
+        Look {
+            skin: Color::new(0.9, 0.7, 0.55, 1.0),
+            hair: Color::new(0.1, 0.3, 0.7, 1.0),
+            shirt: Color::new(0.15, 0.35, 0.8, 1.0),
+            pants: Color::new(0.15, 0.35, 0.8, 1.0),
+            hair_style: HairStyle::Cap,
+            build: 1.15,
+            ..STUDENT
+        },
+    ),
+    (
+        "bastiaan",
+        Look {
+            skin: Color::new(0.92, 0.78, 0.65, 1.0),
+            hair: Color::new(0.25, 0.2, 0.15, 1.0),
+            shirt: Color::new(0.15, 0.15, 0.18, 1.0),
+            accent: Color::new(0.6, 0.1, 0.8, 1.0),
+            pants: Color::new(0.12, 0.12, 0.15, 1.0),
+            shoes: Color::new(0.05, 0.05, 0.05, 1.0),
+            build: 1.3,
+            ..STUDENT
+        },
+    ),
+    (
+        "keizer_boss",
+        Look {
+            hair: Color::new(0.2, 0.2, 0.2, 1.0),
+            shirt: Color::new(0.3, 0.15, 0.15, 1.0),
+            accent: Color::new(0.9, 0.1, 0.1, 1.0),
+            build: 1.35,
+            ..KEIZER
+        },
+    ),
+    (
+        "mees",
+        Look {
+            skin: Color::new(0.95, 0.85, 0.75, 1.0),
+            hair: Color::new(1.0, 0.95, 0.65, 1.0),
+            shirt: Color::new(1.0, 0.3, 0.1, 1.0),
+            accent: Color::new(1.0, 0.8, 0.2, 1.0),
+            build: 1.15,
+            ..STUDENT
+        },
+    ),
+];
+
+/// Per-character sprite sheets baked once into a single atlas texture.
+/// Each sheet is a row of `FRAMES_PER_SHEET` frames laid out by `SpriteAnimation::clip`.
+pub struct CharacterSheets {
+    atlas: TextureAtlas,
+    rows: AHashMap<&'static str, usize>,
+}
+
+impl CharacterSheets {
+    pub fn new() -> Self {
+        let width = FRAME_WIDTH * FRAMES_PER_SHEET as u32;
+        let height = FRAME_HEIGHT * SHEETS.len() as u32;
+        let mut image = Image::gen_image_color(width as u16, height as u16, BLANK);
+
+        for (row, (_, look)) in SHEETS.iter().enumerate() {
+            for (frame, pose) in POSES.iter().enumerate() {
+                let mut canvas = FrameCanvas {
+                    image: &mut image,
+                    origin: (frame as u32 * FRAME_WIDTH, row as u32 * FRAME_HEIGHT),
+                };
+                paint_frame(&mut canvas, look, pose);
+                canvas.outline();
+            }
+        }
+
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+
+        let mut atlas = TextureAtlas::new(texture);
+        let mut rows = AHashMap::new();
+        for (row, (name, _)) in SHEETS.iter().enumerate() {
+            for frame in 0..FRAMES_PER_SHEET {
+                atlas.add_region(
+                    format!("{}_{}", name, frame),
+                    (frame as u32 * FRAME_WIDTH) as f32,
+                    (row as u32 * FRAME_HEIGHT) as f32,
+                    FRAME_WIDTH as f32,
+                    FRAME_HEIGHT as f32,
+                );
+            }
+            rows.insert(*name, row);
+        }
+
+        Self { atlas, rows }
+    }
+
+    /// Sheet name for a playable character
+    pub fn sheet_for(id: CharacterId) -> &'static str {
+        match id {
+            CharacterId::Bas => "bas",
+            CharacterId::Berkay => "berkay",
+            CharacterId::Gefferinho => "gefferinho",
+            CharacterId::Hadi => "hadi",
+            CharacterId::Nitin => "nitin",
+            CharacterId::Luca => "luca",
+            CharacterId::PalaBaba => "palababa",
+            CharacterId::Fufinho => "fufinho",
+            CharacterId::EfeAbi => "efeabi",
+            CharacterId::Jad => "jad",
+            CharacterId::Umut => "umut",
+            CharacterId::KeizerBomTaha => "keizer",
+            CharacterId::GoonLordTobese => "tobese",
+        }
+    }
+
+    /// Queue one frame of `sheet` with its pivot at `pos`. `progress` (0..1)
+    /// picks the attack frame; looping clips run off `time`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn queue(
+        &self,
+        batch: &mut SpriteBatch,
+        sheet: &str,
+        animation: SpriteAnimation,
+        time: f32,
+        progress: f32,
+        pos: Vec2,
+        flip_x: bool,
+        tint: Color,
+    ) {
+        let Some(&row) = self.rows.get(sheet) else {
+            return;
+        };
+
+        let source = frame_source(row, animation.frame(time, progress));
+        let size = Vec2::new(FRAME_WIDTH as f32, FRAME_HEIGHT as f32) * SPRITE_SCALE;
+        let dest = Rect::new(pos.x - PIVOT.x, pos.y - PIVOT.y, size.x, size.y);
+
+        batch.push(source, dest, flip_x, tint);
+    }
+
+    /// Queue a keyframed pose of `sheet`. The sample is authored facing right,
+    /// so its offset and rotation are mirrored for `flip_x`.
+    pub fn queue_sample(
+        &self,
+        batch: &mut SpriteBatch,
+        sheet: &str,
+        sample: &AnimationSample,
+        pos: Vec2,
+        flip_x: bool,
+        tint: Color,
+    ) {
+        let Some(&row) = self.rows.get(sheet) else {
+            return;
+        };
+
+        let facing = if flip_x { -1.0 } else { 1.0 };
+        let anchor = pos + Vec2::new(sample.offset.x * facing, sample.offset.y);
+        let source = frame_source(row, sample.sprite.keyed_frame(sample.frame));
+        let size = Vec2::new(FRAME_WIDTH as f32, FRAME_HEIGHT as f32) * SPRITE_SCALE * sample.scale;
+        let pivot = PIVOT * sample.scale;
+        let dest = Rect::new(anchor.x - pivot.x, anchor.y - pivot.y, size.x, size.y);
+
+        batch.push_rotated(source, dest, flip_x, tint, sample.rotation * facing, anchor);
+    }
+
+    /// Draw everything queued against the atlas in one go
+    pub fn flush(&self, batch: &mut SpriteBatch) {
+        batch.flush(&self.atlas.texture);
+    }
+}
+
+fn frame_source(row: usize, frame: usize) -> Rect {
+    Rect::new(
+        (frame as u32 * FRAME_WIDTH) as f32,
+        (row as u32 * FRAME_HEIGHT) as f32,
+        FRAME_WIDTH as f32,
+        FRAME_HEIGHT as f32,
+    )
+}
+
+/// Paints into one frame cell of the atlas, taking world-unit coordinates
+/// relative to the pivot
+struct FrameCanvas<'a> {
+    image: &'a mut Image,
+    origin: (u32, u32),
+}
+
+impl FrameCanvas<'_> {
+    fn to_pixel(x: f32, y: f32) -> (f32, f32) {
+        ((x + PIVOT.x) / SPRITE_SCALE, (y + PIVOT.y) / SPRITE_SCALE)
+    }
+
+    fn in_cell(px: i32, py: i32) -> bool {
+        px >= 0 && py >= 0 && px < FRAME_WIDTH as i32 && py < FRAME_HEIGHT as i32
+    }
+
+    fn get(&self, px: i32, py: i32) -> Color {
+        self.image.get_pixel(self.origin.0 + px as u32, self.origin.1 + py as u32)
+    }
+
+    fn put(&mut self, px: i32, py: i32, color: Color) {
+        if Self::in_cell(px, py) {
+            self.image.set_pixel(self.origin.0 + px as u32, self.origin.1 + py as u32, color);
+        }
+    }
+
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let (x0, y0) = Self::to_pixel(x, y);
+        let (x1, y1) = Self::to_pixel(x + w, y + h);
+        for py in y0.round() as i32..y1.round() as i32 {
+            for px in x0.round() as i32..x1.round() as i32 {
+                self.put(px, py, color);
+            }
+        }
+    }
+
+    fn circle(&mut self, cx: f32, cy: f32, r: f32, color: Color) {
+        self.circle_above(cx, cy, r, f32::MAX, color);
+    }
+
+    /// Circle clipped to the part above `max_y` (hair, caps, helmets)
+    fn circle_above(&mut self, cx: f32, cy: f32, r: f32, max_y: f32, color: Color) {
+        let (pcx, pcy) = Self::to_pixel(cx, cy);
+        let (_, clip) = Self::to_pixel(0.0, max_y);
+        let pr = r / SPRITE_SCALE;
+
+        for py in (pcy - pr).floor() as i32..=(pcy + pr).ceil() as i32 {
+            if py as f32 + 0.5 > clip {
+                break;
+            }
+            for px in (pcx - pr).floor() as i32..=(pcx + pr).ceil() as i32 {
+                let dx = px as f32 + 0.5 - pcx;
+                let dy = py as f32 + 0.5 - pcy;
+                if dx * dx + dy * dy <= pr * pr {
+                    self.put(px, py, color);
+                }
+            }
+        }
+    }
+
+    /// Ring every painted shape with a one-pixel dark edge
+    fn outline(&mut self) {
+        let mut edge = Vec::new();
+        for py in 0..FRAME_HEIGHT as i32 {
+            for px in 0..FRAME_WIDTH as i32 {
+                if self.get(px, py).a > 0.0 {
+                    continue;
+                }
+                let touches = [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                    Self::in_cell(px + dx, py + dy) && self.get(px + dx, py + dy).a > 0.0
+                });
+                if touches {
+                    edge.push((px, py));
+                }
+            }
+        }
+        for (px, py) in edge {
+            self.put(px, py, OUTLINE);
+        }
+    }
+}
+
+fn shade(color: Color, factor: f32) -> Color {
+    Color::new(
+        (color.r * factor).min(1.0),
+        (color.g * factor).min(1.0),
+        (color.b * factor).min(1.0),
+        color.a,
+    )
+}
+
+fn paint_frame(canvas: &mut FrameCanvas, look: &Look, pose: &Pose) {
+    if pose.lying {
+        paint_lying(canvas, look);
+        return;
+    }
+
+    let b = look.build;
+    let y = pose.bob;
+    let lean = -pose.recoil;
+
+    // Legs: thighs stay under the hips, shins and shoes swing with the stride
+    for (hip, stride) in [(-14.0 * b, -pose.stride), (2.0 * b, pose.stride)] {
+        let leg_w = 12.0 * b;
+        canvas.rect(hip, 35.0, leg_w, 22.0, look.pants);
+        canvas.rect(hip + stride * 0.5, 55.0, leg_w, 20.0, shade(look.pants, 0.9));
+        canvas.rect(hip + stride * 0.5 - 1.0, 75.0, leg_w + 6.0, 10.0, look.shoes);
+    }
+
+    // Back arm sits behind the torso
+    if pose.arms_up {
+        canvas.rect(-26.0 * b + lean, -62.0 + y, 9.0, 44.0, shade(look.shirt, 0.75));
+        canvas.circle(-21.5 * b + lean, -64.0 + y, 6.0, shade(look.skin, 0.85));
+    } else {
+        canvas.rect(-26.0 * b + lean, -18.0 + y, 9.0, 28.0, shade(look.shirt, 0.75));
+        canvas.circle(-21.5 * b + lean, 13.0 + y, 5.0, shade(look.skin, 0.85));
+    }
+
+    // Torso with a shaded back edge, collar/tie accent and belt
+    canvas.rect(-20.0 * b + lean, -22.0 + y, 40.0 * b, 58.0, look.shirt);
+    canvas.rect(-20.0 * b + lean, -22.0 + y, 7.0, 58.0, shade(look.shirt, 0.8));
+    canvas.rect(-4.0 + lean, -22.0 + y, 8.0, 24.0, look.accent);
+    canvas.rect(-20.0 * b + lean, 32.0 + y, 40.0 * b, 4.0, shade(look.pants, 0.7));
+
+    paint_head(canvas, look, 2.0 + lean * 1.5, -46.0 + y);
+
+    // Front arm: raised, guard in front of the face, punch forward, or hanging
+    if pose.arms_up {
+        canvas.rect(12.0 * b + lean, -62.0 + y, 9.0, 44.0, look.shirt);
+        canvas.circle(16.5 * b + lean, -64.0 + y, 6.0, look.skin);
+    } else if pose.guard {
+        canvas.rect(10.0 * b + lean, -18.0 + y, 9.0, 16.0, look.shirt);
+        canvas.rect(18.0 * b + lean, -52.0 + y, 9.0, 36.0, shade(look.shirt, 1.1));
+        canvas.circle(22.5 * b + lean, -54.0 + y, 6.0, look.skin);
+    } else if pose.reach > 0.0 {
+        let length = 14.0 + 34.0 * pose.reach;
+        canvas.rect(12.0 * b + lean, -16.0 + y, length, 9.0, look.shirt);
+        let fist_x = 12.0 * b + lean + length + 4.0;
+        canvas.circle(fist_x, -11.5 + y, 6.0 + pose.reach, look.skin);
+    } else {
+        canvas.rect(12.0 * b + lean, -18.0 + y, 9.0, 28.0, look.shirt);
+        canvas.circle(16.5 * b + lean, 13.0 + y, 5.0, look.skin);
+    }
+}
+
+fn paint_head(canvas: &mut FrameCanvas, look: &Look, hx: f32, hy: f32) {
+    // Long hair falls behind the head
+    if matches!(look.hair_style, HairStyle::Long) {
+        canvas.rect(hx - 24.0, hy - 10.0, 14.0, 36.0, look.hair);
+    }
+
+    canvas.circle(hx, hy, 22.0, look.skin);
+    canvas.rect(hx + 9.0, hy - 5.0, 4.0, 6.0, OUTLINE);
+    canvas.rect(hx + 8.0, hy + 9.0, 8.0, 2.0, shade(look.skin, 0.6));
+
+    match look.hair_style {
+        HairStyle::Short | HairStyle::Long => {
+            canvas.circle_above(hx - 2.0, hy - 4.0, 23.0, hy - 8.0, look.hair);
+            canvas.rect(hx - 23.0, hy - 10.0, 8.0, 14.0, look.hair);
+        }
+        HairStyle::Bald => {
+            canvas.circle(hx - 7.0, hy - 12.0, 5.0, shade(look.skin, 1.15));
+            canvas.rect(hx - 22.0, hy - 4.0, 6.0, 10.0, look.hair);
+        }
+        HairStyle::Cap => {
+            canvas.circle_above(hx, hy - 6.0, 23.0, hy - 10.0, look.hair);
+            canvas.rect(hx + 8.0, hy - 13.0, 20.0, 5.0, shade(look.hair, 0.8));
+        }
+        HairStyle::Helmet => {
+            canvas.circle_above(hx, hy - 2.0, 25.0, hy - 6.0, look.hair);
+            canvas.rect(hx - 25.0, hy - 8.0, 50.0, 5.0, shade(look.hair, 0.7));
+        }
+        HairStyle::Toque => {
+            canvas.rect(hx - 14.0, hy - 40.0, 28.0, 24.0, look.hair);
+            canvas.circle(hx, hy - 38.0, 15.0, look.hair);
+            canvas.rect(hx - 20.0, hy - 20.0, 40.0, 8.0, shade(look.hair, 0.9));
+        }
+    }
+}
+
+/// Knocked-down frame: flat on the back with the head behind the fighter
+fn paint_lying(canvas: &mut FrameCanvas, look: &Look) {
+    canvas.rect(18.0, 66.0, 38.0, 12.0, look.pants);
+    canvas.rect(54.0, 60.0, 10.0, 18.0, look.shoes);
+    canvas.rect(-30.0, 58.0, 48.0, 22.0, look.shirt);
+    canvas.rect(-10.0, 58.0, 6.0, 22.0, look.accent);
+    canvas.rect(-24.0, 52.0, 30.0, 8.0, shade(look.shirt, 0.8));
+
+    if !matches!(look.hair_style, HairStyle::Bald) {
+        canvas.circle(-56.0, 68.0, 14.0, look.hair);
+    }
+    canvas.circle(-46.0, 68.0, 16.0, look.skin);
+    canvas.rect(-48.0, 56.0, 4.0, 4.0, OUTLINE);
+}
+
+/// Draw a cosmetic outfit on a fighter whose pivot is at `pos`. Outfits
+/// aren't baked into the sheets, so they sit on the standing head and
+/// don't follow each frame's bob.
+pub fn draw_outfit(outfit: Outfit, color: Color, pos: Vec2, flip_x: bool) {
+    let facing = if flip_x { -1.0 } else { 1.0 };
+    let head = pos + Vec2::new(HEAD.x * facing, HEAD.y);
+    let trim = shade(color, 0.75);
+    match outfit {
+        Outfit::Beanie => {
+            draw_circle(head.x, head.y - 12.0, 21.0, color);
+            draw_rectangle(head.x - 23.0, head.y - 14.0, 46.0, 8.0, trim);
+            draw_circle(head.x, head.y - 34.0, 6.0, WHITE);
+        }
+        Outfit::Shades => {
+            let lens = Vec2::new(head.x + 11.0 * facing, head.y - 3.0);
+            draw_rectangle(lens.x - 7.0, lens.y - 4.0, 14.0, 8.0, color);
+            let temple = head.x - 16.0 * facing;
+            draw_line(lens.x - 7.0 * facing, lens.y - 2.0, temple, lens.y - 2.0, 2.0, color);
+            draw_line(lens.x - 4.0, lens.y - 2.0, lens.x - 1.0, lens.y - 2.0, 1.5, WHITE);
+        }
+        Outfit::Scarf => {
+            draw_rectangle(pos.x - 18.0, head.y + 18.0, 36.0, 8.0, color);
+            let tail = pos.x - 14.0 * facing - 4.0;
+            draw_rectangle(tail, head.y + 22.0, 8.0, 24.0, trim);
+        }
+        Outfit::Crown => {
+            let base = head.y - 24.0;
+            draw_rectangle(head.x - 14.0, base - 6.0, 28.0, 8.0, color);
+            for x in [-14.0, -5.0, 4.0] {
+                let left = Vec2::new(head.x + x, base - 6.0);
+                let tip = Vec2::new(head.x + x + 5.0, base - 16.0);
+                draw_triangle(left, left + Vec2::new(10.0, 0.0), tip, color);
+            }
+            draw_circle(head.x, base - 2.0, 2.5, RED);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::data::GameConfig;
+use crate::ecs::{EventHandler, Fighter, GameEvent, HitboxComponent, HurtboxComponent, Team};
+use crate::ecs::{Transform, World};
+use crate::plugins::{on_off, Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+use macroquad::prelude::*;
+
+/// Seconds a marker stays on screen
+const MARKER_LIFETIME: f64 = 1.5;
+
+/// Debug views and switches: hit markers and hitboxes in the world, the
+/// frame-step keys, the console and the RNG audit
+pub struct RenderDebugPlugin;
+
+impl Plugin for RenderDebugPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry
+            .add_event_handler(|| Box::new(HitMarkers::new()))
+            .add_event_handler(|| Box::new(HitboxView))
+            .add_settings_page(SettingsPage {
+                title: "Debug",
+                entries: vec![
+                    SettingsEntry {
+                        label: |config| {
+                            format!("Hit Markers: {}", on_off(config.debug_hit_markers))
+                        },
+                        activate: |config| config.debug_hit_markers = !config.debug_hit_markers,
+                    },
+                    SettingsEntry {
+                        label: |config| {
+                            format!("Frame Step Keys F5-F8: {}", on_off(config.debug_controls))
+                        },
+                        activate: |config| {
+                            config.debug_controls = !config.debug_controls;
+                            crate::data::set_debug_controls(config.debug_controls);
+                        },
+                    },
+                    SettingsEntry {
+                        label: |config| {
+                            format!("Developer Console (~): {}", on_off(config.dev_console))
+                        },
+                        activate: |config| {
+                            config.dev_console = !config.dev_console;
+                            crate::data::set_dev_console(config.dev_console);
+                        },
+                    },
+                    SettingsEntry {
+                        label: |config| format!("RNG Audit: {}", on_off(config.rng_audit)),
+                        activate: |config| config.rng_audit = !config.rng_audit,
+                    },
+                ],
+            });
+    }
+}
+
+enum Marker {
+    Hit { damage: f32 },
+    Death { team: Team },
+}
+
+/// Crosses where hits landed and rings where fighters died, with the damage
+/// dealt, so hit positions can be checked against the sprites
+struct HitMarkers {
+    enabled: bool,
+    markers: Vec<(Vec2, Marker, f64)>,
+}
+
+impl HitMarkers {
+    fn new() -> Self {
+        Self {
+            enabled: GameConfig::load().debug_hit_markers,
+            markers: Vec::new(),
+        }
+    }
+}
+
+impl EventHandler for HitMarkers {
+    fn handle(&mut self, _world: &World, event: &GameEvent) {
+        if !self.enabled {
+            return;
+        }
+
+        let now = get_time();
+        self.markers
+            .retain(|(_, _, time)| now - time < MARKER_LIFETIME);
+        match *event {
+            GameEvent::HitLanded {
+                damage, position, ..
+            } => self.markers.push((position, Marker::Hit { damage }, now)),
+            GameEvent::EntityDied { team, position, .. } => {
+                self.markers.push((position, Marker::Death { team }, now))
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&self, _world: &World) {
+        let now = get_time();
+        for (position, marker, time) in &self.markers {
+            let age = now - time;
+            if age >= MARKER_LIFETIME {
+                continue;
+            }
+            let alpha = 1.0 - (age / MARKER_LIFETIME) as f32;
+
+            match marker {
+                Marker::Hit { damage } => {
+                    let color = Color::new(1.0, 1.0, 0.0, alpha);
+                    draw_line(
+                        position.x - 8.0,
+                        position.y,
+                        position.x + 8.0,
+                        position.y,
+                        2.0,
+                        color,
+                    );
+                    draw_line(
+                        position.x,
+                        position.y - 8.0,
+                        position.x,
+                        position.y + 8.0,
+                        2.0,
+                        color,
+                    );
+                    draw_text(
+                        &format!("{:.1}", damage),
+                        position.x + 10.0,
+                        position.y - 10.0,
+                        16.0,
+                        color,
+                    );
+                }
+                Marker::Death { team } => {
+                    let color = if team.is_allied(Team::Player) {
+                        Color::new(0.3, 0.6, 1.0, alpha)
+                    } else {
+                        Color::new(1.0, 0.3, 0.3, alpha)
+                    };
+                    draw_circle_lines(position.x, position.y, 24.0, 2.0, color);
+                }
+            }
+        }
+    }
+}
+
+/// Outlines of every live hurtbox, and of hitboxes while they're out,
+/// switched on with `hitboxes on` in the console
+struct HitboxView;
+
+impl EventHandler for HitboxView {
+    fn handle(&mut self, _world: &World, _event: &GameEvent) {}
+
+    fn render(&self, world: &World) {
+        if !crate::data::get_debug_hitboxes() {
+            return;
+        }
+
+        for (_, (hurtbox, transform)) in world.query::<(HurtboxComponent, Transform)>() {
+            if hurtbox.active {
+                let center = transform.position + hurtbox.hurtbox.offset;
+                draw_box(center, hurtbox.hurtbox.size, Color::new(0.2, 1.0, 0.3, 0.8));
+            }
+        }
+        for (entity, (hitbox, transform)) in world.query::<(HitboxComponent, Transform)>() {
+            if !hitbox.active {
+                continue;
+            }
+            // Offsets point the way the fighter faces, as the combat system reads them
+            let mut offset = hitbox.hitbox.offset;
+            if world.get_component::<Fighter>(entity).is_some_and(|f| f.facing < 0.0) {
+                offset.x = -offset.x;
+            }
+            let color = Color::new(1.0, 0.2, 0.2, 0.9);
+            draw_box(transform.position + offset, hitbox.hitbox.size, color);
+        }
+    }
+}
+
+fn draw_box(center: Vec2, size: Vec2, color: Color) {
+    let corner = center - size * 0.5;
+    draw_rectangle(corner.x, corner.y, size.x, size.y, Color::new(color.r, color.g, color.b, 0.15));
+    draw_rectangle_lines(corner.x, corner.y, size.x, size.y, 2.0, color);
+}
@@ -209,6 +209,27 @@ impl EnhancedVFXSystem {
 
     /// Spawn impact effect with direction
     pub fn spawn_impact(&mut self, position: Vec2, direction: Vec2, impact_type: ImpactType) {
+        self.spawn_impact_with(position, direction, impact_type, None);
+    }
+
+    /// An impact whose sparks are `spark_color` instead of the type's own
+    pub fn spawn_tinted_impact(
+        &mut self,
+        position: Vec2,
+        direction: Vec2,
+        impact_type: ImpactType,
+        spark_color: Color,
+    ) {
+        self.spawn_impact_with(position, direction, impact_type, Some(spark_color));
+    }
+
+    fn spawn_impact_with(
+        &mut self,
+        position: Vec2,
+        direction: Vec2,
+        impact_type: ImpactType,
+        spark_color: Option<Color>,
+    ) {
         let (intensity, spark_count, lifetime, base_color) = match impact_type {
             ImpactType::Light => (1.0, 5, 0.15, YELLOW),
             ImpactType::Medium => (1.5, 10, 0.25, ORANGE),
@@ -232,7 +253,7 @@ impl EnhancedVFXSystem {
                 lifetime: 0.0,
                 max_lifetime: rand::gen_range(0.1, lifetime),
                 size: rand::gen_range(0.5, 1.5) * intensity,
-                color: base_color,
+                color: spark_color.unwrap_or(base_color),
             });
         }
 
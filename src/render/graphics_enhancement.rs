@@ -158,12 +158,17 @@ impl GraphicsEnhancement {
 
     /// Trigger a screen shake effect
     pub fn trigger_screen_shake(&mut self, intensity: f32, duration: f32) {
-        self.screen_shake_intensity = intensity;
+        self.screen_shake_intensity = intensity * crate::data::get_screen_shake();
         self.shake_timer = duration;
     }
 
     /// Trigger a screen flash effect
     pub fn trigger_screen_flash(&mut self, color: Color, duration: f32) {
+        let strength = crate::data::get_screen_flash();
+        if strength <= 0.0 {
+            return;
+        }
+        let color = Color::new(color.r, color.g, color.b, color.a * strength);
         self.screen_flash_color = Some((color, duration));
         self.flash_timer = 0.0;
     }
@@ -414,6 +419,7 @@ impl EnhancedSprite {
             ];
 
             for offset in &outline_offsets {
+                crate::app::count_draw_call();
                 draw_texture_ex(
                     &self.texture,
                     self.position.x + offset.x,
@@ -439,6 +445,7 @@ impl EnhancedSprite {
             final_color.b = (final_color.b + self.emissive.b * self.glow).min(1.0);
         }
 
+        crate::app::count_draw_call();
         draw_texture_ex(
             &self.texture,
             self.position.x,
@@ -464,6 +471,7 @@ impl EnhancedSprite {
                     glow_alpha,
                 );
 
+                crate::app::count_draw_call();
                 draw_texture_ex(
                     &self.texture,
                     self.position.x - (self.size.x * (glow_scale - 1.0)) * 0.5,
@@ -1,4 +1,5 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// Enhanced map system with parallax, animated backgrounds, and weather
 pub struct MapSystem {
@@ -9,9 +10,11 @@ pub struct MapSystem {
     pub lighting: LightingSystem,
     pub hazards: Vec<MapHazard>,
     pub interactive_objects: Vec<InteractiveObject>,
+    pub spawn_points: Vec<SpawnPoint>,
+    next_spawn_point: usize,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum MapType {
     Classroom,
     Hallway,
@@ -24,6 +27,77 @@ pub enum MapType {
     Kitchen,
 }
 
+impl MapType {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            MapType::Classroom => "Classroom",
+            MapType::Hallway => "Hallway",
+            MapType::Cafeteria => "Cafeteria",
+            MapType::Gym => "Gym",
+            MapType::Library => "Library",
+            MapType::Rooftop => "Rooftop",
+            MapType::PrincipalOffice => "Principal's Office",
+            MapType::JanitorCloset => "Janitor Closet",
+            MapType::Kitchen => "Kitchen",
+        }
+    }
+}
+
+/// Where enemies enter the arena from. `x` is a fraction of the arena width so
+/// the same layout works at any resolution.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnPoint {
+    pub kind: SpawnPointKind,
+    pub x: f32,
+    pub landing_depth: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpawnPointKind {
+    Door,      // Kicked open in the back wall
+    Window,    // Vaulted through, landing further into the room
+    Stairwell, // Climbs up from the front edge of the floor
+}
+
+impl SpawnPointKind {
+    /// How long the entrance animation takes before the enemy can act
+    pub fn entrance_duration(self) -> f32 {
+        match self {
+            SpawnPointKind::Door => 0.8,
+            SpawnPointKind::Window => 0.9,
+            SpawnPointKind::Stairwell => 1.0,
+        }
+    }
+}
+
+impl SpawnPoint {
+    fn new(kind: SpawnPointKind, x: f32, landing_depth: f32) -> Self {
+        Self {
+            kind,
+            x,
+            landing_depth,
+        }
+    }
+
+    /// Off-floor position the entrance starts from
+    pub fn origin(&self, arena: Rect) -> Vec2 {
+        let x = arena.x + arena.w * self.x;
+        match self.kind {
+            SpawnPointKind::Door => Vec2::new(x, arena.y - 30.0),
+            SpawnPointKind::Window => Vec2::new(x, arena.y - 80.0),
+            SpawnPointKind::Stairwell => Vec2::new(x, arena.y + arena.h + 60.0),
+        }
+    }
+
+    /// Floor position the enemy ends up at once the entrance finishes
+    pub fn landing(&self, arena: Rect) -> Vec2 {
+        Vec2::new(
+            arena.x + arena.w * self.x,
+            self.landing_depth.clamp(arena.y, arena.y + arena.h),
+        )
+    }
+}
+
 /// Parallax scrolling layer
 pub struct ParallaxLayer {
     pub texture_name: String,
@@ -103,6 +177,8 @@ pub struct LightingSystem {
     pub dynamic_lights: Vec<DynamicLight>,
     pub shadows_enabled: bool,
     pub time_of_day: f32, // 0.0 = midnight, 0.5 = noon, 1.0 = midnight
+    /// Seconds the lights have been flickering
+    clock: f32,
 }
 
 pub struct DynamicLight {
@@ -170,12 +246,67 @@ impl MapSystem {
             lighting: LightingSystem::new(),
             hazards: Vec::new(),
             interactive_objects: Vec::new(),
+            spawn_points: Vec::new(),
+            next_spawn_point: 0,
         };
 
         system.initialize_map(map_type);
+        system.setup_spawn_points(map_type);
         system
     }
 
+    fn setup_spawn_points(&mut self, map_type: MapType) {
+        use SpawnPointKind::*;
+
+        // Kept mostly on the right so enemies don't drop in on top of the player's start
+        let points: &[(SpawnPointKind, f32, f32)] = match map_type {
+            MapType::Classroom => &[(Door, 0.9, 380.0), (Window, 0.6, 450.0), (Window, 0.75, 470.0)],
+            MapType::Hallway => &[
+                (Door, 0.5, 370.0),
+                (Door, 0.7, 370.0),
+                (Door, 0.9, 370.0),
+                (Stairwell, 0.95, 610.0),
+            ],
+            MapType::Cafeteria => &[
+                (Door, 0.8, 390.0),
+                (Window, 0.55, 460.0),
+                (Window, 0.7, 460.0),
+                (Stairwell, 0.9, 600.0),
+            ],
+            MapType::Gym => &[(Door, 0.75, 380.0), (Door, 0.9, 380.0), (Stairwell, 0.6, 620.0)],
+            MapType::Library => &[(Door, 0.85, 380.0), (Window, 0.5, 440.0), (Stairwell, 0.65, 610.0)],
+            MapType::Rooftop => &[(Door, 0.7, 400.0), (Stairwell, 0.85, 600.0)],
+            MapType::PrincipalOffice => &[(Door, 0.8, 380.0), (Window, 0.5, 450.0)],
+            MapType::JanitorCloset => &[(Door, 0.7, 400.0)],
+            MapType::Kitchen => &[(Door, 0.85, 390.0), (Window, 0.6, 440.0)],
+        };
+
+        self.spawn_points = points
+            .iter()
+            .map(|&(kind, x, depth)| SpawnPoint::new(kind, x, depth))
+            .collect();
+        self.next_spawn_point = 0;
+    }
+
+    /// The first door in the back wall, if the map has one
+    pub fn door(&self) -> Option<SpawnPoint> {
+        self.spawn_points
+            .iter()
+            .find(|point| point.kind == SpawnPointKind::Door)
+            .copied()
+    }
+
+    /// Next spawn point in rotation, so consecutive enemies use different entrances
+    pub fn next_spawn_point(&mut self) -> Option<SpawnPoint> {
+        if self.spawn_points.is_empty() {
+            return None;
+        }
+
+        let point = self.spawn_points[self.next_spawn_point % self.spawn_points.len()];
+        self.next_spawn_point += 1;
+        Some(point)
+    }
+
     fn initialize_map(&mut self, map_type: MapType) {
         match map_type {
             MapType::Classroom => self.setup_classroom(),
@@ -772,13 +903,15 @@ impl LightingSystem {
             dynamic_lights: Vec::new(),
             shadows_enabled: false,
             time_of_day: 0.5,
+            clock: 0.0,
         }
     }
 
     pub fn update(&mut self, dt: f32) {
+        self.clock += dt;
         for light in &mut self.dynamic_lights {
             if light.flicker {
-                let flicker = (get_time() as f32 * light.flicker_speed).sin() * light.flicker_amount;
+                let flicker = (self.clock * light.flicker_speed).sin() * light.flicker_amount;
                 light.intensity = 1.0 - light.flicker_amount + flicker.abs();
             }
         }
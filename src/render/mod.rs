@@ -1,6 +1,9 @@
+pub mod animation;
 pub mod atlas;
 pub mod camera;
+pub mod character_sheets;
 // pub mod character_renderer;
+pub mod debug;
 pub mod enhanced_vfx;
 pub mod graphics_enhancement;
 pub mod map_system;
@@ -13,12 +16,15 @@ pub mod screen_effects;
 pub mod texture_manager;
 pub mod vfx;
 
-pub use camera::GameCamera;
+pub use animation::AnimationPlayer;
+pub use camera::{FocusPriority, GameCamera};
+pub use character_sheets::{draw_outfit, CharacterSheets, SpriteAnimation};
 // pub use character_renderer::CharacterRenderer;
-pub use enhanced_vfx::{EnhancedVFXSystem, ImpactType, AuraType, DistortionType};
-pub use graphics_enhancement::{GraphicsEnhancement, EnhancedSprite};
+pub use enhanced_vfx::{EnhancedVFXSystem, ImpactType, DistortionType};
+pub use graphics_enhancement::GraphicsEnhancement;
 pub use map_system::{MapSystem, ParallaxLayer, AnimatedObject, WeatherSystem, DynamicLight};
 pub use motion_trail::{MotionTrail, AttackTrail};
+pub use post::{PostMoment, PostProcessor};
 pub use screen_effects::ScreenEffects;
 // pub use shader_system::ShaderSystem;
 // pub use skeletal_animation::SkeletalAnimationSystem;
@@ -81,6 +81,7 @@ impl MotionTrail {
             let mut color = self.trail_color;
             color.a = final_alpha;
 
+            crate::app::count_draw_call();
             draw_texture_ex(
                 texture,
                 pos.x - (size.x * scale * 0.5),
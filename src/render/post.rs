@@ -1,60 +1,339 @@
+use crate::data::crash;
 use macroquad::prelude::*;
 
-pub struct PostProcessor {
-    pub bloom_intensity: f32,
+/// How strongly each stage of the post shader is applied. Colors use alpha as
+/// their strength, so the default is "no effect".
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PostEffects {
+    pub bloom: f32,
     pub chromatic_aberration: f32,
-    pub vignette_intensity: f32,
-    pub heat_haze_intensity: f32,
-    pub screen_shake: f32,
-    pub color_grading: ColorGrading,
+    pub vignette: f32,
+    /// Red pulse at the screen edges
+    pub danger: f32,
+    pub desaturate: f32,
+    pub tint: Color,
+    pub flash: Color,
 }
 
-#[derive(Clone, Debug)]
-pub struct ColorGrading {
-    pub brightness: f32,
-    pub contrast: f32,
-    pub saturation: f32,
-    pub tint: Color,
+impl PostEffects {
+    fn scaled(self, amount: f32) -> Self {
+        Self {
+            bloom: self.bloom * amount,
+            chromatic_aberration: self.chromatic_aberration * amount,
+            vignette: self.vignette * amount,
+            danger: self.danger * amount,
+            desaturate: self.desaturate * amount,
+            tint: Color::new(self.tint.r, self.tint.g, self.tint.b, self.tint.a * amount),
+            flash: Color::new(self.flash.r, self.flash.g, self.flash.b, self.flash.a * amount),
+        }
+    }
+
+    /// Layer `other` on top; scalar effects add, colors go to whichever is stronger
+    fn combined(self, other: Self) -> Self {
+        let strongest = |a: Color, b: Color| if b.a > a.a { b } else { a };
+        Self {
+            bloom: self.bloom + other.bloom,
+            chromatic_aberration: self.chromatic_aberration + other.chromatic_aberration,
+            vignette: (self.vignette + other.vignette).min(1.0),
+            danger: (self.danger + other.danger).min(1.0),
+            desaturate: (self.desaturate + other.desaturate).min(1.0),
+            tint: strongest(self.tint, other.tint),
+            flash: strongest(self.flash, other.flash),
+        }
+    }
+}
+
+/// Gameplay beats with their own look; each fades out over its duration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostMoment {
+    SuperActivation,
+    BombExplosion,
+}
+
+impl PostMoment {
+    fn duration(self) -> f32 {
+        match self {
+            PostMoment::SuperActivation => 0.9,
+            PostMoment::BombExplosion => 0.35,
+        }
+    }
+
+    fn effects(self) -> PostEffects {
+        match self {
+            // Slow-mo look: washed out, cool tint, glowing highlights
+            PostMoment::SuperActivation => PostEffects {
+                bloom: 0.8,
+                chromatic_aberration: 1.5,
+                desaturate: 0.55,
+                tint: Color::new(0.7, 0.85, 1.0, 0.5),
+                ..Default::default()
+            },
+            PostMoment::BombExplosion => PostEffects {
+                bloom: 0.6,
+                chromatic_aberration: 2.5,
+                flash: Color::new(1.0, 0.95, 0.8, 0.75),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+struct ActiveMoment {
+    moment: PostMoment,
+    elapsed: f32,
+}
+
+/// The world is drawn into an off-screen target between `begin` and `end`, then
+/// composited to the screen through the post shader. With `enabled` off (or if
+/// the shader fails to compile) it falls back to cheap full-screen overlays.
+pub struct PostProcessor {
+    pub enabled: bool,
+    /// Effects that are always on
+    pub base: PostEffects,
+    low_health: f32,
+    moments: Vec<ActiveMoment>,
+    target: Option<RenderTarget>,
+    material: Option<Material>,
+    /// Whether the shader has been built yet; a run stepped without a
+    /// window never draws, so never builds it
+    compiled: bool,
 }
 
 impl PostProcessor {
-    pub fn new() -> Self {
+    pub fn new(enabled: bool) -> Self {
         Self {
-            bloom_intensity: 0.5,
-            chromatic_aberration: 0.0,
-            vignette_intensity: 0.3,
-            heat_haze_intensity: 0.0,
-            screen_shake: 0.0,
-            color_grading: ColorGrading {
-                brightness: 1.0,
-                contrast: 1.0,
-                saturation: 1.0,
-                tint: WHITE,
+            enabled,
+            base: PostEffects {
+                bloom: 0.25,
+                vignette: 0.3,
+                ..Default::default()
             },
+            low_health: 0.0,
+            moments: Vec::new(),
+            target: None,
+            material: None,
+            compiled: false,
         }
     }
 
-    pub fn apply_effects(&self) {
-        if self.vignette_intensity > 0.0 {
-            self.apply_vignette();
+    /// The compositing shader, built the first time a frame is drawn
+    fn compile_shader() -> Option<Material> {
+        load_material(
+            ShaderSource::Glsl {
+                vertex: POST_VERTEX_SHADER,
+                fragment: POST_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms: vec![
+                    UniformDesc::new("TexelSize", UniformType::Float2),
+                    UniformDesc::new("Bloom", UniformType::Float1),
+                    UniformDesc::new("Aberration", UniformType::Float1),
+                    UniformDesc::new("Vignette", UniformType::Float1),
+                    UniformDesc::new("Danger", UniformType::Float1),
+                    UniformDesc::new("Desaturate", UniformType::Float1),
+                    UniformDesc::new("Tint", UniformType::Float4),
+                    UniformDesc::new("Flash", UniformType::Float4),
+                ],
+                ..Default::default()
+            },
+        )
+        .map_err(|e| crash::log(format!("Post-processing shader unavailable: {:?}", e)))
+        .ok()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for moment in &mut self.moments {
+            moment.elapsed += dt;
         }
+        self.moments.retain(|active| active.elapsed < active.moment.duration());
+    }
+
+    /// Start a moment's effect; re-triggering one that's playing restarts it
+    pub fn trigger(&mut self, moment: PostMoment) {
+        self.moments.retain(|active| active.moment != moment);
+        self.moments.push(ActiveMoment {
+            moment,
+            elapsed: 0.0,
+        });
+    }
+
+    /// 0 = healthy, 1 = about to go down. Drives the pulsing red vignette.
+    pub fn set_low_health(&mut self, amount: f32) {
+        self.low_health = amount.clamp(0.0, 1.0);
     }
 
-    fn apply_vignette(&self) {
-        let width = screen_width();
-        let height = screen_height();
-        let center = Vec2::new(width * 0.5, height * 0.5);
+    fn current_effects(&self) -> PostEffects {
+        let pulse = 0.75 + 0.25 * (get_time() as f32 * 6.0).sin();
+        let mut effects = self.base.combined(PostEffects {
+            danger: self.low_health * 0.6 * pulse,
+            vignette: self.low_health * 0.3,
+            ..Default::default()
+        });
 
-        for i in 0..20 {
-            let radius = width.max(height) * (1.0 - i as f32 * 0.05);
-            let alpha = self.vignette_intensity * 0.02;
-            draw_circle_lines(
-                center.x,
-                center.y,
-                radius,
-                2.0,
-                Color::new(0.0, 0.0, 0.0, alpha),
-            );
+        for active in &self.moments {
+            let fade = 1.0 - active.elapsed / active.moment.duration();
+            effects = effects.combined(active.moment.effects().scaled(fade));
         }
+        effects.flash.a *= crate::data::get_screen_flash();
+        effects
     }
+
+    /// Render target the world pass should draw into, or None to draw straight
+    /// to the screen
+    pub fn begin(&mut self) -> Option<RenderTarget> {
+        if !self.compiled {
+            self.compiled = true;
+            self.material = Self::compile_shader();
+        }
+        if !self.enabled || self.material.is_none() {
+            return None;
+        }
+
+        let (width, height) = (screen_width() as u32, screen_height() as u32);
+        let stale = self.target.as_ref().is_none_or(|target| {
+            target.texture.width() as u32 != width || target.texture.height() as u32 != height
+        });
+        if stale {
+            let target = render_target(width.max(1), height.max(1));
+            target.texture.set_filter(FilterMode::Linear);
+            self.target = Some(target);
+        }
+
+        self.target.clone()
+    }
+
+    /// Composite the world pass to the screen. Leaves the default camera set.
+    pub fn end(&self) {
+        set_default_camera();
+        let effects = self.current_effects();
+
+        let target = self.target.as_ref().filter(|_| self.enabled);
+        let (Some(target), Some(material)) = (target, &self.material) else {
+            Self::draw_overlays(&effects);
+            return;
+        };
+
+        let size = target.texture.size();
+        material.set_uniform("TexelSize", [1.0 / size.x, 1.0 / size.y]);
+        material.set_uniform("Bloom", effects.bloom);
+        material.set_uniform("Aberration", effects.chromatic_aberration);
+        material.set_uniform("Vignette", effects.vignette);
+        material.set_uniform("Danger", effects.danger);
+        material.set_uniform("Desaturate", effects.desaturate);
+        material.set_uniform("Tint", rgba(effects.tint));
+        material.set_uniform("Flash", rgba(effects.flash));
+
+        gl_use_material(material);
+        crate::app::count_draw_call();
+        draw_texture_ex(
+            &target.texture,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(Vec2::new(screen_width(), screen_height())),
+                ..Default::default()
+            },
+        );
+        gl_use_default_material();
+    }
+
+    /// Low-end path: approximate tint, danger and flash with translucent quads
+    fn draw_overlays(effects: &PostEffects) {
+        let (width, height) = (screen_width(), screen_height());
+
+        if effects.tint.a > 0.0 {
+            let tint = effects.tint;
+            let overlay = Color::new(tint.r, tint.g, tint.b, tint.a * 0.25);
+            draw_rectangle(0.0, 0.0, width, height, overlay);
+        }
+
+        if effects.danger > 0.0 {
+            let edge = Color::new(0.8, 0.0, 0.05, effects.danger * 0.5);
+            let band = height * 0.08;
+            draw_rectangle(0.0, 0.0, width, band, edge);
+            draw_rectangle(0.0, height - band, width, band, edge);
+            draw_rectangle(0.0, band, band, height - band * 2.0, edge);
+            draw_rectangle(width - band, band, band, height - band * 2.0, edge);
+        }
+
+        if effects.flash.a > 0.0 {
+            draw_rectangle(0.0, 0.0, width, height, effects.flash);
+        }
+    }
+}
+
+fn rgba(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+const POST_VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+"#;
+
+const POST_FRAGMENT_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform vec2 TexelSize;
+uniform float Bloom;
+uniform float Aberration;
+uniform float Vignette;
+uniform float Danger;
+uniform float Desaturate;
+uniform vec4 Tint;
+uniform vec4 Flash;
+
+vec3 highlights(vec2 at) {
+    return max(texture2D(Texture, at).rgb - 0.7, 0.0);
+}
+
+void main() {
+    // Chromatic aberration: split red and blue away from the centre
+    vec2 split = (uv - 0.5) * Aberration * 0.01;
+    vec3 scene = vec3(
+        texture2D(Texture, uv + split).r,
+        texture2D(Texture, uv).g,
+        texture2D(Texture, uv - split).b
+    );
+
+    // Bloom: box-blurred highlights added back over the scene
+    if (Bloom > 0.0) {
+        vec3 glow = vec3(0.0);
+        for (int x = -2; x <= 2; x++) {
+            for (int y = -2; y <= 2; y++) {
+                glow += highlights(uv + vec2(float(x), float(y)) * TexelSize * 3.0);
+            }
+        }
+        scene += glow / 25.0 * Bloom * 2.0;
+    }
+
+    float grey = dot(scene, vec3(0.299, 0.587, 0.114));
+    scene = mix(scene, vec3(grey), Desaturate);
+    scene = mix(scene, scene * Tint.rgb, Tint.a);
+
+    float edge = smoothstep(0.3, 0.75, length(uv - 0.5));
+    scene *= 1.0 - edge * Vignette;
+    scene = mix(scene, vec3(0.8, 0.0, 0.05), edge * Danger);
+
+    scene = mix(scene, Flash.rgb, Flash.a);
+    gl_FragColor = vec4(scene, 1.0) * color;
 }
+"#;
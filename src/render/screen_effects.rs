@@ -45,6 +45,7 @@ impl ScreenEffects {
 
     /// Trigger screen shake with specified intensity and duration
     pub fn shake(&mut self, intensity: f32, duration: f32) {
+        let intensity = intensity * crate::data::get_screen_shake();
         self.shake_intensity = intensity.max(self.shake_intensity);
         self.shake_duration = duration.max(self.shake_duration);
         self.shake_timer = 0.0;
@@ -107,7 +108,11 @@ impl ScreenEffects {
 
     /// Trigger screen flash effect
     pub fn flash(&mut self, color: Color, duration: f32) {
-        self.flash_color = color;
+        let strength = crate::data::get_screen_flash();
+        if strength <= 0.0 {
+            return;
+        }
+        self.flash_color = Color::new(color.r, color.g, color.b, color.a * strength);
         self.flash_intensity = 1.0;
         self.flash_timer = duration;
     }
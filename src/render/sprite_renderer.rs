@@ -389,6 +389,7 @@ impl SpriteRenderer {
         }
 
         for command in commands {
+            crate::app::count_draw_call();
             draw_texture_ex(
                 &command.texture,
                 command.dest.x,
@@ -421,6 +422,7 @@ impl SpriteRenderer {
             1.0,
         );
 
+        crate::app::count_draw_call();
         draw_texture_ex(
             &render_target.texture,
             0.0,
@@ -1,5 +1,6 @@
 use macroquad::prelude::*;
 use std::collections::HashMap;
+use crate::assets;
 use crate::render::graphics_enhancement::EnhancedSprite;
 
 /// Manages procedurally generated textures for the game
@@ -15,19 +16,25 @@ impl TextureManager {
             sprites: HashMap::new(),
         };
         manager.generate_all_textures();
+        manager.use_loaded_textures();
         manager
     }
 
-    fn generate_all_textures(&mut self) {
-        // Generate character textures
-        self.generate_character_texture("bas", Color::new(0.85, 0.65, 0.45, 1.0));
-        self.generate_character_texture("berkay", Color::new(1.0, 0.85, 0.70, 1.0));
-        self.generate_character_texture("gefferinho", Color::new(0.65, 0.45, 0.30, 1.0));
-        self.generate_character_texture("hadi", Color::new(0.65, 0.45, 0.30, 1.0));
-        self.generate_character_texture("nitin", Color::new(0.65, 0.45, 0.30, 1.0));
-        self.generate_character_texture("luca", Color::new(1.0, 0.85, 0.70, 1.0));
-        self.generate_character_texture("palababa", Color::new(1.0, 0.90, 0.80, 1.0));
+    /// Swap in any texture from `assets/textures` for the generated one of
+    /// the same name, or add it if nothing by that name is generated
+    fn use_loaded_textures(&mut self) {
+        for (name, handle) in assets::all::<Texture2D>("textures/") {
+            let Some(texture) = assets::get(handle) else {
+                continue;
+            };
+            self.textures.insert(name.clone(), texture);
+            if let Some(sprite) = self.create_enhanced_sprite(&name) {
+                self.sprites.insert(name, sprite);
+            }
+        }
+    }
 
+    fn generate_all_textures(&mut self) {
         // Generate effect textures
         self.generate_explosion_texture();
         self.generate_lightning_texture();
@@ -37,161 +44,6 @@ impl TextureManager {
         self.generate_aura_texture();
     }
 
-    fn generate_character_texture(&mut self, name: &str, skin_color: Color) {
-        let size = 128;
-        let mut image = Image::gen_image_color(size, size, Color::new(0.0, 0.0, 0.0, 0.0));
-
-        // Draw character with realistic shading
-        for y in 0..size {
-            for x in 0..size {
-                let cx = size as f32 / 2.0;
-                let cy = size as f32 / 2.0;
-
-                // Head region (upper third)
-                if y < size / 3 {
-                    let head_radius = size as f32 * 0.2;
-                    let dist = ((x as f32 - cx).powi(2) + (y as f32 - cy * 0.5).powi(2)).sqrt();
-
-                    if dist < head_radius {
-                        // Apply lighting gradient for 3D effect
-                        let light_factor = 1.0 - (dist / head_radius) * 0.3;
-                        let shaded_color = Color::new(
-                            skin_color.r * light_factor,
-                            skin_color.g * light_factor,
-                            skin_color.b * light_factor,
-                            1.0
-                        );
-                        image.set_pixel(x as u32, y as u32, shaded_color);
-
-                        // Add highlight for realism
-                        let highlight_dist = ((x as f32 - (cx - head_radius * 0.3)).powi(2) +
-                                            (y as f32 - (cy * 0.5 - head_radius * 0.3)).powi(2)).sqrt();
-                        if highlight_dist < head_radius * 0.2 {
-                            let highlight = Color::new(
-                                (skin_color.r + 0.2).min(1.0),
-                                (skin_color.g + 0.2).min(1.0),
-                                (skin_color.b + 0.2).min(1.0),
-                                0.3
-                            );
-                            let existing = image.get_pixel(x as u32, y as u32);
-                            image.set_pixel(x as u32, y as u32, Color::new(
-                                existing.r * 0.7 + highlight.r * 0.3,
-                                existing.g * 0.7 + highlight.g * 0.3,
-                                existing.b * 0.7 + highlight.b * 0.3,
-                                1.0
-                            ));
-                        }
-                    }
-                }
-
-                // Body region (middle third)
-                else if y < (size * 2) / 3 {
-                    let body_width = size as f32 * 0.35;
-                    let body_height = size as f32 * 0.3;
-                    let body_y = cy;
-
-                    if (x as f32 - cx).abs() < body_width / 2.0 &&
-                       (y as f32 - body_y).abs() < body_height / 2.0 {
-                        // Shirt color with shading
-                        let shirt_base = match name {
-                            "bas" => Color::new(0.8, 0.2, 0.2, 1.0), // Red
-                            "berkay" => Color::new(0.2, 0.2, 0.8, 1.0), // Blue
-                            "gefferinho" => Color::new(0.2, 0.8, 0.2, 1.0), // Green
-                            "hadi" => Color::new(0.8, 0.8, 0.2, 1.0), // Yellow
-                            "nitin" => Color::new(0.8, 0.2, 0.8, 1.0), // Purple
-                            "luca" => Color::new(0.2, 0.8, 0.8, 1.0), // Cyan
-                            _ => Color::new(0.5, 0.5, 0.5, 1.0), // Gray
-                        };
-
-                        // Add muscle definition with shadows
-                        let muscle_shadow = ((x as f32 - cx).abs() / (body_width / 2.0)) * 0.2;
-                        let shaded_shirt = Color::new(
-                            shirt_base.r * (1.0 - muscle_shadow),
-                            shirt_base.g * (1.0 - muscle_shadow),
-                            shirt_base.b * (1.0 - muscle_shadow),
-                            1.0
-                        );
-                        image.set_pixel(x as u32, y as u32, shaded_shirt);
-                    }
-                }
-
-                // Legs region (lower third)
-                else {
-                    let leg_separation = size as f32 * 0.08;
-                    let leg_width = size as f32 * 0.12;
-                    let leg_height = size as f32 * 0.3;
-                    let legs_y = cy * 1.5;
-
-                    // Left leg
-                    if (x as f32 - (cx - leg_separation)).abs() < leg_width / 2.0 &&
-                       y as f32 > legs_y && (y as f32) < legs_y + leg_height {
-                        let pants_color = Color::new(0.2, 0.2, 0.3, 1.0);
-                        let shadow = ((y as f32 - legs_y) / leg_height) * 0.3;
-                        image.set_pixel(x as u32, y as u32, Color::new(
-                            pants_color.r * (1.0 - shadow),
-                            pants_color.g * (1.0 - shadow),
-                            pants_color.b * (1.0 - shadow),
-                            1.0
-                        ));
-                    }
-
-                    // Right leg
-                    if (x as f32 - (cx + leg_separation)).abs() < leg_width / 2.0 &&
-                       y as f32 > legs_y && (y as f32) < legs_y + leg_height {
-                        let pants_color = Color::new(0.2, 0.2, 0.3, 1.0);
-                        let shadow = ((y as f32 - legs_y) / leg_height) * 0.3;
-                        image.set_pixel(x as u32, y as u32, Color::new(
-                            pants_color.r * (1.0 - shadow),
-                            pants_color.g * (1.0 - shadow),
-                            pants_color.b * (1.0 - shadow),
-                            1.0
-                        ));
-                    }
-                }
-            }
-        }
-
-        // Add hair
-        for y in 0..size/4 {
-            for x in 0..size {
-                let cx = size as f32 / 2.0;
-                let cy = size as f32 * 0.25;
-                let hair_radius = size as f32 * 0.22;
-                let dist = ((x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)).sqrt();
-
-                if dist < hair_radius && y < size / 6 {
-                    let hair_color = if name == "palababa" {
-                        Color::new(0.9, 0.9, 0.9, 1.0) // White hair
-                    } else {
-                        Color::new(0.1, 0.1, 0.1, 1.0) // Black hair
-                    };
-
-                    // Add hair texture
-                    let noise = ((x as f32 * 0.1).sin() * (y as f32 * 0.1).cos()).abs();
-                    let textured_hair = Color::new(
-                        hair_color.r * (0.8 + noise * 0.2),
-                        hair_color.g * (0.8 + noise * 0.2),
-                        hair_color.b * (0.8 + noise * 0.2),
-                        1.0
-                    );
-                    image.set_pixel(x as u32, y as u32, textured_hair);
-                }
-            }
-        }
-
-        let texture = Texture2D::from_image(&image);
-        texture.set_filter(FilterMode::Linear);
-
-        let mut sprite = EnhancedSprite::new(texture.clone());
-        sprite.metallic = 0.1;
-        sprite.roughness = 0.8;
-        sprite.emissive = Color::new(0.0, 0.0, 0.0, 0.0);
-        sprite.glow = 0.0;
-
-        self.textures.insert(format!("character_{}", name), texture);
-        self.sprites.insert(format!("character_{}", name), sprite);
-    }
-
     fn generate_explosion_texture(&mut self) {
         let size = 256;
         let mut image = Image::gen_image_color(size, size, Color::new(0.0, 0.0, 0.0, 0.0));
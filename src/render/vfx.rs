@@ -1,4 +1,5 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 pub struct VFXManager {
@@ -17,7 +18,7 @@ pub struct VisualEffect {
     pub rotation: f32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VFXType {
     HitSpark,
     BlockFlash,
@@ -37,6 +38,8 @@ pub enum VFXType {
     IceShatter,
     PoisonCloud,
     ShockWave,
+    /// Cracks running along the floor from a heavy landing
+    GroundCrack,
 }
 
 #[derive(Clone)]
@@ -272,11 +275,32 @@ impl VFXManager {
                 scale: 1.0,
                 rotation: 0.0,
             },
+            VFXType::GroundCrack => {
+                self.spawn_dust_particles(position, 12);
+                VisualEffect {
+                    effect_type,
+                    position,
+                    lifetime: 0.0,
+                    max_lifetime: 0.9,
+                    color: Color::new(0.25, 0.2, 0.15, 0.9),
+                    scale: 1.0,
+                    rotation: rand::gen_range(0.0, std::f32::consts::TAU),
+                }
+            }
         };
 
         self.effects.push_back(effect);
     }
 
+    /// Spawn an effect that points somewhere, like a trail streaming behind
+    /// a swing. `rotation` is in radians; facing left is PI.
+    pub fn spawn_effect_rotated(&mut self, effect_type: VFXType, position: Vec2, rotation: f32) {
+        self.spawn_effect(effect_type, position);
+        if let Some(effect) = self.effects.back_mut() {
+            effect.rotation = rotation;
+        }
+    }
+
     fn spawn_paint_particles(&mut self, position: Vec2, count: usize) {
         for particle in self.particle_pool.iter_mut().take(count) {
             if !particle.active {
@@ -616,6 +640,34 @@ impl VFXManager {
                         y = next_y;
                     }
                 }
+                VFXType::DashTrail => {
+                    // Streaks trailing away from the direction of travel
+                    let back = Vec2::from_angle(effect.rotation + std::f32::consts::PI);
+                    let length = effect.scale * 60.0 * alpha;
+                    for i in -1i32..=1 {
+                        let start = effect.position + back.perp() * i as f32 * 8.0;
+                        let end = start + back * length * (1.0 - i.abs() as f32 * 0.3);
+                        draw_line(start.x, start.y, end.x, end.y, 3.0, color);
+                    }
+                }
+                VFXType::GroundCrack => {
+                    // Flattened onto the floor; `rotation` seeds the jag
+                    let reach = effect.scale * 70.0 * (effect.lifetime * 6.0).min(1.0);
+                    for i in 0..5 {
+                        let angle = i as f32 * std::f32::consts::PI / 4.0;
+                        let mut from = effect.position;
+                        for step in 1..=3 {
+                            let jag = (effect.rotation + (i * 3 + step) as f32 * 1.7).sin() * 6.0;
+                            let to = effect.position
+                                + Vec2::new(
+                                    angle.cos() * reach * step as f32 / 3.0,
+                                    angle.sin().abs() * -0.25 * reach * step as f32 / 3.0 + jag,
+                                );
+                            draw_line(from.x, from.y, to.x, to.y, 3.0 - step as f32 * 0.6, color);
+                            from = to;
+                        }
+                    }
+                }
                 _ => {}
             }
         }
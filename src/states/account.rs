@@ -0,0 +1,245 @@
+use crate::progression::account_level::PrestigeResult;
+use crate::progression::{PlayerAccount, PrestigeBadge};
+use crate::states::State;
+use crate::ui::account_banner::{render_account_banner, render_prestige_badge, BANNER_HEIGHT};
+use crate::ui::UiLayout;
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Title,
+    Banner,
+    Prestige,
+}
+
+const ROWS: [Row; 3] = [Row::Title, Row::Banner, Row::Prestige];
+
+/// The active profile's account: level, lifetime stats, which title and
+/// banner it wears, and prestiging once it has reached the cap
+pub struct AccountState {
+    account: PlayerAccount,
+    selected: usize,
+    /// Prestige asks twice
+    confirm_prestige: bool,
+    status: Option<(String, Color)>,
+}
+
+impl AccountState {
+    pub fn new() -> Self {
+        Self {
+            account: PlayerAccount::default(),
+            selected: 0,
+            confirm_prestige: false,
+            status: None,
+        }
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.account.save() {
+            self.status = Some((e, RED));
+        }
+    }
+
+    fn prestige(&mut self) {
+        if !self.confirm_prestige {
+            self.confirm_prestige = true;
+            return;
+        }
+        self.confirm_prestige = false;
+
+        match self.account.prestige() {
+            PrestigeResult::Success {
+                prestige_level,
+                currency_gained,
+                reward,
+            } => {
+                let mut message = format!(
+                    "Prestige {}! +{:.0} prestige currency",
+                    prestige_level, currency_gained
+                );
+                if let Some(reward) = reward {
+                    message = format!("{} | {}", message, reward.name);
+                }
+                self.status = Some((message, YELLOW));
+                self.save();
+            }
+            PrestigeResult::NotEligible => {
+                self.status = Some(("Not high enough level to prestige".to_string(), RED));
+            }
+        }
+    }
+
+    fn render_stats(&self, x: f32, y: f32) {
+        let progression = &self.account.progression;
+        let stats = [
+            format!("Games played: {}", progression.total_games_played),
+            format!(
+                "Wins: {} ({:.0}%)",
+                progression.total_wins,
+                progression.get_win_rate()
+            ),
+            format!(
+                "Kills: {}  Deaths: {}  K/D: {:.2}",
+                progression.total_kills,
+                progression.total_deaths,
+                progression.get_kd_ratio()
+            ),
+            format!("Damage dealt: {:.0}", progression.total_damage_dealt),
+            format!("Playtime: {:.1}h", progression.get_playtime_hours()),
+            format!("Arc Tokens earned: {:.0}", progression.lifetime_currency_earned),
+        ];
+        for (i, line) in stats.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * 30.0, 22.0, LIGHTGRAY);
+        }
+    }
+
+    fn row_label(&self, row: Row) -> String {
+        let progression = &self.account.progression;
+        match row {
+            Row::Title => format!(
+                "TITLE: < {} > ({} unlocked)",
+                progression.profile_title,
+                progression.unlocked_titles.len()
+            ),
+            Row::Banner => format!(
+                "BANNER: < {} > ({} unlocked)",
+                progression.profile_banner,
+                progression.unlocked_banners.len()
+            ),
+            Row::Prestige if self.confirm_prestige => {
+                "PRESS ENTER AGAIN TO GO BACK TO LEVEL 1".to_string()
+            }
+            Row::Prestige if self.account.prestige.can_prestige(progression.level) => {
+                "PRESTIGE".to_string()
+            }
+            Row::Prestige => "PRESTIGE (REACH LEVEL 100)".to_string(),
+        }
+    }
+}
+
+impl State for AccountState {
+    fn enter(&mut self) {
+        self.account = PlayerAccount::load();
+        self.selected = 0;
+        self.confirm_prestige = false;
+        self.status = None;
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        let mid = ui.width() * 0.5;
+        let title = "ACCOUNT";
+        let dims = measure_text(title, None, 64, 1.0);
+        draw_text(title, mid - dims.width * 0.5, 100.0, 64.0, Color::new(1.0, 0.8, 0.0, 1.0));
+
+        let x = mid - 420.0;
+        let width = 840.0;
+        render_account_banner(&self.account, x, 130.0, width);
+
+        // Progress to the next level
+        let progression = &self.account.progression;
+        let bar_y = 130.0 + BANNER_HEIGHT + 16.0;
+        let fraction = (progression.get_progress_percent() / 100.0).clamp(0.0, 1.0);
+        draw_rectangle(x, bar_y, width, 14.0, Color::new(0.15, 0.15, 0.15, 0.9));
+        draw_rectangle(x, bar_y, width * fraction, 14.0, Color::new(1.0, 0.8, 0.0, 1.0));
+        draw_rectangle_lines(x, bar_y, width, 14.0, 1.0, GRAY);
+        let xp = format!(
+            "{:.0} / {:.0} XP to level {}",
+            progression.xp,
+            progression.xp_for_next_level(),
+            progression.level + 1
+        );
+        draw_text(&xp, x, bar_y + 36.0, 20.0, GRAY);
+
+        self.render_stats(x, bar_y + 80.0);
+
+        // Prestige standing on the right
+        let prestige = &self.account.prestige;
+        let right = x + width - 320.0;
+        let prestige_y = bar_y + 80.0;
+        draw_text(
+            &format!("Prestige {}", prestige.prestige_level),
+            right,
+            prestige_y,
+            28.0,
+            WHITE,
+        );
+        match self.account.badge() {
+            Some(badge) => {
+                let (badge_x, badge_y) = (right + 200.0, prestige_y - 8.0);
+                render_prestige_badge(badge, prestige.prestige_level, badge_x, badge_y, 18.0);
+                let name = format!("{} badge", badge.name());
+                draw_text(&name, right, prestige_y + 30.0, 20.0, badge.color());
+            }
+            None => {
+                draw_text("No badge yet", right, prestige_y + 30.0, 20.0, GRAY);
+            }
+        }
+        let bonus = (prestige.permanent_bonuses.xp_multiplier - 1.0) * 100.0;
+        draw_text(&format!("+{:.0}% account XP", bonus), right, prestige_y + 60.0, 20.0, GRAY);
+        if let Some((level, badge)) = PrestigeBadge::next_tier(prestige.prestige_level) {
+            let line = format!("{} badge at prestige {}", badge.name(), level);
+            draw_text(&line, right, prestige_y + 90.0, 18.0, DARKGRAY);
+        }
+
+        let rows_y = prestige_y + 220.0;
+        for (i, &row) in ROWS.iter().enumerate() {
+            let selected = i == self.selected;
+            let (prefix, color) = if selected { ("> ", YELLOW) } else { ("  ", WHITE) };
+            let label = format!("{}{}", prefix, self.row_label(row));
+            draw_text(&label, x, rows_y + i as f32 * 44.0, 28.0, color);
+        }
+
+        if let Some((status, color)) = &self.status {
+            let dims = measure_text(status, None, 22, 1.0);
+            draw_text(status, mid - dims.width * 0.5, ui.height() - 90.0, 22.0, *color);
+        }
+
+        let hint = "W/S = Choose | A/D = Change | ENTER = Prestige | ESC = Back";
+        let dims = measure_text(hint, None, 22, 1.0);
+        draw_text(hint, mid - dims.width * 0.5, ui.height() - 50.0, 22.0, GRAY);
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        let previous = self.selected;
+        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + ROWS.len() - 1) % ROWS.len();
+        }
+        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % ROWS.len();
+        }
+        if self.selected != previous {
+            self.confirm_prestige = false;
+        }
+
+        let change = is_key_pressed(KeyCode::A)
+            || is_key_pressed(KeyCode::Left)
+            || is_key_pressed(KeyCode::D)
+            || is_key_pressed(KeyCode::Right);
+        match ROWS[self.selected] {
+            Row::Title if change => {
+                self.account.progression.cycle_title();
+                self.save();
+            }
+            Row::Banner if change => {
+                self.account.progression.cycle_banner();
+                self.save();
+            }
+            Row::Prestige if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) => {
+                self.prestige();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::progression::{Achievement, AchievementCategory, AchievementManager};
+use crate::states::State;
+use crate::ui::UiLayout;
+use macroquad::prelude::*;
+
+/// Tabs in the order they're stepped through; `None` shows everything
+const CATEGORIES: [Option<AchievementCategory>; 9] = [
+    None,
+    Some(AchievementCategory::Combat),
+    Some(AchievementCategory::Progression),
+    Some(AchievementCategory::Challenge),
+    Some(AchievementCategory::Mastery),
+    Some(AchievementCategory::Social),
+    Some(AchievementCategory::Collection),
+    Some(AchievementCategory::Exploration),
+    Some(AchievementCategory::Secret),
+];
+const VISIBLE_ROWS: usize = 7;
+const ROW_HEIGHT: f32 = 74.0;
+
+/// Every achievement for the active profile, with how far along each is.
+/// Secret ones stay hidden until they're unlocked.
+pub struct AchievementsState {
+    manager: AchievementManager,
+    category: usize,
+    selected: usize,
+    /// First row on screen
+    scroll: usize,
+}
+
+impl AchievementsState {
+    pub fn new() -> Self {
+        Self {
+            manager: AchievementManager::new(),
+            category: 0,
+            selected: 0,
+            scroll: 0,
+        }
+    }
+
+    /// The current tab's achievements: unlocked first, then by name
+    fn listed(&self) -> Vec<&Achievement> {
+        let mut listed: Vec<&Achievement> = match CATEGORIES[self.category] {
+            Some(category) => self.manager.get_by_category(category),
+            None => self.manager.get_all(),
+        };
+        listed.sort_by(|a, b| b.unlocked.cmp(&a.unlocked).then_with(|| a.name.cmp(&b.name)));
+        listed
+    }
+
+    fn render_row(&self, achievement: &Achievement, selected: bool, y: f32, mid: f32) {
+        let x = mid - 420.0;
+        let width = 840.0;
+        let accent = achievement.difficulty.to_color();
+        let secret = achievement.hidden && !achievement.unlocked;
+
+        let background = if selected {
+            Color::new(1.0, 1.0, 0.0, 0.12)
+        } else {
+            Color::new(1.0, 1.0, 1.0, 0.04)
+        };
+        draw_rectangle(x, y, width, ROW_HEIGHT - 8.0, background);
+        draw_rectangle(x, y, 6.0, ROW_HEIGHT - 8.0, if secret { DARKGRAY } else { accent });
+
+        let (name, description) = if secret {
+            ("???", "Secret achievement")
+        } else {
+            (achievement.name.as_str(), achievement.description.as_str())
+        };
+        let name_color = if achievement.unlocked { WHITE } else { LIGHTGRAY };
+        draw_text(name, x + 20.0, y + 26.0, 26.0, name_color);
+        draw_text(description, x + 20.0, y + 50.0, 18.0, GRAY);
+
+        // Progress bar on the right
+        let bar_x = x + width - 260.0;
+        let bar_width = 240.0;
+        let fraction = (achievement.progress / achievement.max_progress).clamp(0.0, 1.0);
+        draw_rectangle(bar_x, y + 38.0, bar_width, 12.0, Color::new(0.15, 0.15, 0.15, 0.9));
+        draw_rectangle(bar_x, y + 38.0, bar_width * fraction, 12.0, accent);
+        draw_rectangle_lines(bar_x, y + 38.0, bar_width, 12.0, 1.0, GRAY);
+
+        let status = if achievement.unlocked {
+            "UNLOCKED".to_string()
+        } else if achievement.max_progress <= 1.0 {
+            "LOCKED".to_string()
+        } else {
+            let progress = achievement.progress.min(achievement.max_progress);
+            format!("{:.0} / {:.0}", progress, achievement.max_progress)
+        };
+        let color = if achievement.unlocked { accent } else { GRAY };
+        let dims = measure_text(&status, None, 18, 1.0);
+        draw_text(&status, bar_x + bar_width - dims.width, y + 28.0, 18.0, color);
+    }
+}
+
+impl State for AchievementsState {
+    fn enter(&mut self) {
+        self.manager = AchievementManager::load();
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        let mid = ui.width() * 0.5;
+        let title = "ACHIEVEMENTS";
+        let dims = measure_text(title, None, 64, 1.0);
+        draw_text(title, mid - dims.width * 0.5, 100.0, 64.0, Color::new(1.0, 0.8, 0.0, 1.0));
+
+        let unlocked = self.manager.get_unlocked().len();
+        let total = self.manager.get_all().len();
+        let summary = format!(
+            "{} / {} unlocked ({:.0}%)",
+            unlocked,
+            total,
+            self.manager.get_completion_percent()
+        );
+        let dims = measure_text(&summary, None, 24, 1.0);
+        draw_text(&summary, mid - dims.width * 0.5, 140.0, 24.0, LIGHTGRAY);
+
+        let category = match CATEGORIES[self.category] {
+            Some(category) => category.to_string().to_uppercase(),
+            None => "ALL".to_string(),
+        };
+        let tab = format!("< {} >", category);
+        let dims = measure_text(&tab, None, 30, 1.0);
+        draw_text(&tab, mid - dims.width * 0.5, 190.0, 30.0, WHITE);
+
+        let listed = self.listed();
+        if listed.is_empty() {
+            let empty = "Nothing in this category";
+            let dims = measure_text(empty, None, 24, 1.0);
+            draw_text(empty, mid - dims.width * 0.5, 260.0, 24.0, GRAY);
+        }
+        for (row, achievement) in listed.iter().enumerate().skip(self.scroll).take(VISIBLE_ROWS) {
+            let y = 220.0 + (row - self.scroll) as f32 * ROW_HEIGHT;
+            self.render_row(achievement, row == self.selected, y, mid);
+        }
+
+        let hint = "W/S = Scroll | A/D = Category | ESC = Back";
+        let dims = measure_text(hint, None, 22, 1.0);
+        draw_text(hint, mid - dims.width * 0.5, ui.height() - 50.0, 22.0, GRAY);
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        let count = self.listed().len();
+        if count > 0 && (is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up)) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if count > 0 && (is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down)) {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        let tabs = CATEGORIES.len();
+        let mut step = 0;
+        if is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left) {
+            step = tabs - 1;
+        }
+        if is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right) {
+            step = 1;
+        }
+        if step != 0 {
+            self.category = (self.category + step) % tabs;
+            self.selected = 0;
+        }
+
+        // Keep the selection on screen
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+    }
+}
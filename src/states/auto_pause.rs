@@ -0,0 +1,205 @@
+use crate::data::GameConfig;
+use crate::plugins::{on_off, Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+
+/// Idle times the settings page steps through, in seconds; 0 is off
+const IDLE_CHOICES: [u32; 5] = [0, 30, 60, 120, 300];
+const COUNTDOWN_CHOICES: [u32; 4] = [0, 3, 5, 10];
+
+/// Why a run paused itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AwayReason {
+    FocusLost,
+    Idle,
+}
+
+/// Pauses a solo run the player has walked away from, and hands it back
+/// with a countdown so nobody gets hit the moment they return
+#[derive(Clone, Debug)]
+pub struct AutoPause {
+    /// Seconds without input before pausing; 0 never does
+    idle_after: f32,
+    on_focus_loss: bool,
+    mute: bool,
+    resume_delay: f32,
+    idle: f32,
+    away: Option<AwayReason>,
+    /// Seconds left before play picks up again
+    resuming: Option<f32>,
+}
+
+impl AutoPause {
+    pub fn from_config(config: &GameConfig) -> Self {
+        Self {
+            idle_after: config.idle_pause_seconds as f32,
+            on_focus_loss: config.pause_on_focus_loss,
+            mute: config.mute_when_away,
+            resume_delay: config.resume_countdown_seconds as f32,
+            idle: 0.0,
+            away: None,
+            resuming: None,
+        }
+    }
+
+    /// One frame of `dt` real seconds. `input` is whether the player touched
+    /// anything; `focus_lost` whether the window was left since last frame.
+    pub fn update(&mut self, dt: f32, input: bool, focus_lost: bool) {
+        if let Some(remaining) = &mut self.resuming {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                self.resuming = None;
+                self.away = None;
+                self.idle = 0.0;
+            }
+            return;
+        }
+
+        if self.away.is_some() {
+            if input {
+                self.resume();
+            }
+            return;
+        }
+
+        if focus_lost && self.on_focus_loss {
+            self.away = Some(AwayReason::FocusLost);
+            return;
+        }
+        self.idle = if input { 0.0 } else { self.idle + dt };
+        if self.idle_after > 0.0 && self.idle >= self.idle_after {
+            self.away = Some(AwayReason::Idle);
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.resume_delay > 0.0 {
+            self.resuming = Some(self.resume_delay);
+        } else {
+            self.away = None;
+            self.idle = 0.0;
+        }
+    }
+
+    /// Whether the run is held, away or counting down
+    pub fn holding(&self) -> bool {
+        self.away.is_some()
+    }
+
+    pub fn reason(&self) -> Option<AwayReason> {
+        self.away
+    }
+
+    /// Whole seconds left on the resume countdown, if it's running
+    pub fn countdown(&self) -> Option<u32> {
+        self.resuming.map(|remaining| remaining.ceil().max(1.0) as u32)
+    }
+
+    /// Whether audio should be silent right now
+    pub fn mutes(&self) -> bool {
+        self.mute && self.away.is_some()
+    }
+}
+
+/// Step to the choice after `current`, wrapping round
+fn next_choice(choices: &[u32], current: u32) -> u32 {
+    let index = choices.iter().position(|&choice| choice == current);
+    choices[index.map_or(0, |index| (index + 1) % choices.len())]
+}
+
+fn seconds_or_off(seconds: u32) -> String {
+    if seconds == 0 {
+        "OFF".to_string()
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// The "Pause & Audio" settings page
+pub struct AutoPausePlugin;
+
+impl Plugin for AutoPausePlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Pause & Audio",
+            entries: vec![
+                SettingsEntry {
+                    label: |config| {
+                        format!("Pause When Idle: {}", seconds_or_off(config.idle_pause_seconds))
+                    },
+                    activate: |config| {
+                        config.idle_pause_seconds =
+                            next_choice(&IDLE_CHOICES, config.idle_pause_seconds)
+                    },
+                },
+                SettingsEntry {
+                    label: |config| {
+                        format!("Pause When Window Is Left: {}", on_off(config.pause_on_focus_loss))
+                    },
+                    activate: |config| config.pause_on_focus_loss = !config.pause_on_focus_loss,
+                },
+                SettingsEntry {
+                    label: |config| format!("Mute While Away: {}", on_off(config.mute_when_away)),
+                    activate: |config| config.mute_when_away = !config.mute_when_away,
+                },
+                SettingsEntry {
+                    label: |config| {
+                        let seconds = config.resume_countdown_seconds;
+                        format!("Resume Countdown: {}", seconds_or_off(seconds))
+                    },
+                    activate: |config| {
+                        config.resume_countdown_seconds =
+                            next_choice(&COUNTDOWN_CHOICES, config.resume_countdown_seconds)
+                    },
+                },
+            ],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto_pause() -> AutoPause {
+        AutoPause::from_config(&GameConfig {
+            idle_pause_seconds: 30,
+            resume_countdown_seconds: 3,
+            ..GameConfig::default()
+        })
+    }
+
+    #[test]
+    fn idle_runs_pause_and_count_down_back_in() {
+        let mut pause = auto_pause();
+        pause.update(20.0, false, false);
+        pause.update(5.0, true, false);
+        pause.update(20.0, false, false);
+        assert!(!pause.holding(), "input resets the idle timer");
+
+        pause.update(10.0, false, false);
+        assert_eq!(pause.reason(), Some(AwayReason::Idle));
+        assert!(pause.mutes());
+
+        pause.update(0.1, true, false);
+        assert_eq!(pause.countdown(), Some(3));
+        pause.update(2.5, true, false);
+        assert_eq!(pause.countdown(), Some(1));
+        assert!(pause.holding());
+        pause.update(1.0, false, false);
+        assert!(!pause.holding());
+        assert_eq!(pause.countdown(), None);
+    }
+
+    #[test]
+    fn leaving_the_window_pauses_when_enabled() {
+        let mut pause = auto_pause();
+        pause.update(0.1, true, true);
+        assert_eq!(pause.reason(), Some(AwayReason::FocusLost));
+
+        let mut ignoring = AutoPause::from_config(&GameConfig {
+            pause_on_focus_loss: false,
+            ..GameConfig::default()
+        });
+        ignoring.update(0.1, true, true);
+        assert!(!ignoring.holding());
+    }
+}
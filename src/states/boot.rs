@@ -1,6 +1,17 @@
+use crate::assets::{self, AssetLoader};
+use crate::data::cloud::{self, CloudSync, SyncOutcome};
+use crate::data::crash::{self, PendingReport};
+use crate::data::events::{self, format_countdown};
+use crate::data::SaveBundle;
 use crate::states::State;
+use crate::ui::release_notes::{self, NoteStyle, ReleaseNotesPanel};
 use crate::updater::{UpdateStatus, Updater};
 use macroquad::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set once the first boot reaches the menu; boots after that are the menu's
+/// Check for Updates, which offers updates the player put off
+static BOOTED: AtomicBool = AtomicBool::new(false);
 
 pub struct BootState {
     timer: f32,
@@ -8,7 +19,20 @@ pub struct BootState {
     transition_to_menu: bool,
     updater: Updater,
     update_check_started: bool,
-    update_selected_option: usize, // 0 = Install Now, 1 = Skip
+    // 0 = Install Now, 1 = Background, 2 = Release Notes, 3 = Remind Later, 4 = Skip
+    update_selected_option: usize,
+    release_notes: Option<ReleaseNotesPanel>,
+    /// Cloud save sync; the menu waits for it so a download lands first
+    cloud: Option<CloudSync>,
+    /// Both sides changed: when this machine's data did, and the cloud's
+    cloud_conflict: Option<(u64, SaveBundle)>,
+    cloud_message: Option<(String, Color)>,
+    /// A crash from last time the player hasn't said whether to send
+    crash_report: Option<PendingReport>,
+    crash_message: Option<(String, Color)>,
+    /// Loading textures, fonts, sounds and data files; only the first boot
+    /// has any to load
+    assets: Option<AssetLoader>,
 }
 
 impl BootState {
@@ -20,6 +44,52 @@ impl BootState {
             updater: Updater::new(),
             update_check_started: false,
             update_selected_option: 0,
+            release_notes: None,
+            cloud: None,
+            cloud_conflict: None,
+            cloud_message: None,
+            crash_report: None,
+            crash_message: None,
+            assets: None,
+        }
+    }
+
+    fn finish_cloud_sync(&mut self, result: Result<SyncOutcome, String>) {
+        self.cloud_message = Some(match result {
+            Ok(SyncOutcome::Downloaded(remote)) => self.keep_cloud(&remote),
+            Ok(SyncOutcome::Conflict {
+                local_modified_at,
+                remote,
+            }) => {
+                self.cloud_conflict = Some((local_modified_at, remote));
+                return;
+            }
+            Ok(outcome @ SyncOutcome::Queued(_)) => (outcome.describe(), YELLOW),
+            Ok(outcome) => (outcome.describe(), GREEN),
+            Err(e) => (format!("Cloud sync failed: {}", e), RED),
+        });
+    }
+
+    fn poll_assets(&mut self) {
+        let Some(mut loader) = self.assets.take() else {
+            return;
+        };
+        loader.poll();
+        if !loader.is_done() {
+            self.assets = Some(loader);
+            return;
+        }
+        let (loaded, failed) = loader.finish();
+        for failure in failed {
+            crash::log(format!("Failed to load asset {}", failure));
+        }
+        assets::install(loaded);
+    }
+
+    fn keep_cloud(&mut self, remote: &SaveBundle) -> (String, Color) {
+        match cloud::apply_download(remote) {
+            Ok(count) => (format!("Downloaded {} files from the cloud", count), GREEN),
+            Err(e) => (format!("Cloud download failed: {}", e), RED),
         }
     }
 }
@@ -29,17 +99,35 @@ impl State for BootState {
         self.timer = 0.0;
         self.logo_alpha = 0.0;
         self.transition_to_menu = false;
+        crate::updater::check_for_new_version();
+        crate::data::events::refresh_in_background();
+        self.cloud = CloudSync::start();
+        self.crash_report = crash::pending_report();
+        if !assets::is_loaded() {
+            self.assets = Some(AssetLoader::start());
+        }
     }
 
-    fn exit(&mut self) {}
+    /// Getting past the boot screen is what proves an update launches
+    fn exit(&mut self) {
+        crate::updater::confirm_launch();
+        BOOTED.store(true, Ordering::Relaxed);
+    }
 
     fn update(&mut self, dt: f32) {
         self.timer += dt;
+        self.updater.poll();
+        self.poll_assets();
+
+        if let Some(result) = self.cloud.as_ref().and_then(CloudSync::poll) {
+            self.cloud = None;
+            self.finish_cloud_sync(result);
+        }
 
         // Start update check after 0.5 seconds
         if self.timer > 0.5 && !self.update_check_started {
             self.update_check_started = true;
-            self.updater.check_for_updates();
+            self.updater.check_for_updates(!BOOTED.load(Ordering::Relaxed));
         }
 
         // Handle logo fade in/out
@@ -74,13 +162,21 @@ impl State for BootState {
     }
 
     fn should_transition(&self) -> Option<crate::states::StateType> {
-        if self.transition_to_menu {
+        let cloud_settled = self.cloud.is_none() && self.cloud_conflict.is_none();
+        let settled = cloud_settled && self.crash_report.is_none() && self.assets.is_none();
+        if self.transition_to_menu && settled {
             Some(crate::states::StateType::Menu)
         } else {
             None
         }
     }
 
+    /// Escape cancels a download or closes the release notes rather than
+    /// skipping to the menu under them
+    fn handles_escape(&self) -> bool {
+        self.updater.status == UpdateStatus::Downloading || self.release_notes.is_some()
+    }
+
     fn fixed_update(&mut self, _dt: f64) {}
 
     fn render(&mut self, _interpolation: f32) {
@@ -125,15 +221,23 @@ impl State for BootState {
             }
             UpdateStatus::UpdateAvailable => {
                 self.render_update_dialog();
+                if let Some(panel) = &mut self.release_notes {
+                    panel.render();
+                }
             }
             UpdateStatus::Downloading => {
                 self.render_download_progress();
             }
             UpdateStatus::Installing => {
-                let text = "Installing update...";
-                let dims = measure_text(text, None, 24, 1.0);
+                let text = match self.updater.verification {
+                    Some(verification) => {
+                        format!("Installing update ({})...", verification.label())
+                    }
+                    None => "Installing update...".to_string(),
+                };
+                let dims = measure_text(&text, None, 24, 1.0);
                 draw_text(
-                    text,
+                    &text,
                     screen_width() * 0.5 - dims.width * 0.5,
                     screen_height() * 0.7,
                     24.0,
@@ -152,6 +256,22 @@ impl State for BootState {
                     );
                 }
             }
+            UpdateStatus::Deferred => {
+                if let Some(info) = &self.updater.info {
+                    let text = format!(
+                        "Update v{} is waiting in Check for Updates",
+                        info.latest_version
+                    );
+                    let dims = measure_text(&text, None, 20, 1.0);
+                    draw_text(
+                        &text,
+                        screen_width() * 0.5 - dims.width * 0.5,
+                        screen_height() * 0.7,
+                        20.0,
+                        Color::new(0.7, 0.7, 0.7, 0.8),
+                    );
+                }
+            }
             UpdateStatus::UpToDate => {
                 let text = "✓ Up to date";
                 let dims = measure_text(text, None, 20, 1.0);
@@ -165,9 +285,56 @@ impl State for BootState {
             }
             _ => {}
         }
+
+        self.render_asset_progress();
+        self.render_cloud_status();
+        self.render_crash_prompt();
     }
 
     fn handle_input(&mut self) {
+        if let Some((_, remote)) = &self.cloud_conflict {
+            if is_key_pressed(KeyCode::L) {
+                self.cloud_conflict = None;
+                self.cloud = CloudSync::keep_local();
+            } else if is_key_pressed(KeyCode::C) {
+                let remote = remote.clone();
+                self.cloud_conflict = None;
+                self.cloud_message = Some(self.keep_cloud(&remote));
+            }
+            return;
+        }
+
+        if let Some(report) = self.crash_report.take() {
+            if is_key_pressed(KeyCode::U) {
+                self.crash_message = Some(match report.send() {
+                    Ok(message) => (message, GREEN),
+                    Err(e) => (e, RED),
+                });
+            } else if is_key_pressed(KeyCode::K) {
+                report.keep_private();
+                self.crash_message = Some(("Crash report kept on this computer".into(), GRAY));
+            } else {
+                self.crash_report = Some(report);
+            }
+            return;
+        }
+
+        if self.updater.status == UpdateStatus::Downloading {
+            if is_key_pressed(KeyCode::Escape) {
+                self.updater.cancel();
+            }
+            return;
+        }
+
+        if let Some(panel) = &mut self.release_notes {
+            panel.handle_input();
+            let close = [KeyCode::Escape, KeyCode::Enter, KeyCode::Backspace];
+            if close.into_iter().any(is_key_pressed) {
+                self.release_notes = None;
+            }
+            return;
+        }
+
         // Handle update dialog input
         if self.updater.status == UpdateStatus::UpdateAvailable {
             if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
@@ -176,17 +343,33 @@ impl State for BootState {
                 }
             }
             if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
-                if self.update_selected_option < 1 {
+                if self.update_selected_option < 4 {
                     self.update_selected_option += 1;
                 }
             }
             if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) {
-                if self.update_selected_option == 0 {
-                    // Install update
-                    self.updater.download_and_install();
-                } else {
-                    // Skip update
-                    self.transition_to_menu = true;
+                match self.update_selected_option {
+                    0 => self.updater.download_and_install(),
+                    1 => {
+                        // Keep playing; it installs when the game exits
+                        self.updater.download_in_background();
+                        self.transition_to_menu = true;
+                    }
+                    2 => {
+                        if let Some(info) = &self.updater.info {
+                            let title = format!("Bas Veeg Arc v{}", info.latest_version);
+                            self.release_notes =
+                                Some(ReleaseNotesPanel::new(title, &info.changelog));
+                        }
+                    }
+                    3 => {
+                        self.updater.remind_later();
+                        self.transition_to_menu = true;
+                    }
+                    _ => {
+                        self.updater.skip_version();
+                        self.transition_to_menu = true;
+                    }
                 }
             }
         } else {
@@ -200,6 +383,97 @@ impl State for BootState {
 
 // Helper methods for rendering
 impl BootState {
+    fn render_cloud_status(&self) {
+        let y = screen_height() * 0.78;
+        let centered = |text: &str, y: f32, size: f32, color: Color| {
+            let dims = measure_text(text, None, size as u16, 1.0);
+            draw_text(text, screen_width() * 0.5 - dims.width * 0.5, y, size, color);
+        };
+
+        if let Some((local_modified_at, remote)) = &self.cloud_conflict {
+            draw_rectangle(
+                0.0,
+                0.0,
+                screen_width(),
+                screen_height(),
+                Color::new(0.0, 0.0, 0.0, 0.7),
+            );
+            let now = events::now();
+            let local = format!(
+                "This computer: changed {} ago",
+                format_countdown(now.saturating_sub(*local_modified_at))
+            );
+            let cloud = format!(
+                "Cloud: uploaded {} ago",
+                format_countdown(now.saturating_sub(remote.exported_at))
+            );
+            let mid = screen_height() * 0.5;
+            centered("CLOUD SAVE CONFLICT", mid - 60.0, 40.0, YELLOW);
+            let both = "Both this computer and the cloud changed since the last sync";
+            centered(both, mid - 20.0, 22.0, WHITE);
+            centered(&local, mid + 20.0, 22.0, LIGHTGRAY);
+            centered(&cloud, mid + 50.0, 22.0, LIGHTGRAY);
+            centered("L = Keep this computer's   C = Keep the cloud's", mid + 100.0, 24.0, WHITE);
+        } else if self.cloud.is_some() {
+            centered("Syncing save with the cloud...", y, 20.0, LIGHTGRAY);
+        } else if let Some((message, color)) = &self.cloud_message {
+            centered(message, y, 20.0, *color);
+        }
+    }
+
+    /// A bar along the bottom while assets load, named by the one loading
+    fn render_asset_progress(&self) {
+        let Some(loader) = &self.assets else {
+            return;
+        };
+        let width = 400.0;
+        let x = screen_width() * 0.5 - width * 0.5;
+        let y = screen_height() * 0.9;
+        draw_rectangle(x, y, width, 8.0, Color::new(0.2, 0.2, 0.2, 1.0));
+        let fill = width * loader.progress();
+        draw_rectangle(x, y, fill, 8.0, Color::new(0.4, 0.6, 1.0, 1.0));
+
+        let label = match loader.current() {
+            Some(name) => format!("Loading {} ({:.0}%)", name, loader.progress() * 100.0),
+            None => "Loading...".to_string(),
+        };
+        let dims = measure_text(&label, None, 18, 1.0);
+        draw_text(&label, screen_width() * 0.5 - dims.width * 0.5, y - 10.0, 18.0, GRAY);
+    }
+
+    /// Asks about last time's crash once the cloud has nothing to ask
+    fn render_crash_prompt(&self) {
+        let centered = |text: &str, y: f32, size: f32, color: Color| {
+            let dims = measure_text(text, None, size as u16, 1.0);
+            draw_text(text, screen_width() * 0.5 - dims.width * 0.5, y, size, color);
+        };
+
+        let Some(report) = &self.crash_report else {
+            if let Some((message, color)) = &self.crash_message {
+                centered(message, screen_height() * 0.82, 20.0, *color);
+            }
+            return;
+        };
+        if self.cloud_conflict.is_some() {
+            return;
+        }
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            screen_width(),
+            screen_height(),
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        let panic: String = report.panic().chars().take(80).collect();
+        let mid = screen_height() * 0.5;
+        centered("THE GAME CRASHED LAST TIME", mid - 60.0, 40.0, YELLOW);
+        centered(&panic, mid - 20.0, 20.0, LIGHTGRAY);
+        let contents = "The report has the error, the game's recent log and what was on screen";
+        centered(contents, mid + 20.0, 20.0, LIGHTGRAY);
+        centered("U = Send the report   K = Keep it private", mid + 80.0, 24.0, WHITE);
+    }
+
     fn render_update_dialog(&self) {
         // Semi-transparent overlay
         draw_rectangle(
@@ -212,7 +486,7 @@ impl BootState {
 
         // Dialog box
         let box_width = 600.0;
-        let box_height = 400.0;
+        let box_height = 560.0;
         let box_x = screen_width() * 0.5 - box_width * 0.5;
         let box_y = screen_height() * 0.5 - box_height * 0.5;
 
@@ -265,13 +539,17 @@ impl BootState {
                 Color::new(0.8, 0.8, 0.8, 1.0),
             );
 
-            // Show first 3 lines of changelog
-            let lines: Vec<&str> = info.changelog.lines().take(3).collect();
-            for (i, line) in lines.iter().enumerate() {
-                let truncated = if line.len() > 60 {
-                    format!("{}...", &line[..60])
+            // Show the first 3 lines of the changelog; the rest is in the notes
+            let lines = release_notes::parse_markdown(&info.changelog);
+            let preview = lines
+                .iter()
+                .filter(|line| !matches!(line.style, NoteStyle::Heading | NoteStyle::Gap))
+                .take(3);
+            for (i, line) in preview.enumerate() {
+                let truncated = if line.text.chars().count() > 60 {
+                    format!("{}...", line.text.chars().take(60).collect::<String>())
                 } else {
-                    line.to_string()
+                    line.text.clone()
                 };
                 draw_text(
                     &truncated,
@@ -284,9 +562,15 @@ impl BootState {
         }
 
         // Options
-        let options = ["Install Now & Restart", "Skip This Update"];
+        let options = [
+            "Install Now & Restart",
+            "Download While Playing",
+            "Release Notes",
+            "Remind Me Later",
+            "Skip This Version",
+        ];
         for (i, option) in options.iter().enumerate() {
-            let y = box_y + box_height - 100.0 + i as f32 * 50.0;
+            let y = box_y + 290.0 + i as f32 * 48.0;
             let is_selected = i == self.update_selected_option;
 
             if is_selected {
@@ -355,7 +639,7 @@ impl BootState {
             Color::new(0.2, 0.2, 0.2, 1.0),
         );
 
-        let progress = self.updater.download_progress / 100.0;
+        let progress = (self.updater.download_progress / 100.0).clamp(0.0, 1.0);
         draw_rectangle(
             bar_x,
             bar_y,
@@ -366,14 +650,35 @@ impl BootState {
 
         draw_rectangle_lines(bar_x, bar_y, bar_width, bar_height, 2.0, WHITE);
 
-        let percent = format!("{}%", self.updater.download_progress as u32);
-        let percent_dims = measure_text(&percent, None, 20, 1.0);
+        // Without a size from the server, only the bytes so far can be shown
+        let megabytes = |bytes: u64| bytes as f32 / (1024.0 * 1024.0);
+        let downloaded = megabytes(self.updater.downloaded_bytes);
+        let amount = match self.updater.total_bytes {
+            Some(total) => format!(
+                "{:.1} / {:.1} MB ({}%)",
+                downloaded,
+                megabytes(total),
+                self.updater.download_progress as u32
+            ),
+            None => format!("{:.1} MB", downloaded),
+        };
+        let amount_dims = measure_text(&amount, None, 20, 1.0);
         draw_text(
-            &percent,
-            screen_width() * 0.5 - percent_dims.width * 0.5,
+            &amount,
+            screen_width() * 0.5 - amount_dims.width * 0.5,
             bar_y + 50.0,
             20.0,
             WHITE,
         );
+
+        let hint = "ESC to cancel";
+        let hint_dims = measure_text(hint, None, 18, 1.0);
+        draw_text(
+            hint,
+            screen_width() * 0.5 - hint_dims.width * 0.5,
+            bar_y + 80.0,
+            18.0,
+            GRAY,
+        );
     }
 }
@@ -1,7 +1,21 @@
-use crate::data::{Character, CharacterId, CHARACTERS};
+use crate::audio::stingers::{victory_theme, ALT_STINGER_LEVEL};
+use crate::combat::{CharacterMoveset, CharacterStats};
+use crate::data::cosmetics::{
+    slot_cosmetics, Cosmetic, CosmeticLook, CosmeticSlot, CosmeticsProgress, Outfit, Unlock,
+};
+use crate::data::poses::{character_poses, PoseChoice, ALT_INTRO_LEVEL, ALT_VICTORY_LEVEL};
+use crate::data::{crash, Character, CharacterId, ShopManager, CHARACTERS};
+use crate::ecs::AllyPreset;
+use crate::progression::roster::{Access, Roster, TrialRun, UNLOCK_COST};
+use crate::progression::MasteryManager;
+use crate::render::atlas::SpriteBatch;
+use crate::render::{draw_outfit, CharacterSheets, SpriteAnimation};
 use crate::states::{State, StateType};
 use macroquad::prelude::*;
 
+/// Reads one stat off a character for the stat bars
+type StatOf = fn(&CharacterStats) -> f32;
+
 pub struct CharacterSelectState {
     selected_index: usize,
     selected_character: Option<CharacterId>,
@@ -10,6 +24,27 @@ pub struct CharacterSelectState {
     animation_time: f32,
     hover_pulse: f32,
     chars_per_row: usize, // Track the current grid layout
+    ally_preset: AllyPreset,
+    pose_choice: PoseChoice,
+    /// Second character brought along for assists and tags
+    tag_partner: Option<CharacterId>,
+    /// Saved mastery for every character on the active profile
+    mastery: MasteryManager,
+    cosmetics: CosmeticsProgress,
+    /// Arc Tokens cosmetics are bought with
+    shop: ShopManager,
+    cosmetic_slot: CosmeticSlot,
+    /// Cosmetic being looked at in the slot, by its place in the slot's list;
+    /// `None` shows what's equipped
+    browsing: Option<usize>,
+    cosmetic_status: Option<String>,
+    /// Who the profile has unlocked, and whether today's trial run is left
+    roster: Roster,
+    roster_status: Option<String>,
+    /// Combat stats for each entry of `CHARACTERS`, in the same order
+    stats: Vec<CharacterStats>,
+    sheets: CharacterSheets,
+    batch: SpriteBatch,
 }
 
 impl CharacterSelectState {
@@ -22,7 +57,307 @@ impl CharacterSelectState {
             animation_time: 0.0,
             hover_pulse: 0.0,
             chars_per_row: 5, // Default to 5
+            ally_preset: crate::data::get_ally_preset(),
+            pose_choice: crate::data::get_pose_choice(),
+            tag_partner: crate::data::get_tag_partner(),
+            mastery: MasteryManager::load(),
+            cosmetics: CosmeticsProgress::load(),
+            shop: ShopManager::load(),
+            cosmetic_slot: CosmeticSlot::Palette,
+            browsing: None,
+            cosmetic_status: None,
+            roster: Roster::load(),
+            roster_status: None,
+            stats: CHARACTERS
+                .iter()
+                .map(|character| {
+                    CharacterMoveset::for_character(character.id.to_character_type()).stats
+                })
+                .collect(),
+            sheets: CharacterSheets::new(),
+            batch: SpriteBatch::default(),
+        }
+    }
+
+    fn highlighted_level(&self) -> (CharacterId, u32) {
+        let id = CHARACTERS[self.selected_index].id;
+        (id, self.mastery.get_mastery(id).map_or(1, |m| m.level))
+    }
+
+    /// Where the equipped cosmetic sits in the slot's list
+    fn equipped_index(&self, id: CharacterId) -> Option<usize> {
+        let equipped = self.cosmetics.loadout(id).slot(self.cosmetic_slot)?;
+        slot_cosmetics(id, self.cosmetic_slot).position(|cosmetic| cosmetic.id == equipped.id)
+    }
+
+    /// The cosmetic on show in the slot: the one being browsed, or the one equipped
+    fn browsed(&self) -> Option<&'static Cosmetic> {
+        let id = CHARACTERS[self.selected_index].id;
+        let index = self.browsing.or_else(|| self.equipped_index(id))?;
+        slot_cosmetics(id, self.cosmetic_slot).nth(index)
+    }
+
+    /// Step to the slot's next cosmetic, then back to none. Anything
+    /// available goes straight on; locked ones are only previewed.
+    fn cycle_cosmetic(&mut self) {
+        let (id, level) = self.highlighted_level();
+        let count = slot_cosmetics(id, self.cosmetic_slot).count();
+        let next = self
+            .browsing
+            .or_else(|| self.equipped_index(id))
+            .map_or(0, |index| index + 1);
+        self.browsing = Some(next).filter(|&next| next < count);
+        self.cosmetic_status = None;
+
+        let slot = self.cosmetic_slot;
+        let browsed = self.browsing.and_then(|index| slot_cosmetics(id, slot).nth(index));
+        match browsed {
+            Some(cosmetic) if !self.cosmetics.available(cosmetic, id, level) => return,
+            cosmetic => self.cosmetics.equip(id, self.cosmetic_slot, cosmetic),
+        }
+        self.save_cosmetics();
+    }
+
+    fn buy_cosmetic(&mut self) {
+        let Some(cosmetic) = self.browsed() else {
+            return;
+        };
+        let (id, level) = self.highlighted_level();
+        if self.cosmetics.available(cosmetic, id, level) {
+            self.cosmetic_status = Some(format!("{} is already unlocked", cosmetic.name));
+            return;
+        }
+        match self.cosmetics.buy(cosmetic, &mut self.shop) {
+            Ok(()) => {
+                self.cosmetics.equip(id, self.cosmetic_slot, Some(cosmetic));
+                self.cosmetic_status = Some(format!("{} bought and equipped", cosmetic.name));
+                if let Err(e) = self.shop.save() {
+                    self.cosmetic_status = Some(e);
+                }
+                self.save_cosmetics();
+            }
+            Err(e) => self.cosmetic_status = Some(e),
+        }
+    }
+
+    /// Spend Arc Tokens on the highlighted character, if they're locked
+    fn unlock_character(&mut self) {
+        let character = &CHARACTERS[self.selected_index];
+        if let Err(e) = self.roster.unlock(character.id, &mut self.shop) {
+            self.roster_status = Some(e);
+            return;
+        }
+        self.roster_status = Some(format!("{} unlocked", character.name));
+    }
+
+    /// Take the highlighted character into a run, on today's trial if they're
+    /// locked. Whether the run can go ahead.
+    fn pick_character(&mut self, character_id: CharacterId) -> bool {
+        let trial = match self.roster.access(character_id) {
+            Access::Unlocked => None,
+            Access::Trial => {
+                if let Err(e) = self.roster.start_trial(character_id) {
+                    self.roster_status = Some(e);
+                    return false;
+                }
+                if let Err(e) = self.roster.save() {
+                    crash::log(format!("Failed to save the trial run: {}", e));
+                }
+                Some(TrialRun {
+                    character: character_id,
+                    previous: crate::data::get_selected_character(),
+                })
+            }
+            Access::Locked => {
+                self.roster_status = self.roster.start_trial(character_id).err();
+                return false;
+            }
+        };
+        crate::data::set_trial_run(trial);
+        true
+    }
+
+    /// Lock state of the highlighted character and how to get them
+    fn draw_roster(&self, y: f32, size: f32) {
+        let id = CHARACTERS[self.selected_index].id;
+        let text = match (&self.roster_status, self.roster.access(id)) {
+            (Some(status), _) => status.clone(),
+            (None, Access::Unlocked) => return,
+            (None, Access::Trial) => format!(
+                "LOCKED - ENTER: FREE TRIAL RUN ({} LEFT TODAY)  |  U: UNLOCK FOR {} ARC TOKENS \
+                 (YOU HAVE {})",
+                self.roster.trial_runs_left(),
+                UNLOCK_COST,
+                self.shop.currency()
+            ),
+            (None, Access::Locked) => format!(
+                "LOCKED - TRIAL USED TODAY  |  U: UNLOCK FOR {} ARC TOKENS (YOU HAVE {})",
+                UNLOCK_COST,
+                self.shop.currency()
+            ),
+        };
+        let dims = measure_text(&text, None, size as u16, 1.0);
+        let x = screen_width() * 0.5 - dims.width * 0.5;
+        draw_text(&text, x, y, size, ORANGE);
+    }
+
+    fn save_cosmetics(&mut self) {
+        if let Err(e) = self.cosmetics.save() {
+            self.cosmetic_status = Some(e);
+        }
+    }
+
+    /// What's on in each slot, and how to get the one being browsed
+    fn draw_cosmetics(&self, y: f32, size: f32) {
+        let (id, level) = self.highlighted_level();
+        let loadout = self.cosmetics.loadout(id);
+        let slots: Vec<String> = CosmeticSlot::ALL
+            .iter()
+            .map(|&slot| {
+                let name = loadout
+                    .slot(slot)
+                    .filter(|cosmetic| self.cosmetics.available(cosmetic, id, level))
+                    .map_or("None", |cosmetic| cosmetic.name);
+                if slot == self.cosmetic_slot {
+                    format!("[{}: {}]", slot.label(), name)
+                } else {
+                    format!("{}: {}", slot.label(), name)
+                }
+            })
+            .collect();
+        let loadout_text = slots.join("  ");
+        let dims = measure_text(&loadout_text, None, size as u16, 1.0);
+        let x = screen_width() * 0.5 - dims.width * 0.5;
+        draw_text(&loadout_text, x, y, size, Color::new(0.75, 0.85, 1.0, 0.95));
+
+        let detail = match (&self.cosmetic_status, self.browsed()) {
+            (Some(status), _) => status.clone(),
+            (None, Some(cosmetic)) if !self.cosmetics.available(cosmetic, id, level) => {
+                match cosmetic.unlock {
+                    Unlock::Tokens(cost) => format!(
+                        "{}: {} ARC TOKENS (YOU HAVE {}) - B TO BUY",
+                        cosmetic.name,
+                        cost,
+                        self.shop.currency()
+                    ),
+                    Unlock::Mastery(required) => {
+                        format!("{}: UNLOCKS AT MASTERY {}", cosmetic.name, required)
+                    }
+                }
+            }
+            _ => return,
+        };
+        let dims = measure_text(&detail, None, size as u16, 1.0);
+        let x = screen_width() * 0.5 - dims.width * 0.5;
+        draw_text(&detail, x, y - size - 4.0, size, Color::new(1.0, 0.6, 0.4, 0.95));
+    }
+
+    /// Tint and outfit for the preview: what's equipped, with the cosmetic
+    /// being browsed tried on over it even if it's still locked
+    fn preview_look(&self, id: CharacterId, level: u32) -> (Color, Option<(Outfit, Color)>) {
+        let equipped = self.cosmetics.equipped(id, level);
+        let mut tint = equipped.tint.unwrap_or_else(|| {
+            self.mastery.get_mastery(id).map_or(WHITE, |mastery| mastery.skin_tint())
+        });
+        let mut outfit = equipped.outfit;
+        match self.browsed().map(|cosmetic| &cosmetic.look) {
+            Some(CosmeticLook::Palette([r, g, b])) => tint = Color::new(*r, *g, *b, 1.0),
+            Some(CosmeticLook::Outfit(kind, [r, g, b])) => {
+                outfit = Some((*kind, Color::new(*r, *g, *b, 1.0)))
+            }
+            _ => {}
+        }
+        (tint, outfit)
+    }
+
+    /// The highlighted character idling on a plinth, in its current look
+    fn draw_idle_preview(&mut self, x: f32, y: f32, width: f32, height: f32, size: f32) {
+        let character = &CHARACTERS[self.selected_index];
+        let color = Self::get_character_color(character.id);
+        draw_rectangle(x, y, width, height, Color::new(0.05, 0.05, 0.08, 0.9));
+        draw_rectangle_lines(x, y, width, height, 2.0, color);
+        draw_text("PREVIEW", x + 12.0, y + size + 8.0, size, LIGHTGRAY);
+
+        let (id, level) = self.highlighted_level();
+        let pos = vec2(x + width * 0.5, y + height * 0.5 + 10.0);
+        draw_ellipse(pos.x, pos.y + 86.0, 48.0, 14.0, 0.0, Color::new(0.0, 0.0, 0.0, 0.5));
+        let (tint, outfit) = self.preview_look(id, level);
+        self.sheets.queue(
+            &mut self.batch,
+            CharacterSheets::sheet_for(id),
+            SpriteAnimation::Idle,
+            self.animation_time,
+            0.0,
+            pos,
+            false,
+            tint,
+        );
+        self.sheets.flush(&mut self.batch);
+        if let Some((outfit, color)) = outfit {
+            draw_outfit(outfit, color, pos, false);
+        }
+
+        let name_dims = measure_text(character.name, None, size as u16, 1.0);
+        let name_y = y + height - size - 12.0;
+        draw_text(character.name, pos.x - name_dims.width * 0.5, name_y, size, color);
+        if let Some(mastery) = self.mastery.get_mastery(id) {
+            let rank = format!("{}  LV {}", mastery.rank.to_string().to_uppercase(), mastery.level);
+            let dims = measure_text(&rank, None, size as u16, 1.0);
+            let rank_y = name_y + size + 4.0;
+            draw_text(&rank, pos.x - dims.width * 0.5, rank_y, size, mastery.rank.to_color());
+        }
+    }
+
+    /// Bars for the highlighted character's combat stats, each measured
+    /// against the best on the roster, then its ability's timings
+    fn draw_stat_bars(&self, x: f32, y: f32, width: f32, size: f32) {
+        const BARS: [(&str, StatOf); 7] = [
+            ("HEALTH", |stats| stats.max_health),
+            ("POWER", |stats| stats.base_damage),
+            ("SPEED", |stats| stats.base_speed),
+            ("ATTACK RATE", |stats| stats.attack_speed),
+            ("WEIGHT", |stats| stats.weight),
+            ("DASH", |stats| stats.dash_distance),
+            ("BLOCK", |stats| stats.block_strength),
+        ];
+        let character = &CHARACTERS[self.selected_index];
+        let color = Self::get_character_color(character.id);
+        let stats = &self.stats[self.selected_index];
+        let row_height = size * 2.4;
+        let height = size * 4.0 + row_height * (BARS.len() + 2) as f32;
+        draw_rectangle(x, y, width, height, Color::new(0.05, 0.05, 0.08, 0.9));
+        draw_rectangle_lines(x, y, width, height, 2.0, color);
+        draw_text("STATS", x + 12.0, y + size + 8.0, size, LIGHTGRAY);
+
+        let bar_x = x + 12.0;
+        let bar_width = width - 24.0;
+        for (i, (label, value_of)) in BARS.iter().enumerate() {
+            let row_y = y + size * 2.5 + i as f32 * row_height;
+            let value = value_of(stats);
+            let best = self.stats.iter().map(value_of).fold(f32::EPSILON, f32::max);
+            let value_text = if value >= 10.0 {
+                format!("{:.0}", value)
+            } else {
+                format!("{:.2}", value)
+            };
+            let value_dims = measure_text(&value_text, None, size as u16, 1.0);
+            draw_text(label, bar_x, row_y + size, size, WHITE);
+            let value_x = bar_x + bar_width - value_dims.width;
+            draw_text(&value_text, value_x, row_y + size, size, YELLOW);
+
+            let fill_y = row_y + size + 4.0;
+            let fraction = (value / best).clamp(0.0, 1.0);
+            draw_rectangle(bar_x, fill_y, bar_width, 6.0, Color::new(0.15, 0.15, 0.15, 0.9));
+            draw_rectangle(bar_x, fill_y, bar_width * fraction, 6.0, color);
         }
+
+        let ability_y = y + size * 2.5 + BARS.len() as f32 * row_height + size;
+        draw_text(character.ability_name, bar_x, ability_y, size, color);
+        let timings = format!(
+            "{}s active  |  {}s cooldown",
+            character.duration, character.cooldown
+        );
+        draw_text(&timings, bar_x, ability_y + size + 6.0, size, LIGHTGRAY);
     }
 
     pub fn get_selected_character(&self) -> Option<CharacterId> {
@@ -65,6 +400,52 @@ impl CharacterSelectState {
         }
     }
 
+    /// Rank, progress to the next level, the next unlock and the equipped
+    /// palette swap, along the right of the details panel
+    fn draw_mastery_details(&self, id: CharacterId, margin: f32, y: f32, scale_factor: f32) {
+        let Some(mastery) = self.mastery.get_mastery(id) else {
+            return;
+        };
+        let x = screen_width() - margin - 330.0 * scale_factor;
+        let width = 310.0 * scale_factor;
+        let size = (14.0 * scale_factor).clamp(11.0, 16.0);
+        let color = mastery.rank.to_color();
+
+        let rank = mastery.rank.to_string().to_uppercase();
+        let heading = format!("MASTERY: {}  LV {}", rank, mastery.level);
+        draw_text(&heading, x, y + 20.0 * scale_factor, size, color);
+
+        let bar_y = y + 28.0 * scale_factor;
+        let bar_height = 8.0 * scale_factor;
+        let fraction = (mastery.get_progress_percent() / 100.0).clamp(0.0, 1.0);
+        draw_rectangle(x, bar_y, width, bar_height, Color::new(0.15, 0.15, 0.15, 0.9));
+        draw_rectangle(x, bar_y, width * fraction, bar_height, color);
+        draw_rectangle_lines(x, bar_y, width, bar_height, 1.0, GRAY);
+
+        let next = match mastery.next_milestone() {
+            Some(milestone) => {
+                let (kind, reward) = milestone.reward.describe();
+                format!("NEXT (LV {}): {} - {}", milestone.level, kind, reward)
+            }
+            None => "Every mastery reward unlocked".to_string(),
+        };
+        draw_text(&next, x, y + 56.0 * scale_factor, size, LIGHTGRAY);
+
+        let skin = match mastery.equipped_skin.as_str() {
+            "" => "Default",
+            skin => skin,
+        };
+        let unlocks = format!(
+            "PALETTE: {}  |  VOICE LINES: {}{}",
+            skin,
+            mastery.unlocked_voice_lines.len(),
+            if mastery.has_signature_move() { "  |  SIGNATURE" } else { "" }
+        );
+        let tint = mastery.skin_tint();
+        let tint = Color::new(tint.r, tint.g, tint.b, 0.95);
+        draw_text(&unlocks, x, y + 76.0 * scale_factor, size, tint);
+    }
+
     fn draw_character_portrait(
         &self,
         x: f32,
@@ -336,6 +717,14 @@ impl State for CharacterSelectState {
         self.selected_index = 0;
         self.selected_character = None;
         self.transition_to = None;
+        // Runs since last time may have paid out tokens and mastery
+        self.mastery = MasteryManager::load();
+        self.cosmetics = CosmeticsProgress::load();
+        self.shop = ShopManager::load();
+        self.browsing = None;
+        self.cosmetic_status = None;
+        self.roster = Roster::load();
+        self.roster_status = None;
         self.animation_time = 0.0;
         self.hover_pulse = 1.0;
     }
@@ -512,6 +901,25 @@ impl State for CharacterSelectState {
                 );
             }
 
+            // Mastery badge in the corner
+            if let Some(mastery) = self.mastery.get_mastery(character.id) {
+                let badge = format!("LV {}", mastery.level);
+                let badge_size = (13.0 * scale_factor).clamp(10.0, 16.0);
+                let badge_dims = measure_text(&badge, None, badge_size as u16, 1.0);
+                draw_text(
+                    &badge,
+                    x + card_width - badge_dims.width - 8.0,
+                    card_y + badge_size + 4.0,
+                    badge_size,
+                    mastery.rank.to_color(),
+                );
+            }
+
+            if !self.roster.is_unlocked(character.id) {
+                let badge_size = (13.0 * scale_factor).clamp(10.0, 16.0);
+                draw_text("LOCKED", x + 8.0, card_y + badge_size + 4.0, badge_size, ORANGE);
+            }
+
             // Character portrait - scaled
             self.draw_character_portrait(
                 x + card_width * 0.5,
@@ -617,6 +1025,16 @@ impl State for CharacterSelectState {
             );
         }
 
+        // Idle preview and stat bars in the margins either side of the grid
+        let side_width = (start_x - 40.0).min(280.0);
+        if side_width >= 160.0 {
+            let side_x = (start_x - side_width) * 0.5;
+            let side_size = (15.0 * scale_factor).clamp(12.0, 18.0);
+            self.draw_idle_preview(side_x, start_y, side_width, 300.0, side_size);
+            let stats_x = screen_width() - side_x - side_width;
+            self.draw_stat_bars(stats_x, start_y, side_width, side_size);
+        }
+
         // Details panel for selected character - scaled
         let character = &CHARACTERS[self.selected_index];
         let char_color = Self::get_character_color(character.id);
@@ -659,6 +1077,8 @@ impl State for CharacterSelectState {
             GOLD,
         );
 
+        self.draw_mastery_details(character.id, detail_margin, detail_y, scale_factor);
+
         // Effects section - scaled
         let effects_label = "ABILITY EFFECTS:";
         draw_text(effects_label, detail_margin + 15.0 * scale_factor, detail_y + 78.0 * scale_factor, label_size, char_color);
@@ -741,7 +1161,71 @@ impl State for CharacterSelectState {
             Color::new(0.0, 0.0, 0.0, 0.8),
         );
 
-        let instructions = "ARROW KEYS: Navigate  |  ENTER: Select  |  ESC: Back";
+        // Ally competence and the tag partner for this run
+        let partner = self
+            .tag_partner
+            .map_or("NONE", |partner| Character::get_by_id(partner).name);
+        let ally_text = format!(
+            "ALLIES: {}  -  {}  |  PARTNER: {}",
+            self.ally_preset.name().to_uppercase(),
+            self.ally_preset.description(),
+            partner.to_uppercase()
+        );
+        let ally_size = (14.0 * scale_factor).clamp(11.0, 18.0);
+        let ally_dims = measure_text(&ally_text, None, ally_size as u16, 1.0);
+        draw_text(
+            &ally_text,
+            screen_width() * 0.5 - ally_dims.width * 0.5,
+            instructions_y - 30.0 * scale_factor,
+            ally_size,
+            Color::new(0.3, 1.0, 0.5, 0.95),
+        );
+
+        // Intro/victory poses; alternates need mastery with the highlighted character
+        let character_id = CHARACTERS[self.selected_index].id;
+        let level = self.mastery.get_mastery(character_id).map_or(1, |m| m.level);
+        let poses = character_poses(character_id);
+        let pose_label = |name: &str, slot: usize, required: u32| {
+            if slot > 0 && level < required {
+                format!("{} (MASTERY {})", name, required)
+            } else {
+                name.to_string()
+            }
+        };
+        let stingers = &victory_theme(character_id).stingers;
+        let theme = if level >= ALT_STINGER_LEVEL {
+            format!("{} / {}", stingers[0].name, stingers[1].name)
+        } else {
+            format!("{} (+1 AT MASTERY {})", stingers[0].name, ALT_STINGER_LEVEL)
+        };
+        let pose_text = format!(
+            "INTRO: {}  |  VICTORY: {}  |  THEME: {}",
+            pose_label(
+                poses.intros[self.pose_choice.intro].name,
+                self.pose_choice.intro,
+                ALT_INTRO_LEVEL
+            ),
+            pose_label(
+                poses.victories[self.pose_choice.victory].name,
+                self.pose_choice.victory,
+                ALT_VICTORY_LEVEL
+            ),
+            theme,
+        );
+        let pose_dims = measure_text(&pose_text, None, ally_size as u16, 1.0);
+        draw_text(
+            &pose_text,
+            screen_width() * 0.5 - pose_dims.width * 0.5,
+            instructions_y - 50.0 * scale_factor,
+            ally_size,
+            Color::new(1.0, 0.85, 0.4, 0.95),
+        );
+        self.draw_cosmetics(instructions_y - 70.0 * scale_factor, ally_size);
+        self.draw_roster(instructions_y - 110.0 * scale_factor, ally_size);
+
+        let instructions = "ARROW KEYS: Navigate  |  ENTER: Select  |  T: Partner  |  \
+            TAB: Ally AI  |  I/V: Poses  |  P: Palette  |  C/X: Cosmetics  |  B: Buy  |  \
+            U: Unlock  |  ESC: Back";
         let inst_size = (16.0 * scale_factor).min(20.0).max(12.0);
         let inst_dims = measure_text(instructions, None, inst_size as u16, 1.0);
         draw_text(
@@ -766,6 +1250,8 @@ impl State for CharacterSelectState {
     }
 
     fn handle_input(&mut self) {
+        let previous_index = self.selected_index;
+
         // Navigate left
         if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
             if self.selected_index % self.chars_per_row > 0 {
@@ -814,11 +1300,83 @@ impl State for CharacterSelectState {
             }
         }
 
+        // Cycle ally competence
+        if is_key_pressed(KeyCode::Tab) {
+            self.ally_preset = self.ally_preset.next();
+            crate::data::set_ally_preset(self.ally_preset);
+        }
+
+        // Bring the highlighted character along as tag partner, or leave them.
+        // Trials are for the character being played, not partners.
+        if is_key_pressed(KeyCode::T) {
+            let character = &CHARACTERS[self.selected_index];
+            let character_id = character.id;
+            if !self.roster.is_unlocked(character_id) {
+                self.roster_status = Some(format!("Unlock {} to bring them along", character.name));
+                return;
+            }
+            self.tag_partner = match self.tag_partner {
+                Some(partner) if partner == character_id => None,
+                _ => Some(character_id),
+            };
+            crate::data::set_tag_partner(self.tag_partner);
+        }
+
+        // Cycle intro and victory pose
+        if is_key_pressed(KeyCode::I) {
+            self.pose_choice.intro = (self.pose_choice.intro + 1) % 2;
+            crate::data::set_pose_choice(self.pose_choice);
+        }
+        if is_key_pressed(KeyCode::V) {
+            self.pose_choice.victory = (self.pose_choice.victory + 1) % 2;
+            crate::data::set_pose_choice(self.pose_choice);
+        }
+
+        // Cycle the highlighted character's unlocked palette swaps
+        if is_key_pressed(KeyCode::P) {
+            let character_id = CHARACTERS[self.selected_index].id;
+            if let Some(mastery) = self.mastery.get_mastery_mut(character_id) {
+                mastery.cycle_skin();
+                if let Err(e) = mastery.save() {
+                    crash::log(e.to_string());
+                }
+            }
+        }
+
+        // Cosmetics: C picks the slot, X steps through it, B buys what's shown
+        if self.selected_index != previous_index {
+            self.browsing = None;
+            self.cosmetic_status = None;
+            self.roster_status = None;
+        }
+        if is_key_pressed(KeyCode::C) {
+            self.cosmetic_slot = self.cosmetic_slot.next();
+            self.browsing = None;
+            self.cosmetic_status = None;
+        }
+        if is_key_pressed(KeyCode::X) {
+            self.cycle_cosmetic();
+        }
+        if is_key_pressed(KeyCode::B) {
+            self.buy_cosmetic();
+        }
+        if is_key_pressed(KeyCode::U) {
+            self.unlock_character();
+        }
+
         // Select character
         if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) {
             let character_id = CHARACTERS[self.selected_index].id;
+            if !self.pick_character(character_id) {
+                return;
+            }
             self.selected_character = Some(character_id);
             crate::data::set_selected_character(character_id);
+            // Nobody tags out to themselves
+            if self.tag_partner == Some(character_id) {
+                self.tag_partner = None;
+                crate::data::set_tag_partner(None);
+            }
             self.transition_to = Some(StateType::Gameplay);
         }
 
@@ -4,6 +4,9 @@ use macroquad::prelude::*;
 pub struct ControlsState {
     transition_to: Option<StateType>,
     scroll_offset: f32,
+    /// Opened from the pause menu; going back pops to the run
+    over_run: bool,
+    done: bool,
 }
 
 impl ControlsState {
@@ -11,6 +14,8 @@ impl ControlsState {
         Self {
             transition_to: None,
             scroll_offset: 0.0,
+            over_run: false,
+            done: false,
         }
     }
 }
@@ -19,6 +24,8 @@ impl State for ControlsState {
     fn enter(&mut self) {
         self.transition_to = None;
         self.scroll_offset = 0.0;
+        self.over_run = crate::data::take_over_run();
+        self.done = false;
     }
 
     fn exit(&mut self) {}
@@ -47,7 +54,7 @@ impl State for ControlsState {
         let mut current_y = start_y;
 
         // Co-op Controls Section
-        self.draw_section_header("CO-OP MODE (2 PLAYERS)", current_y, Color::new(1.0, 0.6, 0.2, 1.0));
+        self.draw_section_header("CO-OP MODE (2-4 PLAYERS)", current_y, Color::new(1.0, 0.6, 0.2, 1.0));
         current_y += 40.0;
         draw_text(
             "PLAYER 1 (Blue):",
@@ -71,9 +78,31 @@ impl State for ControlsState {
         );
         current_y += 35.0;
         current_y = self.draw_keybind("Arrow Keys", "Move", current_y);
-        current_y = self.draw_keybind("Numpad 1 / Enter", "Attack", current_y);
-        current_y = self.draw_keybind("Numpad 2 / Backspace", "Heavy Attack", current_y);
-        current_y = self.draw_keybind("Numpad 3 / Right Shift", "Ability", current_y);
+        current_y = self.draw_keybind("Enter", "Attack", current_y);
+        current_y = self.draw_keybind("Backspace", "Heavy Attack", current_y);
+        current_y = self.draw_keybind("Right Shift", "Special Attack", current_y);
+        current_y = self.draw_keybind("'", "Drop In / Out", current_y);
+        current_y += 20.0;
+        draw_text(
+            "PLAYER 3 (Green):",
+            220.0,
+            current_y,
+            24.0,
+            Color::new(0.2, 1.0, 0.2, 1.0),
+        );
+        current_y += 35.0;
+        current_y = self.draw_keybind("Numpad 8/4/5/6", "Move", current_y);
+        current_y = self.draw_keybind("Numpad 1 / 2 / 3", "Attack / Heavy / Special", current_y);
+        current_y = self.draw_keybind("Numpad +", "Drop In / Out", current_y);
+        current_y += 20.0;
+        current_y = self.draw_keybind("Special x2", "Team Super (both players, full meter)", current_y);
+        current_y =
+            self.draw_keybind("Hold F / ' / Numpad +", "Revive a downed teammate or ally", current_y);
+        current_y = self.draw_keybind(
+            "O (co-op select)",
+            "Online co-op: each player uses WASD, J/K/L and F",
+            current_y,
+        );
         current_y += section_spacing;
 
         // Movement Section (Single Player)
@@ -91,14 +120,19 @@ impl State for ControlsState {
         current_y = self.draw_keybind("J", "Light Attack", current_y);
         current_y = self.draw_keybind("K", "Heavy Attack", current_y);
         current_y = self.draw_keybind("L", "Special Attack", current_y);
+        current_y = self.draw_keybind("Left Shift", "Block High (stops overheads)", current_y);
+        current_y = self.draw_keybind("Left Shift + S", "Block Low (stops lows)", current_y);
         current_y = self.draw_keybind("E", "Activate Character Ability", current_y);
+        current_y = self.draw_keybind("Q", "Call Tag Partner Assist", current_y);
+        current_y = self.draw_keybind("T", "Tag Partner In (shared health)", current_y);
         current_y += section_spacing;
 
         // Shop Section
         self.draw_section_header("SHOP", current_y, Color::new(1.0, 0.9, 0.4, 1.0));
         current_y += 40.0;
-        current_y = self.draw_keybind("B", "Open/Close Shop", current_y);
-        current_y = self.draw_keybind("1-8", "Buy Upgrade", current_y);
+        current_y = self.draw_keybind("B", "Open/Close Shop (at the shopkeeper)", current_y);
+        current_y = self.draw_keybind("1-5", "Buy From Stock", current_y);
+        current_y = self.draw_keybind("H", "Haggle (better style, bigger discount)", current_y);
         current_y += section_spacing;
 
         // Menu Section
@@ -174,18 +208,30 @@ impl State for ControlsState {
         }
         if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
             self.scroll_offset += 5.0;
-            self.scroll_offset = self.scroll_offset.min(600.0);
+            self.scroll_offset = self.scroll_offset.min(800.0);
         }
 
         // Go back
         if is_key_pressed(KeyCode::Escape) {
-            self.transition_to = Some(StateType::Menu);
+            if self.over_run {
+                self.done = true;
+            } else {
+                self.transition_to = Some(StateType::Menu);
+            }
         }
     }
 
     fn should_transition(&self) -> Option<StateType> {
         self.transition_to
     }
+
+    fn should_pop(&self) -> bool {
+        self.done
+    }
+
+    fn handles_escape(&self) -> bool {
+        self.over_run
+    }
 }
 
 impl ControlsState {
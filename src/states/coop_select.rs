@@ -1,5 +1,9 @@
 use crate::states::{State, StateType};
+use crate::combat::versus_ai::CpuDifficulty;
 use crate::data::characters::{Character, CharacterId, CHARACTERS};
+use crate::data::palette;
+use crate::data::{CoopBots, CoopScaling};
+use crate::progression::roster::{Roster, LOCKED_HERE};
 use macroquad::prelude::*;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -15,8 +19,20 @@ pub struct CoopSelectState {
     ready_count: usize,
     countdown_timer: Option<f32>,
     transition_to: Option<StateType>,
+    shared_lives: u32,
+    /// Difficulty table previewed for the current team size
+    scaling: CoopScaling,
+    /// Empty slots handed to bots, filled in order, and how well they play
+    bots: usize,
+    bot_difficulty: CpuDifficulty,
+    /// Who the profile has unlocked; nobody locked can be readied
+    roster: Roster,
 }
 
+/// Bounds for the team's shared lives setting
+const MIN_SHARED_LIVES: u32 = 1;
+const MAX_SHARED_LIVES: u32 = 9;
+
 #[derive(Clone)]
 struct PlayerSelection {
     selected_character: usize,
@@ -26,13 +42,6 @@ struct PlayerSelection {
     color: Color,
 }
 
-const PLAYER_COLORS: [Color; 4] = [
-    Color::new(0.2, 0.5, 1.0, 1.0), // Blue (P1)
-    Color::new(1.0, 0.2, 0.2, 1.0), // Red (P2)
-    Color::new(0.2, 1.0, 0.2, 1.0), // Green (P3)
-    Color::new(1.0, 1.0, 0.2, 1.0), // Yellow (P4)
-];
-
 impl CoopSelectState {
     pub fn new() -> Self {
         // Both players join automatically for local 2-player
@@ -42,14 +51,14 @@ impl CoopSelectState {
             character_id: CHARACTERS[0].id,
             is_ready: false,
             input_device: InputDeviceType::KeyboardMouse, // WASD + J/K
-            color: PLAYER_COLORS[0],
+            color: palette::active().player_color(0),
         });
         slots[1] = Some(PlayerSelection {
             selected_character: 1,
             character_id: CHARACTERS[1].id,
             is_ready: false,
             input_device: InputDeviceType::Gamepad(0), // Arrow keys + Enter/Backspace
-            color: PLAYER_COLORS[1],
+            color: palette::active().player_color(1),
         });
 
         Self {
@@ -58,6 +67,11 @@ impl CoopSelectState {
             ready_count: 0,
             countdown_timer: None,
             transition_to: None,
+            shared_lives: crate::data::get_coop_lives(),
+            scaling: CoopScaling::load(),
+            bots: 0,
+            bot_difficulty: CpuDifficulty::Normal,
+            roster: Roster::load(),
         }
     }
 
@@ -68,7 +82,7 @@ impl CoopSelectState {
                 character_id: CHARACTERS[0].id,
                 is_ready: false,
                 input_device: InputDeviceType::KeyboardMouse,
-                color: PLAYER_COLORS[slot],
+                color: palette::active().player_color(slot),
             });
         }
     }
@@ -81,6 +95,51 @@ impl CoopSelectState {
         }
     }
 
+    fn empty_slots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.player_slots.len()).filter(|&slot| self.player_slots[slot].is_none())
+    }
+
+    /// Which bot plays `slot`, if it's one of the empty ones they fill
+    fn bot_index(&self, slot: usize) -> Option<usize> {
+        self.empty_slots().position(|empty| empty == slot).filter(|&bot| bot < self.bots)
+    }
+
+    /// One unlocked character per bot, each one nobody has picked yet
+    fn bot_characters(&self) -> Vec<CharacterId> {
+        let mut taken: Vec<CharacterId> = self
+            .player_slots
+            .iter()
+            .flatten()
+            .map(|player| player.character_id)
+            .collect();
+        let mut bots = Vec::new();
+        for _ in 0..self.bots {
+            let Some(character) = CHARACTERS
+                .iter()
+                .map(|c| c.id)
+                .find(|&id| !taken.contains(&id) && self.roster.is_unlocked(id))
+            else {
+                break;
+            };
+            taken.push(character);
+            bots.push(character);
+        }
+        bots
+    }
+
+    /// Humans plus bots
+    fn team_size(&self) -> usize {
+        self.player_slots.iter().flatten().count() + self.bots
+    }
+
+    /// Everyone human is ready, with someone to play alongside
+    fn check_countdown(&mut self) {
+        let humans = self.player_slots.iter().flatten().count();
+        if self.ready_count == humans && self.team_size() >= 2 {
+            self.countdown_timer = Some(3.0);
+        }
+    }
+
     pub fn get_player_configs(&self) -> Vec<(CharacterId, InputDeviceType)> {
         self.player_slots
             .iter()
@@ -93,6 +152,8 @@ impl CoopSelectState {
 
 impl State for CoopSelectState {
     fn enter(&mut self) {
+        self.scaling = CoopScaling::load();
+        self.roster = Roster::load();
         self.ready_count = 0;
         self.countdown_timer = None;
         self.transition_to = None;
@@ -110,7 +171,15 @@ impl State for CoopSelectState {
                     .iter()
                     .filter_map(|slot| slot.as_ref().map(|p| p.character_id))
                     .collect();
+                let bots = self.bot_characters();
+                let scaling = self.scaling.for_players(player_chars.len() + bots.len());
+                crate::data::set_coop_bots(CoopBots {
+                    characters: bots,
+                    difficulty: self.bot_difficulty,
+                });
                 crate::data::set_coop_players(player_chars);
+                crate::data::set_coop_lives(self.shared_lives);
+                crate::data::set_coop_scaling(scaling);
 
                 self.transition_to = Some(StateType::CoopMode);
             }
@@ -184,12 +253,14 @@ impl State for CoopSelectState {
                 draw_text(&p_text, x + 10.0, y + 30.0, 25.0, player.color);
 
                 // Status
-                let status = if player.is_ready {
-                    "READY!"
+                let (status, status_color) = if player.is_ready {
+                    ("READY!", GREEN)
+                } else if !self.roster.is_unlocked(player.character_id) {
+                    (LOCKED_HERE, ORANGE)
                 } else {
-                    "Selecting..."
+                    ("Selecting...", WHITE)
                 };
-                draw_text(status, x + 10.0, y + 55.0, 20.0, if player.is_ready { GREEN } else { WHITE });
+                draw_text(status, x + 10.0, y + 55.0, 20.0, status_color);
 
                 // Character preview
                 let char_data = Character::get_by_id(player.character_id);
@@ -250,6 +321,23 @@ impl State for CoopSelectState {
                     draw_text(&effect_text, x + 10.0, effect_y, 15.0, Color::new(0.8, 0.8, 0.8, 1.0));
                     effect_y += 18.0;
                 }
+            } else if let Some(bot) = self.bot_index(slot_idx) {
+                // Bot slot
+                draw_rectangle_lines(x, y, slot_width, slot_height, 2.0, LIGHTGRAY);
+                let color = palette::active().player_color(slot_idx);
+                let p_text = format!("PLAYER {} - BOT", slot_idx + 1);
+                draw_text(&p_text, x + 10.0, y + 30.0, 25.0, color);
+
+                let character = self.bot_characters().get(bot).copied();
+                let name = character.map_or("-", |id| Character::get_by_id(id).name);
+                let dims = measure_text(name, None, 30, 1.0);
+                let center_x = x + slot_width * 0.5;
+                draw_text(name, center_x - dims.width * 0.5, y + slot_height * 0.5, 30.0, WHITE);
+
+                let level = format!("CPU {}  |  Drop in to take over", self.bot_difficulty.label());
+                let dims = measure_text(&level, None, 18, 1.0);
+                let level_y = y + slot_height * 0.5 + 30.0;
+                draw_text(&level, center_x - dims.width * 0.5, level_y, 18.0, GRAY);
             } else {
                 // Empty slot
                 draw_rectangle_lines(x, y, slot_width, slot_height, 2.0, DARKGRAY);
@@ -269,7 +357,36 @@ impl State for CoopSelectState {
         // Ready status
         let active_players: usize = self.player_slots.iter().filter(|p| p.is_some()).count();
         let ready_text = format!("Ready: {}/{}", self.ready_count, active_players);
-        draw_text(&ready_text, sw * 0.5 - 60.0, sh - 50.0, 25.0, if self.ready_count >= 2 && self.ready_count == active_players { GREEN } else { WHITE });
+        let all_ready = self.ready_count == active_players && self.team_size() >= 2;
+        let ready_color = if all_ready { GREEN } else { WHITE };
+        draw_text(&ready_text, sw * 0.5 - 60.0, sh - 50.0, 25.0, ready_color);
+
+        // Shared lives setting
+        let lives_text = format!(
+            "TEAM LIVES: < {} >  (W/S)  |  B = Bots: {}  V = Bot level  |  O = Online co-op",
+            self.shared_lives, self.bots
+        );
+        let lives_dims = measure_text(&lives_text, None, 22, 1.0);
+        draw_text(
+            &lives_text,
+            sw * 0.5 - lives_dims.width * 0.5,
+            sh - 85.0,
+            22.0,
+            Color::new(1.0, 0.4, 0.5, 1.0),
+        );
+
+        // What the team size does to the run
+        let team = self.team_size();
+        let scaling = self.scaling.for_players(team);
+        let scaling_text = format!("{} PLAYERS: {}", team, scaling.summary());
+        let scaling_dims = measure_text(&scaling_text, None, 18, 1.0);
+        draw_text(
+            &scaling_text,
+            sw * 0.5 - scaling_dims.width * 0.5,
+            sh - 115.0,
+            18.0,
+            LIGHTGRAY,
+        );
 
         // Countdown
         if let Some(timer) = self.countdown_timer {
@@ -290,6 +407,8 @@ impl State for CoopSelectState {
             return; // Lock input during countdown
         }
 
+        let mut ready = false;
+
         // Player 1 controls (WASD + J/K)
         if let Some(player1) = &mut self.player_slots[0] {
             if !player1.is_ready {
@@ -308,15 +427,11 @@ impl State for CoopSelectState {
                     player1.character_id = CHARACTERS[player1.selected_character].id;
                 }
 
-                // Ready up
-                if is_key_pressed(KeyCode::J) {
+                // Ready up, on someone the profile has unlocked
+                if is_key_pressed(KeyCode::J) && self.roster.is_unlocked(player1.character_id) {
                     player1.is_ready = true;
                     self.ready_count += 1;
-                    // Check countdown inline
-                    let active_players = self.player_slots.iter().filter(|p| p.is_some()).count();
-                    if self.ready_count >= 2 && self.ready_count == active_players {
-                        self.countdown_timer = Some(3.0);
-                    }
+                    ready = true;
                 }
             } else {
                 // Unready
@@ -346,15 +461,11 @@ impl State for CoopSelectState {
                     player2.character_id = CHARACTERS[player2.selected_character].id;
                 }
 
-                // Ready up
-                if is_key_pressed(KeyCode::Enter) {
+                // Ready up, on someone the profile has unlocked
+                if is_key_pressed(KeyCode::Enter) && self.roster.is_unlocked(player2.character_id) {
                     player2.is_ready = true;
                     self.ready_count += 1;
-                    // Check countdown inline
-                    let active_players = self.player_slots.iter().filter(|p| p.is_some()).count();
-                    if self.ready_count >= 2 && self.ready_count == active_players {
-                        self.countdown_timer = Some(3.0);
-                    }
+                    ready = true;
                 }
             } else {
                 // Unready
@@ -366,6 +477,32 @@ impl State for CoopSelectState {
             }
         }
 
+        if ready {
+            self.check_countdown();
+        }
+
+        // Either player can change the shared lives pool
+        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
+            self.shared_lives = (self.shared_lives + 1).min(MAX_SHARED_LIVES);
+        }
+        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+            self.shared_lives = self.shared_lives.saturating_sub(1).max(MIN_SHARED_LIVES);
+        }
+
+        // Fill the empty slots with bots, one more each press
+        if is_key_pressed(KeyCode::B) {
+            let empty = self.empty_slots().count();
+            self.bots = (self.bots + 1) % (empty + 1);
+        }
+        if is_key_pressed(KeyCode::V) {
+            self.bot_difficulty = self.bot_difficulty.next();
+        }
+
+        // Play with someone over the internet instead
+        if is_key_pressed(KeyCode::O) {
+            self.transition_to = Some(StateType::OnlineLobby);
+        }
+
         // Cancel
         if is_key_pressed(KeyCode::Escape) {
             self.transition_to = Some(StateType::Menu);
@@ -1,69 +1,255 @@
-use crate::states::State;
+use crate::data::crash;
+use crate::data::cutscenes::{Cutscene, CutsceneId, CutsceneScript, CutsceneStep, PLAYER_SHEET};
+use crate::data::encounters::EncounterLine;
+use crate::data::localization::{self, LineText};
+use crate::data::poses::{PoseMotion, POSE_DURATION};
+use crate::render::atlas::SpriteBatch;
+use crate::render::{CharacterSheets, SpriteAnimation};
+use crate::states::versus::stage_color;
+use crate::states::{State, StateType};
+use crate::ui::UiLayout;
 use macroquad::prelude::*;
 
+/// Where the floor starts on the stage, in canvas units
+const FLOOR_Y: f32 = 760.0;
+/// Height of the letterbox bars
+const BAR_HEIGHT: f32 = 90.0;
+
+/// Plays one scripted scene: the camera moves, actors pose and lines play out
+/// in order. ENTER skips a line; ESC skips the lot.
 pub struct CutsceneState {
-    current_scene: usize,
-    timer: f32,
-    dialogue_index: usize,
-    dialogues: Vec<(String, String)>,
+    scene: Cutscene,
+    /// Where to go once it's over; `None` pops back to the state underneath
+    then: Option<StateType>,
+    step: usize,
+    step_time: f32,
+    elapsed: f32,
+    /// Focus and zoom now, and where the current move set off from
+    camera: (Vec2, f32),
+    camera_from: (Vec2, f32),
+    /// Each actor's pose and when it started, by their place in the scene
+    poses: Vec<Option<(PoseMotion, f32)>>,
+    line: Option<EncounterLine>,
+    finished: bool,
+    transition_to: Option<StateType>,
+    player_sheet: &'static str,
+    sheets: CharacterSheets,
+    batch: SpriteBatch,
 }
 
 impl CutsceneState {
     pub fn new() -> Self {
+        let scene = CutsceneScript::default()
+            .scene(CutsceneId::Opening)
+            .cloned()
+            .expect("the default script has an opening");
         Self {
-            current_scene: 0,
-            timer: 0.0,
-            dialogue_index: 0,
-            dialogues: vec![
-                ("Bas".to_string(), "Kom dan! Ik veeg niks, bro!".to_string()),
-                ("Meneer Wolters".to_string(), "Bas, vegen!".to_string()),
-                ("Bas".to_string(), "Nee!".to_string()),
-                (
-                    "Meneer Wolters".to_string(),
-                    "Bas! Vegen, nu meteen!".to_string(),
+            scene,
+            then: Some(StateType::Menu),
+            step: 0,
+            step_time: 0.0,
+            elapsed: 0.0,
+            camera: (Vec2::new(960.0, 540.0), 1.0),
+            camera_from: (Vec2::new(960.0, 540.0), 1.0),
+            poses: Vec::new(),
+            line: None,
+            finished: false,
+            transition_to: None,
+            player_sheet: CharacterSheets::sheet_for(crate::data::get_selected_character()),
+            sheets: CharacterSheets::new(),
+            batch: SpriteBatch::default(),
+        }
+    }
+
+    /// Run steps until one holds the scene, or there are none left
+    fn play(&mut self) {
+        while let Some(step) = self.scene.steps.get(self.step) {
+            match step {
+                CutsceneStep::Camera {
+                    focus,
+                    zoom,
+                    duration,
+                } => {
+                    let t = if *duration > 0.0 {
+                        (self.step_time / duration).min(1.0)
+                    } else {
+                        1.0
+                    };
+                    // Eased in and out
+                    let t = t * t * (3.0 - 2.0 * t);
+                    let (from, from_zoom) = self.camera_from;
+                    let to = Vec2::new(focus.0, focus.1);
+                    self.camera = (from.lerp(to, t), from_zoom + (zoom - from_zoom) * t);
+                    if t < 1.0 {
+                        return;
+                    }
+                }
+                CutsceneStep::Pose { actor, motion } => {
+                    let index = self.scene.actors.iter().position(|a| &a.name == actor);
+                    if let Some(pose) = index.and_then(|index| self.poses.get_mut(index)) {
+                        *pose = Some((*motion, self.elapsed));
+                    }
+                }
+                CutsceneStep::Line(line) => {
+                    if self.step_time < line.duration {
+                        self.line = Some(line.clone());
+                        return;
+                    }
+                    self.line = None;
+                }
+                CutsceneStep::Music(cue) => crate::data::queue_music_cue(*cue),
+                CutsceneStep::Wait(seconds) => {
+                    if self.step_time < *seconds {
+                        return;
+                    }
+                }
+            }
+            self.step += 1;
+            self.step_time = 0.0;
+            self.camera_from = self.camera;
+        }
+
+        if !self.finished {
+            self.finished = true;
+            self.transition_to = self.then;
+        }
+    }
+
+    fn render_stage(&mut self, ui: &UiLayout) {
+        let (focus, zoom) = self.camera;
+        set_camera(&Camera2D {
+            target: focus,
+            zoom: Vec2::new(2.0 * zoom / ui.width(), 2.0 * zoom / ui.height()),
+            ..Default::default()
+        });
+
+        // Drawn well past the canvas so a wide shot never runs out of stage
+        let backdrop = stage_color(self.scene.backdrop);
+        let floor = Color::new(backdrop.r * 0.6, backdrop.g * 0.6, backdrop.b * 0.6, 1.0);
+        draw_rectangle(-1920.0, -1080.0, 5760.0, 1080.0 + FLOOR_Y, backdrop);
+        draw_rectangle(-1920.0, FLOOR_Y, 5760.0, 1080.0, floor);
+        draw_line(-1920.0, FLOOR_Y, 3840.0, FLOOR_Y, 4.0, Color::new(0.0, 0.0, 0.0, 0.3));
+
+        let mut batch = std::mem::take(&mut self.batch);
+        for (actor, pose) in self.scene.actors.iter().zip(&self.poses) {
+            let position = Vec2::new(actor.position.0, actor.position.1);
+            let shadow = Color::new(0.0, 0.0, 0.0, 0.4);
+            draw_ellipse(position.x, position.y + 70.0, 48.0, 16.0, 0.0, shadow);
+            let sheet = if actor.sheet == PLAYER_SHEET {
+                self.player_sheet
+            } else {
+                actor.sheet.as_str()
+            };
+            let (animation, progress) = match pose {
+                Some((motion, since)) => (
+                    SpriteAnimation::for_pose(*motion),
+                    (self.elapsed - since) / POSE_DURATION,
                 ),
-            ],
+                None => (SpriteAnimation::Idle, 0.0),
+            };
+            self.sheets.queue(
+                &mut batch,
+                sheet,
+                animation,
+                self.elapsed,
+                progress,
+                position,
+                actor.facing < 0.0,
+                WHITE,
+            );
+        }
+        self.sheets.flush(&mut batch);
+        self.batch = batch;
+    }
+
+    fn render_dialogue(&self, ui: &UiLayout) {
+        let Some(line) = &self.line else {
+            return;
+        };
+        let box_height = 150.0;
+        let box_y = ui.height() - BAR_HEIGHT - box_height - 20.0;
+        draw_rectangle(20.0, box_y, ui.width() - 40.0, box_height, Color::new(0.0, 0.0, 0.0, 0.9));
+        draw_rectangle_lines(20.0, box_y, ui.width() - 40.0, box_height, 3.0, WHITE);
+        draw_text(&line.speaker, 40.0, box_y + 30.0, 28.0, YELLOW);
+        let spoken = localization::spoken(&LineText::Written {
+            dutch: line.dutch.clone(),
+            english: line.english.clone(),
+        });
+        draw_text(&spoken.voice, 40.0, box_y + 65.0, 24.0, WHITE);
+        if let Some(subtitle) = &spoken.subtitle {
+            draw_text(subtitle, 40.0, box_y + 100.0, 20.0, Color::new(0.7, 0.7, 0.7, 1.0));
         }
     }
 }
 
 impl State for CutsceneState {
     fn enter(&mut self) {
-        self.current_scene = 0;
-        self.timer = 0.0;
-        self.dialogue_index = 0;
+        if let Some((id, then)) = crate::data::take_cutscene() {
+            let (script, problems) = CutsceneScript::load();
+            for problem in &problems {
+                crash::log(format!("Cutscene script: {}", problem));
+            }
+            if let Some(scene) = script.scene(id) {
+                self.scene = scene.clone();
+            }
+            self.then = then;
+        }
+        self.step = 0;
+        self.step_time = 0.0;
+        self.elapsed = 0.0;
+        self.poses = vec![None; self.scene.actors.len()];
+        self.line = None;
+        self.finished = false;
+        self.transition_to = None;
+        self.player_sheet = CharacterSheets::sheet_for(crate::data::get_selected_character());
+        self.play();
     }
 
     fn exit(&mut self) {}
 
     fn update(&mut self, dt: f32) {
-        self.timer += dt;
+        self.elapsed += dt;
+        self.step_time += dt;
+        self.play();
+        if self.line.is_some() {
+            crate::data::duck_music();
+        }
     }
 
     fn fixed_update(&mut self, _dt: f64) {}
 
     fn render(&mut self, _interpolation: f32) {
         clear_background(BLACK);
+        let ui = UiLayout::current();
+        self.render_stage(&ui);
 
-        draw_rectangle(
-            0.0,
-            screen_height() - 200.0,
-            screen_width(),
-            200.0,
-            Color::new(0.0, 0.0, 0.0, 0.8),
-        );
-
-        if self.dialogue_index < self.dialogues.len() {
-            let (speaker, text) = &self.dialogues[self.dialogue_index];
+        ui.begin();
+        draw_rectangle(0.0, 0.0, ui.width(), BAR_HEIGHT, BLACK);
+        draw_rectangle(0.0, ui.height() - BAR_HEIGHT, ui.width(), BAR_HEIGHT, BLACK);
+        self.render_dialogue(&ui);
 
-            draw_text(speaker, 50.0, screen_height() - 150.0, 30.0, YELLOW);
-            draw_text(text, 50.0, screen_height() - 100.0, 25.0, WHITE);
-        }
+        let hint = localization::tr("cutscene.hint");
+        let dims = measure_text(&hint, None, 20, 1.0);
+        let hint_y = ui.height() - BAR_HEIGHT * 0.5 + 6.0;
+        draw_text(&hint, ui.width() - dims.width - 40.0, hint_y, 20.0, GRAY);
+        ui.end();
     }
 
     fn handle_input(&mut self) {
-        if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
-            self.dialogue_index += 1;
+        let next = is_key_pressed(KeyCode::Space)
+            || is_key_pressed(KeyCode::Enter)
+            || is_key_pressed(KeyCode::J);
+        if next && self.line.is_some() {
+            self.line = None;
+            self.step_time = f32::MAX;
         }
     }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+
+    fn should_pop(&self) -> bool {
+        self.finished && self.then.is_none()
+    }
 }
@@ -1,27 +1,37 @@
-use crate::ecs::sys::{CombatSystem, System};
+use crate::audio::announcer::{Announcer, Callout};
+use crate::audio::voice_bank::{FighterVoice, VoiceCue};
+use crate::combat::hitbox::{AttackHeight, BlockStance};
+use crate::coop::input_handler::{KeyboardLayout, ReboundKeys};
+use crate::data::crash;
+use crate::ecs::sys::{run_system, CombatSystem, PhysicsSystem};
 use crate::data::characters::{AbilityState, Character, CharacterId};
 use crate::ecs::comp::*;
 use crate::ecs::entity::EntityId;
+use crate::ecs::interpolation::TransformSnapshot;
 use crate::ecs::world::World;
+use crate::data::events::{self, RewardKind};
+use crate::data::localization::LineText;
+use crate::data::ShopManager;
+use crate::progression::achievements::{AchievementEvent, AchievementReward, RunSummary};
+use crate::progression::{AchievementManager, CharacterMastery};
 use crate::states::{State, StateType};
+use crate::ui::dialogue_box::{DialogueBox, DialogueLine};
+use crate::ui::subtitles::SubtitleManager;
+use crate::ui::{ToastQueue, UiLayout};
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
-struct DialogueLine {
-    speaker: String,
-    dutch: String,
-    english: String,
-    duration: f32,
-}
-
 pub struct EndlessState {
     world: World,
     combat_system: CombatSystem,
+    physics_system: PhysicsSystem,
     player_entity: Option<EntityId>,
     enemy_entities: Vec<EntityId>,
     camera_position: Vec2,
     camera_target: Vec2,
     camera_zoom: f32,
+    transform_snapshot: TransformSnapshot,
+    render_alpha: f32,
 
     // Endless mode specific
     wave: u32,
@@ -34,9 +44,12 @@ pub struct EndlessState {
     difficulty_multiplier: f32,
 
     // UI
-    current_dialogue: Option<DialogueLine>,
-    dialogue_timer: f32,
+    dialogue_box: DialogueBox,
     dialogue_queue: Vec<DialogueLine>,
+    /// Voice lines spoken over the fight without stopping it
+    subtitles: SubtitleManager,
+    announcer: Announcer,
+    voice: FighterVoice,
     show_wave_banner: bool,
     wave_banner_timer: f32,
 
@@ -54,6 +67,11 @@ pub struct EndlessState {
     // Auto-attack system
     auto_attack_timer: f32,
     auto_attack_delay: f32,
+    /// P1's keys, with the Controls page's rebinds
+    keys: ReboundKeys,
+
+    achievements: AchievementManager,
+    toasts: ToastQueue,
 }
 
 impl EndlessState {
@@ -65,11 +83,14 @@ impl EndlessState {
         Self {
             world,
             combat_system,
+            physics_system: PhysicsSystem::new(),
             player_entity: None,
             enemy_entities: Vec::new(),
             camera_position: Vec2::new(screen_width() * 0.5, screen_height() * 0.5),
             camera_target: Vec2::new(screen_width() * 0.5, screen_height() * 0.5),
             camera_zoom: 1.0,
+            transform_snapshot: TransformSnapshot::new(),
+            render_alpha: 1.0,
 
             // Start with wave 1
             wave: 1,
@@ -82,8 +103,10 @@ impl EndlessState {
             difficulty_multiplier: 1.0,
 
             // UI
-            current_dialogue: None,
-            dialogue_timer: 0.0,
+            dialogue_box: DialogueBox::new(),
+            subtitles: SubtitleManager::new(),
+            announcer: Announcer::new(),
+            voice: FighterVoice::new(),
             dialogue_queue: Vec::new(),
             show_wave_banner: false,
             wave_banner_timer: 0.0,
@@ -102,7 +125,46 @@ impl EndlessState {
             // Auto-attack system
             auto_attack_timer: 0.0,
             auto_attack_delay: 0.25,
+            keys: ReboundKeys::new(KeyboardLayout::Wasd),
+
+            achievements: AchievementManager::load(),
+            toasts: ToastQueue::new(),
+        }
+    }
+
+    /// Count `event` towards achievements, announcing and paying out whatever
+    /// it unlocks straight into the saved shop and mastery
+    fn record_achievement(&mut self, event: AchievementEvent) {
+        for achievement in self.achievements.record(event) {
+            let accent = achievement.difficulty.to_color();
+            self.toasts
+                .push("ACHIEVEMENT UNLOCKED", &achievement.name, &achievement.description, accent);
+            if let Err(e) = self.grant_reward(&achievement.name, achievement.reward) {
+                crash::log(format!("Failed to grant achievement reward: {}", e));
+            }
+        }
+    }
+
+    /// Skill points only last as long as a story run's tree, so endless
+    /// leaves them be
+    fn grant_reward(&self, name: &str, reward: AchievementReward) -> Result<(), String> {
+        let mut mastery = CharacterMastery::load(self.selected_character);
+        let (unlocked, unlock) = match reward {
+            AchievementReward::Currency(amount) => {
+                let amount = amount.round() as u32;
+                let (amount, _) = events::apply_bonuses(amount, RewardKind::Achievement);
+                let mut shop = ShopManager::load();
+                shop.add_currency(amount, &format!("Achievement: {}", name))?;
+                return shop.save();
+            }
+            AchievementReward::Title(title) => (&mut mastery.unlocked_titles, title),
+            AchievementReward::Skin(skin) => (&mut mastery.unlocked_skins, skin),
+            AchievementReward::SkillPoints(_) | AchievementReward::None => return Ok(()),
+        };
+        if !unlocked.contains(&unlock) {
+            unlocked.push(unlock);
         }
+        mastery.save()
     }
 
     fn spawn_player(&mut self) {
@@ -159,9 +221,16 @@ impl EndlessState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            player,
+            PhysicsBody::fighter(Team::Player, character_type.weight()),
+        );
+
         self.world.add_component(
             player,
             CollisionBox {
@@ -180,6 +249,7 @@ impl EndlessState {
         self.enemies_defeated = 0;
         self.show_wave_banner = true;
         self.wave_banner_timer = 3.0;
+        self.announcer.announce(Callout::WaveStart(self.wave as usize));
 
         // Scale difficulty
         self.enemies_per_wave = 3 + (self.wave / 3);
@@ -188,8 +258,10 @@ impl EndlessState {
         // Show wave announcement
         self.dialogue_queue.push(DialogueLine {
             speaker: "System".to_string(),
-            dutch: format!("Golf {}!", self.wave),
-            english: format!("Wave {}!", self.wave),
+            text: LineText::Written {
+                dutch: format!("Golf {}!", self.wave),
+                english: format!("Wave {}!", self.wave),
+            },
             duration: 2.0,
         });
     }
@@ -275,9 +347,16 @@ impl EndlessState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            enemy,
+            PhysicsBody::fighter(Team::Enemy, enemy_type.weight()),
+        );
+
         self.world.add_component(
             enemy,
             CollisionBox {
@@ -295,6 +374,8 @@ impl EndlessState {
                 state_timer: 0.0,
                 reaction_delay: 0.2 + rand::gen_range(0.0, 0.3),
                 difficulty: self.difficulty_multiplier,
+                reaction_scale: 1.0,
+                ability_rate: 1.0,
             },
         );
 
@@ -316,6 +397,7 @@ impl EndlessState {
             // Wave complete!
             self.score += 100 * self.wave;
             self.wave += 1;
+            self.record_achievement(AchievementEvent::EndlessWaveReached(self.wave));
 
             // Give player a small health bonus
             if let Some(player_entity) = self.player_entity {
@@ -337,23 +419,40 @@ impl State for EndlessState {
         self.spawn_wave();
     }
 
-    fn exit(&mut self) {}
+    fn exit(&mut self) {
+        if let Err(e) = self.achievements.save() {
+            crash::log(e.to_string());
+        }
+    }
 
     fn update(&mut self, dt: f32) {
+        self.toasts.update(dt);
+        // The music sits under anyone talking
+        if self.dialogue_box.is_showing() || self.subtitles.active_subtitle.is_some() {
+            crate::data::duck_music();
+        }
+    }
+
+    fn fixed_update(&mut self, dt: f64) {
+        let dt = dt as f32;
+        self.transform_snapshot.capture(&self.world);
+
         // Update dialogue
-        if self.current_dialogue.is_none() && !self.dialogue_queue.is_empty() {
-            self.current_dialogue = self.dialogue_queue.pop();
-            self.dialogue_timer = 0.0;
+        if !self.dialogue_box.is_showing() {
+            if let Some(line) = self.dialogue_queue.pop() {
+                self.dialogue_box.show(line);
+            }
         }
 
-        if let Some(ref dialogue) = self.current_dialogue {
-            self.dialogue_timer += dt;
-            if self.dialogue_timer >= dialogue.duration {
-                self.current_dialogue = None;
-            } else {
+        if self.dialogue_box.is_showing() {
+            self.dialogue_box.update(dt);
+            if self.dialogue_box.is_showing() {
                 return; // Freeze game during dialogue
             }
         }
+        self.subtitles.update(dt);
+        self.announcer.update(dt);
+        self.voice.update(dt);
 
         if self.show_wave_banner {
             self.wave_banner_timer -= dt;
@@ -399,7 +498,19 @@ impl State for EndlessState {
         let ability_damage_mult = self.ability_state.get_damage_multiplier();
         let total_damage_mult = self.player_attack_multiplier * ability_damage_mult;
         self.combat_system.set_player_attack_multiplier(total_damage_mult);
-        self.combat_system.update(&mut self.world, dt);
+        run_system(&mut self.combat_system, &mut self.world, dt);
+
+        // Knockback from combat is integrated against the screen edges
+        self.physics_system.set_arena(Rect::new(
+            30.0,
+            30.0,
+            screen_width() - 60.0,
+            screen_height() - 60.0,
+        ));
+        run_system(&mut self.physics_system, &mut self.world, dt);
+
+        // Endless scores kills directly below; drop combat events so the queue doesn't grow
+        self.world.events().drain();
 
         // Remove dead enemies
         let mut dead_enemies = Vec::new();
@@ -416,6 +527,7 @@ impl State for EndlessState {
         for entity in dead_enemies {
             self.enemy_entities.retain(|&e| e != entity);
             self.world.destroy_entity(entity);
+            self.record_achievement(AchievementEvent::EnemyDefeated);
         }
 
         // Check if player died
@@ -425,10 +537,22 @@ impl State for EndlessState {
                     self.game_over = true;
                     self.dialogue_queue.push(DialogueLine {
                         speaker: "System".to_string(),
-                        dutch: format!("Spel Voorbij! Score: {}", self.score),
-                        english: format!("Game Over! Score: {}", self.score),
+                        text: LineText::Written {
+                            dutch: format!("Spel Voorbij! Score: {}", self.score),
+                            english: format!("Game Over! Score: {}", self.score),
+                        },
                         duration: 3.0,
                     });
+                    // Endless always ends in a loss
+                    self.record_achievement(AchievementEvent::RunFinished(RunSummary {
+                        solo: true,
+                        died: true,
+                        took_damage: true,
+                        ..RunSummary::default()
+                    }));
+                    if let Err(e) = self.achievements.save() {
+                        crash::log(e.to_string());
+                    }
                 }
             }
         }
@@ -446,9 +570,8 @@ impl State for EndlessState {
         self.camera_position = self.camera_position.lerp(self.camera_target, 0.1);
     }
 
-    fn fixed_update(&mut self, _dt: f64) {}
-
-    fn render(&mut self, _interpolation: f32) {
+    fn render(&mut self, interpolation: f32) {
+        self.render_alpha = interpolation;
         clear_background(Color::new(0.1, 0.05, 0.15, 1.0));
 
         // Render player
@@ -464,11 +587,6 @@ impl State for EndlessState {
         // Render UI
         self.render_ui();
 
-        // Render dialogue
-        if let Some(ref dialogue) = self.current_dialogue {
-            self.render_dialogue(dialogue);
-        }
-
         // Render wave banner
         if self.show_wave_banner {
             let banner_text = format!("WAVE {}", self.wave);
@@ -542,6 +660,16 @@ impl State for EndlessState {
                 GRAY,
             );
         }
+
+        let ui = UiLayout::current();
+        ui.begin();
+        if !self.dialogue_box.is_showing() {
+            self.subtitles.render(&ui);
+        }
+        // Lines move on by themselves here; the attack keys would skip them
+        self.dialogue_box.render(&ui);
+        self.toasts.render(&ui);
+        ui.end();
     }
 
     fn handle_input(&mut self) {
@@ -552,6 +680,7 @@ impl State for EndlessState {
             return;
         }
 
+        let keys = *self.keys.current();
         if let Some(player_entity) = self.player_entity {
             if let Some(fighter) = self.world.get_component::<Fighter>(player_entity) {
                 if fighter.state != FighterState::Hitstun
@@ -561,16 +690,16 @@ impl State for EndlessState {
                     let mut velocity = Vec2::ZERO;
 
                     // Movement
-                    if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+                    if is_key_down(keys.move_left) || is_key_down(KeyCode::Left) {
                         velocity.x = -300.0;
                     }
-                    if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+                    if is_key_down(keys.move_right) || is_key_down(KeyCode::Right) {
                         velocity.x = 300.0;
                     }
-                    if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
+                    if is_key_down(keys.move_up) || is_key_down(KeyCode::Up) {
                         velocity.y = -300.0;
                     }
-                    if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
+                    if is_key_down(keys.move_down) || is_key_down(KeyCode::Down) {
                         velocity.y = 300.0;
                     }
 
@@ -586,14 +715,15 @@ impl State for EndlessState {
                     let mut new_state = None;
 
                     // Auto-attack system - hold button for continuous attacks
-                    if is_key_down(KeyCode::J) || is_key_down(KeyCode::K) || is_key_down(KeyCode::L) {
+                    let attacks = [keys.light_attack, keys.heavy_attack, keys.special_attack];
+                    if attacks.into_iter().any(is_key_down) {
                         // Check if it's time to attack again
                         if self.auto_attack_timer <= 0.0 {
-                            if is_key_down(KeyCode::J) {
+                            if is_key_down(keys.light_attack) {
                                 new_state = Some(FighterState::LightAttack);
-                            } else if is_key_down(KeyCode::K) {
+                            } else if is_key_down(keys.heavy_attack) {
                                 new_state = Some(FighterState::HeavyAttack);
-                            } else if is_key_down(KeyCode::L) {
+                            } else if is_key_down(keys.special_attack) {
                                 new_state = Some(FighterState::Special);
                             }
 
@@ -608,47 +738,30 @@ impl State for EndlessState {
                     }
 
                     // Ability activation
-                    if is_key_pressed(KeyCode::E) {
+                    if is_key_pressed(keys.ability) {
                         if self.ability_state.can_activate() {
                             let voice_line = self.ability_state.activate();
+                            self.voice.speak(self.selected_character, VoiceCue::Ability);
                             if !voice_line.is_empty() {
                                 // Check if this is Jad's special KFC Rage ability
                                 if self.selected_character == CharacterId::Jad {
                                     // Jad's special dialogue sequence
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Jad".to_string(),
-                                        dutch: "KFC RAGE!".to_string(),
-                                        english: "KFC RAGE!".to_string(),
-                                        duration: 2.0,
-                                    });
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Jad".to_string(),
-                                        dutch: "nu ben ik boos".to_string(),
-                                        english: "now I'm angry".to_string(),
-                                        duration: 1.5,
-                                    });
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Umut".to_string(),
-                                        dutch: "typisch".to_string(),
-                                        english: "typical".to_string(),
-                                        duration: 1.5,
-                                    });
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Jad".to_string(),
-                                        dutch: "ik eet".to_string(),
-                                        english: "I eat".to_string(),
-                                        duration: 1.5,
-                                    });
-                                    self.dialogue_queue.reverse();
+                                    for (speaker, key) in [
+                                        ("Jad", "dialogue.jad.rage"),
+                                        ("Jad", "dialogue.jad.angry"),
+                                        ("Umut", "dialogue.jad.umut"),
+                                        ("Jad", "dialogue.jad.eat"),
+                                    ] {
+                                        self.subtitles.say(speaker, LineText::Key(key.to_string()));
+                                    }
                                 } else {
-                                    // Regular ability activation dialogue
+                                    // Regular ability activation line, spoken over the fight
                                     let character = Character::get_by_id(self.selected_character);
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: character.name.to_string(),
+                                    let line = LineText::Written {
                                         dutch: voice_line.to_string(),
                                         english: voice_line.to_string(),
-                                        duration: 2.0,
-                                    });
+                                    };
+                                    self.subtitles.say(character.name, line);
                                 }
 
                                 // Apply health boost if applicable
@@ -717,6 +830,9 @@ impl EndlessState {
         let health = self.world.get_component::<Health>(entity);
 
         if let (Some(transform), Some(fighter)) = (transform, fighter) {
+            let position = self
+                .transform_snapshot
+                .position(entity, transform.position, self.render_alpha);
             let color = match fighter.team {
                 Team::Player => BLUE,
                 Team::Enemy => RED,
@@ -724,8 +840,8 @@ impl EndlessState {
             };
 
             draw_rectangle(
-                transform.position.x - 30.0,
-                transform.position.y - 40.0,
+                position.x - 30.0,
+                position.y - 40.0,
                 60.0,
                 80.0,
                 color,
@@ -735,15 +851,15 @@ impl EndlessState {
             if let Some(health) = health {
                 let health_percent = health.current / health.maximum;
                 draw_rectangle(
-                    transform.position.x - 30.0,
-                    transform.position.y - 55.0,
+                    position.x - 30.0,
+                    position.y - 55.0,
                     60.0 * health_percent,
                     5.0,
                     GREEN,
                 );
                 draw_rectangle_lines(
-                    transform.position.x - 30.0,
-                    transform.position.y - 55.0,
+                    position.x - 30.0,
+                    position.y - 55.0,
                     60.0,
                     5.0,
                     1.0,
@@ -801,55 +917,4 @@ impl EndlessState {
 
         draw_text(&ability_text, 80.0, ability_y, 18.0, WHITE);
     }
-
-    fn render_dialogue(&self, dialogue: &DialogueLine) {
-        let box_height = 120.0;
-        let box_y = screen_height() - box_height - 20.0;
-
-        // Background
-        draw_rectangle(
-            20.0,
-            box_y,
-            screen_width() - 40.0,
-            box_height,
-            Color::new(0.0, 0.0, 0.0, 0.9),
-        );
-
-        // Border
-        draw_rectangle_lines(
-            20.0,
-            box_y,
-            screen_width() - 40.0,
-            box_height,
-            3.0,
-            WHITE,
-        );
-
-        // Speaker
-        draw_text(
-            &dialogue.speaker,
-            40.0,
-            box_y + 30.0,
-            25.0,
-            YELLOW,
-        );
-
-        // Text (Dutch)
-        draw_text(
-            &dialogue.dutch,
-            40.0,
-            box_y + 60.0,
-            20.0,
-            WHITE,
-        );
-
-        // Text (English) - smaller
-        draw_text(
-            &dialogue.english,
-            40.0,
-            box_y + 85.0,
-            16.0,
-            GRAY,
-        );
-    }
 }
\ No newline at end of file
@@ -1,89 +1,284 @@
 use std::collections::HashMap;
 
-use crate::combat::hitbox::{Hitbox, HitType, SpecialType};
+use crate::audio::announcer::{Announcer, Callout};
+use crate::audio::sfx::SFXManager;
+use crate::audio::voice_bank::{FighterVoice, VoiceCue};
+use crate::app::TimeScaleRequest;
+use crate::audio::MusicCue;
+use crate::combat::hitbox::{AttackHeight, BlockStance, Hitbox, HitType};
+use crate::combat::character_movesets::{CharacterMoveset, STAGE_SCALE};
+use crate::combat::combo_assist::ComboAssist;
 use crate::combat::hurtbox::Hurtbox;
 use crate::combat::inputs::InputManager;
 use crate::combat::combo_system::{ComboSystem, StyleRank};
 use crate::combat::plane_system::PlaneSystem;
-use crate::data::{AbilityState, CharacterId, ShopManager, UpgradeId};
-use crate::ecs::System as EcsSystem;
+use crate::combat::tag_team::TagTeam;
+use crate::combat::versus_ai::CpuDifficulty;
+use crate::data::balance::{self, BalanceReport, Difficulty, RunRecord, RunTally};
+use crate::data::cosmetics::{CosmeticsProgress, EquippedCosmetics};
+use crate::data::crash;
+use crate::data::cutscenes::CutsceneId;
+use crate::data::dialogue::{Conversation, DialogueMemory, DialogueScript, RunFacts, AFTERMATH};
+use crate::data::encounters::{EncounterBoss, EncounterLine, EncounterScript};
+use crate::data::localization::{self, LineText};
+use crate::data::events::{self, RewardKind};
+use crate::data::loot::DropTable;
+use crate::data::palette::{self, PaletteSet};
+use crate::net::{NetInput, NetSession, HASH_INTERVAL};
+use crate::data::poses::{PosePlayback, PoseVariant, POSE_DURATION};
+use crate::data::shop::{haggled, rank_cost, stacked_value};
+use crate::data::{
+    AbilityState, CharacterId, Consumable, CoopScaling, GameConfig, PlayerCountScaling,
+    RunReport, ShopManager, StoryResult, UpgradeId,
+};
 use crate::ecs::{
-    AIBehavior, AIController, Bomb, BossPhase, CharacterType, EntityId, Fighter, FighterState,
-    Health, HitboxComponent, HurtboxComponent, Particle, ParticleType, Stamina, Team, Transform,
-    Velocity, World,
+    AIBehavior, AIController, Assist, Bomb, GameEvent, BossPhase, BossWindup, CharacterType,
+    ColorRamp, DangerZone, Dash, Destructible, DestructibleKind, Emitter, EmitterShape, EntityId,
+    Fighter, FighterState, Health, HitboxComponent, HurtboxComponent, Knockback, LootDrop,
+    ParticleType, PhysicsBody, Pickup, PowerUpKind, PropKind, Rarity, Shopkeeper, SpawnEntrance,
+    Stamina, StageProp, Team, Transform, TransformSnapshot, Velocity, WindupVfx, World,
 };
 use crate::ecs::{
-    AISystem, AnimationSystem, CombatSystem, MovementSystem, ParticleSystem, PhysicsSystem,
+    apply_knockback, CombatSystem, EventHandler, HitModifiers, ParticleSystem, PhysicsSystem,
+    Schedule, Stage, StagePropSystem,
+};
+use crate::render::atlas::SpriteBatch;
+use crate::render::{AnimationPlayer, CharacterSheets, PostMoment, PostProcessor, SpriteAnimation};
+use crate::render::draw_outfit;
+use crate::render::FocusPriority;
+use crate::render::{GameCamera, TextureManager, GraphicsEnhancement, EnhancedVFXSystem, MapSystem};
+use crate::coop::input_handler::{KeyboardBindings, KeyboardLayout};
+use crate::coop::player_manager::{BLEED_OUT_TIME, MAX_PLAYERS};
+use crate::coop::{CoopInputHandler, CoopPlayerManager, CoopUI, InputDevice, PlayerSlot};
+use crate::coop::{BotView, CoopBot, PingBoard, PingWheel, PlayerInput};
+use crate::coop::{ReviveSystem, SharedComboSystem, SharedLivesPool, Teammate};
+use crate::progression::{SkillTreeManager, CharacterMastery, AchievementManager, PlayerAccount};
+use crate::progression::account_level::ACCOUNT_XP_SHARE;
+use crate::progression::achievements::{AchievementEvent, AchievementReward, RunSummary};
+use crate::progression::challenges::{ChallengeModifier, ChallengeReward, ChallengeRun};
+use crate::progression::{ChallengeKind, ChallengeManager, ChallengeType};
+use crate::progression::character_mastery::MasteryReward;
+use crate::progression::MasteryRank;
+use crate::progression::practice::{PracticeProgress, PracticeStart};
+use crate::progression::roster::TrialRun;
+use crate::progression::skill_tree::{CancelRoute, SkillBonuses};
+use crate::progression::winter_arc::{
+    Curse, MetaUnlock, RunResult, RunUpgrade, UpgradeOffer, WinterArcProgress, WinterArcRun,
 };
-use crate::render::{TextureManager, GraphicsEnhancement, EnhancedSprite, EnhancedVFXSystem, MapSystem};
-use crate::coop::{CoopPlayerManager, SharedComboSystem, ReviveSystem};
-use crate::progression::{SkillTreeManager, CharacterMastery, AchievementManager, AccountProgression};
+use crate::states::auto_pause::{AutoPause, AwayReason};
 use crate::states::State;
 use crate::states::StateType;
+use crate::ui::account_banner::render_prestige_badge;
+use crate::ui::console::{self, CommandRegistry, CommandResult};
+use crate::ui::dialogue_box::{DialogueBox, DialogueLine};
+use crate::ui::pause::{PauseAction, PauseMenu};
+use crate::ui::subtitles::SubtitleManager;
+use crate::ui::{Anchor, HudLayout, HudWidget, ToastQueue, UiLayout};
+use crate::util::host::{Headless, Host, Window};
+use crate::util::rng::SeededRng;
+use crate::util::spatial::SpatialGrid;
 use macroquad::prelude::*;
 
 pub struct GameplayState {
+    /// The window the run reads its keys, screen size and clock from
+    host: Box<dyn Host>,
     world: World,
     player_entity: Option<EntityId>,
-    player2_entity: Option<EntityId>, // For co-op player 2
     ally_entities: Vec<EntityId>,
     ally_roster: Vec<CharacterType>,
     enemy_entities: Vec<EntityId>,
-    camera_pos: Vec2,
-    movement_system: MovementSystem,
-    physics_system: PhysicsSystem,
-    animation_system: AnimationSystem,
-    combat_system: CombatSystem,
-    particle_system: ParticleSystem,
-    ai_system: AISystem,
+    camera: GameCamera,
+    post: PostProcessor,
+    /// Where the player put the HUD widgets, from the profile
+    hud_layout: HudLayout,
+    // Enemy drops: the table, the dice (seeded from the session online, so
+    // both sides drop the same loot) and the team's power-ups with the
+    // seconds they have left
+    loot_table: DropTable,
+    loot_rng: SeededRng,
+    power_ups: Vec<(PowerUpKind, f32)>,
+    transform_snapshot: TransformSnapshot,
+    /// How far render is between the last two fixed ticks
+    render_alpha: f32,
+    /// ECS systems registered by plugins
+    systems: Schedule,
+    /// Plugin handlers that see every event after the state's own
+    event_handlers: Vec<Box<dyn EventHandler>>,
     input_manager: InputManager,
-    texture_manager: TextureManager,
+    /// Generated on the first frame drawn, so a headless run never needs a GPU
+    texture_manager: Option<TextureManager>,
+    character_sheets: Option<CharacterSheets>,
+    sprite_batch: SpriteBatch,
     graphics_enhancement: Option<*mut GraphicsEnhancement>,
     current_map: MapType,
     current_wave: usize,
     waves_completed: usize,
     enemies_to_spawn: usize,
     spawn_timer: f32,
+    /// Every map's waves, from `encounters.json`, and what was wrong with it
+    /// if the built-in waves are being played instead
+    encounters: EncounterScript,
+    encounter_problems: Vec<String>,
+    /// Enemies of this wave spawned so far, to go round its spawn points
+    wave_spawned: usize,
+    /// P1's health as the wave started, given back when it's restarted
+    wave_start_health: Option<f32>,
     paused: bool,
+    pause_menu: PauseMenu,
+    /// Pauses solo runs left idle or in the background
+    auto_pause: AutoPause,
+    /// Team, name tag and health bar colours for this run
+    palette: PaletteSet,
     dialogue_queue: Vec<DialogueLine>,
-    current_dialogue: Option<DialogueLine>,
-    dialogue_timer: f32,
+    dialogue_box: DialogueBox,
+    /// Voice lines and barks spoken over the fight without stopping it
+    subtitles: SubtitleManager,
+    /// Bosses that have already called out at half health
+    bosses_barked: Vec<EntityId>,
+    announcer: Announcer,
+    /// P1's shouts on abilities and grunts on hits
+    voice: FighterVoice,
+    /// Hits P1 has taken back to back, and how long the streak has left
+    /// before it stops counting as a combo
+    hits_taken: u32,
+    hits_taken_timer: f32,
     show_controls: bool,
     control_fade: f32,
     previous_fighter_states: HashMap<EntityId, FighterState>,
     shop_manager: ShopManager,
     shop_open: bool,
+    /// The shopkeeper set up between waves, and what they brought this visit
+    shopkeeper: Option<EntityId>,
+    keeper_stock: Vec<ShopStock>,
+    /// Best style rank P1 hit this wave; the shopkeeper haggles on it
+    wave_best_rank: StyleRank,
     shop_feedback_timer: f32,
     shop_feedback_message: Option<String>,
     player_move_speed: f32,
     player_max_health: f32,
     player_attack_multiplier: f32,
     game_over: bool,
+    // Waves and damage so far, logged for balance reports when the run ends
+    run_tally: RunTally,
+    difficulty: Difficulty,
+    /// What was logged of the run once it's over
+    finished_run: Option<RunRecord>,
     selected_character: CharacterId,
+    /// The selected character's stats and signature special
+    player_moveset: CharacterMoveset,
     ability_state: AbilityState,
+    /// Single-player tag partner, when one was picked
+    tag_team: Option<TagTeam>,
+    /// The partner while they're in for an assist
+    assist_entity: Option<EntityId>,
     burning_enemies: HashMap<EntityId, (f32, f32)>, // entity -> (remaining_time, dps)
     transition_to: Option<StateType>,
+    /// A cutscene to play over the run, which resumes after it
+    overlay: Option<StateType>,
     bomb_entities: Vec<EntityId>,
     bomb_spawn_timer: f32,
+    target_grid: SpatialGrid<EntityId>,
+    grid_hits: Vec<EntityId>,
     boss_battle_won: bool,
-    dialogue_choice_active: bool,
+    /// The conversation after the boss battle, while it's being answered
+    conversation: Option<Conversation>,
     dialogue_choice_selected: usize,
+    // Branching routes: options offered after a map, and the one being played
+    route_choices: Vec<(MapType, RouteModifier)>,
+    route_choice_selected: usize,
+    route_modifier: RouteModifier,
+    /// Co-op difficulty for the team's size; solo runs aren't scaled
+    coop_scaling: PlayerCountScaling,
+    visited_maps: Vec<MapType>,
+    // Winter Arc: the run in progress, the card highlighted while picking an
+    // upgrade, the rooms still ahead and the payout once the run is over
+    winter_arc: Option<WinterArcRun>,
+    upgrade_choice_selected: usize,
+    winter_arc_rooms: Vec<MapType>,
+    winter_arc_result: Option<RunResult>,
+    /// Today's and this week's challenges, and the seeded run being played
+    /// if this is one
+    challenge_manager: ChallengeManager,
+    challenge_run: Option<ChallengeRun>,
+    /// Set for a run started from the practice screen, which pays out nothing
+    practice: Option<PracticeStart>,
+    /// Set while the character hasn't been unlocked and is only on trial
+    trial: Option<TrialRun>,
+    /// Furthest wave regular runs have started on each map
+    practice_progress: PracticeProgress,
     // Auto-attack system
     auto_attack_timer: f32,
     auto_attack_delay: f32,
     is_holding_attack: bool,
+    /// One-button combos from the accessibility settings
+    combo_assist: ComboAssist,
     // New integrated systems
     coop_manager: Option<CoopPlayerManager>,
     shared_combo: Option<SharedComboSystem>,
     revive_system: Option<ReviveSystem>,
+    lives_pool: Option<SharedLivesPool>,
+    coop_ui: Option<CoopUI>,
+    /// Reads the devices of every human player after P1
+    coop_input: CoopInputHandler,
+    /// P1's keys this frame, with the Controls page's rebinds
+    keys: KeyboardBindings,
+    /// Each seat's ping wheel, and the markers teammates have placed
+    ping_wheels: [PingWheel; MAX_PLAYERS],
+    pings: PingBoard,
+    /// Seats bots are playing, and how well a bot plays a seat a human drops
+    /// out of; `None` in runs the lobby didn't give bots
+    bots: [Option<CoopBot>; MAX_PLAYERS],
+    bot_difficulty: Option<CpuDifficulty>,
+    /// Online co-op: the link to the partner, this machine's input gathered
+    /// since the last tick, and whether the tick is stalled on the partner
+    net_session: Option<NetSession>,
+    net_input: NetInput,
+    net_waiting: bool,
     combo_system: ComboSystem,
     plane_system: Option<PlaneSystem>,
+    /// Space pressed while flying, held until the next tick drops the bomb
+    bomb_drop_queued: bool,
+    /// Impact, footstep and bomb sounds, panned to where they happen
+    sfx: SFXManager,
+    footstep_timer: f32,
     enhanced_vfx: EnhancedVFXSystem,
     map_system: MapSystem,
     skill_tree_manager: SkillTreeManager,
+    /// What the player's tree adds to this run
+    skill_bonuses: SkillBonuses,
+    /// P1's crit, lifesteal and damage reduction as the combat system has them
+    hit_modifiers: HitModifiers,
+    /// Dashing, heavies and guarding run on the stamina bar this run
+    hardcore_stamina: bool,
     character_mastery: CharacterMastery,
+    /// P1's palette, outfit, hit sparks and victory pose
+    cosmetics: EquippedCosmetics,
+    /// Which of the ability's lines, its own then the unlocked ones, is next
+    voice_line_turn: usize,
     achievement_manager: AchievementManager,
-    account_progression: AccountProgression,
+    /// Achievement unlocks popping up in the corner
+    toasts: ToastQueue,
+    /// Set from the console: the players' health is kept topped up
+    god_mode: bool,
+    /// Whether any player has been hit this wave and this run, and gone down
+    wave_damage_taken: bool,
+    run_damage_taken: bool,
+    run_went_down: bool,
+    /// Whether allies fought alongside at any point this run
+    run_had_allies: bool,
+    /// Seconds played and Arc Tokens earned, for the account's lifetime stats
+    run_time: f32,
+    run_tokens: u32,
+    /// Enemies taken down this run, for the story's conditions
+    run_kills: u32,
+    /// The rest of what the results screen shows: the best style reached,
+    /// XP earned and everything unlocked this run
+    run_best_rank: StyleRank,
+    run_mastery_xp: f32,
+    run_account_xp: f32,
+    run_unlocks: Vec<String>,
+    account: PlayerAccount,
+    player_pose: Option<PosePlayback>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -96,13 +291,249 @@ enum MapType {
     Rooftop,
 }
 
-struct DialogueLine {
-    speaker: String,
-    dutch: String,
-    english: String,
-    duration: f32,
+impl MapType {
+    /// Matching layout in the map system (spawn points, hazards, lighting)
+    fn layout(self) -> crate::render::map_system::MapType {
+        use crate::render::map_system::MapType as Layout;
+        match self {
+            MapType::Classroom => Layout::Classroom,
+            MapType::Hallway => Layout::Hallway,
+            MapType::Cafeteria => Layout::Cafeteria,
+            MapType::Gym => Layout::Gym,
+            MapType::Library => Layout::Library,
+            MapType::Rooftop => Layout::Rooftop,
+        }
+    }
+
+    /// The run's map for a layout, if runs can be played there
+    fn from_layout(layout: crate::render::map_system::MapType) -> Option<Self> {
+        use crate::render::map_system::MapType as Layout;
+        match layout {
+            Layout::Classroom => Some(MapType::Classroom),
+            Layout::Hallway => Some(MapType::Hallway),
+            Layout::Cafeteria => Some(MapType::Cafeteria),
+            Layout::Gym => Some(MapType::Gym),
+            Layout::Library => Some(MapType::Library),
+            Layout::Rooftop => Some(MapType::Rooftop),
+            _ => None,
+        }
+    }
+}
+
+/// Maps between the classroom and the rooftop, in their usual order. After each
+/// map the next two unvisited ones are offered as a route choice.
+const ROUTE_MAPS: [MapType; 4] = [
+    MapType::Hallway,
+    MapType::Cafeteria,
+    MapType::Gym,
+    MapType::Library,
+];
+
+impl MapType {
+    fn name(self) -> &'static str {
+        match self {
+            MapType::Classroom => "Classroom",
+            MapType::Hallway => "Hallway",
+            MapType::Cafeteria => "Cafeteria",
+            MapType::Gym => "Gym",
+            MapType::Library => "Library",
+            MapType::Rooftop => "Rooftop",
+        }
+    }
+
+    /// Modifier a route into this map comes with
+    fn route_modifier(self) -> RouteModifier {
+        match self {
+            MapType::Hallway => RouteModifier::Quiet,
+            MapType::Cafeteria => RouteModifier::Rowdy,
+            MapType::Gym => RouteModifier::Crowded,
+            MapType::Library => RouteModifier::Elite,
+            MapType::Classroom | MapType::Rooftop => RouteModifier::None,
+        }
+    }
+
+    /// Map an enemy knocked through this map's door crashes into
+    fn through_the_door(self) -> Option<MapType> {
+        match self {
+            MapType::Classroom => Some(MapType::Hallway),
+            _ => None,
+        }
+    }
+}
+
+/// Run modifier picked with a route; lasts until the map it leads to is cleared
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RouteModifier {
+    None,
+    Quiet,
+    Rowdy,
+    Crowded,
+    Elite,
+}
+
+impl RouteModifier {
+    fn description(self) -> &'static str {
+        match self {
+            RouteModifier::None => "No modifiers",
+            RouteModifier::Quiet => "Fewer enemies, heal 40% on arrival",
+            RouteModifier::Rowdy => "Enemies react faster, +50% kill XP",
+            RouteModifier::Crowded => "+50% enemies, +50% Arc Tokens",
+            RouteModifier::Elite => "Tougher enemies, free upgrade when cleared",
+        }
+    }
+
+    fn enemy_count_scale(self) -> f32 {
+        match self {
+            RouteModifier::Quiet => 0.7,
+            RouteModifier::Crowded => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    fn enemy_health_scale(self) -> f32 {
+        match self {
+            RouteModifier::Elite => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Multiplies enemy AI reaction delay
+    fn enemy_reaction_scale(self) -> f32 {
+        match self {
+            RouteModifier::Rowdy => 0.6,
+            _ => 1.0,
+        }
+    }
+
+    fn token_scale(self) -> f32 {
+        match self {
+            RouteModifier::Crowded => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    fn xp_scale(self) -> f32 {
+        match self {
+            RouteModifier::Rowdy => 1.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Walkable floor band fighters are kept inside, on a screen this wide
+fn arena_bounds(screen_width: f32) -> Rect {
+    Rect::new(60.0, 340.0, screen_width - 120.0, 320.0)
 }
 
+/// Knockback speed that carries an enemy through a door, and how close to
+/// the door it has to be
+const KNOCK_THROUGH_SPEED: f32 = 250.0;
+const KNOCK_THROUGH_REACH: f32 = 90.0;
+/// Share of max health lost when knocked off the rooftop
+const RING_OUT_DAMAGE: f32 = 0.2;
+/// Where a rung-out fighter climbs back onto the roof, in from the edge
+const RING_OUT_RETURN: f32 = 140.0;
+
+/// Enemies within this distance of a player are kept in frame
+const THREAT_RANGE: f32 = 520.0;
+/// How many of the nearest threats the camera frames alongside the players
+const FRAMED_THREATS: usize = 2;
+/// Transforms sit at the feet; the camera frames fighters' chests
+const FRAMING_LIFT: Vec2 = Vec2::new(0.0, -48.0);
+/// How long each kind of moment holds the camera, in seconds
+const BOSS_INTRO_FOCUS: f32 = 2.5;
+const STAGE_CHANGE_FOCUS: f32 = 1.4;
+const SUPER_FOCUS: f32 = 0.8;
+const EXPLOSION_FOCUS: f32 = 0.4;
+
+/// Input device of each co-op seat in join order, with the key that drops in
+/// from it. P1 always plays on the main keyboard.
+const COOP_DEVICES: [(InputDevice, &str); MAX_PLAYERS] = [
+    (InputDevice::Keyboard, "F"),
+    (InputDevice::KeyboardArrows, "'"),
+    (InputDevice::KeyboardNumpad, "Numpad +"),
+    (InputDevice::Gamepad(0), "A"),
+];
+
+/// Arc Tokens for each boss beaten, before any bounty
+const BOSS_REWARD: u32 = 100;
+
+/// Team meter each co-op hit adds, before the manager's team multiplier
+const TEAM_HIT_METER: f32 = 3.0;
+/// A team attack fills more meter and hits harder
+const TEAM_ATTACK_METER: f32 = 8.0;
+/// Extra damage a team attack deals, as a fraction of the hit
+const TEAM_ATTACK_BONUS: f32 = 0.5;
+/// Full team meter, all of which the dual super spends
+const TEAM_SUPER_COST: f32 = 100.0;
+/// Dual super damage to every enemy near either player, before the team combo
+const TEAM_SUPER_DAMAGE: f32 = 60.0;
+const TEAM_SUPER_RADIUS: f32 = 320.0;
+
+/// What each power-up does while it lasts
+const STRENGTH_MULTIPLIER: f32 = 1.5;
+const HASTE_MULTIPLIER: f32 = 1.3;
+const REGEN_PER_SECOND: f32 = 4.0;
+/// Gap between several drops from one enemy, so they don't stack
+const LOOT_SPREAD: f32 = 40.0;
+
+/// Mastery XP for clearing a wave, plus more for each wave further in
+const MASTERY_WAVE_XP: f32 = 150.0;
+const MASTERY_WAVE_XP_STEP: f32 = 25.0;
+/// A combo earns mastery XP every this many hits, for each hit in it
+const MASTERY_COMBO_STEP: u32 = 10;
+const MASTERY_COMBO_XP: f32 = 3.0;
+/// The signature move unlocked at Master rank goes off with the ability
+const SIGNATURE_DAMAGE: f32 = 80.0;
+const SIGNATURE_RADIUS: f32 = 360.0;
+/// Blast a defeated enemy goes off with under the Volatile challenge modifier
+const DEATH_BLAST_RADIUS: f32 = 90.0;
+const DEATH_BLAST_DAMAGE: f32 = 12.0;
+/// Hardcore stamina: a dash is a quick slide that costs a chunk of the bar,
+/// heavies cost more than usual, and holding or taking hits on a guard
+/// drains it
+const DASH_STAMINA: f32 = 25.0;
+const DASH_SPEED: f32 = 900.0;
+const DASH_TIME: f32 = 0.15;
+const HEAVY_STAMINA: f32 = 30.0;
+const HARDCORE_HEAVY_STAMINA: f32 = 40.0;
+const GUARD_STAMINA_PER_SECOND: f32 = 12.0;
+const BLOCKED_HIT_STAMINA: f32 = 10.0;
+/// Share of the bar an exhausted fighter has to win back before acting again
+const EXHAUSTION_RECOVERY: f32 = 0.35;
+/// Share of its health a boss calls out at
+const BOSS_BARK_HEALTH: f32 = 0.5;
+/// Hitstop on a landed heavy or special, in seconds
+const HEAVY_HITSTOP: f32 = 0.05;
+const SPECIAL_HITSTOP: f32 = 0.07;
+/// Simulation speed and length of the slow motion on the blow that clears a
+/// wave, and on a super going off
+const FINAL_BLOW_SLOW_MOTION: (f32, f32) = (0.25, 1.0);
+const SUPER_SLOW_MOTION: (f32, f32) = (0.5, 0.6);
+/// Hits on P1 inside the window that make hitting back a combo breaker
+const COMBO_BREAKER_HITS: u32 = 3;
+const COMBO_BREAKER_WINDOW: f32 = 1.5;
+/// Seconds between a walking player's footsteps
+const FOOTSTEP_INTERVAL: f32 = 0.32;
+
+/// What the first rank of each shop upgrade is worth; later ranks give less,
+/// and they stack on the skill tree's bonuses
+const SHOP_ATTACK: f32 = 0.4;
+const SHOP_HEALTH: f32 = 40.0;
+const SHOP_SPEED: f32 = 60.0;
+const SHOP_COOLDOWN_REDUCTION: f32 = 0.2;
+const SHOP_DURATION_BONUS: f32 = 2.0;
+const SHOP_LIFESTEAL: f32 = 0.1;
+const SHOP_DAMAGE_REDUCTION: f32 = 0.15;
+const SHOP_CRIT_CHANCE: f32 = 0.2;
+/// Health a Revive Token stands P1 back up with
+const REVIVE_TOKEN_HEALTH: f32 = 0.5;
+const SMOKE_RADIUS: f32 = 220.0;
+const SMOKE_STUN: f32 = 2.5;
+
+/// Upgrades the shopkeeper brings each visit, next to one consumable
+const KEEPER_UPGRADES: usize = 3;
+
 #[derive(Clone, Copy)]
 struct ShopOption {
     id: UpgradeId,
@@ -162,22 +593,101 @@ const SHOP_OPTIONS: [ShopOption; 8] = [
     },
 ];
 
+/// One row of the shopkeeper's stall
+#[derive(Clone, Copy)]
+enum ShopStock {
+    Upgrade(ShopOption),
+    Item(Consumable),
+}
+
 impl GameplayState {
     pub fn set_graphics_enhancement(&mut self, graphics: *mut GraphicsEnhancement) {
         self.graphics_enhancement = Some(graphics);
     }
 
-    pub fn enable_coop(&mut self, _max_players: usize) {
+    /// Scale the enemies for `difficulty`. Only the balance simulation plays
+    /// at anything but Normal; set it before `enter` spawns the first wave.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+        self.coop_scaling = difficulty.scaling();
+        if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+            combat.set_enemy_attack_multiplier(self.coop_scaling.enemy_damage);
+        }
+    }
+
+    pub fn enable_coop(&mut self, players: usize) {
+        // The lobby hands over what it previewed; fall back to the table
+        self.coop_scaling = crate::data::get_coop_scaling()
+            .unwrap_or_else(|| CoopScaling::load().for_players(players));
+        if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+            combat.set_enemy_attack_multiplier(self.coop_scaling.enemy_damage);
+        }
+
         self.coop_manager = Some(CoopPlayerManager::new());
         self.shared_combo = Some(SharedComboSystem::new());
         self.revive_system = Some(ReviveSystem::new());
+        self.lives_pool = Some(SharedLivesPool::new(crate::data::get_coop_lives()));
+        self.coop_ui = Some(CoopUI::new());
+    }
+
+    /// Play a Winter Arc run: the maps in a shuffled order, an upgrade pick
+    /// after every wave and a single life. Shop upgrades stay at home.
+    pub fn enable_winter_arc(&mut self) {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        let mut run = WinterArcRun::new(seed, &WinterArcProgress::load());
+        let mut rooms = ROUTE_MAPS.to_vec();
+        run.shuffle(&mut rooms);
+        self.winter_arc_rooms = rooms;
+        self.winter_arc = Some(run);
+    }
+
+    /// Play the board's seeded run: everyone gets the same spawns and drops
+    /// on the same day, under the same modifiers
+    pub fn enable_challenge(&mut self, kind: ChallengeKind) {
+        let run = self.challenge_manager.run(kind);
+        self.seed_simulation(run.seed);
+        self.challenge_run = Some(run);
+    }
+
+    /// Reseed everything a tick rolls dice with: the world's generator for
+    /// crits, AI and spawns, and the loot's
+    fn seed_simulation(&mut self, seed: u64) {
+        self.world.seed_rng(seed);
+        self.loot_rng = SeededRng::new(seed);
+    }
+
+    /// Product of `scale` over the challenge run's modifiers, 1 outside one
+    fn challenge_scale(&self, scale: fn(ChallengeModifier) -> f32) -> f32 {
+        self.challenge_run.as_ref().map_or(1.0, |run| run.scale(scale))
+    }
+
+    fn challenge_has(&self, modifier: ChallengeModifier) -> bool {
+        self.challenge_run.as_ref().is_some_and(|run| run.has(modifier))
     }
 
     pub fn new() -> Self {
+        Self::with_host(Box::new(Window), crate::data::get_selected_character())
+    }
+
+    /// A run as `character` on `host`, which tests and the balance runner
+    /// swap for a headless one
+    pub fn with_host(host: Box<dyn Host>, character: CharacterId) -> Self {
+        let plugins = crate::plugins::registry();
+        // Online sessions and challenge boards swap this for their own seed
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        let mut world = World::new();
+        world.seed_rng(seed);
         Self {
-            world: World::new(),
+            camera: GameCamera::new(host.screen_size()),
+            host,
+            world,
             player_entity: None,
-            player2_entity: None,
             ally_entities: Vec::new(),
             ally_roster: vec![
                 CharacterType::Luca,
@@ -186,61 +696,137 @@ impl GameplayState {
                 CharacterType::Nitin,
             ],
             enemy_entities: Vec::new(),
-            camera_pos: Vec2::ZERO,
-            movement_system: MovementSystem,
-            physics_system: PhysicsSystem::new(),
-            animation_system: AnimationSystem,
-            combat_system: CombatSystem::new(),
-            particle_system: ParticleSystem,
-            ai_system: AISystem,
+            post: PostProcessor::new(crate::data::get_post_processing()),
+            transform_snapshot: TransformSnapshot::new(),
+            render_alpha: 1.0,
+            systems: plugins.schedule(),
+            event_handlers: plugins.event_handlers(),
             input_manager: InputManager::new(),
-            texture_manager: TextureManager::new(),
+            texture_manager: None,
+            character_sheets: None,
+            sprite_batch: SpriteBatch::default(),
             graphics_enhancement: None,
             current_map: MapType::Classroom,
             current_wave: 0,
             waves_completed: 0,
             enemies_to_spawn: 0,
             spawn_timer: 0.0,
+            encounters: EncounterScript::default(),
+            encounter_problems: Vec::new(),
+            wave_spawned: 0,
+            wave_start_health: None,
             paused: false,
+            pause_menu: PauseMenu::new(),
+            auto_pause: AutoPause::from_config(&GameConfig::load()),
+            palette: palette::active(),
             dialogue_queue: Vec::new(),
-            current_dialogue: None,
-            dialogue_timer: 0.0,
+            dialogue_box: DialogueBox::new(),
+            subtitles: SubtitleManager::new(),
+            announcer: Announcer::new(),
+            voice: FighterVoice::new(),
+            hits_taken: 0,
+            hits_taken_timer: 0.0,
+            bosses_barked: Vec::new(),
             show_controls: true,
             control_fade: 1.0,
             previous_fighter_states: HashMap::new(),
             shop_manager: ShopManager::load(),
             shop_open: false,
+            shopkeeper: None,
+            keeper_stock: Vec::new(),
+            wave_best_rank: StyleRank::D,
             shop_feedback_timer: 0.0,
             shop_feedback_message: None,
             player_move_speed: 260.0,
             player_max_health: 100.0,
             player_attack_multiplier: 1.0,
             game_over: false,
-            selected_character: crate::data::get_selected_character(),
-            ability_state: AbilityState::new(crate::data::get_selected_character()),
+            run_tally: RunTally::default(),
+            difficulty: Difficulty::Normal,
+            finished_run: None,
+            selected_character: character,
+            player_moveset: CharacterMoveset::for_character(character.to_character_type()),
+            ability_state: AbilityState::new(character),
+            tag_team: None,
+            assist_entity: None,
             burning_enemies: HashMap::new(),
             transition_to: None,
+            overlay: None,
             bomb_entities: Vec::new(),
             bomb_spawn_timer: 0.0,
+            target_grid: SpatialGrid::new(128.0),
+            grid_hits: Vec::new(),
             boss_battle_won: false,
-            dialogue_choice_active: false,
+            conversation: None,
             dialogue_choice_selected: 0,
+            route_choices: Vec::new(),
+            route_choice_selected: 0,
+            route_modifier: RouteModifier::None,
+            coop_scaling: PlayerCountScaling::SOLO,
+            visited_maps: vec![MapType::Classroom],
+            winter_arc: None,
+            challenge_manager: ChallengeManager::load(),
+            challenge_run: None,
+            hud_layout: HudLayout::load(),
+            loot_table: DropTable::load(),
+            loot_rng: SeededRng::new(seed),
+            power_ups: Vec::new(),
+            upgrade_choice_selected: 0,
+            winter_arc_rooms: Vec::new(),
+            winter_arc_result: None,
+            practice: None,
+            trial: None,
+            practice_progress: PracticeProgress::load(),
             // Auto-attack system
             auto_attack_timer: 0.0,
             auto_attack_delay: 0.25, // Attack every 0.25 seconds when holding
             is_holding_attack: false,
+            combo_assist: ComboAssist::new(),
             // New integrated systems
             coop_manager: None, // Will be initialized when co-op is enabled
             shared_combo: None,
             revive_system: None,
+            lives_pool: None,
+            coop_ui: None,
+            coop_input: CoopInputHandler::new(),
+            keys: KeyboardLayout::Wasd.bindings(&crate::data::get_key_rebinds()),
+            ping_wheels: [PingWheel::default(); MAX_PLAYERS],
+            pings: PingBoard::new(),
+            bots: Default::default(),
+            bot_difficulty: None,
+            net_session: None,
+            net_input: NetInput::default(),
+            net_waiting: false,
             combo_system: ComboSystem::new(),
             plane_system: None, // Initialized when Keizer Bom Taha uses ability
+            bomb_drop_queued: false,
+            sfx: SFXManager::new(),
+            footstep_timer: 0.0,
             enhanced_vfx: EnhancedVFXSystem::new(),
             map_system: MapSystem::new(crate::render::map_system::MapType::Classroom),
-            skill_tree_manager: SkillTreeManager::new(),
-            character_mastery: CharacterMastery::new(crate::data::get_selected_character()),
-            achievement_manager: AchievementManager::new(),
-            account_progression: AccountProgression::new(),
+            skill_tree_manager: SkillTreeManager::load(),
+            skill_bonuses: SkillBonuses::default(),
+            hit_modifiers: HitModifiers::default(),
+            hardcore_stamina: crate::data::get_hardcore_stamina(),
+            character_mastery: CharacterMastery::load(character),
+            cosmetics: EquippedCosmetics::default(),
+            voice_line_turn: 0,
+            achievement_manager: AchievementManager::load(),
+            toasts: ToastQueue::new(),
+            god_mode: false,
+            wave_damage_taken: false,
+            run_damage_taken: false,
+            run_went_down: false,
+            run_had_allies: false,
+            run_time: 0.0,
+            run_tokens: 0,
+            run_kills: 0,
+            run_best_rank: StyleRank::D,
+            run_mastery_xp: 0.0,
+            run_account_xp: 0.0,
+            run_unlocks: Vec::new(),
+            account: PlayerAccount::load(),
+            player_pose: None,
         }
     }
 
@@ -290,6 +876,8 @@ impl GameplayState {
             },
         );
 
+        self.world.add_component(entity, AnimationPlayer::new());
+
         self.world.add_component(
             entity,
             Fighter {
@@ -314,9 +902,16 @@ impl GameplayState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            entity,
+            PhysicsBody::fighter(Team::Player, self.player_moveset.stats.weight),
+        );
+
         self.world.add_component(
             entity,
             Stamina {
@@ -332,117 +927,171 @@ impl GameplayState {
         self.player_entity = Some(entity);
     }
 
-    fn spawn_coop_players(&mut self, player_chars: Vec<CharacterId>) {
-        // Spawn multiple players for co-op
-        for (i, &char_id) in player_chars.iter().enumerate() {
-            let entity = self.world.create_entity();
+    fn spawn_coop_players(&mut self, mut player_chars: Vec<CharacterId>) {
+        // Bots fill the seats after the humans', in local play only
+        let online = self.net_session.is_some();
+        let humans = player_chars.len();
+        let bots = crate::data::take_coop_bots().filter(|_| !online);
+        if let Some(bots) = &bots {
+            player_chars.extend(&bots.characters);
+            self.bot_difficulty = Some(bots.difficulty);
+        }
 
-            // Position players side by side
-            let x_offset = 200.0 + (i as f32 * 150.0);
+        // Spawn multiple players for co-op, side by side
+        let players: Vec<(CharacterId, EntityId)> = player_chars
+            .iter()
+            .take(MAX_PLAYERS)
+            .enumerate()
+            .map(|(i, &char_id)| {
+                let position = Vec2::new(200.0 + i as f32 * 150.0, 500.0);
+                (char_id, self.spawn_player_entity(char_id, position))
+            })
+            .collect();
 
-            self.world.add_component(
-                entity,
-                Transform {
-                    position: Vec2::new(x_offset, 500.0),
-                    rotation: 0.0,
-                    scale: Vec2::ONE,
-                },
-            );
+        if let Some(&(char_id, entity)) = players.first() {
+            self.player_entity = Some(entity);
+            self.selected_character = char_id;
+            self.player_moveset = CharacterMoveset::for_character(char_id.to_character_type());
+            self.ability_state = AbilityState::new(char_id);
+            self.refresh_upgrades();
+            let mastery_level = self.character_mastery.level;
+            self.cosmetics = CosmeticsProgress::load().equipped(char_id, mastery_level);
+        }
 
-            self.world.add_component(
-                entity,
-                Velocity {
-                    linear: Vec2::ZERO,
-                    angular: 0.0,
-                },
-            );
+        // Enable co-op systems; every seat is a human on its own device, or
+        // online, on the inputs both sides exchange
+        if players.len() > 1 {
+            self.enable_coop(players.len());
+            if let Some(manager) = self.coop_manager.as_mut() {
+                let configs = players
+                    .iter()
+                    .zip(COOP_DEVICES)
+                    .enumerate()
+                    .map(|(index, (&(char_id, _), (device, _)))| {
+                        if online {
+                            (char_id, InputDevice::Network(index as u8))
+                        } else if index >= humans {
+                            (char_id, InputDevice::Bot(index as u8))
+                        } else {
+                            (char_id, device)
+                        }
+                    })
+                    .collect();
+                manager.init_coop(configs);
+                for (index, &(_, entity)) in players.iter().enumerate() {
+                    let slot = PlayerSlot::from_index(index);
+                    if let Some(player) = slot.and_then(|slot| manager.get_player_mut(slot)) {
+                        player.set_entity(entity);
+                    }
+                }
+            }
+            if let Some(difficulty) = self.bot_difficulty {
+                for (index, &(char_id, _)) in players.iter().enumerate().skip(humans) {
+                    self.bots[index] = Some(CoopBot::new(char_id, difficulty));
+                }
+            }
+        }
+    }
 
-            self.world.add_component(
-                entity,
-                Health {
-                    current: self.player_max_health,
-                    maximum: self.player_max_health,
-                    armor: 0.0,
-                },
-            );
+    /// A human-controlled fighter; the caller decides which seat it belongs to
+    fn spawn_player_entity(&mut self, char_id: CharacterId, position: Vec2) -> EntityId {
+        let entity = self.world.create_entity();
 
-            self.world.add_component(
-                entity,
-                HurtboxComponent {
-                    hurtbox: Hurtbox::new_standing(),
-                    active: true,
-                },
-            );
+        self.world.add_component(
+            entity,
+            Transform {
+                position,
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            },
+        );
 
-            self.world.add_component(
-                entity,
-                HitboxComponent {
-                    hitbox: Hitbox::new_light(),
-                    active: false,
-                    hits_registered: Vec::new(),
-                },
-            );
+        self.world.add_component(
+            entity,
+            Velocity {
+                linear: Vec2::ZERO,
+                angular: 0.0,
+            },
+        );
+
+        self.world.add_component(
+            entity,
+            Health {
+                current: self.player_max_health,
+                maximum: self.player_max_health,
+                armor: 0.0,
+            },
+        );
 
-            // Convert CharacterId to CharacterType
-            let character_type = char_id.to_character_type();
+        self.world.add_component(
+            entity,
+            HurtboxComponent {
+                hurtbox: Hurtbox::new_standing(),
+                active: true,
+            },
+        );
 
-            self.world.add_component(
-                entity,
-                Fighter {
-                    character_type,
-                    state: FighterState::Idle,
-                    combo_counter: 0,
-                    meter: 0.0,
-                    max_meter: 100.0,
-                    hitstun: 0.0,
-                    blockstun: 0.0,
-                    invulnerable: false,
-                    facing: 1.0,
-                    attack_timer: 0.0,
-                    team: Team::Player,
-                    consecutive_hits_taken: 0,
-                    hit_decay_timer: 0.0,
-                    // New combat system fields
-                    combo_chain: 0,
-                    combo_window_timer: 0.0,
-                    can_cancel: true,
-                    attack_startup: 0.0,
-                    attack_recovery: 0.0,
-                    is_blocking: false,
-                    parry_window: 0.0,
-                },
-            );
+        self.world.add_component(
+            entity,
+            HitboxComponent {
+                hitbox: Hitbox::new_light(),
+                active: false,
+                hits_registered: Vec::new(),
+            },
+        );
 
-            self.world.add_component(
-                entity,
-                Stamina {
-                    current: 100.0,
-                    maximum: 100.0,
-                    regen_rate: 40.0,
-                    regen_delay: 0.5,
-                    regen_delay_timer: 0.0,
-                    exhausted: false,
-                },
-            );
+        // Convert CharacterId to CharacterType
+        let character_type = char_id.to_character_type();
 
-            // Set first player as main player, second as player 2
-            if i == 0 {
-                self.player_entity = Some(entity);
-                self.selected_character = char_id;
-                self.ability_state = AbilityState::new(char_id);
-            } else if i == 1 {
-                // Second player is player 2 (not AI ally)
-                self.player2_entity = Some(entity);
-            } else {
-                // Any additional players become AI allies
-                self.ally_entities.push(entity);
-            }
-        }
+        self.world.add_component(entity, AnimationPlayer::new());
 
-        // Enable co-op systems
-        if player_chars.len() > 1 {
-            self.enable_coop(player_chars.len());
-        }
+        self.world.add_component(
+            entity,
+            Fighter {
+                character_type,
+                state: FighterState::Idle,
+                combo_counter: 0,
+                meter: 0.0,
+                max_meter: 100.0,
+                hitstun: 0.0,
+                blockstun: 0.0,
+                invulnerable: false,
+                facing: 1.0,
+                attack_timer: 0.0,
+                team: Team::Player,
+                consecutive_hits_taken: 0,
+                hit_decay_timer: 0.0,
+                // New combat system fields
+                combo_chain: 0,
+                combo_window_timer: 0.0,
+                can_cancel: true,
+                attack_startup: 0.0,
+                attack_recovery: 0.0,
+                is_blocking: false,
+                parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
+            },
+        );
+
+        self.world.add_component(
+            entity,
+            PhysicsBody::fighter(Team::Player, character_type.weight()),
+        );
+
+        self.world.add_component(
+            entity,
+            Stamina {
+                current: 100.0,
+                maximum: 100.0,
+                regen_rate: 40.0,
+                regen_delay: 0.5,
+                regen_delay_timer: 0.0,
+                exhausted: false,
+            },
+        );
+
+        entity
     }
 
     fn spawn_big_boss(&mut self) {
@@ -491,6 +1140,8 @@ impl GameplayState {
             },
         );
 
+        self.world.add_component(boss_entity, AnimationPlayer::new());
+
         self.world.add_component(
             boss_entity,
             Fighter {
@@ -515,9 +1166,16 @@ impl GameplayState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            boss_entity,
+            PhysicsBody::fighter(Team::Enemy, CharacterType::Bastiaan.weight()),
+        );
+
         self.world.add_component(
             boss_entity,
             Stamina {
@@ -538,10 +1196,13 @@ impl GameplayState {
                 state_timer: 0.0,
                 reaction_delay: 0.15, // Very fast attacks
                 difficulty: 1.0,      // Maximum difficulty
+                reaction_scale: 1.0,
+                ability_rate: 1.0,
             },
         );
 
-        self.enemy_entities.push(boss_entity);
+        self.register_enemy(boss_entity);
+        self.focus_boss_intro(boss_entity);
 
         // Spawn Keizer Bom Taha - bomb thrower in the sky
         let keizer_entity = self.world.create_entity();
@@ -549,7 +1210,7 @@ impl GameplayState {
         self.world.add_component(
             keizer_entity,
             Transform {
-                position: Vec2::new(screen_width() * 0.7, 200.0), // High in the sky
+                position: Vec2::new(self.host.screen_width() * 0.7, 200.0), // High in the sky
                 rotation: 0.0,
                 scale: Vec2::new(1.5, 1.5), // Bigger than normal
             },
@@ -588,6 +1249,8 @@ impl GameplayState {
             },
         );
 
+        self.world.add_component(keizer_entity, AnimationPlayer::new());
+
         self.world.add_component(
             keizer_entity,
             Fighter {
@@ -612,9 +1275,16 @@ impl GameplayState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            keizer_entity,
+            PhysicsBody::fighter(Team::Enemy, CharacterType::KeizerBomTaha.weight()),
+        );
+
         self.world.add_component(
             keizer_entity,
             Stamina {
@@ -635,17 +1305,20 @@ impl GameplayState {
                 state_timer: 0.0,
                 reaction_delay: 1.5, // Slow bomb attacks
                 difficulty: 0.8,
+                reaction_scale: 1.0,
+                ability_rate: 1.0,
             },
         );
 
-        self.enemy_entities.push(keizer_entity);
+        self.register_enemy(keizer_entity);
 
         // Show boss intro dialogue
-        self.show_dialogue("Bastiaan", "IK BEN DE EINDBAAS!", "I AM THE FINAL BOSS!");
-        self.show_dialogue("Keizer Bom Taha", "Ik gooi bommen!", "I throw bombs!");
+        self.show_dialogue("Bastiaan", "dialogue.boss.bastiaan");
+        self.show_dialogue("Keizer Bom Taha", "dialogue.boss.keizer");
     }
 
     fn spawn_bomb(&mut self, pos: Vec2) {
+        self.play_sound_at(self.sfx.get_bomb_whistle(), pos);
         let bomb_entity = self.world.create_entity();
 
         self.world.add_component(
@@ -676,7 +1349,107 @@ impl GameplayState {
         self.bomb_entities.push(bomb_entity);
     }
 
-    fn spawn_enemy(&mut self, pos: Vec2, character: CharacterType) {
+    /// Replace the last map's props with the current map's
+    fn spawn_stage_props(&mut self) {
+        let old: Vec<_> = self.world.query::<StageProp>().map(|(e, _)| e).collect();
+        for entity in old {
+            self.world.commands().destroy(entity);
+        }
+
+        let arena = arena_bounds(self.host.screen_width());
+        let at = |x: f32, y: f32| Vec2::new(arena.x + arena.w * x, arena.y + arena.h * y);
+        let props = match self.current_map {
+            MapType::Classroom => [(0.25, 0.3), (0.5, 0.75), (0.75, 0.4)]
+                .iter()
+                .map(|&(x, y)| (PropKind::Chair { thrown_by: None }, at(x, y), Vec2::ZERO, 0.0))
+                .collect(),
+            MapType::Cafeteria => vec![
+                (PropKind::SoupSpill { radius: 80.0 }, at(0.3, 0.55), Vec2::ZERO, 0.0),
+                (PropKind::SoupSpill { radius: 60.0 }, at(0.7, 0.25), Vec2::ZERO, 0.0),
+            ],
+            MapType::Gym => vec![
+                (PropKind::Basketball, at(0.3, 0.3), Vec2::new(260.0, 180.0), 0.0),
+                (PropKind::Basketball, at(0.7, 0.7), Vec2::new(-220.0, -140.0), 0.0),
+            ],
+            // Staggered so the shelves don't all come down at once
+            MapType::Library => [(0.2, 0.0), (0.5, 2.5), (0.8, 5.0)]
+                .iter()
+                .map(|&(x, delay)| (PropKind::Bookshelf, at(x, 0.0), Vec2::ZERO, delay))
+                .collect(),
+            MapType::Rooftop => vec![(
+                PropKind::WindGust { direction: 1.0 },
+                at(0.5, 0.5),
+                Vec2::ZERO,
+                0.0,
+            )],
+            MapType::Hallway => Vec::new(),
+        };
+
+        for (kind, position, linear, timer) in props {
+            let entity = self.world.create_entity();
+            self.world.add_component(
+                entity,
+                Transform {
+                    position,
+                    rotation: 0.0,
+                    scale: Vec2::new(1.0, 1.0),
+                },
+            );
+            self.world.add_component(
+                entity,
+                Velocity {
+                    linear,
+                    angular: 0.0,
+                },
+            );
+            self.world.add_component(entity, StageProp { kind, timer });
+        }
+    }
+
+    /// Fresh furniture for the wave; wreckage from the last one is cleared
+    /// away, while loot left on the floor runs out on its own
+    fn spawn_destructibles(&mut self) {
+        let old: Vec<_> = self.world.query::<Destructible>().map(|(e, _)| e).collect();
+        for entity in old {
+            self.world.commands().destroy(entity);
+        }
+
+        let arena = arena_bounds(self.host.screen_width());
+        let at = |x: f32, y: f32| Vec2::new(arena.x + arena.w * x, arena.y + arena.h * y);
+        let furniture: Vec<_> = match self.current_map {
+            MapType::Classroom => [(0.15, 0.2), (0.4, 0.2), (0.6, 0.55), (0.85, 0.55)]
+                .iter()
+                .map(|&(x, y)| (DestructibleKind::Desk, at(x, y)))
+                .collect(),
+            MapType::Hallway | MapType::Gym => [0.2, 0.45, 0.7]
+                .iter()
+                .map(|&x| (DestructibleKind::Locker, at(x, 0.05)))
+                .collect(),
+            MapType::Cafeteria => vec![
+                (DestructibleKind::Table, at(0.2, 0.35)),
+                (DestructibleKind::Table, at(0.8, 0.65)),
+            ],
+            MapType::Library => vec![
+                (DestructibleKind::Table, at(0.35, 0.6)),
+                (DestructibleKind::Desk, at(0.65, 0.6)),
+            ],
+            MapType::Rooftop => Vec::new(),
+        };
+
+        for (kind, position) in furniture {
+            self.world
+                .commands()
+                .spawn()
+                .with(Transform {
+                    position,
+                    rotation: 0.0,
+                    scale: Vec2::ONE,
+                })
+                .with(Destructible::new(kind));
+        }
+    }
+
+    fn spawn_enemy(&mut self, pos: Vec2, character: CharacterType) -> EntityId {
         let entity = self.world.create_entity();
 
         self.world.add_component(
@@ -722,6 +1495,8 @@ impl GameplayState {
             },
         );
 
+        self.world.add_component(entity, AnimationPlayer::new());
+
         self.world.add_component(
             entity,
             Fighter {
@@ -746,9 +1521,16 @@ impl GameplayState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            entity,
+            PhysicsBody::fighter(Team::Enemy, character.weight()),
+        );
+
         self.world.add_component(
             entity,
             Stamina {
@@ -772,22 +1554,50 @@ impl GameplayState {
             _ => (AIBehavior::Balanced, 0.4),
         };
 
+        let stagger = self.world.rng().range_f32(0.0, 0.2);
         self.world.add_component(
             entity,
             AIController {
                 behavior,
                 target_entity: None, // Let AI system find nearest target (player or ally)
-                state_timer: rand::gen_range(0.0, 0.2),
+                state_timer: stagger,
                 reaction_delay: (0.35_f32 - difficulty * 0.15_f32).max(0.18_f32),
                 difficulty,
+                reaction_scale: 1.0,
+                ability_rate: 1.0,
             },
         );
 
-        self.enemy_entities.push(entity);
+        self.register_enemy(entity);
+        entity
     }
 
-    fn spawn_mees_boss(&mut self) {
-        // Spawn Mees boss - throws pita sirrachas with fire damage
+    /// Track a new enemy, toughened for the co-op team's size and any
+    /// Winter Arc curse
+    /// Give a freshly spawned boss the camera while it introduces itself
+    fn focus_boss_intro(&mut self, boss: EntityId) {
+        if let Some(transform) = self.world.get_component::<Transform>(boss) {
+            self.camera.push_focus(
+                FocusPriority::BossIntro,
+                transform.position + FRAMING_LIFT,
+                1.25,
+                Some(BOSS_INTRO_FOCUS),
+            );
+        }
+    }
+
+    fn register_enemy(&mut self, entity: EntityId) {
+        let curse = self.winter_arc.as_ref().map_or(1.0, |run| run.enemy_health_scale());
+        let scale = self.coop_scaling.enemy_health * curse;
+        if let Some(health) = self.world.get_component_mut::<Health>(entity) {
+            health.maximum *= scale;
+            health.current = health.maximum;
+        }
+        self.enemy_entities.push(entity);
+    }
+
+    fn spawn_mees_boss(&mut self) {
+        // Spawn Mees boss - throws pita sirrachas with fire damage
         let boss_entity = self.world.create_entity();
 
         self.world.add_component(
@@ -838,12 +1648,15 @@ impl GameplayState {
                     hit_type: HitType::Heavy,
                     can_juggle: false,
                     armor_break: true,
+                    height: AttackHeight::Mid,
                 },
                 active: false,
                 hits_registered: Vec::new(),
             },
         );
 
+        self.world.add_component(boss_entity, AnimationPlayer::new());
+
         self.world.add_component(
             boss_entity,
             Fighter {
@@ -868,9 +1681,16 @@ impl GameplayState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            boss_entity,
+            PhysicsBody::fighter(Team::Enemy, CharacterType::Mees.weight()),
+        );
+
         self.world.add_component(
             boss_entity,
             Stamina {
@@ -891,17 +1711,16 @@ impl GameplayState {
                 state_timer: 0.0,
                 reaction_delay: 0.2,
                 difficulty: 1.5,
+                reaction_scale: 1.0,
+                ability_rate: 1.0,
             },
         );
 
-        self.enemy_entities.push(boss_entity);
+        self.register_enemy(boss_entity);
+        self.focus_boss_intro(boss_entity);
 
         // Show boss health bar or special UI
-        self.show_dialogue(
-            "Mees",
-            "Je krijgt geen tikkie, dus je krijgt mijn pita sirracha!",
-            "No tikkie for you, so you get my pita sirracha!",
-        );
+        self.show_dialogue("Mees", "dialogue.boss.mees");
     }
 
     fn spawn_ally(&mut self, pos: Vec2, character: CharacterType) {
@@ -950,6 +1769,8 @@ impl GameplayState {
             },
         );
 
+        self.world.add_component(entity, AnimationPlayer::new());
+
         self.world.add_component(
             entity,
             Fighter {
@@ -974,9 +1795,16 @@ impl GameplayState {
                 attack_recovery: 0.0,
                 is_blocking: false,
                 parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
             },
         );
 
+        self.world.add_component(
+            entity,
+            PhysicsBody::fighter(Team::Ally, character.weight()),
+        );
+
         self.world.add_component(
             entity,
             Stamina {
@@ -989,16 +1817,8 @@ impl GameplayState {
             },
         );
 
-        self.world.add_component(
-            entity,
-            AIController {
-                behavior: AIBehavior::Support,
-                target_entity: None,
-                state_timer: rand::gen_range(0.0, 0.5),  // More varied start times
-                reaction_delay: 0.6,  // Slower reactions (was 0.32) - less spam
-                difficulty: 0.35,  // Lower skill level (was 0.45)
-            },
-        );
+        let controller = crate::data::get_ally_preset().controller(self.world.rng());
+        self.world.add_component(entity, controller);
 
         self.ally_entities.push(entity);
     }
@@ -1015,7 +1835,7 @@ impl GameplayState {
             if roster.is_empty() {
                 break;
             }
-            let idx = rand::gen_range(0, roster.len());
+            let idx = self.world.rng().range(0, roster.len() as i32) as usize;
             let character = roster.remove(idx);
             self.spawn_ally(pos, character);
         }
@@ -1024,8 +1844,24 @@ impl GameplayState {
 
 impl State for GameplayState {
     fn enter(&mut self) {
+        self.practice = crate::data::take_practice_start();
+        self.trial = crate::data::take_trial_run();
         self.apply_initial_upgrades();
 
+        let (encounters, problems) =
+            EncounterScript::load(|map| MapSystem::new(map).spawn_points.len());
+        for problem in &problems {
+            crash::log(format!("Encounter script: {}", problem));
+        }
+        self.encounters = encounters;
+        self.encounter_problems = problems;
+
+        // An online run comes from the lobby, and both sides roll the same dice
+        self.net_session = crate::net::take_session();
+        if let Some(seed) = self.net_session.as_ref().map(|session| session.seed()) {
+            self.seed_simulation(seed);
+        }
+
         // Check if we're in co-op mode
         if let Some(coop_players) = crate::data::get_coop_players() {
             // Co-op mode - spawn multiple players
@@ -1033,76 +1869,105 @@ impl State for GameplayState {
             // Clear the co-op data after using it
             crate::data::clear_coop_players();
         } else {
-            // Single player mode
+            // Single player mode, with a tag partner waiting on the bench
             self.spawn_player();
+            let selected = self.selected_character;
+            self.tag_team = crate::data::get_tag_partner()
+                .filter(|partner| *partner != selected)
+                .map(|partner| TagTeam::new(partner, CharacterMastery::load(partner)));
         }
 
-        self.combat_system
-            .set_player_attack_multiplier(self.player_attack_multiplier);
+        self.set_player_attack_multiplier(self.player_attack_multiplier);
+        let starting_meter = self.skill_bonuses.starting_meter.min(1.0);
+        if let Some(fighter) = self
+            .player_entity
+            .and_then(|entity| self.world.get_component_mut::<Fighter>(entity))
+        {
+            fighter.meter = fighter.max_meter * starting_meter;
+        }
+
+        // The player's intro pose opens the run, ahead of the story lines
+        let intro = crate::data::get_pose_choice()
+            .intro(self.selected_character, self.character_mastery.level);
+        self.start_pose(intro);
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Meneer Wolters".to_string(),
-            dutch: "Bas, vegen!".to_string(),
-            english: "Bas, sweep!".to_string(),
+            text: LineText::Key("dialogue.intro.wolters_1".to_string()),
             duration: 2.0,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Meneer Wolters".to_string(),
-            dutch: "Bas! Vegen, nu meteen!".to_string(),
-            english: "Bas! Sweep right now!".to_string(),
+            text: LineText::Key("dialogue.intro.wolters_2".to_string()),
             duration: 2.4,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Berkay".to_string(),
-            dutch: "Nee bro, ze gaan zien.".to_string(),
-            english: "No bro, they'll see.".to_string(),
+            text: LineText::Key("dialogue.intro.berkay".to_string()),
             duration: 2.2,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Luca".to_string(),
-            dutch: "Wacht maar… ik heb een winter arc plan.".to_string(),
-            english: "Just wait... I've got a winter arc plan.".to_string(),
+            text: LineText::Key("dialogue.intro.luca".to_string()),
             duration: 2.5,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Nitin".to_string(),
-            dutch: "Ik ga m’n barras in hun stoppen.".to_string(),
-            english: "I'm putting my barras in them.".to_string(),
+            text: LineText::Key("dialogue.intro.nitin".to_string()),
             duration: 2.5,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Hadi".to_string(),
-            dutch: "Aina broeg… ze gaan zien.".to_string(),
-            english: "Always, bro... they'll see.".to_string(),
+            text: LineText::Key("dialogue.intro.hadi".to_string()),
             duration: 2.3,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Bas".to_string(),
-            dutch: "Kom dan! Ik veeg niks, bro!".to_string(),
-            english: "Come on then! I'm not sweeping anything, bro!".to_string(),
+            text: LineText::Key("dialogue.intro.bas".to_string()),
             duration: 2.4,
         });
 
         self.dialogue_queue.push(DialogueLine {
             speaker: "Bastiaan".to_string(),
-            dutch: "Je hebt alles verpest, Bas! Mijn kunst was perfect!".to_string(),
-            english: "You ruined everything, Bas! My artwork was perfect!".to_string(),
+            text: LineText::Key("dialogue.intro.bastiaan".to_string()),
             duration: 2.6,
         });
 
         self.dialogue_queue.reverse();
 
+        // Practice skips the story and drops straight into the chosen wave
+        if let Some(start) = self.practice.clone() {
+            self.dialogue_queue.clear();
+            self.start_practice(&start);
+        }
+
+        self.spawn_stage_props();
         self.start_wave();
     }
 
     fn exit(&mut self) {
+        crate::data::set_away_muted(false);
         let _ = self.shop_manager.save();
+        let _ = self.character_mastery.save();
+        if let Some(tag) = &self.tag_team {
+            let _ = tag.partner_mastery.save();
+        }
+        if let Err(e) = self.achievement_manager.save() {
+            crash::log(e.to_string());
+        }
+        self.save_challenges();
+        // Leaving a Winter Arc run loses it, same as falling
+        self.finish_winter_arc(false);
+        // Modes picked from the menu go on with the character that's unlocked
+        if let Some(trial) = self.trial.take() {
+            crate::data::set_selected_character(trial.previous);
+        }
     }
 
     fn update(&mut self, dt: f32) {
@@ -1113,7 +1978,10 @@ impl State for GameplayState {
                 self.shop_feedback_message = None;
             }
         }
+        self.toasts.update(dt);
+        self.pings.update(dt);
 
+        self.update_auto_pause();
         if self.paused {
             return;
         }
@@ -1122,6 +1990,53 @@ impl State for GameplayState {
         if self.game_over {
             return;
         }
+        self.run_time += dt;
+
+        // Online, the leash moves players and so runs in the lockstep tick
+        if self.net_session.is_none() {
+            self.leash_players();
+        }
+        self.update_camera(dt);
+        self.update_post_effects(dt);
+
+        // Key presses only register for the frame they happen in, so anything
+        // reading them runs here rather than in the fixed tick
+        self.input_manager.update(&*self.host, &self.keys);
+        if self.plane_system.is_some() && self.host.key_pressed(self.keys.dodge) {
+            self.bomb_drop_queued = true;
+        }
+
+        if !self.dialogue_box.is_showing() && !self.shop_open {
+            if self.conversation.is_some() {
+                self.handle_dialogue_choice();
+            } else if !self.route_choices.is_empty() {
+                self.handle_route_choice();
+            } else if self.upgrade_choice_open() {
+                self.handle_upgrade_choice();
+            }
+        }
+    }
+
+    fn fixed_update(&mut self, dt: f64) {
+        let dt = dt as f32;
+        self.transform_snapshot.capture(&self.world);
+        self.world.set_view_width(self.host.screen_width());
+
+        if self.paused || self.game_over || self.camera.is_hit_stopped() {
+            return;
+        }
+        if !self.step_online(dt) {
+            return;
+        }
+
+        // Poses keep playing while their quip holds the rest of the game
+        if let Some(pose) = &mut self.player_pose {
+            pose.update(dt);
+            if pose.is_finished() {
+                self.player_pose = None;
+                self.camera.pop_focus(FocusPriority::Player);
+            }
+        }
 
         // Update combo system
         self.combo_system.update(dt);
@@ -1130,55 +2045,73 @@ impl State for GameplayState {
         self.enhanced_vfx.update(dt);
 
         // Update map system with player position
-        let camera_pos = if let Some(player_entity) = self.player_entity {
-            if let Some(transform) = self.world.get_component::<Transform>(player_entity) {
-                transform.position
-            } else {
-                Vec2::ZERO
-            }
-        } else {
-            Vec2::ZERO
-        };
-        self.map_system.update(dt, camera_pos);
+        self.map_system.update(dt, self.camera.position);
 
         // Update ability state
         self.ability_state.update(dt);
+        self.update_ability_aura();
+        self.update_tag_team(dt);
 
         // Update stamina for all entities
+        self.update_dashes(dt);
         self.update_stamina_system(dt);
 
         // Update plane system if active
         if let Some(plane) = &mut self.plane_system {
             // Get player input for plane control
             let mut input_direction = Vec2::ZERO;
-            if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
+            let keys = &self.keys;
+            if self.host.key_down(keys.move_up) || self.host.key_down(KeyCode::Up) {
                 input_direction.y -= 1.0;
             }
-            if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
+            if self.host.key_down(keys.move_down) || self.host.key_down(KeyCode::Down) {
                 input_direction.y += 1.0;
             }
-            if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+            if self.host.key_down(keys.move_left) || self.host.key_down(KeyCode::Left) {
                 input_direction.x -= 1.0;
             }
-            if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+            if self.host.key_down(keys.move_right) || self.host.key_down(KeyCode::Right) {
                 input_direction.x += 1.0;
             }
 
             plane.update(dt, input_direction);
 
             // Handle bomb dropping
-            if is_key_pressed(KeyCode::Space) {
+            if std::mem::take(&mut self.bomb_drop_queued) {
                 use crate::combat::plane_system::BombPattern;
                 let bombs = plane.drop_bomb(BombPattern::Single);
+                let whistle = self.sfx.get_bomb_whistle();
+                for bomb in &bombs {
+                    let x = self.camera.world_to_screen(bomb.position).x;
+                    let width = self.host.screen_width();
+                    crate::data::queue_sound(SFXManager::positional(whistle, x, width));
+                }
                 // Damage enemies hit by bombs
+                self.target_grid.clear();
+                for &enemy_entity in &self.enemy_entities {
+                    if let Some(enemy_transform) = self.world.get_component::<Transform>(enemy_entity) {
+                        self.target_grid.insert(enemy_entity, enemy_transform.position);
+                    }
+                }
                 for bomb in &bombs {
                     if bomb.exploded {
-                        for &enemy_entity in &self.enemy_entities.clone() {
+                        self.world.events().send(GameEvent::BombExploded {
+                            position: bomb.position,
+                            radius: bomb.explosion_radius,
+                        });
+                        self.target_grid.query_radius(
+                            bomb.position,
+                            bomb.explosion_radius + 30.0,
+                            &mut self.grid_hits,
+                        );
+                        for &enemy_entity in &self.grid_hits {
                             if let Some(enemy_transform) = self.world.get_component::<Transform>(enemy_entity) {
                                 if bomb.check_collision(enemy_transform.position, 30.0) {
+                                    let away = (enemy_transform.position - bomb.position).normalize_or_zero();
                                     if let Some(enemy_health) = self.world.get_component_mut::<Health>(enemy_entity) {
                                         enemy_health.current = (enemy_health.current - bomb.damage).max(0.0);
                                     }
+                                    apply_knockback(&mut self.world, enemy_entity, away * 420.0, 380.0);
                                 }
                             }
                         }
@@ -1191,15 +2124,20 @@ impl State for GameplayState {
         if self.auto_attack_timer > 0.0 {
             self.auto_attack_timer -= dt;
         }
+        self.combo_assist.update(dt);
 
         // Update ability voice line timer
         // Voice lines now handled through dialogue system
 
         // Update combat system with ability damage multiplier
         let ability_damage_mult = self.ability_state.get_damage_multiplier();
-        let total_damage_mult = self.player_attack_multiplier * ability_damage_mult;
-        self.combat_system
-            .set_player_attack_multiplier(total_damage_mult);
+        let tag_combo_mult = self.tag_team.as_ref().map_or(1.0, TagTeam::damage_bonus);
+        let total_damage_mult = self.player_attack_multiplier
+            * ability_damage_mult
+            * tag_combo_mult
+            * self.power_up_multiplier(PowerUpKind::Strength, STRENGTH_MULTIPLIER);
+        self.set_player_attack_multiplier(total_damage_mult);
+        self.update_power_ups(dt);
 
         // Update burning enemies
         let mut enemies_to_remove = Vec::new();
@@ -1209,6 +2147,7 @@ impl State for GameplayState {
             // Apply fire damage
             if let Some(enemy_health) = self.world.get_component_mut::<Health>(*entity) {
                 enemy_health.current = (enemy_health.current - *dps * dt).max(0.0);
+                self.run_tally.add_dealt("Burn", *dps * dt);
             }
 
             // Mark for removal if time expired
@@ -1222,20 +2161,41 @@ impl State for GameplayState {
             self.burning_enemies.remove(&entity);
         }
 
-        if self.current_dialogue.is_none() && !self.dialogue_queue.is_empty() {
-            self.current_dialogue = self.dialogue_queue.pop();
-            self.dialogue_timer = 0.0;
+        if !self.dialogue_box.is_showing() {
+            if let Some(line) = self.dialogue_queue.pop() {
+                self.dialogue_box.show(line);
+            }
         }
 
-        if let Some(ref dialogue) = self.current_dialogue {
-            self.dialogue_timer += dt;
-            if self.dialogue_timer >= dialogue.duration {
-                self.current_dialogue = None;
-            } else {
+        // The music sits under anyone talking and under a super's cinematic
+        let super_playing = self
+            .world
+            .query::<Fighter>()
+            .any(|(_, fighter)| fighter.state == FighterState::Super);
+        if self.dialogue_box.is_showing()
+            || self.conversation.is_some()
+            || self.subtitles.active_subtitle.is_some()
+            || super_playing
+        {
+            crate::data::duck_music();
+        }
+
+        if self.dialogue_box.is_showing() {
+            self.dialogue_box.update(dt);
+            if self.dialogue_box.is_showing() {
                 self.shop_open = false;
                 return;
             }
         }
+        self.bark_at_half_health();
+        self.subtitles.update(dt);
+        self.announcer.update(dt);
+        self.voice.update(dt);
+        self.hits_taken_timer -= dt;
+        if self.hits_taken_timer <= 0.0 {
+            self.hits_taken = 0;
+        }
+        self.play_footsteps(dt);
 
         if self.show_controls {
             self.control_fade -= dt * 0.1;
@@ -1244,29 +2204,17 @@ impl State for GameplayState {
             }
         }
 
+        self.update_shopkeeper(dt);
         if self.shop_open {
             return;
         }
 
-        // Handle dialogue choice after boss battle
-        if self.dialogue_choice_active {
-            if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
-                if self.dialogue_choice_selected > 0 {
-                    self.dialogue_choice_selected -= 1;
-                }
-            }
-            if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
-                if self.dialogue_choice_selected < 2 {
-                    self.dialogue_choice_selected += 1;
-                }
-            }
-            if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) {
-                // Player made a choice, end the game
-                self.dialogue_choice_active = false;
-                self.boss_battle_won = true;
-                // Transition to menu after choice is made
-                self.transition_to = Some(StateType::Menu);
-            }
+        // The boss dialogue choice, route choice and upgrade choice hold the
+        // game until picked
+        if self.conversation.is_some()
+            || !self.route_choices.is_empty()
+            || self.upgrade_choice_open()
+        {
             return;
         }
 
@@ -1301,7 +2249,6 @@ impl State for GameplayState {
         }
 
         // Update bombs - make them fall and check collisions
-        let mut bombs_to_remove = Vec::new();
         let mut explosion_positions = Vec::new();
         let mut entities_to_damage = Vec::new();
 
@@ -1311,101 +2258,71 @@ impl State for GameplayState {
             }
         }
 
-        for &bomb_entity in &self.bomb_entities {
-            if let Some(transform) = self.world.get_component::<Transform>(bomb_entity) {
-                let bomb_pos = transform.position;
+        // Bombs can hit any player or ally; bucket them once per frame
+        self.target_grid.clear();
+        for &entity in self.human_entities().iter().chain(self.ally_entities.iter()) {
+            if let Some(transform) = self.world.get_component::<Transform>(entity) {
+                self.target_grid.insert(entity, transform.position);
+            }
+        }
 
-                // Check if bomb hit the ground
-                if bomb_pos.y > 600.0 {
-                    bombs_to_remove.push(bomb_entity);
-                    explosion_positions.push(bomb_pos);
-                } else {
-                    // Check collision with player
-                    if let Some(player_entity) = self.player_entity {
-                        if let Some(player_transform) =
-                            self.world.get_component::<Transform>(player_entity)
-                        {
-                            let dist = (bomb_pos - player_transform.position).length();
-                            if dist < 40.0 {
-                                bombs_to_remove.push(bomb_entity);
-                                explosion_positions.push(bomb_pos);
-                                entities_to_damage.push(player_entity);
-                            }
-                        }
-                    }
+        // Exploded bombs are despawned at the next world flush
+        let world = &self.world;
+        self.bomb_entities.retain(|&bomb_entity| {
+            let Some(bomb_pos) = world.get_component::<Transform>(bomb_entity).map(|t| t.position)
+            else {
+                return true;
+            };
 
-                    // Check collision with allies
-                    for &ally_entity in &self.ally_entities {
-                        if let Some(ally_transform) =
-                            self.world.get_component::<Transform>(ally_entity)
-                        {
-                            let dist = (bomb_pos - ally_transform.position).length();
-                            if dist < 40.0 && !bombs_to_remove.contains(&bomb_entity) {
-                                bombs_to_remove.push(bomb_entity);
-                                explosion_positions.push(bomb_pos);
-                                entities_to_damage.push(ally_entity);
-                            }
-                        }
-                    }
+            // Check if bomb hit the ground
+            let exploded = if bomb_pos.y > 600.0 {
+                true
+            } else {
+                self.target_grid.query_radius(bomb_pos, 40.0, &mut self.grid_hits);
+
+                // The player takes priority over allies, and each bomb hits once
+                let target = self
+                    .grid_hits
+                    .iter()
+                    .copied()
+                    .find(|&e| Some(e) == self.player_entity)
+                    .or_else(|| self.grid_hits.first().copied());
+
+                if let Some(target) = target {
+                    entities_to_damage.push((target, bomb_pos));
                 }
+                target.is_some()
+            };
+
+            if exploded {
+                explosion_positions.push(bomb_pos);
+                world.commands().destroy(bomb_entity);
             }
-        }
+            !exploded
+        });
 
         // Apply damage to hit entities
-        for entity in entities_to_damage {
+        for (entity, bomb_pos) in entities_to_damage {
             if let Some(health) = self.world.get_component_mut::<Health>(entity) {
                 health.current = (health.current - 25.0).max(0.0);
 
                 // If player took damage, break combo
                 if Some(entity) == self.player_entity {
+                    self.run_tally.add_taken("Bomb", 25.0);
                     self.combo_system.break_combo();
                 }
             }
-        }
 
-        // Create explosion particles
-        for explosion_pos in explosion_positions {
-            let particle_count = 20;
-            for _ in 0..particle_count {
-                let angle = rand::gen_range(0.0, std::f32::consts::PI * 2.0);
-                let speed = rand::gen_range(50.0, 200.0);
-                let particle_entity = self.world.create_entity();
-                self.world.add_component(
-                    particle_entity,
-                    Transform {
-                        position: explosion_pos,
-                        rotation: 0.0,
-                        scale: Vec2::ONE,
-                    },
-                );
-                self.world.add_component(
-                    particle_entity,
-                    Velocity {
-                        linear: Vec2::new(angle.cos() * speed, angle.sin() * speed),
-                        angular: 0.0,
-                    },
-                );
-                self.world.add_component(
-                    particle_entity,
-                    Particle {
-                        particle_type: ParticleType::Smoke,
-                        lifetime: 0.0,
-                        max_lifetime: 1.0,
-                        velocity: Vec2::new(angle.cos() * speed, angle.sin() * speed),
-                        acceleration: Vec2::new(0.0, 50.0),
-                        color_start: Color::new(1.0, 0.5, 0.0, 1.0),
-                        color_end: Color::new(0.3, 0.3, 0.3, 0.0),
-                        size_start: 15.0,
-                        size_end: 3.0,
-                    },
-                );
+            if let Some(position) = self.world.get_component::<Transform>(entity).map(|t| t.position) {
+                let away = (position - bomb_pos).normalize_or_zero();
+                apply_knockback(&mut self.world, entity, away * 320.0, 300.0);
             }
         }
 
-        // Remove exploded bombs
-        for bomb_entity in bombs_to_remove {
-            self.world.destroy_entity(bomb_entity);
-            self.bomb_entities.retain(|&e| e != bomb_entity);
+        for position in explosion_positions {
+            self.world
+                .events()
+                .send(GameEvent::BombExploded { position, radius: 40.0 });
         }
 
         if self.enemies_to_spawn > 0 {
@@ -1413,181 +2330,228 @@ impl State for GameplayState {
             if self.spawn_timer <= 0.0 {
                 self.spawn_wave_enemy();
                 self.enemies_to_spawn -= 1;
-                self.spawn_timer = 1.0;
+                self.spawn_timer = self
+                    .encounters
+                    .wave(self.current_map.layout(), self.current_wave)
+                    .map_or(1.0, |wave| wave.interval);
             }
         }
 
-        self.enemy_entities.retain(|&entity| {
-            if let Some(health) = self.world.get_component::<Health>(entity) {
-                if health.current > 0.0 {
-                    true
-                } else {
-                    // Enemy died - award XP and check achievements
-                    let xp_reward = 50.0; // Base XP per kill
-                    let combo_multiplier = 1.0 + (self.combo_system.combo_count as f32 * 0.05);
-                    let total_xp = xp_reward * combo_multiplier;
-
-                    // Award XP to character mastery
-                    self.character_mastery.add_xp(total_xp);
-
-                    // Award account progression XP
-                    self.account_progression.add_xp(total_xp * 0.5);
+        // Remove dead enemies and allies; rewards and VFX are handled from EntityDied.
+        // In co-op, allies at 0 HP are downed instead and removed once they bleed out.
+        let allies_revivable = self.revive_system.is_some();
+        let mut bosses_defeated = 0;
+        let mut defeated_bosses = Vec::new();
+        let mut fallen = Vec::new();
+        for (entities, team) in [
+            (&mut self.enemy_entities, Team::Enemy),
+            (&mut self.ally_entities, Team::Ally),
+        ] {
+            entities.retain(|&entity| {
+                let Some(health) = self.world.get_component::<Health>(entity) else {
+                    return false;
+                };
+                if health.current > 0.0 || (team == Team::Ally && allies_revivable) {
+                    return true;
+                }
 
-                    // Check achievements (kills, combos, etc.)
-                    self.achievement_manager.update_progress("total_kills", 1.0);
-                    if self.combo_system.combo_count >= 10 {
-                        self.achievement_manager.update_progress("high_combo_kills", 1.0);
+                let boss = self
+                    .world
+                    .get_component::<AIController>(entity)
+                    .is_some_and(|ai| matches!(ai.behavior, AIBehavior::Boss(_)));
+                if boss {
+                    bosses_defeated += 1;
+                    if let Some(fighter) = self.world.get_component::<Fighter>(entity) {
+                        defeated_bosses.push(fighter.character_type);
                     }
-                    if self.combo_system.style_rank >= crate::combat::combo_system::StyleRank::S {
-                        self.achievement_manager.update_progress("s_rank_kills", 1.0);
+                }
+                if let Some(transform) = self.world.get_component::<Transform>(entity) {
+                    let position = transform.position;
+                    self.world.events().send(GameEvent::EntityDied {
+                        entity,
+                        team,
+                        position,
+                    });
+                    if boss {
+                        self.world.events().send(GameEvent::BossDefeated { position });
                     }
-
-                    // Get enemy position for VFX
-                    if let Some(transform) = self.world.get_component::<Transform>(entity) {
-                        let pos = transform.position;
-                        // Spawn death VFX
-                        use crate::render::enhanced_vfx::ImpactType;
-                        self.enhanced_vfx.spawn_impact(pos, Vec2::new(0.0, -1.0), ImpactType::Heavy);
+                    if team == Team::Enemy {
+                        fallen.push((position, boss));
                     }
-
-                    self.world.destroy_entity(entity);
-                    false
-                }
-            } else {
-                false
-            }
-        });
-
-        // Remove dead allies
-        self.ally_entities.retain(|&entity| {
-            if let Some(health) = self.world.get_component::<Health>(entity) {
-                if health.current > 0.0 {
-                    true
-                } else {
-                    self.world.destroy_entity(entity);
-                    false
                 }
-            } else {
+                self.world.commands().destroy(entity);
                 false
-            }
-        });
+            });
+        }
+        for character in defeated_bosses {
+            Self::boss_bark(&mut self.subtitles, &character, "defeated");
+            self.announcer.announce(Callout::KnockOut);
+        }
+        // The blow that clears the wave plays out in slow motion
+        if !fallen.is_empty() && self.enemy_entities.is_empty() && self.enemies_to_spawn == 0 {
+            let (scale, duration) = FINAL_BLOW_SLOW_MOTION;
+            self.request_time_scale(TimeScaleRequest::SlowMotion { scale, duration });
+        }
+        if bosses_defeated > 0 {
+            self.grant_currency(BOSS_REWARD * bosses_defeated, RewardKind::Boss, "Boss defeated");
+        }
+        for (position, boss) in fallen {
+            self.drop_loot(position, boss);
+        }
 
         // Check if both bosses are defeated on Rooftop
         if self.current_map == MapType::Rooftop
             && self.enemy_entities.is_empty()
             && !self.boss_battle_won
-            && !self.dialogue_choice_active
+            && self.conversation.is_none()
         {
-            // Both bosses are dead! Show dialogue choice
-            self.dialogue_choice_active = true;
-            self.dialogue_choice_selected = 0;
+            // Both bosses are dead! Strike the victory pose, then show dialogue choice
+            let victory = self.cosmetics.victory_pose.unwrap_or_else(|| {
+                crate::data::get_pose_choice()
+                    .victory(self.selected_character, self.character_mastery.level)
+            });
+            self.start_pose(victory);
+            crate::data::queue_music_cue(MusicCue::Victory {
+                character: self.selected_character,
+                mastery_level: self.character_mastery.level,
+            });
+            let _ = self.character_mastery.save();
+            self.start_conversation();
         }
 
         if self.enemy_entities.is_empty()
             && self.enemies_to_spawn == 0
-            && !self.dialogue_choice_active
+            && self.shopkeeper.is_none()
+            && self.conversation.is_none()
+            && self.route_choices.is_empty()
+            && !self.upgrade_choice_open()
         {
             self.complete_wave();
         }
 
-        self.input_manager.update();
+        // Sync point: spawns and despawns queued since the last frame land before systems run
+        self.world.flush();
 
-        self.movement_system.update(&mut self.world, dt);
-        self.physics_system.update(&mut self.world, dt);
-        self.animation_system.update(&mut self.world, dt);
-
-        // Track player attacks for combo system before combat update
-        let player_attacking = if let Some(player_entity) = self.player_entity {
-            if let Some(fighter) = self.world.get_component::<Fighter>(player_entity) {
-                matches!(fighter.state, FighterState::LightAttack | FighterState::HeavyAttack |
-                         FighterState::Special | FighterState::Super)
-            } else {
-                false
+        // The side edges of the floor are walls for knockback
+        if let Some(physics) = self.systems.get_mut::<PhysicsSystem>() {
+            physics.set_arena(arena_bounds(self.host.screen_width()));
+        }
+        if let Some(props) = self.systems.get_mut::<StagePropSystem>() {
+            props.set_arena(arena_bounds(self.host.screen_width()));
+        }
+        self.systems.run(Stage::Motion, &mut self.world, dt);
+
+        let splats = self
+            .systems
+            .get::<PhysicsSystem>()
+            .map(|physics| physics.wall_splats().to_vec())
+            .unwrap_or_default();
+        for splat in splats {
+            use crate::render::enhanced_vfx::ImpactType;
+            // The rooftop has no walls, only a drop
+            if self.current_map == MapType::Rooftop {
+                self.ring_out(splat.entity, splat.position);
+                continue;
             }
-        } else {
-            false
-        };
-
-        self.combat_system.update(&mut self.world, dt);
-
-        // After combat, check for hits and register them with combo system
-        if player_attacking {
-            if let Some(player_entity) = self.player_entity {
-                if let Some(hitbox) = self.world.get_component::<HitboxComponent>(player_entity) {
-                    // If hitbox hit anyone, register it with combo system
-                    if !hitbox.hits_registered.is_empty() {
-                        use crate::combat::combo_system::MoveType;
-
-                        let move_type = if let Some(fighter) = self.world.get_component::<Fighter>(player_entity) {
-                            match fighter.state {
-                                FighterState::LightAttack => MoveType::LightAttack,
-                                FighterState::HeavyAttack => MoveType::HeavyAttack,
-                                FighterState::Special => MoveType::SpecialAttack,
-                                FighterState::Super => MoveType::Ability,
-                                _ => MoveType::LightAttack,
-                            }
-                        } else {
-                            MoveType::LightAttack
-                        };
-
-                        let damage = 10.0 * self.player_attack_multiplier;
-                        let is_critical = false; // Could add crit system later
-
-                        let combo_result = self.combo_system.register_hit(move_type, damage, is_critical);
-
-                        // Show combo VFX if active
-                        if combo_result.combo_count % 5 == 0 {
-                            if let Some(transform) = self.world.get_component::<Transform>(player_entity) {
-                                self.enhanced_vfx.show_combo_text(
-                                    transform.position,
-                                    combo_result.combo_count,
-                                    combo_result.style_rank
-                                );
-                            }
-                        }
+            let into_wall = if splat.position.x < self.host.screen_width() * 0.5 {
+                Vec2::new(-1.0, 0.0)
+            } else {
+                Vec2::new(1.0, 0.0)
+            };
+            let impact = if splat.speed > 500.0 {
+                ImpactType::Critical
+            } else {
+                ImpactType::Heavy
+            };
+            self.enhanced_vfx.spawn_impact(
+                splat.position + into_wall * 30.0 - Vec2::new(0.0, 20.0),
+                -into_wall,
+                impact,
+            );
 
-                        // Spawn impact VFX and damage numbers
-                        for &hit_entity_id in &hitbox.hits_registered {
-                            let hit_entity = EntityId(hit_entity_id);
-                            if let Some(transform) = self.world.get_component::<Transform>(hit_entity) {
-                                let pos = transform.position;
-
-                                // Impact effect
-                                use crate::render::enhanced_vfx::ImpactType;
-                                let impact_type = match move_type {
-                                    MoveType::LightAttack => ImpactType::Light,
-                                    MoveType::HeavyAttack => ImpactType::Heavy,
-                                    MoveType::SpecialAttack => ImpactType::Critical,
-                                    _ => ImpactType::Medium,
-                                };
-
-                                let direction = if let Some(player_transform) = self.world.get_component::<Transform>(player_entity) {
-                                    (pos - player_transform.position).normalize()
-                                } else {
-                                    Vec2::new(1.0, 0.0)
-                                };
+            // Slamming into the wall hurts, scaled by how hard they hit it
+            let damage = (splat.speed * 0.02).min(15.0);
+            if let Some(health) = self.world.get_component_mut::<Health>(splat.entity) {
+                health.current = (health.current - damage).max(0.0);
+                self.enhanced_vfx
+                    .show_damage_number(splat.position - Vec2::new(0.0, 60.0), damage, false);
+            }
+        }
 
-                                self.enhanced_vfx.spawn_impact(pos, direction, impact_type);
-                                self.enhanced_vfx.show_damage_number(pos, damage, is_critical);
-                            }
-                        }
-                    }
+        self.systems.run(Stage::Combat, &mut self.world, dt);
+        self.keep_god_mode();
+        self.check_knock_through();
+
+        // Guard feedback: sparks on a clean block, a heavy flash when a mixup opens the guard
+        let guard_events = self
+            .systems
+            .get::<CombatSystem>()
+            .map(|combat| combat.guard_events().to_vec())
+            .unwrap_or_default();
+        for event in guard_events {
+            if event.blocked && self.hardcore_stamina {
+                if let Some(stamina) = self.world.get_component_mut::<Stamina>(event.defender) {
+                    stamina.drain(BLOCKED_HIT_STAMINA);
+                }
+            }
+            if let (Some(attacker), Some(defender)) = (
+                self.world.get_component::<Transform>(event.attacker).map(|t| t.position),
+                self.world.get_component::<Transform>(event.defender).map(|t| t.position),
+            ) {
+                use crate::render::enhanced_vfx::ImpactType;
+                let direction = (defender - attacker).normalize_or_zero();
+                let hit_y = match event.height {
+                    AttackHeight::Overhead => -40.0,
+                    AttackHeight::Low => 40.0,
+                    AttackHeight::Mid => 0.0,
+                };
+                let impact = if event.blocked {
+                    ImpactType::Light
+                } else if event.stance == BlockStance::Crouching {
+                    ImpactType::Critical
+                } else {
+                    ImpactType::Heavy
+                };
+                self.enhanced_vfx
+                    .spawn_impact(defender + Vec2::new(0.0, hit_y), direction, impact);
+
+                // An opened guard sounds like the hit it lets through
+                if event.blocked {
+                    let parried = self
+                        .world
+                        .get_component::<Fighter>(event.defender)
+                        .is_some_and(|fighter| fighter.state == FighterState::Parrying);
+                    let sound = if parried {
+                        crate::audio::sfx::ImpactType::Parry
+                    } else {
+                        crate::audio::sfx::ImpactType::Block
+                    };
+                    self.play_sound_at(self.sfx.get_impact_sound(sound), defender);
                 }
             }
         }
 
-        self.particle_system.update(&mut self.world, dt);
-        self.ai_system.update(&mut self.world, dt);
+        self.systems.run(Stage::Late, &mut self.world, dt);
         self.process_fighter_states(dt);
         self.clamp_fighter_positions();
+        self.dispatch_events();
+        self.update_coop_survival(dt);
+        if let Some(combo) = self.shared_combo.as_mut() {
+            combo.update(dt);
+        }
 
+        self.keep_god_mode();
         self.check_game_over();
-        self.camera_pos = Vec2::ZERO;
     }
 
-    fn fixed_update(&mut self, _dt: f64) {}
+    fn render(&mut self, interpolation: f32) {
+        self.render_alpha = interpolation;
+        self.texture_manager.get_or_insert_with(TextureManager::new);
+        self.character_sheets.get_or_insert_with(CharacterSheets::new);
+        let post_target = self.post.begin();
+        self.camera.apply_transform(post_target);
+        clear_background(BLACK);
 
-    fn render(&mut self, _interpolation: f32) {
         // Render map system backgrounds and parallax layers first
         // Note: The map_system doesn't have a render method, so we'll keep the gradient backgrounds
         // and add VFX rendering at the end
@@ -1673,6 +2637,10 @@ impl State for GameplayState {
             draw_circle(x, y, size, Color::new(1.0, 1.0, 1.0, alpha));
         }
 
+        self.render_spawn_entrances();
+        self.render_floor_props();
+        self.render_boss_telegraphs();
+
         let mut draw_order: Vec<_> = self
             .world
             .query::<Transform>()
@@ -1685,17 +2653,18 @@ impl State for GameplayState {
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        for (entity, _) in draw_order {
-            if let Some(transform) = self.world.get_component::<Transform>(entity) {
-                let pos = transform.position - self.camera_pos;
-
+        // Ground layer and character sprites; sprites all come from the one atlas
+        // texture and are drawn together, overlays go on top afterwards
+        let mut batch = std::mem::take(&mut self.sprite_batch);
+        for &(entity, _) in &draw_order {
+            if let Some((ground_pos, pos)) = self.draw_position(entity) {
                 if let Some(fighter) = self.world.get_component::<Fighter>(entity) {
                     // Enhanced shadow with gradient effect
                     let shadow_color = Color::new(0.0, 0.0, 0.0, 0.4);
-                    draw_ellipse(pos.x, pos.y + 70.0, 48.0, 16.0, 0.0, shadow_color);
+                    draw_ellipse(ground_pos.x, ground_pos.y + 70.0, 48.0, 16.0, 0.0, shadow_color);
                     draw_ellipse(
-                        pos.x,
-                        pos.y + 70.0,
+                        ground_pos.x,
+                        ground_pos.y + 70.0,
                         36.0,
                         12.0,
                         0.0,
@@ -1704,14 +2673,24 @@ impl State for GameplayState {
 
                     let is_player = self.player_entity.map(|id| id == entity).unwrap_or(false);
                     let is_ally = self.ally_entities.contains(&entity);
-
-                    // Character outline glow
-                    let glow_color = if is_player {
-                        Color::new(1.0, 0.9, 0.0, 0.5)
+                    let guest = self.coop_guest(entity);
+
+                    // Character outline glow; a tag partner shares the player's
+                    let is_assist = Some(entity) == self.assist_entity;
+                    let glow_color = if is_player || is_assist {
+                        self.palette.player.glow.color()
+                    } else if let Some((_, color)) = guest {
+                        Color::new(color.r, color.g, color.b, 0.5)
                     } else if is_ally {
-                        Color::new(0.3, 1.0, 0.5, 0.4)
+                        self.palette.ally.glow.color()
+                    } else {
+                        self.palette.enemy.glow.color()
+                    };
+                    // Spawn invulnerability flashes the outline white
+                    let glow_color = if fighter.invulnerable && (get_time() * 12.0) as i64 % 2 == 0 {
+                        self.palette.invulnerable.color()
                     } else {
-                        Color::new(1.0, 0.3, 0.3, 0.4)
+                        glow_color
                     };
 
                     for offset in [3.0, 2.0, 1.0] {
@@ -1732,117 +2711,190 @@ impl State for GameplayState {
                     if is_player && self.ability_state.active {
                         let time = get_time();
                         let pulse = (time * 3.0).sin() * 0.3 + 0.7;
-                        let aura_color = match self.selected_character {
-                            CharacterId::Berkay => Color::new(1.0, 0.5, 0.0, 0.4 * pulse as f32),
-                            CharacterId::Luca => Color::new(0.3, 0.6, 1.0, 0.4 * pulse as f32),
-                            CharacterId::Gefferinho => {
-                                Color::new(1.0, 0.2, 0.2, 0.4 * pulse as f32)
-                            }
-                            CharacterId::Bas => Color::new(0.0, 1.0, 0.5, 0.4 * pulse as f32),
-                            CharacterId::Hadi => Color::new(1.0, 0.8, 0.0, 0.4 * pulse as f32),
-                            CharacterId::Nitin => Color::new(1.0, 0.3, 0.0, 0.4 * pulse as f32),
-                            CharacterId::PalaBaba => Color::new(0.8, 0.0, 0.2, 0.4 * pulse as f32),
-                            CharacterId::Fufinho => Color::new(0.9, 0.0, 0.9, 0.4 * pulse as f32), // Purple
-                            CharacterId::EfeAbi => Color::new(0.7, 0.3, 0.1, 0.4 * pulse as f32), // Brown
-                            CharacterId::Jad => Color::new(1.0, 0.0, 0.0, 0.4 * pulse as f32), // Red
-                            CharacterId::Umut => Color::new(0.5, 0.0, 1.0, 0.4 * pulse as f32), // Purple for Terraria
-                            CharacterId::KeizerBomTaha => Color::new(0.2, 0.6, 0.3, 0.4 * pulse as f32), // Green
-                            CharacterId::GoonLordTobese => Color::new(1.0, 1.0, 1.0, 0.4 * pulse as f32), // White (milk)
-                        };
+                        let base = self.ability_aura_color();
+                        let aura_color = Color::new(base.r, base.g, base.b, 0.4 * pulse as f32);
                         // Draw multiple pulsing rings for aura effect
                         for i in 0..4 {
                             let radius =
                                 55.0 + i as f32 * 18.0 + (time * 2.0 + i as f64).sin() as f32 * 5.0;
                             draw_circle_lines(pos.x, pos.y, radius, 4.0, aura_color);
                         }
-
-                        // Add particle sparkles around player
-                        for i in 0..8 {
-                            let angle = time * 2.0 + i as f64 * std::f64::consts::PI * 0.25;
-                            let sparkle_x = pos.x + (angle.cos() as f32) * 65.0;
-                            let sparkle_y = pos.y + (angle.sin() as f32) * 65.0;
-                            let sparkle_pulse =
-                                ((time * 5.0 + i as f64 * 0.3).sin() * 0.5 + 0.5) as f32;
-                            draw_circle(sparkle_x, sparkle_y, 3.0 * sparkle_pulse, aura_color);
-                        }
-                    }
-
-                    self.render_character(pos, fighter, is_player);
-
-                    // Render fire effect for burning enemies
-                    if self.burning_enemies.contains_key(&entity) {
-                        let time = get_time();
-                        for i in 0..3 {
-                            let offset_y = -30.0 - i as f32 * 10.0
-                                + (time * 5.0 + i as f64 * 0.5).sin() as f32 * 5.0;
-                            let offset_x = ((time * 3.0 + i as f64 * 0.7).sin() * 10.0) as f32;
-                            let size = 8.0 + i as f32 * 2.0;
-                            let alpha = 0.7 - i as f32 * 0.2;
-                            draw_circle(
-                                pos.x + offset_x,
-                                pos.y + offset_y,
-                                size,
-                                Color::new(1.0, 0.5, 0.0, alpha),
-                            );
-                            draw_circle(
-                                pos.x + offset_x,
-                                pos.y + offset_y,
-                                size * 0.6,
-                                Color::new(1.0, 0.8, 0.0, alpha),
-                            );
-                        }
                     }
 
-                    let is_ally = self.ally_entities.contains(&entity);
-                    let name = self.character_display_name(&fighter.character_type, is_player);
-
-                    let tag_color = if is_player {
-                        YELLOW
-                    } else if is_ally {
-                        Color::new(0.5, 1.0, 0.6, 1.0)
-                    } else {
-                        Color::new(1.0, 0.5, 0.5, 1.0)
-                    };
+                    self.queue_character(&mut batch, entity, pos, fighter, is_player);
+                }
 
-                    self.render_nametag(pos, &name, tag_color);
+                if let Some(prop) = self.world.get_component::<StageProp>(entity) {
+                    self.render_stage_prop(entity, pos, prop);
+                }
+                if let Some(furniture) = self.world.get_component::<Destructible>(entity) {
+                    self.render_destructible(pos, furniture);
+                }
+                if let Some(pickup) = self.world.get_component::<Pickup>(entity) {
+                    self.render_pickup(pos, pickup);
+                }
+                if let Some(shopkeeper) = self.world.get_component::<Shopkeeper>(entity) {
+                    self.render_shopkeeper(pos, shopkeeper);
                 }
 
-                if let Some(health) = self.world.get_component::<Health>(entity) {
-                    let health_pct = health.current / health.maximum;
+                // Render bombs
+                if let Some(_bomb) = self.world.get_component::<Bomb>(entity) {
+                    let time = get_time() as f32;
 
-                    // Enhanced health bar with gradient and glow
-                    let bar_width = 70.0;
-                    let bar_height = 8.0;
-                    let bar_x = pos.x - bar_width * 0.5;
-                    let bar_y = pos.y - 85.0;
+                    // Shadow
+                    draw_ellipse(
+                        pos.x,
+                        pos.y + 20.0,
+                        18.0,
+                        8.0,
+                        0.0,
+                        Color::new(0.0, 0.0, 0.0, 0.4),
+                    );
 
-                    // Background shadow
-                    draw_rectangle(
-                        bar_x + 2.0,
-                        bar_y + 2.0,
-                        bar_width,
-                        bar_height,
-                        Color::new(0.0, 0.0, 0.0, 0.5),
+                    // Main bomb body - black sphere
+                    draw_circle(pos.x, pos.y, 18.0, Color::new(0.1, 0.1, 0.1, 1.0));
+                    draw_circle(
+                        pos.x - 4.0,
+                        pos.y - 4.0,
+                        15.0,
+                        Color::new(0.2, 0.2, 0.2, 1.0),
                     );
 
-                    // Dark background
-                    draw_rectangle(
-                        bar_x,
-                        bar_y,
-                        bar_width,
-                        bar_height,
-                        Color::new(0.15, 0.15, 0.15, 0.9),
+                    // Highlight to make it look 3D
+                    draw_circle(
+                        pos.x - 6.0,
+                        pos.y - 6.0,
+                        6.0,
+                        Color::new(0.4, 0.4, 0.4, 0.8),
+                    );
+
+                    // Fuse on top
+                    draw_rectangle(
+                        pos.x - 2.0,
+                        pos.y - 18.0,
+                        4.0,
+                        10.0,
+                        Color::new(0.3, 0.2, 0.1, 1.0),
+                    );
+
+                    // Animated sparking fuse tip
+                    let spark_size = ((time * 10.0).sin().abs() * 3.0 + 2.0) as f32;
+                    draw_circle(
+                        pos.x,
+                        pos.y - 18.0,
+                        spark_size,
+                        Color::new(1.0, 0.3, 0.0, 1.0),
+                    );
+                    draw_circle(
+                        pos.x,
+                        pos.y - 18.0,
+                        spark_size * 0.6,
+                        Color::new(1.0, 0.8, 0.0, 1.0),
                     );
 
-                    // Health bar with gradient (green to yellow to red based on health)
-                    let health_color = if health_pct > 0.6 {
-                        Color::new(0.2 + health_pct * 0.3, 1.0, 0.3, 1.0)
-                    } else if health_pct > 0.3 {
-                        Color::new(1.0, 0.8, 0.2, 1.0)
+                    // Danger symbol
+                    draw_text(
+                        "!",
+                        pos.x - 3.0,
+                        pos.y + 5.0,
+                        20.0,
+                        Color::new(1.0, 0.0, 0.0, 0.9),
+                    );
+                }
+            }
+        }
+        if let Some(sheets) = &self.character_sheets {
+            sheets.flush(&mut batch);
+        }
+        self.sprite_batch = batch;
+
+        for (entity, _) in draw_order {
+            if let Some((_, pos)) = self.draw_position(entity) {
+                if let Some(fighter) = self.world.get_component::<Fighter>(entity) {
+                    let is_player = self.player_entity.map(|id| id == entity).unwrap_or(false);
+                    let is_ally = self.ally_entities.contains(&entity);
+
+                    self.render_character_overlay(pos, fighter);
+                    if let Some((outfit, color)) = self.cosmetics.outfit.filter(|_| is_player) {
+                        draw_outfit(outfit, color, pos, fighter.facing < 0.0);
+                    }
+
+                    // Render fire effect for burning enemies
+                    if self.burning_enemies.contains_key(&entity) {
+                        let time = get_time();
+                        for i in 0..3 {
+                            let offset_y = -30.0 - i as f32 * 10.0
+                                + (time * 5.0 + i as f64 * 0.5).sin() as f32 * 5.0;
+                            let offset_x = ((time * 3.0 + i as f64 * 0.7).sin() * 10.0) as f32;
+                            let size = 8.0 + i as f32 * 2.0;
+                            let alpha = 0.7 - i as f32 * 0.2;
+                            draw_circle(
+                                pos.x + offset_x,
+                                pos.y + offset_y,
+                                size,
+                                Color::new(1.0, 0.5, 0.0, alpha),
+                            );
+                            draw_circle(
+                                pos.x + offset_x,
+                                pos.y + offset_y,
+                                size * 0.6,
+                                Color::new(1.0, 0.8, 0.0, alpha),
+                            );
+                        }
+                    }
+
+                    let guest = self.coop_guest(entity);
+                    let name = match guest {
+                        Some((slot, _)) if self.bots[slot.to_index()].is_some() => {
+                            format!("P{} BOT", slot.to_index() + 1)
+                        }
+                        Some((slot, _)) => format!("P{}", slot.to_index() + 1),
+                        None => self.character_display_name(&fighter.character_type, is_player),
+                    };
+
+                    let tag_color = if is_player {
+                        self.palette.player.nametag.color()
+                    } else if let Some((_, color)) = guest {
+                        color
+                    } else if is_ally {
+                        self.palette.ally.nametag.color()
                     } else {
-                        Color::new(1.0, 0.3, 0.2, 1.0)
+                        self.palette.enemy.nametag.color()
                     };
 
+                    self.render_nametag(pos, &name, tag_color);
+                }
+
+                if let Some(health) = self.world.get_component::<Health>(entity) {
+                    let health_pct = health.current / health.maximum;
+
+                    // Enhanced health bar with gradient and glow
+                    let bar_width = 70.0;
+                    let bar_height = 8.0;
+                    let bar_x = pos.x - bar_width * 0.5;
+                    let bar_y = pos.y - 85.0;
+
+                    // Background shadow
+                    draw_rectangle(
+                        bar_x + 2.0,
+                        bar_y + 2.0,
+                        bar_width,
+                        bar_height,
+                        Color::new(0.0, 0.0, 0.0, 0.5),
+                    );
+
+                    // Dark background
+                    draw_rectangle(
+                        bar_x,
+                        bar_y,
+                        bar_width,
+                        bar_height,
+                        self.palette.health.background.color(),
+                    );
+
+                    // Health bar stepping from high to low colour as health drops
+                    let health_color = self.palette.health.fill(health_pct);
+
                     draw_rectangle(
                         bar_x,
                         bar_y,
@@ -1874,7 +2926,7 @@ impl State for GameplayState {
                         bar_width,
                         bar_height,
                         2.0,
-                        Color::new(0.8, 0.8, 0.8, 0.9),
+                        self.palette.health.border.color(),
                     );
 
                     // Health text
@@ -1890,73 +2942,11 @@ impl State for GameplayState {
                     );
                 }
 
-                // Render bombs
-                if let Some(_bomb) = self.world.get_component::<Bomb>(entity) {
-                    let time = get_time() as f32;
-
-                    // Shadow
-                    draw_ellipse(
-                        pos.x,
-                        pos.y + 20.0,
-                        18.0,
-                        8.0,
-                        0.0,
-                        Color::new(0.0, 0.0, 0.0, 0.4),
-                    );
-
-                    // Main bomb body - black sphere
-                    draw_circle(pos.x, pos.y, 18.0, Color::new(0.1, 0.1, 0.1, 1.0));
-                    draw_circle(
-                        pos.x - 4.0,
-                        pos.y - 4.0,
-                        15.0,
-                        Color::new(0.2, 0.2, 0.2, 1.0),
-                    );
-
-                    // Highlight to make it look 3D
-                    draw_circle(
-                        pos.x - 6.0,
-                        pos.y - 6.0,
-                        6.0,
-                        Color::new(0.4, 0.4, 0.4, 0.8),
-                    );
-
-                    // Fuse on top
-                    draw_rectangle(
-                        pos.x - 2.0,
-                        pos.y - 18.0,
-                        4.0,
-                        10.0,
-                        Color::new(0.3, 0.2, 0.1, 1.0),
-                    );
-
-                    // Animated sparking fuse tip
-                    let spark_size = ((time * 10.0).sin().abs() * 3.0 + 2.0) as f32;
-                    draw_circle(
-                        pos.x,
-                        pos.y - 18.0,
-                        spark_size,
-                        Color::new(1.0, 0.3, 0.0, 1.0),
-                    );
-                    draw_circle(
-                        pos.x,
-                        pos.y - 18.0,
-                        spark_size * 0.6,
-                        Color::new(1.0, 0.8, 0.0, 1.0),
-                    );
-
-                    // Danger symbol
-                    draw_text(
-                        "!",
-                        pos.x - 3.0,
-                        pos.y + 5.0,
-                        20.0,
-                        Color::new(1.0, 0.0, 0.0, 0.9),
-                    );
-                }
             }
         }
 
+        self.render_particles();
+
         // Render plane if active
         if let Some(plane) = &self.plane_system {
             if plane.in_plane || plane.entering_plane || plane.exiting_plane {
@@ -2009,60 +2999,65 @@ impl State for GameplayState {
                         draw_circle(bomb.position.x, bomb.position.y, explosion_size * 0.7, Color::new(1.0, 0.8, 0.0, 0.8));
                     }
                 }
-
-                // Plane UI
-                let ui_x = screen_width() - 250.0;
-                let ui_y = 150.0;
-                draw_text("PLANE MODE", ui_x, ui_y, 20.0, YELLOW);
-                draw_text(
-                    &format!("Altitude: {:.0}m", plane.altitude),
-                    ui_x,
-                    ui_y + 25.0,
-                    16.0,
-                    WHITE,
-                );
-                draw_text(
-                    &format!("Bombs: {}/{}", plane.bombs_remaining, plane.max_bombs),
-                    ui_x,
-                    ui_y + 45.0,
-                    16.0,
-                    WHITE,
-                );
-                draw_text(
-                    &format!("Duration: {:.1}s", plane.get_duration_remaining()),
-                    ui_x,
-                    ui_y + 65.0,
-                    16.0,
-                    WHITE,
-                );
             }
         }
 
         // Render enhanced VFX on top of game objects
         self.enhanced_vfx.render();
+        self.render_pings();
+        for handler in &self.event_handlers {
+            handler.render(&self.world);
+        }
 
-        self.render_hud();
-        self.render_combo_ui();
-        self.render_controls();
-        self.render_dialogue();
+        // Everything below is UI, laid out on the virtual canvas
+        self.post.end();
+        self.camera.render_letterbox();
+        let ui = UiLayout::current();
+        ui.begin();
+        self.render_coop_status(&ui);
+        if self.net_session.is_none() {
+            self.render_join_hint(&ui);
+        }
+        self.render_net_status(&ui);
+        self.render_plane_ui(&ui);
+
+        self.render_hud(&ui);
+        self.render_combo_ui(&ui);
+        self.render_controls(&ui);
+        if !self.dialogue_box.is_showing() {
+            self.subtitles.render(&ui);
+        }
+        self.dialogue_box.render(&ui);
 
         if self.shop_open {
-            self.render_shop();
+            self.render_shop(&ui);
         }
 
-        self.render_shop_feedback();
+        self.render_shop_feedback(&ui);
+        self.render_encounter_problems(&ui);
+        self.toasts.render(&ui);
 
         // Render dialogue choice if active
-        if self.dialogue_choice_active {
-            self.render_dialogue_choice();
+        if let Some(conversation) = &self.conversation {
+            self.render_dialogue_choice(&ui, conversation);
         }
 
-        if self.game_over {
+        if !self.route_choices.is_empty() {
+            self.render_route_choice(&ui);
+        }
+
+        if self.upgrade_choice_open() {
+            self.render_upgrade_choice(&ui);
+        }
+
+        if self.game_over && self.winter_arc_result.is_some() {
+            self.render_winter_arc_result(&ui);
+        } else if self.game_over {
             draw_rectangle(
                 0.0,
                 0.0,
-                screen_width(),
-                screen_height(),
+                ui.width(),
+                ui.height(),
                 Color::new(0.0, 0.0, 0.0, 0.7),
             );
             let title = "GAME OVER";
@@ -2070,68 +3065,102 @@ impl State for GameplayState {
             let title_dims = measure_text(title, None, title_size as u16, 1.0);
             draw_text(
                 title,
-                screen_width() * 0.5 - title_dims.width * 0.5,
-                screen_height() * 0.4,
+                ui.width() * 0.5 - title_dims.width * 0.5,
+                ui.height() * 0.4,
                 title_size,
                 Color::new(1.0, 0.2, 0.2, 1.0),
             );
 
-            let prompt = "Bas fainted! Press ESC or ENTER to return to menu.";
+            let prompt = "Bas fainted! Press ESC or ENTER to see the results.";
             let prompt_dims = measure_text(prompt, None, 28, 1.0);
             draw_text(
                 prompt,
-                screen_width() * 0.5 - prompt_dims.width * 0.5,
-                screen_height() * 0.4 + 60.0,
+                ui.width() * 0.5 - prompt_dims.width * 0.5,
+                ui.height() * 0.4 + 60.0,
                 28.0,
                 Color::new(1.0, 1.0, 1.0, 1.0),
             );
+        } else if self.pause_menu.active {
+            self.pause_menu.render(&ui, self.shop_feedback_message.as_deref());
         } else if self.paused {
             draw_rectangle(
                 0.0,
                 0.0,
-                screen_width(),
-                screen_height(),
+                ui.width(),
+                ui.height(),
                 Color::new(0.0, 0.0, 0.0, 0.5),
             );
             let text = "PAUSED";
             let size = 60.0;
             let dims = measure_text(text, None, size as u16, 1.0);
-            draw_text(
-                text,
-                screen_width() * 0.5 - dims.width * 0.5,
-                screen_height() * 0.5,
-                size,
-                WHITE,
-            );
+            let center = ui.anchor(Anchor::Center, Vec2::ZERO);
+            draw_text(text, center.x - dims.width * 0.5, center.y, size, WHITE);
+
+            let detail = match (self.auto_pause.countdown(), self.auto_pause.reason()) {
+                (Some(seconds), _) => Some(format!("Back in {}...", seconds)),
+                (None, Some(AwayReason::Idle)) => Some("Paused while idle - press any key".into()),
+                (None, Some(AwayReason::FocusLost)) => {
+                    Some("Paused while away - press any key".into())
+                }
+                (None, None) => None,
+            };
+            if let Some(detail) = detail {
+                let dims = measure_text(&detail, None, 28, 1.0);
+                let y = center.y + 50.0;
+                draw_text(&detail, center.x - dims.width * 0.5, y, 28.0, LIGHTGRAY);
+            }
         }
+
+        ui.end();
     }
 
     fn handle_input(&mut self) {
+        self.keys = self.coop_input.player_one_keys();
         if self.game_over {
-            if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter) {
-                self.transition_to = Some(StateType::Menu);
+            if self.host.key_pressed(KeyCode::Escape) || self.host.key_pressed(KeyCode::Enter) {
+                crate::data::set_run_report(self.run_report(self.boss_battle_won));
+                self.transition_to = Some(StateType::Results);
             }
             return;
         }
 
-        if let Some(_) = self.current_dialogue {
-            if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
-                self.current_dialogue = None;
-                self.dialogue_timer = 0.0;
-            } else {
-                return;
-            }
+        if self.pause_menu.active {
+            self.handle_pause_menu();
+            return;
+        }
+        // Coming back from an auto-pause goes through its countdown
+        if self.host.key_pressed(KeyCode::Escape) && !self.auto_pause.holding() {
+            self.pause_menu.toggle();
+            self.paused = true;
+            return;
         }
 
-        if is_key_pressed(KeyCode::B) {
-            self.shop_open = !self.shop_open;
-            if self.shop_open {
-                self.set_shop_feedback("Shop opened — press 1-8 to buy upgrades");
+        if self.dialogue_box.is_showing() {
+            if self.dialogue_box.handle_input(&*self.host) {
+                self.dialogue_queue.clear();
             }
+            return;
+        }
+
+        // Route and upgrade choice overlays take the keys; they're handled in update
+        if !self.route_choices.is_empty() || self.upgrade_choice_open() {
+            return;
         }
 
-        if is_key_pressed(KeyCode::Escape) {
-            self.paused = !self.paused;
+        if self.host.key_pressed(KeyCode::B) && self.winter_arc.is_some() {
+            self.set_shop_feedback("The shop is closed during a Winter Arc run");
+        } else if self.host.key_pressed(KeyCode::B)
+            && self.challenge_has(ChallengeModifier::NoShop)
+        {
+            self.set_shop_feedback("The shop is closed for today's challenge");
+        } else if self.host.key_pressed(KeyCode::B) && self.shop_open {
+            self.shop_open = false;
+        } else if self.host.key_pressed(KeyCode::B) && self.shopkeeper.is_none() {
+            self.set_shop_feedback("The shopkeeper only sets up between waves");
+        } else if self.host.key_pressed(KeyCode::B) && !self.near_shopkeeper() {
+            self.set_shop_feedback("Walk over to the shopkeeper to shop");
+        } else if self.host.key_pressed(KeyCode::B) {
+            self.shop_open = true;
         }
 
         if self.paused {
@@ -2143,49 +3172,117 @@ impl State for GameplayState {
             return;
         }
 
-        if let Some(player_entity) = self.player_entity {
+        // Online, both seats move in the fixed tick from the exchanged inputs
+        if self.net_session.is_some() {
+            let local = self
+                .coop_input
+                .get_player_input(&*self.host, PlayerSlot::Player1, InputDevice::Keyboard);
+            self.net_input.merge(NetInput::from_player_input(&local));
+            return;
+        }
+
+        // Offline only: the bomb isn't part of the exchanged inputs
+        if self.host.key_pressed(KeyCode::G) {
+            self.use_smoke_bomb();
+        }
+
+        // The single-player tag partner: the ping key calls them in, T swaps
+        // them in
+        if self.tag_team.is_some() {
+            if self.host.key_pressed(self.keys.ping) {
+                self.call_assist();
+            }
+            if self.host.key_pressed(KeyCode::T) {
+                self.tag_partner();
+            }
+        }
+
+        // P1 stands still while picking from the ping wheel
+        let mut aiming = false;
+        if let Some(entity) = self.player_entity {
+            let input = self
+                .coop_input
+                .get_player_input(&*self.host, PlayerSlot::Player1, InputDevice::Keyboard);
+            aiming = self.update_ping_wheel(0, entity, &input);
+        }
+
+        if let Some(player_entity) = self.player_entity.filter(|_| !aiming) {
+            let keys = self.keys;
             let mut move_input = 0.0;
             let mut move_depth = 0.0;
             let mut new_state = None;
+            let mut guard = None;
 
             if let Some(fighter) = self.world.get_component::<Fighter>(player_entity) {
-                if fighter.hitstun <= 0.0 && fighter.blockstun <= 0.0 {
-                    if is_key_down(KeyCode::A) {
+                // Hold block to guard: standing blocks overheads, down + block
+                // blocks lows
+                if fighter.hitstun <= 0.0
+                    && self.host.key_down(keys.block)
+                    && !self.guard_broken(player_entity)
+                {
+                    guard = Some(if self.host.key_down(keys.move_down) {
+                        BlockStance::Crouching
+                    } else {
+                        BlockStance::Standing
+                    });
+                } else if fighter.hitstun <= 0.0 && fighter.blockstun <= 0.0 {
+                    if self.host.key_down(keys.move_left) {
                         move_input -= 1.0;
                     }
-                    if is_key_down(KeyCode::D) {
+                    if self.host.key_down(keys.move_right) {
                         move_input += 1.0;
                     }
-                    if is_key_down(KeyCode::W) {
+                    if self.host.key_down(keys.move_up) {
                         move_depth -= 1.0;
                     }
-                    if is_key_down(KeyCode::S) {
+                    if self.host.key_down(keys.move_down) {
                         move_depth += 1.0;
                     }
 
                     // Auto-attack system - hold button for continuous attacks
-                    if is_key_down(KeyCode::J) || is_key_down(KeyCode::K) || is_key_down(KeyCode::L) {
+                    let assisted = crate::data::get_combo_assist_key()
+                        .is_some_and(|key| self.host.key_down(key));
+                    if assisted
+                        || self.host.key_down(keys.light_attack)
+                        || self.host.key_down(keys.heavy_attack)
+                        || self.host.key_down(keys.special_attack)
+                    {
                         // Mark that we're holding an attack button
                         self.is_holding_attack = true;
 
-                        // Check if it's time to attack again
-                        if self.auto_attack_timer <= 0.0 {
-                            // Determine attack type and stamina cost
-                            let (attack_type, stamina_cost) = if is_key_down(KeyCode::J) {
-                                (Some(FighterState::LightAttack), 15.0)
-                            } else if is_key_down(KeyCode::K) {
-                                (Some(FighterState::HeavyAttack), 30.0)
-                            } else if is_key_down(KeyCode::L) {
-                                (Some(FighterState::Special), 50.0)
+                        // Determine attack type and stamina cost
+                        let (attack_type, stamina_cost) = if assisted {
+                            let swing = self.combo_assist.next_attack();
+                            let cost = if swing == FighterState::HeavyAttack {
+                                self.heavy_stamina()
                             } else {
-                                (None, 0.0)
+                                15.0
                             };
+                            (Some(swing), cost)
+                        } else if self.host.key_down(keys.light_attack) {
+                            (Some(FighterState::LightAttack), 15.0)
+                        } else if self.host.key_down(keys.heavy_attack) {
+                            (Some(FighterState::HeavyAttack), self.heavy_stamina())
+                        } else if self.host.key_down(keys.special_attack) {
+                            (Some(FighterState::Special), 50.0)
+                        } else {
+                            (None, 0.0)
+                        };
+                        let cancel = attack_type
+                            .is_some_and(|next| self.cancels_into(fighter.state, next));
 
+                        // Check if it's time to attack again, or a skill lets
+                        // this attack follow the last one straight away
+                        if self.auto_attack_timer <= 0.0 || cancel {
                             // Check if we have enough stamina
                             if let Some(attack) = attack_type {
                                 if self.consume_stamina(player_entity, stamina_cost) {
                                     new_state = Some(attack);
-                                    self.auto_attack_timer = self.auto_attack_delay;
+                                    self.auto_attack_timer = self.auto_attack_delay
+                                        / self.player_moveset.stats.attack_speed;
+                                    if assisted {
+                                        self.combo_assist.advance();
+                                    }
                                 }
                             }
                         }
@@ -2196,47 +3293,29 @@ impl State for GameplayState {
                     }
 
                     // Ability activation
-                    if is_key_pressed(KeyCode::E) {
+                    if self.host.key_pressed(keys.ability) {
                         if self.ability_state.can_activate() {
                             let voice_line = self.ability_state.activate();
+                            self.voice.speak(self.selected_character, VoiceCue::Ability);
                             if !voice_line.is_empty() {
                                 // Check if this is Jad's special KFC Rage ability
                                 if self.selected_character == CharacterId::Jad {
                                     // Jad's special dialogue sequence
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Jad".to_string(),
-                                        dutch: "KFC RAGE!".to_string(),
-                                        english: "KFC RAGE!".to_string(),
-                                        duration: 2.0,
-                                    });
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Jad".to_string(),
-                                        dutch: "nu ben ik boos".to_string(),
-                                        english: "now I'm angry".to_string(),
-                                        duration: 1.5,
-                                    });
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Umut".to_string(),
-                                        dutch: "typisch".to_string(),
-                                        english: "typical".to_string(),
-                                        duration: 1.5,
-                                    });
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: "Jad".to_string(),
-                                        dutch: "ik eet".to_string(),
-                                        english: "I eat".to_string(),
-                                        duration: 1.5,
-                                    });
-                                    self.dialogue_queue.reverse();
+                                    for (speaker, key) in [
+                                        ("Jad", "dialogue.jad.rage"),
+                                        ("Jad", "dialogue.jad.angry"),
+                                        ("Umut", "dialogue.jad.umut"),
+                                        ("Jad", "dialogue.jad.eat"),
+                                    ] {
+                                        self.subtitles.say(speaker, LineText::Key(key.to_string()));
+                                    }
                                 } else {
-                                    // Regular ability activation dialogue
+                                    // Regular ability activation line, spoken over the fight
                                     let character = crate::data::characters::Character::get_by_id(self.selected_character);
-                                    self.dialogue_queue.push(DialogueLine {
-                                        speaker: character.name.to_string(),
-                                        dutch: voice_line.to_string(),
-                                        english: voice_line.to_string(), // Keep same for now
-                                        duration: 2.0,
-                                    });
+                                    let line = self.next_voice_line(voice_line);
+                                    // Unlocked lines aren't in the locale files, so
+                                    // they're shown as written
+                                    self.subtitles.say(character.name, LineText::Key(line));
                                 }
 
                                 // Apply health boost if applicable
@@ -2305,15 +3384,21 @@ impl State for GameplayState {
                                         break;
                                     }
                                 }
+
+                                // Master rank adds the signature move
+                                if self.character_mastery.has_signature_move() {
+                                    self.signature_move(player_entity);
+                                }
                             }
                         }
                     }
                 }
             }
 
+            let speed_multiplier = self.ability_state.get_speed_multiplier()
+                * self.power_up_multiplier(PowerUpKind::Haste, HASTE_MULTIPLIER);
             if let Some(transform) = self.world.get_component_mut::<Transform>(player_entity) {
-                let dt = get_frame_time();
-                let speed_multiplier = self.ability_state.get_speed_multiplier();
+                let dt = self.host.frame_time();
                 let effective_move_speed = self.player_move_speed * speed_multiplier;
                 let depth_speed = effective_move_speed * 0.65;
                 transform.position.x += move_input * effective_move_speed * dt;
@@ -2322,7 +3407,8 @@ impl State for GameplayState {
                 let min_depth = 340.0;
                 let max_depth = 660.0;
                 transform.position.y = transform.position.y.clamp(min_depth, max_depth);
-                transform.position.x = transform.position.x.clamp(60.0, screen_width() - 60.0);
+                let right = self.host.screen_width() - 60.0;
+                transform.position.x = transform.position.x.clamp(60.0, right);
             }
 
             if let Some(velocity) = self.world.get_component_mut::<Velocity>(player_entity) {
@@ -2345,3559 +3431,5901 @@ impl State for GameplayState {
                 }
             }
 
-            if let Some(state) = new_state {
-                if let Some(fighter) = self.world.get_component_mut::<Fighter>(player_entity) {
-                    fighter.state = state;
+            if guard.is_some() && self.hardcore_stamina {
+                let drain = GUARD_STAMINA_PER_SECOND * self.host.frame_time();
+                if let Some(stamina) = self.world.get_component_mut::<Stamina>(player_entity) {
+                    stamina.drain(drain);
                 }
+            } else if guard.is_none()
+                && self.host.key_pressed(keys.dodge)
+                && self.plane_system.is_none()
+            {
+                let direction = Vec2::new(move_input, move_depth);
+                self.try_dash(player_entity, direction);
             }
-        }
-
-        // Player 2 controls (Arrow keys + Enter/Backspace)
-        if let Some(player2_entity) = self.player2_entity {
-            let mut move_input = 0.0;
-            let mut move_depth = 0.0;
-            let mut new_state = None;
 
-            if let Some(fighter) = self.world.get_component::<Fighter>(player2_entity) {
-                if fighter.hitstun <= 0.0 && fighter.blockstun <= 0.0 {
-                    if is_key_down(KeyCode::Left) {
-                        move_input -= 1.0;
-                    }
-                    if is_key_down(KeyCode::Right) {
-                        move_input += 1.0;
-                    }
-                    if is_key_down(KeyCode::Up) {
-                        move_depth -= 1.0;
-                    }
-                    if is_key_down(KeyCode::Down) {
-                        move_depth += 1.0;
-                    }
-
-                    // Attack controls for player 2
-                    if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Kp1) {
-                        if self.consume_stamina(player2_entity, 15.0) {
-                            new_state = Some(FighterState::LightAttack);
-                        }
-                    } else if is_key_pressed(KeyCode::Backspace) || is_key_pressed(KeyCode::Kp2) {
-                        if self.consume_stamina(player2_entity, 30.0) {
-                            new_state = Some(FighterState::HeavyAttack);
+            if let Some(fighter) = self.world.get_component_mut::<Fighter>(player_entity) {
+                match guard {
+                    Some(stance) => {
+                        fighter.is_blocking = true;
+                        fighter.block_stance = stance;
+                        if matches!(fighter.state, FighterState::Idle | FighterState::Walking) {
+                            fighter.state = FighterState::Blocking;
                         }
-                    } else if is_key_pressed(KeyCode::RightShift) || is_key_pressed(KeyCode::Kp3) {
-                        if self.consume_stamina(player2_entity, 50.0) {
-                            new_state = Some(FighterState::Special);
+                    }
+                    None => {
+                        fighter.is_blocking = false;
+                        if fighter.state == FighterState::Blocking {
+                            fighter.state = FighterState::Idle;
                         }
                     }
                 }
             }
 
-            if let Some(transform) = self.world.get_component_mut::<Transform>(player2_entity) {
-                let dt = get_frame_time();
-                let effective_move_speed = self.player_move_speed;
-                let depth_speed = effective_move_speed * 0.65;
-                transform.position.x += move_input * effective_move_speed * dt;
-                transform.position.y += move_depth * depth_speed * dt;
-
-                let min_depth = 340.0;
-                let max_depth = 660.0;
-                transform.position.y = transform.position.y.clamp(min_depth, max_depth);
-                transform.position.x = transform.position.x.clamp(60.0, screen_width() - 60.0);
-            }
-
-            if let Some(velocity) = self.world.get_component_mut::<Velocity>(player2_entity) {
-                velocity.linear = Vec2::ZERO;
-            }
-
-            if let Some(fighter) = self.world.get_component_mut::<Fighter>(player2_entity) {
-                if move_input.abs() > 0.01
-                    && matches!(fighter.state, FighterState::Idle | FighterState::Walking)
-                {
-                    fighter.state = FighterState::Walking;
-                } else if move_input.abs() <= 0.01 && fighter.state == FighterState::Walking {
-                    fighter.state = FighterState::Idle;
-                }
-
-                if move_input > 0.1 {
-                    fighter.facing = 1.0;
-                } else if move_input < -0.1 {
-                    fighter.facing = -1.0;
-                }
-            }
-
             if let Some(state) = new_state {
-                if let Some(fighter) = self.world.get_component_mut::<Fighter>(player2_entity) {
+                if let Some(fighter) = self.world.get_component_mut::<Fighter>(player_entity) {
                     fighter.state = state;
                 }
             }
         }
+
+        self.handle_drop_in_out();
+        self.update_bots(self.host.frame_time());
+        self.handle_team_super_input();
+
+        for (slot, device, entity) in self.coop_guests() {
+            self.handle_guest_input(slot, device, entity, self.host.frame_time());
+        }
     }
 
     fn should_transition(&self) -> Option<StateType> {
         self.transition_to
     }
-}
 
-impl GameplayState {
-    fn start_wave(&mut self) {
-        self.current_wave += 1;
-        self.refresh_allies_for_wave();
+    fn take_overlay(&mut self) -> Option<StateType> {
+        self.overlay.take()
+    }
 
-        // Special boss battle on rooftop - spawn immediately
-        if self.current_map == MapType::Rooftop && self.current_wave == 1 {
-            self.spawn_big_boss();
-            self.enemies_to_spawn = 0;
-            return;
+    fn handles_escape(&self) -> bool {
+        true
+    }
+
+    fn game_speed(&self) -> f32 {
+        if self.net_session.is_some() || self.coop_manager.is_some() {
+            1.0
+        } else {
+            crate::data::get_game_speed()
         }
+    }
 
-        // Special Mees boss battle at cafeteria
-        if self.current_map == MapType::Cafeteria && self.current_wave == 1 {
-            // Show dialogue for Mees encounter (pushed in reverse order)
-            self.dialogue_queue.push(DialogueLine {
-                speaker: "Mees".to_string(),
-                dutch: "Geen tikkie! Pita sirracha komt dan eraan!".to_string(),
-                english: "No tikkie! Pita sirracha incoming then!".to_string(),
-                duration: 3.5,
-            });
-            self.dialogue_queue.push(DialogueLine {
-                speaker: "Berkay".to_string(),
-                dutch: "Nee".to_string(),
-                english: "No".to_string(),
-                duration: 2.0,
-            });
-            self.dialogue_queue.push(DialogueLine {
-                speaker: "Mees".to_string(),
-                dutch: "Berkay, mag ik tikkie?".to_string(),
-                english: "Berkay, can I have tikkie?".to_string(),
-                duration: 3.0,
+    fn run_command(&mut self, line: &str) -> Option<CommandResult> {
+        Self::console_commands().run(self, line)
+    }
+
+    fn command_usages(&self) -> Vec<String> {
+        Self::console_commands().usages()
+    }
+}
+
+/// Enemies `spawn enemy` takes, by the name they're typed as
+const CONSOLE_ENEMIES: [(&str, CharacterType); 6] = [
+    ("wolters", CharacterType::Wolters),
+    ("prefect_a", CharacterType::PrefectA),
+    ("prefect_b", CharacterType::PrefectB),
+    ("chef", CharacterType::Chef),
+    ("librarian", CharacterType::Librarian),
+    ("coach", CharacterType::Coach),
+];
+/// Most enemies one `spawn enemy` puts down
+const MAX_CONSOLE_SPAWNS: usize = 20;
+
+impl GameplayState {
+    /// Developer console commands over a run
+    fn console_commands() -> CommandRegistry<Self> {
+        let mut commands = CommandRegistry::<Self>::new();
+        commands
+            .add("spawn", "enemy <name> [count] | boss <bastiaan|mees>", |game, args| {
+                game.console_allowed()?;
+                match args.first().copied() {
+                    Some("enemy") => game.console_spawn_enemies(&args[1..]),
+                    Some("boss") => game.console_spawn_boss(args.get(1).copied()),
+                    _ => Err("Spawn an enemy or a boss".to_string()),
+                }
+            })
+            .add("give", "<tokens|skill_points> <amount>", |game, args| {
+                game.console_allowed()?;
+                // Like every reward, these stay off in runs that don't earn
+                if game.practice.is_some() || game.trial.is_some() {
+                    return Err("Practice and trial runs don't earn anything".to_string());
+                }
+                let amount = console::arg::<u32>(args, 1, "amount")?;
+                match args.first().copied() {
+                    Some("tokens") => {
+                        game.shop_manager.add_currency(amount, crate::data::ledger::CONSOLE_GRANT)?;
+                        game.shop_manager.save()?;
+                        Ok(format!("{} Arc Tokens", game.shop_manager.currency()))
+                    }
+                    Some("skill_points") => {
+                        game.grant_skill_points(amount);
+                        Ok(format!("+{} skill points", amount))
+                    }
+                    _ => Err("Give tokens or skill_points".to_string()),
+                }
+            })
+            .add("set", "wave <number>", |game, args| {
+                game.console_allowed()?;
+                match args.first().copied() {
+                    Some("wave") => game.console_set_wave(console::arg(args, 1, "wave")?),
+                    _ => Err("Only the wave can be set".to_string()),
+                }
+            })
+            .add("god", "[on|off]", |game, args| {
+                game.console_allowed()?;
+                game.god_mode = console::switch(args, game.god_mode)?;
+                Ok(format!("God mode {}", if game.god_mode { "on" } else { "off" }))
             });
+        commands
+    }
 
-            self.spawn_mees_boss();
-            self.enemies_to_spawn = 0;
-            return;
+    /// Online runs follow the shared tick, which the console can't touch
+    fn console_allowed(&self) -> Result<(), String> {
+        match self.net_session {
+            Some(_) => Err("The console can't change an online run".to_string()),
+            None => Ok(()),
         }
+    }
 
-        let enemy_count = match self.current_map {
-            MapType::Classroom => 3 + self.current_wave,
-            MapType::Hallway => 5 + self.current_wave,
-            MapType::Cafeteria => 6 + self.current_wave,
-            MapType::Gym => 8 + self.current_wave,
-            MapType::Library => 7 + self.current_wave,
-            MapType::Rooftop => 0, // Bosses already spawned
-        };
-        self.enemies_to_spawn = enemy_count;
-        self.spawn_timer = 0.5;
+    /// Somewhere on the floor to put an enemy down, without an entrance
+    fn console_spawn_point(&mut self) -> Vec2 {
+        match self.map_system.next_spawn_point() {
+            Some(point) => point.landing(arena_bounds(self.host.screen_width())),
+            None => arena_bounds(self.host.screen_width()).center(),
+        }
     }
 
-    fn spawn_wave_enemy(&mut self) {
-        let spawn_x = 800.0 + (get_time() * 1000.0) as f32 % 200.0;
-        let spawn_y = (450.0 + (get_time() * 777.0) as f32 % 200.0).clamp(340.0, 660.0);
-
-        let enemy_type = match self.current_map {
-            MapType::Classroom => CharacterType::Wolters,
-            MapType::Hallway | MapType::Cafeteria => {
-                if (get_time() * 100.0) as i32 % 2 == 0 {
-                    CharacterType::PrefectA
-                } else {
-                    CharacterType::PrefectB
-                }
-            }
-            MapType::Gym => CharacterType::Coach,
-            MapType::Library => CharacterType::Librarian,
-            MapType::Rooftop => CharacterType::Bastiaan,
+    fn console_spawn_enemies(&mut self, args: &[&str]) -> CommandResult {
+        let name = args.first().ok_or("Missing enemy name")?.to_ascii_lowercase();
+        let Some(&(_, character)) = CONSOLE_ENEMIES.iter().find(|(enemy, _)| *enemy == name)
+        else {
+            let names: Vec<&str> = CONSOLE_ENEMIES.iter().map(|(enemy, _)| *enemy).collect();
+            return Err(format!("No enemy '{}'; try {}", name, names.join(", ")));
         };
+        let count = match args.get(1) {
+            Some(_) => console::arg::<usize>(args, 1, "count")?,
+            None => 1,
+        }
+        .clamp(1, MAX_CONSOLE_SPAWNS);
 
-        self.spawn_enemy(Vec2::new(spawn_x, spawn_y), enemy_type);
+        for _ in 0..count {
+            let position = self.console_spawn_point();
+            self.spawn_enemy(position, character);
+        }
+        Ok(format!("Spawned {} x{}", name, count))
     }
 
-    fn complete_wave(&mut self) {
-        self.waves_completed += 1;
+    fn console_spawn_boss(&mut self, name: Option<&str>) -> CommandResult {
+        match name {
+            Some("bastiaan") => self.spawn_big_boss(),
+            Some("mees") => self.spawn_mees_boss(),
+            _ => return Err("Spawn boss bastiaan or mees".to_string()),
+        }
+        Ok(String::new())
+    }
 
-        let reward = 40 + (self.current_wave as u32 * 5);
-        self.grant_currency(reward, "Wave cleared");
+    /// Clear the floor and start `wave` of the current map
+    fn console_set_wave(&mut self, wave: usize) -> CommandResult {
+        let waves = self.map_waves();
+        if wave == 0 || wave > waves {
+            return Err(format!("This map has waves 1 to {}", waves));
+        }
 
-        if self.current_wave >= 3 {
-            self.transition_to_next_map();
-        } else {
-            self.start_wave();
+        for entity in self.enemy_entities.drain(..) {
+            self.world.commands().destroy(entity);
+        }
+        if let Some(keeper) = self.shopkeeper.take() {
+            self.world.commands().destroy(keeper);
+            self.shop_open = false;
+            self.keeper_stock.clear();
         }
+        self.current_wave = wave - 1;
+        self.start_wave();
+        Ok(format!("Wave {} of {}", wave, waves))
     }
 
-    fn transition_to_next_map(&mut self) {
-        self.current_wave = 0;
-
-        self.current_map = match self.current_map {
-            MapType::Classroom => {
-                self.show_dialogue("Luca", "Naar de gang!", "To the hallway!");
-                MapType::Hallway
+    /// Under god mode nothing gets the players below full health
+    fn keep_god_mode(&mut self) {
+        if !self.god_mode {
+            return;
+        }
+        for entity in self.human_entities() {
+            if let Some(health) = self.world.get_component_mut::<Health>(entity) {
+                health.current = health.maximum;
             }
-            MapType::Hallway => {
-                self.show_dialogue(
-                    "Berkay",
-                    "Laten we naar de kantine gaan!",
-                    "Let's go to the cafeteria!",
-                );
-                MapType::Cafeteria
+        }
+    }
+}
+
+impl GameplayState {
+    fn start_wave(&mut self) {
+        crate::data::queue_music_cue(MusicCue::Battle);
+        self.wave_best_rank = StyleRank::D;
+        self.current_wave += 1;
+        self.announcer.announce(Callout::WaveStart(self.current_wave));
+        self.run_tally.waves += 1;
+        self.record_reached_wave();
+        self.wave_spawned = 0;
+        self.wave_damage_taken = false;
+        self.wave_start_health = self
+            .player_entity
+            .and_then(|player| self.world.get_component::<Health>(player))
+            .map(|health| health.current);
+        self.refresh_allies_for_wave();
+        self.run_had_allies |= !self.ally_entities.is_empty();
+        self.spawn_destructibles();
+
+        let map = self.current_map.layout();
+        let Some(wave) = self.encounters.wave(map, self.current_wave).cloned() else {
+            self.enemies_to_spawn = 0;
+            return;
+        };
+        self.queue_encounter_lines(&wave.intro);
+        if self.current_map == MapType::Rooftop && wave.boss.is_some() {
+            self.play_cutscene(CutsceneId::RooftopIntro);
+        }
+        match wave.boss {
+            Some(EncounterBoss::Bastiaan) => self.spawn_big_boss(),
+            Some(EncounterBoss::Mees) => self.spawn_mees_boss(),
+            None => {}
+        }
+
+        // Bigger waves for every extra human in co-op, under a Blizzard and
+        // in a Swarm
+        let curse = self.winter_arc.as_ref().map_or(1.0, |run| run.enemy_count_scale());
+        let swarm = self.challenge_scale(ChallengeModifier::enemy_count_scale);
+        let scale = self.route_modifier.enemy_count_scale()
+            * self.coop_scaling.spawn_count
+            * curse
+            * swarm;
+        let enemy_count = (wave.enemies as f32 * scale).round() as usize;
+        self.enemies_to_spawn = enemy_count;
+        self.spawn_timer = wave.delay;
+    }
+
+    fn handle_pause_menu(&mut self) {
+        match self.pause_menu.handle_input(&*self.host) {
+            PauseAction::Resume => {
+                self.pause_menu.toggle();
+                self.paused = false;
             }
-            MapType::Cafeteria => {
-                self.show_dialogue("Hadi", "De gymzaal is volgende!", "The gym is next!");
-                MapType::Gym
+            PauseAction::RestartWave => match self.wave_restart_blocked() {
+                Some(reason) => self.set_shop_feedback(reason),
+                None => {
+                    self.restart_wave();
+                    self.pause_menu.toggle();
+                    self.paused = false;
+                }
+            },
+            // Opened over the run, which stays paused underneath
+            PauseAction::Settings => {
+                crate::data::set_over_run();
+                self.overlay = Some(StateType::Settings);
             }
-            MapType::Gym => {
-                self.show_dialogue("Nitin", "Naar de bibliotheek!", "To the library!");
-                MapType::Library
+            PauseAction::Controls => {
+                crate::data::set_over_run();
+                self.overlay = Some(StateType::Controls);
             }
-            MapType::Library => {
-                self.show_dialogue(
-                    "You",
-                    "Naar het dak voor de laatste strijd!",
-                    "To the roof for the final battle!",
-                );
-                MapType::Rooftop
+            PauseAction::QuitToMenu => self.transition_to = Some(StateType::Menu),
+            PauseAction::None => {}
+        }
+    }
+
+    /// Why the wave can't be restarted right now, if it can't
+    fn wave_restart_blocked(&self) -> Option<&'static str> {
+        if self.net_session.is_some() || self.coop_manager.is_some() {
+            Some("Waves can't be restarted in co-op")
+        } else if self.winter_arc.is_some() {
+            Some("A Winter Arc run can't be restarted")
+        } else if self.challenge_run.is_some() {
+            Some("Challenge runs can't be restarted")
+        } else if self.shopkeeper.is_some()
+            || (self.enemy_entities.is_empty() && self.enemies_to_spawn == 0)
+        {
+            Some("Only a wave in progress can be restarted")
+        } else {
+            None
+        }
+    }
+
+    /// Clear the wave's enemies and start it over, with P1 back on the
+    /// health they had when it began
+    fn restart_wave(&mut self) {
+        for entity in self.enemy_entities.drain(..) {
+            self.world.commands().destroy(entity);
+        }
+        self.bosses_barked.clear();
+        self.dialogue_queue.clear();
+        self.dialogue_box.clear();
+        self.combo_system.break_combo();
+        self.hits_taken = 0;
+        if let (Some(player), Some(health)) = (self.player_entity, self.wave_start_health) {
+            if let Some(current) = self.world.get_component_mut::<Health>(player) {
+                current.current = health;
             }
-            MapType::Rooftop => MapType::Rooftop,
-        };
+        }
 
+        // Started again under the same number
+        self.current_wave -= 1;
+        self.run_tally.waves -= 1;
         self.start_wave();
     }
 
-    fn show_dialogue(&mut self, speaker: &str, dutch: &str, english: &str) {
-        self.dialogue_queue.push(DialogueLine {
-            speaker: speaker.to_string(),
-            dutch: dutch.to_string(),
-            english: english.to_string(),
-            duration: 2.5,
-        });
+    /// Waves the current map is scripted with
+    fn map_waves(&self) -> usize {
+        self.encounters.waves(self.current_map.layout()).len()
     }
 
-    fn process_fighter_states(&mut self, dt: f32) {
-        let fighter_states: Vec<_> = self
-            .world
-            .query::<Fighter>()
-            .map(|(entity, fighter)| (entity, fighter.state))
-            .collect();
-
-        let mut state_changes = Vec::new();
-
-        for (entity, state) in &fighter_states {
-            let previous = self.previous_fighter_states.get(entity).copied();
-            if previous != Some(*state) {
-                state_changes.push((*entity, previous, *state));
-            }
+    /// Queue scripted lines to play, in order, after whatever's already queued
+    fn queue_encounter_lines(&mut self, lines: &[EncounterLine]) {
+        // The queue plays from the back
+        for line in lines {
+            self.dialogue_queue.insert(
+                0,
+                DialogueLine {
+                    speaker: line.speaker.clone(),
+                    text: LineText::Written {
+                        dutch: line.dutch.clone(),
+                        english: line.english.clone(),
+                    },
+                    duration: line.duration,
+                },
+            );
         }
+    }
 
-        let mut deactivate_hitboxes = Vec::new();
+    fn spawn_wave_enemy(&mut self) {
+        let Some(wave) = self.encounters.wave(self.current_map.layout(), self.current_wave) else {
+            return;
+        };
+        let total = wave.total_weight();
+        let roll = self.world.rng().range(0, total.max(1) as i32) as u32;
+        let Some(enemy_type) = wave.pick(roll) else {
+            return;
+        };
+        let scripted_point = match wave.spawn_points.as_slice() {
+            [] => None,
+            points => Some(points[self.wave_spawned % points.len()]),
+        };
+        self.wave_spawned += 1;
 
-        for (entity, _) in &fighter_states {
-            if let Some(fighter) = self.world.get_component_mut::<Fighter>(*entity) {
-                if fighter.attack_timer > 0.0 {
-                    fighter.attack_timer -= dt;
-                    if fighter.attack_timer <= 0.0 {
-                        fighter.attack_timer = 0.0;
-                        deactivate_hitboxes.push(*entity);
+        let spawn_point = match scripted_point {
+            Some(index) => self.map_system.spawn_points.get(index).copied(),
+            None => self.map_system.next_spawn_point(),
+        };
+        let Some(spawn_point) = spawn_point else {
+            let spawn_x = 800.0 + (self.host.time() * 1000.0) as f32 % 200.0;
+            let spawn_y = (450.0 + (self.host.time() * 777.0) as f32 % 200.0).clamp(340.0, 660.0);
+            let entity = self.spawn_enemy(Vec2::new(spawn_x, spawn_y), enemy_type);
+            self.apply_route_modifier(entity);
+            return;
+        };
 
-                        if matches!(
-                            fighter.state,
-                            FighterState::LightAttack
-                                | FighterState::HeavyAttack
-                                | FighterState::Special
-                                | FighterState::Super
-                        ) {
-                            fighter.state = FighterState::Idle;
-                        }
-                    }
-                }
+        let arena = arena_bounds(self.host.screen_width());
+        let origin = spawn_point.origin(arena);
+        let entity = self.spawn_enemy(origin, enemy_type);
+        self.apply_route_modifier(entity);
+        self.world.add_component(
+            entity,
+            SpawnEntrance::new(spawn_point.kind, origin, spawn_point.landing(arena)),
+        );
+        if let Some(fighter) = self.world.get_component_mut::<Fighter>(entity) {
+            fighter.invulnerable = true;
+        }
+    }
 
-                if fighter.hitstun > 0.0 {
-                    fighter.hitstun = (fighter.hitstun - dt).max(0.0);
-                    if fighter.hitstun <= 0.0 && fighter.state == FighterState::Hitstun {
-                        fighter.state = FighterState::Idle;
-                    }
-                }
+    /// Scale a freshly spawned wave enemy by the current route's modifier
+    /// and the challenge run's
+    fn apply_route_modifier(&mut self, entity: EntityId) {
+        let health_scale = self.route_modifier.enemy_health_scale()
+            * self.challenge_scale(ChallengeModifier::enemy_health_scale);
+        if let Some(health) = self.world.get_component_mut::<Health>(entity) {
+            health.maximum *= health_scale;
+            health.current = health.maximum;
+        }
+        if let Some(ai) = self.world.get_component_mut::<AIController>(entity) {
+            ai.reaction_scale *= self.route_modifier.enemy_reaction_scale();
+        }
+    }
 
-                if fighter.blockstun > 0.0 {
-                    fighter.blockstun = (fighter.blockstun - dt).max(0.0);
-                    if fighter.blockstun <= 0.0 && fighter.state == FighterState::Blockstun {
-                        fighter.state = FighterState::Idle;
-                    }
+    /// Hand this frame's events to the combo, VFX, progression and UI handlers
+    fn dispatch_events(&mut self) {
+        for event in self.world.events().drain() {
+            match event {
+                GameEvent::HitLanded {
+                    attacker,
+                    defender,
+                    damage,
+                    position,
+                    critical,
+                } => self.on_hit_landed(attacker, defender, damage, position, critical),
+                GameEvent::EntityDied {
+                    entity,
+                    team,
+                    position,
+                } => self.on_entity_died(entity, team, position),
+                GameEvent::WaveCompleted { wave } => self.on_wave_completed(wave),
+                GameEvent::BossDefeated { position } => self.on_boss_defeated(position),
+                GameEvent::TeammateRevived { position } => self.on_teammate_revived(position),
+                GameEvent::BossWindup { cue, position } => self.play_sound_at(cue, position),
+                GameEvent::BombExploded { position, radius } => {
+                    self.on_bomb_exploded(position, radius)
                 }
+                GameEvent::PickupCollected {
+                    collector,
+                    drop,
+                    position,
+                } => self.on_pickup_collected(collector, drop, position),
+                GameEvent::Ping {
+                    kind,
+                    position,
+                    seat,
+                } => self.pings.place(seat, kind, position),
+            }
+            for handler in &mut self.event_handlers {
+                handler.handle(&self.world, &event);
             }
         }
+    }
 
-        for entity in deactivate_hitboxes {
-            if let Some(hitbox) = self.world.get_component_mut::<HitboxComponent>(entity) {
-                hitbox.active = false;
-                hitbox.hits_registered.clear();
-            }
+    fn set_player_attack_multiplier(&mut self, multiplier: f32) {
+        if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+            combat.set_player_attack_multiplier(multiplier);
         }
+    }
 
-        for (entity, previous, current) in state_changes {
-            self.on_fighter_state_changed(entity, previous, current);
+    fn on_hit_landed(
+        &mut self,
+        attacker: EntityId,
+        defender: EntityId,
+        damage: f32,
+        position: Vec2,
+        is_critical: bool,
+    ) {
+        use crate::combat::combo_system::MoveType;
+        use crate::render::enhanced_vfx::ImpactType;
+
+        self.shake_for_hit(attacker);
+        self.play_hit_sound(attacker, position);
+        self.register_team_hit(attacker, defender, damage, position);
+        self.tally_hit(attacker, defender, damage);
+        if self.human_entities().contains(&defender) {
+            self.wave_damage_taken = true;
+            self.run_damage_taken = true;
+        }
+        if Some(defender) == self.player_entity {
+            self.hits_taken += 1;
+            self.hits_taken_timer = COMBO_BREAKER_WINDOW;
+            self.voice.speak(self.selected_character, VoiceCue::Hurt);
+        }
+        if Some(attacker) == self.player_entity {
+            if self.hits_taken >= COMBO_BREAKER_HITS {
+                self.announcer.announce(Callout::ComboBreaker);
+            }
+            self.hits_taken = 0;
+            self.voice.speak(self.selected_character, VoiceCue::Hit);
         }
 
-        self.previous_fighter_states.clear();
-        for (entity, state) in fighter_states {
-            self.previous_fighter_states.insert(entity, state);
+        // Only the player's hits, and their partner's assists, feed the combo meter
+        if Some(attacker) != self.player_entity && Some(attacker) != self.assist_entity {
+            return;
         }
-    }
 
-    fn clamp_fighter_positions(&mut self) {
-        let fighters: Vec<_> = self
+        // Hits on a launched enemy count as air attacks for style
+        let juggled = self
             .world
-            .query::<Fighter>()
-            .map(|(entity, _)| entity)
-            .collect();
+            .get_component::<Knockback>(defender)
+            .is_some_and(|kb| kb.is_airborne());
+
+        let move_type = match self.world.get_component::<Fighter>(attacker).map(|f| f.state) {
+            _ if juggled => MoveType::AirAttack,
+            Some(FighterState::HeavyAttack) => MoveType::HeavyAttack,
+            Some(FighterState::Special) => MoveType::SpecialAttack,
+            Some(FighterState::Super) => MoveType::Ability,
+            _ => MoveType::LightAttack,
+        };
+        let previous_rank = self.combo_system.style_rank;
+        let combo_result = self.combo_system.register_hit(move_type, damage, is_critical);
+        if combo_result.style_rank > previous_rank {
+            self.announcer.announce(Callout::RankUp(combo_result.style_rank));
+        }
+        self.wave_best_rank = self.wave_best_rank.max(combo_result.style_rank);
+        self.run_best_rank = self.run_best_rank.max(combo_result.style_rank);
+        let combo = combo_result.combo_count;
+        if combo > 0 && combo.is_multiple_of(MASTERY_COMBO_STEP) {
+            let xp = combo as f32 * MASTERY_COMBO_XP * self.route_modifier.xp_scale();
+            self.grant_mastery_xp(xp, "Combo");
+        }
+        self.record_achievement(AchievementEvent::DamageDealt(damage));
+        self.record_achievement(AchievementEvent::ComboReached(combo_result.combo_count));
+        self.record_challenge(ChallengeType::DealDamage { amount: damage }, damage);
+        self.record_challenge(ChallengeType::ReachCombo { combo }, combo as f32);
+        self.record_achievement(AchievementEvent::StyleRankReached(combo_result.style_rank));
+
+        // Show combo VFX if active
+        if combo_result.combo_count.is_multiple_of(5) {
+            if let Some(transform) = self.world.get_component::<Transform>(attacker) {
+                self.enhanced_vfx.show_combo_text(
+                    transform.position,
+                    combo_result.combo_count,
+                    combo_result.style_rank,
+                );
+            }
+        }
 
-        for entity in fighters {
-            if let Some(transform) = self.world.get_component_mut::<Transform>(entity) {
-                transform.position.x = transform.position.x.clamp(60.0, screen_width() - 60.0);
-                transform.position.y = transform.position.y.clamp(340.0, 660.0);
+        let impact_type = match move_type {
+            MoveType::LightAttack => ImpactType::Light,
+            MoveType::HeavyAttack => ImpactType::Heavy,
+            MoveType::SpecialAttack => ImpactType::Critical,
+            _ => ImpactType::Medium,
+        };
+
+        let direction = match self.world.get_component::<Transform>(attacker) {
+            Some(attacker_transform) => (position - attacker_transform.position).normalize_or_zero(),
+            None => Vec2::new(1.0, 0.0),
+        };
+
+        match self.cosmetics.hit_spark {
+            Some(color) => {
+                self.enhanced_vfx.spawn_tinted_impact(position, direction, impact_type, color)
             }
+            None => self.enhanced_vfx.spawn_impact(position, direction, impact_type),
         }
+        self.enhanced_vfx.show_damage_number(position, damage, is_critical);
     }
 
-    fn update_stamina_system(&mut self, dt: f32) {
-        // Collect all entities with Stamina component
-        let entities: Vec<EntityId> = self
-            .world
-            .query::<Stamina>()
-            .map(|(entity, _)| entity)
-            .collect();
+    fn on_entity_died(&mut self, entity: EntityId, team: Team, position: Vec2) {
+        use crate::render::enhanced_vfx::ImpactType;
 
-        for entity in entities {
-            if let (Some(fighter), Some(stamina)) = (
-                self.world.get_component::<Fighter>(entity),
-                self.world.get_component::<Stamina>(entity)
-            ) {
-                let is_attacking = matches!(
-                    fighter.state,
-                    FighterState::LightAttack | FighterState::HeavyAttack | FighterState::Special
-                );
+        self.burning_enemies.remove(&entity);
 
-                // Only regenerate if not attacking or in recovery
-                let should_regen = !is_attacking && fighter.attack_recovery <= 0.0;
+        if team != Team::Enemy {
+            return;
+        }
 
-                // Update regen delay timer
-                let new_timer = if should_regen {
-                    (stamina.regen_delay_timer - dt).max(0.0)
-                } else {
-                    stamina.regen_delay
-                };
+        // Enemy died - award XP and check achievements
+        let xp_reward = 50.0; // Base XP per kill
+        let combo_multiplier = 1.0 + (self.combo_system.combo_count as f32 * 0.05);
+        let total_xp = xp_reward * combo_multiplier * self.route_modifier.xp_scale();
+
+        // Award XP to character mastery and account progression; practice
+        // doesn't count
+        if self.practice.is_none() {
+            self.grant_mastery_xp(total_xp, "Enemy defeated");
+            self.account.progression.total_kills += 1;
+            self.run_kills += 1;
+        }
 
-                // Regenerate stamina after delay
-                let new_current = if should_regen && new_timer <= 0.0 {
-                    (stamina.current + stamina.regen_rate * dt).min(stamina.maximum)
-                } else {
-                    stamina.current
-                };
+        self.record_achievement(AchievementEvent::EnemyDefeated);
+        self.record_challenge(
+            ChallengeType::DefeatEnemies {
+                count: 1,
+                enemy_type: None,
+            },
+            1.0,
+        );
+        if self.challenge_has(ChallengeModifier::ExplodingEnemies) {
+            self.death_blast(position);
+        }
 
-                // Update exhaustion state
-                let new_exhausted = new_current <= 0.0;
+        // Spawn death VFX
+        self.enhanced_vfx.spawn_impact(position, Vec2::new(0.0, -1.0), ImpactType::Heavy);
+    }
 
-                // Update the component
-                if let Some(stamina_mut) = self.world.get_component_mut::<Stamina>(entity) {
-                    stamina_mut.current = new_current;
-                    stamina_mut.regen_delay_timer = new_timer;
-                    stamina_mut.exhausted = new_exhausted;
+    /// A Volatile enemy going off: hurts and shoves every player close by
+    fn death_blast(&mut self, position: Vec2) {
+        for entity in self.human_entities() {
+            let Some(at) = self.world.get_component::<Transform>(entity).map(|t| t.position) else {
+                continue;
+            };
+            if at.distance(position) > DEATH_BLAST_RADIUS {
+                continue;
+            }
+            if let Some(health) = self.world.get_component_mut::<Health>(entity) {
+                if health.current <= 0.0 {
+                    continue;
                 }
+                health.current = (health.current - DEATH_BLAST_DAMAGE).max(0.0);
+            }
+            if Some(entity) == self.player_entity {
+                self.run_tally.add_taken("Death blast", DEATH_BLAST_DAMAGE);
+                self.combo_system.break_combo();
+            }
+            self.wave_damage_taken = true;
+            self.run_damage_taken = true;
+            let away = (at - position).normalize_or_zero();
+            apply_knockback(&mut self.world, entity, away * 280.0, 260.0);
+        }
+        self.world.events().send(GameEvent::BombExploded {
+            position,
+            radius: DEATH_BLAST_RADIUS,
+        });
+    }
 
-                // Apply exhaustion effects to fighter
-                if new_exhausted {
-                    if let Some(fighter_mut) = self.world.get_component_mut::<Fighter>(entity) {
-                        fighter_mut.invulnerable = false; // Can't dodge while exhausted
-                    }
-                }
+    /// Bosses call out once when they're knocked down to half health
+    fn bark_at_half_health(&mut self) {
+        for &entity in &self.enemy_entities {
+            if self.bosses_barked.contains(&entity) {
+                continue;
+            }
+            let boss = self
+                .world
+                .get_component::<AIController>(entity)
+                .is_some_and(|ai| matches!(ai.behavior, AIBehavior::Boss(_)));
+            let hurt = self
+                .world
+                .get_component::<Health>(entity)
+                .is_some_and(|health| health.current < health.maximum * BOSS_BARK_HEALTH);
+            let Some(fighter) = self.world.get_component::<Fighter>(entity) else {
+                continue;
+            };
+            if boss && hurt {
+                Self::boss_bark(&mut self.subtitles, &fighter.character_type, "hurt");
+                self.bosses_barked.push(entity);
             }
         }
     }
 
-    fn consume_stamina(&mut self, entity: EntityId, amount: f32) -> bool {
-        if let Some(stamina) = self.world.get_component::<Stamina>(entity) {
-            if stamina.current >= amount {
-                if let Some(stamina_mut) = self.world.get_component_mut::<Stamina>(entity) {
-                    stamina_mut.current -= amount;
-                    stamina_mut.regen_delay_timer = stamina_mut.regen_delay;
-                    return true;
+    /// Queue `boss`'s bark for `moment`, if it has one
+    fn boss_bark(subtitles: &mut SubtitleManager, boss: &CharacterType, moment: &str) {
+        let (speaker, id) = match boss {
+            CharacterType::Bastiaan => ("Bastiaan", "bastiaan"),
+            CharacterType::KeizerBomTahaBoss => ("Keizer Bom Taha", "keizer"),
+            CharacterType::Mees => ("Mees", "mees"),
+            _ => return,
+        };
+        subtitles.say(speaker, LineText::Key(format!("bark.{}.{}", id, moment)));
+    }
+
+    fn on_boss_defeated(&mut self, position: Vec2) {
+        let gold = Color::new(1.0, 0.8, 0.2, 1.0);
+        self.enhanced_vfx.spawn_dynamic_light(position, gold, 2.0, 260.0, 1.2, false);
+        self.record_achievement(AchievementEvent::BossDefeated);
+    }
+
+    fn on_teammate_revived(&mut self, position: Vec2) {
+        let green = Color::new(0.4, 1.0, 0.5, 1.0);
+        self.enhanced_vfx.spawn_dynamic_light(position, green, 1.2, 140.0, 0.8, false);
+        self.record_achievement(AchievementEvent::TeammateRevived);
+        self.record_challenge(ChallengeType::RevivePlayers { count: 1 }, 1.0);
+    }
+
+    /// Roll the drop table for a defeated enemy and scatter what comes up
+    fn drop_loot(&mut self, position: Vec2, boss: bool) {
+        let drops = self.loot_table.roll_enemy(&mut self.loot_rng, boss);
+        let middle = drops.len().saturating_sub(1) as f32 * 0.5;
+        for (i, entry) in drops.into_iter().enumerate() {
+            let spread = Vec2::new((i as f32 - middle) * LOOT_SPREAD, 0.0);
+            self.world
+                .commands()
+                .spawn()
+                .with(Transform {
+                    position: position + spread,
+                    rotation: 0.0,
+                    scale: Vec2::ONE,
+                })
+                .with(Pickup::new(entry.drop, entry.rarity));
+        }
+    }
+
+    /// `multiplier` while the power-up is running, otherwise 1
+    fn power_up_multiplier(&self, kind: PowerUpKind, multiplier: f32) -> f32 {
+        if self.power_ups.iter().any(|(active, _)| *active == kind) {
+            multiplier
+        } else {
+            1.0
+        }
+    }
+
+    /// Run down the power-up timers; regen heals every player while it lasts
+    fn update_power_ups(&mut self, dt: f32) {
+        if self.power_ups.iter().any(|(kind, _)| *kind == PowerUpKind::Regen) {
+            for entity in self.human_entities() {
+                if let Some(health) = self.world.get_component_mut::<Health>(entity) {
+                    if health.current > 0.0 {
+                        let healed = health.current + REGEN_PER_SECOND * dt;
+                        health.current = healed.min(health.maximum);
+                    }
                 }
             }
         }
-        false
+
+        self.power_ups.retain_mut(|(_, left)| {
+            *left -= dt;
+            *left > 0.0
+        });
     }
 
-    fn check_game_over(&mut self) {
-        if self.game_over {
-            return;
-        }
+    fn on_wave_completed(&mut self, wave: usize) {
+        let reward = 40 + (wave as u32 * 5);
+        let scale = self.route_modifier.token_scale() * self.coop_scaling.token_reward;
+        let reward = (reward as f32 * scale).round() as u32;
+        self.grant_currency(reward, RewardKind::Wave, "Wave cleared");
 
-        if let Some(player_entity) = self.player_entity {
-            let dead = match self.world.get_component::<Health>(player_entity) {
-                Some(health) => health.current <= 0.0,
-                None => true,
-            };
+        let xp = MASTERY_WAVE_XP + MASTERY_WAVE_XP_STEP * wave as f32;
+        self.grant_mastery_xp(xp * self.route_modifier.xp_scale(), "Wave cleared");
 
-            if dead {
-                self.trigger_game_over();
+        self.record_achievement(AchievementEvent::WaveCleared {
+            wave: self.waves_completed as u32,
+            flawless: !self.wave_damage_taken,
+        });
+        let reached = ChallengeType::CompleteWave {
+            wave: self.waves_completed as u32,
+            mode: "Any".to_string(),
+        };
+        self.record_challenge(reached, self.waves_completed as f32);
+
+        // The seeded run is done once enough waves are down
+        let cleared_run = self
+            .challenge_run
+            .as_ref()
+            .filter(|run| self.waves_completed >= run.kind.run_waves())
+            .map(|run| run.kind);
+        if let Some(kind) = cleared_run {
+            if let Some(bonus) = self.challenge_manager.complete_run(kind) {
+                let title = format!("{} Run", kind.label());
+                let detail = format!("{} waves cleared", kind.run_waves());
+                self.toasts.push("CHALLENGE COMPLETE", &title, &detail, GOLD);
+                let reason = format!("{} challenge", kind.label());
+                self.grant_currency(bonus, RewardKind::Challenge, &reason);
+                self.save_challenges();
             }
         }
     }
 
-    fn trigger_game_over(&mut self) {
-        self.game_over = true;
-        self.shop_open = false;
-        self.dialogue_queue.clear();
-        self.current_dialogue = None;
+    /// Tokens go to the shared purse and power-ups to the whole team; health
+    /// and meter go to whoever grabbed them
+    fn on_pickup_collected(&mut self, collector: EntityId, drop: LootDrop, position: Vec2) {
+        let color = match drop {
+            LootDrop::Tokens(amount) => {
+                self.grant_currency(amount, RewardKind::Pickup, "Token pickup");
+                // The sparkle shows who grabbed them
+                self.coop_guest(collector)
+                    .map(|(_, color)| color)
+                    .unwrap_or(Color::new(1.0, 0.85, 0.2, 1.0))
+            }
+            LootDrop::HealthPack(amount) => {
+                if let Some(health) = self.world.get_component_mut::<Health>(collector) {
+                    health.current = (health.current + amount).min(health.maximum);
+                }
+                Color::new(1.0, 0.3, 0.3, 1.0)
+            }
+            LootDrop::MeterBattery(amount) => {
+                if let Some(fighter) = self.world.get_component_mut::<Fighter>(collector) {
+                    fighter.meter = (fighter.meter + amount).min(fighter.max_meter);
+                }
+                Color::new(0.3, 0.6, 1.0, 1.0)
+            }
+            LootDrop::PowerUp { kind, duration } => {
+                // Grabbing one that's running tops it back up
+                match self.power_ups.iter_mut().find(|(active, _)| *active == kind) {
+                    Some((_, left)) => *left = left.max(duration),
+                    None => self.power_ups.push((kind, duration)),
+                }
+                kind.color()
+            }
+        };
+
+        self.world
+            .commands()
+            .spawn()
+            .with(Transform {
+                position: position + Vec2::new(0.0, 46.0),
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            })
+            .with(
+                Emitter::burst(12, ParticleType::Spark, ColorRamp::fade(color))
+                    .speed(60.0, 140.0)
+                    .lifetime(0.3, 0.6)
+                    .size(5.0, 1.0),
+            );
     }
 
-    fn on_fighter_state_changed(
-        &mut self,
-        entity: EntityId,
-        _previous: Option<FighterState>,
-        current: FighterState,
-    ) {
-        if matches!(
-            current,
-            FighterState::LightAttack
-                | FighterState::HeavyAttack
-                | FighterState::Special
-                | FighterState::Super
-        ) {
-            self.activate_attack_hitbox(entity, current);
+    fn on_bomb_exploded(&mut self, explosion_pos: Vec2, radius: f32) {
+        self.play_sound_at(self.sfx.get_explosion_sound(), explosion_pos);
+        self.camera.add_trauma(0.5);
+        self.camera.push_focus(
+            FocusPriority::Explosion,
+            explosion_pos,
+            1.1,
+            Some(EXPLOSION_FOCUS),
+        );
+        self.post.trigger(PostMoment::BombExplosion);
+
+        // Explosion smoke, more for bigger blasts
+        let particle_count = (radius * 0.5).clamp(12.0, 40.0) as usize;
+        let colors = ColorRamp::new(
+            Color::new(1.0, 0.9, 0.5, 1.0),
+            Color::new(0.3, 0.3, 0.3, 0.0),
+        )
+        .with_mid(Color::new(1.0, 0.5, 0.0, 0.8));
+        self.world
+            .commands()
+            .spawn()
+            .with(Transform {
+                position: explosion_pos,
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            })
+            .with(
+                Emitter::burst(particle_count, ParticleType::Smoke, colors)
+                    .speed(50.0, 200.0)
+                    .lifetime(1.0, 1.0)
+                    .size(15.0, 3.0)
+                    .acceleration(Vec2::new(0.0, 50.0)),
+            );
+    }
 
-            if let Some(origin) = self
-                .world
-                .get_component::<Transform>(entity)
-                .map(|t| t.position)
+    fn complete_wave(&mut self) {
+        self.waves_completed += 1;
+        self.world.events().send(GameEvent::WaveCompleted {
+            wave: self.current_wave,
+        });
+        if let Some(outro) = self
+            .encounters
+            .wave(self.current_map.layout(), self.current_wave)
+            .map(|wave| wave.outro.clone())
+        {
+            self.queue_encounter_lines(&outro);
+        }
+
+        // A Winter Arc run picks an upgrade before it moves on
+        if let Some(run) = &mut self.winter_arc {
+            run.wave_cleared();
+            run.roll_offers();
+            let heal = 0.15 * run.stacks(UpgradeId::WarmBlanket) as f32;
+            self.upgrade_choice_selected = 0;
+            if let Some(health) = self
+                .player_entity
+                .and_then(|player| self.world.get_component_mut::<Health>(player))
             {
-                self.spawn_attack_particles(origin, current);
+                health.current = (health.current + health.maximum * heal).min(health.maximum);
             }
+            return;
+        }
+
+        if self.current_wave >= self.map_waves() {
+            self.finish_route();
+            self.offer_route_choice();
+        } else if self.shopkeeper_visits() {
+            self.open_shop_break();
+        } else {
+            self.start_wave();
         }
     }
 
-    fn activate_attack_hitbox(&mut self, entity: EntityId, state: FighterState) {
-        let (duration, hitbox) = match state {
-            FighterState::LightAttack => (0.25, Hitbox::new_light()),
-            FighterState::HeavyAttack => (0.4, Hitbox::new_heavy()),
-            FighterState::Special => (0.55, Hitbox::new_special(SpecialType::Paintbrush)),
-            FighterState::Super => (0.8, Hitbox::new_super()),
-            _ => (0.0, Hitbox::new_light()),
+    /// The shopkeeper stays away from Winter Arc runs, No Shop challenges and
+    /// online runs, whose waves follow the shared tick
+    fn shopkeeper_visits(&self) -> bool {
+        self.winter_arc.is_none()
+            && !self.challenge_has(ChallengeModifier::NoShop)
+            && self.net_session.is_none()
+    }
+
+    /// Set the shopkeeper up at the back of the floor with a fresh stock
+    fn open_shop_break(&mut self) {
+        let mut options = SHOP_OPTIONS
+            .iter()
+            .filter(|option| self.shop_manager.rank(option.id) < option.id.max_rank())
+            .copied()
+            .collect::<Vec<_>>();
+        self.loot_rng.shuffle(&mut options);
+        self.keeper_stock = options
+            .into_iter()
+            .take(KEEPER_UPGRADES)
+            .map(ShopStock::Upgrade)
+            .collect();
+        let item = self.loot_rng.range(0, Consumable::ALL.len() as i32) as usize;
+        self.keeper_stock.push(ShopStock::Item(Consumable::ALL[item]));
+
+        let arena = arena_bounds(self.host.screen_width());
+        let keeper = self.world.create_entity();
+        let position = Vec2::new(arena.center().x, arena.y + 20.0);
+        let transform = Transform {
+            position,
+            rotation: 0.0,
+            scale: Vec2::ONE,
         };
+        self.world.add_component(keeper, transform);
+        self.world.add_component(keeper, Shopkeeper::arriving());
+        self.shopkeeper = Some(keeper);
+        crate::data::queue_music_cue(MusicCue::Shop);
+        self.set_shop_feedback("The shopkeeper has set up shop - walk over and press B");
+    }
 
-        let mut new_facing = None;
-        if let Some(velocity) = self.world.get_component::<Velocity>(entity) {
-            if velocity.linear.x > 1.0 {
-                new_facing = Some(1.0);
-            } else if velocity.linear.x < -1.0 {
-                new_facing = Some(-1.0);
-            }
+    /// Pack the shopkeeper up and send in the next wave
+    fn close_shop_break(&mut self) {
+        if let Some(keeper) = self.shopkeeper.take() {
+            self.world.commands().destroy(keeper);
         }
+        self.shop_open = false;
+        self.keeper_stock.clear();
+        self.start_wave();
+    }
 
-        if let Some(fighter) = self.world.get_component_mut::<Fighter>(entity) {
-            fighter.attack_timer = duration;
-            if let Some(facing) = new_facing {
-                fighter.facing = facing;
+    fn update_shopkeeper(&mut self, dt: f32) {
+        let Some(keeper) = self.shopkeeper else {
+            return;
+        };
+        let gone = match self.world.get_component_mut::<Shopkeeper>(keeper) {
+            Some(shopkeeper) => {
+                shopkeeper.lifetime -= dt;
+                shopkeeper.lifetime <= 0.0
             }
+            None => true,
+        };
+        if gone {
+            self.set_shop_feedback("The shopkeeper packed up - here comes the next wave!");
+            self.close_shop_break();
         }
+    }
 
-        if let Some(hitbox_comp) = self.world.get_component_mut::<HitboxComponent>(entity) {
-            hitbox_comp.hitbox = hitbox;
-            hitbox_comp.active = true;
-            hitbox_comp.hits_registered.clear();
+    fn near_shopkeeper(&self) -> bool {
+        let position = |entity: Option<EntityId>| {
+            let entity = entity?;
+            Some(self.world.get_component::<Transform>(entity)?.position)
+        };
+        match (position(self.shopkeeper), position(self.player_entity)) {
+            (Some(keeper), Some(player)) => keeper.distance(player) <= Shopkeeper::REACH,
+            _ => false,
         }
     }
 
-    fn spawn_attack_particles(&mut self, center: Vec2, state: FighterState) {
-        let (count, base_color, lifetime, size_start) = match state {
-            FighterState::LightAttack => (6, Color::new(1.0, 0.85, 0.4, 0.9), 0.25, 12.0),
-            FighterState::HeavyAttack => (10, Color::new(1.0, 0.55, 0.35, 0.95), 0.3, 14.0),
-            FighterState::Special => (14, Color::new(0.45, 0.8, 1.0, 0.95), 0.35, 16.0),
-            FighterState::Super => (20, Color::new(0.85, 0.4, 1.0, 1.0), 0.4, 18.0),
-            _ => (6, Color::new(1.0, 0.85, 0.4, 0.9), 0.25, 12.0),
+    /// Share off the shopkeeper's prices, if they've been haggled with
+    fn keeper_discount(&self) -> f32 {
+        self.shopkeeper
+            .and_then(|keeper| self.world.get_component::<Shopkeeper>(keeper))
+            .and_then(|shopkeeper| shopkeeper.discount)
+            .unwrap_or(0.0)
+    }
+
+    /// Talk the shopkeeper down on the strength of the wave's best style rank.
+    /// They only hear one pitch a visit.
+    fn haggle(&mut self) {
+        let rank = self.wave_best_rank;
+        let Some(shopkeeper) = self
+            .shopkeeper
+            .and_then(|keeper| self.world.get_component_mut::<Shopkeeper>(keeper))
+        else {
+            return;
         };
+        if shopkeeper.discount.is_some() {
+            self.set_shop_feedback("\"My prices are final, kid.\"");
+            return;
+        }
 
-        for _ in 0..count {
-            let angle = rand::gen_range(0.0, std::f32::consts::PI * 2.0);
-            let dir = Vec2::new(angle.cos(), angle.sin());
-            let speed = rand::gen_range(140.0, 280.0);
-            let velocity = dir * speed;
-            let acceleration = dir * -220.0;
-            let offset = dir * rand::gen_range(6.0, 22.0);
-            let particle_lifetime = rand::gen_range(lifetime * 0.6, lifetime * 1.1);
-            let size_end = size_start * 0.25;
+        let discount = rank.haggle_discount();
+        shopkeeper.discount = Some(discount);
+        if discount > 0.0 {
+            let percent = (discount * 100.0).round();
+            let rank = rank.to_string();
+            let message = format!("{} rank style! {:.0}% off everything", rank, percent);
+            self.set_shop_feedback(message);
+        } else {
+            self.set_shop_feedback("\"Come back when you've got some style.\"");
+        }
+    }
 
-            let particle_entity = self.world.create_entity();
+    /// Pay out the current route's end-of-map reward
+    fn finish_route(&mut self) {
+        if self.route_modifier != RouteModifier::Elite {
+            return;
+        }
 
-            self.world.add_component(
-                particle_entity,
-                Transform {
-                    position: center + offset,
-                    rotation: 0.0,
-                    scale: Vec2::splat(size_start),
-                },
-            );
+        let unowned = SHOP_OPTIONS
+            .iter()
+            .find(|option| self.shop_manager.rank(option.id) < option.id.max_rank())
+            .copied();
+        match unowned {
+            Some(option) => {
+                self.shop_manager.try_purchase(option.id, 0);
+                self.apply_upgrade_effect(option.id);
+                match self.shop_manager.save() {
+                    Ok(_) => self.set_shop_feedback(format!("Route reward: {}", option.title)),
+                    Err(err) => self.set_shop_feedback(format!("Reward save failed: {}", err)),
+                }
+            }
+            // Everything owned already: pay the upgrade's worth in tokens instead
+            None => self.grant_currency(150, RewardKind::Route, "Route reward"),
+        }
+    }
 
-            self.world.add_component(
-                particle_entity,
-                Particle {
-                    particle_type: ParticleType::Energy,
-                    lifetime: 0.0,
-                    max_lifetime: particle_lifetime,
-                    velocity,
-                    acceleration,
-                    color_start: base_color,
-                    color_end: Color::new(base_color.r, base_color.g, base_color.b, 0.0),
-                    size_start,
-                    size_end,
-                },
-            );
+    /// Offer the next two unvisited maps, or head straight to the rooftop once
+    /// the route is exhausted
+    fn offer_route_choice(&mut self) {
+        self.route_choices = ROUTE_MAPS
+            .iter()
+            .copied()
+            .filter(|map| !self.visited_maps.contains(map))
+            .take(2)
+            .map(|map| (map, map.route_modifier()))
+            .collect();
+        self.route_choice_selected = 0;
+
+        if self.route_choices.is_empty() {
+            self.transition_to_map(MapType::Rooftop, RouteModifier::None);
         }
     }
 
-    fn set_shop_feedback<S: Into<String>>(&mut self, message: S) {
-        self.shop_feedback_message = Some(message.into());
-        self.shop_feedback_timer = 2.5;
+    /// Dialogue choice after the boss battle
+    /// Open the post-boss conversation, with what this run did and what
+    /// earlier runs chose deciding which answers are on offer
+    fn start_conversation(&mut self) {
+        let (script, problems) = DialogueScript::load();
+        for problem in &problems {
+            crash::log(format!("Dialogue script: {}", problem));
+        }
+        let facts = RunFacts {
+            kills: self.run_kills,
+            waves: self.run_tally.waves,
+            flawless: !self.run_damage_taken,
+            went_down: self.run_went_down,
+        };
+        self.conversation = Some(Conversation::new(script, DialogueMemory::load(), facts));
+        self.dialogue_choice_selected = 0;
     }
 
-    fn grant_currency(&mut self, amount: u32, reason: &str) {
-        if amount == 0 {
+    fn handle_dialogue_choice(&mut self) {
+        let Some(conversation) = &mut self.conversation else {
+            return;
+        };
+        let last = conversation.choices().len().saturating_sub(1);
+        let keys = &self.keys;
+        if self.host.key_pressed(KeyCode::Up) || self.host.key_pressed(keys.move_up) {
+            self.dialogue_choice_selected = self.dialogue_choice_selected.saturating_sub(1);
+        }
+        if self.host.key_pressed(KeyCode::Down) || self.host.key_pressed(keys.move_down) {
+            self.dialogue_choice_selected = (self.dialogue_choice_selected + 1).min(last);
+        }
+        if !(self.host.key_pressed(KeyCode::Enter) || self.host.key_pressed(keys.confirm)) {
             return;
         }
+        let Some(ending) = conversation.choose(self.dialogue_choice_selected) else {
+            // On to the next question
+            self.dialogue_choice_selected = 0;
+            return;
+        };
 
-        self.shop_manager.add_currency(amount);
-        match self.shop_manager.save() {
-            Ok(_) => self.set_shop_feedback(format!("{} (+{} Arc Tokens)", reason, amount)),
-            Err(err) => self.set_shop_feedback(format!("Currency save failed: {}", err)),
+        // The conversation reached an ending, which ends the game
+        let memory = conversation.memory().clone();
+        self.conversation = None;
+        if self.practice.is_none() {
+            if let Err(e) = memory.save() {
+                crash::log(format!("Failed to save dialogue choices: {}", e));
+            }
+        }
+        // Taken before the run is recorded, which banks the playtime
+        let result = StoryResult {
+            ending,
+            epilogue: ending.epilogue(&memory),
+            waves_completed: self.run_tally.waves as usize,
+            enemies_defeated: self.run_kills,
+            time_survived: self.run_time as u32,
+        };
+        self.boss_battle_won = true;
+        self.record_run(true);
+        // A cleared Winter Arc run stops on its payout first
+        if self.winter_arc.is_some() {
+            self.finish_winter_arc(true);
+            self.game_over = true;
+            return;
         }
+        // The ending's cutscene, then its results screen
+        crate::data::set_run_report(self.run_report(true));
+        crate::data::set_story_result(result);
+        crate::data::set_cutscene(ending.cutscene(), Some(StateType::Results));
+        self.transition_to = Some(StateType::Cutscene);
     }
 
-    fn apply_initial_upgrades(&mut self) {
-        self.player_move_speed = 260.0;
-        self.player_max_health = 100.0;
-        self.player_attack_multiplier = 1.0;
+    /// Cut away to a scripted scene; the run picks up where it left off.
+    /// Online the other side wouldn't wait, and practice skips the story.
+    fn play_cutscene(&mut self, scene: CutsceneId) {
+        if self.net_session.is_some() || self.practice.is_some() {
+            return;
+        }
+        crate::data::set_cutscene(scene, None);
+        self.overlay = Some(StateType::Cutscene);
+    }
 
-        if self.shop_manager.has_upgrade(UpgradeId::SpeedBoost) {
-            self.player_move_speed = 320.0;
+    fn handle_route_choice(&mut self) {
+        let last = self.route_choices.len().saturating_sub(1);
+        let keys = self.keys;
+        if self.host.key_pressed(KeyCode::Left) || self.host.key_pressed(keys.move_left) {
+            self.route_choice_selected = self.route_choice_selected.saturating_sub(1);
         }
-        if self.shop_manager.has_upgrade(UpgradeId::HealthBoost) {
-            self.player_max_health = 140.0;
+        if self.host.key_pressed(KeyCode::Right) || self.host.key_pressed(keys.move_right) {
+            self.route_choice_selected = (self.route_choice_selected + 1).min(last);
         }
-        if self.shop_manager.has_upgrade(UpgradeId::AttackBoost) {
-            self.player_attack_multiplier = 1.4;
+        if self.host.key_pressed(KeyCode::Key1) {
+            self.route_choice_selected = 0;
+        }
+        if self.host.key_pressed(KeyCode::Key2) {
+            self.route_choice_selected = last.min(1);
         }
 
-        // Apply skill tree bonuses
-        let skill_bonuses = self.skill_tree_manager.calculate_bonuses(self.selected_character);
-        self.player_attack_multiplier *= 1.0 + skill_bonuses.damage_multiplier;
-        self.player_max_health *= 1.0 + skill_bonuses.max_health_multiplier;
-        self.player_move_speed *= 1.0 + skill_bonuses.movement_speed;
+        if self.host.key_pressed(KeyCode::Enter) || self.host.key_pressed(keys.confirm) {
+            let (map, modifier) = self.route_choices[self.route_choice_selected];
+            self.route_choices.clear();
+            self.transition_to_map(map, modifier);
+        }
     }
 
-    fn apply_upgrade_effect(&mut self, upgrade: UpgradeId) {
-        match upgrade {
-            UpgradeId::AttackBoost => {
-                self.player_attack_multiplier = 1.4;
-                self.combat_system
-                    .set_player_attack_multiplier(self.player_attack_multiplier);
-                self.set_shop_feedback("Attack power increased!");
-            }
-            UpgradeId::HealthBoost => {
-                self.player_max_health = 140.0;
-                if let Some(player_entity) = self.player_entity {
-                    if let Some(health) = self.world.get_component_mut::<Health>(player_entity) {
-                        let missing = self.player_max_health - health.maximum;
-                        health.maximum = self.player_max_health;
-                        health.current = (health.current + missing).min(health.maximum);
-                    }
-                }
-                self.set_shop_feedback("Health increased!");
-            }
-            UpgradeId::SpeedBoost => {
-                self.player_move_speed = 320.0;
-                self.set_shop_feedback("Movement speed increased!");
-            }
-            UpgradeId::CooldownReduction => {
-                self.set_shop_feedback("Cooldown reduction purchased! (Not yet implemented)");
-            }
-            UpgradeId::AbilityDuration => {
-                self.set_shop_feedback("Ability duration purchased! (Not yet implemented)");
-            }
-            UpgradeId::LifeSteal => {
-                self.set_shop_feedback("Life steal purchased! (Not yet implemented)");
-            }
-            UpgradeId::DamageReduction => {
-                self.set_shop_feedback("Damage reduction purchased! (Not yet implemented)");
-            }
-            UpgradeId::CriticalChance => {
-                self.set_shop_feedback("Critical chance purchased! (Not yet implemented)");
+    fn transition_to_map(&mut self, map: MapType, modifier: RouteModifier) {
+        self.current_wave = 0;
+        self.current_map = map;
+        self.route_modifier = modifier;
+        self.visited_maps.push(map);
+
+        match map {
+            MapType::Hallway => self.show_dialogue("Luca", "dialogue.route.hallway"),
+            MapType::Cafeteria => self.show_dialogue("Berkay", "dialogue.route.cafeteria"),
+            MapType::Gym => self.show_dialogue("Hadi", "dialogue.route.gym"),
+            MapType::Library => self.show_dialogue("Nitin", "dialogue.route.library"),
+            MapType::Rooftop => self.show_dialogue("You", "dialogue.route.rooftop"),
+            MapType::Classroom => {}
+        }
+
+        // Quiet route: a breather before the next map
+        if modifier == RouteModifier::Quiet {
+            if let Some(health) = self
+                .player_entity
+                .and_then(|player| self.world.get_component_mut::<Health>(player))
+            {
+                health.current = (health.current + health.maximum * 0.4).min(health.maximum);
             }
         }
-    }
 
-    fn handle_shop_controls(&mut self) {
-        for (index, option) in SHOP_OPTIONS.iter().enumerate() {
-            let key = match index {
-                0 => KeyCode::Key1,
-                1 => KeyCode::Key2,
-                2 => KeyCode::Key3,
-                3 => KeyCode::Key4,
-                4 => KeyCode::Key5,
-                5 => KeyCode::Key6,
-                6 => KeyCode::Key7,
-                7 => KeyCode::Key8,
-                _ => continue,
-            };
+        self.map_system = MapSystem::new(self.current_map.layout());
+        self.spawn_stage_props();
 
-            if is_key_pressed(key) {
-                if self.shop_manager.has_upgrade(option.id) {
-                    self.set_shop_feedback(format!("{} is already owned", option.title));
-                    continue;
-                }
+        self.start_wave();
+    }
 
-                if self.shop_manager.try_purchase(option.id, option.cost) {
-                    self.apply_upgrade_effect(option.id);
-                    if let Err(err) = self.shop_manager.save() {
-                        self.set_shop_feedback(format!("Purchase save failed: {}", err));
-                    }
-                } else {
-                    self.set_shop_feedback("Not enough Arc Tokens");
-                }
-            }
+    /// A heavy hit that sends an enemy into the door breaks it down, and the
+    /// fight carries on in the map behind it
+    fn check_knock_through(&mut self) {
+        let (Some(next), Some(door)) = (self.current_map.through_the_door(), self.map_system.door())
+        else {
+            return;
+        };
+        let breach = door.landing(arena_bounds(self.host.screen_width()));
+
+        let knocked_in = self.enemy_entities.iter().any(|&enemy| {
+            let fast = self
+                .world
+                .get_component::<Knockback>(enemy)
+                .is_some_and(|kb| kb.velocity.length() >= KNOCK_THROUGH_SPEED);
+            let at_door = self
+                .world
+                .get_component::<Transform>(enemy)
+                .is_some_and(|t| t.position.distance(breach) < KNOCK_THROUGH_REACH);
+            fast && at_door
+        });
+        if knocked_in {
+            self.break_through(next, breach);
         }
     }
 
-    fn render_shop(&self) {
-        // Calculate scale factor based on screen size
-        let scale_factor = (screen_width() / 1920.0).min(screen_height() / 1080.0).max(0.5).min(1.0);
+    /// Swap the arena mid-wave, keeping the wave and everyone in it
+    fn break_through(&mut self, map: MapType, breach: Vec2) {
+        self.current_map = map;
+        self.visited_maps.push(map);
+        self.winter_arc_rooms.retain(|&room| room != map);
+        self.map_system = MapSystem::new(map.layout());
+        self.spawn_stage_props();
+        self.spawn_destructibles();
+
+        // Pan to the broken door, then ease back to the fight
+        self.camera.push_focus(
+            FocusPriority::StageChange,
+            breach + FRAMING_LIFT,
+            1.3,
+            Some(STAGE_CHANGE_FOCUS),
+        );
+        self.camera.add_trauma(0.6);
+        self.post.trigger(PostMoment::BombExplosion);
+        self.show_dialogue("Luca", "dialogue.route.door_breach");
+    }
 
-        let overlay = Color::new(0.0, 0.0, 0.0, 0.75);
-        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), overlay);
+    /// Knocked off the roof: hurt, then climb back up a little way in
+    fn ring_out(&mut self, entity: EntityId, edge: Vec2) {
+        use crate::render::enhanced_vfx::ImpactType;
+
+        let arena = arena_bounds(self.host.screen_width());
+        let side = if edge.x < arena.x + arena.w * 0.5 { -1.0 } else { 1.0 };
+        self.enhanced_vfx
+            .spawn_impact(edge, Vec2::new(side, 0.0), ImpactType::Critical);
+        self.enhanced_vfx
+            .add_speed_lines(edge, Vec2::new(side, 0.0), 1.0);
+        self.camera.add_trauma(0.5);
+
+        if let Some(health) = self.world.get_component_mut::<Health>(entity) {
+            let damage = health.maximum * RING_OUT_DAMAGE;
+            health.current = (health.current - damage).max(0.0);
+            self.enhanced_vfx
+                .show_damage_number(edge - Vec2::new(0.0, 60.0), damage, true);
+        }
+        if let Some(kb) = self.world.get_component_mut::<Knockback>(entity) {
+            kb.velocity = Vec2::ZERO;
+            kb.height = 0.0;
+            kb.vertical_velocity = 0.0;
+        }
+        if let Some(transform) = self.world.get_component_mut::<Transform>(entity) {
+            transform.position.x -= side * RING_OUT_RETURN;
+        }
+    }
 
-        let title = "Arc Supply Shop";
-        let title_size = (36.0 * scale_factor).min(48.0).max(24.0);
-        let title_dims = measure_text(title, None, title_size as u16, 1.0);
-        draw_text(
-            title,
-            screen_width() * 0.5 - title_dims.width * 0.5,
-            100.0 * scale_factor,
-            title_size,
-            YELLOW,
-        );
+    /// Play an intro/victory pose on the player with its quip as the next line
+    fn start_pose(&mut self, variant: &'static PoseVariant) {
+        let character = crate::data::characters::Character::get_by_id(self.selected_character);
+        self.dialogue_queue.push(DialogueLine {
+            speaker: character.name.to_string(),
+            text: LineText::Written {
+                dutch: variant.dutch.to_string(),
+                english: variant.english.to_string(),
+            },
+            duration: POSE_DURATION,
+        });
+        self.player_pose = Some(PosePlayback::new(variant));
 
-        let currency_text = format!("Arc Tokens: {}", self.shop_manager.currency());
-        let currency_box_width = 280.0 * scale_factor;
-        let currency_box_height = 30.0 * scale_factor;
-        draw_rectangle(
-            screen_width() * 0.5 - currency_box_width * 0.5,
-            130.0 * scale_factor,
-            currency_box_width,
-            currency_box_height,
-            Color::new(0.15, 0.15, 0.2, 0.9),
-        );
-        let currency_size = (20.0 * scale_factor).min(26.0).max(16.0);
-        let currency_dims = measure_text(&currency_text, None, currency_size as u16, 1.0);
-        draw_text(
-            &currency_text,
-            screen_width() * 0.5 - currency_dims.width * 0.5,
-            150.0 * scale_factor,
-            currency_size,
-            Color::new(1.0, 0.9, 0.3, 1.0),
-        );
+        if let Some(transform) = self
+            .player_entity
+            .and_then(|player| self.world.get_component::<Transform>(player))
+        {
+            let target = transform.position + FRAMING_LIFT;
+            self.camera.push_focus(FocusPriority::Player, target, 1.3, None);
+        }
+    }
 
-        let width = (500.0 * scale_factor).min(680.0).max(400.0);
-        let height = (70.0 * scale_factor).min(90.0).max(60.0);
-        let base_x = screen_width() * 0.5 - width * 0.5;
-        let base_y = 180.0 * scale_factor;
+    /// Queue `key`'s line from the locale files
+    fn show_dialogue(&mut self, speaker: &str, key: &str) {
+        self.dialogue_queue.push(DialogueLine {
+            speaker: speaker.to_string(),
+            text: LineText::Key(key.to_string()),
+            duration: 2.5,
+        });
+    }
 
-        for (index, option) in SHOP_OPTIONS.iter().enumerate() {
-            let y = base_y + index as f32 * (height + 15.0 * scale_factor);
-            let owned = self.shop_manager.has_upgrade(option.id);
-            let background = if owned {
-                Color::new(0.15, 0.35, 0.18, 0.9)
-            } else {
-                Color::new(0.18, 0.18, 0.26, 0.9)
-            };
+    fn process_fighter_states(&mut self, dt: f32) {
+        let fighter_states: Vec<_> = self
+            .world
+            .query::<Fighter>()
+            .map(|(entity, fighter)| (entity, fighter.state))
+            .collect();
 
-            draw_rectangle(base_x, y, width, height, background);
-            draw_rectangle_lines(
-                base_x,
-                y,
-                width,
-                height,
-                1.5 * scale_factor,
-                Color::new(1.0, 1.0, 1.0, 0.3),
-            );
+        let mut state_changes = Vec::new();
 
-            let key_label = format!("{}.", index + 1);
-            let key_size = (20.0 * scale_factor).min(26.0).max(16.0);
-            draw_text(&key_label, base_x + 12.0 * scale_factor, y + height * 0.5, key_size, WHITE);
+        for (entity, state) in &fighter_states {
+            let previous = self.previous_fighter_states.get(entity).copied();
+            if previous != Some(*state) {
+                state_changes.push((*entity, previous, *state));
+            }
+        }
 
-            let title_size = (20.0 * scale_factor).min(26.0).max(16.0);
-            draw_text(
-                option.title,
-                base_x + 45.0 * scale_factor,
-                y + height * 0.35,
-                title_size,
-                Color::new(1.0, 0.9, 0.5, 1.0),
-            );
+        let mut deactivate_hitboxes = Vec::new();
 
-            let desc_size = (14.0 * scale_factor).min(18.0).max(12.0);
-            draw_text(
-                option.description,
-                base_x + 45.0 * scale_factor,
-                y + height * 0.65,
-                desc_size,
-                Color::new(0.85, 0.85, 0.95, 1.0),
-            );
+        for (entity, _) in &fighter_states {
+            if let Some(fighter) = self.world.get_component_mut::<Fighter>(*entity) {
+                if fighter.attack_timer > 0.0 {
+                    fighter.attack_timer -= dt;
+                    if fighter.attack_timer <= 0.0 {
+                        fighter.attack_timer = 0.0;
+                        deactivate_hitboxes.push(*entity);
 
-            let cost_text = if owned {
-                "Owned".to_string()
-            } else {
-                format!("Cost: {}", option.cost)
-            };
+                        if matches!(
+                            fighter.state,
+                            FighterState::LightAttack
+                                | FighterState::HeavyAttack
+                                | FighterState::Special
+                                | FighterState::Super
+                        ) {
+                            fighter.state = FighterState::Idle;
+                        }
+                    }
+                }
 
-            let cost_color = if owned {
-                Color::new(0.6, 1.0, 0.6, 1.0)
-            } else if self.shop_manager.currency() >= option.cost {
-                Color::new(0.7, 0.9, 1.0, 1.0)
-            } else {
-                Color::new(1.0, 0.5, 0.5, 1.0)
-            };
+                if fighter.hitstun > 0.0 {
+                    fighter.hitstun = (fighter.hitstun - dt).max(0.0);
+                    if fighter.hitstun <= 0.0
+                        && matches!(fighter.state, FighterState::Hitstun | FighterState::KnockedDown)
+                    {
+                        fighter.state = FighterState::Idle;
+                    }
+                }
 
-            let cost_size = (18.0 * scale_factor).min(24.0).max(14.0);
-            let cost_dims = measure_text(&cost_text, None, cost_size as u16, 1.0);
-            draw_text(
-                &cost_text,
-                base_x + width - cost_dims.width - 15.0 * scale_factor,
-                y + height * 0.5,
-                cost_size,
-                cost_color,
-            );
+                if fighter.blockstun > 0.0 {
+                    fighter.blockstun = (fighter.blockstun - dt).max(0.0);
+                    if fighter.blockstun <= 0.0 && fighter.state == FighterState::Blockstun {
+                        fighter.state = FighterState::Idle;
+                    }
+                }
+            }
         }
 
-        let footer = "Press 1-8 to buy • B to close";
-        let footer_size = (16.0 * scale_factor).min(20.0).max(14.0);
-        let footer_dims = measure_text(footer, None, footer_size as u16, 1.0);
-        draw_text(
-            footer,
-            screen_width() * 0.5 - footer_dims.width * 0.5,
-            screen_height() - 40.0 * scale_factor,
-            footer_size,
-            WHITE,
-        );
-    }
+        for entity in deactivate_hitboxes {
+            if let Some(hitbox) = self.world.get_component_mut::<HitboxComponent>(entity) {
+                hitbox.active = false;
+                hitbox.hits_registered.clear();
+            }
+        }
 
-    fn render_shop_feedback(&self) {
-        if let Some(ref message) = self.shop_feedback_message {
-            let alpha = self.shop_feedback_timer.min(1.5) / 1.5;
-            let bg = Color::new(0.05, 0.05, 0.1, alpha * 0.85);
-            let text_color = Color::new(1.0, 0.95, 0.7, alpha);
-            let dims = measure_text(message, None, 24, 1.0);
-            let x = screen_width() * 0.5 - dims.width * 0.5 - 14.0;
-            let y = screen_height() - 100.0;
-            draw_rectangle(x, y - 28.0, dims.width + 28.0, 46.0, bg);
-            draw_rectangle_lines(
-                x,
-                y - 28.0,
-                dims.width + 28.0,
-                46.0,
-                1.5,
-                Color::new(1.0, 1.0, 1.0, alpha * 0.6),
-            );
-            draw_text(
-                message,
-                screen_width() * 0.5 - dims.width * 0.5,
-                y,
-                24.0,
-                text_color,
-            );
+        for (entity, previous, current) in state_changes {
+            self.on_fighter_state_changed(entity, previous, current);
         }
-    }
 
-    fn render_hud(&self) {
-        if let Some(player_entity) = self.player_entity {
-            if let Some(health) = self.world.get_component::<Health>(player_entity) {
-                draw_rectangle(50.0, 50.0, 300.0, 30.0, Color::new(0.2, 0.0, 0.0, 0.8));
-                draw_rectangle(
-                    50.0,
-                    50.0,
-                    300.0 * (health.current / health.maximum),
-                    30.0,
-                    Color::new(0.8, 0.0, 0.0, 1.0),
-                );
-                draw_rectangle_lines(50.0, 50.0, 300.0, 30.0, 2.0, WHITE);
+        self.previous_fighter_states.clear();
+        for (entity, state) in fighter_states {
+            self.previous_fighter_states.insert(entity, state);
+        }
+    }
 
-                let hp_text = format!("{:.0}/{:.0}", health.current, health.maximum);
-                draw_text(&hp_text, 60.0, 70.0, 20.0, WHITE);
-            }
+    /// Frame the players and their nearest threats, letterboxing dialogue and poses
+    fn update_camera(&mut self, dt: f32) {
+        let players: Vec<Vec2> = self
+            .human_entities()
+            .into_iter()
+            .filter_map(|entity| self.world.get_component::<Transform>(entity))
+            .map(|transform| transform.position + FRAMING_LIFT)
+            .collect();
 
-            if let Some(fighter) = self.world.get_component::<Fighter>(player_entity) {
-                draw_rectangle(50.0, 90.0, 200.0, 20.0, Color::new(0.0, 0.0, 0.2, 0.8));
-                draw_rectangle(
-                    50.0,
-                    90.0,
-                    200.0 * (fighter.meter / fighter.max_meter),
-                    20.0,
-                    Color::new(0.0, 0.4, 0.8, 1.0),
-                );
-                draw_rectangle_lines(50.0, 90.0, 200.0, 20.0, 2.0, WHITE);
+        let mut threats: Vec<(f32, Vec2)> = self
+            .enemy_entities
+            .iter()
+            .filter_map(|&entity| self.world.get_component::<Transform>(entity))
+            .filter_map(|transform| {
+                let pos = transform.position + FRAMING_LIFT;
+                let distance = players
+                    .iter()
+                    .map(|player| player.distance(pos))
+                    .fold(f32::INFINITY, f32::min);
+                (distance < THREAT_RANGE).then_some((distance, pos))
+            })
+            .collect();
+        threats.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-                if fighter.combo_counter > 0 {
-                    let combo_text = format!("COMBO x{}", fighter.combo_counter);
-                    draw_text(&combo_text, screen_width() - 200.0, 100.0, 40.0, YELLOW);
-                }
-            }
+        let mut points = players;
+        points.extend(threats.iter().take(FRAMED_THREATS).map(|&(_, pos)| pos));
 
-            // Stamina bar
-            if let Some(stamina) = self.world.get_component::<Stamina>(player_entity) {
-                let stamina_y = 115.0;
-                let stamina_width = 200.0;
-                let stamina_height = 18.0;
+        let screen = self.host.screen_size();
+        self.camera.set_viewport(screen);
+        self.camera.frame_targets(&points);
+        self.camera.set_bounds(Vec2::ZERO, screen);
+        self.camera.set_letterbox(self.dialogue_box.is_showing() || self.player_pose.is_some());
+        self.camera.update(dt);
+    }
 
-                // Background
-                draw_rectangle(50.0, stamina_y, stamina_width, stamina_height,
-                    Color::new(0.15, 0.15, 0.0, 0.8));
+    /// Fade out moment effects and pulse the danger vignette below 35% player health
+    fn update_post_effects(&mut self, dt: f32) {
+        let health_pct = self
+            .player_entity
+            .and_then(|entity| self.world.get_component::<Health>(entity))
+            .map(|health| health.current / health.maximum.max(1.0))
+            .unwrap_or(1.0);
 
-                // Stamina fill
-                let stamina_color = if stamina.exhausted {
-                    Color::new(0.5, 0.0, 0.0, 1.0) // Red when exhausted
-                } else if stamina.current < 30.0 {
-                    Color::new(0.8, 0.5, 0.0, 1.0) // Orange when low
-                } else {
-                    Color::new(0.8, 0.8, 0.0, 1.0) // Yellow when normal
-                };
+        self.post.set_low_health((0.35 - health_pct) / 0.35);
+        self.post.update(dt);
+    }
 
-                draw_rectangle(
-                    50.0,
-                    stamina_y,
-                    stamina_width * (stamina.current / stamina.maximum),
-                    stamina_height,
-                    stamina_color,
-                );
+    /// Queue `id` panned to where `position` is on screen
+    fn play_sound_at(&self, id: &str, position: Vec2) {
+        let screen_x = self.camera.world_to_screen(position).x;
+        crate::data::queue_sound(SFXManager::positional(id, screen_x, self.host.screen_width()));
+    }
 
-                draw_rectangle_lines(50.0, stamina_y, stamina_width, stamina_height, 2.0, WHITE);
+    /// The impact sound for what the attacker hit with
+    fn play_hit_sound(&self, attacker: EntityId, position: Vec2) {
+        use crate::audio::sfx::ImpactType;
+        let impact = match self.world.get_component::<Fighter>(attacker).map(|f| f.state) {
+            Some(FighterState::Special | FighterState::Super) => ImpactType::Special,
+            Some(FighterState::HeavyAttack | FighterState::Launcher) => ImpactType::Heavy,
+            _ => ImpactType::Light,
+        };
+        self.play_sound_at(self.sfx.get_impact_sound(impact), position);
+    }
 
-                // Stamina text
-                let stamina_text = format!("STAMINA {:.0}/{:.0}", stamina.current, stamina.maximum);
-                draw_text(&stamina_text, 60.0, stamina_y + 13.0, 14.0, WHITE);
-            }
+    /// Each walking player's feet, on whatever the map's floor is
+    fn play_footsteps(&mut self, dt: f32) {
+        self.footstep_timer -= dt;
+        if self.footstep_timer > 0.0 {
+            return;
         }
+        let walking: Vec<Vec2> = self
+            .human_entities()
+            .into_iter()
+            .filter(|&entity| {
+                self.world
+                    .get_component::<Fighter>(entity)
+                    .is_some_and(|fighter| fighter.state == FighterState::Walking)
+            })
+            .filter_map(|entity| self.world.get_component::<Transform>(entity))
+            .map(|transform| transform.position)
+            .collect();
+        // The first step lands as soon as someone sets off
+        if walking.is_empty() {
+            self.footstep_timer = 0.0;
+            return;
+        }
+        let step = SFXManager::get_footstep_sound(self.current_map.layout());
+        for position in walking {
+            self.play_sound_at(step, position);
+        }
+        self.footstep_timer = FOOTSTEP_INTERVAL;
+    }
 
-        let map_text = format!("{:?}", self.current_map);
-        draw_text(&map_text, screen_width() - 200.0, 40.0, 30.0, WHITE);
-
-        let wave_text = format!("Wave {}", self.current_wave);
-        draw_text(&wave_text, screen_width() - 200.0, 70.0, 25.0, YELLOW);
+    /// Hitstop or slow motion for the whole simulation. Online every peer has
+    /// to tick in step, so it's left out there.
+    fn request_time_scale(&self, request: TimeScaleRequest) {
+        if self.net_session.is_none() {
+            crate::data::queue_time_scale(request);
+        }
+    }
 
-        let enemies_text = format!(
-            "Enemies: {} + {}",
-            self.enemy_entities.len(),
-            self.enemies_to_spawn
-        );
-        draw_text(
-            &enemies_text,
-            50.0,
-            130.0,
-            20.0,
-            Color::new(0.8, 0.8, 0.8, 1.0),
-        );
+    /// Heavier hits shake harder; supers also freeze the action behind a zoom punch
+    fn shake_for_hit(&mut self, attacker: EntityId) {
+        match self.world.get_component::<Fighter>(attacker).map(|f| f.state) {
+            Some(FighterState::HeavyAttack | FighterState::Launcher) => {
+                self.camera.add_trauma(0.3);
+                self.request_time_scale(TimeScaleRequest::Hitstop(HEAVY_HITSTOP));
+            }
+            Some(FighterState::Special) => {
+                self.camera.add_trauma(0.4);
+                self.request_time_scale(TimeScaleRequest::Hitstop(SPECIAL_HITSTOP));
+            }
+            Some(FighterState::Super) => {
+                self.camera.add_trauma(0.6);
+                self.camera.hit_stop(0.12, 0.12);
+            }
+            _ => {}
+        }
+    }
 
-        let currency_text = format!("Arc Tokens: {}", self.shop_manager.currency());
-        draw_text(
-            &currency_text,
-            screen_width() - 200.0,
-            130.0,
-            22.0,
-            Color::new(1.0, 0.9, 0.4, 1.0),
-        );
+    fn clamp_fighter_positions(&mut self) {
+        let fighters: Vec<_> = self
+            .world
+            .query::<Fighter>()
+            .map(|(entity, _)| entity)
+            .collect();
 
-        for (i, ally_entity) in self.ally_entities.iter().enumerate() {
-            if let Some(health) = self.world.get_component::<Health>(*ally_entity) {
-                if let Some(fighter) = self.world.get_component::<Fighter>(*ally_entity) {
-                    let y = 160.0 + i as f32 * 25.0;
-                    let name = self.character_display_name(&fighter.character_type, false);
+        for entity in fighters {
+            // Entrances start off the floor (door frames, windows, stairs)
+            if self
+                .world
+                .get_component::<SpawnEntrance>(entity)
+                .is_some_and(|s| s.is_entering())
+            {
+                continue;
+            }
 
-                    draw_text(&name, 50.0, y, 18.0, Color::new(0.8, 0.8, 0.8, 1.0));
-                    draw_rectangle(120.0, y - 12.0, 100.0, 10.0, Color::new(0.2, 0.0, 0.0, 0.6));
-                    draw_rectangle(
-                        120.0,
-                        y - 12.0,
-                        100.0 * (health.current / health.maximum),
-                        10.0,
-                        Color::new(0.0, 0.8, 0.0, 1.0),
-                    );
-                }
+            if let Some(transform) = self.world.get_component_mut::<Transform>(entity) {
+                let right = self.host.screen_width() - 60.0;
+                transform.position.x = transform.position.x.clamp(60.0, right);
+                transform.position.y = transform.position.y.clamp(340.0, 660.0);
             }
         }
+    }
 
-        // Render ability UI
-        use crate::data::Character;
-        let character = Character::get_by_id(self.selected_character);
-        let ability_y = 120.0;
+    fn update_stamina_system(&mut self, dt: f32) {
+        // Collect all entities with Stamina component
+        let entities: Vec<EntityId> = self
+            .world
+            .query::<Stamina>()
+            .map(|(entity, _)| entity)
+            .collect();
 
-        // Ability icon/button
-        let ability_color = if self.ability_state.can_activate() {
-            Color::new(0.0, 0.8, 0.0, 0.8)
-        } else if self.ability_state.active {
-            Color::new(1.0, 0.8, 0.0, 0.8)
-        } else {
-            Color::new(0.3, 0.3, 0.3, 0.8)
-        };
+        for entity in entities {
+            if let (Some(fighter), Some(stamina)) = (
+                self.world.get_component::<Fighter>(entity),
+                self.world.get_component::<Stamina>(entity)
+            ) {
+                let is_attacking = matches!(
+                    fighter.state,
+                    FighterState::LightAttack | FighterState::HeavyAttack | FighterState::Special
+                );
 
-        draw_rectangle(50.0, ability_y, 200.0, 40.0, Color::new(0.0, 0.0, 0.0, 0.6));
+                // Only regenerate if not attacking or in recovery; hardcore
+                // also holds it back while guarding or dashing
+                let dashing = self.world.get_component::<Dash>(entity).is_some();
+                let acting = self.hardcore_stamina && (fighter.is_blocking || dashing);
+                let should_regen = !is_attacking && fighter.attack_recovery <= 0.0 && !acting;
 
-        if self.ability_state.active {
-            let remaining_pct = self.ability_state.active_time / character.duration;
-            draw_rectangle(50.0, ability_y, 200.0 * remaining_pct, 40.0, ability_color);
-        } else if self.ability_state.cooldown_time > 0.0 {
-            let cooldown_pct = 1.0 - (self.ability_state.cooldown_time / character.cooldown);
-            draw_rectangle(50.0, ability_y, 200.0 * cooldown_pct, 40.0, ability_color);
-        } else {
-            draw_rectangle(50.0, ability_y, 200.0, 40.0, ability_color);
+                // Update regen delay timer
+                let new_timer = if should_regen {
+                    (stamina.regen_delay_timer - dt).max(0.0)
+                } else {
+                    stamina.regen_delay
+                };
+
+                // Regenerate stamina after delay
+                let new_current = if should_regen && new_timer <= 0.0 {
+                    (stamina.current + stamina.regen_rate * dt).min(stamina.maximum)
+                } else {
+                    stamina.current
+                };
+
+                // Update exhaustion state. Hardcore keeps a fighter exhausted
+                // until enough of the bar is back.
+                let recovering = self.hardcore_stamina
+                    && stamina.exhausted
+                    && new_current < stamina.maximum * EXHAUSTION_RECOVERY;
+                let new_exhausted = new_current <= 0.0 || recovering;
+
+                // Update the component
+                if let Some(stamina_mut) = self.world.get_component_mut::<Stamina>(entity) {
+                    stamina_mut.current = new_current;
+                    stamina_mut.regen_delay_timer = new_timer;
+                    stamina_mut.exhausted = new_exhausted;
+                }
+
+                // Apply exhaustion effects to fighter
+                if new_exhausted {
+                    if let Some(fighter_mut) = self.world.get_component_mut::<Fighter>(entity) {
+                        fighter_mut.invulnerable = false; // Can't dodge while exhausted
+                    }
+                }
+            }
         }
+    }
 
-        draw_rectangle_lines(50.0, ability_y, 200.0, 40.0, 2.0, WHITE);
+    fn consume_stamina(&mut self, entity: EntityId, amount: f32) -> bool {
+        if let Some(stamina) = self.world.get_component::<Stamina>(entity) {
+            if stamina.current >= amount && !(self.hardcore_stamina && stamina.exhausted) {
+                if let Some(stamina_mut) = self.world.get_component_mut::<Stamina>(entity) {
+                    stamina_mut.current -= amount;
+                    stamina_mut.regen_delay_timer = stamina_mut.regen_delay;
+                    return true;
+                }
+            }
+        }
+        false
+    }
 
-        let ability_text = if self.ability_state.active {
-            format!(
-                "{} [{:.1}s]",
-                character.ability_name, self.ability_state.active_time
-            )
-        } else if self.ability_state.cooldown_time > 0.0 {
-            format!(
-                "{} [{:.1}s]",
-                character.ability_name, self.ability_state.cooldown_time
-            )
+    fn heavy_stamina(&self) -> f32 {
+        if self.hardcore_stamina {
+            HARDCORE_HEAVY_STAMINA
         } else {
-            format!("{} [E]", character.ability_name)
-        };
-
-        draw_text(&ability_text, 60.0, ability_y + 25.0, 18.0, WHITE);
+            HEAVY_STAMINA
+        }
+    }
 
-        // Voice lines are now shown through the dialogue system which freezes the game
+    /// Hardcore drops the guard of anyone who's run out of stamina
+    fn guard_broken(&self, entity: EntityId) -> bool {
+        self.hardcore_stamina
+            && self.world.get_component::<Stamina>(entity).is_some_and(|s| s.exhausted)
     }
 
-    fn character_display_name(&self, character: &CharacterType, is_player: bool) -> String {
-        if is_player {
-            return "You".to_string();
+    /// Hardcore dash along `direction`, or the way the fighter faces when
+    /// there's no input. Needs the stamina for it and a free fighter.
+    fn try_dash(&mut self, entity: EntityId, direction: Vec2) {
+        if !self.hardcore_stamina || self.world.get_component::<Dash>(entity).is_some() {
+            return;
         }
+        let Some(fighter) = self.world.get_component::<Fighter>(entity) else {
+            return;
+        };
+        if fighter.hitstun > 0.0
+            || fighter.blockstun > 0.0
+            || !matches!(fighter.state, FighterState::Idle | FighterState::Walking)
+        {
+            return;
+        }
+        let direction = if direction.length() > 0.01 {
+            direction.normalize()
+        } else {
+            Vec2::new(fighter.facing, 0.0)
+        };
+        if self.consume_stamina(entity, DASH_STAMINA) {
+            self.world.add_component(entity, Dash {
+                direction,
+                timer: DASH_TIME,
+            });
+        }
+    }
 
-        match character {
-            CharacterType::Bas => "Bas".to_string(),
-            CharacterType::Berkay => "Berkay".to_string(),
-            CharacterType::Hadi => "Hadi".to_string(),
-            CharacterType::Luca => "Luca".to_string(),
-            CharacterType::Nitin => "Nitin".to_string(),
-            CharacterType::Gefferinho => "Gefferinho".to_string(),
-            CharacterType::YigitBaba => "Yigit Baba".to_string(),
-            CharacterType::Wolters => "Wolters".to_string(),
-            CharacterType::PrefectA => "Prefect A".to_string(),
-            CharacterType::PrefectB => "Prefect B".to_string(),
-            CharacterType::Chef => "Chef".to_string(),
-            CharacterType::Librarian => "Librarian".to_string(),
-            CharacterType::Coach => "Coach".to_string(),
-            CharacterType::Bastiaan => "BOSS BASTIAAN".to_string(),
-            CharacterType::KeizerBomTaha => "Keizer Bom Taha".to_string(),
-            CharacterType::KeizerBomTahaBoss => "BOSS KEIZER BOM TAHA".to_string(),
-            CharacterType::Mees => "BOSS MEES - PITA SIRRACHA".to_string(),
+    fn update_dashes(&mut self, dt: f32) {
+        let dashes: Vec<(EntityId, Vec2, f32)> = self
+            .world
+            .query::<Dash>()
+            .map(|(entity, dash)| (entity, dash.direction, dash.timer))
+            .collect();
+        let bounds = arena_bounds(self.host.screen_width());
+        for (entity, direction, timer) in dashes {
+            if let Some(transform) = self.world.get_component_mut::<Transform>(entity) {
+                let step = direction * DASH_SPEED * dt.min(timer);
+                transform.position.x = (transform.position.x + step.x)
+                    .clamp(bounds.left(), bounds.right());
+                transform.position.y = (transform.position.y + step.y)
+                    .clamp(bounds.top(), bounds.bottom());
+            }
+            if timer <= dt {
+                self.world.remove_component::<Dash>(entity);
+            } else if let Some(dash) = self.world.get_component_mut::<Dash>(entity) {
+                dash.timer -= dt;
+            }
         }
     }
 
-    fn render_nametag(&self, pos: Vec2, name: &str, color: Color) {
-        if name.is_empty() {
+    fn check_game_over(&mut self) {
+        if self.game_over {
             return;
         }
 
-        let font_size = 20.0;
-        let metrics = measure_text(name, None, font_size as u16, 1.0);
-        let width = metrics.width + 24.0;
-        let height = metrics.height + 14.0;
-        let x = pos.x - width * 0.5;
-        let y = pos.y - 115.0;
+        // Co-op runs end once nobody is left standing or able to come back
+        let dead = match &self.coop_manager {
+            Some(manager) if manager.get_player(PlayerSlot::Player1).is_some() => {
+                manager.get_active_players().is_empty()
+            }
+            _ => self.player_entity.is_some_and(|player| {
+                self.world.get_component::<Health>(player).is_none_or(|h| h.current <= 0.0)
+            }),
+        };
 
-        // Shadow
-        draw_rectangle(
-            x + 2.0,
-            y + 2.0,
-            width,
-            height,
-            Color::new(0.0, 0.0, 0.0, 0.6),
-        );
+        if dead && self.use_revive_token() {
+            return;
+        }
+        if dead {
+            self.credit_traded_wave();
+            self.trigger_game_over();
+        }
+    }
 
-        // Background with gradient effect
-        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.85));
+    /// A solo P1 who carries a Revive Token gets back up instead of losing the run
+    fn use_revive_token(&mut self) -> bool {
+        let Some(player) = self.player_entity else {
+            return false;
+        };
+        if self.coop_manager.is_some() || !self.shop_manager.use_consumable(Consumable::ReviveToken)
+        {
+            return false;
+        }
 
-        // Top highlight
-        draw_rectangle(
-            x,
-            y,
-            width,
-            height * 0.3,
-            Color::new(color.r * 0.5, color.g * 0.5, color.b * 0.5, 0.6),
-        );
+        Self::stand_up_player(&mut self.world, player, REVIVE_TOKEN_HEALTH);
+        self.post.trigger(PostMoment::SuperActivation);
+        self.set_shop_feedback("Revive Token used!");
+        true
+    }
 
-        // Colored border with glow
-        draw_rectangle_lines(x, y, width, height, 2.5, color);
-        draw_rectangle_lines(
-            x - 1.0,
-            y - 1.0,
-            width + 2.0,
-            height + 2.0,
-            1.0,
-            Color::new(color.r, color.g, color.b, 0.4),
-        );
+    /// Throw a Smoke Bomb: every enemy close to P1 is stunned
+    fn use_smoke_bomb(&mut self) {
+        let Some(position) = self
+            .player_entity
+            .and_then(|player| self.world.get_component::<Transform>(player))
+            .map(|transform| transform.position)
+        else {
+            return;
+        };
+        if !self.shop_manager.use_consumable(Consumable::SmokeBomb) {
+            self.set_shop_feedback("No Smoke Bombs left; buy them in the shop");
+            return;
+        }
 
-        // Text with subtle shadow
-        draw_text(
-            name,
-            pos.x - metrics.width * 0.5 + 1.0,
-            y + height - 4.0,
-            font_size,
-            Color::new(0.0, 0.0, 0.0, 0.7),
-        );
+        for &enemy in &self.enemy_entities {
+            let close = self
+                .world
+                .get_component::<Transform>(enemy)
+                .is_some_and(|transform| transform.position.distance(position) <= SMOKE_RADIUS);
+            if let Some(fighter) = self.world.get_component_mut::<Fighter>(enemy).filter(|_| close)
+            {
+                fighter.hitstun = fighter.hitstun.max(SMOKE_STUN);
+                fighter.state = FighterState::Hitstun;
+            }
+        }
 
-        // Main text
-        draw_text(
-            name,
-            pos.x - metrics.width * 0.5,
-            y + height - 5.0,
-            font_size,
-            color,
+        let colors = ColorRamp::new(
+            Color::new(0.8, 0.8, 0.85, 0.9),
+            Color::new(0.4, 0.4, 0.45, 0.0),
         );
+        self.world
+            .commands()
+            .spawn()
+            .with(Transform {
+                position,
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            })
+            .with(
+                Emitter::burst(40, ParticleType::Smoke, colors)
+                    .speed(60.0, SMOKE_RADIUS)
+                    .lifetime(1.5, 1.0)
+                    .size(24.0, 6.0)
+                    .acceleration(Vec2::new(0.0, -30.0)),
+            );
+        let left = self.shop_manager.carried(Consumable::SmokeBomb);
+        self.set_shop_feedback(format!("Smoke Bomb! {} left", left));
     }
 
-    fn render_classroom(&self) {
-        let time = get_time() as f32;
-        let sw = screen_width();
-        let sh = screen_height();
+    /// Falling on the same frame as the wave's last enemy is a trade. Both
+    /// checks run on health after every hit of the frame has landed, so it
+    /// doesn't matter which blow was dealt first: the run still ends, but
+    /// the wave counts as cleared.
+    fn credit_traded_wave(&mut self) {
+        let last_enemy_down = self.enemies_to_spawn == 0
+            && !self.enemy_entities.is_empty()
+            && self.enemy_entities.iter().all(|&enemy| {
+                self.world.get_component::<Health>(enemy).is_none_or(|h| h.current <= 0.0)
+            });
+        if last_enemy_down {
+            self.waves_completed += 1;
+        }
+    }
 
-        // Enhanced floor with tiles - resolution friendly
-        let floor_y = sh * 0.55;
-        let tile_cols = (sw / 80.0).ceil() as i32;
-        let tile_rows = ((sh - floor_y) / 40.0).ceil() as i32;
+    fn trigger_game_over(&mut self) {
+        self.game_over = true;
+        self.shop_open = false;
+        self.dialogue_queue.clear();
+        self.dialogue_box.clear();
+        self.subtitles.clear();
+        self.announcer.clear();
+        self.record_run(false);
+        self.finish_winter_arc(false);
+    }
 
-        for i in 0..tile_cols {
-            for j in 0..tile_rows {
-                let x = i as f32 * 80.0;
-                let y = floor_y + j as f32 * 40.0;
-                let tile_shade = if (i + j) % 2 == 0 { 0.45 } else { 0.42 };
-                draw_rectangle(
-                    x,
-                    y,
-                    80.0,
-                    40.0,
-                    Color::new(tile_shade, tile_shade - 0.05, tile_shade - 0.1, 1.0),
-                );
-                draw_rectangle_lines(x, y, 80.0, 40.0, 1.0, Color::new(0.3, 0.25, 0.2, 0.3));
+    /// What the results screen shows of this run
+    fn run_report(&self, cleared: bool) -> RunReport {
+        let retry = match &self.challenge_run {
+            _ if self.net_session.is_some() || self.coop_manager.is_some() => None,
+            _ if self.practice.is_some() || self.trial.is_some() => None,
+            _ if self.winter_arc.is_some() => Some(StateType::WinterArc),
+            Some(run) if run.kind == ChallengeKind::Daily => Some(StateType::DailyChallenge),
+            Some(_) => Some(StateType::WeeklyChallenge),
+            None => Some(StateType::Gameplay),
+        };
+        RunReport {
+            cleared,
+            waves_cleared: self.waves_completed as u32,
+            max_combo: self.combo_system.max_combo_this_session,
+            best_rank: self.run_best_rank,
+            flawless: !self.run_damage_taken,
+            tokens_earned: self.run_tokens,
+            mastery_xp: self.run_mastery_xp,
+            account_xp: self.run_account_xp,
+            unlocks: self.run_unlocks.clone(),
+            retry,
+            trial: self.trial.map(|trial| trial.character),
+        }
+    }
+
+    /// Add a hit on or by P1 to the run's damage totals
+    fn tally_hit(&mut self, attacker: EntityId, defender: EntityId, damage: f32) {
+        if Some(defender) == self.player_entity {
+            let source = self
+                .world
+                .get_component::<Fighter>(attacker)
+                .map(|fighter| format!("{:?}", fighter.character_type))
+                .or_else(|| {
+                    let prop = self.world.get_component::<StageProp>(attacker)?;
+                    Some(prop.kind.name().to_string())
+                })
+                .unwrap_or_else(|| "Unknown".to_string());
+            self.run_tally.add_taken(&source, damage);
+        } else if Some(attacker) == self.player_entity {
+            let source = match self.world.get_component::<Fighter>(attacker).map(|f| f.state) {
+                Some(FighterState::HeavyAttack) => "HeavyAttack",
+                Some(FighterState::Special) => "Special",
+                Some(FighterState::Super) => "Super",
+                _ => "LightAttack",
+            };
+            self.run_tally.add_dealt(source, damage);
+            if self.practice.is_none() {
+                self.account.progression.total_damage_dealt += damage as f64;
             }
         }
+    }
 
-        // Ceiling beam with depth - resolution friendly
-        let ceiling_y = sh * 0.09;
-        draw_rectangle(
-            0.0,
-            ceiling_y,
-            sw,
-            sh * 0.03,
-            Color::new(0.25, 0.2, 0.15, 1.0),
-        );
-        draw_rectangle(
-            0.0,
-            ceiling_y + sh * 0.005,
-            sw,
-            sh * 0.02,
-            Color::new(0.35, 0.28, 0.22, 1.0),
-        );
-        draw_rectangle(
-            0.0,
-            ceiling_y + sh * 0.025,
-            sw,
-            sh * 0.002,
-            Color::new(0.15, 0.12, 0.1, 1.0),
-        );
+    /// Lockstep for an online game: send this tick's local input, then play
+    /// both seats' inputs for the tick once the partner's has arrived. Returns
+    /// false while the tick has to wait on the partner.
+    fn step_online(&mut self, dt: f32) -> bool {
+        let Some(session) = self.net_session.as_mut() else {
+            return true;
+        };
 
-        // Ceiling lights
-        for i in 0..((sw / 300.0) as i32) {
-            let light_x = 150.0 + i as f32 * 300.0;
-            draw_rectangle(
-                light_x - 40.0,
-                ceiling_y + sh * 0.027,
-                80.0,
-                15.0,
-                Color::new(0.9, 0.9, 0.8, 0.8),
-            );
-            // Light glow
-            draw_circle(
-                light_x,
-                ceiling_y + sh * 0.035,
-                60.0,
-                Color::new(1.0, 1.0, 0.9, 0.15),
-            );
+        session.poll();
+        if let Some(reason) = session.closed().map(str::to_string) {
+            let message = format!("Online game ended: {}", reason);
+            crash::log(message.to_string());
+            self.net_session = None;
+            self.net_waiting = false;
+            self.set_shop_feedback(message);
+            self.trigger_game_over();
+            return false;
         }
 
-        // Enhanced blackboard with frame - resolution friendly
-        let board_x = sw * 0.03;
-        let board_y = sh * 0.15;
-        let board_w = sw * 0.2;
-        let board_h = sh * 0.28;
+        session.submit_local(self.net_input);
+        self.net_input.pressed = 0;
+        let desync = session.take_desync();
+        let inputs = session.next_inputs();
+        let frame = session.frame();
 
-        draw_rectangle(
-            board_x + 5.0,
-            board_y + 5.0,
-            board_w,
-            board_h,
-            Color::new(0.15, 0.12, 0.1, 1.0),
-        ); // Shadow
-        draw_rectangle(
-            board_x,
-            board_y,
-            board_w,
-            board_h,
-            Color::new(0.5, 0.42, 0.35, 1.0),
-        ); // Frame
-        draw_rectangle(
-            board_x + 10.0,
-            board_y + 10.0,
-            board_w - 20.0,
-            board_h - 20.0,
-            Color::new(0.08, 0.25, 0.08, 1.0),
-        ); // Board
+        if let Some(frame) = desync {
+            let message = format!("Out of sync with your partner since tick {}", frame);
+            crash::log(message.to_string());
+            self.set_shop_feedback(message);
+        }
 
-        // Frame details
-        draw_rectangle(
-            board_x + 5.0,
-            board_y + 5.0,
-            board_w - 10.0,
-            5.0,
-            Color::new(0.6, 0.52, 0.45, 1.0),
-        );
-        draw_rectangle(
-            board_x + 5.0,
-            board_y + 5.0,
-            5.0,
-            board_h - 10.0,
-            Color::new(0.6, 0.52, 0.45, 1.0),
-        );
+        let Some(inputs) = inputs else {
+            self.net_waiting = true;
+            return false;
+        };
+        self.net_waiting = false;
 
-        // Chalk text with glow - dynamic font size
-        let text = "BAS VEGEN";
-        let font_size = (board_w * 0.15).max(20.0);
-        let text_x = board_x + board_w * 0.15;
-        let text_y = board_y + board_h * 0.45;
-        draw_text(
-            text,
-            text_x + 2.0,
-            text_y + 2.0,
-            font_size,
-            Color::new(0.7, 0.7, 0.7, 0.3),
-        );
-        draw_text(text, text_x, text_y, font_size, WHITE);
+        for (seat, input) in inputs.into_iter().enumerate() {
+            self.coop_input
+                .set_network_input(seat as u8, input.to_player_input());
+        }
+        self.handle_team_super_input();
+        for (slot, device, entity) in self.coop_guests() {
+            self.handle_guest_input(slot, device, entity, dt);
+        }
+        self.leash_players();
 
-        let small_font = (board_w * 0.12).max(16.0);
-        let text2_y = board_y + board_h * 0.7;
-        draw_text(
-            "A + B = C",
-            text_x + 2.0,
-            text2_y + 2.0,
-            small_font,
-            Color::new(0.7, 0.7, 0.7, 0.3),
-        );
-        draw_text("A + B = C", text_x, text2_y, small_font, WHITE);
+        if frame % HASH_INTERVAL == 0 {
+            let hash = self.state_hash();
+            if let Some(session) = self.net_session.as_mut() {
+                session.report_hash(frame, hash);
+            }
+        }
+        true
+    }
 
-        // Chalk ledge
-        draw_rectangle(
-            board_x + 10.0,
-            board_y + board_h - 2.0,
-            board_w - 20.0,
-            8.0,
-            Color::new(0.4, 0.34, 0.28, 1.0),
+    /// What both sides of an online game have to agree on: where every
+    /// fighter stands, their health, and the wave
+    fn state_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for &entity in self.human_entities().iter().chain(&self.enemy_entities) {
+            if let Some(transform) = self.world.get_component::<Transform>(entity) {
+                transform.position.x.to_bits().hash(&mut hasher);
+                transform.position.y.to_bits().hash(&mut hasher);
+            }
+            if let Some(health) = self.world.get_component::<Health>(entity) {
+                health.current.to_bits().hash(&mut hasher);
+            }
+        }
+        self.current_wave.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Log the finished run for the balance reports. Co-op runs scale waves
+    /// and share damage and practice skips ahead, so only solo runs are kept.
+    fn record_run(&mut self, cleared: bool) {
+        self.record_run_achievements(cleared);
+        if self.coop_manager.is_some() || self.practice.is_some() {
+            return;
+        }
+
+        let record = self.run_tally.finish(
+            self.selected_character,
+            self.difficulty,
+            self.current_map.name(),
+            cleared,
+            &self.shop_manager.owned_upgrades(),
         );
+        if let Err(e) = balance::record_run(&record) {
+            crash::log(format!("Failed to record run: {}", e));
+        }
+        self.finished_run = Some(record);
+    }
 
-        // Enhanced desks with shadows and depth - resolution friendly
-        let desk_count = ((sw - board_w * 1.5) / (sw * 0.12)).floor().min(6.0).max(3.0) as i32;
-        let desk_start_x = board_x + board_w + sw * 0.05;
-        let desk_spacing = (sw - desk_start_x - sw * 0.2) / desk_count as f32;
-        let desk_w = (desk_spacing * 0.75).min(sw * 0.1);
-        let desk_h = sh * 0.11;
-        let desk_y = floor_y - desk_h - sh * 0.05;
+    fn on_fighter_state_changed(
+        &mut self,
+        entity: EntityId,
+        _previous: Option<FighterState>,
+        current: FighterState,
+    ) {
+        if current == FighterState::Super && Some(entity) == self.player_entity {
+            self.post.trigger(PostMoment::SuperActivation);
+            let (scale, duration) = SUPER_SLOW_MOTION;
+            self.request_time_scale(TimeScaleRequest::SlowMotion { scale, duration });
+        }
+        if current == FighterState::Super {
+            if let Some(transform) = self.world.get_component::<Transform>(entity) {
+                let target = transform.position + FRAMING_LIFT;
+                self.camera
+                    .push_focus(FocusPriority::SuperUser, target, 1.2, Some(SUPER_FOCUS));
+            }
+        }
 
-        for i in 0..desk_count {
-            let desk_x = desk_start_x + (i as f32 * desk_spacing);
+        if matches!(
+            current,
+            FighterState::LightAttack
+                | FighterState::HeavyAttack
+                | FighterState::Special
+                | FighterState::Super
+        ) {
+            self.activate_attack_hitbox(entity, current);
 
-            // Desk shadow
-            draw_rectangle(
-                desk_x + 3.0,
-                desk_y + 3.0,
-                desk_w,
-                desk_h,
-                Color::new(0.0, 0.0, 0.0, 0.3),
-            );
+            if let Some(origin) = self
+                .world
+                .get_component::<Transform>(entity)
+                .map(|t| t.position)
+            {
+                self.spawn_attack_particles(origin, current);
+            }
+        }
+    }
 
-            // Desk top with highlight
-            draw_rectangle(
-                desk_x,
-                desk_y,
-                desk_w,
-                desk_h,
-                Color::new(0.55, 0.45, 0.35, 1.0),
-            );
-            draw_rectangle(
-                desk_x,
-                desk_y,
-                desk_w,
-                desk_h * 0.2,
-                Color::new(0.65, 0.55, 0.45, 1.0),
-            ); // Highlight
-            draw_rectangle_lines(
-                desk_x,
-                desk_y,
-                desk_w,
-                desk_h,
-                2.0,
-                Color::new(0.4, 0.32, 0.25, 1.0),
-            );
+    fn activate_attack_hitbox(&mut self, entity: EntityId, state: FighterState) {
+        let (mut duration, mut hitbox) = state.attack().unwrap_or((0.0, Hitbox::new_light()));
 
-            // Chair with backrest
-            let chair_w = desk_w * 0.35;
-            let chair_h = desk_h * 0.85;
-            let chair_x = desk_x + (desk_w - chair_w) * 0.5;
-            let chair_y = desk_y - chair_h - sh * 0.01;
+        // Characters swing at their own pace and throw their own special
+        if let Some(moveset) = self.moveset_for(entity) {
+            match (state, moveset.special()) {
+                (FighterState::LightAttack | FighterState::HeavyAttack, _) => {
+                    duration /= moveset.stats.attack_speed;
+                }
+                (FighterState::Special, Some(special)) => {
+                    duration = special.duration();
+                    hitbox = special.hitbox(STAGE_SCALE);
+                }
+                _ => {}
+            }
+        }
 
-            draw_rectangle(
-                chair_x,
-                chair_y + 3.0,
-                chair_w,
-                chair_h,
-                Color::new(0.25, 0.25, 0.3, 1.0),
-            );
-            draw_rectangle(
-                chair_x + 2.0,
-                chair_y,
-                chair_w - 4.0,
-                chair_h - 2.0,
-                Color::new(0.35, 0.35, 0.4, 1.0),
-            );
-            // Backrest
-            draw_rectangle(
-                chair_x - chair_w * 0.12,
-                chair_y - desk_h * 0.25,
-                chair_w * 1.25,
-                desk_h * 0.3,
-                Color::new(0.35, 0.35, 0.4, 1.0),
+        let mut new_facing = None;
+        if let Some(velocity) = self.world.get_component::<Velocity>(entity) {
+            if velocity.linear.x > 1.0 {
+                new_facing = Some(1.0);
+            } else if velocity.linear.x < -1.0 {
+                new_facing = Some(-1.0);
+            }
+        }
+
+        // A queued overhead or low wins; otherwise the move keeps its own height
+        let mut height = hitbox.height;
+        if let Some(fighter) = self.world.get_component_mut::<Fighter>(entity) {
+            fighter.attack_timer = duration;
+            if let Some(facing) = new_facing {
+                fighter.facing = facing;
+            }
+            let queued = std::mem::replace(&mut fighter.next_attack_height, AttackHeight::Mid);
+            if queued != AttackHeight::Mid {
+                height = queued;
+            }
+        }
+        if let Some(player) = self.world.get_component_mut::<AnimationPlayer>(entity) {
+            player.play_timed(state, duration);
+        }
+
+        if let Some(hitbox_comp) = self.world.get_component_mut::<HitboxComponent>(entity) {
+            // Armed only: the attack clip's HitboxOn event opens the active frames
+            hitbox_comp.hitbox = hitbox.with_height(height);
+            hitbox_comp.active = false;
+            hitbox_comp.hits_registered.clear();
+        }
+    }
+
+    /// The moveset behind the player or their partner's assist
+    fn moveset_for(&self, entity: EntityId) -> Option<&CharacterMoveset> {
+        if Some(entity) == self.player_entity {
+            return Some(&self.player_moveset);
+        }
+        self.tag_team
+            .as_ref()
+            .filter(|_| Some(entity) == self.assist_entity)
+            .map(|tag| &tag.partner_moveset)
+    }
+
+    /// Signature color of the selected character's ability aura
+    fn ability_aura_color(&self) -> Color {
+        Self::character_color(self.selected_character)
+    }
+
+    fn character_color(character: CharacterId) -> Color {
+        match character {
+            CharacterId::Berkay => Color::new(1.0, 0.5, 0.0, 1.0),
+            CharacterId::Luca => Color::new(0.3, 0.6, 1.0, 1.0),
+            CharacterId::Gefferinho => Color::new(1.0, 0.2, 0.2, 1.0),
+            CharacterId::Bas => Color::new(0.0, 1.0, 0.5, 1.0),
+            CharacterId::Hadi => Color::new(1.0, 0.8, 0.0, 1.0),
+            CharacterId::Nitin => Color::new(1.0, 0.3, 0.0, 1.0),
+            CharacterId::PalaBaba => Color::new(0.8, 0.0, 0.2, 1.0),
+            CharacterId::Fufinho => Color::new(0.9, 0.0, 0.9, 1.0), // Purple
+            CharacterId::EfeAbi => Color::new(0.7, 0.3, 0.1, 1.0), // Brown
+            CharacterId::Jad => Color::new(1.0, 0.0, 0.0, 1.0), // Red
+            CharacterId::Umut => Color::new(0.5, 0.0, 1.0, 1.0), // Purple for Terraria
+            CharacterId::KeizerBomTaha => Color::new(0.2, 0.6, 0.3, 1.0), // Green
+            CharacterId::GoonLordTobese => Color::new(1.0, 1.0, 1.0, 1.0), // White (milk)
+        }
+    }
+
+    /// Keep a sparkle emitter on the player while their ability is active
+    fn update_ability_aura(&mut self) {
+        let Some(player) = self.player_entity else {
+            return;
+        };
+
+        let has_aura = self.world.get_component::<Emitter>(player).is_some();
+        if self.ability_state.active && !has_aura {
+            let base = self.ability_aura_color();
+            let colors = ColorRamp::fade(Color::new(base.r, base.g, base.b, 0.8));
+            self.world.add_component(
+                player,
+                Emitter::continuous(30.0, ParticleType::Energy, colors)
+                    .shape(EmitterShape::Ring(65.0))
+                    .speed(10.0, 35.0)
+                    .lifetime(0.4, 0.8)
+                    .size(6.0, 1.0)
+                    .acceleration(Vec2::new(0.0, -40.0))
+                    .attached(),
             );
+        } else if !self.ability_state.active && has_aura {
+            self.world.remove_component::<Emitter>(player);
         }
+    }
 
-        // Enhanced teacher's desk - resolution friendly
-        let teacher_desk_w = sw * 0.08;
-        let teacher_desk_h = sh * 0.21;
-        let teacher_x = sw - teacher_desk_w - sw * 0.02;
-        let teacher_y = sh * 0.26;
+    /// Tick the bench's cooldowns and send a finished assist back out
+    fn update_tag_team(&mut self, dt: f32) {
+        let combo_live = self.combo_system.is_active();
+        if let Some(tag) = self.tag_team.as_mut() {
+            tag.update(dt, combo_live);
+        }
 
-        draw_rectangle(
-            teacher_x + 5.0,
-            teacher_y + 5.0,
-            teacher_desk_w,
-            teacher_desk_h,
-            Color::new(0.0, 0.0, 0.0, 0.3),
+        let Some(entity) = self.assist_entity else {
+            return;
+        };
+        let done = match self.world.get_component_mut::<Assist>(entity) {
+            Some(assist) => {
+                assist.lifetime -= dt;
+                assist.lifetime <= 0.0
+            }
+            None => true,
+        };
+        if done {
+            self.world.commands().destroy(entity);
+            self.assist_entity = None;
+        }
+    }
+
+    /// The partner jumps in behind the player, throws their special and
+    /// leaves. They can't be hit while they're in.
+    fn call_assist(&mut self) {
+        if self.assist_entity.is_some() {
+            return;
+        }
+        let Some(player) = self.player_entity else {
+            return;
+        };
+        let Some((position, facing)) = self
+            .world
+            .get_component::<Transform>(player)
+            .zip(self.world.get_component::<Fighter>(player))
+            .map(|(transform, fighter)| (transform.position, fighter.facing))
+        else {
+            return;
+        };
+        let combo_live = self.combo_system.is_active();
+        let Some(tag) = self.tag_team.as_mut() else {
+            return;
+        };
+        if !tag.call_assist(combo_live) {
+            return;
+        }
+        let partner = tag.partner;
+        let attack = tag.partner_moveset.special().map_or(0.55, |special| special.duration());
+
+        let entity = self.world.create_entity();
+        self.world.add_component(
+            entity,
+            Transform {
+                position: position - Vec2::new(facing * Assist::OFFSET, 0.0),
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            },
         );
-        draw_rectangle(
-            teacher_x,
-            teacher_y,
-            teacher_desk_w,
-            teacher_desk_h,
-            Color::new(0.65, 0.55, 0.45, 1.0),
-        );
-        draw_rectangle(
-            teacher_x,
-            teacher_y,
-            teacher_desk_w,
-            teacher_desk_h * 0.13,
-            Color::new(0.75, 0.65, 0.55, 1.0),
-        );
-        draw_rectangle_lines(
-            teacher_x,
-            teacher_y,
-            teacher_desk_w,
-            teacher_desk_h,
-            3.0,
-            Color::new(0.5, 0.4, 0.32, 1.0),
-        );
-
-        // Computer/papers on desk
-        let item_size = teacher_desk_w * 0.4;
-        draw_rectangle(
-            teacher_x + teacher_desk_w * 0.1,
-            teacher_y + teacher_desk_h * 0.08,
-            item_size,
-            item_size * 0.75,
-            Color::new(0.85, 0.85, 0.85, 1.0),
-        );
-        draw_rectangle(
-            teacher_x + teacher_desk_w * 0.12,
-            teacher_y + teacher_desk_h * 0.1,
-            item_size * 0.9,
-            item_size * 0.68,
-            Color::new(0.2, 0.3, 0.4, 1.0),
-        );
-        draw_rectangle(
-            teacher_x + teacher_desk_w * 0.55,
-            teacher_y + teacher_desk_h * 0.13,
-            item_size * 0.8,
-            item_size * 0.55,
-            Color::new(0.95, 0.95, 0.85, 1.0),
+        self.world.add_component(
+            entity,
+            HitboxComponent {
+                hitbox: Hitbox::new_light(),
+                active: false,
+                hits_registered: Vec::new(),
+            },
         );
-
-        // Enhanced plant - resolution friendly
-        let plant_w = sw * 0.045;
-        let plant_h = sh * 0.14;
-        let plant_x = sw - plant_w * 3.2;
-        let plant_y = floor_y - plant_h;
-
-        draw_rectangle(
-            plant_x + 5.0,
-            plant_y + 3.0,
-            plant_w,
-            plant_h,
-            Color::new(0.0, 0.0, 0.0, 0.3),
+        self.world.add_component(entity, AnimationPlayer::new());
+        self.world.add_component(
+            entity,
+            Fighter {
+                character_type: partner.to_character_type(),
+                state: FighterState::Special,
+                combo_counter: 0,
+                meter: 0.0,
+                max_meter: 100.0,
+                hitstun: 0.0,
+                blockstun: 0.0,
+                invulnerable: true,
+                facing,
+                attack_timer: 0.0,
+                team: Team::Player,
+                consecutive_hits_taken: 0,
+                hit_decay_timer: 0.0,
+                combo_chain: 0,
+                combo_window_timer: 0.0,
+                can_cancel: false,
+                attack_startup: 0.0,
+                attack_recovery: 0.0,
+                is_blocking: false,
+                parry_window: 0.0,
+                block_stance: BlockStance::Standing,
+                next_attack_height: AttackHeight::Mid,
+            },
         );
-        draw_rectangle(
-            plant_x,
-            plant_y,
-            plant_w,
-            plant_h,
-            Color::new(0.45, 0.3, 0.2, 1.0),
+        self.world.add_component(
+            entity,
+            Assist {
+                character: partner,
+                lifetime: attack + Assist::LINGER,
+            },
         );
+        self.assist_entity = Some(entity);
+    }
 
-        let plant_center_x = plant_x + plant_w * 0.5;
-        let plant_top_y = plant_y - plant_h * 0.15;
-        let leaf_radius = plant_w * 0.8;
+    /// Swap the partner in. The two share one health bar, so the incoming
+    /// character keeps the outgoing one's share of it; tagging mid-combo
+    /// brings them in swinging their special.
+    fn tag_partner(&mut self) {
+        let Some(player) = self.player_entity else {
+            return;
+        };
+        let can_tag = self.world.get_component::<Fighter>(player).is_some_and(|fighter| {
+            fighter.hitstun <= 0.0 && fighter.state != FighterState::KnockedDown
+        });
+        if !can_tag {
+            return;
+        }
+        let combo_live = self.combo_system.is_active();
+        let Some(tag) = self.tag_team.as_mut() else {
+            return;
+        };
+        if !tag.tag(
+            &mut self.selected_character,
+            &mut self.ability_state,
+            &mut self.character_mastery,
+            combo_live,
+        ) {
+            return;
+        }
 
-        draw_circle(
-            plant_center_x,
-            plant_top_y,
-            leaf_radius,
-            Color::new(0.25, 0.55, 0.25, 1.0),
-        );
-        draw_circle(
-            plant_center_x - leaf_radius * 0.3,
-            plant_top_y + leaf_radius * 0.25,
-            leaf_radius * 0.7,
-            Color::new(0.3, 0.6, 0.3, 1.0),
-        );
-        draw_circle(
-            plant_center_x + leaf_radius * 0.3,
-            plant_top_y + leaf_radius * 0.35,
-            leaf_radius * 0.6,
-            Color::new(0.35, 0.65, 0.35, 1.0),
-        );
+        let character = self.selected_character;
+        self.player_moveset = CharacterMoveset::for_character(character.to_character_type());
+        let share = self
+            .world
+            .get_component::<Health>(player)
+            .map_or(1.0, |health| health.current / health.maximum.max(1.0));
+        self.apply_initial_upgrades();
+        let max_health = self.player_max_health;
+        if let Some(health) = self.world.get_component_mut::<Health>(player) {
+            health.maximum = max_health;
+            health.current = max_health * share;
+        }
+        if let Some(body) = self.world.get_component_mut::<PhysicsBody>(player) {
+            *body = PhysicsBody::fighter(Team::Player, self.player_moveset.stats.weight);
+        }
+        let mastery_level = self.character_mastery.level;
+        self.cosmetics = CosmeticsProgress::load().equipped(character, mastery_level);
+        self.update_ability_aura();
 
-        // Enhanced windows with light rays - resolution friendly
-        let window_w = sw * 0.065;
-        let window_h = sh * 0.17;
-        let window_y = sh * 0.2;
-        let window_spacing = sw * 0.1;
-        let window_start = board_x + board_w + sw * 0.03;
-        let window_count = ((sw - window_start - plant_x) / window_spacing)
-            .floor()
-            .min(8.0) as i32;
+        if combo_live {
+            let already_special = self
+                .world
+                .get_component::<Fighter>(player)
+                .is_some_and(|fighter| fighter.state == FighterState::Special);
+            if already_special {
+                self.activate_attack_hitbox(player, FighterState::Special);
+            } else if let Some(fighter) = self.world.get_component_mut::<Fighter>(player) {
+                fighter.state = FighterState::Special;
+            }
+        }
+    }
 
-        for i in 0..window_count {
-            let window_x = window_start + (i as f32 * window_spacing);
-            if window_x + window_w < plant_x - sw * 0.02 {
-                // Window shadow
-                draw_rectangle(
-                    window_x + 3.0,
-                    window_y + 3.0,
-                    window_w,
-                    window_h,
-                    Color::new(0.0, 0.0, 0.0, 0.2),
-                );
+    /// Every human-controlled fighter still in the run, P1 first
+    fn human_entities(&self) -> Vec<EntityId> {
+        let mut humans: Vec<EntityId> = self.player_entity.into_iter().collect();
+        if let Some(manager) = &self.coop_manager {
+            for entity in manager.get_active_players().iter().filter_map(|p| p.get_entity()) {
+                if !humans.contains(&entity) {
+                    humans.push(entity);
+                }
+            }
+        }
+        humans
+    }
 
-                // Window frame
-                draw_rectangle(
-                    window_x - 5.0,
-                    window_y - 5.0,
-                    window_w + 10.0,
-                    window_h + 10.0,
-                    Color::new(0.45, 0.38, 0.3, 1.0),
-                );
+    /// Co-op players after P1 who are on their feet, with the device they play
+    /// on. Online, P1 plays from exchanged inputs like everyone else.
+    fn coop_guests(&self) -> Vec<(PlayerSlot, InputDevice, EntityId)> {
+        let Some(manager) = &self.coop_manager else {
+            return Vec::new();
+        };
+        let online = self.net_session.is_some();
+
+        manager
+            .get_active_players()
+            .iter()
+            .filter(|player| (online || player.slot != PlayerSlot::Player1) && !player.is_downed)
+            .filter_map(|player| Some((player.slot, player.input_device, player.get_entity()?)))
+            .collect()
+    }
 
-                // Glass with subtle animation
-                let light_variation = ((time + i as f32 * 0.5).sin() * 0.05 + 0.75) as f32;
-                draw_rectangle(
-                    window_x,
-                    window_y,
-                    window_w,
-                    window_h,
-                    Color::new(0.65 * light_variation, 0.75 * light_variation, 0.95, 0.4),
-                );
+    /// Seat and colour of a co-op player after P1, if `entity` is one
+    fn coop_guest(&self, entity: EntityId) -> Option<(PlayerSlot, Color)> {
+        self.coop_manager
+            .as_ref()?
+            .get_active_players()
+            .into_iter()
+            .find(|player| {
+                player.slot != PlayerSlot::Player1 && player.get_entity() == Some(entity)
+            })
+            .map(|player| (player.slot, player.color))
+    }
 
-                // Light rays through window
-                let ray_start_y = window_y + window_h;
-                for ray in 0..3 {
-                    let ray_x = window_x + window_w * 0.2 + ray as f32 * window_w * 0.3;
-                    let ray_alpha = 0.1 + ((time * 0.5 + ray as f32 * 0.3).sin() * 0.05) as f32;
-                    for y_step in 0..10 {
-                        let y_pos = ray_start_y + y_step as f32 * sh * 0.05;
-                        draw_line(
-                            ray_x,
-                            ray_start_y,
-                            ray_x + window_w * 0.15,
-                            y_pos,
-                            window_w * 0.3,
-                            Color::new(1.0, 1.0, 0.9, ray_alpha),
-                        );
-                    }
-                }
+    /// Interact on a free device drops a new player in next to P1; pressing it
+    /// again drops them back out
+    fn handle_drop_in_out(&mut self) {
+        for (index, (device, _)) in COOP_DEVICES.into_iter().enumerate().skip(1) {
+            let Some(seat) = PlayerSlot::from_index(index) else {
+                continue;
+            };
+            if !self.coop_input.get_player_input(&*self.host, seat, device).interact_pressed {
+                continue;
+            }
 
-                // Window dividers
-                draw_rectangle_lines(
-                    window_x,
-                    window_y,
-                    window_w,
-                    window_h,
-                    4.0,
-                    Color::new(0.4, 0.33, 0.26, 1.0),
-                );
-                // Vertical divider
-                draw_line(
-                    window_x + window_w * 0.5,
-                    window_y,
-                    window_x + window_w * 0.5,
-                    window_y + window_h,
-                    3.0,
-                    Color::new(0.4, 0.33, 0.26, 1.0),
-                );
-                // Horizontal divider
-                draw_line(
-                    window_x,
-                    window_y + window_h * 0.5,
-                    window_x + window_w,
-                    window_y + window_h * 0.5,
-                    3.0,
-                    Color::new(0.4, 0.33, 0.26, 1.0),
-                );
+            match self.coop_manager.as_ref().and_then(|m| m.slot_for_device(device)) {
+                // Interact next to a downed teammate is the start of a revive
+                Some(slot) if self.teammate_to_revive(slot).is_some() => {}
+                Some(slot) => self.drop_out(slot),
+                None => self.drop_in(device),
             }
         }
     }
 
-    fn render_hallway(&self) {
-        let time = get_time() as f32;
-        let sw = screen_width();
-        let sh = screen_height();
+    /// Join on `device` beside P1 as a character nobody else is playing. A
+    /// solo run switches to co-op rules (downs, revives, shared lives).
+    /// With a bot in the run, the human takes over its fighter as it stands.
+    fn drop_in(&mut self, device: InputDevice) {
+        if let Some(seat) = self.bots.iter().position(Option::is_some) {
+            self.bots[seat] = None;
+            let slot = PlayerSlot::from_index(seat);
+            let manager = self.coop_manager.as_mut();
+            if let Some(player) = manager.zip(slot).and_then(|(m, slot)| m.get_player_mut(slot)) {
+                player.input_device = device;
+            }
+            return;
+        }
 
-        // Tiled floor - resolution friendly
-        let floor_y = sh * 0.78;
-        let tile_w = sw * 0.045;
-        let tile_h = sh * 0.065;
-        let tile_cols = (sw / tile_w).ceil() as i32;
-        let tile_rows = ((sh - floor_y) / tile_h).ceil() as i32;
+        let Some(player_entity) = self.player_entity else {
+            return;
+        };
+        let Some(anchor) = self
+            .world
+            .get_component::<Transform>(player_entity)
+            .map(|transform| transform.position)
+        else {
+            return;
+        };
 
-        for i in 0..tile_cols {
-            for j in 0..tile_rows {
-                let x = i as f32 * tile_w;
-                let y = floor_y + j as f32 * tile_h;
-                let shade = if (i + j) % 2 == 0 { 0.65 } else { 0.6 };
-                draw_rectangle(
-                    x,
-                    y,
-                    tile_w,
-                    tile_h,
-                    Color::new(shade, shade - 0.08, shade - 0.15, 1.0),
-                );
+        if self.coop_manager.is_none() {
+            self.enable_coop(MAX_PLAYERS);
+        }
+        let selected = self.selected_character;
+        let Some(manager) = self.coop_manager.as_mut() else {
+            return;
+        };
+        if manager.get_player(PlayerSlot::Player1).is_none() {
+            manager.init_coop(vec![(selected, InputDevice::Keyboard)]);
+            if let Some(player) = manager.get_player_mut(PlayerSlot::Player1) {
+                player.set_entity(player_entity);
             }
         }
 
-        // Enhanced lockers with depth - resolution friendly
-        let locker_w = sw * 0.055;
-        let locker_h = sh * 0.46;
-        let locker_y = sh * 0.2;
-        let locker_count = (sw / locker_w).ceil().min(25.0) as i32;
-
-        for i in 0..locker_count {
-            let locker_x = i as f32 * locker_w;
+        let taken: Vec<CharacterId> = (0..MAX_PLAYERS)
+            .filter_map(PlayerSlot::from_index)
+            .filter_map(|slot| manager.get_player(slot))
+            .map(|player| player.character_type)
+            .collect();
+        let character = crate::data::characters::CHARACTERS
+            .iter()
+            .map(|character| character.id)
+            .find(|id| !taken.contains(id))
+            .unwrap_or(selected);
+        let Some(slot) = manager.add_player(character, device) else {
+            return;
+        };
 
-            // Shadow
-            draw_rectangle(
-                locker_x + 3.0,
-                locker_y + 3.0,
-                locker_w - 10.0,
-                locker_h,
-                Color::new(0.0, 0.0, 0.0, 0.3),
-            );
-            // Main locker body
-            draw_rectangle(
-                locker_x,
-                locker_y,
-                locker_w - 10.0,
-                locker_h,
-                Color::new(0.45, 0.45, 0.65, 1.0),
-            );
-            // Top highlight
-            draw_rectangle(
-                locker_x,
-                locker_y,
-                locker_w - 10.0,
-                locker_h * 0.08,
-                Color::new(0.55, 0.55, 0.75, 1.0),
-            );
-            // Outline
-            draw_rectangle_lines(
-                locker_x,
-                locker_y,
-                locker_w - 10.0,
-                locker_h,
-                3.0,
-                Color::new(0.35, 0.35, 0.55, 1.0),
-            );
+        let bounds = arena_bounds(self.host.screen_width());
+        let x = (anchor.x - 100.0).clamp(bounds.left(), bounds.right());
+        let entity = self.spawn_player_entity(character, Vec2::new(x, anchor.y));
+        if let Some(player) = self.coop_manager.as_mut().and_then(|m| m.get_player_mut(slot)) {
+            player.set_entity(entity);
+        }
+    }
 
-            // Lock
-            let lock_x = locker_x + (locker_w - 10.0) * 0.5;
-            let lock_y = locker_y + locker_h * 0.5;
-            draw_circle(lock_x, lock_y, 7.0, Color::new(0.2, 0.2, 0.2, 1.0));
-            draw_circle(lock_x, lock_y, 5.0, Color::new(0.8, 0.8, 0.8, 1.0));
+    /// Leave the run. Downed and eliminated players have to sit it out, or
+    /// dropping out would dodge losing a life.
+    fn drop_out(&mut self, slot: PlayerSlot) {
+        let Some(manager) = self.coop_manager.as_mut() else {
+            return;
+        };
+        let Some(player) = manager.get_player(slot) else {
+            return;
+        };
+        if slot == PlayerSlot::Player1 || player.is_downed || !player.is_active {
+            return;
+        }
 
-            // Divider line
-            draw_line(
-                locker_x,
-                lock_y - 5.0,
-                locker_x + locker_w - 10.0,
-                lock_y - 5.0,
-                2.0,
-                Color::new(0.35, 0.35, 0.55, 1.0),
-            );
+        // A run with bots hands the seat back to one
+        if let Some(difficulty) = self.bot_difficulty {
+            let seat = slot.to_index();
+            self.bots[seat] = Some(CoopBot::new(player.character_type, difficulty));
+            if let Some(player) = manager.get_player_mut(slot) {
+                player.input_device = InputDevice::Bot(seat as u8);
+            }
+            return;
         }
 
-        // Ceiling with lights - resolution friendly
-        let ceiling_h = sh * 0.2;
-        draw_rectangle(0.0, 0.0, sw, ceiling_h, Color::new(0.92, 0.92, 0.87, 1.0));
+        let entity = player.get_entity();
+        manager.remove_player(slot);
+        if let Some(revives) = self.revive_system.as_mut() {
+            revives.cancel_revive(slot);
+        }
+        if let Some(entity) = entity {
+            self.world.destroy_entity(entity);
+        }
+    }
 
-        let light_spacing = sw * 0.12;
-        let light_count = (sw / light_spacing).floor() as i32;
-        let light_radius = (sw * 0.022).min(35.0);
+    /// Co-op hits feed one shared combo and the team meter. Landing on a target
+    /// a partner just hit is a team attack: bonus damage and extra meter.
+    fn register_team_hit(
+        &mut self,
+        attacker: EntityId,
+        defender: EntityId,
+        damage: f32,
+        position: Vec2,
+    ) {
+        let (Some(manager), Some(combo)) = (self.coop_manager.as_mut(), self.shared_combo.as_mut())
+        else {
+            return;
+        };
+        let Some(slot) = manager
+            .get_active_players()
+            .iter()
+            .find(|player| player.get_entity() == Some(attacker))
+            .map(|player| player.slot)
+        else {
+            return;
+        };
 
-        for i in 0..light_count {
-            let light_x = i as f32 * light_spacing + light_spacing * 0.5;
-            let light_y = ceiling_h * 0.5;
-            let glow = ((time * 2.0 + i as f32).sin() * 0.1 + 0.9) as f32;
+        let team_attack = combo.register_hit(slot, defender);
+        let meter = if team_attack {
+            TEAM_ATTACK_METER
+        } else {
+            TEAM_HIT_METER
+        };
+        manager.add_combo(meter, slot);
+        if !team_attack {
+            return;
+        }
 
-            // Glow effect
-            draw_circle(
-                light_x,
-                light_y,
-                light_radius * 1.2,
-                Color::new(1.0 * glow, 0.95 * glow, 0.8 * glow, 0.6),
-            );
-            // Light fixture
-            draw_circle(
-                light_x,
-                light_y,
-                light_radius * 0.8,
-                Color::new(1.0, 0.98, 0.85, 1.0),
-            );
+        let bonus = damage * TEAM_ATTACK_BONUS;
+        if let Some(health) = self.world.get_component_mut::<Health>(defender) {
+            health.current = (health.current - bonus).max(0.0);
         }
+        self.enhanced_vfx.show_damage_number(position + Vec2::new(0.0, -30.0), bonus, true);
     }
 
-    fn render_cafeteria(&self) {
-        let sw = screen_width();
-        let sh = screen_height();
-        let floor_y = sh * 0.78;
-
-        // Floor
-        draw_rectangle(
-            0.0,
-            floor_y,
-            sw,
-            sh - floor_y,
-            Color::new(0.8, 0.8, 0.7, 1.0),
-        );
-
-        // Tables - resolution friendly
-        let table_w = sw * 0.12;
-        let table_h = sh * 0.13;
-        let table_y = sh * 0.52;
-        let table_spacing = sw * 0.15;
-        let table_count = ((sw - sw * 0.1) / table_spacing).floor().min(8.0).max(3.0) as i32;
+    /// With the team meter full, two players pressing special together
+    /// unleash the dual super
+    fn handle_team_super_input(&mut self) {
+        let full = self
+            .coop_manager
+            .as_ref()
+            .is_some_and(|manager| manager.get_combo_meter() >= TEAM_SUPER_COST);
+        if !full {
+            return;
+        }
 
-        for i in 0..table_count {
-            let table_x = sw * 0.05 + i as f32 * table_spacing;
+        let mut pressed = Vec::new();
+        let p1_standing = self
+            .coop_manager
+            .as_ref()
+            .and_then(|manager| manager.get_player(PlayerSlot::Player1))
+            .is_some_and(|player| player.is_active && !player.is_downed);
+        let special = self.keys.special_attack;
+        if p1_standing && self.net_session.is_none() && self.host.key_pressed(special) {
+            pressed.push(PlayerSlot::Player1);
+        }
+        for (slot, device, _) in self.coop_guests() {
+            if self.coop_input.get_player_input(&*self.host, slot, device).special_attack_pressed {
+                pressed.push(slot);
+            }
+        }
 
-            // Table top
-            draw_rectangle(
-                table_x,
-                table_y,
-                table_w,
-                table_h,
-                Color::new(0.6, 0.5, 0.4, 1.0),
-            );
+        for slot in pressed {
+            let Some(partner) = self
+                .shared_combo
+                .as_mut()
+                .and_then(|combo| combo.request_team_super(slot))
+            else {
+                continue;
+            };
+            let spent = self
+                .coop_manager
+                .as_mut()
+                .is_some_and(|manager| manager.spend_combo_meter(TEAM_SUPER_COST));
+            if spent {
+                self.team_super([partner, slot]);
+            }
+            break;
+        }
+    }
 
-            // Table legs
-            let leg_w = table_w * 0.1;
-            let leg_h = sh * 0.1;
-            draw_rectangle(
-                table_x + table_w * 0.1,
-                table_y + table_h,
-                leg_w,
-                leg_h,
-                Color::new(0.4, 0.3, 0.2, 1.0),
-            );
-            draw_rectangle(
-                table_x + table_w * 0.8,
-                table_y + table_h,
-                leg_w,
-                leg_h,
-                Color::new(0.4, 0.3, 0.2, 1.0),
-            );
+    /// Both players go into their super while every enemy near either of them
+    /// takes a hit scaled by the team combo
+    fn team_super(&mut self, players: [PlayerSlot; 2]) {
+        use crate::render::enhanced_vfx::ImpactType;
 
-            // Benches
-            let bench_w = table_w * 0.3;
-            let bench_h = table_h * 0.2;
-            let bench_y = table_y - table_h * 0.25;
+        let Some(manager) = &self.coop_manager else {
+            return;
+        };
+        let entities: Vec<EntityId> = players
+            .iter()
+            .filter_map(|&slot| manager.get_player(slot)?.get_entity())
+            .collect();
+        let origins: Vec<Vec2> = entities
+            .iter()
+            .filter_map(|&entity| self.world.get_component::<Transform>(entity))
+            .map(|transform| transform.position)
+            .collect();
 
-            draw_rectangle(
-                table_x + table_w * 0.1,
-                bench_y,
-                bench_w,
-                bench_h,
-                Color::new(0.5, 0.4, 0.3, 1.0),
-            );
-            draw_rectangle(
-                table_x + table_w * 0.6,
-                bench_y,
-                bench_w,
-                bench_h,
-                Color::new(0.5, 0.4, 0.3, 1.0),
-            );
+        for &entity in &entities {
+            if let Some(fighter) = self.world.get_component_mut::<Fighter>(entity) {
+                fighter.state = FighterState::Super;
+            }
         }
 
-        // Counter/service area
-        let counter_h = sh * 0.2;
-        draw_rectangle(
-            0.0,
-            sh * 0.13,
-            sw,
-            counter_h,
-            Color::new(0.7, 0.7, 0.7, 1.0),
-        );
-
-        // Menu board
-        let menu_w = sw * 0.13;
-        let menu_h = sh * 0.13;
-        let menu_x = sw * 0.04;
-        let menu_y = sh * 0.16;
+        let multiplier = self
+            .shared_combo
+            .as_ref()
+            .map_or(1.0, |combo| combo.get_combo_multiplier());
+        let damage = TEAM_SUPER_DAMAGE * multiplier;
+        for &enemy in &self.enemy_entities {
+            let Some(position) = self
+                .world
+                .get_component::<Transform>(enemy)
+                .map(|transform| transform.position)
+            else {
+                continue;
+            };
+            if !origins.iter().any(|origin| origin.distance(position) <= TEAM_SUPER_RADIUS) {
+                continue;
+            }
 
-        draw_rectangle(menu_x, menu_y, menu_w, menu_h, Color::new(0.9, 0.9, 0.9, 1.0));
+            if let Some(health) = self.world.get_component_mut::<Health>(enemy) {
+                health.current = (health.current - damage).max(0.0);
+            }
+            self.enhanced_vfx
+                .spawn_impact(position, Vec2::new(0.0, -1.0), ImpactType::Critical);
+            self.enhanced_vfx.show_damage_number(position, damage, true);
+        }
 
-        let menu_font = (menu_w * 0.15).max(20.0);
-        draw_text(
-            "MENU",
-            menu_x + menu_w * 0.2,
-            menu_y + menu_h * 0.6,
-            menu_font,
-            Color::new(0.2, 0.2, 0.2, 1.0),
-        );
+        self.camera.add_trauma(0.8);
+        self.camera.hit_stop(0.25, 0.2);
+        self.post.trigger(PostMoment::SuperActivation);
     }
 
-    fn render_gym(&self) {
-        let sw = screen_width();
-        let sh = screen_height();
-        let floor_y = sh * 0.78;
+    /// Step `seat`'s ping wheel. On release the marker goes where they aimed,
+    /// or the way they face for a tap, and is sent as an event so every
+    /// teammate sees it. Returns whether the wheel is open.
+    fn update_ping_wheel(&mut self, seat: usize, entity: EntityId, input: &PlayerInput) -> bool {
+        if self.coop_manager.is_none() {
+            return false;
+        }
+        let Some(wheel) = self.ping_wheels.get_mut(seat) else {
+            return false;
+        };
+        let Some((kind, aim)) = wheel.update(input.ping, input.movement) else {
+            return wheel.is_open();
+        };
 
-        // Wooden floor
-        draw_rectangle(
-            0.0,
-            floor_y,
-            sw,
-            sh - floor_y,
-            Color::new(0.9, 0.7, 0.5, 1.0),
-        );
+        let Some(position) = self
+            .world
+            .get_component::<Transform>(entity)
+            .map(|transform| transform.position)
+        else {
+            return false;
+        };
+        let aim = if aim == Vec2::ZERO {
+            let facing = self.world.get_component::<Fighter>(entity).map_or(1.0, |f| f.facing);
+            Vec2::new(facing.signum(), 0.0)
+        } else {
+            aim
+        };
+        self.world.events().send(GameEvent::Ping {
+            kind,
+            position: kind.placement(position, aim),
+            seat,
+        });
+        false
+    }
 
-        // Floor lines - resolution friendly
-        let line_spacing = sw * 0.055;
-        let line_count = (sw / line_spacing).ceil() as i32;
+    /// Teammates' markers, and the wheels of players on this machine
+    fn render_pings(&self) {
+        self.pings.render();
 
-        for i in 0..line_count {
-            let line_x = i as f32 * line_spacing;
-            draw_line(
-                line_x,
-                floor_y,
-                line_x,
-                sh,
-                3.0,
-                Color::new(0.8, 0.6, 0.4, 1.0),
-            );
+        let Some(manager) = &self.coop_manager else {
+            return;
+        };
+        let local_seat = self.net_session.as_ref().map(|session| session.local_seat());
+        for player in manager.get_active_players() {
+            let seat = player.slot.to_index();
+            if local_seat.is_some_and(|local| local != seat) {
+                continue;
+            }
+            let Some(position) = player
+                .get_entity()
+                .and_then(|entity| self.world.get_component::<Transform>(entity))
+                .map(|transform| transform.position)
+            else {
+                continue;
+            };
+            self.ping_wheels[seat].render(position + Vec2::new(0.0, -60.0));
         }
+    }
 
-        // Center court circle - resolution friendly
-        let circle_y = sh * 0.59;
-        let circle_radius = (sw * 0.06).min(sh * 0.13);
+    /// Let each bot pick its seat's input for this frame
+    fn update_bots(&mut self, dt: f32) {
+        let Some(manager) = &self.coop_manager else {
+            return;
+        };
+        let enemies: Vec<Vec2> = self
+            .enemy_entities
+            .iter()
+            .filter(|&&enemy| {
+                self.world.get_component::<Health>(enemy).is_some_and(|h| h.current > 0.0)
+            })
+            .filter_map(|&enemy| self.world.get_component::<Transform>(enemy))
+            .map(|transform| transform.position)
+            .collect();
+        let leader = self
+            .player_entity
+            .and_then(|entity| self.world.get_component::<Transform>(entity))
+            .map_or(Vec2::ZERO, |transform| transform.position);
+
+        let seats: Vec<(usize, EntityId)> = manager
+            .get_active_players()
+            .iter()
+            .filter(|player| matches!(player.input_device, InputDevice::Bot(_)))
+            .filter_map(|player| Some((player.slot.to_index(), player.get_entity()?)))
+            .collect();
+        for (seat, entity) in seats {
+            let (Some(transform), Some(fighter)) = (
+                self.world.get_component::<Transform>(entity),
+                self.world.get_component::<Fighter>(entity),
+            ) else {
+                continue;
+            };
+            let view = BotView {
+                position: transform.position,
+                enemies: &enemies,
+                can_act: fighter.hitstun <= 0.0
+                    && fighter.blockstun <= 0.0
+                    && matches!(fighter.state, FighterState::Idle | FighterState::Walking),
+                leader,
+            };
+            if let Some(bot) = self.bots[seat].as_mut() {
+                let input = bot.think(dt, &view, self.world.rng());
+                self.coop_input.set_bot_input(seat as u8, input);
+            }
+        }
+    }
 
-        draw_circle(sw * 0.5, circle_y, circle_radius, Color::new(1.0, 1.0, 1.0, 0.3));
-        draw_circle_lines(sw * 0.5, circle_y, circle_radius, 5.0, WHITE);
+    /// Movement and attacks for a co-op player after P1, read from their device
+    fn handle_guest_input(
+        &mut self,
+        slot: PlayerSlot,
+        device: InputDevice,
+        entity: EntityId,
+        dt: f32,
+    ) {
+        let input = self.coop_input.get_player_input(&*self.host, slot, device);
+        let aiming = self.update_ping_wheel(slot.to_index(), entity, &input);
+        let mut movement = Vec2::ZERO;
+        let mut new_state = None;
+
+        if let Some(fighter) = self.world.get_component::<Fighter>(entity) {
+            if fighter.hitstun <= 0.0 && fighter.blockstun <= 0.0 && !aiming {
+                movement = input.movement;
+
+                if input.light_attack_pressed {
+                    if self.consume_stamina(entity, 15.0) {
+                        new_state = Some(FighterState::LightAttack);
+                    }
+                } else if input.heavy_attack_pressed {
+                    if self.consume_stamina(entity, self.heavy_stamina()) {
+                        new_state = Some(FighterState::HeavyAttack);
+                    }
+                } else if input.special_attack_pressed && self.consume_stamina(entity, 50.0) {
+                    new_state = Some(FighterState::Special);
+                }
+            }
+        }
 
-        // Basketball hoops - resolution friendly
-        let hoop_y = sh * 0.26;
-        let pole_w = sw * 0.008;
-        let pole_h = sh * 0.26;
-        let backboard_w = sw * 0.008;
-        let backboard_h = sh * 0.39;
-        let rim_radius = (sw * 0.032).min(sh * 0.065);
+        if input.dodge_pressed && !aiming {
+            self.try_dash(entity, movement);
+        }
 
-        // Left hoop
-        let left_x = sw * 0.08;
-        draw_rectangle(left_x, hoop_y, pole_w, pole_h, Color::new(0.8, 0.8, 0.8, 1.0));
-        draw_circle(left_x, hoop_y - rim_radius * 0.4, rim_radius, Color::new(1.0, 0.6, 0.2, 1.0));
-        draw_rectangle(
-            left_x - backboard_w * 0.5,
-            hoop_y - rim_radius * 0.4,
-            backboard_w,
-            backboard_h,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        );
+        let speed = self.player_move_speed
+            * self.power_up_multiplier(PowerUpKind::Haste, HASTE_MULTIPLIER);
+        if let Some(transform) = self.world.get_component_mut::<Transform>(entity) {
+            let depth_speed = speed * 0.65;
+            transform.position.x += movement.x * speed * dt;
+            transform.position.y += movement.y * depth_speed * dt;
 
-        // Right hoop
-        let right_x = sw - sw * 0.08;
-        draw_rectangle(right_x, hoop_y, pole_w, pole_h, Color::new(0.8, 0.8, 0.8, 1.0));
-        draw_circle(right_x, hoop_y - rim_radius * 0.4, rim_radius, Color::new(1.0, 0.6, 0.2, 1.0));
+            let bounds = arena_bounds(self.host.screen_width());
+            transform.position.y = transform.position.y.clamp(bounds.top(), bounds.bottom());
+            transform.position.x = transform.position.x.clamp(bounds.left(), bounds.right());
+        }
+
+        if let Some(velocity) = self.world.get_component_mut::<Velocity>(entity) {
+            velocity.linear = Vec2::ZERO;
+        }
+
+        if let Some(fighter) = self.world.get_component_mut::<Fighter>(entity) {
+            if movement.x.abs() > 0.01
+                && matches!(fighter.state, FighterState::Idle | FighterState::Walking)
+            {
+                fighter.state = FighterState::Walking;
+            } else if movement.x.abs() <= 0.01 && fighter.state == FighterState::Walking {
+                fighter.state = FighterState::Idle;
+            }
+
+            if movement.x > 0.1 {
+                fighter.facing = 1.0;
+            } else if movement.x < -0.1 {
+                fighter.facing = -1.0;
+            }
+
+            if let Some(state) = new_state {
+                fighter.state = state;
+            }
+        }
     }
 
-    fn render_library(&self) {
-        let sw = screen_width();
-        let sh = screen_height();
-        let floor_y = sh * 0.78;
+    /// Keep the players within one screen of each other, so the camera can
+    /// always frame them all. Downed players stay where they fell.
+    fn leash_players(&mut self) {
+        let humans = self.human_entities();
+        let positions: Vec<Vec2> = humans
+            .iter()
+            .filter_map(|&entity| self.world.get_component::<Transform>(entity))
+            .map(|transform| transform.position)
+            .collect();
+        let Some(&first) = positions.first() else {
+            return;
+        };
 
-        // Floor
-        draw_rectangle(
-            0.0,
-            floor_y,
-            sw,
-            sh - floor_y,
-            Color::new(0.5, 0.4, 0.3, 1.0),
-        );
+        let (min, max) = positions
+            .iter()
+            .fold((first, first), |(min, max), &p| (min.min(p), max.max(p)));
+        let span = self.camera.framing_span();
+        if max.x - min.x <= span.x && max.y - min.y <= span.y {
+            return;
+        }
 
-        // Bookshelves - resolution friendly
-        let shelf_w = sw * 0.095;
-        let shelf_h = sh * 0.52;
-        let shelf_y = sh * 0.2;
-        let shelf_spacing = sw * 0.125;
-        let shelf_count = ((sw - sw * 0.1) / shelf_spacing).floor().min(10.0).max(4.0) as i32;
+        let centre = (min + max) * 0.5;
+        let half = span * 0.5;
+        let standing: Vec<EntityId> = humans
+            .into_iter()
+            .filter(|&entity| {
+                let downed = self.coop_manager.as_ref().is_some_and(|manager| {
+                    manager
+                        .get_active_players()
+                        .iter()
+                        .any(|player| player.get_entity() == Some(entity) && player.is_downed)
+                });
+                !downed
+            })
+            .collect();
+        for entity in standing {
+            if let Some(transform) = self.world.get_component_mut::<Transform>(entity) {
+                transform.position = transform.position.clamp(centre - half, centre + half);
+            }
+        }
+    }
 
-        for i in 0..shelf_count {
-            let shelf_x = sw * 0.05 + i as f32 * shelf_spacing;
+    /// Downs, revives, executions and the shared lives pool for the human
+    /// co-op players and their allies
+    fn update_coop_survival(&mut self, dt: f32) {
+        let (Some(manager), Some(revives)) = (&self.coop_manager, &self.revive_system) else {
+            return;
+        };
 
-            // Shelf frame
-            draw_rectangle(
-                shelf_x,
-                shelf_y,
-                shelf_w,
-                shelf_h,
-                Color::new(0.4, 0.3, 0.2, 1.0),
-            );
+        // Done before anything is borrowed mutably: who's holding interact,
+        // and which downed teammates have an enemy standing over them
+        let seats: Vec<(PlayerSlot, InputDevice)> = manager
+            .get_active_players()
+            .iter()
+            .map(|player| (player.slot, player.input_device))
+            .collect();
+        let execute_range = revives.get_execute_range();
+        let enemy_positions: Vec<Vec2> = self
+            .enemy_entities
+            .iter()
+            .filter(|&&enemy| {
+                self.world
+                    .get_component::<Health>(enemy)
+                    .is_some_and(|health| health.current > 0.0)
+            })
+            .filter_map(|&enemy| Some(self.world.get_component::<Transform>(enemy)?.position))
+            .collect();
+        let threatened: Vec<Teammate> = self
+            .downed_teammates()
+            .into_iter()
+            .filter(|&(_, position)| {
+                enemy_positions
+                    .iter()
+                    .any(|enemy| enemy.distance(position) <= execute_range)
+            })
+            .map(|(teammate, _)| teammate)
+            .collect();
+        let fallen_allies: Vec<EntityId> = self
+            .ally_entities
+            .iter()
+            .copied()
+            .filter(|&ally| {
+                self.world
+                    .get_component::<Health>(ally)
+                    .is_some_and(|health| health.current <= 0.0)
+            })
+            .collect();
+        let holding: Vec<PlayerSlot> = seats
+            .into_iter()
+            .filter(|&(slot, device)| {
+                self.coop_input.get_player_input(&*self.host, slot, device).interact
+            })
+            .map(|(slot, _)| slot)
+            .collect();
 
-            // Shelves
-            let shelf_rows = 5;
-            let row_h = shelf_h / shelf_rows as f32;
+        let (Some(manager), Some(revives)) =
+            (self.coop_manager.as_mut(), self.revive_system.as_mut())
+        else {
+            return;
+        };
 
-            for j in 0..shelf_rows {
-                let row_y = shelf_y + j as f32 * row_h + row_h * 0.15;
-                let row_shelf_h = row_h * 0.78;
+        let players: Vec<(PlayerSlot, EntityId)> = manager
+            .get_active_players()
+            .iter()
+            .filter_map(|player| Some((player.slot, player.get_entity()?)))
+            .collect();
+        let is_downed = |manager: &CoopPlayerManager, slot: PlayerSlot| {
+            manager.get_player(slot).is_some_and(|p| p.is_downed)
+        };
 
-                draw_rectangle(
-                    shelf_x + shelf_w * 0.05,
-                    row_y,
-                    shelf_w * 0.9,
-                    row_shelf_h,
-                    Color::new(0.3, 0.2, 0.1, 1.0),
-                );
+        for &(slot, entity) in &players {
+            let out_of_health = self
+                .world
+                .get_component::<Health>(entity)
+                .is_some_and(|health| health.current <= 0.0);
+            if out_of_health && !is_downed(manager, slot) {
+                manager.down_player(slot);
+                self.run_went_down = true;
+            }
+        }
+        // Allies go down for a revive too rather than dying outright
+        for &ally in &fallen_allies {
+            revives.down_ally(ally);
+        }
 
-                // Books
-                let book_w = shelf_w * 0.08;
-                let book_count = ((shelf_w * 0.9) / book_w).floor() as i32;
+        // Nobody left standing to revive anyone: skip straight to bleeding out
+        if manager.are_all_players_downed() {
+            for &(slot, _) in &players {
+                manager.bleed_out(slot);
+            }
+        }
 
-                for k in 0..book_count {
-                    let book_x = shelf_x + shelf_w * 0.08 + k as f32 * book_w;
-                    let book_color = if k % 3 == 0 {
-                        Color::new(0.7, 0.2, 0.2, 1.0)
-                    } else if k % 3 == 1 {
-                        Color::new(0.2, 0.4, 0.7, 1.0)
-                    } else {
-                        Color::new(0.2, 0.6, 0.2, 1.0)
-                    };
-                    draw_rectangle(
-                        book_x,
-                        row_y + row_shelf_h * 0.1,
-                        book_w * 0.9,
-                        row_shelf_h * 0.8,
-                        book_color,
-                    );
+        let positions: Vec<(Teammate, Vec2)> = players
+            .iter()
+            .map(|&(slot, entity)| (Teammate::Player(slot), entity))
+            .chain(self.ally_entities.iter().map(|&ally| (Teammate::Ally(ally), ally)))
+            .filter_map(|(teammate, entity)| {
+                Some((teammate, self.world.get_component::<Transform>(entity)?.position))
+            })
+            .collect();
+
+        // Holding interact next to a downed teammate starts reviving them
+        let range = revives.get_revive_range();
+        for &(downed, position) in &positions {
+            let down = match downed {
+                Teammate::Player(slot) => is_downed(manager, slot),
+                Teammate::Ally(ally) => revives.is_ally_downed(ally),
+            };
+            if !down || revives.is_being_revived(downed) {
+                continue;
+            }
+            let reviver = positions.iter().find_map(|&(other, other_position)| match other {
+                Teammate::Player(slot)
+                    if other != downed
+                        && holding.contains(&slot)
+                        && !is_downed(manager, slot)
+                        && other_position.distance(position) <= range =>
+                {
+                    Some(slot)
                 }
+                _ => None,
+            });
+            if let Some(reviver) = reviver {
+                revives.start_revive(downed, reviver, position);
             }
         }
 
-        // Reading table - resolution friendly
-        let table_w = sw * 0.13;
-        let table_h = sh * 0.1;
-        let table_x = sw * 0.5 - table_w * 0.5;
-        let table_y = sh * 0.59;
-
-        draw_rectangle(table_x, table_y, table_w, table_h, Color::new(0.6, 0.5, 0.4, 1.0));
+        let was_downed: Vec<_> = players
+            .iter()
+            .copied()
+            .filter(|&(slot, _)| is_downed(manager, slot))
+            .collect();
+        let bled_out = manager.update(dt);
+        let outcome = revives.update(dt, manager, &positions, &holding, &threatened);
+
+        for (slot, entity) in was_downed {
+            if bled_out.contains(&slot) {
+                // Full death: a life brings them back, otherwise they're out
+                if self.lives_pool.as_mut().is_some_and(|pool| pool.consume()) {
+                    Self::stand_up_player(&mut self.world, entity, 1.0);
+                } else {
+                    manager.eliminate(slot);
+                }
+            } else if !is_downed(manager, slot) {
+                // Revived before bleeding out: no life spent
+                Self::stand_up_player(&mut self.world, entity, 0.5);
+            }
+        }
 
-        // Chairs
-        let chair_size = table_h * 0.75;
-        let chair_y = table_y - chair_size - sh * 0.015;
+        for teammate in &outcome.revived {
+            if let Teammate::Ally(ally) = *teammate {
+                Self::stand_up_player(&mut self.world, ally, 0.5);
+            }
+            if let Some(&(_, position)) = positions.iter().find(|(other, _)| other == teammate) {
+                self.world.events().send(GameEvent::TeammateRevived { position });
+            }
+        }
+        let lost_allies: Vec<EntityId> = outcome
+            .executed
+            .iter()
+            .filter_map(|teammate| match *teammate {
+                Teammate::Ally(ally) => Some(ally),
+                Teammate::Player(_) => None,
+            })
+            .chain(outcome.bled_out.iter().copied())
+            .collect();
+        for ally in lost_allies {
+            if let Some(transform) = self.world.get_component::<Transform>(ally) {
+                let position = transform.position;
+                self.world.events().send(GameEvent::EntityDied {
+                    entity: ally,
+                    team: Team::Ally,
+                    position,
+                });
+            }
+            self.world.commands().destroy(ally);
+            self.ally_entities.retain(|&entity| entity != ally);
+        }
 
-        draw_rectangle(
-            table_x + table_w * 0.1,
-            chair_y,
-            chair_size,
-            chair_size,
-            Color::new(0.3, 0.3, 0.3, 1.0),
-        );
-        draw_rectangle(
-            table_x + table_w * 0.6,
-            chair_y,
-            chair_size,
-            chair_size,
-            Color::new(0.3, 0.3, 0.3, 1.0),
-        );
+        // Downed and eliminated players stay on the floor and can't be hit
+        let grounded: Vec<EntityId> = (0..MAX_PLAYERS)
+            .filter_map(PlayerSlot::from_index)
+            .filter_map(|slot| manager.get_player(slot))
+            .filter(|player| player.is_downed || !player.is_active)
+            .filter_map(|player| player.get_entity())
+            .chain(
+                self.ally_entities
+                    .iter()
+                    .copied()
+                    .filter(|&ally| revives.is_ally_downed(ally)),
+            )
+            .collect();
+        for entity in grounded {
+            if let Some(fighter) = self.world.get_component_mut::<Fighter>(entity) {
+                fighter.state = FighterState::KnockedDown;
+                fighter.hitstun = fighter.hitstun.max(0.2);
+                fighter.invulnerable = true;
+            }
+            if let Some(velocity) = self.world.get_component_mut::<Velocity>(entity) {
+                velocity.linear = Vec2::ZERO;
+            }
+        }
     }
 
-    fn render_rooftop(&self) {
-        let sw = screen_width();
-        let sh = screen_height();
-        let floor_y = sh * 0.78;
+    /// Downed co-op players and allies, with where they lie
+    fn downed_teammates(&self) -> Vec<(Teammate, Vec2)> {
+        let position_of = |entity: EntityId| {
+            self.world
+                .get_component::<Transform>(entity)
+                .map(|transform| transform.position)
+        };
 
-        // Rooftop floor
-        draw_rectangle(
-            0.0,
-            floor_y,
-            sw,
-            sh - floor_y,
-            Color::new(0.4, 0.4, 0.4, 1.0),
-        );
+        let players = self.coop_manager.iter().flat_map(|manager| {
+            manager
+                .get_active_players()
+                .into_iter()
+                .filter(|player| player.is_downed)
+                .filter_map(|player| {
+                    Some((Teammate::Player(player.slot), position_of(player.get_entity()?)?))
+                })
+        });
+        let allies = self.revive_system.iter().flat_map(|revives| {
+            self.ally_entities
+                .iter()
+                .filter(|&&ally| revives.is_ally_downed(ally))
+                .filter_map(|&ally| Some((Teammate::Ally(ally), position_of(ally)?)))
+        });
 
-        // Clouds - resolution friendly
-        let cloud_count = ((sw / (sw * 0.32)).ceil() as i32).max(2).min(5);
-        let cloud_spacing = sw / cloud_count as f32;
+        players.chain(allies).collect()
+    }
 
-        for i in 0..cloud_count {
-            let cloud_x = cloud_spacing * 0.3 + i as f32 * cloud_spacing;
-            let cloud_y = sh * 0.13;
-            let cloud_r1 = sw * 0.05;
-            let cloud_r2 = sw * 0.038;
+    /// The downed teammate standing co-op player `slot` is close enough to
+    /// revive, if any
+    fn teammate_to_revive(&self, slot: PlayerSlot) -> Option<Teammate> {
+        let revives = self.revive_system.as_ref()?;
+        let player = self.coop_manager.as_ref()?.get_player(slot)?;
+        if player.is_downed || !player.is_active {
+            return None;
+        }
+        let position = self
+            .world
+            .get_component::<Transform>(player.get_entity()?)?
+            .position;
+
+        self.downed_teammates()
+            .into_iter()
+            .find(|&(downed, downed_position)| {
+                downed != Teammate::Player(slot)
+                    && downed_position.distance(position) <= revives.get_revive_range()
+            })
+            .map(|(downed, _)| downed)
+    }
 
-            draw_circle(cloud_x, cloud_y, cloud_r1, Color::new(1.0, 1.0, 1.0, 0.8));
-            draw_circle(
-                cloud_x + cloud_r1 * 0.6,
-                cloud_y + cloud_r1 * 0.25,
-                cloud_r2,
-                Color::new(1.0, 1.0, 1.0, 0.7),
-            );
+    fn stand_up_player(world: &mut World, entity: EntityId, health_fraction: f32) {
+        if let Some(health) = world.get_component_mut::<Health>(entity) {
+            health.current = health.maximum * health_fraction;
         }
+        if let Some(fighter) = world.get_component_mut::<Fighter>(entity) {
+            fighter.state = FighterState::Idle;
+            fighter.hitstun = 0.0;
+            fighter.invulnerable = false;
+        }
+    }
 
-        // Roof edge and railing
-        let edge_y = floor_y - sh * 0.026;
-        draw_rectangle(0.0, edge_y, sw, sh * 0.026, Color::new(0.6, 0.6, 0.6, 1.0));
-        draw_rectangle(
-            0.0,
-            edge_y + sh * 0.013,
-            sw,
-            sh * 0.0065,
-            Color::new(0.8, 0.8, 0.0, 1.0),
+    /// Shared lives plus down/bleed-out markers over downed co-op players
+    fn render_plane_ui(&self, ui: &UiLayout) {
+        let Some(plane) = &self.plane_system else {
+            return;
+        };
+        if !(plane.in_plane || plane.entering_plane || plane.exiting_plane) {
+            return;
+        }
+
+        let panel = ui.anchor(Anchor::TopRight, Vec2::new(250.0, 150.0));
+        let (ui_x, ui_y) = (panel.x, panel.y);
+        draw_text("PLANE MODE", ui_x, ui_y, 20.0, YELLOW);
+        draw_text(
+            &format!("Altitude: {:.0}m", plane.altitude),
+            ui_x,
+            ui_y + 25.0,
+            16.0,
+            WHITE,
+        );
+        draw_text(
+            &format!("Bombs: {}/{}", plane.bombs_remaining, plane.max_bombs),
+            ui_x,
+            ui_y + 45.0,
+            16.0,
+            WHITE,
+        );
+        draw_text(
+            &format!("Duration: {:.1}s", plane.get_duration_remaining()),
+            ui_x,
+            ui_y + 65.0,
+            16.0,
+            WHITE,
         );
+    }
 
-        // Safety fence - resolution friendly
-        let fence_y = sh * 0.59;
-        let fence_h = sh * 0.17;
-        let fence_spacing = sw * 0.09;
-        let fence_count = (sw / fence_spacing).ceil() as i32;
+    fn render_coop_status(&self, layout: &UiLayout) {
+        let (Some(manager), Some(ui)) = (&self.coop_manager, &self.coop_ui) else {
+            return;
+        };
 
-        for i in 0..fence_count {
-            let fence_x = i as f32 * fence_spacing;
-            draw_rectangle(
-                fence_x,
-                fence_y,
-                sw * 0.004,
-                fence_h,
-                Color::new(0.3, 0.3, 0.3, 1.0),
-            );
+        if let Some(lives) = &self.lives_pool {
+            ui.render_lives(lives);
         }
 
-        // Horizontal fence rails
-        draw_rectangle(0.0, fence_y, sw, sh * 0.01, Color::new(0.3, 0.3, 0.3, 1.0));
-        draw_rectangle(
-            0.0,
-            fence_y + fence_h * 0.5,
-            sw,
-            sh * 0.01,
-            Color::new(0.3, 0.3, 0.3, 1.0),
-        );
+        if let Some(revives) = &self.revive_system {
+            let downed = self.downed_teammates();
+            for &(teammate, position) in &downed {
+                let position = layout.to_canvas(self.camera.world_to_screen(position));
+                let (label, color, remaining, progress) = match teammate {
+                    Teammate::Player(slot) => {
+                        let Some(player) = manager.get_player(slot) else {
+                            continue;
+                        };
+                        (
+                            format!("P{}", slot.to_index() + 1),
+                            player.color,
+                            BLEED_OUT_TIME - player.revive_timer,
+                            player.revive_progress,
+                        )
+                    }
+                    Teammate::Ally(ally) => {
+                        let Some(fighter) = self.world.get_component::<Fighter>(ally) else {
+                            continue;
+                        };
+                        (
+                            self.character_display_name(&fighter.character_type, false),
+                            Color::new(0.5, 1.0, 0.6, 1.0),
+                            BLEED_OUT_TIME - revives.ally_down_time(ally).unwrap_or(0.0),
+                            revives.get_active_revive(teammate).map_or(0.0, |(_, p)| p),
+                        )
+                    }
+                };
+                ui.render_downed_marker(
+                    position,
+                    &label,
+                    color,
+                    remaining,
+                    progress,
+                    revives.get_execution_progress(teammate),
+                );
+                ui.render_revive_prompt(revives, teammate);
+            }
 
-        // AC unit - resolution friendly
-        let ac_w = sw * 0.06;
-        let ac_h = sh * 0.13;
-        let ac_x = sw * 0.08;
-        let ac_y = sh * 0.46;
+            // Tell standing players in reach which key picks the teammate up
+            for player in manager.get_active_players() {
+                let Some(target) = self.teammate_to_revive(player.slot) else {
+                    continue;
+                };
+                if revives.is_being_revived(target) {
+                    continue;
+                }
+                // Online, everyone plays on the main side of their keyboard
+                let device = match player.input_device {
+                    InputDevice::Bot(_) => continue,
+                    InputDevice::Network(_) => InputDevice::Keyboard,
+                    device => device,
+                };
+                let key = COOP_DEVICES
+                    .iter()
+                    .find(|(candidate, _)| *candidate == device)
+                    .map_or("Interact", |(_, key)| *key);
+                if let Some(&(_, position)) = downed.iter().find(|(t, _)| *t == target) {
+                    let position = layout.to_canvas(self.camera.world_to_screen(position));
+                    ui.render_revive_hint(position, key, player.color);
+                }
+            }
+        }
 
-        draw_rectangle(ac_x, ac_y, ac_w, ac_h, Color::new(0.7, 0.7, 0.7, 1.0));
-        draw_rectangle(
-            ac_x + ac_w * 0.125,
-            ac_y + ac_h * 0.1,
-            ac_w * 0.75,
-            ac_h * 0.3,
-            Color::new(0.2, 0.2, 0.2, 1.0),
+        if let Some(combo) = &self.shared_combo {
+            ui.render_combo_counter(combo);
+        }
+        if self.human_entities().len() > 1 {
+            ui.render_team_super(manager.get_combo_meter(), TEAM_SUPER_COST);
+        }
+    }
+
+    /// Online, tell the player when the game is held up by their partner
+    fn render_net_status(&self, ui: &UiLayout) {
+        if !self.net_waiting {
+            return;
+        }
+
+        let text = "Waiting for partner...";
+        let dims = measure_text(text, None, 28, 1.0);
+        let center = ui.anchor(Anchor::Center, Vec2::ZERO);
+        draw_text(
+            text,
+            center.x - dims.width * 0.5,
+            center.y,
+            28.0,
+            Color::new(1.0, 1.0, 1.0, 0.8),
         );
+    }
 
-        // Water tower - resolution friendly
-        let tower_w = sw * 0.095;
-        let tower_h = sh * 0.2;
-        let tower_x = sw - tower_w - sw * 0.04;
-        let tower_y = sh * 0.39;
+    /// Which free keyboard seats can still drop in
+    fn render_join_hint(&self, ui: &UiLayout) {
+        let free: Vec<&str> = COOP_DEVICES
+            .iter()
+            .skip(1)
+            .filter(|(device, _)| !matches!(device, InputDevice::Gamepad(_)))
+            .filter(|(device, _)| {
+                let manager = self.coop_manager.as_ref();
+                manager.and_then(|m| m.slot_for_device(*device)).is_none()
+            })
+            .map(|&(_, key)| key)
+            .collect();
+        if free.is_empty() {
+            return;
+        }
 
-        draw_rectangle(tower_x, tower_y, tower_w, tower_h, Color::new(0.8, 0.8, 0.8, 1.0));
-        draw_circle(
-            tower_x + tower_w * 0.5,
-            tower_y + tower_h * 0.5,
-            tower_w * 0.27,
-            Color::new(0.6, 0.6, 0.6, 1.0),
+        let text = format!("Press {} to join", free.join(" or "));
+        let dims = measure_text(&text, None, 18, 1.0);
+        let bottom = ui.anchor(Anchor::Bottom, Vec2::new(0.0, 12.0));
+        draw_text(
+            &text,
+            bottom.x - dims.width * 0.5,
+            bottom.y,
+            18.0,
+            Color::new(1.0, 1.0, 1.0, 0.5),
         );
     }
 
-    fn render_character(&self, base_pos: Vec2, fighter: &Fighter, is_player: bool) {
-        let state = fighter.state;
-        let time = get_time() as f32;
-        let attack_phase = Self::attack_phase(state, fighter.attack_timer);
-        let bob = if matches!(state, FighterState::Walking) {
-            (time * 8.0).sin() * 2.0
-        } else {
-            0.0
+    fn render_particles(&self) {
+        let Some(particles) = self.systems.get::<ParticleSystem>() else {
+            return;
         };
-        let sway = match state {
-            FighterState::LightAttack
-            | FighterState::HeavyAttack
-            | FighterState::Special
-            | FighterState::Super => fighter.facing * (6.0 + 8.0 * attack_phase),
-            FighterState::Hitstun => -fighter.facing * 4.0,
-            FighterState::Dodging => fighter.facing * (time * 14.0).cos() * 3.0,
-            _ => 0.0,
+        for particle in particles.particles() {
+            let pos = particle.position;
+            let color = particle.color();
+            let radius = particle.size() * 0.5;
+
+            match particle.particle_type {
+                ParticleType::Spark => {
+                    let tail = pos - particle.velocity.normalize_or_zero() * radius * 3.0;
+                    draw_line(pos.x, pos.y, tail.x, tail.y, radius.max(1.0), color);
+                }
+                ParticleType::Energy => {
+                    // Soft halo under a bright core
+                    draw_circle(
+                        pos.x,
+                        pos.y,
+                        radius * 1.8,
+                        Color::new(color.r, color.g, color.b, color.a * 0.3),
+                    );
+                    draw_circle(pos.x, pos.y, radius, color);
+                }
+                _ => draw_circle(pos.x, pos.y, radius, color),
+            }
+        }
+    }
+
+    fn spawn_attack_particles(&mut self, center: Vec2, state: FighterState) {
+        let (count, base_color, lifetime, size_start) = match state {
+            FighterState::LightAttack => (6, Color::new(1.0, 0.85, 0.4, 0.9), 0.25, 12.0),
+            FighterState::HeavyAttack => (10, Color::new(1.0, 0.55, 0.35, 0.95), 0.3, 14.0),
+            FighterState::Special => (14, Color::new(0.45, 0.8, 1.0, 0.95), 0.35, 16.0),
+            FighterState::Super => (20, Color::new(0.85, 0.4, 1.0, 1.0), 0.4, 18.0),
+            _ => (6, Color::new(1.0, 0.85, 0.4, 0.9), 0.25, 12.0),
         };
 
-        let pos = base_pos + Vec2::new(sway, bob);
-
-        // Try to render with enhanced sprites first
-        let char_name = if is_player {
-            match self.selected_character {
-                CharacterId::Bas => "bas",
-                CharacterId::Berkay => "berkay",
-                CharacterId::Gefferinho => "gefferinho",
-                CharacterId::Hadi => "hadi",
-                CharacterId::Nitin => "nitin",
-                CharacterId::Luca => "luca",
-                CharacterId::PalaBaba => "palababa",
-                CharacterId::Fufinho => "fufinho",
-                CharacterId::EfeAbi => "efeabi",
-                CharacterId::Jad => "jad",
-                CharacterId::Umut => "umut",
-                CharacterId::KeizerBomTaha => "keizer",
-                CharacterId::GoonLordTobese => "tobese",
+        self.world
+            .commands()
+            .spawn()
+            .with(Transform {
+                position: center,
+                rotation: 0.0,
+                scale: Vec2::ONE,
+            })
+            .with(
+                Emitter::burst(count, ParticleType::Energy, ColorRamp::fade(base_color))
+                    .shape(EmitterShape::Circle(22.0))
+                    .speed(140.0, 280.0)
+                    .lifetime(lifetime * 0.6, lifetime * 1.1)
+                    .size(size_start, size_start * 0.25)
+                    .radial_acceleration(-220.0),
+            );
+    }
+
+    fn set_shop_feedback<S: Into<String>>(&mut self, message: S) {
+        self.shop_feedback_message = Some(message.into());
+        self.shop_feedback_timer = 2.5;
+    }
+
+    /// Mastery XP for the selected character, announcing level ups and paying
+    /// out milestone rewards. Practice runs don't count.
+    fn grant_mastery_xp(&mut self, xp: f32, source: &str) {
+        if self.practice.is_some() {
+            return;
+        }
+
+        let level = self.character_mastery.level;
+        self.run_mastery_xp += xp;
+        let rewards = self.character_mastery.add_xp(xp, source);
+        let rank = self.character_mastery.rank;
+        if self.character_mastery.level > level {
+            let title = format!("Level {}", self.character_mastery.level);
+            self.toasts.push("MASTERY LEVEL UP", &title, rank.to_string(), rank.to_color());
+        }
+
+        for reward in rewards {
+            let (title, detail) = reward.describe();
+            self.toasts.push("MASTERY UNLOCK", title, &detail, rank.to_color());
+            // Tokens show in the results' own row
+            if !matches!(reward, MasteryReward::Currency(_)) {
+                self.run_unlocks.push(format!("{}: {}", title, detail));
             }
-        } else {
-            match &fighter.character_type {
-                CharacterType::Bas => "bas",
-                CharacterType::Berkay => "berkay",
-                CharacterType::Gefferinho => "gefferinho",
-                CharacterType::Hadi => "hadi",
-                CharacterType::Nitin => "nitin",
-                CharacterType::Luca => "luca",
-                CharacterType::YigitBaba => "palababa",
-                _ => "bas", // Default to Bas for other character types
+            match reward {
+                MasteryReward::SkillPoints(points) => self.grant_skill_points(points),
+                MasteryReward::Currency(amount) => {
+                    let amount = amount.round() as u32;
+                    self.grant_currency(amount, RewardKind::Mastery, "Mastery milestone");
+                }
+                _ => {}
             }
-        };
+        }
 
-        if let Some(sprite) = self.texture_manager.get_sprite(&format!("character_{}", char_name)) {
-            // Render with enhanced sprite
-            let mut enhanced_sprite = sprite.clone();
-            enhanced_sprite.position = pos - Vec2::new(64.0, 100.0); // Center the sprite
-            enhanced_sprite.size = Vec2::new(128.0, 128.0);
+        self.grant_account_xp(xp * ACCOUNT_XP_SHARE);
+    }
 
-            // Apply combat effects
-            if matches!(state, FighterState::Super) {
-                enhanced_sprite.glow = 1.0;
-                enhanced_sprite.emissive = Color::new(1.0, 0.8, 0.0, 1.0);
-                enhanced_sprite.outline_color = Some(Color::new(1.0, 1.0, 0.0, 0.8));
-            } else if matches!(state, FighterState::Special) {
-                enhanced_sprite.glow = 0.5;
-                enhanced_sprite.emissive = Color::new(0.5, 0.5, 1.0, 0.5);
-            } else if matches!(state, FighterState::Hitstun) {
-                enhanced_sprite.color = Color::new(1.0, 0.5, 0.5, 1.0);
+    /// The account's share of XP, announcing each level and paying out its
+    /// milestone reward
+    fn grant_account_xp(&mut self, xp: f32) {
+        let accent = Color::new(1.0, 0.8, 0.0, 1.0);
+        let levels = self.account.add_xp(xp);
+        self.run_account_xp += xp;
+        for &level in &levels {
+            let Some(reward) = self.account.progression.claim_level_reward(level) else {
+                continue;
+            };
+            let title = format!("Level {}", reward.level);
+            let mut detail = format!("+{:.0} Arc Tokens", reward.currency);
+            if reward.skill_points > 0 {
+                detail = format!("{}, +{} skill points", detail, reward.skill_points);
             }
+            self.toasts.push("ACCOUNT LEVEL UP", &title, &detail, accent);
 
-            // Flip sprite based on facing direction
-            if fighter.facing < 0.0 {
-                enhanced_sprite.size.x *= -1.0;
-                enhanced_sprite.position.x += 128.0;
+            let amount = reward.currency.round() as u32;
+            self.grant_currency(amount, RewardKind::AccountLevel, "Account level reward");
+            if reward.skill_points > 0 {
+                self.grant_skill_points(reward.skill_points);
             }
+            if let Some(title) = reward.title {
+                self.toasts.push("NEW TITLE", title, "Now worn on your banner", accent);
+                self.run_unlocks.push(format!("Title: {}", title));
+            }
+            if let Some(banner) = reward.banner {
+                let color = self.account.progression.banner_color();
+                self.toasts.push("NEW BANNER", banner, "Shown on the results screen", color);
+                self.run_unlocks.push(format!("Banner: {}", banner));
+            }
+        }
+        if !levels.is_empty() {
+            self.save_account();
+        }
+    }
 
-            enhanced_sprite.draw();
+    fn save_account(&self) {
+        if let Err(e) = self.account.save() {
+            crash::log(e.to_string());
+        }
+    }
 
-            // Add dynamic lighting during combat
-            if matches!(state, FighterState::LightAttack | FighterState::HeavyAttack | FighterState::Special | FighterState::Super) {
-                self.add_combat_lighting(pos, state, attack_phase);
+    /// Points to spend on the character's skill tree, kept between runs
+    fn grant_skill_points(&mut self, points: u32) {
+        self.skill_tree_manager.add_skill_points(self.selected_character, points);
+        if let Err(e) = self.skill_tree_manager.save() {
+            crash::log(e.to_string());
+        }
+    }
 
-                // Add spectacular VFX for attacks
-                self.add_combat_vfx(pos, state, attack_phase, fighter.facing);
+    /// Whether a skill tree cancel route lets `next` interrupt `current`
+    fn cancels_into(&self, current: FighterState, next: FighterState) -> bool {
+        self.skill_bonuses.cancel_routes.iter().any(|route| match route {
+            CancelRoute::LightIntoHeavy => {
+                current == FighterState::LightAttack && next == FighterState::HeavyAttack
+            }
+            CancelRoute::HeavyIntoSpecial => {
+                current == FighterState::HeavyAttack && next == FighterState::Special
             }
+        })
+    }
 
-            return;
+    /// The ability's call-out, taking turns with the lines mastery unlocked
+    fn next_voice_line(&mut self, own: &str) -> String {
+        let unlocked = &self.character_mastery.unlocked_voice_lines;
+        let turn = self.voice_line_turn % (unlocked.len() + 1);
+        self.voice_line_turn += 1;
+        match turn {
+            0 => own.to_string(),
+            _ => unlocked[turn - 1].clone(),
         }
+    }
 
-        // Fallback: Render player with selected character appearance using primitives
-        if is_player {
-            self.render_selected_character(pos, state, attack_phase, fighter.facing);
+    /// Master rank's signature move: a burst hitting every enemy around the
+    /// player on top of the ability
+    fn signature_move(&mut self, player: EntityId) {
+        use crate::render::enhanced_vfx::ImpactType;
+
+        let Some(origin) = self.world.get_component::<Transform>(player).map(|t| t.position) else {
             return;
+        };
+        for &enemy in &self.enemy_entities {
+            let Some(position) = self.world.get_component::<Transform>(enemy).map(|t| t.position)
+            else {
+                continue;
+            };
+            if origin.distance(position) > SIGNATURE_RADIUS {
+                continue;
+            }
+
+            if let Some(health) = self.world.get_component_mut::<Health>(enemy) {
+                health.current = (health.current - SIGNATURE_DAMAGE).max(0.0);
+            }
+            self.enhanced_vfx
+                .spawn_impact(position, Vec2::new(0.0, -1.0), ImpactType::Critical);
+            self.enhanced_vfx.show_damage_number(position, SIGNATURE_DAMAGE, true);
         }
 
-        match &fighter.character_type {
-            CharacterType::Bas => {
-                // HEAD with depth and shading
-                draw_circle(
-                    pos.x + 1.5,
-                    pos.y - 43.0,
-                    26.0,
-                    Color::new(0.7, 0.6, 0.5, 0.5),
-                ); // Shadow
-                draw_circle(pos.x, pos.y - 45.0, 26.0, Color::new(0.92, 0.82, 0.72, 1.0)); // Base
-                draw_circle(
-                    pos.x - 6.0,
-                    pos.y - 48.0,
-                    10.0,
-                    Color::new(1.0, 0.9, 0.8, 0.4),
-                ); // Highlight
+        let character = crate::data::characters::Character::get_by_id(self.selected_character);
+        let accent = MasteryRank::Master.to_color();
+        self.toasts.push("SIGNATURE MOVE", character.signature_move, character.name, accent);
+        self.enhanced_vfx.spawn_dynamic_light(origin, accent, 2.0, SIGNATURE_RADIUS, 0.8, false);
+        self.camera.add_trauma(0.7);
+        self.post.trigger(PostMoment::SuperActivation);
+    }
 
-                // NECK
-                draw_rectangle(
-                    pos.x - 8.0,
-                    pos.y - 22.0,
-                    16.0,
-                    10.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
+    /// Pay out Arc Tokens, boosted by any bonus event running right now.
+    /// Practice runs earn nothing.
+    fn grant_currency(&mut self, amount: u32, kind: RewardKind, reason: &str) {
+        if amount == 0 || self.practice.is_some() {
+            return;
+        }
 
-                // TORSO with blue shirt and depth
-                draw_rectangle(
-                    pos.x - 22.0,
-                    pos.y - 18.0,
-                    44.0,
-                    52.0,
-                    Color::new(0.15, 0.2, 0.6, 1.0),
-                ); // Shadow layer
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 20.0,
-                    40.0,
-                    50.0,
-                    Color::new(0.2, 0.35, 0.85, 1.0),
-                ); // Main shirt
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 20.0,
-                    40.0,
-                    8.0,
-                    Color::new(0.3, 0.45, 0.95, 1.0),
-                ); // Shoulder highlight
+        let (amount, bonuses) = events::apply_bonuses(amount, kind);
+        if let Err(err) = self.shop_manager.add_currency(amount, reason) {
+            self.set_shop_feedback(format!("Reward rejected: {}", err));
+            return;
+        }
+        self.record_achievement(AchievementEvent::CurrencyEarned(amount));
+        self.run_tokens += amount;
+        let message = if bonuses.is_empty() {
+            format!("{} (+{} Arc Tokens)", reason, amount)
+        } else {
+            format!("{} (+{} Arc Tokens, {})", reason, amount, bonuses.join(", "))
+        };
+        match self.shop_manager.save() {
+            Ok(_) => self.set_shop_feedback(message),
+            Err(err) => self.set_shop_feedback(format!("Currency save failed: {}", err)),
+        }
+    }
 
-                // Collar
-                draw_line(
-                    pos.x - 10.0,
-                    pos.y - 20.0,
-                    pos.x,
-                    pos.y - 10.0,
-                    3.0,
-                    Color::new(0.15, 0.25, 0.7, 1.0),
-                );
-                draw_line(
-                    pos.x + 10.0,
-                    pos.y - 20.0,
-                    pos.x,
-                    pos.y - 10.0,
-                    3.0,
-                    Color::new(0.15, 0.25, 0.7, 1.0),
-                );
+    /// Count `event` towards achievements, announcing and paying out whatever
+    /// it unlocks. Practice runs don't count.
+    fn record_achievement(&mut self, event: AchievementEvent) {
+        if self.practice.is_some() {
+            return;
+        }
+        for achievement in self.achievement_manager.record(event) {
+            let accent = achievement.difficulty.to_color();
+            self.toasts
+                .push("ACHIEVEMENT UNLOCKED", &achievement.name, &achievement.description, accent);
+            self.run_unlocks.push(format!("Achievement: {}", achievement.name));
+            match achievement.reward {
+                AchievementReward::Currency(amount) => {
+                    let reason = format!("Achievement: {}", achievement.name);
+                    self.grant_currency(amount.round() as u32, RewardKind::Achievement, &reason);
+                }
+                AchievementReward::SkillPoints(points) => self.grant_skill_points(points),
+                AchievementReward::Title(title) => {
+                    if !self.character_mastery.unlocked_titles.contains(&title) {
+                        self.character_mastery.unlocked_titles.push(title);
+                    }
+                }
+                AchievementReward::Skin(skin) => {
+                    if !self.character_mastery.unlocked_skins.contains(&skin) {
+                        self.character_mastery.unlocked_skins.push(skin);
+                    }
+                }
+                AchievementReward::None => {}
+            }
+        }
+    }
 
-                // Shirt buttons
-                for i in 0..3 {
-                    let button_y = pos.y - 8.0 + i as f32 * 12.0;
-                    draw_circle(pos.x, button_y, 2.5, Color::new(0.9, 0.9, 0.9, 1.0));
-                    draw_circle(pos.x, button_y, 1.5, Color::new(0.15, 0.2, 0.6, 1.0));
+    /// Count `progress` towards the board's challenges, announcing and paying
+    /// out the ones it completes. Practice runs don't count.
+    fn record_challenge(&mut self, challenge_type: ChallengeType, progress: f32) {
+        if self.practice.is_some() {
+            return;
+        }
+        for challenge in self.challenge_manager.update_progress(&challenge_type, progress) {
+            let accent = challenge.difficulty.to_color();
+            self.toasts
+                .push("CHALLENGE COMPLETE", &challenge.name, &challenge.description, accent);
+            self.grant_challenge_reward(challenge.reward, &challenge.name);
+        }
+    }
+
+    fn grant_challenge_reward(&mut self, reward: ChallengeReward, name: &str) {
+        let reason = format!("Challenge: {}", name);
+        match reward {
+            ChallengeReward::Currency(amount) => {
+                let amount = amount * self.challenge_manager.get_streak_bonus();
+                self.grant_currency(amount.round() as u32, RewardKind::Challenge, &reason);
+            }
+            ChallengeReward::XP(xp) => self.grant_mastery_xp(xp, &reason),
+            ChallengeReward::SkillPoints(points) => self.grant_skill_points(points),
+            ChallengeReward::Cosmetic(title) => {
+                if !self.character_mastery.unlocked_titles.contains(&title) {
+                    self.character_mastery.unlocked_titles.push(title);
+                }
+            }
+            ChallengeReward::Multiple(rewards) => {
+                for reward in rewards {
+                    self.grant_challenge_reward(reward, name);
                 }
+            }
+        }
+    }
 
-                // PANTS with shading
-                draw_rectangle(
-                    pos.x - 16.0,
-                    pos.y + 32.0,
-                    32.0,
-                    42.0,
-                    Color::new(0.25, 0.25, 0.35, 1.0),
-                ); // Shadow
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y + 30.0,
-                    30.0,
-                    40.0,
-                    Color::new(0.35, 0.35, 0.45, 1.0),
-                ); // Main pants
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y + 30.0,
-                    30.0,
-                    6.0,
-                    Color::new(0.45, 0.45, 0.55, 1.0),
-                ); // Waist highlight
-
-                // Belt
-                draw_rectangle(
-                    pos.x - 16.0,
-                    pos.y + 28.0,
-                    32.0,
-                    5.0,
-                    Color::new(0.2, 0.15, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 4.0,
-                    pos.y + 27.0,
-                    8.0,
-                    7.0,
-                    Color::new(0.6, 0.5, 0.3, 1.0),
-                ); // Buckle
-                draw_rectangle(
-                    pos.x - 2.0,
-                    pos.y + 28.0,
-                    4.0,
-                    5.0,
-                    Color::new(0.4, 0.3, 0.2, 1.0),
-                );
+    fn save_challenges(&self) {
+        if let Err(e) = self.challenge_manager.save() {
+            crash::log(e.to_string());
+        }
+    }
 
-                // LEGS with depth
-                draw_rectangle(
-                    pos.x - 11.0,
-                    pos.y + 72.0,
-                    10.0,
-                    22.0,
-                    Color::new(0.25, 0.25, 0.35, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 1.0,
-                    pos.y + 72.0,
-                    10.0,
-                    22.0,
-                    Color::new(0.25, 0.25, 0.35, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.35, 0.35, 0.45, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.35, 0.35, 0.45, 1.0),
-                );
+    /// The achievements and challenges that look at whole runs, then save
+    /// them all
+    fn record_run_achievements(&mut self, cleared: bool) {
+        if self.practice.is_some() {
+            return;
+        }
+        let coop = self.coop_manager.is_some();
+        if cleared && coop {
+            self.record_challenge(ChallengeType::WinCoopGame, 1.0);
+        }
+        if cleared && !self.run_damage_taken {
+            self.record_challenge(ChallengeType::FlawlessVictory, 1.0);
+        }
+        self.save_challenges();
+        self.record_achievement(AchievementEvent::RunFinished(RunSummary {
+            cleared,
+            coop,
+            solo: !coop && !self.run_had_allies,
+            died: !cleared || self.run_went_down,
+            took_damage: self.run_damage_taken,
+        }));
+        let character = crate::data::CHARACTERS.iter().find(|c| c.id == self.selected_character);
+        if let Some(character) = character {
+            self.record_achievement(AchievementEvent::MasteryLevel {
+                character: character.name.to_string(),
+                level: self.character_mastery.level,
+            });
+        }
+        if let Err(e) = self.achievement_manager.save() {
+            crash::log(e.to_string());
+        }
 
-                // SHOES with detail
-                draw_ellipse(
-                    pos.x - 6.0,
-                    pos.y + 92.0,
-                    10.0,
-                    6.0,
-                    0.0,
-                    Color::new(0.15, 0.15, 0.15, 1.0),
-                );
-                draw_ellipse(
-                    pos.x + 6.0,
-                    pos.y + 92.0,
-                    10.0,
-                    6.0,
-                    0.0,
-                    Color::new(0.15, 0.15, 0.15, 1.0),
-                );
-                draw_ellipse(
-                    pos.x - 6.0,
-                    pos.y + 91.0,
-                    9.0,
-                    5.0,
-                    0.0,
-                    Color::new(0.25, 0.25, 0.25, 1.0),
-                );
-                draw_ellipse(
-                    pos.x + 6.0,
-                    pos.y + 91.0,
-                    9.0,
-                    5.0,
-                    0.0,
-                    Color::new(0.25, 0.25, 0.25, 1.0),
-                );
+        // XP already went in as it was earned
+        let account = &mut self.account.progression;
+        account.record_game(cleared, 0.0, self.run_tokens as f32);
+        account.add_playtime(std::mem::take(&mut self.run_time) as u64);
+        if !cleared || self.run_went_down {
+            account.total_deaths += 1;
+        }
+        self.save_account();
+    }
 
-                // ARMS with muscles and skin tone
-                let arm_idle = Vec2::new(15.0, (time * 12.0).cos() * 1.5);
-                let arm_offset = match state {
-                    FighterState::LightAttack
-                    | FighterState::HeavyAttack
-                    | FighterState::Special
-                    | FighterState::Super => {
-                        Vec2::new(28.0 + 40.0 * attack_phase, -12.0 - 24.0 * attack_phase)
-                    }
-                    _ => arm_idle,
-                };
+    /// Hold a solo run the player has left, by leaving the window or not
+    /// touching anything, and count down once they're back. Online and co-op
+    /// runs carry on for the others.
+    fn update_auto_pause(&mut self) {
+        let focus_lost = crate::data::take_window_away();
+        let solo = self.net_session.is_none() && self.coop_manager.is_none();
+        if !solo || self.game_over || (self.paused && !self.auto_pause.holding()) {
+            return;
+        }
 
-                // Left arm (back)
-                draw_rectangle(
-                    pos.x - 26.0,
-                    pos.y - 9.0,
-                    10.0,
-                    32.0,
-                    Color::new(0.82, 0.72, 0.62, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 10.0,
-                    8.0,
-                    8.0,
-                    Color::new(1.0, 0.9, 0.8, 0.6),
-                ); // Highlight
-
-                // Right arm (front, animated)
-                draw_rectangle(
-                    pos.x + 18.0,
-                    pos.y - 9.0 + arm_offset.y,
-                    10.0,
-                    32.0,
-                    Color::new(0.82, 0.72, 0.62, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 17.0,
-                    pos.y - 10.0 + arm_offset.y,
-                    8.0,
-                    30.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 17.0,
-                    pos.y - 10.0 + arm_offset.y,
-                    8.0,
-                    8.0,
-                    Color::new(1.0, 0.9, 0.8, 0.6),
-                );
+        let input = self.host.touched();
+        self.auto_pause.update(self.host.frame_time(), input, focus_lost);
+        self.paused = self.auto_pause.holding();
+        crate::data::set_away_muted(self.auto_pause.mutes());
+    }
 
-                // HANDS/FISTS
-                if matches!(
-                    state,
-                    FighterState::LightAttack
-                        | FighterState::HeavyAttack
-                        | FighterState::Special
-                        | FighterState::Super
-                ) {
-                    draw_circle(
-                        pos.x + 26.0 + arm_offset.x,
-                        pos.y + arm_offset.y + 2.0,
-                        10.0,
-                        Color::new(0.82, 0.72, 0.62, 1.0),
-                    );
-                    draw_circle(
-                        pos.x + 25.0 + arm_offset.x,
-                        pos.y + arm_offset.y,
-                        9.0,
-                        Color::new(0.9, 0.8, 0.7, 1.0),
-                    );
-                    self.render_attack_slash(
-                        pos + Vec2::new(25.0 + arm_offset.x, arm_offset.y),
-                        fighter,
-                        attack_phase,
-                    );
-                } else {
-                    draw_circle(
-                        pos.x + 17.0,
-                        pos.y + 22.0,
-                        6.0,
-                        Color::new(0.9, 0.8, 0.7, 1.0),
-                    );
-                    draw_circle(
-                        pos.x - 25.0,
-                        pos.y + 22.0,
-                        6.0,
-                        Color::new(0.9, 0.8, 0.7, 1.0),
-                    );
-                }
+    /// Ranks of a shop upgrade the run starts with: everything owned, or
+    /// for practice only what the loadout brings. Debug loadouts can bring
+    /// upgrades that were never bought, at rank 1.
+    fn upgrade_rank(&self, upgrade: UpgradeId) -> u32 {
+        let owned = self.shop_manager.rank(upgrade);
+        match &self.practice {
+            Some(start) if start.upgrades.contains(&upgrade) => owned.max(1),
+            Some(_) => 0,
+            None => owned,
+        }
+    }
 
-                // FACE DETAILS
-                // Eyes
-                draw_circle(
-                    pos.x - 9.0,
-                    pos.y - 47.0,
-                    5.0,
-                    Color::new(1.0, 1.0, 1.0, 1.0),
-                );
-                draw_circle(
-                    pos.x + 9.0,
-                    pos.y - 47.0,
-                    5.0,
-                    Color::new(1.0, 1.0, 1.0, 1.0),
-                );
-                draw_circle(
-                    pos.x - 8.0,
-                    pos.y - 46.0,
-                    3.5,
-                    Color::new(0.15, 0.3, 0.5, 1.0),
-                );
-                draw_circle(
-                    pos.x + 8.0,
-                    pos.y - 46.0,
-                    3.5,
-                    Color::new(0.15, 0.3, 0.5, 1.0),
-                );
-                draw_circle(
-                    pos.x - 7.0,
-                    pos.y - 47.0,
-                    1.5,
-                    Color::new(1.0, 1.0, 1.0, 0.8),
-                ); // Glint
-                draw_circle(
-                    pos.x + 9.0,
-                    pos.y - 47.0,
-                    1.5,
-                    Color::new(1.0, 1.0, 1.0, 0.8),
-                );
-
-                // Eyebrows
-                draw_line(
-                    pos.x - 12.0,
-                    pos.y - 52.0,
-                    pos.x - 5.0,
-                    pos.y - 53.0,
-                    2.0,
-                    Color::new(0.3, 0.25, 0.2, 1.0),
-                );
-                draw_line(
-                    pos.x + 12.0,
-                    pos.y - 52.0,
-                    pos.x + 5.0,
-                    pos.y - 53.0,
-                    2.0,
-                    Color::new(0.3, 0.25, 0.2, 1.0),
-                );
+    /// Move to the practice start: its map, with the waves before the chosen
+    /// one counted as cleared
+    fn start_practice(&mut self, start: &PracticeStart) {
+        let Some(map) = MapType::from_layout(start.map) else {
+            return;
+        };
+        self.current_map = map;
+        self.current_wave = (start.wave as usize).saturating_sub(1);
+        if !self.visited_maps.contains(&map) {
+            self.visited_maps.push(map);
+        }
+        self.map_system = MapSystem::new(start.map);
+    }
 
-                // Nose
-                draw_line(
-                    pos.x,
-                    pos.y - 44.0,
-                    pos.x + 2.0,
-                    pos.y - 40.0,
-                    2.0,
-                    Color::new(0.82, 0.72, 0.62, 1.0),
-                );
+    /// Remember how far regular runs get, which is where practice may start
+    fn record_reached_wave(&mut self) {
+        if self.practice.is_some() {
+            return;
+        }
+        let map = self.current_map.layout();
+        if self.practice_progress.record(map, self.current_wave as u32) {
+            if let Err(e) = self.practice_progress.save() {
+                crash::log(format!("Failed to save practice progress: {}", e));
+            }
+        }
+    }
 
-                // Mouth
-                let mouth_curve_y = if matches!(state, FighterState::Hitstun) {
-                    -36.0
-                } else {
-                    -38.0
-                };
-                draw_line(
-                    pos.x - 6.0,
-                    pos.y + mouth_curve_y,
-                    pos.x + 6.0,
-                    pos.y + mouth_curve_y,
-                    2.0,
-                    Color::new(0.6, 0.3, 0.3, 1.0),
-                );
+    fn apply_initial_upgrades(&mut self) {
+        let stats = &self.player_moveset.stats;
+        self.player_move_speed = stats.base_speed;
+        self.player_max_health = stats.max_health * STAGE_SCALE;
+        self.player_attack_multiplier = stats.base_damage;
+
+        // A Winter Arc run starts without the shop's upgrades
+        if self.winter_arc.is_none() {
+            let speed = stacked_value(SHOP_SPEED, self.upgrade_rank(UpgradeId::SpeedBoost));
+            let health = stacked_value(SHOP_HEALTH, self.upgrade_rank(UpgradeId::HealthBoost));
+            let attack = stacked_value(SHOP_ATTACK, self.upgrade_rank(UpgradeId::AttackBoost));
+            self.player_move_speed += speed;
+            self.player_max_health += health;
+            self.player_attack_multiplier += attack;
+        }
 
-                // HAIR with volume
-                draw_circle(
-                    pos.x - 10.0,
-                    pos.y - 60.0,
-                    12.0,
-                    Color::new(0.35, 0.28, 0.22, 1.0),
-                );
-                draw_circle(
-                    pos.x + 8.0,
-                    pos.y - 62.0,
-                    11.0,
-                    Color::new(0.35, 0.28, 0.22, 1.0),
-                );
-                draw_circle(pos.x, pos.y - 65.0, 13.0, Color::new(0.35, 0.28, 0.22, 1.0));
-                draw_circle(
-                    pos.x - 15.0,
-                    pos.y - 55.0,
-                    10.0,
-                    Color::new(0.35, 0.28, 0.22, 1.0),
-                );
-                draw_circle(
-                    pos.x + 13.0,
-                    pos.y - 57.0,
-                    9.0,
-                    Color::new(0.35, 0.28, 0.22, 1.0),
-                );
+        // Apply skill tree bonuses; the starting meter and cancel routes are
+        // picked up once the player is on the stage
+        let skill_bonuses = self.skill_tree_manager.calculate_bonuses(self.selected_character);
+        self.player_attack_multiplier *= 1.0 + skill_bonuses.damage_multiplier;
+        self.player_max_health *= 1.0 + skill_bonuses.max_health_multiplier;
+        self.player_move_speed *= 1.0 + skill_bonuses.movement_speed;
+        self.skill_bonuses = skill_bonuses;
+
+        // Then the run's unlocks and head start
+        if let Some(run) = &self.winter_arc {
+            self.player_max_health += run.starting_health_bonus();
+            let enemy_damage = run.enemy_damage_scale();
+            for upgrade in run.upgrades().to_vec() {
+                self.apply_run_upgrade(upgrade);
+            }
+            if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+                combat.set_enemy_attack_multiplier(enemy_damage);
+            }
+        }
 
-                // Hair highlights
-                draw_circle(
-                    pos.x - 5.0,
-                    pos.y - 63.0,
-                    5.0,
-                    Color::new(0.45, 0.38, 0.32, 0.6),
-                );
+        if self.challenge_run.is_some() {
+            self.player_attack_multiplier *= self.challenge_scale(ChallengeModifier::damage_scale);
+            let enemy_damage = self.challenge_scale(ChallengeModifier::enemy_damage_scale);
+            if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+                combat.set_enemy_attack_multiplier(enemy_damage);
             }
-            CharacterType::Wolters => {
-                draw_circle(pos.x, pos.y - 45.0, 28.0, Color::new(0.9, 0.7, 0.6, 1.0));
+        }
 
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 20.0,
-                    50.0,
-                    55.0,
-                    Color::new(0.4, 0.4, 0.4, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y - 15.0,
-                    30.0,
-                    8.0,
-                    Color::new(0.8, 0.2, 0.2, 1.0),
-                );
+        self.apply_upgrade_modifiers();
+    }
 
-                draw_rectangle(
-                    pos.x - 18.0,
-                    pos.y + 35.0,
-                    36.0,
-                    40.0,
-                    Color::new(0.2, 0.2, 0.3, 1.0),
-                );
+    /// Ability timing and hit bonuses: owned shop upgrades on top of the skill
+    /// tree's, each added together and then held to its cap. Like the other
+    /// shop upgrades they sit out Winter Arc runs.
+    fn apply_upgrade_modifiers(&mut self) {
+        let owned = |upgrade, value| match self.winter_arc {
+            Some(_) => 0.0,
+            None => stacked_value(value, self.upgrade_rank(upgrade)),
+        };
+        let bonuses = &self.skill_bonuses;
+
+        // The skill tree counts in percent, and its duration as a share of the base
+        let cooldown_reduction = bonuses.cooldown_reduction / 100.0
+            + owned(UpgradeId::CooldownReduction, SHOP_COOLDOWN_REDUCTION);
+        let character = crate::data::Character::get_by_id(self.ability_state.character_id);
+        let duration_bonus = character.duration * bonuses.ability_duration
+            + owned(UpgradeId::AbilityDuration, SHOP_DURATION_BONUS);
+
+        let modifiers = HitModifiers {
+            crit_chance: bonuses.crit_chance / 100.0
+                + owned(UpgradeId::CriticalChance, SHOP_CRIT_CHANCE),
+            crit_multiplier: HitModifiers::default().crit_multiplier + bonuses.crit_damage,
+            lifesteal: bonuses.lifesteal / 100.0 + owned(UpgradeId::LifeSteal, SHOP_LIFESTEAL),
+            damage_reduction: bonuses.damage_reduction / 100.0
+                + owned(UpgradeId::DamageReduction, SHOP_DAMAGE_REDUCTION),
+        }
+        .capped();
 
-                draw_rectangle(
-                    pos.x - 12.0,
-                    pos.y + 75.0,
-                    10.0,
-                    25.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 75.0,
-                    10.0,
-                    25.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
+        self.ability_state.set_upgrades(cooldown_reduction, duration_bonus);
+        self.hit_modifiers = modifiers;
+        if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+            combat.set_player_modifiers(modifiers);
+        }
+    }
 
-                draw_rectangle(
-                    pos.x - 30.0,
-                    pos.y - 10.0,
-                    10.0,
-                    35.0,
-                    Color::new(0.9, 0.7, 0.6, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 20.0,
-                    pos.y - 10.0,
-                    10.0,
-                    35.0,
-                    Color::new(0.9, 0.7, 0.6, 1.0),
-                );
+    /// Work the run's stats out again after a rank was bought or sold
+    fn refresh_upgrades(&mut self) {
+        self.apply_initial_upgrades();
+        self.sync_player_stats();
+    }
 
-                draw_circle(
-                    pos.x - 10.0,
-                    pos.y - 48.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x + 10.0,
-                    pos.y - 48.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
+    fn apply_upgrade_effect(&mut self, upgrade: UpgradeId) {
+        self.refresh_upgrades();
+        let message = match upgrade {
+            UpgradeId::AttackBoost => "Attack power increased!",
+            UpgradeId::HealthBoost => "Health increased!",
+            UpgradeId::SpeedBoost => "Movement speed increased!",
+            UpgradeId::CooldownReduction => "Ability cooldown reduced!",
+            UpgradeId::AbilityDuration => "Ability lasts longer!",
+            UpgradeId::LifeSteal => "Your hits heal you more!",
+            UpgradeId::DamageReduction => "Incoming damage reduced!",
+            UpgradeId::CriticalChance => "Critical hits land more often!",
+            // Drawn during Winter Arc runs, never bought
+            UpgradeId::WarmBlanket | UpgradeId::ColdBlood | UpgradeId::FrostGuard => return,
+        };
+        let rank = self.shop_manager.rank(upgrade);
+        self.set_shop_feedback(format!("{} Rank {}/{}", message, rank, upgrade.max_rank()));
+    }
 
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y - 35.0,
-                    20.0,
-                    3.0,
-                    Color::new(0.5, 0.4, 0.3, 1.0),
-                );
+    /// One stack of a Winter Arc upgrade. Unlike the shop's these add up, and
+    /// they last only for the run.
+    fn apply_run_upgrade(&mut self, upgrade: UpgradeId) {
+        match upgrade {
+            UpgradeId::AttackBoost => self.player_attack_multiplier *= 1.15,
+            UpgradeId::HealthBoost => self.player_max_health += 20.0,
+            UpgradeId::SpeedBoost => self.player_move_speed *= 1.08,
+            UpgradeId::ColdBlood => {
+                self.player_attack_multiplier *= 1.3;
+                self.player_max_health = (self.player_max_health - 10.0).max(10.0);
+            }
+            // Warm Blanket and Frost Guard are read off the run when a wave
+            // ends and when enemies hit; the rest never come up in a run
+            _ => {}
+        }
+    }
 
-                draw_circle(
-                    pos.x - 30.0,
-                    pos.y - 45.0,
-                    8.0,
-                    Color::new(0.7, 0.5, 0.4, 1.0),
-                );
-                draw_circle(
-                    pos.x + 30.0,
-                    pos.y - 45.0,
-                    8.0,
-                    Color::new(0.7, 0.5, 0.4, 1.0),
-                );
+    /// Take the card, then carry on with the room or on to the next one
+    fn pick_upgrade(&mut self, index: usize) {
+        let Some(offer) = self.winter_arc.as_mut().and_then(|run| run.take(index)) else {
+            return;
+        };
+        self.apply_upgrade_offer(offer);
+        self.sync_player_stats();
 
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y - 30.0,
-                    20.0,
-                    2.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-            }
-            CharacterType::Luca => {
-                draw_circle(pos.x, pos.y - 45.0, 24.0, Color::new(0.95, 0.85, 0.75, 1.0));
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 20.0,
-                    40.0,
-                    50.0,
-                    Color::new(0.1, 0.6, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y + 30.0,
-                    30.0,
-                    40.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.3, 0.3, 0.3, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.3, 0.3, 0.3, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.95, 0.85, 0.75, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 17.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.95, 0.85, 0.75, 1.0),
-                );
-                draw_circle(
-                    pos.x - 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.1, 0.3, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x + 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.1, 0.3, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 18.0,
-                    pos.y - 62.0,
-                    36.0,
-                    4.0,
-                    Color::new(0.6, 0.5, 0.4, 1.0),
-                );
+        if self.current_wave < self.map_waves() {
+            self.start_wave();
+            return;
+        }
+        let next = if self.winter_arc_rooms.is_empty() {
+            MapType::Rooftop
+        } else {
+            self.winter_arc_rooms.remove(0)
+        };
+        self.transition_to_map(next, RouteModifier::None);
+    }
+
+    fn apply_upgrade_offer(&mut self, offer: UpgradeOffer) {
+        for _ in 0..offer.stacks() {
+            self.apply_run_upgrade(offer.upgrade);
+        }
+
+        let title = RunUpgrade::get(offer.upgrade).map_or("Upgrade", |upgrade| upgrade.title);
+        match offer.curse {
+            Some(curse) => {
+                if curse == Curse::Frostbite {
+                    let scale = self.winter_arc.as_ref().map_or(1.0, |run| run.max_health_scale());
+                    self.player_max_health *= scale;
+                }
+                self.set_shop_feedback(format!("{} x2, cursed with {}", title, curse.name()));
             }
-            CharacterType::Hadi => {
-                draw_circle(pos.x, pos.y - 45.0, 23.0, Color::new(0.85, 0.75, 0.65, 1.0));
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 20.0,
-                    40.0,
-                    50.0,
-                    Color::new(0.8, 0.4, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y + 30.0,
-                    30.0,
-                    40.0,
-                    Color::new(0.4, 0.4, 0.5, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.85, 0.75, 0.65, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 17.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.85, 0.75, 0.65, 1.0),
-                );
-                draw_circle(
-                    pos.x - 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.3, 0.2, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x + 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.3, 0.2, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y - 60.0,
-                    30.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
+            None => self.set_shop_feedback(format!("{} taken", title)),
+        }
+
+        let enemy_damage = self.winter_arc.as_ref().map_or(1.0, |run| run.enemy_damage_scale());
+        if let Some(combat) = self.systems.get_mut::<CombatSystem>() {
+            combat.set_enemy_attack_multiplier(enemy_damage);
+        }
+    }
+
+    /// Carry changed stats over to the spawned player
+    fn sync_player_stats(&mut self) {
+        self.set_player_attack_multiplier(self.player_attack_multiplier);
+        let max_health = self.player_max_health;
+        if let Some(health) = self
+            .player_entity
+            .and_then(|player| self.world.get_component_mut::<Health>(player))
+        {
+            let gained = (max_health - health.maximum).max(0.0);
+            health.maximum = max_health;
+            health.current = (health.current + gained).min(health.maximum);
+        }
+    }
+
+    fn upgrade_choice_open(&self) -> bool {
+        self.winter_arc.as_ref().is_some_and(|run| !run.offers().is_empty())
+    }
+
+    fn handle_upgrade_choice(&mut self) {
+        let count = self.winter_arc.as_ref().map_or(0, |run| run.offers().len());
+        let last = count.saturating_sub(1);
+        let keys = self.keys;
+        if self.host.key_pressed(KeyCode::Left) || self.host.key_pressed(keys.move_left) {
+            self.upgrade_choice_selected = self.upgrade_choice_selected.saturating_sub(1);
+        }
+        if self.host.key_pressed(KeyCode::Right) || self.host.key_pressed(keys.move_right) {
+            self.upgrade_choice_selected = (self.upgrade_choice_selected + 1).min(last);
+        }
+        for (index, key) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4]
+            .into_iter()
+            .enumerate()
+            .take(count)
+        {
+            if self.host.key_pressed(key) {
+                self.upgrade_choice_selected = index;
             }
-            CharacterType::Berkay => {
-                draw_circle(pos.x, pos.y - 45.0, 25.0, Color::new(0.9, 0.8, 0.7, 1.0));
-                draw_rectangle(
-                    pos.x - 22.0,
-                    pos.y - 20.0,
-                    44.0,
-                    52.0,
-                    Color::new(0.5, 0.5, 0.5, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 16.0,
-                    pos.y + 32.0,
-                    32.0,
-                    38.0,
-                    Color::new(0.1, 0.1, 0.3, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 27.0,
-                    pos.y - 10.0,
-                    9.0,
-                    32.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 18.0,
-                    pos.y - 10.0,
-                    9.0,
-                    32.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_circle(
-                    pos.x - 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_circle(
-                    pos.x + 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 22.0,
-                    pos.y - 63.0,
-                    44.0,
-                    5.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
+        }
+
+        if self.host.key_pressed(KeyCode::Enter) || self.host.key_pressed(keys.confirm) {
+            self.pick_upgrade(self.upgrade_choice_selected);
+        }
+    }
+
+    /// End the run, bank its Frost Shards in the profile and keep the payout
+    /// for the end screen. Nothing else of the run survives it.
+    fn finish_winter_arc(&mut self, cleared: bool) {
+        let Some(run) = self.winter_arc.take() else {
+            return;
+        };
+
+        let mut progress = WinterArcProgress::load();
+        let result = progress.bank(&run, cleared);
+        if let Err(e) = progress.save() {
+            crash::log(format!("Failed to save Winter Arc progress: {}", e));
+        }
+        self.winter_arc_result = Some(result);
+    }
+
+    /// 1-5 buy from the shopkeeper's stock and SHIFT sells an upgrade back;
+    /// H haggles and ENTER sends them off early
+    fn handle_shop_controls(&mut self) {
+        const KEYS: [KeyCode; 5] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+        ];
+        if self.host.key_pressed(KeyCode::H) {
+            self.haggle();
+            return;
+        }
+        if self.host.key_pressed(KeyCode::Enter) {
+            self.set_shop_feedback("Thanks for shopping - here comes the next wave!");
+            self.close_shop_break();
+            return;
+        }
+
+        let selling =
+            self.host.key_down(KeyCode::LeftShift) || self.host.key_down(KeyCode::RightShift);
+        let Some(index) = KEYS.iter().position(|&key| self.host.key_pressed(key)) else {
+            return;
+        };
+        let Some(&stock) = self.keeper_stock.get(index) else {
+            return;
+        };
+        let discount = self.keeper_discount();
+
+        let option = match stock {
+            ShopStock::Upgrade(option) => option,
+            ShopStock::Item(item) => {
+                match self.shop_manager.buy_consumable(item, discount) {
+                    Ok(()) => {
+                        let carried = self.shop_manager.carried(item);
+                        let message = format!("{} bought ({} carried)", item.label(), carried);
+                        self.set_shop_feedback(message);
+                        self.save_shop();
+                    }
+                    Err(err) => self.set_shop_feedback(err),
+                }
+                return;
             }
-            CharacterType::Nitin => {
-                draw_circle(pos.x, pos.y - 45.0, 24.0, Color::new(0.75, 0.6, 0.5, 1.0));
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 20.0,
-                    40.0,
-                    50.0,
-                    Color::new(0.3, 0.3, 0.6, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y + 30.0,
-                    30.0,
-                    40.0,
-                    Color::new(0.5, 0.3, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    8.0,
-                    20.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.75, 0.6, 0.5, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 17.0,
-                    pos.y - 10.0,
-                    8.0,
-                    30.0,
-                    Color::new(0.75, 0.6, 0.5, 1.0),
-                );
-                draw_circle(
-                    pos.x - 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x + 8.0,
-                    pos.y - 45.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 62.0,
-                    40.0,
-                    4.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
+        };
+
+        // Refunds go by the list price, haggled or not
+        if selling {
+            match self.shop_manager.sell(option.id, option.cost) {
+                Ok(refund) => {
+                    self.refresh_upgrades();
+                    let message = format!("Sold a rank of {} for {}", option.title, refund);
+                    self.set_shop_feedback(message);
+                    self.save_shop();
+                }
+                Err(err) => self.set_shop_feedback(err),
             }
-            CharacterType::PrefectA | CharacterType::PrefectB => {
-                draw_circle(pos.x, pos.y - 45.0, 26.0, Color::new(0.9, 0.8, 0.7, 1.0));
-                draw_rectangle(
-                    pos.x - 23.0,
-                    pos.y - 20.0,
-                    46.0,
-                    52.0,
-                    Color::new(0.2, 0.2, 0.5, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 10.0,
-                    pos.y - 15.0,
-                    20.0,
-                    5.0,
-                    Color::new(0.9, 0.9, 0.0, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 17.0,
-                    pos.y + 32.0,
-                    34.0,
-                    38.0,
-                    Color::new(0.2, 0.2, 0.3, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 11.0,
-                    pos.y + 70.0,
-                    9.0,
-                    22.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    9.0,
-                    22.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 28.0,
-                    pos.y - 10.0,
-                    9.0,
-                    32.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 19.0,
-                    pos.y - 10.0,
-                    9.0,
-                    32.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_circle(
-                    pos.x - 9.0,
-                    pos.y - 47.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x + 9.0,
-                    pos.y - 47.0,
-                    3.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 5.0,
-                    pos.y - 35.0,
-                    10.0,
-                    2.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
+            return;
+        }
+
+        let rank = self.shop_manager.rank(option.id);
+        if rank >= option.id.max_rank() {
+            self.set_shop_feedback(format!("{} is maxed out", option.title));
+        } else if self.shop_manager.try_purchase(option.id, haggled(option.cost, discount)) {
+            self.apply_upgrade_effect(option.id);
+            self.save_shop();
+        } else {
+            self.set_shop_feedback("Not enough Arc Tokens");
+        }
+    }
+
+    fn save_shop(&mut self) {
+        if let Err(err) = self.shop_manager.save() {
+            self.set_shop_feedback(format!("Shop save failed: {}", err));
+        }
+    }
+
+    fn render_shop(&self, ui: &UiLayout) {
+        // Calculate scale factor based on screen size
+        let scale_factor = (ui.width() / 1920.0).min(ui.height() / 1080.0).clamp(0.5, 1.0);
+
+        let overlay = Color::new(0.0, 0.0, 0.0, 0.75);
+        draw_rectangle(0.0, 0.0, ui.width(), ui.height(), overlay);
+
+        let title = "Arc Supply Shop";
+        let title_size = (36.0 * scale_factor).min(48.0).max(24.0);
+        let title_dims = measure_text(title, None, title_size as u16, 1.0);
+        draw_text(
+            title,
+            ui.width() * 0.5 - title_dims.width * 0.5,
+            100.0 * scale_factor,
+            title_size,
+            YELLOW,
+        );
+
+        let left = self
+            .shopkeeper
+            .and_then(|keeper| self.world.get_component::<Shopkeeper>(keeper))
+            .map_or(0.0, |shopkeeper| shopkeeper.lifetime.max(0.0));
+        let discount = self.keeper_discount();
+        let mut currency_text = format!(
+            "Arc Tokens: {} • Leaving in {:.0}s",
+            self.shop_manager.currency(),
+            left.ceil()
+        );
+        if discount > 0.0 {
+            currency_text = format!("{} • {:.0}% off", currency_text, discount * 100.0);
+        }
+        let currency_box_width = 480.0 * scale_factor;
+        let currency_box_height = 30.0 * scale_factor;
+        draw_rectangle(
+            ui.width() * 0.5 - currency_box_width * 0.5,
+            130.0 * scale_factor,
+            currency_box_width,
+            currency_box_height,
+            Color::new(0.15, 0.15, 0.2, 0.9),
+        );
+        let currency_size = (20.0 * scale_factor).min(26.0).max(16.0);
+        let currency_dims = measure_text(&currency_text, None, currency_size as u16, 1.0);
+        draw_text(
+            &currency_text,
+            ui.width() * 0.5 - currency_dims.width * 0.5,
+            150.0 * scale_factor,
+            currency_size,
+            Color::new(1.0, 0.9, 0.3, 1.0),
+        );
+
+        let width = (500.0 * scale_factor).min(680.0).max(400.0);
+        let height = (70.0 * scale_factor).min(90.0).max(60.0);
+        let base_x = ui.width() * 0.5 - width * 0.5;
+        let base_y = 180.0 * scale_factor;
+
+        let currency = self.shop_manager.currency();
+        let price_color = |cost: u32| {
+            if currency >= cost {
+                Color::new(0.7, 0.9, 1.0, 1.0)
+            } else {
+                Color::new(1.0, 0.5, 0.5, 1.0)
             }
-            CharacterType::Chef => {
-                draw_circle(pos.x, pos.y - 45.0, 30.0, Color::new(0.95, 0.85, 0.8, 1.0));
-                draw_rectangle(
-                    pos.x - 30.0,
-                    pos.y - 20.0,
-                    60.0,
-                    60.0,
-                    Color::new(1.0, 1.0, 1.0, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y + 40.0,
-                    40.0,
-                    35.0,
-                    Color::new(0.3, 0.3, 0.3, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 14.0,
-                    pos.y + 75.0,
-                    11.0,
-                    20.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 3.0,
-                    pos.y + 75.0,
-                    11.0,
-                    20.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 35.0,
-                    pos.y - 10.0,
-                    12.0,
-                    35.0,
-                    Color::new(0.95, 0.85, 0.8, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 23.0,
-                    pos.y - 10.0,
-                    12.0,
-                    35.0,
-                    Color::new(0.95, 0.85, 0.8, 1.0),
+        };
+        let maxed_color = Color::new(0.6, 1.0, 0.6, 1.0);
+
+        // This visit's upgrades with their rank and next price, then its consumable
+        let rows: Vec<(&str, &str, String, Color, bool)> = self
+            .keeper_stock
+            .iter()
+            .map(|stock| match *stock {
+                ShopStock::Upgrade(option) => {
+                    let rank = self.shop_manager.rank(option.id);
+                    let max = option.id.max_rank();
+                    let (status, color) = if rank >= max {
+                        (format!("Rank {}/{} • Maxed", rank, max), maxed_color)
+                    } else {
+                        let cost = rank_cost(haggled(option.cost, discount), rank);
+                        (format!("Rank {}/{} • Cost: {}", rank, max, cost), price_color(cost))
+                    };
+                    (option.title, option.description, status, color, rank > 0)
+                }
+                ShopStock::Item(item) => {
+                    let carried = self.shop_manager.carried(item);
+                    let cost = haggled(item.cost(), discount);
+                    let status =
+                        format!("{}/{} • Cost: {}", carried, item.max_carried(), cost);
+                    let color = if carried >= item.max_carried() {
+                        maxed_color
+                    } else {
+                        price_color(cost)
+                    };
+                    (item.label(), item.description(), status, color, carried > 0)
+                }
+            })
+            .collect();
+
+        for (index, (title, description, status, status_color, owned)) in
+            rows.iter().enumerate()
+        {
+            let y = base_y + index as f32 * (height + 10.0 * scale_factor);
+            let background = if *owned {
+                Color::new(0.15, 0.35, 0.18, 0.9)
+            } else {
+                Color::new(0.18, 0.18, 0.26, 0.9)
+            };
+
+            draw_rectangle(base_x, y, width, height, background);
+            draw_rectangle_lines(
+                base_x,
+                y,
+                width,
+                height,
+                1.5 * scale_factor,
+                Color::new(1.0, 1.0, 1.0, 0.3),
+            );
+
+            let key_label = format!("{}.", index + 1);
+            let key_size = (20.0 * scale_factor).min(26.0).max(16.0);
+            draw_text(&key_label, base_x + 12.0 * scale_factor, y + height * 0.5, key_size, WHITE);
+
+            let title_size = (20.0 * scale_factor).min(26.0).max(16.0);
+            draw_text(
+                title,
+                base_x + 45.0 * scale_factor,
+                y + height * 0.35,
+                title_size,
+                Color::new(1.0, 0.9, 0.5, 1.0),
+            );
+
+            let desc_size = (14.0 * scale_factor).min(18.0).max(12.0);
+            draw_text(
+                description,
+                base_x + 45.0 * scale_factor,
+                y + height * 0.65,
+                desc_size,
+                Color::new(0.85, 0.85, 0.95, 1.0),
+            );
+
+            let cost_size = (18.0 * scale_factor).min(24.0).max(14.0);
+            let cost_dims = measure_text(status, None, cost_size as u16, 1.0);
+            draw_text(
+                status,
+                base_x + width - cost_dims.width - 15.0 * scale_factor,
+                y + height * 0.5,
+                cost_size,
+                *status_color,
+            );
+        }
+
+        let footer = "1-5 buy • SHIFT sells back • H haggle • ENTER next wave • B to close";
+        let footer_size = (16.0 * scale_factor).min(20.0).max(14.0);
+        let footer_dims = measure_text(footer, None, footer_size as u16, 1.0);
+        draw_text(
+            footer,
+            ui.width() * 0.5 - footer_dims.width * 0.5,
+            ui.height() - 40.0 * scale_factor,
+            footer_size,
+            WHITE,
+        );
+    }
+
+    fn render_shop_feedback(&self, ui: &UiLayout) {
+        if let Some(ref message) = self.shop_feedback_message {
+            let alpha = self.shop_feedback_timer.min(1.5) / 1.5;
+            let bg = Color::new(0.05, 0.05, 0.1, alpha * 0.85);
+            let text_color = Color::new(1.0, 0.95, 0.7, alpha);
+            let dims = measure_text(message, None, 24, 1.0);
+            let bottom = ui.anchor(Anchor::Bottom, Vec2::new(0.0, 100.0));
+            let x = bottom.x - dims.width * 0.5 - 14.0;
+            let y = bottom.y;
+            draw_rectangle(x, y - 28.0, dims.width + 28.0, 46.0, bg);
+            draw_rectangle_lines(
+                x,
+                y - 28.0,
+                dims.width + 28.0,
+                46.0,
+                1.5,
+                Color::new(1.0, 1.0, 1.0, alpha * 0.6),
+            );
+            draw_text(message, bottom.x - dims.width * 0.5, y, 24.0, text_color);
+        }
+    }
+
+    /// Debug box listing what's wrong with `encounters.json`; the run plays
+    /// the built-in waves until it's fixed
+    fn render_encounter_problems(&self, ui: &UiLayout) {
+        const SHOWN: usize = 6;
+        if self.encounter_problems.is_empty() {
+            return;
+        }
+
+        let mut lines: Vec<String> = self.encounter_problems.iter().take(SHOWN).cloned().collect();
+        if self.encounter_problems.len() > SHOWN {
+            lines.push(format!("...and {} more", self.encounter_problems.len() - SHOWN));
+        }
+        let width = 620.0;
+        let height = 40.0 + lines.len() as f32 * 20.0;
+        let top = ui.anchor(Anchor::Top, Vec2::new(0.0, 110.0));
+        let x = top.x - width * 0.5;
+        draw_rectangle(x, top.y, width, height, Color::new(0.15, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, top.y, width, height, 2.0, RED);
+        draw_text(
+            "encounters.json has problems; playing the built-in waves",
+            x + 12.0,
+            top.y + 24.0,
+            20.0,
+            Color::new(1.0, 0.5, 0.5, 1.0),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x + 12.0, top.y + 48.0 + i as f32 * 20.0, 18.0, WHITE);
+        }
+    }
+
+    fn render_hud(&self, ui: &UiLayout) {
+        let layout = &self.hud_layout;
+        layout.draw(ui, HudWidget::Health, || self.render_health_widget());
+        layout.draw(ui, HudWidget::Meter, || self.render_meter_widget());
+        layout.draw(ui, HudWidget::Combo, || self.render_combo_widget());
+        layout.draw(ui, HudWidget::Allies, || self.render_allies_widget());
+        layout.draw(ui, HudWidget::Minimap, || self.render_minimap());
+        layout.draw(ui, HudWidget::Partner, || self.render_partner_widget());
+
+        // Stage info runs down the top-right corner
+        let column = ui.anchor(Anchor::TopRight, Vec2::new(200.0, 0.0)).x;
+
+        let map_text = format!("{:?}", self.current_map);
+        draw_text(&map_text, column, 40.0, 30.0, WHITE);
+
+        if let Some(run) = &self.winter_arc {
+            let run_text = format!("Winter Arc: {} shards", run.shards(false));
+            draw_text(&run_text, column, 100.0, 18.0, Color::new(0.6, 0.85, 1.0, 1.0));
+            for (i, curse) in run.curses().iter().enumerate() {
+                let y = 155.0 + i as f32 * 20.0;
+                draw_text(curse.name(), column, y, 18.0, Color::new(0.8, 0.5, 1.0, 1.0));
+            }
+        } else if self.route_modifier != RouteModifier::None {
+            let modifier_text = format!("{:?} route", self.route_modifier);
+            draw_text(
+                &modifier_text,
+                column,
+                100.0,
+                18.0,
+                Color::new(1.0, 0.6, 0.3, 1.0),
+            );
+        }
+
+        let wave_text = format!("Wave {}", self.current_wave);
+        draw_text(&wave_text, column, 70.0, 25.0, YELLOW);
+
+        if let Some(trial) = &self.trial {
+            let character = crate::data::characters::Character::get_by_id(trial.character);
+            let banner = format!("TRIAL RUN: {}", character.name.to_uppercase());
+            let dims = measure_text(&banner, None, 24, 1.0);
+            let top = ui.anchor(Anchor::Top, Vec2::new(0.0, 36.0));
+            draw_text(&banner, top.x - dims.width * 0.5, top.y, 24.0, ORANGE);
+        }
+
+        let currency_text = format!("Arc Tokens: {}", self.shop_manager.currency());
+        draw_text(
+            &currency_text,
+            column,
+            130.0,
+            22.0,
+            Color::new(1.0, 0.9, 0.4, 1.0),
+        );
+
+        // Voice lines are now shown through the dialogue system which freezes the game
+    }
+
+    /// Health bar with stamina under it, in the widget's own units
+    fn render_health_widget(&self) {
+        let Some(player_entity) = self.player_entity else {
+            return;
+        };
+
+        if let Some(health) = self.world.get_component::<Health>(player_entity) {
+            draw_rectangle(0.0, 0.0, 300.0, 30.0, Color::new(0.2, 0.0, 0.0, 0.8));
+            draw_rectangle(
+                0.0,
+                0.0,
+                300.0 * (health.current / health.maximum),
+                30.0,
+                Color::new(0.8, 0.0, 0.0, 1.0),
+            );
+            draw_rectangle_lines(0.0, 0.0, 300.0, 30.0, 2.0, WHITE);
+
+            let hp_text = format!("{:.0}/{:.0}", health.current, health.maximum);
+            draw_text(&hp_text, 10.0, 20.0, 20.0, WHITE);
+        }
+
+        // Stamina bar
+        if let Some(stamina) = self.world.get_component::<Stamina>(player_entity) {
+            let stamina_y = 37.0;
+            let stamina_width = 200.0;
+            let stamina_height = 18.0;
+
+            // Background
+            draw_rectangle(0.0, stamina_y, stamina_width, stamina_height,
+                Color::new(0.15, 0.15, 0.0, 0.8));
+
+            // Stamina fill
+            let stamina_color = if stamina.exhausted {
+                Color::new(0.5, 0.0, 0.0, 1.0) // Red when exhausted
+            } else if stamina.current < 30.0 {
+                Color::new(0.8, 0.5, 0.0, 1.0) // Orange when low
+            } else {
+                Color::new(0.8, 0.8, 0.0, 1.0) // Yellow when normal
+            };
+
+            draw_rectangle(
+                0.0,
+                stamina_y,
+                stamina_width * (stamina.current / stamina.maximum),
+                stamina_height,
+                stamina_color,
+            );
+
+            draw_rectangle_lines(0.0, stamina_y, stamina_width, stamina_height, 2.0, WHITE);
+
+            // Stamina text
+            let stamina_text = format!("STAMINA {:.0}/{:.0}", stamina.current, stamina.maximum);
+            draw_text(&stamina_text, 10.0, stamina_y + 13.0, 14.0, WHITE);
+            if self.hardcore_stamina && stamina.exhausted {
+                let x = stamina_width + 8.0;
+                draw_text("EXHAUSTED", x, stamina_y + 14.0, 18.0, Color::new(1.0, 0.3, 0.2, 1.0));
+            }
+        }
+    }
+
+    /// Super meter with the ability bar and any power-ups under it
+    fn render_meter_widget(&self) {
+        if let Some(fighter) = self
+            .player_entity
+            .and_then(|player| self.world.get_component::<Fighter>(player))
+        {
+            draw_rectangle(0.0, 0.0, 200.0, 20.0, Color::new(0.0, 0.0, 0.2, 0.8));
+            draw_rectangle(
+                0.0,
+                0.0,
+                200.0 * (fighter.meter / fighter.max_meter),
+                20.0,
+                Color::new(0.0, 0.4, 0.8, 1.0),
+            );
+            draw_rectangle_lines(0.0, 0.0, 200.0, 20.0, 2.0, WHITE);
+        }
+
+        use crate::data::Character;
+        let character = Character::get_by_id(self.selected_character);
+        let ability_y = 30.0;
+
+        // Ability icon/button
+        let ability_color = if self.ability_state.can_activate() {
+            Color::new(0.0, 0.8, 0.0, 0.8)
+        } else if self.ability_state.active {
+            Color::new(1.0, 0.8, 0.0, 0.8)
+        } else {
+            Color::new(0.3, 0.3, 0.3, 0.8)
+        };
+
+        draw_rectangle(0.0, ability_y, 200.0, 40.0, Color::new(0.0, 0.0, 0.0, 0.6));
+
+        if self.ability_state.active {
+            let remaining_pct = self.ability_state.active_time / self.ability_state.duration();
+            draw_rectangle(0.0, ability_y, 200.0 * remaining_pct, 40.0, ability_color);
+        } else if self.ability_state.cooldown_time > 0.0 {
+            let cooldown = self.ability_state.cooldown();
+            let cooldown_pct = 1.0 - self.ability_state.cooldown_time / cooldown;
+            draw_rectangle(0.0, ability_y, 200.0 * cooldown_pct, 40.0, ability_color);
+        } else {
+            draw_rectangle(0.0, ability_y, 200.0, 40.0, ability_color);
+        }
+
+        draw_rectangle_lines(0.0, ability_y, 200.0, 40.0, 2.0, WHITE);
+
+        let ability_text = if self.ability_state.active {
+            format!(
+                "{} [{:.1}s]",
+                character.ability_name, self.ability_state.active_time
+            )
+        } else if self.ability_state.cooldown_time > 0.0 {
+            format!(
+                "{} [{:.1}s]",
+                character.ability_name, self.ability_state.cooldown_time
+            )
+        } else {
+            format!("{} [E]", character.ability_name)
+        };
+
+        draw_text(&ability_text, 10.0, ability_y + 25.0, 18.0, WHITE);
+
+        // Power-ups the team picked up, with the seconds they have left
+        let mut x = 0.0;
+        for &(kind, left) in &self.power_ups {
+            let text = format!("{} {:.0}s", kind.name(), left.ceil());
+            draw_text(&text, x, ability_y + 60.0, 16.0, kind.color());
+            x += measure_text(&text, None, 16, 1.0).width + 10.0;
+        }
+
+        // Standing bonuses from upgrades and the skill tree
+        let modifiers = &self.hit_modifiers;
+        let ability = &self.ability_state;
+        let bonuses = [
+            (ability.cooldown_reduction, format!("CD -{:.0}%", ability.cooldown_reduction * 100.0)),
+            (ability.duration_bonus, format!("DUR +{:.1}s", ability.duration_bonus)),
+            (modifiers.crit_chance, format!("CRIT {:.0}%", modifiers.crit_chance * 100.0)),
+            (modifiers.lifesteal, format!("STEAL {:.0}%", modifiers.lifesteal * 100.0)),
+            (modifiers.damage_reduction, format!("DR {:.0}%", modifiers.damage_reduction * 100.0)),
+        ];
+        let mut x = 0.0;
+        for (_, text) in bonuses.iter().filter(|(value, _)| *value > 0.0) {
+            draw_text(text, x, ability_y + 80.0, 16.0, Color::new(0.6, 0.9, 1.0, 0.9));
+            x += measure_text(text, None, 16, 1.0).width + 10.0;
+        }
+
+        // Consumables carried this run
+        let mut x = 0.0;
+        for item in Consumable::ALL {
+            let carried = self.shop_manager.carried(item);
+            if carried == 0 {
+                continue;
+            }
+            let text = match item {
+                Consumable::SmokeBomb => format!("{} x{} [G]", item.label(), carried),
+                Consumable::ReviveToken => format!("{} x{}", item.label(), carried),
+            };
+            draw_text(&text, x, ability_y + 100.0, 16.0, Color::new(1.0, 0.85, 0.4, 0.9));
+            x += measure_text(&text, None, 16, 1.0).width + 10.0;
+        }
+    }
+
+    fn render_combo_widget(&self) {
+        let Some(fighter) = self
+            .player_entity
+            .and_then(|player| self.world.get_component::<Fighter>(player))
+        else {
+            return;
+        };
+
+        if fighter.combo_counter > 0 {
+            let combo_text = format!("COMBO x{}", fighter.combo_counter);
+            draw_text(&combo_text, 0.0, 35.0, 40.0, YELLOW);
+        }
+    }
+
+    fn render_allies_widget(&self) {
+        for (i, ally_entity) in self.ally_entities.iter().enumerate() {
+            if let Some(health) = self.world.get_component::<Health>(*ally_entity) {
+                if let Some(fighter) = self.world.get_component::<Fighter>(*ally_entity) {
+                    let y = 18.0 + i as f32 * 25.0;
+                    let name = self.character_display_name(&fighter.character_type, false);
+
+                    draw_text(&name, 0.0, y, 18.0, Color::new(0.8, 0.8, 0.8, 1.0));
+                    draw_rectangle(70.0, y - 12.0, 100.0, 10.0, Color::new(0.2, 0.0, 0.0, 0.6));
+                    draw_rectangle(
+                        70.0,
+                        y - 12.0,
+                        100.0 * (health.current / health.maximum),
+                        10.0,
+                        Color::new(0.0, 0.8, 0.0, 1.0),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Who's on the bench, and how soon they can be called in or tagged
+    fn render_partner_widget(&self) {
+        let Some(tag) = &self.tag_team else {
+            return;
+        };
+        let name = crate::data::Character::get_by_id(tag.partner).name;
+        let color = Self::character_color(tag.partner);
+        draw_rectangle(0.0, 0.0, 200.0, 70.0, Color::new(0.0, 0.0, 0.0, 0.6));
+
+        // Portrait: their initials on their color
+        let backing = Color::new(color.r * 0.4, color.g * 0.4, color.b * 0.4, 0.9);
+        draw_circle(32.0, 35.0, 26.0, backing);
+        draw_circle_lines(32.0, 35.0, 26.0, 3.0, color);
+        let initials: String = name.chars().take(2).collect::<String>().to_uppercase();
+        let dims = measure_text(&initials, None, 24, 1.0);
+        draw_text(&initials, 32.0 - dims.width * 0.5, 43.0, 24.0, WHITE);
+
+        if tag.chain() > 0 {
+            let chain = format!("TAG COMBO x{}", tag.chain());
+            draw_text(&chain, 66.0, 18.0, 18.0, Color::new(1.0, 0.8, 0.0, 1.0));
+        } else {
+            draw_text(name, 66.0, 18.0, 18.0, WHITE);
+        }
+
+        for (i, (label, charge)) in [("Q ASSIST", tag.assist_charge()), ("T TAG", tag.tag_charge())]
+            .into_iter()
+            .enumerate()
+        {
+            let y = 26.0 + i as f32 * 20.0;
+            let fill = if charge >= 1.0 { color } else { Color::new(0.3, 0.3, 0.3, 0.9) };
+            draw_rectangle(66.0, y, 126.0, 14.0, Color::new(0.1, 0.1, 0.1, 0.8));
+            draw_rectangle(66.0, y, 126.0 * charge.clamp(0.0, 1.0), 14.0, fill);
+            draw_rectangle_lines(66.0, y, 126.0, 14.0, 1.0, WHITE);
+            draw_text(label, 70.0, y + 11.0, 14.0, WHITE);
+        }
+    }
+
+    /// The arena squashed into a strip: you, your allies and the enemies left
+    fn render_minimap(&self) {
+        let size = HudWidget::Minimap.size();
+        let strip = Rect::new(0.0, 20.0, size.x, size.y - 20.0);
+        draw_rectangle(strip.x, strip.y, strip.w, strip.h, Color::new(0.0, 0.0, 0.0, 0.5));
+        draw_rectangle_lines(strip.x, strip.y, strip.w, strip.h, 2.0, WHITE);
+
+        let enemies_text = format!(
+            "Enemies: {} + {}",
+            self.enemy_entities.len(),
+            self.enemies_to_spawn
+        );
+        draw_text(&enemies_text, 0.0, 14.0, 18.0, Color::new(0.8, 0.8, 0.8, 1.0));
+
+        let arena = arena_bounds(self.host.screen_width());
+        let point = |position: Vec2| {
+            let x = ((position.x - arena.x) / arena.w).clamp(0.0, 1.0);
+            let y = ((position.y - arena.y) / arena.h).clamp(0.0, 1.0);
+            Vec2::new(strip.x + 6.0 + x * (strip.w - 12.0), strip.y + 6.0 + y * (strip.h - 12.0))
+        };
+        let dot = |entity: EntityId, radius: f32, color: Color| {
+            let Some(transform) = self.world.get_component::<Transform>(entity) else {
+                return;
+            };
+            let at = point(transform.position);
+            draw_circle(at.x, at.y, radius, color);
+        };
+
+        for marker in self.pings.markers() {
+            let at = point(marker.position);
+            draw_circle_lines(at.x, at.y, 5.0, 1.5, marker.kind.color());
+        }
+
+        for &enemy in &self.enemy_entities {
+            dot(enemy, 3.0, Color::new(1.0, 0.3, 0.3, 1.0));
+        }
+        for &ally in &self.ally_entities {
+            dot(ally, 3.0, Color::new(0.3, 1.0, 0.4, 1.0));
+        }
+        if let Some(player) = self.player_entity {
+            dot(player, 4.5, YELLOW);
+        }
+    }
+
+    fn character_display_name(&self, character: &CharacterType, is_player: bool) -> String {
+        if is_player {
+            return "You".to_string();
+        }
+
+        match character {
+            CharacterType::Bas => "Bas".to_string(),
+            CharacterType::Berkay => "Berkay".to_string(),
+            CharacterType::Hadi => "Hadi".to_string(),
+            CharacterType::Luca => "Luca".to_string(),
+            CharacterType::Nitin => "Nitin".to_string(),
+            CharacterType::Gefferinho => "Gefferinho".to_string(),
+            CharacterType::YigitBaba => "Yigit Baba".to_string(),
+            CharacterType::Wolters => "Wolters".to_string(),
+            CharacterType::PrefectA => "Prefect A".to_string(),
+            CharacterType::PrefectB => "Prefect B".to_string(),
+            CharacterType::Chef => "Chef".to_string(),
+            CharacterType::Librarian => "Librarian".to_string(),
+            CharacterType::Coach => "Coach".to_string(),
+            CharacterType::Bastiaan => "BOSS BASTIAAN".to_string(),
+            CharacterType::KeizerBomTaha => "Keizer Bom Taha".to_string(),
+            CharacterType::KeizerBomTahaBoss => "BOSS KEIZER BOM TAHA".to_string(),
+            CharacterType::Mees => "BOSS MEES - PITA SIRRACHA".to_string(),
+        }
+    }
+
+    fn render_nametag(&self, pos: Vec2, name: &str, color: Color) {
+        if name.is_empty() {
+            return;
+        }
+
+        let font_size = 20.0;
+        let metrics = measure_text(name, None, font_size as u16, 1.0);
+        let width = metrics.width + 24.0;
+        let height = metrics.height + 14.0;
+        let x = pos.x - width * 0.5;
+        let y = pos.y - 115.0;
+
+        // Shadow
+        draw_rectangle(
+            x + 2.0,
+            y + 2.0,
+            width,
+            height,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+
+        // Background with gradient effect
+        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.85));
+
+        // Top highlight
+        draw_rectangle(
+            x,
+            y,
+            width,
+            height * 0.3,
+            Color::new(color.r * 0.5, color.g * 0.5, color.b * 0.5, 0.6),
+        );
+
+        // Colored border with glow
+        draw_rectangle_lines(x, y, width, height, 2.5, color);
+        draw_rectangle_lines(
+            x - 1.0,
+            y - 1.0,
+            width + 2.0,
+            height + 2.0,
+            1.0,
+            Color::new(color.r, color.g, color.b, 0.4),
+        );
+
+        // Text with subtle shadow
+        draw_text(
+            name,
+            pos.x - metrics.width * 0.5 + 1.0,
+            y + height - 4.0,
+            font_size,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+
+        // Main text
+        draw_text(
+            name,
+            pos.x - metrics.width * 0.5,
+            y + height - 5.0,
+            font_size,
+            color,
+        );
+    }
+
+    /// Soup puddles and rooftop wind, drawn under everything standing on the floor
+    fn render_floor_props(&self) {
+        let time = get_time() as f32;
+
+        for (entity, prop) in self.world.query::<StageProp>() {
+            let Some((ground_pos, _)) = self.draw_position(entity) else {
+                continue;
+            };
+            let floor = ground_pos + Vec2::new(0.0, 70.0);
+
+            match prop.kind {
+                PropKind::SoupSpill { radius } => {
+                    let broth = Color::new(0.75, 0.55, 0.2, 0.7);
+                    draw_ellipse(floor.x, floor.y, radius, radius * 0.5, 0.0, broth);
+                    let shine = floor - Vec2::new(radius * 0.2, radius * 0.1);
+                    let shine_color = Color::new(1.0, 0.85, 0.5, 0.5);
+                    draw_ellipse(shine.x, shine.y, radius * 0.5, radius * 0.2, 0.0, shine_color);
+                    // Bits of carrot bobbing in the puddle
+                    for i in 0..5 {
+                        let angle = i as f32 * 1.3 + time * 0.4;
+                        let chunk = floor
+                            + Vec2::new(angle.cos() * radius * 0.6, angle.sin() * radius * 0.3);
+                        draw_circle(chunk.x, chunk.y, 4.0, Color::new(0.95, 0.45, 0.1, 0.9));
+                    }
+                }
+                PropKind::WindGust { .. } => {
+                    let arena = arena_bounds(self.host.screen_width());
+                    let Some(direction) = prop.gust() else {
+                        continue;
+                    };
+                    for i in 0..18 {
+                        let lane = arena.y + (i as f32 * 0.37).fract() * arena.h + 60.0;
+                        let travel = (time * 900.0 + i as f32 * 211.0) % (arena.w + 200.0);
+                        let x = if direction > 0.0 {
+                            arena.x - 100.0 + travel
+                        } else {
+                            arena.x + arena.w + 100.0 - travel
+                        };
+                        let streak = Color::new(1.0, 1.0, 1.0, 0.35);
+                        draw_line(x, lane, x - direction * 80.0, lane, 2.0, streak);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Chairs, basketballs and bookshelves, sorted in with the fighters
+    fn render_stage_prop(&self, entity: EntityId, pos: Vec2, prop: &StageProp) {
+        let time = get_time() as f32;
+        let floor = pos + Vec2::new(0.0, 70.0);
+        let shadow = Color::new(0.0, 0.0, 0.0, 0.35);
+
+        match prop.kind {
+            PropKind::Chair { thrown_by } => {
+                let rotation = self
+                    .world
+                    .get_component::<Transform>(entity)
+                    .map(|t| t.rotation)
+                    .unwrap_or(0.0);
+                let lift = if thrown_by.is_some() { 40.0 } else { 0.0 };
+                let seat = floor - Vec2::new(0.0, 30.0 + lift);
+                let wood = Color::new(0.55, 0.35, 0.18, 1.0);
+                draw_ellipse(floor.x, floor.y, 28.0, 8.0, 0.0, shadow);
+                draw_rectangle_ex(seat.x, seat.y, 44.0, 8.0, DrawRectangleParams {
+                    offset: vec2(0.5, 0.5),
+                    rotation,
+                    color: wood,
+                });
+                draw_rectangle_ex(seat.x - 18.0, seat.y - 22.0, 8.0, 36.0, DrawRectangleParams {
+                    offset: vec2(0.5, 0.5),
+                    rotation,
+                    color: wood,
+                });
+                if thrown_by.is_none() {
+                    let metal = Color::new(0.3, 0.3, 0.3, 1.0);
+                    draw_line(seat.x - 18.0, seat.y, seat.x - 18.0, floor.y, 3.0, metal);
+                    draw_line(seat.x + 18.0, seat.y, seat.x + 18.0, floor.y, 3.0, metal);
+                }
+            }
+            PropKind::Basketball => {
+                let bounce = (time * 6.0 + pos.x * 0.01).sin().abs() * 40.0;
+                let ball = floor - Vec2::new(0.0, 14.0 + bounce);
+                draw_ellipse(floor.x, floor.y, 16.0 - bounce * 0.15, 5.0, 0.0, shadow);
+                draw_circle(ball.x, ball.y, 14.0, Color::new(0.9, 0.45, 0.1, 1.0));
+                draw_line(ball.x - 14.0, ball.y, ball.x + 14.0, ball.y, 1.5, BLACK);
+                draw_line(ball.x, ball.y - 14.0, ball.x, ball.y + 14.0, 1.5, BLACK);
+            }
+            PropKind::Bookshelf => {
+                let wood = Color::new(0.4, 0.25, 0.12, 1.0);
+                if prop.toppled() {
+                    draw_rectangle(floor.x - 70.0, floor.y - 10.0, 140.0, 150.0, wood);
+                    // Books scattered past the top of the shelf
+                    for i in 0..6 {
+                        let x = floor.x - 60.0 + i as f32 * 21.0;
+                        let shade = i as f32 * 0.1;
+                        let book = Color::new(0.3 + shade, 0.2, 0.5 - shade * 0.5, 1.0);
+                        draw_rectangle(x, floor.y + 150.0, 16.0, 8.0, book);
+                    }
+                    return;
+                }
+
+                let sway = if prop.wobbling() { (time * 30.0).sin() * 4.0 } else { 0.0 };
+                if prop.wobbling() {
+                    // Where it's about to land
+                    let warning = 0.25 + (time * 10.0).sin().abs() * 0.2;
+                    let zone = Color::new(1.0, 0.1, 0.1, warning);
+                    draw_rectangle(floor.x - 70.0, floor.y, 140.0, 150.0, zone);
+                }
+                draw_rectangle(floor.x - 70.0 + sway, floor.y - 190.0, 140.0, 190.0, wood);
+                for shelf in 0..4 {
+                    let y = floor.y - 180.0 + shelf as f32 * 45.0;
+                    for i in 0..6 {
+                        let x = floor.x - 62.0 + sway + i as f32 * 21.0;
+                        let book =
+                            Color::new(0.3 + i as f32 * 0.1, 0.2 + shelf as f32 * 0.1, 0.5, 1.0);
+                        draw_rectangle(x, y, 16.0, 36.0, book);
+                    }
+                }
+            }
+            PropKind::SoupSpill { .. } | PropKind::WindGust { .. } => {}
+        }
+    }
+
+    /// Furniture darkens and cracks as it takes hits, then lies in pieces
+    fn render_destructible(&self, pos: Vec2, furniture: &Destructible) {
+        let floor = pos + Vec2::new(0.0, 70.0);
+        let size = furniture.size;
+        let left = floor.x - size.x * 0.5;
+        let (base, trim) = match furniture.kind {
+            DestructibleKind::Desk | DestructibleKind::Table => {
+                (Color::new(0.6, 0.4, 0.2, 1.0), Color::new(0.35, 0.22, 0.1, 1.0))
+            }
+            DestructibleKind::Locker => {
+                (Color::new(0.45, 0.55, 0.65, 1.0), Color::new(0.25, 0.3, 0.35, 1.0))
+            }
+        };
+        draw_ellipse(floor.x, floor.y, size.x * 0.55, 10.0, 0.0, Color::new(0.0, 0.0, 0.0, 0.3));
+
+        if furniture.is_broken() {
+            for i in 0..5 {
+                let x = left + i as f32 * size.x * 0.2;
+                let tilt = if i % 2 == 0 { -8.0 } else { 6.0 };
+                draw_line(x, floor.y - 4.0, x + size.x * 0.18, floor.y + tilt, 6.0, trim);
+            }
+            return;
+        }
+
+        let wear = 1.0 - furniture.health / furniture.max_health.max(1.0);
+        let shade = 1.0 - wear * 0.35;
+        let body = Color::new(base.r * shade, base.g * shade, base.b * shade, 1.0);
+        match furniture.kind {
+            DestructibleKind::Locker => {
+                draw_rectangle(left, floor.y - size.y, size.x, size.y, body);
+                draw_rectangle_lines(left, floor.y - size.y, size.x, size.y, 3.0, trim);
+                for slot in 0..3 {
+                    let y = floor.y - size.y + 12.0 + slot as f32 * 8.0;
+                    draw_line(left + 15.0, y, left + size.x - 15.0, y, 2.0, trim);
+                }
+            }
+            DestructibleKind::Desk | DestructibleKind::Table => {
+                let top = floor.y - size.y;
+                draw_rectangle(left, top, size.x, 10.0, body);
+                draw_line(left + 6.0, top + 10.0, left + 6.0, floor.y, 5.0, trim);
+                let right = left + size.x - 6.0;
+                draw_line(right, top + 10.0, right, floor.y, 5.0, trim);
+            }
+        }
+
+        // Cracks spread as it wears down
+        let cracks = (wear * 4.0).ceil() as usize;
+        for i in 0..cracks {
+            let x = left + size.x * (0.2 + i as f32 * 0.2);
+            let y = floor.y - size.y + 4.0;
+            draw_line(x, y, x + 8.0, y + 12.0, 1.5, BLACK);
+            draw_line(x + 8.0, y + 12.0, x + 2.0, y + 20.0, 1.5, BLACK);
+        }
+    }
+
+    /// The shopkeeper behind their stall, with how long they're staying and a
+    /// prompt once P1 is close enough to shop
+    fn render_shopkeeper(&self, pos: Vec2, shopkeeper: &Shopkeeper) {
+        let time = get_time() as f32;
+        let floor = pos + Vec2::new(0.0, 70.0);
+        draw_ellipse(floor.x, floor.y, 70.0, 14.0, 0.0, Color::new(0.0, 0.0, 0.0, 0.35));
+
+        // Keeper, bobbing to the shop track
+        let bob = (time * 3.0).sin().abs() * 3.0;
+        let coat = Color::new(0.35, 0.2, 0.45, 1.0);
+        draw_rectangle(pos.x - 16.0, floor.y - 96.0 - bob, 32.0, 48.0, coat);
+        let skin = Color::new(0.95, 0.78, 0.6, 1.0);
+        draw_circle(pos.x, floor.y - 110.0 - bob, 14.0, skin);
+        draw_rectangle(pos.x - 16.0, floor.y - 128.0 - bob, 32.0, 8.0, DARKGRAY);
+
+        // Stall counter and striped awning
+        let wood = Color::new(0.55, 0.35, 0.18, 1.0);
+        draw_rectangle(floor.x - 60.0, floor.y - 50.0, 120.0, 50.0, wood);
+        draw_rectangle_lines(floor.x - 60.0, floor.y - 50.0, 120.0, 50.0, 2.0, BLACK);
+        for stripe in 0..6 {
+            let color = if stripe % 2 == 0 { RED } else { WHITE };
+            let x = floor.x - 66.0 + stripe as f32 * 22.0;
+            draw_rectangle(x, floor.y - 150.0, 22.0, 14.0, color);
+        }
+        draw_text("SHOP", floor.x - 24.0, floor.y - 20.0, 24.0, YELLOW);
+
+        let blink = shopkeeper.leaving() && (time * 6.0) as i32 % 2 == 0;
+        let timer_color = if shopkeeper.leaving() { RED } else { WHITE };
+        if !blink {
+            let timer = format!("{:.0}s", shopkeeper.lifetime.max(0.0).ceil());
+            draw_text(&timer, floor.x - 14.0, floor.y - 158.0, 22.0, timer_color);
+        }
+        if !self.shop_open && self.near_shopkeeper() {
+            let prompt = "[B] Shop";
+            let dims = measure_text(prompt, None, 22, 1.0);
+            draw_text(prompt, floor.x - dims.width * 0.5, floor.y + 28.0, 22.0, YELLOW);
+        }
+    }
+
+    /// Spinning Arc Token, blinking out over its last seconds
+    fn render_pickup(&self, pos: Vec2, pickup: &Pickup) {
+        let time = get_time() as f32;
+        if pickup.expiring() && (time * 8.0) as i32 % 2 == 0 {
+            return;
+        }
+
+        let floor = pos + Vec2::new(0.0, 70.0);
+        let hover = (time * 4.0).sin() * 4.0;
+        let item = floor - Vec2::new(0.0, 24.0 + hover);
+        draw_ellipse(floor.x, floor.y, 12.0, 4.0, 0.0, Color::new(0.0, 0.0, 0.0, 0.3));
+
+        // Anything better than common glows in its rarity's colour
+        if pickup.rarity != Rarity::Common {
+            let glow = pickup.rarity.color();
+            let pulse = 0.25 + (time * 3.0).sin().abs() * 0.2;
+            draw_circle(item.x, item.y, 22.0, Color::new(glow.r, glow.g, glow.b, pulse));
+        }
+
+        let label_color = Color::new(1.0, 1.0, 1.0, 0.9);
+        match pickup.drop {
+            LootDrop::Tokens(amount) => {
+                let spin = (time * 5.0).cos().abs().max(0.2);
+                let gold = Color::new(1.0, 0.8, 0.1, 1.0);
+                draw_ellipse(item.x, item.y, 12.0 * spin, 12.0, 0.0, gold);
+                let rim = Color::new(0.7, 0.5, 0.0, 1.0);
+                draw_ellipse_lines(item.x, item.y, 12.0 * spin, 12.0, 0.0, 2.0, rim);
+                draw_text(
+                    &format!("+{}", amount),
+                    item.x - 10.0,
+                    item.y - 18.0,
+                    18.0,
+                    Color::new(1.0, 0.9, 0.3, 1.0),
                 );
-                draw_circle(pos.x + 35.0, pos.y, 10.0, Color::new(0.7, 0.7, 0.7, 1.0));
+            }
+            LootDrop::HealthPack(_) => {
+                draw_rectangle(item.x - 11.0, item.y - 9.0, 22.0, 18.0, WHITE);
+                draw_rectangle_lines(item.x - 11.0, item.y - 9.0, 22.0, 18.0, 2.0, GRAY);
+                let red = Color::new(0.9, 0.1, 0.1, 1.0);
+                draw_rectangle(item.x - 2.5, item.y - 6.0, 5.0, 12.0, red);
+                draw_rectangle(item.x - 6.0, item.y - 2.5, 12.0, 5.0, red);
+            }
+            LootDrop::MeterBattery(_) => {
+                let blue = Color::new(0.3, 0.6, 1.0, 1.0);
+                let casing = Color::new(0.1, 0.1, 0.2, 1.0);
+                draw_rectangle(item.x - 7.0, item.y - 11.0, 14.0, 22.0, casing);
+                draw_rectangle(item.x - 3.0, item.y - 14.0, 6.0, 3.0, GRAY);
+                let charge = 0.4 + (time * 2.0).sin().abs() * 0.6;
+                let fill = 18.0 * charge;
+                draw_rectangle(item.x - 5.0, item.y + 9.0 - fill, 10.0, fill, blue);
+                draw_rectangle_lines(item.x - 7.0, item.y - 11.0, 14.0, 22.0, 2.0, blue);
+            }
+            LootDrop::PowerUp { kind, .. } => {
+                let color = kind.color();
+                draw_circle(item.x, item.y, 11.0, color);
+                draw_circle_lines(item.x, item.y, 11.0, 2.0, WHITE);
+                let letter = &kind.name()[..1];
+                let dims = measure_text(letter, None, 18, 1.0);
+                draw_text(letter, item.x - dims.width * 0.5, item.y + 6.0, 18.0, BLACK);
+                draw_text(kind.name(), item.x - 24.0, item.y - 18.0, 16.0, label_color);
+            }
+        }
+    }
+
+    /// Doors swinging open, shattering windows and stair rails behind entering enemies
+    /// Danger indicators for the attacks bosses are winding up: the zone
+    /// fills in as the wind-up runs out and flashes while the attack lands
+    fn render_boss_telegraphs(&self) {
+        for (entity, (windup, transform)) in self.world.query::<(BossWindup, Transform)>() {
+            let facing = self.world.get_component::<Fighter>(entity).map_or(1.0, |f| f.facing);
+            let pos = transform.position;
+            let progress = windup.progress();
+            let telegraph = &windup.telegraph;
+            let flash = if windup.landing { 0.6 } else { 0.15 + progress * 0.3 };
+            let fill = Color::new(1.0, 0.15, 0.1, flash);
+            let edge = Color::new(1.0, 0.3, 0.2, 0.9);
+
+            match telegraph.danger_zone {
+                DangerZone::Circle { radius } => {
+                    draw_circle(pos.x, pos.y, radius * progress, fill);
+                    draw_circle_lines(pos.x, pos.y, radius, 3.0, edge);
+                }
+                DangerZone::Rect { .. } => {
+                    // Fill grows out from the boss's side of the zone
+                    let zone = telegraph.danger_rect(pos, facing);
+                    let width = zone.w * progress;
+                    let x = if facing > 0.0 { zone.x } else { zone.x + zone.w - width };
+                    draw_rectangle(x, zone.y, width, zone.h, fill);
+                    draw_rectangle_lines(zone.x, zone.y, zone.w, zone.h, 3.0, edge);
+                }
+            }
+
+            if windup.landing {
+                continue;
+            }
+            let pulse = (self.host.time() as f32 * 12.0).sin() * 0.5 + 0.5;
+            match telegraph.windup_vfx {
+                WindupVfx::Glint => {
+                    let glint = pos + Vec2::new(40.0 * facing, -30.0);
+                    let size = 6.0 + progress * 10.0;
+                    let color = Color::new(1.0, 1.0, 0.8, 0.5 + pulse * 0.5);
+                    draw_line(glint.x - size, glint.y, glint.x + size, glint.y, 2.0, color);
+                    draw_line(glint.x, glint.y - size, glint.x, glint.y + size, 2.0, color);
+                }
+                WindupVfx::ChargeRing => {
+                    let radius = 120.0 * (1.0 - progress) + 30.0;
+                    draw_circle_lines(pos.x, pos.y, radius, 4.0, Color::new(1.0, 0.6, 0.1, 0.8));
+                }
+                WindupVfx::AimLine => {
+                    if pulse > 0.5 {
+                        let end = pos.x + 700.0 * facing;
+                        draw_line(pos.x, pos.y, end, pos.y, 2.0, Color::new(1.0, 0.9, 0.2, 0.8));
+                    }
+                }
+                WindupVfx::Aura => {
+                    let color = Color::new(1.0, 0.8, 0.2, 0.2 + pulse * 0.3);
+                    draw_circle(pos.x, pos.y, 70.0 + progress * 20.0, color);
+                }
+            }
+        }
+    }
+
+    fn render_spawn_entrances(&self) {
+        use crate::render::map_system::SpawnPointKind;
+
+        for (_, entrance) in self.world.query::<SpawnEntrance>() {
+            if !entrance.is_entering() {
+                continue;
+            }
+
+            let t = entrance.progress();
+            let origin = entrance.from;
+            let fade = 1.0 - ((t - 0.8) / 0.2).clamp(0.0, 1.0);
+
+            match entrance.kind {
+                SpawnPointKind::Door => {
+                    let frame_color = Color::new(0.25, 0.15, 0.08, fade);
+                    draw_rectangle(origin.x - 40.0, origin.y - 120.0, 80.0, 150.0, Color::new(0.05, 0.05, 0.05, fade));
+                    draw_rectangle_lines(origin.x - 44.0, origin.y - 124.0, 88.0, 158.0, 6.0, frame_color);
+
+                    // Rattles while being kicked, then swings open
+                    let kick = (t / 0.35).min(1.0);
+                    let shake = if kick < 1.0 { (t * 80.0).sin() * 3.0 } else { 0.0 };
+                    let swing = ((t - 0.35) / 0.15).clamp(0.0, 1.0);
+                    let door_width = 76.0 * (1.0 - swing * 0.8);
+                    draw_rectangle(
+                        origin.x - 38.0 + shake,
+                        origin.y - 118.0,
+                        door_width,
+                        146.0,
+                        Color::new(0.55, 0.35, 0.2, fade),
+                    );
+                    draw_circle(origin.x - 38.0 + door_width - 10.0 + shake, origin.y - 45.0, 4.0, Color::new(0.9, 0.8, 0.3, fade));
+                }
+                SpawnPointKind::Window => {
+                    let frame_color = Color::new(0.8, 0.8, 0.85, fade);
+                    draw_rectangle(origin.x - 50.0, origin.y - 90.0, 100.0, 80.0, Color::new(0.5, 0.7, 0.9, 0.35 * fade));
+                    draw_rectangle_lines(origin.x - 50.0, origin.y - 90.0, 100.0, 80.0, 5.0, frame_color);
+                    draw_line(origin.x, origin.y - 90.0, origin.x, origin.y - 10.0, 3.0, frame_color);
+
+                    // Glass shards burst out as they come through
+                    for i in 0..10 {
+                        let angle = i as f32 * 0.63 + 0.3;
+                        let spread = t * 140.0;
+                        let shard = origin
+                            + Vec2::new(angle.cos() * spread, -50.0 + angle.sin() * spread * 0.5 + t * t * 120.0);
+                        draw_triangle(
+                            shard,
+                            shard + Vec2::new(6.0, 2.0),
+                            shard + Vec2::new(2.0, 8.0),
+                            Color::new(0.8, 0.9, 1.0, fade * 0.8),
+                        );
+                    }
+                }
+                SpawnPointKind::Stairwell => {
+                    let rail_color = Color::new(0.35, 0.35, 0.4, fade);
+                    for step in 0..4 {
+                        let y = origin.y - 20.0 - step as f32 * 18.0;
+                        draw_rectangle(origin.x - 45.0, y, 90.0, 6.0, Color::new(0.3, 0.28, 0.25, fade));
+                    }
+                    draw_line(origin.x - 50.0, origin.y, origin.x - 50.0, origin.y - 110.0, 4.0, rail_color);
+                    draw_line(origin.x + 50.0, origin.y, origin.x + 50.0, origin.y - 110.0, 4.0, rail_color);
+                }
+            }
+        }
+    }
+
+    fn render_classroom(&self) {
+        let time = get_time() as f32;
+        let sw = screen_width();
+        let sh = screen_height();
+
+        // Enhanced floor with tiles - resolution friendly
+        let floor_y = sh * 0.55;
+        let tile_cols = (sw / 80.0).ceil() as i32;
+        let tile_rows = ((sh - floor_y) / 40.0).ceil() as i32;
+
+        for i in 0..tile_cols {
+            for j in 0..tile_rows {
+                let x = i as f32 * 80.0;
+                let y = floor_y + j as f32 * 40.0;
+                let tile_shade = if (i + j) % 2 == 0 { 0.45 } else { 0.42 };
                 draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 75.0,
-                    50.0,
-                    25.0,
-                    Color::new(1.0, 1.0, 1.0, 1.0),
+                    x,
+                    y,
+                    80.0,
+                    40.0,
+                    Color::new(tile_shade, tile_shade - 0.05, tile_shade - 0.1, 1.0),
                 );
+                draw_rectangle_lines(x, y, 80.0, 40.0, 1.0, Color::new(0.3, 0.25, 0.2, 0.3));
             }
-            CharacterType::Librarian => {
-                draw_circle(pos.x, pos.y - 45.0, 25.0, Color::new(0.95, 0.9, 0.85, 1.0));
-                draw_rectangle(
-                    pos.x - 22.0,
-                    pos.y - 20.0,
-                    44.0,
-                    55.0,
-                    Color::new(0.5, 0.3, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 17.0,
-                    pos.y + 35.0,
-                    34.0,
-                    35.0,
-                    Color::new(0.3, 0.2, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 11.0,
-                    pos.y + 70.0,
-                    9.0,
-                    20.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 70.0,
-                    9.0,
-                    20.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 27.0,
-                    pos.y - 10.0,
-                    9.0,
-                    30.0,
-                    Color::new(0.95, 0.9, 0.85, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 18.0,
-                    pos.y - 10.0,
-                    9.0,
-                    30.0,
-                    Color::new(0.95, 0.9, 0.85, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 27.0,
-                    pos.y - 5.0,
-                    15.0,
-                    20.0,
-                    Color::new(0.2, 0.4, 0.6, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 15.0,
-                    pos.y - 50.0,
-                    8.0,
-                    8.0,
-                    Color::new(0.3, 0.3, 0.3, 1.0),
-                );
+        }
+
+        // Ceiling beam with depth - resolution friendly
+        let ceiling_y = sh * 0.09;
+        draw_rectangle(
+            0.0,
+            ceiling_y,
+            sw,
+            sh * 0.03,
+            Color::new(0.25, 0.2, 0.15, 1.0),
+        );
+        draw_rectangle(
+            0.0,
+            ceiling_y + sh * 0.005,
+            sw,
+            sh * 0.02,
+            Color::new(0.35, 0.28, 0.22, 1.0),
+        );
+        draw_rectangle(
+            0.0,
+            ceiling_y + sh * 0.025,
+            sw,
+            sh * 0.002,
+            Color::new(0.15, 0.12, 0.1, 1.0),
+        );
+
+        // Ceiling lights
+        for i in 0..((sw / 300.0) as i32) {
+            let light_x = 150.0 + i as f32 * 300.0;
+            draw_rectangle(
+                light_x - 40.0,
+                ceiling_y + sh * 0.027,
+                80.0,
+                15.0,
+                Color::new(0.9, 0.9, 0.8, 0.8),
+            );
+            // Light glow
+            draw_circle(
+                light_x,
+                ceiling_y + sh * 0.035,
+                60.0,
+                Color::new(1.0, 1.0, 0.9, 0.15),
+            );
+        }
+
+        // Enhanced blackboard with frame - resolution friendly
+        let board_x = sw * 0.03;
+        let board_y = sh * 0.15;
+        let board_w = sw * 0.2;
+        let board_h = sh * 0.28;
+
+        draw_rectangle(
+            board_x + 5.0,
+            board_y + 5.0,
+            board_w,
+            board_h,
+            Color::new(0.15, 0.12, 0.1, 1.0),
+        ); // Shadow
+        draw_rectangle(
+            board_x,
+            board_y,
+            board_w,
+            board_h,
+            Color::new(0.5, 0.42, 0.35, 1.0),
+        ); // Frame
+        draw_rectangle(
+            board_x + 10.0,
+            board_y + 10.0,
+            board_w - 20.0,
+            board_h - 20.0,
+            Color::new(0.08, 0.25, 0.08, 1.0),
+        ); // Board
+
+        // Frame details
+        draw_rectangle(
+            board_x + 5.0,
+            board_y + 5.0,
+            board_w - 10.0,
+            5.0,
+            Color::new(0.6, 0.52, 0.45, 1.0),
+        );
+        draw_rectangle(
+            board_x + 5.0,
+            board_y + 5.0,
+            5.0,
+            board_h - 10.0,
+            Color::new(0.6, 0.52, 0.45, 1.0),
+        );
+
+        // Chalk text with glow - dynamic font size
+        let text = "BAS VEGEN";
+        let font_size = (board_w * 0.15).max(20.0);
+        let text_x = board_x + board_w * 0.15;
+        let text_y = board_y + board_h * 0.45;
+        draw_text(
+            text,
+            text_x + 2.0,
+            text_y + 2.0,
+            font_size,
+            Color::new(0.7, 0.7, 0.7, 0.3),
+        );
+        draw_text(text, text_x, text_y, font_size, WHITE);
+
+        let small_font = (board_w * 0.12).max(16.0);
+        let text2_y = board_y + board_h * 0.7;
+        draw_text(
+            "A + B = C",
+            text_x + 2.0,
+            text2_y + 2.0,
+            small_font,
+            Color::new(0.7, 0.7, 0.7, 0.3),
+        );
+        draw_text("A + B = C", text_x, text2_y, small_font, WHITE);
+
+        // Chalk ledge
+        draw_rectangle(
+            board_x + 10.0,
+            board_y + board_h - 2.0,
+            board_w - 20.0,
+            8.0,
+            Color::new(0.4, 0.34, 0.28, 1.0),
+        );
+
+        // Enhanced desks with shadows and depth - resolution friendly
+        let desk_count = ((sw - board_w * 1.5) / (sw * 0.12)).floor().min(6.0).max(3.0) as i32;
+        let desk_start_x = board_x + board_w + sw * 0.05;
+        let desk_spacing = (sw - desk_start_x - sw * 0.2) / desk_count as f32;
+        let desk_w = (desk_spacing * 0.75).min(sw * 0.1);
+        let desk_h = sh * 0.11;
+        let desk_y = floor_y - desk_h - sh * 0.05;
+
+        for i in 0..desk_count {
+            let desk_x = desk_start_x + (i as f32 * desk_spacing);
+
+            // Desk shadow
+            draw_rectangle(
+                desk_x + 3.0,
+                desk_y + 3.0,
+                desk_w,
+                desk_h,
+                Color::new(0.0, 0.0, 0.0, 0.3),
+            );
+
+            // Desk top with highlight
+            draw_rectangle(
+                desk_x,
+                desk_y,
+                desk_w,
+                desk_h,
+                Color::new(0.55, 0.45, 0.35, 1.0),
+            );
+            draw_rectangle(
+                desk_x,
+                desk_y,
+                desk_w,
+                desk_h * 0.2,
+                Color::new(0.65, 0.55, 0.45, 1.0),
+            ); // Highlight
+            draw_rectangle_lines(
+                desk_x,
+                desk_y,
+                desk_w,
+                desk_h,
+                2.0,
+                Color::new(0.4, 0.32, 0.25, 1.0),
+            );
+
+            // Chair with backrest
+            let chair_w = desk_w * 0.35;
+            let chair_h = desk_h * 0.85;
+            let chair_x = desk_x + (desk_w - chair_w) * 0.5;
+            let chair_y = desk_y - chair_h - sh * 0.01;
+
+            draw_rectangle(
+                chair_x,
+                chair_y + 3.0,
+                chair_w,
+                chair_h,
+                Color::new(0.25, 0.25, 0.3, 1.0),
+            );
+            draw_rectangle(
+                chair_x + 2.0,
+                chair_y,
+                chair_w - 4.0,
+                chair_h - 2.0,
+                Color::new(0.35, 0.35, 0.4, 1.0),
+            );
+            // Backrest
+            draw_rectangle(
+                chair_x - chair_w * 0.12,
+                chair_y - desk_h * 0.25,
+                chair_w * 1.25,
+                desk_h * 0.3,
+                Color::new(0.35, 0.35, 0.4, 1.0),
+            );
+        }
+
+        // Enhanced teacher's desk - resolution friendly
+        let teacher_desk_w = sw * 0.08;
+        let teacher_desk_h = sh * 0.21;
+        let teacher_x = sw - teacher_desk_w - sw * 0.02;
+        let teacher_y = sh * 0.26;
+
+        draw_rectangle(
+            teacher_x + 5.0,
+            teacher_y + 5.0,
+            teacher_desk_w,
+            teacher_desk_h,
+            Color::new(0.0, 0.0, 0.0, 0.3),
+        );
+        draw_rectangle(
+            teacher_x,
+            teacher_y,
+            teacher_desk_w,
+            teacher_desk_h,
+            Color::new(0.65, 0.55, 0.45, 1.0),
+        );
+        draw_rectangle(
+            teacher_x,
+            teacher_y,
+            teacher_desk_w,
+            teacher_desk_h * 0.13,
+            Color::new(0.75, 0.65, 0.55, 1.0),
+        );
+        draw_rectangle_lines(
+            teacher_x,
+            teacher_y,
+            teacher_desk_w,
+            teacher_desk_h,
+            3.0,
+            Color::new(0.5, 0.4, 0.32, 1.0),
+        );
+
+        // Computer/papers on desk
+        let item_size = teacher_desk_w * 0.4;
+        draw_rectangle(
+            teacher_x + teacher_desk_w * 0.1,
+            teacher_y + teacher_desk_h * 0.08,
+            item_size,
+            item_size * 0.75,
+            Color::new(0.85, 0.85, 0.85, 1.0),
+        );
+        draw_rectangle(
+            teacher_x + teacher_desk_w * 0.12,
+            teacher_y + teacher_desk_h * 0.1,
+            item_size * 0.9,
+            item_size * 0.68,
+            Color::new(0.2, 0.3, 0.4, 1.0),
+        );
+        draw_rectangle(
+            teacher_x + teacher_desk_w * 0.55,
+            teacher_y + teacher_desk_h * 0.13,
+            item_size * 0.8,
+            item_size * 0.55,
+            Color::new(0.95, 0.95, 0.85, 1.0),
+        );
+
+        // Enhanced plant - resolution friendly
+        let plant_w = sw * 0.045;
+        let plant_h = sh * 0.14;
+        let plant_x = sw - plant_w * 3.2;
+        let plant_y = floor_y - plant_h;
+
+        draw_rectangle(
+            plant_x + 5.0,
+            plant_y + 3.0,
+            plant_w,
+            plant_h,
+            Color::new(0.0, 0.0, 0.0, 0.3),
+        );
+        draw_rectangle(
+            plant_x,
+            plant_y,
+            plant_w,
+            plant_h,
+            Color::new(0.45, 0.3, 0.2, 1.0),
+        );
+
+        let plant_center_x = plant_x + plant_w * 0.5;
+        let plant_top_y = plant_y - plant_h * 0.15;
+        let leaf_radius = plant_w * 0.8;
+
+        draw_circle(
+            plant_center_x,
+            plant_top_y,
+            leaf_radius,
+            Color::new(0.25, 0.55, 0.25, 1.0),
+        );
+        draw_circle(
+            plant_center_x - leaf_radius * 0.3,
+            plant_top_y + leaf_radius * 0.25,
+            leaf_radius * 0.7,
+            Color::new(0.3, 0.6, 0.3, 1.0),
+        );
+        draw_circle(
+            plant_center_x + leaf_radius * 0.3,
+            plant_top_y + leaf_radius * 0.35,
+            leaf_radius * 0.6,
+            Color::new(0.35, 0.65, 0.35, 1.0),
+        );
+
+        // Enhanced windows with light rays - resolution friendly
+        let window_w = sw * 0.065;
+        let window_h = sh * 0.17;
+        let window_y = sh * 0.2;
+        let window_spacing = sw * 0.1;
+        let window_start = board_x + board_w + sw * 0.03;
+        let window_count = ((sw - window_start - plant_x) / window_spacing)
+            .floor()
+            .min(8.0) as i32;
+
+        for i in 0..window_count {
+            let window_x = window_start + (i as f32 * window_spacing);
+            if window_x + window_w < plant_x - sw * 0.02 {
+                // Window shadow
                 draw_rectangle(
-                    pos.x + 7.0,
-                    pos.y - 50.0,
-                    8.0,
-                    8.0,
-                    Color::new(0.3, 0.3, 0.3, 1.0),
+                    window_x + 3.0,
+                    window_y + 3.0,
+                    window_w,
+                    window_h,
+                    Color::new(0.0, 0.0, 0.0, 0.2),
                 );
+
+                // Window frame
                 draw_rectangle(
-                    pos.x - 17.0,
-                    pos.y - 65.0,
-                    34.0,
-                    8.0,
-                    Color::new(0.7, 0.7, 0.7, 1.0),
+                    window_x - 5.0,
+                    window_y - 5.0,
+                    window_w + 10.0,
+                    window_h + 10.0,
+                    Color::new(0.45, 0.38, 0.3, 1.0),
                 );
-            }
-            CharacterType::Coach => {
-                draw_circle(pos.x, pos.y - 45.0, 28.0, Color::new(0.9, 0.75, 0.65, 1.0));
+
+                // Glass with subtle animation
+                let light_variation = ((time + i as f32 * 0.5).sin() * 0.05 + 0.75) as f32;
                 draw_rectangle(
-                    pos.x - 28.0,
-                    pos.y - 20.0,
-                    56.0,
-                    58.0,
-                    Color::new(0.2, 0.5, 0.2, 1.0),
+                    window_x,
+                    window_y,
+                    window_w,
+                    window_h,
+                    Color::new(0.65 * light_variation, 0.75 * light_variation, 0.95, 0.4),
                 );
-                draw_rectangle(
-                    pos.x - 12.0,
-                    pos.y - 10.0,
-                    24.0,
+
+                // Light rays through window
+                let ray_start_y = window_y + window_h;
+                for ray in 0..3 {
+                    let ray_x = window_x + window_w * 0.2 + ray as f32 * window_w * 0.3;
+                    let ray_alpha = 0.1 + ((time * 0.5 + ray as f32 * 0.3).sin() * 0.05) as f32;
+                    for y_step in 0..10 {
+                        let y_pos = ray_start_y + y_step as f32 * sh * 0.05;
+                        draw_line(
+                            ray_x,
+                            ray_start_y,
+                            ray_x + window_w * 0.15,
+                            y_pos,
+                            window_w * 0.3,
+                            Color::new(1.0, 1.0, 0.9, ray_alpha),
+                        );
+                    }
+                }
+
+                // Window dividers
+                draw_rectangle_lines(
+                    window_x,
+                    window_y,
+                    window_w,
+                    window_h,
                     4.0,
-                    Color::new(1.0, 1.0, 1.0, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y + 38.0,
-                    40.0,
-                    37.0,
-                    Color::new(0.1, 0.3, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 13.0,
-                    pos.y + 75.0,
-                    11.0,
-                    22.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 75.0,
-                    11.0,
-                    22.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 33.0,
-                    pos.y - 10.0,
-                    11.0,
-                    35.0,
-                    Color::new(0.9, 0.75, 0.65, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 22.0,
-                    pos.y - 10.0,
-                    11.0,
-                    35.0,
-                    Color::new(0.9, 0.75, 0.65, 1.0),
+                    Color::new(0.4, 0.33, 0.26, 1.0),
                 );
-                draw_circle(
-                    pos.x + 33.0,
-                    pos.y - 5.0,
-                    8.0,
-                    Color::new(0.8, 0.8, 0.8, 1.0),
+                // Vertical divider
+                draw_line(
+                    window_x + window_w * 0.5,
+                    window_y,
+                    window_x + window_w * 0.5,
+                    window_y + window_h,
+                    3.0,
+                    Color::new(0.4, 0.33, 0.26, 1.0),
                 );
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 68.0,
-                    40.0,
-                    15.0,
-                    Color::new(0.8, 0.1, 0.1, 1.0),
+                // Horizontal divider
+                draw_line(
+                    window_x,
+                    window_y + window_h * 0.5,
+                    window_x + window_w,
+                    window_y + window_h * 0.5,
+                    3.0,
+                    Color::new(0.4, 0.33, 0.26, 1.0),
                 );
             }
-            CharacterType::Bastiaan => {
-                draw_circle(pos.x, pos.y - 45.0, 27.0, Color::new(0.95, 0.85, 0.75, 1.0));
-                draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 20.0,
-                    50.0,
-                    55.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 8.0,
-                    pos.y - 15.0,
-                    16.0,
-                    20.0,
-                    Color::new(0.9, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 18.0,
-                    pos.y + 35.0,
-                    36.0,
-                    40.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 12.0,
-                    pos.y + 75.0,
-                    10.0,
-                    25.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 2.0,
-                    pos.y + 75.0,
-                    10.0,
-                    25.0,
-                    Color::new(0.1, 0.1, 0.1, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 30.0,
-                    pos.y - 10.0,
-                    10.0,
-                    35.0,
-                    Color::new(0.95, 0.85, 0.75, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 20.0,
-                    pos.y - 10.0,
-                    10.0,
-                    35.0,
-                    Color::new(0.95, 0.85, 0.75, 1.0),
-                );
-                draw_rectangle(
-                    pos.x + 30.0,
-                    pos.y - 8.0,
-                    20.0,
-                    10.0,
-                    Color::new(0.6, 0.3, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x - 10.0,
-                    pos.y - 48.0,
-                    4.0,
-                    Color::new(0.8, 0.1, 0.1, 1.0),
-                );
-                draw_circle(
-                    pos.x + 10.0,
-                    pos.y - 48.0,
-                    4.0,
-                    Color::new(0.8, 0.1, 0.1, 1.0),
-                );
+        }
+    }
+
+    fn render_hallway(&self) {
+        let time = get_time() as f32;
+        let sw = screen_width();
+        let sh = screen_height();
+
+        // Tiled floor - resolution friendly
+        let floor_y = sh * 0.78;
+        let tile_w = sw * 0.045;
+        let tile_h = sh * 0.065;
+        let tile_cols = (sw / tile_w).ceil() as i32;
+        let tile_rows = ((sh - floor_y) / tile_h).ceil() as i32;
+
+        for i in 0..tile_cols {
+            for j in 0..tile_rows {
+                let x = i as f32 * tile_w;
+                let y = floor_y + j as f32 * tile_h;
+                let shade = if (i + j) % 2 == 0 { 0.65 } else { 0.6 };
                 draw_rectangle(
-                    pos.x - 25.0,
-                    pos.y - 70.0,
-                    50.0,
-                    15.0,
-                    Color::new(0.3, 0.3, 0.8, 1.0),
+                    x,
+                    y,
+                    tile_w,
+                    tile_h,
+                    Color::new(shade, shade - 0.08, shade - 0.15, 1.0),
                 );
-                draw_circle(pos.x, pos.y - 62.0, 10.0, Color::new(0.1, 0.1, 0.1, 1.0));
             }
-            CharacterType::KeizerBomTaha => {
-                // Plane body - metallic silver
-                draw_rectangle(
-                    pos.x - 50.0,
-                    pos.y - 20.0,
-                    100.0,
-                    35.0,
-                    Color::new(0.7, 0.7, 0.75, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 48.0,
-                    pos.y - 18.0,
-                    96.0,
-                    31.0,
-                    Color::new(0.8, 0.8, 0.85, 1.0),
-                );
+        }
+
+        // Enhanced lockers with depth - resolution friendly
+        let locker_w = sw * 0.055;
+        let locker_h = sh * 0.46;
+        let locker_y = sh * 0.2;
+        let locker_count = (sw / locker_w).ceil().min(25.0) as i32;
+
+        for i in 0..locker_count {
+            let locker_x = i as f32 * locker_w;
+
+            // Shadow
+            draw_rectangle(
+                locker_x + 3.0,
+                locker_y + 3.0,
+                locker_w - 10.0,
+                locker_h,
+                Color::new(0.0, 0.0, 0.0, 0.3),
+            );
+            // Main locker body
+            draw_rectangle(
+                locker_x,
+                locker_y,
+                locker_w - 10.0,
+                locker_h,
+                Color::new(0.45, 0.45, 0.65, 1.0),
+            );
+            // Top highlight
+            draw_rectangle(
+                locker_x,
+                locker_y,
+                locker_w - 10.0,
+                locker_h * 0.08,
+                Color::new(0.55, 0.55, 0.75, 1.0),
+            );
+            // Outline
+            draw_rectangle_lines(
+                locker_x,
+                locker_y,
+                locker_w - 10.0,
+                locker_h,
+                3.0,
+                Color::new(0.35, 0.35, 0.55, 1.0),
+            );
 
-                // Cockpit window
-                draw_rectangle(
-                    pos.x - 30.0,
-                    pos.y - 15.0,
-                    25.0,
-                    15.0,
-                    Color::new(0.3, 0.5, 0.7, 0.7),
-                );
+            // Lock
+            let lock_x = locker_x + (locker_w - 10.0) * 0.5;
+            let lock_y = locker_y + locker_h * 0.5;
+            draw_circle(lock_x, lock_y, 7.0, Color::new(0.2, 0.2, 0.2, 1.0));
+            draw_circle(lock_x, lock_y, 5.0, Color::new(0.8, 0.8, 0.8, 1.0));
 
-                // Wings
-                draw_rectangle(
-                    pos.x - 80.0,
-                    pos.y,
-                    160.0,
-                    12.0,
-                    Color::new(0.65, 0.65, 0.7, 1.0),
-                );
+            // Divider line
+            draw_line(
+                locker_x,
+                lock_y - 5.0,
+                locker_x + locker_w - 10.0,
+                lock_y - 5.0,
+                2.0,
+                Color::new(0.35, 0.35, 0.55, 1.0),
+            );
+        }
 
-                // Keizer head in cockpit
-                draw_circle(
-                    pos.x - 17.0,
-                    pos.y - 8.0,
-                    10.0,
-                    Color::new(0.9, 0.8, 0.7, 1.0),
-                );
-                draw_circle(
-                    pos.x - 20.0,
-                    pos.y - 10.0,
-                    3.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
-                draw_circle(
-                    pos.x - 14.0,
-                    pos.y - 10.0,
-                    3.0,
-                    Color::new(0.2, 0.2, 0.2, 1.0),
-                );
+        // Ceiling with lights - resolution friendly
+        let ceiling_h = sh * 0.2;
+        draw_rectangle(0.0, 0.0, sw, ceiling_h, Color::new(0.92, 0.92, 0.87, 1.0));
 
-                // Propeller (animated)
-                let prop_angle = time * 20.0;
-                for i in 0..3 {
-                    let angle = prop_angle + (i as f32 * std::f32::consts::PI * 2.0 / 3.0);
-                    let px = pos.x + 50.0 + angle.cos() * 25.0;
-                    let py = pos.y + angle.sin() * 25.0;
-                    draw_line(
-                        pos.x + 50.0,
-                        pos.y,
-                        px,
-                        py,
-                        3.0,
-                        Color::new(0.5, 0.5, 0.5, 0.8),
-                    );
-                }
+        let light_spacing = sw * 0.12;
+        let light_count = (sw / light_spacing).floor() as i32;
+        let light_radius = (sw * 0.022).min(35.0);
 
-                // Bombs hanging underneath
-                let bomb_offset = (time * 3.0).sin() * 3.0;
-                for i in 0..2 {
-                    let bomb_x = pos.x - 20.0 + i as f32 * 40.0;
-                    let bomb_y = pos.y + 20.0 + bomb_offset;
-                    draw_ellipse(
-                        bomb_x,
-                        bomb_y,
-                        8.0,
-                        12.0,
-                        0.0,
-                        Color::new(0.2, 0.2, 0.2, 1.0),
-                    );
-                    draw_ellipse(
-                        bomb_x,
-                        bomb_y - 10.0,
-                        3.0,
-                        5.0,
-                        0.0,
-                        Color::new(0.8, 0.2, 0.2, 1.0),
-                    ); // Fuse
-                }
-            }
-            CharacterType::Gefferinho | CharacterType::YigitBaba => {
-                // Player characters - handled by render_selected_character above
-                // This should not be reached but is here for completeness
-                draw_circle(pos.x, pos.y - 45.0, 26.0, Color::new(0.9, 0.8, 0.7, 1.0));
-                draw_rectangle(
-                    pos.x - 20.0,
-                    pos.y - 20.0,
-                    40.0,
-                    50.0,
-                    Color::new(0.5, 0.5, 0.5, 1.0),
-                );
-            }
-            CharacterType::KeizerBomTahaBoss => {
-                // Boss version - same as player version for now
-                draw_rectangle(
-                    pos.x - 50.0,
-                    pos.y - 30.0,
-                    100.0,
-                    20.0,
-                    Color::new(0.7, 0.7, 0.75, 1.0),
-                );
-                draw_rectangle(
-                    pos.x - 30.0,
-                    pos.y - 35.0,
-                    60.0,
-                    10.0,
-                    Color::new(0.75, 0.75, 0.8, 1.0),
-                );
-            }
-            CharacterType::Mees => {
-                // Mees - blonde boy with medium hair who throws pita sirrachas
-                // HEAD - blonde hair
-                draw_circle(pos.x, pos.y - 45.0, 26.0, Color::new(1.0, 0.95, 0.65, 1.0)); // Blonde hair
-                // Face
-                draw_circle(pos.x, pos.y - 38.0, 22.0, Color::new(1.0, 0.85, 0.70, 1.0)); // Skin
-                // Eyes
-                draw_circle(pos.x - 7.0, pos.y - 40.0, 3.0, Color::new(0.4, 0.6, 0.8, 1.0)); // Blue eyes
-                draw_circle(pos.x + 7.0, pos.y - 40.0, 3.0, Color::new(0.4, 0.6, 0.8, 1.0));
-                // Body - red/orange shirt (pita sirracha colors)
-                draw_rectangle(
-                    pos.x - 22.0,
-                    pos.y - 20.0,
-                    44.0,
-                    55.0,
-                    Color::new(1.0, 0.3, 0.1, 1.0), // Orange-red for pita sirracha theme
-                );
-                // Draw pita in hand if attacking
-                if attack_phase > 0.0 {
-                    let hand_x = pos.x + 30.0 * fighter.facing;
-                    let hand_y = pos.y - 10.0;
-                    // Pita bread
-                    draw_ellipse(hand_x, hand_y, 15.0, 8.0, 0.3, Color::new(0.9, 0.8, 0.6, 1.0));
-                    // Sirracha sauce
-                    draw_circle(hand_x, hand_y, 5.0, Color::new(1.0, 0.2, 0.0, 0.8));
-                }
-            }
-        }
+        for i in 0..light_count {
+            let light_x = i as f32 * light_spacing + light_spacing * 0.5;
+            let light_y = ceiling_h * 0.5;
+            let glow = ((time * 2.0 + i as f32).sin() * 0.1 + 0.9) as f32;
 
-        if attack_phase > 0.0 {
-            self.render_attack_slash(base_pos, fighter, attack_phase);
+            // Glow effect
+            draw_circle(
+                light_x,
+                light_y,
+                light_radius * 1.2,
+                Color::new(1.0 * glow, 0.95 * glow, 0.8 * glow, 0.6),
+            );
+            // Light fixture
+            draw_circle(
+                light_x,
+                light_y,
+                light_radius * 0.8,
+                Color::new(1.0, 0.98, 0.85, 1.0),
+            );
         }
+    }
 
-        if matches!(state, FighterState::Hitstun) {
-            draw_circle_lines(
-                pos.x,
-                pos.y - 46.0,
-                28.0,
-                3.0,
-                Color::new(1.0, 0.3, 0.3, 0.5),
+    fn render_cafeteria(&self) {
+        let sw = screen_width();
+        let sh = screen_height();
+        let floor_y = sh * 0.78;
+
+        // Floor
+        draw_rectangle(
+            0.0,
+            floor_y,
+            sw,
+            sh - floor_y,
+            Color::new(0.8, 0.8, 0.7, 1.0),
+        );
+
+        // Tables - resolution friendly
+        let table_w = sw * 0.12;
+        let table_h = sh * 0.13;
+        let table_y = sh * 0.52;
+        let table_spacing = sw * 0.15;
+        let table_count = ((sw - sw * 0.1) / table_spacing).floor().min(8.0).max(3.0) as i32;
+
+        for i in 0..table_count {
+            let table_x = sw * 0.05 + i as f32 * table_spacing;
+
+            // Table top
+            draw_rectangle(
+                table_x,
+                table_y,
+                table_w,
+                table_h,
+                Color::new(0.6, 0.5, 0.4, 1.0),
+            );
+
+            // Table legs
+            let leg_w = table_w * 0.1;
+            let leg_h = sh * 0.1;
+            draw_rectangle(
+                table_x + table_w * 0.1,
+                table_y + table_h,
+                leg_w,
+                leg_h,
+                Color::new(0.4, 0.3, 0.2, 1.0),
+            );
+            draw_rectangle(
+                table_x + table_w * 0.8,
+                table_y + table_h,
+                leg_w,
+                leg_h,
+                Color::new(0.4, 0.3, 0.2, 1.0),
+            );
+
+            // Benches
+            let bench_w = table_w * 0.3;
+            let bench_h = table_h * 0.2;
+            let bench_y = table_y - table_h * 0.25;
+
+            draw_rectangle(
+                table_x + table_w * 0.1,
+                bench_y,
+                bench_w,
+                bench_h,
+                Color::new(0.5, 0.4, 0.3, 1.0),
+            );
+            draw_rectangle(
+                table_x + table_w * 0.6,
+                bench_y,
+                bench_w,
+                bench_h,
+                Color::new(0.5, 0.4, 0.3, 1.0),
             );
         }
 
-        if matches!(state, FighterState::Special | FighterState::Super) {
-            let aura_alpha = 0.25 + 0.35 * (attack_phase.max(0.1));
-            draw_circle_lines(
-                pos.x,
-                pos.y - 40.0,
-                36.0 + attack_phase * 14.0,
-                2.0,
-                Color::new(0.4, 0.8, 1.0, aura_alpha),
+        // Counter/service area
+        let counter_h = sh * 0.2;
+        draw_rectangle(
+            0.0,
+            sh * 0.13,
+            sw,
+            counter_h,
+            Color::new(0.7, 0.7, 0.7, 1.0),
+        );
+
+        // Menu board
+        let menu_w = sw * 0.13;
+        let menu_h = sh * 0.13;
+        let menu_x = sw * 0.04;
+        let menu_y = sh * 0.16;
+
+        draw_rectangle(menu_x, menu_y, menu_w, menu_h, Color::new(0.9, 0.9, 0.9, 1.0));
+
+        let menu_font = (menu_w * 0.15).max(20.0);
+        draw_text(
+            "MENU",
+            menu_x + menu_w * 0.2,
+            menu_y + menu_h * 0.6,
+            menu_font,
+            Color::new(0.2, 0.2, 0.2, 1.0),
+        );
+    }
+
+    fn render_gym(&self) {
+        let sw = screen_width();
+        let sh = screen_height();
+        let floor_y = sh * 0.78;
+
+        // Wooden floor
+        draw_rectangle(
+            0.0,
+            floor_y,
+            sw,
+            sh - floor_y,
+            Color::new(0.9, 0.7, 0.5, 1.0),
+        );
+
+        // Floor lines - resolution friendly
+        let line_spacing = sw * 0.055;
+        let line_count = (sw / line_spacing).ceil() as i32;
+
+        for i in 0..line_count {
+            let line_x = i as f32 * line_spacing;
+            draw_line(
+                line_x,
+                floor_y,
+                line_x,
+                sh,
+                3.0,
+                Color::new(0.8, 0.6, 0.4, 1.0),
             );
         }
+
+        // Center court circle - resolution friendly
+        let circle_y = sh * 0.59;
+        let circle_radius = (sw * 0.06).min(sh * 0.13);
+
+        draw_circle(sw * 0.5, circle_y, circle_radius, Color::new(1.0, 1.0, 1.0, 0.3));
+        draw_circle_lines(sw * 0.5, circle_y, circle_radius, 5.0, WHITE);
+
+        // Basketball hoops - resolution friendly
+        let hoop_y = sh * 0.26;
+        let pole_w = sw * 0.008;
+        let pole_h = sh * 0.26;
+        let backboard_w = sw * 0.008;
+        let backboard_h = sh * 0.39;
+        let rim_radius = (sw * 0.032).min(sh * 0.065);
+
+        // Left hoop
+        let left_x = sw * 0.08;
+        draw_rectangle(left_x, hoop_y, pole_w, pole_h, Color::new(0.8, 0.8, 0.8, 1.0));
+        draw_circle(left_x, hoop_y - rim_radius * 0.4, rim_radius, Color::new(1.0, 0.6, 0.2, 1.0));
+        draw_rectangle(
+            left_x - backboard_w * 0.5,
+            hoop_y - rim_radius * 0.4,
+            backboard_w,
+            backboard_h,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+
+        // Right hoop
+        let right_x = sw - sw * 0.08;
+        draw_rectangle(right_x, hoop_y, pole_w, pole_h, Color::new(0.8, 0.8, 0.8, 1.0));
+        draw_circle(right_x, hoop_y - rim_radius * 0.4, rim_radius, Color::new(1.0, 0.6, 0.2, 1.0));
     }
 
-    fn render_selected_character(
-        &self,
-        pos: Vec2,
-        state: FighterState,
-        attack_phase: f32,
-        facing: f32,
-    ) {
-        let time = get_time() as f32;
+    fn render_library(&self) {
+        let sw = screen_width();
+        let sh = screen_height();
+        let floor_y = sh * 0.78;
 
-        // Get character-specific attributes based on selection
-        let (skin_color, head_size, body_scale, hair_style) = match self.selected_character {
-            CharacterId::Bas => (Color::new(0.85, 0.65, 0.45, 1.0), 28.0, 1.15, 0), // Bigger, brown skin
-            CharacterId::Berkay => (Color::new(1.0, 0.85, 0.70, 1.0), 26.0, 1.0, 1), // Normal, light skin
-            CharacterId::Gefferinho => (Color::new(0.65, 0.45, 0.30, 1.0), 26.0, 1.0, 2), // Brown skin
-            CharacterId::Hadi => (Color::new(0.65, 0.45, 0.30, 1.0), 26.0, 1.0, 3), // Brown skin
-            CharacterId::Nitin => (Color::new(0.65, 0.45, 0.30, 1.0), 26.0, 1.0, 4), // Brown skin
-            CharacterId::Luca => (Color::new(1.0, 0.85, 0.70, 1.0), 26.0, 1.0, 5), // Normal, light skin
-            CharacterId::PalaBaba => (Color::new(1.0, 0.90, 0.80, 1.0), 26.0, 1.0, 6), // White skin
-            CharacterId::Fufinho => (Color::new(0.45, 0.30, 0.20, 1.0), 26.0, 1.05, 7), // Dark brown skin
-            CharacterId::EfeAbi => (Color::new(0.90, 0.75, 0.60, 1.0), 26.0, 1.1, 8), // Light brown skin
-            CharacterId::Jad => (Color::new(1.0, 0.85, 0.70, 1.0), 26.0, 1.2, 9), // Normal skin, wider build
-            CharacterId::Umut => (Color::new(0.95, 0.80, 0.65, 1.0), 25.0, 1.1, 10), // Slightly tan, medium build
-            CharacterId::KeizerBomTaha => (Color::new(0.85, 0.75, 0.65, 1.0), 28.0, 1.2, 11), // Military commander
-            CharacterId::GoonLordTobese => (Color::new(1.0, 0.95, 0.90, 1.0), 25.0, 1.0, 12), // White/pale skin
-        };
+        // Floor
+        draw_rectangle(
+            0.0,
+            floor_y,
+            sw,
+            sh - floor_y,
+            Color::new(0.5, 0.4, 0.3, 1.0),
+        );
 
-        let brown_hair = Color::new(0.3, 0.2, 0.1, 1.0);
-        let black_hair = Color::new(0.1, 0.1, 0.1, 1.0);
+        // Bookshelves - resolution friendly
+        let shelf_w = sw * 0.095;
+        let shelf_h = sh * 0.52;
+        let shelf_y = sh * 0.2;
+        let shelf_spacing = sw * 0.125;
+        let shelf_count = ((sw - sw * 0.1) / shelf_spacing).floor().min(10.0).max(4.0) as i32;
 
-        // Arm animation
-        let arm_idle = Vec2::new(15.0, (time * 12.0).cos() * 1.5);
-        let arm_offset = match state {
-            FighterState::LightAttack
-            | FighterState::HeavyAttack
-            | FighterState::Special
-            | FighterState::Super => {
-                Vec2::new(28.0 + 40.0 * attack_phase, -12.0 - 24.0 * attack_phase)
-            }
-            _ => arm_idle,
-        };
+        for i in 0..shelf_count {
+            let shelf_x = sw * 0.05 + i as f32 * shelf_spacing;
 
-        // Shadow
-        draw_circle(
-            pos.x + 1.5,
-            pos.y - 43.0 * body_scale,
-            head_size * 0.9,
-            Color::new(0.7, 0.6, 0.5, 0.5),
-        );
+            // Shelf frame
+            draw_rectangle(
+                shelf_x,
+                shelf_y,
+                shelf_w,
+                shelf_h,
+                Color::new(0.4, 0.3, 0.2, 1.0),
+            );
 
-        // HEAD
-        draw_circle(pos.x, pos.y - 45.0 * body_scale, head_size, skin_color);
-        draw_circle(
-            pos.x - 6.0,
-            pos.y - 48.0 * body_scale,
-            10.0,
-            Color::new(
-                skin_color.r * 1.1,
-                skin_color.g * 1.1,
-                skin_color.b * 1.1,
-                0.4,
-            ),
-        );
-
-        // HAIR based on character
-        match hair_style {
-            0 => {
-                // Bas - big curly hair
-                for i in 0..6 {
-                    let angle = (i as f32 / 6.0) * std::f32::consts::PI * 2.0;
-                    draw_circle(
-                        pos.x + angle.cos() * head_size * 0.7,
-                        pos.y - 45.0 * body_scale - head_size * 0.5 + angle.sin() * head_size * 0.5,
-                        head_size * 0.4,
-                        brown_hair,
-                    );
-                }
-            }
-            1 => {
-                // Berkay - fluffy hair
-                draw_circle(
-                    pos.x,
-                    pos.y - 60.0 * body_scale,
-                    head_size * 0.85,
-                    brown_hair,
-                );
-                draw_circle(
-                    pos.x - head_size * 0.4,
-                    pos.y - 55.0 * body_scale,
-                    head_size * 0.5,
-                    brown_hair,
-                );
-                draw_circle(
-                    pos.x + head_size * 0.4,
-                    pos.y - 55.0 * body_scale,
-                    head_size * 0.5,
-                    brown_hair,
+            // Shelves
+            let shelf_rows = 5;
+            let row_h = shelf_h / shelf_rows as f32;
+
+            for j in 0..shelf_rows {
+                let row_y = shelf_y + j as f32 * row_h + row_h * 0.15;
+                let row_shelf_h = row_h * 0.78;
+
+                draw_rectangle(
+                    shelf_x + shelf_w * 0.05,
+                    row_y,
+                    shelf_w * 0.9,
+                    row_shelf_h,
+                    Color::new(0.3, 0.2, 0.1, 1.0),
                 );
-            }
-            2 => {
-                // Gefferinho - curly hair
-                for i in 0..5 {
-                    let angle = (i as f32 / 5.0) * std::f32::consts::PI * 2.0;
-                    draw_circle(
-                        pos.x + angle.cos() * head_size * 0.6,
-                        pos.y - 45.0 * body_scale - head_size * 0.4 + angle.sin() * head_size * 0.4,
-                        head_size * 0.35,
-                        brown_hair,
+
+                // Books
+                let book_w = shelf_w * 0.08;
+                let book_count = ((shelf_w * 0.9) / book_w).floor() as i32;
+
+                for k in 0..book_count {
+                    let book_x = shelf_x + shelf_w * 0.08 + k as f32 * book_w;
+                    let book_color = if k % 3 == 0 {
+                        Color::new(0.7, 0.2, 0.2, 1.0)
+                    } else if k % 3 == 1 {
+                        Color::new(0.2, 0.4, 0.7, 1.0)
+                    } else {
+                        Color::new(0.2, 0.6, 0.2, 1.0)
+                    };
+                    draw_rectangle(
+                        book_x,
+                        row_y + row_shelf_h * 0.1,
+                        book_w * 0.9,
+                        row_shelf_h * 0.8,
+                        book_color,
                     );
                 }
             }
-            3 => {
-                // Hadi - buzz cut
-                draw_circle(
-                    pos.x,
-                    pos.y - 50.0 * body_scale,
-                    head_size * 0.9,
-                    black_hair,
-                );
-            }
-            4 => {
-                // Nitin - slicked back
-                draw_rectangle(
-                    pos.x - head_size * 0.7,
-                    pos.y - 65.0 * body_scale,
-                    head_size * 1.4,
-                    head_size * 0.7,
-                    brown_hair,
-                );
-            }
-            5 => {
-                // Luca - normal hair
-                draw_circle(
-                    pos.x,
-                    pos.y - 58.0 * body_scale,
-                    head_size * 0.9,
-                    brown_hair,
-                );
-            }
-            6 => {
-                // Yigit Baba - medium hair
-                draw_circle(
-                    pos.x,
-                    pos.y - 58.0 * body_scale,
-                    head_size * 0.85,
-                    brown_hair,
-                );
-                draw_circle(
-                    pos.x - head_size * 0.35,
-                    pos.y - 55.0 * body_scale,
-                    head_size * 0.45,
-                    brown_hair,
-                );
-                draw_circle(
-                    pos.x + head_size * 0.35,
-                    pos.y - 55.0 * body_scale,
-                    head_size * 0.45,
-                    brown_hair,
-                );
-            }
-            _ => {}
         }
 
-        // NECK
-        draw_rectangle(
-            pos.x - 8.0 * body_scale,
-            pos.y - 22.0 * body_scale,
-            16.0 * body_scale,
-            10.0,
-            Color::new(
-                skin_color.r * 0.95,
-                skin_color.g * 0.95,
-                skin_color.b * 0.95,
-                1.0,
-            ),
-        );
-
-        // TORSO with different colored shirt based on character
-        let shirt_color = match self.selected_character {
-            CharacterId::Bas => Color::new(0.2, 0.35, 0.85, 1.0), // Blue
-            CharacterId::Berkay => Color::new(0.85, 0.4, 0.0, 1.0), // Orange
-            CharacterId::Luca => Color::new(0.3, 0.6, 1.0, 1.0),  // Light Blue
-            CharacterId::Gefferinho => Color::new(0.8, 0.2, 0.2, 1.0), // Red
-            CharacterId::Hadi => Color::new(0.9, 0.7, 0.0, 1.0),  // Gold
-            CharacterId::Nitin => Color::new(0.9, 0.3, 0.0, 1.0), // Fire Orange
-            CharacterId::PalaBaba => Color::new(0.7, 0.0, 0.15, 1.0), // Crimson
-            CharacterId::Fufinho => Color::new(0.6, 0.0, 0.6, 1.0), // Purple
-            CharacterId::EfeAbi => Color::new(0.7, 0.3, 0.1, 1.0), // Brown (lahmacun color)
-            CharacterId::Jad => Color::new(0.8, 0.0, 0.0, 1.0), // Red (KFC rage)
-            CharacterId::Umut => Color::new(0.4, 0.2, 0.6, 1.0), // Purple (Terraria)
-            CharacterId::KeizerBomTaha => Color::new(0.2, 0.4, 0.2, 1.0), // Military green
-            CharacterId::GoonLordTobese => Color::new(0.95, 0.95, 0.95, 1.0), // White (milk)
-        };
+        // Reading table - resolution friendly
+        let table_w = sw * 0.13;
+        let table_h = sh * 0.1;
+        let table_x = sw * 0.5 - table_w * 0.5;
+        let table_y = sh * 0.59;
 
+        draw_rectangle(table_x, table_y, table_w, table_h, Color::new(0.6, 0.5, 0.4, 1.0));
+
+        // Chairs
+        let chair_size = table_h * 0.75;
+        let chair_y = table_y - chair_size - sh * 0.015;
+
+        draw_rectangle(
+            table_x + table_w * 0.1,
+            chair_y,
+            chair_size,
+            chair_size,
+            Color::new(0.3, 0.3, 0.3, 1.0),
+        );
         draw_rectangle(
-            pos.x - 22.0 * body_scale,
-            pos.y - 18.0 * body_scale,
-            44.0 * body_scale,
-            52.0 * body_scale,
-            Color::new(
-                shirt_color.r * 0.7,
-                shirt_color.g * 0.7,
-                shirt_color.b * 0.7,
-                1.0,
-            ),
+            table_x + table_w * 0.6,
+            chair_y,
+            chair_size,
+            chair_size,
+            Color::new(0.3, 0.3, 0.3, 1.0),
         );
+    }
+
+    fn render_rooftop(&self) {
+        let sw = screen_width();
+        let sh = screen_height();
+        let floor_y = sh * 0.78;
+
+        // Rooftop floor
         draw_rectangle(
-            pos.x - 20.0 * body_scale,
-            pos.y - 20.0 * body_scale,
-            40.0 * body_scale,
-            50.0 * body_scale,
-            shirt_color,
+            0.0,
+            floor_y,
+            sw,
+            sh - floor_y,
+            Color::new(0.4, 0.4, 0.4, 1.0),
         );
 
-        // PANTS
+        // Clouds - resolution friendly
+        let cloud_count = ((sw / (sw * 0.32)).ceil() as i32).max(2).min(5);
+        let cloud_spacing = sw / cloud_count as f32;
+
+        for i in 0..cloud_count {
+            let cloud_x = cloud_spacing * 0.3 + i as f32 * cloud_spacing;
+            let cloud_y = sh * 0.13;
+            let cloud_r1 = sw * 0.05;
+            let cloud_r2 = sw * 0.038;
+
+            draw_circle(cloud_x, cloud_y, cloud_r1, Color::new(1.0, 1.0, 1.0, 0.8));
+            draw_circle(
+                cloud_x + cloud_r1 * 0.6,
+                cloud_y + cloud_r1 * 0.25,
+                cloud_r2,
+                Color::new(1.0, 1.0, 1.0, 0.7),
+            );
+        }
+
+        // Roof edge and railing
+        let edge_y = floor_y - sh * 0.026;
+        draw_rectangle(0.0, edge_y, sw, sh * 0.026, Color::new(0.6, 0.6, 0.6, 1.0));
         draw_rectangle(
-            pos.x - 15.0 * body_scale,
-            pos.y + 30.0 * body_scale,
-            30.0 * body_scale,
-            40.0 * body_scale,
-            Color::new(0.35, 0.35, 0.45, 1.0),
+            0.0,
+            edge_y + sh * 0.013,
+            sw,
+            sh * 0.0065,
+            Color::new(0.8, 0.8, 0.0, 1.0),
         );
 
-        // LEGS
+        // Safety fence - resolution friendly
+        let fence_y = sh * 0.59;
+        let fence_h = sh * 0.17;
+        let fence_spacing = sw * 0.09;
+        let fence_count = (sw / fence_spacing).ceil() as i32;
+
+        for i in 0..fence_count {
+            let fence_x = i as f32 * fence_spacing;
+            draw_rectangle(
+                fence_x,
+                fence_y,
+                sw * 0.004,
+                fence_h,
+                Color::new(0.3, 0.3, 0.3, 1.0),
+            );
+        }
+
+        // Horizontal fence rails
+        draw_rectangle(0.0, fence_y, sw, sh * 0.01, Color::new(0.3, 0.3, 0.3, 1.0));
         draw_rectangle(
-            pos.x - 10.0 * body_scale,
-            pos.y + 70.0 * body_scale,
-            8.0 * body_scale,
-            20.0,
-            Color::new(0.35, 0.35, 0.45, 1.0),
+            0.0,
+            fence_y + fence_h * 0.5,
+            sw,
+            sh * 0.01,
+            Color::new(0.3, 0.3, 0.3, 1.0),
         );
+
+        // AC unit - resolution friendly
+        let ac_w = sw * 0.06;
+        let ac_h = sh * 0.13;
+        let ac_x = sw * 0.08;
+        let ac_y = sh * 0.46;
+
+        draw_rectangle(ac_x, ac_y, ac_w, ac_h, Color::new(0.7, 0.7, 0.7, 1.0));
         draw_rectangle(
-            pos.x + 2.0 * body_scale,
-            pos.y + 70.0 * body_scale,
-            8.0 * body_scale,
-            20.0,
-            Color::new(0.35, 0.35, 0.45, 1.0),
+            ac_x + ac_w * 0.125,
+            ac_y + ac_h * 0.1,
+            ac_w * 0.75,
+            ac_h * 0.3,
+            Color::new(0.2, 0.2, 0.2, 1.0),
+        );
+
+        // Water tower - resolution friendly
+        let tower_w = sw * 0.095;
+        let tower_h = sh * 0.2;
+        let tower_x = sw - tower_w - sw * 0.04;
+        let tower_y = sh * 0.39;
+
+        draw_rectangle(tower_x, tower_y, tower_w, tower_h, Color::new(0.8, 0.8, 0.8, 1.0));
+        draw_circle(
+            tower_x + tower_w * 0.5,
+            tower_y + tower_h * 0.5,
+            tower_w * 0.27,
+            Color::new(0.6, 0.6, 0.6, 1.0),
         );
+    }
+
+    /// Ground position and drawn position (lifted by knockback) in world space
+    fn draw_position(&self, entity: EntityId) -> Option<(Vec2, Vec2)> {
+        let transform = self.world.get_component::<Transform>(entity)?;
+        let ground_pos = self
+            .transform_snapshot
+            .position(entity, transform.position, self.render_alpha);
+        // Launched fighters are drawn above their shadow
+        let lift = self
+            .world
+            .get_component::<Knockback>(entity)
+            .map(|kb| kb.height)
+            .unwrap_or(0.0);
+        Some((ground_pos, ground_pos - Vec2::new(0.0, lift)))
+    }
+
+    fn character_sheet(&self, fighter: &Fighter, is_player: bool) -> &'static str {
+        if is_player {
+            return CharacterSheets::sheet_for(self.selected_character);
+        }
+
+        match &fighter.character_type {
+            CharacterType::Bas => "bas",
+            CharacterType::Berkay => "berkay",
+            CharacterType::Gefferinho => "gefferinho",
+            CharacterType::Hadi => "hadi",
+            CharacterType::Nitin => "nitin",
+            CharacterType::Luca => "luca",
+            CharacterType::YigitBaba => "palababa",
+            CharacterType::KeizerBomTaha => "keizer",
+            CharacterType::Wolters => "wolters",
+            CharacterType::PrefectA => "prefect_a",
+            CharacterType::PrefectB => "prefect_b",
+            CharacterType::Chef => "chef",
+            CharacterType::Librarian => "librarian",
+            CharacterType::Coach => "coach",
+            CharacterType::Bastiaan => "bastiaan",
+            CharacterType::KeizerBomTahaBoss => "keizer_boss",
+            CharacterType::Mees => "mees",
+        }
+    }
+
+    /// Queue the fighter's current animation frame into the sprite batch
+    fn queue_character(
+        &self,
+        batch: &mut SpriteBatch,
+        entity: EntityId,
+        base_pos: Vec2,
+        fighter: &Fighter,
+        is_player: bool,
+    ) {
+        let Some(sheets) = &self.character_sheets else {
+            return;
+        };
+        let tint = match fighter.state {
+            FighterState::Super => Color::new(1.0, 0.95, 0.7, 1.0),
+            FighterState::Special => Color::new(0.8, 0.85, 1.0, 1.0),
+            FighterState::Hitstun => Color::new(1.0, 0.5, 0.5, 1.0),
+            _ => WHITE,
+        };
+        // The player's cosmetic palette, or failing that their mastery palette swap
+        let (tint, skin) = if is_player {
+            let skin = self.cosmetics.tint.unwrap_or_else(|| self.character_mastery.skin_tint());
+            (Color::new(tint.r * skin.r, tint.g * skin.g, tint.b * skin.b, tint.a), skin)
+        } else {
+            (tint, WHITE)
+        };
+        let sheet = match self.world.get_component::<Assist>(entity) {
+            Some(assist) => CharacterSheets::sheet_for(assist.character),
+            None => self.character_sheet(fighter, is_player),
+        };
+        let flip_x = fighter.facing < 0.0;
+
+        if let Some(pose) = self.player_pose.as_ref().filter(|_| is_player) {
+            sheets.queue(
+                batch,
+                sheet,
+                SpriteAnimation::for_pose(pose.variant.motion),
+                pose.elapsed,
+                pose.progress(),
+                base_pos,
+                flip_x,
+                skin,
+            );
+            return;
+        }
+
+        let sample = match self.world.get_component::<AnimationPlayer>(entity) {
+            Some(player) => player.sample(),
+            None => AnimationPlayer::new().sample(),
+        };
+        sheets
+            .queue_sample(batch, sheet, &sample, base_pos, flip_x, tint);
+    }
+
+    /// Attack slashes, hit rings and combat lighting drawn over the sprites
+    fn render_character_overlay(&self, pos: Vec2, fighter: &Fighter) {
+        let state = fighter.state;
+        let attack_phase = Self::attack_phase(state, fighter.attack_timer);
+
+        if attack_phase > 0.0 {
+            self.render_attack_slash(pos, fighter, attack_phase);
+        }
 
-        // SHOES
-        draw_ellipse(
-            pos.x - 6.0 * body_scale,
-            pos.y + 91.0 * body_scale,
-            9.0,
-            5.0,
-            0.0,
-            Color::new(0.25, 0.25, 0.25, 1.0),
-        );
-        draw_ellipse(
-            pos.x + 6.0 * body_scale,
-            pos.y + 91.0 * body_scale,
-            9.0,
-            5.0,
-            0.0,
-            Color::new(0.25, 0.25, 0.25, 1.0),
-        );
+        if matches!(state, FighterState::Hitstun) {
+            draw_circle_lines(
+                pos.x,
+                pos.y - 46.0,
+                28.0,
+                3.0,
+                Color::new(1.0, 0.3, 0.3, 0.5),
+            );
+        }
 
-        // ARMS
-        draw_rectangle(
-            pos.x - 26.0 * body_scale,
-            pos.y - 9.0 * body_scale,
-            8.0 * body_scale,
-            30.0,
-            skin_color,
-        );
-        draw_rectangle(
-            pos.x + 17.0 * body_scale,
-            pos.y - 10.0 * body_scale + arm_offset.y,
-            8.0 * body_scale,
-            30.0,
-            skin_color,
-        );
+        if matches!(state, FighterState::Special | FighterState::Super) {
+            let aura_alpha = 0.25 + 0.35 * (attack_phase.max(0.1));
+            draw_circle_lines(
+                pos.x,
+                pos.y - 40.0,
+                36.0 + attack_phase * 14.0,
+                2.0,
+                Color::new(0.4, 0.8, 1.0, aura_alpha),
+            );
+        }
 
-        // HANDS/FISTS
         if matches!(
             state,
             FighterState::LightAttack
@@ -5905,111 +9333,9 @@ impl GameplayState {
                 | FighterState::Special
                 | FighterState::Super
         ) {
-            draw_circle(
-                pos.x + 25.0 + arm_offset.x,
-                pos.y + arm_offset.y,
-                9.0,
-                skin_color,
-            );
-
-            // Render attack slash effect
-            let slash_pos = pos + Vec2::new(25.0 + arm_offset.x, arm_offset.y);
-            let slash_color = match self.selected_character {
-                CharacterId::Bas => Color::new(0.0, 1.0, 0.5, 0.8),
-                CharacterId::Berkay => Color::new(1.0, 0.5, 0.0, 0.8),
-                CharacterId::Luca => Color::new(0.3, 0.6, 1.0, 0.8),
-                CharacterId::Gefferinho => Color::new(1.0, 0.2, 0.2, 0.8),
-                CharacterId::Hadi => Color::new(1.0, 0.8, 0.0, 0.8),
-                CharacterId::Nitin => Color::new(1.0, 0.3, 0.0, 0.8),
-                CharacterId::PalaBaba => Color::new(0.8, 0.0, 0.2, 0.8),
-                CharacterId::Fufinho => Color::new(0.9, 0.0, 0.9, 0.8), // Purple
-                CharacterId::EfeAbi => Color::new(0.7, 0.3, 0.1, 0.8), // Brown
-                CharacterId::Jad => Color::new(1.0, 0.0, 0.0, 0.8), // Red
-                CharacterId::Umut => Color::new(0.6, 0.2, 1.0, 0.8), // Purple
-                CharacterId::KeizerBomTaha => Color::new(0.0, 0.8, 0.4, 0.8), // Green
-                CharacterId::GoonLordTobese => Color::new(1.0, 1.0, 1.0, 0.8), // White (milk)
-            };
-            let slash_size = 30.0 + attack_phase * 20.0;
-            draw_circle(
-                slash_pos.x,
-                slash_pos.y,
-                slash_size,
-                Color::new(slash_color.r, slash_color.g, slash_color.b, 0.3),
-            );
-            draw_circle(slash_pos.x, slash_pos.y, slash_size * 0.7, slash_color);
-        } else {
-            draw_circle(
-                pos.x + 17.0 * body_scale,
-                pos.y + 22.0 * body_scale,
-                6.0,
-                skin_color,
-            );
-            draw_circle(
-                pos.x - 25.0 * body_scale,
-                pos.y + 22.0 * body_scale,
-                6.0,
-                skin_color,
-            );
+            self.add_combat_lighting(pos, state, attack_phase);
+            self.add_combat_vfx(pos, state, attack_phase, fighter.facing);
         }
-
-        // FACE - Eyes
-        draw_circle(
-            pos.x - 9.0,
-            pos.y - 47.0 * body_scale,
-            5.0,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        );
-        draw_circle(
-            pos.x + 9.0,
-            pos.y - 47.0 * body_scale,
-            5.0,
-            Color::new(1.0, 1.0, 1.0, 1.0),
-        );
-        draw_circle(
-            pos.x - 8.0,
-            pos.y - 46.0 * body_scale,
-            3.5,
-            Color::new(0.15, 0.3, 0.5, 1.0),
-        );
-        draw_circle(
-            pos.x + 8.0,
-            pos.y - 46.0 * body_scale,
-            3.5,
-            Color::new(0.15, 0.3, 0.5, 1.0),
-        );
-
-        // Eyebrows
-        draw_line(
-            pos.x - 12.0,
-            pos.y - 52.0 * body_scale,
-            pos.x - 5.0,
-            pos.y - 53.0 * body_scale,
-            2.0,
-            Color::new(0.3, 0.25, 0.2, 1.0),
-        );
-        draw_line(
-            pos.x + 12.0,
-            pos.y - 52.0 * body_scale,
-            pos.x + 5.0,
-            pos.y - 53.0 * body_scale,
-            2.0,
-            Color::new(0.3, 0.25, 0.2, 1.0),
-        );
-
-        // Mouth
-        let mouth_y = if matches!(state, FighterState::Hitstun) {
-            -36.0
-        } else {
-            -38.0
-        };
-        draw_line(
-            pos.x - 6.0,
-            pos.y + mouth_y * body_scale,
-            pos.x + 6.0,
-            pos.y + mouth_y * body_scale,
-            2.0,
-            Color::new(0.6, 0.3, 0.3, 1.0),
-        );
     }
 
     fn render_attack_slash(&self, pos: Vec2, fighter: &Fighter, phase: f32) {
@@ -6055,11 +9381,10 @@ impl GameplayState {
         }
     }
 
-    fn render_combo_ui(&self) {
+    fn render_combo_ui(&self, ui: &UiLayout) {
         // Render combo system UI
         if self.combo_system.is_active() {
-            let x = screen_width() - 300.0;
-            let y = 150.0;
+            let Vec2 { x, y } = ui.anchor(Anchor::TopRight, Vec2::new(300.0, 150.0));
 
             // Combo count
             let combo_text = format!("{} HIT COMBO", self.combo_system.combo_count);
@@ -6093,8 +9418,8 @@ impl GameplayState {
         }
 
         // Render character mastery progress
-        let mastery_x = 50.0;
-        let mastery_y = screen_height() - 100.0;
+        let mastery = ui.anchor(Anchor::BottomLeft, Vec2::new(50.0, 100.0));
+        let (mastery_x, mastery_y) = (mastery.x, mastery.y);
 
         let level_text = format!("Mastery Lv.{}", self.character_mastery.level);
         draw_text(&level_text, mastery_x, mastery_y, 18.0,
@@ -6119,11 +9444,16 @@ impl GameplayState {
         let acc_x = mastery_x;
         let acc_y = mastery_y + 50.0;
 
-        let acc_text = format!("Account Lv.{}", self.account_progression.level);
+        let acc_text = format!("Account Lv.{}", self.account.progression.level);
         draw_text(&acc_text, acc_x, acc_y, 16.0, Color::new(1.0, 0.8, 0.0, 1.0));
+        if let Some(badge) = self.account.badge() {
+            let width = measure_text(&acc_text, None, 16, 1.0).width;
+            let prestige = self.account.prestige.prestige_level;
+            render_prestige_badge(badge, prestige, acc_x + width + 16.0, acc_y - 5.0, 10.0);
+        }
     }
 
-    fn render_controls(&self) {
+    fn render_controls(&self, ui: &UiLayout) {
         if self.show_controls {
             let alpha = self.control_fade.max(0.0);
             let header_color = Color::new(1.0, 1.0, 1.0, alpha);
@@ -6143,8 +9473,11 @@ impl GameplayState {
             let row_height = 30.0;
             let block_height = controls.len() as f32 * row_height + 24.0;
             let block_width = 280.0;
-            let origin_x = screen_width() - block_width - 20.0;
-            let origin_y = screen_height() - block_height - 20.0;
+            let origin = ui.anchor(
+                Anchor::BottomRight,
+                Vec2::new(block_width + 20.0, block_height + 20.0),
+            );
+            let (origin_x, origin_y) = (origin.x, origin.y);
 
             draw_rectangle(origin_x, origin_y, block_width, block_height, bg_color);
             draw_rectangle_lines(
@@ -6165,13 +9498,16 @@ impl GameplayState {
     }
 
     fn add_combat_vfx(&self, pos: Vec2, state: FighterState, phase: f32, facing: f32) {
+        let Some(textures) = &self.texture_manager else {
+            return;
+        };
         // Add spectacular visual effects based on attack type
         let time = get_time() as f32;
 
         match state {
             FighterState::Super => {
                 // Create explosion effect
-                if let Some(explosion_sprite) = self.texture_manager.get_sprite("explosion") {
+                if let Some(explosion_sprite) = textures.get_sprite("explosion") {
                     let mut explosion = explosion_sprite.clone();
                     explosion.position = pos - Vec2::new(128.0, 128.0);
                     explosion.size = Vec2::new(256.0, 256.0) * (1.0 + phase * 0.5);
@@ -6195,7 +9531,7 @@ impl GameplayState {
             }
             FighterState::Special => {
                 // Create aura effect
-                if let Some(aura_sprite) = self.texture_manager.get_sprite("aura") {
+                if let Some(aura_sprite) = textures.get_sprite("aura") {
                     let mut aura = aura_sprite.clone();
                     aura.position = pos - Vec2::new(128.0, 128.0);
                     aura.size = Vec2::new(256.0, 256.0) * (0.8 + (time * 3.0).sin() * 0.2);
@@ -6205,7 +9541,7 @@ impl GameplayState {
 
                 // Lightning effects for special
                 if phase > 0.3 && phase < 0.7 {
-                    if let Some(lightning_sprite) = self.texture_manager.get_sprite("lightning") {
+                    if let Some(lightning_sprite) = textures.get_sprite("lightning") {
                         let mut lightning = lightning_sprite.clone();
                         lightning.position = pos + Vec2::new(facing * 50.0, -100.0);
                         lightning.size = Vec2::new(128.0, 256.0);
@@ -6216,7 +9552,7 @@ impl GameplayState {
             }
             FighterState::HeavyAttack => {
                 // Fire effect for heavy attacks
-                if let Some(fire_sprite) = self.texture_manager.get_sprite("fire") {
+                if let Some(fire_sprite) = textures.get_sprite("fire") {
                     let mut fire = fire_sprite.clone();
                     fire.position = pos + Vec2::new(facing * (30.0 + phase * 50.0), -20.0);
                     fire.size = Vec2::new(64.0, 96.0) * (1.0 + phase * 0.3);
@@ -6226,7 +9562,7 @@ impl GameplayState {
 
                 // Impact effect
                 if phase > 0.5 {
-                    if let Some(impact_sprite) = self.texture_manager.get_sprite("impact") {
+                    if let Some(impact_sprite) = textures.get_sprite("impact") {
                         let mut impact = impact_sprite.clone();
                         impact.position = pos + Vec2::new(facing * 80.0, 0.0);
                         impact.size = Vec2::new(128.0, 128.0);
@@ -6344,75 +9680,284 @@ impl GameplayState {
         }
     }
 
-    fn render_dialogue(&self) {
-        if let Some(ref dialogue) = self.current_dialogue {
-            let box_height = 150.0;
-            let box_y = screen_height() - box_height - 20.0;
+    fn render_route_choice(&self, ui: &UiLayout) {
+        draw_rectangle(
+            0.0,
+            0.0,
+            ui.width(),
+            ui.height(),
+            Color::new(0.0, 0.0, 0.0, 0.75),
+        );
 
-            draw_rectangle(
-                20.0,
-                box_y,
-                screen_width() - 40.0,
-                box_height,
-                Color::new(0.0, 0.0, 0.0, 0.9),
+        let title = localization::tr("gameplay.choose_route");
+        let title_dims = measure_text(&title, None, 48, 1.0);
+        draw_text(
+            &title,
+            ui.width() * 0.5 - title_dims.width * 0.5,
+            ui.height() * 0.25,
+            48.0,
+            Color::new(1.0, 0.8, 0.0, 1.0),
+        );
+
+        let card_width = (ui.width() * 0.3).clamp(260.0, 420.0);
+        let card_height = 180.0;
+        let gap = 40.0;
+        let count = self.route_choices.len() as f32;
+        let total_width = card_width * count + gap * (count - 1.0);
+        let start_x = ui.width() * 0.5 - total_width * 0.5;
+        let card_y = ui.height() * 0.38;
+
+        for (i, (map, modifier)) in self.route_choices.iter().enumerate() {
+            let x = start_x + i as f32 * (card_width + gap);
+            let (fill, border) = if i == self.route_choice_selected {
+                (Color::new(0.2, 0.4, 0.8, 0.6), Color::new(0.4, 0.6, 1.0, 1.0))
+            } else {
+                (Color::new(0.15, 0.15, 0.2, 0.9), Color::new(0.4, 0.4, 0.5, 1.0))
+            };
+            draw_rectangle(x, card_y, card_width, card_height, fill);
+            draw_rectangle_lines(x, card_y, card_width, card_height, 3.0, border);
+
+            let name = format!("{}. {}", i + 1, map.name());
+            draw_text(&name, x + 20.0, card_y + 50.0, 34.0, WHITE);
+            draw_text(
+                modifier.description(),
+                x + 20.0,
+                card_y + 100.0,
+                18.0,
+                Color::new(1.0, 0.9, 0.4, 1.0),
             );
-            draw_rectangle_lines(20.0, box_y, screen_width() - 40.0, box_height, 3.0, WHITE);
+        }
+
+        let hint = "A/D or 1/2 to pick, ENTER to go";
+        let hint_dims = measure_text(hint, None, 20, 1.0);
+        draw_text(
+            hint,
+            ui.width() * 0.5 - hint_dims.width * 0.5,
+            card_y + card_height + 50.0,
+            20.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        );
+    }
 
-            draw_text(&dialogue.speaker, 40.0, box_y + 30.0, 28.0, YELLOW);
+    fn render_upgrade_choice(&self, ui: &UiLayout) {
+        let Some(run) = &self.winter_arc else {
+            return;
+        };
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            ui.width(),
+            ui.height(),
+            Color::new(0.0, 0.0, 0.0, 0.75),
+        );
+
+        let title = "PICK AN UPGRADE";
+        let title_dims = measure_text(title, None, 48, 1.0);
+        draw_text(
+            title,
+            ui.width() * 0.5 - title_dims.width * 0.5,
+            ui.height() * 0.25,
+            48.0,
+            Color::new(0.6, 0.85, 1.0, 1.0),
+        );
 
-            draw_text(&dialogue.dutch, 40.0, box_y + 65.0, 24.0, WHITE);
+        let offers = run.offers();
+        let count = offers.len() as f32;
+        let gap = 30.0;
+        let card_width = ((ui.width() * 0.8 - gap * (count - 1.0)) / count).clamp(220.0, 340.0);
+        let card_height = 200.0;
+        let total_width = card_width * count + gap * (count - 1.0);
+        let start_x = ui.width() * 0.5 - total_width * 0.5;
+        let card_y = ui.height() * 0.38;
+
+        for (i, offer) in offers.iter().enumerate() {
+            let x = start_x + i as f32 * (card_width + gap);
+            // Cursed cards are tinted purple
+            let (fill, border) = match (i == self.upgrade_choice_selected, offer.curse) {
+                (true, Some(_)) => {
+                    (Color::new(0.4, 0.15, 0.5, 0.7), Color::new(0.8, 0.4, 1.0, 1.0))
+                }
+                (true, None) => (Color::new(0.2, 0.4, 0.8, 0.6), Color::new(0.4, 0.6, 1.0, 1.0)),
+                (false, Some(_)) => {
+                    (Color::new(0.2, 0.1, 0.25, 0.9), Color::new(0.5, 0.3, 0.6, 1.0))
+                }
+                (false, None) => {
+                    (Color::new(0.15, 0.15, 0.2, 0.9), Color::new(0.4, 0.4, 0.5, 1.0))
+                }
+            };
+            draw_rectangle(x, card_y, card_width, card_height, fill);
+            draw_rectangle_lines(x, card_y, card_width, card_height, 3.0, border);
 
+            let Some(upgrade) = RunUpgrade::get(offer.upgrade) else {
+                continue;
+            };
+            let stacks = run.stacks(offer.upgrade);
+            let name = format!("{}. {}", i + 1, upgrade.title);
+            draw_text(&name, x + 16.0, card_y + 40.0, 26.0, WHITE);
             draw_text(
-                &dialogue.english,
-                40.0,
-                box_y + 100.0,
-                20.0,
-                Color::new(0.7, 0.7, 0.7, 1.0),
+                upgrade.description,
+                x + 16.0,
+                card_y + 80.0,
+                18.0,
+                Color::new(1.0, 0.9, 0.4, 1.0),
             );
+            if stacks > 0 {
+                let owned = format!("Have {}", stacks);
+                draw_text(&owned, x + 16.0, card_y + 105.0, 16.0, GRAY);
+            }
+
+            if let Some(curse) = offer.curse {
+                let curse_text = format!("CURSED x2: {}", curse.name());
+                let curse_color = Color::new(0.9, 0.5, 1.0, 1.0);
+                draw_text(&curse_text, x + 16.0, card_y + 140.0, 18.0, curse_color);
+                draw_text(
+                    curse.description(),
+                    x + 16.0,
+                    card_y + 165.0,
+                    16.0,
+                    Color::new(0.8, 0.6, 0.9, 1.0),
+                );
+            }
+        }
+
+        let hint = format!(
+            "A/D or 1-{} to pick, ENTER to take  |  Frost Shards so far: {}",
+            offers.len(),
+            run.shards(false)
+        );
+        let hint_dims = measure_text(&hint, None, 20, 1.0);
+        draw_text(
+            &hint,
+            ui.width() * 0.5 - hint_dims.width * 0.5,
+            card_y + card_height + 50.0,
+            20.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        );
+    }
+
+    /// End screen of a Winter Arc run, won or lost
+    fn render_winter_arc_result(&self, ui: &UiLayout) {
+        let Some(result) = &self.winter_arc_result else {
+            return;
+        };
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            ui.width(),
+            ui.height(),
+            Color::new(0.0, 0.0, 0.0, 0.75),
+        );
+        let centered = |text: &str, y: f32, size: f32, color: Color| {
+            let dims = measure_text(text, None, size as u16, 1.0);
+            draw_text(text, ui.width() * 0.5 - dims.width * 0.5, y, size, color);
+        };
+
+        let mut y = ui.height() * 0.3;
+        if result.cleared {
+            centered("WINTER ARC COMPLETE", y, 64.0, Color::new(0.6, 0.85, 1.0, 1.0));
+        } else {
+            centered("RUN OVER", y, 64.0, Color::new(1.0, 0.2, 0.2, 1.0));
+            y += 40.0;
+            centered("The run and its upgrades are gone.", y, 22.0, LIGHTGRAY);
+        }
+
+        y += 60.0;
+        let payout = format!(
+            "Waves cleared: {}   |   +{} Frost Shards",
+            result.waves, result.shards
+        );
+        centered(&payout, y, 28.0, WHITE);
+        y += 36.0;
+        let total = format!("{} Frost Shards earned over every run", result.total_shards);
+        centered(&total, y, 20.0, Color::new(0.6, 0.85, 1.0, 1.0));
+
+        for unlock in &result.unlocked {
+            y += 40.0;
+            let text = format!("UNLOCKED {}: {}", unlock.name(), unlock.description());
+            centered(&text, y, 22.0, YELLOW);
+        }
+
+        if let Some(next) = MetaUnlock::ALL
+            .iter()
+            .find(|unlock| unlock.threshold() > result.total_shards)
+        {
+            y += 40.0;
+            let text = format!("Next: {} at {} Frost Shards", next.name(), next.threshold());
+            centered(&text, y, 18.0, GRAY);
         }
+
+        centered(
+            &format!("Seed {}  |  ESC or ENTER to see the results", result.seed),
+            ui.height() - 60.0,
+            18.0,
+            GRAY,
+        );
     }
 
-    fn render_dialogue_choice(&self) {
+    fn render_dialogue_choice(&self, ui: &UiLayout, conversation: &Conversation) {
+        let Some(node) = conversation.node() else {
+            return;
+        };
         // Overlay to darken the screen
         draw_rectangle(
             0.0,
             0.0,
-            screen_width(),
-            screen_height(),
+            ui.width(),
+            ui.height(),
             Color::new(0.0, 0.0, 0.0, 0.8),
         );
 
-        // Title
-        let title = "BOSSES DEFEATED!";
+        // Title: the fight for the opening question, the speaker after that
+        let title = if node.id == AFTERMATH {
+            localization::tr("gameplay.bosses_defeated")
+        } else {
+            node.speaker.to_uppercase()
+        };
+        let title = title.as_str();
         let title_size = 52.0;
         let title_dims = measure_text(title, None, title_size as u16, 1.0);
         draw_text(
             title,
-            screen_width() * 0.5 - title_dims.width * 0.5,
-            screen_height() * 0.25,
+            ui.width() * 0.5 - title_dims.width * 0.5,
+            ui.height() * 0.25,
             title_size,
             Color::new(1.0, 0.8, 0.0, 1.0),
         );
 
-        // Question
-        let question = "What should we do now?";
-        let question_dims = measure_text(question, None, 32, 1.0);
+        // Question, with its subtitle under it
+        let question = localization::spoken(&LineText::Written {
+            dutch: node.dutch.clone(),
+            english: node.english.clone(),
+        });
+        let question_dims = measure_text(&question.voice, None, 32, 1.0);
         draw_text(
-            question,
-            screen_width() * 0.5 - question_dims.width * 0.5,
-            screen_height() * 0.35,
+            &question.voice,
+            ui.width() * 0.5 - question_dims.width * 0.5,
+            ui.height() * 0.32,
             32.0,
             WHITE,
         );
+        if let Some(subtitle) = &question.subtitle {
+            let subtitle_dims = measure_text(subtitle, None, 22, 1.0);
+            draw_text(
+                subtitle,
+                ui.width() * 0.5 - subtitle_dims.width * 0.5,
+                ui.height() * 0.32 + 34.0,
+                22.0,
+                Color::new(0.7, 0.7, 0.7, 1.0),
+            );
+        }
 
         // Choice options
-        let choices = [
-            "Build a party to celebrate!",
-            "Repair the school together.",
-            "Go home and rest.",
-        ];
+        let choices: Vec<&str> = conversation
+            .choices()
+            .iter()
+            .map(|choice| choice.text.as_str())
+            .collect();
 
-        let choice_y_start = screen_height() * 0.45;
+        let choice_y_start = ui.height() * 0.45;
         let choice_spacing = 70.0;
 
         for (i, choice) in choices.iter().enumerate() {
@@ -6422,16 +9967,16 @@ impl GameplayState {
             // Draw selection box
             if is_selected {
                 draw_rectangle(
-                    screen_width() * 0.25 - 20.0,
+                    ui.width() * 0.25 - 20.0,
                     y - 35.0,
-                    screen_width() * 0.5 + 40.0,
+                    ui.width() * 0.5 + 40.0,
                     60.0,
                     Color::new(0.2, 0.4, 0.8, 0.5),
                 );
                 draw_rectangle_lines(
-                    screen_width() * 0.25 - 20.0,
+                    ui.width() * 0.25 - 20.0,
                     y - 35.0,
-                    screen_width() * 0.5 + 40.0,
+                    ui.width() * 0.5 + 40.0,
                     60.0,
                     3.0,
                     Color::new(0.4, 0.6, 1.0, 1.0),
@@ -6440,7 +9985,7 @@ impl GameplayState {
                 // Arrow indicator
                 draw_text(
                     "▶",
-                    screen_width() * 0.25 - 50.0,
+                    ui.width() * 0.25 - 50.0,
                     y + 5.0,
                     36.0,
                     Color::new(1.0, 0.8, 0.0, 1.0),
@@ -6454,18 +9999,486 @@ impl GameplayState {
                 Color::new(0.8, 0.8, 0.8, 1.0)
             };
 
-            draw_text(choice, screen_width() * 0.25, y + 5.0, 28.0, text_color);
+            draw_text(choice, ui.width() * 0.25, y + 5.0, 28.0, text_color);
         }
 
         // Instructions
-        let instruction = "Use W/S or UP/DOWN to select, ENTER to confirm";
-        let instruction_dims = measure_text(instruction, None, 20, 1.0);
+        let instruction = localization::tr("gameplay.choice_hint");
+        let instruction_dims = measure_text(&instruction, None, 20, 1.0);
         draw_text(
-            instruction,
-            screen_width() * 0.5 - instruction_dims.width * 0.5,
-            screen_height() * 0.85,
+            &instruction,
+            ui.width() * 0.5 - instruction_dims.width * 0.5,
+            ui.height() * 0.85,
             20.0,
             Color::new(0.6, 0.6, 0.6, 1.0),
         );
     }
 }
+
+/// Frames a simulated run gets before it's called off as lost: twenty minutes
+const SIMULATED_RUN_FRAMES: usize = 60 * 60 * 20;
+const SIMULATED_FRAME: f32 = 1.0 / 60.0;
+
+/// Plays a run with nobody at the keys, for the balance simulation and the
+/// tests: fights the way a co-op bot does on P1's keys, buys each upgrade on
+/// the stall between waves and clicks through dialogue, choices and endings
+struct Autopilot {
+    host: Headless,
+    bot: CoopBot,
+    /// The bot's own dice, so driving it doesn't shift the run's rolls
+    rng: SeededRng,
+    /// Stall slots already tried this shop break
+    shop_slots_tried: usize,
+}
+
+impl Autopilot {
+    fn new(host: Headless, character: CharacterId, seed: u64) -> Self {
+        Self {
+            host,
+            bot: CoopBot::new(character, CpuDifficulty::Hard),
+            rng: SeededRng::new(seed),
+            shop_slots_tried: 0,
+        }
+    }
+
+    /// Press this frame's keys, then play it
+    fn play(&mut self, game: &mut GameplayState) {
+        self.press(game);
+        self.step(game);
+    }
+
+    /// One frame in the order the state manager runs it
+    fn step(&self, game: &mut GameplayState) {
+        game.handle_input();
+        game.fixed_update(SIMULATED_FRAME as f64);
+        game.update(SIMULATED_FRAME);
+        self.host.end_frame(SIMULATED_FRAME);
+    }
+
+    fn press(&mut self, game: &GameplayState) {
+        if game.shopkeeper.is_none() {
+            self.shop_slots_tried = 0;
+        }
+        let keeper = game.shopkeeper.and_then(|keeper| position(game, keeper));
+
+        if game.dialogue_box.is_showing() || !game.dialogue_queue.is_empty() {
+            self.host.tap(KeyCode::Backspace);
+        } else if game.conversation.is_some() || !game.route_choices.is_empty() {
+            self.stand_still(game);
+            self.host.tap(KeyCode::Enter);
+        } else if game.shop_open {
+            self.shop(game);
+        } else if let Some(keeper) = keeper {
+            if game.near_shopkeeper() {
+                self.stand_still(game);
+                self.host.tap(KeyCode::B);
+            } else {
+                self.walk_towards(game, keeper);
+            }
+        } else {
+            self.fight(game);
+        }
+    }
+
+    /// Try each upgrade on the stall once, then send the shopkeeper off
+    fn shop(&mut self, game: &GameplayState) {
+        const KEYS: [KeyCode; 5] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+        ];
+        self.stand_still(game);
+        let next = (self.shop_slots_tried..game.keeper_stock.len().min(KEYS.len()))
+            .find(|&slot| matches!(game.keeper_stock[slot], ShopStock::Upgrade(_)));
+        match next {
+            Some(slot) => {
+                self.host.tap(KEYS[slot]);
+                self.shop_slots_tried = slot + 1;
+            }
+            None => self.host.tap(KeyCode::Enter),
+        }
+    }
+
+    /// Let the bot pick P1's move against the enemies on screen
+    fn fight(&mut self, game: &GameplayState) {
+        let Some(player) = game.player_entity else {
+            return;
+        };
+        let (Some(transform), Some(fighter)) = (
+            game.world.get_component::<Transform>(player),
+            game.world.get_component::<Fighter>(player),
+        ) else {
+            return;
+        };
+        let enemies: Vec<Vec2> =
+            game.enemy_entities.iter().filter_map(|&enemy| position(game, enemy)).collect();
+        let view = BotView {
+            position: transform.position,
+            enemies: &enemies,
+            can_act: fighter.hitstun <= 0.0
+                && fighter.blockstun <= 0.0
+                && matches!(fighter.state, FighterState::Idle | FighterState::Walking),
+            leader: transform.position,
+        };
+        let input = self.bot.think(SIMULATED_FRAME, &view, &mut self.rng);
+
+        self.hold_direction(game, input.movement);
+        for (key, pressed) in [
+            (game.keys.light_attack, input.light_attack_pressed),
+            (game.keys.heavy_attack, input.heavy_attack_pressed),
+            (game.keys.special_attack, input.special_attack_pressed),
+        ] {
+            if pressed {
+                self.host.tap(key);
+            }
+        }
+    }
+
+    /// Hold the keys that walk P1 towards `target`
+    fn walk_towards(&self, game: &GameplayState, target: Vec2) {
+        let Some(player) = game.player_entity.and_then(|player| position(game, player)) else {
+            return;
+        };
+        let offset = target - player;
+        let step = |gap: f32, slack: f32| if gap.abs() > slack { gap.signum() } else { 0.0 };
+        self.hold_direction(game, Vec2::new(step(offset.x, 20.0), step(offset.y, 10.0)));
+    }
+
+    /// Hold P1's movement keys for the way `direction` points
+    fn hold_direction(&self, game: &GameplayState, direction: Vec2) {
+        let keys = &game.keys;
+        for (key, wanted) in [
+            (keys.move_left, direction.x < -0.3),
+            (keys.move_right, direction.x > 0.3),
+            (keys.move_up, direction.y < -0.3),
+            (keys.move_down, direction.y > 0.3),
+        ] {
+            if wanted {
+                self.host.hold(key);
+            } else {
+                self.host.release(key);
+            }
+        }
+    }
+
+    fn stand_still(&self, game: &GameplayState) {
+        self.hold_direction(game, Vec2::ZERO);
+    }
+}
+
+fn position(game: &GameplayState, entity: EntityId) -> Option<Vec2> {
+    Some(game.world.get_component::<Transform>(entity)?.position)
+}
+
+/// A solo run as `character` on a headless window, seeded with `seed`
+fn headless_run(
+    character: CharacterId,
+    difficulty: Difficulty,
+    seed: u64,
+) -> (GameplayState, Autopilot) {
+    let host = Headless::new(Vec2::new(1280.0, 720.0));
+    let mut game = GameplayState::with_host(Box::new(host.clone()), character);
+    game.seed_simulation(seed);
+    game.set_difficulty(difficulty);
+    game.enter();
+    (game, Autopilot::new(host, character, seed))
+}
+
+/// Play one run on the autopilot and return what it logged. A run still
+/// going after `frames` is ended as lost.
+fn simulate_run(
+    character: CharacterId,
+    difficulty: Difficulty,
+    seed: u64,
+    frames: usize,
+) -> Option<RunRecord> {
+    let (mut game, mut pilot) = headless_run(character, difficulty, seed);
+    for _ in 0..frames {
+        if game.game_over {
+            break;
+        }
+        pilot.play(&mut game);
+    }
+    if !game.game_over {
+        game.record_run(false);
+    }
+    game.finished_run.take()
+}
+
+/// Play `runs` simulated runs per character and difficulty and write what
+/// they logged as `balance_simulation.json` and `.csv` next to the balance
+/// reports. Each run starts from a fresh profile in a scratch data root, with
+/// a copy of the tuning tables, so the real profile is never touched.
+pub fn simulate_balance(runs: usize) -> Result<String, String> {
+    let reports = balance::balance_directory();
+    let sandbox = std::env::temp_dir().join("bas-veeg-arc-simulation");
+    let data_root = crate::data::SaveBundle::data_root();
+
+    let mut records = Vec::new();
+    let mut failed = Ok(());
+    'runs: for (index, character) in crate::data::CHARACTERS.iter().enumerate() {
+        for difficulty in Difficulty::ALL {
+            for run in 0..runs {
+                if let Err(e) = sandbox_data(&sandbox, &reports) {
+                    failed = Err(e);
+                    break 'runs;
+                }
+                let seed = ((index as u64) << 32) | ((difficulty as u64) << 16) | run as u64;
+                records.extend(simulate_run(character.id, difficulty, seed, SIMULATED_RUN_FRAMES));
+            }
+        }
+    }
+
+    crate::data::SaveBundle::set_data_root(data_root);
+    let _ = std::fs::remove_dir_all(&sandbox);
+    failed?;
+
+    BalanceReport::from_runs(&records).save_as(&reports, "balance_simulation")?;
+    let cleared = records.iter().filter(|record| record.cleared).count();
+    Ok(format!(
+        "Simulated {} runs ({} cleared), report written to {}",
+        records.len(),
+        cleared,
+        reports.join("balance_simulation.csv").display()
+    ))
+}
+
+/// Point this thread's save folders at an empty `dir` holding only the
+/// tuning tables from `tuning`
+fn sandbox_data(dir: &std::path::Path, tuning: &std::path::Path) -> Result<(), String> {
+    let _ = std::fs::remove_dir_all(dir);
+    crate::data::SaveBundle::set_data_root(Some(dir.to_path_buf()));
+
+    let target = balance::balance_directory();
+    std::fs::create_dir_all(&target)
+        .map_err(|e| format!("Failed to create simulation folder: {}", e))?;
+    let Ok(entries) = std::fs::read_dir(tuning) else {
+        return Ok(());
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if name.ends_with(".json") && !name.starts_with("balance_") {
+            std::fs::copy(&path, target.join(name))
+                .map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coop::input_handler::{BindAction, KeyRebind};
+    use crate::ecs::Telegraph;
+    use std::sync::Once;
+
+    /// Three minutes of play
+    const PATIENCE: usize = 60 * 180;
+    const SEED: u64 = 3295;
+
+    /// Point this test's saves at a scratch folder, away from the
+    /// developer's profile
+    fn scratch_home() {
+        static CLEARED: Once = Once::new();
+        let dir = std::env::temp_dir().join("bas-veeg-arc-tests");
+        CLEARED.call_once(|| {
+            let _ = std::fs::remove_dir_all(&dir);
+        });
+        crate::data::SaveBundle::set_data_root(Some(dir));
+    }
+
+    /// A solo run as Bas on a headless window, past the intro lines
+    fn solo_run() -> (GameplayState, Autopilot) {
+        scratch_home();
+        let (mut game, pilot) = headless_run(CharacterId::Bas, Difficulty::Normal, SEED);
+        game.god_mode = true;
+        while game.dialogue_box.is_showing() || !game.dialogue_queue.is_empty() {
+            pilot.host.tap(KeyCode::Backspace);
+            pilot.step(&mut game);
+        }
+        (game, pilot)
+    }
+
+    /// Walk at the nearest enemy swinging, until `done` or time runs out
+    fn fight_until(
+        game: &mut GameplayState,
+        pilot: &mut Autopilot,
+        done: impl Fn(&GameplayState) -> bool,
+    ) -> bool {
+        for _ in 0..PATIENCE {
+            if done(game) {
+                return true;
+            }
+            pilot.fight(game);
+            pilot.step(game);
+        }
+        done(game)
+    }
+
+    fn boss_phase(game: &GameplayState) -> Option<BossPhase> {
+        game.enemy_entities.iter().find_map(|&enemy| {
+            match game.world.get_component::<AIController>(enemy)?.behavior {
+                AIBehavior::Boss(phase) => Some(phase),
+                _ => None,
+            }
+        })
+    }
+
+    /// Mees, once wave one's fighters have been cleared out of the way
+    fn mees_run() -> (GameplayState, Autopilot, EntityId) {
+        let (mut game, pilot) = solo_run();
+        for enemy in game.enemy_entities.drain(..) {
+            game.world.commands().destroy(enemy);
+        }
+        game.enemies_to_spawn = 0;
+        game.spawn_mees_boss();
+        pilot.step(&mut game);
+        let boss = game.enemy_entities[0];
+        (game, pilot, boss)
+    }
+
+    #[test]
+    fn fighting_through_the_first_wave_clears_it_and_brings_the_shopkeeper() {
+        let (mut game, mut pilot) = solo_run();
+        assert_eq!(game.current_wave, 1);
+
+        assert!(fight_until(&mut game, &mut pilot, |game| game.waves_completed == 1));
+        assert!(game.enemy_entities.is_empty());
+        assert!(game.shopkeeper.is_some());
+        assert!(game.run_kills > 0);
+    }
+
+    #[test]
+    fn the_shopkeeper_sells_an_upgrade_then_sends_in_the_next_wave() {
+        let (mut game, mut pilot) = solo_run();
+        assert!(fight_until(&mut game, &mut pilot, |game| game.shopkeeper.is_some()));
+        game.shop_manager.add_currency(500, "Wave cleared").unwrap();
+
+        let keeper = game.shopkeeper.and_then(|keeper| position(&game, keeper)).unwrap();
+        for _ in 0..PATIENCE {
+            if game.near_shopkeeper() {
+                break;
+            }
+            pilot.walk_towards(&game, keeper);
+            pilot.step(&mut game);
+        }
+        pilot.stand_still(&game);
+        pilot.host.tap(KeyCode::B);
+        pilot.step(&mut game);
+        assert!(game.shop_open);
+
+        let ShopStock::Upgrade(option) = game.keeper_stock[0] else {
+            panic!("The first stall slot is an upgrade");
+        };
+        // Simulated runs in other tests may have bought ranks on the profile
+        let rank = game.shop_manager.rank(option.id);
+        let tokens = game.shop_manager.currency();
+        pilot.host.tap(KeyCode::Key1);
+        pilot.step(&mut game);
+        assert_eq!(game.shop_manager.rank(option.id), rank + 1);
+        assert_eq!(game.shop_manager.currency(), tokens - rank_cost(option.cost, rank));
+
+        pilot.host.tap(KeyCode::Enter);
+        pilot.step(&mut game);
+        assert!(!game.shop_open && game.shopkeeper.is_none());
+        assert_eq!(game.current_wave, 2);
+    }
+
+    #[test]
+    fn a_boss_steps_up_a_phase_for_each_third_of_its_health_it_loses() {
+        let (mut game, mut pilot, _) = mees_run();
+        assert_eq!(boss_phase(&game), Some(BossPhase::Phase1));
+
+        for phase in [BossPhase::Phase2, BossPhase::Phase3] {
+            assert!(fight_until(&mut game, &mut pilot, |game| boss_phase(game) >= Some(phase)));
+            assert_eq!(boss_phase(&game), Some(phase));
+        }
+    }
+
+    #[test]
+    fn a_boss_winds_up_its_attack_in_the_open_before_throwing_it() {
+        let (mut game, pilot, boss) = mees_run();
+        let winding_up =
+            |game: &GameplayState| game.world.get_component::<BossWindup>(boss).cloned();
+        for _ in 0..PATIENCE {
+            if winding_up(&game).is_some() {
+                break;
+            }
+            pilot.step(&mut game);
+        }
+        let windup = winding_up(&game).expect("Mees winds up an attack");
+        let telegraph = Telegraph::for_attack(CharacterType::Mees, windup.attack);
+        assert_eq!(Some(windup.telegraph), telegraph);
+        assert!(windup.windup >= windup.telegraph.readable_windup());
+
+        let state =
+            |game: &GameplayState| game.world.get_component::<Fighter>(boss).map(|f| f.state);
+        while !winding_up(&game).unwrap().landing {
+            assert_ne!(state(&game), Some(windup.attack));
+            pilot.step(&mut game);
+        }
+        assert_eq!(state(&game), Some(windup.attack));
+        assert!(winding_up(&game).unwrap().elapsed >= windup.windup);
+    }
+
+    #[test]
+    fn a_rebound_key_throws_the_solo_light_attack_and_the_old_one_stops() {
+        let (mut game, pilot) = solo_run();
+        let swings_on = |game: &mut GameplayState, key: KeyCode| {
+            pilot.host.hold(key);
+            let swung = (0..10).any(|_| {
+                pilot.step(game);
+                let player = game.player_entity.unwrap();
+                let state = game.world.get_component::<Fighter>(player).map(|f| f.state);
+                state == Some(FighterState::LightAttack)
+            });
+            pilot.host.release(key);
+            for _ in 0..60 {
+                pilot.step(game);
+            }
+            swung
+        };
+
+        let mut keys = KeyboardBindings::default();
+        keys.rebind(BindAction::LightAttack, KeyCode::U);
+        let previous = crate::data::get_key_rebinds();
+        crate::data::set_key_rebinds(KeyRebind::differences(KeyboardLayout::Wasd, &keys));
+        let (on_j, on_u) = (swings_on(&mut game, KeyCode::J), swings_on(&mut game, KeyCode::U));
+        crate::data::set_key_rebinds(previous);
+
+        assert!(!on_j);
+        assert!(on_u);
+    }
+
+    #[test]
+    fn the_console_gives_no_tokens_in_practice_or_trial_runs() {
+        let (mut game, _pilot) = solo_run();
+        let tokens = game.shop_manager.currency();
+        game.trial = Some(TrialRun { character: CharacterId::Bas, previous: CharacterId::Bas });
+        assert!(matches!(game.run_command("give tokens 100"), Some(Err(_))));
+        game.trial = None;
+        let map = crate::render::map_system::MapType::Classroom;
+        game.practice = Some(PracticeStart { map, wave: 1, upgrades: vec![] });
+        assert!(matches!(game.run_command("give tokens 100"), Some(Err(_))));
+        assert_eq!(game.shop_manager.currency(), tokens);
+
+        game.practice = None;
+        assert!(matches!(game.run_command("give tokens 100"), Some(Ok(_))));
+        assert_eq!(game.shop_manager.currency(), tokens + 100);
+    }
+
+    #[test]
+    fn a_simulated_run_fights_through_a_wave_and_past_the_shop() {
+        scratch_home();
+        let record = simulate_run(CharacterId::Jad, Difficulty::Easy, SEED, PATIENCE)
+            .expect("The run is logged");
+        assert_eq!(record.character, CharacterId::Jad);
+        assert_eq!(record.difficulty, Difficulty::Easy);
+        assert!(record.waves >= 2);
+        assert!(!record.damage_dealt.is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::data::crash;
+use crate::states::{State, StateType};
+use crate::ui::hud_layout::{self, GRID};
+use crate::ui::{HudLayout, HudPreset, HudWidget, UiLayout};
+use macroquad::prelude::*;
+
+/// How much one wheel notch or +/- press resizes a widget
+const SCALE_STEP: f32 = 0.1;
+
+/// Drag the HUD widgets around a mock stage and resize them. The layout is
+/// saved with the profile on the way out.
+pub struct HudEditorState {
+    layout: HudLayout,
+    /// Index into `HudWidget::ALL`
+    selected: usize,
+    /// Widget under the mouse, and where on it it was grabbed
+    drag: Option<(HudWidget, Vec2)>,
+    snap: bool,
+    transition_to: Option<StateType>,
+}
+
+impl HudEditorState {
+    pub fn new() -> Self {
+        Self {
+            layout: HudLayout::load(),
+            selected: 0,
+            drag: None,
+            snap: true,
+            transition_to: None,
+        }
+    }
+
+    fn selected_widget(&self) -> HudWidget {
+        HudWidget::ALL[self.selected]
+    }
+
+    fn select(&mut self, widget: HudWidget) {
+        self.selected = HudWidget::ALL.iter().position(|w| *w == widget).unwrap_or(0);
+    }
+
+    /// Topmost widget under `point`; later widgets draw over earlier ones
+    fn widget_at(&self, point: Vec2, ui: &UiLayout) -> Option<HudWidget> {
+        HudWidget::ALL
+            .iter()
+            .rev()
+            .copied()
+            .find(|widget| self.layout.placement(*widget).rect(ui).contains(point))
+    }
+
+    fn move_widget(&mut self, widget: HudWidget, top_left: Vec2, ui: &UiLayout) {
+        let top_left = if self.snap {
+            Vec2::new(hud_layout::snap(top_left.x), hud_layout::snap(top_left.y))
+        } else {
+            top_left
+        };
+        self.layout.placement_mut(widget).move_to(top_left, ui);
+    }
+
+    fn rescale_widget(&mut self, widget: HudWidget, steps: f32, ui: &UiLayout) {
+        let placement = self.layout.placement_mut(widget);
+        // Keep to whole steps so a layout can always be scaled back to 1x
+        let scale = ((placement.scale + steps * SCALE_STEP) / SCALE_STEP).round() * SCALE_STEP;
+        placement.set_scale(scale, ui);
+    }
+
+    fn handle_mouse(&mut self, ui: &UiLayout) {
+        let (x, y) = mouse_position();
+        let cursor = ui.to_canvas(Vec2::new(x, y));
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.drag = self.widget_at(cursor, ui).map(|widget| {
+                self.select(widget);
+                (widget, cursor - self.layout.placement(widget).rect(ui).point())
+            });
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            self.drag = None;
+        }
+        if let Some((widget, grab)) = self.drag {
+            self.move_widget(widget, cursor - grab, ui);
+        }
+
+        let wheel = mouse_wheel().1;
+        if wheel != 0.0 {
+            let widget = self.widget_at(cursor, ui).unwrap_or(self.selected_widget());
+            self.rescale_widget(widget, wheel.signum(), ui);
+        }
+    }
+
+    fn handle_keys(&mut self, ui: &UiLayout) {
+        if is_key_pressed(KeyCode::Tab) {
+            self.selected = (self.selected + 1) % HudWidget::ALL.len();
+        }
+        if is_key_pressed(KeyCode::G) {
+            self.snap = !self.snap;
+        }
+
+        let widget = self.selected_widget();
+        let step = if self.snap { GRID } else { 1.0 };
+        let nudge = [
+            (KeyCode::Left, Vec2::new(-step, 0.0)),
+            (KeyCode::Right, Vec2::new(step, 0.0)),
+            (KeyCode::Up, Vec2::new(0.0, -step)),
+            (KeyCode::Down, Vec2::new(0.0, step)),
+        ]
+        .iter()
+        .filter(|(key, _)| is_key_pressed(*key))
+        .fold(Vec2::ZERO, |total, (_, step)| total + *step);
+        if nudge != Vec2::ZERO {
+            let top_left = self.layout.placement(widget).rect(ui).point() + nudge;
+            self.move_widget(widget, top_left, ui);
+        }
+
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            self.rescale_widget(widget, 1.0, ui);
+        }
+        if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            self.rescale_widget(widget, -1.0, ui);
+        }
+        if is_key_pressed(KeyCode::H) {
+            let placement = self.layout.placement_mut(widget);
+            placement.visible = !placement.visible;
+        }
+
+        let preset_keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3];
+        for (key, preset) in preset_keys.iter().zip(HudPreset::ALL) {
+            if is_key_pressed(*key) {
+                self.layout = preset.layout();
+            }
+        }
+    }
+}
+
+impl State for HudEditorState {
+    fn enter(&mut self) {
+        self.transition_to = None;
+    }
+
+    fn exit(&mut self) {
+        if let Err(e) = self.layout.save() {
+            crash::log(format!("Failed to save HUD layout: {}", e));
+        }
+    }
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.12, 0.12, 0.16, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        // A stand-in floor, so it's clear what the widgets will cover
+        draw_rectangle(
+            0.0,
+            ui.height() * 0.55,
+            ui.width(),
+            ui.height() * 0.45,
+            Color::new(0.22, 0.2, 0.18, 1.0),
+        );
+
+        if self.snap {
+            let line = Color::new(1.0, 1.0, 1.0, 0.05);
+            for i in 0..=(ui.width() / GRID) as u32 {
+                let x = i as f32 * GRID;
+                draw_line(x, 0.0, x, ui.height(), 1.0, line);
+            }
+            for i in 0..=(ui.height() / GRID) as u32 {
+                let y = i as f32 * GRID;
+                draw_line(0.0, y, ui.width(), y, 1.0, line);
+            }
+        }
+
+        for widget in HudWidget::ALL {
+            let placement = self.layout.placement(widget);
+            let rect = placement.rect(&ui);
+            let selected = widget == self.selected_widget();
+            let (fill, border) = match (placement.visible, selected) {
+                (false, _) => (Color::new(0.3, 0.3, 0.3, 0.2), GRAY),
+                (true, true) => (Color::new(1.0, 0.9, 0.2, 0.3), YELLOW),
+                (true, false) => (Color::new(0.3, 0.6, 1.0, 0.3), WHITE),
+            };
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, border);
+
+            let label = if placement.visible {
+                format!("{} x{:.1}", widget.label(), placement.scale)
+            } else {
+                format!("{} (hidden)", widget.label())
+            };
+            draw_text(&label, rect.x + 6.0, rect.y + 18.0, 18.0, border);
+        }
+
+        let mid = ui.width() * 0.5;
+        let title = "HUD LAYOUT";
+        let dims = measure_text(title, None, 40, 1.0);
+        draw_text(title, mid - dims.width * 0.5, ui.height() * 0.4, 40.0, WHITE);
+
+        let presets: Vec<String> = HudPreset::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, preset)| format!("{} = {}", i + 1, preset.label()))
+            .collect();
+        let lines = [
+            "Drag = Move | Wheel or +/- = Scale | TAB = Next | Arrows = Nudge".to_string(),
+            format!(
+                "H = Hide | G = Grid snap ({}) | {} | ESC = Save & Back",
+                if self.snap { "ON" } else { "OFF" },
+                presets.join(" | ")
+            ),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let dims = measure_text(line, None, 20, 1.0);
+            let y = ui.height() * 0.4 + 40.0 + i as f32 * 26.0;
+            draw_text(line, mid - dims.width * 0.5, y, 20.0, LIGHTGRAY);
+        }
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.transition_to = Some(StateType::Settings);
+            return;
+        }
+
+        let ui = UiLayout::current();
+        self.handle_mouse(&ui);
+        self.handle_keys(&ui);
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+}
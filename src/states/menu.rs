@@ -1,43 +1,513 @@
+use crate::coop::{CoopInputHandler, InputDevice, PlayerSlot};
+use crate::data::localization::tr;
+use crate::data::{CharacterId, MatchSettings, CHARACTERS};
+use crate::progression::{Challenge, ChallengeKind, ChallengeManager};
+use crate::render::atlas::SpriteBatch;
+use crate::render::{CharacterSheets, SpriteAnimation};
+use crate::states::versus::{stage_color, VERSUS_STAGES};
 use crate::states::{State, StateType};
+use crate::ui::menu_ui::{MenuAction, MenuUI};
+use crate::ui::UiLayout;
+use crate::updater::{FeatureLink, WhatsNew};
+use crate::util::host::Window;
 use macroquad::prelude::*;
 
+/// Devices that can drive the menu; anyone can pick a mode
+const MENU_DEVICES: [InputDevice; 3] =
+    [InputDevice::Keyboard, InputDevice::KeyboardArrows, InputDevice::Gamepad(0)];
+/// Seconds each stage is behind the menu, and how long it fades in over
+const STAGE_TIME: f32 = 8.0;
+const STAGE_FADE: f32 = 1.0;
+/// Where the floor starts on the background stage, in canvas units
+const FLOOR_Y: f32 = 760.0;
+/// Changelog lines are cut to fit the news panel
+const NEWS_LINE_LENGTH: usize = 44;
+
+/// The stage behind the menu: the versus stages in turn, with the player's
+/// character squaring up to a different rival on each
+struct MenuBackdrop {
+    elapsed: f32,
+    player: CharacterId,
+    sheets: CharacterSheets,
+    batch: SpriteBatch,
+}
+
+impl MenuBackdrop {
+    fn new() -> Self {
+        Self {
+            elapsed: 0.0,
+            player: crate::data::get_selected_character(),
+            sheets: CharacterSheets::new(),
+            batch: SpriteBatch::default(),
+        }
+    }
+
+    fn rival(&self, stage: usize) -> CharacterId {
+        let rivals: Vec<CharacterId> =
+            CHARACTERS.iter().map(|c| c.id).filter(|&id| id != self.player).collect();
+        rivals.get(stage % rivals.len().max(1)).copied().unwrap_or(self.player)
+    }
+
+    fn render(&mut self, ui: &UiLayout) {
+        let stage = (self.elapsed / STAGE_TIME) as usize;
+        let count = VERSUS_STAGES.len();
+        let fade = ((self.elapsed % STAGE_TIME) / STAGE_FADE).min(1.0);
+        let from = stage_color(VERSUS_STAGES[(stage + count - 1) % count]);
+        let to = stage_color(VERSUS_STAGES[stage % count]);
+        let mix = |a: f32, b: f32| a + (b - a) * fade;
+        let backdrop = Color::new(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b), 1.0);
+
+        // A slow drift across the stage
+        let focus = Vec2::new(960.0 + (self.elapsed * 0.15).sin() * 160.0, 500.0);
+        let zoom = 1.15;
+        set_camera(&Camera2D {
+            target: focus,
+            zoom: Vec2::new(2.0 * zoom / ui.width(), 2.0 * zoom / ui.height()),
+            ..Default::default()
+        });
+
+        let floor = Color::new(backdrop.r * 0.6, backdrop.g * 0.6, backdrop.b * 0.6, 1.0);
+        draw_rectangle(-1920.0, -1080.0, 5760.0, 1080.0 + FLOOR_Y, backdrop);
+        draw_rectangle(-1920.0, FLOOR_Y, 5760.0, 1080.0, floor);
+        draw_line(-1920.0, FLOOR_Y, 3840.0, FLOOR_Y, 4.0, Color::new(0.0, 0.0, 0.0, 0.3));
+
+        let mut batch = std::mem::take(&mut self.batch);
+        let fighters = [
+            (self.player, Vec2::new(760.0, 690.0), false),
+            (self.rival(stage), Vec2::new(1160.0, 690.0), true),
+        ];
+        for (character, position, flip) in fighters {
+            let shadow = Color::new(0.0, 0.0, 0.0, 0.4 * fade);
+            draw_ellipse(position.x, position.y + 70.0, 48.0, 16.0, 0.0, shadow);
+            let sheet = CharacterSheets::sheet_for(character);
+            let tint = Color::new(1.0, 1.0, 1.0, fade);
+            let animation = SpriteAnimation::Idle;
+            self.sheets
+                .queue(&mut batch, sheet, animation, self.elapsed, 0.0, position, flip, tint);
+        }
+        self.sheets.flush(&mut batch);
+        self.batch = batch;
+    }
+}
+
 pub struct MenuState {
-    selected_option: usize,
-    options: Vec<String>,
-    background_offset: f32,
+    menu: MenuUI,
+    backdrop: MenuBackdrop,
+    input: CoopInputHandler,
     transition_to: Option<StateType>,
+    /// Post-update popup; its entries are the feature links, then Close
+    whats_new: Option<WhatsNew>,
+    whats_new_selected: usize,
+    /// Name of the profile being played, under the title
+    profile_name: String,
+    /// Today's and this week's challenges, down the left side
+    challenges: ChallengeManager,
 }
 
 impl MenuState {
     pub fn new() -> Self {
         Self {
-            selected_option: 0,
-            options: vec![
-                "START STORY".to_string(),
-                "ENDLESS MODE".to_string(),
-                "CO-OP MODE (2 PLAYERS LOCAL)".to_string(),
-                "SKILL TREE".to_string(),
-                "CONTROLS".to_string(),
-                "EXIT".to_string(),
-            ],
-            background_offset: 0.0,
+            menu: MenuUI::hub(),
+            backdrop: MenuBackdrop::new(),
+            input: CoopInputHandler::new(),
             transition_to: None,
+            whats_new: None,
+            whats_new_selected: 0,
+            profile_name: String::new(),
+            challenges: ChallengeManager::new(),
+        }
+    }
+}
+
+impl MenuState {
+    fn select(&mut self, action: MenuAction) {
+        let next = match action {
+            MenuAction::Story => StateType::CharacterSelect,
+            MenuAction::Coop => StateType::CoopSelect,
+            MenuAction::Versus => {
+                let player1 = crate::data::get_selected_character();
+                crate::data::set_match_settings(MatchSettings::new(player1));
+                StateType::VersusSelect
+            }
+            MenuAction::Training => StateType::Training,
+            MenuAction::Practice => StateType::Practice,
+            MenuAction::Endless => StateType::EndlessMode,
+            MenuAction::WinterArc => StateType::WinterArc,
+            // Listed as coming soon, so never picked
+            MenuAction::BossRush => return,
+            MenuAction::DailyChallenge => StateType::DailyChallenge,
+            MenuAction::WeeklyChallenge => StateType::WeeklyChallenge,
+            MenuAction::SkillTree => StateType::SkillTree,
+            MenuAction::Achievements => StateType::Achievements,
+            MenuAction::Account => StateType::Account,
+            MenuAction::Settings => StateType::Settings,
+            MenuAction::Controls => StateType::Controls,
+            MenuAction::Profiles => StateType::Profiles,
+            MenuAction::Mods => StateType::Mods,
+            // The boot screen runs the check and offers the download
+            MenuAction::CheckForUpdates => StateType::Boot,
+            MenuAction::Exit => {
+                crate::data::cloud::upload_on_exit();
+                crate::updater::install_staged_update();
+                std::process::exit(0);
+            }
+        };
+        self.transition_to = Some(next);
+    }
+
+    fn link_action(link: FeatureLink) -> MenuAction {
+        match link {
+            FeatureLink::Story => MenuAction::Story,
+            FeatureLink::Endless => MenuAction::Endless,
+            FeatureLink::Coop => MenuAction::Coop,
+            FeatureLink::Versus => MenuAction::Versus,
+            FeatureLink::SkillTree => MenuAction::SkillTree,
+            FeatureLink::Settings => MenuAction::Settings,
+            FeatureLink::Controls => MenuAction::Controls,
+        }
+    }
+
+    fn handle_whats_new_input(&mut self) {
+        let Some(whats_new) = &self.whats_new else {
+            return;
+        };
+        let entries = whats_new.links.len() + 1;
+
+        if is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left) {
+            self.whats_new_selected = (self.whats_new_selected + entries - 1) % entries;
+        }
+        if is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right) {
+            self.whats_new_selected = (self.whats_new_selected + 1) % entries;
+        }
+
+        let close = is_key_pressed(KeyCode::Escape);
+        if close || is_key_pressed(KeyCode::J) || is_key_pressed(KeyCode::Enter) {
+            let link = whats_new.links.get(self.whats_new_selected).copied();
+            self.whats_new = None;
+            crate::updater::mark_seen();
+
+            if let (false, Some(link)) = (close, link) {
+                let action = Self::link_action(link);
+                self.menu.focus(action);
+                self.select(action);
+            }
+        }
+    }
+
+    fn render_whats_new(&self, ui: &UiLayout) {
+        let Some(whats_new) = &self.whats_new else {
+            return;
+        };
+
+        draw_rectangle(0.0, 0.0, ui.width(), ui.height(), Color::new(0.0, 0.0, 0.0, 0.7));
+
+        let box_width = 900.0;
+        let box_height = 640.0;
+        let box_x = ui.width() * 0.5 - box_width * 0.5;
+        let box_y = ui.height() * 0.5 - box_height * 0.5;
+        draw_rectangle(box_x, box_y, box_width, box_height, Color::new(0.15, 0.15, 0.2, 1.0));
+        draw_rectangle_lines(
+            box_x,
+            box_y,
+            box_width,
+            box_height,
+            3.0,
+            Color::new(0.3, 0.6, 1.0, 1.0),
+        );
+
+        let title = "WHAT'S NEW";
+        let title_dims = measure_text(title, None, 44, 1.0);
+        draw_text(
+            title,
+            box_x + box_width * 0.5 - title_dims.width * 0.5,
+            box_y + 60.0,
+            44.0,
+            Color::new(0.4, 0.8, 1.0, 1.0),
+        );
+
+        let subtitle = format!(
+            "Updated from v{} to v{}",
+            whats_new.previous_version,
+            crate::updater::CURRENT_VERSION
+        );
+        let subtitle_dims = measure_text(&subtitle, None, 22, 1.0);
+        draw_text(
+            &subtitle,
+            box_x + box_width * 0.5 - subtitle_dims.width * 0.5,
+            box_y + 95.0,
+            22.0,
+            Color::new(0.7, 0.7, 0.7, 1.0),
+        );
+
+        // Notes for each release, newest first, until the box runs out of room
+        let notes_bottom = box_y + box_height - 110.0;
+        let mut y = box_y + 145.0;
+        'releases: for release in &whats_new.releases {
+            if y > notes_bottom {
+                break;
+            }
+            draw_text(&format!("v{}", release.version), box_x + 40.0, y, 26.0, YELLOW);
+            y += 32.0;
+
+            for line in &release.highlights {
+                if y > notes_bottom {
+                    break 'releases;
+                }
+                let line: String = line.chars().take(70).collect();
+                draw_text(&format!("- {}", line), box_x + 60.0, y, 20.0, WHITE);
+                y += 26.0;
+            }
+            if release.hidden > 0 && y <= notes_bottom {
+                let more = format!("...and {} more", release.hidden);
+                draw_text(&more, box_x + 60.0, y, 18.0, GRAY);
+                y += 26.0;
+            }
+            y += 10.0;
+        }
+
+        if whats_new.releases.is_empty() {
+            draw_text("No release notes were published.", box_x + 40.0, y, 20.0, GRAY);
+        }
+
+        // Links to the features the notes mention, then Close
+        let labels: Vec<String> = whats_new
+            .links
+            .iter()
+            .map(|link| format!("Try {}", link.label()))
+            .chain(std::iter::once("Close".to_string()))
+            .collect();
+        let spacing = 24.0;
+        let widths: Vec<f32> = labels
+            .iter()
+            .map(|label| measure_text(label, None, 22, 1.0).width + 30.0)
+            .collect();
+        let total = widths.iter().sum::<f32>() + spacing * (widths.len() - 1) as f32;
+        let mut x = box_x + box_width * 0.5 - total * 0.5;
+        let button_y = box_y + box_height - 70.0;
+
+        for (i, (label, width)) in labels.iter().zip(&widths).enumerate() {
+            let selected = i == self.whats_new_selected;
+            let fill = if selected {
+                Color::new(0.3, 0.6, 1.0, 0.8)
+            } else {
+                Color::new(0.25, 0.25, 0.3, 1.0)
+            };
+            draw_rectangle(x, button_y, *width, 40.0, fill);
+            if selected {
+                draw_rectangle_lines(x, button_y, *width, 40.0, 2.0, YELLOW);
+            }
+            draw_text(label, x + 15.0, button_y + 27.0, 22.0, WHITE);
+            x += width + spacing;
+        }
+
+        let hint = "A/D to choose, ENTER to confirm, ESC to close";
+        let hint_dims = measure_text(hint, None, 16, 1.0);
+        draw_text(
+            hint,
+            box_x + box_width * 0.5 - hint_dims.width * 0.5,
+            box_y + box_height - 12.0,
+            16.0,
+            GRAY,
+        );
+    }
+
+    /// Bonus events running now or starting within the week, with countdowns,
+    /// down the top-right corner
+    fn render_bonus_events(&self, ui: &UiLayout) {
+        use crate::data::events::{self, EventStatus};
+
+        let schedule = events::schedule();
+        let now = events::now();
+        let listed = schedule.upcoming(now, 7 * 24 * 60 * 60);
+        if listed.is_empty() {
+            return;
+        }
+
+        let x = ui.width() - 360.0;
+        let mut y = 40.0;
+        draw_text("BONUS EVENTS", x, y, 22.0, Color::new(1.0, 0.9, 0.4, 1.0));
+        for (event, status) in listed {
+            y += 26.0;
+            let (countdown, color) = match status {
+                EventStatus::Active(left) => (
+                    format!("ends in {}", events::format_countdown(left)),
+                    Color::new(0.3, 1.0, 0.4, 1.0),
+                ),
+                EventStatus::Upcoming(starts_in) => (
+                    format!("starts in {}", events::format_countdown(starts_in)),
+                    Color::new(0.7, 0.7, 0.7, 1.0),
+                ),
+                EventStatus::Over => continue,
+            };
+            draw_text(&event.title, x, y, 20.0, color);
+            y += 20.0;
+            let detail = format!("{}  |  {}", event.describe(), countdown);
+            draw_text(&detail, x, y, 16.0, Color::new(0.8, 0.8, 0.8, 0.9));
+        }
+    }
+
+    /// The challenge board down the left side: each seeded run with its
+    /// modifiers, then the challenges alongside it, with reset countdowns
+    fn render_challenge_board(&self, ui: &UiLayout) {
+        use crate::data::events;
+
+        let x = 40.0;
+        let mut y = ui.height() * 0.34;
+        let gold = Color::new(1.0, 0.9, 0.4, 1.0);
+        let done = Color::new(0.3, 1.0, 0.4, 1.0);
+        draw_text("CHALLENGES", x, y, 26.0, gold);
+        if self.challenges.streak_days > 0 {
+            let streak = format!(
+                "{} day streak: rewards x{:.2}",
+                self.challenges.streak_days,
+                self.challenges.get_streak_bonus()
+            );
+            y += 22.0;
+            draw_text(&streak, x, y, 16.0, done);
         }
+
+        let boards = [
+            (
+                ChallengeKind::Daily,
+                &self.challenges.daily_modifiers,
+                &self.challenges.daily_challenges,
+                self.challenges.time_until_daily_reset(),
+            ),
+            (
+                ChallengeKind::Weekly,
+                &self.challenges.weekly_modifiers,
+                &self.challenges.weekly_challenges,
+                self.challenges.time_until_weekly_reset(),
+            ),
+        ];
+        for (kind, modifiers, challenges, resets_in) in boards {
+            y += 36.0;
+            let heading = format!(
+                "{}  {:.0}%  |  resets in {}",
+                kind.label().to_uppercase(),
+                self.challenges.get_completion_percent(kind == ChallengeKind::Daily),
+                events::format_countdown(resets_in)
+            );
+            draw_text(&heading, x, y, 20.0, WHITE);
+
+            y += 22.0;
+            let run = if self.challenges.run_completed(kind) {
+                ("Seeded run cleared".to_string(), done)
+            } else {
+                let run = format!(
+                    "Seeded run: clear {} waves for {} Arc Tokens",
+                    kind.run_waves(),
+                    kind.run_reward()
+                );
+                (run, gold)
+            };
+            draw_text(&run.0, x, y, 16.0, run.1);
+            for modifier in modifiers.iter() {
+                y += 20.0;
+                let twist = format!("{}: {}", modifier.name(), modifier.description());
+                draw_text(&twist, x + 10.0, y, 16.0, Color::new(1.0, 0.5, 0.4, 1.0));
+            }
+
+            for challenge in challenges.iter() {
+                y += 20.0;
+                self.render_challenge_line(challenge, x, y);
+            }
+        }
+    }
+
+    fn render_challenge_line(&self, challenge: &Challenge, x: f32, y: f32) {
+        let completed = self.challenges.is_completed(&challenge.id, challenge.is_daily);
+        let (progress, color) = if completed {
+            ("done".to_string(), Color::new(0.3, 1.0, 0.4, 1.0))
+        } else {
+            let progress = format!("{:.0}/{:.0}", challenge.progress, challenge.max_progress);
+            (progress, challenge.difficulty.to_color())
+        };
+        let line = format!("{}: {} ({})", challenge.name, challenge.description, progress);
+        draw_text(&line, x + 10.0, y, 16.0, color);
+    }
+
+    /// The latest release's changelog from the update check, in the bottom
+    /// right corner
+    fn render_news(&self, ui: &UiLayout) {
+        let x = ui.width() - 420.0;
+        let mut y = ui.height() * 0.64;
+        draw_text(&tr("menu.news"), x, y, 24.0, Color::new(0.4, 0.8, 1.0, 1.0));
+        let Some(news) = crate::updater::news() else {
+            y += 26.0;
+            draw_text(&tr("menu.no_news"), x, y, 16.0, GRAY);
+            return;
+        };
+
+        y += 28.0;
+        let heading = format!("v{}", news.version);
+        draw_text(&heading, x, y, 20.0, YELLOW);
+        for line in &news.highlights {
+            y += 22.0;
+            let mut line: String = line.chars().take(NEWS_LINE_LENGTH).collect();
+            if line.chars().count() == NEWS_LINE_LENGTH {
+                line.push_str("...");
+            }
+            draw_text(&format!("- {}", line), x, y, 16.0, Color::new(0.9, 0.9, 0.9, 0.9));
+        }
+        if news.hidden > 0 {
+            y += 22.0;
+            draw_text(&format!("...and {} more", news.hidden), x, y, 16.0, GRAY);
+        }
+    }
+
+    /// Status of a background update download, tucked into the bottom corner
+    fn render_update_indicator(&self, ui: &UiLayout) {
+        use crate::updater::BackgroundUpdate;
+
+        let (text, color) = match crate::updater::background_update() {
+            Some(BackgroundUpdate::Downloading { version, progress }) => (
+                format!("Downloading update v{}... {:.0}%", version, progress),
+                Color::new(0.7, 0.7, 0.7, 0.8),
+            ),
+            Some(BackgroundUpdate::Staged {
+                version,
+                verification,
+            }) => (
+                format!("Update v{} installs when you quit ({})", version, verification.label()),
+                Color::new(0.3, 1.0, 0.4, 0.9),
+            ),
+            Some(BackgroundUpdate::Failed(error)) => (error, Color::new(1.0, 0.4, 0.4, 0.8)),
+            None => return,
+        };
+
+        draw_text(&text, 20.0, ui.height() - 20.0, 18.0, color);
     }
 }
 
 impl State for MenuState {
     fn enter(&mut self) {
-        self.selected_option = 0;
         self.transition_to = None;
+        self.backdrop.player = crate::data::get_selected_character();
+        let active = crate::data::get_active_profile();
+        let slots = crate::data::SaveManager::new().get_save_slots();
+        self.profile_name = match &slots[active] {
+            Some(info) => info.profile_name.clone(),
+            None => crate::data::save::DEFAULT_PROFILE.to_string(),
+        };
+        // Back at the menu, any versus session is over
+        crate::data::clear_match_settings();
+        self.challenges = ChallengeManager::load();
     }
 
-    fn exit(&mut self) {}
+    fn exit(&mut self) {
+        // Escape rebuilds the menu, which dismisses the popup too
+        if self.whats_new.take().is_some() {
+            crate::updater::mark_seen();
+        }
+    }
 
     fn update(&mut self, dt: f32) {
-        self.background_offset += dt * 20.0;
-        if self.background_offset > 100.0 {
-            self.background_offset -= 100.0;
+        self.menu.update(dt);
+        self.backdrop.elapsed += dt;
+
+        if self.whats_new.is_none() {
+            self.whats_new = crate::updater::take_whats_new();
         }
     }
 
@@ -45,21 +515,17 @@ impl State for MenuState {
 
     fn render(&mut self, _interpolation: f32) {
         clear_background(BLACK);
+        let ui = UiLayout::current();
+        self.backdrop.render(&ui);
+        ui.begin();
 
-        // Calculate scale factor based on screen size
-        let scale_factor = (screen_width() / 1920.0).min(screen_height() / 1080.0).max(0.5);
-        let sw = screen_width();
-        let sh = screen_height();
-
-        // Animated background with scaling
-        for i in 0..20 {
-            for j in 0..12 {
-                let x = i as f32 * 100.0 * scale_factor - self.background_offset;
-                let y = j as f32 * 100.0 * scale_factor;
-                let size = 2.0 * scale_factor;
-                draw_circle(x, y, size, Color::new(0.3, 0.2, 0.4, 0.3));
-            }
-        }
+        // Calculate scale factor based on canvas size
+        let scale_factor = (ui.width() / 1920.0).min(ui.height() / 1080.0).max(0.5);
+        let sw = ui.width();
+        let sh = ui.height();
+
+        // Dimmed so the menu reads over the stage
+        draw_rectangle(0.0, 0.0, sw, sh, Color::new(0.0, 0.0, 0.0, 0.55));
 
         let title = "BAS VEEG ARC";
         let title_size = (80.0 * scale_factor).min(100.0).max(40.0);
@@ -83,58 +549,57 @@ impl State for MenuState {
             Color::new(0.8, 0.8, 0.8, 0.8),
         );
 
-        // Menu options with adaptive positioning
-        let option_start_y = sh * 0.4;
-        let option_spacing = sh * 0.08;
-
-        for (i, option) in self.options.iter().enumerate() {
-            let y = option_start_y + i as f32 * option_spacing;
-            let size = (35.0 * scale_factor).min(40.0).max(20.0);
-            let color = if i == self.selected_option {
-                YELLOW
-            } else {
-                WHITE
-            };
+        let profile = format!("PROFILE: {}", self.profile_name.to_uppercase());
+        let profile_size = (22.0 * scale_factor).clamp(14.0, 26.0);
+        let profile_dims = measure_text(&profile, None, profile_size as u16, 1.0);
+        draw_text(
+            &profile,
+            sw * 0.5 - profile_dims.width * 0.5,
+            sh * 0.25,
+            profile_size,
+            Color::new(0.4, 0.8, 1.0, 0.9),
+        );
 
-            let text_dims = measure_text(option, None, size as u16, 1.0);
-            let x = sw * 0.5 - text_dims.width * 0.5;
+        self.menu.render(&ui, sh * 0.34, scale_factor);
 
-            if i == self.selected_option {
-                draw_rectangle(
-                    x - 20.0 * scale_factor,
-                    y - size * 0.8,
-                    text_dims.width + 40.0 * scale_factor,
-                    size + 10.0 * scale_factor,
-                    Color::new(1.0, 1.0, 0.0, 0.2),
-                );
-            }
+        let hint = tr("menu.hint");
+        let hint_dims = measure_text(&hint, None, 16, 1.0);
+        let hint_color = Color::new(1.0, 1.0, 1.0, 0.5);
+        draw_text(&hint, sw * 0.5 - hint_dims.width * 0.5, sh - 20.0, 16.0, hint_color);
 
-            draw_text(option, x, y, size, color);
-        }
+        self.render_news(&ui);
+        self.render_update_indicator(&ui);
+        self.render_bonus_events(&ui);
+        self.render_challenge_board(&ui);
+        self.render_whats_new(&ui);
+        ui.end();
     }
 
     fn handle_input(&mut self) {
-        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
-            if self.selected_option > 0 {
-                self.selected_option -= 1;
-            } else {
-                self.selected_option = self.options.len() - 1;
-            }
-        }
-
-        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
-            self.selected_option = (self.selected_option + 1) % self.options.len();
+        if self.whats_new.is_some() {
+            self.handle_whats_new_input();
+            return;
         }
 
-        if is_key_pressed(KeyCode::J) || is_key_pressed(KeyCode::Enter) {
-            match self.selected_option {
-                0 => self.transition_to = Some(StateType::CharacterSelect),
-                1 => self.transition_to = Some(StateType::EndlessMode),
-                2 => self.transition_to = Some(StateType::CoopSelect), // Co-op character select
-                3 => self.transition_to = Some(StateType::SkillTree),
-                4 => self.transition_to = Some(StateType::Controls),
-                5 => std::process::exit(0),
-                _ => {}
+        for device in MENU_DEVICES {
+            let input = self.input.get_player_input(&Window, PlayerSlot::Player1, device);
+            if input.up_pressed {
+                self.menu.navigate_up();
+            }
+            if input.down_pressed {
+                self.menu.navigate_down();
+            }
+            if input.left_pressed {
+                self.menu.navigate_left();
+            }
+            if input.right_pressed {
+                self.menu.navigate_right();
+            }
+            if input.confirm_pressed {
+                if let Some(action) = self.menu.get_selected_action() {
+                    self.select(action);
+                }
+                return;
             }
         }
     }
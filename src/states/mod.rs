@@ -1,3 +1,6 @@
+pub mod account;
+pub mod achievements;
+pub mod auto_pause;
 pub mod boot;
 pub mod character_select;
 pub mod controls;
@@ -5,14 +8,22 @@ pub mod coop_select;
 pub mod cutscene;
 pub mod endless;
 pub mod gameplay;
+pub mod hud_editor;
 pub mod menu;
+pub mod mods;
+pub mod online_lobby;
+pub mod practice;
+pub mod profiles;
 pub mod results;
 pub mod settings;
 pub mod skill_tree_ui;
 pub mod training;
 pub mod tutorial_system;
 pub mod versus;
+pub mod versus_select;
 
+use crate::data::crash;
+use crate::ui::console::CommandResult;
 use std::collections::VecDeque;
 
 #[derive(Clone, Copy, Debug)]
@@ -25,12 +36,23 @@ pub enum StateType {
     Cutscene,
     Training,
     Versus,
+    VersusSelect,
     Results,
     Settings,
+    HudEditor,
     EndlessMode,
+    WinterArc,
+    DailyChallenge,
+    WeeklyChallenge,
     CoopMode,
     CoopSelect,
+    OnlineLobby,
     SkillTree,
+    Profiles,
+    Practice,
+    Achievements,
+    Account,
+    Mods,
 }
 
 pub trait State {
@@ -43,12 +65,48 @@ pub trait State {
     fn should_transition(&self) -> Option<StateType> {
         None
     }
+
+    /// A state to run on top of this one until it pops; handed over once
+    fn take_overlay(&mut self) -> Option<StateType> {
+        None
+    }
+
+    /// Done, and the state underneath should carry on
+    fn should_pop(&self) -> bool {
+        false
+    }
+
+    /// Escape means something to this state, so the manager leaves it be
+    fn handles_escape(&self) -> bool {
+        false
+    }
+
+    /// How fast the simulation runs under this state; single-player modes
+    /// take the game speed setting
+    fn game_speed(&self) -> f32 {
+        1.0
+    }
+
+    /// Run a developer console line with one of this state's commands;
+    /// `None` when it has none by that name
+    fn run_command(&mut self, _line: &str) -> Option<CommandResult> {
+        None
+    }
+
+    /// How this state's console commands are typed, for `help`
+    fn command_usages(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 pub struct StateManager {
     states: VecDeque<Box<dyn State>>,
+    /// What each of `states` is, for crash reports
+    state_types: Vec<StateType>,
     pending_transitions: Vec<StateTransition>,
     should_quit: bool,
+    /// Builds the state for each type; tests swap in their own
+    factory: fn(StateType) -> Box<dyn State>,
 }
 
 pub enum StateTransition {
@@ -59,23 +117,33 @@ pub enum StateTransition {
 
 impl StateManager {
     pub fn new() -> Self {
+        Self::with_factory(create_state)
+    }
+
+    pub fn with_factory(factory: fn(StateType) -> Box<dyn State>) -> Self {
         Self {
             states: VecDeque::new(),
+            state_types: Vec::new(),
             pending_transitions: Vec::new(),
             should_quit: false,
+            factory,
         }
     }
 
     pub fn push_state(&mut self, state_type: StateType) {
-        let mut state = self.create_state(state_type);
+        let mut state = (self.factory)(state_type);
         state.enter();
         self.states.push_back(state);
+        self.state_types.push(state_type);
+        self.note_states();
     }
 
     pub fn pop_state(&mut self) {
         if let Some(mut state) = self.states.pop_back() {
             state.exit();
         }
+        self.state_types.pop();
+        self.note_states();
 
         if self.states.is_empty() {
             self.should_quit = true;
@@ -86,10 +154,28 @@ impl StateManager {
         if let Some(mut old_state) = self.states.pop_back() {
             old_state.exit();
         }
+        self.state_types.pop();
 
-        let mut new_state = self.create_state(state_type);
+        let mut new_state = (self.factory)(state_type);
         new_state.enter();
         self.states.push_back(new_state);
+        self.state_types.push(state_type);
+        self.note_states();
+    }
+
+    /// Log the change and keep the crash report's summary of what's up
+    fn note_states(&self) {
+        let stack: Vec<String> =
+            self.state_types.iter().map(|state| format!("{:?}", state)).collect();
+        let stack = stack.join(" > ");
+        crash::log(format!("States: {}", stack));
+        crash::set_summary(format!(
+            "States: {}\nCharacter: {:?}\nProfile: {}\nTick rate: {}",
+            stack,
+            crate::data::get_selected_character(),
+            crate::data::get_active_profile() + 1,
+            crate::data::get_tick_rate()
+        ));
     }
 
     pub fn update(&mut self, dt: f32) {
@@ -102,6 +188,10 @@ impl StateManager {
             if let Some(next_state) = state.should_transition() {
                 self.pending_transitions
                     .push(StateTransition::Replace(next_state));
+            } else if let Some(overlay) = state.take_overlay() {
+                self.pending_transitions.push(StateTransition::Push(overlay));
+            } else if state.should_pop() {
+                self.pending_transitions.push(StateTransition::Pop);
             }
         }
     }
@@ -112,13 +202,37 @@ impl StateManager {
         }
     }
 
+    /// One frame without the render: `ticks` fixed updates of `timestep`,
+    /// then input and the per-frame update of `dt`
+    pub fn step(&mut self, ticks: u32, timestep: f64, dt: f32) {
+        for _ in 0..ticks {
+            self.fixed_update(timestep);
+        }
+        self.update(dt);
+    }
+
     pub fn render(&mut self, interpolation: f32) {
         if let Some(state) = self.states.back_mut() {
             state.render(interpolation);
         }
     }
 
+    pub fn game_speed(&self) -> f32 {
+        self.states.back().map_or(1.0, |state| state.game_speed())
+    }
+
+    pub fn run_command(&mut self, line: &str) -> Option<CommandResult> {
+        self.states.back_mut().and_then(|state| state.run_command(line))
+    }
+
+    pub fn command_usages(&self) -> Vec<String> {
+        self.states.back().map_or_else(Vec::new, |state| state.command_usages())
+    }
+
     pub fn handle_escape(&mut self) {
+        if self.states.back().is_some_and(|state| state.handles_escape()) {
+            return;
+        }
         if self.states.len() > 1 {
             self.pending_transitions.push(StateTransition::Pop);
         } else {
@@ -143,28 +257,209 @@ impl StateManager {
             }
         }
     }
+}
 
-    fn create_state(&self, state_type: StateType) -> Box<dyn State> {
-        match state_type {
-            StateType::Boot => Box::new(boot::BootState::new()),
-            StateType::Menu => Box::new(menu::MenuState::new()),
-            StateType::CharacterSelect => Box::new(character_select::CharacterSelectState::new()),
-            StateType::Controls => Box::new(controls::ControlsState::new()),
-            StateType::Gameplay => Box::new(gameplay::GameplayState::new()),
-            StateType::Cutscene => Box::new(cutscene::CutsceneState::new()),
-            StateType::Training => Box::new(training::TrainingState::new()),
-            StateType::Versus => Box::new(versus::VersusState::new()),
-            StateType::Results => Box::new(results::ResultsState::new()),
-            StateType::Settings => Box::new(settings::SettingsState::new()),
-            StateType::EndlessMode => Box::new(endless::EndlessState::new()),
-            StateType::CoopSelect => Box::new(coop_select::CoopSelectState::new()),
-            StateType::SkillTree => Box::new(skill_tree_ui::SkillTreeUIState::new()),
-            StateType::CoopMode => {
-                // Create gameplay with co-op enabled
-                let mut state = gameplay::GameplayState::new();
-                state.enable_coop(4); // Enable for up to 4 players
-                Box::new(state)
+fn create_state(state_type: StateType) -> Box<dyn State> {
+    match state_type {
+        StateType::Boot => Box::new(boot::BootState::new()),
+        StateType::Menu => Box::new(menu::MenuState::new()),
+        StateType::CharacterSelect => Box::new(character_select::CharacterSelectState::new()),
+        StateType::Controls => Box::new(controls::ControlsState::new()),
+        StateType::Gameplay => Box::new(gameplay::GameplayState::new()),
+        StateType::Cutscene => Box::new(cutscene::CutsceneState::new()),
+        StateType::Training => Box::new(training::TrainingState::new()),
+        StateType::Versus => Box::new(versus::VersusState::new()),
+        StateType::VersusSelect => Box::new(versus_select::VersusSelectState::new()),
+        StateType::Results => Box::new(results::ResultsState::new()),
+        StateType::Settings => Box::new(settings::SettingsState::new()),
+        StateType::HudEditor => Box::new(hud_editor::HudEditorState::new()),
+        StateType::EndlessMode => Box::new(endless::EndlessState::new()),
+        StateType::CoopSelect => Box::new(coop_select::CoopSelectState::new()),
+        StateType::OnlineLobby => Box::new(online_lobby::OnlineLobbyState::new()),
+        StateType::SkillTree => Box::new(skill_tree_ui::SkillTreeUIState::new()),
+        StateType::Profiles => Box::new(profiles::ProfilesState::new()),
+        StateType::Practice => Box::new(practice::PracticeState::new()),
+        StateType::Achievements => Box::new(achievements::AchievementsState::new()),
+        StateType::Account => Box::new(account::AccountState::new()),
+        StateType::Mods => Box::new(mods::ModsState::new()),
+        StateType::CoopMode => {
+            // Create gameplay with co-op enabled
+            let mut state = gameplay::GameplayState::new();
+            state.enable_coop(4); // Enable for up to 4 players
+            Box::new(state)
+        }
+        StateType::WinterArc => {
+            let mut state = gameplay::GameplayState::new();
+            state.enable_winter_arc();
+            Box::new(state)
+        }
+        StateType::DailyChallenge => {
+            let mut state = gameplay::GameplayState::new();
+            state.enable_challenge(crate::progression::ChallengeKind::Daily);
+            Box::new(state)
+        }
+        StateType::WeeklyChallenge => {
+            let mut state = gameplay::GameplayState::new();
+            state.enable_challenge(crate::progression::ChallengeKind::Weekly);
+            Box::new(state)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static LOG: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    }
+
+    fn log(event: &str, state_type: StateType) {
+        LOG.with(|log| log.borrow_mut().push(format!("{} {:?}", event, state_type)));
+    }
+
+    fn take_log() -> Vec<String> {
+        LOG.with(|log| std::mem::take(&mut *log.borrow_mut()))
+    }
+
+    /// Records what the manager does to it; Boot moves on to the menu after
+    /// its first update, like the real boot screen
+    struct StubState {
+        state_type: StateType,
+        updated: bool,
+        overlaid: bool,
+    }
+
+    impl State for StubState {
+        fn enter(&mut self) {
+            log("enter", self.state_type);
+        }
+
+        fn exit(&mut self) {
+            log("exit", self.state_type);
+        }
+
+        fn update(&mut self, _dt: f32) {
+            self.updated = true;
+            log("update", self.state_type);
+        }
+
+        fn fixed_update(&mut self, _dt: f64) {
+            log("tick", self.state_type);
+        }
+
+        fn render(&mut self, _interpolation: f32) {}
+
+        fn handle_input(&mut self) {
+            log("input", self.state_type);
+        }
+
+        fn should_transition(&self) -> Option<StateType> {
+            match self.state_type {
+                StateType::Boot if self.updated => Some(StateType::Menu),
+                _ => None,
+            }
+        }
+
+        /// Gameplay cuts to a scene on its first frame, which ends on its first
+        fn take_overlay(&mut self) -> Option<StateType> {
+            match self.state_type {
+                StateType::Gameplay if !self.overlaid => {
+                    self.overlaid = true;
+                    Some(StateType::Cutscene)
+                }
+                _ => None,
             }
         }
+
+        fn should_pop(&self) -> bool {
+            matches!(self.state_type, StateType::Cutscene) && self.updated
+        }
+
+        fn handles_escape(&self) -> bool {
+            matches!(self.state_type, StateType::Gameplay)
+        }
+    }
+
+    fn stub_state(state_type: StateType) -> Box<dyn State> {
+        Box::new(StubState {
+            state_type,
+            updated: false,
+            overlaid: false,
+        })
+    }
+
+    #[test]
+    fn step_runs_ticks_before_the_frame_update() {
+        let mut manager = StateManager::with_factory(stub_state);
+        manager.push_state(StateType::Menu);
+        manager.step(2, 1.0 / 120.0, 1.0 / 60.0);
+
+        assert_eq!(
+            take_log(),
+            ["enter Menu", "tick Menu", "tick Menu", "input Menu", "update Menu"]
+        );
+    }
+
+    #[test]
+    fn requested_transition_lands_on_the_next_frame() {
+        let mut manager = StateManager::with_factory(stub_state);
+        manager.push_state(StateType::Boot);
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(take_log(), ["enter Boot", "input Boot", "update Boot"]);
+
+        manager.step(1, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(
+            take_log(),
+            ["tick Boot", "exit Boot", "enter Menu", "input Menu", "update Menu"]
+        );
+    }
+
+    #[test]
+    fn escape_pops_back_and_quits_from_the_last_state() {
+        let mut manager = StateManager::with_factory(stub_state);
+        manager.push_state(StateType::Menu);
+        manager.push_state(StateType::Settings);
+        take_log();
+
+        manager.handle_escape();
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(take_log(), ["exit Settings", "input Menu", "update Menu"]);
+        assert!(!manager.should_quit());
+
+        // At the base state escape returns to a fresh menu rather than quitting
+        manager.handle_escape();
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(take_log(), ["exit Menu", "enter Menu", "input Menu", "update Menu"]);
+
+        manager.pop_state();
+        assert!(manager.should_quit());
+    }
+
+    #[test]
+    fn escape_is_left_to_a_state_that_handles_it() {
+        let mut manager = StateManager::with_factory(stub_state);
+        manager.push_state(StateType::Gameplay);
+        take_log();
+
+        manager.handle_escape();
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(take_log(), ["input Gameplay", "update Gameplay"]);
+    }
+
+    #[test]
+    fn an_overlay_pops_back_to_the_state_it_covered() {
+        let mut manager = StateManager::with_factory(stub_state);
+        manager.push_state(StateType::Gameplay);
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        take_log();
+
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(take_log(), ["enter Cutscene", "input Cutscene", "update Cutscene"]);
+
+        manager.step(0, 1.0 / 120.0, 1.0 / 60.0);
+        assert_eq!(take_log(), ["exit Cutscene", "input Gameplay", "update Gameplay"]);
+        assert!(!manager.should_quit());
     }
 }
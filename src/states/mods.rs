@@ -0,0 +1,194 @@
+use crate::data::mods::{self, ModReport, ModStatus};
+use crate::data::{palette, GameConfig};
+use crate::states::State;
+use crate::ui::UiLayout;
+use macroquad::prelude::*;
+
+const VISIBLE_ROWS: usize = 6;
+const ROW_HEIGHT: f32 = 96.0;
+
+/// Mods found in the mods folder and what the compatibility check made of
+/// each. Refused mods are listed so players can see why they didn't load.
+pub struct ModsState {
+    reports: Vec<ModReport>,
+    selected: usize,
+    /// First row on screen
+    scroll: usize,
+    status: Option<(String, Color)>,
+}
+
+impl ModsState {
+    pub fn new() -> Self {
+        Self {
+            reports: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            status: None,
+        }
+    }
+
+    fn rescan(&mut self) {
+        mods::rescan();
+        self.reports = mods::reports();
+        self.selected = self.selected.min(self.reports.len().saturating_sub(1));
+    }
+
+    /// Switch the selected mod on or off; refused ones stay off
+    fn toggle(&mut self) {
+        let Some(report) = self.reports.get(self.selected) else {
+            return;
+        };
+        if report.status == ModStatus::Refused {
+            self.status = Some((format!("{} can't be enabled", report.manifest.name), RED));
+            return;
+        }
+
+        let mut config = GameConfig::load();
+        let folder = report.folder.clone();
+        if report.enabled {
+            config.disabled_mods.push(folder);
+        } else {
+            config.disabled_mods.retain(|disabled| *disabled != folder);
+        }
+        if let Err(e) = config.save() {
+            self.status = Some((e, RED));
+            return;
+        }
+
+        self.rescan();
+        palette::reload(&config);
+        self.status = Some(("Takes effect from the next run".to_string(), GREEN));
+    }
+
+    fn status_color(status: ModStatus) -> Color {
+        match status {
+            ModStatus::Compatible => GREEN,
+            ModStatus::Warning => ORANGE,
+            ModStatus::Refused => RED,
+        }
+    }
+
+    fn render_row(&self, report: &ModReport, selected: bool, y: f32, mid: f32) {
+        let x = mid - 440.0;
+        let width = 880.0;
+        let accent = Self::status_color(report.status);
+
+        let background = if selected {
+            Color::new(1.0, 1.0, 0.0, 0.12)
+        } else {
+            Color::new(1.0, 1.0, 1.0, 0.04)
+        };
+        draw_rectangle(x, y, width, ROW_HEIGHT - 8.0, background);
+        draw_rectangle(x, y, 6.0, ROW_HEIGHT - 8.0, accent);
+
+        let name_color = if report.active() { WHITE } else { GRAY };
+        let title = if report.manifest.version.is_empty() {
+            report.manifest.name.clone()
+        } else {
+            format!("{} {}", report.manifest.name, report.manifest.version)
+        };
+        draw_text(&title, x + 20.0, y + 26.0, 26.0, name_color);
+
+        let state = if report.status == ModStatus::Refused {
+            report.status.label().to_string()
+        } else {
+            let enabled = if report.enabled { "ON" } else { "OFF" };
+            format!("{}  {}", report.status.label(), enabled)
+        };
+        let dims = measure_text(&state, None, 22, 1.0);
+        draw_text(&state, x + width - 20.0 - dims.width, y + 26.0, 22.0, accent);
+
+        let files = if report.files.is_empty() {
+            "No data files".to_string()
+        } else {
+            report.files.join(", ")
+        };
+        let detail = if report.manifest.description.is_empty() {
+            files
+        } else {
+            format!("{} ({})", report.manifest.description, files)
+        };
+        draw_text(&detail, x + 20.0, y + 48.0, 18.0, LIGHTGRAY);
+
+        // First two notes; the rest are in the log
+        for (i, note) in report.notes.iter().take(2).enumerate() {
+            draw_text(note, x + 20.0, y + 68.0 + i as f32 * 16.0, 16.0, accent);
+        }
+    }
+}
+
+impl State for ModsState {
+    fn enter(&mut self) {
+        self.selected = 0;
+        self.scroll = 0;
+        self.rescan();
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        let mid = ui.width() * 0.5;
+        let title = "MODS";
+        let dims = measure_text(title, None, 64, 1.0);
+        draw_text(title, mid - dims.width * 0.5, 100.0, 64.0, Color::new(1.0, 0.8, 0.0, 1.0));
+
+        let api = format!("Mod API {}", mods::MOD_API_VERSION);
+        let dims = measure_text(&api, None, 24, 1.0);
+        draw_text(&api, mid - dims.width * 0.5, 140.0, 24.0, LIGHTGRAY);
+
+        if self.reports.is_empty() {
+            let folder = mods::mods_directory();
+            let empty = format!("No mods in {}", folder.display());
+            let dims = measure_text(&empty, None, 24, 1.0);
+            draw_text(&empty, mid - dims.width * 0.5, 220.0, 24.0, GRAY);
+        }
+        let visible = self.reports.iter().enumerate().skip(self.scroll).take(VISIBLE_ROWS);
+        for (row, report) in visible {
+            let y = 180.0 + (row - self.scroll) as f32 * ROW_HEIGHT;
+            self.render_row(report, row == self.selected, y, mid);
+        }
+
+        if let Some((message, color)) = &self.status {
+            let dims = measure_text(message, None, 22, 1.0);
+            draw_text(message, mid - dims.width * 0.5, ui.height() - 85.0, 22.0, *color);
+        }
+
+        let hint = "W/S = Select | ENTER = Enable/Disable | R = Rescan | ESC = Back";
+        let dims = measure_text(hint, None, 22, 1.0);
+        draw_text(hint, mid - dims.width * 0.5, ui.height() - 50.0, 22.0, GRAY);
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        let count = self.reports.len();
+        if count > 0 && (is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up)) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if count > 0 && (is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down)) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            self.toggle();
+        }
+        if is_key_pressed(KeyCode::R) {
+            self.rescan();
+            self.status = Some((format!("Found {} mods", self.reports.len()), LIGHTGRAY));
+        }
+
+        // Keep the selection on screen
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use crate::data::characters::{Character, CHARACTERS};
+use crate::data::{CharacterId, CoopScaling, GameConfig, PlayerCountScaling};
+use crate::net::{Message, NetRole, NetSession};
+use crate::states::{State, StateType};
+use crate::updater::CURRENT_VERSION;
+use macroquad::prelude::*;
+
+/// Room codes the relay hands out are this long
+const CODE_LENGTH: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LobbyPhase {
+    /// Picking host or join, and a character
+    Choose,
+    /// Typing the room code to join
+    EnterCode,
+    /// On the relay, waiting for the room code, the partner or the start
+    Connected,
+}
+
+/// Online co-op lobby: host a room and share its code, or join one by code.
+/// The run starts once the host sees the partner and presses Enter.
+pub struct OnlineLobbyState {
+    phase: LobbyPhase,
+    /// 0 = host, 1 = join
+    selected_option: usize,
+    character_index: usize,
+    code_input: String,
+    relay_address: String,
+    session: Option<NetSession>,
+    partner: Option<CharacterId>,
+    /// Difficulty the run will have; the host's table wins once it starts
+    scaling: PlayerCountScaling,
+    error: Option<String>,
+    transition_to: Option<StateType>,
+}
+
+impl OnlineLobbyState {
+    pub fn new() -> Self {
+        let selected = crate::data::get_selected_character();
+        Self {
+            phase: LobbyPhase::Choose,
+            selected_option: 0,
+            character_index: CHARACTERS.iter().position(|c| c.id == selected).unwrap_or(0),
+            code_input: String::new(),
+            relay_address: GameConfig::load().relay_address,
+            session: None,
+            partner: None,
+            scaling: CoopScaling::load().for_players(2),
+            error: None,
+            transition_to: None,
+        }
+    }
+
+    fn character(&self) -> CharacterId {
+        CHARACTERS[self.character_index].id
+    }
+
+    fn connect(&mut self, result: Result<NetSession, String>) {
+        match result {
+            Ok(session) => {
+                self.session = Some(session);
+                self.phase = LobbyPhase::Connected;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn disconnect(&mut self, error: Option<String>) {
+        self.session = None;
+        self.partner = None;
+        self.phase = LobbyPhase::Choose;
+        self.error = error;
+    }
+
+    /// Hand the session to the co-op run, host's character first
+    fn start(&mut self, seed: u64, tick_rate: u32, lives: u32, scaling: PlayerCountScaling) {
+        let (Some(mut session), Some(partner)) = (self.session.take(), self.partner) else {
+            return;
+        };
+
+        session.begin(seed);
+        let players = match session.role() {
+            NetRole::Host => vec![self.character(), partner],
+            NetRole::Client => vec![partner, self.character()],
+        };
+        crate::data::set_coop_players(players);
+        crate::data::set_coop_lives(lives);
+        crate::data::set_coop_scaling(scaling);
+        crate::data::set_tick_rate(tick_rate);
+        crate::net::set_session(session);
+        self.transition_to = Some(StateType::CoopMode);
+    }
+
+    fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Joined => {
+                let hello = Message::Hello {
+                    version: CURRENT_VERSION.to_string(),
+                    character: self.character(),
+                };
+                if let Some(session) = &mut self.session {
+                    session.send(&hello);
+                }
+            }
+            // Both sides run the same simulation, so the builds have to match
+            Message::Hello { version, .. } if version != CURRENT_VERSION => {
+                self.disconnect(Some(format!(
+                    "Partner is on version {}, you're on {}",
+                    version, CURRENT_VERSION
+                )));
+            }
+            Message::Hello { character, .. } => self.partner = Some(character),
+            Message::Start {
+                seed,
+                tick_rate,
+                lives,
+                scaling,
+            } => self.start(seed, tick_rate, lives, scaling),
+            _ => {}
+        }
+    }
+}
+
+impl State for OnlineLobbyState {
+    fn enter(&mut self) {
+        self.transition_to = None;
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+
+        let messages = session.poll();
+        if let Some(reason) = session.closed() {
+            let reason = reason.to_string();
+            self.disconnect(Some(reason));
+            return;
+        }
+        for message in messages {
+            self.handle_message(message);
+        }
+    }
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.1, 0.1, 0.15, 1.0));
+
+        let sw = screen_width();
+        let sh = screen_height();
+        let centered = |text: &str, y: f32, size: f32, color: Color| {
+            let dims = measure_text(text, None, size as u16, 1.0);
+            draw_text(text, sw * 0.5 - dims.width * 0.5, y, size, color);
+        };
+
+        centered("ONLINE CO-OP", 80.0, 48.0, YELLOW);
+        centered(&format!("Relay: {}", self.relay_address), 115.0, 18.0, GRAY);
+
+        let character = Character::get_by_id(self.character());
+        let mut y = sh * 0.35;
+        match self.phase {
+            LobbyPhase::Choose => {
+                for (index, option) in ["HOST A ROOM", "JOIN A ROOM"].iter().enumerate() {
+                    let color = if index == self.selected_option { YELLOW } else { WHITE };
+                    let text = if index == self.selected_option {
+                        format!("> {} <", option)
+                    } else {
+                        option.to_string()
+                    };
+                    centered(&text, y, 32.0, color);
+                    y += 50.0;
+                }
+                centered(&format!("Character: < {} >", character.name), y + 30.0, 26.0, WHITE);
+                centered(
+                    "W/S = Host or Join | A/D = Character | ENTER = Confirm | ESC = Back",
+                    sh - 50.0,
+                    20.0,
+                    GRAY,
+                );
+            }
+            LobbyPhase::EnterCode => {
+                centered("ENTER ROOM CODE", y, 32.0, WHITE);
+                let code = format!("{:_<width$}", self.code_input, width = CODE_LENGTH);
+                centered(&code, y + 70.0, 56.0, YELLOW);
+                centered("ENTER = Join | BACKSPACE = Delete | ESC = Back", sh - 50.0, 20.0, GRAY);
+            }
+            LobbyPhase::Connected => {
+                let Some(session) = &self.session else {
+                    return;
+                };
+
+                if session.role() == NetRole::Host {
+                    match session.room_code() {
+                        Some(code) => {
+                            centered("ROOM CODE", y, 26.0, WHITE);
+                            centered(code, y + 70.0, 64.0, YELLOW);
+                        }
+                        None => centered("Opening room...", y, 26.0, WHITE),
+                    }
+                } else {
+                    centered("Joined room", y, 26.0, WHITE);
+                }
+
+                y += 140.0;
+                centered(&format!("You: {}", character.name), y, 24.0, WHITE);
+                match self.partner {
+                    Some(partner) => {
+                        let name = Character::get_by_id(partner).name;
+                        centered(&format!("Partner: {}", name), y + 35.0, 24.0, GREEN);
+                        let prompt = match session.role() {
+                            NetRole::Host => "Press ENTER to start",
+                            NetRole::Client => "Waiting for the host to start...",
+                        };
+                        centered(prompt, y + 90.0, 26.0, YELLOW);
+                        centered("2 PLAYERS", y + 140.0, 20.0, WHITE);
+                        centered(&self.scaling.summary(), y + 165.0, 18.0, LIGHTGRAY);
+                    }
+                    None => centered("Waiting for partner...", y + 35.0, 24.0, GRAY),
+                }
+                centered("ESC = Leave", sh - 50.0, 20.0, GRAY);
+            }
+        }
+
+        if let Some(error) = &self.error {
+            centered(error, sh - 90.0, 20.0, Color::new(1.0, 0.4, 0.4, 1.0));
+        }
+    }
+
+    fn handle_input(&mut self) {
+        if is_key_pressed(KeyCode::Escape) {
+            match self.phase {
+                LobbyPhase::Choose => self.transition_to = Some(StateType::CoopSelect),
+                _ => self.disconnect(None),
+            }
+            return;
+        }
+
+        // Typed characters queue up until read; only the code entry wants them
+        if self.phase != LobbyPhase::EnterCode {
+            while get_char_pressed().is_some() {}
+        }
+
+        match self.phase {
+            LobbyPhase::Choose => {
+                if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
+                    self.selected_option = 0;
+                }
+                if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+                    self.selected_option = 1;
+                }
+                if is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left) {
+                    self.character_index =
+                        (self.character_index + CHARACTERS.len() - 1) % CHARACTERS.len();
+                }
+                if is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right) {
+                    self.character_index = (self.character_index + 1) % CHARACTERS.len();
+                }
+
+                if is_key_pressed(KeyCode::Enter) {
+                    if self.selected_option == 0 {
+                        let result = NetSession::host(&self.relay_address);
+                        self.connect(result);
+                    } else {
+                        self.code_input.clear();
+                        self.error = None;
+                        self.phase = LobbyPhase::EnterCode;
+                    }
+                }
+            }
+            LobbyPhase::EnterCode => {
+                while let Some(c) = get_char_pressed() {
+                    if c.is_ascii_alphanumeric() && self.code_input.len() < CODE_LENGTH {
+                        self.code_input.push(c.to_ascii_uppercase());
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    self.code_input.pop();
+                }
+                if is_key_pressed(KeyCode::Enter) && !self.code_input.is_empty() {
+                    let result = NetSession::join(&self.relay_address, &self.code_input);
+                    self.connect(result);
+                }
+            }
+            LobbyPhase::Connected => {
+                let is_host = self.session.as_ref().map(|s| s.role()) == Some(NetRole::Host);
+                if is_host && self.partner.is_some() && is_key_pressed(KeyCode::Enter) {
+                    let seed = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|elapsed| elapsed.as_nanos() as u64)
+                        .unwrap_or(0);
+                    let tick_rate = crate::data::get_tick_rate();
+                    let lives = crate::data::get_coop_lives();
+                    let scaling = self.scaling;
+                    if let Some(session) = &mut self.session {
+                        session.send(&Message::Start {
+                            seed,
+                            tick_rate,
+                            lives,
+                            scaling,
+                        });
+                    }
+                    self.start(seed, tick_rate, lives, scaling);
+                }
+            }
+        }
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+}
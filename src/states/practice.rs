@@ -0,0 +1,244 @@
+use crate::data::encounters::{EncounterScript, SCRIPTED_MAPS};
+use crate::data::{ShopManager, UpgradeId, CHARACTERS};
+use crate::progression::practice::{PracticeProgress, PracticeStart};
+use crate::progression::roster::{Roster, TrialRun};
+use crate::render::map_system::{MapSystem, MapType};
+use crate::states::{State, StateType};
+use crate::ui::UiLayout;
+use macroquad::prelude::*;
+
+/// Rows above the loadout toggles: map, wave and character
+const SETTING_ROWS: usize = 3;
+
+/// Sets up a practice run: any map and wave a regular run has reached, a
+/// character, and which owned shop upgrades to bring. Practice pays out
+/// nothing and isn't logged as a run, so locked characters can be tried here
+/// as often as the player likes.
+pub struct PracticeState {
+    progress: PracticeProgress,
+    roster: Roster,
+    encounters: EncounterScript,
+    /// Upgrades that can go in the loadout, each with whether it's taken
+    loadout: Vec<(UpgradeId, bool)>,
+    map: usize,
+    /// Counted from 1
+    wave: u32,
+    character: usize,
+    selected: usize,
+    transition_to: Option<StateType>,
+}
+
+impl PracticeState {
+    pub fn new() -> Self {
+        Self {
+            progress: PracticeProgress::default(),
+            roster: Roster::load(),
+            encounters: EncounterScript::default(),
+            loadout: Vec::new(),
+            map: 0,
+            wave: 1,
+            character: 0,
+            selected: 0,
+            transition_to: None,
+        }
+    }
+
+    fn map(&self) -> MapType {
+        SCRIPTED_MAPS[self.map]
+    }
+
+    fn wave_count(&self) -> u32 {
+        (self.encounters.waves(self.map()).len() as u32).max(1)
+    }
+
+    /// Map, wave, character, the loadout, then START
+    fn row_count(&self) -> usize {
+        SETTING_ROWS + self.loadout.len() + 1
+    }
+
+    fn start_row(&self) -> usize {
+        self.row_count() - 1
+    }
+
+    fn unlocked(&self) -> bool {
+        self.progress.unlocked(self.map(), self.wave)
+    }
+
+    /// Step the selected row's value back or forward
+    fn change(&mut self, step: i32) {
+        match self.selected {
+            0 => {
+                let count = SCRIPTED_MAPS.len() as i32;
+                self.map = (self.map as i32 + step).rem_euclid(count) as usize;
+                self.wave = self.wave.min(self.wave_count());
+            }
+            1 => {
+                let count = self.wave_count() as i32;
+                self.wave = (self.wave as i32 - 1 + step).rem_euclid(count) as u32 + 1;
+            }
+            2 => {
+                let count = CHARACTERS.len() as i32;
+                self.character = (self.character as i32 + step).rem_euclid(count) as usize;
+            }
+            _ => {}
+        }
+    }
+
+    fn confirm(&mut self) {
+        if self.selected == self.start_row() {
+            self.start();
+        } else if let Some((_, taken)) = self.loadout.get_mut(self.selected - SETTING_ROWS) {
+            *taken = !*taken;
+        } else {
+            self.change(1);
+        }
+    }
+
+    fn start(&mut self) {
+        if !self.unlocked() {
+            return;
+        }
+        let character = CHARACTERS[self.character].id;
+        let trial = (!self.roster.is_unlocked(character)).then(|| TrialRun {
+            character,
+            previous: crate::data::get_selected_character(),
+        });
+        crate::data::set_trial_run(trial);
+        crate::data::set_selected_character(character);
+        crate::data::set_practice_start(PracticeStart {
+            map: self.map(),
+            wave: self.wave,
+            upgrades: self
+                .loadout
+                .iter()
+                .filter(|(_, taken)| *taken)
+                .map(|(upgrade, _)| *upgrade)
+                .collect(),
+        });
+        self.transition_to = Some(StateType::Gameplay);
+    }
+
+    fn render_row(&self, label: &str, value: &str, row: usize, y: f32, mid: f32) {
+        let selected = row == self.selected;
+        let color = if selected { YELLOW } else { WHITE };
+        if selected {
+            draw_rectangle(mid - 360.0, y - 30.0, 720.0, 42.0, Color::new(1.0, 1.0, 0.0, 0.15));
+        }
+        draw_text(label, mid - 330.0, y, 28.0, color);
+        let dims = measure_text(value, None, 28, 1.0);
+        draw_text(value, mid + 330.0 - dims.width, y, 28.0, color);
+    }
+}
+
+impl State for PracticeState {
+    fn enter(&mut self) {
+        self.progress = PracticeProgress::load();
+        self.roster = Roster::load();
+        let (encounters, _) = EncounterScript::load(|map| MapSystem::new(map).spawn_points.len());
+        self.encounters = encounters;
+
+        // Only what's been bought can be brought, unless debugging
+        let shop = ShopManager::load();
+        let everything = crate::data::get_debug_controls();
+        self.loadout = UpgradeId::SOLD
+            .iter()
+            .filter(|&&upgrade| everything || shop.has_upgrade(upgrade))
+            .map(|&upgrade| (upgrade, true))
+            .collect();
+
+        let selected = crate::data::get_selected_character();
+        self.character = CHARACTERS.iter().position(|c| c.id == selected).unwrap_or(0);
+        self.wave = self.wave.min(self.wave_count());
+        self.selected = self.selected.min(self.start_row());
+        self.transition_to = None;
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        let mid = ui.width() * 0.5;
+        let title = "PRACTICE";
+        let dims = measure_text(title, None, 64, 1.0);
+        draw_text(title, mid - dims.width * 0.5, 120.0, 64.0, Color::new(1.0, 0.8, 0.0, 1.0));
+
+        let map = format!("{:?}", self.map()).to_uppercase();
+        let wave = format!("{} / {}", self.wave, self.wave_count());
+        let picked = &CHARACTERS[self.character];
+        let character = if self.roster.is_unlocked(picked.id) {
+            picked.name.to_uppercase()
+        } else {
+            format!("{} (TRIAL)", picked.name.to_uppercase())
+        };
+        self.render_row("MAP", &format!("< {} >", map), 0, 200.0, mid);
+        self.render_row("WAVE", &format!("< {} >", wave), 1, 245.0, mid);
+        self.render_row("CHARACTER", &format!("< {} >", character), 2, 290.0, mid);
+
+        let mut y = 360.0;
+        draw_text("LOADOUT", mid - 330.0, y - 10.0, 22.0, GRAY);
+        y += 25.0;
+        if self.loadout.is_empty() {
+            draw_text("No shop upgrades owned yet", mid - 330.0, y, 24.0, GRAY);
+            y += 45.0;
+        }
+        for (index, (upgrade, taken)) in self.loadout.iter().enumerate() {
+            let value = if *taken { "ON" } else { "OFF" };
+            self.render_row(upgrade.label(), value, SETTING_ROWS + index, y, mid);
+            y += 40.0;
+        }
+
+        y += 20.0;
+        let reached = self.progress.reached(self.map());
+        if self.unlocked() {
+            self.render_row("START", "", self.start_row(), y, mid);
+        } else {
+            self.render_row("LOCKED", "", self.start_row(), y, mid);
+            let locked = if reached == 0 {
+                format!("Reach the {} in a run to practice there", map.to_lowercase())
+            } else {
+                format!("Runs have only reached wave {} here", reached)
+            };
+            let dims = measure_text(&locked, None, 22, 1.0);
+            draw_text(&locked, mid - dims.width * 0.5, y + 40.0, 22.0, RED);
+        }
+
+        let hint = "W/S = Choose | A/D = Change | ENTER = Toggle / Start | ESC = Back";
+        let dims = measure_text(hint, None, 22, 1.0);
+        draw_text(hint, mid - dims.width * 0.5, ui.height() - 60.0, 22.0, GRAY);
+        let note = "Practice runs earn no Arc Tokens, XP or achievements";
+        let dims = measure_text(note, None, 20, 1.0);
+        draw_text(note, mid - dims.width * 0.5, ui.height() - 90.0, 20.0, DARKGRAY);
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        let rows = self.row_count();
+        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + rows - 1) % rows;
+        }
+        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % rows;
+        }
+        if is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left) {
+            self.change(-1);
+        }
+        if is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right) {
+            self.change(1);
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) {
+            self.confirm();
+        }
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+}
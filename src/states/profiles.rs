@@ -0,0 +1,239 @@
+use crate::data::characters::Character;
+use crate::data::events::{self, format_countdown};
+use crate::data::save::{self, SaveInfo, SaveManager, MAX_PROFILE_NAME, PROFILE_SLOTS};
+use crate::states::{State, StateType};
+use crate::ui::UiLayout;
+use macroquad::prelude::*;
+
+const CARD_WIDTH: f32 = 900.0;
+const CARD_HEIGHT: f32 = 130.0;
+
+/// Picks which profile is played. Each slot shows its name, playtime,
+/// completion, character and when it was last played.
+pub struct ProfilesState {
+    slots: Vec<Option<SaveInfo>>,
+    selected: usize,
+    /// Name being typed for the selected slot, new or renamed
+    naming: Option<String>,
+    /// Delete asks twice
+    confirm_delete: bool,
+    status: Option<(String, Color)>,
+    transition_to: Option<StateType>,
+}
+
+impl ProfilesState {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            selected: 0,
+            naming: None,
+            confirm_delete: false,
+            status: None,
+            transition_to: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.slots = SaveManager::new().get_save_slots();
+    }
+
+    /// Play the selected profile and head back to the menu
+    fn pick(&mut self) {
+        if self.selected != crate::data::get_active_profile() {
+            if let Err(e) = save::switch_profile(self.selected) {
+                self.status = Some((e, RED));
+                return;
+            }
+        }
+        self.transition_to = Some(StateType::Menu);
+    }
+
+    /// Create the profile being typed in an empty slot, or rename the one
+    /// that's there
+    fn finish_naming(&mut self) {
+        let Some(name) = self.naming.take() else {
+            return;
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+
+        let mut saves = SaveManager::new();
+        let result = if self.slots[self.selected].is_some() {
+            saves.load_from_slot(self.selected).and_then(|_| {
+                if let Some(save) = saves.get_current_save_mut() {
+                    save.profile_name = name.clone();
+                }
+                saves.save_to_slot(self.selected)
+            })
+        } else {
+            saves.create_new_save(name.clone());
+            saves.save_to_slot(self.selected)
+        };
+
+        self.status = Some(match result {
+            Ok(()) => (format!("Saved {}", name), GREEN),
+            Err(e) => (e, RED),
+        });
+        self.refresh();
+    }
+
+    fn delete(&mut self) {
+        if !self.confirm_delete {
+            self.confirm_delete = true;
+            self.status = Some(("Press X again to delete this profile".to_string(), YELLOW));
+            return;
+        }
+
+        self.confirm_delete = false;
+        self.status = Some(match SaveManager::new().delete_profile(self.selected) {
+            Ok(()) => ("Profile deleted".to_string(), GREEN),
+            Err(e) => (e, RED),
+        });
+        self.refresh();
+    }
+
+    fn render_card(&self, ui: &UiLayout, slot: usize, y: f32) {
+        let x = ui.width() * 0.5 - CARD_WIDTH * 0.5;
+        let selected = slot == self.selected;
+        let active = slot == crate::data::get_active_profile();
+
+        let fill = if selected {
+            Color::new(0.3, 0.6, 1.0, 0.35)
+        } else {
+            Color::new(0.2, 0.2, 0.25, 0.9)
+        };
+        draw_rectangle(x, y, CARD_WIDTH, CARD_HEIGHT, fill);
+        if selected {
+            draw_rectangle_lines(x, y, CARD_WIDTH, CARD_HEIGHT, 3.0, YELLOW);
+        }
+
+        let name = match (&self.naming, &self.slots[slot]) {
+            (Some(typed), _) if selected => format!("{}_", typed),
+            (_, Some(info)) => info.profile_name.clone(),
+            (_, None) => "EMPTY SLOT".to_string(),
+        };
+        draw_text(&format!("{}. {}", slot + 1, name), x + 30.0, y + 48.0, 36.0, WHITE);
+        if active {
+            draw_text("PLAYING", x + CARD_WIDTH - 140.0, y + 44.0, 24.0, GREEN);
+        }
+
+        let Some(info) = &self.slots[slot] else {
+            draw_text("ENTER to create", x + 30.0, y + 95.0, 22.0, GRAY);
+            return;
+        };
+
+        let character = info
+            .selected_character
+            .map_or("No character yet", |id| Character::get_by_id(id).name);
+        let last_played = events::now().saturating_sub(info.timestamp);
+        let details = format!(
+            "{} played  |  {:.0}% complete  |  {}  |  last played {} ago",
+            format_countdown(info.playtime as u64),
+            info.completion * 100.0,
+            character,
+            format_countdown(last_played)
+        );
+        draw_text(&details, x + 30.0, y + 95.0, 22.0, LIGHTGRAY);
+    }
+}
+
+impl State for ProfilesState {
+    fn enter(&mut self) {
+        self.selected = crate::data::get_active_profile();
+        self.naming = None;
+        self.confirm_delete = false;
+        self.status = None;
+        self.transition_to = None;
+        self.refresh();
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {}
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.08, 0.08, 0.12, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        let mid = ui.width() * 0.5;
+        let title = "PROFILES";
+        let dims = measure_text(title, None, 64, 1.0);
+        draw_text(title, mid - dims.width * 0.5, 140.0, 64.0, Color::new(1.0, 0.8, 0.0, 1.0));
+
+        for slot in 0..PROFILE_SLOTS {
+            self.render_card(&ui, slot, 220.0 + slot as f32 * (CARD_HEIGHT + 30.0));
+        }
+
+        let hint = if self.naming.is_some() {
+            "Type a name | ENTER = Save | BACKSPACE = Erase"
+        } else {
+            "W/S = Choose | ENTER = Play | R = Rename | X = Delete | ESC = Back"
+        };
+        let dims = measure_text(hint, None, 22, 1.0);
+        draw_text(hint, mid - dims.width * 0.5, ui.height() - 80.0, 22.0, GRAY);
+
+        if let Some((status, color)) = &self.status {
+            let dims = measure_text(status, None, 22, 1.0);
+            draw_text(status, mid - dims.width * 0.5, ui.height() - 120.0, 22.0, *color);
+        }
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        if let Some(name) = &mut self.naming {
+            while let Some(c) = get_char_pressed() {
+                let allowed = c.is_ascii_alphanumeric() || c == ' ' || c == '-';
+                if allowed && name.len() < MAX_PROFILE_NAME {
+                    name.push(c);
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                name.pop();
+            }
+            if is_key_pressed(KeyCode::Enter) {
+                self.finish_naming();
+            }
+            return;
+        }
+
+        // Typed characters queue up until read; only naming wants them
+        while get_char_pressed().is_some() {}
+
+        let previous = self.selected;
+        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + PROFILE_SLOTS - 1) % PROFILE_SLOTS;
+        }
+        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % PROFILE_SLOTS;
+        }
+        if self.selected != previous {
+            self.confirm_delete = false;
+            self.status = None;
+        }
+
+        let occupied = self.slots[self.selected].is_some();
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) {
+            if occupied {
+                self.pick();
+            } else {
+                self.naming = Some(String::new());
+            }
+        }
+        if occupied && is_key_pressed(KeyCode::R) {
+            self.naming = self.slots[self.selected].as_ref().map(|info| info.profile_name.clone());
+        }
+        if occupied && is_key_pressed(KeyCode::X) {
+            self.delete();
+        }
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+}
@@ -1,8 +1,363 @@
+use crate::audio::MusicCue;
+use crate::combat::combo_system::StyleRank;
+use crate::data::replay::Replay;
+use crate::data::dialogue::StoryEnding;
+use crate::data::localization::tr;
+use crate::data::{Character, MatchResult, RunReport, StoryResult};
+use crate::progression::roster::UNLOCK_COST;
+use crate::progression::PlayerAccount;
+use crate::render::map_system::MapType;
+use crate::states::versus::VERSUS_STAGES;
 use crate::states::{State, StateType};
+use crate::ui::account_banner::render_account_banner;
 use macroquad::prelude::*;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QuickAction {
+    Rematch,
+    CharacterSelect,
+    ChangeStage,
+    SaveReplay,
+    MainMenu,
+}
+
+const QUICK_ACTIONS: [QuickAction; 5] = [
+    QuickAction::Rematch,
+    QuickAction::CharacterSelect,
+    QuickAction::ChangeStage,
+    QuickAction::SaveReplay,
+    QuickAction::MainMenu,
+];
+
+impl QuickAction {
+    fn label(self) -> &'static str {
+        match self {
+            QuickAction::Rematch => "REMATCH",
+            QuickAction::CharacterSelect => "CHARACTER SELECT",
+            QuickAction::ChangeStage => "CHANGE STAGE",
+            QuickAction::SaveReplay => "SAVE REPLAY",
+            QuickAction::MainMenu => "MAIN MENU",
+        }
+    }
+}
+
+/// After a versus match: quick actions that keep the session going
+struct VersusResults {
+    duration: f32,
+    rounds: [u32; 2],
+    replay: Option<Replay>,
+    replay_saved: bool,
+    stage_index: usize,
+    picking_stage: bool,
+    selected_action: usize,
+    status: Option<String>,
+}
+
+impl VersusResults {
+    fn new(result: MatchResult) -> Self {
+        let stage = crate::data::get_match_settings()
+            .map(|settings| settings.stage)
+            .unwrap_or(MapType::Classroom);
+
+        Self {
+            duration: result.duration,
+            rounds: result.rounds,
+            replay: result.replay,
+            replay_saved: false,
+            stage_index: VERSUS_STAGES.iter().position(|&s| s == stage).unwrap_or(0),
+            picking_stage: false,
+            selected_action: 0,
+            status: None,
+        }
+    }
+
+    fn handle_input(&mut self) -> Option<StateType> {
+        let confirm = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J);
+
+        if self.picking_stage {
+            let count = VERSUS_STAGES.len();
+            if is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left) {
+                self.stage_index = (self.stage_index + count - 1) % count;
+            }
+            if is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right) {
+                self.stage_index = (self.stage_index + 1) % count;
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                self.picking_stage = false;
+            }
+            if confirm {
+                if let Some(mut settings) = crate::data::get_match_settings() {
+                    settings.stage = VERSUS_STAGES[self.stage_index];
+                    crate::data::set_match_settings(settings);
+                }
+                return Some(StateType::Versus);
+            }
+            return None;
+        }
+
+        let count = QUICK_ACTIONS.len();
+        if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
+            self.selected_action = (self.selected_action + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
+            self.selected_action = (self.selected_action + 1) % count;
+        }
+        if !confirm {
+            return None;
+        }
+
+        match QUICK_ACTIONS[self.selected_action] {
+            QuickAction::Rematch => return Some(StateType::Versus),
+            QuickAction::CharacterSelect => return Some(StateType::VersusSelect),
+            QuickAction::ChangeStage => self.picking_stage = true,
+            QuickAction::SaveReplay => self.save_replay(),
+            QuickAction::MainMenu => return Some(StateType::Menu),
+        }
+        None
+    }
+
+    fn save_replay(&mut self) {
+        let message = match &self.replay {
+            _ if self.replay_saved => "Replay already saved".to_string(),
+            Some(replay) => match replay.save() {
+                Ok(path) => {
+                    self.replay_saved = true;
+                    format!("Replay saved to {}", path.display())
+                }
+                Err(e) => e,
+            },
+            None => "No replay was recorded for this match".to_string(),
+        };
+        self.status = Some(message);
+    }
+
+    fn render(&self) {
+        let info = Color::new(0.7, 0.9, 1.0, 1.0);
+        draw_text(
+            &format!(
+                "Rounds: {} - {}   Match time: {:.1}s",
+                self.rounds[0], self.rounds[1], self.duration
+            ),
+            300.0,
+            240.0,
+            26.0,
+            info,
+        );
+
+        let mut y = 320.0;
+        for (i, action) in QUICK_ACTIONS.iter().enumerate() {
+            let selected = i == self.selected_action;
+            let label = match action {
+                QuickAction::ChangeStage if self.picking_stage => format!(
+                    "< {} >",
+                    VERSUS_STAGES[self.stage_index].display_name().to_uppercase()
+                ),
+                QuickAction::ChangeStage => format!(
+                    "{}: {}",
+                    action.label(),
+                    VERSUS_STAGES[self.stage_index].display_name().to_uppercase()
+                ),
+                QuickAction::SaveReplay if self.replay_saved => {
+                    format!("{} (SAVED)", action.label())
+                }
+                _ => action.label().to_string(),
+            };
+            let (prefix, color) = if selected {
+                ("> ", YELLOW)
+            } else {
+                ("  ", WHITE)
+            };
+            draw_text(&format!("{}{}", prefix, label), 300.0, y, 32.0, color);
+            y += 50.0;
+        }
+
+        if let Some(status) = &self.status {
+            draw_text(status, 300.0, y + 20.0, 20.0, info);
+        }
+
+        let hint = if self.picking_stage {
+            "A/D: Stage | ENTER: Play | BACKSPACE: Cancel"
+        } else {
+            "W/S: Select | ENTER: Confirm"
+        };
+        draw_text(
+            hint,
+            screen_width() * 0.5 - 150.0,
+            650.0,
+            20.0,
+            Color::new(1.0, 1.0, 1.0, 0.6),
+        );
+    }
+}
+
+/// Best style first, then up a grade each for clearing the run and taking no
+/// damage
+const GRADES: [StyleRank; 7] = [
+    StyleRank::D,
+    StyleRank::C,
+    StyleRank::B,
+    StyleRank::A,
+    StyleRank::S,
+    StyleRank::SS,
+    StyleRank::SSS,
+];
+/// Unlocks listed by name; the rest are counted
+const LISTED_UNLOCKS: usize = 5;
+
+fn run_grade(report: &RunReport) -> StyleRank {
+    let base = GRADES.iter().position(|&rank| rank == report.best_rank).unwrap_or(0);
+    let steps = report.cleared as usize + report.flawless as usize;
+    GRADES[(base + steps).min(GRADES.len() - 1)]
+}
+
+/// After a gameplay run: its grade, what it earned and unlocked, and a way
+/// back in
+struct RunResults {
+    report: RunReport,
+    grade: StyleRank,
+    /// Retry is picked rather than continue
+    retry_selected: bool,
+}
+
+impl RunResults {
+    fn new(report: RunReport) -> Self {
+        Self {
+            grade: run_grade(&report),
+            report,
+            retry_selected: false,
+        }
+    }
+
+    fn handle_input(&mut self) -> Option<StateType> {
+        let switch = is_key_pressed(KeyCode::A)
+            || is_key_pressed(KeyCode::D)
+            || is_key_pressed(KeyCode::Left)
+            || is_key_pressed(KeyCode::Right);
+        if switch && self.report.retry.is_some() {
+            self.retry_selected = !self.retry_selected;
+        }
+        if !(is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J)) {
+            return None;
+        }
+        match self.report.retry {
+            Some(retry) if self.retry_selected => Some(retry),
+            _ => Some(StateType::Menu),
+        }
+    }
+
+    /// Stats down the left and the grade on the right, revealed one after
+    /// the other like the score rows
+    fn render(&self, display_timer: f32) {
+        let info = Color::new(0.7, 0.9, 1.0, 1.0);
+        let report = &self.report;
+        let rows = [
+            (tr("results.waves"), report.waves_cleared.to_string(), WHITE),
+            (tr("results.max_combo"), report.max_combo.to_string(), WHITE),
+            (
+                tr("results.best_style"),
+                report.best_rank.to_string().to_string(),
+                report.best_rank.to_color(),
+            ),
+        ];
+        for (i, (label, value, color)) in rows.iter().enumerate() {
+            if display_timer <= 0.5 + i as f32 * 0.4 {
+                break;
+            }
+            let y = 270.0 + i as f32 * 45.0;
+            draw_text(label, 300.0, y, 30.0, WHITE);
+            draw_text(value, 600.0, y, 30.0, *color);
+        }
+        if display_timer > 1.7 && report.flawless {
+            draw_text(&tr("results.flawless"), 300.0, 405.0, 24.0, GOLD);
+        }
+
+        if display_timer > 2.0 {
+            let x = screen_width() * 0.5 + 260.0;
+            draw_text(&tr("results.grade"), x, 270.0, 30.0, WHITE);
+            let grade = self.grade.to_string();
+            draw_text(grade, x, 380.0, 110.0, self.grade.to_color());
+        }
+
+        if display_timer > 2.5 {
+            self.render_rewards(300.0, 470.0, info);
+            self.render_unlocks(screen_width() * 0.5 + 60.0, 470.0);
+        }
+
+        if display_timer > 3.0 {
+            self.render_actions(650.0);
+        }
+    }
+
+    fn render_rewards(&self, x: f32, y: f32, color: Color) {
+        draw_text(&tr("results.rewards"), x, y, 26.0, YELLOW);
+        let report = &self.report;
+        let rows = [
+            (tr("results.tokens"), report.tokens_earned as f32),
+            (tr("results.mastery_xp"), report.mastery_xp),
+            (tr("results.account_xp"), report.account_xp),
+        ];
+        for (i, (label, amount)) in rows.iter().enumerate() {
+            let row_y = y + 35.0 + i as f32 * 30.0;
+            draw_text(label, x, row_y, 22.0, color);
+            draw_text(&format!("+{:.0}", amount), x + 220.0, row_y, 22.0, WHITE);
+        }
+    }
+
+    fn render_unlocks(&self, x: f32, y: f32) {
+        draw_text(&tr("results.unlocks"), x, y, 26.0, YELLOW);
+        let unlocks = &self.report.unlocks;
+        if unlocks.is_empty() {
+            draw_text(&tr("results.no_unlocks"), x, y + 35.0, 20.0, GRAY);
+            return;
+        }
+        for (i, unlock) in unlocks.iter().take(LISTED_UNLOCKS).enumerate() {
+            draw_text(unlock, x, y + 35.0 + i as f32 * 26.0, 20.0, WHITE);
+        }
+        if unlocks.len() > LISTED_UNLOCKS {
+            let more = tr("results.more_unlocks")
+                .replace("{}", &(unlocks.len() - LISTED_UNLOCKS).to_string());
+            let more_y = y + 35.0 + LISTED_UNLOCKS as f32 * 26.0;
+            draw_text(&more, x, more_y, 20.0, GRAY);
+        }
+    }
+
+    fn render_actions(&self, y: f32) {
+        let mut actions = vec![(tr("results.continue_action"), !self.retry_selected)];
+        if self.report.retry.is_some() {
+            actions.push((tr("results.retry"), self.retry_selected));
+        }
+        let mut x = screen_width() * 0.5 - 150.0;
+        for (label, selected) in actions {
+            let (text, color) = if selected {
+                (format!("> {}", label), YELLOW)
+            } else {
+                (format!("  {}", label), WHITE)
+            };
+            draw_text(&text, x, y, 32.0, color);
+            x += 220.0;
+        }
+        draw_text(
+            &tr("results.run_hint"),
+            screen_width() * 0.5 - 150.0,
+            y + 40.0,
+            20.0,
+            Color::new(1.0, 1.0, 1.0, 0.6),
+        );
+
+        // Trials end with the way to keep the character
+        if let Some(character) = self.report.trial {
+            let prompt = tr("results.trial")
+                .replacen("{}", Character::get_by_id(character).name, 1)
+                .replacen("{}", &UNLOCK_COST.to_string(), 1);
+            let dims = measure_text(&prompt, None, 22, 1.0);
+            draw_text(&prompt, screen_width() * 0.5 - dims.width * 0.5, y + 80.0, 22.0, ORANGE);
+        }
+    }
+}
+
 pub struct ResultsState {
-    winner: String,
+    /// None for a drawn versus match
+    winner: Option<String>,
     score: u32,
     time_bonus: u32,
     combo_bonus: u32,
@@ -11,11 +366,52 @@ pub struct ResultsState {
     transition_to: Option<StateType>,
     waves_completed: usize,
     enemies_defeated: u32,
+    versus: Option<VersusResults>,
+    /// Set when a story run was cleared; each ending has its own screen
+    story: Option<StoryResult>,
+    /// Set after any gameplay run, story or not
+    run: Option<RunResults>,
+    /// Worn title, banner and prestige badge under the headline
+    account: PlayerAccount,
 }
 
 impl ResultsState {
     pub fn new() -> Self {
-        Self::with_data("PLAYER".to_string(), 0, 0, 0)
+        let mut state = match crate::data::take_match_result() {
+            Some(result) => Self::versus(result),
+            None => match crate::data::take_story_result() {
+                Some(result) => Self::story(result),
+                None => Self::with_data("PLAYER".to_string(), 0, 0, 0),
+            },
+        };
+        if state.versus.is_none() {
+            state.run = crate::data::take_run_report().map(RunResults::new);
+        }
+        state
+    }
+
+    fn story(result: StoryResult) -> Self {
+        let mut state = Self::with_data(
+            String::new(),
+            result.waves_completed,
+            result.enemies_defeated,
+            result.time_survived,
+        );
+        // The bonus row rewards whatever the ending was about
+        state.combo_bonus = match result.ending {
+            StoryEnding::Party => result.enemies_defeated * 50,
+            StoryEnding::Repair => result.waves_completed as u32 * 400,
+            StoryEnding::Home => result.time_survived * 5,
+        };
+        state.story = Some(result);
+        state
+    }
+
+    fn versus(result: MatchResult) -> Self {
+        let mut state = Self::with_data(String::new(), 0, 0, 0);
+        state.winner = result.winner.clone();
+        state.versus = Some(VersusResults::new(result));
+        state
     }
 
     pub fn with_data(winner: String, waves_completed: usize, enemies_defeated: u32, time_survived: u32) -> Self {
@@ -27,7 +423,7 @@ impl ResultsState {
         let total_score = base_score + time_bonus + combo_bonus;
 
         Self {
-            winner,
+            winner: Some(winner),
             score: base_score,
             time_bonus,
             combo_bonus,
@@ -36,12 +432,17 @@ impl ResultsState {
             transition_to: None,
             waves_completed,
             enemies_defeated,
+            versus: None,
+            story: None,
+            run: None,
+            account: PlayerAccount::load(),
         }
     }
 }
 
 impl State for ResultsState {
     fn enter(&mut self) {
+        crate::data::queue_music_cue(MusicCue::Results);
         self.display_timer = 0.0;
         self.total_score = self.score + self.time_bonus + self.combo_bonus;
         self.transition_to = None;
@@ -56,9 +457,24 @@ impl State for ResultsState {
     fn fixed_update(&mut self, _dt: f64) {}
 
     fn render(&mut self, _interpolation: f32) {
-        clear_background(BLACK);
+        let ending = self.story.as_ref().map(|story| story.ending);
+        clear_background(match ending {
+            Some(StoryEnding::Party) => Color::new(0.12, 0.04, 0.16, 1.0),
+            Some(StoryEnding::Repair) => Color::new(0.14, 0.09, 0.04, 1.0),
+            Some(StoryEnding::Home) => Color::new(0.03, 0.05, 0.12, 1.0),
+            None => BLACK,
+        });
 
-        let victory_text = format!("{} WINS!", self.winner);
+        let run = self.run.as_ref().filter(|_| ending.is_none());
+        let (victory_text, victory_color) = match (ending, &self.winner) {
+            (Some(StoryEnding::Party), _) => (StoryEnding::Party.title().to_string(), PINK),
+            (Some(StoryEnding::Repair), _) => (StoryEnding::Repair.title().to_string(), ORANGE),
+            (Some(StoryEnding::Home), _) => (StoryEnding::Home.title().to_string(), SKYBLUE),
+            _ if run.is_some_and(|run| run.report.cleared) => (tr("results.run_cleared"), GOLD),
+            _ if run.is_some() => (tr("results.run_over"), RED),
+            (None, Some(winner)) => (tr("results.wins").replace("{}", winner), GOLD),
+            (None, None) => (tr("results.draw"), WHITE),
+        };
         let victory_size = 80.0;
         let victory_dims = measure_text(&victory_text, None, victory_size as u16, 1.0);
         draw_text(
@@ -66,16 +482,36 @@ impl State for ResultsState {
             screen_width() * 0.5 - victory_dims.width * 0.5,
             150.0,
             victory_size,
-            GOLD,
+            victory_color,
         );
+        let banner_width = 600.0;
+        let banner_x = screen_width() * 0.5 - banner_width * 0.5;
+        render_account_banner(&self.account, banner_x, 168.0, banner_width);
+
+        if let Some(versus) = &self.versus {
+            versus.render();
+            return;
+        }
+        if let Some(run) = run {
+            run.render(self.display_timer);
+            return;
+        }
+
+        if let Some(story) = &self.story {
+            // How it went, beside the score
+            let color = Color::new(0.9, 0.9, 0.9, 0.8);
+            for (i, line) in story.epilogue.iter().enumerate() {
+                draw_text(line, 760.0, 300.0 + i as f32 * 32.0, 22.0, color);
+            }
+        }
 
         if self.display_timer > 1.0 {
-            draw_text("Base Score:", 300.0, 300.0, 30.0, WHITE);
+            draw_text(&tr("results.base_score"), 300.0, 300.0, 30.0, WHITE);
             draw_text(&format!("{:06}", self.score), 600.0, 300.0, 30.0, YELLOW);
         }
 
         if self.display_timer > 1.5 {
-            draw_text("Time Bonus:", 300.0, 350.0, 30.0, WHITE);
+            draw_text(&tr("results.time_bonus"), 300.0, 350.0, 30.0, WHITE);
             draw_text(
                 &format!("{:06}", self.time_bonus),
                 600.0,
@@ -86,7 +522,10 @@ impl State for ResultsState {
         }
 
         if self.display_timer > 2.0 {
-            draw_text("Combo Bonus:", 300.0, 400.0, 30.0, WHITE);
+            let label = ending.map_or_else(|| tr("results.combo_bonus"), |ending| {
+                ending.bonus_label().to_string()
+            });
+            draw_text(&label, 300.0, 400.0, 30.0, WHITE);
             draw_text(
                 &format!("{:06}", self.combo_bonus),
                 600.0,
@@ -98,7 +537,7 @@ impl State for ResultsState {
 
         if self.display_timer > 2.5 {
             draw_line(300.0, 450.0, 700.0, 450.0, 2.0, WHITE);
-            draw_text("TOTAL:", 300.0, 500.0, 40.0, WHITE);
+            draw_text(&tr("results.total"), 300.0, 500.0, 40.0, WHITE);
             draw_text(
                 &format!("{:06}", self.total_score),
                 600.0,
@@ -112,22 +551,28 @@ impl State for ResultsState {
             // Show stats
             let stats_y = 550.0;
             draw_text(
-                &format!("Waves Completed: {}", self.waves_completed),
+                &format!("{}: {}", tr("results.waves"), self.waves_completed),
                 300.0,
                 stats_y,
                 24.0,
                 Color::new(0.7, 0.9, 1.0, 1.0),
             );
             draw_text(
-                &format!("Enemies Defeated: {}", self.enemies_defeated),
+                &format!("{}: {}", tr("results.enemies"), self.enemies_defeated),
                 300.0,
                 stats_y + 35.0,
                 24.0,
                 Color::new(0.7, 0.9, 1.0, 1.0),
             );
 
+            // A cleared story run also shows what it earned
+            if let Some(run) = &self.run {
+                run.render_rewards(760.0, 520.0, Color::new(0.7, 0.9, 1.0, 1.0));
+                run.render_actions(650.0);
+                return;
+            }
             draw_text(
-                "Press SPACE to continue",
+                &tr("results.continue"),
                 screen_width() * 0.5 - 150.0,
                 650.0,
                 20.0,
@@ -137,6 +582,23 @@ impl State for ResultsState {
     }
 
     fn handle_input(&mut self) {
+        if let Some(versus) = &mut self.versus {
+            // Short grace period so the last hit of the match doesn't pick an action
+            if self.display_timer > 0.5 {
+                if let Some(next) = versus.handle_input() {
+                    self.transition_to = Some(next);
+                }
+            }
+            return;
+        }
+
+        if let Some(run) = &mut self.run {
+            if self.display_timer > 3.0 {
+                self.transition_to = run.handle_input();
+            }
+            return;
+        }
+
         if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
             if self.display_timer > 3.0 {
                 self.transition_to = Some(StateType::Menu);
@@ -148,3 +610,32 @@ impl State for ResultsState {
         self.transition_to
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(best_rank: StyleRank, cleared: bool, flawless: bool) -> RunReport {
+        RunReport {
+            cleared,
+            waves_cleared: 4,
+            max_combo: 12,
+            best_rank,
+            flawless,
+            tokens_earned: 300,
+            mastery_xp: 120.0,
+            account_xp: 30.0,
+            unlocks: Vec::new(),
+            retry: Some(StateType::Gameplay),
+            trial: None,
+        }
+    }
+
+    #[test]
+    fn clearing_and_going_unhurt_each_lift_the_grade() {
+        assert_eq!(run_grade(&report(StyleRank::B, false, false)), StyleRank::B);
+        assert_eq!(run_grade(&report(StyleRank::B, true, false)), StyleRank::A);
+        assert_eq!(run_grade(&report(StyleRank::B, true, true)), StyleRank::S);
+        assert_eq!(run_grade(&report(StyleRank::SS, true, true)), StyleRank::SSS);
+    }
+}
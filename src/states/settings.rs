@@ -1,88 +1,669 @@
-use crate::states::State;
+use crate::audio::mixer::BusVolumes;
+use crate::coop::input_handler::{self, BindAction, KeyRebind, KeyboardLayout};
+use crate::data::cloud::{self, CloudSync, SyncOutcome};
+use crate::data::config::{MIN_GAME_SPEED, RESOLUTIONS, TICK_RATES};
+use crate::data::localization::tr;
+use crate::data::{crash, GameConfig, SaveBundle};
+use crate::plugins::SettingsPage;
+use crate::states::{State, StateType};
+use crate::ui::UiLayout;
 use macroquad::prelude::*;
 
+/// Groups of built-in options, each on a page of its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Section {
+    Video,
+    Audio,
+    Controls,
+    Gameplay,
+    Accessibility,
+    SaveData,
+}
+
+const SECTIONS: [Section; 6] = [
+    Section::Video,
+    Section::Audio,
+    Section::Controls,
+    Section::Gameplay,
+    Section::Accessibility,
+    Section::SaveData,
+];
+
+impl Section {
+    fn title_key(self) -> &'static str {
+        match self {
+            Section::Video => "settings.video",
+            Section::Audio => "settings.audio",
+            Section::Controls => "settings.controls",
+            Section::Gameplay => "settings.gameplay",
+            Section::Accessibility => "settings.accessibility",
+            Section::SaveData => "settings.save_data",
+        }
+    }
+
+    fn settings(self) -> Vec<Setting> {
+        use Setting::*;
+
+        match self {
+            Section::Video => vec![
+                Fullscreen,
+                Resolution,
+                Vsync,
+                UiScaling,
+                PostProcessing,
+                FxIntensity,
+                HudLayout,
+            ],
+            Section::Audio => vec![
+                MasterVolume,
+                SfxVolume,
+                MusicVolume,
+                VoiceVolume,
+                AnnouncerVolume,
+                Announcer,
+            ],
+            Section::Controls => {
+                let mut settings = vec![RebindLayout];
+                settings.extend(BindAction::ALL.map(Rebind));
+                settings.extend([ResetBindings, ControlsReference]);
+                settings
+            }
+            Section::Gameplay => vec![TickRate, HardcoreStamina, UpdateChannel],
+            Section::Accessibility => vec![
+                Colorblind,
+                ScreenShake,
+                ScreenFlash,
+                GameFeel,
+                GameSpeed,
+                ComboAssist,
+                ComboAssistKey,
+            ],
+            Section::SaveData => vec![Export, Import, CloudSync],
+        }
+    }
+}
+
+/// One built-in option
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    Fullscreen,
+    Resolution,
+    Vsync,
+    UiScaling,
+    PostProcessing,
+    FxIntensity,
+    HudLayout,
+    MasterVolume,
+    SfxVolume,
+    MusicVolume,
+    VoiceVolume,
+    AnnouncerVolume,
+    Announcer,
+    /// Which side of the keyboard the rebind lines below it show
+    RebindLayout,
+    Rebind(BindAction),
+    ResetBindings,
+    ControlsReference,
+    TickRate,
+    HardcoreStamina,
+    /// Stable releases only, or pre-releases too
+    UpdateChannel,
+    Colorblind,
+    ScreenShake,
+    ScreenFlash,
+    GameFeel,
+    /// Single-player only, from `MIN_GAME_SPEED` up to full speed
+    GameSpeed,
+    ComboAssist,
+    ComboAssistKey,
+    Export,
+    Import,
+    CloudSync,
+}
+
+/// Page of settings on screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Page {
+    Main,
+    Section(Section),
+    /// A page registered by a plugin
+    Plugin(usize),
+}
+
+/// A line on the page, in the order they're listed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Row {
+    Open(Page),
+    Setting(Setting),
+    /// An entry of the plugin page on screen
+    Entry(usize),
+    Back,
+}
+
 pub struct SettingsState {
     selected_option: usize,
-    master_volume: f32,
-    sfx_volume: f32,
-    music_volume: f32,
-    fx_intensity: f32,
-    vsync_enabled: bool,
+    config: GameConfig,
+    /// Pages registered by plugins, listed after the built-in sections
+    pages: Vec<SettingsPage>,
+    page: Page,
+    /// Keyboard side the controls page rebinds
+    rebind_layout: KeyboardLayout,
+    /// Waiting for the key to put this setting on
+    listening: Option<Setting>,
+    /// Result of the last export or import, shown under the options
+    status: Option<(String, Color)>,
+    /// Cloud sync started from here, and the cloud's data while the player
+    /// picks a side of a conflict
+    cloud: Option<CloudSync>,
+    cloud_conflict: Option<SaveBundle>,
+    transition_to: Option<StateType>,
+    /// Opened from the pause menu; going back pops to the run
+    over_run: bool,
+    done: bool,
 }
 
 impl SettingsState {
     pub fn new() -> Self {
         Self {
             selected_option: 0,
-            master_volume: 1.0,
-            sfx_volume: 1.0,
-            music_volume: 0.8,
-            fx_intensity: 1.0,
-            vsync_enabled: true,
+            config: GameConfig::load(),
+            pages: crate::plugins::registry().settings_pages().to_vec(),
+            page: Page::Main,
+            rebind_layout: KeyboardLayout::Wasd,
+            listening: None,
+            status: None,
+            cloud: None,
+            cloud_conflict: None,
+            transition_to: None,
+            over_run: false,
+            done: false,
         }
     }
 }
 
+impl SettingsState {
+    fn rows(&self) -> Vec<Row> {
+        let mut rows: Vec<Row> = match self.page {
+            Page::Main => SECTIONS
+                .map(|section| Row::Open(Page::Section(section)))
+                .into_iter()
+                .chain((0..self.pages.len()).map(|page| Row::Open(Page::Plugin(page))))
+                .collect(),
+            Page::Section(section) => section.settings().into_iter().map(Row::Setting).collect(),
+            Page::Plugin(page) => (0..self.pages[page].entries.len()).map(Row::Entry).collect(),
+        };
+        rows.push(Row::Back);
+        rows
+    }
+
+    fn title(&self, page: Page) -> String {
+        match page {
+            Page::Main => tr("settings.title"),
+            Page::Section(section) => tr(section.title_key()),
+            Page::Plugin(page) => self.pages[page].title.to_uppercase(),
+        }
+    }
+
+    fn row_label(&self, row: Row) -> String {
+        match row {
+            Row::Open(page) => format!("{} >", self.title(page)),
+            Row::Setting(setting) => self.setting_label(setting),
+            Row::Entry(entry) => match self.page {
+                Page::Plugin(page) => (self.pages[page].entries[entry].label)(&self.config),
+                _ => String::new(),
+            },
+            Row::Back => tr("settings.back"),
+        }
+    }
+
+    fn setting_label(&self, setting: Setting) -> String {
+        let config = &self.config;
+        let switch = |on: bool| tr(if on { "settings.on" } else { "settings.off" });
+        let named = |key: &str, value: String| format!("{}: {}", tr(key), value);
+        if let Some(value) = self.slider_value(setting) {
+            let key = match setting {
+                Setting::MasterVolume => "settings.master_volume",
+                Setting::SfxVolume => "settings.sfx_volume",
+                Setting::MusicVolume => "settings.music_volume",
+                Setting::VoiceVolume => "settings.voice_volume",
+                Setting::AnnouncerVolume => "settings.announcer_volume",
+                Setting::FxIntensity => "settings.fx_intensity",
+                Setting::ScreenShake => "settings.screen_shake",
+                Setting::ScreenFlash => "settings.screen_flash",
+                Setting::GameSpeed => "settings.game_speed",
+                _ => "settings.game_feel",
+            };
+            return named(key, format!("{:.0}%", value * 100.0));
+        }
+
+        match setting {
+            Setting::Fullscreen => named("settings.fullscreen", switch(config.fullscreen)),
+            Setting::Resolution => {
+                let [width, height] = config.resolution;
+                named("settings.resolution", format!("{}x{}", width, height))
+            }
+            Setting::Vsync => named("settings.vsync", switch(config.vsync_enabled)),
+            Setting::UiScaling => {
+                named("settings.ui_scaling", config.ui_scale_mode.label().to_string())
+            }
+            Setting::PostProcessing => {
+                let value = if config.post_processing { "settings.on" } else { "settings.low_end" };
+                named("settings.post_processing", tr(value))
+            }
+            Setting::HudLayout => format!("{} >", tr("settings.hud_layout")),
+            Setting::Announcer => named("settings.announcer", switch(config.announcer_enabled)),
+            Setting::RebindLayout => {
+                named("settings.keyboard", self.rebind_layout.label().to_string())
+            }
+            Setting::Rebind(action) if self.listening == Some(setting) => {
+                format!("{}: {}", action.label(), tr("settings.press_key"))
+            }
+            Setting::Rebind(action) => {
+                let key = self.layout_bindings().key(action);
+                format!("{}: {}", action.label(), input_handler::key_name(key))
+            }
+            Setting::ResetBindings => tr("settings.reset_keys"),
+            Setting::ControlsReference => format!("{} >", tr("settings.controls_reference")),
+            Setting::TickRate => named("settings.tick_rate", format!("{} Hz", config.tick_rate)),
+            Setting::HardcoreStamina => {
+                named("settings.hardcore_stamina", switch(config.hardcore_stamina))
+            }
+            Setting::UpdateChannel => {
+                named("settings.update_channel", config.update_channel.label().to_string())
+            }
+            Setting::Colorblind => {
+                named("settings.colorblind", config.colorblind_mode.label().to_string())
+            }
+            Setting::ComboAssist => named("settings.combo_assist", switch(config.combo_assist)),
+            Setting::ComboAssistKey if self.listening == Some(setting) => {
+                named("settings.combo_assist_key", tr("settings.press_key"))
+            }
+            Setting::ComboAssistKey => {
+                named("settings.combo_assist_key", config.combo_assist_key.clone())
+            }
+            Setting::Export => tr("settings.export"),
+            Setting::Import => tr("settings.import"),
+            Setting::CloudSync if config.cloud_url.is_empty() => tr("settings.cloud_off"),
+            Setting::CloudSync => tr("settings.cloud_sync"),
+            _ => String::new(),
+        }
+    }
+
+    /// Where a slider is, 0 to 1; `None` for anything that isn't one
+    fn slider_value(&self, setting: Setting) -> Option<f32> {
+        let config = &self.config;
+        match setting {
+            Setting::MasterVolume => Some(config.master_volume),
+            Setting::SfxVolume => Some(config.sfx_volume),
+            Setting::MusicVolume => Some(config.music_volume),
+            Setting::VoiceVolume => Some(config.voice_volume),
+            Setting::AnnouncerVolume => Some(config.announcer_volume),
+            Setting::FxIntensity => Some(config.fx_intensity),
+            Setting::ScreenShake => Some(config.screen_shake),
+            Setting::ScreenFlash => Some(config.screen_flash),
+            Setting::GameFeel => Some(config.game_feel_intensity),
+            Setting::GameSpeed => Some(config.game_speed),
+            _ => None,
+        }
+    }
+
+    fn slider(config: &mut GameConfig, setting: Setting) -> Option<&mut f32> {
+        match setting {
+            Setting::MasterVolume => Some(&mut config.master_volume),
+            Setting::SfxVolume => Some(&mut config.sfx_volume),
+            Setting::MusicVolume => Some(&mut config.music_volume),
+            Setting::VoiceVolume => Some(&mut config.voice_volume),
+            Setting::AnnouncerVolume => Some(&mut config.announcer_volume),
+            Setting::FxIntensity => Some(&mut config.fx_intensity),
+            Setting::ScreenShake => Some(&mut config.screen_shake),
+            Setting::ScreenFlash => Some(&mut config.screen_flash),
+            Setting::GameFeel => Some(&mut config.game_feel_intensity),
+            Setting::GameSpeed => Some(&mut config.game_speed),
+            _ => None,
+        }
+    }
+
+    /// Slide the picked slider by `step`, heard and seen straight away
+    fn nudge(&mut self, setting: Setting, step: f32) {
+        let Some(value) = Self::slider(&mut self.config, setting) else {
+            return;
+        };
+        let (min, max) = Self::slider_range(setting);
+        *value = (*value + step).clamp(min, max);
+        crate::data::set_volumes(BusVolumes::from_config(&self.config));
+        crate::data::set_game_feel(self.config.game_feel_intensity);
+        crate::data::set_screen_shake(self.config.screen_shake);
+        crate::data::set_screen_flash(self.config.screen_flash);
+        crate::data::set_game_speed(self.config.game_speed);
+    }
+
+    fn slider_range(setting: Setting) -> (f32, f32) {
+        match setting {
+            Setting::GameSpeed => (MIN_GAME_SPEED, 1.0),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    fn layout_bindings(&self) -> input_handler::KeyboardBindings {
+        self.rebind_layout.bindings(&self.config.key_rebinds)
+    }
+
+    /// Put the setting being rebound on `key`
+    fn bind_key(&mut self, setting: Setting, key: KeyCode) {
+        if !input_handler::BINDABLE_KEYS.contains(&key) {
+            self.status = Some(("That key can't be bound".to_string(), ORANGE));
+            return;
+        }
+
+        let Setting::Rebind(action) = setting else {
+            self.config.combo_assist_key = input_handler::key_name(key);
+            crate::data::set_combo_assist_key(self.config.combo_assist_key());
+            self.status = None;
+            return;
+        };
+        let mut bindings = self.layout_bindings();
+        bindings.rebind(action, key);
+        let layout = self.rebind_layout;
+        self.config.key_rebinds.retain(|rebind| rebind.layout != layout);
+        self.config.key_rebinds.extend(KeyRebind::differences(layout, &bindings));
+        crate::data::set_key_rebinds(self.config.key_rebinds.clone());
+        self.status = None;
+    }
+
+    fn select_setting(&mut self, setting: Setting) {
+        // The HUD editor would end the run, and the run would save over
+        // anything loaded under it
+        let main_menu_only = matches!(
+            setting,
+            Setting::HudLayout | Setting::ControlsReference | Setting::Import | Setting::CloudSync
+        );
+        if main_menu_only && self.over_run {
+            let message = "Open this from the main menu, not mid-run";
+            self.status = Some((message.to_string(), ORANGE));
+            return;
+        }
+
+        match setting {
+            Setting::Fullscreen => {
+                self.config.fullscreen = !self.config.fullscreen;
+                self.config.apply_window();
+            }
+            Setting::Resolution => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|&size| size == self.config.resolution)
+                    .unwrap_or(0);
+                self.config.resolution = RESOLUTIONS[(current + 1) % RESOLUTIONS.len()];
+                self.config.apply_window();
+            }
+            Setting::Vsync => {
+                self.config.vsync_enabled = !self.config.vsync_enabled;
+                self.status = Some((tr("settings.next_launch"), YELLOW));
+            }
+            Setting::UiScaling => {
+                self.config.ui_scale_mode = self.config.ui_scale_mode.next();
+                crate::data::set_ui_scale_mode(self.config.ui_scale_mode);
+            }
+            Setting::PostProcessing => {
+                self.config.post_processing = !self.config.post_processing;
+                crate::data::set_post_processing(self.config.post_processing);
+            }
+            Setting::HudLayout => self.transition_to = Some(StateType::HudEditor),
+            Setting::Announcer => {
+                self.config.announcer_enabled = !self.config.announcer_enabled;
+                crate::data::set_volumes(BusVolumes::from_config(&self.config));
+            }
+            Setting::RebindLayout => self.rebind_layout = self.rebind_layout.next(),
+            Setting::Rebind(_) | Setting::ComboAssistKey => self.listening = Some(setting),
+            Setting::ResetBindings => {
+                let layout = self.rebind_layout;
+                self.config.key_rebinds.retain(|rebind| rebind.layout != layout);
+                crate::data::set_key_rebinds(self.config.key_rebinds.clone());
+            }
+            Setting::ControlsReference => self.transition_to = Some(StateType::Controls),
+            Setting::TickRate => self.cycle_tick_rate(),
+            Setting::HardcoreStamina => {
+                self.config.hardcore_stamina = !self.config.hardcore_stamina;
+                crate::data::set_hardcore_stamina(self.config.hardcore_stamina);
+            }
+            Setting::UpdateChannel => {
+                self.config.update_channel = self.config.update_channel.next();
+            }
+            Setting::Colorblind => {
+                self.config.colorblind_mode = self.config.colorblind_mode.next();
+                crate::data::palette::reload(&self.config);
+            }
+            Setting::ComboAssist => {
+                self.config.combo_assist = !self.config.combo_assist;
+                crate::data::set_combo_assist_key(self.config.combo_assist_key());
+            }
+            Setting::Export => self.export_save_data(),
+            Setting::Import => self.import_save_data(),
+            Setting::CloudSync => self.sync_cloud(),
+            // Sliders move with left and right
+            _ => {}
+        }
+    }
+
+    fn select_row(&mut self, row: Row) {
+        match row {
+            Row::Open(page) => {
+                self.page = page;
+                self.selected_option = 0;
+                self.status = None;
+            }
+            Row::Setting(setting) => self.select_setting(setting),
+            Row::Entry(entry) => {
+                if let Page::Plugin(page) = self.page {
+                    (self.pages[page].entries[entry].activate)(&mut self.config);
+                }
+            }
+            Row::Back => self.go_back(),
+        }
+    }
+
+    /// Up to the main page, or out of settings from there
+    fn go_back(&mut self) {
+        if self.page == Page::Main {
+            if self.over_run {
+                self.done = true;
+            } else {
+                self.transition_to = Some(StateType::Menu);
+            }
+            return;
+        }
+
+        let from = Row::Open(self.page);
+        self.page = Page::Main;
+        self.selected_option = self.rows().iter().position(|&row| row == from).unwrap_or(0);
+        self.status = None;
+    }
+
+    fn cycle_tick_rate(&mut self) {
+        let current = TICK_RATES
+            .iter()
+            .position(|&rate| rate == self.config.tick_rate)
+            .unwrap_or(0);
+        self.config.tick_rate = TICK_RATES[(current + 1) % TICK_RATES.len()];
+        crate::data::set_tick_rate(self.config.tick_rate);
+    }
+
+    fn export_save_data(&mut self) {
+        // Bundle what's on screen, not what was last written
+        if let Err(e) = self.config.save() {
+            crash::log(e.to_string());
+        }
+
+        self.status = Some(match SaveBundle::export() {
+            Ok(path) => (format!("Exported to {}", path.display()), GREEN),
+            Err(e) => (e, RED),
+        });
+    }
+
+    /// Import the newest bundle in the exports folder
+    fn sync_cloud(&mut self) {
+        if self.cloud.is_some() {
+            return;
+        }
+        if let Err(e) = self.config.save() {
+            crash::log(e.to_string());
+        }
+        self.cloud = CloudSync::start();
+        if self.cloud.is_some() {
+            self.status = Some(("Syncing with the cloud...".to_string(), LIGHTGRAY));
+        }
+    }
+
+    fn finish_cloud_sync(&mut self, result: Result<SyncOutcome, String>) {
+        self.status = Some(match result {
+            Ok(SyncOutcome::Downloaded(remote)) => self.keep_cloud(&remote),
+            Ok(SyncOutcome::Conflict { remote, .. }) => {
+                self.cloud_conflict = Some(remote);
+                let choice = "Both sides changed: L = Keep this computer's, C = Keep the cloud's";
+                (choice.to_string(), YELLOW)
+            }
+            Ok(outcome @ SyncOutcome::Queued(_)) => (outcome.describe(), YELLOW),
+            Ok(outcome) => (outcome.describe(), GREEN),
+            Err(e) => (format!("Cloud sync failed: {}", e), RED),
+        });
+    }
+
+    fn keep_cloud(&mut self, remote: &SaveBundle) -> (String, Color) {
+        match cloud::apply_download(remote) {
+            Ok(count) => {
+                self.config = GameConfig::load();
+                (format!("Downloaded {} files from the cloud", count), GREEN)
+            }
+            Err(e) => (format!("Cloud download failed: {}", e), RED),
+        }
+    }
+
+    fn import_save_data(&mut self) {
+        let Some(path) = SaveBundle::latest_export() else {
+            self.status = Some(("No save bundle found in the exports folder".to_string(), RED));
+            return;
+        };
+
+        self.status = Some(match SaveBundle::read(&path).and_then(|bundle| bundle.apply()) {
+            Ok(count) => {
+                self.config = GameConfig::load();
+                self.config.apply_globals();
+                (format!("Imported {} files from {}", count, path.display()), GREEN)
+            }
+            Err(e) => (e, RED),
+        });
+    }
+}
+
 impl State for SettingsState {
     fn enter(&mut self) {
         self.selected_option = 0;
+        self.page = Page::Main;
+        self.listening = None;
+        self.transition_to = None;
+        self.over_run = crate::data::take_over_run();
+        self.done = false;
+        self.status = None;
     }
 
-    fn exit(&mut self) {}
+    fn exit(&mut self) {
+        if let Err(e) = self.config.save() {
+            crash::log(e.to_string());
+        }
+    }
 
-    fn update(&mut self, _dt: f32) {}
+    fn update(&mut self, _dt: f32) {
+        if let Some(result) = self.cloud.as_ref().and_then(CloudSync::poll) {
+            self.cloud = None;
+            self.finish_cloud_sync(result);
+        }
+    }
 
     fn fixed_update(&mut self, _dt: f64) {}
 
     fn render(&mut self, _interpolation: f32) {
         clear_background(BLACK);
+        let ui = UiLayout::current();
+        ui.begin();
 
-        draw_text("SETTINGS", screen_width() * 0.5 - 100.0, 100.0, 50.0, WHITE);
+        let title = self.title(self.page);
+        let title_dims = measure_text(&title, None, 50, 1.0);
+        draw_text(&title, ui.width() * 0.5 - title_dims.width * 0.5, 100.0, 50.0, WHITE);
 
-        let options = vec![
-            format!("Master Volume: {:.0}%", self.master_volume * 100.0),
-            format!("SFX Volume: {:.0}%", self.sfx_volume * 100.0),
-            format!("Music Volume: {:.0}%", self.music_volume * 100.0),
-            format!("FX Intensity: {:.0}%", self.fx_intensity * 100.0),
-            format!("VSync: {}", if self.vsync_enabled { "ON" } else { "OFF" }),
-            "Back".to_string(),
-        ];
+        // Long pages like the controls close up to fit
+        let rows = self.rows();
+        let spacing = ((ui.height() - 340.0) / rows.len() as f32).min(50.0);
+        let size = (spacing * 0.5).clamp(20.0, 25.0);
 
-        for (i, option) in options.iter().enumerate() {
-            let y = 250.0 + i as f32 * 50.0;
+        for (i, &row) in rows.iter().enumerate() {
+            let y = 200.0 + i as f32 * spacing;
             let color = if i == self.selected_option {
                 YELLOW
             } else {
                 WHITE
             };
 
-            draw_text(option, 300.0, y, 25.0, color);
+            draw_text(&self.row_label(row), 300.0, y, size, color);
 
-            if i < 4 && i == self.selected_option {
+            let slider = match row {
+                Row::Setting(setting) => self.slider_value(setting),
+                _ => None,
+            };
+            if let (Some(value), true) = (slider, i == self.selected_option) {
                 let bar_width = 200.0;
-                let bar_x = 600.0;
-                draw_rectangle(
-                    bar_x,
-                    y - 20.0,
-                    bar_width,
-                    10.0,
-                    Color::new(0.3, 0.3, 0.3, 1.0),
-                );
-
-                let fill_width = bar_width
-                    * match i {
-                        0 => self.master_volume,
-                        1 => self.sfx_volume,
-                        2 => self.music_volume,
-                        3 => self.fx_intensity,
-                        _ => 0.0,
-                    };
-                draw_rectangle(bar_x, y - 20.0, fill_width, 10.0, YELLOW);
+                let bar_x = 800.0;
+                let (min, max) = match row {
+                    Row::Setting(setting) => Self::slider_range(setting),
+                    _ => (0.0, 1.0),
+                };
+                let fill = (value - min) / (max - min);
+                draw_rectangle(bar_x, y - 14.0, bar_width, 10.0, Color::new(0.3, 0.3, 0.3, 1.0));
+                draw_rectangle(bar_x, y - 14.0, bar_width * fill, 10.0, YELLOW);
             }
         }
+
+        let mut y = 200.0 + rows.len() as f32 * spacing + 20.0;
+        if let Some((message, color)) = &self.status {
+            draw_text(message, 300.0, y, 20.0, *color);
+            y += 28.0;
+        }
+        if self.page == Page::Section(Section::Controls) {
+            draw_text(&tr("settings.keys_hint"), 300.0, y, 18.0, GRAY);
+        }
+
+        ui.end();
     }
 
     fn handle_input(&mut self) {
+        if let Some(remote) = &self.cloud_conflict {
+            if is_key_pressed(KeyCode::L) {
+                self.cloud_conflict = None;
+                self.cloud = CloudSync::keep_local();
+                self.status = Some(("Uploading to the cloud...".to_string(), LIGHTGRAY));
+            } else if is_key_pressed(KeyCode::C) {
+                let remote = remote.clone();
+                self.cloud_conflict = None;
+                self.status = Some(self.keep_cloud(&remote));
+            }
+            return;
+        }
+
+        if let Some(setting) = self.listening {
+            if is_key_pressed(KeyCode::Escape) {
+                self.listening = None;
+            } else if let Some(key) = get_last_key_pressed() {
+                self.listening = None;
+                self.bind_key(setting, key);
+            }
+            return;
+        }
+
+        if self.handles_escape() && is_key_pressed(KeyCode::Escape) {
+            self.go_back();
+            return;
+        }
+
+        let rows = self.rows();
         if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
             if self.selected_option > 0 {
                 self.selected_option -= 1;
@@ -90,37 +671,53 @@ impl State for SettingsState {
         }
 
         if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
-            if self.selected_option < 5 {
+            if self.selected_option + 1 < rows.len() {
                 self.selected_option += 1;
             }
         }
 
-        if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
-            match self.selected_option {
-                0 => self.master_volume = (self.master_volume - 0.01).max(0.0),
-                1 => self.sfx_volume = (self.sfx_volume - 0.01).max(0.0),
-                2 => self.music_volume = (self.music_volume - 0.01).max(0.0),
-                3 => self.fx_intensity = (self.fx_intensity - 0.01).max(0.0),
-                _ => {}
+        let Some(&row) = rows.get(self.selected_option) else {
+            return;
+        };
+        if let Row::Setting(setting) = row {
+            if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+                self.nudge(setting, -0.01);
             }
-        }
-
-        if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
-            match self.selected_option {
-                0 => self.master_volume = (self.master_volume + 0.01).min(1.0),
-                1 => self.sfx_volume = (self.sfx_volume + 0.01).min(1.0),
-                2 => self.music_volume = (self.music_volume + 0.01).min(1.0),
-                3 => self.fx_intensity = (self.fx_intensity + 0.01).min(1.0),
-                _ => {}
+            if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+                self.nudge(setting, 0.01);
             }
         }
 
         if is_key_pressed(KeyCode::J) || is_key_pressed(KeyCode::Enter) {
-            match self.selected_option {
-                4 => self.vsync_enabled = !self.vsync_enabled,
-                5 => {}
-                _ => {}
-            }
+            self.select_row(row);
+        }
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+
+    fn should_pop(&self) -> bool {
+        self.done
+    }
+
+    /// Escape steps back a page; on the main page it leaves, which the
+    /// state manager does unless settings is open over a run
+    fn handles_escape(&self) -> bool {
+        self.over_run || self.page != Page::Main || self.listening.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_section_lists_its_own_settings() {
+        let listed: Vec<Setting> = SECTIONS.iter().flat_map(|s| s.settings()).collect();
+        for (i, setting) in listed.iter().enumerate() {
+            assert!(!listed[i + 1..].contains(setting), "{:?} is listed twice", setting);
         }
+        assert!(Section::Controls.settings().contains(&Setting::Rebind(BindAction::Back)));
     }
 }
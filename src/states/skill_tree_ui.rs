@@ -1,72 +1,405 @@
 use crate::states::{State, StateType};
 use crate::data::characters::{Character, CharacterId, CHARACTERS};
+use crate::data::ShopManager;
 use crate::progression::SkillTreeManager;
 use macroquad::prelude::*;
 
 // Re-export types from progression module for easier access
-use crate::progression::skill_tree::{SkillNode, SkillBranch};
+use crate::progression::skill_tree::{SkillNode, SkillTree, SkillType, TOKENS_PER_POINT};
+
+/// World-space gap between tiers, left to right
+const TIER_GAP: f32 = 190.0;
+/// World-space gap between nodes sharing a tier
+const SLOT_GAP: f32 = 110.0;
+/// Empty space between one branch's band and the next
+const BAND_GAP: f32 = 70.0;
+const NODE_RADIUS: f32 = 34.0;
+const MIN_ZOOM: f32 = 0.4;
+const MAX_ZOOM: f32 = 2.0;
+/// How much one wheel notch or +/- press zooms
+const ZOOM_STEP: f32 = 1.15;
+/// How quickly the camera settles on the selected node
+const FOLLOW_RATE: f32 = 8.0;
+
+const BRANCH_COLORS: [Color; 3] = [RED, BLUE, GREEN];
+
+/// Where a node sits on the graph. `branch` and `index` point back into the
+/// character's tree.
+struct NodeSpot {
+    id: String,
+    branch: usize,
+    index: usize,
+    position: Vec2,
+}
 
+/// The whole tree as one graph: each branch a band of nodes, tiers running
+/// left to right, with lines from every prerequisite to what it opens up.
+/// Drag or use the wheel to look around; the selection keeps the camera on
+/// itself until the graph is dragged.
 pub struct SkillTreeUIState {
     skill_tree_manager: SkillTreeManager,
+    shop: ShopManager,
     selected_character: usize,
     selected_character_id: CharacterId,
-    selected_branch: BranchSelection,
+    layout: Vec<NodeSpot>,
+    /// Index into `layout`
     selected_node: usize,
+    /// World point at the centre of the graph area
+    pan: Vec2,
+    zoom: f32,
+    /// Last mouse position while the graph is being dragged
+    drag: Option<Vec2>,
+    follow_selection: bool,
     transition_to: Option<StateType>,
     feedback_message: Option<(String, f32)>, // message and timer
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-enum BranchSelection {
-    Offense,
-    Defense,
-    Utility,
-}
-
 impl SkillTreeUIState {
     pub fn new() -> Self {
-        Self {
+        let mut state = Self {
             skill_tree_manager: SkillTreeManager::new(),
+            shop: ShopManager::load(),
             selected_character: 0,
             selected_character_id: CHARACTERS[0].id,
-            selected_branch: BranchSelection::Offense,
+            layout: Vec::new(),
             selected_node: 0,
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            drag: None,
+            follow_selection: true,
             transition_to: None,
             feedback_message: None,
+        };
+        state.rebuild_layout();
+        state
+    }
+
+    /// Lay out the selected character's tree and jump to its first node
+    fn rebuild_layout(&mut self) {
+        self.layout = self
+            .skill_tree_manager
+            .get_tree(self.selected_character_id)
+            .map(layout_tree)
+            .unwrap_or_default();
+        self.selected_node = 0;
+        self.follow_selection = true;
+        if let Some(spot) = self.layout.first() {
+            self.pan = spot.position;
         }
     }
 
-    fn get_current_branch(&self) -> Option<&SkillBranch> {
-        if let Some(tree) = self.skill_tree_manager.get_tree(self.selected_character_id) {
-            Some(match self.selected_branch {
-                BranchSelection::Offense => &tree.branch_offense,
-                BranchSelection::Defense => &tree.branch_defense,
-                BranchSelection::Utility => &tree.branch_utility,
+    fn node(&self, spot: &NodeSpot) -> Option<&SkillNode> {
+        let tree = self.skill_tree_manager.get_tree(self.selected_character_id)?;
+        tree.branches()[spot.branch].nodes.get(spot.index)
+    }
+
+    fn selected_spot(&self) -> Option<&NodeSpot> {
+        self.layout.get(self.selected_node)
+    }
+
+    /// Area the graph is drawn in, below the header and above the details
+    fn graph_area() -> Rect {
+        Rect::new(20.0, 90.0, screen_width() - 40.0, screen_height() - 290.0)
+    }
+
+    fn to_screen(&self, world: Vec2) -> Vec2 {
+        Self::graph_area().center() + (world - self.pan) * self.zoom
+    }
+
+    fn to_world(&self, screen: Vec2) -> Vec2 {
+        self.pan + (screen - Self::graph_area().center()) / self.zoom
+    }
+
+    fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    /// Move the selection to the nearest node lying towards `direction`,
+    /// favouring ones straight ahead over ones off to the side
+    fn step_selection(&mut self, direction: Vec2) {
+        let Some(from) = self.selected_spot().map(|spot| spot.position) else {
+            return;
+        };
+        let best = self
+            .layout
+            .iter()
+            .enumerate()
+            .filter_map(|(i, spot)| {
+                let offset = spot.position - from;
+                let ahead = offset.dot(direction);
+                let aside = offset.perp_dot(direction).abs();
+                (ahead > 0.0).then_some((i, ahead + aside * 2.0))
             })
-        } else {
-            None
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, _)) = best {
+            self.selected_node = i;
+            self.follow_selection = true;
         }
     }
 
-    fn get_current_branch_mut(&mut self) -> Option<&mut SkillBranch> {
-        if let Some(tree) = self.skill_tree_manager.get_tree_mut(self.selected_character_id) {
-            Some(match self.selected_branch {
-                BranchSelection::Offense => &mut tree.branch_offense,
-                BranchSelection::Defense => &mut tree.branch_defense,
-                BranchSelection::Utility => &mut tree.branch_utility,
-            })
-        } else {
-            None
+    fn switch_character(&mut self, step: isize) {
+        let count = CHARACTERS.len() as isize;
+        let index = self.selected_character as isize + step;
+        self.selected_character = index.rem_euclid(count) as usize;
+        self.selected_character_id = CHARACTERS[self.selected_character].id;
+        self.rebuild_layout();
+    }
+
+    fn buy_with_points(&mut self, id: &str) {
+        match self.skill_tree_manager.unlock_skill(self.selected_character_id, id) {
+            Ok(()) => {
+                self.show_feedback("Skill upgraded!".to_string());
+                self.save_tree();
+            }
+            Err(e) => self.show_feedback(e),
+        }
+    }
+
+    fn buy_with_tokens(&mut self, id: &str) {
+        let result = self.skill_tree_manager.unlock_skill_for_tokens(
+            self.selected_character_id,
+            id,
+            &mut self.shop,
+        );
+        match result {
+            Ok(tokens) => {
+                self.show_feedback(format!("Skill upgraded for {} Arc Tokens!", tokens));
+                if let Err(e) = self.shop.save() {
+                    self.show_feedback(e);
+                }
+                self.save_tree();
+            }
+            Err(e) => self.show_feedback(e),
+        }
+    }
+
+    fn save_tree(&mut self) {
+        if let Err(e) = self.skill_tree_manager.save() {
+            self.show_feedback(e);
         }
     }
 
     fn show_feedback(&mut self, message: String) {
         self.feedback_message = Some((message, 3.0));
     }
+
+    fn handle_mouse(&mut self) {
+        let (x, y) = mouse_position();
+        let cursor = Vec2::new(x, y);
+        let over_graph = Self::graph_area().contains(cursor);
+
+        if is_mouse_button_pressed(MouseButton::Left) && over_graph {
+            let world = self.to_world(cursor);
+            let clicked = self
+                .layout
+                .iter()
+                .position(|spot| spot.position.distance(world) <= NODE_RADIUS);
+            match clicked {
+                Some(i) => {
+                    self.selected_node = i;
+                    self.follow_selection = true;
+                }
+                None => self.drag = Some(cursor),
+            }
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            self.drag = None;
+        }
+        if let Some(last) = self.drag {
+            if cursor != last {
+                self.pan -= (cursor - last) / self.zoom;
+                self.follow_selection = false;
+            }
+            self.drag = Some(cursor);
+        }
+
+        let wheel = mouse_wheel().1;
+        if wheel != 0.0 && over_graph {
+            // Zoom about the cursor, so the point under it stays put
+            let before = self.to_world(cursor);
+            self.zoom_by(if wheel > 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP });
+            self.pan += before - self.to_world(cursor);
+            self.follow_selection = false;
+        }
+    }
+
+    fn render_graph(&self, tree: &SkillTree) {
+        let area = Self::graph_area();
+        draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.03, 0.03, 0.07, 1.0));
+
+        let branches = tree.branches();
+        let node_at = |spot: &NodeSpot| &branches[spot.branch].nodes[spot.index];
+
+        // Branch names at the head of each band
+        for (b, branch) in branches.iter().enumerate() {
+            let Some(top) = self
+                .layout
+                .iter()
+                .filter(|spot| spot.branch == b)
+                .map(|spot| spot.position.y)
+                .min_by(f32::total_cmp)
+            else {
+                continue;
+            };
+            let at = self.to_screen(Vec2::new(-TIER_GAP * 0.9, top));
+            if area.contains(at) {
+                draw_text(&branch.name, at.x, at.y, 24.0 * self.zoom.max(0.7), BRANCH_COLORS[b]);
+            }
+        }
+
+        // Edges first so the nodes cover their ends
+        for spot in &self.layout {
+            let node = node_at(spot);
+            let to = self.to_screen(spot.position);
+            for prereq in &node.prerequisites {
+                let Some(from_spot) = self.layout.iter().find(|other| &other.id == prereq) else {
+                    continue;
+                };
+                let from = self.to_screen(from_spot.position);
+                let color = if node_at(from_spot).current_level > 0 {
+                    Color::new(0.4, 0.9, 0.4, 0.9)
+                } else {
+                    Color::new(0.4, 0.4, 0.45, 0.7)
+                };
+                draw_line(from.x, from.y, to.x, to.y, 3.0 * self.zoom, color);
+            }
+        }
+
+        let radius = NODE_RADIUS * self.zoom;
+        for (i, spot) in self.layout.iter().enumerate() {
+            let node = node_at(spot);
+            let at = self.to_screen(spot.position);
+            let bounds = Rect::new(at.x - radius, at.y - radius, radius * 2.0, radius * 2.0);
+            if !area.overlaps(&bounds) {
+                continue;
+            }
+
+            let selected = i == self.selected_node;
+            let fill = if node.current_level >= node.max_level {
+                Color::new(0.35, 0.3, 0.1, 1.0)
+            } else if node.current_level > 0 {
+                Color::new(0.15, 0.3, 0.15, 1.0)
+            } else if node.unlocked {
+                Color::new(0.2, 0.2, 0.25, 1.0)
+            } else {
+                Color::new(0.08, 0.08, 0.1, 1.0)
+            };
+            draw_circle(at.x, at.y, radius, fill);
+            let ring = if selected {
+                YELLOW
+            } else if node.unlocked {
+                BRANCH_COLORS[spot.branch]
+            } else {
+                DARKGRAY
+            };
+            draw_circle_lines(at.x, at.y, radius, if selected { 4.0 } else { 2.0 }, ring);
+
+            let level = format!("{}/{}", node.current_level, node.max_level);
+            let size = 18.0 * self.zoom;
+            let dims = measure_text(&level, None, size as u16, 1.0);
+            let level_color = if node.current_level >= node.max_level { GOLD } else { WHITE };
+            draw_text(&level, at.x - dims.width * 0.5, at.y + size * 0.3, size, level_color);
+
+            // Names only once they're big enough to read
+            if self.zoom >= 0.7 {
+                let size = 15.0 * self.zoom;
+                let dims = measure_text(&node.name, None, size as u16, 1.0);
+                let name_color = if node.unlocked { LIGHTGRAY } else { GRAY };
+                let y = at.y + radius + size;
+                draw_text(&node.name, at.x - dims.width * 0.5, y, size, name_color);
+            }
+        }
+
+        draw_rectangle_lines(area.x, area.y, area.w, area.h, 2.0, YELLOW);
+    }
+
+    fn render_details(&self, node: &SkillNode) {
+        let sw = screen_width();
+        let sh = screen_height();
+        let (x, y, w, h) = (20.0, sh - 185.0, sw - 40.0, 140.0);
+        draw_rectangle(x, y, w, h, Color::new(0.1, 0.1, 0.15, 0.9));
+        draw_rectangle_lines(x, y, w, h, 2.0, YELLOW);
+
+        let detail_x = x + 15.0;
+        let heading = format!(
+            "{}  (Tier {}, level {}/{})",
+            node.name, node.tier, node.current_level, node.max_level
+        );
+        draw_text(&heading, detail_x, y + 28.0, 22.0, YELLOW);
+        draw_text(&node.description, detail_x, y + 55.0, 16.0, LIGHTGRAY);
+
+        let points = self.skill_tree_manager.get_available_points(self.selected_character_id);
+        let tokens = node.skill_point_cost * TOKENS_PER_POINT;
+        let (status, color) = if !node.unlocked {
+            (format!("LOCKED - Requires {}", self.prerequisite_names(node)), RED)
+        } else if node.current_level >= node.max_level {
+            ("MAXED".to_string(), GOLD)
+        } else {
+            (
+                format!(
+                    "ENTER: {} skill point(s)   T: {} Arc Tokens",
+                    node.skill_point_cost, tokens
+                ),
+                if points >= node.skill_point_cost || self.shop.currency() >= tokens {
+                    GREEN
+                } else {
+                    ORANGE
+                },
+            )
+        };
+        draw_text(&status, detail_x, y + 85.0, 18.0, color);
+        if let SkillType::CancelRoute(route) = node.skill_type {
+            let route = format!("New cancel route: {}", route.label());
+            draw_text(&route, detail_x, y + 112.0, 16.0, SKYBLUE);
+        }
+    }
+
+    fn prerequisite_names(&self, node: &SkillNode) -> String {
+        node.prerequisites
+            .iter()
+            .map(|id| {
+                self.layout
+                    .iter()
+                    .find(|spot| &spot.id == id)
+                    .and_then(|spot| self.node(spot))
+                    .map_or(id.clone(), |prereq| prereq.name.clone())
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Place every node of `tree`: x by tier, y by branch band and then by the
+/// node's slot among others of the same tier
+fn layout_tree(tree: &SkillTree) -> Vec<NodeSpot> {
+    let mut spots = Vec::new();
+    let mut band_top = 0.0;
+    for (b, branch) in tree.branches().iter().enumerate() {
+        let mut slots = std::collections::HashMap::new();
+        for (index, node) in branch.nodes.iter().enumerate() {
+            let slot = slots.entry(node.tier).or_insert(0usize);
+            spots.push(NodeSpot {
+                id: node.id.clone(),
+                branch: b,
+                index,
+                position: Vec2::new(
+                    node.tier.saturating_sub(1) as f32 * TIER_GAP,
+                    band_top + *slot as f32 * SLOT_GAP,
+                ),
+            });
+            *slot += 1;
+        }
+        let tallest = slots.values().copied().max().unwrap_or(1);
+        band_top += tallest as f32 * SLOT_GAP + BAND_GAP;
+    }
+    spots
 }
 
 impl State for SkillTreeUIState {
     fn enter(&mut self) {
+        self.skill_tree_manager = SkillTreeManager::load();
+        self.shop = ShopManager::load();
+        self.rebuild_layout();
         self.transition_to = None;
         self.feedback_message = None;
     }
@@ -81,6 +414,12 @@ impl State for SkillTreeUIState {
                 self.feedback_message = None;
             }
         }
+
+        if self.follow_selection {
+            if let Some(target) = self.selected_spot().map(|spot| spot.position) {
+                self.pan += (target - self.pan) * (1.0 - (-FOLLOW_RATE * dt).exp());
+            }
+        }
     }
 
     fn fixed_update(&mut self, _dt: f64) {}
@@ -95,259 +434,35 @@ impl State for SkillTreeUIState {
         let title = "SKILL TREE";
         let title_size = 50.0;
         let title_dims = measure_text(title, None, title_size as u16, 1.0);
-        draw_text(
-            title,
-            sw * 0.5 - title_dims.width * 0.5,
-            50.0,
-            title_size,
-            GOLD,
-        );
-
-        // Instructions
-        let instructions = "A/D=Change Character | W/S=Select Node | Q/E=Switch Branch | ENTER=Upgrade | ESC=Back";
-        let inst_size = 16.0;
-        let inst_dims = measure_text(instructions, None, inst_size as u16, 1.0);
-        draw_text(
-            instructions,
-            sw * 0.5 - inst_dims.width * 0.5,
-            sh - 30.0,
-            inst_size,
-            LIGHTGRAY,
-        );
-
-        // Character info section (left)
-        let char_panel_x = 20.0;
-        let char_panel_y = 100.0;
-        let char_panel_width = 250.0;
-
-        draw_rectangle_lines(char_panel_x, char_panel_y, char_panel_width, 400.0, 2.0, YELLOW);
+        draw_text(title, sw * 0.5 - title_dims.width * 0.5, 50.0, title_size, GOLD);
 
         let character = Character::get_by_id(self.selected_character_id);
-        draw_text("CHARACTER", char_panel_x + 10.0, char_panel_y + 30.0, 25.0, YELLOW);
-        draw_text(character.name, char_panel_x + 10.0, char_panel_y + 60.0, 30.0, WHITE);
-
-        // Character simple visual
-        let char_x = char_panel_x + char_panel_width * 0.5;
-        let char_y = char_panel_y + 150.0;
-        draw_circle(char_x, char_y, 30.0, SKYBLUE);
-        draw_rectangle(char_x - 20.0, char_y + 20.0, 40.0, 50.0, SKYBLUE);
-        draw_rectangle(char_x - 35.0, char_y + 30.0, 15.0, 40.0, SKYBLUE);
-        draw_rectangle(char_x + 20.0, char_y + 30.0, 15.0, 40.0, SKYBLUE);
-        draw_rectangle(char_x - 15.0, char_y + 70.0, 12.0, 45.0, SKYBLUE);
-        draw_rectangle(char_x + 3.0, char_y + 70.0, 12.0, 45.0, SKYBLUE);
-
-        // Skill points available
-        let available_points = self.skill_tree_manager.get_available_points(self.selected_character_id);
-        draw_text(
-            &format!("Skill Points: {}", available_points),
-            char_panel_x + 10.0,
-            char_panel_y + 270.0,
-            22.0,
-            if available_points > 0 { GREEN } else { GRAY },
-        );
-
-        // Total points info (just show it's a progression system)
+        draw_text(&format!("< {} >", character.name), 20.0, 50.0, 30.0, WHITE);
+        let points = self.skill_tree_manager.get_available_points(self.selected_character_id);
+        let wallet = format!("Skill Points: {}   Arc Tokens: {}", points, self.shop.currency());
+        let wallet_dims = measure_text(&wallet, None, 20, 1.0);
         draw_text(
-            "Upgrade skills to grow stronger!",
-            char_panel_x + 10.0,
-            char_panel_y + 300.0,
-            16.0,
-            LIGHTGRAY,
+            &wallet,
+            sw - wallet_dims.width - 20.0,
+            50.0,
+            20.0,
+            if points > 0 { GREEN } else { LIGHTGRAY },
         );
 
-        // Branch tabs (top center)
-        let tab_width = 200.0;
-        let tab_height = 50.0;
-        let tab_spacing = 10.0;
-        let tabs_start_x = sw * 0.5 - (tab_width * 1.5 + tab_spacing);
-        let tabs_y = 100.0;
-
-        let branches = [
-            (BranchSelection::Offense, "OFFENSE", RED),
-            (BranchSelection::Defense, "DEFENSE", BLUE),
-            (BranchSelection::Utility, "UTILITY", GREEN),
-        ];
-
-        for (i, (branch_type, name, color)) in branches.iter().enumerate() {
-            let tab_x = tabs_start_x + i as f32 * (tab_width + tab_spacing);
-            let is_selected = *branch_type == self.selected_branch;
-
-            let bg_color = if is_selected {
-                Color::new(color.r, color.g, color.b, 0.3)
-            } else {
-                Color::new(0.1, 0.1, 0.1, 0.5)
-            };
-
-            draw_rectangle(tab_x, tabs_y, tab_width, tab_height, bg_color);
-            draw_rectangle_lines(
-                tab_x,
-                tabs_y,
-                tab_width,
-                tab_height,
-                if is_selected { 3.0 } else { 1.0 },
-                *color,
-            );
-
-            let text_dims = measure_text(name, None, 20, 1.0);
-            draw_text(
-                name,
-                tab_x + tab_width * 0.5 - text_dims.width * 0.5,
-                tabs_y + 32.0,
-                20.0,
-                if is_selected { WHITE } else { GRAY },
-            );
+        if let Some(tree) = self.skill_tree_manager.get_tree(self.selected_character_id) {
+            self.render_graph(tree);
         }
-
-        // Skill tree panel (center/right)
-        let tree_panel_x = char_panel_x + char_panel_width + 20.0;
-        let tree_panel_y = tabs_y + tab_height + 20.0;
-        let tree_panel_width = sw - tree_panel_x - 20.0;
-        let tree_panel_height = sh - tree_panel_y - 80.0;
-
-        draw_rectangle_lines(tree_panel_x, tree_panel_y, tree_panel_width, tree_panel_height, 2.0, match self.selected_branch {
-            BranchSelection::Offense => RED,
-            BranchSelection::Defense => BLUE,
-            BranchSelection::Utility => GREEN,
-        });
-
-        // Render skill nodes
-        if let Some(branch) = self.get_current_branch() {
-            // Branch name and description
-            draw_text(&branch.name, tree_panel_x + 20.0, tree_panel_y + 30.0, 28.0, YELLOW);
-            draw_text(&branch.description, tree_panel_x + 20.0, tree_panel_y + 55.0, 16.0, LIGHTGRAY);
-
-            // Render nodes in a grid
-            let node_size = 100.0;
-            let node_spacing = 30.0;
-            let nodes_start_x = tree_panel_x + 50.0;
-            let nodes_start_y = tree_panel_y + 100.0;
-
-            for (i, node) in branch.nodes.iter().enumerate() {
-                let col = i % 4;
-                let row = i / 4;
-                let node_x = nodes_start_x + col as f32 * (node_size + node_spacing);
-                let node_y = nodes_start_y + row as f32 * (node_size + node_spacing);
-
-                // Node background
-                let node_color = if i == self.selected_node {
-                    Color::new(0.3, 0.3, 0.4, 1.0)
-                } else if node.unlocked && node.current_level > 0 {
-                    Color::new(0.2, 0.3, 0.2, 1.0)
-                } else if node.unlocked {
-                    Color::new(0.2, 0.2, 0.2, 1.0)
-                } else {
-                    Color::new(0.1, 0.1, 0.1, 0.8)
-                };
-
-                draw_rectangle(node_x, node_y, node_size, node_size, node_color);
-
-                let border_color = if i == self.selected_node {
-                    YELLOW
-                } else if node.unlocked && node.current_level > 0 {
-                    GREEN
-                } else if node.unlocked {
-                    WHITE
-                } else {
-                    DARKGRAY
-                };
-
-                draw_rectangle_lines(node_x, node_y, node_size, node_size, 2.0, border_color);
-
-                // Node tier indicator
-                draw_text(
-                    &format!("T{}", node.tier),
-                    node_x + 5.0,
-                    node_y + 18.0,
-                    14.0,
-                    GRAY,
-                );
-
-                // Node name (truncate if too long)
-                let name_display = if node.name.len() > 12 {
-                    format!("{}...", &node.name[..9])
-                } else {
-                    node.name.clone()
-                };
-                draw_text(&name_display, node_x + 5.0, node_y + 42.0, 14.0, WHITE);
-
-                // Level indicator
-                let level_text = format!("{}/{}", node.current_level, node.max_level);
-                draw_text(&level_text, node_x + 5.0, node_y + 62.0, 16.0, if node.current_level == node.max_level { GOLD } else { YELLOW });
-
-                // Cost
-                if node.current_level < node.max_level {
-                    draw_text(
-                        &format!("Cost: {}", node.skill_point_cost),
-                        node_x + 5.0,
-                        node_y + 82.0,
-                        12.0,
-                        if available_points >= node.skill_point_cost { GREEN } else { RED },
-                    );
-                } else {
-                    draw_text("MAX", node_x + 30.0, node_y + 82.0, 14.0, GOLD);
-                }
-            }
-
-            // Selected node details panel
-            if let Some(node) = branch.nodes.get(self.selected_node) {
-                let detail_panel_y = tree_panel_y + tree_panel_height - 180.0;
-                draw_rectangle(
-                    tree_panel_x + 20.0,
-                    detail_panel_y,
-                    tree_panel_width - 40.0,
-                    160.0,
-                    Color::new(0.1, 0.1, 0.15, 0.9),
-                );
-                draw_rectangle_lines(
-                    tree_panel_x + 20.0,
-                    detail_panel_y,
-                    tree_panel_width - 40.0,
-                    160.0,
-                    2.0,
-                    YELLOW,
-                );
-
-                let detail_x = tree_panel_x + 30.0;
-                let mut detail_y = detail_panel_y + 25.0;
-
-                draw_text(&node.name, detail_x, detail_y, 22.0, YELLOW);
-                detail_y += 30.0;
-
-                draw_text(&node.description, detail_x, detail_y, 16.0, LIGHTGRAY);
-                detail_y += 25.0;
-
-                // Status
-                let status_text = if !node.unlocked {
-                    "LOCKED - Requires prerequisites"
-                } else if node.current_level >= node.max_level {
-                    "MAXED"
-                } else if available_points < node.skill_point_cost {
-                    "Need more skill points"
-                } else {
-                    "Press ENTER to upgrade"
-                };
-
-                let status_color = if !node.unlocked {
-                    RED
-                } else if node.current_level >= node.max_level {
-                    GOLD
-                } else if available_points >= node.skill_point_cost {
-                    GREEN
-                } else {
-                    ORANGE
-                };
-
-                draw_text(status_text, detail_x, detail_y, 18.0, status_color);
-                detail_y += 25.0;
-
-                // Prerequisites
-                if !node.prerequisites.is_empty() && !node.unlocked {
-                    let prereq_text = format!("Requires: {:?}", node.prerequisites);
-                    draw_text(&prereq_text[..prereq_text.len().min(60)], detail_x, detail_y, 14.0, RED);
-                }
-            }
+        if let Some(node) = self.selected_spot().and_then(|spot| self.node(spot)) {
+            self.render_details(node);
         }
 
+        // Instructions
+        let instructions = "Q/E=Change Character | WASD/Arrows=Select | Drag=Pan | \
+                            Wheel/+/-=Zoom | ENTER=Spend Points | T=Spend Tokens | ESC=Back";
+        let inst_size = 16.0;
+        let inst_dims = measure_text(instructions, None, inst_size as u16, 1.0);
+        draw_text(instructions, sw * 0.5 - inst_dims.width * 0.5, sh - 20.0, inst_size, LIGHTGRAY);
+
         // Feedback message
         if let Some((ref message, timer)) = self.feedback_message {
             let alpha = (timer / 3.0).min(1.0);
@@ -356,7 +471,7 @@ impl State for SkillTreeUIState {
             draw_text(
                 message,
                 sw * 0.5 - msg_dims.width * 0.5,
-                sh * 0.5 - 100.0,
+                80.0,
                 msg_size,
                 Color::new(1.0, 1.0, 0.0, alpha),
             );
@@ -364,71 +479,40 @@ impl State for SkillTreeUIState {
     }
 
     fn handle_input(&mut self) {
-        // Character selection
-        if is_key_pressed(KeyCode::A) || is_key_pressed(KeyCode::Left) {
-            if self.selected_character > 0 {
-                self.selected_character -= 1;
-            } else {
-                self.selected_character = CHARACTERS.len() - 1;
-            }
-            self.selected_character_id = CHARACTERS[self.selected_character].id;
-            self.selected_node = 0;
-        }
-
-        if is_key_pressed(KeyCode::D) || is_key_pressed(KeyCode::Right) {
-            self.selected_character = (self.selected_character + 1) % CHARACTERS.len();
-            self.selected_character_id = CHARACTERS[self.selected_character].id;
-            self.selected_node = 0;
-        }
-
-        // Branch selection
         if is_key_pressed(KeyCode::Q) {
-            self.selected_branch = match self.selected_branch {
-                BranchSelection::Offense => BranchSelection::Utility,
-                BranchSelection::Defense => BranchSelection::Offense,
-                BranchSelection::Utility => BranchSelection::Defense,
-            };
-            self.selected_node = 0;
+            self.switch_character(-1);
         }
-
         if is_key_pressed(KeyCode::E) {
-            self.selected_branch = match self.selected_branch {
-                BranchSelection::Offense => BranchSelection::Defense,
-                BranchSelection::Defense => BranchSelection::Utility,
-                BranchSelection::Utility => BranchSelection::Offense,
-            };
-            self.selected_node = 0;
+            self.switch_character(1);
         }
 
-        // Node selection - get branch length first to avoid borrow issues
-        let branch_len = self.get_current_branch().map(|b| b.nodes.len()).unwrap_or(0);
-
-        if branch_len > 0 {
-            if is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up) {
-                if self.selected_node >= 4 {
-                    self.selected_node -= 4;
-                }
+        let steps = [
+            ([KeyCode::A, KeyCode::Left], Vec2::NEG_X),
+            ([KeyCode::D, KeyCode::Right], Vec2::X),
+            ([KeyCode::W, KeyCode::Up], Vec2::NEG_Y),
+            ([KeyCode::S, KeyCode::Down], Vec2::Y),
+        ];
+        for (keys, direction) in steps {
+            if keys.iter().any(|key| is_key_pressed(*key)) {
+                self.step_selection(direction);
             }
+        }
 
-            if is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down) {
-                if self.selected_node + 4 < branch_len {
-                    self.selected_node += 4;
-                }
-            }
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            self.zoom_by(ZOOM_STEP);
+        }
+        if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            self.zoom_by(1.0 / ZOOM_STEP);
+        }
+        self.handle_mouse();
 
-            // Upgrade node - get skill ID first to avoid borrow issues
+        let skill_id = self.selected_spot().map(|spot| spot.id.clone());
+        if let Some(id) = skill_id {
             if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::J) {
-                let skill_id = self.get_current_branch()
-                    .and_then(|b| b.nodes.get(self.selected_node))
-                    .map(|n| n.id.clone());
-
-                if let Some(id) = skill_id {
-                    if self.skill_tree_manager.upgrade_skill(self.selected_character_id, &id) {
-                        self.show_feedback("Skill upgraded!".to_string());
-                    } else {
-                        self.show_feedback("Cannot upgrade skill".to_string());
-                    }
-                }
+                self.buy_with_points(&id);
+            }
+            if is_key_pressed(KeyCode::T) {
+                self.buy_with_tokens(&id);
             }
         }
 
@@ -437,10 +521,11 @@ impl State for SkillTreeUIState {
             self.transition_to = Some(StateType::Menu);
         }
 
-        // Give test skill points (for testing)
-        if is_key_pressed(KeyCode::P) {
+        // Give test skill points
+        if is_key_pressed(KeyCode::P) && crate::data::get_debug_controls() {
             self.skill_tree_manager.add_skill_points(self.selected_character_id, 5);
             self.show_feedback("Added 5 skill points for testing!".to_string());
+            self.save_tree();
         }
     }
 
@@ -1,98 +1,840 @@
-use crate::states::State;
+use crate::combat::character_movesets::{FrameDataCalculator, MoveData};
+use crate::combat::hitbox::{AttackHeight, BlockStance};
+use crate::combat::inputs::{InputManager, BUTTON_LABELS};
+use crate::combat::integration::{ActionType, DummyBehavior};
+use crate::combat::{CharacterCombatState, CombatIntegrationManager, MoveId};
+use crate::coop::input_handler::{self, BindAction, KeyboardLayout, ReboundKeys};
+use crate::data::characters::{Character, CharacterId};
+use crate::ecs::CharacterType;
+use crate::progression::roster::{Roster, UNLOCK_COST};
+use crate::states::{State, StateType};
+use crate::ui::input_display;
 use macroquad::prelude::*;
 
+const PLAYER_ID: u32 = 1;
+const DUMMY_ID: u32 = 2;
+const PLAYER_START: Vec2 = Vec2::new(400.0, 500.0);
+const DUMMY_START: Vec2 = Vec2::new(800.0, 500.0);
+
+/// Keizer Bom Taha is the only character with frame data so far, so both
+/// sides of the training room use his moveset
+const TRAINING_CHARACTER: CharacterType = CharacterType::KeizerBomTaha;
+/// Who the player is in the room, trialled for free when still locked
+const TRAINING_FIGHTER: CharacterId = CharacterId::KeizerBomTaha;
+
+const MOVE_KEYS: [(BindAction, MoveId); 3] = [
+    (BindAction::LightAttack, MoveId::MilitaryStrike),
+    (BindAction::HeavyAttack, MoveId::BayonetThrust),
+    (BindAction::SpecialAttack, MoveId::CommanderKick),
+];
+
+/// The dummy punishes with its fastest button
+const COUNTER_MOVE: MoveId = MoveId::MilitaryStrike;
+
+/// Behaviors T cycles through
+const BEHAVIORS: [DummyBehavior; 7] = [
+    DummyBehavior::Idle,
+    DummyBehavior::Block,
+    DummyBehavior::BlockFirstHit,
+    DummyBehavior::RandomBlock,
+    DummyBehavior::Counterattack,
+    DummyBehavior::Record,
+    DummyBehavior::Playback,
+];
+
+/// Ticks the dummy has to be left alone before "block after first hit" lets
+/// the next hit through again
+const GUARD_RESET_TICKS: u32 = 60;
+
+/// Ticks between the end of a recording and the start of its next loop
+const PLAYBACK_GAP: u64 = 30;
+
+/// Rows of the input history column
+const INPUT_ROWS: usize = 12;
+
 pub struct TrainingState {
-    dummy_hp: f32,
-    dummy_pos: Vec2,
-    player_pos: Vec2,
-    input_history: Vec<String>,
+    combat: CombatIntegrationManager,
+    input_manager: InputManager,
+    /// P1's keys, with the Controls page's rebinds
+    keys: ReboundKeys,
+    /// Simulation ticks since the room opened
+    frame: u64,
+    /// Ticks since the dummy was last hit or blocked
+    dummy_idle_ticks: u32,
+    /// Dummy got hit or blocked and should punish the moment it can act
+    counter_pending: bool,
+    record_start: u64,
+    playback_frame: u64,
+    /// Guard the recording has the dummy holding
+    playback_guard: bool,
+    last_exchange: Option<Exchange>,
+    guard_drill: GuardDrill,
+    player_guard: Option<BlockStance>,
+    roster: Roster,
+    transition_to: Option<StateType>,
+}
+
+/// The last attack that connected, for the frame data readout
+struct Exchange {
+    name: &'static str,
+    by_player: bool,
+    blocked: bool,
+    startup: u32,
+    /// Attacker's frame advantage afterwards
+    advantage: i32,
+}
+
+/// Dummy that cycles mid/overhead/low attacks so the player can practice blocking
+struct GuardDrill {
+    enabled: bool,
+    timer: f32,
+    sequence_index: usize,
+    incoming: Option<AttackHeight>,
+    last_result: Option<(AttackHeight, bool)>,
+    result_timer: f32,
+    blocked: u32,
+    missed: u32,
+}
+
+const DRILL_SEQUENCE: [AttackHeight; 6] = [
+    AttackHeight::Mid,
+    AttackHeight::Overhead,
+    AttackHeight::Low,
+    AttackHeight::Low,
+    AttackHeight::Overhead,
+    AttackHeight::Mid,
+];
+const DRILL_INTERVAL: f32 = 1.6;
+const DRILL_TELEGRAPH: f32 = 0.6;
+
+impl GuardDrill {
+    fn new() -> Self {
+        Self {
+            enabled: true,
+            timer: DRILL_INTERVAL,
+            sequence_index: 0,
+            incoming: None,
+            last_result: None,
+            result_timer: 0.0,
+            blocked: 0,
+            missed: 0,
+        }
+    }
+
+    fn update(&mut self, dt: f32, guard: Option<BlockStance>) {
+        if self.result_timer > 0.0 {
+            self.result_timer -= dt;
+        }
+
+        if !self.enabled {
+            return;
+        }
+
+        self.timer -= dt;
+
+        if self.incoming.is_none() && self.timer <= DRILL_TELEGRAPH {
+            self.incoming = Some(DRILL_SEQUENCE[self.sequence_index % DRILL_SEQUENCE.len()]);
+        }
+
+        if self.timer <= 0.0 {
+            if let Some(height) = self.incoming.take() {
+                let blocked = guard.is_some_and(|stance| height.is_blocked_by(stance));
+                if blocked {
+                    self.blocked += 1;
+                } else {
+                    self.missed += 1;
+                }
+                self.last_result = Some((height, blocked));
+                self.result_timer = 1.0;
+            }
+
+            self.sequence_index += 1;
+            self.timer = DRILL_INTERVAL;
+        }
+    }
+
+    fn hint(height: AttackHeight) -> &'static str {
+        match height.required_stance() {
+            None => "Block either way (Shift)",
+            Some(BlockStance::Standing) => "Block STANDING (Shift)",
+            Some(BlockStance::Crouching) => "Block CROUCHING (Shift + S)",
+        }
+    }
 }
 
 impl TrainingState {
     pub fn new() -> Self {
-        Self {
-            dummy_hp: 100.0,
-            dummy_pos: Vec2::new(800.0, 500.0),
-            player_pos: Vec2::new(400.0, 500.0),
-            input_history: Vec::new(),
+        let mut combat = CombatIntegrationManager::new();
+        combat.frame_data_display.enabled = true;
+        combat.training_mode.enabled = true;
+
+        let mut state = Self {
+            combat,
+            input_manager: InputManager::new(),
+            keys: ReboundKeys::new(KeyboardLayout::Wasd),
+            frame: 0,
+            dummy_idle_ticks: GUARD_RESET_TICKS,
+            counter_pending: false,
+            record_start: 0,
+            playback_frame: 0,
+            playback_guard: false,
+            last_exchange: None,
+            guard_drill: GuardDrill::new(),
+            player_guard: None,
+            roster: Roster::load(),
+            transition_to: None,
+        };
+        state.reset_fighters();
+        state
+    }
+
+    fn reset_fighters(&mut self) {
+        self.combat.register_character(PLAYER_ID, TRAINING_CHARACTER, PLAYER_START);
+        self.combat.register_character(DUMMY_ID, TRAINING_CHARACTER, DUMMY_START);
+        if let Some(dummy) = self.fighter_mut(DUMMY_ID) {
+            dummy.facing_right = false;
+        }
+        self.dummy_idle_ticks = GUARD_RESET_TICKS;
+        self.counter_pending = false;
+        self.playback_guard = false;
+        self.last_exchange = None;
+    }
+
+    fn fighter(&self, id: u32) -> Option<&CharacterCombatState> {
+        self.combat.character_states.get(&id)
+    }
+
+    fn fighter_mut(&mut self, id: u32) -> Option<&mut CharacterCombatState> {
+        self.combat.character_states.get_mut(&id)
+    }
+
+    fn position(&self, id: u32) -> Vec2 {
+        self.fighter(id).map(|f| f.position).unwrap_or_default()
+    }
+
+    fn move_data(&self, move_id: MoveId) -> Option<MoveData> {
+        self.combat
+            .character_movesets
+            .get(&TRAINING_CHARACTER)?
+            .get_move(move_id)
+            .cloned()
+    }
+
+    fn behavior(&self) -> DummyBehavior {
+        self.combat.training_mode.dummy_behavior
+    }
+
+    fn cycle_behavior(&mut self) {
+        let index = BEHAVIORS.iter().position(|b| *b == self.behavior()).unwrap_or(0);
+        let behavior = BEHAVIORS[(index + 1) % BEHAVIORS.len()];
+
+        let training = &mut self.combat.training_mode;
+        training.dummy_behavior = behavior;
+        training.record_mode = behavior == DummyBehavior::Record;
+        training.playback_mode = false;
+        match behavior {
+            DummyBehavior::Record => {
+                training.recorded_actions.clear();
+                self.record_start = self.frame;
+            }
+            DummyBehavior::Playback => {
+                training.start_playback();
+                self.playback_frame = 0;
+            }
+            _ => {}
+        }
+        self.playback_guard = false;
+        self.counter_pending = false;
+    }
+
+    fn can_act(fighter: &CharacterCombatState) -> bool {
+        fighter.current_move.is_none()
+            && fighter.hitstun_remaining == 0
+            && fighter.blockstun_remaining == 0
+    }
+
+    fn start_move(&mut self, id: u32, move_id: MoveId) {
+        let frame = self.frame;
+        let Some(fighter) = self.fighter_mut(id) else {
+            return;
+        };
+        if !Self::can_act(fighter) {
+            return;
+        }
+        fighter.current_move = Some(move_id);
+        fighter.move_frame = 0;
+        fighter.blocking = false;
+        self.combat.spawn_swing_vfx(id, move_id);
+
+        if id == PLAYER_ID {
+            self.combat
+                .frame_data_display
+                .record_input(frame, move_id, TRAINING_CHARACTER);
+            self.combat
+                .training_mode
+                .record_action(frame - self.record_start, ActionType::Move(move_id));
+        }
+    }
+
+    /// One simulation frame: advance moves and stun, let the dummy act, then
+    /// check both sides' attacks
+    fn tick(&mut self, dt: f32) {
+        self.frame += 1;
+        self.combat.update(dt);
+        self.input_manager.record_frame(self.keys.current());
+
+        for id in [PLAYER_ID, DUMMY_ID] {
+            let total = self
+                .fighter(id)
+                .and_then(|f| f.current_move)
+                .and_then(|move_id| self.move_data(move_id))
+                .map(|m| m.startup_frames + m.active_frames + m.recovery_frames);
+            if let (Some(total), Some(fighter)) = (total, self.fighter_mut(id)) {
+                if fighter.move_frame >= total {
+                    fighter.current_move = None;
+                    fighter.move_frame = 0;
+                }
+            }
+        }
+
+        self.update_dummy();
+        self.resolve_attack(PLAYER_ID, DUMMY_ID);
+        self.resolve_attack(DUMMY_ID, PLAYER_ID);
+
+        // Health refills once a combo is over
+        if self.combat.training_mode.infinite_health {
+            for fighter in self.combat.character_states.values_mut() {
+                if fighter.hitstun_remaining == 0 {
+                    fighter.health = fighter.max_health;
+                }
+            }
+        }
+    }
+
+    fn update_dummy(&mut self) {
+        self.dummy_idle_ticks = self.dummy_idle_ticks.saturating_add(1);
+        let behavior = self.behavior();
+
+        if behavior == DummyBehavior::Playback {
+            let frame = self.playback_frame;
+            while let Some(action) = self.combat.training_mode.get_playback_action(frame) {
+                match action {
+                    ActionType::Move(move_id) => self.start_move(DUMMY_ID, move_id),
+                    ActionType::Block => self.playback_guard = true,
+                    ActionType::Idle => self.playback_guard = false,
+                    _ => {}
+                }
+            }
+            self.playback_frame += 1;
+
+            let training = &mut self.combat.training_mode;
+            let last_frame = training.recorded_actions.last().map_or(0, |a| a.frame);
+            if training.playback_index >= training.recorded_actions.len()
+                && self.playback_frame > last_frame + PLAYBACK_GAP
+            {
+                training.start_playback();
+                self.playback_frame = 0;
+                self.playback_guard = false;
+            }
+        }
+
+        let can_punish = self.fighter(DUMMY_ID).is_some_and(Self::can_act);
+        if behavior == DummyBehavior::Counterattack && self.counter_pending && can_punish {
+            self.counter_pending = false;
+            self.start_move(DUMMY_ID, COUNTER_MOVE);
+        }
+
+        // Random blocks are rolled per attack in `blocks`
+        let blocking = match behavior {
+            DummyBehavior::Block | DummyBehavior::Counterattack => true,
+            DummyBehavior::BlockFirstHit => self.dummy_idle_ticks < GUARD_RESET_TICKS,
+            DummyBehavior::Playback => self.playback_guard,
+            _ => false,
+        };
+        if let Some(dummy) = self.fighter_mut(DUMMY_ID) {
+            dummy.blocking =
+                blocking && dummy.hitstun_remaining == 0 && dummy.current_move.is_none();
+        }
+    }
+
+    fn blocks(
+        &self,
+        defender_id: u32,
+        defender: &CharacterCombatState,
+        height: AttackHeight,
+    ) -> bool {
+        if defender_id == PLAYER_ID {
+            return self.player_guard.is_some_and(|stance| height.is_blocked_by(stance));
+        }
+
+        // The dummy always picks the right guard height
+        let guarding = defender.hitstun_remaining == 0 && defender.current_move.is_none();
+        defender.blocking
+            || (guarding
+                && self.behavior() == DummyBehavior::RandomBlock
+                && rand::gen_range(0, 2) == 0)
+    }
+
+    /// Attacks connect on their first active frame
+    fn resolve_attack(&mut self, attacker_id: u32, defender_id: u32) {
+        let (Some(attacker), Some(defender)) = (
+            self.fighter(attacker_id).cloned(),
+            self.fighter(defender_id).cloned(),
+        ) else {
+            return;
+        };
+        let Some(move_id) = attacker.current_move else {
+            return;
+        };
+        let Some(move_data) = self.move_data(move_id) else {
+            return;
+        };
+        if attacker.move_frame != move_data.startup_frames + 1 {
+            return;
+        }
+
+        let hitbox = hitbox(&attacker, &move_data);
+        if !hitbox.overlaps(&hurtbox(&defender)) {
+            return;
+        }
+
+        let blocked = self.blocks(defender_id, &defender, move_data.attack_height());
+        if defender_id == DUMMY_ID {
+            self.dummy_idle_ticks = 0;
+            self.counter_pending = true;
+        }
+
+        if blocked {
+            if let Some(defender) = self.fighter_mut(defender_id) {
+                defender.blockstun_remaining = move_data.blockstun_frames;
+            }
+        } else {
+            self.combat
+                .process_hit(attacker_id, defender_id, move_id, hitbox.center());
+            if let Some(defender) = self.fighter_mut(defender_id) {
+                defender.hitstun_remaining = move_data.hitstun_frames;
+                defender.current_move = None;
+                defender.move_frame = 0;
+                defender.blocking = false;
+            }
+        }
+
+        self.last_exchange = Some(Exchange {
+            name: move_data.name,
+            by_player: attacker_id == PLAYER_ID,
+            blocked,
+            startup: move_data.startup_frames,
+            advantage: if blocked {
+                FrameDataCalculator::frame_advantage_on_block(&move_data)
+            } else {
+                FrameDataCalculator::frame_advantage_on_hit(&move_data)
+            },
+        });
+    }
+
+    fn render_fighters(&self) {
+        let (Some(player), Some(dummy)) = (self.fighter(PLAYER_ID), self.fighter(DUMMY_ID)) else {
+            return;
+        };
+
+        // Crouch-blocking shrinks the player box to its lower half
+        let body = hurtbox(player);
+        let (player_top, player_height) = match self.player_guard {
+            Some(BlockStance::Crouching) => (body.y + body.h * 0.5, body.h * 0.5),
+            _ => (body.y, body.h),
+        };
+        draw_rectangle(body.x, player_top, body.w, player_height, BLUE);
+        if self.player_guard.is_some() {
+            draw_rectangle_lines(
+                body.x - 4.0,
+                player_top - 4.0,
+                body.w + 8.0,
+                player_height + 8.0,
+                3.0,
+                SKYBLUE,
+            );
+        }
+
+        let body = hurtbox(dummy);
+        let color = if dummy.hitstun_remaining > 0 {
+            Color::new(0.8, 0.4, 0.4, 1.0)
+        } else {
+            Color::new(0.5, 0.5, 0.5, 1.0)
+        };
+        draw_rectangle(body.x, body.y, body.w, body.h, color);
+        if dummy.blocking || dummy.blockstun_remaining > 0 {
+            draw_rectangle_lines(
+                body.x - 4.0,
+                body.y - 4.0,
+                body.w + 8.0,
+                body.h + 8.0,
+                3.0,
+                SKYBLUE,
+            );
+        }
+
+        if let Some(moveset) = self.combat.character_movesets.get(&TRAINING_CHARACTER) {
+            self.combat.frame_data_display.render(player, moveset);
+            self.combat.frame_data_display.render(dummy, moveset);
+        }
+    }
+
+    fn render_frame_data(&mut self) {
+        let x = 50.0;
+        let training = &self.combat.training_mode;
+
+        draw_text(
+            &format!("Dummy: {} (T to change)", behavior_label(self.behavior())),
+            x,
+            265.0,
+            22.0,
+            YELLOW,
+        );
+        let recording = match self.behavior() {
+            DummyBehavior::Record => Some(format!(
+                "Recording your attacks and guard: {} actions",
+                training.recorded_actions.len()
+            )),
+            DummyBehavior::Playback if training.recorded_actions.is_empty() => {
+                Some("Nothing recorded yet - record first".to_string())
+            }
+            DummyBehavior::Playback => Some(format!(
+                "Playing back {} actions on loop",
+                training.recorded_actions.len()
+            )),
+            _ => None,
+        };
+        if let Some(text) = recording {
+            draw_text(&text, x, 290.0, 20.0, LIGHTGRAY);
+        }
+
+        draw_text("KEY  MOVE               STARTUP  ON HIT  ON BLOCK", x, 330.0, 18.0, GRAY);
+        let keys = *self.keys.current();
+        for (row, (action, move_id)) in MOVE_KEYS.iter().enumerate() {
+            let label = input_handler::key_name(keys.key(*action));
+            let Some(move_data) = self.move_data(*move_id) else {
+                continue;
+            };
+            let on_block = FrameDataCalculator::frame_advantage_on_block(&move_data);
+            let safety = if FrameDataCalculator::is_safe_on_block(&move_data) {
+                "safe".to_string()
+            } else {
+                format!("punish {}f", FrameDataCalculator::punish_window(&move_data))
+            };
+            let text = format!(
+                "{:<4} {:<18} {:>5}f   {:>+4}   {:>+4} ({})",
+                label,
+                move_data.name,
+                move_data.startup_frames,
+                FrameDataCalculator::frame_advantage_on_hit(&move_data),
+                on_block,
+                safety
+            );
+            draw_text(&text, x, 355.0 + row as f32 * 22.0, 18.0, WHITE);
+        }
+
+        if let Some(exchange) = &self.last_exchange {
+            let who = if exchange.by_player { "You" } else { "Dummy" };
+            let outcome = if exchange.blocked { "blocked" } else { "hit" };
+            let color = if exchange.advantage >= 0 { GREEN } else { ORANGE };
+            draw_text(
+                &format!(
+                    "{}: {} {} | startup {}f | {:+} on {}",
+                    who, exchange.name, outcome, exchange.startup, exchange.advantage, outcome
+                ),
+                x,
+                445.0,
+                20.0,
+                color,
+            );
+        }
+    }
+
+    /// The room is a free trial of a character the profile hasn't unlocked,
+    /// with the way to keep them
+    fn render_trial_banner(&self) {
+        if self.roster.is_unlocked(TRAINING_FIGHTER) {
+            return;
+        }
+        let name = Character::get_by_id(TRAINING_FIGHTER).name;
+        let banner = format!("TRIAL: {}", name.to_uppercase());
+        let prompt =
+            format!("Unlock {} for {} Arc Tokens with U on character select", name, UNLOCK_COST);
+        for (text, y, size) in [(banner.as_str(), 36.0, 24.0), (prompt.as_str(), 62.0, 18.0)] {
+            let dims = measure_text(text, None, size as u16, 1.0);
+            draw_text(text, screen_width() * 0.5 - dims.width * 0.5, y, size, ORANGE);
+        }
+    }
+
+    fn render_guard_hud(&self) {
+        let drill = &self.guard_drill;
+        let x = 50.0;
+        let player_pos = self.position(PLAYER_ID);
+        let dummy_pos = self.position(DUMMY_ID);
+
+        let status = if drill.enabled { "ON" } else { "OFF" };
+        draw_text(
+            &format!("Guard drill: {} (G to toggle)", status),
+            x,
+            170.0,
+            20.0,
+            GRAY,
+        );
+
+        if !drill.enabled {
+            return;
+        }
+
+        draw_text(
+            &format!("Blocked: {}   Missed: {}", drill.blocked, drill.missed),
+            x,
+            195.0,
+            20.0,
+            WHITE,
+        );
+
+        let stance_text = match self.player_guard {
+            Some(BlockStance::Standing) => "Guard: HIGH",
+            Some(BlockStance::Crouching) => "Guard: LOW",
+            None => "Guard: none",
+        };
+        draw_text(stance_text, x, 220.0, 20.0, SKYBLUE);
+
+        if let Some(height) = drill.incoming {
+            let color = match height {
+                AttackHeight::Mid => WHITE,
+                AttackHeight::Overhead => ORANGE,
+                AttackHeight::Low => Color::new(0.4, 0.8, 1.0, 1.0),
+            };
+            let label = format!("INCOMING {}", height.label());
+            let dims = measure_text(&label, None, 36, 1.0);
+            draw_text(
+                &label,
+                dummy_pos.x - dims.width * 0.5,
+                dummy_pos.y - 110.0,
+                36.0,
+                color,
+            );
+            draw_text(
+                GuardDrill::hint(height),
+                dummy_pos.x - 120.0,
+                dummy_pos.y - 80.0,
+                20.0,
+                LIGHTGRAY,
+            );
+        }
+
+        if let Some((height, blocked)) = drill.last_result {
+            if drill.result_timer > 0.0 {
+                let (text, color) = if blocked {
+                    ("BLOCKED".to_string(), GREEN)
+                } else {
+                    (format!("{} HIT - WRONG GUARD", height.label()), RED)
+                };
+                draw_text(
+                    &text,
+                    player_pos.x - 80.0,
+                    player_pos.y - 90.0,
+                    24.0,
+                    Color::new(color.r, color.g, color.b, drill.result_timer.min(1.0)),
+                );
+            }
         }
     }
 }
 
+/// Where a fighter can be hit; matches the frame data overlay
+fn hurtbox(fighter: &CharacterCombatState) -> Rect {
+    Rect::new(fighter.position.x - 30.0, fighter.position.y - 80.0, 60.0, 160.0)
+}
+
+/// A move's hitbox, mirrored for fighters facing left
+fn hitbox(fighter: &CharacterCombatState, move_data: &MoveData) -> Rect {
+    let mut offset = move_data.hitbox_offset;
+    if !fighter.facing_right {
+        offset.x = -offset.x;
+    }
+    let center = fighter.position + offset;
+    Rect::new(
+        center.x - move_data.hitbox_size.x / 2.0,
+        center.y - move_data.hitbox_size.y / 2.0,
+        move_data.hitbox_size.x,
+        move_data.hitbox_size.y,
+    )
+}
+
+fn behavior_label(behavior: DummyBehavior) -> &'static str {
+    match behavior {
+        DummyBehavior::Idle => "Idle",
+        DummyBehavior::Crouch => "Crouch",
+        DummyBehavior::Stand => "Stand",
+        DummyBehavior::Jump => "Jump",
+        DummyBehavior::Block => "Block all",
+        DummyBehavior::BlockFirstHit => "Block after first hit",
+        DummyBehavior::RandomBlock => "Random block",
+        DummyBehavior::Counterattack => "Block and counterattack",
+        DummyBehavior::Record => "Record",
+        DummyBehavior::Playback => "Playback",
+    }
+}
+
 impl State for TrainingState {
     fn enter(&mut self) {
-        self.dummy_hp = 100.0;
-        self.input_history.clear();
+        self.transition_to = None;
+        self.combat.frame_data_display.input_history.clear();
+        self.input_manager.clear_history();
+        self.guard_drill = GuardDrill::new();
+        self.roster = Roster::load();
+        self.reset_fighters();
     }
 
     fn exit(&mut self) {}
 
-    fn update(&mut self, _dt: f32) {
+    fn update(&mut self, dt: f32) {
         if is_key_pressed(KeyCode::R) {
-            self.dummy_hp = 100.0;
-            self.dummy_pos = Vec2::new(800.0, 500.0);
-            self.player_pos = Vec2::new(400.0, 500.0);
+            self.reset_fighters();
+            self.input_manager.clear_history();
+            self.guard_drill = GuardDrill::new();
         }
+
+        self.guard_drill.update(dt, self.player_guard);
     }
 
-    fn fixed_update(&mut self, _dt: f64) {}
+    fn fixed_update(&mut self, dt: f64) {
+        self.tick(dt as f32);
+    }
 
     fn render(&mut self, _interpolation: f32) {
         clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
 
-        draw_rectangle(
-            self.player_pos.x - 30.0,
-            self.player_pos.y - 60.0,
-            60.0,
-            120.0,
-            BLUE,
-        );
-        draw_rectangle(
-            self.dummy_pos.x - 30.0,
-            self.dummy_pos.y - 60.0,
-            60.0,
-            120.0,
-            Color::new(0.5, 0.5, 0.5, 1.0),
-        );
+        self.render_fighters();
+        self.combat.vfx.render();
+        self.combat.enhanced_vfx.render();
 
         draw_text("TRAINING MODE", 50.0, 50.0, 40.0, WHITE);
+        if let Some(dummy) = self.fighter(DUMMY_ID) {
+            draw_text(
+                &format!("Dummy HP: {:.0} / {:.0}", dummy.health.max(0.0), dummy.max_health),
+                50.0,
+                100.0,
+                20.0,
+                WHITE,
+            );
+        }
+        let keys = *self.keys.current();
+        let attacks = MOVE_KEYS.map(|(action, _)| input_handler::key_name(keys.key(action)));
+        let display = &self.combat.frame_data_display;
+        let overlay = if display.enabled { "ON" } else { "OFF" };
+        let inputs = if display.show_input_history { "ON" } else { "OFF" };
         draw_text(
-            &format!("Dummy HP: {:.0}", self.dummy_hp),
+            &format!(
+                "{} = Attack | R = Reset | H = Hitboxes ({}) | F1 = Inputs ({}) | ESC = Back",
+                attacks.join("/"),
+                overlay,
+                inputs
+            ),
             50.0,
-            100.0,
+            130.0,
             20.0,
-            WHITE,
+            GRAY,
         );
-        draw_text("Press R to reset", 50.0, 130.0, 20.0, GRAY);
 
-        for (i, input) in self.input_history.iter().rev().take(10).enumerate() {
-            draw_text(
-                input,
-                screen_width() - 200.0,
-                50.0 + i as f32 * 25.0,
-                20.0,
-                Color::new(1.0, 1.0, 1.0, 1.0 - i as f32 * 0.1),
+        self.render_trial_banner();
+        self.render_guard_hud();
+        self.render_frame_data();
+
+        let display = &self.combat.frame_data_display;
+        if display.show_input_history {
+            input_display::render(
+                self.input_manager.history(),
+                &BUTTON_LABELS,
+                screen_width() - 430.0,
+                50.0,
+                INPUT_ROWS,
             );
+            for (i, input) in display.input_history.iter().rev().take(10).enumerate() {
+                let name = self.move_data(input.move_id).map_or("?", |m| m.name);
+                draw_text(
+                    &format!("{:>5} {}", input.frame, name),
+                    screen_width() - 260.0,
+                    50.0 + i as f32 * 25.0,
+                    20.0,
+                    Color::new(1.0, 1.0, 1.0, 1.0 - i as f32 * 0.1),
+                );
+            }
         }
     }
 
     fn handle_input(&mut self) {
-        if is_key_down(KeyCode::A) {
-            self.player_pos.x -= 5.0;
-            self.input_history.push("A".to_string());
+        if is_key_pressed(KeyCode::Escape) {
+            self.transition_to = Some(StateType::Menu);
+            return;
         }
-        if is_key_down(KeyCode::D) {
-            self.player_pos.x += 5.0;
-            self.input_history.push("D".to_string());
+
+        let keys = *self.keys.current();
+        let was_guarding = self.player_guard.is_some();
+        self.player_guard = if is_key_down(keys.block) {
+            if is_key_down(keys.move_down) {
+                Some(BlockStance::Crouching)
+            } else {
+                Some(BlockStance::Standing)
+            }
+        } else {
+            None
+        };
+        if self.player_guard.is_some() != was_guarding {
+            let action = if was_guarding { ActionType::Idle } else { ActionType::Block };
+            self.combat
+                .training_mode
+                .record_action(self.frame - self.record_start, action);
         }
-        if is_key_pressed(KeyCode::J) {
-            self.input_history.push("J".to_string());
+
+        if is_key_pressed(KeyCode::G) {
+            self.guard_drill.enabled = !self.guard_drill.enabled;
+            self.guard_drill.incoming = None;
+            self.guard_drill.timer = DRILL_INTERVAL;
+        }
+        if is_key_pressed(KeyCode::T) {
+            self.cycle_behavior();
+        }
+        if is_key_pressed(KeyCode::H) {
+            let display = &mut self.combat.frame_data_display;
+            display.enabled = !display.enabled;
         }
-        if is_key_pressed(KeyCode::K) {
-            self.input_history.push("K".to_string());
+        if is_key_pressed(KeyCode::F1) {
+            let display = &mut self.combat.frame_data_display;
+            display.show_input_history = !display.show_input_history;
+        }
+
+        let guard = self.player_guard;
+        let max_x = self.position(DUMMY_ID).x - 60.0;
+        if let Some(player) = self.fighter_mut(PLAYER_ID) {
+            player.blocking = guard.is_some();
+            if guard.is_none() && Self::can_act(player) {
+                if is_key_down(keys.move_left) {
+                    player.position.x -= 5.0;
+                }
+                if is_key_down(keys.move_right) {
+                    player.position.x += 5.0;
+                }
+                player.position.x = player.position.x.clamp(50.0, max_x);
+            }
         }
-        if is_key_pressed(KeyCode::L) {
-            self.input_history.push("L".to_string());
+
+        if guard.is_none() {
+            for (action, move_id) in MOVE_KEYS {
+                if is_key_pressed(keys.key(action)) {
+                    self.start_move(PLAYER_ID, move_id);
+                }
+            }
         }
     }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+
+    fn game_speed(&self) -> f32 {
+        crate::data::get_game_speed()
+    }
 }
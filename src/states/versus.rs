@@ -1,92 +1,1001 @@
-use crate::states::State;
+use crate::audio::announcer::{Announcer, Callout};
+use crate::audio::voice_bank::{FighterVoice, VoiceCue};
+use crate::audio::MusicCue;
+use crate::combat::versus_ai::{CpuOpponent, CpuSettings, CpuView};
+use crate::coop::input_handler::PlayerInput;
+use crate::coop::CoopInputHandler;
+use crate::data::characters::Character;
+use crate::data::cosmetics::{CosmeticsProgress, EquippedCosmetics};
+use crate::data::poses::{PoseChoice, PosePlayback, PoseVariant, POSE_DURATION};
+use crate::data::replay::{
+    InputSnapshot, PositionSnapshot, Replay, ReplayFrame, ReplayManager, ReplaySetup,
+};
+use crate::data::{CharacterId, GameConfig, MatchResult, MatchSettings};
+use crate::progression::CharacterMastery;
+use crate::render::atlas::SpriteBatch;
+use crate::render::map_system::MapType;
+use crate::render::{draw_outfit, CharacterSheets, SpriteAnimation};
+use crate::states::versus_select::{VERSUS_DEVICES, VERSUS_SLOTS};
+use crate::states::{State, StateType};
+use crate::ui::broadcast::{BroadcastOverlay, BroadcastPlayer};
+use crate::ui::input_display::{self, InputHistory, HISTORY_LENGTH};
+use crate::ui::subtitles::SubtitleManager;
+use crate::ui::UiLayout;
+use crate::util::host::Window;
+use crate::util::rng::SeededRng;
 use macroquad::prelude::*;
+use std::path::PathBuf;
 
-pub struct VersusState {
-    player1_hp: f32,
-    player2_hp: f32,
-    player1_pos: Vec2,
-    player2_pos: Vec2,
+/// Stages a versus match can be played on, in the order the results screen cycles them
+pub const VERSUS_STAGES: [MapType; 6] = [
+    MapType::Classroom,
+    MapType::Hallway,
+    MapType::Cafeteria,
+    MapType::Gym,
+    MapType::Library,
+    MapType::Rooftop,
+];
+
+const ROUND_TIME: f32 = 99.0;
+/// Rounds needed to take the match (best of 3)
+const ROUNDS_TO_WIN: u32 = 2;
+/// "ROUND N" / "FIGHT!" banner before control is handed over
+const ROUND_START_TIME: f32 = 1.5;
+/// How long a decided round's result stays up before the next one
+const ROUND_OVER_TIME: f32 = 2.0;
+/// A drawn round goes to sudden death: both fighters on a sliver of health
+/// that a single clean light finishes, on a shorter clock
+const SUDDEN_DEATH_HP: f32 = 5.0;
+const SUDDEN_DEATH_TIME: f32 = 30.0;
+/// The fight steps at a fixed 60 Hz, one replay frame a step, matching
+/// `ReplayManager`'s duration math
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+const START_POSITIONS: [Vec2; 2] = [Vec2::new(400.0, 500.0), Vec2::new(800.0, 500.0)];
+const WALK_SPEED: f32 = 300.0;
+const BLOCK_WALK_SPEED: f32 = 120.0;
+/// Fighters can't walk through each other or off the screen edges
+const MIN_SEPARATION: f32 = 70.0;
+const ARENA_MARGIN: f32 = 80.0;
+/// Share of an attack's damage that still gets through a block
+const CHIP_DAMAGE: f32 = 0.2;
+const HITSTUN_TIME: f32 = 0.3;
+/// Clean hits in a row before hitting back counts as a combo breaker
+const COMBO_BREAKER_HITS: u32 = 3;
+
+/// Replay button bits: what's held, then what went down that frame
+const BUTTON_LIGHT: u32 = 1;
+const BUTTON_HEAVY: u32 = 1 << 1;
+const BUTTON_BLOCK: u32 = 1 << 2;
+const BUTTON_LIGHT_PRESSED: u32 = 1 << 3;
+const BUTTON_HEAVY_PRESSED: u32 = 1 << 4;
+
+/// Input display labels, in the bit order `record_replay_frame` packs them
+const INPUT_LABELS: [&str; 3] = ["L", "H", "B"];
+const INPUT_ROWS: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AttackKind {
+    Light,
+    Heavy,
+}
+
+impl AttackKind {
+    fn damage(self) -> f32 {
+        match self {
+            AttackKind::Light => 6.0,
+            AttackKind::Heavy => 14.0,
+        }
+    }
+
+    fn range(self) -> f32 {
+        match self {
+            AttackKind::Light => 110.0,
+            AttackKind::Heavy => 135.0,
+        }
+    }
+
+    fn knockback(self) -> f32 {
+        match self {
+            AttackKind::Light => 25.0,
+            AttackKind::Heavy => 60.0,
+        }
+    }
+
+    /// When in the swing the hit connects
+    fn active_at(self) -> f32 {
+        match self {
+            AttackKind::Light => 0.08,
+            AttackKind::Heavy => 0.2,
+        }
+    }
+
+    /// Total swing length; no other action until it ends
+    fn duration(self) -> f32 {
+        match self {
+            AttackKind::Light => 0.3,
+            AttackKind::Heavy => 0.6,
+        }
+    }
+}
+
+struct Attack {
+    kind: AttackKind,
+    elapsed: f32,
+    resolved: bool,
+}
+
+/// One side of the match
+struct VersusFighter {
+    pos: Vec2,
+    hp: f32,
+    attack: Option<Attack>,
+    blocking: bool,
+    walking: bool,
+    hitstun: f32,
+}
+
+impl VersusFighter {
+    fn new(pos: Vec2) -> Self {
+        Self {
+            pos,
+            hp: 100.0,
+            attack: None,
+            blocking: false,
+            walking: false,
+            hitstun: 0.0,
+        }
+    }
+
+    fn can_act(&self) -> bool {
+        self.attack.is_none() && self.hitstun <= 0.0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RoundPhase {
+    /// Player performing their intro pose; P1 goes first, then P2
+    Intro(usize),
+    /// Time left on the round banner
+    RoundStart(f32),
+    Fighting,
+    /// Round winner (None for a draw) and how long the result has been shown
+    RoundOver(Option<usize>, f32),
+    /// Index of the player who took the match
+    Victory(usize),
+    /// Sudden death was drawn too; how long "DRAW GAME" has been up
+    Drawn(f32),
+}
+
+/// The part of a match that's stepped on fixed frames, and that a replay can
+/// be played again through: both fighters, the round clock, and P2's CPU
+/// rolling off the match seed. Nothing in here reads the screen, the wall
+/// clock or macroquad's `rand`.
+struct Fight {
+    fighters: [VersusFighter; 2],
     round_timer: f32,
+    /// P2's brain when nobody's at the second controls
+    cpu: Option<CpuOpponent>,
+    rng: SeededRng,
+    arena_width: f32,
+}
+
+impl Fight {
+    fn new(setup: &ReplaySetup) -> Self {
+        Self {
+            fighters: START_POSITIONS.map(VersusFighter::new),
+            round_timer: ROUND_TIME,
+            cpu: setup.cpu.map(|settings| {
+                CpuOpponent::new(settings, setup.characters[1].to_character_type())
+            }),
+            rng: SeededRng::new(setup.seed),
+            arena_width: setup.arena_width,
+        }
+    }
+
+    /// Both fighters back on their marks with a full clock
+    fn start_round(&mut self, sudden_death: bool) {
+        self.fighters = START_POSITIONS.map(VersusFighter::new);
+        self.round_timer = ROUND_TIME;
+        if sudden_death {
+            for fighter in &mut self.fighters {
+                fighter.hp = SUDDEN_DEATH_HP;
+            }
+            self.round_timer = SUDDEN_DEATH_TIME;
+        }
+    }
+
+    /// One frame of fighting. The CPU, if there is one, replaces P2's input.
+    /// Returns the inputs that were played, and who landed a clean hit.
+    fn step(&mut self, mut inputs: [PlayerInput; 2]) -> ([PlayerInput; 2], Vec<usize>) {
+        if let Some(input) = self.cpu_input() {
+            inputs[1] = input;
+        }
+
+        for (fighter, input) in self.fighters.iter_mut().zip(&inputs) {
+            fighter.hitstun = (fighter.hitstun - FRAME_TIME).max(0.0);
+
+            if fighter.can_act() {
+                fighter.blocking = input.block;
+                if input.heavy_attack_pressed {
+                    fighter.attack = Some(Attack {
+                        kind: AttackKind::Heavy,
+                        elapsed: 0.0,
+                        resolved: false,
+                    });
+                } else if input.light_attack_pressed {
+                    fighter.attack = Some(Attack {
+                        kind: AttackKind::Light,
+                        elapsed: 0.0,
+                        resolved: false,
+                    });
+                }
+            }
+
+            let speed = if fighter.blocking {
+                BLOCK_WALK_SPEED
+            } else {
+                WALK_SPEED
+            };
+            let step = if fighter.can_act() {
+                input.movement.x * speed * FRAME_TIME
+            } else {
+                0.0
+            };
+            fighter.pos.x += step;
+            fighter.walking = step != 0.0;
+        }
+
+        let clean_hits = (0..2).filter(|&attacker| self.advance_attack(attacker)).collect();
+
+        // Keep the fighters apart and on screen
+        let [p1, p2] = [self.fighters[0].pos.x, self.fighters[1].pos.x];
+        let gap = p2 - p1;
+        if gap.abs() < MIN_SEPARATION {
+            let push = (MIN_SEPARATION - gap.abs()) * 0.5 * if gap >= 0.0 { 1.0 } else { -1.0 };
+            self.fighters[0].pos.x -= push;
+            self.fighters[1].pos.x += push;
+        }
+        for fighter in &mut self.fighters {
+            fighter.pos.x = fighter
+                .pos
+                .x
+                .clamp(ARENA_MARGIN, self.arena_width - ARENA_MARGIN);
+        }
+
+        self.round_timer = (self.round_timer - FRAME_TIME).max(0.0);
+        (inputs, clean_hits)
+    }
+
+    fn round_over(&self) -> bool {
+        self.round_timer <= 0.0 || self.fighters.iter().any(|fighter| fighter.hp <= 0.0)
+    }
+
+    /// Step `attacker`'s swing, landing it on the other player once it's
+    /// active. Whether it landed clean.
+    fn advance_attack(&mut self, attacker: usize) -> bool {
+        let defender = 1 - attacker;
+        let facing = self.facing(attacker);
+        let distance = (self.fighters[defender].pos.x - self.fighters[attacker].pos.x) * facing;
+
+        let Some(attack) = &mut self.fighters[attacker].attack else {
+            return false;
+        };
+        attack.elapsed += FRAME_TIME;
+        let kind = attack.kind;
+        let finished = attack.elapsed >= kind.duration();
+        let lands = !attack.resolved && attack.elapsed >= kind.active_at();
+        if lands {
+            attack.resolved = true;
+        }
+        if finished {
+            self.fighters[attacker].attack = None;
+        }
+
+        if !lands || distance <= 0.0 || distance > kind.range() {
+            return false;
+        }
+        let target = &mut self.fighters[defender];
+        let blocked = target.blocking;
+        let (damage, knockback) = if blocked {
+            (kind.damage() * CHIP_DAMAGE, kind.knockback() * 0.5)
+        } else {
+            target.hitstun = HITSTUN_TIME;
+            (kind.damage(), kind.knockback())
+        };
+        target.hp = (target.hp - damage).max(0.0);
+        target.pos.x += knockback * facing;
+        !blocked
+    }
+
+    /// 1.0 if `player` faces right, -1.0 if left; fighters always face each other
+    fn facing(&self, player: usize) -> f32 {
+        let other = self.fighters[1 - player].pos.x;
+        if other >= self.fighters[player].pos.x {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    /// What the CPU plays as P2 this frame, when it's the CPU's seat
+    fn cpu_input(&mut self) -> Option<PlayerInput> {
+        let cpu = self.cpu.as_mut()?;
+        let [human, own] = &self.fighters;
+        let distance = (human.pos.x - own.pos.x).abs();
+        // Worth reacting to if it's still coming and would reach
+        let incoming = human.attack.as_ref().is_some_and(|attack| {
+            !attack.resolved && distance <= attack.kind.range() + MIN_SEPARATION * 0.5
+        });
+        let view = CpuView {
+            distance,
+            toward: if human.pos.x >= own.pos.x { 1.0 } else { -1.0 },
+            incoming,
+            can_act: own.can_act(),
+            light_range: AttackKind::Light.range(),
+            heavy_range: AttackKind::Heavy.range(),
+        };
+        Some(cpu.think(FRAME_TIME, &view, &mut self.rng))
+    }
+}
+
+/// What follows a decided round; `wins` already counts it
+enum NextRound {
+    Match(usize),
+    Round { sudden_death: bool },
+    Drawn,
+}
+
+/// A drawn round goes to sudden death, and a drawn sudden death draws the match
+fn next_round(wins: [u32; 2], winner: Option<usize>, sudden_death: bool) -> NextRound {
+    match winner {
+        Some(w) if wins[w] >= ROUNDS_TO_WIN => NextRound::Match(w),
+        Some(_) => NextRound::Round {
+            sudden_death: false,
+        },
+        None if sudden_death => NextRound::Drawn,
+        None => NextRound::Round { sudden_death: true },
+    }
+}
+
+/// The replay frame for a step: what was played, and where it left the fighters
+fn replay_frame(
+    number: u32,
+    inputs: &[PlayerInput; 2],
+    fighters: &[VersusFighter; 2],
+) -> ReplayFrame {
+    ReplayFrame {
+        frame_number: number,
+        inputs: inputs
+            .iter()
+            .enumerate()
+            .map(|(player, input)| InputSnapshot {
+                player_id: player as u8,
+                buttons: [
+                    (input.light_attack, BUTTON_LIGHT),
+                    (input.heavy_attack, BUTTON_HEAVY),
+                    (input.block, BUTTON_BLOCK),
+                    (input.light_attack_pressed, BUTTON_LIGHT_PRESSED),
+                    (input.heavy_attack_pressed, BUTTON_HEAVY_PRESSED),
+                ]
+                .iter()
+                .filter(|(down, _)| *down)
+                .fold(0, |bits, (_, bit)| bits | bit),
+                stick_x: input.movement.x,
+                stick_y: input.movement.y,
+            })
+            .collect(),
+        positions: fighters
+            .iter()
+            .enumerate()
+            .map(|(id, fighter)| PositionSnapshot {
+                entity_id: id as u32,
+                x: fighter.pos.x,
+                y: fighter.pos.y,
+            })
+            .collect(),
+        health_values: fighters.iter().map(|fighter| fighter.hp).collect(),
+        meter_values: Vec::new(),
+    }
+}
+
+/// The input a recorded snapshot stands for
+fn input_from(snapshot: &InputSnapshot) -> PlayerInput {
+    let down = |bit: u32| snapshot.buttons & bit != 0;
+    PlayerInput {
+        movement: Vec2::new(snapshot.stick_x, snapshot.stick_y),
+        light_attack: down(BUTTON_LIGHT),
+        heavy_attack: down(BUTTON_HEAVY),
+        block: down(BUTTON_BLOCK),
+        light_attack_pressed: down(BUTTON_LIGHT_PRESSED),
+        heavy_attack_pressed: down(BUTTON_HEAVY_PRESSED),
+        ..Default::default()
+    }
+}
+
+/// Play a recorded versus match back from its setup and check every frame
+/// lands the fighters where the recording has them. How many frames matched.
+pub fn resimulate(replay: &Replay) -> Result<usize, String> {
+    let setup = replay
+        .metadata
+        .setup
+        .ok_or("The replay is from before versus recorded its setup")?;
+    let mut fight = Fight::new(&setup);
+    let mut wins = [0, 0];
+    let mut sudden_death = false;
+    for (i, frame) in replay.frames.iter().enumerate() {
+        if frame.frame_number as usize != i {
+            return Err(format!("Frame {} is missing", i));
+        }
+        let mut inputs: [PlayerInput; 2] = Default::default();
+        for snapshot in &frame.inputs {
+            if let Some(input) = inputs.get_mut(snapshot.player_id as usize) {
+                *input = input_from(snapshot);
+            }
+        }
+        fight.step(inputs);
+
+        for (player, fighter) in fight.fighters.iter().enumerate() {
+            let pos = frame.positions.get(player).map(|snapshot| snapshot.x);
+            let hp = frame.health_values.get(player).copied();
+            if pos != Some(fighter.pos.x) || hp != Some(fighter.hp) {
+                return Err(format!(
+                    "Frame {} diverges: P{} is at {:.2} with {:.1} HP, recorded {:?} and {:?}",
+                    i,
+                    player + 1,
+                    fighter.pos.x,
+                    fighter.hp,
+                    pos,
+                    hp
+                ));
+            }
+        }
+
+        if fight.round_over() {
+            let winner = round_winner(fight.fighters.each_ref().map(|fighter| fighter.hp));
+            if let Some(w) = winner {
+                wins[w] += 1;
+            }
+            match next_round(wins, winner, sudden_death) {
+                NextRound::Round { sudden_death: next } => {
+                    sudden_death = next;
+                    fight.start_round(sudden_death);
+                }
+                NextRound::Match(_) | NextRound::Drawn => return Ok(i + 1),
+            }
+        }
+    }
+    Ok(replay.frames.len())
+}
+
+/// Check the replay at `path`, or the last one saved, plays back the same
+pub fn verify_replay(path: Option<PathBuf>) -> Result<String, String> {
+    let path = path.or_else(Replay::latest).ok_or("There are no saved replays")?;
+    Replay::verify(&path, resimulate).map(|matched| format!("{}: {}", path.display(), matched))
+}
+
+/// Who took a round on health. Equal health, a double KO included, is a draw.
+fn round_winner(hp: [f32; 2]) -> Option<usize> {
+    match hp[0].total_cmp(&hp[1]) {
+        std::cmp::Ordering::Greater => Some(0),
+        std::cmp::Ordering::Less => Some(1),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+pub struct VersusState {
+    fight: Fight,
+    /// The humans' input, with presses kept until a frame steps on them
+    inputs: [PlayerInput; 2],
+    input: CoopInputHandler,
+    round: u32,
+    round_wins: [u32; 2],
+    /// The round being fought breaks a draw
+    sudden_death: bool,
+    /// Fighting time across every round, for the results screen
+    match_time: f32,
+    characters: [CharacterId; 2],
+    stage: MapType,
+    pose_choices: [PoseChoice; 2],
+    mastery_levels: [u32; 2],
+    /// P1 wears their profile's cosmetics; P2 shares the keyboard and doesn't have any
+    cosmetics: [EquippedCosmetics; 2],
+    poses: [Option<PosePlayback>; 2],
+    phase: RoundPhase,
+    sheets: CharacterSheets,
+    batch: SpriteBatch,
+    subtitles: SubtitleManager,
+    announcer: Announcer,
+    voices: [FighterVoice; 2],
+    /// Clean hits each player has landed since the other last landed one
+    combos: [u32; 2],
+    recorder: ReplayManager,
+    /// Time not yet stepped through, under a frame
+    frame_clock: f32,
+    replay_frame: u32,
+    replay: Option<Replay>,
+    /// Per-player input columns, sampled with the replay frames
+    input_histories: [InputHistory; 2],
+    show_inputs: bool,
+    cpu_settings: Option<CpuSettings>,
+    /// Streaming overlay, when it's switched on
+    broadcast: Option<BroadcastOverlay>,
+    transition_to: Option<StateType>,
 }
 
 impl VersusState {
     pub fn new() -> Self {
+        // Rematches and re-selects keep the running session's setup
+        let settings = crate::data::get_match_settings()
+            .unwrap_or_else(|| MatchSettings::new(crate::data::get_selected_character()));
+        crate::data::set_match_settings(settings);
+        let [player1, player2] = settings.characters;
+        let mastery_levels = [
+            CharacterMastery::load(player1).level,
+            CharacterMastery::load(player2).level,
+        ];
+
         Self {
-            player1_hp: 100.0,
-            player2_hp: 100.0,
-            player1_pos: Vec2::new(400.0, 500.0),
-            player2_pos: Vec2::new(800.0, 500.0),
-            round_timer: 99.0,
+            fight: Fight::new(&ReplaySetup {
+                characters: settings.characters,
+                cpu: settings.cpu,
+                seed: 0,
+                arena_width: screen_width(),
+            }),
+            inputs: Default::default(),
+            input: CoopInputHandler::new(),
+            round: 1,
+            round_wins: [0, 0],
+            sudden_death: false,
+            match_time: 0.0,
+            characters: settings.characters,
+            stage: settings.stage,
+            pose_choices: [crate::data::get_pose_choice(), PoseChoice::default()],
+            mastery_levels,
+            cosmetics: [
+                CosmeticsProgress::load().equipped(player1, mastery_levels[0]),
+                EquippedCosmetics::default(),
+            ],
+            poses: [None, None],
+            phase: RoundPhase::Intro(0),
+            sheets: CharacterSheets::new(),
+            batch: SpriteBatch::default(),
+            subtitles: SubtitleManager::new(),
+            announcer: Announcer::new(),
+            voices: [FighterVoice::new(), FighterVoice::new()],
+            combos: [0, 0],
+            recorder: ReplayManager::new(),
+            frame_clock: 0.0,
+            replay_frame: 0,
+            replay: None,
+            input_histories: [
+                InputHistory::new(HISTORY_LENGTH),
+                InputHistory::new(HISTORY_LENGTH),
+            ],
+            show_inputs: false,
+            cpu_settings: settings.cpu,
+            broadcast: None,
+            transition_to: None,
+        }
+    }
+
+    fn winner_name(&self, winner: usize) -> &'static str {
+        Character::get_by_id(self.characters[winner]).name
+    }
+
+    fn start_pose(&mut self, player: usize, variant: &'static PoseVariant) {
+        let character = Character::get_by_id(self.characters[player]);
+        self.subtitles.add_translated_subtitle(
+            character.name.to_string(),
+            variant.dutch.to_string(),
+            variant.english.to_string(),
+            POSE_DURATION,
+        );
+        self.poses[player] = Some(PosePlayback::new(variant));
+    }
+
+    fn start_intro(&mut self, player: usize) {
+        let intro =
+            self.pose_choices[player].intro(self.characters[player], self.mastery_levels[player]);
+        self.start_pose(player, intro);
+    }
+
+    /// Reset both fighters for the next round and show its banner
+    fn start_round(&mut self) {
+        self.fight.start_round(self.sudden_death);
+        self.combos = [0, 0];
+        self.phase = RoundPhase::RoundStart(ROUND_START_TIME);
+    }
+
+    /// Score the round on health. Both sides' hits for the frame have already
+    /// landed, so a trade that drops both fighters is a double KO, not a win
+    /// for whoever swung first.
+    fn end_round(&mut self) {
+        let [p1, p2] = &self.fight.fighters;
+        let winner = round_winner([p1.hp, p2.hp]);
+        if self.fight.fighters.iter().any(|fighter| fighter.hp <= 0.0) {
+            self.announcer.announce(Callout::KnockOut);
+        }
+        if let Some(winner) = winner {
+            self.announcer.announce(Callout::RoundWin(self.characters[winner]));
+            self.round_wins[winner] += 1;
+            // The match winner's stinger waits for the victory
+            if self.round_wins[winner] < ROUNDS_TO_WIN {
+                crate::data::queue_music_cue(MusicCue::RoundWin {
+                    character: self.characters[winner],
+                    mastery_level: self.mastery_levels[winner],
+                });
+            }
+        }
+        self.phase = RoundPhase::RoundOver(winner, 0.0);
+    }
+
+    fn end_match(&mut self, winner: usize) {
+        let victory = self.cosmetics[winner].victory_pose.unwrap_or_else(|| {
+            self.pose_choices[winner].victory(self.characters[winner], self.mastery_levels[winner])
+        });
+        self.start_pose(winner, victory);
+        self.phase = RoundPhase::Victory(winner);
+        crate::data::queue_music_cue(MusicCue::Victory {
+            character: self.characters[winner],
+            mastery_level: self.mastery_levels[winner],
+        });
+        self.replay = self
+            .recorder
+            .stop_recording(self.winner_name(winner).to_string());
+    }
+
+    fn end_match_drawn(&mut self) {
+        self.phase = RoundPhase::Drawn(0.0);
+        self.replay = self.recorder.stop_recording("Draw".to_string());
+    }
+
+    fn update_round_phase(&mut self, dt: f32) {
+        match self.phase {
+            RoundPhase::RoundStart(remaining) => {
+                let remaining = remaining - dt;
+                self.phase = if remaining <= 0.0 {
+                    RoundPhase::Fighting
+                } else {
+                    RoundPhase::RoundStart(remaining)
+                };
+            }
+            RoundPhase::RoundOver(winner, shown) => {
+                let shown = shown + dt;
+                if shown < ROUND_OVER_TIME {
+                    self.phase = RoundPhase::RoundOver(winner, shown);
+                    return;
+                }
+
+                match next_round(self.round_wins, winner, self.sudden_death) {
+                    NextRound::Match(w) => self.end_match(w),
+                    NextRound::Round { sudden_death } => {
+                        if !sudden_death {
+                            self.round += 1;
+                        }
+                        self.sudden_death = sudden_death;
+                        self.start_round();
+                    }
+                    NextRound::Drawn => self.end_match_drawn(),
+                }
+            }
+            RoundPhase::Drawn(shown) => {
+                let shown = shown + dt;
+                if shown < ROUND_OVER_TIME {
+                    self.phase = RoundPhase::Drawn(shown);
+                } else {
+                    self.finish_match(None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Count a clean hit towards `attacker`'s combo, calling out a breaker if
+    /// it cut short one landing on them, and let both fighters shout
+    fn land_clean_hit(&mut self, attacker: usize) {
+        let defender = 1 - attacker;
+        if self.combos[defender] >= COMBO_BREAKER_HITS {
+            self.announcer.announce(Callout::ComboBreaker);
+        }
+        self.combos[defender] = 0;
+        self.combos[attacker] += 1;
+        self.voices[attacker].speak(self.characters[attacker], VoiceCue::Hit);
+        self.voices[defender].speak(self.characters[defender], VoiceCue::Hurt);
+    }
+
+    /// Log a stepped frame to the recording and the input displays
+    fn record_frame(&mut self, inputs: &[PlayerInput; 2]) {
+        for (history, input) in self.input_histories.iter_mut().zip(inputs) {
+            history.push(
+                input_display::numpad_direction(input.movement.x, input.movement.y),
+                input_display::pack_buttons(&[
+                    input.light_attack,
+                    input.heavy_attack,
+                    input.block,
+                ]),
+            );
+        }
+        self.recorder
+            .record_frame(replay_frame(self.replay_frame, inputs, &self.fight.fighters));
+        self.replay_frame += 1;
+    }
+
+    /// Hand the finished match to the results screen; no winner is a draw
+    fn finish_match(&mut self, winner: Option<usize>) {
+        crate::data::set_match_result(MatchResult {
+            winner: winner.map(|winner| self.winner_name(winner).to_uppercase()),
+            rounds: self.round_wins,
+            duration: self.match_time,
+            replay: self.replay.take(),
+        });
+        self.transition_to = Some(StateType::Results);
+    }
+
+    fn update_poses(&mut self, dt: f32) {
+        for pose in self.poses.iter_mut().flatten() {
+            pose.update(dt);
+        }
+
+        if let RoundPhase::Victory(winner) = self.phase {
+            let finished = self.poses[winner].as_ref().is_none_or(PosePlayback::is_finished);
+            if finished && self.transition_to.is_none() {
+                self.finish_match(Some(winner));
+            }
+        }
+
+        if let RoundPhase::Intro(player) = self.phase {
+            if self.poses[player].as_ref().is_none_or(PosePlayback::is_finished) {
+                self.poses[player] = None;
+                if player == 0 {
+                    self.start_intro(1);
+                    self.phase = RoundPhase::Intro(1);
+                } else {
+                    self.start_round();
+                }
+            }
+        }
+    }
+
+    fn queue_player(&mut self, player: usize) {
+        let sheet = CharacterSheets::sheet_for(self.characters[player]);
+        let fighter = &self.fight.fighters[player];
+        let (animation, time, progress) = match (&self.poses[player], &fighter.attack) {
+            (Some(pose), _) => (
+                SpriteAnimation::for_pose(pose.variant.motion),
+                pose.elapsed,
+                pose.progress(),
+            ),
+            (None, Some(attack)) => (
+                SpriteAnimation::Attack,
+                attack.elapsed,
+                attack.elapsed / attack.kind.duration(),
+            ),
+            (None, None) if fighter.hp <= 0.0 => (SpriteAnimation::Down, get_time() as f32, 0.0),
+            (None, None) if fighter.hitstun > 0.0 => {
+                (SpriteAnimation::Hurt, get_time() as f32, 0.0)
+            }
+            (None, None) if fighter.blocking => (SpriteAnimation::Block, get_time() as f32, 0.0),
+            (None, None) if fighter.walking => (SpriteAnimation::Walk, get_time() as f32, 0.0),
+            (None, None) => (SpriteAnimation::Idle, get_time() as f32, 0.0),
+        };
+        let pos = fighter.pos;
+        let flip = self.fight.facing(player) < 0.0;
+        let tint = self.cosmetics[player].tint.unwrap_or(WHITE);
+        self.sheets.queue(
+            &mut self.batch,
+            sheet,
+            animation,
+            time,
+            progress,
+            pos,
+            flip,
+            tint,
+        );
+    }
+
+    /// Health bar with a row of round-win pips underneath; P2's drains towards
+    /// the centre from the right
+    fn render_player_hud(&self, player: usize) {
+        let bar_width = 300.0;
+        let x = if player == 0 {
+            50.0
+        } else {
+            screen_width() - 350.0
+        };
+        let full = if self.sudden_death { SUDDEN_DEATH_HP } else { 100.0 };
+        let hp = self.fight.fighters[player].hp / full;
+
+        draw_rectangle(x, 50.0, bar_width, 30.0, Color::new(0.2, 0.0, 0.0, 0.8));
+        let fill_x = if player == 0 {
+            x
+        } else {
+            screen_width() - 50.0 - bar_width * hp
+        };
+        draw_rectangle(fill_x, 50.0, bar_width * hp, 30.0, Color::new(0.8, 0.0, 0.0, 1.0));
+        draw_rectangle_lines(x, 50.0, bar_width, 30.0, 2.0, WHITE);
+
+        if player == 0 {
+            draw_text("P1", 20.0, 70.0, 20.0, WHITE);
+        } else {
+            let label = if self.fight.cpu.is_some() { "CPU" } else { "P2" };
+            let label_x = screen_width() - 20.0 - measure_text(label, None, 20, 1.0).width;
+            draw_text(label, label_x, 70.0, 20.0, WHITE);
+        }
+
+        let name = Character::get_by_id(self.characters[player]).name.to_uppercase();
+        let name_x = if player == 0 {
+            x
+        } else {
+            x + bar_width - measure_text(&name, None, 18, 1.0).width
+        };
+        draw_text(&name, name_x, 40.0, 18.0, WHITE);
+
+        // Round wins, filled in as they're taken
+        for pip in 0..ROUNDS_TO_WIN {
+            let offset = 12.0 + pip as f32 * 28.0;
+            let pip_x = if player == 0 {
+                x + offset
+            } else {
+                x + bar_width - offset
+            };
+            let won = pip < self.round_wins[player];
+            if won {
+                draw_circle(pip_x, 98.0, 9.0, YELLOW);
+            }
+            draw_circle_lines(pip_x, 98.0, 9.0, 2.0, WHITE);
         }
     }
+
+    fn render_banner(&self) {
+        let (text, color) = match self.phase {
+            RoundPhase::RoundStart(remaining) if remaining > ROUND_START_TIME * 0.4 => {
+                if self.sudden_death {
+                    ("SUDDEN DEATH".to_string(), RED)
+                } else {
+                    (format!("ROUND {}", self.round), WHITE)
+                }
+            }
+            RoundPhase::RoundStart(_) => ("FIGHT!".to_string(), YELLOW),
+            RoundPhase::RoundOver(Some(winner), _) => (
+                format!("{} TAKES THE ROUND", self.winner_name(winner).to_uppercase()),
+                YELLOW,
+            ),
+            RoundPhase::RoundOver(None, _) if self.fight.fighters.iter().all(|f| f.hp <= 0.0) => {
+                ("DOUBLE KO".to_string(), WHITE)
+            }
+            RoundPhase::RoundOver(None, _) => ("DRAW".to_string(), WHITE),
+            RoundPhase::Drawn(_) => ("DRAW GAME".to_string(), WHITE),
+            RoundPhase::Victory(winner) => {
+                (format!("{} WINS", self.winner_name(winner).to_uppercase()), YELLOW)
+            }
+            _ => return,
+        };
+
+        let dims = measure_text(&text, None, 64, 1.0);
+        draw_text(
+            &text,
+            screen_width() * 0.5 - dims.width * 0.5,
+            screen_height() * 0.35,
+            64.0,
+            color,
+        );
+    }
 }
 
 impl State for VersusState {
     fn enter(&mut self) {
-        self.player1_hp = 100.0;
-        self.player2_hp = 100.0;
-        self.round_timer = 99.0;
+        let setup = ReplaySetup {
+            characters: self.characters,
+            cpu: self.cpu_settings,
+            seed: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64),
+            arena_width: screen_width(),
+        };
+        self.fight = Fight::new(&setup);
+        self.round = 1;
+        self.round_wins = [0, 0];
+        self.sudden_death = false;
+        self.match_time = 0.0;
+        self.poses = [None, None];
+        self.subtitles.clear();
+        self.announcer.clear();
+        self.start_intro(0);
+        self.phase = RoundPhase::Intro(0);
+        self.transition_to = None;
+
+        let characters = self
+            .characters
+            .iter()
+            .map(|&id| Character::get_by_id(id).name.to_string())
+            .collect();
+        self.recorder
+            .start_recording(self.stage.display_name().to_string(), characters, setup);
+        self.frame_clock = 0.0;
+        self.replay_frame = 0;
+        self.replay = None;
+        for history in &mut self.input_histories {
+            history.clear();
+        }
+        self.broadcast = BroadcastOverlay::from_config(&GameConfig::load());
     }
 
     fn exit(&mut self) {}
 
     fn update(&mut self, dt: f32) {
-        self.round_timer -= dt;
-        if self.round_timer < 0.0 {
-            self.round_timer = 0.0;
+        self.update_poses(dt);
+        self.update_round_phase(dt);
+        self.subtitles.update(dt);
+        self.announcer.update(dt);
+        for voice in &mut self.voices {
+            voice.update(dt);
+        }
+
+        if self.phase != RoundPhase::Fighting {
+            return;
+        }
+
+        // Fixed steps, so a replay plays back the same from its setup
+        self.frame_clock += dt;
+        while self.frame_clock >= FRAME_TIME && self.phase == RoundPhase::Fighting {
+            self.frame_clock -= FRAME_TIME;
+            let (played, clean_hits) = self.fight.step(self.inputs.clone());
+            for input in &mut self.inputs {
+                input.light_attack_pressed = false;
+                input.heavy_attack_pressed = false;
+            }
+            for attacker in clean_hits {
+                self.land_clean_hit(attacker);
+            }
+            self.record_frame(&played);
+            self.match_time += FRAME_TIME;
+
+            if self.fight.round_over() {
+                self.end_round();
+            }
         }
     }
 
     fn fixed_update(&mut self, _dt: f64) {}
 
     fn render(&mut self, _interpolation: f32) {
-        clear_background(BLACK);
-
+        clear_background(stage_color(self.stage));
         draw_rectangle(
-            self.player1_pos.x - 30.0,
-            self.player1_pos.y - 60.0,
-            60.0,
-            120.0,
-            BLUE,
+            0.0,
+            560.0,
+            screen_width(),
+            screen_height() - 560.0,
+            Color::new(0.0, 0.0, 0.0, 0.35),
         );
-        draw_rectangle(
-            self.player2_pos.x - 30.0,
-            self.player2_pos.y - 60.0,
-            60.0,
-            120.0,
-            RED,
+        let stage_name = self.stage.display_name().to_uppercase();
+        let stage_dims = measure_text(&stage_name, None, 20, 1.0);
+        draw_text(
+            &stage_name,
+            screen_width() * 0.5 - stage_dims.width * 0.5,
+            110.0,
+            20.0,
+            Color::new(1.0, 1.0, 1.0, 0.7),
         );
 
-        draw_rectangle(50.0, 50.0, 300.0, 30.0, Color::new(0.2, 0.0, 0.0, 0.8));
-        draw_rectangle(
-            50.0,
-            50.0,
-            300.0 * (self.player1_hp / 100.0),
-            30.0,
-            Color::new(0.8, 0.0, 0.0, 1.0),
-        );
-        draw_rectangle_lines(50.0, 50.0, 300.0, 30.0, 2.0, WHITE);
-        draw_text("P1", 20.0, 70.0, 20.0, WHITE);
+        for fighter in &self.fight.fighters {
+            let pos = fighter.pos;
+            draw_ellipse(pos.x, pos.y + 70.0, 48.0, 16.0, 0.0, Color::new(0.0, 0.0, 0.0, 0.4));
+        }
+        self.queue_player(0);
+        self.queue_player(1);
+        self.sheets.flush(&mut self.batch);
+        for player in 0..2 {
+            if let Some((outfit, color)) = self.cosmetics[player].outfit {
+                let flip = self.fight.facing(player) < 0.0;
+                draw_outfit(outfit, color, self.fight.fighters[player].pos, flip);
+            }
+        }
 
-        draw_rectangle(
-            screen_width() - 350.0,
-            50.0,
-            300.0,
-            30.0,
-            Color::new(0.2, 0.0, 0.0, 0.8),
-        );
-        let p2_hp_width = 300.0 * (self.player2_hp / 100.0);
-        draw_rectangle(
-            screen_width() - 50.0 - p2_hp_width,
-            50.0,
-            p2_hp_width,
-            30.0,
-            Color::new(0.8, 0.0, 0.0, 1.0),
-        );
-        draw_rectangle_lines(screen_width() - 350.0, 50.0, 300.0, 30.0, 2.0, WHITE);
-        draw_text("P2", screen_width() - 45.0, 70.0, 20.0, WHITE);
+        self.render_player_hud(0);
+        self.render_player_hud(1);
 
-        let timer_text = format!("{:02}", self.round_timer as i32);
+        let timer_text = format!("{:02}", self.fight.round_timer.ceil() as i32);
         let timer_dims = measure_text(&timer_text, None, 60, 1.0);
         draw_text(
             &timer_text,
@@ -95,14 +1004,141 @@ impl State for VersusState {
             60.0,
             YELLOW,
         );
+
+        self.render_banner();
+        let ui = UiLayout::current();
+        ui.begin();
+        self.subtitles.render(&ui);
+        ui.end();
+
+        if let Some(overlay) = &self.broadcast {
+            // Carries its own input columns
+            overlay.render(
+                [0, 1].map(|player| BroadcastPlayer {
+                    character: Character::get_by_id(self.characters[player]).name,
+                    score: self.round_wins[player],
+                    inputs: &self.input_histories[player],
+                }),
+                self.round,
+                ROUNDS_TO_WIN,
+                &INPUT_LABELS,
+            );
+        } else if self.show_inputs {
+            let [p1, p2] = &self.input_histories;
+            input_display::render(p1, &INPUT_LABELS, 58.0, 130.0, INPUT_ROWS);
+            input_display::render(p2, &INPUT_LABELS, screen_width() - 192.0, 130.0, INPUT_ROWS);
+        }
     }
 
     fn handle_input(&mut self) {
-        if is_key_down(KeyCode::A) {
-            self.player1_pos.x -= 5.0;
+        if is_key_pressed(KeyCode::F1) {
+            self.show_inputs = !self.show_inputs;
+        }
+        // The CPU's input is picked in update, where it can see the fight.
+        // Presses hold until a step plays them.
+        let humans = if self.fight.cpu.is_some() { 1 } else { 2 };
+        for player in 0..humans {
+            let held = &self.inputs[player];
+            let mut input = self
+                .input
+                .get_player_input(&Window, VERSUS_SLOTS[player], VERSUS_DEVICES[player]);
+            input.light_attack_pressed |= held.light_attack_pressed;
+            input.heavy_attack_pressed |= held.heavy_attack_pressed;
+            self.inputs[player] = input;
+        }
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+}
+
+/// Backdrop for a stage until versus draws the full map art
+pub fn stage_color(stage: MapType) -> Color {
+    match stage {
+        MapType::Classroom => Color::new(0.45, 0.38, 0.28, 1.0),
+        MapType::Hallway => Color::new(0.28, 0.35, 0.45, 1.0),
+        MapType::Cafeteria => Color::new(0.5, 0.42, 0.3, 1.0),
+        MapType::Gym => Color::new(0.42, 0.3, 0.2, 1.0),
+        MapType::Library => Color::new(0.3, 0.22, 0.18, 1.0),
+        MapType::Rooftop => Color::new(0.35, 0.45, 0.6, 1.0),
+        _ => Color::new(0.15, 0.15, 0.18, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::versus_ai::{CpuDifficulty, CpuStyle};
+    use crate::data::replay::ReplayMetadata;
+
+    #[test]
+    fn a_trade_that_drops_both_fighters_is_a_draw() {
+        assert_eq!(round_winner([0.0, 0.0]), None);
+        assert_eq!(round_winner([40.0, 40.0]), None);
+        assert_eq!(round_winner([0.0, 1.0]), Some(1));
+        assert_eq!(round_winner([SUDDEN_DEATH_HP, 0.0]), Some(0));
+    }
+
+    /// Ten seconds of P1 walking in and swinging at a hard CPU, or up to
+    /// the first KO
+    fn recorded_match(setup: ReplaySetup) -> Replay {
+        let mut fight = Fight::new(&setup);
+        let mut frames = Vec::new();
+        for number in 0..600 {
+            let p1 = PlayerInput {
+                movement: Vec2::new(if number % 90 < 60 { 1.0 } else { -1.0 }, 0.0),
+                light_attack_pressed: number % 20 == 0,
+                heavy_attack_pressed: number % 45 == 0,
+                ..Default::default()
+            };
+            let (played, _) = fight.step([p1, PlayerInput::default()]);
+            frames.push(replay_frame(number, &played, &fight.fighters));
+            if fight.round_over() {
+                break;
+            }
         }
-        if is_key_down(KeyCode::D) {
-            self.player1_pos.x += 5.0;
+        Replay {
+            metadata: ReplayMetadata {
+                version: "1.0.0".to_string(),
+                timestamp: 0,
+                duration: frames.len() as f32 * FRAME_TIME,
+                stage: String::new(),
+                characters: Vec::new(),
+                winner: String::new(),
+                setup: Some(setup),
+            },
+            frames,
+            checksum: 0,
+            rng_audit: None,
         }
     }
+
+    #[test]
+    fn a_replay_plays_back_only_from_its_own_seed_and_inputs() {
+        let setup = ReplaySetup {
+            characters: [CharacterId::Bas, CharacterId::Luca],
+            cpu: Some(CpuSettings {
+                difficulty: CpuDifficulty::Hard,
+                style: CpuStyle::Rushdown,
+            }),
+            seed: 7,
+            arena_width: 1280.0,
+        };
+        let replay = recorded_match(setup);
+        assert_eq!(resimulate(&replay), Ok(replay.frames.len()));
+
+        let mut reseeded = replay.clone();
+        reseeded.metadata.setup = Some(ReplaySetup { seed: 8, ..setup });
+        assert!(resimulate(&reseeded).is_err());
+
+        let mut tampered = replay.clone();
+        // P1 walks in on the first frame instead of swinging
+        tampered.frames[0].inputs[0].buttons = 0;
+        assert!(resimulate(&tampered).unwrap_err().starts_with("Frame 0 diverges"));
+
+        let mut old = replay;
+        old.metadata.setup = None;
+        assert!(resimulate(&old).is_err());
+    }
 }
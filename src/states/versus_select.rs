@@ -0,0 +1,364 @@
+use crate::combat::versus_ai::CpuSettings;
+use crate::coop::{CoopInputHandler, InputDevice, PlayerSlot};
+use crate::data::characters::CHARACTERS;
+use crate::data::palette;
+use crate::data::MatchSettings;
+use crate::progression::roster::{Roster, LOCKED_HERE};
+use crate::render::atlas::SpriteBatch;
+use crate::render::{CharacterSheets, SpriteAnimation};
+use crate::states::versus::{stage_color, VERSUS_STAGES};
+use crate::states::{State, StateType};
+use crate::ui::UiLayout;
+use crate::util::host::Window;
+use macroquad::prelude::*;
+
+/// Devices the two versus players read from; both share the keyboard by default
+pub const VERSUS_DEVICES: [InputDevice; 2] = [InputDevice::Keyboard, InputDevice::KeyboardArrows];
+pub const VERSUS_SLOTS: [PlayerSlot; 2] = [PlayerSlot::Player1, PlayerSlot::Player2];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SelectPhase {
+    Characters,
+    /// Both players locked in; either of them picks the stage
+    Stage,
+}
+
+/// 1v1 setup: each player picks a character on their own device, then the stage
+pub struct VersusSelectState {
+    settings: MatchSettings,
+    selected: [usize; 2],
+    ready: [bool; 2],
+    stage_index: usize,
+    phase: SelectPhase,
+    input: CoopInputHandler,
+    sheets: CharacterSheets,
+    batch: SpriteBatch,
+    /// Who the profile has unlocked; nobody locked can be picked, CPU included
+    roster: Roster,
+    transition_to: Option<StateType>,
+}
+
+impl VersusSelectState {
+    pub fn new() -> Self {
+        // Coming back from results keeps the previous picks highlighted
+        let settings = crate::data::get_match_settings()
+            .unwrap_or_else(|| MatchSettings::new(crate::data::get_selected_character()));
+        let index_of = |id| CHARACTERS.iter().position(|c| c.id == id).unwrap_or(0);
+
+        Self {
+            settings,
+            selected: settings.characters.map(index_of),
+            ready: [false, false],
+            stage_index: VERSUS_STAGES
+                .iter()
+                .position(|&stage| stage == settings.stage)
+                .unwrap_or(0),
+            phase: SelectPhase::Characters,
+            input: CoopInputHandler::new(),
+            sheets: CharacterSheets::new(),
+            batch: SpriteBatch::default(),
+            roster: Roster::load(),
+            transition_to: None,
+        }
+    }
+
+    /// Whether the character highlighted for `player` can be picked
+    fn pickable(&self, player: usize) -> bool {
+        self.roster.is_unlocked(CHARACTERS[self.selected[player]].id)
+    }
+
+    fn handle_character_input(&mut self, player: usize) {
+        let input = self
+            .input
+            .get_player_input(&Window, VERSUS_SLOTS[player], VERSUS_DEVICES[player]);
+        let count = CHARACTERS.len();
+
+        if self.ready[player] {
+            if input.back_pressed {
+                self.ready[player] = false;
+            }
+            return;
+        }
+
+        if input.left_pressed {
+            self.selected[player] = (self.selected[player] + count - 1) % count;
+        }
+        if input.right_pressed {
+            self.selected[player] = (self.selected[player] + 1) % count;
+        }
+        if input.confirm_pressed && self.pickable(player) {
+            self.ready[player] = true;
+            self.settings.characters[player] = CHARACTERS[self.selected[player]].id;
+        }
+    }
+
+    /// With P2 left to the CPU, P1 picks for both: their own character, then
+    /// the CPU's along with its difficulty (up) and style (down)
+    fn handle_cpu_input(&mut self, cpu: CpuSettings) {
+        if !self.ready[0] || self.ready[1] {
+            self.handle_character_input(0);
+            if self.ready[1] {
+                let input = self
+                    .input
+                    .get_player_input(&Window, VERSUS_SLOTS[0], VERSUS_DEVICES[0]);
+                if input.back_pressed {
+                    self.ready = [true, false];
+                }
+            }
+            return;
+        }
+
+        let input = self.input.get_player_input(&Window, VERSUS_SLOTS[0], VERSUS_DEVICES[0]);
+        let count = CHARACTERS.len();
+        if input.back_pressed {
+            self.ready[0] = false;
+            return;
+        }
+        if input.left_pressed {
+            self.selected[1] = (self.selected[1] + count - 1) % count;
+        }
+        if input.right_pressed {
+            self.selected[1] = (self.selected[1] + 1) % count;
+        }
+        if input.up_pressed {
+            self.settings.cpu = Some(CpuSettings {
+                difficulty: cpu.difficulty.next(),
+                ..cpu
+            });
+        }
+        if input.down_pressed {
+            self.settings.cpu = Some(CpuSettings {
+                style: cpu.style.next(),
+                ..cpu
+            });
+        }
+        if input.confirm_pressed && self.pickable(1) {
+            self.ready[1] = true;
+            self.settings.characters[1] = CHARACTERS[self.selected[1]].id;
+        }
+    }
+
+    fn handle_stage_input(&mut self) {
+        let count = VERSUS_STAGES.len();
+
+        for player in 0..2 {
+            let input = self
+                .input
+                .get_player_input(&Window, VERSUS_SLOTS[player], VERSUS_DEVICES[player]);
+            if input.left_pressed {
+                self.stage_index = (self.stage_index + count - 1) % count;
+            }
+            if input.right_pressed {
+                self.stage_index = (self.stage_index + 1) % count;
+            }
+            if input.back_pressed {
+                self.phase = SelectPhase::Characters;
+                self.ready = [false, false];
+                return;
+            }
+            if input.confirm_pressed {
+                self.settings.stage = VERSUS_STAGES[self.stage_index];
+                crate::data::set_match_settings(self.settings);
+                crate::data::set_selected_character(self.settings.characters[0]);
+                self.transition_to = Some(StateType::Versus);
+                return;
+            }
+        }
+    }
+
+    fn render_player_panel(&mut self, ui: &UiLayout, player: usize) {
+        let panel_width = 620.0;
+        let panel_height = 640.0;
+        let x = if player == 0 {
+            ui.width() * 0.5 - panel_width - 40.0
+        } else {
+            ui.width() * 0.5 + 40.0
+        };
+        let y = 200.0;
+        let color = palette::active().player_color(player);
+
+        let border = if self.ready[player] { GREEN } else { color };
+        draw_rectangle(x, y, panel_width, panel_height, Color::new(0.0, 0.0, 0.0, 0.4));
+        draw_rectangle_lines(x, y, panel_width, panel_height, 4.0, border);
+        let cpu = self.settings.cpu.filter(|_| player == 1);
+        let title = match cpu {
+            Some(_) => "CPU".to_string(),
+            None => format!("PLAYER {}", player + 1),
+        };
+        draw_text(&title, x + 20.0, y + 45.0, 36.0, color);
+
+        let (status, status_color) = if self.ready[player] {
+            ("READY!", GREEN)
+        } else if cpu.is_some() && !self.ready[0] {
+            ("Waiting for P1...", WHITE)
+        } else if !self.pickable(player) {
+            (LOCKED_HERE, ORANGE)
+        } else {
+            ("Selecting...", WHITE)
+        };
+        draw_text(status, x + 20.0, y + 80.0, 24.0, status_color);
+        if let Some(cpu) = cpu {
+            let setup = format!(
+                "UP: {}  |  DOWN: {}",
+                cpu.difficulty.label(),
+                cpu.style.label()
+            );
+            draw_text(&setup, x + 20.0, y + 112.0, 22.0, YELLOW);
+        }
+
+        let character = &CHARACTERS[self.selected[player]];
+        let sheet = CharacterSheets::sheet_for(character.id);
+        let animation = if self.ready[player] {
+            SpriteAnimation::Cheer
+        } else {
+            SpriteAnimation::Idle
+        };
+        let preview = Vec2::new(x + panel_width * 0.5, y + 330.0);
+        draw_ellipse(preview.x, preview.y + 70.0, 60.0, 18.0, 0.0, Color::new(0.0, 0.0, 0.0, 0.4));
+        // Players face each other across the screen
+        self.sheets.queue(
+            &mut self.batch,
+            sheet,
+            animation,
+            get_time() as f32,
+            0.0,
+            preview,
+            player == 1,
+            WHITE,
+        );
+
+        let info_y = y + 480.0;
+        draw_text(&format!("< {} >", character.name), x + 20.0, info_y, 40.0, WHITE);
+        draw_text(
+            &format!("Ability: {}", character.ability_name),
+            x + 20.0,
+            info_y + 40.0,
+            24.0,
+            YELLOW,
+        );
+        draw_text(
+            &format!(
+                "Duration: {:.0}s | Cooldown: {:.0}s",
+                character.duration, character.cooldown
+            ),
+            x + 20.0,
+            info_y + 72.0,
+            20.0,
+            GRAY,
+        );
+    }
+
+    fn render_stage_picker(&self, ui: &UiLayout) {
+        let stage = VERSUS_STAGES[self.stage_index];
+        let width = 900.0;
+        let height = 260.0;
+        let x = ui.width() * 0.5 - width * 0.5;
+        let y = ui.height() * 0.5 - height * 0.5;
+
+        draw_rectangle(0.0, 0.0, ui.width(), ui.height(), Color::new(0.0, 0.0, 0.0, 0.6));
+        draw_rectangle(x, y, width, height, stage_color(stage));
+        draw_rectangle_lines(x, y, width, height, 4.0, YELLOW);
+
+        let title = "SELECT STAGE";
+        let title_dims = measure_text(title, None, 40, 1.0);
+        draw_text(title, x + width * 0.5 - title_dims.width * 0.5, y + 60.0, 40.0, YELLOW);
+
+        let name = format!("< {} >", stage.display_name().to_uppercase());
+        let name_dims = measure_text(&name, None, 56, 1.0);
+        draw_text(&name, x + width * 0.5 - name_dims.width * 0.5, y + 150.0, 56.0, WHITE);
+
+        let count = format!("{}/{}", self.stage_index + 1, VERSUS_STAGES.len());
+        let count_dims = measure_text(&count, None, 22, 1.0);
+        draw_text(
+            &count,
+            x + width * 0.5 - count_dims.width * 0.5,
+            y + 200.0,
+            22.0,
+            Color::new(1.0, 1.0, 1.0, 0.7),
+        );
+    }
+}
+
+impl State for VersusSelectState {
+    fn enter(&mut self) {
+        self.ready = [false, false];
+        self.roster = Roster::load();
+        self.phase = SelectPhase::Characters;
+        self.transition_to = None;
+    }
+
+    fn exit(&mut self) {}
+
+    fn update(&mut self, _dt: f32) {
+        if self.phase == SelectPhase::Characters && self.ready == [true, true] {
+            self.phase = SelectPhase::Stage;
+        }
+    }
+
+    fn fixed_update(&mut self, _dt: f64) {}
+
+    fn render(&mut self, _interpolation: f32) {
+        clear_background(Color::new(0.1, 0.1, 0.15, 1.0));
+        let ui = UiLayout::current();
+        ui.begin();
+
+        let title = "VERSUS - CHARACTER SELECT";
+        let title_dims = measure_text(title, None, 56, 1.0);
+        draw_text(title, ui.width() * 0.5 - title_dims.width * 0.5, 90.0, 56.0, YELLOW);
+
+        let instructions = "P1: A/D=Select | J=Ready | K=Unready  |  \
+                            P2: Arrows=Select | ENTER=Ready | BACKSPACE=Unready  |  \
+                            TAB=P2 vs CPU";
+        let inst_dims = measure_text(instructions, None, 22, 1.0);
+        draw_text(
+            instructions,
+            ui.width() * 0.5 - inst_dims.width * 0.5,
+            140.0,
+            22.0,
+            WHITE,
+        );
+
+        self.render_player_panel(&ui, 0);
+        self.render_player_panel(&ui, 1);
+        self.sheets.flush(&mut self.batch);
+
+        let vs_dims = measure_text("VS", None, 80, 1.0);
+        draw_text("VS", ui.width() * 0.5 - vs_dims.width * 0.5, 540.0, 80.0, WHITE);
+
+        if self.phase == SelectPhase::Stage {
+            self.render_stage_picker(&ui);
+        }
+
+        ui.end();
+    }
+
+    fn handle_input(&mut self) {
+        match self.phase {
+            SelectPhase::Characters => {
+                if is_key_pressed(KeyCode::Tab) {
+                    self.settings.cpu = match self.settings.cpu {
+                        Some(_) => None,
+                        None => Some(CpuSettings::default()),
+                    };
+                    self.ready[1] = false;
+                }
+                match self.settings.cpu {
+                    Some(cpu) => self.handle_cpu_input(cpu),
+                    None => {
+                        self.handle_character_input(0);
+                        self.handle_character_input(1);
+                    }
+                }
+            }
+            SelectPhase::Stage => self.handle_stage_input(),
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.transition_to = Some(StateType::Menu);
+        }
+    }
+
+    fn should_transition(&self) -> Option<StateType> {
+        self.transition_to
+    }
+}
@@ -0,0 +1,31 @@
+use crate::progression::{PlayerAccount, PrestigeBadge};
+use macroquad::prelude::*;
+
+pub const BANNER_HEIGHT: f32 = 56.0;
+
+/// The account's banner strip in its colour, with the worn title, the level
+/// and the prestige badge once there is one
+pub fn render_account_banner(account: &PlayerAccount, x: f32, y: f32, width: f32) {
+    let progression = &account.progression;
+    draw_rectangle(x, y, width, BANNER_HEIGHT, progression.banner_color());
+    draw_rectangle_lines(x, y, width, BANNER_HEIGHT, 2.0, Color::new(1.0, 1.0, 1.0, 0.6));
+
+    let title = progression.profile_title.to_uppercase();
+    draw_text(&title, x + 16.0, y + 24.0, 24.0, WHITE);
+    let level = format!("Account Lv.{}", progression.level);
+    draw_text(&level, x + 16.0, y + 46.0, 18.0, LIGHTGRAY);
+
+    if let Some(badge) = account.badge() {
+        let level = account.prestige.prestige_level;
+        render_prestige_badge(badge, level, x + width - 36.0, y + BANNER_HEIGHT * 0.5, 20.0);
+    }
+}
+
+/// A diamond in the badge tier's colour with the prestige count on it
+pub fn render_prestige_badge(badge: PrestigeBadge, prestige_level: u32, x: f32, y: f32, size: f32) {
+    draw_poly(x, y, 4, size, 0.0, badge.color());
+    draw_poly_lines(x, y, 4, size, 0.0, 2.0, WHITE);
+    let count = prestige_level.to_string();
+    let dims = measure_text(&count, None, size as u16, 1.0);
+    draw_text(&count, x - dims.width * 0.5, y + dims.height * 0.5, size, BLACK);
+}
@@ -0,0 +1,211 @@
+use crate::data::GameConfig;
+use crate::plugins::{on_off, Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+use crate::ui::input_display::{self, InputHistory};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Height of the scoreboard strip along the bottom of the screen
+const STRIP_HEIGHT: f32 = 64.0;
+const NAME_PLATE_WIDTH: f32 = 360.0;
+const SCORE_BOX_WIDTH: f32 = 56.0;
+const INPUT_ROWS: usize = 8;
+
+/// Colours a side's plates can be tinted with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccentColor {
+    Red,
+    Blue,
+    Green,
+    Gold,
+    Purple,
+    Pink,
+}
+
+impl AccentColor {
+    pub const ALL: [AccentColor; 6] = [
+        AccentColor::Red,
+        AccentColor::Blue,
+        AccentColor::Green,
+        AccentColor::Gold,
+        AccentColor::Purple,
+        AccentColor::Pink,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AccentColor::Red => "RED",
+            AccentColor::Blue => "BLUE",
+            AccentColor::Green => "GREEN",
+            AccentColor::Gold => "GOLD",
+            AccentColor::Purple => "PURPLE",
+            AccentColor::Pink => "PINK",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            AccentColor::Red => Color::new(0.85, 0.15, 0.15, 1.0),
+            AccentColor::Blue => Color::new(0.15, 0.4, 0.9, 1.0),
+            AccentColor::Green => Color::new(0.15, 0.7, 0.3, 1.0),
+            AccentColor::Gold => Color::new(0.95, 0.7, 0.1, 1.0),
+            AccentColor::Purple => Color::new(0.55, 0.25, 0.85, 1.0),
+            AccentColor::Pink => Color::new(0.95, 0.35, 0.65, 1.0),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let current = Self::ALL.iter().position(|&accent| accent == self).unwrap_or(0);
+        Self::ALL[(current + 1) % Self::ALL.len()]
+    }
+}
+
+/// The settings page for the overlay. Names are typed into `settings.json`
+/// directly, since the settings screen has no text entry.
+pub struct BroadcastPlugin;
+
+impl Plugin for BroadcastPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Broadcast",
+            entries: vec![
+                SettingsEntry {
+                    label: |config| format!("Overlay: {}", on_off(config.broadcast_overlay)),
+                    activate: |config| {
+                        config.broadcast_overlay = !config.broadcast_overlay;
+                        crate::data::set_broadcast_overlay(config.broadcast_overlay);
+                    },
+                },
+                SettingsEntry {
+                    label: |config| format!("P1 Accent: {}", config.broadcast_accents[0].label()),
+                    activate: |config| {
+                        config.broadcast_accents[0] = config.broadcast_accents[0].next();
+                    },
+                },
+                SettingsEntry {
+                    label: |config| format!("P2 Accent: {}", config.broadcast_accents[1].label()),
+                    activate: |config| {
+                        config.broadcast_accents[1] = config.broadcast_accents[1].next();
+                    },
+                },
+            ],
+        });
+    }
+}
+
+/// One side of the match as the overlay shows it
+pub struct BroadcastPlayer<'a> {
+    pub character: &'a str,
+    /// Rounds taken so far
+    pub score: u32,
+    pub inputs: &'a InputHistory,
+}
+
+/// Extra layer for streaming local matches: a scoreboard strip with the
+/// players' names, characters and score, the round count, and both input
+/// histories. Drawn over everything else, in screen space.
+pub struct BroadcastOverlay {
+    names: [String; 2],
+    accents: [AccentColor; 2],
+}
+
+impl BroadcastOverlay {
+    /// The overlay as configured, or `None` if it's switched off (in
+    /// settings or with `--broadcast`)
+    pub fn from_config(config: &GameConfig) -> Option<Self> {
+        if !crate::data::get_broadcast_overlay() {
+            return None;
+        }
+
+        let names = [0usize, 1].map(|player| {
+            let name = config.broadcast_names[player].trim();
+            if name.is_empty() {
+                format!("PLAYER {}", player + 1)
+            } else {
+                name.to_uppercase()
+            }
+        });
+        Some(Self {
+            names,
+            accents: config.broadcast_accents,
+        })
+    }
+
+    pub fn render(
+        &self,
+        players: [BroadcastPlayer; 2],
+        round: u32,
+        rounds_to_win: u32,
+        input_labels: &[&str],
+    ) {
+        let top = screen_height() - STRIP_HEIGHT;
+        let mid = screen_width() * 0.5;
+        let backing = Color::new(0.05, 0.05, 0.08, 0.9);
+        draw_rectangle(0.0, top, screen_width(), STRIP_HEIGHT, backing);
+
+        for (side, player) in players.iter().enumerate() {
+            self.render_plate(side, player, top);
+
+            let input_y = top - 12.0 - INPUT_ROWS as f32 * 24.0;
+            let input_x = if side == 0 {
+                28.0
+            } else {
+                screen_width() - 162.0
+            };
+            input_display::render(player.inputs, input_labels, input_x, input_y, INPUT_ROWS);
+        }
+
+        let round_text = format!("ROUND {}", round);
+        let dims = measure_text(&round_text, None, 26, 1.0);
+        draw_text(&round_text, mid - dims.width * 0.5, top + 28.0, 26.0, WHITE);
+
+        let format_text = format!("FIRST TO {}", rounds_to_win);
+        let dims = measure_text(&format_text, None, 16, 1.0);
+        draw_text(&format_text, mid - dims.width * 0.5, top + 50.0, 16.0, LIGHTGRAY);
+    }
+
+    /// Name plate and score box; P2's mirror P1's from the right edge
+    fn render_plate(&self, side: usize, player: &BroadcastPlayer, top: f32) {
+        let accent = self.accents[side].color();
+        let (plate_x, score_x) = if side == 0 {
+            (0.0, NAME_PLATE_WIDTH)
+        } else {
+            let plate_x = screen_width() - NAME_PLATE_WIDTH;
+            (plate_x, plate_x - SCORE_BOX_WIDTH)
+        };
+
+        draw_rectangle(
+            plate_x,
+            top,
+            NAME_PLATE_WIDTH,
+            STRIP_HEIGHT,
+            Color::new(accent.r, accent.g, accent.b, 0.35),
+        );
+        // Accent stripe along the plate's top edge
+        draw_rectangle(plate_x, top, NAME_PLATE_WIDTH, 4.0, accent);
+        draw_rectangle(score_x, top, SCORE_BOX_WIDTH, STRIP_HEIGHT, accent);
+
+        let name = &self.names[side];
+        let character = player.character.to_uppercase();
+        let (name_x, character_x) = if side == 0 {
+            (plate_x + 20.0, plate_x + 20.0)
+        } else {
+            let right = plate_x + NAME_PLATE_WIDTH - 20.0;
+            (
+                right - measure_text(name, None, 28, 1.0).width,
+                right - measure_text(&character, None, 18, 1.0).width,
+            )
+        };
+        draw_text(name, name_x, top + 30.0, 28.0, WHITE);
+        draw_text(&character, character_x, top + 52.0, 18.0, LIGHTGRAY);
+
+        let score = player.score.to_string();
+        let dims = measure_text(&score, None, 44, 1.0);
+        draw_text(
+            &score,
+            score_x + SCORE_BOX_WIDTH * 0.5 - dims.width * 0.5,
+            top + 47.0,
+            44.0,
+            WHITE,
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use macroquad::prelude::*;
+
+/// Lines the console keeps before dropping the oldest
+const MAX_LOG: usize = 200;
+/// Share of the screen the console covers when down
+const CONSOLE_HEIGHT: f32 = 0.45;
+/// Seconds it takes to drop down or roll up
+const SLIDE_TIME: f32 = 0.12;
+
+/// What a command hands back: a line for the log, or why it didn't run
+pub type CommandResult = Result<String, String>;
+
+/// One console command: the word that runs it, the arguments it takes for
+/// `help`, and the handler, given the rest of the line split on spaces
+pub struct Command<T> {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub run: fn(&mut T, &[&str]) -> CommandResult,
+}
+
+/// Console commands run against a `T`. The app keeps the commands that work
+/// anywhere; a state adds its own by keeping a registry over itself and
+/// handing lines to it from `State::run_command`.
+pub struct CommandRegistry<T> {
+    commands: Vec<Command<T>>,
+}
+
+impl<T> CommandRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: fn(&mut T, &[&str]) -> CommandResult,
+    ) -> &mut Self {
+        self.commands.push(Command { name, usage, run });
+        self
+    }
+
+    /// Run `line` against `target`; `None` when no command here has its name
+    pub fn run(&self, target: &mut T, line: &str) -> Option<CommandResult> {
+        let mut words = line.split_whitespace();
+        let name = words.next()?;
+        let args: Vec<&str> = words.collect();
+        let command =
+            self.commands.iter().find(|command| command.name.eq_ignore_ascii_case(name))?;
+        Some((command.run)(target, &args))
+    }
+
+    /// Each command as it's typed, for `help`
+    pub fn usages(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|command| format!("{} {}", command.name, command.usage).trim_end().to_string())
+            .collect()
+    }
+}
+
+impl<T> Default for CommandRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse the argument at `index` as a `T`, naming it in the error
+pub fn arg<T: std::str::FromStr>(args: &[&str], index: usize, name: &str) -> Result<T, String> {
+    let value = args.get(index).ok_or_else(|| format!("Missing {}", name))?;
+    value.parse().map_err(|_| format!("'{}' isn't a valid {}", value, name))
+}
+
+/// `on`/`off` for switch commands; nothing flips the current value
+pub fn switch(args: &[&str], current: bool) -> Result<bool, String> {
+    match args.first().map(|arg| arg.to_ascii_lowercase()).as_deref() {
+        None => Ok(!current),
+        Some("on") | Some("1") => Ok(true),
+        Some("off") | Some("0") => Ok(false),
+        Some(other) => Err(format!("'{}' isn't on or off", other)),
+    }
+}
+
+/// The drop-down developer console: a log of what ran, and a line to type
+/// into with Up and Down going back through what was entered before
+#[derive(Default)]
+pub struct DevConsole {
+    open: bool,
+    input: String,
+    log: Vec<(String, Color)>,
+    history: Vec<String>,
+    /// How far back Up has gone into `history`
+    recalled: Option<usize>,
+    /// 0 rolled up, 1 all the way down
+    drop: f32,
+}
+
+impl DevConsole {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.recalled = None;
+        // Typed characters queue up until read; the key that opened it is one
+        while get_char_pressed().is_some() {}
+    }
+
+    pub fn print(&mut self, line: impl Into<String>, color: Color) {
+        if self.log.len() == MAX_LOG {
+            self.log.remove(0);
+        }
+        self.log.push((line.into(), color));
+    }
+
+    /// Echo a line as it's run, before whatever it prints
+    pub fn echo(&mut self, line: &str) {
+        self.print(format!("> {}", line), GRAY);
+    }
+
+    /// Show what running a command came to
+    pub fn report(&mut self, result: CommandResult) {
+        match result {
+            Ok(message) if message.is_empty() => {}
+            Ok(message) => self.print(message, WHITE),
+            Err(error) => self.print(error, ORANGE),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let target = if self.open { 1.0 } else { 0.0 };
+        let step = dt / SLIDE_TIME;
+        self.drop = if self.drop < target {
+            (self.drop + step).min(target)
+        } else {
+            (self.drop - step).max(target)
+        };
+    }
+
+    /// Typing, history and Enter; the line entered this frame, if one was
+    pub fn handle_input(&mut self) -> Option<String> {
+        while let Some(c) = get_char_pressed() {
+            if !c.is_control() && c != '`' && c != '~' {
+                self.input.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.recall(true);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.recall(false);
+        }
+        if !is_key_pressed(KeyCode::Enter) {
+            return None;
+        }
+
+        self.recalled = None;
+        let line = std::mem::take(&mut self.input).trim().to_string();
+        if line.is_empty() {
+            return None;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        Some(line)
+    }
+
+    /// Put an older (or newer) entered line back on the input
+    fn recall(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let last = self.history.len() - 1;
+        self.recalled = match (self.recalled, older) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        self.input = self.recalled.map_or_else(String::new, |index| self.history[index].clone());
+    }
+
+    pub fn render(&self) {
+        if self.drop <= 0.0 {
+            return;
+        }
+
+        let height = screen_height() * CONSOLE_HEIGHT;
+        let bottom = height * self.drop;
+        let background = Color::new(0.0, 0.0, 0.0, 0.85);
+        draw_rectangle(0.0, bottom - height, screen_width(), height, background);
+        draw_line(0.0, bottom, screen_width(), bottom, 2.0, Color::new(1.0, 1.0, 0.0, 0.6));
+
+        let size = 18.0;
+        let line_height = 20.0;
+        let input_y = bottom - 12.0;
+        let cursor = if (get_time() * 2.0) as i64 % 2 == 0 { "_" } else { "" };
+        draw_text(&format!("> {}{}", self.input, cursor), 12.0, input_y, size, YELLOW);
+
+        let mut y = input_y - line_height * 1.5;
+        for (line, color) in self.log.iter().rev() {
+            if y < bottom - height {
+                break;
+            }
+            draw_text(line, 12.0, y, size, *color);
+            y -= line_height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_runs_commands_by_name_with_their_arguments() {
+        let mut registry = CommandRegistry::<i32>::new();
+        registry
+            .add("add", "<amount>", |total, args| {
+                *total += arg::<i32>(args, 0, "amount")?;
+                Ok(format!("Total {}", total))
+            })
+            .add("reset", "", |total, _| {
+                *total = 0;
+                Ok(String::new())
+            });
+
+        let mut total = 1;
+        assert_eq!(registry.run(&mut total, "ADD 4"), Some(Ok("Total 5".to_string())));
+        assert_eq!(
+            registry.run(&mut total, "add four"),
+            Some(Err("'four' isn't a valid amount".to_string()))
+        );
+        assert_eq!(registry.run(&mut total, "spawn enemy"), None);
+        assert_eq!(registry.run(&mut total, "   "), None);
+        assert_eq!(registry.usages(), vec!["add <amount>", "reset"]);
+        assert_eq!(switch(&["off"], true), Ok(false));
+        assert_eq!(switch(&[], false), Ok(true));
+    }
+}
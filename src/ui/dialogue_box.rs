@@ -0,0 +1,309 @@
+use crate::audio::mixer::{Bus, SoundRequest};
+use crate::audio::sfx::SFXManager;
+use crate::data::localization::{self, LineText};
+use crate::ui::{Anchor, UiLayout};
+use crate::util::host::Host;
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+/// Letters typed out per second, and how much faster holding fast-forward is
+const REVEAL_RATE: f32 = 45.0;
+const FAST_FORWARD: f32 = 4.0;
+/// Least time a typed-out line stays up before moving on by itself
+const MIN_HOLD: f32 = 0.8;
+/// A blip every this many letters, spaces not counted
+const BLIP_EVERY: usize = 2;
+/// Lines the backlog keeps, oldest dropped first
+const BACKLOG_SIZE: usize = 50;
+const BOX_HEIGHT: f32 = 150.0;
+const PORTRAIT_RADIUS: f32 = 46.0;
+
+#[derive(Clone, Debug)]
+pub struct DialogueLine {
+    pub speaker: String,
+    pub text: LineText,
+    /// Seconds the line is up for with auto on, typing included
+    pub duration: f32,
+}
+
+/// The box lines are spoken in: each one types out with its speaker's
+/// portrait and blips, and moves on by itself with auto on or when the
+/// player presses on. Everything said is kept for the backlog.
+pub struct DialogueBox {
+    line: Option<DialogueLine>,
+    /// Letters of the voice text shown so far
+    revealed: f32,
+    /// Seconds since the whole line was shown
+    held: f32,
+    /// Letters (spaces not counted) already blipped for
+    blipped: usize,
+    auto: bool,
+    fast_forward: bool,
+    backlog: VecDeque<DialogueLine>,
+    /// Lines scrolled back from the newest, while the backlog is open
+    backlog_scroll: Option<usize>,
+    sfx: SFXManager,
+}
+
+impl DialogueBox {
+    pub fn new() -> Self {
+        Self {
+            line: None,
+            revealed: 0.0,
+            held: 0.0,
+            blipped: 0,
+            auto: true,
+            fast_forward: false,
+            backlog: VecDeque::new(),
+            backlog_scroll: None,
+            sfx: SFXManager::new(),
+        }
+    }
+
+    pub fn show(&mut self, line: DialogueLine) {
+        if self.backlog.len() == BACKLOG_SIZE {
+            self.backlog.pop_front();
+        }
+        self.backlog.push_back(line.clone());
+        self.line = Some(line);
+        self.revealed = 0.0;
+        self.held = 0.0;
+        self.blipped = 0;
+    }
+
+    pub fn is_showing(&self) -> bool {
+        self.line.is_some()
+    }
+
+    /// Drop the current line; the backlog keeps it
+    pub fn clear(&mut self) {
+        self.line = None;
+        self.backlog_scroll = None;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        // The backlog holds the conversation where it is
+        if self.backlog_scroll.is_some() {
+            return;
+        }
+        let Some(line) = &self.line else {
+            return;
+        };
+
+        let voice = localization::spoken(&line.text).voice;
+        let letters = voice.chars().count() as f32;
+        let speed = if self.fast_forward { FAST_FORWARD } else { 1.0 };
+        if self.revealed < letters {
+            self.revealed = (self.revealed + REVEAL_RATE * speed * dt).min(letters);
+            self.blip(&voice);
+            return;
+        }
+
+        self.held += dt * speed;
+        let typing = letters / REVEAL_RATE;
+        if self.auto && self.held >= (line.duration - typing).max(MIN_HOLD) {
+            self.line = None;
+        }
+    }
+
+    /// Queue the speaker's blip for every few letters that just appeared
+    fn blip(&mut self, voice: &str) {
+        let Some(line) = &self.line else {
+            return;
+        };
+        let letters = voice
+            .chars()
+            .take(self.revealed as usize)
+            .filter(|c| !c.is_whitespace())
+            .count();
+        if letters >= self.blipped + BLIP_EVERY {
+            self.blipped = letters;
+            let blip = self.sfx.get_blip_sound(&line.speaker);
+            crate::data::queue_sound(SoundRequest::new(Bus::Voice, blip));
+        }
+    }
+
+    /// Show the rest of the line, or move past it once it's all there
+    pub fn advance(&mut self) {
+        let Some(line) = &self.line else {
+            return;
+        };
+        let letters = localization::spoken(&line.text).voice.chars().count() as f32;
+        if self.revealed < letters {
+            self.revealed = letters;
+        } else {
+            self.line = None;
+        }
+    }
+
+    /// Keys for the line on screen. True when the player skipped the rest
+    /// of the conversation, which the caller drops.
+    pub fn handle_input(&mut self, host: &dyn Host) -> bool {
+        if host.key_pressed(KeyCode::L) {
+            self.backlog_scroll = match self.backlog_scroll {
+                Some(_) => None,
+                None => Some(0),
+            };
+        }
+        if let Some(scroll) = &mut self.backlog_scroll {
+            let oldest = self.backlog.len().saturating_sub(1);
+            if host.key_pressed(KeyCode::W) || host.key_pressed(KeyCode::Up) {
+                *scroll = (*scroll + 1).min(oldest);
+            }
+            if host.key_pressed(KeyCode::S) || host.key_pressed(KeyCode::Down) {
+                *scroll = scroll.saturating_sub(1);
+            }
+            return false;
+        }
+
+        self.fast_forward = host.key_down(KeyCode::LeftControl);
+        if host.key_pressed(KeyCode::Tab) {
+            self.auto = !self.auto;
+        }
+        if host.key_pressed(KeyCode::Backspace) {
+            self.clear();
+            return true;
+        }
+        if host.key_pressed(KeyCode::Space)
+            || host.key_pressed(KeyCode::Enter)
+            || host.key_pressed(KeyCode::J)
+        {
+            self.advance();
+        }
+        false
+    }
+
+    /// Draw the box along the bottom of `ui`, and the backlog over it when
+    /// that's open
+    pub fn render(&self, ui: &UiLayout) {
+        if self.backlog_scroll.is_some() {
+            self.render_backlog(ui);
+            return;
+        }
+        let Some(line) = &self.line else {
+            return;
+        };
+
+        let box_y = ui.anchor(Anchor::BottomLeft, Vec2::new(20.0, BOX_HEIGHT + 20.0)).y;
+        let box_width = ui.width() - 40.0;
+        draw_rectangle(20.0, box_y, box_width, BOX_HEIGHT, Color::new(0.0, 0.0, 0.0, 0.9));
+        draw_rectangle_lines(20.0, box_y, box_width, BOX_HEIGHT, 3.0, WHITE);
+
+        // Portrait: the speaker's initials on their colour
+        let style = crate::data::get_subtitle_style();
+        let color = style.speaker_color(&line.speaker);
+        let center = Vec2::new(40.0 + PORTRAIT_RADIUS, box_y + BOX_HEIGHT * 0.5);
+        let backing = Color::new(color.r * 0.35, color.g * 0.35, color.b * 0.35, 1.0);
+        draw_circle(center.x, center.y, PORTRAIT_RADIUS, backing);
+        draw_circle_lines(center.x, center.y, PORTRAIT_RADIUS, 3.0, color);
+        let initials: String = line.speaker.chars().take(2).collect::<String>().to_uppercase();
+        let dims = measure_text(&initials, None, 40, 1.0);
+        draw_text(&initials, center.x - dims.width * 0.5, center.y + 14.0, 40.0, WHITE);
+
+        // Looked up as it's drawn, so a language switch shows straight away
+        let spoken = localization::spoken(&line.text);
+        let letters = spoken.voice.chars().count();
+        let shown = self.revealed as usize;
+        let text_x = center.x + PORTRAIT_RADIUS + 24.0;
+        draw_text(&line.speaker, text_x, box_y + 30.0, 28.0, color);
+        let voice: String = spoken.voice.chars().take(shown).collect();
+        draw_text(&voice, text_x, box_y + 65.0, 24.0, WHITE);
+
+        // The subtitle keeps pace with the voice, at the subtitle size
+        if let Some(subtitle) = &spoken.subtitle {
+            let share = if letters == 0 { 1.0 } else { shown as f32 / letters as f32 };
+            let count = (subtitle.chars().count() as f32 * share).ceil() as usize;
+            let subtitle: String = subtitle.chars().take(count).collect();
+            let size = style.font_size(20.0);
+            let y = box_y + 80.0 + size;
+            draw_text(&subtitle, text_x, y, size, Color::new(0.7, 0.7, 0.7, 1.0));
+        }
+
+        let right = 20.0 + box_width - 20.0;
+        if shown >= letters && (get_time() * 2.0) as i64 % 2 == 0 {
+            draw_text("▼", right - 20.0, box_y + BOX_HEIGHT - 16.0, 24.0, YELLOW);
+        }
+        if self.auto {
+            let auto = localization::tr("dialogue.auto");
+            let dims = measure_text(&auto, None, 18, 1.0);
+            draw_text(&auto, right - dims.width, box_y + 24.0, 18.0, SKYBLUE);
+        }
+        let hint = localization::tr("dialogue.hint");
+        let dims = measure_text(&hint, None, 16, 1.0);
+        let hint_color = Color::new(1.0, 1.0, 1.0, 0.5);
+        draw_text(&hint, right - dims.width - 30.0, box_y + BOX_HEIGHT - 12.0, 16.0, hint_color);
+    }
+
+    fn render_backlog(&self, ui: &UiLayout) {
+        draw_rectangle(0.0, 0.0, ui.width(), ui.height(), Color::new(0.0, 0.0, 0.0, 0.85));
+        let title = localization::tr("dialogue.backlog");
+        draw_text(&title, 60.0, 80.0, 40.0, YELLOW);
+
+        let style = crate::data::get_subtitle_style();
+        let scroll = self.backlog_scroll.unwrap_or(0);
+        let row_height = 64.0;
+        let rows = ((ui.height() - 200.0) / row_height).max(1.0) as usize;
+        let newest = self.backlog.len().saturating_sub(scroll);
+        let first = newest.saturating_sub(rows);
+        for (i, line) in self.backlog.range(first..newest).enumerate() {
+            let y = 140.0 + i as f32 * row_height;
+            let spoken = localization::spoken(&line.text);
+            let color = style.speaker_color(&line.speaker);
+            draw_text(&line.speaker, 60.0, y, 22.0, color);
+            draw_text(&spoken.voice, 240.0, y, 22.0, WHITE);
+            if let Some(subtitle) = &spoken.subtitle {
+                draw_text(subtitle, 240.0, y + 24.0, 18.0, Color::new(0.7, 0.7, 0.7, 1.0));
+            }
+        }
+
+        let hint = localization::tr("dialogue.backlog_hint");
+        draw_text(&hint, 60.0, ui.height() - 40.0, 20.0, Color::new(1.0, 1.0, 1.0, 0.6));
+    }
+}
+
+impl Default for DialogueBox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(duration: f32) -> DialogueLine {
+        DialogueLine {
+            speaker: "Luca".to_string(),
+            text: LineText::Key("abcdefghij".to_string()),
+            duration,
+        }
+    }
+
+    #[test]
+    fn lines_type_out_then_move_on_with_auto() {
+        let mut dialogue = DialogueBox::new();
+        dialogue.show(line(1.0));
+        dialogue.update(5.0 / REVEAL_RATE);
+        assert_eq!(dialogue.revealed as usize, 5);
+
+        dialogue.advance();
+        assert_eq!(dialogue.revealed, 10.0);
+        dialogue.update(MIN_HOLD * 0.5);
+        assert!(dialogue.is_showing());
+        dialogue.update(MIN_HOLD);
+        assert!(!dialogue.is_showing());
+    }
+
+    #[test]
+    fn without_auto_a_line_waits_and_the_backlog_keeps_it() {
+        let mut dialogue = DialogueBox::new();
+        dialogue.auto = false;
+        dialogue.show(line(0.5));
+        dialogue.update(60.0);
+        dialogue.update(60.0);
+        assert!(dialogue.is_showing());
+        dialogue.advance();
+        assert!(!dialogue.is_showing());
+        assert_eq!(dialogue.backlog.len(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::data::crash;
+use crate::data::save::{SaveManager, DEFAULT_PROFILE};
+use crate::ui::layout::{Anchor, UiLayout};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Spacing of the editor's snap grid, in canvas units
+pub const GRID: f32 = 20.0;
+pub const MIN_SCALE: f32 = 0.5;
+pub const MAX_SCALE: f32 = 2.0;
+
+/// Pieces of the in-game HUD the player can move around
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudWidget {
+    /// Health and stamina
+    Health,
+    /// Super meter, the ability bar and running power-ups
+    Meter,
+    Combo,
+    Allies,
+    /// Where everyone stands across the arena
+    Minimap,
+    /// The tag partner's portrait and cooldowns
+    Partner,
+}
+
+impl HudWidget {
+    pub const ALL: [HudWidget; 6] = [
+        HudWidget::Health,
+        HudWidget::Meter,
+        HudWidget::Combo,
+        HudWidget::Allies,
+        HudWidget::Minimap,
+        HudWidget::Partner,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HudWidget::Health => "Health",
+            HudWidget::Meter => "Meter",
+            HudWidget::Combo => "Combo",
+            HudWidget::Allies => "Allies",
+            HudWidget::Minimap => "Minimap",
+            HudWidget::Partner => "Partner",
+        }
+    }
+
+    /// Footprint at 1x scale, in canvas units
+    pub fn size(self) -> Vec2 {
+        match self {
+            HudWidget::Health => Vec2::new(300.0, 55.0),
+            HudWidget::Meter => Vec2::new(200.0, 95.0),
+            HudWidget::Combo => Vec2::new(220.0, 50.0),
+            HudWidget::Allies => Vec2::new(170.0, 100.0),
+            HudWidget::Minimap => Vec2::new(320.0, 50.0),
+            HudWidget::Partner => Vec2::new(200.0, 70.0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WidgetPlacement {
+    pub widget: HudWidget,
+    pub anchor: Anchor,
+    /// In from the anchor to the matching point on the widget, so a widget
+    /// anchored bottom-right keeps its corner that far from the screen's
+    pub offset: (f32, f32),
+    pub scale: f32,
+    pub visible: bool,
+}
+
+impl WidgetPlacement {
+    /// Where the widget sits on the canvas right now
+    pub fn rect(&self, ui: &UiLayout) -> Rect {
+        let size = self.widget.size() * self.scale;
+        let point = ui.anchor(self.anchor, Vec2::new(self.offset.0, self.offset.1));
+        let top_left = point - size * self.anchor.pivot();
+        Rect::new(top_left.x, top_left.y, size.x, size.y)
+    }
+
+    /// Move the widget's top-left corner to `top_left`, re-anchoring it to
+    /// whichever part of the screen it ended up in so it stays there when
+    /// the window changes shape
+    pub fn move_to(&mut self, top_left: Vec2, ui: &UiLayout) {
+        let size = self.widget.size() * self.scale;
+        let center = top_left + size * 0.5;
+        let left = center.x < ui.width() / 3.0;
+        let right = center.x > ui.width() * 2.0 / 3.0;
+        let top = center.y < ui.height() * 0.5;
+        self.anchor = match (top, left, right) {
+            (true, true, _) => Anchor::TopLeft,
+            (true, _, true) => Anchor::TopRight,
+            (true, _, _) => Anchor::Top,
+            (false, true, _) => Anchor::BottomLeft,
+            (false, _, true) => Anchor::BottomRight,
+            (false, _, _) => Anchor::Bottom,
+        };
+        let offset = ui.offset_from(self.anchor, top_left + size * self.anchor.pivot());
+        self.offset = (offset.x, offset.y);
+    }
+
+    /// Resize about the widget's centre
+    pub fn set_scale(&mut self, scale: f32, ui: &UiLayout) {
+        let center = self.rect(ui).center();
+        self.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+        let size = self.widget.size() * self.scale;
+        self.move_to(center - size * 0.5, ui);
+    }
+}
+
+/// Starting points for a layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudPreset {
+    Default,
+    /// Health, meter and combo only, a little smaller
+    Minimal,
+    /// Kept inside the broadcast safe area and out of the bottom-right
+    /// corner, where a facecam usually goes
+    StreamerSafe,
+}
+
+impl HudPreset {
+    pub const ALL: [HudPreset; 3] = [
+        HudPreset::Default,
+        HudPreset::Minimal,
+        HudPreset::StreamerSafe,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HudPreset::Default => "Default",
+            HudPreset::Minimal => "Minimal",
+            HudPreset::StreamerSafe => "Streamer-safe",
+        }
+    }
+
+    pub fn layout(self) -> HudLayout {
+        let place = |widget, anchor, x, y, scale, visible| WidgetPlacement {
+            widget,
+            anchor,
+            offset: (x, y),
+            scale,
+            visible,
+        };
+        let widgets = match self {
+            HudPreset::Default => vec![
+                place(HudWidget::Health, Anchor::TopLeft, 50.0, 50.0, 1.0, true),
+                place(HudWidget::Meter, Anchor::TopLeft, 50.0, 120.0, 1.0, true),
+                place(HudWidget::Combo, Anchor::TopRight, 240.0, 260.0, 1.0, true),
+                place(HudWidget::Allies, Anchor::TopLeft, 50.0, 230.0, 1.0, true),
+                place(HudWidget::Minimap, Anchor::Bottom, 0.0, 30.0, 1.0, true),
+                place(HudWidget::Partner, Anchor::TopLeft, 50.0, 345.0, 1.0, true),
+            ],
+            HudPreset::Minimal => vec![
+                place(HudWidget::Health, Anchor::TopLeft, 40.0, 40.0, 0.8, true),
+                place(HudWidget::Meter, Anchor::TopLeft, 40.0, 100.0, 0.8, true),
+                place(HudWidget::Combo, Anchor::TopRight, 240.0, 260.0, 0.8, true),
+                place(HudWidget::Allies, Anchor::TopLeft, 40.0, 180.0, 0.8, false),
+                place(HudWidget::Minimap, Anchor::Bottom, 0.0, 30.0, 0.8, false),
+                place(HudWidget::Partner, Anchor::TopLeft, 40.0, 180.0, 0.8, true),
+            ],
+            // 5% of the canvas in from every edge
+            HudPreset::StreamerSafe => vec![
+                place(HudWidget::Health, Anchor::TopLeft, 100.0, 60.0, 1.0, true),
+                place(HudWidget::Meter, Anchor::TopLeft, 100.0, 130.0, 1.0, true),
+                place(HudWidget::Combo, Anchor::Top, 0.0, 200.0, 1.0, true),
+                place(HudWidget::Allies, Anchor::BottomLeft, 100.0, 60.0, 1.0, true),
+                place(HudWidget::Minimap, Anchor::Top, 0.0, 60.0, 1.0, true),
+                place(HudWidget::Partner, Anchor::BottomLeft, 100.0, 180.0, 1.0, true),
+            ],
+        };
+        HudLayout { widgets }
+    }
+}
+
+/// Where each HUD widget goes, saved with the profile
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct HudLayout {
+    pub widgets: Vec<WidgetPlacement>,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        HudPreset::Default.layout()
+    }
+}
+
+impl HudLayout {
+    /// The layout saved with the profile; a new profile gets the default
+    pub fn load() -> Self {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        match saves.load_or_create(profile, DEFAULT_PROFILE) {
+            Ok(save) => save.hud_layout.clone(),
+            Err(e) => {
+                crash::log(format!("Failed to load HUD layout: {}", e));
+                Self::default()
+            }
+        }
+    }
+
+    /// Write the layout back into the profile, leaving the rest of it be
+    pub fn save(&self) -> Result<(), String> {
+        let mut saves = SaveManager::new();
+        let profile = crate::data::get_active_profile();
+        saves.load_or_create(profile, DEFAULT_PROFILE)?.hud_layout = self.clone();
+        saves.save_to_slot(profile)
+    }
+
+    /// The widget's placement. One a saved layout predates comes from the
+    /// default layout.
+    pub fn placement(&self, widget: HudWidget) -> WidgetPlacement {
+        self.widgets
+            .iter()
+            .find(|placement| placement.widget == widget)
+            .copied()
+            .unwrap_or_else(|| HudPreset::Default.layout().placement(widget))
+    }
+
+    pub fn placement_mut(&mut self, widget: HudWidget) -> &mut WidgetPlacement {
+        let index = match self.widgets.iter().position(|placement| placement.widget == widget) {
+            Some(index) => index,
+            None => {
+                let placement = HudPreset::Default.layout().placement(widget);
+                self.widgets.push(placement);
+                self.widgets.len() - 1
+            }
+        };
+        &mut self.widgets[index]
+    }
+
+    /// Draw a widget where the layout puts it. `draw` works in the widget's
+    /// own units, with 0,0 at its top-left corner.
+    pub fn draw(&self, ui: &UiLayout, widget: HudWidget, draw: impl FnOnce()) {
+        let placement = self.placement(widget);
+        if !placement.visible {
+            return;
+        }
+        let rect = placement.rect(ui);
+        ui.begin_at(rect.point(), placement.scale);
+        draw();
+        ui.begin();
+    }
+}
+
+/// Round to the nearest grid line
+pub fn snap(value: f32) -> f32 {
+    (value / GRID).round() * GRID
+}
@@ -0,0 +1,162 @@
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+/// Changes of input kept per history; older ones fall off the bottom
+pub const HISTORY_LENGTH: usize = 20;
+
+const ROW_HEIGHT: f32 = 24.0;
+const COLUMN_WIDTH: f32 = 150.0;
+
+/// One line of the history: a direction and set of buttons held unchanged
+/// for `frames` ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputEntry {
+    /// Numpad notation: 5 is neutral, 6 forward (right), 2 down, 8 up
+    pub direction: u8,
+    /// Bit `i` is button `i` of whatever label set the history is drawn with
+    pub buttons: u8,
+    pub frames: u32,
+}
+
+/// Rolling record of what a player held, one tick at a time. A new entry
+/// starts whenever the direction or buttons change.
+pub struct InputHistory {
+    entries: VecDeque<InputEntry>,
+    capacity: usize,
+}
+
+impl InputHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record one tick of input
+    pub fn push(&mut self, direction: u8, buttons: u8) {
+        if let Some(last) = self.entries.back_mut() {
+            if last.direction == direction && last.buttons == buttons {
+                last.frames = last.frames.saturating_add(1);
+                return;
+            }
+        }
+
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(InputEntry {
+            direction,
+            buttons,
+            frames: 1,
+        });
+    }
+
+    /// Newest first
+    pub fn entries(&self) -> impl Iterator<Item = &InputEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Numpad direction for a stick or set of keys, in screen space (negative y is up)
+pub fn numpad_direction(x: f32, y: f32) -> u8 {
+    let column = if x < 0.0 {
+        0
+    } else if x > 0.0 {
+        2
+    } else {
+        1
+    };
+    let row = if y > 0.0 {
+        0
+    } else if y < 0.0 {
+        2
+    } else {
+        1
+    };
+    1 + column + row * 3
+}
+
+/// Pack held buttons into a bit set; bit `i` is `held[i]`
+pub fn pack_buttons(held: &[bool]) -> u8 {
+    held.iter()
+        .enumerate()
+        .filter(|(_, down)| **down)
+        .fold(0, |bits, (bit, _)| bits | 1 << bit)
+}
+
+/// Draw the history as a column growing down from `(x, y)`: the frame count,
+/// an arrow for the direction and the labels of the held buttons
+pub fn render(history: &InputHistory, labels: &[&str], x: f32, y: f32, rows: usize) {
+    if history.is_empty() || rows == 0 {
+        return;
+    }
+    let shown = history.len().min(rows);
+
+    draw_rectangle(
+        x - 8.0,
+        y - 4.0,
+        COLUMN_WIDTH,
+        shown as f32 * ROW_HEIGHT + 8.0,
+        Color::new(0.0, 0.0, 0.0, 0.5),
+    );
+
+    for (row, entry) in history.entries().take(rows).enumerate() {
+        let top = y + row as f32 * ROW_HEIGHT;
+        let fade = 1.0 - row as f32 / rows as f32 * 0.6;
+        let color = Color::new(1.0, 1.0, 1.0, fade);
+
+        let frames = if entry.frames > 99 {
+            "99+".to_string()
+        } else {
+            entry.frames.to_string()
+        };
+        draw_text(&frames, x, top + 17.0, 18.0, Color::new(0.7, 0.7, 0.7, fade));
+        draw_direction(entry.direction, x + 48.0, top + ROW_HEIGHT * 0.5, color);
+
+        let pressed: Vec<&str> = labels
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| entry.buttons & 1 << bit != 0)
+            .map(|(_, label)| *label)
+            .collect();
+        draw_text(
+            &pressed.join(" "),
+            x + 66.0,
+            top + 17.0,
+            18.0,
+            Color::new(1.0, 0.85, 0.2, fade),
+        );
+    }
+}
+
+/// A dot for neutral, otherwise an arrow pointing the numpad direction
+fn draw_direction(direction: u8, x: f32, y: f32, color: Color) {
+    if direction == 5 || !(1..=9).contains(&direction) {
+        draw_circle(x, y, 3.0, color);
+        return;
+    }
+
+    let index = direction - 1;
+    let dx = (index % 3) as f32 - 1.0;
+    let dy = 1.0 - (index / 3) as f32;
+    let dir = Vec2::new(dx, dy).normalize() * 8.0;
+    let tip = Vec2::new(x, y) + dir;
+    let tail = Vec2::new(x, y) - dir;
+    draw_line(tail.x, tail.y, tip.x, tip.y, 2.0, color);
+
+    let side = dir.perp() * 0.5;
+    draw_triangle(tip + dir * 0.4, tip - dir * 0.2 + side, tip - dir * 0.2 - side, color);
+}
@@ -0,0 +1,156 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Size UI is laid out against. The canvas grows along whichever axis the
+/// window is wider or taller than 16:9, so anchored elements reach the edges.
+pub const CANVAS_WIDTH: f32 = 1920.0;
+pub const CANVAS_HEIGHT: f32 = 1080.0;
+
+/// How the canvas is scaled onto the window
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UiScaleMode {
+    /// Largest scale that fits, fractional if need be
+    #[default]
+    Fit,
+    /// Snap to whole multiples (or whole fractions below 1x) for crisp pixels
+    Integer,
+}
+
+impl UiScaleMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            UiScaleMode::Fit => "FIT",
+            UiScaleMode::Integer => "INTEGER",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            UiScaleMode::Fit => UiScaleMode::Integer,
+            UiScaleMode::Integer => UiScaleMode::Fit,
+        }
+    }
+}
+
+/// Point on the canvas that positions are measured from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Center,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Where on a box the anchor sits, as a fraction of its size, so a box
+    /// placed against the bottom-right corner grows up and to the left
+    pub fn pivot(self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => Vec2::new(0.0, 0.0),
+            Anchor::Top => Vec2::new(0.5, 0.0),
+            Anchor::TopRight => Vec2::new(1.0, 0.0),
+            Anchor::Center => Vec2::new(0.5, 0.5),
+            Anchor::BottomLeft => Vec2::new(0.0, 1.0),
+            Anchor::Bottom => Vec2::new(0.5, 1.0),
+            Anchor::BottomRight => Vec2::new(1.0, 1.0),
+        }
+    }
+}
+
+/// The canvas mapping for the current window. Rebuild it each frame, since the
+/// window and scale mode can change at any time.
+#[derive(Clone, Copy, Debug)]
+pub struct UiLayout {
+    scale: f32,
+    size: Vec2,
+}
+
+impl UiLayout {
+    pub fn current() -> Self {
+        Self::for_screen(
+            Vec2::new(screen_width(), screen_height()),
+            crate::data::get_ui_scale_mode(),
+        )
+    }
+
+    pub fn for_screen(screen: Vec2, mode: UiScaleMode) -> Self {
+        let fit = (screen.x / CANVAS_WIDTH).min(screen.y / CANVAS_HEIGHT).max(0.01);
+        let scale = match mode {
+            UiScaleMode::Fit => fit,
+            UiScaleMode::Integer if fit >= 1.0 => fit.floor(),
+            UiScaleMode::Integer => 1.0 / (1.0 / fit).ceil(),
+        };
+
+        Self {
+            scale,
+            size: screen / scale,
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.size.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.size.y
+    }
+
+    /// Canvas position `offset` away from `anchor`; offsets point inwards, so
+    /// `(20, 20)` from `BottomRight` is 20 units up and left of the corner
+    pub fn anchor(&self, anchor: Anchor, offset: Vec2) -> Vec2 {
+        let (origin, direction) = self.origin(anchor);
+        origin + offset * direction
+    }
+
+    /// The offset from `anchor` that lands on `point`; undoes `anchor`
+    pub fn offset_from(&self, anchor: Anchor, point: Vec2) -> Vec2 {
+        let (origin, direction) = self.origin(anchor);
+        (point - origin) * direction
+    }
+
+    /// Anchor's canvas position and which way is inwards from it
+    fn origin(&self, anchor: Anchor) -> (Vec2, Vec2) {
+        let (x, y) = (self.size.x, self.size.y);
+        match anchor {
+            Anchor::TopLeft => (Vec2::ZERO, Vec2::new(1.0, 1.0)),
+            Anchor::Top => (Vec2::new(x * 0.5, 0.0), Vec2::new(1.0, 1.0)),
+            Anchor::TopRight => (Vec2::new(x, 0.0), Vec2::new(-1.0, 1.0)),
+            Anchor::Center => (Vec2::new(x * 0.5, y * 0.5), Vec2::new(1.0, 1.0)),
+            Anchor::BottomLeft => (Vec2::new(0.0, y), Vec2::new(1.0, -1.0)),
+            Anchor::Bottom => (Vec2::new(x * 0.5, y), Vec2::new(1.0, -1.0)),
+            Anchor::BottomRight => (Vec2::new(x, y), Vec2::new(-1.0, -1.0)),
+        }
+    }
+
+    /// Convert a screen-space point (e.g. from `GameCamera::world_to_screen`)
+    pub fn to_canvas(self, screen_pos: Vec2) -> Vec2 {
+        screen_pos / self.scale
+    }
+
+    /// Draw in canvas units until `end`
+    pub fn begin(&self) {
+        set_camera(&Camera2D {
+            target: self.size * 0.5,
+            zoom: Vec2::new(2.0 / self.size.x, 2.0 / self.size.y),
+            ..Default::default()
+        });
+    }
+
+    /// Like `begin`, but with `origin` as 0,0 and every unit `scale` canvas
+    /// units, for drawing a widget the player has moved and resized
+    pub fn begin_at(&self, origin: Vec2, scale: f32) {
+        let view = self.size / scale;
+        set_camera(&Camera2D {
+            target: view * 0.5 - origin / scale,
+            zoom: Vec2::new(2.0 / view.x, 2.0 / view.y),
+            ..Default::default()
+        });
+    }
+
+    pub fn end(&self) {
+        set_default_camera();
+    }
+}
@@ -1,147 +1,301 @@
+use crate::data::localization::tr;
+use crate::ui::UiLayout;
 use macroquad::prelude::*;
 
+/// The main menu as a hub: a column of categories, each holding the modes
+/// under it, picked with left and right. Every category remembers the mode
+/// last picked in it.
 pub struct MenuUI {
     pub selected_index: usize,
-    pub menu_items: Vec<MenuItem>,
+    pub categories: Vec<MenuCategory>,
+    /// Mode picked in each category, by category
+    pub selected_items: Vec<usize>,
     pub transition_timer: f32,
-    pub background_scroll: f32,
+    /// Eases the selection highlight from the last category to this one
+    highlight_y: f32,
+}
+
+pub struct MenuCategory {
+    /// Locale key of the heading
+    pub label: &'static str,
+    pub items: Vec<MenuItem>,
 }
 
 #[derive(Clone)]
 pub struct MenuItem {
-    pub text: String,
+    /// Locale key of the label
+    pub label: &'static str,
     pub enabled: bool,
     pub action: MenuAction,
-    pub position: Vec2,
-    pub target_position: Vec2,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// Where picking a mode on the hub goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MenuAction {
-    StartGame,
-    Settings,
-    Training,
+    Story,
+    Coop,
     Versus,
-    Credits,
+    Training,
+    Practice,
+    Endless,
+    WinterArc,
+    BossRush,
+    DailyChallenge,
+    WeeklyChallenge,
+    SkillTree,
+    Achievements,
+    Account,
+    Settings,
+    Controls,
+    Profiles,
+    Mods,
+    CheckForUpdates,
     Exit,
-    Back,
-    None,
 }
 
-impl MenuUI {
-    pub fn new(items: Vec<String>) -> Self {
-        let mut menu_items = Vec::new();
-        let start_y = 300.0;
-        let spacing = 60.0;
-
-        for (i, text) in items.into_iter().enumerate() {
-            let action = match text.as_str() {
-                "START GAME" | "STORY" => MenuAction::StartGame,
-                "SETTINGS" => MenuAction::Settings,
-                "TRAINING" => MenuAction::Training,
-                "VERSUS" => MenuAction::Versus,
-                "CREDITS" => MenuAction::Credits,
-                "EXIT" | "QUIT" => MenuAction::Exit,
-                "BACK" => MenuAction::Back,
-                _ => MenuAction::None,
-            };
+impl MenuItem {
+    fn new(label: &'static str, action: MenuAction) -> Self {
+        Self {
+            label,
+            enabled: true,
+            action,
+        }
+    }
 
-            let y = start_y + i as f32 * spacing;
-            menu_items.push(MenuItem {
-                text,
-                enabled: true,
-                action,
-                position: Vec2::new(screen_width() * 0.5, y),
-                target_position: Vec2::new(screen_width() * 0.5, y),
-            });
+    /// Listed so players know it's coming, but can't be picked yet
+    fn coming_soon(label: &'static str, action: MenuAction) -> Self {
+        Self {
+            label,
+            enabled: false,
+            action,
         }
+    }
+}
+
+impl MenuCategory {
+    fn new(label: &'static str, items: Vec<MenuItem>) -> Self {
+        Self { label, items }
+    }
+}
 
+impl MenuUI {
+    pub fn new(categories: Vec<MenuCategory>) -> Self {
         Self {
             selected_index: 0,
-            menu_items,
+            selected_items: vec![0; categories.len()],
+            categories,
             transition_timer: 0.0,
-            background_scroll: 0.0,
+            highlight_y: 0.0,
         }
     }
 
+    /// Every mode the game has, grouped the way the main menu shows them
+    pub fn hub() -> Self {
+        use MenuAction::*;
+
+        Self::new(vec![
+            MenuCategory::new(
+                "menu.hub.story",
+                vec![MenuItem::new("menu.story", Story), MenuItem::new("menu.coop", Coop)],
+            ),
+            MenuCategory::new("menu.hub.versus", vec![MenuItem::new("menu.versus", Versus)]),
+            MenuCategory::new(
+                "menu.hub.training",
+                vec![
+                    MenuItem::new("menu.training", Training),
+                    MenuItem::new("menu.practice", Practice),
+                ],
+            ),
+            MenuCategory::new(
+                "menu.hub.survival",
+                vec![
+                    MenuItem::new("menu.endless", Endless),
+                    MenuItem::new("menu.winter_arc", WinterArc),
+                ],
+            ),
+            MenuCategory::new(
+                "menu.hub.boss_rush",
+                vec![MenuItem::coming_soon("menu.boss_rush", BossRush)],
+            ),
+            MenuCategory::new(
+                "menu.hub.challenges",
+                vec![
+                    MenuItem::new("menu.daily", DailyChallenge),
+                    MenuItem::new("menu.weekly", WeeklyChallenge),
+                ],
+            ),
+            MenuCategory::new(
+                "menu.hub.shop",
+                vec![
+                    MenuItem::new("menu.skill_tree", SkillTree),
+                    MenuItem::new("menu.achievements", Achievements),
+                    MenuItem::new("menu.account", Account),
+                ],
+            ),
+            MenuCategory::new(
+                "menu.hub.settings",
+                vec![
+                    MenuItem::new("menu.settings", Settings),
+                    MenuItem::new("menu.controls", Controls),
+                    MenuItem::new("menu.profiles", Profiles),
+                    MenuItem::new("menu.mods", Mods),
+                ],
+            ),
+            MenuCategory::new(
+                "menu.hub.updater",
+                vec![MenuItem::new("menu.check_updates", CheckForUpdates)],
+            ),
+            MenuCategory::new("menu.exit", vec![MenuItem::new("menu.exit", Exit)]),
+        ])
+    }
+
     pub fn update(&mut self, dt: f32) {
         self.transition_timer += dt;
-        self.background_scroll += dt * 20.0;
-
-        for item in &mut self.menu_items {
-            item.position = item.position.lerp(item.target_position, 0.1);
-        }
+        let target = self.selected_index as f32;
+        self.highlight_y += (target - self.highlight_y) * (dt * 14.0).min(1.0);
     }
 
     pub fn navigate_up(&mut self) {
         if self.selected_index > 0 {
             self.selected_index -= 1;
         } else {
-            self.selected_index = self.menu_items.len() - 1;
+            self.selected_index = self.categories.len() - 1;
         }
     }
 
     pub fn navigate_down(&mut self) {
-        self.selected_index = (self.selected_index + 1) % self.menu_items.len();
+        self.selected_index = (self.selected_index + 1) % self.categories.len();
     }
 
-    pub fn get_selected_action(&self) -> MenuAction {
-        if self.selected_index < self.menu_items.len() {
-            self.menu_items[self.selected_index].action.clone()
-        } else {
-            MenuAction::None
-        }
+    pub fn navigate_left(&mut self) {
+        let count = self.categories[self.selected_index].items.len();
+        let item = &mut self.selected_items[self.selected_index];
+        *item = (*item + count - 1) % count;
     }
 
-    pub fn render(&self) {
-        self.render_background();
+    pub fn navigate_right(&mut self) {
+        let count = self.categories[self.selected_index].items.len();
+        let item = &mut self.selected_items[self.selected_index];
+        *item = (*item + 1) % count;
+    }
 
-        for (i, item) in self.menu_items.iter().enumerate() {
-            let is_selected = i == self.selected_index;
-            let color = if !item.enabled {
-                GRAY
-            } else if is_selected {
-                YELLOW
-            } else {
-                WHITE
-            };
+    fn selected_item(&self) -> &MenuItem {
+        let category = &self.categories[self.selected_index];
+        &category.items[self.selected_items[self.selected_index]]
+    }
 
-            let size = if is_selected { 45.0 } else { 35.0 };
-            let text_dims = measure_text(&item.text, None, size as u16, 1.0);
-            let x = item.position.x - text_dims.width * 0.5;
-
-            if is_selected {
-                let pulse = (self.transition_timer * 3.0).sin() * 0.5 + 0.5;
-                let glow_color = Color::new(1.0, 1.0, 0.0, 0.2 * pulse);
-                draw_rectangle(
-                    x - 30.0,
-                    item.position.y - size * 0.8,
-                    text_dims.width + 60.0,
-                    size + 10.0,
-                    glow_color,
-                );
-
-                draw_circle(x - 50.0, item.position.y - size * 0.3, 5.0, YELLOW);
-                draw_circle(
-                    x + text_dims.width + 50.0,
-                    item.position.y - size * 0.3,
-                    5.0,
-                    YELLOW,
-                );
+    /// The mode highlighted now; `None` while it can't be picked
+    pub fn get_selected_action(&self) -> Option<MenuAction> {
+        let item = self.selected_item();
+        item.enabled.then_some(item.action)
+    }
+
+    /// Move the highlight onto `action`, wherever it's listed
+    pub fn focus(&mut self, action: MenuAction) {
+        for (index, category) in self.categories.iter().enumerate() {
+            if let Some(item) = category.items.iter().position(|item| item.action == action) {
+                self.selected_index = index;
+                self.selected_items[index] = item;
+                return;
             }
+        }
+    }
+
+    /// Categories down the middle from `top`, with the modes of the one
+    /// picked listed under it
+    pub fn render(&self, ui: &UiLayout, top: f32, scale: f32) {
+        let spacing = (ui.height() - top - 60.0 * scale) / self.categories.len() as f32;
+        let size = (34.0 * scale).clamp(20.0, 40.0);
+        let item_size = (20.0 * scale).clamp(14.0, 24.0);
+        let center = ui.width() * 0.5;
+
+        // The highlight eases between rows rather than jumping
+        let pulse = (self.transition_timer * 3.0).sin() * 0.5 + 0.5;
+        let highlight_y = top + self.highlight_y * spacing;
+        let width = 620.0 * scale;
+        draw_rectangle(
+            center - width * 0.5,
+            highlight_y - size * 0.85,
+            width,
+            size + item_size + 16.0 * scale,
+            Color::new(1.0, 1.0, 0.0, 0.12 + 0.08 * pulse),
+        );
 
-            draw_text(&item.text, x, item.position.y, size, color);
+        for (i, category) in self.categories.iter().enumerate() {
+            let y = top + i as f32 * spacing;
+            let selected = i == self.selected_index;
+            let label = tr(category.label);
+            let color = if selected { YELLOW } else { WHITE };
+            let dims = measure_text(&label, None, size as u16, 1.0);
+            draw_text(&label, center - dims.width * 0.5, y, size, color);
+
+            if selected {
+                self.render_items(category, center, y + item_size + 6.0 * scale, item_size);
+            }
         }
     }
 
-    fn render_background(&self) {
-        for i in 0..30 {
-            for j in 0..20 {
-                let x = (i as f32 * 80.0 - self.background_scroll) % screen_width();
-                let y = j as f32 * 80.0;
-                let alpha = ((i + j) % 2) as f32 * 0.05 + 0.02;
-                draw_rectangle(x, y, 40.0, 40.0, Color::new(0.2, 0.1, 0.3, alpha));
+    fn render_items(&self, category: &MenuCategory, center: f32, y: f32, size: f32) {
+        let picked = self.selected_items[self.selected_index];
+        let labels: Vec<String> = category
+            .items
+            .iter()
+            .map(|item| {
+                if item.enabled {
+                    tr(item.label)
+                } else {
+                    format!("{} ({})", tr(item.label), tr("menu.coming_soon"))
+                }
+            })
+            .collect();
+        let gap = 36.0;
+        let widths: Vec<f32> =
+            labels.iter().map(|label| measure_text(label, None, size as u16, 1.0).width).collect();
+        let total = widths.iter().sum::<f32>() + gap * labels.len().saturating_sub(1) as f32;
+        let mut x = center - total * 0.5;
+
+        for (i, (item, label)) in category.items.iter().zip(&labels).enumerate() {
+            let width = widths[i];
+            let color = match (item.enabled, i == picked) {
+                (false, _) => GRAY,
+                (true, true) => GOLD,
+                (true, false) => Color::new(0.8, 0.8, 0.8, 0.8),
+            };
+            draw_text(label, x, y, size, color);
+            if i == picked {
+                draw_line(x, y + 5.0, x + width, y + 5.0, 2.0, color);
             }
+            x += width + gap;
+        }
+
+        if category.items.len() > 1 {
+            let arrows = Color::new(1.0, 1.0, 0.0, 0.6);
+            draw_text("<", center - total * 0.5 - 30.0, y, size, arrows);
+            draw_text(">", center + total * 0.5 + 14.0, y, size, arrows);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_category_keeps_its_pick_and_coming_soon_cant_be_picked() {
+        let mut menu = MenuUI::hub();
+        menu.navigate_right();
+        assert_eq!(menu.get_selected_action(), Some(MenuAction::Coop));
+        menu.navigate_down();
+        menu.navigate_up();
+        assert_eq!(menu.get_selected_action(), Some(MenuAction::Coop));
+
+        menu.focus(MenuAction::BossRush);
+        assert_eq!(menu.get_selected_action(), None);
+        menu.focus(MenuAction::Controls);
+        menu.navigate_left();
+        assert_eq!(menu.get_selected_action(), Some(MenuAction::Settings));
+        menu.navigate_up();
+        menu.navigate_left();
+        assert_eq!(menu.get_selected_action(), Some(MenuAction::Account));
+    }
+}
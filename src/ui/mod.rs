@@ -1,7 +1,19 @@
+pub mod account_banner;
+pub mod broadcast;
+pub mod console;
 pub mod damage_numbers;
+pub mod dialogue_box;
 pub mod hud;
+pub mod hud_layout;
+pub mod input_display;
+pub mod layout;
 pub mod menu_ui;
 pub mod pause;
+pub mod release_notes;
 pub mod subtitles;
+pub mod toast;
 
 pub use damage_numbers::DamageNumberManager;
+pub use hud_layout::{HudLayout, HudPreset, HudWidget};
+pub use layout::{Anchor, UiLayout, UiScaleMode};
+pub use toast::ToastQueue;
@@ -1,9 +1,15 @@
+use crate::ui::UiLayout;
+use crate::util::host::Host;
 use macroquad::prelude::*;
 
+/// The menu over a paused run. Quitting asks first, since it throws the run
+/// away.
 pub struct PauseMenu {
     pub active: bool,
     pub selected_option: usize,
     pub options: Vec<String>,
+    /// Asking whether to really quit; true while YES is picked
+    confirm_quit: Option<bool>,
 }
 
 impl PauseMenu {
@@ -13,15 +19,18 @@ impl PauseMenu {
             selected_option: 0,
             options: vec![
                 "RESUME".to_string(),
-                "RESTART".to_string(),
+                "RESTART WAVE".to_string(),
                 "SETTINGS".to_string(),
+                "CONTROLS".to_string(),
                 "QUIT TO MENU".to_string(),
             ],
+            confirm_quit: None,
         }
     }
 
     pub fn toggle(&mut self) {
         self.active = !self.active;
+        self.confirm_quit = None;
         if self.active {
             self.selected_option = 0;
         }
@@ -39,43 +48,89 @@ impl PauseMenu {
         self.selected_option = (self.selected_option + 1) % self.options.len();
     }
 
-    pub fn render(&self) {
+    /// Keys for the open menu, and what the player picked. Escape backs out
+    /// of the quit prompt, or resumes.
+    pub fn handle_input(&mut self, host: &dyn Host) -> PauseAction {
+        let back = host.key_pressed(KeyCode::Escape);
+        let select = host.key_pressed(KeyCode::Enter) || host.key_pressed(KeyCode::J);
+
+        if let Some(yes) = &mut self.confirm_quit {
+            let switch = host.key_pressed(KeyCode::A)
+                || host.key_pressed(KeyCode::D)
+                || host.key_pressed(KeyCode::Left)
+                || host.key_pressed(KeyCode::Right);
+            if switch {
+                *yes = !*yes;
+            }
+            return self.answer_quit(back, select);
+        }
+
+        if back {
+            return PauseAction::Resume;
+        }
+        if host.key_pressed(KeyCode::W) || host.key_pressed(KeyCode::Up) {
+            self.navigate_up();
+        }
+        if host.key_pressed(KeyCode::S) || host.key_pressed(KeyCode::Down) {
+            self.navigate_down();
+        }
+        if !select {
+            return PauseAction::None;
+        }
+        match self.get_selected_action() {
+            PauseAction::QuitToMenu => {
+                self.confirm_quit = Some(false);
+                PauseAction::None
+            }
+            action => action,
+        }
+    }
+
+    /// Settle the quit prompt: Escape or NO closes it, YES quits
+    fn answer_quit(&mut self, back: bool, select: bool) -> PauseAction {
+        match self.confirm_quit {
+            Some(_) if back => {
+                self.confirm_quit = None;
+                PauseAction::None
+            }
+            Some(yes) if select => {
+                self.confirm_quit = None;
+                if yes {
+                    PauseAction::QuitToMenu
+                } else {
+                    PauseAction::None
+                }
+            }
+            _ => PauseAction::None,
+        }
+    }
+
+    /// Draw the menu in the middle of `ui`, with `status` under the options
+    pub fn render(&self, ui: &UiLayout, status: Option<&str>) {
         if !self.active {
             return;
         }
 
-        draw_rectangle(
-            0.0,
-            0.0,
-            screen_width(),
-            screen_height(),
-            Color::new(0.0, 0.0, 0.0, 0.7),
-        );
-
-        let box_width = 400.0;
-        let box_height = 300.0;
-        let box_x = screen_width() * 0.5 - box_width * 0.5;
-        let box_y = screen_height() * 0.5 - box_height * 0.5;
-
-        draw_rectangle(
-            box_x,
-            box_y,
-            box_width,
-            box_height,
-            Color::new(0.1, 0.1, 0.15, 0.95),
-        );
+        let center_x = ui.width() * 0.5;
+        draw_rectangle(0.0, 0.0, ui.width(), ui.height(), Color::new(0.0, 0.0, 0.0, 0.7));
+
+        let box_width = 440.0;
+        let box_height = 380.0;
+        let box_x = center_x - box_width * 0.5;
+        let box_y = ui.height() * 0.5 - box_height * 0.5;
+
+        draw_rectangle(box_x, box_y, box_width, box_height, Color::new(0.1, 0.1, 0.15, 0.95));
         draw_rectangle_lines(box_x, box_y, box_width, box_height, 2.0, WHITE);
 
         let title = "PAUSED";
         let title_size = 50.0;
         let title_dims = measure_text(title, None, title_size as u16, 1.0);
-        draw_text(
-            title,
-            screen_width() * 0.5 - title_dims.width * 0.5,
-            box_y + 60.0,
-            title_size,
-            WHITE,
-        );
+        draw_text(title, center_x - title_dims.width * 0.5, box_y + 60.0, title_size, WHITE);
+
+        if let Some(yes) = self.confirm_quit {
+            self.render_quit_prompt(center_x, box_y, yes);
+            return;
+        }
 
         for (i, option) in self.options.iter().enumerate() {
             let y = box_y + 120.0 + i as f32 * 40.0;
@@ -87,7 +142,7 @@ impl PauseMenu {
             };
 
             let text_dims = measure_text(option, None, size as u16, 1.0);
-            let x = screen_width() * 0.5 - text_dims.width * 0.5;
+            let x = center_x - text_dims.width * 0.5;
 
             if i == self.selected_option {
                 draw_rectangle(
@@ -101,24 +156,82 @@ impl PauseMenu {
 
             draw_text(option, x, y, size, color);
         }
+
+        if let Some(status) = status {
+            let dims = measure_text(status, None, 18, 1.0);
+            let y = box_y + box_height - 24.0;
+            draw_text(status, center_x - dims.width * 0.5, y, 18.0, ORANGE);
+        }
+    }
+
+    fn render_quit_prompt(&self, center_x: f32, box_y: f32, yes: bool) {
+        let question = "Quit the run and go back to the menu?";
+        let dims = measure_text(question, None, 22, 1.0);
+        draw_text(question, center_x - dims.width * 0.5, box_y + 140.0, 22.0, WHITE);
+        let warning = "Progress in this run is lost";
+        let dims = measure_text(warning, None, 18, 1.0);
+        draw_text(warning, center_x - dims.width * 0.5, box_y + 170.0, 18.0, GRAY);
+
+        for (i, (label, picked)) in [("NO", !yes), ("YES", yes)].into_iter().enumerate() {
+            let x = center_x - 100.0 + i as f32 * 160.0;
+            let color = if picked { YELLOW } else { WHITE };
+            draw_text(label, x, box_y + 250.0, 32.0, color);
+        }
+        let hint = "A/D to choose, ENTER to confirm, ESC to go back";
+        let dims = measure_text(hint, None, 16, 1.0);
+        let hint_color = Color::new(1.0, 1.0, 1.0, 0.5);
+        draw_text(hint, center_x - dims.width * 0.5, box_y + 330.0, 16.0, hint_color);
     }
 
     pub fn get_selected_action(&self) -> PauseAction {
         match self.selected_option {
             0 => PauseAction::Resume,
-            1 => PauseAction::Restart,
+            1 => PauseAction::RestartWave,
             2 => PauseAction::Settings,
-            3 => PauseAction::QuitToMenu,
+            3 => PauseAction::Controls,
+            4 => PauseAction::QuitToMenu,
             _ => PauseAction::None,
         }
     }
 }
 
+impl Default for PauseMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum PauseAction {
     Resume,
-    Restart,
+    RestartWave,
     Settings,
+    Controls,
     QuitToMenu,
     None,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quitting_waits_for_a_yes() {
+        let mut menu = PauseMenu::new();
+        menu.toggle();
+        menu.navigate_up();
+        assert_eq!(menu.get_selected_action(), PauseAction::QuitToMenu);
+
+        menu.confirm_quit = Some(false);
+        assert_eq!(menu.answer_quit(false, true), PauseAction::None);
+        assert_eq!(menu.confirm_quit, None);
+
+        menu.confirm_quit = Some(true);
+        assert_eq!(menu.answer_quit(true, false), PauseAction::None);
+        assert_eq!(menu.confirm_quit, None);
+
+        menu.confirm_quit = Some(true);
+        assert_eq!(menu.answer_quit(false, false), PauseAction::None);
+        assert_eq!(menu.answer_quit(false, true), PauseAction::QuitToMenu);
+    }
+}
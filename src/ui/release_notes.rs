@@ -0,0 +1,291 @@
+use macroquad::prelude::*;
+
+/// Pixels an arrow press or wheel notch scrolls the notes
+const SCROLL_STEP: f32 = 32.0;
+const TEXT_SIZE: f32 = 20.0;
+const LINE_HEIGHT: f32 = 26.0;
+
+/// How a line of release notes is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteStyle {
+    Heading,
+    Subheading,
+    Bullet,
+    Quote,
+    Text,
+    /// Space between paragraphs
+    Gap,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteLine {
+    pub style: NoteStyle,
+    pub text: String,
+    /// How deep a bullet is nested
+    pub indent: usize,
+}
+
+impl NoteLine {
+    fn new(style: NoteStyle, text: &str, indent: usize) -> Self {
+        Self {
+            style,
+            text: inline(text),
+            indent,
+        }
+    }
+}
+
+/// Lines of a markdown changelog: headings, bullets, quotes and paragraphs,
+/// with the inline markup taken out
+pub fn parse_markdown(body: &str) -> Vec<NoteLine> {
+    let mut lines: Vec<NoteLine> = Vec::new();
+    let mut in_code = false;
+
+    for raw in body.lines() {
+        let trimmed = raw.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(NoteLine::new(NoteStyle::Text, trimmed, 1));
+            continue;
+        }
+
+        let rule = trimmed.len() >= 3 && trimmed.chars().all(|c| matches!(c, '-' | '*' | '_'));
+        if trimmed.is_empty() || rule {
+            // One gap between blocks, none before the first
+            if lines.last().is_some_and(|line| line.style != NoteStyle::Gap) {
+                lines.push(NoteLine::new(NoteStyle::Gap, "", 0));
+            }
+            continue;
+        }
+
+        let indent = (raw.len() - raw.trim_start().len()) / 2;
+        let line = if let Some(heading) = trimmed.strip_prefix("# ") {
+            NoteLine::new(NoteStyle::Heading, heading, 0)
+        } else if trimmed.starts_with("##") {
+            NoteLine::new(NoteStyle::Subheading, trimmed.trim_start_matches('#'), 0)
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            NoteLine::new(NoteStyle::Quote, quote, 0)
+        } else if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|m| trimmed.strip_prefix(m))
+        {
+            NoteLine::new(NoteStyle::Bullet, item, indent)
+        } else if let Some(item) = numbered_item(trimmed) {
+            NoteLine::new(NoteStyle::Bullet, item, indent)
+        } else {
+            NoteLine::new(NoteStyle::Text, trimmed, 0)
+        };
+        lines.push(line);
+    }
+
+    while lines.last().is_some_and(|line| line.style == NoteStyle::Gap) {
+        lines.pop();
+    }
+    lines
+}
+
+/// The text of a `1.` or `2)` list item
+fn numbered_item(line: &str) -> Option<&str> {
+    let digits = line.find(|c: char| !c.is_ascii_digit())?;
+    if digits == 0 {
+        return None;
+    }
+    line[digits..].strip_prefix(". ").or_else(|| line[digits..].strip_prefix(") "))
+}
+
+/// `text` without emphasis, code ticks, or link and image targets
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text.trim();
+    while let Some(open) = rest.find('[') {
+        let label_end = rest[open..].find("](").map(|i| open + i);
+        let target_end = label_end.and_then(|end| rest[end..].find(')').map(|i| end + i));
+        let (Some(label_end), Some(target_end)) = (label_end, target_end) else {
+            break;
+        };
+        out.push_str(rest[..open].trim_end_matches('!'));
+        out.push_str(&rest[open + 1..label_end]);
+        rest = &rest[target_end + 1..];
+    }
+    out.push_str(rest);
+    ["**", "__", "~~", "`"].iter().fold(out, |text, mark| text.replace(mark, ""))
+}
+
+/// `text` broken at spaces into lines no wider than `width`
+fn wrap(text: &str, width: f32, measure: impl Fn(&str) -> f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if measure(&candidate) > width && !line.is_empty() {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A release's full changelog in a scrolling panel over the screen
+pub struct ReleaseNotesPanel {
+    title: String,
+    lines: Vec<NoteLine>,
+    scroll: f32,
+    /// Height of the wrapped notes, and of the space they're shown in, as of
+    /// the last render
+    content_height: f32,
+    view_height: f32,
+}
+
+impl ReleaseNotesPanel {
+    pub fn new(title: impl Into<String>, markdown: &str) -> Self {
+        Self {
+            title: title.into(),
+            lines: parse_markdown(markdown),
+            scroll: 0.0,
+            content_height: 0.0,
+            view_height: 0.0,
+        }
+    }
+
+    /// Up and down, page up and down, home and end, and the mouse wheel
+    pub fn handle_input(&mut self) {
+        let mut step = 0.0;
+        if is_key_down(KeyCode::Up) || is_key_down(KeyCode::W) {
+            step -= SCROLL_STEP * 0.25;
+        }
+        if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
+            step += SCROLL_STEP * 0.25;
+        }
+        if is_key_pressed(KeyCode::PageUp) {
+            step -= self.view_height * 0.9;
+        }
+        if is_key_pressed(KeyCode::PageDown) {
+            step += self.view_height * 0.9;
+        }
+        step -= mouse_wheel().1.signum() * SCROLL_STEP;
+        if is_key_pressed(KeyCode::Home) {
+            step = -self.content_height;
+        }
+        if is_key_pressed(KeyCode::End) {
+            step = self.content_height;
+        }
+        let max_scroll = (self.content_height - self.view_height).max(0.0);
+        self.scroll = (self.scroll + step).clamp(0.0, max_scroll);
+    }
+
+    pub fn render(&mut self) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.8));
+        let width = (screen_width() * 0.7).min(900.0);
+        let height = screen_height() * 0.8;
+        let x = (screen_width() - width) * 0.5;
+        let y = (screen_height() - height) * 0.5;
+        draw_rectangle(x, y, width, height, Color::new(0.1, 0.1, 0.15, 1.0));
+        draw_rectangle_lines(x, y, width, height, 3.0, Color::new(0.3, 0.6, 1.0, 1.0));
+
+        let title_dims = measure_text(&self.title, None, 32, 1.0);
+        let title_x = x + (width - title_dims.width) * 0.5;
+        draw_text(&self.title, title_x, y + 45.0, 32.0, Color::new(0.4, 0.8, 1.0, 1.0));
+
+        let top = y + 70.0;
+        let bottom = y + height - 40.0;
+        self.view_height = bottom - top;
+        let mut line_y = top - self.scroll;
+
+        for line in &self.lines {
+            let (size, color, indent) = match line.style {
+                NoteStyle::Heading => (28.0, YELLOW, 0.0),
+                NoteStyle::Subheading => (24.0, GOLD, 0.0),
+                NoteStyle::Bullet => (TEXT_SIZE, WHITE, 20.0 + line.indent as f32 * 24.0),
+                NoteStyle::Quote => (TEXT_SIZE, GRAY, 20.0),
+                NoteStyle::Text => (TEXT_SIZE, LIGHTGRAY, line.indent as f32 * 24.0),
+                NoteStyle::Gap => {
+                    line_y += LINE_HEIGHT * 0.5;
+                    continue;
+                }
+            };
+            let left = x + 30.0 + indent;
+            let text_width = x + width - 40.0 - left;
+            let measure = |text: &str| measure_text(text, None, size as u16, 1.0).width;
+            let wrapped = wrap(&line.text, text_width, measure);
+
+            for (i, text) in wrapped.iter().enumerate() {
+                line_y += size + 6.0;
+                // Only whole lines inside the panel are drawn
+                if line_y - size < top || line_y > bottom {
+                    continue;
+                }
+                if i == 0 && line.style == NoteStyle::Bullet {
+                    draw_text("•", left - 16.0, line_y, size, color);
+                }
+                if line.style == NoteStyle::Quote {
+                    draw_rectangle(left - 12.0, line_y - size, 3.0, size + 4.0, GRAY);
+                }
+                draw_text(text, left, line_y, size, color);
+            }
+        }
+        self.content_height = line_y + self.scroll - top;
+
+        // Scroll bar, when there's more than fits
+        if self.content_height > self.view_height {
+            let track_x = x + width - 16.0;
+            let thumb = self.view_height * self.view_height / self.content_height;
+            let max_scroll = self.content_height - self.view_height;
+            let thumb_y = top + (self.view_height - thumb) * (self.scroll / max_scroll);
+            let track = Color::new(0.3, 0.3, 0.3, 1.0);
+            draw_rectangle(track_x, top, 6.0, self.view_height, track);
+            draw_rectangle(track_x, thumb_y, 6.0, thumb, Color::new(0.4, 0.6, 1.0, 1.0));
+        }
+
+        let hint = "W/S, PAGE UP/DOWN or the mouse wheel to scroll, ESC to close";
+        let hint_dims = measure_text(hint, None, 16, 1.0);
+        let hint_x = x + (width - hint_dims.width) * 0.5;
+        draw_text(hint, hint_x, y + height - 14.0, 16.0, GRAY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_changelogs_become_styled_lines() {
+        let body = "# v1.3.0\n\n## Added\n\
+                    - **Boss rush** mode, see [the wiki](https://example.com)\n  \
+                    - nested `detail`\n1. Numbered\n\n\n\
+                    > Heads up\n```\ncode\n```\n---\nThanks!\n";
+        let lines = parse_markdown(body);
+        let got: Vec<(NoteStyle, &str, usize)> = lines
+            .iter()
+            .map(|line| (line.style, line.text.as_str(), line.indent))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (NoteStyle::Heading, "v1.3.0", 0),
+                (NoteStyle::Gap, "", 0),
+                (NoteStyle::Subheading, "Added", 0),
+                (NoteStyle::Bullet, "Boss rush mode, see the wiki", 0),
+                (NoteStyle::Bullet, "nested detail", 1),
+                (NoteStyle::Bullet, "Numbered", 0),
+                (NoteStyle::Gap, "", 0),
+                (NoteStyle::Quote, "Heads up", 0),
+                (NoteStyle::Text, "code", 1),
+                (NoteStyle::Gap, "", 0),
+                (NoteStyle::Text, "Thanks!", 0),
+            ]
+        );
+
+        let chars = |text: &str| text.chars().count() as f32;
+        assert_eq!(wrap("one two three four", 9.0, chars), vec!["one two", "three", "four"]);
+        assert_eq!(wrap("unbreakable", 4.0, chars), vec!["unbreakable"]);
+    }
+}
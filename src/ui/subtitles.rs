@@ -1,20 +1,114 @@
+use crate::data::localization::{self, LineText};
+use crate::data::GameConfig;
+use crate::plugins::{Plugin, PluginRegistry, SettingsEntry, SettingsPage};
+use crate::ui::UiLayout;
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
+/// How long a line stays up when it's given no duration: a moment to notice
+/// it, then time to read it
+const READ_DELAY: f32 = 1.2;
+const READ_PER_CHAR: f32 = 0.05;
+const MAX_READ_TIME: f32 = 6.0;
+/// Lines waiting behind the one on screen; older ones are dropped so barks
+/// don't trail the fight they're about
+const MAX_QUEUED: usize = 4;
+/// Steps the background opacity setting cycles through
+const BACKGROUND_STEPS: [f32; 5] = [0.0, 0.4, 0.6, 0.8, 1.0];
+
+/// How big subtitles are drawn
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubtitleSize {
+    Small,
+    Normal,
+    Large,
+    Huge,
+}
+
+impl SubtitleSize {
+    pub const ALL: [SubtitleSize; 4] = [
+        SubtitleSize::Small,
+        SubtitleSize::Normal,
+        SubtitleSize::Large,
+        SubtitleSize::Huge,
+    ];
+
+    pub fn scale(self) -> f32 {
+        match self {
+            SubtitleSize::Small => 0.8,
+            SubtitleSize::Normal => 1.0,
+            SubtitleSize::Large => 1.3,
+            SubtitleSize::Huge => 1.6,
+        }
+    }
+
+    pub fn label_key(self) -> &'static str {
+        match self {
+            SubtitleSize::Small => "settings.size_small",
+            SubtitleSize::Normal => "settings.size_normal",
+            SubtitleSize::Large => "settings.size_large",
+            SubtitleSize::Huge => "settings.size_huge",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let current = Self::ALL.iter().position(|&size| size == self).unwrap_or(0);
+        Self::ALL[(current + 1) % Self::ALL.len()]
+    }
+}
+
+/// The accessibility settings every spoken line is drawn with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SubtitleStyle {
+    pub size: SubtitleSize,
+    /// Opacity of the backing behind the text
+    pub background: f32,
+    /// Name each speaker in their own colour instead of all alike
+    pub speaker_colors: bool,
+}
+
+impl SubtitleStyle {
+    pub const DEFAULT: SubtitleStyle = SubtitleStyle {
+        size: SubtitleSize::Normal,
+        background: 0.8,
+        speaker_colors: true,
+    };
+
+    pub fn from_config(config: &GameConfig) -> Self {
+        Self {
+            size: config.subtitle_size,
+            background: config.subtitle_background.clamp(0.0, 1.0),
+            speaker_colors: config.subtitle_speaker_colors,
+        }
+    }
+
+    /// `base` font size at the chosen subtitle size
+    pub fn font_size(&self, base: f32) -> f32 {
+        base * self.size.scale()
+    }
+
+    pub fn speaker_color(&self, speaker: &str) -> Color {
+        if self.speaker_colors {
+            SubtitleManager::get_speaker_color(speaker)
+        } else {
+            YELLOW
+        }
+    }
+}
+
 pub struct SubtitleManager {
     pub active_subtitle: Option<Subtitle>,
     pub subtitle_queue: VecDeque<Subtitle>,
     pub enabled: bool,
-    pub background_opacity: f32,
 }
 
 #[derive(Clone, Debug)]
 pub struct Subtitle {
     pub speaker: String,
-    pub text: String,
+    pub text: LineText,
     pub duration: f32,
     pub timer: f32,
-    pub color: Color,
 }
 
 impl SubtitleManager {
@@ -23,21 +117,60 @@ impl SubtitleManager {
             active_subtitle: None,
             subtitle_queue: VecDeque::new(),
             enabled: true,
-            background_opacity: 0.8,
         }
     }
 
     pub fn add_subtitle(&mut self, speaker: String, text: String, duration: f32) {
-        let color = Self::get_speaker_color(&speaker);
+        let english = match Self::get_translation(&text) {
+            translation if translation.is_empty() => text.clone(),
+            translation => translation,
+        };
+        self.add_translated_subtitle(speaker, text, english, duration);
+    }
+
+    /// Queue a line with its own translation instead of the built-in lookup
+    pub fn add_translated_subtitle(
+        &mut self,
+        speaker: String,
+        text: String,
+        translation: String,
+        duration: f32,
+    ) {
+        let line = LineText::Written {
+            dutch: text,
+            english: translation,
+        };
+        self.queue(speaker, line, duration);
+    }
+
+    /// Queue a line for as long as it takes to read
+    pub fn say(&mut self, speaker: &str, text: LineText) {
+        let duration = Self::reading_time(&text);
+        self.queue(speaker.to_string(), text, duration);
+    }
+
+    fn queue(&mut self, speaker: String, text: LineText, duration: f32) {
+        if self.subtitle_queue.len() == MAX_QUEUED {
+            self.subtitle_queue.pop_front();
+        }
         self.subtitle_queue.push_back(Subtitle {
             speaker,
             text,
             duration,
             timer: 0.0,
-            color,
         });
     }
 
+    /// Seconds to show `text` for, going by the longer of its voice and
+    /// subtitle
+    pub fn reading_time(text: &LineText) -> f32 {
+        let line = localization::spoken(text);
+        let chars = line.voice.chars().count().max(
+            line.subtitle.map_or(0, |subtitle| subtitle.chars().count()),
+        );
+        (READ_DELAY + chars as f32 * READ_PER_CHAR).min(MAX_READ_TIME)
+    }
+
     pub fn update(&mut self, dt: f32) {
         if !self.enabled {
             return;
@@ -55,50 +188,52 @@ impl SubtitleManager {
         }
     }
 
-    pub fn render(&self) {
-        if !self.enabled || self.active_subtitle.is_none() {
+    /// Draw the line on screen, centred above the bottom of `ui`
+    pub fn render(&self, ui: &UiLayout) {
+        if !self.enabled {
             return;
         }
+        let Some(ref subtitle) = self.active_subtitle else {
+            return;
+        };
 
-        if let Some(ref subtitle) = self.active_subtitle {
-            let box_height = 120.0;
-            let box_y = screen_height() - box_height - 50.0;
-
-            draw_rectangle(
-                0.0,
-                box_y,
-                screen_width(),
-                box_height,
-                Color::new(0.0, 0.0, 0.0, self.background_opacity),
-            );
-
-            let speaker_size = 25.0;
-            let text_size = 20.0;
-
-            draw_text(
-                &subtitle.speaker,
-                50.0,
-                box_y + 30.0,
-                speaker_size,
-                subtitle.color,
-            );
-
-            draw_text(&subtitle.text, 50.0, box_y + 60.0, text_size, WHITE);
-
-            let translation = Self::get_translation(&subtitle.text);
-            if !translation.is_empty() {
-                draw_text(
-                    &translation,
-                    50.0,
-                    box_y + 85.0,
-                    text_size * 0.8,
-                    Color::new(0.7, 0.7, 0.7, 0.8),
-                );
-            }
+        let style = crate::data::get_subtitle_style();
+        let speaker_size = style.font_size(25.0);
+        let subtitle_size = style.font_size(18.0);
+
+        // Looked up as it's drawn, so a language switch shows straight away
+        let line = localization::spoken(&subtitle.text);
+        let voice = format!("{}: {}", subtitle.speaker, line.voice);
+        let mut width = measure_text(&voice, None, speaker_size as u16, 1.0).width;
+        let mut height = speaker_size + 20.0;
+        if let Some(translation) = &line.subtitle {
+            width = width.max(measure_text(translation, None, subtitle_size as u16, 1.0).width);
+            height += subtitle_size + 8.0;
+        }
+
+        let x = (ui.width() - width) * 0.5;
+        let y = ui.height() - height - 200.0;
+        draw_rectangle(
+            x - 16.0,
+            y,
+            width + 32.0,
+            height,
+            Color::new(0.0, 0.0, 0.0, style.background),
+        );
+
+        let name = format!("{}: ", subtitle.speaker);
+        let name_width = measure_text(&name, None, speaker_size as u16, 1.0).width;
+        let baseline = y + speaker_size + 4.0;
+        draw_text(&name, x, baseline, speaker_size, style.speaker_color(&subtitle.speaker));
+        draw_text(&line.voice, x + name_width, baseline, speaker_size, WHITE);
+
+        if let Some(translation) = &line.subtitle {
+            let baseline = baseline + subtitle_size + 8.0;
+            draw_text(translation, x, baseline, subtitle_size, Color::new(0.7, 0.7, 0.7, 1.0));
         }
     }
 
-    fn get_speaker_color(speaker: &str) -> Color {
+    pub fn get_speaker_color(speaker: &str) -> Color {
         match speaker {
             "Bas" => Color::new(0.2, 0.4, 1.0, 1.0),
             "Wolters" => Color::new(1.0, 0.2, 0.2, 1.0),
@@ -113,21 +248,21 @@ impl SubtitleManager {
 
     fn get_translation(dutch_text: &str) -> String {
         match dutch_text {
-            "Bas, vegen!" => "[Clean up, Bas!]".to_string(),
-            "Bas! Vegen, nu meteen!" => "[Bas! Clean up, right now!]".to_string(),
+            "Bas, vegen!" => "Clean up, Bas!".to_string(),
+            "Bas! Vegen, nu meteen!" => "Bas! Clean up, right now!".to_string(),
             "Kom dan! Ik veeg niks, bro!" => {
-                "[Come on! I'm not cleaning anything, bro!]".to_string()
+                "Come on! I'm not cleaning anything, bro!".to_string()
             }
-            "Nee bro, ze gaan zien." => "[No bro, they'll see.]".to_string(),
+            "Nee bro, ze gaan zien." => "No bro, they'll see.".to_string(),
             "Wacht maar… ik heb een winter arc plan." => {
-                "[Just wait... I have a winter arc plan.]".to_string()
+                "Just wait... I have a winter arc plan.".to_string()
             }
             "Ik ga m'n barras in hun stoppen." => {
-                "[I'm going to stuff my barras in them.]".to_string()
+                "I'm going to stuff my barras in them.".to_string()
             }
-            "Aina broeg… ze gaan zien." => "[Aina bro... they'll see.]".to_string(),
+            "Aina broeg… ze gaan zien." => "Aina bro... they'll see.".to_string(),
             "Je hebt alles verpest, Bas! Mijn kunst was perfect!" => {
-                "[You ruined everything, Bas! My art was perfect!]".to_string()
+                "You ruined everything, Bas! My art was perfect!".to_string()
             }
             _ => String::new(),
         }
@@ -142,3 +277,94 @@ impl SubtitleManager {
         self.enabled = !self.enabled;
     }
 }
+
+fn next_background(current: f32) -> f32 {
+    BACKGROUND_STEPS
+        .iter()
+        .copied()
+        .find(|&step| step > current + 0.01)
+        .unwrap_or(BACKGROUND_STEPS[0])
+}
+
+fn apply(config: &GameConfig) {
+    crate::data::set_subtitle_style(SubtitleStyle::from_config(config));
+}
+
+/// The "Subtitles" settings page: how big spoken lines are, how dark their
+/// backing is and whether speakers get their own colours
+pub struct SubtitlesPlugin;
+
+impl Plugin for SubtitlesPlugin {
+    fn build(&self, registry: &mut PluginRegistry) {
+        registry.add_settings_page(SettingsPage {
+            title: "Subtitles",
+            entries: vec![
+                SettingsEntry {
+                    label: |config| {
+                        let size = localization::tr(config.subtitle_size.label_key());
+                        format!("{}: {}", localization::tr("settings.subtitle_size"), size)
+                    },
+                    activate: |config| {
+                        config.subtitle_size = config.subtitle_size.next();
+                        apply(config);
+                    },
+                },
+                SettingsEntry {
+                    label: |config| {
+                        let setting = localization::tr("settings.subtitle_background");
+                        format!("{}: {:.0}%", setting, config.subtitle_background * 100.0)
+                    },
+                    activate: |config| {
+                        config.subtitle_background = next_background(config.subtitle_background);
+                        apply(config);
+                    },
+                },
+                SettingsEntry {
+                    label: |config| {
+                        let key = if config.subtitle_speaker_colors {
+                            "settings.on"
+                        } else {
+                            "settings.off"
+                        };
+                        let setting = localization::tr("settings.speaker_colors");
+                        format!("{}: {}", setting, localization::tr(key))
+                    },
+                    activate: |config| {
+                        config.subtitle_speaker_colors = !config.subtitle_speaker_colors;
+                        apply(config);
+                    },
+                },
+            ],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longer_lines_stay_up_longer_and_old_barks_drop() {
+        let short = LineText::Key("hi".to_string());
+        let long = LineText::Key("a line that takes a good while longer to read".to_string());
+        assert!(SubtitleManager::reading_time(&long) > SubtitleManager::reading_time(&short));
+        let endless = LineText::Key("x".repeat(1000));
+        assert_eq!(SubtitleManager::reading_time(&endless), MAX_READ_TIME);
+
+        let mut subtitles = SubtitleManager::new();
+        for i in 0..MAX_QUEUED + 2 {
+            subtitles.say("Bas", LineText::Key(format!("line {}", i)));
+        }
+        assert_eq!(subtitles.subtitle_queue.len(), MAX_QUEUED);
+        subtitles.update(0.0);
+        let shown = subtitles.active_subtitle.as_ref().map(|subtitle| &subtitle.text);
+        assert!(matches!(shown, Some(LineText::Key(key)) if key == "line 2"));
+    }
+
+    #[test]
+    fn background_opacity_cycles_back_to_none() {
+        assert_eq!(next_background(0.8), 1.0);
+        assert_eq!(next_background(1.0), 0.0);
+        assert_eq!(next_background(0.5), 0.6);
+    }
+}
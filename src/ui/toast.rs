@@ -0,0 +1,70 @@
+use crate::ui::UiLayout;
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+/// Seconds each toast stays up, sliding in and out included
+const TOAST_TIME: f32 = 3.5;
+const SLIDE_TIME: f32 = 0.3;
+const TOAST_WIDTH: f32 = 380.0;
+const TOAST_HEIGHT: f32 = 70.0;
+
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub heading: String,
+    pub title: String,
+    pub detail: String,
+    pub accent: Color,
+    timer: f32,
+}
+
+/// Popups in the top right corner, shown one after another
+#[derive(Default)]
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+}
+
+impl ToastQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, heading: &str, title: &str, detail: &str, accent: Color) {
+        self.toasts.push_back(Toast {
+            heading: heading.to_string(),
+            title: title.to_string(),
+            detail: detail.to_string(),
+            accent,
+            timer: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.timer += dt;
+            if toast.timer >= TOAST_TIME {
+                self.toasts.pop_front();
+            }
+        }
+    }
+
+    /// Draw the current toast in `ui`'s space
+    pub fn render(&self, ui: &UiLayout) {
+        let Some(toast) = self.toasts.front() else {
+            return;
+        };
+
+        // Slide in from the right edge, hold, slide back out
+        let shown = (toast.timer / SLIDE_TIME)
+            .min((TOAST_TIME - toast.timer) / SLIDE_TIME)
+            .clamp(0.0, 1.0);
+        let x = ui.width() - (TOAST_WIDTH + 20.0) * shown;
+        let y = 20.0;
+
+        draw_rectangle(x, y, TOAST_WIDTH, TOAST_HEIGHT, Color::new(0.05, 0.05, 0.1, 0.92));
+        draw_rectangle(x, y, 6.0, TOAST_HEIGHT, toast.accent);
+        draw_rectangle_lines(x, y, TOAST_WIDTH, TOAST_HEIGHT, 2.0, toast.accent);
+        draw_text(&toast.heading, x + 18.0, y + 20.0, 18.0, toast.accent);
+        draw_text(&toast.title, x + 18.0, y + 43.0, 26.0, WHITE);
+        draw_text(&toast.detail, x + 18.0, y + 62.0, 16.0, LIGHTGRAY);
+    }
+}
@@ -0,0 +1,260 @@
+use flate2::read::{DeflateDecoder, GzDecoder};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Archive formats releases are packed in, told apart by their first bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Zip,
+    TarGz,
+}
+
+impl Kind {
+    /// `None` for anything that isn't an archive, like a bare executable
+    pub fn of(path: &Path) -> Option<Self> {
+        let mut magic = [0u8; 4];
+        fs::File::open(path).ok()?.read_exact(&mut magic).ok()?;
+        match magic {
+            [b'P', b'K', 3, 4] => Some(Kind::Zip),
+            [0x1f, 0x8b, _, _] => Some(Kind::TarGz),
+            _ => None,
+        }
+    }
+}
+
+/// A file taken out of an archive, relative to its root
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub executable: bool,
+}
+
+/// Unpack `archive` into `dest`, returning the files it held. Entries that
+/// would land outside `dest` fail the whole archive.
+pub fn extract(kind: Kind, archive: &Path, dest: &Path) -> Result<Vec<Entry>, String> {
+    let data = fs::read(archive).map_err(|e| format!("Failed to read the archive: {}", e))?;
+    let files = match kind {
+        Kind::Zip => read_zip(&data)?,
+        Kind::TarGz => read_tar_gz(&data)?,
+    };
+
+    let paths = files
+        .iter()
+        .map(|(name, _, _)| {
+            safe_path(name).ok_or_else(|| format!("Unsafe path in archive: {}", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut entries = Vec::new();
+    for ((name, mode, contents), path) in files.into_iter().zip(paths) {
+        let target = dest.join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to unpack {}: {}", name, e))?;
+        }
+        fs::write(&target, contents).map_err(|e| format!("Failed to unpack {}: {}", name, e))?;
+        entries.push(Entry {
+            path,
+            executable: mode & 0o111 != 0,
+        });
+    }
+    Ok(entries)
+}
+
+/// `name` as a path under the archive root, or `None` if it climbs out of it
+pub(super) fn safe_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// An unpacked file: its name in the archive, Unix mode and contents
+type File = (String, u32, Vec<u8>);
+
+fn u16_at(data: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as usize)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize)
+}
+
+/// Files in a zip, read through its central directory. Stored and deflated
+/// entries are all release builds produce; zip64 isn't supported.
+fn read_zip(data: &[u8]) -> Result<Vec<File>, String> {
+    const END_OF_DIRECTORY: [u8; 4] = [b'P', b'K', 5, 6];
+    let truncated = || "The zip archive is truncated".to_string();
+
+    let end = (0..data.len().saturating_sub(21))
+        .rev()
+        .find(|&at| data[at..at + 4] == END_OF_DIRECTORY)
+        .ok_or("Not a zip archive")?;
+    let count = u16_at(data, end + 10).ok_or_else(truncated)?;
+    let mut at = u32_at(data, end + 16).ok_or_else(truncated)?;
+
+    let mut files = Vec::new();
+    for _ in 0..count {
+        if data.get(at..at + 4) != Some(&[b'P', b'K', 1, 2]) {
+            return Err(truncated());
+        }
+        let method = u16_at(data, at + 10).ok_or_else(truncated)?;
+        let compressed = u32_at(data, at + 20).ok_or_else(truncated)?;
+        let name_len = u16_at(data, at + 28).ok_or_else(truncated)?;
+        let extra_len = u16_at(data, at + 30).ok_or_else(truncated)?;
+        let comment_len = u16_at(data, at + 32).ok_or_else(truncated)?;
+        let mode = u32_at(data, at + 38).ok_or_else(truncated)? >> 16;
+        let local = u32_at(data, at + 42).ok_or_else(truncated)?;
+        let name = data.get(at + 46..at + 46 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        at += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        let start = local
+            + 30
+            + u16_at(data, local + 26).ok_or_else(truncated)?
+            + u16_at(data, local + 28).ok_or_else(truncated)?;
+        let raw = data.get(start..start + compressed).ok_or_else(truncated)?;
+        let contents = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut contents = Vec::new();
+                DeflateDecoder::new(raw)
+                    .read_to_end(&mut contents)
+                    .map_err(|e| format!("Failed to unpack {}: {}", name, e))?;
+                contents
+            }
+            _ => return Err(format!("{} uses an unsupported zip compression", name)),
+        };
+        files.push((name, mode as u32, contents));
+    }
+    Ok(files)
+}
+
+/// Regular files in a gzipped tarball; links and special files are skipped
+fn read_tar_gz(data: &[u8]) -> Result<Vec<File>, String> {
+    let mut tar = Vec::new();
+    GzDecoder::new(data)
+        .read_to_end(&mut tar)
+        .map_err(|e| format!("Failed to unpack the archive: {}", e))?;
+
+    let text = |field: &[u8]| {
+        let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+        String::from_utf8_lossy(&field[..end]).into_owned()
+    };
+    let octal = |field: &[u8]| usize::from_str_radix(text(field).trim(), 8).unwrap_or(0);
+
+    let mut files = Vec::new();
+    let mut at = 0;
+    while let Some(header) = tar.get(at..at + 512) {
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let mut name = text(&header[..100]);
+        if &header[257..262] == b"ustar" {
+            let prefix = text(&header[345..500]);
+            if !prefix.is_empty() {
+                name = format!("{}/{}", prefix, name);
+            }
+        }
+        let size = octal(&header[124..136]);
+        let contents =
+            tar.get(at + 512..at + 512 + size).ok_or("The tar archive is truncated")?;
+        if matches!(header[156], b'0' | 0) {
+            files.push((name, octal(&header[100..108]) as u32, contents.to_vec()));
+        }
+        at += 512 + size.div_ceil(512) * 512;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn tar_entry(tar: &mut Vec<u8>, name: &str, mode: u32, contents: &[u8]) {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(format!("{:07o}", mode).as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+        header[156] = b'0';
+        tar.extend_from_slice(&header);
+        tar.extend_from_slice(contents);
+        tar.resize(tar.len().div_ceil(512) * 512, 0);
+    }
+
+    fn zip_with(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut zip = Vec::new();
+        zip.extend_from_slice(&[b'P', b'K', 3, 4]);
+        zip.extend_from_slice(&[0; 22]);
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0; 2]);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(contents);
+
+        let directory = zip.len() as u32;
+        let mut entry = vec![0u8; 46];
+        entry[..4].copy_from_slice(&[b'P', b'K', 1, 2]);
+        entry[20..24].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+        entry[28..30].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        entry[38..42].copy_from_slice(&(0o100755u32 << 16).to_le_bytes());
+        zip.extend_from_slice(&entry);
+        zip.extend_from_slice(name.as_bytes());
+
+        let mut end = vec![0u8; 22];
+        end[..4].copy_from_slice(&[b'P', b'K', 5, 6]);
+        end[10..12].copy_from_slice(&1u16.to_le_bytes());
+        end[16..20].copy_from_slice(&directory.to_le_bytes());
+        zip.extend_from_slice(&end);
+        zip
+    }
+
+    #[test]
+    fn archives_unpack_and_refuse_paths_outside_the_install() {
+        let dir = std::env::temp_dir().join("bas-veeg-arc-test-archive");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "build/bas-veeg-arc", 0o755, b"binary");
+        tar_entry(&mut tar, "build/assets/sprites.pak", 0o644, b"sprites");
+        let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(&tar).unwrap();
+        let archive = dir.join("release.tar.gz");
+        fs::write(&archive, gz.finish().unwrap()).unwrap();
+
+        assert_eq!(Kind::of(&archive), Some(Kind::TarGz));
+        let unpacked = dir.join("unpacked");
+        let entries = extract(Kind::TarGz, &archive, &unpacked).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry { path: "build/bas-veeg-arc".into(), executable: true },
+                Entry { path: "build/assets/sprites.pak".into(), executable: false },
+            ]
+        );
+        assert_eq!(fs::read(unpacked.join("build/assets/sprites.pak")).unwrap(), b"sprites");
+
+        let zip = dir.join("release.zip");
+        fs::write(&zip, zip_with("bas-veeg-arc.exe", b"binary")).unwrap();
+        assert_eq!(Kind::of(&zip), Some(Kind::Zip));
+        let entries = extract(Kind::Zip, &zip, &unpacked).unwrap();
+        assert_eq!(entries[0].path, PathBuf::from("bas-veeg-arc.exe"));
+        assert!(entries[0].executable);
+
+        fs::write(&zip, zip_with("../escaped", b"evil")).unwrap();
+        assert!(extract(Kind::Zip, &zip, &unpacked).is_err());
+        assert!(!dir.join("escaped").exists());
+        assert_eq!(Kind::of(&unpacked.join("build/bas-veeg-arc")), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,119 @@
+use super::verify::{self, Sha256};
+use super::{archive, install};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Release asset listing every file of each build with its checksum, so an
+/// install only downloads the files that differ from what it has
+pub const FILE_LIST_NAME: &str = "FILES.json";
+
+#[derive(Deserialize)]
+struct FileList {
+    files: Vec<ListedFile>,
+}
+
+#[derive(Deserialize)]
+struct ListedFile {
+    /// Relative to the install folder; `bas-veeg-arc` is the game itself
+    path: String,
+    sha256: String,
+    size: u64,
+    url: String,
+    /// `os-arch` the file is built for, like `linux-x86_64`; files every
+    /// build shares leave it out
+    #[serde(default)]
+    platform: Option<String>,
+}
+
+/// A listed file the install doesn't have yet
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub url: String,
+    pub sha256: Sha256,
+    pub size: u64,
+    pub target: PathBuf,
+    pub executable: bool,
+}
+
+/// The files in `list` this platform needs that differ from the ones at
+/// `install_dir`, with the game binary going to `exe`
+pub fn changes(list: &str, install_dir: &Path, exe: &Path) -> Result<Vec<Change>, String> {
+    let list: FileList = serde_json::from_str(list)
+        .map_err(|e| format!("The release's file list is invalid: {}", e))?;
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+
+    let mut has_binary = false;
+    let mut changes = Vec::new();
+    for file in list.files {
+        if file.platform.as_ref().is_some_and(|built_for| *built_for != platform) {
+            continue;
+        }
+        let invalid = || format!("The release's file list has a bad entry for {}", file.path);
+        let path = archive::safe_path(&file.path).ok_or_else(invalid)?;
+        let sha256 = verify::parse_hex(&file.sha256)
+            .and_then(|hash| hash.try_into().ok())
+            .ok_or_else(invalid)?;
+
+        let binary = install::is_binary(&path);
+        has_binary |= binary;
+        let target = if binary {
+            exe.to_path_buf()
+        } else {
+            install_dir.join(&path)
+        };
+        if verify::hash_file(&target) != Some(sha256) {
+            changes.push(Change {
+                url: file.url,
+                sha256,
+                size: file.size,
+                target,
+                executable: binary,
+            });
+        }
+    }
+
+    if !has_binary {
+        return Err(format!("The release's file list has no build for {}", platform));
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn only_files_that_differ_for_this_platform_are_fetched() {
+        let dir = std::env::temp_dir().join("bas-veeg-arc-test-delta");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::write(dir.join("assets/sprites.pak"), b"abc").unwrap();
+        let exe = dir.join("bas-veeg-arc");
+        fs::write(&exe, b"old binary").unwrap();
+
+        let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let list = format!(
+            r#"{{"files": [
+                {{"path": "bas-veeg-arc", "sha256": "{zero}", "size": 10, "url": "bin",
+                  "platform": "{platform}"}},
+                {{"path": "bas-veeg-arc.exe", "sha256": "{zero}", "size": 10, "url": "exe",
+                  "platform": "other-os"}},
+                {{"path": "assets/sprites.pak", "sha256": "{abc}", "size": 3, "url": "pak"}},
+                {{"path": "assets/voices.pak", "sha256": "{abc}", "size": 3, "url": "voices"}}
+            ]}}"#,
+            zero = "00".repeat(32),
+        );
+
+        let changes = changes(&list, &dir, &exe).unwrap();
+        let urls: Vec<&str> = changes.iter().map(|change| change.url.as_str()).collect();
+        assert_eq!(urls, vec!["bin", "voices"]);
+        assert_eq!(changes[0].target, exe);
+        assert!(changes[0].executable);
+
+        let escaping = list.replace("assets/voices.pak", "../voices.pak");
+        assert!(super::changes(&escaping, &dir, &exe).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::data::crash;
+use super::{archive, Updater};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// The game binary's name in archives and file lists, before any extension
+const BINARY_NAME: &str = "bas-veeg-arc";
+/// Launches an update gets to reach the menu before it's rolled back
+const MAX_LAUNCH_ATTEMPTS: u32 = 2;
+/// How long the watcher gives a new build to reach the menu
+const LAUNCH_GRACE: Duration = Duration::from_secs(20);
+/// Starts the old build as a watcher over the launch of the new one
+pub const WATCH_ARG: &str = "--launch-update";
+
+/// A file ready to go into the install: where it goes and where it's waiting
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incoming {
+    pub target: PathBuf,
+    pub staged: PathBuf,
+    pub executable: bool,
+}
+
+/// What the last update replaced, kept until the new build reaches the menu
+#[derive(Serialize, Deserialize)]
+struct Journal {
+    version: String,
+    /// Launches of the new build that haven't reached the menu
+    attempts: u32,
+    executable: PathBuf,
+    files: Vec<Replaced>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Replaced {
+    path: PathBuf,
+    /// The file it replaced; `None` when it's new with the update
+    backup: Option<PathBuf>,
+}
+
+/// Whether `path` is the game itself rather than one of its data files
+pub fn is_binary(path: &Path) -> bool {
+    let stem = path.file_stem().and_then(|stem| stem.to_str());
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    stem == Some(BINARY_NAME) && matches!(extension, None | Some("exe"))
+}
+
+/// Where each file unpacked from a release archive goes. A folder holding
+/// everything is the archive's own and dropped; the game binary replaces the
+/// installed executable, and the rest lands beside it.
+pub fn archive_layout(entries: Vec<archive::Entry>, unpacked: &Path, exe: &Path) -> Vec<Incoming> {
+    let install_dir = exe.parent().unwrap_or(Path::new("."));
+    let root = entries.first().and_then(|entry| entry.path.components().next());
+    let shared_root = root
+        .filter(|root| {
+            entries
+                .iter()
+                .all(|entry| entry.path.components().count() > 1 && entry.path.starts_with(root))
+        })
+        .map(|root| PathBuf::from(root.as_os_str()));
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let relative = match &shared_root {
+                Some(root) => entry.path.strip_prefix(root).unwrap_or(&entry.path),
+                None => &entry.path,
+            };
+            let binary = is_binary(relative);
+            Incoming {
+                target: if binary {
+                    exe.to_path_buf()
+                } else {
+                    install_dir.join(relative)
+                },
+                staged: unpacked.join(&entry.path),
+                executable: binary || entry.executable,
+            }
+        })
+        .collect()
+}
+
+/// Put every file in place, backing up what each replaces. If one fails,
+/// those already swapped go back; otherwise the backups stay until the new
+/// build reaches the menu.
+pub fn install(version: &str, files: &[Incoming]) -> Result<(), String> {
+    let replaced = replace_all(files)?;
+    let journal = Journal {
+        version: version.to_string(),
+        attempts: 0,
+        executable: Updater::installed_executable()?,
+        files: replaced,
+    };
+    write_journal(&journal).inspect_err(|_| {
+        let _ = restore(&journal.files);
+    })
+}
+
+fn replace_all(files: &[Incoming]) -> Result<Vec<Replaced>, String> {
+    let mut replaced = Vec::new();
+    for file in files {
+        match replace(file) {
+            Ok(done) => replaced.push(done),
+            Err(e) => {
+                let _ = restore(&replaced);
+                return Err(e);
+            }
+        }
+    }
+    Ok(replaced)
+}
+
+fn replace(file: &Incoming) -> Result<Replaced, String> {
+    let target = &file.target;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let backup = target.exists().then(|| with_suffix(target, ".old"));
+
+    #[cfg(target_os = "windows")]
+    {
+        // A running executable can't be written over, but it can be moved aside
+        if let Some(backup) = &backup {
+            let _ = fs::remove_file(backup);
+            fs::rename(target, backup)
+                .map_err(|e| format!("Failed to back up {}: {}", target.display(), e))?;
+        }
+        if let Err(e) = fs::copy(&file.staged, target) {
+            if let Some(backup) = &backup {
+                let _ = fs::rename(backup, target);
+            }
+            return Err(format!("Failed to install {}: {}", target.display(), e));
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if file.executable {
+            fs::set_permissions(&file.staged, fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Failed to set permissions: {}", e))?;
+        }
+        if let Some(backup) = &backup {
+            fs::copy(target, backup)
+                .map_err(|e| format!("Failed to back up {}: {}", target.display(), e))?;
+        }
+
+        // Copy next to the file and rename over it, which works while it's
+        // running and never leaves it half-written
+        let incoming = with_suffix(target, ".new");
+        fs::copy(&file.staged, &incoming)
+            .map_err(|e| format!("Failed to install {}: {}", target.display(), e))?;
+        fs::rename(&incoming, target).map_err(|e| {
+            let _ = fs::remove_file(&incoming);
+            format!("Failed to install {}: {}", target.display(), e)
+        })?;
+    }
+
+    Ok(Replaced {
+        path: target.clone(),
+        backup,
+    })
+}
+
+/// Put back what `replaced` swapped out, last first
+fn restore(replaced: &[Replaced]) -> Result<(), String> {
+    let mut failed = Ok(());
+    for file in replaced.iter().rev() {
+        let restored = match &file.backup {
+            Some(backup) => put_back(backup, &file.path),
+            None => fs::remove_file(&file.path),
+        };
+        if let Err(e) = restored {
+            failed = Err(format!("Failed to restore {}: {}", file.path.display(), e));
+        }
+    }
+    failed
+}
+
+fn put_back(backup: &Path, path: &Path) -> io::Result<()> {
+    // Renaming over a running Windows executable fails, but moving it aside works
+    if fs::rename(backup, path).is_err() {
+        let _ = fs::rename(path, with_suffix(path, ".failed"));
+        fs::rename(backup, path)?;
+    }
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn journal_path() -> Option<PathBuf> {
+    let exe = Updater::installed_executable().ok()?;
+    Some(exe.with_file_name("update-rollback.json"))
+}
+
+fn read_journal() -> Option<Journal> {
+    serde_json::from_str(&fs::read_to_string(journal_path()?).ok()?).ok()
+}
+
+fn write_journal(journal: &Journal) -> Result<(), String> {
+    let path = journal_path().ok_or("Failed to find the install folder")?;
+    let json = serde_json::to_string_pretty(journal).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to record the update for rollback: {}", e))
+}
+
+/// The new build reached the menu, so its update is here to stay
+pub fn confirm_launch() {
+    if let Some(path) = journal_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Count this launch against an unconfirmed update, rolling it back once it
+/// has failed to reach the menu too often. `true` when the old build was
+/// put back and started in this one's place.
+pub fn recover_failed_update() -> bool {
+    let Some(mut journal) = read_journal() else {
+        return false;
+    };
+    if journal.attempts < MAX_LAUNCH_ATTEMPTS {
+        journal.attempts += 1;
+        let _ = write_journal(&journal);
+        return false;
+    }
+    roll_back_and_start(journal)
+}
+
+fn roll_back_and_start(journal: Journal) -> bool {
+    match restore(&journal.files) {
+        Ok(()) => {
+            crash::log(format!("Update {} didn't start; rolled back", journal.version));
+            confirm_launch();
+            Command::new(&journal.executable).spawn().is_ok()
+        }
+        Err(e) => {
+            crash::log(format!("Failed to roll back update {}: {}", journal.version, e));
+            false
+        }
+    }
+}
+
+/// Start the freshly installed build. The old one, kept as its backup, is
+/// started to watch it launch, so it can be rolled back if it doesn't.
+pub fn restart_into_update(exe: &Path) {
+    let old_build = read_journal().and_then(|journal| {
+        journal.files.into_iter().find(|file| file.path == exe).and_then(|file| file.backup)
+    });
+    let watching = old_build.is_some_and(|old| Command::new(old).arg(WATCH_ARG).spawn().is_ok());
+    if !watching {
+        let _ = Command::new(exe).spawn();
+    }
+}
+
+/// Run by the old build under `--launch-update`: start the new build and
+/// wait for it to reach the menu. If it can't start, or exits with an error
+/// before then, the old files go back and the old build starts instead.
+pub fn watch_update_launch() {
+    let Some(journal) = read_journal() else {
+        return;
+    };
+    if !launched(&journal.executable) {
+        roll_back_and_start(journal);
+    }
+}
+
+/// Whether `exe` started and reached the menu, or at least didn't fail
+/// before the grace ran out
+fn launched(exe: &Path) -> bool {
+    let Ok(mut child) = Command::new(exe).spawn() else {
+        return false;
+    };
+    let started = Instant::now();
+    while started.elapsed() < LAUNCH_GRACE {
+        if journal_path().is_some_and(|path| !path.exists()) {
+            return true;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) => std::thread::sleep(Duration::from_millis(200)),
+            Err(_) => return true,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaced_files_keep_backups_and_restore_undoes_the_update() {
+        let dir = std::env::temp_dir().join("bas-veeg-arc-test-install");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("staged/build/assets")).unwrap();
+        fs::write(dir.join("bas-veeg-arc"), b"old binary").unwrap();
+        fs::write(dir.join("staged/build/bas-veeg-arc"), b"new binary").unwrap();
+        fs::write(dir.join("staged/build/assets/music.pak"), b"music").unwrap();
+
+        let entries = vec![
+            archive::Entry {
+                path: "build/bas-veeg-arc".into(),
+                executable: false,
+            },
+            archive::Entry {
+                path: "build/assets/music.pak".into(),
+                executable: false,
+            },
+        ];
+        let exe = dir.join("bas-veeg-arc");
+        let files = archive_layout(entries, &dir.join("staged"), &exe);
+        assert_eq!(files[0].target, exe);
+        assert!(files[0].executable);
+        assert_eq!(files[1].target, dir.join("assets/music.pak"));
+
+        let replaced = replace_all(&files).unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), b"new binary");
+        assert_eq!(fs::read(dir.join("bas-veeg-arc.old")).unwrap(), b"old binary");
+        assert_eq!(replaced[1].backup, None);
+
+        restore(&replaced).unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), b"old binary");
+        assert!(!dir.join("assets/music.pak").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
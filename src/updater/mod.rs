@@ -1,26 +1,93 @@
-use serde::Deserialize;
+use crate::data::{crash, GameConfig};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+mod archive;
+mod delta;
+mod install;
+mod verify;
+mod version;
+mod whats_new;
+
+pub use install::{confirm_launch, recover_failed_update, watch_update_launch, WATCH_ARG};
+pub use verify::Verification;
+use version::Version;
+pub use whats_new::{check_for_new_version, mark_seen, news, take_whats_new, FeatureLink, WhatsNew};
 
 pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const GITHUB_REPO: &str = "compiledkernel-idk/bas-veeg-arc";
 const UPDATE_CHECK_URL: &str =
     "https://api.github.com/repos/compiledkernel-idk/bas-veeg-arc/releases/latest";
+/// Every release, pre-releases included, newest first
+const RELEASES_URL: &str =
+    "https://api.github.com/repos/compiledkernel-idk/bas-veeg-arc/releases?per_page=30";
+/// Seconds "remind me later" keeps the launch check quiet
+const REMIND_DELAY: u64 = 24 * 60 * 60;
+/// Seconds the release check waits on GitHub before giving up
+const CHECK_TIMEOUT: u64 = 10;
+/// Bytes read from the download between progress reports and cancel checks
+const DOWNLOAD_CHUNK: usize = 64 * 1024;
 
 #[derive(Debug, Clone)]
 pub struct UpdateInfo {
     pub available: bool,
     pub latest_version: String,
-    pub download_url: Option<String>,
+    pub source: Option<UpdateSource>,
+    /// Why `source` is missing for an available update
+    pub asset_error: Option<String>,
     pub changelog: String,
 }
 
+/// Where an update downloads from, with the published checksums it has to
+/// match before it's installed
+#[derive(Debug, Clone)]
+pub enum UpdateSource {
+    /// One asset holding the build: an executable, or an archive of the
+    /// whole install
+    Asset { url: String, manifest: verify::Manifest },
+    /// The release's file list, so only the files that changed download
+    Files { url: String, manifest: verify::Manifest },
+}
+
 #[derive(Deserialize)]
 struct GithubRelease {
     tag_name: String,
     name: String,
     body: String,
     assets: Vec<GithubAsset>,
+    #[serde(default)]
+    draft: bool,
+}
+
+/// Which releases the player is offered
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Pre-releases too, as soon as they're published
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn label(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "STABLE",
+            UpdateChannel::Beta => "BETA",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            UpdateChannel::Stable => UpdateChannel::Beta,
+            UpdateChannel::Beta => UpdateChannel::Stable,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -29,11 +96,154 @@ struct GithubAsset {
     browser_download_url: String,
 }
 
+/// How a release asset is packaged, judged from its file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AssetPackage {
+    Exe,
+    AppImage,
+    /// Bare executable with no extension
+    Binary,
+    Zip,
+    TarGz,
+}
+
+impl AssetPackage {
+    fn from_name(name: &str) -> Option<Self> {
+        if name.ends_with(".exe") {
+            Some(AssetPackage::Exe)
+        } else if name.ends_with(".appimage") {
+            Some(AssetPackage::AppImage)
+        } else if name.ends_with(".zip") {
+            Some(AssetPackage::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(AssetPackage::TarGz)
+        } else if !name.contains('.') || name.ends_with(".x86_64") || name.ends_with(".aarch64") {
+            Some(AssetPackage::Binary)
+        } else {
+            None
+        }
+    }
+
+    /// Packages usable on this platform, most preferred first
+    fn preference_order() -> Vec<AssetPackage> {
+        #[cfg(target_os = "windows")]
+        let order = vec![AssetPackage::Exe, AssetPackage::Zip];
+
+        // An AppImage install should stay an AppImage, a bare install a bare binary
+        #[cfg(target_os = "linux")]
+        let order = if std::env::var_os("APPIMAGE").is_some() {
+            vec![
+                AssetPackage::AppImage,
+                AssetPackage::Binary,
+                AssetPackage::TarGz,
+                AssetPackage::Zip,
+            ]
+        } else {
+            vec![
+                AssetPackage::Binary,
+                AssetPackage::TarGz,
+                AssetPackage::AppImage,
+                AssetPackage::Zip,
+            ]
+        };
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let order = vec![AssetPackage::Binary, AssetPackage::TarGz, AssetPackage::Zip];
+
+        order
+    }
+}
+
+/// Name fragments release assets use for each OS and architecture
+fn os_tokens() -> &'static [&'static str] {
+    match std::env::consts::OS {
+        "windows" => &["windows", "win64"],
+        "macos" => &["macos", "darwin", "osx"],
+        "linux" => &["linux"],
+        _ => &[],
+    }
+}
+
+fn arch_tokens(arch: &str) -> &'static [&'static str] {
+    match arch {
+        "x86_64" => &["x86_64", "x64", "amd64"],
+        "aarch64" => &["aarch64", "arm64"],
+        _ => &[],
+    }
+}
+
+const KNOWN_ARCHES: [&str; 2] = ["x86_64", "aarch64"];
+
+/// Checks for and installs updates without holding the game up: the network
+/// work runs on a worker thread, and `poll` takes in what it reports
 pub struct Updater {
     pub status: UpdateStatus,
     pub info: Option<UpdateInfo>,
+    /// Percent downloaded; stays at 0 when the server doesn't give a size
     pub download_progress: f32,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
     pub error_message: Option<String>,
+    /// How the download was checked, once it has been
+    pub verification: Option<Verification>,
+    /// Whether the check running is the one at launch
+    on_launch: bool,
+    /// Reports from the worker running the current check or download
+    events: Option<Receiver<UpdateEvent>>,
+    /// Raised to stop the download in flight
+    cancel: Arc<AtomicBool>,
+}
+
+/// What a worker thread sends back to `Updater::poll`
+enum UpdateEvent {
+    Checked(Result<UpdateInfo, String>),
+    Progress { written: u64, total: Option<u64> },
+    Installing(Verification),
+    Installed(Result<(), String>),
+}
+
+/// An update being fetched while the game keeps running
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackgroundUpdate {
+    Downloading { version: String, progress: f32 },
+    /// Fully downloaded and verified; swapped in when the game exits
+    Staged { version: String, verification: Verification },
+    Failed(String),
+}
+
+// Outlives the boot screen's Updater, and is shared with the download thread
+static BACKGROUND_UPDATE: Mutex<Option<BackgroundUpdate>> = Mutex::new(None);
+// What a staged background update puts in place on exit
+static STAGED_FILES: Mutex<Vec<install::Incoming>> = Mutex::new(Vec::new());
+
+pub fn background_update() -> Option<BackgroundUpdate> {
+    if let Ok(update) = BACKGROUND_UPDATE.lock() {
+        update.clone()
+    } else {
+        None
+    }
+}
+
+fn set_background_update(update: BackgroundUpdate) {
+    if let Ok(mut current) = BACKGROUND_UPDATE.lock() {
+        *current = Some(update);
+    }
+}
+
+/// Install a staged background update. Call on the way out so the new version
+/// is what starts next launch; a download still in flight is left alone.
+pub fn install_staged_update() {
+    let Some(BackgroundUpdate::Staged { version, .. }) = background_update() else {
+        return;
+    };
+
+    let files = STAGED_FILES.lock().map(|files| files.clone()).unwrap_or_default();
+    match install::install(&version, &files) {
+        Ok(_) => {
+            let _ = fs::remove_dir_all(Updater::staged_update_dir());
+        }
+        Err(e) => crash::log(format!("Failed to install update {}: {}", version, e)),
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -46,6 +256,9 @@ pub enum UpdateStatus {
     Installing,
     Error,
     UpToDate,
+    /// Newer than this build, but the player skipped it or asked to be
+    /// reminded later
+    Deferred,
 }
 
 impl Updater {
@@ -54,34 +267,155 @@ impl Updater {
             status: UpdateStatus::Idle,
             info: None,
             download_progress: 0.0,
+            downloaded_bytes: 0,
+            total_bytes: None,
             error_message: None,
+            verification: None,
+            on_launch: false,
+            events: None,
+            cancel: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn check_for_updates(&mut self) {
+    /// Ask GitHub for the latest release on the player's channel; the answer
+    /// arrives through `poll`. The check at launch keeps quiet about a
+    /// version the player put off, where one they ask for from the menu
+    /// doesn't.
+    pub fn check_for_updates(&mut self, on_launch: bool) {
         self.status = UpdateStatus::Checking;
         self.error_message = None;
+        self.on_launch = on_launch;
+        let channel = GameConfig::load().update_channel;
+        self.spawn(move |sender, _| {
+            let _ = sender.send(UpdateEvent::Checked(Self::fetch_latest_release(channel)));
+        });
+    }
+
+    /// Stop offering this version; a newer one is offered again
+    pub fn skip_version(&mut self) {
+        let Some(info) = &self.info else {
+            return;
+        };
+        let mut config = GameConfig::load();
+        config.skipped_version = Some(info.latest_version.clone());
+        Self::save_config(&config);
+        self.status = UpdateStatus::Deferred;
+    }
+
+    /// Keep the launch check quiet about updates for a day
+    pub fn remind_later(&mut self) {
+        let mut config = GameConfig::load();
+        config.remind_updates_after = crate::data::events::now() + REMIND_DELAY;
+        Self::save_config(&config);
+        self.status = UpdateStatus::Deferred;
+    }
+
+    fn save_config(config: &GameConfig) {
+        if let Err(e) = config.save() {
+            crash::log(format!("Failed to save update preferences: {}", e));
+        }
+    }
+
+    /// Whether the player has put `version` off, by skipping it or asking to
+    /// be reminded later
+    fn is_deferred(config: &GameConfig, version: &str, now: u64) -> bool {
+        config.skipped_version.as_deref() == Some(version) || now < config.remind_updates_after
+    }
+
+    /// Run `job` on a worker thread with a fresh channel and cancel flag, in
+    /// place of whatever ran before
+    fn spawn(&mut self, job: impl FnOnce(Sender<UpdateEvent>, Arc<AtomicBool>) + Send + 'static) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let cancel = self.cancel.clone();
+        let (sender, events) = mpsc::channel();
+        std::thread::spawn(move || job(sender, cancel));
+        self.events = Some(events);
+    }
+
+    /// Take in everything the worker has reported since the last frame
+    pub fn poll(&mut self) {
+        while let Some(events) = &self.events {
+            match events.try_recv() {
+                Ok(event) => self.apply(event),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.events = None;
+                    let working = matches!(
+                        self.status,
+                        UpdateStatus::Checking
+                            | UpdateStatus::Downloading
+                            | UpdateStatus::Installing
+                    );
+                    if working {
+                        self.fail("The updater stopped unexpectedly".to_string());
+                    }
+                }
+            }
+        }
+    }
 
-        match self.fetch_latest_release() {
-            Ok(info) => {
+    fn apply(&mut self, event: UpdateEvent) {
+        match event {
+            UpdateEvent::Checked(Ok(info)) => {
                 if info.available {
-                    self.status = UpdateStatus::UpdateAvailable;
+                    let now = crate::data::events::now();
+                    let deferred = self.on_launch
+                        && Self::is_deferred(&GameConfig::load(), &info.latest_version, now);
+                    self.status = if deferred {
+                        UpdateStatus::Deferred
+                    } else {
+                        UpdateStatus::UpdateAvailable
+                    };
                     self.info = Some(info);
                 } else {
                     self.status = UpdateStatus::UpToDate;
                 }
             }
-            Err(e) => {
-                self.status = UpdateStatus::Error;
-                self.error_message = Some(format!("Failed to check for updates: {}", e));
+            UpdateEvent::Checked(Err(e)) => {
+                self.fail(format!("Failed to check for updates: {}", e))
+            }
+            UpdateEvent::Progress { written, total } => {
+                self.downloaded_bytes = written;
+                self.total_bytes = total;
+                self.download_progress = match total.filter(|&total| total > 0) {
+                    Some(total) => written as f32 / total as f32 * 100.0,
+                    None => 0.0,
+                };
             }
+            UpdateEvent::Installing(verification) => {
+                self.status = UpdateStatus::Installing;
+                self.verification = Some(verification);
+            }
+            UpdateEvent::Installed(Ok(())) => self.status = UpdateStatus::ReadyToInstall,
+            UpdateEvent::Installed(Err(e)) => self.fail(e),
+        }
+    }
+
+    fn fail(&mut self, message: String) {
+        self.status = UpdateStatus::Error;
+        self.error_message = Some(message);
+    }
+
+    /// Stop the download in flight and offer the update again. Once it's
+    /// installing, the swap is left to finish.
+    pub fn cancel(&mut self) {
+        if self.status != UpdateStatus::Downloading {
+            return;
         }
+        self.cancel.store(true, Ordering::Relaxed);
+        self.events = None;
+        self.status = UpdateStatus::UpdateAvailable;
+        self.download_progress = 0.0;
+        self.downloaded_bytes = 0;
+        self.total_bytes = None;
     }
 
-    fn fetch_latest_release(&self) -> Result<UpdateInfo, String> {
+    fn fetch_json<T: DeserializeOwned>(url: &str) -> Result<T, String> {
         // Fetch from GitHub API with user agent (required by GitHub)
-        let response = minreq::get(UPDATE_CHECK_URL)
+        let response = minreq::get(url)
             .with_header("User-Agent", "bas-veeg-arc-updater")
+            .with_timeout(CHECK_TIMEOUT)
             .send()
             .map_err(|e| format!("Network error: {}", e))?;
 
@@ -92,9 +426,28 @@ impl Updater {
             ));
         }
 
-        let release: GithubRelease =
-            serde_json::from_str(response.as_str().map_err(|e| e.to_string())?)
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
+        serde_json::from_str(response.as_str().map_err(|e| e.to_string())?)
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// The newest published release, leaving drafts out
+    fn newest_release(releases: Vec<GithubRelease>) -> Option<GithubRelease> {
+        releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .filter_map(|release| Some((Version::parse(&release.tag_name)?, release)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, release)| release)
+    }
+
+    fn fetch_latest_release(channel: UpdateChannel) -> Result<UpdateInfo, String> {
+        // GitHub's latest release is never a pre-release
+        let release: GithubRelease = match channel {
+            UpdateChannel::Stable => Self::fetch_json(UPDATE_CHECK_URL)?,
+            UpdateChannel::Beta => Self::newest_release(Self::fetch_json(RELEASES_URL)?)
+                .ok_or("No releases have been published")?,
+        };
+        whats_new::set_news(&release.tag_name, &release.body);
 
         // Remove 'v' prefix if present
         let latest_version = release.tag_name.trim_start_matches('v').to_string();
@@ -102,108 +455,292 @@ impl Updater {
 
         let available = Self::is_newer_version(&latest_version, current_version);
 
-        // Find the appropriate download URL for this platform
-        let download_url = if available {
-            self.find_platform_asset(&release.assets)
+        // Find the appropriate download for this platform
+        let (source, asset_error) = if !available {
+            (None, None)
         } else {
-            None
+            match Self::find_source(&release.assets) {
+                Ok(source) => (Some(source), None),
+                Err(e) => (None, Some(e)),
+            }
         };
 
         Ok(UpdateInfo {
             available,
             latest_version: latest_version.clone(),
-            download_url,
+            source,
+            asset_error,
             changelog: release.body,
         })
     }
 
-    fn find_platform_asset(&self, assets: &[GithubAsset]) -> Option<String> {
-        #[cfg(target_os = "windows")]
-        let platform_name = "windows";
+    /// How to fetch a release: through its file list when it has one, so only
+    /// what changed downloads, otherwise the asset built for this platform.
+    /// A release that publishes no checksums can't be verified, so it isn't
+    /// offered at all.
+    fn find_source(assets: &[GithubAsset]) -> Result<UpdateSource, String> {
+        let unverifiable =
+            || "This release publishes no checksums to verify it by; download it manually";
+
+        if let Some(list) = assets.iter().find(|asset| asset.name == delta::FILE_LIST_NAME) {
+            return Ok(UpdateSource::Files {
+                url: list.browser_download_url.clone(),
+                manifest: verify::Manifest::find(assets, &list.name).ok_or_else(unverifiable)?,
+            });
+        }
 
-        #[cfg(target_os = "linux")]
-        let platform_name = "linux";
-
-        #[cfg(target_os = "macos")]
-        let platform_name = "macos";
-
-        // Look for asset containing platform name
-        for asset in assets {
-            let name_lower = asset.name.to_lowercase();
-            if name_lower.contains(platform_name) {
-                #[cfg(target_os = "windows")]
-                if name_lower.ends_with(".exe") {
-                    return Some(asset.browser_download_url.clone());
+        let asset = Self::find_platform_asset(assets)?;
+        Ok(UpdateSource::Asset {
+            url: asset.browser_download_url.clone(),
+            manifest: verify::Manifest::find(assets, &asset.name).ok_or_else(unverifiable)?,
+        })
+    }
+
+    /// Pick the asset built for this OS and architecture, preferring the package
+    /// type that can replace the running install. Assets that name no
+    /// architecture are taken to be x86_64 builds, as older releases were.
+    fn find_platform_asset(assets: &[GithubAsset]) -> Result<&GithubAsset, String> {
+        let arch = std::env::consts::ARCH;
+        let names_arch = |name: &str, arch: &str| {
+            arch_tokens(arch).iter().any(|token| name.contains(token))
+        };
+
+        let candidates: Vec<(AssetPackage, &GithubAsset)> = assets
+            .iter()
+            .filter_map(|asset| {
+                let name = asset.name.to_lowercase();
+                if !os_tokens().iter().any(|token| name.contains(token)) {
+                    return None;
                 }
 
-                #[cfg(not(target_os = "windows"))]
-                if !name_lower.ends_with(".exe") {
-                    return Some(asset.browser_download_url.clone());
+                let arch_matches = if KNOWN_ARCHES.iter().any(|known| names_arch(&name, known)) {
+                    names_arch(&name, arch)
+                } else {
+                    arch == "x86_64"
+                };
+                if !arch_matches {
+                    return None;
                 }
+
+                AssetPackage::from_name(&name).map(|package| (package, asset))
+            })
+            .collect();
+
+        let best = AssetPackage::preference_order().into_iter().find_map(|package| {
+            candidates
+                .iter()
+                .find(|(candidate, _)| *candidate == package)
+        });
+
+        match best {
+            Some((_, asset)) => Ok(*asset),
+            None => {
+                let available: Vec<&str> = assets.iter().map(|a| a.name.as_str()).collect();
+                Err(format!(
+                    "No release asset for {}/{} (available: {})",
+                    std::env::consts::OS,
+                    arch,
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                ))
             }
         }
-
-        None
     }
 
+    /// Whether `latest` is a later version than `current`; never when either
+    /// isn't a version at all
     fn is_newer_version(latest: &str, current: &str) -> bool {
-        // Simple version comparison (assumes semantic versioning)
-        let parse_version =
-            |v: &str| -> Vec<u32> { v.split('.').filter_map(|s| s.parse().ok()).collect() };
+        match (Version::parse(latest), Version::parse(current)) {
+            (Some(latest), Some(current)) => latest > current,
+            _ => false,
+        }
+    }
+
+    /// Download the update and swap it in on a worker thread, with progress
+    /// coming in through `poll`
+    pub fn download_and_install(&mut self) {
+        let download = self.info.as_ref().and_then(|info| {
+            Some((info.source.clone()?, info.latest_version.clone()))
+        });
+        let Some((source, version)) = download else {
+            self.report_missing_asset();
+            return;
+        };
 
-        let latest_parts = parse_version(latest);
-        let current_parts = parse_version(current);
+        self.status = UpdateStatus::Downloading;
+        self.error_message = None;
+        self.verification = None;
+        self.download_progress = 0.0;
+        self.downloaded_bytes = 0;
+        self.total_bytes = None;
+        self.spawn(move |sender, cancel| {
+            let dir = Self::download_dir();
+            let progress = |written, total| {
+                let _ = sender.send(UpdateEvent::Progress { written, total });
+            };
+            let result = match Self::prepare(&source, &dir, &cancel, progress) {
+                Ok((files, verification)) => {
+                    let _ = sender.send(UpdateEvent::Installing(verification));
+                    let installed = install::install(&version, &files);
+                    let _ = fs::remove_dir_all(&dir);
+                    installed.map_err(|e| format!("Installation failed: {}", e))
+                }
+                Err(e) => Err(format!("Download failed: {}", e)),
+            };
+            let _ = sender.send(UpdateEvent::Installed(result));
+        });
+    }
 
-        for i in 0..latest_parts.len().max(current_parts.len()) {
-            let l = latest_parts.get(i).unwrap_or(&0);
-            let c = current_parts.get(i).unwrap_or(&0);
+    fn report_missing_asset(&mut self) {
+        let reason = self.info.as_ref().and_then(|info| info.asset_error.clone());
+        self.status = UpdateStatus::Error;
+        self.error_message =
+            Some(reason.unwrap_or_else(|| "No update download for this platform".to_string()));
+    }
 
-            if l > c {
-                return true;
-            } else if l < c {
-                return false;
-            }
+    /// Download the update on a worker thread while the game is played; progress
+    /// is reported through `background_update`
+    pub fn download_in_background(&mut self) {
+        let Some(info) = self.info.clone() else {
+            return;
+        };
+        let Some(source) = info.source else {
+            self.report_missing_asset();
+            return;
+        };
+
+        if matches!(
+            background_update(),
+            Some(BackgroundUpdate::Downloading { .. } | BackgroundUpdate::Staged { .. })
+        ) {
+            return;
         }
 
-        false
+        let version = info.latest_version;
+        set_background_update(BackgroundUpdate::Downloading {
+            version: version.clone(),
+            progress: 0.0,
+        });
+
+        std::thread::spawn(move || {
+            let result = Self::stage_update(&source, &version);
+            set_background_update(match result {
+                Ok(verification) => BackgroundUpdate::Staged {
+                    version,
+                    verification,
+                },
+                Err(e) => BackgroundUpdate::Failed(format!("Update download failed: {}", e)),
+            });
+        });
     }
 
-    pub fn download_and_install(&mut self) {
-        // Clone the URL to avoid borrow checker issues
-        let url = if let Some(info) = &self.info {
-            info.download_url.clone()
-        } else {
-            None
-        };
+    /// Download to the staging folder, where it waits for the game to exit
+    fn stage_update(source: &UpdateSource, version: &str) -> Result<Verification, String> {
+        let never = AtomicBool::new(false);
+        let dir = Self::staged_update_dir();
+        let (files, verification) = Self::prepare(source, &dir, &never, |written, total| {
+            if let Some(total) = total.filter(|&total| total > 0) {
+                set_background_update(BackgroundUpdate::Downloading {
+                    version: version.to_string(),
+                    progress: written as f32 / total as f32 * 100.0,
+                });
+            }
+        })?;
+        if let Ok(mut staged) = STAGED_FILES.lock() {
+            *staged = files;
+        }
+        Ok(verification)
+    }
 
-        if let Some(url) = url {
-            self.status = UpdateStatus::Downloading;
-
-            match self.download_update(&url) {
-                Ok(temp_path) => {
-                    self.status = UpdateStatus::Installing;
-                    match self.install_update(&temp_path) {
-                        Ok(_) => {
-                            self.status = UpdateStatus::ReadyToInstall;
-                        }
-                        Err(e) => {
-                            self.status = UpdateStatus::Error;
-                            self.error_message = Some(format!("Installation failed: {}", e));
-                        }
+    /// Download and verify everything `source` needs into `dir`, unpacking
+    /// archives, and list the files to put in place
+    fn prepare(
+        source: &UpdateSource,
+        dir: &Path,
+        cancel: &AtomicBool,
+        progress: impl Fn(u64, Option<u64>),
+    ) -> Result<(Vec<install::Incoming>, Verification), String> {
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create update folder: {}", e))?;
+        let exe = Self::installed_executable()?;
+
+        match source {
+            UpdateSource::Asset { url, manifest } => {
+                let path = dir.join("download");
+                let verification = Self::download(url, &path, manifest, cancel, progress)?;
+                let files = match archive::Kind::of(&path) {
+                    Some(kind) => {
+                        let unpacked = dir.join("unpacked");
+                        let entries = archive::extract(kind, &path, &unpacked)?;
+                        install::archive_layout(entries, &unpacked, &exe)
                     }
+                    None => vec![install::Incoming {
+                        target: exe,
+                        staged: path,
+                        executable: true,
+                    }],
+                };
+                Ok((files, verification))
+            }
+            UpdateSource::Files { url, manifest } => {
+                let list_path = dir.join(delta::FILE_LIST_NAME);
+                let verification = Self::download(url, &list_path, manifest, cancel, |_, _| {})?;
+                let list = fs::read_to_string(&list_path)
+                    .map_err(|e| format!("Failed to read the release's file list: {}", e))?;
+                let install_dir = exe.parent().ok_or("Failed to find the install folder")?;
+                let changes = delta::changes(&list, install_dir, &exe)?;
+
+                // Progress runs across every file that's fetched
+                let total = changes.iter().map(|change| change.size).sum();
+                let mut done = 0;
+                let mut files = Vec::new();
+                for (i, change) in changes.into_iter().enumerate() {
+                    let staged = dir.join(i.to_string());
+                    Self::fetch(&change.url, &staged, &change.sha256, cancel, |written, _| {
+                        progress(done + written, Some(total))
+                    })?;
+                    done += change.size;
+                    files.push(install::Incoming {
+                        target: change.target,
+                        staged,
+                        executable: change.executable,
+                    });
                 }
-                Err(e) => {
-                    self.status = UpdateStatus::Error;
-                    self.error_message = Some(format!("Download failed: {}", e));
-                }
+                Ok((files, verification))
             }
         }
     }
 
-    fn download_update(&mut self, url: &str) -> Result<PathBuf, String> {
-        let response = minreq::get(url)
+    /// Fetch `url` to `path` once the manifest's checksum for it is in hand
+    fn download(
+        url: &str,
+        path: &Path,
+        manifest: &verify::Manifest,
+        cancel: &AtomicBool,
+        progress: impl Fn(u64, Option<u64>),
+    ) -> Result<Verification, String> {
+        let (expected, verification) = manifest.expected()?;
+        Self::fetch(url, path, &expected, cancel, progress)?;
+        Ok(verification)
+    }
+
+    /// Stream `url` to a partial file next to `path`, reporting bytes written
+    /// and the full size as they come in. The file only moves to `path` once
+    /// it's complete and matches `expected`, so a cut-short, cancelled or
+    /// tampered download is never installed.
+    fn fetch(
+        url: &str,
+        path: &Path,
+        expected: &verify::Sha256,
+        cancel: &AtomicBool,
+        progress: impl Fn(u64, Option<u64>),
+    ) -> Result<(), String> {
+        let mut response = minreq::get(url)
             .with_header("User-Agent", "bas-veeg-arc-updater")
-            .send()
+            .send_lazy()
             .map_err(|e| format!("Download error: {}", e))?;
 
         if response.status_code != 200 {
@@ -213,85 +750,151 @@ impl Updater {
             ));
         }
 
-        // Save to temp file
-        let temp_dir = std::env::temp_dir();
-
-        #[cfg(target_os = "windows")]
-        let temp_file = temp_dir.join("bas-veeg-arc-update.exe");
-
-        #[cfg(not(target_os = "windows"))]
-        let temp_file = temp_dir.join("bas-veeg-arc-update");
-
-        fs::write(&temp_file, response.as_bytes())
-            .map_err(|e| format!("Failed to save update: {}", e))?;
-
-        self.download_progress = 100.0;
-
-        Ok(temp_file)
+        let total = response
+            .headers
+            .get("content-length")
+            .and_then(|length| length.parse::<u64>().ok());
+        progress(0, total);
+
+        let partial_path = path.with_extension("part");
+        let file = fs::File::create(&partial_path)
+            .map_err(|e| format!("Failed to create update file: {}", e))?;
+        let mut file = verify::Sha256Writer::new(file);
+        let copied = copy_with_progress(&mut response, &mut file, cancel, |written| {
+            progress(written, total)
+        });
+        let hash = file.finish();
+
+        let complete = copied.and_then(|written| match total {
+            Some(total) if total != written => Err("Download was cut short".to_string()),
+            _ if hash != *expected => {
+                Err("The download doesn't match the release checksum; not installing it"
+                    .to_string())
+            }
+            _ => Ok(()),
+        });
+        if let Err(e) = complete {
+            let _ = fs::remove_file(&partial_path);
+            return Err(e);
+        }
+        fs::rename(&partial_path, path).map_err(|e| format!("Failed to stage update: {}", e))
     }
 
-    fn install_update(&self, temp_path: &PathBuf) -> Result<(), String> {
-        let current_exe = std::env::current_exe()
-            .map_err(|e| format!("Failed to get current executable path: {}", e))?;
-
-        #[cfg(target_os = "windows")]
-        {
-            // On Windows: rename old exe, move new one in place
-            let backup_path = current_exe.with_extension("exe.old");
-
-            // Remove old backup if exists
-            let _ = fs::remove_file(&backup_path);
-
-            // Rename current exe to .old
-            fs::rename(&current_exe, &backup_path)
-                .map_err(|e| format!("Failed to backup current executable: {}", e))?;
-
-            // Move new exe to current location
-            fs::copy(temp_path, &current_exe)
-                .map_err(|e| format!("Failed to install new executable: {}", e))?;
+    /// Where a background update waits for the game to exit
+    fn staged_update_dir() -> PathBuf {
+        std::env::temp_dir().join("bas-veeg-arc-staged-update")
+    }
 
-            Ok(())
-        }
+    /// Where an Install Now download lands before it's swapped in
+    fn download_dir() -> PathBuf {
+        std::env::temp_dir().join("bas-veeg-arc-update")
+    }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            // On Linux/Unix: replace binary directly
-            use std::os::unix::fs::PermissionsExt;
-
-            // Make the new file executable
-            let mut perms = fs::metadata(temp_path)
-                .map_err(|e| format!("Failed to get file metadata: {}", e))?
-                .permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(temp_path, perms)
-                .map_err(|e| format!("Failed to set permissions: {}", e))?;
-
-            // Backup current
-            let backup_path = format!("{}.old", current_exe.display());
-            let _ = fs::remove_file(&backup_path);
-            fs::copy(&current_exe, &backup_path).map_err(|e| format!("Failed to backup: {}", e))?;
-
-            // Replace with new version
-            fs::copy(temp_path, &current_exe)
-                .map_err(|e| format!("Failed to install update: {}", e))?;
-
-            Ok(())
+    /// The file an update replaces. Inside an AppImage `current_exe` points into
+    /// the read-only mount, so the AppImage itself is swapped instead.
+    fn installed_executable() -> Result<PathBuf, String> {
+        if let Some(appimage) = std::env::var_os("APPIMAGE") {
+            return Ok(PathBuf::from(appimage));
         }
+        std::env::current_exe()
+            .map_err(|e| format!("Failed to get current executable path: {}", e))
     }
 
     pub fn restart_game(&self) {
-        let current_exe = std::env::current_exe().unwrap();
+        let current_exe = Self::installed_executable().unwrap();
+        install::restart_into_update(&current_exe);
+        std::process::exit(0);
+    }
+}
 
-        #[cfg(target_os = "windows")]
-        {
-            std::process::Command::new(current_exe).spawn().ok();
-        }
+impl Drop for Updater {
+    /// Leaving the boot screen mid-download stops the download too
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            std::process::Command::new(current_exe).spawn().ok();
+/// Copy `reader` into `writer` a chunk at a time, reporting the running total
+/// after each chunk and stopping as soon as `cancel` is raised
+fn copy_with_progress(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64),
+) -> Result<u64, String> {
+    let mut buffer = vec![0u8; DOWNLOAD_CHUNK];
+    let mut written = 0;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
         }
+        let read = reader.read(&mut buffer).map_err(|e| format!("Download error: {}", e))?;
+        if read == 0 {
+            return Ok(written);
+        }
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to save update: {}", e))?;
+        written += read as u64;
+        progress(written);
+    }
+}
 
-        std::process::exit(0);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pre_releases_come_before_their_release_and_deferrals_hold() {
+        assert!(Updater::is_newer_version("1.2.0-beta.1", "1.1.9"));
+        assert!(Updater::is_newer_version("1.2.0", "1.2.0-beta.2"));
+        assert!(Updater::is_newer_version("1.2.0-beta.10", "1.2.0-beta.2"));
+        assert!(Updater::is_newer_version("1.2.0-rc.1", "1.2.0-beta.3"));
+        assert!(!Updater::is_newer_version("1.2", "1.2.0"));
+
+        let release = |tag: &str, draft| GithubRelease {
+            tag_name: tag.to_string(),
+            name: String::new(),
+            body: String::new(),
+            assets: Vec::new(),
+            draft,
+        };
+        let newest = Updater::newest_release(vec![
+            release("v1.3.0-beta.1", false),
+            release("v1.4.0", true),
+            release("v1.2.1", false),
+        ]);
+        assert_eq!(newest.map(|release| release.tag_name), Some("v1.3.0-beta.1".to_string()));
+
+        let mut config = GameConfig {
+            skipped_version: Some("1.3.0".to_string()),
+            ..GameConfig::default()
+        };
+        assert!(Updater::is_deferred(&config, "1.3.0", 100));
+        assert!(!Updater::is_deferred(&config, "1.3.1", 100));
+        config.remind_updates_after = 200;
+        assert!(Updater::is_deferred(&config, "1.3.1", 100));
+        assert!(!Updater::is_deferred(&config, "1.3.1", 200));
+    }
+
+    #[test]
+    fn copy_reports_every_chunk_and_stops_when_cancelled() {
+        let data = vec![7u8; DOWNLOAD_CHUNK * 2 + 10];
+        let mut copied = Vec::new();
+        let mut reports = Vec::new();
+        let cancel = AtomicBool::new(false);
+        let written = copy_with_progress(&mut data.as_slice(), &mut copied, &cancel, |written| {
+            reports.push(written)
+        });
+        assert_eq!(written, Ok(data.len() as u64));
+        assert_eq!(copied, data);
+        assert_eq!(reports.last(), Some(&(data.len() as u64)));
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+
+        cancel.store(true, Ordering::Relaxed);
+        let mut rest = Vec::new();
+        let cancelled = copy_with_progress(&mut data.as_slice(), &mut rest, &cancel, |_| {});
+        assert_eq!(cancelled, Err("Cancelled".to_string()));
+        assert!(rest.is_empty());
     }
 }
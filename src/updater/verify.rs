@@ -0,0 +1,205 @@
+use super::GithubAsset;
+use ring::digest::{Context, SHA256, SHA256_OUTPUT_LEN};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Release asset listing the SHA-256 of every other asset, in `sha256sum`'s format
+const MANIFEST_NAME: &str = "SHA256SUMS";
+/// Raw 64-byte Ed25519 signature over the manifest
+const SIGNATURE_NAME: &str = "SHA256SUMS.sig";
+/// Hex Ed25519 public key releases are signed with. Builds made without one
+/// still check checksums, but can't tell a tampered manifest from a real one.
+const RELEASE_KEY: Option<&str> = option_env!("BAS_VEEG_ARC_RELEASE_KEY");
+
+pub type Sha256 = [u8; SHA256_OUTPUT_LEN];
+
+/// How far a download was checked before it was let through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// Matches the release manifest
+    Checksum,
+    /// Matches a manifest carrying a valid release signature
+    Signed,
+}
+
+impl Verification {
+    pub fn label(self) -> &'static str {
+        match self {
+            Verification::Checksum => "checksum verified",
+            Verification::Signed => "signature and checksum verified",
+        }
+    }
+}
+
+/// Where a release publishes the checksums of its assets, and the asset
+/// being downloaded from it
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    url: String,
+    signature_url: Option<String>,
+    asset: String,
+}
+
+impl Manifest {
+    pub(super) fn find(assets: &[GithubAsset], asset: &str) -> Option<Self> {
+        let url_of = |name: &str| {
+            assets
+                .iter()
+                .find(|asset| asset.name == name)
+                .map(|asset| asset.browser_download_url.clone())
+        };
+        Some(Self {
+            url: url_of(MANIFEST_NAME)?,
+            signature_url: url_of(SIGNATURE_NAME),
+            asset: asset.to_string(),
+        })
+    }
+
+    /// Fetch the manifest, check its signature when this build carries the
+    /// release key, and look up the checksum the asset should have
+    pub fn expected(&self) -> Result<(Sha256, Verification), String> {
+        let manifest = fetch(&self.url)?;
+        let verification = match RELEASE_KEY {
+            Some(key) => {
+                let signature_url = self
+                    .signature_url
+                    .as_ref()
+                    .ok_or_else(|| "The release manifest isn't signed".to_string())?;
+                let key = parse_hex(key.trim()).ok_or("This build's release key is invalid")?;
+                check_signature(&manifest, &fetch(signature_url)?, &key)?;
+                Verification::Signed
+            }
+            None => Verification::Checksum,
+        };
+
+        let text = String::from_utf8_lossy(&manifest);
+        let expected = checksum_for(&text, &self.asset)
+            .ok_or_else(|| format!("The release manifest has no checksum for {}", self.asset))?;
+        Ok((expected, verification))
+    }
+}
+
+fn fetch(url: &str) -> Result<Vec<u8>, String> {
+    let response = minreq::get(url)
+        .with_header("User-Agent", "bas-veeg-arc-updater")
+        .with_timeout(super::CHECK_TIMEOUT)
+        .send()
+        .map_err(|e| format!("Couldn't fetch the release manifest: {}", e))?;
+    if response.status_code != 200 {
+        return Err(format!(
+            "Release manifest download failed with status {}",
+            response.status_code
+        ));
+    }
+    Ok(response.into_bytes())
+}
+
+/// The checksum listed for `asset`, from `<hex>  <name>` lines. `sha256sum -b`
+/// marks names with a `*`.
+fn checksum_for(manifest: &str, asset: &str) -> Option<Sha256> {
+    manifest.lines().find_map(|line| {
+        let (hex, name) = line.trim().split_once(char::is_whitespace)?;
+        let name = name.trim_start();
+        if name.strip_prefix('*').unwrap_or(name) != asset {
+            return None;
+        }
+        parse_hex(hex)?.try_into().ok()
+    })
+}
+
+pub(super) fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn check_signature(manifest: &[u8], signature: &[u8], key: &[u8]) -> Result<(), String> {
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(manifest, signature)
+        .map_err(|_| "The release manifest's signature doesn't match".to_string())
+}
+
+/// SHA-256 of the file at `path`; `None` if it can't be read
+pub fn hash_file(path: &Path) -> Option<Sha256> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256Writer::new(io::sink());
+    io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finish())
+}
+
+/// Passes writes through while hashing them, so a download is checked
+/// without reading it back off disk
+pub struct Sha256Writer<W> {
+    inner: W,
+    context: Context,
+}
+
+impl<W: Write> Sha256Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            context: Context::new(&SHA256),
+        }
+    }
+
+    pub fn finish(self) -> Sha256 {
+        let mut hash = [0; SHA256_OUTPUT_LEN];
+        hash.copy_from_slice(self.context.finish().as_ref());
+        hash
+    }
+}
+
+impl<W: Write> Write for Sha256Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.context.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn manifest_checksums_match_what_was_written() {
+        let manifest = format!(
+            "{}  bas-veeg-arc-windows.exe\n{} *bas-veeg-arc-linux\n",
+            "00".repeat(32),
+            ABC_SHA256
+        );
+        let mut writer = Sha256Writer::new(Vec::new());
+        writer.write_all(b"abc").unwrap();
+        assert_eq!(checksum_for(&manifest, "bas-veeg-arc-linux"), Some(writer.finish()));
+        assert_ne!(checksum_for(&manifest, "bas-veeg-arc-windows.exe"), None);
+        assert_eq!(checksum_for(&manifest, "bas-veeg-arc-macos"), None);
+        assert_eq!(parse_hex("abc"), None);
+    }
+
+    #[test]
+    fn only_the_release_key_signs_a_manifest() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let key = pair.public_key().as_ref();
+        let manifest = format!("{}  bas-veeg-arc-linux\n", ABC_SHA256);
+        let signature = pair.sign(manifest.as_bytes());
+
+        assert!(check_signature(manifest.as_bytes(), signature.as_ref(), key).is_ok());
+        let tampered = manifest.replace("ba78", "0078");
+        assert!(check_signature(tampered.as_bytes(), signature.as_ref(), key).is_err());
+    }
+}
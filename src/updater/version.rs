@@ -0,0 +1,138 @@
+use std::cmp::Ordering;
+
+/// A semantic version, as release tags like `v1.10.0` or `1.2.0-rc.1+build.7`
+/// carry it. Missing minor and patch numbers count as zero, and build
+/// metadata is dropped since it doesn't affect precedence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Dotted pre-release identifiers; empty for a release
+    pre: Vec<Identifier>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Identifier {
+    Numeric(u64),
+    Named(String),
+}
+
+impl Version {
+    /// `None` for anything that isn't a version, like a `nightly` tag
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+        let text = text.split_once('+').map_or(text, |(version, _build)| version);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+
+        let mut numbers = core.split('.');
+        let mut number = |required: bool| match numbers.next() {
+            Some(part) => part.parse::<u64>().ok(),
+            None if !required => Some(0),
+            None => None,
+        };
+        let (major, minor, patch) = (number(true)?, number(false)?, number(false)?);
+        if numbers.next().is_some() {
+            return None;
+        }
+
+        let pre = match pre {
+            Some(pre) => pre.split('.').map(Identifier::parse).collect::<Option<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    pub fn is_pre_release(&self) -> bool {
+        !self.pre.is_empty()
+    }
+}
+
+impl Identifier {
+    fn parse(part: &str) -> Option<Self> {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return None;
+        }
+        Some(match part.parse() {
+            Ok(number) => Identifier::Numeric(number),
+            Err(_) => Identifier::Named(part.to_string()),
+        })
+    }
+}
+
+impl Ord for Identifier {
+    /// Numbers compare as numbers and come before names, which compare as text
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::Numeric(_), Identifier::Named(_)) => Ordering::Less,
+            (Identifier::Named(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::Named(a), Identifier::Named(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    /// A pre-release comes before its release; between two pre-releases the
+    /// first differing identifier decides, and a shorter list comes first
+    fn cmp(&self, other: &Self) -> Ordering {
+        let core = |version: &Self| (version.major, version.minor, version.patch);
+        core(self).cmp(&core(other)).then_with(|| {
+            match (self.is_pre_release(), other.is_pre_release()) {
+                (false, false) => Ordering::Equal,
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (true, true) => self.pre.cmp(&other.pre),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn versions_follow_semver_precedence() {
+        assert!(version("v1.10.0") > version("v1.9.3"));
+        assert!(version("1.2.0") > version("1.2.0-rc.1"));
+        assert!(version("1.2.0-rc.1") > version("1.2.0-beta.11"));
+        assert!(version("1.2.0-beta.11") > version("1.2.0-beta.2"));
+        assert!(version("1.2.0-beta") < version("1.2.0-beta.1"));
+        assert!(version("1.2.0-alpha.1") < version("1.2.0-alpha.beta"));
+        assert!(version("2.0.0-alpha") > version("1.99.99"));
+
+        assert_eq!(version("1.2.0+build.7"), version("v1.2.0"));
+        assert_eq!(version("1.2"), version("1.2.0"));
+        assert!(version("1.2.0-rc.1+linux").is_pre_release());
+
+        for invalid in ["nightly", "", "v", "1.2.3.4", "1.x.0", "1.2.0-", "1.2.0-rc..1"] {
+            assert_eq!(Version::parse(invalid), None, "{:?}", invalid);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::data::crash;
+use super::{GithubRelease, Updater, CURRENT_VERSION, GITHUB_REPO};
+use crate::data::GameConfig;
+use std::sync::Mutex;
+
+/// Changelog lines shown per release before the rest is cut off
+const MAX_NOTES_PER_RELEASE: usize = 6;
+
+/// Menu destinations a changelog can point at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureLink {
+    Story,
+    Endless,
+    Coop,
+    Versus,
+    SkillTree,
+    Settings,
+    Controls,
+}
+
+impl FeatureLink {
+    const ALL: [FeatureLink; 7] = [
+        FeatureLink::Story,
+        FeatureLink::Endless,
+        FeatureLink::Coop,
+        FeatureLink::Versus,
+        FeatureLink::SkillTree,
+        FeatureLink::Settings,
+        FeatureLink::Controls,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FeatureLink::Story => "Story",
+            FeatureLink::Endless => "Endless Mode",
+            FeatureLink::Coop => "Co-op",
+            FeatureLink::Versus => "Versus",
+            FeatureLink::SkillTree => "Skill Tree",
+            FeatureLink::Settings => "Settings",
+            FeatureLink::Controls => "Controls",
+        }
+    }
+
+    /// Lowercase words in a changelog line that mean it's about this feature
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            FeatureLink::Story => &["story", "chapter", "cutscene"],
+            FeatureLink::Endless => &["endless", "wave"],
+            FeatureLink::Coop => &["co-op", "coop"],
+            FeatureLink::Versus => &["versus", "vs mode"],
+            FeatureLink::SkillTree => &["skill tree", "skill point"],
+            FeatureLink::Settings => &["setting", "option"],
+            FeatureLink::Controls => &["control", "rebind", "gamepad"],
+        }
+    }
+}
+
+/// Summary of one release the player hasn't seen the notes for yet
+#[derive(Debug, Clone)]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub highlights: Vec<String>,
+    /// How many changelog lines didn't fit in `highlights`
+    pub hidden: usize,
+}
+
+/// Everything that changed since the version the player last ran
+#[derive(Debug, Clone)]
+pub struct WhatsNew {
+    pub previous_version: String,
+    /// Newest release first
+    pub releases: Vec<ReleaseNotes>,
+    pub links: Vec<FeatureLink>,
+}
+
+// Filled in by the fetch thread, taken once by the menu
+static WHATS_NEW: Mutex<Option<WhatsNew>> = Mutex::new(None);
+// The latest release's notes, kept from the boot screen's update check for
+// the menu's news panel
+static NEWS: Mutex<Option<ReleaseNotes>> = Mutex::new(None);
+
+/// The popup to show, if the fetch for it has finished. Only returned once.
+pub fn take_whats_new() -> Option<WhatsNew> {
+    WHATS_NEW.lock().ok().and_then(|mut pending| pending.take())
+}
+
+/// Notes on the latest release, once the update check has fetched them
+pub fn news() -> Option<ReleaseNotes> {
+    NEWS.lock().ok().and_then(|news| news.clone())
+}
+
+pub(super) fn set_news(tag: &str, body: &str) {
+    if let Ok(mut news) = NEWS.lock() {
+        *news = Some(release_notes(tag, &changelog_lines(body)));
+    }
+}
+
+fn release_notes(tag: &str, lines: &[String]) -> ReleaseNotes {
+    ReleaseNotes {
+        version: tag.trim_start_matches('v').to_string(),
+        hidden: lines.len().saturating_sub(MAX_NOTES_PER_RELEASE),
+        highlights: lines.iter().take(MAX_NOTES_PER_RELEASE).cloned().collect(),
+    }
+}
+
+/// Fetch release notes in the background if this is the first launch since
+/// the game was updated. A fresh install just records the current version.
+pub fn check_for_new_version() {
+    let mut config = GameConfig::load();
+    let Some(previous) = config.last_seen_version.clone() else {
+        config.last_seen_version = Some(CURRENT_VERSION.to_string());
+        if let Err(e) = config.save() {
+            crash::log(format!("Failed to record game version: {}", e));
+        }
+        return;
+    };
+
+    if !Updater::is_newer_version(CURRENT_VERSION, previous.trim_start_matches('v')) {
+        return;
+    }
+
+    std::thread::spawn(move || match fetch_whats_new(&previous) {
+        Ok(whats_new) => {
+            if let Ok(mut pending) = WHATS_NEW.lock() {
+                *pending = Some(whats_new);
+            }
+        }
+        // Left unseen so the next launch tries again
+        Err(e) => crash::log(format!("Failed to fetch release notes: {}", e)),
+    });
+}
+
+/// Stop showing the popup for this version
+pub fn mark_seen() {
+    let mut config = GameConfig::load();
+    config.last_seen_version = Some(CURRENT_VERSION.to_string());
+    if let Err(e) = config.save() {
+        crash::log(format!("Failed to record game version: {}", e));
+    }
+}
+
+fn fetch_whats_new(previous: &str) -> Result<WhatsNew, String> {
+    let url = format!("https://api.github.com/repos/{}/releases", GITHUB_REPO);
+    let response = minreq::get(url)
+        .with_header("User-Agent", "bas-veeg-arc-updater")
+        .send()
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if response.status_code != 200 {
+        return Err(format!(
+            "GitHub API returned status {}",
+            response.status_code
+        ));
+    }
+
+    let releases: Vec<GithubRelease> =
+        serde_json::from_str(response.as_str().map_err(|e| e.to_string())?)
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    let previous = previous.trim_start_matches('v');
+    let current = CURRENT_VERSION.trim_start_matches('v');
+
+    // Everything after the last version played, up to and including this one.
+    // GitHub lists newest first.
+    let mut links = Vec::new();
+    let notes = releases
+        .iter()
+        .filter(|release| {
+            let version = release.tag_name.trim_start_matches('v');
+            Updater::is_newer_version(version, previous)
+                && !Updater::is_newer_version(version, current)
+        })
+        .map(|release| {
+            let lines = changelog_lines(&release.body);
+            for line in &lines {
+                let lower = line.to_lowercase();
+                for link in FeatureLink::ALL {
+                    let mentioned = link.keywords().iter().any(|word| lower.contains(word));
+                    if mentioned && !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+            }
+
+            release_notes(&release.tag_name, &lines)
+        })
+        .collect();
+
+    Ok(WhatsNew {
+        previous_version: previous.to_string(),
+        releases: notes,
+        links,
+    })
+}
+
+/// Plain-text lines of a markdown changelog, without headings' and bullets'
+/// markup or blank lines
+fn changelog_lines(body: &str) -> Vec<String> {
+    body.lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(['#', '-', '*', '+', '>'])
+                .trim()
+                .replace("**", "")
+                .replace('`', "")
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
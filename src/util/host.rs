@@ -0,0 +1,182 @@
+use macroquad::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// What the game reads from the window it runs in: the keyboard and mouse,
+/// the screen's size and the clock. In the game that's macroquad's window;
+/// tests and the balance runner step states headless on `Headless`.
+pub trait Host {
+    fn key_down(&self, key: KeyCode) -> bool;
+    /// Went down this frame
+    fn key_pressed(&self, key: KeyCode) -> bool;
+    /// Came up this frame
+    fn key_released(&self, key: KeyCode) -> bool;
+    /// Any key held, or the mouse moved or held down, this frame
+    fn touched(&self) -> bool;
+    fn screen_size(&self) -> Vec2;
+    /// Seconds the last frame took
+    fn frame_time(&self) -> f32;
+    /// Seconds since the game started
+    fn time(&self) -> f64;
+
+    fn screen_width(&self) -> f32 {
+        self.screen_size().x
+    }
+}
+
+/// The macroquad window the game is running in
+pub struct Window;
+
+impl Host for Window {
+    fn key_down(&self, key: KeyCode) -> bool {
+        is_key_down(key)
+    }
+
+    fn key_pressed(&self, key: KeyCode) -> bool {
+        is_key_pressed(key)
+    }
+
+    fn key_released(&self, key: KeyCode) -> bool {
+        is_key_released(key)
+    }
+
+    fn touched(&self) -> bool {
+        !get_keys_down().is_empty()
+            || mouse_delta_position() != Vec2::ZERO
+            || is_mouse_button_down(MouseButton::Left)
+            || is_mouse_button_down(MouseButton::Right)
+    }
+
+    fn screen_size(&self) -> Vec2 {
+        Vec2::new(screen_width(), screen_height())
+    }
+
+    fn frame_time(&self) -> f32 {
+        get_frame_time()
+    }
+
+    fn time(&self) -> f64 {
+        get_time()
+    }
+}
+
+/// A window that isn't there: keys go down when a script says so and the
+/// clock moves when it's told to. Clones share the same keyboard, so one
+/// can be handed to a state and another kept to drive it.
+#[derive(Clone)]
+pub struct Headless {
+    frame: Rc<RefCell<HeadlessFrame>>,
+}
+
+struct HeadlessFrame {
+    down: HashSet<KeyCode>,
+    pressed: HashSet<KeyCode>,
+    released: HashSet<KeyCode>,
+    /// Tapped this frame, to come back up when it ends
+    taps: HashSet<KeyCode>,
+    screen: Vec2,
+    frame_time: f32,
+    time: f64,
+}
+
+impl Headless {
+    pub fn new(screen: Vec2) -> Self {
+        Self {
+            frame: Rc::new(RefCell::new(HeadlessFrame {
+                down: HashSet::new(),
+                pressed: HashSet::new(),
+                released: HashSet::new(),
+                taps: HashSet::new(),
+                screen,
+                frame_time: 0.0,
+                time: 0.0,
+            })),
+        }
+    }
+
+    /// Put `key` down until it's released
+    pub fn hold(&self, key: KeyCode) {
+        let mut frame = self.frame.borrow_mut();
+        if frame.down.insert(key) {
+            frame.pressed.insert(key);
+        }
+    }
+
+    pub fn release(&self, key: KeyCode) {
+        let mut frame = self.frame.borrow_mut();
+        if frame.down.remove(&key) {
+            frame.released.insert(key);
+        }
+    }
+
+    /// Press `key` for this frame only
+    pub fn tap(&self, key: KeyCode) {
+        self.hold(key);
+        self.frame.borrow_mut().taps.insert(key);
+    }
+
+    /// Finish a frame of `dt` seconds: the clock moves on, taps come back
+    /// up and this frame's presses stop counting as new
+    pub fn end_frame(&self, dt: f32) {
+        let mut frame = self.frame.borrow_mut();
+        frame.pressed.clear();
+        frame.released.clear();
+        let taps: Vec<KeyCode> = frame.taps.drain().collect();
+        for key in taps {
+            frame.down.remove(&key);
+            frame.released.insert(key);
+        }
+        frame.frame_time = dt;
+        frame.time += dt as f64;
+    }
+}
+
+impl Host for Headless {
+    fn key_down(&self, key: KeyCode) -> bool {
+        self.frame.borrow().down.contains(&key)
+    }
+
+    fn key_pressed(&self, key: KeyCode) -> bool {
+        self.frame.borrow().pressed.contains(&key)
+    }
+
+    fn key_released(&self, key: KeyCode) -> bool {
+        self.frame.borrow().released.contains(&key)
+    }
+
+    fn touched(&self) -> bool {
+        !self.frame.borrow().down.is_empty()
+    }
+
+    fn screen_size(&self) -> Vec2 {
+        self.frame.borrow().screen
+    }
+
+    fn frame_time(&self) -> f32 {
+        self.frame.borrow().frame_time
+    }
+
+    fn time(&self) -> f64 {
+        self.frame.borrow().time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_tap_is_pressed_for_one_frame_and_a_hold_stays_down() {
+        let host = Headless::new(Vec2::new(1280.0, 720.0));
+        host.tap(KeyCode::J);
+        host.hold(KeyCode::D);
+        assert!(host.key_pressed(KeyCode::J) && host.key_down(KeyCode::J));
+        assert!(host.key_pressed(KeyCode::D));
+
+        host.end_frame(0.5);
+        assert!(!host.key_down(KeyCode::J) && host.key_released(KeyCode::J));
+        assert!(host.key_down(KeyCode::D) && !host.key_pressed(KeyCode::D));
+        assert_eq!(host.time(), 0.5);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod math;
 pub mod pools;
+pub mod host;
 pub mod rng;
+pub mod spatial;
 
 // Utility modules available for future use
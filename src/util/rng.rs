@@ -1,36 +1,166 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::Location;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// One draw from a `SeededRng` while an audit was running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RngCall {
+    /// How many draws that generator had made before this one
+    pub index: u64,
+    /// `file:line:column` of the code that asked for the number
+    pub site: String,
+    pub value: u32,
+}
+
+/// Every RNG draw made during a run, in order. Two runs fed the same inputs
+/// must produce identical audits, or replays and netplay will desync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RngAudit {
+    pub calls: Vec<RngCall>,
+}
+
+impl RngAudit {
+    /// One tab-separated `index site value` line per draw
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut text = String::new();
+        for call in &self.calls {
+            let _ = writeln!(text, "{}\t{}\t{}", call.index, call.site, call.value);
+        }
+
+        fs::write(path, text).map_err(|e| format!("Failed to write RNG log: {}", e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read RNG log {}: {}", path.display(), e))?;
+
+        let calls = text
+            .lines()
+            .enumerate()
+            .map(|(line, entry)| {
+                let mut fields = entry.split('\t');
+                let call = (|| {
+                    Some(RngCall {
+                        index: fields.next()?.parse().ok()?,
+                        site: fields.next()?.to_string(),
+                        value: fields.next()?.parse().ok()?,
+                    })
+                })();
+                call.ok_or_else(|| format!("Malformed RNG log line {}: {}", line + 1, entry))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { calls })
+    }
+
+    /// Where `other` first stops matching this audit, if anywhere
+    pub fn first_divergence(&self, other: &RngAudit) -> Option<String> {
+        let mismatch = self.calls.iter().zip(&other.calls).position(|(a, b)| a != b);
+        match mismatch {
+            Some(draw) => {
+                let (expected, actual) = (&self.calls[draw], &other.calls[draw]);
+                Some(format!(
+                    "draw {} differs: expected #{} at {} = {}, got #{} at {} = {}",
+                    draw,
+                    expected.index,
+                    expected.site,
+                    expected.value,
+                    actual.index,
+                    actual.site,
+                    actual.value
+                ))
+            }
+            None if self.calls.len() != other.calls.len() => Some(format!(
+                "expected {} draws, got {}",
+                self.calls.len(),
+                other.calls.len()
+            )),
+            None => None,
+        }
+    }
+}
+
+// Some while an audit is running
+static AUDIT: Mutex<Option<RngAudit>> = Mutex::new(None);
+
+/// Start logging every `SeededRng` draw, discarding any audit in progress
+pub fn start_audit() {
+    if let Ok(mut audit) = AUDIT.lock() {
+        *audit = Some(RngAudit::default());
+    }
+}
+
+/// Stop logging and return what was drawn, if an audit was running
+pub fn finish_audit() -> Option<RngAudit> {
+    AUDIT.lock().ok().and_then(|mut audit| audit.take())
+}
+
+/// A number in `min..max` for what only shows on screen: camera shake,
+/// particles, ambience. These can be drawn any number of times a rendered
+/// frame, so they come from macroquad's generator and never from the
+/// simulation's `SeededRng`, which replays and netplay rely on.
+pub fn cosmetic(min: f32, max: f32) -> f32 {
+    macroquad::rand::gen_range(min, max)
+}
+
 pub struct SeededRng {
     state: u64,
+    draws: u64,
 }
 
 impl SeededRng {
     pub fn new(seed: u64) -> Self {
         Self {
             state: seed ^ 0x5DEECE66D,
+            draws: 0,
         }
     }
 
+    // Every draw is `#[track_caller]` so audits record the gameplay code that
+    // asked for the number, not this file
+
+    #[track_caller]
     pub fn next_u32(&mut self) -> u32 {
         self.state = self.state.wrapping_mul(1103515245).wrapping_add(12345);
-        (self.state >> 16) as u32
+        let value = (self.state >> 16) as u32;
+
+        if let Ok(mut audit) = AUDIT.lock() {
+            if let Some(audit) = audit.as_mut() {
+                audit.calls.push(RngCall {
+                    index: self.draws,
+                    site: Location::caller().to_string(),
+                    value,
+                });
+            }
+        }
+        self.draws += 1;
+        value
     }
 
+    #[track_caller]
     pub fn next_f32(&mut self) -> f32 {
         self.next_u32() as f32 / u32::MAX as f32
     }
 
+    #[track_caller]
     pub fn range(&mut self, min: i32, max: i32) -> i32 {
         let range = (max - min) as u32;
         min + (self.next_u32() % range) as i32
     }
 
+    #[track_caller]
     pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
         min + self.next_f32() * (max - min)
     }
 
+    #[track_caller]
     pub fn chance(&mut self, probability: f32) -> bool {
         self.next_f32() < probability
     }
 
+    #[track_caller]
     pub fn shuffle<T>(&mut self, slice: &mut [T]) {
         let len = slice.len();
         for i in 0..len {
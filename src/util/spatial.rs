@@ -0,0 +1,158 @@
+use ahash::AHashMap;
+use macroquad::prelude::*;
+
+/// Uniform grid for broad-phase hit detection and nearest-target queries.
+/// Rebuilt every frame: `clear` keeps cell allocations around for reuse.
+pub struct SpatialGrid<T: Copy + PartialEq> {
+    cell_size: f32,
+    cells: AHashMap<(i32, i32), Vec<(T, Vec2)>>,
+    min_cell: (i32, i32),
+    max_cell: (i32, i32),
+    len: usize,
+}
+
+impl<T: Copy + PartialEq> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(1.0),
+            cells: AHashMap::new(),
+            min_cell: (i32::MAX, i32::MAX),
+            max_cell: (i32::MIN, i32::MIN),
+            len: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.min_cell = (i32::MAX, i32::MAX);
+        self.max_cell = (i32::MIN, i32::MIN);
+        self.len = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn cell_of(&self, position: Vec2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cell_range(&self, center: Vec2, size: Vec2) -> ((i32, i32), (i32, i32)) {
+        let half = size * 0.5;
+        (self.cell_of(center - half), self.cell_of(center + half))
+    }
+
+    /// Insert an item at a single point
+    pub fn insert(&mut self, item: T, position: Vec2) {
+        let cell = self.cell_of(position);
+        self.push(cell, item, position);
+        self.len += 1;
+    }
+
+    /// Insert an item into every cell its box (centered at `center`) overlaps
+    pub fn insert_aabb(&mut self, item: T, center: Vec2, size: Vec2) {
+        let ((x0, y0), (x1, y1)) = self.cell_range(center, size);
+        for cx in x0..=x1 {
+            for cy in y0..=y1 {
+                self.push((cx, cy), item, center);
+            }
+        }
+        self.len += 1;
+    }
+
+    fn push(&mut self, cell: (i32, i32), item: T, position: Vec2) {
+        self.min_cell = (self.min_cell.0.min(cell.0), self.min_cell.1.min(cell.1));
+        self.max_cell = (self.max_cell.0.max(cell.0), self.max_cell.1.max(cell.1));
+        self.cells.entry(cell).or_default().push((item, position));
+    }
+
+    /// Collect items whose cells overlap the box centered at `center`.
+    /// This is a broad phase: callers still run their exact overlap test.
+    pub fn query_aabb(&self, center: Vec2, size: Vec2, out: &mut Vec<T>) {
+        out.clear();
+        let ((x0, y0), (x1, y1)) = self.cell_range(center, size);
+        for cx in x0.max(self.min_cell.0)..=x1.min(self.max_cell.0) {
+            for cy in y0.max(self.min_cell.1)..=y1.min(self.max_cell.1) {
+                if let Some(cell) = self.cells.get(&(cx, cy)) {
+                    for (item, _) in cell {
+                        if !out.contains(item) {
+                            out.push(*item);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collect items whose stored position lies within `radius` of `position`
+    pub fn query_radius(&self, position: Vec2, radius: f32, out: &mut Vec<T>) {
+        out.clear();
+        let radius_sq = radius * radius;
+        let ((x0, y0), (x1, y1)) = self.cell_range(position, Vec2::splat(radius * 2.0));
+        for cx in x0.max(self.min_cell.0)..=x1.min(self.max_cell.0) {
+            for cy in y0.max(self.min_cell.1)..=y1.min(self.max_cell.1) {
+                if let Some(cell) = self.cells.get(&(cx, cy)) {
+                    for (item, pos) in cell {
+                        if (*pos - position).length_squared() <= radius_sq && !out.contains(item) {
+                            out.push(*item);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Closest accepted item, searching outward ring by ring from `position`
+    pub fn nearest<F>(&self, position: Vec2, mut accept: F) -> Option<(T, f32)>
+    where
+        F: FnMut(T) -> bool,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (ox, oy) = self.cell_of(position);
+        let max_ring = (ox - self.min_cell.0)
+            .abs()
+            .max((self.max_cell.0 - ox).abs())
+            .max((oy - self.min_cell.1).abs())
+            .max((self.max_cell.1 - oy).abs());
+
+        let mut best: Option<(T, f32)> = None;
+
+        for ring in 0..=max_ring {
+            // Anything in this ring or beyond is at least (ring - 1) cells away
+            if let Some((_, best_dist_sq)) = best {
+                let min_dist = (ring - 1).max(0) as f32 * self.cell_size;
+                if min_dist * min_dist > best_dist_sq {
+                    break;
+                }
+            }
+
+            for cx in (ox - ring)..=(ox + ring) {
+                for cy in (oy - ring)..=(oy + ring) {
+                    let on_ring = (cx - ox).abs() == ring || (cy - oy).abs() == ring;
+                    if !on_ring {
+                        continue;
+                    }
+
+                    if let Some(cell) = self.cells.get(&(cx, cy)) {
+                        for (item, pos) in cell {
+                            let dist_sq = (*pos - position).length_squared();
+                            if best.is_none_or(|(_, d)| dist_sq < d) && accept(*item) {
+                                best = Some((*item, dist_sq));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(item, dist_sq)| (item, dist_sq.sqrt()))
+    }
+}